@@ -20,7 +20,7 @@ impl ExtendRecipe {
         let authority = self
             .authority
             .to_authority(&self.authority_address, &self.deployment)
-            .await;
+            .await?;
 
         let mut handle: ReadySetHandle = ReadySetHandle::new(authority).await;
         handle.ready().await.unwrap();
@@ -273,7 +273,8 @@ impl NoriaExecutor {
         let authority = opts
             .authority
             .to_authority(&opts.authority_address, &opts.deployment.unwrap())
-            .await;
+            .await
+            .unwrap();
         let mut handle: ReadySetHandle = ReadySetHandle::new(authority).await;
         handle.ready().await.unwrap();
 
@@ -103,7 +103,6 @@ impl Writer {
             db_queries: Vec::new(),
         };
 
-        let auto_increments: Arc<RwLock<HashMap<Relation, AtomicUsize>>> = Arc::default();
         let query_cache: Arc<RwLock<HashMap<ViewCreateRequest, Relation>>> = Arc::default();
         let query_status_cache: &'static _ = Box::leak(Box::new(QueryStatusCache::new()));
         let upstream =
@@ -111,7 +110,6 @@ impl Writer {
         let server_supports_pagination = ch.supports_pagination().await?;
         let noria = NoriaConnector::new(
             ch.clone(),
-            auto_increments,
             query_cache,
             ReadBehavior::Blocking,
             Dialect::DEFAULT_MYSQL,
@@ -283,7 +281,7 @@ impl Writer {
             let auth = self
                 .authority
                 .to_authority(&self.authority_address, &self.deployment)
-                .await;
+                .await?;
             let ch = ReadySetHandle::new(auth).await;
 
             threads.push(tokio::spawn(async move {
@@ -0,0 +1,11 @@
+SET @items_rows = 100000;
+SET @categories = 1000;
+
+DROP TABLE IF EXISTS items;
+
+CREATE TABLE items (
+  id int(11) NOT NULL PRIMARY KEY,
+  category_id int(11) NOT NULL COMMENT 'UNIFORM 0 @categories',
+  name varchar(100) NOT NULL COMMENT 'REGEX "[A-Za-z ]{10,60}"',
+  price int(11) NOT NULL COMMENT 'UNIFORM 1 100000'
+) COMMENT = 'ROWS=@items_rows';
@@ -0,0 +1,48 @@
+SET @users_rows = 10000;
+SET @stories_rows = 50000;
+SET @comments_rows = 200000;
+SET @votes_rows = 500000;
+SET @tags_rows = 100;
+SET @taggings_rows = 100000;
+
+DROP TABLE IF EXISTS users, stories, comments, votes, tags, taggings;
+
+CREATE TABLE users (
+  id int(11) NOT NULL PRIMARY KEY,
+  username varchar(50) NOT NULL COMMENT 'REGEX "[a-z]{6,20}" UNIQUE',
+  karma int(11) NOT NULL COMMENT 'UNIFORM 0 10000'
+) COMMENT = 'ROWS=@users_rows';
+
+CREATE TABLE stories (
+  id int(11) NOT NULL PRIMARY KEY,
+  user_id int(11) NOT NULL COMMENT 'UNIFORM 0 @users_rows',
+  title varchar(150) NOT NULL COMMENT 'REGEX "[A-Za-z ]{20,100}"',
+  url varchar(250) NOT NULL COMMENT 'REGEX "[a-z0-9/]{20,80}"',
+  hotness int(11) NOT NULL COMMENT 'UNIFORM 0 100000',
+  comments_count int(11) NOT NULL COMMENT 'UNIFORM 0 100'
+) COMMENT = 'ROWS=@stories_rows';
+
+CREATE TABLE comments (
+  id int(11) NOT NULL PRIMARY KEY,
+  story_id int(11) NOT NULL COMMENT 'ZIPF 0 @stories_rows 1.15',
+  user_id int(11) NOT NULL COMMENT 'UNIFORM 0 @users_rows',
+  comment varchar(500) NOT NULL COMMENT 'REGEX "[A-Za-z ]{20,400}"',
+  upvotes int(11) NOT NULL COMMENT 'UNIFORM 0 50'
+) COMMENT = 'ROWS=@comments_rows';
+
+CREATE TABLE votes (
+  user_id int(11) NOT NULL COMMENT 'UNIFORM 0 @users_rows',
+  story_id int(11) NOT NULL COMMENT 'ZIPF 0 @stories_rows 1.15',
+  vote int(11) NOT NULL COMMENT 'CONSTANT 1'
+) COMMENT = 'ROWS=@votes_rows';
+
+CREATE TABLE tags (
+  id int(11) NOT NULL PRIMARY KEY,
+  tag varchar(25) NOT NULL COMMENT 'REGEX "[a-z]{3,20}" UNIQUE'
+) COMMENT = 'ROWS=@tags_rows';
+
+CREATE TABLE taggings (
+  id int(11) NOT NULL PRIMARY KEY,
+  story_id int(11) NOT NULL COMMENT 'UNIFORM 0 @stories_rows',
+  tag_id int(11) NOT NULL COMMENT 'UNIFORM 0 @tags_rows'
+) COMMENT = 'ROWS=@taggings_rows';
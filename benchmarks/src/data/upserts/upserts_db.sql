@@ -0,0 +1,14 @@
+SET @counters_rows = 100000;
+SET @settings_rows = 100000;
+
+DROP TABLE IF EXISTS counters, user_settings;
+
+CREATE TABLE counters (
+  id int(11) NOT NULL PRIMARY KEY,
+  hits int(11) NOT NULL COMMENT 'UNIFORM 0 1000'
+) COMMENT = 'ROWS=@counters_rows';
+
+CREATE TABLE user_settings (
+  user_id int(11) NOT NULL PRIMARY KEY,
+  theme int(11) NOT NULL COMMENT 'UNIFORM 0 16'
+) COMMENT = 'ROWS=@settings_rows';
@@ -67,6 +67,11 @@ struct BenchmarkRunner {
     #[clap(long, value_hint = ValueHint::FilePath)]
     results_file: Option<PathBuf>,
 
+    /// A directory to append CSV files of throughput and latency histograms for each iteration of
+    /// the benchmark to. The directory is created if it does not exist.
+    #[clap(long, value_hint = ValueHint::DirPath)]
+    csv_dir: Option<PathBuf>,
+
     /// Runs the benchmarks against a noria adapter and server run in the same process. Note that
     /// some of the benchmarks with certain schemas may not work without an upstream database.
     /// When using `--local` benchmark results may vary based on compiler optimizations, using
@@ -353,6 +358,16 @@ impl BenchmarkRunner {
                 duration.as_micros() as f64
             );
 
+            if let Some(dir) = &self.csv_dir {
+                benchmarks::reporting::csv::write_csv(
+                    dir,
+                    benchmark_cmd.name(),
+                    i,
+                    duration,
+                    &result,
+                )?;
+            }
+
             if let Some(report_mode) = self.report_mode {
                 let session = benchmarks::reporting::BenchSession {
                     start_time: bench_start_time,
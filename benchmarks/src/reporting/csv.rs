@@ -0,0 +1,136 @@
+//! Writes benchmark results to CSV files, so that they can be compared across runs (and releases)
+//! with a spreadsheet or a plotting script.
+//!
+//! Two files are appended to in the output directory:
+//!
+//! * `throughput.csv` - one row per metric per iteration, with the number of samples recorded, the
+//!   number of samples per second over the iteration, and summary statistics of the samples.
+//! * `latency_histogram.csv` - the full percentile distribution of every metric recorded in
+//!   microseconds, one row per percentile step.
+
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::benchmark::BenchmarkResults;
+
+const THROUGHPUT_FILE: &str = "throughput.csv";
+const THROUGHPUT_HEADER: &str = "benchmark,iteration,metric,unit,samples,elapsed_seconds,\
+                                 samples_per_second,min,mean,p50,p90,p99,p99_9,max";
+
+const LATENCY_HISTOGRAM_FILE: &str = "latency_histogram.csv";
+const LATENCY_HISTOGRAM_HEADER: &str = "benchmark,iteration,metric,unit,percentile,value,count";
+
+/// The unit, as formatted in [`crate::benchmark::BenchmarkData`], of metrics that are included in
+/// the latency histogram output
+const LATENCY_UNIT: &str = "Microseconds";
+
+/// Open `file` in `dir` for appending, writing `header` first if the file is new
+fn open_csv(dir: &Path, file: &str, header: &str) -> anyhow::Result<BufWriter<std::fs::File>> {
+    let path = dir.join(file);
+    let exists = path.exists();
+    let mut writer = BufWriter::new(OpenOptions::new().create(true).append(true).open(path)?);
+    if !exists {
+        writeln!(writer, "{}", header)?;
+    }
+    Ok(writer)
+}
+
+/// Append the results of one iteration of the benchmark named `benchmark`, which took `elapsed`
+/// to run, to the CSV files in `dir`. The directory is created if it does not exist.
+pub fn write_csv(
+    dir: &Path,
+    benchmark: &str,
+    iteration: u32,
+    elapsed: Duration,
+    results: &BenchmarkResults,
+) -> anyhow::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let mut throughput = open_csv(dir, THROUGHPUT_FILE, THROUGHPUT_HEADER)?;
+    let mut latency = open_csv(dir, LATENCY_HISTOGRAM_FILE, LATENCY_HISTOGRAM_HEADER)?;
+
+    // Sort by metric name so the output is stable across runs
+    let mut metrics = results.results.iter().collect::<Vec<_>>();
+    metrics.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let elapsed_seconds = elapsed.as_secs_f64();
+    for (metric, data) in metrics {
+        let hist = data.to_histogram(0.0, 1.0);
+        writeln!(
+            throughput,
+            "{benchmark},{iteration},{metric},{},{},{elapsed_seconds:.3},{:.3},{},{:.3},{},{},{},{},{}",
+            data.unit,
+            hist.len(),
+            hist.len() as f64 / elapsed_seconds,
+            hist.min(),
+            hist.mean(),
+            hist.value_at_quantile(0.5),
+            hist.value_at_quantile(0.9),
+            hist.value_at_quantile(0.99),
+            hist.value_at_quantile(0.999),
+            hist.max(),
+        )?;
+
+        if data.unit != LATENCY_UNIT || hist.is_empty() {
+            continue;
+        }
+        for step in hist.iter_quantiles(1) {
+            writeln!(
+                latency,
+                "{benchmark},{iteration},{metric},{},{:.6},{},{}",
+                data.unit,
+                step.quantile_iterated_to() * 100.0,
+                step.value_iterated_to(),
+                step.count_since_last_iteration(),
+            )?;
+        }
+    }
+
+    throughput.flush()?;
+    latency.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use metrics::Unit;
+
+    use super::*;
+    use crate::benchmark::MetricGoal;
+
+    #[test]
+    fn writes_throughput_and_latency() {
+        let dir = std::env::temp_dir().join(format!("benchmark-csv-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut results = BenchmarkResults::new();
+        for latency in 1..=100 {
+            results.push(
+                "duration_overall",
+                Unit::Microseconds,
+                MetricGoal::Decreasing,
+                latency as f64,
+            );
+        }
+        results.push("rows", Unit::Count, MetricGoal::Increasing, 5.0);
+
+        write_csv(&dir, "test", 0, Duration::from_secs(10), &results).unwrap();
+        write_csv(&dir, "test", 1, Duration::from_secs(10), &results).unwrap();
+
+        let throughput = std::fs::read_to_string(dir.join(THROUGHPUT_FILE)).unwrap();
+        let lines = throughput.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[0], THROUGHPUT_HEADER);
+        assert!(lines[1].starts_with("test,0,duration_overall,Microseconds,100,10.000,10.000,1,"));
+        assert!(lines[2].starts_with("test,0,rows,Count,1,"));
+
+        let latency = std::fs::read_to_string(dir.join(LATENCY_HISTOGRAM_FILE)).unwrap();
+        let mut lines = latency.lines();
+        assert_eq!(lines.next(), Some(LATENCY_HISTOGRAM_HEADER));
+        assert!(lines.all(|l| l.starts_with("test,0,duration_overall,")
+            || l.starts_with("test,1,duration_overall,")));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
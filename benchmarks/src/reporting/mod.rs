@@ -8,6 +8,7 @@ use self::analysis::Stats;
 use crate::benchmark::{BenchmarkResults, MetricGoal};
 
 mod analysis;
+pub mod csv;
 mod storage;
 
 #[derive(clap::ArgEnum, Clone, Copy)]
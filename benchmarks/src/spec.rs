@@ -272,6 +272,15 @@ impl WorkloadSpec {
                     name: None,
                     inner: nom_sql::CacheInner::Statement(Box::new(stmt)),
                     always: false,
+                    filter_pushdown: true,
+                    approximate_aggregates: false,
+                    lazy_joins: false,
+                    reader_placement: vec![],
+                    concurrently: false,
+                    max_staleness: None,
+                    bucket_retention: None,
+                    freshness: Default::default(),
+                    result_limits: Default::default(),
                 };
 
                 let _ = conn.query_drop(create_cache_query.to_string()).await;
@@ -361,6 +370,24 @@ queries:
         let _: WorkloadSpec = serde_yaml::from_str(spec).unwrap();
     }
 
+    #[test]
+    fn canned_workloads() {
+        for yaml in [
+            include_str!("data/lobsters/lobsters_workload.yaml"),
+            include_str!("data/upserts/upserts_workload.yaml"),
+            include_str!("data/in_lists/in_lists_workload.yaml"),
+        ] {
+            let spec = WorkloadSpec::from_yaml(yaml).unwrap();
+            for query in spec.queries {
+                assert_eq!(query.spec.matches('?').count(), query.params.len());
+                assert!(query
+                    .params
+                    .iter()
+                    .all(|p| spec.distributions.iter().any(|d| d.name == p.distribution)));
+            }
+        }
+    }
+
     #[test]
     fn serde() {
         let spec = WorkloadSpec {
@@ -193,6 +193,15 @@ impl ArbitraryQueryParameters {
             name: Some("q".into()),
             inner: nom_sql::CacheInner::Statement(Box::new(stmt)),
             always: false,
+            filter_pushdown: true,
+            approximate_aggregates: false,
+            lazy_joins: false,
+            reader_placement: vec![],
+            concurrently: false,
+            max_staleness: None,
+            bucket_retention: None,
+            freshness: Default::default(),
+            result_limits: Default::default(),
         };
 
         conn.query_drop(create_cache_query.to_string()).await?;
@@ -254,7 +263,7 @@ pub(crate) fn column_to_sqltype(c: &ColumnType) -> SqlType {
         MYSQL_TYPE_DATETIME => SqlType::DateTime(None),
         MYSQL_TYPE_DATE => SqlType::Date,
         MYSQL_TYPE_TIMESTAMP => SqlType::Timestamp,
-        MYSQL_TYPE_TIME => SqlType::Time,
+        MYSQL_TYPE_TIME => SqlType::Time(None),
         MYSQL_TYPE_JSON => SqlType::Json,
         t => unimplemented!("Unsupported type: {:?}", t),
     }
@@ -284,4 +284,23 @@ mod tests {
             ColumnGenerator::NonRepeating(_)
         ));
     }
+
+    #[test]
+    fn canned_schemas_generate_data() {
+        let data_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/data");
+        for schema_file in [
+            "in_lists/in_lists_db.sql",
+            "lobsters/lobsters_db.sql",
+            "news_app/news_app_db.sql",
+            "upserts/upserts_db.sql",
+        ] {
+            let schema = DatabaseSchema::try_from(data_dir.join(schema_file)).unwrap();
+            let mut spec = DatabaseGenerationSpec::new(schema);
+            assert!(!spec.tables.is_empty(), "{schema_file} has no tables");
+            for table in spec.tables.values_mut() {
+                let rows = table.table.generate_data(10, false);
+                assert_eq!(rows.len(), 10, "{schema_file}");
+            }
+        }
+    }
 }
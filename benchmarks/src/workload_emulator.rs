@@ -9,7 +9,7 @@ use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
-use clap::Parser;
+use clap::{Parser, ValueHint};
 use metrics::Unit;
 use mysql_async::prelude::Queryable;
 use mysql_async::Value;
@@ -24,7 +24,9 @@ use zipf::ZipfDistribution;
 
 use crate::benchmark::{BenchmarkControl, BenchmarkResults, DeploymentParameters, MetricGoal};
 use crate::spec::WorkloadSpec;
+use crate::utils::generate::DataGenerator;
 use crate::utils::multi_thread::{self, MultithreadBenchmark};
+use crate::utils::path::benchmark_path;
 use crate::utils::prometheus::ForwardPrometheusMetrics;
 use crate::utils::us_to_ms;
 
@@ -43,6 +45,12 @@ pub struct WorkloadEmulator {
     /// Duration of the benchmark in seconds
     #[clap(long, short, parse(try_from_str = crate::utils::seconds_as_str_to_duration))]
     run_for: Option<Duration>,
+    /// Path to a SQL schema to install and populate with generated data on the setup database
+    /// before running the workload. If not provided, the workload runs against whatever data is
+    /// already in the database.
+    #[clap(long, value_hint = ValueHint::AnyPath)]
+    #[serde(default)]
+    schema: Option<PathBuf>,
     #[clap(skip)]
     #[serde(skip)]
     query_set: Arc<Mutex<Option<Arc<QuerySet>>>>,
@@ -108,8 +116,14 @@ impl WorkloadResultBatch {
 #[async_trait]
 impl BenchmarkControl for WorkloadEmulator {
     async fn setup(&self, deployment: &DeploymentParameters) -> anyhow::Result<()> {
-        let yaml = std::fs::read_to_string(&self.spec).unwrap();
-        let spec = WorkloadSpec::from_yaml(&yaml).unwrap();
+        if let Some(schema) = &self.schema {
+            let data_generator = DataGenerator::new(schema);
+            data_generator.install(&deployment.setup_conn_str).await?;
+            data_generator.generate(&deployment.setup_conn_str).await?;
+        }
+
+        let yaml = std::fs::read_to_string(benchmark_path(&self.spec)?)?;
+        let spec = WorkloadSpec::from_yaml(&yaml)?;
 
         let distributions = spec
             .load_distributions(&mut deployment.connect_to_setup().await?)
@@ -151,6 +165,9 @@ impl BenchmarkControl for WorkloadEmulator {
             ("workers".to_string(), self.workers.to_string()),
         ]
         .into();
+        if let Some(schema) = &self.schema {
+            labels.insert("schema".to_string(), schema.display().to_string());
+        }
 
         if let Some(queryset) = self.query_set.lock().unwrap().as_ref() {
             for Query { spec, idx, .. } in &queryset.queries {
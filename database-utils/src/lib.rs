@@ -20,8 +20,12 @@ use serde::{Deserialize, Serialize};
 use {mysql_async as mysql, tokio_postgres as pgsql};
 
 use crate::error::{DatabaseError, DatabaseURLParseError};
+use crate::row_size::{OversizedRowPolicy, RowSizeLimit};
+use crate::tls::SslVerifyMode;
 
 pub mod error;
+pub mod row_size;
+pub mod tls;
 
 #[allow(missing_docs)] // If we add docs they get added into --help binary text which is confusing
 #[derive(Debug, Clone, Parser, PartialEq, Eq, Serialize, Deserialize)]
@@ -32,8 +36,8 @@ pub struct UpstreamConfig {
     #[serde(default)]
     pub upstream_db_url: Option<RedactedString>,
 
-    /// Disable verification of SSL certificates supplied by the upstream database (postgres
-    /// only, ignored for mysql). Ignored if `--upstream-db-url` is not passed.
+    /// Disable verification of SSL certificates supplied by the upstream database. Ignored if
+    /// `--upstream-db-url` is not passed, or if `--ssl-verify-mode` is passed.
     ///
     /// # Warning
     ///
@@ -50,6 +54,26 @@ pub struct UpstreamConfig {
     #[serde(default)]
     pub ssl_root_cert: Option<PathBuf>,
 
+    /// A path to a PKCS #12 archive containing a client certificate and private key to present
+    /// to the upstream database, for upstreams which require clients to authenticate with a
+    /// certificate.
+    #[clap(long, env = "SSL_CLIENT_IDENTITY")]
+    #[serde(default)]
+    pub ssl_client_identity: Option<PathBuf>,
+
+    /// The password for the archive passed to `--ssl-client-identity`, if it's encrypted.
+    #[clap(long, env = "SSL_CLIENT_IDENTITY_PASSWORD")]
+    #[serde(default)]
+    pub ssl_client_identity_password: Option<RedactedString>,
+
+    /// How to verify the SSL certificate supplied by the upstream database: verify that it's
+    /// signed by a trusted CA and matches the upstream host name (`full`), verify only that it's
+    /// signed by a trusted CA (`ca-only`), or don't verify it at all (`disabled`). For mysql,
+    /// passing this requires connections to the upstream database to use TLS.
+    #[clap(long, arg_enum, env = "SSL_VERIFY_MODE")]
+    #[serde(default)]
+    pub ssl_verify_mode: Option<SslVerifyMode>,
+
     /// Disable running DDL Streaming Replication Setup for PostgreSQL. If this flag is set
     /// the DDL Streaming Replication Setup SQL queries will need to be manually run on the
     /// primary server before streaming replication will start.
@@ -71,6 +95,13 @@ pub struct UpstreamConfig {
     #[serde(default)]
     pub replication_tables: Option<RedactedString>,
 
+    /// A comma-separated list of tables to exclude from replication, in the same format as
+    /// `--replication-tables`. Row changes and DDL for these tables will be ignored, even if they
+    /// would otherwise be replicated according to `--replication-tables`.
+    #[clap(long, env = "REPLICATION_TABLES_IGNORE")]
+    #[serde(default)]
+    pub replication_tables_ignore: Option<RedactedString>,
+
     /// Sets the time (in seconds) between reports of progress snapshotting the database. A value
     /// of 0 disables reporting.
     #[clap(long, default_value = "30")]
@@ -81,6 +112,52 @@ pub struct UpstreamConfig {
     #[clap(long, default_value = "50")]
     #[serde(default)]
     pub replication_pool_size: usize,
+
+    /// Sets the time (in seconds) between checks that the schema of the replicated tables
+    /// matches the schema of the upstream database. Tables whose schema has drifted from the
+    /// upstream database are resnapshotted. A value of 0 disables checking.
+    #[clap(long, env = "SCHEMA_DRIFT_CHECK_INTERVAL", default_value = "300")]
+    #[serde(default = "default_schema_drift_check_interval_secs")]
+    pub schema_drift_check_interval_secs: u32,
+
+    /// Sets the interval (in seconds) at which the upstream database is asked to send heartbeats
+    /// on an idle replication connection (mysql only). If nothing is received on the replication
+    /// connection for twice this long, it's assumed to have been dropped, and the replicator
+    /// reconnects and resumes from the last position it read. A value of 0 disables heartbeats.
+    #[clap(long, env = "REPLICATION_HEARTBEAT_PERIOD", default_value = "30")]
+    #[serde(default = "default_replication_heartbeat_period_secs")]
+    pub replication_heartbeat_period_secs: u32,
+
+    /// Sets the maximum number of row operations from consecutive binlog row events for the same
+    /// table that are batched together into a single write to ReadySet (mysql only).
+    #[clap(long, env = "REPLICATION_MAX_BATCH_SIZE", default_value = "1000")]
+    #[serde(default = "default_replication_max_batch_size")]
+    pub replication_max_batch_size: usize,
+
+    /// Sets the maximum time (in milliseconds) that row operations read from the binlog are held
+    /// in a batch before being written to ReadySet, even if no more are read in the meantime
+    /// (mysql only).
+    #[clap(long, env = "REPLICATION_MAX_BATCH_LATENCY_MS", default_value = "50")]
+    #[serde(default = "default_replication_max_batch_latency_ms")]
+    pub replication_max_batch_latency_ms: u64,
+
+    /// Maximum size, in bytes, of a single row written to ReadySet, either by replication or
+    /// through the adapter. Rows larger than this are handled according to
+    /// `--oversized-row-policy` (unset = no limit)
+    #[clap(long, env = "MAX_ROW_SIZE")]
+    #[serde(default)]
+    pub max_row_size: Option<usize>,
+
+    /// What to do with rows larger than `--max-row-size`: truncate their largest text and binary
+    /// values until they fit (`truncate`), or don't write them at all (`skip`)
+    #[clap(
+        long,
+        arg_enum,
+        default_value = "truncate",
+        env = "OVERSIZED_ROW_POLICY"
+    )]
+    #[serde(default)]
+    pub oversized_row_policy: OversizedRowPolicy,
 }
 
 impl UpstreamConfig {
@@ -102,6 +179,15 @@ impl UpstreamConfig {
         }
     }
 
+    /// Returns the limit on the size of rows written to ReadySet, if one is configured. See
+    /// [`row_size`] for more information.
+    pub fn row_size_limit(&self) -> Option<RowSizeLimit> {
+        self.max_row_size.map(|max_row_size| RowSizeLimit {
+            max_row_size,
+            policy: self.oversized_row_policy,
+        })
+    }
+
     pub fn from_url<S: AsRef<str>>(url: S) -> Self {
         UpstreamConfig {
             upstream_db_url: Some(url.as_ref().to_string().into()),
@@ -118,6 +204,22 @@ fn default_snapshot_report_interval_secs() -> u16 {
     UpstreamConfig::default().snapshot_report_interval_secs
 }
 
+fn default_schema_drift_check_interval_secs() -> u32 {
+    UpstreamConfig::default().schema_drift_check_interval_secs
+}
+
+fn default_replication_heartbeat_period_secs() -> u32 {
+    UpstreamConfig::default().replication_heartbeat_period_secs
+}
+
+fn default_replication_max_batch_size() -> usize {
+    UpstreamConfig::default().replication_max_batch_size
+}
+
+fn default_replication_max_batch_latency_ms() -> u64 {
+    UpstreamConfig::default().replication_max_batch_latency_ms
+}
+
 fn duration_from_seconds(i: &str) -> Result<Duration, ParseIntError> {
     i.parse::<u64>().map(Duration::from_secs)
 }
@@ -131,9 +233,19 @@ impl Default for UpstreamConfig {
             replication_server_id: Default::default(),
             replicator_restart_timeout: Duration::from_secs(30),
             replication_tables: Default::default(),
+            replication_tables_ignore: Default::default(),
             snapshot_report_interval_secs: 30,
             ssl_root_cert: None,
+            ssl_client_identity: None,
+            ssl_client_identity_password: None,
+            ssl_verify_mode: None,
             replication_pool_size: 50,
+            schema_drift_check_interval_secs: 300,
+            replication_heartbeat_period_secs: 30,
+            replication_max_batch_size: 1000,
+            replication_max_batch_latency_ms: 50,
+            max_row_size: None,
+            oversized_row_policy: Default::default(),
         }
     }
 }
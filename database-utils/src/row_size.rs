@@ -0,0 +1,377 @@
+//! Limits on the size of individual rows written to ReadySet.
+//!
+//! A single row with very large values (such as a huge `TEXT` or `BLOB` column) has to be held in
+//! memory, serialized into one message between the adapter, the replicator and the domains, and
+//! written into one packet to clients. If [`UpstreamConfig::max_row_size`] is set, every row
+//! written to a base table - whether it's replicated from the upstream database (while
+//! snapshotting or streaming) or written directly through the adapter - is checked against the
+//! limit first, and rows which exceed it are handled according to the configured
+//! [`OversizedRowPolicy`].
+//!
+//! Truncation is deterministic, so a replicated delete of an oversized row is truncated to the
+//! same value as the row that was inserted, and still matches it.
+//!
+//! [`UpstreamConfig::max_row_size`]: crate::UpstreamConfig::max_row_size
+
+use std::sync::Arc;
+
+use readyset_client::{Modification, TableOperation};
+use readyset_data::DfValue;
+use serde::{Deserialize, Serialize};
+
+/// The size that values which aren't text or binary data are counted as, regardless of their
+/// type. This is an upper bound on the size of the encoding of most scalar values.
+const SCALAR_VALUE_SIZE: usize = 8;
+
+/// What to do with rows that exceed the configured maximum row size
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, clap::ArgEnum,
+)]
+pub enum OversizedRowPolicy {
+    /// Truncate the largest text or binary values in the row until the row fits, and log a
+    /// warning
+    #[default]
+    Truncate,
+    /// Don't write the row at all
+    Skip,
+}
+
+/// The outcome of checking a row against a [`RowSizeLimit`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowSizeCheck {
+    /// The row was within the limit, and is unchanged
+    Fits,
+    /// The row exceeded the limit, and values in it were truncated so that it fits
+    Truncated,
+    /// The row exceeded the limit, and should be skipped
+    Skip,
+}
+
+impl RowSizeCheck {
+    /// Returns the name of the action taken for an oversized row, for use in metrics, or `None` if
+    /// the row fit within the limit
+    pub fn action(self) -> Option<&'static str> {
+        match self {
+            RowSizeCheck::Fits => None,
+            RowSizeCheck::Truncated => Some("truncated"),
+            RowSizeCheck::Skip => Some("skipped"),
+        }
+    }
+}
+
+/// A maximum size for rows written to ReadySet, along with what to do with rows that exceed it.
+/// See the [module documentation](self) for more information.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RowSizeLimit {
+    /// The maximum size of a row, in bytes
+    pub max_row_size: usize,
+    /// What to do with rows larger than `max_row_size`
+    pub policy: OversizedRowPolicy,
+}
+
+/// Returns the (approximate) size of a row, in bytes: the length of each of its text and binary
+/// values, plus a fixed size for every other value
+pub fn row_size(row: &[DfValue]) -> usize {
+    row.iter().map(value_size).sum()
+}
+
+fn value_size(value: &DfValue) -> usize {
+    match value {
+        DfValue::Text(t) => t.as_bytes().len(),
+        DfValue::TinyText(t) => t.as_bytes().len(),
+        DfValue::ByteArray(b) => b.len(),
+        _ => SCALAR_VALUE_SIZE,
+    }
+}
+
+/// Truncate a text or binary value to at most `len` bytes (rounding down to a character boundary
+/// for text). Returns `None` if the value can't be truncated.
+fn truncate_value(value: &DfValue, len: usize) -> Option<DfValue> {
+    match value {
+        DfValue::Text(_) | DfValue::TinyText(_) => {
+            let s = <&str>::try_from(value).ok()?;
+            let mut end = len.min(s.len());
+            while !s.is_char_boundary(end) {
+                end -= 1;
+            }
+            Some(DfValue::from(&s[..end]))
+        }
+        DfValue::ByteArray(b) => {
+            let end = len.min(b.len());
+            Some(DfValue::ByteArray(Arc::new(b[..end].to_vec())))
+        }
+        _ => None,
+    }
+}
+
+impl RowSizeLimit {
+    /// Check `row` against this limit, truncating it in place if it's too large and the policy is
+    /// [`OversizedRowPolicy::Truncate`].
+    ///
+    /// Values are truncated largest first, so that as little of the row as possible is lost. The
+    /// values at the indices in `key_columns` - the columns of the table which are part of a key
+    /// or index - still count towards the size of the row, but are never truncated, since
+    /// truncating them could make distinct keys collide. If the row is still too large once all
+    /// its other text and binary values have been truncated to nothing, it's skipped.
+    pub fn check(&self, row: &mut [DfValue], key_columns: &[usize]) -> RowSizeCheck {
+        let mut size = row_size(row);
+        if size <= self.max_row_size {
+            return RowSizeCheck::Fits;
+        }
+        if self.policy == OversizedRowPolicy::Skip {
+            return RowSizeCheck::Skip;
+        }
+
+        while size > self.max_row_size {
+            let (idx, len) = match row
+                .iter()
+                .enumerate()
+                .filter(|(i, v)| {
+                    !key_columns.contains(i)
+                        && matches!(
+                            v,
+                            DfValue::Text(_) | DfValue::TinyText(_) | DfValue::ByteArray(_)
+                        )
+                })
+                .map(|(i, v)| (i, value_size(v)))
+                .filter(|(_, len)| *len > 0)
+                .max_by_key(|(_, len)| *len)
+            {
+                Some(largest) => largest,
+                None => return RowSizeCheck::Skip,
+            };
+            let excess = size - self.max_row_size;
+            let truncated = match truncate_value(&row[idx], len.saturating_sub(excess)) {
+                Some(truncated) => truncated,
+                None => return RowSizeCheck::Skip,
+            };
+            size = size - len + value_size(&truncated);
+            row[idx] = truncated;
+        }
+
+        RowSizeCheck::Truncated
+    }
+
+    /// Check the values set by `update`, given as pairs of column index and modification, against
+    /// this limit as if they were a row, truncating them in place if they're too large and the
+    /// policy is [`OversizedRowPolicy::Truncate`]. Values set for any of the `key_columns` are
+    /// never truncated.
+    pub fn check_update<'a, I>(&self, update: I, key_columns: &[usize]) -> RowSizeCheck
+    where
+        I: IntoIterator<Item = (usize, &'a mut Modification)>,
+    {
+        let mut values = update
+            .into_iter()
+            .filter_map(|(col, m)| match m {
+                Modification::Set(v) => Some((col, v)),
+                Modification::Apply(..) | Modification::None => None,
+            })
+            .collect::<Vec<_>>();
+        let mut row = values
+            .iter()
+            .map(|(_, v)| (**v).clone())
+            .collect::<Vec<_>>();
+        // Map the key columns onto positions in the row of set values
+        let row_key_columns = values
+            .iter()
+            .enumerate()
+            .filter(|(_, (col, _))| key_columns.contains(col))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        let check = self.check(&mut row, &row_key_columns);
+        if check == RowSizeCheck::Truncated {
+            for ((_, value), truncated) in values.iter_mut().zip(row) {
+                **value = truncated;
+            }
+        }
+        check
+    }
+
+    /// Check all the row data in `op` against this limit, truncating it in place if it's too
+    /// large and the policy is [`OversizedRowPolicy::Truncate`]. Values in any of the
+    /// `key_columns` are never truncated.
+    pub fn check_op(&self, op: &mut TableOperation, key_columns: &[usize]) -> RowSizeCheck {
+        match op {
+            TableOperation::Insert(row) | TableOperation::DeleteRow { row } => {
+                self.check(row, key_columns)
+            }
+            TableOperation::Update { update, .. } => {
+                self.check_update(update.iter_mut().enumerate(), key_columns)
+            }
+            TableOperation::InsertOrUpdate { row, update } => {
+                match (
+                    self.check(row, key_columns),
+                    self.check_update(update.iter_mut().enumerate(), key_columns),
+                ) {
+                    (RowSizeCheck::Skip, _) | (_, RowSizeCheck::Skip) => RowSizeCheck::Skip,
+                    (RowSizeCheck::Fits, RowSizeCheck::Fits) => RowSizeCheck::Fits,
+                    _ => RowSizeCheck::Truncated,
+                }
+            }
+            // Keys are never truncated, since they have to match the keys of the stored rows
+            TableOperation::DeleteByKey { .. }
+            | TableOperation::Truncate
+            | TableOperation::SetReplicationOffset(_)
+            | TableOperation::SetSnapshotMode(_) => RowSizeCheck::Fits,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(max_row_size: usize, policy: OversizedRowPolicy) -> RowSizeLimit {
+        RowSizeLimit {
+            max_row_size,
+            policy,
+        }
+    }
+
+    #[test]
+    fn rows_within_limit_are_unchanged() {
+        let mut row = vec![DfValue::from(1), DfValue::from("abc")];
+        assert_eq!(row_size(&row), 11);
+        assert_eq!(
+            limit(11, OversizedRowPolicy::Skip).check(&mut row, &[]),
+            RowSizeCheck::Fits
+        );
+        assert_eq!(row, vec![DfValue::from(1), DfValue::from("abc")]);
+    }
+
+    #[test]
+    fn truncates_largest_values_first() {
+        let mut row = vec![
+            DfValue::from(1),
+            DfValue::from("a".repeat(20)),
+            DfValue::ByteArray(Arc::new(vec![0; 10])),
+        ];
+        let limit = limit(30, OversizedRowPolicy::Truncate);
+        assert_eq!(limit.check(&mut row, &[]), RowSizeCheck::Truncated);
+        assert_eq!(
+            row,
+            vec![
+                DfValue::from(1),
+                DfValue::from("a".repeat(12)),
+                DfValue::ByteArray(Arc::new(vec![0; 10])),
+            ]
+        );
+
+        // Truncation is deterministic, so deletes of the same row match the truncated row
+        let mut again = vec![
+            DfValue::from(1),
+            DfValue::from("a".repeat(20)),
+            DfValue::ByteArray(Arc::new(vec![0; 10])),
+        ];
+        limit.check(&mut again, &[]);
+        assert_eq!(row, again);
+    }
+
+    #[test]
+    fn truncates_text_on_char_boundaries() {
+        let mut row = vec![DfValue::from("ééé")];
+        assert_eq!(
+            limit(3, OversizedRowPolicy::Truncate).check(&mut row, &[]),
+            RowSizeCheck::Truncated
+        );
+        assert_eq!(row, vec![DfValue::from("é")]);
+    }
+
+    #[test]
+    fn checks_all_operations() {
+        let limit = limit(10, OversizedRowPolicy::Truncate);
+
+        let mut update = TableOperation::Update {
+            key: vec![DfValue::from("a".repeat(20))],
+            update: vec![
+                Modification::None,
+                Modification::Set(DfValue::from("a".repeat(20))),
+            ],
+        };
+        assert_eq!(limit.check_op(&mut update, &[]), RowSizeCheck::Truncated);
+        assert_eq!(
+            update,
+            TableOperation::Update {
+                key: vec![DfValue::from("a".repeat(20))],
+                update: vec![
+                    Modification::None,
+                    Modification::Set(DfValue::from("a".repeat(10))),
+                ],
+            }
+        );
+
+        let mut insert_or_update = TableOperation::InsertOrUpdate {
+            row: vec![DfValue::from(1), DfValue::from("a".repeat(20))],
+            update: vec![Modification::None, Modification::Set(DfValue::from(2))],
+        };
+        assert_eq!(
+            limit.check_op(&mut insert_or_update, &[]),
+            RowSizeCheck::Truncated
+        );
+        assert_eq!(
+            insert_or_update,
+            TableOperation::InsertOrUpdate {
+                row: vec![DfValue::from(1), DfValue::from("a".repeat(2))],
+                update: vec![Modification::None, Modification::Set(DfValue::from(2))],
+            }
+        );
+
+        let mut delete = TableOperation::DeleteByKey {
+            key: vec![DfValue::from("a".repeat(20))],
+        };
+        assert_eq!(limit.check_op(&mut delete, &[]), RowSizeCheck::Fits);
+    }
+
+    #[test]
+    fn never_truncates_key_columns() {
+        let limit = limit(30, OversizedRowPolicy::Truncate);
+
+        let mut row = vec![DfValue::from("k".repeat(20)), DfValue::from("a".repeat(15))];
+        assert_eq!(limit.check(&mut row, &[0]), RowSizeCheck::Truncated);
+        assert_eq!(
+            row,
+            vec![DfValue::from("k".repeat(20)), DfValue::from("a".repeat(10))]
+        );
+
+        // If the key columns alone are too large, the row is skipped rather than truncated
+        let mut row = vec![DfValue::from("k".repeat(40)), DfValue::from("a".repeat(15))];
+        assert_eq!(limit.check(&mut row, &[0]), RowSizeCheck::Skip);
+
+        let mut update = TableOperation::Update {
+            key: vec![DfValue::from(1)],
+            update: vec![
+                Modification::None,
+                Modification::Set(DfValue::from("k".repeat(20))),
+                Modification::Set(DfValue::from("a".repeat(15))),
+            ],
+        };
+        assert_eq!(limit.check_op(&mut update, &[1]), RowSizeCheck::Truncated);
+        assert_eq!(
+            update,
+            TableOperation::Update {
+                key: vec![DfValue::from(1)],
+                update: vec![
+                    Modification::None,
+                    Modification::Set(DfValue::from("k".repeat(20))),
+                    Modification::Set(DfValue::from("a".repeat(10))),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn skips_oversized_rows() {
+        let mut row = vec![DfValue::from("a".repeat(20))];
+        assert_eq!(
+            limit(10, OversizedRowPolicy::Skip).check(&mut row, &[]),
+            RowSizeCheck::Skip
+        );
+
+        // Rows which can't be truncated enough are skipped too
+        let mut row = vec![DfValue::from(1), DfValue::from(2)];
+        assert_eq!(
+            limit(10, OversizedRowPolicy::Truncate).check(&mut row, &[]),
+            RowSizeCheck::Skip
+        );
+    }
+}
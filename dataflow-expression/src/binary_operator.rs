@@ -50,6 +50,10 @@ pub enum BinaryOperator {
     /// `<=`
     LessOrEqual,
 
+    /// MySQL `<=>`, which behaves like [`BinaryOperator::Equal`] but treats NULL as equal to
+    /// NULL
+    NullSafeEqual,
+
     /// `IS`
     Is,
 
@@ -68,6 +72,27 @@ pub enum BinaryOperator {
     /// `/`
     Divide,
 
+    /// `%` or MySQL `MOD`
+    Modulo,
+
+    /// MySQL `DIV`, which divides its operands and truncates the result to an integer
+    IntegerDivide,
+
+    /// MySQL `&`, which operates on its operands converted to unsigned 64-bit integers
+    BitwiseAnd,
+
+    /// MySQL `|`, which operates on its operands converted to unsigned 64-bit integers
+    BitwiseOr,
+
+    /// MySQL `^`, which operates on its operands converted to unsigned 64-bit integers
+    BitwiseXor,
+
+    /// MySQL `<<`, which operates on its operands converted to unsigned 64-bit integers
+    ShiftLeft,
+
+    /// MySQL `>>`, which operates on its operands converted to unsigned 64-bit integers
+    ShiftRight,
+
     /// `?`
     JsonExists,
 
@@ -140,6 +165,10 @@ impl BinaryOperator {
             GreaterOrEqual => Self::GreaterOrEqual,
             Less => Self::Less,
             LessOrEqual => Self::LessOrEqual,
+            NullSafeEqual if dialect.engine() != SqlEngine::MySQL => {
+                unsupported!("'{op}' not available in {}", dialect.engine())
+            }
+            NullSafeEqual => Self::NullSafeEqual,
             Add => Self::Add,
             Subtract => {
                 if left_type.is_jsonb() {
@@ -151,6 +180,18 @@ impl BinaryOperator {
             HashSubtract => Self::JsonSubtractPath,
             Multiply => Self::Multiply,
             Divide => Self::Divide,
+            Modulo => Self::Modulo,
+            IntegerDivide | BitwiseAnd | BitwiseOr | BitwiseXor | ShiftLeft | ShiftRight
+                if dialect.engine() != SqlEngine::MySQL =>
+            {
+                unsupported!("'{op}' not available in {}", dialect.engine())
+            }
+            IntegerDivide => Self::IntegerDivide,
+            BitwiseAnd => Self::BitwiseAnd,
+            BitwiseOr => Self::BitwiseOr,
+            BitwiseXor => Self::BitwiseXor,
+            ShiftLeft => Self::ShiftLeft,
+            ShiftRight => Self::ShiftRight,
             Like => Self::Like,
             NotLike => Self::NotLike,
             ILike => Self::ILike,
@@ -293,6 +334,7 @@ impl BinaryOperator {
             | Self::GreaterOrEqual
             | Self::Less
             | Self::LessOrEqual
+            | Self::NullSafeEqual
             | Self::Is
             | Self::IsNot
             | Self::JsonExists
@@ -305,6 +347,14 @@ impl BinaryOperator {
             | Self::JsonKeyExtractText
             | Self::JsonKeyPathExtractText => Ok(DfType::DEFAULT_TEXT),
 
+            Self::IntegerDivide => Ok(DfType::BigInt),
+
+            Self::BitwiseAnd
+            | Self::BitwiseOr
+            | Self::BitwiseXor
+            | Self::ShiftLeft
+            | Self::ShiftRight => Ok(DfType::UnsignedBigInt),
+
             _ => Ok(left_type.clone()),
         }
     }
@@ -325,6 +375,7 @@ impl fmt::Display for BinaryOperator {
             Self::GreaterOrEqual => ">=",
             Self::Less => "<",
             Self::LessOrEqual => "<=",
+            Self::NullSafeEqual => "<=>",
             Self::Is => "IS",
             Self::IsNot => "IS NOT",
             Self::Add => "+",
@@ -332,6 +383,13 @@ impl fmt::Display for BinaryOperator {
             Self::JsonSubtractPath => "#-",
             Self::Multiply => "*",
             Self::Divide => "/",
+            Self::Modulo => "%",
+            Self::IntegerDivide => "DIV",
+            Self::BitwiseAnd => "&",
+            Self::BitwiseOr => "|",
+            Self::BitwiseXor => "^",
+            Self::ShiftLeft => "<<",
+            Self::ShiftRight => ">>",
             Self::JsonExists => "?",
             Self::JsonAnyExists => "?|",
             Self::JsonAllExists => "?&",
@@ -365,6 +423,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn mysql_only_operators() {
+        for op in [
+            SqlBinaryOperator::IntegerDivide,
+            SqlBinaryOperator::BitwiseAnd,
+            SqlBinaryOperator::BitwiseOr,
+            SqlBinaryOperator::BitwiseXor,
+            SqlBinaryOperator::ShiftLeft,
+            SqlBinaryOperator::ShiftRight,
+        ] {
+            BinaryOperator::from_sql_op(op, Dialect::DEFAULT_MYSQL, &DfType::Int, &DfType::Int)
+                .unwrap();
+            BinaryOperator::from_sql_op(
+                op,
+                Dialect::DEFAULT_POSTGRESQL,
+                &DfType::Int,
+                &DfType::Int,
+            )
+            .unwrap_err();
+        }
+    }
+
     mod output_type {
         use super::*;
 
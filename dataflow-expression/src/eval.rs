@@ -1,11 +1,14 @@
-use std::borrow::Borrow;
+use std::borrow::{Borrow, Cow};
+use std::str::FromStr;
 
-use readyset_data::{Array, ArrayD, DfType, DfValue, IxDyn};
+use readyset_data::{Array, ArrayD, DfType, DfValue, Dialect, IxDyn};
 use readyset_errors::{invalid_err, unsupported, ReadySetError, ReadySetResult};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
 use serde_json::Value as JsonValue;
 
 use crate::like::{CaseInsensitive, CaseSensitive, LikePattern};
-use crate::{utils, BinaryOperator, CaseWhenBranch, Expr};
+use crate::{utils, BinaryOperator, CaseWhenBranch, Expr, UnaryOperator};
 
 macro_rules! non_null {
     ($df_value:expr) => {
@@ -19,10 +22,239 @@ macro_rules! non_null {
 mod builtins;
 mod json;
 
+/// Returns the longest prefix of `s` (after any leading whitespace) which is a valid number, the
+/// way MySQL converts strings to numbers
+fn numeric_prefix(s: &str) -> &str {
+    let s = s.trim_start();
+    let bytes = s.as_bytes();
+    let digits_from = |mut i: usize| {
+        while bytes.get(i).map_or(false, u8::is_ascii_digit) {
+            i += 1;
+        }
+        i
+    };
+
+    let mut end = if matches!(bytes.first(), Some(b'+' | b'-')) {
+        1
+    } else {
+        0
+    };
+    let int_end = digits_from(end);
+    let mut has_digits = int_end > end;
+    end = int_end;
+    if bytes.get(end) == Some(&b'.') {
+        let frac_end = digits_from(end + 1);
+        has_digits |= frac_end > end + 1;
+        end = frac_end;
+    }
+    if !has_digits {
+        return "";
+    }
+    if matches!(bytes.get(end), Some(b'e' | b'E')) {
+        let exp_start = end + usize::from(matches!(bytes.get(end + 1), Some(b'+' | b'-'))) + 1;
+        let exp_end = digits_from(exp_start);
+        if exp_end > exp_start {
+            end = exp_end;
+        }
+    }
+    &s[..end]
+}
+
+/// Convert `value` to a number if it's a string, for use as an operand of an arithmetic operator
+fn numeric_operand(value: &DfValue, dialect: Dialect) -> ReadySetResult<Cow<'_, DfValue>> {
+    let s = match value.as_str() {
+        Some(s) => s,
+        None => return Ok(Cow::Borrowed(value)),
+    };
+
+    if dialect.lenient_string_to_number() {
+        let prefix = numeric_prefix(s);
+        let n = if prefix.is_empty() {
+            0.0
+        } else {
+            prefix.parse::<f64>().unwrap_or_default()
+        };
+        Ok(Cow::Owned(DfValue::try_from(n)?))
+    } else if let Ok(n) = s.trim().parse::<i64>() {
+        Ok(Cow::Owned(n.into()))
+    } else {
+        Decimal::from_str(s.trim())
+            .map(|d| Cow::Owned(d.into()))
+            .map_err(|_| invalid_err!("invalid input syntax for type numeric: \"{s}\""))
+    }
+}
+
+/// Returns true if `value` is a numeric zero
+fn is_zero(value: &DfValue) -> bool {
+    match value {
+        DfValue::Int(n) => *n == 0,
+        DfValue::UnsignedInt(n) => *n == 0,
+        DfValue::Float(f) => *f == 0.0,
+        DfValue::Double(f) => *f == 0.0,
+        DfValue::Numeric(d) => d.is_zero(),
+        _ => false,
+    }
+}
+
+/// Returns the result of dividing by zero according to the semantics of `dialect`
+fn division_by_zero(dialect: Dialect) -> ReadySetResult<DfValue> {
+    if dialect.division_by_zero_is_null() {
+        Ok(DfValue::None)
+    } else {
+        Err(invalid_err!("division by zero"))
+    }
+}
+
+/// Returns the remainder of dividing the number `left` by the (non-zero) number `right`, which
+/// has the same sign as `left`
+fn modulo(left: &DfValue, right: &DfValue) -> ReadySetResult<DfValue> {
+    match (left, right) {
+        (DfValue::Float(_) | DfValue::Double(_), _)
+        | (_, DfValue::Float(_) | DfValue::Double(_)) => {
+            DfValue::try_from(f64::try_from(left)? % f64::try_from(right)?)
+        }
+        (DfValue::Numeric(_), _) | (_, DfValue::Numeric(_)) => {
+            Ok((Decimal::try_from(left)? % Decimal::try_from(right)?).into())
+        }
+        _ => DfValue::try_from(i128::try_from(left)? % i128::try_from(right)?),
+    }
+}
+
+/// Returns the result of dividing the number `left` by the (non-zero) number `right`, truncated
+/// to an integer, as MySQL's `DIV` operator does
+fn integer_divide(left: &DfValue, right: &DfValue) -> ReadySetResult<DfValue> {
+    let quotient = match (left, right) {
+        (DfValue::Float(_) | DfValue::Double(_), _)
+        | (_, DfValue::Float(_) | DfValue::Double(_)) => {
+            let quotient = (f64::try_from(left)? / f64::try_from(right)?).trunc();
+            quotient.to_i128()
+        }
+        (DfValue::Numeric(_), _) | (_, DfValue::Numeric(_)) => Decimal::try_from(left)?
+            .checked_div(Decimal::try_from(right)?)
+            .and_then(|quotient| quotient.trunc().to_i128()),
+        _ => Some(i128::try_from(left)? / i128::try_from(right)?),
+    };
+    quotient
+        .ok_or_else(|| invalid_err!("result of {left} DIV {right} is out of range"))
+        .and_then(DfValue::try_from)
+}
+
+/// Convert the number `value` to an unsigned 64-bit integer, for use as an operand of a bitwise
+/// operator, the way MySQL does: non-integers are rounded to the nearest integer, and negative
+/// integers are reinterpreted as their two's complement
+fn bitwise_operand(value: &DfValue) -> ReadySetResult<u64> {
+    match value {
+        DfValue::Int(i) => Ok(*i as u64),
+        DfValue::UnsignedInt(u) => Ok(*u),
+        DfValue::Float(_) | DfValue::Double(_) => {
+            let f = f64::try_from(value)?.round();
+            Ok(if f < 0.0 { f as i64 as u64 } else { f as u64 })
+        }
+        DfValue::Numeric(d) => {
+            let d = d.round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero);
+            Ok(match d.to_i64() {
+                Some(i) => i as u64,
+                None => d
+                    .to_u64()
+                    .unwrap_or(if d.is_sign_negative() { 0 } else { u64::MAX }),
+            })
+        }
+        _ => u64::try_from(value),
+    }
+}
+
+/// Returns the negation of the number `value`, or NULL if the negation is out of range (as for
+/// the other arithmetic operators)
+fn negate(value: &DfValue) -> ReadySetResult<DfValue> {
+    match value {
+        DfValue::Int(i) => Ok(i.checked_neg().map_or(DfValue::None, DfValue::from)),
+        DfValue::UnsignedInt(u) => Ok(DfValue::try_from(-i128::from(*u)).unwrap_or(DfValue::None)),
+        DfValue::Float(f) => Ok(DfValue::Float(-f)),
+        DfValue::Double(f) => Ok(DfValue::Double(-f)),
+        DfValue::Numeric(d) => Ok((-**d).into()),
+        _ => Err(invalid_err!("cannot negate {value}")),
+    }
+}
+
+/// Returns whether `value` is true when used as a boolean condition, according to the semantics of
+/// `dialect`
+fn is_truthy(value: &DfValue, dialect: Dialect) -> ReadySetResult<bool> {
+    let s = match value.as_str() {
+        Some(s) => s,
+        None => return Ok(value.is_truthy()),
+    };
+
+    if dialect.lenient_string_to_number() {
+        return Ok(numeric_operand(value, dialect)?.is_truthy());
+    }
+
+    // Postgres accepts any unique prefix of `true`, `false`, `yes`, or `no`, as well as `on`,
+    // `off`, `1`, and `0`
+    let s = s.trim().to_ascii_lowercase();
+    match s.as_str() {
+        "1" | "on" => Ok(true),
+        "0" | "of" | "off" => Ok(false),
+        _ if !s.is_empty() && ("true".starts_with(&s) || "yes".starts_with(&s)) => Ok(true),
+        _ if !s.is_empty() && ("false".starts_with(&s) || "no".starts_with(&s)) => Ok(false),
+        _ => Err(invalid_err!(
+            "invalid input syntax for type boolean: \"{s}\""
+        )),
+    }
+}
+
+/// Coerce `value`, of type `from_ty`, to `to_ty`, unless the types are already the same. Once an
+/// expression has been [type checked](Expr::typecheck), the operands of equality comparisons
+/// against literals usually have the same type, so this avoids coercing (and cloning) them for
+/// every row.
+fn coerce_operand<'a>(
+    value: &'a DfValue,
+    to_ty: &DfType,
+    from_ty: &DfType,
+) -> ReadySetResult<Cow<'a, DfValue>> {
+    if to_ty == from_ty {
+        Ok(Cow::Borrowed(value))
+    } else {
+        Ok(Cow::Owned(value.coerce_to(to_ty, from_ty)?))
+    }
+}
+
+/// Evaluate `op` on operands which are known to never be NULL (see
+/// [`Expr::analyze_nullability`]), without checking them for NULL or converting them from strings.
+///
+/// Returns `None` for operators which don't have a faster path, and for operands which are strings,
+/// in which case the operation should be evaluated with [`eval_binary_op`] instead.
+fn eval_non_null_binary_op(
+    op: BinaryOperator,
+    (left, left_ty): (&DfValue, &DfType),
+    (right, right_ty): (&DfValue, &DfType),
+) -> Option<ReadySetResult<DfValue>> {
+    use BinaryOperator::*;
+
+    if left.as_str().is_some() || right.as_str().is_some() {
+        return None;
+    }
+
+    Some(match op {
+        Add => left + right,
+        Subtract => left - right,
+        Multiply => left * right,
+        And => Ok((left.is_truthy() && right.is_truthy()).into()),
+        Or => Ok((left.is_truthy() || right.is_truthy()).into()),
+        Equal if left_ty == right_ty => Ok((left == right).into()),
+        NotEqual if left_ty == right_ty => Ok((left != right).into()),
+        Greater => Ok((left > right).into()),
+        GreaterOrEqual => Ok((left >= right).into()),
+        Less => Ok((left < right).into()),
+        LessOrEqual => Ok((left <= right).into()),
+        _ => return None,
+    })
+}
+
 fn eval_binary_op(
     op: BinaryOperator,
     (left, left_ty): (&DfValue, &DfType),
     (right, right_ty): (&DfValue, &DfType),
+    dialect: Dialect,
 ) -> ReadySetResult<DfValue> {
     use BinaryOperator::*;
 
@@ -56,19 +288,93 @@ fn eval_binary_op(
         .into()
     };
 
+    let numeric_operands = || -> ReadySetResult<_> {
+        Ok((
+            numeric_operand(left, dialect)?,
+            numeric_operand(right, dialect)?,
+        ))
+    };
+
+    let bitwise = |f: fn(u64, u64) -> u64| -> ReadySetResult<DfValue> {
+        let (left, right) = numeric_operands()?;
+        let (left, right) = (non_null!(&*left), non_null!(&*right));
+        Ok(f(bitwise_operand(left)?, bitwise_operand(right)?).into())
+    };
+
     match op {
-        Add => Ok((non_null!(left) + non_null!(right))?),
-        Subtract => Ok((non_null!(left) - non_null!(right))?),
-        Multiply => Ok((non_null!(left) * non_null!(right))?),
-        Divide => Ok((non_null!(left) / non_null!(right))?),
-        And => Ok((non_null!(left).is_truthy() && non_null!(right).is_truthy()).into()),
-        Or => Ok((non_null!(left).is_truthy() || non_null!(right).is_truthy()).into()),
-        Equal => Ok((non_null!(left) == &non_null!(right).coerce_to(left_ty, right_ty)?).into()),
-        NotEqual => Ok((non_null!(left) != &non_null!(right).coerce_to(left_ty, right_ty)?).into()),
+        Add => {
+            let (left, right) = numeric_operands()?;
+            Ok((non_null!(&*left) + non_null!(&*right))?)
+        }
+        Subtract => {
+            let (left, right) = numeric_operands()?;
+            Ok((non_null!(&*left) - non_null!(&*right))?)
+        }
+        Multiply => {
+            let (left, right) = numeric_operands()?;
+            Ok((non_null!(&*left) * non_null!(&*right))?)
+        }
+        Divide => {
+            let (left, right) = numeric_operands()?;
+            let (left, right) = (non_null!(&*left), non_null!(&*right));
+            if is_zero(right) {
+                return division_by_zero(dialect);
+            }
+            Ok((left / right)?)
+        }
+        Modulo => {
+            let (left, right) = numeric_operands()?;
+            let (left, right) = (non_null!(&*left), non_null!(&*right));
+            if is_zero(right) {
+                return division_by_zero(dialect);
+            }
+            modulo(left, right)
+        }
+        IntegerDivide => {
+            let (left, right) = numeric_operands()?;
+            let (left, right) = (non_null!(&*left), non_null!(&*right));
+            if is_zero(right) {
+                return division_by_zero(dialect);
+            }
+            integer_divide(left, right)
+        }
+        BitwiseAnd => bitwise(|left, right| left & right),
+        BitwiseOr => bitwise(|left, right| left | right),
+        BitwiseXor => bitwise(|left, right| left ^ right),
+        // Shifting by 64 or more bits shifts out every bit
+        ShiftLeft => bitwise(|left, right| {
+            u32::try_from(right)
+                .ok()
+                .and_then(|right| left.checked_shl(right))
+                .unwrap_or(0)
+        }),
+        ShiftRight => bitwise(|left, right| {
+            u32::try_from(right)
+                .ok()
+                .and_then(|right| left.checked_shr(right))
+                .unwrap_or(0)
+        }),
+        And => Ok(
+            (is_truthy(non_null!(left), dialect)? && is_truthy(non_null!(right), dialect)?).into(),
+        ),
+        Or => Ok(
+            (is_truthy(non_null!(left), dialect)? || is_truthy(non_null!(right), dialect)?).into(),
+        ),
+        Equal => {
+            Ok((non_null!(left) == &*coerce_operand(non_null!(right), left_ty, right_ty)?).into())
+        }
+        NotEqual => {
+            Ok((non_null!(left) != &*coerce_operand(non_null!(right), left_ty, right_ty)?).into())
+        }
         Greater => Ok((non_null!(left) > non_null!(right)).into()),
         GreaterOrEqual => Ok((non_null!(left) >= non_null!(right)).into()),
         Less => Ok((non_null!(left) < non_null!(right)).into()),
         LessOrEqual => Ok((non_null!(left) <= non_null!(right)).into()),
+        NullSafeEqual => match (left, right) {
+            (DfValue::None, DfValue::None) => Ok(true.into()),
+            (DfValue::None, _) | (_, DfValue::None) => Ok(false.into()),
+            _ => Ok((left == &*coerce_operand(right, left_ty, right_ty)?).into()),
+        },
         Is => Ok((left == right).into()),
         IsNot => Ok((left != right).into()),
         Like => Ok(like(CaseSensitive, false)),
@@ -262,11 +568,51 @@ impl Expr {
                 .ok_or(ReadySetError::ProjectExprInvalidColumnIndex(*index)),
             Expr::Literal { val, .. } => Ok(val.clone()),
             Expr::Op {
-                op, left, right, ..
+                op,
+                left,
+                right,
+                dialect,
+                non_null_operands,
+                ..
             } => {
                 let left_val = left.eval(record)?;
+                // Don't bother evaluating the right-hand side of boolean operators if the
+                // left-hand side already determines the result
+                match op {
+                    BinaryOperator::And
+                        if !left_val.is_none() && !is_truthy(&left_val, *dialect)? =>
+                    {
+                        return Ok(false.into())
+                    }
+                    BinaryOperator::Or if is_truthy(&left_val, *dialect)? => return Ok(true.into()),
+                    _ => {}
+                }
                 let right_val = right.eval(record)?;
-                eval_binary_op(*op, (&left_val, left.ty()), (&right_val, right.ty()))
+                if *non_null_operands {
+                    if let Some(res) = eval_non_null_binary_op(
+                        *op,
+                        (&left_val, left.ty()),
+                        (&right_val, right.ty()),
+                    ) {
+                        return res;
+                    }
+                }
+                eval_binary_op(
+                    *op,
+                    (&left_val, left.ty()),
+                    (&right_val, right.ty()),
+                    *dialect,
+                )
+            }
+            Expr::UnaryOp {
+                op, expr, dialect, ..
+            } => {
+                let val = non_null!(expr.eval(record)?);
+                match op {
+                    UnaryOperator::Neg => negate(&numeric_operand(&val, *dialect)?),
+                    // NOT NULL is NULL, which is handled above
+                    UnaryOperator::Not => Ok((!is_truthy(&val, *dialect)?).into()),
+                }
             }
             Expr::OpAny {
                 op, left, right, ..
@@ -280,8 +626,14 @@ impl Expr {
                 }
                 let mut res = DfValue::from(false);
                 for member in right_val.as_array()?.values() {
-                    if eval_binary_op(*op, (&left_val, left.ty()), (member, right_member_ty))?
-                        .is_truthy()
+                    if eval_binary_op(
+                        *op,
+                        (&left_val, left.ty()),
+                        (member, right_member_ty),
+                        // ANY and ALL only exist in PostgreSQL
+                        Dialect::DEFAULT_POSTGRESQL,
+                    )?
+                    .is_truthy()
                     {
                         res = true.into();
                         break;
@@ -301,8 +653,13 @@ impl Expr {
                 }
                 let mut res = DfValue::from(true);
                 for member in right_val.as_array()?.values() {
-                    if !eval_binary_op(*op, (&left_val, left.ty()), (member, right_member_ty))?
-                        .is_truthy()
+                    if !eval_binary_op(
+                        *op,
+                        (&left_val, left.ty()),
+                        (member, right_member_ty),
+                        Dialect::DEFAULT_POSTGRESQL,
+                    )?
+                    .is_truthy()
                     {
                         res = false.into();
                         break;
@@ -312,6 +669,9 @@ impl Expr {
             }
             Expr::Cast { expr, ty, .. } => {
                 let res = expr.eval(record)?;
+                if ty == expr.ty() {
+                    return Ok(res);
+                }
                 Ok(res.coerce_to(ty, expr.ty())?)
             }
             Expr::Call { func, ty } => func.eval(ty, record),
@@ -421,9 +781,13 @@ mod tests {
                 right: Box::new(make_literal(3.into())),
                 op: BinaryOperator::Add,
                 ty: DfType::Unknown,
+                dialect: Dialect::DEFAULT_MYSQL,
+                non_null_operands: false,
             }),
             op: BinaryOperator::Add,
             ty: DfType::Unknown,
+            dialect: Dialect::DEFAULT_MYSQL,
+            non_null_operands: false,
         };
         assert_eq!(
             expr.eval(&[DfValue::from(1), DfValue::from(2)]).unwrap(),
@@ -431,6 +795,138 @@ mod tests {
         );
     }
 
+    #[test]
+    fn eval_boolean_ops_short_circuit() {
+        // Column 5 doesn't exist, so evaluating the right-hand side would be an error
+        let expr = |op, left: DfValue| Op {
+            left: Box::new(make_literal(left)),
+            right: Box::new(make_column(5)),
+            op,
+            ty: DfType::Bool,
+            dialect: Dialect::DEFAULT_MYSQL,
+            non_null_operands: false,
+        };
+        assert_eq!(
+            expr(BinaryOperator::And, false.into())
+                .eval::<DfValue>(&[])
+                .unwrap(),
+            false.into()
+        );
+        assert_eq!(
+            expr(BinaryOperator::Or, true.into())
+                .eval::<DfValue>(&[])
+                .unwrap(),
+            true.into()
+        );
+        expr(BinaryOperator::And, true.into())
+            .eval::<DfValue>(&[])
+            .unwrap_err();
+        expr(BinaryOperator::And, DfValue::None)
+            .eval::<DfValue>(&[])
+            .unwrap_err();
+        expr(BinaryOperator::Or, false.into())
+            .eval::<DfValue>(&[])
+            .unwrap_err();
+    }
+
+    #[test]
+    fn division_by_zero() {
+        assert_eq!(eval_expr("1 / 0", MySQL), DfValue::None);
+        assert_eq!(eval_expr("1.5 / 0", MySQL), DfValue::None);
+        try_eval_expr("1 / 0", PostgreSQL).unwrap_err();
+        try_eval_expr("1.5 / 0", PostgreSQL).unwrap_err();
+        assert_eq!(eval_expr("1 % 0", MySQL), DfValue::None);
+        assert_eq!(eval_expr("1.5 MOD 0.0", MySQL), DfValue::None);
+        assert_eq!(eval_expr("1 DIV 0", MySQL), DfValue::None);
+        try_eval_expr("1 % 0", PostgreSQL).unwrap_err();
+    }
+
+    #[test]
+    fn eval_modulo() {
+        assert_eq!(eval_expr("17 % 8", MySQL), DfValue::from(1));
+        assert_eq!(eval_expr("17 MOD 8", MySQL), DfValue::from(1));
+        assert_eq!(eval_expr("-17 % 8", MySQL), DfValue::from(-1));
+        assert_eq!(eval_expr("17 % -8", PostgreSQL), DfValue::from(1));
+        assert_eq!(eval_expr("5.5 % 2", MySQL), DfValue::Double(1.5));
+        assert_eq!(eval_expr("'17' % 8", MySQL), DfValue::Double(1.0));
+        assert_eq!(eval_expr("null % 8", MySQL), DfValue::None);
+    }
+
+    #[test]
+    fn eval_integer_divide() {
+        assert_eq!(eval_expr("17 DIV 8", MySQL), DfValue::from(2));
+        assert_eq!(eval_expr("-17 DIV 8", MySQL), DfValue::from(-2));
+        assert_eq!(eval_expr("5.5 DIV 2", MySQL), DfValue::from(2));
+        assert_eq!(eval_expr("'17abc' DIV 8", MySQL), DfValue::from(2));
+        assert_eq!(eval_expr("null DIV 8", MySQL), DfValue::None);
+    }
+
+    #[test]
+    fn eval_bitwise_operators() {
+        assert_eq!(eval_expr("12 & 10", MySQL), DfValue::from(8u64));
+        assert_eq!(eval_expr("12 | 10", MySQL), DfValue::from(14u64));
+        assert_eq!(eval_expr("12 ^ 10", MySQL), DfValue::from(6u64));
+        assert_eq!(eval_expr("1 << 3", MySQL), DfValue::from(8u64));
+        assert_eq!(eval_expr("8 >> 3", MySQL), DfValue::from(1u64));
+        assert_eq!(eval_expr("1 << 64", MySQL), DfValue::from(0u64));
+        assert_eq!(eval_expr("1 << -1", MySQL), DfValue::from(0u64));
+        assert_eq!(eval_expr("-1 | 0", MySQL), DfValue::from(u64::MAX));
+        assert_eq!(eval_expr("2.5 | 0", MySQL), DfValue::from(3u64));
+        assert_eq!(eval_expr("null & 1", MySQL), DfValue::None);
+    }
+
+    #[test]
+    fn string_arithmetic() {
+        assert_eq!(eval_expr("'3abc' + 1", MySQL), DfValue::Double(4.0));
+        assert_eq!(eval_expr("'abc' * 2", MySQL), DfValue::Double(0.0));
+        assert_eq!(eval_expr("'1.5e1' - 5", MySQL), DfValue::Double(10.0));
+        assert_eq!(eval_expr("'3' + 1", PostgreSQL), DfValue::from(4));
+        try_eval_expr("'3abc' + 1", PostgreSQL).unwrap_err();
+    }
+
+    #[test]
+    fn eval_negation() {
+        assert_eq!(eval_expr("-(2 + 3)", MySQL), DfValue::from(-5));
+        assert_eq!(eval_expr("-(-5)", PostgreSQL), DfValue::from(5));
+        assert_eq!(
+            eval_expr("-(9223372036854775808)", MySQL),
+            DfValue::from(i64::MIN)
+        );
+        assert_eq!(eval_expr("-(18446744073709551615)", MySQL), DfValue::None);
+        assert_eq!(eval_expr("-(1.5)", MySQL), DfValue::Double(-1.5));
+        assert_eq!(eval_expr("-('3abc')", MySQL), DfValue::Double(-3.0));
+        assert_eq!(eval_expr("-(NULL)", MySQL), DfValue::None);
+    }
+
+    #[test]
+    fn eval_not() {
+        assert_eq!(eval_expr("NOT 2", MySQL), DfValue::from(false));
+        assert_eq!(eval_expr("NOT 0", MySQL), DfValue::from(true));
+        assert_eq!(eval_expr("NOT (NULL)", MySQL), DfValue::None);
+        assert_eq!(eval_expr("NOT (1 AND NULL)", MySQL), DfValue::None);
+        assert_eq!(eval_expr("NOT (0 AND NULL)", MySQL), DfValue::from(true));
+        assert_eq!(eval_expr("NOT 'f'", PostgreSQL), DfValue::from(true));
+    }
+
+    #[test]
+    fn string_truthiness() {
+        assert_eq!(eval_expr("'abc' AND 1", MySQL), DfValue::from(false));
+        assert_eq!(eval_expr("'2abc' AND 1", MySQL), DfValue::from(true));
+        assert_eq!(eval_expr("'t' AND true", PostgreSQL), DfValue::from(true));
+        assert_eq!(eval_expr("'no' OR false", PostgreSQL), DfValue::from(false));
+        try_eval_expr("'abc' AND true", PostgreSQL).unwrap_err();
+    }
+
+    #[test]
+    fn null_safe_equal() {
+        assert_eq!(eval_expr("1 <=> 1", MySQL), DfValue::from(true));
+        assert_eq!(eval_expr("1 <=> 2", MySQL), DfValue::from(false));
+        assert_eq!(eval_expr("null <=> null", MySQL), DfValue::from(true));
+        assert_eq!(eval_expr("1 <=> null", MySQL), DfValue::from(false));
+        assert_eq!(eval_expr("null <=> 1", MySQL), DfValue::from(false));
+        assert_eq!(eval_expr("1 = null", MySQL), DfValue::None);
+    }
+
     #[test]
     fn eval_json_exists() {
         let expr = Op {
@@ -438,6 +934,8 @@ mod tests {
             right: Box::new(column_with_type(1, DfType::Text(Collation::default()))),
             op: BinaryOperator::JsonExists,
             ty: DfType::Bool,
+            dialect: Dialect::DEFAULT_POSTGRESQL,
+            non_null_operands: false,
         };
         assert_eq!(
             expr.eval(&[DfValue::from("{\"abc\": 42}"), DfValue::from("xyz")])
@@ -468,6 +966,8 @@ mod tests {
             right: Box::new(column_with_type(1, DfType::Text(Collation::default()))),
             op: BinaryOperator::JsonExists,
             ty: DfType::Bool,
+            dialect: Dialect::DEFAULT_POSTGRESQL,
+            non_null_operands: false,
         };
         expr.eval(&[DfValue::from("bad_json"), DfValue::from("abc")])
             .unwrap_err();
@@ -485,6 +985,8 @@ mod tests {
             )),
             op: BinaryOperator::JsonAnyExists,
             ty: DfType::Bool,
+            dialect: Dialect::DEFAULT_POSTGRESQL,
+            non_null_operands: false,
         };
         assert_eq!(
             expr.eval(&[
@@ -530,6 +1032,8 @@ mod tests {
             )),
             op: BinaryOperator::JsonAnyExists,
             ty: DfType::Bool,
+            dialect: Dialect::DEFAULT_POSTGRESQL,
+            non_null_operands: false,
         };
         expr.eval(&[DfValue::from("bad_json"), DfValue::from("abc")])
             .unwrap_err();
@@ -547,6 +1051,8 @@ mod tests {
             )),
             op: BinaryOperator::JsonAllExists,
             ty: DfType::Bool,
+            dialect: Dialect::DEFAULT_POSTGRESQL,
+            non_null_operands: false,
         };
         assert_eq!(
             expr.eval(&[
@@ -648,6 +1154,8 @@ mod tests {
             )),
             op: BinaryOperator::JsonAllExists,
             ty: DfType::Bool,
+            dialect: Dialect::DEFAULT_POSTGRESQL,
+            non_null_operands: false,
         };
         expr.eval(&[DfValue::from("bad_json"), DfValue::from("abc")])
             .unwrap_err();
@@ -662,6 +1170,8 @@ mod tests {
             right: Box::new(column_with_type(1, DfType::Jsonb)),
             op: BinaryOperator::JsonConcat,
             ty: DfType::Jsonb,
+            dialect: Dialect::DEFAULT_POSTGRESQL,
+            non_null_operands: false,
         };
 
         let test_eval = |left, right| {
@@ -708,6 +1218,8 @@ mod tests {
             right: Box::new(column_with_type(1, DfType::Unknown)),
             op: BinaryOperator::JsonSubtract,
             ty: DfType::Jsonb,
+            dialect: Dialect::DEFAULT_POSTGRESQL,
+            non_null_operands: false,
         };
 
         assert_eq!(
@@ -835,6 +1347,8 @@ mod tests {
                     right: Box::new(make_literal($value)),
                     op: $binary_op,
                     ty: DfType::Unknown,
+                    dialect: Dialect::DEFAULT_MYSQL,
+                    non_null_operands: false,
                 };
                 assert_eq!(
                     expr.eval::<DfValue>(&[dt.into()]).unwrap(),
@@ -956,6 +1470,8 @@ mod tests {
                 op: BinaryOperator::And,
                 right: Box::new(make_literal(3.into())),
                 ty: DfType::Unknown,
+                dialect: Dialect::DEFAULT_MYSQL,
+                non_null_operands: false,
             }
             .eval::<DfValue>(&[])
             .unwrap(),
@@ -968,6 +1484,8 @@ mod tests {
                 op: BinaryOperator::And,
                 right: Box::new(make_literal(0.into())),
                 ty: DfType::Unknown,
+                dialect: Dialect::DEFAULT_MYSQL,
+                non_null_operands: false,
             }
             .eval::<DfValue>(&[])
             .unwrap(),
@@ -984,6 +1502,8 @@ mod tests {
                     op: BinaryOperator::Equal,
                     right: Box::new(make_literal(1.into())),
                     ty: DfType::Bool,
+                    dialect: Dialect::DEFAULT_MYSQL,
+                    non_null_operands: false,
                 },
                 body: make_literal("yes".try_into().unwrap()),
             }],
@@ -1009,6 +1529,8 @@ mod tests {
                         op: BinaryOperator::Equal,
                         right: Box::new(make_literal(1.into())),
                         ty: DfType::Bool,
+                        dialect: Dialect::DEFAULT_MYSQL,
+                        non_null_operands: false,
                     },
                     body: make_literal("one".try_into().unwrap()),
                 },
@@ -1018,6 +1540,8 @@ mod tests {
                         op: BinaryOperator::Equal,
                         right: Box::new(make_literal(2.into())),
                         ty: DfType::Bool,
+                        dialect: Dialect::DEFAULT_MYSQL,
+                        non_null_operands: false,
                     },
                     body: make_literal("two".try_into().unwrap()),
                 },
@@ -1042,6 +1566,8 @@ mod tests {
             op: BinaryOperator::Like,
             right: Box::new(make_literal("f%".into())),
             ty: DfType::Unknown,
+            dialect: Dialect::DEFAULT_MYSQL,
+            non_null_operands: false,
         };
         let res = expr.eval::<DfValue>(&[]).unwrap();
         assert!(res.is_truthy());
@@ -1054,6 +1580,8 @@ mod tests {
             op: BinaryOperator::Like,
             right: Box::new(make_literal("abc".into())),
             ty: DfType::Bool,
+            dialect: Dialect::DEFAULT_MYSQL,
+            non_null_operands: false,
         };
         let res = expr.eval(&[DfValue::None]).unwrap();
         assert_eq!(res, DfValue::None)
@@ -1080,6 +1608,8 @@ mod tests {
                 ty: DfType::Unknown,
             }),
             ty: DfType::Bool,
+            dialect: Dialect::DEFAULT_MYSQL,
+            non_null_operands: false,
         };
 
         let true_res = expr.eval(&[DfValue::from(1)]).unwrap();
@@ -91,6 +91,38 @@ fn month(date: &NaiveDate) -> u8 {
     date.month() as u8
 }
 
+/// Truncate `datetime` to the given precision, as the first argument to `date_trunc`. Returns
+/// `None` if the precision isn't recognized.
+fn date_trunc(precision: &str, datetime: NaiveDateTime) -> Option<NaiveDateTime> {
+    let date = datetime.date();
+    let start_of_day = |date: NaiveDate| date.and_hms(0, 0, 0);
+    let start_of_year = |year: i32| NaiveDate::from_ymd_opt(year, 1, 1).map(start_of_day);
+    Some(match precision.to_ascii_lowercase().as_str() {
+        "microseconds" => datetime.with_nanosecond(datetime.nanosecond() / 1_000 * 1_000)?,
+        "milliseconds" => {
+            datetime.with_nanosecond(datetime.nanosecond() / 1_000_000 * 1_000_000)?
+        }
+        "second" => datetime.with_nanosecond(0)?,
+        "minute" => date.and_hms(datetime.hour(), datetime.minute(), 0),
+        "hour" => date.and_hms(datetime.hour(), 0, 0),
+        "day" => start_of_day(date),
+        "week" => start_of_day(
+            date - chrono::Duration::days(date.weekday().num_days_from_monday().into()),
+        ),
+        "month" => start_of_day(date.with_day(1)?),
+        "quarter" => start_of_day(NaiveDate::from_ymd_opt(
+            date.year(),
+            (date.month0() / 3) * 3 + 1,
+            1,
+        )?),
+        "year" => start_of_year(date.year())?,
+        "decade" => start_of_year(date.year().div_euclid(10) * 10)?,
+        "century" => start_of_year((date.year() - 1).div_euclid(100) * 100 + 1)?,
+        "millennium" => start_of_year((date.year() - 1).div_euclid(1000) * 1000 + 1)?,
+        _ => return None,
+    })
+}
+
 fn timediff_datetimes(time1: &NaiveDateTime, time2: &NaiveDateTime) -> MySqlTime {
     let duration = time1.sub(*time2);
     MySqlTime::new(duration)
@@ -390,6 +422,55 @@ impl BuiltinFunction {
                     day_of_week(&(NaiveDate::try_from(&param_cast)?)) as i64,
                 ))
             }
+            BuiltinFunction::DateTrunc(precision_arg, arg) => {
+                let precision = non_null!(precision_arg.eval(record)?);
+                let precision =
+                    try_cast_or_none!(precision, &DfType::DEFAULT_TEXT, precision_arg.ty());
+                let precision = <&str>::try_from(&precision)?;
+                let param = non_null!(arg.eval(record)?);
+                let mk_err = || ReadySetError::ProjectExprBuiltInFunctionError {
+                    function: "date_trunc".to_owned(),
+                    message: format!("unit \"{}\" not recognized", precision),
+                };
+
+                // Timestamps with a time zone are truncated in their own time zone
+                if let DfValue::TimestampTz(ts) = &param {
+                    if ts.has_timezone() {
+                        let datetime = ts.to_chrono();
+                        let truncated =
+                            date_trunc(precision, datetime.naive_local()).ok_or_else(mk_err)?;
+                        return match datetime.offset().from_local_datetime(&truncated) {
+                            LocalResult::Single(dt) => Ok(DfValue::TimestampTz(dt.into())),
+                            _ => Ok(DfValue::None),
+                        };
+                    }
+                }
+
+                let param_cast = try_cast_or_none!(
+                    param,
+                    &DfType::Timestamp {
+                        subsecond_digits: ty.subsecond_digits().unwrap_or_default()
+                    },
+                    arg.ty()
+                );
+                let truncated = date_trunc(precision, NaiveDateTime::try_from(&param_cast)?)
+                    .ok_or_else(mk_err)?;
+                Ok(DfValue::from(truncated))
+            }
+            BuiltinFunction::FromUnixtime(arg) => {
+                let param = non_null!(arg.eval(record)?);
+                let param_cast = try_cast_or_none!(param, &DfType::Double, arg.ty());
+                let secs = f64::try_from(&param_cast)?;
+                // MySQL returns NULL for negative timestamps
+                if secs < 0.0 {
+                    return Ok(DfValue::None);
+                }
+                let nanos = (secs.fract() * 1_000_000_000.0).round() as u32;
+                match NaiveDateTime::from_timestamp_opt(secs.trunc() as i64, nanos) {
+                    Some(datetime) => Ok(DfValue::from(datetime)),
+                    None => Ok(DfValue::None),
+                }
+            }
             BuiltinFunction::IfNull(arg1, arg2) => {
                 let param1 = arg1.eval(record)?;
                 let param2 = arg2.eval(record)?;
@@ -606,6 +687,36 @@ impl BuiltinFunction {
                 .as_array()
                 .map(|array| DfValue::from(array.len()))
                 .ok_or_else(|| invalid_err!("cannot get array length of a non-array")),
+            BuiltinFunction::Sqrt(arg) => {
+                let param = non_null!(arg.eval(record)?);
+                let param_cast = try_cast_or_none!(param, &DfType::Double, arg.ty());
+                let val = f64::try_from(&param_cast)?;
+                // Both MySQL and PostgreSQL return NULL for the square root of a negative number
+                if val < 0.0 {
+                    return Ok(DfValue::None);
+                }
+                DfValue::try_from(val.sqrt())
+            }
+            BuiltinFunction::Floor(arg) => {
+                let param = non_null!(arg.eval(record)?);
+                match param {
+                    DfValue::Int(_) | DfValue::UnsignedInt(_) => Ok(param),
+                    DfValue::Numeric(ref d) if !ty.is_any_float() => Ok(d
+                        .floor()
+                        .to_i64()
+                        .map(DfValue::Int)
+                        .unwrap_or(DfValue::None)),
+                    _ => {
+                        let param_cast = try_cast_or_none!(param, &DfType::Double, arg.ty());
+                        let val = f64::try_from(&param_cast)?.floor();
+                        if ty.is_any_float() {
+                            DfValue::try_from(val)
+                        } else {
+                            Ok(DfValue::Int(val as i64))
+                        }
+                    }
+                }
+            }
             BuiltinFunction::JsonDepth(expr) => non_null!(expr.eval(record)?)
                 .to_json()
                 .map(|json| crate::eval::json::json_depth(&json).into()),
@@ -1008,6 +1119,75 @@ mod tests {
         assert_eq!(expr3.eval(&[DfValue::None]).unwrap(), value);
     }
 
+    #[test]
+    fn eval_call_date_trunc() {
+        let expr = make_call(BuiltinFunction::DateTrunc(make_column(0), make_column(1)));
+        let datetime = NaiveDateTime::new(
+            NaiveDate::from_ymd(2003, 10, 15),
+            NaiveTime::from_hms_milli(5, 13, 33, 250),
+        );
+        let trunc = |precision: &str| {
+            expr.eval::<DfValue>(&[precision.try_into().unwrap(), datetime.into()])
+                .unwrap()
+        };
+        let expected = |y, m, d, h, min, s| {
+            DfValue::from(NaiveDateTime::new(
+                NaiveDate::from_ymd(y, m, d),
+                NaiveTime::from_hms(h, min, s),
+            ))
+        };
+
+        assert_eq!(trunc("second"), expected(2003, 10, 15, 5, 13, 33));
+        assert_eq!(trunc("minute"), expected(2003, 10, 15, 5, 13, 0));
+        assert_eq!(trunc("HOUR"), expected(2003, 10, 15, 5, 0, 0));
+        assert_eq!(trunc("day"), expected(2003, 10, 15, 0, 0, 0));
+        // 2003-10-15 was a Wednesday
+        assert_eq!(trunc("week"), expected(2003, 10, 13, 0, 0, 0));
+        assert_eq!(trunc("month"), expected(2003, 10, 1, 0, 0, 0));
+        assert_eq!(trunc("quarter"), expected(2003, 10, 1, 0, 0, 0));
+        assert_eq!(trunc("year"), expected(2003, 1, 1, 0, 0, 0));
+        assert_eq!(trunc("decade"), expected(2000, 1, 1, 0, 0, 0));
+        assert_eq!(trunc("century"), expected(2001, 1, 1, 0, 0, 0));
+
+        assert_eq!(
+            expr.eval::<DfValue>(&[
+                "hour".try_into().unwrap(),
+                datetime.to_string().try_into().unwrap()
+            ])
+            .unwrap(),
+            expected(2003, 10, 15, 5, 0, 0)
+        );
+        assert_eq!(
+            expr.eval::<DfValue>(&["hour".try_into().unwrap(), DfValue::None])
+                .unwrap(),
+            DfValue::None
+        );
+        expr.eval::<DfValue>(&["fortnight".try_into().unwrap(), datetime.into()])
+            .unwrap_err();
+    }
+
+    #[test]
+    fn eval_call_from_unixtime() {
+        let expr = make_call(BuiltinFunction::FromUnixtime(make_column(0)));
+        assert_eq!(
+            expr.eval::<DfValue>(&[DfValue::Int(1_066_194_813)])
+                .unwrap(),
+            NaiveDateTime::new(
+                NaiveDate::from_ymd(2003, 10, 15),
+                NaiveTime::from_hms(5, 13, 33)
+            )
+            .into()
+        );
+        assert_eq!(
+            expr.eval::<DfValue>(&[DfValue::Int(-1)]).unwrap(),
+            DfValue::None
+        );
+        assert_eq!(
+            expr.eval::<DfValue>(&[DfValue::None]).unwrap(),
+            DfValue::None
+        );
+    }
+
     #[test]
     fn eval_call_month() {
         let expr = make_call(BuiltinFunction::Month(make_column(0)));
@@ -1323,6 +1503,47 @@ mod tests {
         assert_eq!(expr.eval::<DfValue>(&[param1, param2]).unwrap(), want);
     }
 
+    #[test]
+    fn eval_call_sqrt() {
+        let expr = make_call(BuiltinFunction::Sqrt(make_column(0)));
+        assert_eq!(
+            expr.eval::<DfValue>(&[DfValue::Int(16)]).unwrap(),
+            DfValue::try_from(4.0_f64).unwrap()
+        );
+        assert_eq!(
+            expr.eval::<DfValue>(&[DfValue::try_from(2.25_f64).unwrap()])
+                .unwrap(),
+            DfValue::try_from(1.5_f64).unwrap()
+        );
+        assert_eq!(
+            expr.eval::<DfValue>(&[DfValue::Int(-1)]).unwrap(),
+            DfValue::None
+        );
+        assert_eq!(
+            expr.eval::<DfValue>(&[DfValue::None]).unwrap(),
+            DfValue::None
+        );
+    }
+
+    #[test]
+    fn eval_call_floor() {
+        let expr = make_call(BuiltinFunction::Floor(make_column(0)));
+        assert_eq!(
+            expr.eval::<DfValue>(&[DfValue::Int(16)]).unwrap(),
+            DfValue::Int(16)
+        );
+        assert_eq!(
+            expr.eval::<DfValue>(&[DfValue::try_from(-2.25_f64).unwrap()])
+                .unwrap(),
+            DfValue::Int(-3)
+        );
+        assert_eq!(
+            expr.eval::<DfValue>(&[DfValue::None]).unwrap(),
+            DfValue::None
+        );
+        assert_eq!(eval_expr("floor(7199 / 3600)", MySQL), 1.into());
+    }
+
     #[test]
     fn eval_call_round_with_negative_precision() {
         let expr = make_call(BuiltinFunction::Round(make_column(0), make_column(1)));
@@ -4,7 +4,10 @@ mod binary_operator;
 mod eval;
 pub mod like;
 mod lower;
+pub mod nullability;
 mod post_lookup;
+pub mod typecheck;
+mod unary_operator;
 pub mod utils;
 
 use std::fmt::{self, Display, Formatter};
@@ -22,6 +25,7 @@ pub use crate::post_lookup::{
     PostLookup, PostLookupAggregate, PostLookupAggregateFunction, PostLookupAggregates,
     PreInsertion, ReaderProcessing,
 };
+pub use crate::unary_operator::*;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum BuiltinFunction {
@@ -34,6 +38,10 @@ pub enum BuiltinFunction {
     },
     /// [`dayofweek`](https://dev.mysql.com/doc/refman/8.0/en/date-and-time-functions.html#function_dayofweek)
     DayOfWeek(Expr),
+    /// [`date_trunc`](https://www.postgresql.org/docs/current/functions-datetime.html#FUNCTIONS-DATETIME-TRUNC)
+    DateTrunc(Expr, Expr),
+    /// [`from_unixtime`](https://dev.mysql.com/doc/refman/8.0/en/date-and-time-functions.html#function_from-unixtime)
+    FromUnixtime(Expr),
     /// [`ifnull`](https://dev.mysql.com/doc/refman/8.0/en/flow-control-functions.html#function_ifnull)
     IfNull(Expr, Expr),
     /// [`month`](https://dev.mysql.com/doc/refman/8.0/en/date-and-time-functions.html#function_month)
@@ -46,6 +54,10 @@ pub enum BuiltinFunction {
     DateFormat(Expr, Expr),
     /// [`round`](https://dev.mysql.com/doc/refman/8.0/en/mathematical-functions.html#function_round)
     Round(Expr, Expr),
+    /// [`sqrt`](https://dev.mysql.com/doc/refman/8.0/en/mathematical-functions.html#function_sqrt)
+    Sqrt(Expr),
+    /// [`floor`](https://dev.mysql.com/doc/refman/8.0/en/mathematical-functions.html#function_floor)
+    Floor(Expr),
     /// [`json_depth`](https://dev.mysql.com/doc/refman/8.0/en/json-attribute-functions.html#function_json-depth)
     JsonDepth(Expr),
     /// [`json_valid`](https://dev.mysql.com/doc/refman/8.0/en/json-attribute-functions.html#function_json-valid)
@@ -114,12 +126,16 @@ impl BuiltinFunction {
         match self {
             ConvertTZ { .. } => "convert_tz",
             DayOfWeek { .. } => "dayofweek",
+            DateTrunc { .. } => "date_trunc",
+            FromUnixtime { .. } => "from_unixtime",
             IfNull { .. } => "ifnull",
             Month { .. } => "month",
             Timediff { .. } => "timediff",
             Addtime { .. } => "addtime",
             DateFormat { .. } => "date_format",
             Round { .. } => "round",
+            Sqrt { .. } => "sqrt",
+            Floor { .. } => "floor",
             JsonDepth { .. } => "json_depth",
             JsonValid { .. } => "json_valid",
             JsonQuote { .. } => "json_quote",
@@ -158,6 +174,12 @@ impl Display for BuiltinFunction {
             DayOfWeek(arg) => {
                 write!(f, "({})", arg)
             }
+            DateTrunc(precision, arg) => {
+                write!(f, "({}, {})", precision, arg)
+            }
+            FromUnixtime(arg) => {
+                write!(f, "({})", arg)
+            }
             IfNull(arg1, arg2) => {
                 write!(f, "({}, {})", arg1, arg2)
             }
@@ -176,6 +198,9 @@ impl Display for BuiltinFunction {
             Round(arg1, precision) => {
                 write!(f, "({}, {})", arg1, precision)
             }
+            Sqrt(arg) | Floor(arg) => {
+                write!(f, "({})", arg)
+            }
             JsonDepth(arg) | JsonValid(arg) | JsonQuote(arg) | JsonTypeof(arg)
             | JsonArrayLength(arg) | JsonStripNulls(arg) | JsonbPretty(arg) => {
                 write!(f, "({})", arg)
@@ -293,6 +318,23 @@ pub enum Expr {
         left: Box<Expr>,
         right: Box<Expr>,
         ty: DfType,
+        /// The dialect whose semantics to evaluate the operation with, for operators whose
+        /// behavior differs between dialects (such as division by zero)
+        dialect: Dialect,
+        /// Whether both operands have been proven to never evaluate to NULL by
+        /// [`Expr::analyze_nullability`], in which case the operation is evaluated without
+        /// checking them for NULL
+        non_null_operands: bool,
+    },
+
+    /// A unary (prefix) operation
+    UnaryOp {
+        op: UnaryOperator,
+        expr: Box<Expr>,
+        ty: DfType,
+        /// The dialect whose semantics to evaluate the operation with, for converting strings to
+        /// numbers or booleans
+        dialect: Dialect,
     },
 
     /// Test if the LHS satisfies OP for any element in the RHS, which must evaluate to some kind
@@ -358,6 +400,7 @@ impl Display for Expr {
             Op {
                 op, left, right, ..
             } => write!(f, "({} {} {})", left, op, right),
+            UnaryOp { op, expr, .. } => write!(f, "({op} {expr})"),
             OpAny {
                 op, left, right, ..
             } => {
@@ -394,6 +437,7 @@ impl Expr {
             Expr::Column { ty, .. }
             | Expr::Literal { ty, .. }
             | Expr::Op { ty, .. }
+            | Expr::UnaryOp { ty, .. }
             | Expr::OpAny { ty, .. }
             | Expr::OpAll { ty, .. }
             | Expr::Call { ty, .. }
@@ -94,6 +94,22 @@ impl LikePattern {
     }
 }
 
+/// Returns the literal prefix of the given LIKE pattern: the characters before its first
+/// (unescaped) wildcard, with escapes removed. Every string which matches the pattern
+/// case-sensitively starts with this prefix.
+pub fn literal_prefix(like_pattern: &str) -> String {
+    let mut prefix = String::new();
+    let mut chars = like_pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '%' | '_' => break,
+            '\\' => prefix.push(chars.next_if(|c| matches!(c, '%' | '_')).unwrap_or('\\')),
+            c => prefix.push(c),
+        }
+    }
+    prefix
+}
+
 /// Converts to a [`CaseSensitive`] pattern
 impl From<&str> for LikePattern {
     fn from(s: &str) -> Self {
@@ -143,6 +159,16 @@ mod tests {
         assert!(!LikePattern::new(r"\_", CaseSensitive).matches(r"\a"));
     }
 
+    #[test]
+    fn literal_prefixes() {
+        assert_eq!(literal_prefix("foo%"), "foo");
+        assert_eq!(literal_prefix("foo_bar%"), "foo");
+        assert_eq!(literal_prefix("foo"), "foo");
+        assert_eq!(literal_prefix("%foo"), "");
+        assert_eq!(literal_prefix(r"50\%_off%"), "50%");
+        assert_eq!(literal_prefix(r"a\b%"), r"a\b");
+    }
+
     #[proptest]
     fn pattern_matches_itself(pat: String) {
         lazy_static! {
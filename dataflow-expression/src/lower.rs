@@ -2,18 +2,19 @@ use std::iter;
 
 use nom_sql::{
     BinaryOperator as SqlBinaryOperator, Column, Expr as AstExpr, FunctionExpr, InValue, Relation,
-    UnaryOperator,
 };
 use readyset_data::dialect::SqlEngine;
 use readyset_data::{DfType, DfValue};
 use readyset_errors::{
-    internal, internal_err, invalid, invalid_err, unsupported, ReadySetError, ReadySetResult,
+    internal, internal_err, invalid, invalid_err, unsupported, unsupported_feature, ReadySetError,
+    ReadySetResult,
 };
 use readyset_util::redacted::Sensitive;
 use vec1::Vec1;
 
 use crate::{
     BinaryOperator, BuiltinFunction, CaseWhenBranch, Dialect, Expr, NullValueTreatmentArg,
+    UnaryOperator,
 };
 
 /// Context supplied to expression lowering to allow resolving references to objects within the
@@ -126,6 +127,133 @@ fn mysql_least_greatest_compare_as(arg_types: Vec<&DfType>) -> DfType {
 }
 
 impl BuiltinFunction {
+    /// Infer the return type of this function from the types of its arguments, writing it to
+    /// `ty`, and the type its arguments are compared as (for `GREATEST` and `LEAST`) or the
+    /// precision it returns times with (for `CONVERT_TZ`). The return types of functions which
+    /// don't depend on the types of their arguments are determined by the name the function was
+    /// called with, and left as-is in `ty`.
+    ///
+    /// Called both when the function is [lowered](Self::from_name_and_args), and again once the
+    /// types of its arguments have been [resolved](Expr::typecheck).
+    pub(crate) fn infer_type(&mut self, ty: &mut DfType, dialect: Dialect) -> ReadySetResult<()> {
+        use BuiltinFunction::*;
+
+        match self {
+            // Type is inferred from input argument
+            ConvertTZ {
+                args: [input, ..],
+                subsecond_digits,
+            } => {
+                *ty = input.ty().clone();
+                *subsecond_digits = ty
+                    .subsecond_digits()
+                    .unwrap_or_else(|| dialect.default_subsecond_digits());
+            }
+            // Truncating a timestamp with time zone gives a timestamp with time zone; anything
+            // else is truncated as a timestamp
+            DateTrunc(_, ts) => {
+                *ty = match ts.ty() {
+                    ts_ty @ (DfType::Timestamp { .. } | DfType::TimestampTz { .. }) => {
+                        ts_ty.clone()
+                    }
+                    _ => DfType::Timestamp {
+                        subsecond_digits: dialect.default_subsecond_digits(),
+                    },
+                };
+            }
+            // Type is inferred from the value provided
+            IfNull(_, val) => *ty = val.ty().clone(),
+            Addtime(base_time, _) => *ty = base_time.ty().clone(),
+            Round(expr, _) => {
+                use DfType::*;
+                *ty = match *expr.ty() {
+                    Unknown => Unknown,
+
+                    // When the first argument is a DECIMAL value, the return type is also DECIMAL.
+                    Numeric { prec, scale } => Numeric { prec, scale },
+
+                    // When the first argument is of any integer type, the return type is always
+                    // BIGINT.
+                    ref expr_ty if expr_ty.is_any_int() => BigInt,
+
+                    // When the first argument is of any floating-point or non-numeric type, the
+                    // return type is always DOUBLE.
+                    _ => Double,
+                };
+            }
+            // Floating-point arguments give a floating-point result, everything else (including
+            // the result of dividing two integers) an integer
+            Floor(expr) => {
+                *ty = if expr.ty().is_any_float() {
+                    DfType::Double
+                } else {
+                    DfType::BigInt
+                };
+            }
+            Coalesce(arg1, _) => *ty = arg1.ty().clone(),
+            Concat(arg1, rest_args) => {
+                let collation = iter::once(&*arg1)
+                    .chain(rest_args.iter())
+                    .find_map(|expr| match expr.ty() {
+                        DfType::Text(c) => Some(*c),
+                        _ => None,
+                    })
+                    .unwrap_or_default();
+                *ty = DfType::Text(collation);
+            }
+            Substring(string, _, _) => {
+                *ty = if string.ty().is_any_text() {
+                    string.ty().clone()
+                } else {
+                    DfType::DEFAULT_TEXT
+                };
+            }
+            // The type inference rules for GREATEST and LEAST are the same
+            Greatest { args, compare_as } | Least { args, compare_as } => {
+                let arg_tys = args.iter().map(|arg| arg.ty()).collect::<Vec<_>>();
+                match dialect.engine() {
+                    SqlEngine::PostgreSQL => {
+                        *ty = unify_postgres_types(arg_tys)?;
+                        *compare_as = ty.clone();
+                    }
+                    SqlEngine::MySQL => {
+                        // TODO(ENG-1911): What are the rules for MySQL's return type inference?
+                        // The documentation just says "The return type of LEAST() is the aggregated
+                        // type of the comparison argument types."
+                        *ty = arg_tys
+                            .iter()
+                            .find(|t| t.is_known())
+                            .copied()
+                            .cloned()
+                            .unwrap_or(DfType::Binary(0));
+                        *compare_as = mysql_least_greatest_compare_as(arg_tys);
+                    }
+                }
+            }
+            DayOfWeek(_)
+            | FromUnixtime(_)
+            | Month(_)
+            | Timediff(..)
+            | DateFormat(..)
+            | Sqrt(_)
+            | JsonDepth(_)
+            | JsonValid(_)
+            | JsonOverlaps(..)
+            | JsonQuote(_)
+            | JsonTypeof(_)
+            | JsonArrayLength(_)
+            | JsonStripNulls(_)
+            | JsonExtractPath { .. }
+            | JsonbInsert(..)
+            | JsonbSet(..)
+            | JsonbPretty(_)
+            | SplitPart(..)
+            | ArrayToString(..) => {}
+        }
+
+        Ok(())
+    }
+
     pub(crate) fn from_name_and_args<A>(
         name: &str,
         args: A,
@@ -134,57 +262,36 @@ impl BuiltinFunction {
     where
         A: IntoIterator<Item = Expr>,
     {
-        fn type_for_round(expr: &Expr, _precision: &Expr) -> DfType {
-            use DfType::*;
-            match *expr.ty() {
-                Unknown => Unknown,
-
-                // When the first argument is a DECIMAL value, the return type is also DECIMAL.
-                Numeric { prec, scale } => Numeric { prec, scale },
-
-                // When the first argument is of any integer type, the return type is always BIGINT.
-                ref ty if ty.is_any_int() => BigInt,
-
-                // When the first argument is of any floating-point or non-numeric type, the return
-                // type is always DOUBLE.
-                _ => Double,
-            }
-        }
-
         let arity_error = || ReadySetError::ArityError(name.to_owned());
 
         // TODO: Type-check arguments.
         let mut args = args.into_iter();
         let mut next_arg = || args.next().ok_or_else(arity_error);
 
-        let result = match name {
-            "convert_tz" => {
-                // Type is inferred from input argument
-                let input = next_arg()?;
-                let ty = input.ty().clone();
-                (
-                    Self::ConvertTZ {
-                        args: [input, next_arg()?, next_arg()?],
-                        subsecond_digits: ty
-                            .subsecond_digits()
-                            .unwrap_or_else(|| dialect.default_subsecond_digits()),
-                    },
-                    ty,
-                )
-            }
+        // Functions whose return type depends on the types of their arguments are given an unknown
+        // type here, which is then inferred by `infer_type`
+        let (mut func, mut ty) = match name {
+            "convert_tz" => (
+                Self::ConvertTZ {
+                    args: [next_arg()?, next_arg()?, next_arg()?],
+                    subsecond_digits: dialect.default_subsecond_digits(),
+                },
+                DfType::Unknown,
+            ),
             "dayofweek" => {
                 (
                     Self::DayOfWeek(next_arg()?),
                     DfType::Int, // Day of week is always an int
                 )
             }
-            "ifnull" => {
-                let expr = next_arg()?;
-                let val = next_arg()?;
-                // Type is inferred from the value provided
-                let ty = val.ty().clone();
-                (Self::IfNull(expr, val), ty)
-            }
+            "date_trunc" => (Self::DateTrunc(next_arg()?, next_arg()?), DfType::Unknown),
+            "from_unixtime" => (
+                Self::FromUnixtime(next_arg()?),
+                DfType::DateTime {
+                    subsecond_digits: dialect.default_subsecond_digits(),
+                },
+            ),
+            "ifnull" => (Self::IfNull(next_arg()?, next_arg()?), DfType::Unknown),
             "month" => {
                 (
                     Self::Month(next_arg()?),
@@ -200,11 +307,7 @@ impl BuiltinFunction {
                     },
                 )
             }
-            "addtime" => {
-                let base_time = next_arg()?;
-                let ty = base_time.ty().clone();
-                (Self::Addtime(base_time, next_arg()?), ty)
-            }
+            "addtime" => (Self::Addtime(next_arg()?, next_arg()?), DfType::Unknown),
             "date_format" => (
                 Self::DateFormat(next_arg()?, next_arg()?),
                 DfType::DEFAULT_TEXT,
@@ -215,9 +318,10 @@ impl BuiltinFunction {
                     val: DfValue::Int(0),
                     ty: DfType::Int,
                 });
-                let ty = type_for_round(&expr, &prec);
-                (Self::Round(expr, prec), ty)
+                (Self::Round(expr, prec), DfType::Unknown)
             }
+            "sqrt" => (Self::Sqrt(next_arg()?), DfType::Double),
+            "floor" => (Self::Floor(next_arg()?), DfType::Unknown),
             "json_depth" => (Self::JsonDepth(next_arg()?), DfType::Int),
             "json_valid" => (Self::JsonValid(next_arg()?), DfType::BigInt),
             "json_overlaps" => (Self::JsonOverlaps(next_arg()?, next_arg()?), DfType::BigInt),
@@ -278,78 +382,39 @@ impl BuiltinFunction {
                 DfType::Jsonb,
             ),
             "jsonb_pretty" => (Self::JsonbPretty(next_arg()?), DfType::DEFAULT_TEXT),
-            "coalesce" => {
-                let arg1 = next_arg()?;
-                let ty = arg1.ty().clone();
-                (Self::Coalesce(arg1, args.by_ref().collect()), ty)
-            }
-            "concat" => {
-                let arg1 = next_arg()?;
-                let rest_args = args.by_ref().collect::<Vec<_>>();
-                let collation = iter::once(&arg1)
-                    .chain(&rest_args)
-                    .find_map(|expr| match expr.ty() {
-                        DfType::Text(c) => Some(*c),
-                        _ => None,
-                    })
-                    .unwrap_or_default();
-                (Self::Concat(arg1, rest_args), DfType::Text(collation))
-            }
-            "substring" | "substr" => {
-                let string = next_arg()?;
-                let ty = if string.ty().is_any_text() {
-                    string.ty().clone()
-                } else {
-                    DfType::DEFAULT_TEXT
-                };
-
-                (
-                    Self::Substring(string, next_arg().ok(), next_arg().ok()),
-                    ty,
-                )
-            }
+            "coalesce" => (
+                Self::Coalesce(next_arg()?, args.by_ref().collect()),
+                DfType::Unknown,
+            ),
+            "concat" => (
+                Self::Concat(next_arg()?, args.by_ref().collect()),
+                DfType::Unknown,
+            ),
+            "substring" | "substr" => (
+                Self::Substring(next_arg()?, next_arg().ok(), next_arg().ok()),
+                DfType::Unknown,
+            ),
             "split_part" => (
                 Self::SplitPart(next_arg()?, next_arg()?, next_arg()?),
                 DfType::DEFAULT_TEXT,
             ),
             "greatest" | "least" => {
-                // The type inference rules for GREATEST and LEAST are the same, so this block
-                // covers both then dispatches for the actual function construction at the end
-                let arg1 = next_arg()?;
-                let rest_args = args.by_ref().collect::<Vec<_>>();
-                let arg_tys = iter::once(arg1.ty())
-                    .chain(rest_args.iter().map(|arg| arg.ty()))
-                    .collect::<Vec<_>>();
-                let (compare_as, ty) = match dialect.engine() {
-                    SqlEngine::PostgreSQL => {
-                        let ty = unify_postgres_types(arg_tys)?;
-                        (ty.clone(), ty)
-                    }
-                    SqlEngine::MySQL => {
-                        // TODO(ENG-1911): What are the rules for MySQL's return type inference?
-                        // The documentation just says "The return type of LEAST() is the aggregated
-                        // type of the comparison argument types."
-                        let return_ty = arg_tys
-                            .iter()
-                            .find(|t| t.is_known())
-                            .copied()
-                            .cloned()
-                            .unwrap_or(DfType::Binary(0));
-                        let compare_as = mysql_least_greatest_compare_as(arg_tys);
-                        (compare_as, return_ty)
-                    }
-                };
-
-                let mut args = Vec1::with_capacity(arg1, rest_args.len() + 1);
-                args.extend(rest_args);
-
+                let mut func_args = Vec1::new(next_arg()?);
+                func_args.extend(args.by_ref());
+                let compare_as = DfType::Unknown;
                 (
                     if name == "greatest" {
-                        Self::Greatest { args, compare_as }
+                        Self::Greatest {
+                            args: func_args,
+                            compare_as,
+                        }
                     } else {
-                        Self::Least { args, compare_as }
+                        Self::Least {
+                            args: func_args,
+                            compare_as,
+                        }
                     },
-                    ty,
+                    DfType::Unknown,
                 )
             }
             "array_to_string" => (
@@ -363,7 +428,8 @@ impl BuiltinFunction {
             return Err(arity_error());
         }
 
-        Ok(result)
+        func.infer_type(&mut ty, dialect)?;
+        Ok((func, ty))
     }
 }
 
@@ -452,6 +518,8 @@ impl Expr {
                     left,
                     right,
                     ty,
+                    dialect,
+                    non_null_operands: false,
                 })
             }
             AstExpr::OpAny { lhs, op, rhs } | AstExpr::OpSome { lhs, op, rhs } => {
@@ -460,35 +528,17 @@ impl Expr {
             AstExpr::OpAll { lhs, op, rhs } => {
                 Self::lower_op_any_or_all(*lhs, op, *rhs, dialect, context, true)
             }
-            AstExpr::UnaryOp {
-                op: UnaryOperator::Neg,
-                rhs,
-            } => {
-                let left = Box::new(Self::lower(*rhs, dialect, context)?);
-                // TODO: Negation may change type to signed
-                let ty = left.ty().clone();
-                Ok(Self::Op {
-                    op: BinaryOperator::Multiply,
-                    left,
-                    right: Box::new(Self::Literal {
-                        val: DfValue::Int(-1),
-                        ty: DfType::Int,
-                    }),
+            AstExpr::UnaryOp { op, rhs } => {
+                let op = UnaryOperator::from(op);
+                let expr = Box::new(Self::lower(*rhs, dialect, context)?);
+                let ty = op.output_type(expr.ty());
+                Ok(Self::UnaryOp {
+                    op,
+                    expr,
                     ty,
+                    dialect,
                 })
             }
-            AstExpr::UnaryOp {
-                op: UnaryOperator::Not,
-                rhs,
-            } => Ok(Self::Op {
-                op: BinaryOperator::NotEqual,
-                left: Box::new(Self::lower(*rhs, dialect, context)?),
-                right: Box::new(Self::Literal {
-                    val: DfValue::Int(1),
-                    ty: DfType::Int,
-                }),
-                ty: DfType::Bool, // type of NE is always bool
-            }),
             AstExpr::Cast {
                 expr, ty: to_type, ..
             } => {
@@ -550,6 +600,8 @@ impl Expr {
                             op: comparison_op,
                             right: Box::new(Self::lower(rhs, dialect, context.clone())?),
                             ty: DfType::Bool, // type of =/!= is always bool
+                            dialect,
+                            non_null_operands: false,
                         })
                     };
 
@@ -559,6 +611,8 @@ impl Expr {
                             op: logical_op,
                             right: Box::new(make_comparison(rhs)?),
                             ty: DfType::Bool, // type of =/!= is always bool
+                            dialect,
+                            non_null_operands: false,
                         })
                     })
                 } else if negated {
@@ -624,7 +678,7 @@ impl Expr {
                     ty,
                 })
             }
-            AstExpr::Exists(_) => unsupported!("EXISTS not currently supported"),
+            AstExpr::Exists(_) => unsupported_feature!(Subquery, "EXISTS not currently supported"),
             AstExpr::Variable(_) => unsupported!("Variables not currently supported"),
             AstExpr::Between { .. } | AstExpr::NestedSelect(_) | AstExpr::In { .. } => {
                 internal!("Expression should have been desugared earlier: {expr}")
@@ -1165,6 +1219,29 @@ pub(crate) mod tests {
         }
     }
 
+    #[test]
+    fn negating_unsigned_is_signed() {
+        let expr = parse_expr(ParserDialect::MySQL, "-x").unwrap();
+        let result = Expr::lower(
+            expr,
+            Dialect::DEFAULT_MYSQL,
+            resolve_columns(|_| Ok((0, DfType::UnsignedInt))),
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            Expr::UnaryOp {
+                op: UnaryOperator::Neg,
+                expr: Box::new(Expr::Column {
+                    index: 0,
+                    ty: DfType::UnsignedInt
+                }),
+                ty: DfType::BigInt,
+                dialect: Dialect::DEFAULT_MYSQL,
+            }
+        );
+    }
+
     #[test]
     fn array_expr() {
         let expr = parse_expr(
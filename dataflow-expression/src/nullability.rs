@@ -0,0 +1,226 @@
+//! Analysis of which expressions can never evaluate to NULL.
+//!
+//! Whether a column in the parent node of an expression can contain NULL values is known when the
+//! expression is added to the graph (for example, base table columns declared `NOT NULL` can't).
+//! [`Expr::analyze_nullability`] uses that to prove, once, which operations have operands that are
+//! never NULL, and marks them so that the [evaluator](Expr::eval) can evaluate them without
+//! checking the operands for NULL (or, unless they're strings, converting them to numbers or
+//! booleans first).
+//!
+//! The analysis is conservative: an expression is only considered non-null if it can't evaluate to
+//! NULL for any row whose non-null columns aren't NULL. In particular, arithmetic can evaluate to
+//! NULL on overflow, and casts can evaluate to NULL for values that can't be converted, so neither
+//! is ever considered non-null.
+
+use crate::{BinaryOperator, BuiltinFunction, Expr, UnaryOperator};
+
+impl Expr {
+    /// Returns true if this expression can be proven to never evaluate to NULL, given whether each
+    /// column in the parent node of the node containing the expression is known to never contain
+    /// NULL values.
+    pub fn is_non_null(&self, non_null_columns: &[bool]) -> bool {
+        match self {
+            Expr::Column { index, .. } => non_null_columns.get(*index).copied().unwrap_or(false),
+            Expr::Literal { val, .. } => !val.is_none(),
+            Expr::Op {
+                op, left, right, ..
+            } => match op {
+                BinaryOperator::Is | BinaryOperator::IsNot | BinaryOperator::NullSafeEqual => true,
+                BinaryOperator::And
+                | BinaryOperator::Or
+                | BinaryOperator::Equal
+                | BinaryOperator::NotEqual
+                | BinaryOperator::Greater
+                | BinaryOperator::GreaterOrEqual
+                | BinaryOperator::Less
+                | BinaryOperator::LessOrEqual => {
+                    left.is_non_null(non_null_columns) && right.is_non_null(non_null_columns)
+                }
+                _ => false,
+            },
+            Expr::UnaryOp { op, expr, .. } => match op {
+                UnaryOperator::Not => expr.is_non_null(non_null_columns),
+                // Negation can evaluate to NULL on overflow, like other arithmetic
+                UnaryOperator::Neg => false,
+            },
+            // A NULL left-hand side makes every comparison false, rather than NULL
+            Expr::OpAny { right, .. } | Expr::OpAll { right, .. } => {
+                right.is_non_null(non_null_columns)
+            }
+            Expr::Cast { .. } => false,
+            Expr::Call { func, .. } => match &**func {
+                BuiltinFunction::IfNull(expr, default) => {
+                    expr.is_non_null(non_null_columns) || default.is_non_null(non_null_columns)
+                }
+                BuiltinFunction::Coalesce(first, rest) => {
+                    first.is_non_null(non_null_columns)
+                        || rest.iter().any(|arg| arg.is_non_null(non_null_columns))
+                }
+                _ => false,
+            },
+            Expr::CaseWhen {
+                branches,
+                else_expr,
+                ..
+            } => {
+                branches
+                    .iter()
+                    .all(|branch| branch.body.is_non_null(non_null_columns))
+                    && else_expr.is_non_null(non_null_columns)
+            }
+            Expr::Array { .. } => true,
+        }
+    }
+
+    /// Mark every operation in this expression whose operands can be proven to never evaluate to
+    /// NULL, given whether each column in the parent node of the node containing the expression is
+    /// known to never contain NULL values.
+    ///
+    /// See the [module documentation](crate::nullability) for more information.
+    pub fn analyze_nullability(&mut self, non_null_columns: &[bool]) {
+        match self {
+            Expr::Column { .. } | Expr::Literal { .. } => {}
+            Expr::Op {
+                left,
+                right,
+                non_null_operands,
+                ..
+            } => {
+                left.analyze_nullability(non_null_columns);
+                right.analyze_nullability(non_null_columns);
+                *non_null_operands =
+                    left.is_non_null(non_null_columns) && right.is_non_null(non_null_columns);
+            }
+            Expr::UnaryOp { expr, .. } => expr.analyze_nullability(non_null_columns),
+            Expr::OpAny { left, right, .. } | Expr::OpAll { left, right, .. } => {
+                left.analyze_nullability(non_null_columns);
+                right.analyze_nullability(non_null_columns);
+            }
+            Expr::Cast { expr, .. } => expr.analyze_nullability(non_null_columns),
+            Expr::Call { func, .. } => {
+                for arg in func.arguments_mut() {
+                    arg.analyze_nullability(non_null_columns);
+                }
+            }
+            Expr::CaseWhen {
+                branches,
+                else_expr,
+                ..
+            } => {
+                for branch in branches.iter_mut() {
+                    branch.condition.analyze_nullability(non_null_columns);
+                    branch.body.analyze_nullability(non_null_columns);
+                }
+                else_expr.analyze_nullability(non_null_columns);
+            }
+            Expr::Array { elements, .. } => {
+                for element in elements {
+                    element.analyze_nullability(non_null_columns);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::{parse_expr, Dialect as ParserDialect};
+    use readyset_data::{DfType, DfValue, Dialect};
+    use readyset_errors::internal;
+
+    use super::*;
+    use crate::lower::tests::resolve_columns;
+
+    /// Lower `expr`, in which `x` and `y` are non-null integer columns and `z` is a nullable one
+    fn lower(expr: &str) -> Expr {
+        Expr::lower(
+            parse_expr(ParserDialect::MySQL, expr).unwrap(),
+            Dialect::DEFAULT_MYSQL,
+            resolve_columns(|c| match c.name.as_str() {
+                "x" => Ok((0, DfType::Int)),
+                "y" => Ok((1, DfType::Int)),
+                "z" => Ok((2, DfType::Int)),
+                _ => internal!("what's this column?"),
+            }),
+        )
+        .unwrap()
+    }
+
+    const NON_NULL_COLUMNS: &[bool] = &[true, true, false];
+
+    fn non_null_operands(expr: &Expr) -> bool {
+        match expr {
+            Expr::Op {
+                non_null_operands, ..
+            } => *non_null_operands,
+            _ => panic!("not an operation: {expr:?}"),
+        }
+    }
+
+    #[test]
+    fn non_null_expressions() {
+        for (expr, non_null) in [
+            ("x", true),
+            ("z", false),
+            ("1", true),
+            ("NULL", false),
+            ("x = y", true),
+            ("x = z", false),
+            ("z IS NULL", true),
+            ("x + y", false),
+            ("-x", false),
+            ("NOT x", true),
+            ("NOT z", false),
+            ("x = y AND y > 1", true),
+            ("ifnull(z, x)", true),
+            ("coalesce(z, NULL)", false),
+            ("CASE WHEN z THEN x ELSE 1 END", true),
+            ("CASE WHEN x THEN z ELSE 1 END", false),
+        ] {
+            assert_eq!(
+                lower(expr).is_non_null(NON_NULL_COLUMNS),
+                non_null,
+                "{expr}"
+            );
+        }
+    }
+
+    #[test]
+    fn marks_operations_with_non_null_operands() {
+        let mut expr = lower("(x + y) = z");
+        expr.analyze_nullability(NON_NULL_COLUMNS);
+        assert!(!non_null_operands(&expr));
+        match &expr {
+            Expr::Op { left, .. } => assert!(non_null_operands(left)),
+            _ => panic!("unexpected expression: {expr:?}"),
+        }
+    }
+
+    #[test]
+    fn marked_operations_evaluate_the_same() {
+        let row = [DfValue::from(4), DfValue::from(2), DfValue::None];
+        for expr in [
+            "x + y",
+            "x - y",
+            "x * y",
+            "x / y",
+            "x = y",
+            "x != y",
+            "x > y",
+            "x <= y",
+            "x AND y",
+            "x OR z",
+            "(x * y) + z",
+            "NOT (x AND y)",
+        ] {
+            let unmarked = lower(expr);
+            let mut marked = unmarked.clone();
+            marked.analyze_nullability(NON_NULL_COLUMNS);
+            assert_eq!(
+                marked.eval::<DfValue>(&row).unwrap(),
+                unmarked.eval::<DfValue>(&row).unwrap(),
+                "{expr}"
+            );
+        }
+    }
+}
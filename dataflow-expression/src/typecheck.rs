@@ -0,0 +1,395 @@
+//! Type checking of lowered expressions.
+//!
+//! [Lowering](Expr::lower) infers a type for each node in an expression as it's built, but some of
+//! those types are provisional: string literals in PostgreSQL have an unknown type until they're
+//! used somewhere, and the operands of a comparison are left to be coerced to a common type by the
+//! evaluator, once for every row. [`Expr::typecheck`] is run once, when the node containing the
+//! expression is added to the graph, and:
+//!
+//! - Resolves the type of every column reference from the types of the columns in the parent node
+//! - Re-derives the type of every operator from the (now resolved) types of its operands, checking
+//!   that the operands are valid for the operator
+//! - Coerces literals which are compared with `=`, `!=` or `<=>` against a value of a different
+//!   type to that type ahead of time. A literal on the left-hand side is coerced to the type of a
+//!   non-literal right-hand side; otherwise, a literal on the right-hand side is coerced to the
+//!   type of the left-hand side. The operands of other comparisons (such as `<` or `>=`) aren't
+//!   coerced
+//! - Coerces a literal of unknown type on the right-hand side of `op ANY`/`op ALL` to an array of
+//!   the type of the left-hand side
+//! - Re-infers the return type of every function call whose return type depends on the types of its
+//!   arguments (and, for `GREATEST` and `LEAST`, the type its arguments are compared as) from the
+//!   resolved types of the arguments
+//! - Resolves the type of `CASE` expressions whose first branch has an unknown type from the types
+//!   of the other branches
+//!
+//! Once an expression has been type checked, the operands of each of those equality comparisons
+//! have the same type (unless the literal couldn't be coerced), which lets the
+//! [evaluator](Expr::eval) skip coercing them for every row.
+
+use readyset_data::DfType;
+use readyset_errors::{ReadySetError, ReadySetResult};
+
+use crate::{BinaryOperator, BuiltinFunction, Dialect, Expr, NullValueTreatmentArg};
+
+impl BuiltinFunction {
+    /// Returns mutable references to all the arguments to this function
+    pub(crate) fn arguments_mut(&mut self) -> Vec<&mut Expr> {
+        use BuiltinFunction::*;
+
+        match self {
+            ConvertTZ { args, .. } => args.iter_mut().collect(),
+            DayOfWeek(arg) | FromUnixtime(arg) | Month(arg) | Sqrt(arg) | Floor(arg)
+            | JsonDepth(arg) | JsonValid(arg) | JsonQuote(arg) | JsonTypeof(arg)
+            | JsonArrayLength(arg) | JsonStripNulls(arg) | JsonbPretty(arg) => vec![arg],
+            DateTrunc(arg1, arg2)
+            | IfNull(arg1, arg2)
+            | Timediff(arg1, arg2)
+            | Addtime(arg1, arg2)
+            | DateFormat(arg1, arg2)
+            | Round(arg1, arg2)
+            | JsonOverlaps(arg1, arg2) => vec![arg1, arg2],
+            JsonExtractPath { json, keys } => {
+                let mut args = vec![json];
+                args.extend(keys.iter_mut());
+                args
+            }
+            JsonbInsert(arg1, arg2, arg3, arg4) => {
+                let mut args = vec![arg1, arg2, arg3];
+                args.extend(arg4);
+                args
+            }
+            JsonbSet(arg1, arg2, arg3, arg4, arg5) => {
+                let mut args = vec![arg1, arg2, arg3];
+                args.extend(arg4);
+                if let NullValueTreatmentArg::Expr(Some(arg5)) = arg5 {
+                    args.push(arg5);
+                }
+                args
+            }
+            Coalesce(arg1, rest) | Concat(arg1, rest) => {
+                let mut args = vec![arg1];
+                args.extend(rest.iter_mut());
+                args
+            }
+            Substring(string, from, len) => {
+                let mut args = vec![string];
+                args.extend(from);
+                args.extend(len);
+                args
+            }
+            SplitPart(arg1, arg2, arg3) => vec![arg1, arg2, arg3],
+            Greatest { args, .. } | Least { args, .. } => args.iter_mut().collect(),
+            ArrayToString(array, delimiter, null_string) => {
+                let mut args = vec![array, delimiter];
+                args.extend(null_string);
+                args
+            }
+        }
+    }
+}
+
+/// If `expr` is a literal whose type differs from `ty`, coerce it to `ty`.
+///
+/// If the coercion fails, the literal is left as-is, so that the expression fails (or not) at
+/// evaluation time exactly as it would have without being type checked.
+fn coerce_literal(expr: &mut Expr, ty: &DfType) {
+    if let Expr::Literal {
+        val,
+        ty: literal_ty,
+    } = expr
+    {
+        if literal_ty == ty || ty.is_unknown() {
+            return;
+        }
+        if let Ok(coerced) = val.coerce_to(ty, literal_ty) {
+            *val = coerced;
+            *literal_ty = ty.clone();
+        }
+    }
+}
+
+impl Expr {
+    /// Resolve and check the type of every node in this expression, given the types of the
+    /// columns in the parent node of the node containing the expression, and the SQL dialect the
+    /// expression was lowered in.
+    ///
+    /// See the [module documentation](crate::typecheck) for more information.
+    pub fn typecheck(&mut self, parent_types: &[DfType], dialect: Dialect) -> ReadySetResult<()> {
+        match self {
+            Expr::Column { index, ty } => {
+                *ty = parent_types
+                    .get(*index)
+                    .ok_or(ReadySetError::ProjectExprInvalidColumnIndex(*index))?
+                    .clone();
+            }
+            Expr::Literal { .. } => {}
+            Expr::Op {
+                op,
+                left,
+                right,
+                ty,
+                ..
+            } => {
+                left.typecheck(parent_types, dialect)?;
+                right.typecheck(parent_types, dialect)?;
+                if matches!(
+                    op,
+                    BinaryOperator::Equal
+                        | BinaryOperator::NotEqual
+                        | BinaryOperator::NullSafeEqual
+                ) {
+                    // If both sides are literals, the right-hand side is coerced to the type of
+                    // the left-hand side
+                    if matches!(**left, Expr::Literal { .. })
+                        && !matches!(**right, Expr::Literal { .. })
+                    {
+                        coerce_literal(left, right.ty());
+                    } else {
+                        coerce_literal(right, left.ty());
+                    }
+                }
+                *ty = op.output_type(left.ty(), right.ty())?;
+            }
+            Expr::UnaryOp { op, expr, ty, .. } => {
+                expr.typecheck(parent_types, dialect)?;
+                *ty = op.output_type(expr.ty());
+            }
+            Expr::OpAny {
+                op,
+                left,
+                right,
+                ty,
+            }
+            | Expr::OpAll {
+                op,
+                left,
+                right,
+                ty,
+            } => {
+                left.typecheck(parent_types, dialect)?;
+                right.typecheck(parent_types, dialect)?;
+                if right.ty().is_unknown() && left.ty().is_known() {
+                    coerce_literal(right, &DfType::Array(Box::new(left.ty().clone())));
+                }
+                let right_member_ty = if right.ty().is_array() {
+                    right.ty().innermost_array_type()
+                } else {
+                    &DfType::Unknown
+                };
+                *ty = op.output_type(left.ty(), right_member_ty)?;
+            }
+            Expr::Cast { expr, .. } => expr.typecheck(parent_types, dialect)?,
+            Expr::Call { func, ty } => {
+                for arg in func.arguments_mut() {
+                    arg.typecheck(parent_types, dialect)?;
+                }
+                func.infer_type(ty, dialect)?;
+            }
+            Expr::CaseWhen {
+                branches,
+                else_expr,
+                ty,
+            } => {
+                for branch in branches.iter_mut() {
+                    branch.condition.typecheck(parent_types, dialect)?;
+                    branch.body.typecheck(parent_types, dialect)?;
+                }
+                else_expr.typecheck(parent_types, dialect)?;
+                if ty.is_unknown() {
+                    if let Some(known) = branches
+                        .iter()
+                        .map(|branch| &branch.body)
+                        .chain(Some(&**else_expr))
+                        .map(|expr| expr.ty())
+                        .find(|branch_ty| branch_ty.is_known())
+                    {
+                        *ty = known.clone();
+                    }
+                }
+            }
+            Expr::Array { elements, .. } => {
+                for element in elements {
+                    element.typecheck(parent_types, dialect)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::{parse_expr, Dialect as ParserDialect};
+    use readyset_data::{DfValue, Dialect};
+    use readyset_errors::internal;
+
+    use super::*;
+    use crate::lower::tests::{no_op_lower_context, resolve_columns};
+
+    fn lower_and_typecheck(
+        expr: &str,
+        parser_dialect: ParserDialect,
+        dialect: Dialect,
+        parent_types: &[DfType],
+    ) -> Expr {
+        let lowered_types = parent_types.to_vec();
+        let mut expr = Expr::lower(
+            parse_expr(parser_dialect, expr).unwrap(),
+            dialect,
+            resolve_columns(move |c| match c.name.as_str() {
+                "x" => Ok((0, DfType::Unknown)),
+                "y" => Ok((1, lowered_types[1].clone())),
+                _ => internal!("what's this column?"),
+            }),
+        )
+        .unwrap();
+        expr.typecheck(parent_types, dialect).unwrap();
+        expr
+    }
+
+    #[test]
+    fn resolves_column_types_from_parent() {
+        let expr = lower_and_typecheck(
+            "x + y",
+            ParserDialect::MySQL,
+            Dialect::DEFAULT_MYSQL,
+            &[DfType::Double, DfType::Int],
+        );
+        match expr {
+            Expr::Op {
+                left, right, ty, ..
+            } => {
+                assert_eq!(*left.ty(), DfType::Double);
+                assert_eq!(*right.ty(), DfType::Int);
+                assert_eq!(ty, DfType::Double);
+            }
+            _ => panic!("unexpected expression: {expr:?}"),
+        }
+    }
+
+    #[test]
+    fn coerces_compared_literals() {
+        let expr = lower_and_typecheck(
+            "x = '5'",
+            ParserDialect::PostgreSQL,
+            Dialect::DEFAULT_POSTGRESQL,
+            &[DfType::Int, DfType::Int],
+        );
+        match expr {
+            Expr::Op { right, .. } => assert_eq!(
+                *right,
+                Expr::Literal {
+                    val: DfValue::from(5),
+                    ty: DfType::Int
+                }
+            ),
+            _ => panic!("unexpected expression: {expr:?}"),
+        }
+
+        let expr = lower_and_typecheck(
+            "'5' = x",
+            ParserDialect::PostgreSQL,
+            Dialect::DEFAULT_POSTGRESQL,
+            &[DfType::Int, DfType::Int],
+        );
+        match expr {
+            Expr::Op { left, .. } => assert_eq!(
+                *left,
+                Expr::Literal {
+                    val: DfValue::from(5),
+                    ty: DfType::Int
+                }
+            ),
+            _ => panic!("unexpected expression: {expr:?}"),
+        }
+
+        // Literals which can't be coerced are left alone, to fail at evaluation time
+        let expr = lower_and_typecheck(
+            "x = 'abc'",
+            ParserDialect::PostgreSQL,
+            Dialect::DEFAULT_POSTGRESQL,
+            &[DfType::Int, DfType::Int],
+        );
+        match expr {
+            Expr::Op { right, .. } => assert_eq!(*right.ty(), DfType::Unknown),
+            _ => panic!("unexpected expression: {expr:?}"),
+        }
+    }
+
+    #[test]
+    fn coerces_op_any_array_literals() {
+        let expr = lower_and_typecheck(
+            "x = ANY ('{1,2}')",
+            ParserDialect::PostgreSQL,
+            Dialect::DEFAULT_POSTGRESQL,
+            &[DfType::Int, DfType::Int],
+        );
+        match expr {
+            Expr::OpAny { right, ty, .. } => {
+                assert_eq!(*right.ty(), DfType::Array(Box::new(DfType::Int)));
+                assert_eq!(ty, DfType::Bool);
+            }
+            _ => panic!("unexpected expression: {expr:?}"),
+        }
+    }
+
+    #[test]
+    fn resolves_call_return_types() {
+        let expr = lower_and_typecheck(
+            "coalesce(x, y)",
+            ParserDialect::MySQL,
+            Dialect::DEFAULT_MYSQL,
+            &[DfType::BigInt, DfType::BigInt],
+        );
+        assert_eq!(*expr.ty(), DfType::BigInt);
+
+        let expr = lower_and_typecheck(
+            "round(x)",
+            ParserDialect::MySQL,
+            Dialect::DEFAULT_MYSQL,
+            &[DfType::Int, DfType::Int],
+        );
+        assert_eq!(*expr.ty(), DfType::BigInt);
+
+        let expr = lower_and_typecheck(
+            "greatest(x, y)",
+            ParserDialect::MySQL,
+            Dialect::DEFAULT_MYSQL,
+            &[DfType::Int, DfType::Int],
+        );
+        match expr {
+            Expr::Call { func, ty } => {
+                assert_eq!(ty, DfType::Int);
+                match *func {
+                    BuiltinFunction::Greatest { compare_as, .. } => {
+                        assert_eq!(compare_as, DfType::BigInt)
+                    }
+                    func => panic!("unexpected function: {func:?}"),
+                }
+            }
+            _ => panic!("unexpected expression: {expr:?}"),
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_column_indices() {
+        let mut expr = Expr::lower(
+            parse_expr(ParserDialect::MySQL, "1 + 2").unwrap(),
+            Dialect::DEFAULT_MYSQL,
+            no_op_lower_context(),
+        )
+        .unwrap();
+        expr.typecheck(&[], Dialect::DEFAULT_MYSQL).unwrap();
+
+        let mut expr = Expr::Column {
+            index: 3,
+            ty: DfType::Int,
+        };
+        assert_eq!(
+            expr.typecheck(&[DfType::Int], Dialect::DEFAULT_MYSQL)
+                .unwrap_err(),
+            ReadySetError::ProjectExprInvalidColumnIndex(3)
+        );
+    }
+}
@@ -0,0 +1,53 @@
+use std::fmt;
+
+use nom_sql::UnaryOperator as SqlUnaryOperator;
+use readyset_data::DfType;
+use serde::{Deserialize, Serialize};
+
+/// Prefix operators with an [`Expr`](crate::Expr) as their operand
+///
+/// This type is used as the operator in [`Expr::UnaryOp`](crate::Expr::UnaryOp).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub enum UnaryOperator {
+    /// `-`
+    Neg,
+
+    /// `NOT`
+    Not,
+}
+
+impl From<SqlUnaryOperator> for UnaryOperator {
+    fn from(op: SqlUnaryOperator) -> Self {
+        match op {
+            SqlUnaryOperator::Neg => Self::Neg,
+            SqlUnaryOperator::Not => Self::Not,
+        }
+    }
+}
+
+impl UnaryOperator {
+    /// Returns this operator's output type given the type of its operand
+    pub(crate) fn output_type(&self, operand_type: &DfType) -> DfType {
+        match self {
+            // Negating an unsigned integer gives a signed integer, which needs to be wider to hold
+            // the negation of every unsigned value
+            Self::Neg => match operand_type {
+                DfType::UnsignedTinyInt => DfType::SmallInt,
+                DfType::UnsignedSmallInt => DfType::Int,
+                DfType::UnsignedInt | DfType::UnsignedBigInt => DfType::BigInt,
+                ty => ty.clone(),
+            },
+            Self::Not => DfType::Bool,
+        }
+    }
+}
+
+impl fmt::Display for UnaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let op = match *self {
+            Self::Neg => "-",
+            Self::Not => "NOT",
+        };
+        f.write_str(op)
+    }
+}
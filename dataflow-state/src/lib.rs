@@ -203,6 +203,11 @@ pub trait State: SizeOf + Send {
     /// Return a copy of all records. Panics if the state is only partially materialized.
     fn cloned_records(&self) -> Vec<Vec<DfValue>>;
 
+    /// Call `f` with each record, one at a time, without copying the records or collecting them
+    /// into memory at once like [`cloned_records`](State::cloned_records). Panics if the state is
+    /// only partially materialized.
+    fn for_each_record(&self, f: &mut dyn FnMut(&[DfValue]));
+
     /// Evict up to `bytes` by randomly selected keys, returning a struct representing the index
     /// chosen to evict from along with the keys evicted and the number of bytes evicted.
     fn evict_bytes(&mut self, bytes: usize) -> Option<EvictBytesResult>;
@@ -386,6 +391,14 @@ impl State for MaterializedNodeState {
         }
     }
 
+    fn for_each_record(&self, f: &mut dyn FnMut(&[DfValue])) {
+        match self {
+            MaterializedNodeState::Memory(ms) => ms.for_each_record(f),
+            MaterializedNodeState::Persistent(ps) => ps.for_each_record(f),
+            MaterializedNodeState::PersistentReadHandle(rh) => rh.for_each_record(f),
+        }
+    }
+
     fn evict_bytes(&mut self, bytes: usize) -> Option<EvictBytesResult> {
         match self {
             MaterializedNodeState::Memory(ms) => ms.evict_bytes(bytes),
@@ -422,6 +435,10 @@ impl State for MaterializedNodeState {
 #[derive(Debug, Hash, PartialEq, Eq)]
 pub struct Row(Rc<Vec<DfValue>>);
 
+/// The number of bytes counted towards the size of the state of a node for each row it stores, in
+/// addition to the deep size of each of the row's values
+pub const ROW_OVERHEAD_BYTES: u64 = std::mem::size_of::<Vec<DfValue>>() as u64;
+
 pub type Rows = HashBag<Row, RandomState>;
 
 unsafe impl Send for Row {}
@@ -2,7 +2,8 @@ use std::collections::{BTreeMap, HashMap};
 use std::rc::Rc;
 
 use common::{IndexType, Record, Records, SizeOf, Tag};
-use rand::{self, Rng};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use readyset_client::internal::Index;
 use readyset_client::replication::ReplicationOffset;
 use readyset_client::{KeyComparison, KeyCount};
@@ -17,7 +18,6 @@ use crate::{
     RecordResult, Row, Rows, State,
 };
 
-#[derive(Default)]
 pub struct MemoryState {
     state: Vec<SingleState>,
     weak_indices: HashMap<Vec<usize>, KeyedState>,
@@ -26,6 +26,15 @@ pub struct MemoryState {
     /// The latest replication offset that has been written to the base table backed by this
     /// [`MemoryState`], it is only used when [`LocalAuthority`] is the ReadySet authority.
     replication_offset: Option<ReplicationOffset>,
+    /// Chooses the keys to evict in [`State::evict_bytes`]. See
+    /// [`MemoryState::with_eviction_seed`].
+    eviction_rng: StdRng,
+}
+
+impl Default for MemoryState {
+    fn default() -> Self {
+        Self::with_eviction_seed(0)
+    }
 }
 
 impl SizeOf for MemoryState {
@@ -296,17 +305,25 @@ impl State for MemoryState {
         self.state[0].values().flat_map(fix).collect()
     }
 
+    fn for_each_record(&self, f: &mut dyn FnMut(&[DfValue])) {
+        assert!(!self.state[0].partial());
+        for rows in self.state[0].values() {
+            for row in rows.iter() {
+                f(row)
+            }
+        }
+    }
+
     /// Evicts `bytes` by evicting random keys from the state. The key are first evicted from the
     /// strongly referenced `state`, then they are removed from the weakly referenced
     /// `weak_indices`.
     fn evict_bytes(&mut self, bytes: usize) -> Option<EvictBytesResult> {
-        let mut rng = rand::thread_rng();
-        let state_index = rng.gen_range(0, self.state.len());
+        let state_index = self.eviction_rng.gen_range(0, self.state.len());
         let mut bytes_freed = 0u64;
         let mut keys_evicted = Vec::new();
 
         while bytes_freed < bytes as u64 {
-            let evicted = self.state[state_index].evict_random(&mut rng);
+            let evicted = self.state[state_index].evict_random(&mut self.eviction_rng);
 
             if evicted.is_none() {
                 // There are no more keys in this state.
@@ -398,6 +415,23 @@ impl State for MemoryState {
 }
 
 impl MemoryState {
+    /// Construct a new, empty [`MemoryState`] which seeds the random number generator it uses to
+    /// choose the keys to evict in [`State::evict_bytes`] with `seed`.
+    ///
+    /// A fixed seed makes evictions a deterministic function of the operations applied to the
+    /// state, so that replaying a domain's packet log evicts the same keys as when it was recorded,
+    /// but each node should use a different seed so that nodes don't all evict the same keys.
+    pub fn with_eviction_seed(seed: u64) -> Self {
+        Self {
+            state: Default::default(),
+            weak_indices: Default::default(),
+            by_tag: Default::default(),
+            mem_size: 0,
+            replication_offset: None,
+            eviction_rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
     /// Returns the index in `self.state` of the index keyed on `cols` and with the given
     /// `index_type`, or None if no such index exists.
     fn state_for(&self, cols: &[usize], index_type: IndexType) -> Option<usize> {
@@ -492,6 +526,20 @@ mod tests {
         assert_eq!(3, state.row_count());
     }
 
+    #[test]
+    fn row_overhead_bytes() {
+        let mut state = MemoryState::default();
+        state.add_key(Index::hash_map(vec![0]), None);
+        let row: Vec<DfValue> = vec![1.into(), "a long enough string".into()];
+        let values_bytes = row.iter().map(SizeOf::deep_size_of).sum::<u64>();
+        insert(&mut state, row);
+
+        assert_eq!(
+            state.deep_size_of(),
+            values_bytes + crate::ROW_OVERHEAD_BYTES
+        );
+    }
+
     #[test]
     fn memory_state_process_records() {
         let mut state = MemoryState::default();
@@ -883,4 +931,32 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn evict_bytes_is_deterministic() {
+        let fill = || {
+            let mut state = MemoryState::default();
+            state.add_key(Index::hash_map(vec![0]), Some(vec![Tag::new(0)]));
+            let mut records: Records = (0..100)
+                .map(|i| (vec![i.into(), i.to_string().into()], true))
+                .collect::<Vec<_>>()
+                .into();
+            for i in 0..100 {
+                state.mark_filled(KeyComparison::Equal(vec1![i.into()]), Tag::new(0));
+            }
+            state
+                .process_records(&mut records, Some(Tag::new(0)), None)
+                .unwrap();
+            state
+        };
+
+        let evict = |state: &mut MemoryState| {
+            (0..5)
+                .map(|_| state.evict_bytes(64).unwrap().keys_evicted)
+                .collect::<Vec<_>>()
+        };
+        let evicted = evict(&mut fill());
+        assert_eq!(evicted, evict(&mut fill()));
+        assert!(!evicted.iter().all(Vec::is_empty));
+    }
 }
@@ -656,6 +656,10 @@ impl State for PersistentState {
         self.db.cloned_records()
     }
 
+    fn for_each_record(&self, f: &mut dyn FnMut(&[DfValue])) {
+        self.db.for_each_record(f)
+    }
+
     /// Returns a *row* count estimate from RocksDB (not a key count as the function name would
     /// suggest), since getting a key count could be quite expensive, and we care less about the
     /// key count of persistent nodes anyway.
@@ -926,6 +930,15 @@ impl State for PersistentStateHandle {
             .collect()
     }
 
+    fn for_each_record(&self, f: &mut dyn FnMut(&[DfValue])) {
+        let inner = self.inner();
+        let db = &inner.db;
+        let cf = db.cf_handle(&inner.indices[0].column_family).unwrap();
+        for res in db.full_iterator_cf(cf, IteratorMode::Start) {
+            f(&deserialize_row(res.unwrap().1))
+        }
+    }
+
     fn evict_bytes(&mut self, _: usize) -> Option<crate::EvictBytesResult> {
         None
     }
@@ -2462,7 +2475,11 @@ mod tests {
             .process_records(&mut vec![first.clone(), second.clone()].into(), None, None)
             .unwrap();
 
-        assert_eq!(state.cloned_records(), vec![first, second]);
+        assert_eq!(state.cloned_records(), vec![first.clone(), second.clone()]);
+
+        let mut records = vec![];
+        state.for_each_record(&mut |r| records.push(r.to_vec()));
+        assert_eq!(records, vec![first, second]);
     }
 
     #[test]
@@ -354,7 +354,7 @@ impl SingleState {
 
     /// Evict up to `bytes` by randomly selected keys from state and return them along with the
     /// removed rows
-    pub(super) fn evict_random(&mut self, rng: &mut ThreadRng) -> Option<(Vec<DfValue>, Rows)> {
+    pub(super) fn evict_random<R: Rng>(&mut self, rng: &mut R) -> Option<(Vec<DfValue>, Rows)> {
         self.state.evict_with_seed(rng.gen()).map(|(rows, key)| {
             self.row_count = self.row_count.saturating_sub(1);
             (key, rows)
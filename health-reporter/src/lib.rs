@@ -28,6 +28,8 @@ impl Health {
 pub enum State {
     Healthy,
     Unhealthy,
+    /// Still running, but preparing to shut down: no longer accepting new work.
+    Draining,
     ShuttingDown,
     Unknown,
 }
@@ -37,6 +39,7 @@ impl Display for State {
         let s = match self {
             State::Healthy => "healthy",
             State::Unhealthy => "unhealthy",
+            State::Draining => "draining",
             State::ShuttingDown => "shutting down",
             State::Unknown => "unknown",
         };
@@ -6,6 +6,7 @@ use nom::number::complete::{le_i16, le_i24, le_i64, le_u16, le_u32, le_u8};
 use nom::sequence::preceded;
 use nom::IResult;
 
+use crate::constants::CURSOR_TYPE_READ_ONLY;
 use crate::myc::constants::{CapabilityFlags, Command as CommandByte};
 
 #[derive(Debug)]
@@ -108,6 +109,12 @@ pub enum Command<'a> {
     Execute {
         stmt: u32,
         params: &'a [u8],
+        /// Whether the client asked for the results to be returned through a read-only cursor
+        read_only_cursor: bool,
+    },
+    Fetch {
+        stmt: u32,
+        rows: u32,
     },
     SendLongData {
         stmt: u32,
@@ -120,9 +127,22 @@ pub enum Command<'a> {
 
 pub fn execute(i: &[u8]) -> IResult<&[u8], Command<'_>> {
     let (i, stmt) = le_u32(i)?;
-    let (i, _flags) = take(1u8)(i)?;
+    let (i, flags) = le_u8(i)?;
     let (i, _iterations) = le_u32(i)?;
-    Ok((&[], Command::Execute { stmt, params: i }))
+    Ok((
+        &[],
+        Command::Execute {
+            stmt,
+            params: i,
+            read_only_cursor: flags & CURSOR_TYPE_READ_ONLY != 0,
+        },
+    ))
+}
+
+pub fn fetch(i: &[u8]) -> IResult<&[u8], Command<'_>> {
+    let (i, stmt) = le_u32(i)?;
+    let (i, rows) = le_u32(i)?;
+    Ok((i, Command::Fetch { stmt, rows }))
 }
 
 pub fn send_long_data(i: &[u8]) -> IResult<&[u8], Command<'_>> {
@@ -165,6 +185,7 @@ pub fn parse(i: &[u8]) -> IResult<&[u8], Command<'_>> {
             Command::ResetStmtData,
         ),
         preceded(tag(&[CommandByte::COM_STMT_EXECUTE as u8]), execute),
+        preceded(tag(&[CommandByte::COM_STMT_FETCH as u8]), fetch),
         preceded(
             tag(&[CommandByte::COM_STMT_SEND_LONG_DATA as u8]),
             send_long_data,
@@ -251,4 +272,39 @@ mod tests {
             Command::ListFields(&b"select @@version_comment limit 1"[..])
         );
     }
+
+    #[test]
+    fn it_parses_execute_with_cursor() {
+        let data = &[
+            0x17, 0x01, 0x00, 0x00, 0x00, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x01, 0x03, 0x00,
+            0x2a, 0x00,
+        ];
+        let (_, cmd) = parse(data).unwrap();
+        assert_eq!(
+            cmd,
+            Command::Execute {
+                stmt: 1,
+                params: &[0x00, 0x01, 0x03, 0x00, 0x2a, 0x00],
+                read_only_cursor: true,
+            }
+        );
+
+        let data = &[0x17, 0x01, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00];
+        let (_, cmd) = parse(data).unwrap();
+        assert_eq!(
+            cmd,
+            Command::Execute {
+                stmt: 1,
+                params: &[],
+                read_only_cursor: false,
+            }
+        );
+    }
+
+    #[test]
+    fn it_parses_fetch() {
+        let data = &[0x1c, 0x02, 0x00, 0x00, 0x00, 0x64, 0x00, 0x00, 0x00];
+        let (_, cmd) = parse(data).unwrap();
+        assert_eq!(cmd, Command::Fetch { stmt: 2, rows: 100 });
+    }
 }
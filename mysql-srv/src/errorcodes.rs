@@ -2067,6 +2067,10 @@ pub enum ErrorKind {
     /// transactions on master to account for transactions that have been committed on master but
     /// are not included in GTID_EXECUTED.
     ER_SLAVE_HAS_MORE_GTIDS_THAN_MASTER = 1885,
+    /// Check constraint '%s' is violated.
+    ///
+    /// This isn't a MariaDB error code, but is the one MySQL uses for violated CHECK constraints.
+    ER_CHECK_CONSTRAINT_VIOLATED = 3819,
 }
 
 impl From<u16> for ErrorKind {
@@ -2960,6 +2964,7 @@ impl From<u16> for ErrorKind {
             1883_u16 => ErrorKind::ER_PLUGIN_CANNOT_BE_UNINSTALLED,
             1884_u16 => ErrorKind::ER_GTID_UNSAFE_BINLOG_SPLITTABLE_STATEMENT_AND_GTID_GROUP,
             1885_u16 => ErrorKind::ER_SLAVE_HAS_MORE_GTIDS_THAN_MASTER,
+            3819_u16 => ErrorKind::ER_CHECK_CONSTRAINT_VIOLATED,
             _ => ErrorKind::ER_UNKNOWN_ERROR,
         }
     }
@@ -3856,6 +3861,7 @@ impl From<ErrorKind> for u16 {
             ErrorKind::ER_PLUGIN_CANNOT_BE_UNINSTALLED => 1883_u16,
             ErrorKind::ER_GTID_UNSAFE_BINLOG_SPLITTABLE_STATEMENT_AND_GTID_GROUP => 1884_u16,
             ErrorKind::ER_SLAVE_HAS_MORE_GTIDS_THAN_MASTER => 1885_u16,
+            ErrorKind::ER_CHECK_CONSTRAINT_VIOLATED => 3819_u16,
         }
     }
 }
@@ -4750,7 +4756,8 @@ impl ErrorKind {
             | ErrorKind::ER_AES_INVALID_IV
             | ErrorKind::ER_PLUGIN_CANNOT_BE_UNINSTALLED
             | ErrorKind::ER_GTID_UNSAFE_BINLOG_SPLITTABLE_STATEMENT_AND_GTID_GROUP
-            | ErrorKind::ER_SLAVE_HAS_MORE_GTIDS_THAN_MASTER => b"HY000",
+            | ErrorKind::ER_SLAVE_HAS_MORE_GTIDS_THAN_MASTER
+            | ErrorKind::ER_CHECK_CONSTRAINT_VIOLATED => b"HY000",
             ErrorKind::ER_XAER_NOTA => b"XAE04",
             ErrorKind::ER_XA_RBROLLBACK => b"XA100",
             ErrorKind::ER_DATA_TOO_LONG => b"22001",
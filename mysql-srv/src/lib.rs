@@ -175,6 +175,7 @@ use writers::write_err;
 
 use crate::authentication::{generate_auth_data, hash_password, AUTH_PLUGIN_NAME};
 pub use crate::myc::constants::{ColumnFlags, ColumnType, StatusFlags};
+use crate::resultset::Cursor;
 pub use crate::writers::prepare_column_definitions;
 
 mod authentication;
@@ -228,7 +229,9 @@ impl From<&mysql_async::Column> for Column {
 pub use crate::error::MsqlSrvError;
 pub use crate::errorcodes::ErrorKind;
 pub use crate::params::{ParamParser, ParamValue, Params};
-pub use crate::resultset::{InitWriter, QueryResultWriter, RowWriter, StatementMetaWriter};
+pub use crate::resultset::{
+    InitWriter, QueryResultWriter, RowSource, RowWriter, StatementMetaWriter,
+};
 pub use crate::value::{ToMySqlValue, Value, ValueInner};
 
 /// Implementors of this trait can be used to drive a MySQL-compatible database backend.
@@ -254,6 +257,12 @@ pub trait MySqlShim<W: AsyncWrite + Unpin + Send> {
     /// Any parameters included with the client's command is given in `params`.
     /// A response to the query should be given using the provided
     /// [`QueryResultWriter`](struct.QueryResultWriter.html).
+    ///
+    /// If the client asked for the results to be returned through a read-only cursor, the rows
+    /// written to `results` are held by the server and sent to the client in batches as it fetches
+    /// them with `COM_STMT_FETCH`. To avoid holding every row, write them with
+    /// [`RowWriter::finish_with_source`](struct.RowWriter.html#method.finish_with_source), so that
+    /// they're only read as they're fetched.
     async fn on_execute(
         &mut self,
         id: u32,
@@ -284,6 +293,19 @@ pub trait MySqlShim<W: AsyncWrite + Unpin + Send> {
     fn require_authentication(&self) -> bool {
         true
     }
+
+    /// Called once the client has successfully authenticated as the user with the given username
+    fn on_authenticated(&mut self, _username: &str) {}
+
+    /// Called with the character set (as a collation id) the client asked to use for the
+    /// connection in its handshake
+    fn on_charset(&mut self, _charset: u16) {}
+
+    /// The id of this connection, which is sent to the client in the initial handshake and used to
+    /// refer to the connection in statements such as `KILL`
+    fn connection_id(&self) -> u32 {
+        8
+    }
 }
 
 /// Stores a preencoded result schema for a prepared MySQL statement
@@ -390,7 +412,7 @@ impl<B: MySqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
         );
         init_packet.extend_from_slice(&[10]); // protocol 10
         init_packet.extend_from_slice(self.shim.version().as_bytes());
-        init_packet.extend_from_slice(&[0x08, 0x00, 0x00, 0x00]); // TODO: connection ID
+        init_packet.extend_from_slice(&self.shim.connection_id().to_le_bytes());
         init_packet.extend_from_slice(&auth_data[..8]);
         init_packet.push(0);
         init_packet.extend_from_slice(&CAPABILITIES.to_le_bytes()[..2]);
@@ -437,6 +459,7 @@ impl<B: MySqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
             .1;
 
         self.writer.set_seq(seq + 1);
+        self.shim.on_charset(handshake.charset);
 
         let username = handshake.username.to_owned();
         let password = handshake.password.to_vec();
@@ -509,6 +532,7 @@ impl<B: MySqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
 
         if auth_success {
             debug!(%username, "Successfully authenticated client");
+            self.shim.on_authenticated(&username);
             writers::write_ok_packet(&mut self.writer, 0, 0, StatusFlags::empty()).await?;
         } else {
             debug!(%username, ?client_auth_plugin, "Received incorrect password");
@@ -528,6 +552,8 @@ impl<B: MySqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
         use crate::commands::Command;
 
         let mut stmts: HashMap<u32, _> = HashMap::new();
+        // Cursors left open by executing statements, which the client can fetch rows from
+        let mut cursors: HashMap<u32, Cursor<W>> = HashMap::new();
         while let Some((seq, packet)) = self.reader.next().await? {
             self.writer.set_seq(seq + 1);
             let cmd = commands::parse(&packet)
@@ -563,6 +589,7 @@ impl<B: MySqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
                         .await?;
                 }
                 Command::ResetStmtData(stmt) => {
+                    cursors.remove(&stmt);
                     stmts
                         .get_mut(&stmt)
                         .ok_or_else(|| {
@@ -575,22 +602,87 @@ impl<B: MySqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
                         .clear();
                     writers::write_ok_packet(&mut self.writer, 0, 0, StatusFlags::empty()).await?;
                 }
-                Command::Execute { stmt, params } => {
+                Command::Execute {
+                    stmt,
+                    params,
+                    read_only_cursor,
+                } => {
                     let state = stmts.get_mut(&stmt).ok_or_else(|| {
                         io::Error::new(
                             io::ErrorKind::InvalidData,
                             format!("asked to execute unknown statement {}", stmt),
                         )
                     })?;
+                    // Executing a statement again closes the cursor left open by its last
+                    // execution
+                    cursors.remove(&stmt);
                     {
                         let params = params::ParamParser::new(params, state);
-                        let w = QueryResultWriter::new(&mut self.writer, true);
-                        self.shim
-                            .on_execute(stmt, params, w, &mut self.schema_cache)
-                            .await?;
+                        if read_only_cursor {
+                            let mut cursor = Cursor::default();
+                            let w = QueryResultWriter::with_cursor(&mut self.writer, &mut cursor);
+                            self.shim
+                                .on_execute(stmt, params, w, &mut self.schema_cache)
+                                .await?;
+                            if cursor.open {
+                                cursors.insert(stmt, cursor);
+                            }
+                        } else {
+                            let w = QueryResultWriter::new(&mut self.writer, true);
+                            self.shim
+                                .on_execute(stmt, params, w, &mut self.schema_cache)
+                                .await?;
+                        }
                     }
                     state.long_data.clear();
                 }
+                Command::Fetch { stmt, rows } => {
+                    let cursor = match cursors.get_mut(&stmt) {
+                        Some(cursor) => cursor,
+                        None => {
+                            writers::write_err(
+                                ErrorKind::ER_STMT_HAS_NO_OPEN_CURSOR,
+                                format!("The statement ({}) has no open cursor.", stmt).as_bytes(),
+                                &mut self.writer,
+                            )
+                            .await?;
+                            self.writer.flush().await?;
+                            continue;
+                        }
+                    };
+
+                    // Read one more row than was asked for, so we know whether this batch is the
+                    // last one
+                    let rows = rows as usize;
+                    if let Err(e) = cursor.fill(&mut self.writer, rows.saturating_add(1)).await {
+                        cursors.remove(&stmt);
+                        writers::write_err(
+                            ErrorKind::ER_UNKNOWN_ERROR,
+                            e.to_string().as_bytes(),
+                            &mut self.writer,
+                        )
+                        .await?;
+                        self.writer.flush().await?;
+                        continue;
+                    }
+
+                    let num_rows = rows.min(cursor.rows.len());
+                    for packet in cursor.rows.drain(..num_rows) {
+                        self.writer.enqueue_packet(packet);
+                        if self.writer.queue_len() > resultset::MAX_POOL_ROWS {
+                            self.writer.flush().await?;
+                        }
+                    }
+
+                    let mut status = cursor.status_flags.unwrap_or_else(StatusFlags::empty)
+                        | StatusFlags::SERVER_STATUS_CURSOR_EXISTS;
+                    if cursor.rows.is_empty() {
+                        // All the rows have been fetched, which closes the cursor
+                        status |= StatusFlags::SERVER_STATUS_LAST_ROW_SENT;
+                        cursors.remove(&stmt);
+                    }
+                    writers::write_eof_packet(&mut self.writer, status).await?;
+                }
                 Command::SendLongData { stmt, param, data } => {
                     stmts
                         .get_mut(&stmt)
@@ -608,6 +700,7 @@ impl<B: MySqlShim<W> + Send, R: AsyncRead + Unpin, W: AsyncWrite + Unpin + Send>
                 Command::Close(stmt) => {
                     self.shim.on_close(stmt).await;
                     stmts.remove(&stmt);
+                    cursors.remove(&stmt);
                     // NOTE: spec dictates no response from server
                 }
                 Command::ListFields(_) => {
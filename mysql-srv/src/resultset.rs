@@ -1,8 +1,9 @@
 use std::borrow::Borrow;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::io;
 use std::sync::Arc;
 
+use async_trait::async_trait;
 use tokio::io::AsyncWrite;
 
 use crate::myc::constants::{ColumnFlags, StatusFlags};
@@ -83,15 +84,88 @@ impl<'a, W: AsyncWrite + Unpin + 'a> StatementMetaWriter<'a, W> {
     }
 }
 
+/// A source of the rows of a resultset, which can be read lazily.
+///
+/// Reading the next row may wait, for example on rows being streamed from another database.
+///
+/// See [`RowWriter::finish_with_source`](struct.RowWriter.html#method.finish_with_source).
+#[async_trait]
+pub trait RowSource<W>: Send {
+    /// Write each column of the next row of the resultset to `rw` using
+    /// [`RowWriter::write_col`](struct.RowWriter.html#method.write_col), returning `false` if
+    /// there are no rows left
+    async fn write_next_row(&mut self, rw: &mut RowWriter<'_, W>) -> io::Result<bool>;
+}
+
+/// The rows of a resultset returned through a read-only cursor, which are sent to the client in
+/// batches as it asks for them with `COM_STMT_FETCH`
+///
+/// These are the only cursors a client can open over the protocol; SQL cursors (`DECLARE ...
+/// CURSOR`) only exist inside stored programs, which MySQL runs entirely server-side.
+pub(crate) struct Cursor<W> {
+    /// The encoded rows which haven't been fetched yet
+    pub(crate) rows: VecDeque<Vec<u8>>,
+    /// The source of the rows which haven't been encoded yet, along with the columns of the
+    /// resultset. Rows are only read from the source as the client fetches them.
+    source: Option<(Vec<Column>, Box<dyn RowSource<W>>)>,
+    /// The status flags to send along with each batch of rows, if any
+    pub(crate) status_flags: Option<StatusFlags>,
+    /// Set once a resultset with columns has been written to the cursor. Statements which don't
+    /// return rows don't leave a cursor open.
+    pub(crate) open: bool,
+}
+
+impl<W> Default for Cursor<W> {
+    fn default() -> Self {
+        Cursor {
+            rows: VecDeque::new(),
+            source: None,
+            status_flags: None,
+            open: false,
+        }
+    }
+}
+
+impl<W: AsyncWrite + Unpin + Send> Cursor<W> {
+    /// Read rows from the source of this cursor, if it has one, until at least `n` encoded rows
+    /// are buffered or the source runs out of rows
+    pub(crate) async fn fill(&mut self, writer: &mut PacketWriter<W>, n: usize) -> io::Result<()> {
+        let (columns, mut source) = match self.source.take() {
+            Some(source) => source,
+            None => return Ok(()),
+        };
+
+        let mut exhausted = false;
+        {
+            let result = QueryResultWriter::with_cursor(writer, self);
+            let mut rw = RowWriter::unstarted(result, &columns, None);
+            while rw.buffered_rows() < n {
+                if !source.write_next_row(&mut rw).await? {
+                    exhausted = true;
+                    break;
+                }
+                rw.end_row().await?;
+            }
+        }
+
+        if !exhausted {
+            self.source = Some((columns, source));
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug)]
 enum Finalizer {
     Ok {
         rows: u64,
         last_insert_id: u64,
         status_flags: Option<StatusFlags>,
+        warnings: u16,
     },
     Eof {
         status_flags: Option<StatusFlags>,
+        warnings: u16,
     },
 }
 
@@ -113,11 +187,15 @@ enum Finalizer {
 /// program may panic if an I/O error occurs when sending the end-of-records marker to the client.
 /// To handle such errors, call `no_more_results` explicitly.
 #[must_use]
-pub struct QueryResultWriter<'a, W: AsyncWrite + Unpin> {
+pub struct QueryResultWriter<'a, W> {
     // XXX: specialization instead?
     pub(crate) is_bin: bool,
     pub(crate) writer: &'a mut PacketWriter<W>,
     last_end: Option<Finalizer>,
+    /// If set, rows are written to this cursor rather than to the client
+    cursor: Option<&'a mut Cursor<W>>,
+    /// The number of warnings to report in the next OK packet
+    warnings: u16,
 }
 
 impl<'a, W: AsyncWrite + Unpin> QueryResultWriter<'a, W> {
@@ -126,17 +204,28 @@ impl<'a, W: AsyncWrite + Unpin> QueryResultWriter<'a, W> {
             is_bin,
             writer,
             last_end: None,
+            cursor: None,
+            warnings: 0,
+        }
+    }
+
+    /// Create a writer for the binary resultset of a statement executed with a read-only cursor.
+    /// Only the column definitions are sent to the client right away; the rows are kept in
+    /// `cursor` until the client fetches them.
+    pub(crate) fn with_cursor(writer: &'a mut PacketWriter<W>, cursor: &'a mut Cursor<W>) -> Self {
+        QueryResultWriter {
+            is_bin: true,
+            writer,
+            last_end: None,
+            cursor: Some(cursor),
+            warnings: 0,
         }
     }
 
     async fn finalize(&mut self, more_exists: bool) -> io::Result<()> {
         let mut status = match self.last_end {
-            Some(Finalizer::Ok {
-                rows: _,
-                last_insert_id: _,
-                status_flags,
-            })
-            | Some(Finalizer::Eof { status_flags }) => {
+            Some(Finalizer::Ok { status_flags, .. })
+            | Some(Finalizer::Eof { status_flags, .. }) => {
                 if let Some(sf) = status_flags {
                     sf
                 } else {
@@ -153,9 +242,21 @@ impl<'a, W: AsyncWrite + Unpin> QueryResultWriter<'a, W> {
             Some(Finalizer::Ok {
                 rows,
                 last_insert_id,
+                warnings,
                 ..
-            }) => writers::write_ok_packet(self.writer, rows, last_insert_id, status).await,
-            Some(Finalizer::Eof { .. }) => writers::write_eof_packet(self.writer, status).await,
+            }) => {
+                writers::write_ok_packet_with_warnings(
+                    self.writer,
+                    rows,
+                    last_insert_id,
+                    warnings,
+                    status,
+                )
+                .await
+            }
+            Some(Finalizer::Eof { warnings, .. }) => {
+                writers::write_eof_packet_with_warnings(self.writer, warnings, status).await
+            }
         }
     }
 
@@ -185,6 +286,22 @@ impl<'a, W: AsyncWrite + Unpin> QueryResultWriter<'a, W> {
         RowWriter::new(self, columns, Some(cached)).await
     }
 
+    /// Sets the number of warnings to report to the client in the OK packet sent by the next call
+    /// to [`complete_one`](struct.QueryResultWriter.html#method.complete_one) or
+    /// [`completed`](struct.QueryResultWriter.html#method.completed).
+    pub fn set_warnings(mut self, warnings: u16) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
+    /// Returns whether the resultset is being returned through a read-only cursor, in which case
+    /// rows written with
+    /// [`RowWriter::finish_with_source`](struct.RowWriter.html#method.finish_with_source) are only
+    /// read from their source as the client fetches them
+    pub fn is_cursor(&self) -> bool {
+        self.cursor.is_some()
+    }
+
     /// Send an empty resultset response to the client indicating that `rows` rows were affected by
     /// the query in this resultset. `last_insert_id` may be given to communiate an identifier for
     /// a client's most recent insertion.
@@ -200,6 +317,7 @@ impl<'a, W: AsyncWrite + Unpin> QueryResultWriter<'a, W> {
             rows,
             last_insert_id,
             status_flags,
+            warnings: std::mem::take(&mut self.warnings),
         });
         Ok(self)
     }
@@ -238,7 +356,7 @@ impl<'a, W: AsyncWrite + Unpin> QueryResultWriter<'a, W> {
     }
 }
 
-impl<'a, W: AsyncWrite + Unpin> Drop for QueryResultWriter<'a, W> {
+impl<'a, W> Drop for QueryResultWriter<'a, W> {
     fn drop(&mut self) {
         if let Some(x) = self.last_end.take() {
             eprintln!(
@@ -262,7 +380,7 @@ impl<'a, W: AsyncWrite + Unpin> Drop for QueryResultWriter<'a, W> {
 /// if an I/O error occurs when sending the end-of-records marker to the client. To avoid this,
 /// call [`finish`](struct.RowWriter.html#method.finish) explicitly.
 #[must_use]
-pub struct RowWriter<'a, W: AsyncWrite + Unpin> {
+pub struct RowWriter<'a, W> {
     result: QueryResultWriter<'a, W>,
     bitmap_len: usize,
     /// The index where the null bitmap for the current row begins
@@ -279,8 +397,13 @@ pub struct RowWriter<'a, W: AsyncWrite + Unpin> {
     // Optionally holds the status flags from the last ok packet that we have
     // received from communicating with mysql over fallback.
     last_status_flags: Option<StatusFlags>,
+    /// The number of warnings to report to the client at the end of the resultset
+    warnings: u16,
     /// A buffer to hold row data
     row_data: Option<Vec<u8>>,
+    /// If set, a copy of the encoding of every row written is kept here. See
+    /// [`RowWriter::record_rows`]
+    recorded_rows: Option<Vec<Vec<u8>>>,
 }
 
 impl<'a, W> RowWriter<'a, W>
@@ -292,8 +415,20 @@ where
         columns: &'a [Column],
         cached_column_def: Option<Arc<[u8]>>,
     ) -> io::Result<RowWriter<'a, W>> {
+        let mut rw = Self::unstarted(result, columns, cached_column_def);
+        rw.start().await?;
+        Ok(rw)
+    }
+
+    /// Create a writer for the rows of a resultset without sending the column definitions to the
+    /// client
+    fn unstarted(
+        result: QueryResultWriter<'a, W>,
+        columns: &'a [Column],
+        cached_column_def: Option<Arc<[u8]>>,
+    ) -> RowWriter<'a, W> {
         let bitmap_len = (columns.len() + 7 + 2) / 8;
-        let mut rw = RowWriter {
+        RowWriter {
             result,
             columns,
             cached: cached_column_def,
@@ -304,11 +439,20 @@ where
 
             finished: false,
             last_status_flags: None,
+            warnings: 0,
 
             row_data: None,
-        };
-        rw.start().await?;
-        Ok(rw)
+            recorded_rows: None,
+        }
+    }
+
+    /// Returns the number of encoded rows held by the cursor this resultset is being written to,
+    /// which haven't been fetched by the client yet
+    fn buffered_rows(&self) -> usize {
+        self.result
+            .cursor
+            .as_ref()
+            .map_or(0, |cursor| cursor.rows.len())
     }
 
     async fn start(&mut self) -> io::Result<()> {
@@ -316,12 +460,22 @@ where
             return Ok(());
         }
 
+        let status = if self.result.cursor.is_some() {
+            StatusFlags::SERVER_STATUS_CURSOR_EXISTS
+        } else {
+            StatusFlags::empty()
+        };
         match &self.cached {
             Some(cached) => {
-                writers::column_definitions_cached(self.columns, cached.clone(), self.result.writer)
-                    .await
+                writers::column_definitions_cached(
+                    self.columns,
+                    cached.clone(),
+                    status,
+                    self.result.writer,
+                )
+                .await
             }
-            None => writers::column_definitions(self.columns, self.result.writer).await,
+            None => writers::column_definitions(self.columns, status, self.result.writer).await,
         }
     }
 
@@ -412,7 +566,13 @@ where
         }
 
         if let Some(packet) = self.row_data.take() {
-            self.result.writer.enqueue_packet(packet);
+            if let Some(recorded_rows) = &mut self.recorded_rows {
+                recorded_rows.push(packet.clone());
+            }
+            match &mut self.result.cursor {
+                Some(cursor) => cursor.rows.push_back(packet),
+                None => self.result.writer.enqueue_packet(packet),
+            }
         }
 
         self.col = 0;
@@ -442,6 +602,54 @@ where
         }
         self.end_row().await
     }
+
+    /// Write a single row that was previously encoded for the same columns and protocol (text or
+    /// binary) by a [`RowWriter`], and captured with
+    /// [`record_rows`](struct.RowWriter.html#method.record_rows). The row is sent as-is, without
+    /// re-encoding any of its values.
+    pub async fn write_encoded_row(&mut self, row: &[u8]) -> io::Result<()> {
+        if !self.columns.is_empty() {
+            if self.col != 0 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "cannot write an encoded row in the middle of a row",
+                ));
+            }
+            let mut row_data = self.result.writer.get_buffer();
+            row_data.extend_from_slice(row);
+            self.row_data = Some(row_data);
+            self.col = self.columns.len();
+        }
+        self.end_row().await
+    }
+
+    /// Start keeping a copy of the encoding of every row written from now on, so that the same
+    /// rows can later be sent to another client (using
+    /// [`write_encoded_row`](struct.RowWriter.html#method.write_encoded_row)) without re-encoding
+    /// them. The copies can be retrieved with
+    /// [`take_recorded_rows`](struct.RowWriter.html#method.take_recorded_rows).
+    pub fn record_rows(&mut self) {
+        self.recorded_rows.get_or_insert_with(Vec::new);
+    }
+
+    /// Returns whether rows are being written using the binary protocol, rather than the text
+    /// protocol
+    pub fn is_binary(&self) -> bool {
+        self.result.is_bin
+    }
+
+    /// Returns whether the rows of this resultset are being returned through a read-only cursor,
+    /// in which case they're only sent to the client as it fetches them
+    pub fn is_cursor(&self) -> bool {
+        self.result.cursor.is_some()
+    }
+
+    /// Returns the encoding of all the rows written since
+    /// [`record_rows`](struct.RowWriter.html#method.record_rows) was called, and stops recording
+    /// rows
+    pub fn take_recorded_rows(&mut self) -> Option<Vec<Vec<u8>>> {
+        self.recorded_rows.take()
+    }
 }
 
 impl<'a, W: AsyncWrite + Unpin + 'a> RowWriter<'a, W> {
@@ -458,12 +666,19 @@ impl<'a, W: AsyncWrite + Unpin + 'a> RowWriter<'a, W> {
                 rows: self.col as u64,
                 last_insert_id: 0,
                 status_flags: self.last_status_flags.take(),
+                warnings: self.warnings,
             });
             Ok(())
+        } else if let Some(cursor) = &mut self.result.cursor {
+            // the rows are sent as the client fetches them, each batch followed by an EOF packet
+            cursor.status_flags = self.last_status_flags.take();
+            cursor.open = true;
+            Ok(())
         } else {
             // we wrote out at least one row
             self.result.last_end = Some(Finalizer::Eof {
                 status_flags: self.last_status_flags.take(),
+                warnings: self.warnings,
             });
             Ok(())
         }
@@ -475,6 +690,12 @@ impl<'a, W: AsyncWrite + Unpin + 'a> RowWriter<'a, W> {
         self
     }
 
+    /// Sets the number of warnings to report to the client when finish() gets called.
+    pub fn set_warnings(mut self, warnings: u16) -> Self {
+        self.warnings = warnings;
+        self
+    }
+
     /// Reply to the client's query with an error.
     ///
     /// This also calls `no_more_results` implicitly.
@@ -490,6 +711,29 @@ impl<'a, W: AsyncWrite + Unpin + 'a> RowWriter<'a, W> {
         self.finish_one().await?.no_more_results().await
     }
 
+    /// Write all the rows read from `source` as a part of this resultset, and indicate to the
+    /// client that no more rows are coming.
+    ///
+    /// If the rows are being returned through a read-only cursor, they're read from `source` and
+    /// encoded only as the client fetches them, rather than all being held by the cursor until
+    /// then.
+    pub async fn finish_with_source(mut self, mut source: Box<dyn RowSource<W>>) -> io::Result<()>
+    where
+        W: Send,
+    {
+        match &mut self.result.cursor {
+            Some(cursor) if !self.columns.is_empty() => {
+                cursor.source = Some((self.columns.to_vec(), source));
+            }
+            _ => {
+                while source.write_next_row(&mut self).await? {
+                    self.end_row().await?;
+                }
+            }
+        }
+        self.finish().await
+    }
+
     /// End this resultset response, and indicate to the client that no more rows are coming.
     pub async fn finish_one(mut self) -> io::Result<QueryResultWriter<'a, W>> {
         if !self.columns.is_empty() && self.col != 0 {
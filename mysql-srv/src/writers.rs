@@ -12,9 +12,23 @@ use crate::{Column, ErrorKind};
 pub(crate) async fn write_eof_packet<W: AsyncWrite + Unpin>(
     w: &mut PacketWriter<W>,
     s: StatusFlags,
+) -> io::Result<()> {
+    write_eof_packet_with_warnings(w, 0, s).await
+}
+
+pub(crate) async fn write_eof_packet_with_warnings<W: AsyncWrite + Unpin>(
+    w: &mut PacketWriter<W>,
+    warnings: u16,
+    s: StatusFlags,
 ) -> io::Result<()> {
     let mut buf = w.get_buffer();
-    buf.extend([0xFE, 0x00, 0x00, s.bits() as u8, (s.bits() >> 8) as u8]);
+    buf.extend([
+        0xFE,
+        warnings as u8,
+        (warnings >> 8) as u8,
+        s.bits() as u8,
+        (s.bits() >> 8) as u8,
+    ]);
     w.enqueue_packet(buf);
     Ok(())
 }
@@ -24,6 +38,16 @@ pub(crate) async fn write_ok_packet<W: AsyncWrite + Unpin>(
     rows: u64,
     last_insert_id: u64,
     s: StatusFlags,
+) -> io::Result<()> {
+    write_ok_packet_with_warnings(w, rows, last_insert_id, 0, s).await
+}
+
+pub(crate) async fn write_ok_packet_with_warnings<W: AsyncWrite + Unpin>(
+    w: &mut PacketWriter<W>,
+    rows: u64,
+    last_insert_id: u64,
+    warnings: u16,
+    s: StatusFlags,
 ) -> io::Result<()> {
     const MAX_OK_PACKET_LEN: usize = 1 + 9 + 9 + 2 + 2;
     let mut buf = w.get_buffer();
@@ -32,7 +56,7 @@ pub(crate) async fn write_ok_packet<W: AsyncWrite + Unpin>(
     buf.write_lenenc_int(rows)?;
     buf.write_lenenc_int(last_insert_id)?;
     buf.write_u16::<LittleEndian>(s.bits())?;
-    buf.write_all(&[0x00, 0x00])?; // no warnings
+    buf.write_u16::<LittleEndian>(warnings)?;
     w.enqueue_packet(buf);
     Ok(())
 }
@@ -81,8 +105,8 @@ where
     buf.write_u16::<LittleEndian>(0)?; // number of warnings
     w.enqueue_packet(buf);
 
-    write_column_definitions(pi, w, true).await?;
-    write_column_definitions(ci, w, true).await
+    write_column_definitions(pi, w, true, StatusFlags::empty()).await?;
+    write_column_definitions(ci, w, true, StatusFlags::empty()).await
 }
 
 /// Compute the size of the buffer required to encode this buffer
@@ -166,6 +190,7 @@ pub(crate) async fn write_column_definitions<'a, I, W>(
     i: I,
     w: &mut PacketWriter<W>,
     only_eof_on_nonempty: bool,
+    status: StatusFlags,
 ) -> io::Result<()>
 where
     I: IntoIterator<Item = &'a Column>,
@@ -183,11 +208,15 @@ where
     if empty && only_eof_on_nonempty {
         Ok(())
     } else {
-        write_eof_packet(w, StatusFlags::empty()).await
+        write_eof_packet(w, status).await
     }
 }
 
-pub(crate) async fn column_definitions<'a, I, W>(i: I, w: &mut PacketWriter<W>) -> io::Result<()>
+pub(crate) async fn column_definitions<'a, I, W>(
+    i: I,
+    status: StatusFlags,
+    w: &mut PacketWriter<W>,
+) -> io::Result<()>
 where
     I: IntoIterator<Item = &'a Column>,
     <I as IntoIterator>::IntoIter: ExactSizeIterator,
@@ -197,12 +226,13 @@ where
     let mut buf = w.get_buffer();
     buf.write_lenenc_int(i.len() as u64)?;
     w.enqueue_packet(buf);
-    write_column_definitions(i, w, false).await
+    write_column_definitions(i, w, false, status).await
 }
 
 pub(crate) async fn column_definitions_cached<'a, I, W>(
     i: I,
     cached: Arc<[u8]>,
+    status: StatusFlags,
     w: &mut PacketWriter<W>,
 ) -> io::Result<()>
 where
@@ -213,5 +243,5 @@ where
     let i = i.into_iter();
     w.enqueue_raw(cached).await?;
     w.seq = w.seq.wrapping_add((1 + i.len()) as u8);
-    write_eof_packet(w, StatusFlags::empty()).await
+    write_eof_packet(w, status).await
 }
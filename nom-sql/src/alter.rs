@@ -380,11 +380,146 @@ pub fn alter_table_statement(
     }
 }
 
+/// `ALTER READYSET ...` statement, which changes the state of a running ReadySet deployment.
+///
+/// This is a non-standard ReadySet-specific extension to SQL
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum AlterReadysetStatement {
+    /// `ALTER READYSET REPLICATION PAUSE`
+    PauseReplication,
+    /// `ALTER READYSET REPLICATION RESUME`
+    ResumeReplication,
+    /// `ALTER READYSET QUIESCE`
+    Quiesce,
+    /// `ALTER READYSET SET MIR PLAN FOR <cache> TO '<plan>'`, which replaces the dataflow for a
+    /// cache with dataflow built from a (JSON-serialized) MIR plan, as returned by `EXPLAIN MIR
+    /// PLAN`
+    SetMirPlan { cache: Relation, plan: String },
+}
+
+impl fmt::Display for AlterReadysetStatement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ALTER READYSET ")?;
+        match self {
+            Self::PauseReplication => write!(f, "REPLICATION PAUSE"),
+            Self::ResumeReplication => write!(f, "REPLICATION RESUME"),
+            Self::Quiesce => write!(f, "QUIESCE"),
+            Self::SetMirPlan { cache, plan } => write!(
+                f,
+                "SET MIR PLAN FOR {} TO {}",
+                cache,
+                Literal::String(plan.clone())
+            ),
+        }
+    }
+}
+
+fn set_mir_plan(
+    dialect: Dialect,
+) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], AlterReadysetStatement> {
+    move |i| {
+        let (i, _) = tag_no_case("set")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("mir")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("plan")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("for")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, cache) = relation(dialect)(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("to")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, plan) = dialect.utf8_string_literal()(i)?;
+        Ok((i, AlterReadysetStatement::SetMirPlan { cache, plan }))
+    }
+}
+
+pub fn alter_readyset_statement(
+    dialect: Dialect,
+) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], AlterReadysetStatement> {
+    move |i| {
+        let (i, _) = tag_no_case("alter")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("readyset")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, statement) = alt((
+            preceded(
+                terminated(tag_no_case("replication"), whitespace1),
+                alt((
+                    map(tag_no_case("pause"), |_| {
+                        AlterReadysetStatement::PauseReplication
+                    }),
+                    map(tag_no_case("resume"), |_| {
+                        AlterReadysetStatement::ResumeReplication
+                    }),
+                )),
+            ),
+            map(tag_no_case("quiesce"), |_| AlterReadysetStatement::Quiesce),
+            set_mir_plan(dialect),
+        ))(i)?;
+        let (i, _) = statement_terminator(i)?;
+        Ok((i, statement))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{Column, Dialect, SqlType};
 
+    #[test]
+    fn alter_readyset_replication() {
+        let res = test_parse!(
+            alter_readyset_statement(Dialect::MySQL),
+            b"ALTER READYSET REPLICATION PAUSE"
+        );
+        assert_eq!(res, AlterReadysetStatement::PauseReplication);
+        assert_eq!(res.to_string(), "ALTER READYSET REPLICATION PAUSE");
+
+        let res = test_parse!(
+            alter_readyset_statement(Dialect::MySQL),
+            b"alter readyset\treplication resume;"
+        );
+        assert_eq!(res, AlterReadysetStatement::ResumeReplication);
+    }
+
+    #[test]
+    fn alter_readyset_quiesce() {
+        let res = test_parse!(
+            alter_readyset_statement(Dialect::MySQL),
+            b"ALTER READYSET QUIESCE;"
+        );
+        assert_eq!(res, AlterReadysetStatement::Quiesce);
+        assert_eq!(res.to_string(), "ALTER READYSET QUIESCE");
+    }
+
+    #[test]
+    fn alter_readyset_set_mir_plan() {
+        let res = test_parse!(
+            alter_readyset_statement(Dialect::MySQL),
+            br#"ALTER READYSET SET MIR PLAN FOR `q1` TO '{"nodes": [{"name": "it''s"}]}';"#
+        );
+        assert_eq!(
+            res,
+            AlterReadysetStatement::SetMirPlan {
+                cache: "q1".into(),
+                plan: r#"{"nodes": [{"name": "it's"}]}"#.into()
+            }
+        );
+        assert_eq!(
+            res.to_string(),
+            r#"ALTER READYSET SET MIR PLAN FOR `q1` TO '{"nodes": [{"name": "it''s"}]}'"#
+        );
+
+        let displayed = res.to_string();
+        let reparsed = test_parse!(
+            alter_readyset_statement(Dialect::MySQL),
+            displayed.as_bytes()
+        );
+        assert_eq!(reparsed, res);
+    }
+
     #[test]
     fn display_add_column() {
         let stmt = AlterTableStatement {
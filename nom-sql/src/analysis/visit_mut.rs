@@ -16,13 +16,14 @@ use crate::select::LimitClause;
 use crate::set::Variable;
 use crate::transaction::{CommitStatement, RollbackStatement, StartTransactionStatement};
 use crate::{
-    AlterColumnOperation, AlterTableDefinition, AlterTableStatement, CacheInner, CaseWhenBranch,
-    Column, ColumnConstraint, ColumnSpecification, CommonTableExpr, CompoundSelectStatement,
-    CreateCacheStatement, CreateTableStatement, CreateViewStatement, DeleteStatement,
-    DropAllCachesStatement, DropCacheStatement, DropTableStatement, DropViewStatement,
-    ExplainStatement, Expr, FieldDefinitionExpr, FieldReference, FunctionExpr, GroupByClause,
-    InValue, InsertStatement, JoinClause, JoinConstraint, JoinRightSide, Literal, OrderClause,
-    Relation, SelectSpecification, SelectStatement, SetNames, SetPostgresParameter, SetStatement,
+    AlterColumnOperation, AlterReadysetStatement, AlterTableDefinition, AlterTableStatement,
+    CacheInner, CaseWhenBranch, Column, ColumnConstraint, ColumnSpecification, CommonTableExpr,
+    CompoundSelectStatement, CopyCacheStatement, CreateCacheStatement, CreateTableStatement,
+    CreateViewStatement, DeleteStatement, DropAllCachesStatement, DropCacheStatement,
+    DropTableStatement, DropViewStatement, DumpCachesStatement, ExplainStatement, Expr,
+    FieldDefinitionExpr, FieldReference, FunctionExpr, GroupByClause, InValue, InsertStatement,
+    JoinClause, JoinConstraint, JoinRightSide, KillStatement, Literal, OrderClause, Relation,
+    SelectSpecification, SelectStatement, SetNames, SetPostgresParameter, SetStatement,
     SetVariables, ShowStatement, SqlIdentifier, SqlQuery, SqlType, TableExpr, TableExprInner,
     TableKey, UpdateStatement, UseStatement,
 };
@@ -383,6 +384,27 @@ pub trait VisitorMut<'ast>: Sized {
         Ok(())
     }
 
+    fn visit_alter_readyset_statement(
+        &mut self,
+        _alter_readyset_statement: &'ast mut AlterReadysetStatement,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn visit_dump_caches_statement(
+        &mut self,
+        _dump_caches_statement: &'ast mut DumpCachesStatement,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn visit_copy_cache_statement(
+        &mut self,
+        copy_cache_statement: &'ast mut CopyCacheStatement,
+    ) -> Result<(), Self::Error> {
+        walk_relation(self, &mut copy_cache_statement.name)
+    }
+
     fn visit_drop_view_statement(
         &mut self,
         drop_view_statement: &'ast mut DropViewStatement,
@@ -411,6 +433,13 @@ pub trait VisitorMut<'ast>: Sized {
         Ok(())
     }
 
+    fn visit_kill_statement(
+        &mut self,
+        _kill_statement: &'ast mut KillStatement,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
     fn visit_sql_query(&mut self, sql_query: &'ast mut SqlQuery) -> Result<(), Self::Error> {
         walk_sql_query(self, sql_query)
     }
@@ -491,6 +520,10 @@ pub fn walk_function_expr<'ast, V: VisitorMut<'ast>>(
         FunctionExpr::Max(expr) => visitor.visit_expr(expr.as_mut()),
         FunctionExpr::Min(expr) => visitor.visit_expr(expr.as_mut()),
         FunctionExpr::GroupConcat { expr, .. } => visitor.visit_expr(expr.as_mut()),
+        FunctionExpr::PercentileCont { fraction, expr } => {
+            visitor.visit_literal(fraction)?;
+            visitor.visit_expr(expr.as_mut())
+        }
         FunctionExpr::Call { arguments, .. } => {
             for arg in arguments {
                 visitor.visit_expr(arg)?;
@@ -875,7 +908,7 @@ pub fn walk_column_constraint<'a, V: VisitorMut<'a>>(
         | ColumnConstraint::AutoIncrement
         | ColumnConstraint::PrimaryKey
         | ColumnConstraint::Unique
-        | ColumnConstraint::OnUpdateCurrentTimestamp => Ok(()),
+        | ColumnConstraint::OnUpdateCurrentTimestamp(_) => Ok(()),
     }
 }
 
@@ -999,6 +1032,9 @@ pub fn walk_delete_statement<'a, V: VisitorMut<'a>>(
     delete_statement: &'a mut DeleteStatement,
 ) -> Result<(), V::Error> {
     visitor.visit_table(&mut delete_statement.table)?;
+    for table_expr in &mut delete_statement.using {
+        visitor.visit_table_expr(table_expr)?;
+    }
     if let Some(expr) = &mut delete_statement.where_clause {
         visitor.visit_where_clause(expr)?;
     }
@@ -1024,6 +1060,9 @@ pub fn walk_update_statement<'a, V: VisitorMut<'a>>(
         visitor.visit_column(col)?;
         visitor.visit_expr(expr)?;
     }
+    for table_expr in &mut update_statement.from {
+        visitor.visit_table_expr(table_expr)?;
+    }
 
     if let Some(expr) = &mut update_statement.where_clause {
         visitor.visit_where_clause(expr)?;
@@ -1106,6 +1145,7 @@ pub fn walk_sql_query<'a, V: VisitorMut<'a>>(
         SqlQuery::CreateTable(statement) => visitor.visit_create_table_statement(statement),
         SqlQuery::CreateView(statement) => visitor.visit_create_view_statement(statement),
         SqlQuery::AlterTable(statement) => visitor.visit_alter_table_statement(statement),
+        SqlQuery::AlterReadyset(statement) => visitor.visit_alter_readyset_statement(statement),
         SqlQuery::Insert(statement) => visitor.visit_insert_statement(statement),
         SqlQuery::CompoundSelect(statement) => visitor.visit_compound_select_statement(statement),
         SqlQuery::Select(statement) => visitor.visit_select_statement(statement),
@@ -1122,10 +1162,13 @@ pub fn walk_sql_query<'a, V: VisitorMut<'a>>(
         SqlQuery::CreateCache(statement) => visitor.visit_create_cache_statement(statement),
         SqlQuery::DropCache(statement) => visitor.visit_drop_cache_statement(statement),
         SqlQuery::DropAllCaches(statement) => visitor.visit_drop_all_caches_statement(statement),
+        SqlQuery::DumpCaches(statement) => visitor.visit_dump_caches_statement(statement),
+        SqlQuery::CopyCache(statement) => visitor.visit_copy_cache_statement(statement),
         SqlQuery::DropView(statement) => visitor.visit_drop_view_statement(statement),
         SqlQuery::Use(statement) => visitor.visit_use_statement(statement),
         SqlQuery::Show(statement) => visitor.visit_show_statement(statement),
         SqlQuery::Explain(statement) => visitor.visit_explain_statement(statement),
+        SqlQuery::Kill(statement) => visitor.visit_kill_statement(statement),
     }
 }
 
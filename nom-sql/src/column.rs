@@ -11,7 +11,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::common::{column_identifier_no_alias, parse_comment};
 use crate::expression::expression;
-use crate::sql_type::type_identifier;
+use crate::sql_type::{delim_u16, type_identifier};
 use crate::whitespace::{whitespace0, whitespace1};
 use crate::{Dialect, Expr, Literal, NomSqlResult, Relation, SqlIdentifier, SqlType};
 
@@ -72,7 +72,9 @@ pub enum ColumnConstraint {
     Unique,
     /// NOTE(grfn): Yes, this really is its own special thing, not just an expression - see
     /// <https://dev.mysql.com/doc/refman/8.0/en/timestamp-initialization.html>
-    OnUpdateCurrentTimestamp,
+    ///
+    /// Holds the fractional seconds precision given to the function, if any
+    OnUpdateCurrentTimestamp(Option<u16>),
 }
 
 impl fmt::Display for ColumnConstraint {
@@ -88,7 +90,12 @@ impl fmt::Display for ColumnConstraint {
             ColumnConstraint::AutoIncrement => write!(f, "AUTO_INCREMENT"),
             ColumnConstraint::PrimaryKey => write!(f, "PRIMARY KEY"),
             ColumnConstraint::Unique => write!(f, "UNIQUE"),
-            ColumnConstraint::OnUpdateCurrentTimestamp => write!(f, "ON UPDATE CURRENT_TIMESTAMP"),
+            ColumnConstraint::OnUpdateCurrentTimestamp(None) => {
+                write!(f, "ON UPDATE CURRENT_TIMESTAMP")
+            }
+            ColumnConstraint::OnUpdateCurrentTimestamp(Some(precision)) => {
+                write!(f, "ON UPDATE CURRENT_TIMESTAMP({})", precision)
+            }
         }
     }
 }
@@ -170,8 +177,11 @@ pub fn on_update_current_timestamp(i: LocatedSpan<&[u8]>) -> NomSqlResult<&[u8],
         tag_no_case("localtime"),
         tag_no_case("localtimestamp"),
     ))(i)?;
-    let (i, _) = opt(tag("()"))(i)?;
-    Ok((i, ColumnConstraint::OnUpdateCurrentTimestamp))
+    let (i, precision) = opt(alt((map(tag("()"), |_| None), map(delim_u16, Some))))(i)?;
+    Ok((
+        i,
+        ColumnConstraint::OnUpdateCurrentTimestamp(precision.flatten()),
+    ))
 }
 
 pub fn column_constraint(
@@ -336,6 +346,32 @@ mod tests {
                 ColumnConstraint::DefaultValue(Expr::Literal(Literal::Boolean(true)))
             ));
         }
+
+        #[test]
+        fn on_update_current_timestamp() {
+            let input = b"`c` DATETIME(6) ON UPDATE CURRENT_TIMESTAMP(6)";
+            let cspec = column_specification(Dialect::MySQL)(LocatedSpan::new(input))
+                .unwrap()
+                .1;
+            assert_eq!(
+                cspec.constraints,
+                vec![ColumnConstraint::OnUpdateCurrentTimestamp(Some(6))]
+            );
+            assert_eq!(
+                cspec.to_string(),
+                String::from_utf8(input.to_vec()).unwrap()
+            );
+
+            let cspec = column_specification(Dialect::MySQL)(LocatedSpan::new(
+                b"`c` timestamp ON UPDATE now()",
+            ))
+            .unwrap()
+            .1;
+            assert_eq!(
+                cspec.constraints,
+                vec![ColumnConstraint::OnUpdateCurrentTimestamp(None)]
+            );
+        }
     }
 
     mod postgres {
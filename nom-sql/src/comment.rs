@@ -0,0 +1,542 @@
+//! Preservation of the comments in a query.
+//!
+//! The parsers in this crate treat comments as whitespace, so [`parse_query`] drops them. Some
+//! comments carry meaning for the upstream database or for tooling though, such as optimizer
+//! hints (`SELECT /*+ MAX_EXECUTION_TIME(1000) */ ...`) or routing markers
+//! (`/* route:replica */ SELECT ...`), so [`parse_query_with_comments`] keeps them in the AST of
+//! the statement, in [`SelectStatement::comments`], which re-emits them when displayed.
+//!
+//! Only `SELECT` statements keep their comments, since those are the statements which get
+//! rewritten; other statements are either executed directly or proxied upstream using their
+//! original text.
+//!
+//! Comments are classified by their position in the query:
+//!
+//! * *leading* comments come before the first token of the query,
+//! * *trailing* comments come after the last token of the query (ignoring a trailing `;`), and
+//! * *inline* comments are everywhere else, within the statement or its expressions.
+//!
+//! Since rewriting a query can move, duplicate or remove the expressions around an inline comment,
+//! inline comments are re-emitted directly after the first keyword of the statement, which is
+//! where MySQL expects optimizer hints to be. Their original positions are kept in
+//! [`Comment::offset`].
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{parse_query, Dialect, SelectStatement, SqlQuery};
+
+/// The kind of a [`Comment`]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum CommentKind {
+    /// A comment of the form `/* ... */`
+    Block,
+    /// An optimizer hint, of the form `/*+ ... */`
+    Hint,
+    /// A comment running to the end of the line, starting with `--` (which MySQL only treats as a
+    /// comment if it's followed by whitespace) or, in MySQL, `#`
+    Line,
+}
+
+/// A single comment in a query
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct Comment {
+    pub kind: CommentKind,
+    /// The full text of the comment, including its delimiters (but not the newline ending a
+    /// [`CommentKind::Line`] comment)
+    pub text: String,
+    /// The byte offset of the start of the comment in the original query
+    pub offset: usize,
+}
+
+impl Comment {
+    /// Returns the content of the comment, without its delimiters
+    pub fn body(&self) -> &str {
+        match self.kind {
+            CommentKind::Block => &self.text[2..self.text.len() - 2],
+            CommentKind::Hint => &self.text[3..self.text.len() - 2],
+            CommentKind::Line if self.text.starts_with('#') => &self.text[1..],
+            CommentKind::Line => &self.text[2..],
+        }
+    }
+}
+
+impl fmt::Display for Comment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+/// The comments in the text of a [`SelectStatement`], which are kept in its AST so they survive
+/// rewriting the statement and are re-emitted when it's displayed. See the [module
+/// documentation](self) for more information.
+///
+/// Comments don't change what a statement means, so all `Comments` compare equal and hash
+/// identically: statements which differ only in their comments are still the same statement, and
+/// share caches.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Comments {
+    /// Comments before the first token of the statement
+    pub leading: Vec<Comment>,
+    /// Comments within the statement
+    pub inline: Vec<Comment>,
+    /// Comments after the last token of the statement
+    pub trailing: Vec<Comment>,
+}
+
+impl PartialEq for Comments {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Comments {}
+
+impl PartialOrd for Comments {
+    fn partial_cmp(&self, _: &Self) -> Option<Ordering> {
+        Some(Ordering::Equal)
+    }
+}
+
+impl Hash for Comments {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
+impl Comments {
+    /// Returns true if there are no comments
+    pub fn is_empty(&self) -> bool {
+        self.leading.is_empty() && self.inline.is_empty() && self.trailing.is_empty()
+    }
+
+    /// Returns the optimizer hints in the statement, wherever they appear in it
+    pub fn hints(&self) -> impl Iterator<Item = &Comment> {
+        self.iter()
+            .filter(|comment| comment.kind == CommentKind::Hint)
+    }
+
+    /// Returns all the comments in the statement, in the order they appeared in
+    pub fn iter(&self) -> impl Iterator<Item = &Comment> {
+        self.leading
+            .iter()
+            .chain(&self.inline)
+            .chain(&self.trailing)
+    }
+
+    /// Write the leading comments, which go before the statement
+    pub(crate) fn fmt_leading(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for comment in &self.leading {
+            match comment.kind {
+                CommentKind::Line => writeln!(f, "{comment}")?,
+                CommentKind::Block | CommentKind::Hint => write!(f, "{comment} ")?,
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the inline comments, which go directly after the statement's first keyword
+    pub(crate) fn fmt_inline(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for comment in &self.inline {
+            write!(f, " {comment}")?;
+            if comment.kind == CommentKind::Line {
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the trailing comments, which go after the statement
+    pub(crate) fn fmt_trailing(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut separator = " ";
+        for comment in &self.trailing {
+            write!(f, "{separator}{comment}")?;
+            separator = if comment.kind == CommentKind::Line {
+                "\n"
+            } else {
+                " "
+            };
+        }
+        Ok(())
+    }
+}
+
+/// The result of scanning the text of a query for comments
+struct Scanned {
+    /// The text of the query, with each comment replaced by a single space
+    stripped: String,
+    comments: Vec<Comment>,
+    /// The byte offset of the start of the first token of the query
+    first_token: Option<usize>,
+    /// The byte offset of the end of the last token of the query, ignoring a trailing `;`
+    last_token_end: usize,
+}
+
+/// Returns the byte offset just past the end of the string literal or quoted identifier starting
+/// with the `quote` at `start`, or the length of `bytes` if it's unterminated
+fn skip_quoted(bytes: &[u8], start: usize, quote: u8, backslash_escapes: bool) -> usize {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if backslash_escapes => i += 2,
+            c if c == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// If `input` starts with the opening delimiter of a PostgreSQL dollar-quoted string (`$$` or
+/// `$tag$`), returns that delimiter
+fn dollar_quote_delimiter(input: &str) -> Option<&str> {
+    let bytes = input.as_bytes();
+    let tag_len = bytes[1..].iter().position(|c| *c == b'$')?;
+    let tag = &bytes[1..1 + tag_len];
+    let valid = tag.first().map_or(true, |c| !c.is_ascii_digit())
+        && tag
+            .iter()
+            .all(|c| c.is_ascii_alphanumeric() || *c == b'_' || !c.is_ascii());
+    valid.then(|| &input[..tag_len + 2])
+}
+
+/// Find all the comments in the given query text, skipping over string literals and quoted
+/// identifiers
+fn scan(dialect: Dialect, input: &str) -> Scanned {
+    let bytes = input.as_bytes();
+    let mut stripped = String::with_capacity(input.len());
+    let mut comments = vec![];
+    let mut first_token = None;
+    let mut last_token = 0..0;
+    let mut prev_token_end = 0;
+    // The start of the text that hasn't been copied to `stripped` yet
+    let mut copied = 0;
+
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        // Whether the previous byte could be part of the same identifier (or keyword) as this one
+        let in_identifier = i.checked_sub(1).map_or(false, |p| {
+            bytes[p].is_ascii_alphanumeric() || b"_$".contains(&bytes[p])
+        });
+        let comment = match bytes[i] {
+            b'/' if bytes.get(i + 1) == Some(&b'*') => input[i + 2..].find("*/").map(|end| {
+                i += 2 + end + 2;
+                if bytes.get(start + 2) == Some(&b'+') {
+                    CommentKind::Hint
+                } else {
+                    CommentKind::Block
+                }
+            }),
+            b'-' if bytes.get(i + 1) == Some(&b'-')
+                && (dialect != Dialect::MySQL
+                    || bytes
+                        .get(i + 2)
+                        .map_or(true, |c| c.is_ascii_whitespace() || c.is_ascii_control())) =>
+            {
+                Some(CommentKind::Line)
+            }
+            b'#' if dialect == Dialect::MySQL => Some(CommentKind::Line),
+            // PostgreSQL escape strings (`E'...'`), in which backslashes escape quotes
+            b'e' | b'E'
+                if dialect == Dialect::PostgreSQL
+                    && bytes.get(i + 1) == Some(&b'\'')
+                    && !in_identifier =>
+            {
+                i = skip_quoted(bytes, i + 1, b'\'', true);
+                None
+            }
+            // PostgreSQL dollar-quoted strings (`$$...$$` or `$tag$...$tag$`), which contain no
+            // escapes and end at the next occurrence of their opening delimiter
+            b'$' if dialect == Dialect::PostgreSQL && !in_identifier => {
+                match dollar_quote_delimiter(&input[i..]) {
+                    Some(delimiter) => {
+                        let body = i + delimiter.len();
+                        i = input[body..]
+                            .find(delimiter)
+                            .map_or(bytes.len(), |end| body + end + delimiter.len());
+                    }
+                    None => i += 1,
+                }
+                None
+            }
+            quote @ (b'\'' | b'"' | b'`') => {
+                i = skip_quoted(bytes, i, quote, quote != b'`' && dialect == Dialect::MySQL);
+                None
+            }
+            c if c.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            _ => {
+                i += 1;
+                None
+            }
+        };
+
+        match comment {
+            Some(kind) => {
+                if kind == CommentKind::Line {
+                    i = input[i..]
+                        .find(|c| c == '\n' || c == '\r')
+                        .map(|end| i + end)
+                        .unwrap_or(bytes.len());
+                }
+                comments.push(Comment {
+                    kind,
+                    text: input[start..i].to_owned(),
+                    offset: start,
+                });
+                stripped.push_str(&input[copied..start]);
+                stripped.push(' ');
+                copied = i;
+            }
+            None => {
+                // An unterminated `/*` is left for the parser to reject
+                i = i.max(start + 1);
+                first_token.get_or_insert(start);
+                prev_token_end = last_token.end;
+                last_token = start..i;
+            }
+        }
+    }
+    stripped.push_str(&input[copied..]);
+    let last_token_end = if &bytes[last_token.clone()] == b";" {
+        prev_token_end
+    } else {
+        last_token.end
+    };
+
+    Scanned {
+        stripped,
+        comments,
+        first_token,
+        last_token_end,
+    }
+}
+
+/// Parse the given query, keeping the comments in its text in the AST if it's a `SELECT`. See the
+/// [module documentation](self) for more information.
+pub fn parse_query_with_comments<T>(dialect: Dialect, input: T) -> Result<SqlQuery, String>
+where
+    T: AsRef<str>,
+{
+    let Scanned {
+        stripped,
+        comments,
+        first_token,
+        last_token_end,
+    } = scan(dialect, input.as_ref());
+    let mut query = parse_query(dialect, stripped)?;
+
+    let res = match &mut query {
+        SqlQuery::Select(select) => &mut select.comments,
+        _ => return Ok(query),
+    };
+    for comment in comments {
+        if first_token.map_or(true, |first_token| comment.offset < first_token) {
+            res.leading.push(comment);
+        } else if comment.offset >= last_token_end {
+            res.trailing.push(comment);
+        } else {
+            res.inline.push(comment);
+        }
+    }
+    Ok(query)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+
+    use super::*;
+
+    fn parse(dialect: Dialect, input: &str) -> SelectStatement {
+        match parse_query_with_comments(dialect, input).unwrap() {
+            SqlQuery::Select(select) => select,
+            query => panic!("Expected SELECT, got {query}"),
+        }
+    }
+
+    #[test]
+    fn leading_and_trailing_comments() {
+        let res = parse(
+            Dialect::MySQL,
+            "/* route:replica */ -- app=web\nSELECT * FROM t; # done",
+        );
+        assert_eq!(
+            res.comments.leading,
+            vec![
+                Comment {
+                    kind: CommentKind::Block,
+                    text: "/* route:replica */".into(),
+                    offset: 0,
+                },
+                Comment {
+                    kind: CommentKind::Line,
+                    text: "-- app=web".into(),
+                    offset: 20,
+                },
+            ]
+        );
+        assert!(res.comments.inline.is_empty());
+        assert_eq!(
+            res.comments.trailing,
+            vec![Comment {
+                kind: CommentKind::Line,
+                text: "# done".into(),
+                offset: 48,
+            }]
+        );
+        assert_eq!(res.comments.leading[0].body(), " route:replica ");
+        assert_eq!(
+            res.to_string(),
+            "/* route:replica */ -- app=web\nSELECT * FROM `t` # done"
+        );
+    }
+
+    #[test]
+    fn optimizer_hints() {
+        let res = parse(
+            Dialect::MySQL,
+            "SELECT /*+ MAX_EXECUTION_TIME(1000) */ x FROM t WHERE x = '/* not a comment */'",
+        );
+        assert!(res.comments.leading.is_empty());
+        assert!(res.comments.trailing.is_empty());
+        assert_eq!(
+            res.comments.hints().collect::<Vec<_>>(),
+            vec![&Comment {
+                kind: CommentKind::Hint,
+                text: "/*+ MAX_EXECUTION_TIME(1000) */".into(),
+                offset: 7,
+            }]
+        );
+        assert_eq!(
+            res.to_string(),
+            "SELECT /*+ MAX_EXECUTION_TIME(1000) */ `x` FROM `t` \
+             WHERE (`x` = '/* not a comment */')"
+        );
+    }
+
+    #[test]
+    fn comments_in_expressions() {
+        let res = parse(
+            Dialect::PostgreSQL,
+            "SELECT x FROM t WHERE x = 1 /* bump */ AND id = 2 -- by id",
+        );
+        assert_eq!(
+            res.comments.iter().map(|c| c.offset).collect::<Vec<_>>(),
+            vec![28, 50]
+        );
+        assert_eq!(res.comments.inline.len(), 1);
+        assert_eq!(res.comments.trailing.len(), 1);
+        assert_eq!(
+            res.to_string(),
+            "SELECT /* bump */ `x` FROM `t` WHERE ((`x` = 1) AND (`id` = 2)) -- by id"
+        );
+    }
+
+    #[test]
+    fn mysql_line_comments_need_whitespace() {
+        let res = parse(Dialect::MySQL, "SELECT x FROM t WHERE y = 1--1");
+        assert!(res.comments.is_empty());
+
+        let res = parse(Dialect::MySQL, "SELECT x FROM t --1");
+        assert_eq!(res.comments.trailing.len(), 0);
+        let res = parse(Dialect::PostgreSQL, "SELECT x FROM t --1");
+        assert_eq!(res.comments.trailing.len(), 1);
+    }
+
+    #[test]
+    fn postgres_escape_strings() {
+        let res = parse(
+            Dialect::PostgreSQL,
+            "SELECT x FROM t WHERE x = E'it\\'s -- not a comment' -- end",
+        );
+        assert_eq!(res.comments.inline.len(), 0);
+        assert_eq!(
+            res.comments.trailing,
+            vec![Comment {
+                kind: CommentKind::Line,
+                text: "-- end".into(),
+                offset: 52,
+            }]
+        );
+
+        // Outside of escape strings, backslashes are just characters
+        let scanned = scan(Dialect::PostgreSQL, "SELECT 'a\\' -- end");
+        assert_eq!(scanned.comments.len(), 1);
+    }
+
+    #[test]
+    fn postgres_dollar_quoted_strings() {
+        let scanned = scan(
+            Dialect::PostgreSQL,
+            "SELECT $$ -- not a comment $$, $tag$ /* nor $$ this */ $tag$ FROM t -- end",
+        );
+        assert_eq!(
+            scanned
+                .comments
+                .iter()
+                .map(|c| c.text.as_str())
+                .collect::<Vec<_>>(),
+            vec!["-- end"]
+        );
+
+        // Parameters and identifiers containing `$` don't start dollar-quoted strings
+        let scanned = scan(
+            Dialect::PostgreSQL,
+            "SELECT a$b$ FROM t WHERE x = $1 /* one */ AND y = $2 -- two",
+        );
+        assert_eq!(scanned.comments.len(), 2);
+
+        // MySQL has no dollar-quoted strings
+        let scanned = scan(Dialect::MySQL, "SELECT $$ -- a comment $$ FROM t");
+        assert_eq!(scanned.comments.len(), 1);
+    }
+
+    #[test]
+    fn leading_hints() {
+        let res = parse(Dialect::MySQL, "/*+ NOCACHE */ SELECT x FROM t");
+        assert_eq!(res.comments.leading.len(), 1);
+        assert_eq!(
+            res.comments.hints().map(Comment::body).collect::<Vec<_>>(),
+            vec![" NOCACHE "]
+        );
+    }
+
+    #[test]
+    fn hints_after_ctes() {
+        let res = parse(
+            Dialect::MySQL,
+            "WITH q AS (SELECT x FROM t) SELECT /*+ NO_ICP(q) */ x FROM q",
+        );
+        assert_eq!(
+            res.to_string(),
+            "WITH `q` AS (SELECT `x` FROM `t`) SELECT /*+ NO_ICP(q) */ `x` FROM `q`"
+        );
+    }
+
+    #[test]
+    fn comments_survive_rewrites() {
+        let mut res = parse(Dialect::MySQL, "/* marker */ SELECT /*+ hint */ x FROM t");
+        res.distinct = true;
+        assert_eq!(
+            res.to_string(),
+            "/* marker */ SELECT /*+ hint */ DISTINCT `x` FROM `t`"
+        );
+    }
+
+    #[test]
+    fn comments_dont_change_the_statement() {
+        let commented = parse(Dialect::MySQL, "/* trace=1 */ SELECT x FROM t -- end");
+        let plain = parse(Dialect::MySQL, "SELECT x FROM t");
+        assert_eq!(commented, plain);
+
+        let hash = |select: &SelectStatement| {
+            let mut hasher = DefaultHasher::new();
+            select.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&commented), hash(&plain));
+    }
+}
@@ -19,6 +19,7 @@ use serde::{Deserialize, Serialize};
 use crate::column::Column;
 use crate::dialect::Dialect;
 use crate::expression::expression;
+use crate::literal::literal;
 use crate::table::Relation;
 use crate::whitespace::{whitespace0, whitespace1};
 use crate::{Expr, FunctionExpr, Literal, NomSqlResult, SqlIdentifier};
@@ -46,7 +47,7 @@ impl Display for IndexType {
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum ReferentialAction {
     Cascade,
     SetNull,
@@ -509,6 +510,44 @@ fn substring(dialect: Dialect) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[
     }
 }
 
+fn percentile_cont(
+    dialect: Dialect,
+) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], FunctionExpr> {
+    move |i| {
+        let (i, _) = tag_no_case("percentile_cont")(i)?;
+        let (i, _) = whitespace0(i)?;
+        let (i, fraction) = delimited(
+            terminated(tag("("), whitespace0),
+            literal(dialect),
+            preceded(whitespace0, tag(")")),
+        )(i)?;
+        let (i, _) = tuple((
+            whitespace1,
+            tag_no_case("within"),
+            whitespace1,
+            tag_no_case("group"),
+            whitespace0,
+            tag("("),
+            whitespace0,
+            tag_no_case("order"),
+            whitespace1,
+            tag_no_case("by"),
+            whitespace1,
+        ))(i)?;
+        let (i, expr) = expression(dialect)(i)?;
+        let (i, _) = whitespace0(i)?;
+        let (i, _) = tag(")")(i)?;
+
+        Ok((
+            i,
+            FunctionExpr::PercentileCont {
+                fraction,
+                expr: Box::new(expr),
+            },
+        ))
+    }
+}
+
 fn function_call(
     dialect: Dialect,
 ) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], FunctionExpr> {
@@ -576,6 +615,7 @@ pub fn function_expr(
                 },
             ),
             substring(dialect),
+            percentile_cont(dialect),
             function_call(dialect),
             function_call_without_parens,
         ))(i)
@@ -842,7 +882,7 @@ pub fn field_reference_list(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{to_nom_result, SqlType};
+    use crate::{to_nom_result, Double, SqlType};
 
     fn test_opt_delimited_fn_call(i: &str) -> IResult<&[u8], &[u8]> {
         opt_delimited(tag("("), tag("abc"), tag(")"))(i.as_bytes())
@@ -890,6 +930,25 @@ mod tests {
         assert_eq!(res.unwrap().1, expected);
     }
 
+    #[test]
+    fn percentile_cont() {
+        let qs = b"PERCENTILE_CONT(0.95) WITHIN GROUP (ORDER BY x)";
+        let expected = FunctionExpr::PercentileCont {
+            fraction: Literal::Double(Double {
+                value: 0.95,
+                precision: 2,
+            }),
+            expr: Box::new(Expr::Column(Column::from("x"))),
+        };
+        let res = to_nom_result(function_expr(Dialect::MySQL)(LocatedSpan::new(qs)));
+        let res = res.unwrap().1;
+        assert_eq!(res, expected);
+        assert_eq!(
+            res.to_string(),
+            "percentile_cont(0.95) within group (order by `x`)"
+        );
+    }
+
     #[test]
     fn simple_generic_function() {
         let qlist = [
@@ -0,0 +1,138 @@
+use std::fmt::{self, Display};
+
+use nom::branch::alt;
+use nom::bytes::complete::tag_no_case;
+use nom::combinator::{opt, value};
+use nom::sequence::{preceded, terminated, tuple};
+use nom_locate::LocatedSpan;
+use serde::{Deserialize, Serialize};
+
+use crate::common::statement_terminator;
+use crate::table::{relation, Relation};
+use crate::whitespace::whitespace1;
+use crate::{Dialect, NomSqlResult};
+
+/// The format a [`CopyCacheStatement`] returns the rows of a cache in
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum CopyCacheFormat {
+    /// Comma-separated values, returned as a single text column with one row per record, starting
+    /// with a header record containing the names of the columns
+    Csv,
+    /// An Apache Parquet file, returned as a single binary column which the file is split across,
+    /// with one row per row group (plus one row for the file footer)
+    Parquet,
+}
+
+impl Display for CopyCacheFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CopyCacheFormat::Csv => write!(f, "CSV"),
+            CopyCacheFormat::Parquet => write!(f, "PARQUET"),
+        }
+    }
+}
+
+/// `COPY CACHE <name> TO STDOUT [[WITH] FORMAT { CSV | PARQUET }]` statement, which exports all
+/// the rows currently stored in the reader of a cache to the client, either as the rows themselves
+/// or encoded in the given format.
+///
+/// This is a non-standard ReadySet-specific extension to SQL
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct CopyCacheStatement {
+    pub name: Relation,
+    pub format: Option<CopyCacheFormat>,
+}
+
+impl Display for CopyCacheStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "COPY CACHE {} TO STDOUT", self.name)?;
+        if let Some(format) = self.format {
+            write!(f, " FORMAT {}", format)?;
+        }
+        Ok(())
+    }
+}
+
+fn copy_cache_format(i: LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], CopyCacheFormat> {
+    let (i, _) = opt(terminated(tag_no_case("with"), whitespace1))(i)?;
+    let (i, _) = tag_no_case("format")(i)?;
+    let (i, _) = whitespace1(i)?;
+    alt((
+        value(CopyCacheFormat::Csv, tag_no_case("csv")),
+        value(CopyCacheFormat::Parquet, tag_no_case("parquet")),
+    ))(i)
+}
+
+pub(crate) fn copy_cache(
+    dialect: Dialect,
+) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], CopyCacheStatement> {
+    move |i| {
+        let (i, _) = tag_no_case("copy")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("cache")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, name) = relation(dialect)(i)?;
+        let (i, _) = tuple((whitespace1, tag_no_case("to"), whitespace1))(i)?;
+        let (i, _) = tag_no_case("stdout")(i)?;
+        let (i, format) = opt(preceded(whitespace1, copy_cache_format))(i)?;
+        let (i, _) = statement_terminator(i)?;
+        Ok((i, CopyCacheStatement { name, format }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(dialect: Dialect, s: &str) -> CopyCacheStatement {
+        copy_cache(dialect)(LocatedSpan::new(s.as_bytes()))
+            .unwrap()
+            .1
+    }
+
+    #[test]
+    fn copy_cache_to_stdout() {
+        let stmt = parse(Dialect::MySQL, "copy cache q1 to stdout;");
+        assert_eq!(
+            stmt,
+            CopyCacheStatement {
+                name: "q1".into(),
+                format: None,
+            }
+        );
+        assert_eq!(stmt.to_string(), "COPY CACHE `q1` TO STDOUT");
+    }
+
+    #[test]
+    fn copy_cache_with_format() {
+        let expected = CopyCacheStatement {
+            name: "q1".into(),
+            format: Some(CopyCacheFormat::Csv),
+        };
+        assert_eq!(
+            parse(
+                Dialect::PostgreSQL,
+                "COPY CACHE q1 TO STDOUT WITH FORMAT csv"
+            ),
+            expected
+        );
+        assert_eq!(
+            parse(Dialect::MySQL, "COPY CACHE q1 TO STDOUT FORMAT CSV"),
+            expected
+        );
+        assert_eq!(expected.to_string(), "COPY CACHE `q1` TO STDOUT FORMAT CSV");
+
+        assert_eq!(
+            parse(Dialect::MySQL, "COPY CACHE q1 TO STDOUT FORMAT PARQUET").format,
+            Some(CopyCacheFormat::Parquet)
+        );
+    }
+
+    #[test]
+    fn copy_cache_to_file_is_unsupported() {
+        assert!(copy_cache(Dialect::MySQL)(LocatedSpan::new(
+            b"COPY CACHE q1 TO '/tmp/q1.csv' FORMAT CSV".as_slice()
+        ))
+        .is_err());
+    }
+}
@@ -187,7 +187,90 @@ pub enum CacheInner {
     Id(SqlIdentifier),
 }
 
-/// `CREATE CACHE [ALWAYS] [<name>] FROM ...`
+/// What to do when a read from a cache exceeds one of the cache's [`CacheResultLimits`]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum ResultLimitPolicy {
+    /// Return as much of the result as fits within the limits, flagged as truncated
+    #[default]
+    Truncate,
+    /// Return an error instead of the result
+    Error,
+}
+
+/// Limits on the size of the results read from a cache, specified in a [`CreateCacheStatement`]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct CacheResultLimits {
+    /// The maximum number of rows returned for any single key (specified with `MAX ROWS PER KEY
+    /// <n>`)
+    pub max_rows_per_key: Option<u64>,
+    /// The maximum total size, in bytes, of the rows returned for a single read (specified with
+    /// `MAX RESULT BYTES <n>`)
+    pub max_result_bytes: Option<u64>,
+    /// What to do when a read exceeds either limit (specified with `ON LIMIT {TRUNCATE | ERROR}`)
+    pub on_exceeded: ResultLimitPolicy,
+}
+
+impl CacheResultLimits {
+    /// Returns true if any limit is set
+    pub fn is_limited(&self) -> bool {
+        self.max_rows_per_key.is_some() || self.max_result_bytes.is_some()
+    }
+}
+
+impl Display for CacheResultLimits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(max_rows_per_key) = self.max_rows_per_key {
+            write!(f, "MAX ROWS PER KEY {} ", max_rows_per_key)?;
+        }
+        if let Some(max_result_bytes) = self.max_result_bytes {
+            write!(f, "MAX RESULT BYTES {} ", max_result_bytes)?;
+        }
+        if self.on_exceeded == ResultLimitPolicy::Error {
+            write!(f, "ON LIMIT ERROR ")?;
+        }
+        Ok(())
+    }
+}
+
+/// What to do with a read from a cache while replication is further behind the upstream database
+/// than the cache's [`CacheFreshness::max_replication_lag`]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum ReplicationLagPolicy {
+    /// Proxy the read to the upstream database
+    #[default]
+    Proxy,
+    /// Return an error instead of the result
+    Error,
+}
+
+/// A bound on how stale the results read from a cache may be, specified in a
+/// [`CreateCacheStatement`]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct CacheFreshness {
+    /// The maximum replication lag, in seconds, at which reads are still served from the cache
+    /// (specified with `MAX REPLICATION LAG <seconds>`)
+    pub max_replication_lag: Option<u64>,
+    /// What to do with reads while replication lag exceeds `max_replication_lag` (specified with
+    /// `ON LAG {PROXY | ERROR}`)
+    pub on_exceeded: ReplicationLagPolicy,
+}
+
+impl Display for CacheFreshness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(max_replication_lag) = self.max_replication_lag {
+            write!(f, "MAX REPLICATION LAG {} ", max_replication_lag)?;
+        }
+        if self.on_exceeded == ReplicationLagPolicy::Error {
+            write!(f, "ON LAG ERROR ")?;
+        }
+        Ok(())
+    }
+}
+
+/// `CREATE CACHE [CONCURRENTLY] [ALWAYS] [NO FILTER PUSHDOWN] [APPROXIMATE AGGREGATES]
+/// [LAZY JOINS] [PLACE READERS ON '<constraint>'[, ...]] [MAX STALENESS <seconds>]
+/// [BUCKET RETENTION <seconds>] [MAX REPLICATION LAG <seconds>] [ON LAG {PROXY | ERROR}]
+/// [MAX ROWS PER KEY <n>] [MAX RESULT BYTES <n>] [ON LIMIT {TRUNCATE | ERROR}] [<name>] FROM ...`
 ///
 /// This is a non-standard ReadySet specific extension to SQL
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
@@ -195,14 +278,90 @@ pub struct CreateCacheStatement {
     pub name: Option<Relation>,
     pub inner: CacheInner,
     pub always: bool,
+    /// If false, filters in the query will not be pushed below joins when planning the cache
+    /// (specified with `NO FILTER PUSHDOWN`)
+    #[serde(default = "default_filter_pushdown")]
+    pub filter_pushdown: bool,
+    /// If true, `COUNT(DISTINCT)` and `PERCENTILE_CONT` aggregates in the query are estimated from
+    /// sketches of their inputs rather than computed exactly (specified with `APPROXIMATE
+    /// AGGREGATES`)
+    #[serde(default)]
+    pub approximate_aggregates: bool,
+    /// If true, joins against small, unfiltered dimension tables are performed when the cache is
+    /// read from rather than being materialized in the cache, where possible (specified with `LAZY
+    /// JOINS`)
+    #[serde(default)]
+    pub lazy_joins: bool,
+    /// Constraints on the workers the readers of the cache may be placed on, in the format parsed
+    /// by `WorkerConstraint` (specified with `PLACE READERS ON '<constraint>'[, ...]`)
+    #[serde(default)]
+    pub reader_placement: Vec<String>,
+    /// If true, the statement returns immediately and the cache's initial state is backfilled in
+    /// the background (specified with `CONCURRENTLY`)
+    #[serde(default)]
+    pub concurrently: bool,
+    /// If set, reads of keys which have been evicted from the cache are served the values those
+    /// keys had when they were evicted, as long as that was at most this many seconds ago, while
+    /// the keys are replayed in the background (specified with `MAX STALENESS <seconds>`)
+    #[serde(default)]
+    pub max_staleness: Option<u64>,
+    /// If set, keys of the cache which contain a timestamp (such as the time bucket of a `GROUP BY
+    /// date_trunc('hour', ts)` rollup) older than this many seconds are periodically evicted
+    /// (specified with `BUCKET RETENTION <seconds>`)
+    #[serde(default)]
+    pub bucket_retention: Option<u64>,
+    /// A bound on how far replication may lag behind the upstream database while reads are served
+    /// from the cache
+    #[serde(default)]
+    pub freshness: CacheFreshness,
+    /// Limits on the size of the results read from the cache
+    #[serde(default)]
+    pub result_limits: CacheResultLimits,
+}
+
+fn default_filter_pushdown() -> bool {
+    true
 }
 
 impl Display for CreateCacheStatement {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "CREATE CACHE ")?;
+        if self.concurrently {
+            write!(f, "CONCURRENTLY ")?;
+        }
         if self.always {
             write!(f, "ALWAYS ")?;
         }
+        if !self.filter_pushdown {
+            write!(f, "NO FILTER PUSHDOWN ")?;
+        }
+        if self.approximate_aggregates {
+            write!(f, "APPROXIMATE AGGREGATES ")?;
+        }
+        if self.lazy_joins {
+            write!(f, "LAZY JOINS ")?;
+        }
+        if !self.reader_placement.is_empty() {
+            write!(f, "PLACE READERS ON ")?;
+            for (i, constraint) in self.reader_placement.iter().enumerate() {
+                if i != 0 {
+                    write!(f, ", ")?;
+                }
+                write!(
+                    f,
+                    "'{}'",
+                    constraint.replace('\'', "''").replace('\\', "\\\\")
+                )?;
+            }
+            write!(f, " ")?;
+        }
+        if let Some(max_staleness) = self.max_staleness {
+            write!(f, "MAX STALENESS {} ", max_staleness)?;
+        }
+        if let Some(bucket_retention) = self.bucket_retention {
+            write!(f, "BUCKET RETENTION {} ", bucket_retention)?;
+        }
+        write!(f, "{}{}", self.freshness, self.result_limits)?;
         if let Some(name) = &self.name {
             write!(f, "{} ", name)?;
         }
@@ -741,6 +900,108 @@ pub fn cached_query_inner(
     }
 }
 
+fn unsigned_number(i: LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], u64> {
+    map_res(
+        map_res(digit1, |i: LocatedSpan<&[u8]>| str::from_utf8(&i)),
+        u64::from_str,
+    )(i)
+}
+
+fn cache_result_limits(i: LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], CacheResultLimits> {
+    let (i, max_rows_per_key) = opt(terminated(
+        preceded(
+            tuple((
+                tag_no_case("max"),
+                whitespace1,
+                tag_no_case("rows"),
+                whitespace1,
+                tag_no_case("per"),
+                whitespace1,
+                tag_no_case("key"),
+                whitespace1,
+            )),
+            unsigned_number,
+        ),
+        whitespace1,
+    ))(i)?;
+    let (i, max_result_bytes) = opt(terminated(
+        preceded(
+            tuple((
+                tag_no_case("max"),
+                whitespace1,
+                tag_no_case("result"),
+                whitespace1,
+                tag_no_case("bytes"),
+                whitespace1,
+            )),
+            unsigned_number,
+        ),
+        whitespace1,
+    ))(i)?;
+    let (i, on_exceeded) = opt(terminated(
+        preceded(
+            tuple((
+                tag_no_case("on"),
+                whitespace1,
+                tag_no_case("limit"),
+                whitespace1,
+            )),
+            alt((
+                map(tag_no_case("truncate"), |_| ResultLimitPolicy::Truncate),
+                map(tag_no_case("error"), |_| ResultLimitPolicy::Error),
+            )),
+        ),
+        whitespace1,
+    ))(i)?;
+    Ok((
+        i,
+        CacheResultLimits {
+            max_rows_per_key,
+            max_result_bytes,
+            on_exceeded: on_exceeded.unwrap_or_default(),
+        },
+    ))
+}
+
+fn cache_freshness(i: LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], CacheFreshness> {
+    let (i, max_replication_lag) = opt(terminated(
+        preceded(
+            tuple((
+                tag_no_case("max"),
+                whitespace1,
+                tag_no_case("replication"),
+                whitespace1,
+                tag_no_case("lag"),
+                whitespace1,
+            )),
+            unsigned_number,
+        ),
+        whitespace1,
+    ))(i)?;
+    let (i, on_exceeded) = opt(terminated(
+        preceded(
+            tuple((
+                tag_no_case("on"),
+                whitespace1,
+                tag_no_case("lag"),
+                whitespace1,
+            )),
+            alt((
+                map(tag_no_case("proxy"), |_| ReplicationLagPolicy::Proxy),
+                map(tag_no_case("error"), |_| ReplicationLagPolicy::Error),
+            )),
+        ),
+        whitespace1,
+    ))(i)?;
+    Ok((
+        i,
+        CacheFreshness {
+            max_replication_lag,
+            on_exceeded: on_exceeded.unwrap_or_default(),
+        },
+    ))
+}
+
 /// Parse a [`CreateCacheStatement`]
 pub fn create_cached_query(
     dialect: Dialect,
@@ -750,7 +1011,70 @@ pub fn create_cached_query(
         let (i, _) = whitespace1(i)?;
         let (i, _) = tag_no_case("cache")(i)?;
         let (i, _) = whitespace1(i)?;
+        let (i, concurrently) = opt(terminated(tag_no_case("concurrently"), whitespace1))(i)?;
         let (i, always) = opt(terminated(tag_no_case("always"), whitespace1))(i)?;
+        let (i, no_filter_pushdown) = opt(terminated(
+            tuple((
+                tag_no_case("no"),
+                whitespace1,
+                tag_no_case("filter"),
+                whitespace1,
+                tag_no_case("pushdown"),
+            )),
+            whitespace1,
+        ))(i)?;
+        let (i, approximate_aggregates) = opt(terminated(
+            tuple((
+                tag_no_case("approximate"),
+                whitespace1,
+                tag_no_case("aggregates"),
+            )),
+            whitespace1,
+        ))(i)?;
+        let (i, lazy_joins) = opt(terminated(
+            tuple((tag_no_case("lazy"), whitespace1, tag_no_case("joins"))),
+            whitespace1,
+        ))(i)?;
+        let (i, reader_placement) = opt(terminated(
+            preceded(
+                tuple((
+                    tag_no_case("place"),
+                    whitespace1,
+                    tag_no_case("readers"),
+                    whitespace1,
+                    tag_no_case("on"),
+                    whitespace1,
+                )),
+                separated_list1(ws_sep_comma, dialect.utf8_string_literal()),
+            ),
+            whitespace1,
+        ))(i)?;
+        let (i, max_staleness) = opt(terminated(
+            preceded(
+                tuple((
+                    tag_no_case("max"),
+                    whitespace1,
+                    tag_no_case("staleness"),
+                    whitespace1,
+                )),
+                unsigned_number,
+            ),
+            whitespace1,
+        ))(i)?;
+        let (i, bucket_retention) = opt(terminated(
+            preceded(
+                tuple((
+                    tag_no_case("bucket"),
+                    whitespace1,
+                    tag_no_case("retention"),
+                    whitespace1,
+                )),
+                unsigned_number,
+            ),
+            whitespace1,
+        ))(i)?;
+        let (i, freshness) = cache_freshness(i)?;
+        let (i, result_limits) = cache_result_limits(i)?;
         let (i, name) = opt(terminated(relation(dialect), whitespace1))(i)?;
         let (i, _) = tag_no_case("from")(i)?;
         let (i, _) = whitespace1(i)?;
@@ -761,6 +1085,15 @@ pub fn create_cached_query(
                 name,
                 inner,
                 always: always.is_some(),
+                filter_pushdown: no_filter_pushdown.is_none(),
+                approximate_aggregates: approximate_aggregates.is_some(),
+                lazy_joins: lazy_joins.is_some(),
+                reader_placement: reader_placement.unwrap_or_default(),
+                concurrently: concurrently.is_some(),
+                max_staleness,
+                bucket_retention,
+                freshness,
+                result_limits,
             },
         ))
     }
@@ -1542,6 +1875,244 @@ mod tests {
                 vec![TableExpr::from(Relation::from("users"))]
             );
             assert!(res.always);
+            assert!(res.filter_pushdown);
+        }
+
+        #[test]
+        fn create_cached_query_no_filter_pushdown() {
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE ALWAYS NO FILTER PUSHDOWN foo FROM SELECT id FROM users WHERE name = ?"
+            );
+            assert_eq!(res.name, Some("foo".into()));
+            assert!(res.always);
+            assert!(!res.filter_pushdown);
+            assert_eq!(
+                res.to_string(),
+                "CREATE CACHE ALWAYS NO FILTER PUSHDOWN `foo` FROM SELECT `id` FROM `users` WHERE \
+                 (`name` = ?)"
+            );
+        }
+
+        #[test]
+        fn create_cached_query_approximate_aggregates() {
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE APPROXIMATE AGGREGATES foo FROM SELECT count(distinct id) FROM users"
+            );
+            assert_eq!(res.name, Some("foo".into()));
+            assert!(res.approximate_aggregates);
+            assert_eq!(
+                res.to_string(),
+                "CREATE CACHE APPROXIMATE AGGREGATES `foo` FROM SELECT count(distinct `id`) FROM \
+                 `users`"
+            );
+
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE approximate FROM SELECT id FROM users WHERE name = ?"
+            );
+            assert_eq!(res.name, Some("approximate".into()));
+            assert!(!res.approximate_aggregates);
+        }
+
+        #[test]
+        fn create_cached_query_lazy_joins() {
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE APPROXIMATE AGGREGATES LAZY JOINS foo FROM SELECT f.id, d.name FROM \
+                  f JOIN d ON f.d_id = d.id WHERE f.id = ?"
+            );
+            assert_eq!(res.name, Some("foo".into()));
+            assert!(res.approximate_aggregates);
+            assert!(res.lazy_joins);
+            assert_eq!(
+                res.to_string(),
+                "CREATE CACHE APPROXIMATE AGGREGATES LAZY JOINS `foo` FROM SELECT `f`.`id`, \
+                 `d`.`name` FROM `f` JOIN `d` ON (`f`.`d_id` = `d`.`id`) WHERE (`f`.`id` = ?)"
+            );
+
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE lazy FROM SELECT id FROM users WHERE name = ?"
+            );
+            assert_eq!(res.name, Some("lazy".into()));
+            assert!(!res.lazy_joins);
+        }
+
+        #[test]
+        fn create_cached_query_reader_placement() {
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE PLACE READERS ON 'zone=us-east-1a', 'label:tier=reader' foo FROM \
+                  SELECT id FROM users WHERE name = ?"
+            );
+            assert_eq!(res.name, Some("foo".into()));
+            assert_eq!(
+                res.reader_placement,
+                vec!["zone=us-east-1a".to_owned(), "label:tier=reader".to_owned()]
+            );
+            assert_eq!(
+                res.to_string(),
+                "CREATE CACHE PLACE READERS ON 'zone=us-east-1a', 'label:tier=reader' `foo` FROM \
+                 SELECT `id` FROM `users` WHERE (`name` = ?)"
+            );
+
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE place FROM SELECT id FROM users WHERE name = ?"
+            );
+            assert_eq!(res.name, Some("place".into()));
+            assert!(res.reader_placement.is_empty());
+        }
+
+        #[test]
+        fn create_cached_query_concurrently() {
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE CONCURRENTLY ALWAYS foo FROM SELECT id FROM users WHERE name = ?"
+            );
+            assert_eq!(res.name, Some("foo".into()));
+            assert!(res.concurrently);
+            assert!(res.always);
+            assert_eq!(
+                res.to_string(),
+                "CREATE CACHE CONCURRENTLY ALWAYS `foo` FROM SELECT `id` FROM `users` WHERE \
+                 (`name` = ?)"
+            );
+
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE foo FROM SELECT id FROM users WHERE name = ?"
+            );
+            assert!(!res.concurrently);
+        }
+
+        #[test]
+        fn create_cached_query_max_staleness() {
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE ALWAYS MAX STALENESS 30 foo FROM SELECT id FROM users WHERE name = ?"
+            );
+            assert_eq!(res.name, Some("foo".into()));
+            assert_eq!(res.max_staleness, Some(30));
+            assert_eq!(
+                res.to_string(),
+                "CREATE CACHE ALWAYS MAX STALENESS 30 `foo` FROM SELECT `id` FROM `users` WHERE \
+                 (`name` = ?)"
+            );
+
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE max FROM SELECT id FROM users WHERE name = ?"
+            );
+            assert_eq!(res.name, Some("max".into()));
+            assert_eq!(res.max_staleness, None);
+        }
+
+        #[test]
+        fn create_cached_query_bucket_retention() {
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE BUCKET RETENTION 86400 foo FROM SELECT id FROM users WHERE name = ?"
+            );
+            assert_eq!(res.name, Some("foo".into()));
+            assert_eq!(res.bucket_retention, Some(86400));
+            assert_eq!(
+                res.to_string(),
+                "CREATE CACHE BUCKET RETENTION 86400 `foo` FROM SELECT `id` FROM `users` WHERE \
+                 (`name` = ?)"
+            );
+
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE bucket FROM SELECT id FROM users WHERE name = ?"
+            );
+            assert_eq!(res.name, Some("bucket".into()));
+            assert_eq!(res.bucket_retention, None);
+        }
+
+        #[test]
+        fn create_cached_query_max_replication_lag() {
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE MAX REPLICATION LAG 5 ON LAG ERROR foo FROM SELECT id FROM users \
+                  WHERE name = ?"
+            );
+            assert_eq!(
+                res.freshness,
+                CacheFreshness {
+                    max_replication_lag: Some(5),
+                    on_exceeded: ReplicationLagPolicy::Error,
+                }
+            );
+            assert_eq!(
+                res.to_string(),
+                "CREATE CACHE MAX REPLICATION LAG 5 ON LAG ERROR `foo` FROM SELECT `id` FROM \
+                 `users` WHERE (`name` = ?)"
+            );
+
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE MAX REPLICATION LAG 5 MAX ROWS PER KEY 10 FROM SELECT id FROM users \
+                  WHERE name = ?"
+            );
+            assert_eq!(res.freshness.max_replication_lag, Some(5));
+            assert_eq!(res.freshness.on_exceeded, ReplicationLagPolicy::Proxy);
+            assert_eq!(res.result_limits.max_rows_per_key, Some(10));
+        }
+
+        #[test]
+        fn create_cached_query_result_limits() {
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE MAX ROWS PER KEY 100 MAX RESULT BYTES 4096 ON LIMIT ERROR foo \
+                  FROM SELECT id FROM users WHERE name = ?"
+            );
+            assert_eq!(
+                res.result_limits,
+                CacheResultLimits {
+                    max_rows_per_key: Some(100),
+                    max_result_bytes: Some(4096),
+                    on_exceeded: ResultLimitPolicy::Error,
+                }
+            );
+            assert_eq!(
+                res.to_string(),
+                "CREATE CACHE MAX ROWS PER KEY 100 MAX RESULT BYTES 4096 ON LIMIT ERROR `foo` FROM \
+                 SELECT `id` FROM `users` WHERE (`name` = ?)"
+            );
+
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE MAX ROWS PER KEY 10 FROM SELECT id FROM users WHERE name = ?"
+            );
+            assert_eq!(res.result_limits.max_rows_per_key, Some(10));
+            assert_eq!(res.result_limits.max_result_bytes, None);
+            assert_eq!(res.result_limits.on_exceeded, ResultLimitPolicy::Truncate);
+        }
+
+        #[test]
+        fn create_cached_query_deserialize_without_options() {
+            let res = test_parse!(
+                create_cached_query(Dialect::MySQL),
+                b"CREATE CACHE foo FROM SELECT id FROM users WHERE name = ?"
+            );
+            let mut value = serde_json::to_value(&res).unwrap();
+            let fields = value.as_object_mut().unwrap();
+            for field in [
+                "filter_pushdown",
+                "concurrently",
+                "bucket_retention",
+                "approximate_aggregates",
+                "freshness",
+            ] {
+                assert!(fields.remove(field).is_some(), "missing field {field}");
+            }
+            assert_eq!(
+                serde_json::from_value::<CreateCacheStatement>(value).unwrap(),
+                res
+            );
         }
 
         #[test]
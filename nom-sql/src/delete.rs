@@ -1,26 +1,32 @@
 use std::{fmt, str};
 
+use itertools::Itertools;
 use nom::bytes::complete::tag_no_case;
 use nom::combinator::opt;
-use nom::sequence::{delimited, tuple};
+use nom::sequence::{delimited, preceded, tuple};
 use nom_locate::LocatedSpan;
 use serde::{Deserialize, Serialize};
 
 use crate::common::statement_terminator;
 use crate::select::where_clause;
-use crate::table::{relation, Relation};
+use crate::table::{relation, table_expr_list, Relation};
 use crate::whitespace::whitespace1;
-use crate::{Dialect, Expr, NomSqlResult};
+use crate::{Dialect, Expr, NomSqlResult, TableExpr};
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct DeleteStatement {
     pub table: Relation,
+    /// Other tables joined against in the `WHERE` clause, with `DELETE ... USING`
+    pub using: Vec<TableExpr>,
     pub where_clause: Option<Expr>,
 }
 
 impl fmt::Display for DeleteStatement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "DELETE FROM `{}`", self.table.name)?;
+        if !self.using.is_empty() {
+            write!(f, " USING {}", self.using.iter().join(", "))?;
+        }
         if let Some(ref where_clause) = self.where_clause {
             write!(f, " WHERE ")?;
             write!(f, "{}", where_clause)?;
@@ -33,10 +39,14 @@ pub fn deletion(
     dialect: Dialect,
 ) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], DeleteStatement> {
     move |i| {
-        let (remaining_input, (_, _, table, where_clause, _)) = tuple((
+        let (remaining_input, (_, _, table, using, where_clause, _)) = tuple((
             tag_no_case("delete"),
             delimited(whitespace1, tag_no_case("from"), whitespace1),
             relation(dialect),
+            opt(preceded(
+                delimited(whitespace1, tag_no_case("using"), whitespace1),
+                table_expr_list(dialect),
+            )),
             opt(where_clause(dialect)),
             statement_terminator,
         ))(i)?;
@@ -45,6 +55,7 @@ pub fn deletion(
             remaining_input,
             DeleteStatement {
                 table,
+                using: using.unwrap_or_default(),
                 where_clause,
             },
         ))
@@ -56,7 +67,7 @@ mod tests {
     use super::*;
     use crate::column::Column;
     use crate::table::Relation;
-    use crate::{BinaryOperator, Literal};
+    use crate::{BinaryOperator, Literal, TableExprInner};
 
     #[test]
     fn simple_delete() {
@@ -66,6 +77,7 @@ mod tests {
             res.unwrap().1,
             DeleteStatement {
                 table: Relation::from("users"),
+                using: vec![],
                 where_clause: None,
             }
         );
@@ -82,6 +94,7 @@ mod tests {
                     schema: Some("db1".into()),
                     name: "users".into(),
                 },
+                using: vec![],
                 where_clause: None,
             }
         );
@@ -101,6 +114,7 @@ mod tests {
             res.unwrap().1,
             DeleteStatement {
                 table: Relation::from("users"),
+                using: vec![],
                 where_clause: expected_where_cond,
             }
         );
@@ -113,4 +127,33 @@ mod tests {
         let res = deletion(Dialect::MySQL)(LocatedSpan::new(qstring.as_bytes()));
         assert_eq!(res.unwrap().1.to_string(), expected);
     }
+
+    #[test]
+    fn delete_using() {
+        let qstring = "DELETE FROM t USING u v WHERE t.id = v.t_id;";
+        let res = deletion(Dialect::PostgreSQL)(LocatedSpan::new(qstring.as_bytes()));
+        assert_eq!(
+            res.unwrap().1,
+            DeleteStatement {
+                table: Relation::from("t"),
+                using: vec![TableExpr {
+                    inner: TableExprInner::Table(Relation::from("u")),
+                    alias: Some("v".into()),
+                }],
+                where_clause: Some(Expr::BinaryOp {
+                    lhs: Box::new(Expr::Column(Column::from("t.id"))),
+                    op: BinaryOperator::Equal,
+                    rhs: Box::new(Expr::Column(Column::from("v.t_id"))),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn format_delete_using() {
+        let qstring = "DELETE FROM t USING u, w WHERE t.id = u.t_id";
+        let expected = "DELETE FROM `t` USING `u`, `w` WHERE (`t`.`id` = `u`.`t_id`)";
+        let res = deletion(Dialect::PostgreSQL)(LocatedSpan::new(qstring.as_bytes()));
+        assert_eq!(res.unwrap().1.to_string(), expected);
+    }
 }
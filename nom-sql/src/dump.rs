@@ -0,0 +1,44 @@
+use std::fmt::{self, Display};
+
+use nom::bytes::complete::tag_no_case;
+use nom_locate::LocatedSpan;
+use serde::{Deserialize, Serialize};
+
+use crate::common::statement_terminator;
+use crate::whitespace::whitespace1;
+use crate::NomSqlResult;
+
+/// `DUMP CACHES` statement, which returns the `CREATE CACHE` statements for all the caches
+/// installed in ReadySet, so they can be replayed against another deployment.
+///
+/// This is a non-standard ReadySet-specific extension to SQL
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct DumpCachesStatement {}
+
+impl Display for DumpCachesStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DUMP CACHES")
+    }
+}
+
+pub(crate) fn dump_caches(i: LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], DumpCachesStatement> {
+    let (i, _) = tag_no_case("dump")(i)?;
+    let (i, _) = whitespace1(i)?;
+    let (i, _) = tag_no_case("caches")(i)?;
+    let (i, _) = statement_terminator(i)?;
+    Ok((i, DumpCachesStatement {}))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dump_caches_statement() {
+        assert_eq!(
+            dump_caches(LocatedSpan::new(b"DuMP   caches;")).unwrap().1,
+            DumpCachesStatement {}
+        );
+        assert_eq!(DumpCachesStatement {}.to_string(), "DUMP CACHES");
+    }
+}
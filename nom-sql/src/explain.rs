@@ -3,13 +3,14 @@ use std::fmt::{self, Display};
 use nom::branch::alt;
 use nom::bytes::complete::tag_no_case;
 use nom::combinator::{map, opt};
-use nom::sequence::{terminated, tuple};
+use nom::sequence::{preceded, terminated, tuple};
 use nom_locate::LocatedSpan;
 use serde::{Deserialize, Serialize};
 
 use crate::common::statement_terminator;
+use crate::table::{relation, Relation};
 use crate::whitespace::whitespace1;
-use crate::NomSqlResult;
+use crate::{Dialect, NomSqlResult};
 
 /// EXPLAIN statements
 ///
@@ -20,6 +21,12 @@ pub enum ExplainStatement {
     Graphviz { simplified: bool },
     /// Provides metadata about the last statement that was executed.
     LastStatement,
+    /// Print the canonical plan for a cache: a description of its dataflow nodes which doesn't
+    /// depend on how they're numbered, suitable for diffing
+    Plan { cache: Relation },
+    /// Print the MIR plan for a cache, serialized as JSON, in the form accepted by
+    /// `ALTER READYSET SET MIR PLAN`
+    MirPlan { cache: Relation },
 }
 
 impl Display for ExplainStatement {
@@ -33,6 +40,8 @@ impl Display for ExplainStatement {
                 write!(f, "GRAPHVIZ;")
             }
             ExplainStatement::LastStatement => write!(f, "LAST STATEMENT;"),
+            ExplainStatement::Plan { cache } => write!(f, "PLAN FOR {};", cache),
+            ExplainStatement::MirPlan { cache } => write!(f, "MIR PLAN FOR {};", cache),
         }
     }
 }
@@ -48,18 +57,41 @@ fn explain_graphviz(i: LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], ExplainStateme
     ))
 }
 
-pub(crate) fn explain_statement(i: LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], ExplainStatement> {
-    let (i, _) = tag_no_case("explain")(i)?;
-    let (i, _) = whitespace1(i)?;
-    let (i, stmt) = alt((
-        explain_graphviz,
-        map(
-            tuple((tag_no_case("last"), whitespace1, tag_no_case("statement"))),
-            |_| ExplainStatement::LastStatement,
-        ),
-    ))(i)?;
-    let (i, _) = statement_terminator(i)?;
-    Ok((i, stmt))
+/// Parse `PLAN FOR <cache>`, returning the name of the cache
+fn plan_for(dialect: Dialect) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], Relation> {
+    move |i| {
+        let (i, _) = tag_no_case("plan")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("for")(i)?;
+        let (i, _) = whitespace1(i)?;
+        relation(dialect)(i)
+    }
+}
+
+pub(crate) fn explain_statement(
+    dialect: Dialect,
+) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], ExplainStatement> {
+    move |i| {
+        let (i, _) = tag_no_case("explain")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, stmt) = alt((
+            explain_graphviz,
+            map(plan_for(dialect), |cache| ExplainStatement::Plan { cache }),
+            map(
+                preceded(
+                    terminated(tag_no_case("mir"), whitespace1),
+                    plan_for(dialect),
+                ),
+                |cache| ExplainStatement::MirPlan { cache },
+            ),
+            map(
+                tuple((tag_no_case("last"), whitespace1, tag_no_case("statement"))),
+                |_| ExplainStatement::LastStatement,
+            ),
+        ))(i)?;
+        let (i, _) = statement_terminator(i)?;
+        Ok((i, stmt))
+    }
 }
 
 #[cfg(test)]
@@ -69,7 +101,7 @@ mod tests {
     #[test]
     fn explain_graphviz() {
         assert_eq!(
-            explain_statement(LocatedSpan::new(b"explain graphviz;"))
+            explain_statement(Dialect::MySQL)(LocatedSpan::new(b"explain graphviz;"))
                 .unwrap()
                 .1,
             ExplainStatement::Graphviz { simplified: false }
@@ -79,10 +111,39 @@ mod tests {
     #[test]
     fn explain_last_statement() {
         assert_eq!(
-            explain_statement(LocatedSpan::new(b"explain last statement;"))
+            explain_statement(Dialect::MySQL)(LocatedSpan::new(b"explain last statement;"))
                 .unwrap()
                 .1,
             ExplainStatement::LastStatement
         );
     }
+
+    #[test]
+    fn explain_plan() {
+        let stmt = explain_statement(Dialect::MySQL)(LocatedSpan::new(b"explain plan for `q1`;"))
+            .unwrap()
+            .1;
+        assert_eq!(
+            stmt,
+            ExplainStatement::Plan {
+                cache: Relation::from("q1")
+            }
+        );
+        assert_eq!(stmt.to_string(), "EXPLAIN PLAN FOR `q1`;");
+    }
+
+    #[test]
+    fn explain_mir_plan() {
+        let stmt =
+            explain_statement(Dialect::MySQL)(LocatedSpan::new(b"EXPLAIN MIR PLAN FOR `q1`"))
+                .unwrap()
+                .1;
+        assert_eq!(
+            stmt,
+            ExplainStatement::MirPlan {
+                cache: Relation::from("q1")
+            }
+        );
+        assert_eq!(stmt.to_string(), "EXPLAIN MIR PLAN FOR `q1`;");
+    }
 }
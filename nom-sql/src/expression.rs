@@ -48,6 +48,11 @@ pub enum FunctionExpr {
     /// `GROUP_CONCAT` aggregation. The second argument is the separator
     GroupConcat { expr: Box<Expr>, separator: String },
 
+    /// `PERCENTILE_CONT` ordered-set aggregation, with the syntax:
+    ///
+    /// `PERCENTILE_CONT(fraction) WITHIN GROUP (ORDER BY expr)`
+    PercentileCont { fraction: Literal, expr: Box<Expr> },
+
     /// The SQL `SUBSTRING`/`SUBSTR` function.
     ///
     /// The supported syntax is one of:
@@ -80,7 +85,8 @@ impl FunctionExpr {
             | FunctionExpr::Sum { expr: arg, .. }
             | FunctionExpr::Max(arg)
             | FunctionExpr::Min(arg)
-            | FunctionExpr::GroupConcat { expr: arg, .. } => {
+            | FunctionExpr::GroupConcat { expr: arg, .. }
+            | FunctionExpr::PercentileCont { expr: arg, .. } => {
                 concrete_iter!(iter::once(arg.as_ref()))
             }
             FunctionExpr::CountStar => concrete_iter!(iter::empty()),
@@ -118,6 +124,11 @@ impl Display for FunctionExpr {
             FunctionExpr::GroupConcat { expr, separator } => {
                 write!(f, "group_concat({} separator '{}')", expr, separator)
             }
+            FunctionExpr::PercentileCont { fraction, expr } => write!(
+                f,
+                "percentile_cont({}) within group (order by {})",
+                fraction, expr
+            ),
             FunctionExpr::Call { name, arguments } => {
                 write!(f, "{}({})", name, arguments.iter().join(", "))
             }
@@ -171,6 +182,12 @@ pub enum BinaryOperator {
     Less,
     /// `<=`
     LessOrEqual,
+    /// `<=>`
+    ///
+    /// MySQL-specific NULL-safe equality operator. Behaves like [`BinaryOperator::Equal`], except
+    /// that it returns true rather than NULL if both operands are NULL, and false rather than NULL
+    /// if only one operand is NULL.
+    NullSafeEqual,
     /// `IS`
     Is,
     /// `IS NOT`
@@ -190,6 +207,24 @@ pub enum BinaryOperator {
     Multiply,
     /// `/`
     Divide,
+    /// `%` or `MOD`
+    Modulo,
+    /// `DIV`
+    ///
+    /// MySQL-specific integer division operator
+    IntegerDivide,
+    /// `&`
+    BitwiseAnd,
+    /// `|`
+    BitwiseOr,
+    /// `^`
+    ///
+    /// Bitwise XOR in MySQL (PostgreSQL uses `^` for exponentiation, and `#` for bitwise XOR)
+    BitwiseXor,
+    /// `<<`
+    ShiftLeft,
+    /// `>>`
+    ShiftRight,
 
     /// `?`
     ///
@@ -290,6 +325,7 @@ impl Display for BinaryOperator {
             Self::GreaterOrEqual => ">=",
             Self::Less => "<",
             Self::LessOrEqual => "<=",
+            Self::NullSafeEqual => "<=>",
             Self::Is => "IS",
             Self::IsNot => "IS NOT",
             Self::Add => "+",
@@ -297,6 +333,13 @@ impl Display for BinaryOperator {
             Self::HashSubtract => "#-",
             Self::Multiply => "*",
             Self::Divide => "/",
+            Self::Modulo => "%",
+            Self::IntegerDivide => "DIV",
+            Self::BitwiseAnd => "&",
+            Self::BitwiseOr => "|",
+            Self::BitwiseXor => "^",
+            Self::ShiftLeft => "<<",
+            Self::ShiftRight => ">>",
             Self::QuestionMark => "?",
             Self::QuestionMarkPipe => "?|",
             Self::QuestionMarkAnd => "?&",
@@ -656,6 +699,11 @@ fn binary_operator_no_and_or(i: LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], Binar
             Ok((i, BinaryOperator::IsNot))
         },
         map(pair(tag_no_case("is"), whitespace1), |_| BinaryOperator::Is),
+        // Needs to come before the other sigils so that it isn't partially parsed as `<=`
+        map(tag("<=>"), |_| BinaryOperator::NullSafeEqual),
+        // Need to come before the other sigils so that they aren't partially parsed as `<` or `>`
+        map(tag("<<"), |_| BinaryOperator::ShiftLeft),
+        map(tag(">>"), |_| BinaryOperator::ShiftRight),
         // Sigils are separated due to `alt` limit.
         //
         // NOTE: The order here matters or else some of these will be incorrectly partially parsed,
@@ -684,6 +732,17 @@ fn binary_operator_no_and_or(i: LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], Binar
             map(tag("#>"), |_| BinaryOperator::HashArrow1),
         )),
         map(tag("#-"), |_| BinaryOperator::HashSubtract),
+        // Needs to come after `||`, so that it isn't partially parsed as `|`
+        map(char('|'), |_| BinaryOperator::BitwiseOr),
+        map(char('&'), |_| BinaryOperator::BitwiseAnd),
+        map(char('^'), |_| BinaryOperator::BitwiseXor),
+        map(char('%'), |_| BinaryOperator::Modulo),
+        map(terminated(tag_no_case("mod"), whitespace1), |_| {
+            BinaryOperator::Modulo
+        }),
+        map(terminated(tag_no_case("div"), whitespace1), |_| {
+            BinaryOperator::IntegerDivide
+        }),
     ))(i)
 }
 
@@ -895,12 +954,20 @@ where
             Infix(GreaterOrEqual) => Affix::Infix(Precedence(7), Associativity::Right),
             Infix(Less) => Affix::Infix(Precedence(7), Associativity::Right),
             Infix(LessOrEqual) => Affix::Infix(Precedence(7), Associativity::Right),
+            Infix(NullSafeEqual) => Affix::Infix(Precedence(7), Associativity::Right),
             Infix(Is) => Affix::Infix(Precedence(7), Associativity::Right),
             Infix(IsNot) => Affix::Infix(Precedence(7), Associativity::Right),
-            Infix(Add) => Affix::Infix(Precedence(11), Associativity::Right),
-            Infix(Subtract) => Affix::Infix(Precedence(11), Associativity::Right),
-            Infix(Multiply) => Affix::Infix(Precedence(12), Associativity::Right),
-            Infix(Divide) => Affix::Infix(Precedence(12), Associativity::Right),
+            Infix(BitwiseOr) => Affix::Infix(Precedence(9), Associativity::Left),
+            Infix(BitwiseAnd) => Affix::Infix(Precedence(10), Associativity::Left),
+            Infix(ShiftLeft) => Affix::Infix(Precedence(11), Associativity::Left),
+            Infix(ShiftRight) => Affix::Infix(Precedence(11), Associativity::Left),
+            Infix(Add) => Affix::Infix(Precedence(12), Associativity::Right),
+            Infix(Subtract) => Affix::Infix(Precedence(12), Associativity::Right),
+            Infix(Multiply) => Affix::Infix(Precedence(13), Associativity::Right),
+            Infix(Divide) => Affix::Infix(Precedence(13), Associativity::Right),
+            Infix(Modulo) => Affix::Infix(Precedence(13), Associativity::Left),
+            Infix(IntegerDivide) => Affix::Infix(Precedence(13), Associativity::Left),
+            Infix(BitwiseXor) => Affix::Infix(Precedence(14), Associativity::Left),
             Prefix(Not) => Affix::Prefix(Precedence(6)),
             Prefix(Neg) => Affix::Prefix(Precedence(5)),
             Primary(_) => Affix::Nilfix,
@@ -1486,6 +1553,24 @@ mod tests {
             );
         }
 
+        #[test]
+        fn modulo_integer_division_and_bitwise_operators() {
+            for (cond, op) in [
+                ("x % 8", BinaryOperator::Modulo),
+                ("x MOD 8", BinaryOperator::Modulo),
+                ("x DIV 8", BinaryOperator::IntegerDivide),
+                ("x & 8", BinaryOperator::BitwiseAnd),
+                ("x | 8", BinaryOperator::BitwiseOr),
+                ("x ^ 8", BinaryOperator::BitwiseXor),
+                ("x << 8", BinaryOperator::ShiftLeft),
+                ("x >> 8", BinaryOperator::ShiftRight),
+            ] {
+                let res = test_parse!(expression(Dialect::MySQL), cond.as_bytes());
+                assert_eq!(res, x_operator_value(op, 8_u32.into()), "{cond}");
+                assert_eq!(res.to_string(), format!("(`x` {op} 8)"), "{cond}");
+            }
+        }
+
         #[test]
         fn simple_arithmetic_expression_with_parenthesis() {
             let cond = "( x - 2 )";
@@ -2177,6 +2262,22 @@ mod tests {
                  and (dayofweek(`c`.`d`) between 1 and 3))",
                 )
             }
+
+            #[test]
+            fn modulo_and_integer_division() {
+                parses_same(Dialect::MySQL, "`id` % 8 = ?", "((`id` % 8) = ?)");
+                parses_same(Dialect::MySQL, "1 + 10 % 4 % 3", "(1 + ((10 % 4) % 3))");
+                parses_same(Dialect::MySQL, "10 DIV 3 - 1", "((10 DIV 3) - 1)");
+            }
+
+            #[test]
+            fn bitwise_operators() {
+                parses_same(Dialect::MySQL, "1 | 2 & 3", "(1 | (2 & 3))");
+                parses_same(Dialect::MySQL, "1 & 2 << 3", "(1 & (2 << 3))");
+                parses_same(Dialect::MySQL, "1 << 2 + 3", "(1 << (2 + 3))");
+                parses_same(Dialect::MySQL, "1 * 2 ^ 3", "(1 * (2 ^ 3))");
+                parses_same(Dialect::MySQL, "`x` & 1 = 1", "((`x` & 1) = 1)");
+            }
         }
 
         mod conditions {
@@ -2307,6 +2408,22 @@ mod tests {
                     }
                 );
             }
+
+            #[test]
+            fn null_safe_equal() {
+                let res = test_parse!(expression(Dialect::MySQL), b"foo <=> ?");
+                assert_eq!(
+                    res,
+                    Expr::BinaryOp {
+                        lhs: Box::new(Expr::Column(Column::from("foo"))),
+                        op: BinaryOperator::NullSafeEqual,
+                        rhs: Box::new(Expr::Literal(Literal::Placeholder(
+                            ItemPlaceholder::QuestionMark
+                        )))
+                    }
+                );
+                assert_eq!(res.to_string(), "(`foo` <=> ?)");
+            }
         }
     }
 
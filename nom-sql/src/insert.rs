@@ -1,7 +1,8 @@
 use std::{fmt, str};
 
+use nom::branch::alt;
 use nom::bytes::complete::{tag, tag_no_case};
-use nom::combinator::opt;
+use nom::combinator::{map, opt};
 use nom::multi::separated_list1;
 use nom::sequence::{delimited, preceded, terminated, tuple};
 use nom_locate::LocatedSpan;
@@ -26,7 +27,11 @@ pub struct InsertStatement {
 
 impl fmt::Display for InsertStatement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "INSERT INTO `{}`", self.table.name)?;
+        write!(f, "INSERT ")?;
+        if self.ignore {
+            write!(f, "IGNORE ")?;
+        }
+        write!(f, "INTO `{}`", self.table.name)?;
         if let Some(ref fields) = self.fields {
             write!(
                 f,
@@ -53,7 +58,19 @@ impl fmt::Display for InsertStatement {
                 ))
                 .collect::<Vec<_>>()
                 .join(", ")
-        )
+        )?;
+        if let Some(ref on_duplicate) = self.on_duplicate {
+            write!(
+                f,
+                " ON DUPLICATE KEY UPDATE {}",
+                on_duplicate
+                    .iter()
+                    .map(|(col, expr)| format!("`{}` = {}", col.name, expr))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?;
+        }
+        Ok(())
     }
 }
 
@@ -62,7 +79,7 @@ fn fields(dialect: Dialect) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8]
         delimited(
             preceded(tag("("), whitespace0),
             field_list(dialect),
-            delimited(whitespace0, tag(")"), whitespace1),
+            delimited(whitespace0, tag(")"), whitespace0),
         )(i)
     }
 }
@@ -77,6 +94,44 @@ fn data(dialect: Dialect) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8],
     }
 }
 
+/// Parse the `[(<fields>)] VALUES (<row>), ...` form of the columns and rows to insert
+fn values(
+    dialect: Dialect,
+) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], (Option<Vec<Column>>, Vec<Vec<Expr>>)> {
+    move |i| {
+        let (i, fields) = opt(fields(dialect))(i)?;
+        // MySQL accepts VALUE as a synonym for VALUES
+        let (i, _) = alt((tag_no_case("values"), tag_no_case("value")))(i)?;
+        let (i, _) = whitespace0(i)?;
+        let (i, data) = separated_list1(ws_sep_comma, data(dialect))(i)?;
+        Ok((i, (fields, data)))
+    }
+}
+
+/// Parse MySQL's `SET <field> = <value>, ...` form of the row to insert
+fn set_assignments(
+    dialect: Dialect,
+) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], (Option<Vec<Column>>, Vec<Vec<Expr>>)> {
+    move |i| {
+        let (i, _) = tag_no_case("set")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, assignments) = assignment_expr_list(dialect)(i)?;
+        let (fields, row) = assignments.into_iter().unzip();
+        Ok((i, (Some(fields), vec![row])))
+    }
+}
+
+/// Parse `DEFAULT VALUES`, which inserts a single row with the default value for every column. This
+/// is represented the same way as MySQL's equivalent `() VALUES ()`.
+fn default_values(
+    i: LocatedSpan<&[u8]>,
+) -> NomSqlResult<&[u8], (Option<Vec<Column>>, Vec<Vec<Expr>>)> {
+    map(
+        tuple((tag_no_case("default"), whitespace1, tag_no_case("values"))),
+        |_| (Some(vec![]), vec![vec![]]),
+    )(i)
+}
+
 fn on_duplicate(
     dialect: Dialect,
 ) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], Vec<(Column, Expr)>> {
@@ -92,29 +147,24 @@ fn on_duplicate(
 }
 
 // Parse rule for a SQL insert query.
-// TODO(malte): support REPLACE, nested selection, DEFAULT VALUES
+// TODO(malte): support REPLACE, nested selection
 pub fn insertion(
     dialect: Dialect,
 ) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], InsertStatement> {
     move |i| {
-        let (
-            remaining_input,
-            (_, ignore_res, _, _, _, table, _, fields, _, _, data, on_duplicate, _),
-        ) = tuple((
-            tag_no_case("insert"),
-            opt(preceded(whitespace1, tag_no_case("ignore"))),
-            whitespace1,
-            tag_no_case("into"),
-            whitespace1,
-            relation(dialect),
-            whitespace0,
-            opt(fields(dialect)),
-            tag_no_case("values"),
-            whitespace0,
-            separated_list1(ws_sep_comma, data(dialect)),
-            opt(on_duplicate(dialect)),
-            statement_terminator,
-        ))(i)?;
+        let (remaining_input, (_, ignore_res, _, _, _, table, _, (fields, data), on_duplicate, _)) =
+            tuple((
+                tag_no_case("insert"),
+                opt(preceded(whitespace1, tag_no_case("ignore"))),
+                whitespace1,
+                tag_no_case("into"),
+                whitespace1,
+                relation(dialect),
+                whitespace0,
+                alt((values(dialect), set_assignments(dialect), default_values)),
+                opt(on_duplicate(dialect)),
+                statement_terminator,
+            ))(i)?;
         let ignore = ignore_res.is_some();
 
         Ok((
@@ -301,9 +351,11 @@ mod tests {
             let qstring = "INSERT INTO keystores (`key`, `value`) VALUES ($1, :2) \
                        ON DUPLICATE KEY UPDATE `value` = `value` + 1";
 
-            let res = insertion(Dialect::MySQL)(LocatedSpan::new(qstring.as_bytes()));
+            let res = insertion(Dialect::MySQL)(LocatedSpan::new(qstring.as_bytes()))
+                .unwrap()
+                .1;
             assert_eq!(
-                res.unwrap().1,
+                res,
                 InsertStatement {
                     table: Relation::from("keystores"),
                     fields: Some(vec![Column::from("key"), Column::from("value")]),
@@ -322,6 +374,24 @@ mod tests {
                     ignore: false,
                 }
             );
+            let stringified = res.to_string();
+            assert_eq!(
+                test_parse!(insertion(Dialect::MySQL), stringified.as_bytes()),
+                res
+            );
+        }
+
+        #[test]
+        fn stringify_insert_ignore() {
+            let res = test_parse!(
+                insertion(Dialect::MySQL),
+                b"INSERT IGNORE INTO users (id) VALUES (1)"
+            );
+            assert!(res.ignore);
+            assert_eq!(
+                res.to_string(),
+                "INSERT IGNORE INTO `users` (`id`) VALUES (1)"
+            );
         }
 
         #[test]
@@ -344,6 +414,80 @@ mod tests {
             );
         }
 
+        #[test]
+        fn insert_set_form() {
+            let res = test_parse!(
+                insertion(Dialect::MySQL),
+                b"INSERT INTO users SET id = 42, name = 'test' ON DUPLICATE KEY UPDATE name = 'x'"
+            );
+            assert_eq!(
+                res,
+                InsertStatement {
+                    table: Relation::from("users"),
+                    fields: Some(vec![Column::from("id"), Column::from("name")]),
+                    data: vec![vec![
+                        Expr::Literal(42_u32.into()),
+                        Expr::Literal("test".into())
+                    ]],
+                    on_duplicate: Some(vec![(Column::from("name"), Expr::Literal("x".into()))]),
+                    ignore: false,
+                }
+            );
+            assert_eq!(
+                res.to_string(),
+                "INSERT INTO `users` (`id`, `name`) VALUES (42, 'test') \
+                 ON DUPLICATE KEY UPDATE `name` = 'x'"
+            );
+        }
+
+        #[test]
+        fn insert_default_values() {
+            let expected = InsertStatement {
+                table: Relation::from("users"),
+                fields: Some(vec![]),
+                data: vec![vec![]],
+                on_duplicate: None,
+                ignore: false,
+            };
+            assert_eq!(
+                test_parse!(insertion(Dialect::MySQL), b"INSERT INTO users () VALUES ()"),
+                expected
+            );
+            assert_eq!(
+                test_parse!(
+                    insertion(Dialect::MySQL),
+                    b"INSERT INTO users DEFAULT VALUES"
+                ),
+                expected
+            );
+            let stringified = expected.to_string();
+            assert_eq!(
+                test_parse!(insertion(Dialect::MySQL), stringified.as_bytes()),
+                expected
+            );
+        }
+
+        #[test]
+        fn insert_with_function_values() {
+            let res = test_parse!(
+                insertion(Dialect::MySQL),
+                b"INSERT INTO users (id, created_at) VALUE (UUID(), NOW())"
+            );
+            assert_eq!(
+                res.data,
+                vec![vec![
+                    Expr::Call(FunctionExpr::Call {
+                        name: "UUID".into(),
+                        arguments: vec![]
+                    }),
+                    Expr::Call(FunctionExpr::Call {
+                        name: "NOW".into(),
+                        arguments: vec![]
+                    }),
+                ]]
+            );
+        }
+
         #[test]
         fn stringify_insert_with_reserved_keyword_col() {
             let orig = b"INSERT INTO users (`id`, `name`, `key`) VALUES (1, 'bob', 1);";
@@ -536,5 +680,15 @@ mod tests {
                 }
             );
         }
+
+        #[test]
+        fn insert_default_values() {
+            let res = test_parse!(
+                insertion(Dialect::PostgreSQL),
+                b"INSERT INTO users DEFAULT VALUES"
+            );
+            assert_eq!(res.fields, Some(vec![]));
+            assert_eq!(res.data, vec![vec![]]);
+        }
     }
 }
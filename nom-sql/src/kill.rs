@@ -0,0 +1,109 @@
+use std::fmt::{self, Display};
+
+use nom::branch::alt;
+use nom::bytes::complete::tag_no_case;
+use nom::character::complete::digit1;
+use nom::combinator::{map_parser, opt, value};
+use nom::sequence::terminated;
+use nom_locate::LocatedSpan;
+use serde::{Deserialize, Serialize};
+
+use crate::common::statement_terminator;
+use crate::whitespace::whitespace1;
+use crate::NomSqlResult;
+
+/// What a [`KillStatement`] terminates
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum KillKind {
+    /// Close the connection, terminating any statement it's executing
+    #[default]
+    Connection,
+    /// Terminate the statement the connection is executing, leaving the connection open
+    Query,
+}
+
+impl Display for KillKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KillKind::Connection => write!(f, "CONNECTION"),
+            KillKind::Query => write!(f, "QUERY"),
+        }
+    }
+}
+
+/// MySQL's `KILL [CONNECTION | QUERY] <id>` statement, which terminates the connection with the
+/// given id (as returned by `CONNECTION_ID()`), or the statement it's executing
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct KillStatement {
+    pub kind: KillKind,
+    pub id: u64,
+}
+
+impl Display for KillStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "KILL {} {}", self.kind, self.id)
+    }
+}
+
+pub(crate) fn kill(i: LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], KillStatement> {
+    let (i, _) = tag_no_case("kill")(i)?;
+    let (i, _) = whitespace1(i)?;
+    let (i, kind) = opt(terminated(
+        alt((
+            value(KillKind::Connection, tag_no_case("connection")),
+            value(KillKind::Query, tag_no_case("query")),
+        )),
+        whitespace1,
+    ))(i)?;
+    let (i, id) = map_parser(digit1, nom::character::complete::u64)(i)?;
+    let (i, _) = statement_terminator(i)?;
+    Ok((
+        i,
+        KillStatement {
+            kind: kind.unwrap_or_default(),
+            id,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &[u8]) -> KillStatement {
+        kill(LocatedSpan::new(input)).unwrap().1
+    }
+
+    #[test]
+    fn kill_statement() {
+        assert_eq!(
+            parse(b"KILL 12"),
+            KillStatement {
+                kind: KillKind::Connection,
+                id: 12
+            }
+        );
+        assert_eq!(
+            parse(b"kill   connection 3;"),
+            KillStatement {
+                kind: KillKind::Connection,
+                id: 3
+            }
+        );
+        assert_eq!(
+            parse(b"KILL QUERY 7"),
+            KillStatement {
+                kind: KillKind::Query,
+                id: 7
+            }
+        );
+        assert!(kill(LocatedSpan::new(b"KILL connection")).is_err());
+        assert!(kill(LocatedSpan::new(b"KILL 1 2")).is_err());
+    }
+
+    #[test]
+    fn format_kill_statement() {
+        assert_eq!(parse(b"kill 5").to_string(), "KILL CONNECTION 5");
+        assert_eq!(parse(b"kill query 5").to_string(), "KILL QUERY 5");
+    }
+}
@@ -12,12 +12,19 @@ use nom::error::{ErrorKind, FromExternalError, ParseError};
 use nom::{AsBytes, Err, HexDisplay, IResult};
 use nom_locate::LocatedSpan;
 
-pub use self::alter::{AlterColumnOperation, AlterTableDefinition, AlterTableStatement};
+pub use self::alter::{
+    AlterColumnOperation, AlterReadysetStatement, AlterTableDefinition, AlterTableStatement,
+};
 pub use self::column::{Column, ColumnConstraint, ColumnSpecification};
-pub use self::common::{FieldDefinitionExpr, FieldReference, IndexType, TableKey};
+pub use self::comment::{parse_query_with_comments, Comment, CommentKind, Comments};
+pub use self::common::{
+    FieldDefinitionExpr, FieldReference, IndexType, ReferentialAction, TableKey,
+};
 pub use self::compound_select::{CompoundSelectOperator, CompoundSelectStatement};
+pub use self::copy::{CopyCacheFormat, CopyCacheStatement};
 pub use self::create::{
-    CacheInner, CreateCacheStatement, CreateTableBody, CreateTableStatement, CreateViewStatement,
+    CacheFreshness, CacheInner, CacheResultLimits, CreateCacheStatement, CreateTableBody,
+    CreateTableStatement, CreateViewStatement, ReplicationLagPolicy, ResultLimitPolicy,
     SelectSpecification,
 };
 pub use self::create_table_options::CreateTableOption;
@@ -26,12 +33,14 @@ pub use self::dialect::Dialect;
 pub use self::drop::{
     DropAllCachesStatement, DropCacheStatement, DropTableStatement, DropViewStatement,
 };
+pub use self::dump::DumpCachesStatement;
 pub use self::explain::ExplainStatement;
 pub use self::expression::{
     BinaryOperator, CaseWhenBranch, Expr, FunctionExpr, InValue, UnaryOperator,
 };
 pub use self::insert::InsertStatement;
 pub use self::join::{JoinConstraint, JoinOperator, JoinRightSide};
+pub use self::kill::{KillKind, KillStatement};
 pub use self::literal::{
     embedded_literal, literal, raw_string_literal, utf8_string_literal, Double, Float,
     ItemPlaceholder, Literal, QuotingStyle,
@@ -44,7 +53,7 @@ pub use self::set::{
     SetPostgresParameter, SetPostgresParameterValue, SetStatement, SetVariables, Variable,
     VariableScope,
 };
-pub use self::show::ShowStatement;
+pub use self::show::{FilterPredicate, ShowColumns, ShowStatement, ShowVariables};
 pub use self::sql_identifier::SqlIdentifier;
 pub use self::sql_type::{EnumVariants, SqlType};
 pub use self::table::{replicator_table_list, Relation, TableExpr, TableExprInner};
@@ -60,17 +69,21 @@ mod macros;
 mod alter;
 pub mod analysis;
 mod column;
+mod comment;
 mod common;
 mod compound_select;
+mod copy;
 mod create;
 mod create_table_options;
 mod delete;
 mod drop;
+mod dump;
 mod explain;
 mod expression;
 mod insert;
 mod join;
 mod keywords;
+mod kill;
 mod literal;
 mod order;
 mod rename;
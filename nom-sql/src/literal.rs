@@ -271,7 +271,7 @@ impl Literal {
             SqlType::TimestampTz => arbitrary_date_time()
                 .prop_map(|dt| Self::String(dt.format("%Y-%m-%d %H:%M:%S %:z").to_string()))
                 .boxed(),
-            SqlType::Time => arbitrary_naive_time()
+            SqlType::Time(_) => arbitrary_naive_time()
                 .prop_map(|nt| Self::String(nt.format("%H:%M:%S").to_string()))
                 .boxed(),
             SqlType::Enum(_) => unimplemented!("Enums aren't implemented yet"),
@@ -307,6 +307,7 @@ impl Literal {
                     .prop_map(|bits| Self::BitVector(bits.to_bytes()))
                     .boxed()
             }
+            SqlType::SmallSerial => any::<i16>().prop_map(|i| Self::Integer(i as _)).boxed(),
             SqlType::Serial => any::<i32>().prop_map(Self::from).boxed(),
             SqlType::BigSerial => any::<i64>().prop_map(Self::from).boxed(),
             SqlType::Array(_) => unimplemented!("Arrays aren't implemented yet"),
@@ -6,8 +6,11 @@ use nom_locate::LocatedSpan;
 use readyset_util::redacted::Sensitive;
 use serde::{Deserialize, Serialize};
 
-use crate::alter::{alter_table_statement, AlterTableStatement};
+use crate::alter::{
+    alter_readyset_statement, alter_table_statement, AlterReadysetStatement, AlterTableStatement,
+};
 use crate::compound_select::{compound_selection, CompoundSelectStatement};
+use crate::copy::{copy_cache, CopyCacheStatement};
 use crate::create::{
     create_cached_query, create_table, key_specification, view_creation, CreateCacheStatement,
     CreateTableStatement, CreateViewStatement,
@@ -17,9 +20,11 @@ use crate::drop::{
     drop_all_caches, drop_cached_query, drop_table, drop_view, DropCacheStatement,
     DropTableStatement, DropViewStatement,
 };
+use crate::dump::{dump_caches, DumpCachesStatement};
 use crate::explain::{explain_statement, ExplainStatement};
 use crate::expression::expression;
 use crate::insert::{insertion, InsertStatement};
+use crate::kill::{kill, KillStatement};
 use crate::rename::{rename_table, RenameTableStatement};
 use crate::select::{selection, SelectStatement};
 use crate::set::{set, SetStatement};
@@ -42,7 +47,10 @@ pub enum SqlQuery {
     CreateCache(CreateCacheStatement),
     DropCache(DropCacheStatement),
     DropAllCaches(DropAllCachesStatement),
+    DumpCaches(DumpCachesStatement),
+    CopyCache(CopyCacheStatement),
     AlterTable(AlterTableStatement),
+    AlterReadyset(AlterReadysetStatement),
     Insert(InsertStatement),
     CompoundSelect(CompoundSelectStatement),
     Select(SelectStatement),
@@ -58,6 +66,7 @@ pub enum SqlQuery {
     Use(UseStatement),
     Show(ShowStatement),
     Explain(ExplainStatement),
+    Kill(KillStatement),
 }
 
 impl fmt::Display for SqlQuery {
@@ -70,12 +79,15 @@ impl fmt::Display for SqlQuery {
             SqlQuery::CreateCache(ref create) => write!(f, "{}", create),
             SqlQuery::DropCache(ref drop) => write!(f, "{}", drop),
             SqlQuery::DropAllCaches(ref drop) => write!(f, "{}", drop),
+            SqlQuery::DumpCaches(ref dump) => write!(f, "{}", dump),
+            SqlQuery::CopyCache(ref copy) => write!(f, "{}", copy),
             SqlQuery::Delete(ref delete) => write!(f, "{}", delete),
             SqlQuery::DropTable(ref drop) => write!(f, "{}", drop),
             SqlQuery::DropView(ref drop) => write!(f, "{}", drop),
             SqlQuery::Update(ref update) => write!(f, "{}", update),
             SqlQuery::Set(ref set) => write!(f, "{}", set),
             SqlQuery::AlterTable(ref alter) => write!(f, "{}", alter),
+            SqlQuery::AlterReadyset(ref alter) => write!(f, "{}", alter),
             SqlQuery::CompoundSelect(ref compound) => write!(f, "{}", compound),
             SqlQuery::StartTransaction(ref tx) => write!(f, "{}", tx),
             SqlQuery::Commit(ref commit) => write!(f, "{}", commit),
@@ -84,6 +96,7 @@ impl fmt::Display for SqlQuery {
             SqlQuery::Use(ref use_db) => write!(f, "{}", use_db),
             SqlQuery::Show(ref show) => write!(f, "{}", show),
             SqlQuery::Explain(ref explain) => write!(f, "{}", explain),
+            SqlQuery::Kill(ref kill) => write!(f, "{}", kill),
         }
     }
 }
@@ -107,12 +120,15 @@ impl SqlQuery {
             Self::CreateCache(_) => "CREATE CACHE",
             Self::DropCache(_) => "DROP CACHE",
             Self::DropAllCaches(_) => "DROP ALL CACHES",
+            Self::DumpCaches(_) => "DUMP CACHES",
+            Self::CopyCache(_) => "COPY CACHE",
             Self::Delete(_) => "DELETE",
             Self::DropTable(_) => "DROP TABLE",
             Self::DropView(_) => "DROP VIEW",
             Self::Update(_) => "UPDATE",
             Self::Set(_) => "SET",
             Self::AlterTable(_) => "ALTER TABLE",
+            Self::AlterReadyset(_) => "ALTER READYSET",
             Self::CompoundSelect(_) => "SELECT",
             Self::StartTransaction(_) => "START TRANSACTION",
             Self::Commit(_) => "COMMIT",
@@ -121,6 +137,7 @@ impl SqlQuery {
             Self::Use(_) => "USE",
             Self::Show(_) => "SHOW",
             Self::Explain(_) => "EXPLAIN",
+            Self::Kill(_) => "KILL",
         }
     }
 
@@ -147,15 +164,25 @@ pub fn sql_query(dialect: Dialect) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResul
             map(view_creation(dialect), SqlQuery::CreateView),
             map(create_cached_query(dialect), SqlQuery::CreateCache),
             map(drop_cached_query(dialect), SqlQuery::DropCache),
-            map(drop_all_caches, SqlQuery::DropAllCaches),
-            map(alter_table_statement(dialect), SqlQuery::AlterTable),
+            alt((
+                map(drop_all_caches, SqlQuery::DropAllCaches),
+                map(dump_caches, SqlQuery::DumpCaches),
+                map(copy_cache(dialect), SqlQuery::CopyCache),
+            )),
+            alt((
+                map(alter_table_statement(dialect), SqlQuery::AlterTable),
+                map(alter_readyset_statement(dialect), SqlQuery::AlterReadyset),
+            )),
             map(start_transaction(dialect), SqlQuery::StartTransaction),
             map(commit(dialect), SqlQuery::Commit),
             map(rollback(dialect), SqlQuery::Rollback),
             map(rename_table(dialect), SqlQuery::RenameTable),
             map(use_statement(dialect), SqlQuery::Use),
             map(show(dialect), SqlQuery::Show),
-            map(explain_statement, SqlQuery::Explain),
+            alt((
+                map(explain_statement(dialect), SqlQuery::Explain),
+                map(kill, SqlQuery::Kill),
+            )),
         ))(i)
     }
 }
@@ -356,6 +383,47 @@ mod tests {
         assert_eq!(res, SqlQuery::DropAllCaches(DropAllCachesStatement {}));
     }
 
+    #[test]
+    fn dump_caches() {
+        let res = parse_query(Dialect::MySQL, "DUMP CACHES").unwrap();
+        assert_eq!(res, SqlQuery::DumpCaches(DumpCachesStatement {}));
+    }
+
+    #[test]
+    fn copy_cache() {
+        let res = parse_query(Dialect::PostgreSQL, "COPY CACHE q TO STDOUT").unwrap();
+        assert_eq!(
+            res,
+            SqlQuery::CopyCache(CopyCacheStatement {
+                name: "q".into(),
+                format: None,
+            })
+        );
+    }
+
+    #[test]
+    fn alter_readyset() {
+        for dialect in [Dialect::MySQL, Dialect::PostgreSQL] {
+            let res = parse_query(dialect, "ALTER READYSET REPLICATION PAUSE").unwrap();
+            assert_eq!(
+                res,
+                SqlQuery::AlterReadyset(AlterReadysetStatement::PauseReplication)
+            );
+        }
+    }
+
+    #[test]
+    fn kill_connection() {
+        let res = parse_query(Dialect::MySQL, "KILL CONNECTION 42").unwrap();
+        assert_eq!(
+            res,
+            SqlQuery::Kill(KillStatement {
+                kind: crate::KillKind::Connection,
+                id: 42
+            })
+        );
+    }
+
     mod mysql {
         use std::collections::hash_map::DefaultHasher;
         use std::hash::{Hash, Hasher};
@@ -22,8 +22,8 @@ use crate::order::{order_clause, OrderClause};
 use crate::table::{table_expr, table_expr_list};
 use crate::whitespace::{whitespace0, whitespace1};
 use crate::{
-    Dialect, Expr, FieldReference, FunctionExpr, Literal, NomSqlError, NomSqlResult, SqlIdentifier,
-    TableExpr,
+    Comments, Dialect, Expr, FieldReference, FunctionExpr, Literal, NomSqlError, NomSqlResult,
+    SqlIdentifier, TableExpr,
 };
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq, PartialOrd, Default, Serialize, Deserialize)]
@@ -164,6 +164,10 @@ pub struct SelectStatement {
     pub having: Option<Expr>,
     pub order: Option<OrderClause>,
     pub limit_clause: LimitClause,
+    /// The comments in the text of the statement, if it was parsed with
+    /// [`parse_query_with_comments`](crate::parse_query_with_comments)
+    #[serde(default)]
+    pub comments: Comments,
 }
 
 impl SelectStatement {
@@ -179,6 +183,7 @@ impl SelectStatement {
                         | FunctionExpr::Max(_)
                         | FunctionExpr::Min(_)
                         | FunctionExpr::GroupConcat { .. }
+                        | FunctionExpr::PercentileCont { .. }
                 ),
                 Expr::NestedSelect(select) => select.contains_aggregate_select(),
                 _ => false,
@@ -190,11 +195,14 @@ impl SelectStatement {
 
 impl fmt::Display for SelectStatement {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.comments.fmt_leading(f)?;
         if !self.ctes.is_empty() {
             write!(f, "WITH {} ", self.ctes.iter().join(", "))?;
         }
 
-        write!(f, "SELECT ")?;
+        write!(f, "SELECT")?;
+        self.comments.fmt_inline(f)?;
+        write!(f, " ")?;
         if self.distinct {
             write!(f, "DISTINCT ")?;
         }
@@ -239,6 +247,7 @@ impl fmt::Display for SelectStatement {
         if self.limit_clause.limit().is_some() || self.limit_clause.offset().is_some() {
             write!(f, " {}", self.limit_clause)?;
         }
+        self.comments.fmt_trailing(f)?;
 
         Ok(())
     }
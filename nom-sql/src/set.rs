@@ -321,9 +321,10 @@ fn variable(dialect: Dialect) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u
         let (i, scope) = set_variable_scope_prefix
             .or(|i| Ok((i, VariableScope::Local)))
             .parse(i)?;
-        let (i, name) = dialect
-            .identifier()
-            .map(|ident| ident.to_ascii_lowercase().into())
+        // Variables can have dotted names, such as those of MySQL components
+        // (`component_name.variable`) or ReadySet's own settings (`readyset.setting`)
+        let (i, name) = separated_list1(tag("."), dialect.identifier())
+            .map(|idents| idents.iter().join(".").to_ascii_lowercase().into())
             .parse(i)?;
         Ok((i, Variable { scope, name }))
     }
@@ -470,6 +471,26 @@ mod tests {
         assert_eq!(res2.to_string(), expected);
     }
 
+    #[test]
+    fn dotted_variable_name() {
+        let res = test_parse!(
+            set(Dialect::MySQL),
+            b"SET GLOBAL readyset.eviction_policy = 'lru'"
+        );
+        assert_eq!(
+            res,
+            SetStatement::Variable(SetVariables {
+                variables: vec![(
+                    Variable {
+                        scope: VariableScope::Global,
+                        name: "readyset.eviction_policy".into()
+                    },
+                    Expr::Literal("lru".into())
+                )],
+            })
+        );
+    }
+
     #[test]
     fn session_set() {
         let qstring1 = "set @@Session.var = 1";
@@ -3,13 +3,14 @@ use std::fmt;
 use nom::branch::alt;
 use nom::bytes::complete::tag_no_case;
 use nom::combinator::{map, map_res, opt, value};
-use nom::sequence::{preceded, tuple};
+use nom::sequence::{preceded, terminated, tuple};
 use nom_locate::LocatedSpan;
 use serde::{Deserialize, Serialize};
 
 use crate::expression::expression;
+use crate::table::relation;
 use crate::whitespace::{whitespace0, whitespace1};
-use crate::{Dialect, Expr, NomSqlResult};
+use crate::{Dialect, Expr, NomSqlResult, Relation, VariableScope};
 
 pub type QueryID = String;
 
@@ -17,11 +18,15 @@ pub type QueryID = String;
 pub enum ShowStatement {
     Events,
     Tables(Tables),
+    Columns(ShowColumns),
+    CreateTable(Relation),
     CachedQueries(Option<QueryID>),
     ProxiedQueries(Option<QueryID>),
+    UnsupportedQueries(Option<QueryID>),
     ReadySetStatus,
     ReadySetVersion,
     ReadySetTables,
+    Variables(ShowVariables),
 }
 
 impl fmt::Display for ShowStatement {
@@ -30,6 +35,8 @@ impl fmt::Display for ShowStatement {
         match self {
             Self::Events => write!(f, "EVENTS"),
             Self::Tables(tables) => write!(f, "{}", tables),
+            Self::Columns(columns) => write!(f, "{}", columns),
+            Self::CreateTable(table) => write!(f, "CREATE TABLE {}", table),
             Self::CachedQueries(maybe_query_id) => {
                 if let Some(query_id) = maybe_query_id {
                     write!(f, "CACHES WHERE query_id = {}", query_id)
@@ -44,9 +51,17 @@ impl fmt::Display for ShowStatement {
                     write!(f, "PROXIED QUERIES")
                 }
             }
+            Self::UnsupportedQueries(maybe_query_id) => {
+                if let Some(query_id) = maybe_query_id {
+                    write!(f, "UNSUPPORTED QUERIES WHERE query_id = {}", query_id)
+                } else {
+                    write!(f, "UNSUPPORTED QUERIES")
+                }
+            }
             Self::ReadySetStatus => write!(f, "READYSET STATUS"),
             Self::ReadySetVersion => write!(f, "READYSET VERSION"),
             Self::ReadySetTables => write!(f, "READYSET TABLES"),
+            Self::Variables(variables) => write!(f, "{}", variables),
         }
     }
 }
@@ -87,6 +102,19 @@ fn proxied_queries(
     }
 }
 
+fn unsupported_queries(
+    dialect: Dialect,
+) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], ShowStatement> {
+    move |i| {
+        let (i, _) = tag_no_case("unsupported")(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = tag_no_case("queries")(i)?;
+        let (i, q_id) = opt(preceded(whitespace1, where_query_id(dialect)))(i)?;
+
+        Ok((i, ShowStatement::UnsupportedQueries(q_id)))
+    }
+}
+
 pub fn show(dialect: Dialect) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], ShowStatement> {
     move |i| {
         let (i, _) = tag_no_case("show")(i)?;
@@ -94,6 +122,7 @@ pub fn show(dialect: Dialect) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u
         let (i, statement) = alt((
             cached_queries(dialect),
             proxied_queries(dialect),
+            unsupported_queries(dialect),
             value(
                 ShowStatement::ReadySetStatus,
                 tuple((tag_no_case("readyset"), whitespace1, tag_no_case("status"))),
@@ -107,6 +136,18 @@ pub fn show(dialect: Dialect) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u
                 tuple((tag_no_case("readyset"), whitespace1, tag_no_case("tables"))),
             ),
             map(show_tables(dialect), ShowStatement::Tables),
+            map(show_columns(dialect), ShowStatement::Columns),
+            map(
+                tuple((
+                    tag_no_case("create"),
+                    whitespace1,
+                    tag_no_case("table"),
+                    whitespace1,
+                    relation(dialect),
+                )),
+                |(_, _, _, _, table)| ShowStatement::CreateTable(table),
+            ),
+            map(show_variables(dialect), ShowStatement::Variables),
             value(ShowStatement::Events, tag_no_case("events")),
         ))(i)?;
         Ok((i, statement))
@@ -163,6 +204,89 @@ fn show_tables(dialect: Dialect) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<
     }
 }
 
+/// `SHOW {COLUMNS | FIELDS} {FROM | IN} <table> [{FROM | IN} <db>] [LIKE '<pattern>' | WHERE
+/// <expr>]`
+///
+/// If the database is given separately from the table, it's stored as the schema of `table`
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct ShowColumns {
+    pub table: Relation,
+    pub filter: Option<FilterPredicate>,
+}
+
+impl fmt::Display for ShowColumns {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "COLUMNS FROM {}", self.table)?;
+        if let Some(filter) = self.filter.as_ref() {
+            write!(f, " {}", filter)?;
+        }
+        Ok(())
+    }
+}
+
+fn show_columns(
+    dialect: Dialect,
+) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], ShowColumns> {
+    move |i| {
+        let (i, _) = alt((tag_no_case("columns"), tag_no_case("fields")))(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, _) = alt((tag_no_case("from"), tag_no_case("in")))(i)?;
+        let (i, _) = whitespace1(i)?;
+        let (i, mut table) = relation(dialect)(i)?;
+        let (i, from_db) = opt(map(
+            tuple((
+                whitespace1,
+                alt((tag_no_case("from"), tag_no_case("in"))),
+                whitespace1,
+                dialect.identifier(),
+            )),
+            |(_, _, _, from_db)| from_db,
+        ))(i)?;
+        if from_db.is_some() {
+            table.schema = from_db;
+        }
+        let (i, filter) = opt(filter_predicate(dialect))(i)?;
+        Ok((i, ShowColumns { table, filter }))
+    }
+}
+
+/// `SHOW [GLOBAL | SESSION] VARIABLES [LIKE '<pattern>' | WHERE <expr>]`
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct ShowVariables {
+    pub scope: Option<VariableScope>,
+    pub filter: Option<FilterPredicate>,
+}
+
+impl fmt::Display for ShowVariables {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(scope) = self.scope {
+            write!(f, "{} ", scope)?;
+        }
+        write!(f, "VARIABLES")?;
+        if let Some(filter) = self.filter.as_ref() {
+            write!(f, " {}", filter)?;
+        }
+        Ok(())
+    }
+}
+
+fn show_variables(
+    dialect: Dialect,
+) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], ShowVariables> {
+    move |i| {
+        let (i, scope) = opt(terminated(
+            alt((
+                value(VariableScope::Global, tag_no_case("global")),
+                value(VariableScope::Session, tag_no_case("session")),
+            )),
+            whitespace1,
+        ))(i)?;
+        let (i, _) = tag_no_case("variables")(i)?;
+        let (i, filter) = opt(filter_predicate(dialect))(i)?;
+        Ok((i, ShowVariables { scope, filter }))
+    }
+}
+
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum FilterPredicate {
     Like(String),
@@ -270,6 +394,68 @@ mod tests {
         );
     }
 
+    #[test]
+    fn show_columns() {
+        let res1 = show(Dialect::MySQL)(LocatedSpan::new(b"SHOW COLUMNS FROM t1"))
+            .unwrap()
+            .1;
+        let res2 = show(Dialect::MySQL)(LocatedSpan::new(b"SHOW FIELDS IN t1 IN db1 LIKE 'a%'"))
+            .unwrap()
+            .1;
+        let res3 = show(Dialect::MySQL)(LocatedSpan::new(
+            b"SHOW COLUMNS FROM db1.t1 WHERE Field = 'a'",
+        ))
+        .unwrap()
+        .1;
+        assert_eq!(
+            res1,
+            ShowStatement::Columns(ShowColumns {
+                table: Relation::from("t1"),
+                filter: None,
+            })
+        );
+        assert_eq!(res1.to_string(), "SHOW COLUMNS FROM `t1`");
+        assert_eq!(
+            res2,
+            ShowStatement::Columns(ShowColumns {
+                table: Relation {
+                    schema: Some("db1".into()),
+                    name: "t1".into(),
+                },
+                filter: Some(FilterPredicate::Like("a%".to_string())),
+            })
+        );
+        assert_eq!(
+            res3,
+            ShowStatement::Columns(ShowColumns {
+                table: Relation {
+                    schema: Some("db1".into()),
+                    name: "t1".into(),
+                },
+                filter: Some(FilterPredicate::Where(Expr::BinaryOp {
+                    lhs: Box::new(Expr::Column(Column::from("Field"))),
+                    op: BinaryOperator::Equal,
+                    rhs: Box::new(Expr::Literal(Literal::String("a".to_string()))),
+                })),
+            })
+        );
+    }
+
+    #[test]
+    fn show_create_table() {
+        let res = show(Dialect::MySQL)(LocatedSpan::new(b"SHOW CREATE TABLE db1.t1"))
+            .unwrap()
+            .1;
+        assert_eq!(
+            res,
+            ShowStatement::CreateTable(Relation {
+                schema: Some("db1".into()),
+                name: "t1".into(),
+            })
+        );
+        assert_eq!(res.to_string(), "SHOW CREATE TABLE `db1`.`t1`");
+    }
+
     #[test]
     fn show_events() {
         let qstring1 = "SHOW EVENTS";
@@ -333,6 +519,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn show_unsupported_queries() {
+        let qstring1 = "SHOW UNSUPPORTED QUERIES";
+        let res1 = show(Dialect::MySQL)(LocatedSpan::new(qstring1.as_bytes()))
+            .unwrap()
+            .1;
+        let qstring2 = "SHOW UNSUPPORTED QUERIES where query_id = 'test'";
+        let res2 = show(Dialect::MySQL)(LocatedSpan::new(qstring2.as_bytes()))
+            .unwrap()
+            .1;
+        assert_eq!(res1, ShowStatement::UnsupportedQueries(None));
+        assert_eq!(
+            res2,
+            ShowStatement::UnsupportedQueries(Some("test".to_string()))
+        );
+        assert_eq!(res1.to_string(), qstring1);
+    }
+
     #[test]
     fn show_replication_status() {
         let qstring1 = "SHOW READYSET STATUS";
@@ -368,4 +572,29 @@ mod tests {
         let res = test_parse!(show(Dialect::MySQL), b"SHOW READYSET TABLES");
         assert_eq!(res, ShowStatement::ReadySetTables);
     }
+
+    #[test]
+    fn show_variables() {
+        let res = test_parse!(
+            show(Dialect::MySQL),
+            b"SHOW GLOBAL VARIABLES LIKE 'readyset.%'"
+        );
+        assert_eq!(
+            res,
+            ShowStatement::Variables(ShowVariables {
+                scope: Some(VariableScope::Global),
+                filter: Some(FilterPredicate::Like("readyset.%".to_string())),
+            })
+        );
+        assert_eq!(res.to_string(), "SHOW GLOBAL VARIABLES LIKE 'readyset.%'");
+
+        let res = test_parse!(show(Dialect::MySQL), b"SHOW VARIABLES");
+        assert_eq!(
+            res,
+            ShowStatement::Variables(ShowVariables {
+                scope: None,
+                filter: None,
+            })
+        );
+    }
 }
@@ -5,10 +5,10 @@ use std::{fmt, str};
 use failpoint_macros::set_failpoint;
 use nom::branch::alt;
 use nom::bytes::complete::{tag, tag_no_case};
-use nom::character::complete::digit1;
+use nom::character::complete::{alphanumeric1, digit1};
 #[cfg(feature = "failure_injection")]
 use nom::combinator::fail;
-use nom::combinator::{map, map_parser, opt};
+use nom::combinator::{map, map_parser, not, opt};
 use nom::error::{ErrorKind, ParseError};
 use nom::multi::{fold_many0, separated_list0};
 use nom::sequence::{delimited, preceded, terminated, tuple};
@@ -57,8 +57,7 @@ pub enum SqlType {
     QuotedChar,
     Date,
     DateTime(#[strategy(proptest::option::of(1..=6u16))] Option<u16>),
-    // FIXME(ENG-1832): Parse subsecond digit count.
-    Time,
+    Time(#[strategy(proptest::option::of(1..=6u16))] Option<u16>),
     Timestamp,
     TimestampTz,
     #[weight(0)]
@@ -77,6 +76,7 @@ pub enum SqlType {
     Uuid,
     Bit(Option<u16>),
     VarBit(Option<u16>),
+    SmallSerial,
     Serial,
     BigSerial,
     Array(Box<SqlType>),
@@ -203,7 +203,7 @@ impl fmt::Display for SqlType {
             SqlType::QuotedChar => write!(f, "\"char\""),
             SqlType::Date => write!(f, "DATE"),
             SqlType::DateTime(subsecond_digits) => write_with_len(f, "DATETIME", subsecond_digits),
-            SqlType::Time => write!(f, "TIME"),
+            SqlType::Time(subsecond_digits) => write_with_len(f, "TIME", subsecond_digits),
             SqlType::Timestamp => write!(f, "TIMESTAMP"),
             SqlType::TimestampTz => write!(f, "TIMESTAMP WITH TIME ZONE"),
             SqlType::Binary(len) => write_with_len(f, "BINARY", len),
@@ -233,6 +233,7 @@ impl fmt::Display for SqlType {
                 Ok(())
             }
             SqlType::VarBit(n) => write_with_len(f, "VARBIT", n),
+            SqlType::SmallSerial => write!(f, "SMALLSERIAL"),
             SqlType::Serial => write!(f, "SERIAL"),
             SqlType::BigSerial => write!(f, "BIGSERIAL"),
             SqlType::Array(ref t) => write!(f, "{}[]", t),
@@ -400,12 +401,12 @@ fn delim_digit(i: LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], LocatedSpan<&[u8]>>
     delimited(tag("("), digit1, tag(")"))(i)
 }
 
-fn delim_u16(i: LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], u16> {
+pub(crate) fn delim_u16(i: LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], u16> {
     map_parser(delim_digit, digit_as_u16)(i)
 }
 
 fn int_type<'a, F, G>(
-    tag: &str,
+    tag: &'static str,
     mk_unsigned: F,
     mk_signed: G,
     i: LocatedSpan<&'a [u8]>,
@@ -415,7 +416,7 @@ where
     G: Fn(Option<u16>) -> SqlType + 'static,
 {
     let (remaining_input, (_, len, _, signed)) =
-        tuple((tag_no_case(tag), opt(delim_u16), whitespace0, opt_signed))(i)?;
+        tuple((type_name(tag), opt(delim_u16), whitespace0, opt_signed))(i)?;
 
     if let Some(Sign::Unsigned) = signed {
         Ok((remaining_input, mk_unsigned(len)))
@@ -586,8 +587,12 @@ fn type_identifier_part1(
 fn type_identifier_part2(i: LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], SqlType> {
     alt((
         map(
-            terminated(tag_no_case("time"), opt_without_time_zone),
-            |_| SqlType::Time,
+            tuple((
+                tag_no_case("time"),
+                opt(preceded(whitespace0, delim_u16)),
+                opt_without_time_zone,
+            )),
+            |(_, subsecond_digits, _)| SqlType::Time(subsecond_digits),
         ),
         decimal_or_numeric,
         map(
@@ -651,6 +656,43 @@ fn type_identifier_part3(
     }
 }
 
+/// Parses a type name which isn't followed by any more alphanumeric characters, so that eg `int4`
+/// isn't parsed as `int` followed by `4`
+fn type_name<'a>(
+    name: &'static str,
+) -> impl FnMut(LocatedSpan<&'a [u8]>) -> NomSqlResult<&'a [u8], LocatedSpan<&'a [u8]>> {
+    terminated(tag_no_case(name), not(alphanumeric1))
+}
+
+/// Types (and aliases for types) which only exist in PostgreSQL
+fn postgres_type(dialect: Dialect) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], SqlType> {
+    move |i| match dialect {
+        Dialect::PostgreSQL => alt((
+            map(type_name("int2"), |_| SqlType::SmallInt(None)),
+            map(type_name("int4"), |_| SqlType::Int(None)),
+            map(type_name("int8"), |_| SqlType::BigInt(None)),
+            map(type_name("float4"), |_| SqlType::Real),
+            map(type_name("float8"), |_| SqlType::Double),
+            map(
+                alt((type_name("smallserial"), type_name("serial2"))),
+                |_| SqlType::SmallSerial,
+            ),
+            map(type_name("serial4"), |_| SqlType::Serial),
+            map(type_name("serial8"), |_| SqlType::BigSerial),
+            // Unlike MySQL's, PostgreSQL's DECIMAL is the same type as NUMERIC, which can have a
+            // precision of up to 1000 digits
+            map(
+                tuple((tag_no_case("decimal"), whitespace0, opt(numeric_precision))),
+                |t| SqlType::Numeric(t.2),
+            ),
+        ))(i),
+        Dialect::MySQL => Err(nom::Err::Error(ParseError::from_error_kind(
+            i,
+            ErrorKind::IsNot,
+        ))),
+    }
+}
+
 fn other_type(dialect: Dialect) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], Relation> {
     move |i| match dialect {
         Dialect::PostgreSQL => relation(dialect)(i),
@@ -675,6 +717,7 @@ fn type_identifier_no_arrays(
 ) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], SqlType> {
     move |i| {
         alt((
+            postgres_type(dialect),
             type_identifier_part1(dialect),
             type_identifier_part2,
             type_identifier_part3(dialect),
@@ -1010,7 +1053,7 @@ mod tests {
                 type_identifier(Dialect::PostgreSQL),
                 b"time without time zone"
             );
-            assert_eq!(res, SqlType::Time);
+            assert_eq!(res, SqlType::Time(None));
         }
 
         #[test]
@@ -1060,5 +1103,74 @@ mod tests {
             let res = test_parse!(type_identifier(Dialect::PostgreSQL), b"citext");
             assert_eq!(res, SqlType::Citext);
         }
+
+        #[test]
+        fn int_aliases() {
+            let res = test_parse!(type_identifier(Dialect::PostgreSQL), b"int2");
+            assert_eq!(res, SqlType::SmallInt(None));
+            let res = test_parse!(type_identifier(Dialect::PostgreSQL), b"int4");
+            assert_eq!(res, SqlType::Int(None));
+            let res = test_parse!(type_identifier(Dialect::PostgreSQL), b"INT8[]");
+            assert_eq!(res, SqlType::Array(Box::new(SqlType::BigInt(None))));
+        }
+
+        #[test]
+        fn float_aliases() {
+            let res = test_parse!(type_identifier(Dialect::PostgreSQL), b"float4");
+            assert_eq!(res, SqlType::Real);
+            let res = test_parse!(type_identifier(Dialect::PostgreSQL), b"float8");
+            assert_eq!(res, SqlType::Double);
+        }
+
+        #[test]
+        fn serial_aliases() {
+            let res = test_parse!(type_identifier(Dialect::PostgreSQL), b"smallserial");
+            assert_eq!(res, SqlType::SmallSerial);
+            let res = test_parse!(type_identifier(Dialect::PostgreSQL), b"serial2");
+            assert_eq!(res, SqlType::SmallSerial);
+            let res = test_parse!(type_identifier(Dialect::PostgreSQL), b"serial4");
+            assert_eq!(res, SqlType::Serial);
+            let res = test_parse!(type_identifier(Dialect::PostgreSQL), b"serial8");
+            assert_eq!(res, SqlType::BigSerial);
+        }
+
+        #[test]
+        fn int_prefixed_custom_type() {
+            let res = test_parse!(type_identifier(Dialect::PostgreSQL), b"int4range");
+            assert_eq!(res, SqlType::Other("int4range".into()));
+        }
+
+        #[test]
+        fn decimal_is_numeric() {
+            let res = test_parse!(type_identifier(Dialect::PostgreSQL), b"decimal(1000, 20)");
+            assert_eq!(res, SqlType::Numeric(Some((1000, Some(20)))));
+            let res = test_parse!(type_identifier(Dialect::PostgreSQL), b"decimal");
+            assert_eq!(res, SqlType::Numeric(None));
+        }
+
+        #[test]
+        fn time_with_precision() {
+            let res = test_parse!(
+                type_identifier(Dialect::PostgreSQL),
+                b"time(3) without time zone"
+            );
+            assert_eq!(res, SqlType::Time(Some(3)));
+            assert_eq!(res.to_string(), "TIME(3)");
+        }
+
+        #[test]
+        fn format_type_output_round_trips() {
+            for ty in [
+                SqlType::Numeric(Some((10, Some(2)))),
+                SqlType::SmallSerial,
+                SqlType::ByteArray,
+                SqlType::Inet,
+                SqlType::Citext,
+            ] {
+                let formatted = ty.to_string();
+                let res = test_parse!(type_identifier(Dialect::PostgreSQL), formatted.as_bytes());
+                assert_eq!(res, ty);
+            }
+        }
     }
 }
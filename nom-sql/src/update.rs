@@ -1,22 +1,25 @@
 use std::{fmt, str};
 
+use itertools::Itertools;
 use nom::bytes::complete::tag_no_case;
 use nom::combinator::opt;
-use nom::sequence::tuple;
+use nom::sequence::{preceded, terminated, tuple};
 use nom_locate::LocatedSpan;
 use serde::{Deserialize, Serialize};
 
 use crate::column::Column;
 use crate::common::{assignment_expr_list, statement_terminator};
 use crate::select::where_clause;
-use crate::table::{relation, Relation};
+use crate::table::{relation, table_expr_list, Relation};
 use crate::whitespace::{whitespace0, whitespace1};
-use crate::{Dialect, Expr, NomSqlResult};
+use crate::{Dialect, Expr, NomSqlResult, TableExpr};
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub struct UpdateStatement {
     pub table: Relation,
     pub fields: Vec<(Column, Expr)>,
+    /// Other tables joined against in the `WHERE` clause, with PostgreSQL's `UPDATE ... FROM`
+    pub from: Vec<TableExpr>,
     pub where_clause: Option<Expr>,
 }
 
@@ -33,6 +36,9 @@ impl fmt::Display for UpdateStatement {
                 .collect::<Vec<_>>()
                 .join(", ")
         )?;
+        if !self.from.is_empty() {
+            write!(f, " FROM {}", self.from.iter().join(", "))?;
+        }
         if let Some(ref where_clause) = self.where_clause {
             write!(f, " WHERE ")?;
             write!(f, "{}", where_clause)?;
@@ -45,23 +51,29 @@ pub fn updating(
     dialect: Dialect,
 ) -> impl Fn(LocatedSpan<&[u8]>) -> NomSqlResult<&[u8], UpdateStatement> {
     move |i| {
-        let (remaining_input, (_, _, table, _, _, _, fields, _, where_clause, _)) = tuple((
-            tag_no_case("update"),
-            whitespace1,
-            relation(dialect),
-            whitespace1,
-            tag_no_case("set"),
-            whitespace1,
-            assignment_expr_list(dialect),
-            whitespace0,
-            opt(where_clause(dialect)),
-            statement_terminator,
-        ))(i)?;
+        let (remaining_input, (_, _, table, _, _, _, fields, _, from, where_clause, _)) =
+            tuple((
+                tag_no_case("update"),
+                whitespace1,
+                relation(dialect),
+                whitespace1,
+                tag_no_case("set"),
+                whitespace1,
+                assignment_expr_list(dialect),
+                whitespace0,
+                opt(preceded(
+                    terminated(tag_no_case("from"), whitespace1),
+                    table_expr_list(dialect),
+                )),
+                opt(where_clause(dialect)),
+                statement_terminator,
+            ))(i)?;
         Ok((
             remaining_input,
             UpdateStatement {
                 table,
                 fields,
+                from: from.unwrap_or_default(),
                 where_clause,
             },
         ))
@@ -88,6 +100,7 @@ mod tests {
                     (Column::from("id"), Expr::Literal(42_u32.into())),
                     (Column::from("name"), Expr::Literal("test".into())),
                 ],
+                from: vec![],
                 where_clause: None
             }
         );
@@ -112,6 +125,7 @@ mod tests {
                     (Column::from("id"), Expr::Literal(Literal::from(42_u32)),),
                     (Column::from("name"), Expr::Literal(Literal::from("test",)),),
                 ],
+                from: vec![],
                 where_clause: expected_where_cond,
             }
         );
@@ -149,6 +163,7 @@ mod tests {
                         rhs: Box::new(Expr::Literal(1_u32.into()))
                     },
                 ),],
+                from: vec![],
                 where_clause: expected_where_cond,
             }
         );
@@ -189,6 +204,7 @@ mod tests {
                             })))
                         },
                     )],
+                    from: vec![],
                     where_clause: expected_where_cond,
                 }
             );
@@ -211,6 +227,7 @@ mod tests {
                             rhs: Box::new(Expr::Literal(1_u32.into()))
                         },
                     ),],
+                    from: vec![],
                     where_clause: None
                 }
             );
@@ -235,6 +252,7 @@ mod tests {
                             ]
                         })
                     )],
+                    from: vec![],
                     where_clause: Some(Expr::BinaryOp {
                         lhs: Box::new(Expr::Column(Column::from("permission"))),
                         op: BinaryOperator::Like,
@@ -250,7 +268,7 @@ mod tests {
         use crate::column::Column;
         use crate::table::Relation;
         use crate::Expr::UnaryOp;
-        use crate::{BinaryOperator, Double, UnaryOperator};
+        use crate::{BinaryOperator, Double, TableExprInner, UnaryOperator};
 
         #[test]
         fn updated_with_neg_float() {
@@ -280,6 +298,7 @@ mod tests {
                             })))
                         },
                     ),],
+                    from: vec![],
                     where_clause: expected_where_cond,
                 }
             );
@@ -302,9 +321,42 @@ mod tests {
                             rhs: Box::new(Expr::Literal(1_u32.into()))
                         },
                     ),],
+                    from: vec![],
                     where_clause: None
                 }
             );
         }
+
+        #[test]
+        fn update_from() {
+            let qstring = "UPDATE t SET x = 1 FROM u AS v WHERE t.id = v.t_id";
+
+            let res = updating(Dialect::PostgreSQL)(LocatedSpan::new(qstring.as_bytes()));
+            assert_eq!(
+                res.unwrap().1,
+                UpdateStatement {
+                    table: Relation::from("t"),
+                    fields: vec![(Column::from("x"), Expr::Literal(1_u32.into()))],
+                    from: vec![TableExpr {
+                        inner: TableExprInner::Table(Relation::from("u")),
+                        alias: Some("v".into()),
+                    }],
+                    where_clause: Some(Expr::BinaryOp {
+                        lhs: Box::new(Expr::Column(Column::from("t.id"))),
+                        op: BinaryOperator::Equal,
+                        rhs: Box::new(Expr::Column(Column::from("v.t_id"))),
+                    }),
+                }
+            );
+        }
+
+        #[test]
+        fn format_update_from() {
+            let qstring = "UPDATE t SET x = u.y FROM u, w WHERE t.id = u.t_id";
+            let expected =
+                "UPDATE `t` SET `x` = `u`.`y` FROM `u`, `w` WHERE (`t`.`id` = `u`.`t_id`)";
+            let res = updating(Dialect::PostgreSQL)(LocatedSpan::new(qstring.as_bytes()));
+            assert_eq!(res.unwrap().1.to_string(), expected);
+        }
     }
 }
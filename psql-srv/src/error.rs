@@ -11,15 +11,30 @@ pub enum Error {
     #[error("password authentication failed for user \"{0}\"")]
     AuthenticationFailure(String),
 
+    #[error("{0}")]
+    CheckViolation(String),
+
     #[error("decode error: {0}")]
     DecodeError(#[from] DecodeError),
 
+    #[error("{0}")]
+    DuplicateTable(String),
+
     #[error("encode error: {0}")]
     EncodeError(#[from] EncodeError),
 
+    #[error("{0}")]
+    GroupingError(String),
+
     #[error("incorrect format count: {0}")]
     IncorrectFormatCount(usize),
 
+    #[error("{0}")]
+    InsufficientPrivilege(String),
+
+    #[error("{0}")]
+    InsufficientResources(String),
+
     #[error("internal error: {0}")]
     InternalError(String),
 
@@ -35,9 +50,36 @@ pub enum Error {
     #[error("missing prepared statement: {0}")]
     MissingPreparedStatement(String),
 
+    #[error("{0}")]
+    NotNullViolation(String),
+
     #[error("parse error: {0}")]
     ParseError(String),
 
+    #[error("{0}")]
+    ProgramLimitExceeded(String),
+
+    #[error("{0}")]
+    QueryCanceled(String),
+
+    #[error("{0}")]
+    SyntaxError(String),
+
+    #[error("{0}")]
+    TooManyConnections(String),
+
+    #[error("{0}")]
+    UndefinedColumn(String),
+
+    #[error("{0}")]
+    UndefinedFunction(String),
+
+    #[error("{0}")]
+    UndefinedTable(String),
+
+    #[error("{0}")]
+    UniqueViolation(String),
+
     #[error("unimplemented: {0}")]
     Unimplemented(String),
 
@@ -145,11 +145,26 @@ pub enum QueryResponse<S> {
         schema: Vec<Column>,
         /// The actual resultset produced by the select statement.
         resultset: S,
+        /// A warning about the resultset (for example, that it was truncated), sent to the
+        /// frontend in a `NoticeResponse` before the rows.
+        warning: Option<String>,
     },
     /// The response to an insert statement, including the number of rows inserted.
-    Insert(u64),
+    Insert {
+        /// The number of rows inserted.
+        rows: u64,
+        /// A warning about the insert (for example, that some of the rows were truncated), sent
+        /// to the frontend in a `NoticeResponse` before the command completion.
+        warning: Option<String>,
+    },
     /// The response to an update statement, including the number of rows updated.
-    Update(u64),
+    Update {
+        /// The number of rows updated.
+        rows: u64,
+        /// A warning about the update (for example, that the new values were truncated), sent to
+        /// the frontend in a `NoticeResponse` before the command completion.
+        warning: Option<String>,
+    },
     /// The response to a delete statement, including the number of rows deleted.
     Delete(u64),
     /// The response to a command statement such as "CREATE TABLE".
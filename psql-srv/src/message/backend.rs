@@ -44,6 +44,11 @@ pub enum BackendMessage<R> {
         sqlstate: SqlState,
         message: String,
     },
+    /// A non-fatal warning, sent to the frontend with a severity of `WARNING`
+    NoticeResponse {
+        sqlstate: SqlState,
+        message: String,
+    },
     ParameterDescription {
         parameter_data_types: Vec<Type>,
     },
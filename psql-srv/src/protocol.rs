@@ -375,19 +375,23 @@ impl Protocol {
                         .get(portal_name.borrow() as &str)
                         .ok_or_else(|| Error::MissingPreparedStatement(portal_name.to_string()))?;
                     let response = backend.on_execute(*prepared_statement_id, params).await?;
-                    let res = if let Select { resultset, .. } = response {
+                    let res = if let Select {
+                        resultset, warning, ..
+                    } = response
+                    {
                         Ok(Response::Select {
+                            notice: warning.map(notice_response),
                             header: None,
                             resultset,
                             result_transfer_formats: Some(result_transfer_formats.clone()),
                             trailer: None,
                         })
                     } else {
-                        let tag = match response {
-                            Insert(n) => CommandCompleteTag::Insert(n),
-                            Update(n) => CommandCompleteTag::Update(n),
-                            Delete(n) => CommandCompleteTag::Delete(n),
-                            Command => CommandCompleteTag::Empty,
+                        let (tag, warning) = match response {
+                            Insert { rows, warning } => (CommandCompleteTag::Insert(rows), warning),
+                            Update { rows, warning } => (CommandCompleteTag::Update(rows), warning),
+                            Delete(n) => (CommandCompleteTag::Delete(n), None),
+                            Command => (CommandCompleteTag::Empty, None),
                             #[allow(clippy::unreachable)]
                             Select { .. } => {
                                 unreachable!("Select is handled as a special case above.")
@@ -398,7 +402,13 @@ impl Protocol {
                                 ));
                             }
                         };
-                        Ok(Response::Message(CommandComplete { tag }))
+                        match warning {
+                            Some(warning) => Ok(Response::Messages(smallvec![
+                                notice_response(warning),
+                                CommandComplete { tag },
+                            ])),
+                            None => Ok(Response::Message(CommandComplete { tag })),
+                        }
                     };
                     self.state = State::Ready;
                     res
@@ -408,7 +418,12 @@ impl Protocol {
                 // prepared statement.
                 Query { query } => {
                     let response = backend.on_query(query.borrow()).await?;
-                    if let Select { schema, resultset } = response {
+                    if let Select {
+                        schema,
+                        resultset,
+                        warning,
+                    } = response
+                    {
                         let mut field_descriptions = Vec::with_capacity(schema.len());
                         for i in schema {
                             field_descriptions.push(
@@ -418,6 +433,7 @@ impl Protocol {
                         }
 
                         Ok(Response::Select {
+                            notice: warning.map(notice_response),
                             header: Some(RowDescription { field_descriptions }),
                             resultset,
                             result_transfer_formats: None,
@@ -464,11 +480,11 @@ impl Protocol {
                         messages.push(BackendMessage::ready_for_query_idle());
                         Ok(Response::Messages(messages))
                     } else {
-                        let tag = match response {
-                            Insert(n) => CommandCompleteTag::Insert(n),
-                            Update(n) => CommandCompleteTag::Update(n),
-                            Delete(n) => CommandCompleteTag::Delete(n),
-                            Command => CommandCompleteTag::Empty,
+                        let (tag, warning) = match response {
+                            Insert { rows, warning } => (CommandCompleteTag::Insert(rows), warning),
+                            Update { rows, warning } => (CommandCompleteTag::Update(rows), warning),
+                            Delete(n) => (CommandCompleteTag::Delete(n), None),
+                            Command => (CommandCompleteTag::Empty, None),
                             #[allow(clippy::unreachable)]
                             Select { .. } => {
                                 unreachable!("Select is handled as a special case above.")
@@ -477,10 +493,11 @@ impl Protocol {
                                 unreachable!("SimpleQuery is handled as a special case above.")
                             }
                         };
-                        Ok(Response::Messages(smallvec![
-                            CommandComplete { tag },
-                            BackendMessage::ready_for_query_idle(),
-                        ]))
+                        let mut messages = smallvec![];
+                        messages.extend(warning.map(notice_response));
+                        messages.push(CommandComplete { tag });
+                        messages.push(BackendMessage::ready_for_query_idle());
+                        Ok(Response::Messages(messages))
                     }
                 }
 
@@ -552,15 +569,29 @@ impl Protocol {
 fn make_error_response<R>(error: Error) -> BackendMessage<R> {
     let sqlstate = match error {
         Error::AuthenticationFailure(_) => SqlState::INVALID_PASSWORD,
+        Error::CheckViolation(_) => SqlState::CHECK_VIOLATION,
         Error::DecodeError(_) => SqlState::IO_ERROR,
+        Error::DuplicateTable(_) => SqlState::DUPLICATE_TABLE,
         Error::EncodeError(_) => SqlState::IO_ERROR,
+        Error::GroupingError(_) => SqlState::GROUPING_ERROR,
         Error::IncorrectFormatCount(_) => SqlState::IO_ERROR,
+        Error::InsufficientPrivilege(_) => SqlState::INSUFFICIENT_PRIVILEGE,
+        Error::InsufficientResources(_) => SqlState::INSUFFICIENT_RESOURCES,
         Error::InternalError(_) => SqlState::INTERNAL_ERROR,
         Error::InvalidInteger(_) => SqlState::DATATYPE_MISMATCH,
         Error::IoError(_) => SqlState::IO_ERROR,
         Error::MissingPortal(_) => SqlState::UNDEFINED_PSTATEMENT,
         Error::MissingPreparedStatement(_) => SqlState::UNDEFINED_PSTATEMENT,
+        Error::NotNullViolation(_) => SqlState::NOT_NULL_VIOLATION,
         Error::ParseError(_) => SqlState::INVALID_PSTATEMENT_DEFINITION,
+        Error::ProgramLimitExceeded(_) => SqlState::PROGRAM_LIMIT_EXCEEDED,
+        Error::QueryCanceled(_) => SqlState::QUERY_CANCELED,
+        Error::SyntaxError(_) => SqlState::SYNTAX_ERROR,
+        Error::TooManyConnections(_) => SqlState::TOO_MANY_CONNECTIONS,
+        Error::UndefinedColumn(_) => SqlState::UNDEFINED_COLUMN,
+        Error::UndefinedFunction(_) => SqlState::UNDEFINED_FUNCTION,
+        Error::UndefinedTable(_) => SqlState::UNDEFINED_TABLE,
+        Error::UniqueViolation(_) => SqlState::UNIQUE_VIOLATION,
         Error::Unimplemented(_) => SqlState::FEATURE_NOT_SUPPORTED,
         Error::Unknown(_) => SqlState::INTERNAL_ERROR,
         Error::Unsupported(_) => SqlState::FEATURE_NOT_SUPPORTED,
@@ -575,6 +606,13 @@ fn make_error_response<R>(error: Error) -> BackendMessage<R> {
     }
 }
 
+fn notice_response<R>(message: String) -> BackendMessage<R> {
+    NoticeResponse {
+        sqlstate: SqlState::WARNING,
+        message,
+    }
+}
+
 async fn load_extended_types<B: Backend>(backend: &mut B) -> Result<HashMap<Oid, i16>, Error> {
     let err = |m| {
         Error::InternalError(format!(
@@ -853,6 +891,7 @@ mod tests {
                         vec![Value(DataValue::Int(88)), Value(DataValue::Double(0.123))],
                         vec![Value(DataValue::Int(22)), Value(DataValue::Double(0.456))],
                     ],
+                    warning: None,
                 })
             } else {
                 Ok(QueryResponse::Delete(5))
@@ -906,6 +945,7 @@ mod tests {
                         vec![Value(DataValue::Int(88)), Value(DataValue::Double(0.123))],
                         vec![Value(DataValue::Int(22)), Value(DataValue::Double(0.456))],
                     ],
+                    warning: None,
                 })
             } else {
                 Ok(QueryResponse::Delete(5))
@@ -1216,6 +1256,7 @@ mod tests {
         assert_eq!(
             block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap(),
             Response::Select {
+                notice: None,
                 header: Some(RowDescription {
                     field_descriptions: vec![
                         FieldDescription {
@@ -1894,6 +1935,7 @@ mod tests {
         assert_eq!(
             block_on(protocol.on_request(request, &mut backend, &mut channel)).unwrap(),
             Response::Select {
+                notice: None,
                 header: None,
                 resultset: vec![
                     vec![Value(DataValue::Int(88)), Value(DataValue::Double(0.123))],
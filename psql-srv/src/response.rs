@@ -23,6 +23,7 @@ pub enum Response<R, S> {
     /// `Select` is the most complex variant, containing data rows to be sent to the frontend in
     /// response to a select query.
     Select {
+        notice: Option<BackendMessage<R>>,
         header: Option<BackendMessage<R>>,
         resultset: S,
         result_transfer_formats: Option<Arc<Vec<TransferFormat>>>,
@@ -58,11 +59,16 @@ where
             }
 
             Select {
+                notice,
                 header,
                 resultset,
                 result_transfer_formats,
                 trailer,
             } => {
+                if let Some(notice) = notice {
+                    sink.feed(notice).await?;
+                }
+
                 if let Some(header) = header {
                     sink.feed(header).await?;
                 }
@@ -173,6 +179,7 @@ mod tests {
             resultset: vec![],
             result_transfer_formats: None,
             trailer: None,
+            notice: None,
         };
         let validating_sink = sink::unfold(0, |i, m: BackendMessage<Vec<Value>>| {
             async move {
@@ -208,6 +215,7 @@ mod tests {
                 TransferFormat::Binary,
             ])),
             trailer: Some(BackendMessage::ready_for_query_idle()),
+            notice: None,
         };
         let validating_sink = sink::unfold(0, |i, m: BackendMessage<Vec<Value>>| {
             async move {
@@ -55,6 +55,7 @@ impl Backend for ErrorBackend {
             Ok(QueryResponse::Select {
                 schema: vec![],
                 resultset: vec![],
+                warning: None,
             })
         }
     }
@@ -87,10 +88,12 @@ impl Backend for ErrorBackend {
                     col_type: Type::BOOL,
                 }],
                 resultset: vec![vec![Value(Err(Error::InternalError("factory".to_owned())))]],
+                warning: None,
             }),
             _ => Ok(QueryResponse::Select {
                 schema: vec![],
                 resultset: vec![],
+                warning: None,
             }),
         }
     }
@@ -22,9 +22,11 @@ impl FromStr for DistributionAnnotation {
     type Err = anyhow::Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut chunks = s.split_ascii_whitespace();
+        let s = s.trim();
+        let (kind, rest) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+        let mut chunks = rest.split_ascii_whitespace();
 
-        let spec = match chunks.next().unwrap().to_ascii_lowercase().as_str() {
+        let spec = match kind.to_ascii_lowercase().as_str() {
             "uniform" => {
                 let from: i64 = chunks.next().unwrap().parse().unwrap();
                 let to: i64 = chunks.next().unwrap().parse().unwrap();
@@ -41,7 +43,18 @@ impl FromStr for DistributionAnnotation {
                 }
             }
             "regex" => {
-                let regex = chunks.next().unwrap().trim_matches('"');
+                // A quoted regex may contain whitespace, so take everything up to the closing quote
+                let regex = match rest
+                    .trim_start()
+                    .strip_prefix('"')
+                    .and_then(|quoted| quoted.split_once('"'))
+                {
+                    Some((regex, remainder)) => {
+                        chunks = remainder.split_ascii_whitespace();
+                        regex
+                    }
+                    None => chunks.next().unwrap(),
+                };
                 ColumnGenerationSpec::RandomString(regex.to_owned())
             }
             // Creates unique groups of size `num`.
@@ -89,6 +102,23 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn parse_regex_spec_with_whitespace() {
+        let q = r#"REGEX "[A-Za-z ]{10,60}" UNIQUE"#;
+        let s = q.parse::<DistributionAnnotation>().unwrap();
+        assert!(s.unique);
+        assert!(
+            matches!(s.spec, ColumnGenerationSpec::RandomString(regex) if regex == "[A-Za-z ]{10,60}")
+        );
+
+        let q = "REGEX [a-z]{3,20}";
+        let s = q.parse::<DistributionAnnotation>().unwrap();
+        assert!(!s.unique);
+        assert!(
+            matches!(s.spec, ColumnGenerationSpec::RandomString(regex) if regex == "[a-z]{3,20}")
+        );
+    }
+
     #[test]
     fn parse_constant_spec() {
         let q = "constant 5";
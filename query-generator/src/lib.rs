@@ -140,7 +140,7 @@ fn value_of_type(typ: &SqlType) -> DfValue {
         SqlType::UnsignedBigInt(_) | SqlType::BigSerial => 1u64.into(),
         SqlType::TinyInt(_) => 1i8.into(),
         SqlType::UnsignedTinyInt(_) => 1u8.into(),
-        SqlType::SmallInt(_) => 1i16.into(),
+        SqlType::SmallInt(_) | SqlType::SmallSerial => 1i16.into(),
         SqlType::UnsignedSmallInt(_) => 1u16.into(),
         SqlType::Double | SqlType::Float | SqlType::Real | SqlType::Decimal(_, _) => {
             1.5.try_into().unwrap()
@@ -154,7 +154,7 @@ fn value_of_type(typ: &SqlType) -> DfValue {
                 .ymd(2020, 1, 1)
                 .and_hms(12, 30, 45),
         ),
-        SqlType::Time => NaiveTime::from_hms(12, 30, 45).into(),
+        SqlType::Time(_) => NaiveTime::from_hms(12, 30, 45).into(),
         SqlType::Date => NaiveDate::from_ymd(2020, 1, 1).into(),
         SqlType::Bool => 1i32.into(),
         SqlType::Enum(_) => unimplemented!(),
@@ -250,7 +250,7 @@ fn random_value_of_type(typ: &SqlType) -> DfValue {
                 .ymd(2020, rng.gen_range(1..12), rng.gen_range(1..28))
                 .and_hms(12, 30, 45),
         ),
-        SqlType::Time => NaiveTime::from_hms(12, 30, 45).into(),
+        SqlType::Time(_) => NaiveTime::from_hms(12, 30, 45).into(),
         SqlType::Date => {
             NaiveDate::from_ymd(2020, rng.gen_range(1..12), rng.gen_range(1..28)).into()
         }
@@ -296,6 +296,7 @@ fn random_value_of_type(typ: &SqlType) -> DfValue {
                     .collect::<Vec<bool>>(),
             ))
         }
+        SqlType::SmallSerial => rng.gen_range(1..=i16::MAX).into(),
         SqlType::Serial => (rng.gen::<u32>() + 1).into(),
         SqlType::BigSerial => (rng.gen::<u64>() + 1).into(),
         SqlType::Array(_) => unimplemented!(),
@@ -366,7 +367,7 @@ fn unique_value_of_type(typ: &SqlType, idx: u32) -> DfValue {
         SqlType::Enum(_) => unimplemented!(),
         SqlType::Bool => unimplemented!(),
         SqlType::ByteArray => unimplemented!(),
-        SqlType::Time => NaiveTime::from_hms(12, idx as _, 30).into(),
+        SqlType::Time(_) => NaiveTime::from_hms(12, idx as _, 30).into(),
         SqlType::Json | SqlType::Jsonb => DfValue::from(format!("{{\"k\": {}}}", idx)),
         SqlType::MacAddr => {
             let b1: u8 = ((idx >> 24) & 0xff) as u8;
@@ -409,6 +410,7 @@ fn unique_value_of_type(typ: &SqlType, idx: u32) -> DfValue {
             bytes[3] = (idx & 0xff) as u8;
             DfValue::from(BitVec::from_bytes(&bytes[..]))
         }
+        SqlType::SmallSerial => ((idx + 1) as i16).into(),
         SqlType::Serial => (idx + 1).into(),
         SqlType::BigSerial => ((idx + 1) as u64).into(),
         SqlType::Array(_) => unimplemented!(),
@@ -2449,9 +2451,11 @@ impl QueryOperation {
                     BuiltinFunction::IfNull => add_builtin!(ifnull(SqlType::Text, SqlType::Text)),
                     BuiltinFunction::Month => add_builtin!(month(SqlType::Date)),
                     BuiltinFunction::Timediff => {
-                        add_builtin!(timediff(SqlType::Time, SqlType::Time))
+                        add_builtin!(timediff(SqlType::Time(None), SqlType::Time(None)))
+                    }
+                    BuiltinFunction::Addtime => {
+                        add_builtin!(addtime(SqlType::Time(None), SqlType::Time(None)))
                     }
-                    BuiltinFunction::Addtime => add_builtin!(addtime(SqlType::Time, SqlType::Time)),
                     BuiltinFunction::Round => add_builtin!(round(SqlType::Real)),
                 }
             }
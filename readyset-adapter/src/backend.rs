@@ -54,6 +54,11 @@
 //! with an Unsupported ReadySetError. These queries should not be tried again against ReadySet,
 //! however, if a fallback database exists, may be executed against the fallback.
 //!
+//! The controller also remembers which queries it has found to be unsupported, so that they aren't
+//! planned again by other adapters or after a restart until the planner is upgraded. These queries
+//! can be listed with `SHOW UNSUPPORTED QUERIES`, along with the [`UnsupportedFeature`] that made
+//! them unsupported where the planner knows it, which is also shown by `EXPLAIN LAST STATEMENT`.
+//!
 //! ## Handling component outage
 //!
 //! In a distributed deployment, a component (such as a readyset-server instance) may go down,
@@ -80,19 +85,22 @@ use std::time::{Duration, Instant};
 use futures::future::{self, OptionFuture};
 use mysql_common::row::convert::{FromRow, FromRowError};
 use nom_sql::{
-    CacheInner, CreateCacheStatement, DeleteStatement, Dialect, DropCacheStatement,
-    InsertStatement, Relation, SelectStatement, SetStatement, ShowStatement, SqlIdentifier,
-    SqlQuery, UpdateStatement, UseStatement,
+    AlterReadysetStatement, CacheInner, CreateCacheStatement, DeleteStatement, Dialect,
+    DropCacheStatement, Expr, FieldDefinitionExpr, FilterPredicate, FunctionExpr, InsertStatement,
+    KillKind, KillStatement, Relation, SelectStatement, SetStatement, SetVariables, ShowStatement,
+    ShowVariables, SqlIdentifier, SqlQuery, TableExpr, TableExprInner, UpdateStatement,
+    UseStatement,
 };
 use readyset_client::consistency::Timestamp;
 use readyset_client::query::*;
+use readyset_client::recipe::changelist::CacheOptions;
 use readyset_client::results::Results;
 use readyset_client::{ColumnSchema, ViewCreateRequest};
 pub use readyset_client_metrics::QueryDestination;
 use readyset_client_metrics::{recorded, EventType, QueryExecutionEvent, SqlQueryType};
 use readyset_data::{DfType, DfValue};
 use readyset_errors::ReadySetError::{self, PreparedStatementMissing};
-use readyset_errors::{internal, internal_err, unsupported, ReadySetResult};
+use readyset_errors::{internal, internal_err, unsupported, ReadySetResult, UnsupportedFeature};
 use readyset_telemetry_reporter::{TelemetryBuilder, TelemetryEvent, TelemetrySender};
 use readyset_tracing::{error, instrument_root, trace, warn};
 use readyset_util::redacted::Sensitive;
@@ -102,11 +110,16 @@ use tokio::sync::mpsc::UnboundedSender;
 use tracing::instrument;
 
 use crate::backend::noria_connector::ExecuteSelectContext;
+use crate::connections::{ActiveCommand, ConnectionHandle};
+use crate::fallback_limiter::FallbackLimiter;
+use crate::hints::QueryHints;
 use crate::query_handler::SetBehavior;
 use crate::query_status_cache::QueryStatusCache;
+use crate::readyset_variables::{self, ReadySetSettings};
+use crate::slow_read_log::SlowReadLog;
 use crate::upstream_database::NoriaCompare;
 pub use crate::upstream_database::UpstreamPrepare;
-use crate::{rewrite, QueryHandler, UpstreamDatabase, UpstreamDestination};
+use crate::{information_schema, rewrite, QueryHandler, UpstreamDatabase, UpstreamDestination};
 
 pub mod noria_connector;
 
@@ -255,6 +268,7 @@ pub struct BackendBuilder {
     dialect: Dialect,
     users: HashMap<String, String>,
     require_authentication: bool,
+    admin_user: Option<String>,
     ticket: Option<Timestamp>,
     timestamp_client: Option<TimestampClient>,
     query_log_sender: Option<UnboundedSender<QueryExecutionEvent>>,
@@ -266,6 +280,9 @@ pub struct BackendBuilder {
     query_max_failure_seconds: u64,
     fallback_recovery_seconds: u64,
     telemetry_sender: Option<TelemetrySender>,
+    fallback_limiter: Option<Arc<FallbackLimiter>>,
+    connection: Option<ConnectionHandle>,
+    slow_read_log: Option<Arc<SlowReadLog>>,
 }
 
 impl Default for BackendBuilder {
@@ -275,6 +292,7 @@ impl Default for BackendBuilder {
             dialect: Dialect::MySQL,
             users: Default::default(),
             require_authentication: true,
+            admin_user: None,
             ticket: None,
             timestamp_client: None,
             query_log_sender: None,
@@ -286,6 +304,9 @@ impl Default for BackendBuilder {
             query_max_failure_seconds: (i64::MAX / 1000) as u64,
             fallback_recovery_seconds: 0,
             telemetry_sender: None,
+            fallback_limiter: None,
+            connection: None,
+            slow_read_log: None,
         }
     }
 }
@@ -322,11 +343,15 @@ impl BackendBuilder {
                 query_status_cache,
                 ticket: self.ticket,
                 timestamp_client: self.timestamp_client,
+                fallback_limiter: self.fallback_limiter,
+                connection: self.connection,
+                user: None,
             },
             settings: BackendSettings {
                 slowlog: self.slowlog,
                 dialect: self.dialect,
                 require_authentication: self.require_authentication,
+                admin_user: self.admin_user,
                 validate_queries: self.validate_queries,
                 fail_invalidated_queries: self.fail_invalidated_queries,
                 unsupported_set_mode: self.unsupported_set_mode,
@@ -334,6 +359,7 @@ impl BackendBuilder {
                 query_max_failure_duration: Duration::new(self.query_max_failure_seconds, 0),
                 query_log_ad_hoc_queries: self.query_log_ad_hoc_queries,
                 fallback_recovery_duration: Duration::new(self.fallback_recovery_seconds, 0),
+                slow_read_log: self.slow_read_log,
             },
             telemetry_sender: self.telemetry_sender,
             _query_handler: PhantomData,
@@ -345,6 +371,12 @@ impl BackendBuilder {
         self
     }
 
+    /// Log reads from caches which take longer than the threshold of `slow_read_log` to it
+    pub fn slow_read_log(mut self, slow_read_log: Option<Arc<SlowReadLog>>) -> Self {
+        self.slow_read_log = slow_read_log;
+        self
+    }
+
     pub fn dialect(mut self, dialect: Dialect) -> Self {
         self.dialect = dialect;
         self
@@ -370,6 +402,13 @@ impl BackendBuilder {
         self
     }
 
+    /// The user who is allowed to run administrative statements, such as `SET GLOBAL
+    /// readyset.*`, if authentication is required. If `None`, no user is allowed to run them.
+    pub fn admin_user(mut self, admin_user: Option<String>) -> Self {
+        self.admin_user = admin_user;
+        self
+    }
+
     /// Specifies whether RYW consistency should be enabled. If true, RYW consistency
     /// constraints will be enforced on all reads.
     pub fn enable_ryw(mut self, enable_ryw: bool) -> Self {
@@ -415,6 +454,21 @@ impl BackendBuilder {
         self.telemetry_sender = Some(telemetry_sender);
         self
     }
+
+    /// Limit the reads proxied to the upstream database using the given [`FallbackLimiter`], which
+    /// should be shared between all the backends of an adapter.
+    pub fn fallback_limiter(mut self, fallback_limiter: Arc<FallbackLimiter>) -> Self {
+        self.fallback_limiter = Some(fallback_limiter);
+        self
+    }
+
+    /// Track the activity of the backend on the given connection, and allow it to kill other
+    /// connections registered with the same
+    /// [`ConnectionRegistry`](crate::connections::ConnectionRegistry)
+    pub fn connection(mut self, connection: ConnectionHandle) -> Self {
+        self.connection = Some(connection);
+        self
+    }
 }
 
 /// A [`CachedPreparedStatement`] stores the data needed for an immediate
@@ -512,7 +566,7 @@ where
     /// A cache of queries that we've seen, and their current state, used for processing
     query_status_cache: &'static QueryStatusCache,
     // a cache of all previously parsed queries
-    parsed_query_cache: HashMap<String, SqlQuery>,
+    parsed_query_cache: HashMap<String, (SqlQuery, QueryHints)>,
     // all queries previously prepared on noria or upstream, mapped by their ID.
     prepared_statements: Vec<CachedPreparedStatement<DB>>,
     /// Current RYW ticket. `None` if RYW is not enabled. This `ticket` will
@@ -524,6 +578,30 @@ where
     /// is responsible for creating accurate RYW timestamps/tickets based on writes made by the
     /// Backend client.
     timestamp_client: Option<TimestampClient>,
+    /// Limits on the reads proxied to the upstream database, shared with all other backends
+    fallback_limiter: Option<Arc<FallbackLimiter>>,
+    /// The client connection this backend is serving, if connections are being tracked
+    connection: Option<ConnectionHandle>,
+    /// The user the client authenticated as, if it has authenticated
+    user: Option<String>,
+}
+
+impl<DB> BackendState<DB>
+where
+    DB: UpstreamDatabase,
+{
+    /// Returns the limiter that reads proxied to the upstream database because they failed (or
+    /// can't be run) against ReadySet are subject to, if any.
+    ///
+    /// Reads which are proxied because of the proxy state (eg because they're in a transaction)
+    /// can't be served anywhere else, so aren't limited.
+    fn fallback_limiter(&self) -> Option<&FallbackLimiter> {
+        if self.proxy_state.should_proxy() {
+            None
+        } else {
+            self.fallback_limiter.as_deref()
+        }
+    }
 }
 
 /// Settings that have no state and are constant for a given [`Backend`]
@@ -532,6 +610,8 @@ struct BackendSettings {
     dialect: Dialect,
     slowlog: bool,
     require_authentication: bool,
+    /// The user who is allowed to run administrative statements, if authentication is required
+    admin_user: Option<String>,
     /// Whether to log ad-hoc queries by full query text in the query logger.
     query_log_ad_hoc_queries: bool,
     /// Run select statements with query validation.
@@ -547,6 +627,8 @@ struct BackendSettings {
     /// repeatedly failed for query_max_failure_duration.
     fallback_recovery_duration: Duration,
     fail_invalidated_queries: bool,
+    /// Where to log reads from caches which take longer than a threshold, if anywhere
+    slow_read_log: Option<Arc<SlowReadLog>>,
 }
 
 /// QueryInfo holds information regarding the last query that was sent along this connection
@@ -555,6 +637,8 @@ struct BackendSettings {
 pub struct QueryInfo {
     pub destination: QueryDestination,
     pub noria_error: String,
+    /// The SQL feature that prevented ReadySet from executing the query, if known
+    pub unsupported_feature: Option<UnsupportedFeature>,
 }
 
 impl FromRow for QueryInfo {
@@ -573,6 +657,11 @@ impl FromRow for QueryInfo {
                     res.noria_error = std::str::from_utf8(d)
                         .map_err(|_| FromRowError(row.clone()))?
                         .to_string();
+                } else if c.name_str() == "Unsupported_feature" {
+                    res.unsupported_feature = match dest {
+                        "none" => None,
+                        feature => Some(feature.parse().map_err(|_| FromRowError(row.clone()))?),
+                    };
                 } else {
                     return Err(FromRowError(row.clone()));
                 }
@@ -725,6 +814,11 @@ where
             .unwrap_or_else(|| DB::DEFAULT_DB_VERSION.to_string())
     }
 
+    /// The id of the client connection this backend is serving, if connections are being tracked
+    pub fn connection_id(&self) -> Option<u32> {
+        self.state.connection.as_ref().map(ConnectionHandle::id)
+    }
+
     /// The identifier of the last prepared statement (which is always the last in the vector)
     pub fn last_prepared_id(&self) -> u32 {
         (self.state.prepared_statements.len() - 1)
@@ -761,11 +855,14 @@ where
     /// Executes query on the upstream database, for when it cannot be parsed or executed by noria.
     /// Returns the query result, or an error if fallback is not configured
     #[instrument_root(level = "info")]
-    pub async fn query_fallback<'a>(
+    pub async fn query_fallback<'a, S>(
         upstream: Option<&'a mut DB>,
-        query: &'a str,
+        query: S,
         event: &mut QueryExecutionEvent,
-    ) -> Result<QueryResult<'a, DB>, DB::Error> {
+    ) -> Result<QueryResult<'a, DB>, DB::Error>
+    where
+        S: AsRef<str> + Send + Sync + 'a,
+    {
         let upstream = upstream.ok_or_else(|| {
             ReadySetError::Internal("This case requires an upstream connector".to_string())
         })?;
@@ -779,6 +876,25 @@ where
         result.map(QueryResult::Upstream)
     }
 
+    /// Executes a read on the upstream database, for when it can't be executed by ReadySet, subject
+    /// to the limits of the given [`FallbackLimiter`], if any
+    async fn query_fallback_read<'a>(
+        upstream: Option<&'a mut DB>,
+        fallback_limiter: Option<&FallbackLimiter>,
+        query: &'a str,
+        event: &mut QueryExecutionEvent,
+    ) -> Result<QueryResult<'a, DB>, DB::Error> {
+        let permit = match fallback_limiter {
+            Some(limiter) => Some(limiter.acquire().await?),
+            None => None,
+        };
+        let result = Self::query_fallback(upstream, query, event).await;
+        if let Some(permit) = permit {
+            permit.record(&result);
+        }
+        result
+    }
+
     /// Prepares query on the mysql_backend, if present, when it cannot be parsed or prepared by
     /// noria.
     pub async fn prepare_fallback(
@@ -829,6 +945,7 @@ where
         self.last_query = destination.map(|d| QueryInfo {
             destination: d,
             noria_error: String::new(),
+            unsupported_feature: None,
         });
 
         // Update noria migration state for query
@@ -946,6 +1063,7 @@ where
             self.last_query = Some(QueryInfo {
                 destination: QueryDestination::Upstream,
                 noria_error: String::new(),
+                unsupported_feature: None,
             });
             res
         } else {
@@ -960,6 +1078,7 @@ where
             self.last_query = Some(QueryInfo {
                 destination: QueryDestination::Readyset,
                 noria_error: String::new(),
+                unsupported_feature: None,
             });
             Ok(PrepareResult::Noria(res))
         }
@@ -1013,7 +1132,7 @@ where
         {
             None
         } else {
-            let should_do_noria = self
+            let should_do_noria = match self
                 .state
                 .query_status_cache
                 .query_migration_state(&ViewCreateRequest::new(
@@ -1021,7 +1140,12 @@ where
                     self.noria.schema_search_path().to_owned(),
                 ))
                 .1
-                != MigrationState::Unsupported;
+            {
+                MigrationState::Unsupported => false,
+                // Warming caches are only read from once their backfill completes
+                MigrationState::Warming => !self.has_fallback(),
+                _ => true,
+            };
             Some((rewritten, should_do_noria))
         }
     }
@@ -1078,6 +1202,7 @@ where
                 self.last_query = Some(QueryInfo {
                     destination: QueryDestination::Upstream,
                     noria_error: String::new(),
+                    unsupported_feature: None,
                 });
 
                 res
@@ -1095,6 +1220,7 @@ where
     /// to the calling struct's map of prepared queries with a unique id.
     #[instrument_root(level = "info")]
     pub async fn prepare(&mut self, query: &str) -> Result<&PrepareResult<DB>, DB::Error> {
+        let _active = self.start_command();
         self.last_query = None;
         let mut query_event = QueryExecutionEvent::new(EventType::Prepare);
 
@@ -1194,9 +1320,13 @@ where
         res
     }
 
-    /// Execute a prepared statement on ReadySet
+    /// Execute a prepared statement on the upstream database.
+    ///
+    /// If a [`FallbackLimiter`] is passed, the statement must be a read, and is subject to its
+    /// limits.
     async fn execute_upstream<'a>(
         upstream: &'a mut Option<DB>,
+        fallback_limiter: Option<&FallbackLimiter>,
         prep: &UpstreamPrepare<DB>,
         params: &[DfValue],
         event: &mut QueryExecutionEvent,
@@ -1212,12 +1342,21 @@ where
             event.destination = Some(QueryDestination::Upstream);
         }
 
+        let permit = match fallback_limiter {
+            Some(limiter) => Some(limiter.acquire().await?),
+            None => None,
+        };
+
         let _t = event.start_upstream_timer();
 
-        upstream
+        let result = upstream
             .execute(prep.statement_id, params)
             .await
-            .map(|r| QueryResult::Upstream(r))
+            .map(|r| QueryResult::Upstream(r));
+        if let Some(permit) = permit {
+            permit.record(&result);
+        }
+        result
     }
 
     /// Execute on ReadySet, and if fails execute on upstream
@@ -1225,6 +1364,7 @@ where
     async fn execute_cascade<'a>(
         noria: &'a mut NoriaConnector,
         upstream: &'a mut Option<DB>,
+        fallback_limiter: Option<&FallbackLimiter>,
         noria_prep: &noria_connector::PrepareResult,
         upstream_prep: &UpstreamPrepare<DB>,
         params: &[DfValue],
@@ -1251,15 +1391,35 @@ where
                         info.execute_unsupported();
                     }
                 }
+                // The cache doesn't allow reads to be proxied while replication lags
+                if matches!(
+                    noria_err,
+                    ReadySetError::ReplicationLagExceeded { proxy: false, .. }
+                ) {
+                    return Err(noria_err.into());
+                }
                 if !matches!(
                     noria_err,
-                    ReadySetError::ReaderMissingKey | ReadySetError::NoCacheForQuery
+                    ReadySetError::ReaderMissingKey
+                        | ReadySetError::NoCacheForQuery
+                        | ReadySetError::UnprefixedLikePattern(_)
+                        | ReadySetError::NamespaceQuotaExceeded { .. }
+                        | ReadySetError::ReplicationLagExceeded { .. }
+                        | ReadySetError::ReplicationLagUnknown { .. }
                 ) {
                     warn!(error = %noria_err,
                           "Error received from noria, sending query to fallback");
                 }
 
-                Self::execute_upstream(upstream, upstream_prep, params, event, true).await
+                Self::execute_upstream(
+                    upstream,
+                    fallback_limiter,
+                    upstream_prep,
+                    params,
+                    event,
+                    true,
+                )
+                .await
             }
         }
     }
@@ -1271,13 +1431,15 @@ where
     /// # Panics
     ///
     /// If the cached entry is not of kind `PrepareResult::Upstream` or is not in the
-    /// `MigrationState::Pending` state
+    /// `MigrationState::Pending` or `MigrationState::Warming` state
     async fn update_noria_prepare(
         noria: &mut NoriaConnector,
         cached_entry: &mut CachedPreparedStatement<DB>,
         id: u32,
     ) -> ReadySetResult<()> {
-        debug_assert!(cached_entry.migration_state.is_pending());
+        debug_assert!(
+            cached_entry.migration_state.is_pending() || cached_entry.migration_state.is_warming()
+        );
 
         let upstream_prep: UpstreamPrepare<DB> = match &cached_entry.prep {
             PrepareResult::Upstream(UpstreamPrepare { statement_id, meta }) => UpstreamPrepare {
@@ -1356,6 +1518,7 @@ where
         id: u32,
         params: &[DfValue],
     ) -> Result<QueryResult<'_, DB>, DB::Error> {
+        let _active = self.start_command();
         self.last_query = None;
         let cached_statement = self
             .state
@@ -1364,16 +1527,25 @@ where
             .ok_or(PreparedStatementMissing { statement_id: id })?;
 
         let mut event = QueryExecutionEvent::new(EventType::Execute);
+        event.record_keys = self.settings.slow_read_log.is_some();
         event.query = cached_statement.parsed_query.clone();
         event.query_id = cached_statement.query_id;
 
         let upstream = &mut self.upstream;
         let noria = &mut self.noria;
         let ticket = self.state.ticket.clone();
+        // Not using `BackendState::fallback_limiter`, since `cached_statement` borrows the state
+        let fallback_limiter = self
+            .state
+            .fallback_limiter
+            .as_deref()
+            .filter(|_| !self.state.proxy_state.should_proxy());
 
-        if cached_statement.migration_state.is_pending() {
-            // We got a statement with a pending migration, we want to check if migration is
-            // finished by now
+        if cached_statement.migration_state.is_pending()
+            || cached_statement.migration_state.is_warming()
+        {
+            // We got a statement with a pending migration (or a cache that's still warming), we
+            // want to check if migration is finished by now
             let new_migration_state = self
                 .state
                 .query_status_cache
@@ -1423,10 +1595,28 @@ where
                     .map_err(Into::into)
             }
             PrepareResult::Upstream(prep) => {
-                Self::execute_upstream(upstream, prep, params, &mut event, false).await
+                // Statements only prepared upstream may be writes, which are never limited, so
+                // only limit the ones we know are reads
+                let is_read = matches!(
+                    cached_statement.parsed_query.as_deref(),
+                    Some(SqlQuery::Select(_))
+                );
+                if !is_read {
+                    noria.invalidate_micro_cache();
+                }
+                Self::execute_upstream(
+                    upstream,
+                    fallback_limiter.filter(|_| is_read),
+                    prep,
+                    params,
+                    &mut event,
+                    false,
+                )
+                .await
             }
             PrepareResult::Both(.., uprep) if should_fallback => {
-                Self::execute_upstream(upstream, uprep, params, &mut event, false).await
+                Self::execute_upstream(upstream, fallback_limiter, uprep, params, &mut event, false)
+                    .await
             }
             PrepareResult::Both(nprep, uprep) => {
                 if cached_statement.execution_info.is_none() {
@@ -1438,6 +1628,7 @@ where
                 Self::execute_cascade(
                     noria,
                     upstream,
+                    fallback_limiter,
                     nprep,
                     uprep,
                     params,
@@ -1473,8 +1664,17 @@ where
                 .as_ref()
                 .map(|e| e.to_string())
                 .unwrap_or_default(),
+            unsupported_feature: event
+                .noria_error
+                .as_ref()
+                .and_then(|e| e.unsupported_feature()),
         });
-        log_query(self.query_log_sender.as_ref(), event, self.settings.slowlog);
+        log_query(
+            self.query_log_sender.as_ref(),
+            event,
+            self.settings.slowlog,
+            self.settings.slow_read_log.as_deref(),
+        );
 
         result
     }
@@ -1519,9 +1719,72 @@ where
         }
     }
 
+    /// Record that this backend's connection has started executing a statement, if connections
+    /// are being tracked. See [`ConnectionHandle::start_command`].
+    fn start_command(&self) -> Option<ActiveCommand> {
+        let connection = self.state.connection.as_ref()?;
+        // The upstream connection is replaced if it's reset, so keep the registry up to date
+        connection.set_upstream_id(self.upstream.as_ref().and_then(DB::connection_id));
+        Some(connection.start_command())
+    }
+
+    /// Returns the id of the connection a `KILL` statement with the given id refers to, or an error
+    /// if there's no such connection or the client isn't allowed to kill it. Like MySQL, only the
+    /// admin user can kill the connections of other users.
+    fn kill_target(&self, connection: &ConnectionHandle, id: u64) -> ReadySetResult<u32> {
+        let registry = connection.registry();
+        let id = u32::try_from(id)
+            .ok()
+            .filter(|id| registry.contains(*id))
+            .ok_or_else(|| ReadySetError::BadRequest(format!("Unknown thread id: {id}")))?;
+        if self.require_admin("KILL").is_err() && registry.user(id) != self.state.user {
+            return Err(ReadySetError::AccessDenied(format!(
+                "You are not owner of thread {id}"
+            )));
+        }
+        Ok(id)
+    }
+
+    /// Handles a `KILL` statement by killing the connection with the given id, which must be
+    /// registered with the same [`ConnectionRegistry`](crate::connections::ConnectionRegistry) as
+    /// this backend's connection.
+    ///
+    /// `KILL QUERY` is only handled here if there's no upstream database; otherwise it's forwarded
+    /// upstream, with [`Backend::upstream_kill_query`].
+    fn kill(&self, kill: &KillStatement) -> ReadySetResult<noria_connector::QueryResult<'static>> {
+        let Some(connection) = &self.state.connection else {
+            unsupported!("KILL is not supported without connection tracking");
+        };
+        if kill.kind == KillKind::Query {
+            unsupported!("KILL QUERY requires an upstream database");
+        }
+
+        let id = self.kill_target(connection, kill.id)?;
+        connection.registry().kill(id);
+        Ok(noria_connector::QueryResult::Empty)
+    }
+
+    /// Returns the `KILL QUERY` statement to run against the upstream database to interrupt the
+    /// statement being executed by the connection with the given id, which is sent to the upstream
+    /// connection used by that connection
+    fn upstream_kill_query(&self, id: u64) -> ReadySetResult<String> {
+        let Some(connection) = &self.state.connection else {
+            unsupported!("KILL is not supported without connection tracking");
+        };
+        let id = self.kill_target(connection, id)?;
+        let Some(upstream_id) = connection.registry().upstream_id(id) else {
+            unsupported!("Connection {id} has no upstream connection to kill a query on");
+        };
+        Ok(KillStatement {
+            kind: KillKind::Query,
+            id: upstream_id.into(),
+        }
+        .to_string())
+    }
+
     /// Generates response to the `EXPLAIN LAST STATEMENT` query
     fn explain_last_statement(&self) -> ReadySetResult<noria_connector::QueryResult<'static>> {
-        let (destination, error) = self
+        let (destination, error, unsupported_feature) = self
             .last_query
             .as_ref()
             .map(|info| {
@@ -1531,13 +1794,17 @@ where
                         s if s.is_empty() => "ok".to_string(),
                         s => s.clone(),
                     },
+                    info.unsupported_feature
+                        .map_or("none", UnsupportedFeature::name)
+                        .to_string(),
                 )
             })
-            .unwrap_or_else(|| ("unknown".to_string(), "ok".to_string()));
+            .unwrap_or_else(|| ("unknown".to_string(), "ok".to_string(), "none".to_string()));
 
         Ok(noria_connector::QueryResult::Meta(vec![
             ("Query_destination", destination).into(),
             ("ReadySet_error", error).into(),
+            ("Unsupported_feature", unsupported_feature).into(),
         ]))
     }
 
@@ -1547,7 +1814,8 @@ where
         name: Option<&Relation>,
         mut stmt: SelectStatement,
         override_schema_search_path: Option<Vec<SqlIdentifier>>,
-        always: bool,
+        options: CacheOptions,
+        concurrently: bool,
     ) -> ReadySetResult<noria_connector::QueryResult<'static>> {
         // If we have another query with the same name, drop that query first
         if let Some(name) = name {
@@ -1562,8 +1830,17 @@ where
         }
         // Now migrate the new query
         rewrite::process_query(&mut stmt, self.noria.server_supports_pagination())?;
+        let always = options.always;
+        if concurrently {
+            return self.create_cached_query_concurrently(
+                name,
+                stmt,
+                override_schema_search_path,
+                options,
+            );
+        }
         self.noria
-            .handle_create_cached_query(name, &stmt, override_schema_search_path, always)
+            .handle_create_cached_query(name, &stmt, override_schema_search_path, options)
             .await?;
         self.state.query_status_cache.update_query_migration_state(
             &ViewCreateRequest::new(stmt.clone(), self.noria.schema_search_path().to_owned()),
@@ -1576,6 +1853,60 @@ where
         Ok(noria_connector::QueryResult::Empty)
     }
 
+    /// Forwards a `CREATE CACHE CONCURRENTLY` request to noria.
+    ///
+    /// The query is marked as [`MigrationState::Warming`] (so that reads of it keep being proxied
+    /// upstream) and the statement returns immediately, while the migration and the backfill of
+    /// the cache's initial state run in a background task. Once the backfill completes the query
+    /// is marked as [`MigrationState::Successful`].
+    fn create_cached_query_concurrently(
+        &mut self,
+        name: Option<&Relation>,
+        stmt: SelectStatement,
+        override_schema_search_path: Option<Vec<SqlIdentifier>>,
+        options: CacheOptions,
+    ) -> ReadySetResult<noria_connector::QueryResult<'static>> {
+        let always = options.always;
+        let migration = self.noria.create_cached_query_concurrently(
+            name,
+            &stmt,
+            override_schema_search_path,
+            options,
+        )?;
+        let view_request = ViewCreateRequest::new(stmt, self.noria.schema_search_path().to_owned());
+        let query_status_cache = self.state.query_status_cache;
+        query_status_cache.update_query_migration_state(&view_request, MigrationState::Warming);
+
+        tokio::spawn(async move {
+            let state = match migration.await {
+                Ok(()) => MigrationState::Successful,
+                Err(error) => {
+                    warn!(
+                        %error,
+                        statement = %Sensitive(&view_request.statement),
+                        "Failed to create cache concurrently"
+                    );
+                    if error.caused_by_unsupported() {
+                        MigrationState::Unsupported
+                    } else {
+                        MigrationState::Pending
+                    }
+                }
+            };
+
+            // Don't clobber the state if the cache was dropped while it was warming
+            if query_status_cache.query_status(&view_request).is_warming() {
+                query_status_cache.update_query_migration_state(&view_request, state);
+                query_status_cache.always_attempt_readyset(
+                    &view_request,
+                    always && state == MigrationState::Successful,
+                );
+            }
+        });
+
+        Ok(noria_connector::QueryResult::Empty)
+    }
+
     /// Forwards a `DROP CACHE` request to noria
     async fn drop_cached_query(
         &mut self,
@@ -1614,6 +1945,118 @@ where
         Ok(noria_connector::QueryResult::Empty)
     }
 
+    /// Responds to a `SET GLOBAL readyset.<setting> = <value>` statement, by changing the settings
+    /// of this adapter's fallback limiter, and forwarding all other settings to the controller
+    async fn set_readyset_variables(
+        &mut self,
+        set: &SetVariables,
+    ) -> ReadySetResult<noria_connector::QueryResult<'static>> {
+        self.require_admin("SET GLOBAL readyset.*")?;
+        let fallback_limiter = self.state.fallback_limiter.clone();
+        let settings = ReadySetSettings::parse(
+            set,
+            fallback_limiter
+                .as_ref()
+                .map(|limiter| limiter.limits())
+                .unwrap_or_default(),
+        )?;
+        if settings.fallback_limits.is_some() && fallback_limiter.is_none() {
+            return Err(ReadySetError::BadRequest(
+                "This adapter does not limit fallback queries".to_owned(),
+            ));
+        }
+
+        // Apply the changes to the adapter's settings only once the controller has accepted all of
+        // its own, so that an invalid setting doesn't leave some of the statement applied
+        if !settings.controller_settings.is_empty() {
+            let result = self
+                .noria
+                .reload_config(settings.controller_settings)
+                .await?;
+            if !result.requires_restart.is_empty() {
+                return Err(ReadySetError::BadRequest(format!(
+                    "The following settings can only be changed by restarting ReadySet: {}",
+                    result.requires_restart.join(", ")
+                )));
+            }
+        }
+        if let (Some(limits), Some(limiter)) = (settings.fallback_limits, fallback_limiter) {
+            limiter.set_limits(limits);
+        }
+
+        Ok(noria_connector::QueryResult::Empty)
+    }
+
+    /// Responds to a `SHOW VARIABLES LIKE 'readyset.<pattern>'` query
+    async fn show_readyset_variables(
+        &mut self,
+        pattern: &str,
+    ) -> ReadySetResult<noria_connector::QueryResult<'static>> {
+        let controller_settings = self.noria.config_settings().await?;
+        let fallback_limits = self
+            .state
+            .fallback_limiter
+            .as_ref()
+            .map(|limiter| limiter.limits());
+        Ok(noria_connector::QueryResult::MetaVariables(
+            readyset_variables::show_settings(fallback_limits, controller_settings, pattern)
+                .into_iter()
+                .map(MetaVariable::from)
+                .collect(),
+        ))
+    }
+
+    /// Answers a query against `information_schema`, or a `SHOW TABLES`, `SHOW COLUMNS` or `SHOW
+    /// CREATE TABLE` statement, from the schemas of the base tables known to ReadySet. Used when
+    /// there's no upstream database to proxy these queries to.
+    async fn query_information_schema(
+        &mut self,
+        query: &SqlQuery,
+    ) -> ReadySetResult<noria_connector::QueryResult<'static>> {
+        let catalog = self.noria.table_schemas().await?;
+        let database = self.noria.schema_search_path().first().map(|s| s.as_str());
+        let table = match query {
+            SqlQuery::Select(stmt) => information_schema::select(stmt, &catalog, database)?,
+            SqlQuery::Show(ShowStatement::Tables(tables)) => information_schema::show_tables(
+                tables.full,
+                tables.from_db.as_deref(),
+                tables.filter.as_ref(),
+                &catalog,
+                database,
+            )?,
+            SqlQuery::Show(ShowStatement::Columns(columns)) => {
+                information_schema::show_columns(columns, &catalog, database)?
+            }
+            SqlQuery::Show(ShowStatement::CreateTable(table)) => {
+                information_schema::show_create_table(table, &catalog, database)?
+            }
+            _ => internal!("Not a system table query: {query}"),
+        };
+
+        let schema = SelectSchema {
+            use_bogo: false,
+            schema: Cow::Owned(
+                table
+                    .columns
+                    .iter()
+                    .map(|(name, ty)| ColumnSchema {
+                        column: nom_sql::Column {
+                            name: name.clone(),
+                            table: None,
+                        },
+                        column_type: ty.clone(),
+                        base: None,
+                    })
+                    .collect(),
+            ),
+            columns: Cow::Owned(table.columns.into_iter().map(|(name, _)| name).collect()),
+        };
+        Ok(noria_connector::QueryResult::from_owned(
+            schema,
+            vec![Results::new(table.rows)],
+        ))
+    }
+
     /// Responds to a `SHOW PROXIED QUERIES` query
     async fn show_proxied_queries(
         &mut self,
@@ -1653,6 +2096,7 @@ where
                 let s = match status.migration_state {
                     MigrationState::DryRunSucceeded | MigrationState::Successful => "yes",
                     MigrationState::Pending => "pending",
+                    MigrationState::Warming => "warming",
                     MigrationState::Unsupported => "unsupported",
                 }
                 .to_string();
@@ -1688,10 +2132,25 @@ where
             SqlQuery::Explain(nom_sql::ExplainStatement::Graphviz { simplified }) => {
                 self.noria.graphviz(*simplified).await
             }
+            SqlQuery::Explain(nom_sql::ExplainStatement::Plan { cache }) => {
+                self.noria.canonical_plan(cache).await
+            }
+            SqlQuery::Explain(nom_sql::ExplainStatement::MirPlan { cache }) => {
+                self.noria.mir_plan(cache).await
+            }
             SqlQuery::CreateCache(CreateCacheStatement {
                 name,
                 inner,
                 always,
+                filter_pushdown,
+                approximate_aggregates,
+                lazy_joins,
+                reader_placement,
+                max_staleness,
+                bucket_retention,
+                freshness,
+                result_limits,
+                concurrently,
             }) => {
                 let (stmt, search_path) = match inner {
                     CacheInner::Statement(st) => (*st.clone(), None),
@@ -1707,8 +2166,25 @@ where
                                 }))
                             }
                         },
+                        // We haven't seen this query ourselves, but it might have been seen by
+                        // another adapter, or by a previous run (or version) of this one
                         None => {
-                            return Some(Err(ReadySetError::NoQueryForId { id: id.to_string() }))
+                            let query = match id.parse::<QueryId>() {
+                                Ok(query_id) => self.noria.query_for_id(query_id).await,
+                                Err(e) => Err(e),
+                            };
+                            match query {
+                                Ok(Some(view_request)) => (
+                                    view_request.statement,
+                                    Some(view_request.schema_search_path),
+                                ),
+                                Ok(None) => {
+                                    return Some(Err(ReadySetError::NoQueryForId {
+                                        id: id.to_string(),
+                                    }))
+                                }
+                                Err(e) => return Some(Err(e)),
+                            }
                         }
                     },
                 };
@@ -1722,11 +2198,37 @@ where
                     trace!("No telemetry sender. not sending metric for CREATE CACHE");
                 }
 
-                self.create_cached_query(name.as_ref(), stmt, search_path, *always)
+                let options = CacheOptions {
+                    always: *always,
+                    filter_pushdown: *filter_pushdown,
+                    approximate_aggregates: *approximate_aggregates,
+                    lazy_joins: *lazy_joins,
+                    reader_placement: reader_placement.clone(),
+                    max_staleness: *max_staleness,
+                    bucket_retention: *bucket_retention,
+                    freshness: *freshness,
+                    result_limits: *result_limits,
+                };
+                self.create_cached_query(name.as_ref(), stmt, search_path, options, *concurrently)
                     .await
             }
             SqlQuery::DropCache(DropCacheStatement { name }) => self.drop_cached_query(name).await,
             SqlQuery::DropAllCaches(_) => self.drop_all_caches().await,
+            SqlQuery::DumpCaches(_) => self.noria.dump_caches().await,
+            SqlQuery::CopyCache(stmt) => self.noria.copy_cache(stmt.clone()).await,
+            SqlQuery::AlterReadyset(stmt) => {
+                // These all affect every client of the deployment, not just this connection
+                self.require_admin(match stmt {
+                    AlterReadysetStatement::PauseReplication => "ALTER READYSET REPLICATION PAUSE",
+                    AlterReadysetStatement::ResumeReplication => {
+                        "ALTER READYSET REPLICATION RESUME"
+                    }
+                    AlterReadysetStatement::Quiesce => "ALTER READYSET QUIESCE",
+                    AlterReadysetStatement::SetMirPlan { .. } => "ALTER READYSET SET MIR PLAN",
+                })?;
+                self.noria.alter_readyset(stmt.clone()).await
+            }
+            SqlQuery::Kill(kill) => self.kill(kill),
             SqlQuery::Show(ShowStatement::CachedQueries(query_id)) => {
                 // Log a telemetry event
                 if let Some(ref telemetry_sender) = self.telemetry_sender {
@@ -1741,6 +2243,24 @@ where
             }
             SqlQuery::Show(ShowStatement::ReadySetStatus) => self.noria.readyset_status().await,
             SqlQuery::Show(ShowStatement::ReadySetVersion) => readyset_version(),
+            SqlQuery::Select(stmt) if is_last_write_token(stmt) => Ok(self.last_write_token()),
+            SqlQuery::Select(stmt) if is_view_stats(stmt) => self.noria.view_stats().await,
+            SqlQuery::Select(stmt)
+                if !self.has_fallback()
+                    && information_schema::supports_dialect(self.settings.dialect)
+                    && information_schema::is_information_schema_query(stmt) =>
+            {
+                self.query_information_schema(query).await
+            }
+            SqlQuery::Show(
+                ShowStatement::Tables(_)
+                | ShowStatement::Columns(_)
+                | ShowStatement::CreateTable(_),
+            ) if !self.has_fallback()
+                && information_schema::supports_dialect(self.settings.dialect) =>
+            {
+                self.query_information_schema(query).await
+            }
             SqlQuery::Show(ShowStatement::ReadySetTables) => self.noria.table_statuses().await,
             SqlQuery::Show(ShowStatement::ProxiedQueries(q_id)) => {
                 // Log a telemetry event
@@ -1755,6 +2275,20 @@ where
 
                 self.show_proxied_queries(q_id).await
             }
+            SqlQuery::Show(ShowStatement::UnsupportedQueries(q_id)) => {
+                self.noria.unsupported_queries(q_id).await
+            }
+            SqlQuery::Set(SetStatement::Variable(set))
+                if readyset_variables::is_readyset_set(set) =>
+            {
+                self.set_readyset_variables(set).await
+            }
+            SqlQuery::Show(ShowStatement::Variables(ShowVariables {
+                filter: Some(FilterPredicate::Like(pattern)),
+                ..
+            })) if readyset_variables::is_readyset_pattern(pattern) => {
+                self.show_readyset_variables(pattern).await
+            }
             _ => {
                 drop(_t);
                 // Clear readyset timer, since it was not a readyset request
@@ -1776,8 +2310,29 @@ where
         original_stmt: SelectStatement,
         view_request: &ViewCreateRequest,
         status: Option<QueryStatus>,
+        hints: &QueryHints,
         event: &mut QueryExecutionEvent,
     ) -> Result<QueryResult<'a, DB>, DB::Error> {
+        // Queries hinted to run against a specific cache skip all of our usual decisions about
+        // where to run the query, and don't fall back upstream on failure, since that would hide
+        // whatever problem the hint was used to debug
+        if hints.cache.is_some() {
+            event.destination = Some(QueryDestination::Readyset);
+            let start = Instant::now();
+            let ctx = ExecuteSelectContext::AdHoc {
+                statement: original_stmt,
+                query: original_query,
+                create_if_missing: false,
+                hints,
+            };
+            let res = noria.execute_select(ctx, state.ticket.clone(), event).await;
+            event.readyset_duration = Some(start.elapsed());
+            if let Err(e) = &res {
+                event.set_noria_error(e);
+            }
+            return Ok(res?.into());
+        }
+
         let mut status = status.unwrap_or(QueryStatus {
             migration_state: MigrationState::Unsupported,
             execution_info: None,
@@ -1796,7 +2351,8 @@ where
         if !status.always
             && (upstream.is_some()
                 && (settings.migration_mode != MigrationMode::InRequestPath
-                    && status.migration_state != MigrationState::Successful)
+                    && status.migration_state != MigrationState::Successful
+                    || status.migration_state == MigrationState::Warming)
                 || (status.migration_state == MigrationState::Unsupported)
                 || (status
                     .execution_info
@@ -1813,7 +2369,13 @@ where
                     &status.execution_info.unwrap().last_transition_time,
                 );
             }
-            return Self::query_fallback(upstream, original_query, event).await;
+            return Self::query_fallback_read(
+                upstream,
+                state.fallback_limiter(),
+                original_query,
+                event,
+            )
+            .await;
         }
 
         let noria_res = {
@@ -1823,6 +2385,7 @@ where
                 statement: original_stmt,
                 query: original_query,
                 create_if_missing: settings.migration_mode == MigrationMode::InRequestPath,
+                hints,
             };
             let res = noria.execute_select(ctx, state.ticket.clone(), event).await;
             event.readyset_duration = Some(start.elapsed());
@@ -1875,16 +2438,30 @@ where
                 }
 
                 // Try to execute on fallback if present, as long as query is not an `always`
-                // query.
-                match (always, upstream) {
+                // query, and wasn't refused by a cache that doesn't allow proxying reads when
+                // replication lags.
+                let no_fallback = always
+                    || matches!(
+                        noria_err,
+                        ReadySetError::ReplicationLagExceeded { proxy: false, .. }
+                    );
+                match (no_fallback, upstream) {
                     (true, _) | (_, None) => Err(noria_err.into()),
                     (false, Some(fallback)) => {
                         event.destination = Some(QueryDestination::ReadysetThenUpstream);
+                        let permit = match state.fallback_limiter() {
+                            Some(limiter) => Some(limiter.acquire().await?),
+                            None => None,
+                        };
                         let _t = event.start_upstream_timer();
-                        fallback
+                        let result = fallback
                             .query(original_query)
                             .await
-                            .map(QueryResult::Upstream)
+                            .map(QueryResult::Upstream);
+                        if let Some(permit) = permit {
+                            permit.record(&result);
+                        }
+                        result
                     }
                 }
             }
@@ -1977,6 +2554,30 @@ where
                 trace!(?search_path, "Setting search_path");
                 noria.set_schema_search_path(search_path);
             }
+            SetBehavior::SetMinToken(token) => {
+                let min_token = match token.parse::<Timestamp>() {
+                    Ok(min_token) => min_token,
+                    Err(e) => {
+                        if upstream.is_some() {
+                            event.set_noria_error(&e);
+                        }
+                        return Err(e.into());
+                    }
+                };
+                trace!(%min_token, "Setting minimum consistency token");
+                state.ticket = Some(match &state.ticket {
+                    Some(ticket) => Timestamp::join(ticket, &min_token),
+                    None => min_token,
+                });
+            }
+            SetBehavior::SetSnapshotReads(on) => {
+                trace!(on, "Setting snapshot reads");
+                noria.set_snapshot_reads(on);
+            }
+            SetBehavior::SetReadBehavior(read_behavior) => {
+                trace!(%read_behavior, "Setting read behavior");
+                noria.set_read_behavior(read_behavior);
+            }
         }
 
         Ok(())
@@ -2018,35 +2619,33 @@ where
                     | SqlQuery::Update(UpdateStatement { table: t, .. })
                     | SqlQuery::Delete(DeleteStatement { table: t, .. }) => {
                         event.sql_type = SqlQueryType::Write;
+                        noria.invalidate_micro_cache();
                         let _t = event.start_upstream_timer();
 
                         // Update ticket if RYW enabled
-                        let query_result = if cfg!(feature = "ryw") {
-                            if let Some(timestamp_service) = &mut state.timestamp_client {
-                                let (query_result, identifier) =
-                                    upstream.handle_ryw_write(raw_query).await?;
-
-                                // TODO(andrew): Move table name to table index conversion to
-                                // timestamp service https://app.clubhouse.io/readysettech/story/331
-                                let index = noria.node_index_of(t.name.as_str()).await?;
-                                let affected_tables = vec![WriteKey::TableIndex(index)];
-
-                                let new_timestamp = timestamp_service
-                                    .append_write(WriteId::MySqlGtid(identifier), affected_tables)
-                                    .map_err(|e| internal_err!("{e}"))?;
-
-                                // TODO(andrew, justin): solidify error handling in client
-                                // https://app.clubhouse.io/readysettech/story/366
-                                let current_ticket = state.ticket.as_ref().ok_or_else(|| {
-                                    internal_err!("RYW enabled backends must have a current ticket")
-                                })?;
-
-                                state.ticket =
-                                    Some(Timestamp::join(current_ticket, &new_timestamp));
-                                Ok(query_result)
-                            } else {
-                                upstream.query(raw_query).await
-                            }
+                        let query_result = if let Some(timestamp_service) =
+                            &mut state.timestamp_client
+                        {
+                            let (query_result, identifier) =
+                                upstream.handle_ryw_write(raw_query).await?;
+
+                            // TODO(andrew): Move table name to table index conversion to
+                            // timestamp service https://app.clubhouse.io/readysettech/story/331
+                            let index = noria.node_index_of(t.name.as_str()).await?;
+                            let affected_tables = vec![WriteKey::TableIndex(index)];
+
+                            let new_timestamp = timestamp_service
+                                .append_write(WriteId::MySqlGtid(identifier), affected_tables)
+                                .map_err(|e| internal_err!("{e}"))?;
+
+                            // TODO(andrew, justin): solidify error handling in client
+                            // https://app.clubhouse.io/readysettech/story/366
+                            let current_ticket = state.ticket.as_ref().ok_or_else(|| {
+                                internal_err!("RYW enabled backends must have a current ticket")
+                            })?;
+
+                            state.ticket = Some(Timestamp::join(current_ticket, &new_timestamp));
+                            Ok(query_result)
                         } else {
                             upstream.query(raw_query).await
                         };
@@ -2084,6 +2683,10 @@ where
                     SqlQuery::CreateCache(_)
                     | SqlQuery::DropCache(_)
                     | SqlQuery::DropAllCaches(_)
+                    | SqlQuery::DumpCaches(_)
+                    | SqlQuery::CopyCache(_)
+                    | SqlQuery::AlterReadyset(_)
+                    | SqlQuery::Kill(_)
                     | SqlQuery::Explain(_) => {
                         unreachable!("path returns prior")
                     }
@@ -2101,7 +2704,7 @@ where
                     SqlQuery::Select(_) => unreachable!("read path returns prior"),
                     // CREATE VIEW will still trigger migrations with epxlicit-migrations enabled
                     SqlQuery::CreateView(q) => noria.handle_create_view(q).await,
-                    SqlQuery::CreateTable(q) => noria.handle_table_operation(q.clone()).await,
+                    SqlQuery::CreateTable(q) => noria.handle_create_table(q).await,
                     SqlQuery::AlterTable(q) => noria.handle_table_operation(q.clone()).await,
                     SqlQuery::DropTable(q) => noria.handle_table_operation(q.clone()).await,
                     SqlQuery::DropView(q) => noria.handle_table_operation(q.clone()).await,
@@ -2134,13 +2737,19 @@ where
     #[instrument_root(level = "info")]
     #[inline]
     pub async fn query<'a>(&'a mut self, query: &'a str) -> Result<QueryResult<'a, DB>, DB::Error> {
+        let _active = self.start_command();
         let mut event = QueryExecutionEvent::new(EventType::Query);
+        event.record_keys = self.settings.slow_read_log.is_some();
         let query_log_sender = self.query_log_sender.clone();
         let slowlog = self.settings.slowlog;
+        let slow_read_log = self.settings.slow_read_log.clone();
 
-        let parse_result = {
+        let (parse_result, hints) = {
             let _t = event.start_parse_timer();
-            self.parse_query(query)
+            match self.parse_query_with_hints(query) {
+                Ok((parsed_query, hints)) => (Ok(parsed_query), hints),
+                Err(e) => (Err(e), QueryHints::default()),
+            }
         };
 
         let result = match parse_result {
@@ -2192,14 +2801,32 @@ where
                 )
                 .await
             }
+            // KILL QUERY interrupts the statement the connection is running against the upstream
+            // database, so it has to be sent there with the id of that connection's upstream
+            // connection
+            Ok(SqlQuery::Kill(KillStatement {
+                kind: KillKind::Query,
+                id,
+            })) if self.has_fallback() => match self.upstream_kill_query(id) {
+                Ok(kill_query) => {
+                    Self::query_fallback(self.upstream.as_mut(), kill_query, &mut event).await
+                }
+                Err(e) => Err(e.into()),
+            },
             // ReadySet extensions should never be proxied.
             Ok(ref parsed_query) if let Some(noria_extension) = self.query_noria_extensions(parsed_query, &mut event).await => {
                 noria_extension.map(Into::into).map_err(Into::into)
             }
-            // SET autocommit=1 needs to be handled explicitly or it will end up getting proxied in
-            // most cases.
+            // SET autocommit=1 and SET @readyset_<setting> for the session settings need to be
+            // handled explicitly or they will end up getting proxied in most cases.
             Ok(SqlQuery::Set(s))
-                if Handler::handle_set_statement(&s) == SetBehavior::SetAutocommit(true) =>
+                if matches!(
+                    Handler::handle_set_statement(&s),
+                    SetBehavior::SetAutocommit(true)
+                        | SetBehavior::SetMinToken(_)
+                        | SetBehavior::SetSnapshotReads(_)
+                        | SetBehavior::SetReadBehavior(_)
+                ) =>
             {
                 Self::query_adhoc_non_select(
                     &mut self.noria,
@@ -2223,13 +2850,30 @@ where
                         .map_err(Into::into)
                 }
             }
+            Ok(SqlQuery::Select(_)) if hints.no_cache => {
+                if self.has_fallback() {
+                    Self::query_fallback_read(
+                        self.upstream.as_mut(),
+                        self.state.fallback_limiter(),
+                        query,
+                        &mut event,
+                    )
+                    .await
+                } else {
+                    Err(ReadySetError::Unsupported(
+                        "NOCACHE hint requires an upstream database".to_owned(),
+                    )
+                    .into())
+                }
+            }
             Ok(SqlQuery::Select(stmt)) => {
                 let mut view_request = ViewCreateRequest::new(
                     stmt.clone(),
                     self.noria.schema_search_path().to_owned(),
                 );
                 let (noria_should_try, status) = self.noria_should_try_select(&mut view_request);
-                if noria_should_try {
+                // A query hinted to run against a specific cache overrides our own decision
+                if noria_should_try || hints.cache.is_some() {
                     event.sql_type = SqlQueryType::Read;
                     if self.settings.query_log_ad_hoc_queries {
                         event.query = Some(Arc::new(SqlQuery::Select(stmt.clone())));
@@ -2243,11 +2887,18 @@ where
                         stmt,
                         &view_request,
                         status,
+                        &hints,
                         &mut event,
                     )
                     .await
                 } else {
-                    Self::query_fallback(self.upstream.as_mut(), query, &mut event).await
+                    Self::query_fallback_read(
+                        self.upstream.as_mut(),
+                        self.state.fallback_limiter(),
+                        query,
+                        &mut event,
+                    )
+                    .await
                 }
             }
             Ok(_) if self.state.proxy_state.should_proxy() => {
@@ -2274,9 +2925,18 @@ where
                 .as_ref()
                 .map(|e| e.to_string())
                 .unwrap_or_default(),
+            unsupported_feature: event
+                .noria_error
+                .as_ref()
+                .and_then(|e| e.unsupported_feature()),
         });
 
-        log_query(query_log_sender.as_ref(), event, slowlog);
+        log_query(
+            query_log_sender.as_ref(),
+            event,
+            slowlog,
+            slow_read_log.as_deref(),
+        );
 
         result
     }
@@ -2286,6 +2946,11 @@ where
         self.upstream.is_some()
     }
 
+    /// Returns the connector to the upstream database, if we have fallback enabled.
+    pub fn upstream_mut(&mut self) -> Option<&mut DB> {
+        self.upstream.as_mut()
+    }
+
     /// If we are using fallback, this will return the database that was in the original connection
     /// string, if it exists, otherwise it will return None. If we are not using fallback this will
     /// always return None.
@@ -2301,13 +2966,40 @@ where
         &self.state.ticket
     }
 
+    /// Returns the consistency token for the writes performed so far in this session, as the
+    /// result of a `SELECT LAST_WRITE_TOKEN()` query
+    fn last_write_token(&self) -> noria_connector::QueryResult<'static> {
+        noria_connector::QueryResult::Meta(vec![MetaVariable {
+            name: "LAST_WRITE_TOKEN()".into(),
+            value: self
+                .state
+                .ticket
+                .as_ref()
+                .map(|ticket| ticket.to_string())
+                .unwrap_or_default(),
+        }])
+    }
+
     fn parse_query(&mut self, query: &str) -> ReadySetResult<SqlQuery> {
+        self.parse_query_with_hints(query)
+            .map(|(parsed_query, _)| parsed_query)
+    }
+
+    /// Parse the given query, keeping its comments in the AST, along with the routing hints in
+    /// them (see [`crate::hints`])
+    fn parse_query_with_hints(&mut self, query: &str) -> ReadySetResult<(SqlQuery, QueryHints)> {
         match self.state.parsed_query_cache.entry(query.to_owned()) {
             Entry::Occupied(entry) => Ok(entry.get().clone()),
             Entry::Vacant(entry) => {
                 trace!(%query, "Parsing query");
-                match nom_sql::parse_query(self.settings.dialect, query) {
-                    Ok(parsed_query) => Ok(entry.insert(parsed_query).clone()),
+                match nom_sql::parse_query_with_comments(self.settings.dialect, query) {
+                    Ok(parsed_query) => {
+                        let hints = match &parsed_query {
+                            SqlQuery::Select(select) => QueryHints::from_comments(&select.comments),
+                            _ => QueryHints::default(),
+                        };
+                        Ok(entry.insert((parsed_query, hints)).clone())
+                    }
                     Err(_) => Err(ReadySetError::UnparseableQuery {
                         query: query.to_string(),
                     }),
@@ -2319,6 +3011,29 @@ where
     pub fn does_require_authentication(&self) -> bool {
         self.settings.require_authentication
     }
+
+    /// Records that the client has authenticated as `user`
+    pub fn set_user(&mut self, user: &str) {
+        if let Some(connection) = &self.state.connection {
+            connection.set_user(user);
+        }
+        self.state.user = Some(user.to_owned());
+    }
+
+    /// Returns an error unless the client is allowed to run the administrative `operation`, which
+    /// is the case if authentication isn't required, or if the client authenticated as the admin
+    /// user
+    fn require_admin(&self, operation: &str) -> ReadySetResult<()> {
+        if !self.settings.require_authentication {
+            return Ok(());
+        }
+        match (&self.state.user, &self.settings.admin_user) {
+            (Some(user), Some(admin_user)) if user == admin_user => Ok(()),
+            _ => Err(ReadySetError::AccessDenied(format!(
+                "{operation} can only be run by the user passed to --admin-username"
+            ))),
+        }
+    }
 }
 
 impl<DB, Handler> Drop for Backend<DB, Handler>
@@ -2336,6 +3051,7 @@ fn log_query(
     sender: Option<&UnboundedSender<QueryExecutionEvent>>,
     event: QueryExecutionEvent,
     slowlog: bool,
+    slow_read_log: Option<&SlowReadLog>,
 ) {
     const SLOW_DURATION: std::time::Duration = std::time::Duration::from_millis(5);
 
@@ -2348,6 +3064,10 @@ fn log_query(
         }
     }
 
+    if let Some(slow_read_log) = slow_read_log {
+        slow_read_log.log(&event);
+    }
+
     if let Some(sender) = sender {
         // Drop the error if something goes wrong with query logging.
         if let Err(e) = sender.send(event) {
@@ -2356,6 +3076,41 @@ fn log_query(
     }
 }
 
+/// Returns true if the given statement is exactly `SELECT LAST_WRITE_TOKEN()`
+fn is_last_write_token(stmt: &SelectStatement) -> bool {
+    stmt.tables.is_empty()
+        && matches!(
+            &stmt.fields[..],
+            [FieldDefinitionExpr::Expr {
+                expr: Expr::Call(FunctionExpr::Call { name, arguments }),
+                ..
+            }] if name.eq_ignore_ascii_case("last_write_token") && arguments.is_empty()
+        )
+}
+
+/// Returns true if the given statement is exactly `SELECT * FROM readyset.view_stats`, which reads
+/// the [`ViewStats`](readyset_client::ViewStats) of every cache as if they were a table
+fn is_view_stats(stmt: &SelectStatement) -> bool {
+    let [TableExpr {
+        inner: TableExprInner::Table(Relation {
+            schema: Some(schema),
+            name,
+        }),
+        alias: None,
+    }] = &stmt.tables[..] else {
+        return false;
+    };
+    schema.eq_ignore_ascii_case("readyset")
+        && name.eq_ignore_ascii_case("view_stats")
+        && matches!(&stmt.fields[..], [FieldDefinitionExpr::All])
+        && stmt.join.is_empty()
+        && stmt.where_clause.is_none()
+        && stmt.group_by.is_none()
+        && stmt.having.is_none()
+        && stmt.order.is_none()
+        && stmt.ctes.is_empty()
+}
+
 fn readyset_version() -> ReadySetResult<noria_connector::QueryResult<'static>> {
     Ok(noria_connector::QueryResult::MetaWithHeader(
         <Vec<(String, String)>>::from(READYSET_VERSION.clone())
@@ -1,28 +1,37 @@
 use std::borrow::Cow;
 use std::collections::{BTreeMap, HashMap, HashSet};
-use std::convert::{TryFrom, TryInto};
-use std::fmt;
-use std::sync::{atomic, Arc, RwLock};
-
+use std::convert::TryFrom;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use std::{fmt, iter};
+
+use database_utils::row_size::{RowSizeCheck, RowSizeLimit};
+use futures::TryStreamExt;
 use itertools::Itertools;
+use metrics::increment_counter;
 use nom_sql::analysis::visit_mut::VisitorMut;
 use nom_sql::{
-    self, ColumnConstraint, DeleteStatement, Expr, InsertStatement, Literal, Relation,
-    SelectStatement, SqlIdentifier, SqlQuery, UnaryOperator, UpdateStatement,
+    self, AlterReadysetStatement, Column, CopyCacheFormat, CopyCacheStatement, CreateTableBody,
+    DeleteStatement, Expr, InsertStatement, Literal, Relation, SelectStatement, SqlIdentifier,
+    SqlQuery, UpdateStatement,
 };
+use readyset_client::config::ConfigReloadResult;
 use readyset_client::consistency::Timestamp;
 use readyset_client::internal::LocalNodeIndex;
-use readyset_client::recipe::changelist::{Change, ChangeList, IntoChanges};
+use readyset_client::metrics::recorded;
+use readyset_client::query::QueryId;
+use readyset_client::recipe::changelist::{CacheOptions, Change, ChangeList, IntoChanges};
 use readyset_client::results::{ResultIterator, Results};
+pub use readyset_client::ReadBehavior;
 use readyset_client::{
-    ColumnSchema, ReadQuery, ReaderAddress, ReaderHandle, ReadySetError, ReadySetHandle,
-    ReadySetResult, SchemaType, Table, TableOperation, View, ViewCreateRequest, ViewQuery,
+    ColumnSchema, ColumnarBatch, KeyComparison, LookupResult, ParquetWriter, ReadQuery,
+    ReaderAddress, ReaderHandle, ReadySetError, ReadySetHandle, ReadySetResult, SchemaType, Table,
+    TableOperation, View, ViewCreateRequest, ViewQuery,
 };
-use readyset_data::{DfType, DfValue, Dialect};
+use readyset_data::{column_default_value, DfType, DfValue, Dialect};
 use readyset_errors::ReadySetError::PreparedStatementMissing;
-use readyset_errors::{
-    internal, internal_err, invariant_eq, table_err, unsupported, unsupported_err,
-};
+use readyset_errors::{internal, internal_err, invalid, table_err, unsupported, unsupported_err};
 use readyset_server::worker::readers::{CallResult, ReadRequestHandler};
 use readyset_sql_passes::anonymize::anonymize_literals;
 use readyset_tracing::{error, info, trace, warn};
@@ -30,11 +39,23 @@ use readyset_util::redacted::Sensitive;
 use tracing::instrument;
 
 use crate::backend::SelectSchema;
+use crate::hints::QueryHints;
+use crate::index_advisor::IndexAdvisor;
+use crate::micro_cache::{EncodedResults, MicroCache};
+use crate::namespace_limiter::NamespaceLimiter;
+use crate::replication_lag::ReplicationLagMonitor;
 use crate::rewrite::{self, ProcessedQueryParams};
 use crate::utils;
 
 type StatementID = u32;
 
+/// The number of keys looked up in the reader of a cache at once when executing `COPY CACHE`
+const COPY_CACHE_BATCH_SIZE: usize = 1000;
+
+/// How often to check whether the backfill of a cache created with `CREATE CACHE CONCURRENTLY` has
+/// completed
+const BACKFILL_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
 #[derive(Clone)]
 pub(crate) enum PreparedStatement {
     Select(PreparedSelectStatement),
@@ -217,14 +238,27 @@ pub enum QueryResult<'a> {
     Insert {
         num_rows_inserted: u64,
         first_inserted_id: u64,
+        /// The number of rows which exceeded the maximum row size, and were truncated or
+        /// skipped. Protocols should report this to the client as a warning.
+        oversized_rows: u64,
     },
     Select {
         rows: ResultIterator,
         schema: SelectSchema<'a>,
+        /// The encodings of `rows` cached in the micro cache, if they were read from (or recorded
+        /// into) it. Protocols may use this to write the rows without re-encoding them, and to
+        /// record their encoding of the rows for subsequent reads.
+        encoded: Option<Arc<EncodedResults>>,
+        /// True if `rows` were truncated because they exceeded the result limits of the cache.
+        /// Protocols should report this to the client as a warning.
+        truncated: bool,
     },
     Update {
         num_rows_updated: u64,
         last_inserted_id: u64,
+        /// The number of rows whose new values exceeded the maximum row size, and were truncated
+        /// or skipped. Protocols should report this to the client as a warning.
+        oversized_rows: u64,
     },
     Delete {
         num_rows_deleted: u64,
@@ -245,6 +279,8 @@ impl<'a> QueryResult<'a> {
         QueryResult::Select {
             schema,
             rows: ResultIterator::owned(data),
+            encoded: None,
+            truncated: false,
         }
     }
 
@@ -252,19 +288,47 @@ impl<'a> QueryResult<'a> {
         QueryResult::Select {
             schema,
             rows: ResultIterator::owned(vec![]),
+            encoded: None,
+            truncated: false,
         }
     }
 
     pub fn from_iter(schema: SelectSchema<'a>, rows: ResultIterator) -> Self {
-        QueryResult::Select { schema, rows }
+        QueryResult::Select {
+            schema,
+            rows,
+            encoded: None,
+            truncated: false,
+        }
+    }
+
+    fn with_encoded(mut self, encoded_results: Option<Arc<EncodedResults>>) -> Self {
+        if let QueryResult::Select { encoded, .. } = &mut self {
+            *encoded = encoded_results;
+        }
+        self
+    }
+
+    fn with_truncated(mut self, was_truncated: bool) -> Self {
+        if let QueryResult::Select { truncated, .. } = &mut self {
+            *truncated = was_truncated;
+        }
+        self
     }
 
     #[inline]
     pub fn into_owned(self) -> QueryResult<'static> {
         match self {
-            QueryResult::Select { schema, rows } => QueryResult::Select {
+            QueryResult::Select {
+                schema,
+                rows,
+                encoded,
+                truncated,
+            } => QueryResult::Select {
                 schema: schema.into_owned(),
                 rows,
+                encoded,
+                truncated,
             },
             // Have to manually pass each variant to convince rustc that the
             // returned type is really owned
@@ -272,16 +336,20 @@ impl<'a> QueryResult<'a> {
             QueryResult::Insert {
                 num_rows_inserted,
                 first_inserted_id,
+                oversized_rows,
             } => QueryResult::Insert {
                 num_rows_inserted,
                 first_inserted_id,
+                oversized_rows,
             },
             QueryResult::Update {
                 num_rows_updated,
                 last_inserted_id,
+                oversized_rows,
             } => QueryResult::Update {
                 num_rows_updated,
                 last_inserted_id,
+                oversized_rows,
             },
             QueryResult::Delete { num_rows_deleted } => QueryResult::Delete { num_rows_deleted },
             QueryResult::Meta(meta) => QueryResult::Meta(meta),
@@ -379,7 +447,6 @@ impl ViewCache {
 
 pub struct NoriaConnector {
     inner: NoriaBackend,
-    auto_increments: Arc<RwLock<HashMap<Relation, atomic::AtomicUsize>>>,
     /// Global and thread-local cache of view endpoints and prepared statements.
     view_cache: ViewCache,
 
@@ -406,8 +473,63 @@ pub struct NoriaConnector {
     /// supports a multi-element schema search path, the concept of "currently connected database"
     /// in MySQL can be thought of as a schema search path that only has one element.
     schema_search_path: Vec<SqlIdentifier>,
+
+    /// If set, used to record the columns that reads get filtered on after the reader lookup, so
+    /// that frequently filtered columns can be indexed. See [`IndexAdvisor`].
+    index_advisor: Option<Arc<IndexAdvisor>>,
+
+    /// If set, used to cache the results of point reads for a very short time. See
+    /// [`MicroCache`].
+    micro_cache: Option<Arc<MicroCache>>,
+
+    /// If set, used to limit the rate of reads from the caches in each namespace. See
+    /// [`NamespaceLimiter`].
+    namespace_limiter: Option<Arc<NamespaceLimiter>>,
+
+    /// If set, used to refuse reads from caches while replication lags further behind the
+    /// upstream database than those caches allow. See [`ReplicationLagMonitor`].
+    replication_lag: Option<Arc<ReplicationLagMonitor>>,
+
+    /// Whether reads through this connector are snapshot reads. See [`Self::set_snapshot_reads`].
+    snapshot_reads: bool,
+
+    /// The snapshot that snapshot reads are read at, taken before the first read after snapshot
+    /// reads were enabled
+    snapshot: Option<Timestamp>,
+
+    /// If set, rows inserted through this connector are checked against this limit before
+    /// they're written. See [`database_utils::row_size`].
+    row_size_limit: Option<RowSizeLimit>,
+
+    /// The foreign keys referencing each base table, used to apply referential actions to writes.
+    /// See [`foreign_keys::ForeignKeyCache`].
+    foreign_keys: foreign_keys::ForeignKeyCache,
 }
 
+/// Log and record a metric for a row written through the adapter which exceeded `limit`, if it
+/// did. Returns false if the row should be skipped.
+fn handle_oversized_row(table: &Relation, limit: &RowSizeLimit, check: RowSizeCheck) -> bool {
+    if let Some(action) = check.action() {
+        warn!(
+            %table,
+            max_row_size = limit.max_row_size,
+            action,
+            "Row written through the adapter exceeds the maximum row size"
+        );
+        increment_counter!(
+            recorded::BASE_TABLE_OVERSIZED_ROWS,
+            "table_name" => table.to_string(),
+            "source" => "adapter",
+            "action" => action
+        );
+    }
+    check != RowSizeCheck::Skip
+}
+
+mod auto_increment;
+mod foreign_keys;
+mod joined_writes;
+
 mod request_handler {
     use readyset_server::worker::readers::ReadRequestHandler;
 
@@ -436,22 +558,6 @@ mod request_handler {
     unsafe impl Sync for LocalReadHandler {}
 }
 
-/// The read behavior used when executing a read against ReadySet.
-#[derive(Clone, Copy)]
-pub enum ReadBehavior {
-    /// If ReadySet is unable to immediately service the read due to a cache miss, block on the
-    /// response.
-    Blocking,
-    /// If ReadySet is unable to immediately service the read, return an error.
-    NonBlocking,
-}
-
-impl ReadBehavior {
-    fn is_blocking(&self) -> bool {
-        matches!(self, Self::Blocking)
-    }
-}
-
 /// Provides the necessary context to execute a select statement against noria, either for a
 /// prepared or an ad-hoc query
 #[allow(clippy::large_enum_variant)]
@@ -465,13 +571,13 @@ pub(crate) enum ExecuteSelectContext<'ctx> {
         statement: nom_sql::SelectStatement,
         query: &'ctx str,
         create_if_missing: bool,
+        hints: &'ctx QueryHints,
     },
 }
 
 impl NoriaConnector {
     pub async fn new(
         ch: ReadySetHandle,
-        auto_increments: Arc<RwLock<HashMap<Relation, atomic::AtomicUsize>>>,
         query_cache: Arc<RwLock<HashMap<ViewCreateRequest, Relation>>>,
         read_behavior: ReadBehavior,
         dialect: Dialect,
@@ -480,7 +586,6 @@ impl NoriaConnector {
     ) -> Self {
         NoriaConnector::new_with_local_reads(
             ch,
-            auto_increments,
             query_cache,
             read_behavior,
             None,
@@ -494,7 +599,6 @@ impl NoriaConnector {
     #[allow(clippy::too_many_arguments)]
     pub async fn new_with_local_reads(
         ch: ReadySetHandle,
-        auto_increments: Arc<RwLock<HashMap<Relation, atomic::AtomicUsize>>>,
         query_cache: Arc<RwLock<HashMap<ViewCreateRequest, Relation>>>,
         read_behavior: ReadBehavior,
         read_request_handler: Option<ReadRequestHandler>,
@@ -508,7 +612,6 @@ impl NoriaConnector {
             inner: NoriaBackend {
                 inner: Some(backend),
             },
-            auto_increments,
             view_cache: ViewCache::new(query_cache),
             prepared_statement_cache: HashMap::new(),
             failed_views: HashSet::new(),
@@ -516,6 +619,14 @@ impl NoriaConnector {
             read_request_handler: request_handler::LocalReadHandler::new(read_request_handler),
             dialect,
             schema_search_path,
+            index_advisor: None,
+            micro_cache: None,
+            namespace_limiter: None,
+            replication_lag: None,
+            snapshot_reads: false,
+            snapshot: None,
+            row_size_limit: None,
+            foreign_keys: Default::default(),
         }
     }
 
@@ -534,6 +645,24 @@ impl NoriaConnector {
         Ok(QueryResult::Meta(vec![(label, graphviz).into()]))
     }
 
+    pub(crate) async fn canonical_plan(
+        &mut self,
+        cache: &Relation,
+    ) -> ReadySetResult<QueryResult<'static>> {
+        let noria = &mut self.inner.get_mut()?.noria;
+        let plan = noria.canonical_plan(cache.clone()).await?;
+        Ok(QueryResult::Meta(vec![("CANONICAL PLAN", plan).into()]))
+    }
+
+    pub(crate) async fn mir_plan(
+        &mut self,
+        cache: &Relation,
+    ) -> ReadySetResult<QueryResult<'static>> {
+        let noria = &mut self.inner.get_mut()?.noria;
+        let plan = noria.mir_plan(cache.clone()).await?;
+        Ok(QueryResult::Meta(vec![("MIR PLAN", plan).into()]))
+    }
+
     pub(crate) async fn verbose_views(
         &mut self,
         query_id: &Option<String>,
@@ -600,6 +729,259 @@ impl NoriaConnector {
         ))
     }
 
+    /// Returns the queries which ReadySet has found to be unsupported, along with the reason why
+    pub(crate) async fn unsupported_queries(
+        &mut self,
+        query_id: &Option<String>,
+    ) -> ReadySetResult<QueryResult<'static>> {
+        let mut queries = noria_await!(
+            self.inner.get_mut()?,
+            self.inner.get_mut()?.noria.unsupported_queries()
+        )?;
+        if let Some(q_id) = query_id {
+            queries.retain(|(id, _, _, _)| &id.to_string() == q_id);
+        }
+        let create_dummy_column = |n: &str| ColumnSchema {
+            column: nom_sql::Column {
+                name: n.into(),
+                table: None,
+            },
+            column_type: DfType::DEFAULT_TEXT,
+            base: None,
+        };
+        let select_schema = SelectSchema {
+            use_bogo: false,
+            schema: Cow::Owned(vec![
+                create_dummy_column("query id"),
+                create_dummy_column("unsupported query"),
+                create_dummy_column("reason"),
+                create_dummy_column("unsupported feature"),
+            ]),
+            columns: Cow::Owned(vec![
+                "query id".into(),
+                "unsupported query".into(),
+                "reason".into(),
+                "unsupported feature".into(),
+            ]),
+        };
+        let data = queries
+            .into_iter()
+            .map(|(id, request, reason, feature)| {
+                vec![
+                    DfValue::from(id.to_string()),
+                    DfValue::from(request.statement.to_string()),
+                    DfValue::from(reason),
+                    feature
+                        .map(|feature| DfValue::from(feature.name()))
+                        .unwrap_or(DfValue::None),
+                ]
+            })
+            .collect::<Vec<_>>();
+        Ok(QueryResult::from_owned(
+            select_schema,
+            vec![Results::new(data)],
+        ))
+    }
+
+    /// Returns a script of `CREATE CACHE` statements, one per row, followed by an `ALTER READYSET
+    /// SET MIR PLAN` statement for each cache whose MIR plan has been overridden, which recreates
+    /// all the caches installed in ReadySet when replayed against another deployment
+    pub(crate) async fn dump_caches(&mut self) -> ReadySetResult<QueryResult<'static>> {
+        let caches = noria_await!(
+            self.inner.get_mut()?,
+            self.inner.get_mut()?.noria.cache_definitions()
+        )?;
+        let mir_plans = noria_await!(
+            self.inner.get_mut()?,
+            self.inner.get_mut()?.noria.mir_plan_overrides()
+        )?;
+        let select_schema = SelectSchema {
+            use_bogo: false,
+            schema: Cow::Owned(vec![ColumnSchema {
+                column: nom_sql::Column {
+                    name: "statement".into(),
+                    table: None,
+                },
+                column_type: DfType::DEFAULT_TEXT,
+                base: None,
+            }]),
+            columns: Cow::Owned(vec!["statement".into()]),
+        };
+        let data = caches
+            .into_iter()
+            .map(|stmt| stmt.to_string())
+            .chain(mir_plans.into_iter().map(|(cache, plan)| {
+                AlterReadysetStatement::SetMirPlan { cache, plan }.to_string()
+            }))
+            .map(|stmt| vec![DfValue::from(format!("{stmt};"))])
+            .collect::<Vec<_>>();
+        Ok(QueryResult::from_owned(
+            select_schema,
+            vec![Results::new(data)],
+        ))
+    }
+
+    /// Handles a `COPY CACHE` statement, by reading every key currently stored in the reader of
+    /// the cache in chunks, and returning the resulting rows to the client, either as-is or
+    /// encoded in the requested format.
+    ///
+    /// The chunks are read (and encoded) by a background task as the results are returned, with
+    /// at most one chunk buffered ahead of the client, so the contents of the cache are never held
+    /// in memory at once. If reading a chunk fails part way through, the results end early with
+    /// that error (see [`ResultIterator::take_error`]).
+    pub(crate) async fn copy_cache(
+        &mut self,
+        statement: CopyCacheStatement,
+    ) -> ReadySetResult<QueryResult<'static>> {
+        let view = self
+            .inner
+            .get_mut()?
+            .get_noria_view(&statement.name, false)
+            .await?;
+        let mut reader_handle = match view {
+            View::Single(reader_handle) => reader_handle.clone(),
+            View::MultipleReused(_) => {
+                unsupported!("COPY CACHE is not supported for caches which reuse other caches")
+            }
+        };
+
+        let schema = select_schema(&reader_handle);
+        let rows_schema = SelectSchema {
+            use_bogo: schema.use_bogo,
+            schema: Cow::Owned(schema.schema.into_owned()),
+            columns: Cow::Owned(schema.columns.into_owned()),
+        };
+        let num_columns = rows_schema.columns.len();
+
+        let mut header = None;
+        let mut parquet_writer = None;
+        let result_schema = match statement.format {
+            None => rows_schema.clone(),
+            Some(CopyCacheFormat::Csv) => {
+                header = Some(vec![vec![DfValue::from(csv_record(
+                    rows_schema.columns.iter().map(|col| Some(col.to_string())),
+                ))]]);
+                single_column_schema("csv", DfType::DEFAULT_TEXT)
+            }
+            Some(CopyCacheFormat::Parquet) => {
+                parquet_writer = Some(ParquetWriter::new(&rows_schema.schema));
+                single_column_schema("parquet", DfType::Blob)
+            }
+        };
+
+        let (tx, rx) = tokio::sync::mpsc::channel(1);
+        tokio::spawn(async move {
+            let res: ReadySetResult<()> = async {
+                if let Some(header) = header {
+                    if tx.send(Ok(header)).await.is_err() {
+                        return Ok(());
+                    }
+                }
+
+                let chunks = reader_handle.scan(COPY_CACHE_BATCH_SIZE).await?;
+                futures::pin_mut!(chunks);
+                while let Some(rows) = chunks.try_next().await? {
+                    let chunk = match (statement.format, &mut parquet_writer) {
+                        (Some(CopyCacheFormat::Parquet), Some(writer)) => {
+                            let batch = ColumnarBatch::from_rows(&rows_schema.schema, &rows)?;
+                            vec![vec![DfValue::ByteArray(Arc::new(
+                                writer.write_batch(&batch)?,
+                            ))]]
+                        }
+                        (Some(CopyCacheFormat::Csv), _) => rows
+                            .iter()
+                            .map(|row| {
+                                vec![DfValue::from(csv_record(
+                                    row.iter()
+                                        .take(num_columns)
+                                        .map(|value| (!value.is_none()).then(|| value.to_string())),
+                                ))]
+                            })
+                            .collect(),
+                        _ => rows,
+                    };
+                    if tx.send(Ok(chunk)).await.is_err() {
+                        // The client has gone away
+                        return Ok(());
+                    }
+                }
+
+                if let Some(writer) = parquet_writer {
+                    let _ = tx
+                        .send(Ok(vec![vec![DfValue::ByteArray(Arc::new(
+                            writer.finish(),
+                        ))]]))
+                        .await;
+                }
+                Ok(())
+            }
+            .await;
+
+            if let Err(error) = res {
+                warn!(%error, "Error reading cache for COPY CACHE");
+                let _ = tx.send(Err(error)).await;
+            }
+        });
+
+        Ok(QueryResult::from_iter(
+            result_schema,
+            ResultIterator::streamed(rx),
+        ))
+    }
+
+    /// Handles an `ALTER READYSET` statement
+    pub(crate) async fn alter_readyset(
+        &mut self,
+        statement: AlterReadysetStatement,
+    ) -> ReadySetResult<QueryResult<'static>> {
+        match statement {
+            AlterReadysetStatement::PauseReplication => noria_await!(
+                self.inner.get_mut()?,
+                self.inner.get_mut()?.noria.pause_replication()
+            )?,
+            AlterReadysetStatement::ResumeReplication => noria_await!(
+                self.inner.get_mut()?,
+                self.inner.get_mut()?.noria.resume_replication()
+            )?,
+            AlterReadysetStatement::Quiesce => {
+                let barrier =
+                    noria_await!(self.inner.get_mut()?, self.inner.get_mut()?.noria.quiesce())?;
+                return Ok(QueryResult::from_owned(
+                    SelectSchema {
+                        use_bogo: false,
+                        schema: Cow::Owned(vec![ColumnSchema {
+                            column: nom_sql::Column {
+                                name: "barrier".into(),
+                                table: None,
+                            },
+                            column_type: DfType::UnsignedBigInt,
+                            base: None,
+                        }]),
+                        columns: Cow::Owned(vec!["barrier".into()]),
+                    },
+                    vec![Results::new(vec![vec![DfValue::from(barrier)]])],
+                ));
+            }
+            AlterReadysetStatement::SetMirPlan { cache, plan } => noria_await!(
+                self.inner.get_mut()?,
+                self.inner
+                    .get_mut()?
+                    .noria
+                    .set_mir_plan(cache, plan, self.dialect)
+            )?,
+        }
+        Ok(QueryResult::Empty)
+    }
+
+    /// Return the latest timestamp propagated through the dataflow graph by replication, to take
+    /// as the snapshot for snapshot reads
+    pub(crate) async fn replication_timestamp(&mut self) -> ReadySetResult<Timestamp> {
+        noria_await!(
+            self.inner.get_mut()?,
+            self.inner.get_mut()?.noria.replication_timestamp()
+        )
+    }
+
     pub(crate) fn server_supports_pagination(&self) -> bool {
         self.inner
             .inner
@@ -639,29 +1021,8 @@ impl NoriaConnector {
             }
         };
 
-        let data: Vec<Vec<DfValue>> = q
-            .data
-            .iter()
-            .map(|row| {
-                row.iter()
-                    .map(|expr| match expr {
-                        Expr::Literal(lit) => DfValue::try_from(lit),
-                        // Ad-hoc handle unary negation (for logictests, to allow them to insert
-                        // negative values)
-                        Expr::UnaryOp {
-                            op: UnaryOperator::Neg,
-                            rhs: box Expr::Literal(lit),
-                        } => {
-                            let val = DfValue::try_from(lit)?;
-                            &val * &(-1).into()
-                        }
-                        _ => unsupported!("Only literal values are supported in expressions"),
-                    })
-                    .collect::<Result<Vec<_>, _>>()
-            })
-            .collect::<Result<Vec<_>, _>>()?;
-
-        self.do_insert(&q, data).await
+        let data = utils::insert_values(&q, &[], self.dialect)?;
+        self.do_insert(&q, data, &[]).await
     }
 
     pub async fn prepare_insert(
@@ -749,7 +1110,9 @@ impl NoriaConnector {
                 )
                 .unwrap()
                 .unwrap();
-                self.do_insert(q, vec![coerced_params]).await
+                let data = utils::insert_values(q, &coerced_params, self.dialect)?;
+                let update_params = utils::on_duplicate_params(q, &coerced_params)?;
+                self.do_insert(q, data, update_params).await
             }
             _ => {
                 internal!(
@@ -763,6 +1126,22 @@ impl NoriaConnector {
         &mut self,
         q: &nom_sql::DeleteStatement,
     ) -> ReadySetResult<QueryResult<'_>> {
+        if !q.using.is_empty() {
+            trace!(table = %q.table.name, "delete::lookup joined keys");
+            let keys = self
+                .joined_write_keys(&q.table, &q.using, q.where_clause.as_ref())
+                .await?;
+            let count = keys.len() as u64;
+            if !keys.is_empty() {
+                trace!("delete::execute");
+                self.delete_with_cascade(&q.table, keys).await?;
+            }
+            trace!("delete::done");
+            return Ok(QueryResult::Delete {
+                num_rows_deleted: count,
+            });
+        }
+
         let cond = q
             .where_clause
             .as_ref()
@@ -793,11 +1172,9 @@ impl NoriaConnector {
             Some(flattened) => {
                 let count = flattened.len() as u64;
                 trace!("delete::execute");
-                for key in flattened {
-                    if let Err(e) = mutator.delete(key).await {
-                        error!(error = %e, "failed");
-                        return Err(e);
-                    };
+                if let Err(e) = self.delete_with_cascade(&q.table, flattened).await {
+                    error!(error = %e, "failed");
+                    return Err(e);
                 }
                 trace!("delete::done");
                 Ok(QueryResult::Delete {
@@ -811,7 +1188,37 @@ impl NoriaConnector {
         &'a mut self,
         q: &nom_sql::UpdateStatement,
     ) -> ReadySetResult<QueryResult<'a>> {
-        self.do_update(Cow::Borrowed(q), None).await
+        if q.from.is_empty() {
+            return self.do_update(Cow::Borrowed(q), None).await;
+        }
+
+        trace!(table = %q.table.name, "update::lookup joined keys");
+        let keys = self
+            .joined_write_keys(&q.table, &q.from, q.where_clause.as_ref())
+            .await?;
+        let (_, schema, _) = self.base_table_info(&q.table).await?;
+        let updates = utils::extract_update_params_and_fields(
+            &mut q.clone(),
+            &mut None::<std::iter::Empty<DfValue>>,
+            &schema,
+            self.dialect,
+        )?;
+
+        trace!("update::update");
+        let count = keys.len() as u64;
+        let res = if keys.is_empty() {
+            Ok(RowSizeCheck::Fits)
+        } else {
+            self.update_with_cascade(&q.table, keys, updates).await
+        };
+        self.invalidate_micro_cache();
+        let check = res?;
+        trace!("update::complete");
+        Ok(QueryResult::Update {
+            num_rows_updated: count,
+            last_inserted_id: 0,
+            oversized_rows: if check.action().is_some() { count } else { 0 },
+        })
     }
 
     pub(crate) async fn prepare_update(
@@ -819,6 +1226,10 @@ impl NoriaConnector {
         q: nom_sql::UpdateStatement,
         statement_id: u32,
     ) -> ReadySetResult<PrepareResult> {
+        if !q.from.is_empty() {
+            unsupported!("UPDATE ... FROM is not supported in prepared statements");
+        }
+
         // ensure that we have schemas and endpoints for the query
         trace!(table = %q.table.name, "update::access mutator");
         let mutator = self.inner.get_mut()?.get_noria_table(&q.table).await?;
@@ -876,6 +1287,10 @@ impl NoriaConnector {
         q: DeleteStatement,
         statement_id: u32,
     ) -> ReadySetResult<PrepareResult> {
+        if !q.using.is_empty() {
+            unsupported!("DELETE ... USING is not supported in prepared statements");
+        }
+
         // ensure that we have schemas and endpoints for the query
         trace!(table = %q.table.name, "delete::access mutator");
         let mutator = self.inner.get_mut()?.get_noria_table(&q.table).await?;
@@ -945,9 +1360,30 @@ impl NoriaConnector {
                     .with_schema_search_path(self.schema_search_path.clone())
             )
         )?;
+        self.foreign_keys.invalidate();
         Ok(QueryResult::Empty)
     }
 
+    /// Change the given settings of the deployment. See [`ReadySetHandle::reload_config`].
+    pub(crate) async fn reload_config(
+        &mut self,
+        settings: BTreeMap<String, String>,
+    ) -> ReadySetResult<ConfigReloadResult> {
+        noria_await!(
+            self.inner.get_mut()?,
+            self.inner.get_mut()?.noria.reload_config(settings)
+        )
+    }
+
+    /// Return the current values of the settings of the deployment which can be changed with
+    /// [`Self::reload_config`]
+    pub(crate) async fn config_settings(&mut self) -> ReadySetResult<BTreeMap<String, String>> {
+        noria_await!(
+            self.inner.get_mut()?,
+            self.inner.get_mut()?.noria.config_settings()
+        )
+    }
+
     pub(crate) async fn readyset_status(&mut self) -> ReadySetResult<QueryResult<'static>> {
         let status = noria_await!(self.inner.get_mut()?, self.inner.get_mut()?.noria.status())?;
 
@@ -997,6 +1433,69 @@ impl NoriaConnector {
         Ok(QueryResult::from_owned(schema, vec![Results::new(data)]))
     }
 
+    /// Returns the names and schemas of all the base tables known to ReadySet
+    pub(crate) async fn table_schemas(
+        &mut self,
+    ) -> ReadySetResult<Vec<(Relation, CreateTableBody)>> {
+        let tables = noria_await!(self.inner.get_mut()?, self.inner.get_mut()?.noria.tables())?;
+
+        let mut res = Vec::with_capacity(tables.len());
+        for table in tables.into_keys() {
+            let mutator = self.inner.get_mut()?.get_noria_table(&table).await?;
+            if let Some(schema) = mutator.schema() {
+                res.push((table, schema.clone()));
+            }
+        }
+        Ok(res)
+    }
+
+    /// Returns the number of keys and rows stored in every cache, as the results of a `SELECT *
+    /// FROM readyset.view_stats` query
+    pub(crate) async fn view_stats(&mut self) -> ReadySetResult<QueryResult<'static>> {
+        let stats = noria_await!(
+            self.inner.get_mut()?,
+            self.inner.get_mut()?.noria.view_stats()
+        )?;
+
+        let columns = [
+            ("name", DfType::DEFAULT_TEXT),
+            ("keys", DfType::UnsignedBigInt),
+            ("rows", DfType::UnsignedBigInt),
+            ("partial", DfType::Bool),
+        ];
+        let schema = SelectSchema {
+            use_bogo: false,
+            schema: Cow::Owned(
+                columns
+                    .iter()
+                    .map(|(name, ty)| ColumnSchema {
+                        column: nom_sql::Column {
+                            name: (*name).into(),
+                            table: None,
+                        },
+                        column_type: ty.clone(),
+                        base: None,
+                    })
+                    .collect(),
+            ),
+            columns: Cow::Owned(columns.iter().map(|(name, _)| (*name).into()).collect()),
+        };
+
+        let data = stats
+            .into_iter()
+            .map(|(name, stats)| {
+                vec![
+                    name.to_string().into(),
+                    DfValue::from(stats.keys as u64),
+                    DfValue::from(stats.rows as u64),
+                    DfValue::from(stats.partial),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        Ok(QueryResult::from_owned(schema, vec![Results::new(data)]))
+    }
+
     /// Set the schema search path
     pub fn set_schema_search_path(&mut self, search_path: Vec<SqlIdentifier>) {
         self.schema_search_path = search_path;
@@ -1006,6 +1505,64 @@ impl NoriaConnector {
     pub fn schema_search_path(&self) -> &[SqlIdentifier] {
         self.schema_search_path.as_ref()
     }
+
+    /// Configure an [`IndexAdvisor`] to record the columns that reads through this connector get
+    /// filtered on after the lookup into the reader
+    pub fn set_index_advisor(&mut self, index_advisor: Arc<IndexAdvisor>) {
+        self.index_advisor = Some(index_advisor);
+    }
+
+    /// Configure a [`MicroCache`] to cache the results of point reads through this connector
+    pub fn set_micro_cache(&mut self, micro_cache: Arc<MicroCache>) {
+        self.micro_cache = Some(micro_cache);
+    }
+
+    /// Configure a [`NamespaceLimiter`] to limit the rate of reads through this connector from the
+    /// caches in each namespace
+    pub fn set_namespace_limiter(&mut self, namespace_limiter: Arc<NamespaceLimiter>) {
+        self.namespace_limiter = Some(namespace_limiter);
+    }
+
+    /// Configure a [`ReplicationLagMonitor`] to check reads through this connector against the
+    /// maximum replication lag of the caches they read from
+    pub fn set_replication_lag_monitor(&mut self, replication_lag: Arc<ReplicationLagMonitor>) {
+        self.replication_lag = Some(replication_lag);
+    }
+
+    /// Configure a limit on the size of rows inserted through this connector
+    pub fn set_row_size_limit(&mut self, row_size_limit: RowSizeLimit) {
+        self.row_size_limit = Some(row_size_limit);
+    }
+
+    /// Enable or disable snapshot reads through this connector.
+    ///
+    /// While snapshot reads are enabled, all reads are served at or after the latest timestamp
+    /// propagated through the dataflow graph by replication as of the first read. Enabling
+    /// snapshot reads again takes a new snapshot.
+    ///
+    /// The guarantee is per cache: each read reflects at least every replicated write up to the
+    /// snapshot to the tables that cache reads from. Caches are updated independently, so a read
+    /// may also reflect writes made after the snapshot, and reads of two different caches may
+    /// reflect different such writes. Snapshot reads don't give a consistent view across caches;
+    /// they only guarantee that no cache is read from a point older than the snapshot.
+    pub fn set_snapshot_reads(&mut self, snapshot_reads: bool) {
+        self.snapshot_reads = snapshot_reads;
+        self.snapshot = None;
+    }
+
+    /// Set the [`ReadBehavior`] for reads through this connector, overriding the one it was
+    /// created with
+    pub fn set_read_behavior(&mut self, read_behavior: ReadBehavior) {
+        self.read_behavior = read_behavior;
+    }
+
+    /// Drop all results from the [`MicroCache`], if any, after a write through this connector or
+    /// to the upstream database
+    pub(crate) fn invalidate_micro_cache(&self) {
+        if let Some(micro_cache) = &self.micro_cache {
+            micro_cache.invalidate_all();
+        }
+    }
 }
 
 impl NoriaConnector {
@@ -1016,18 +1573,10 @@ impl NoriaConnector {
         name: Option<&Relation>,
         statement: &nom_sql::SelectStatement,
         override_schema_search_path: Option<Vec<SqlIdentifier>>,
-        always: bool,
+        options: CacheOptions,
     ) -> ReadySetResult<()> {
-        let name = name.cloned().unwrap_or_else(|| {
-            utils::generate_query_name(statement, self.schema_search_path()).into()
-        });
-        let schema_search_path =
-            override_schema_search_path.unwrap_or_else(|| self.schema_search_path.clone());
-        let changelist = ChangeList::from_change(
-            Change::create_cache(name.clone(), statement.clone(), always),
-            self.dialect,
-        )
-        .with_schema_search_path(schema_search_path.clone());
+        let (name, changelist, schema_search_path) =
+            self.create_cache_changelist(name, statement, override_schema_search_path, options);
 
         noria_await!(
             self.inner.get_mut()?,
@@ -1045,6 +1594,62 @@ impl NoriaConnector {
         Ok(())
     }
 
+    /// Handles `CREATE CACHE CONCURRENTLY` statements, by returning a future which performs the
+    /// migration for the cache (including backfilling its initial state) independently of this
+    /// connector, and registers the cache's name once the migration completes.
+    pub fn create_cached_query_concurrently(
+        &mut self,
+        name: Option<&Relation>,
+        statement: &nom_sql::SelectStatement,
+        override_schema_search_path: Option<Vec<SqlIdentifier>>,
+        options: CacheOptions,
+    ) -> ReadySetResult<impl Future<Output = ReadySetResult<()>> + Send + 'static> {
+        let (name, changelist, schema_search_path) =
+            self.create_cache_changelist(name, statement, override_schema_search_path, options);
+        let mut noria = self.inner.get_mut()?.noria.clone();
+        let mut view_cache = self.view_cache.clone();
+        let view_request = ViewCreateRequest::new(statement.clone(), schema_search_path);
+
+        Ok(async move {
+            futures_util::future::poll_fn(|cx| noria.poll_ready(cx)).await?;
+            // The server backfills the cache in the background, so that other migrations aren't
+            // held up by it, and we poll for the backfill to complete
+            if let Some(backfill) = noria.extend_recipe_concurrently(changelist).await? {
+                loop {
+                    futures_util::future::poll_fn(|cx| noria.poll_ready(cx)).await?;
+                    if noria.backfill_done(backfill).await? {
+                        break;
+                    }
+                    tokio::time::sleep(BACKFILL_POLL_INTERVAL).await;
+                }
+            }
+            view_cache.register_statement(&name, view_request);
+            Ok(())
+        })
+    }
+
+    /// Builds the [`ChangeList`] for a `CREATE CACHE` statement, returning it along with the name
+    /// of the cache and the schema search path the statement will be migrated with
+    fn create_cache_changelist(
+        &self,
+        name: Option<&Relation>,
+        statement: &nom_sql::SelectStatement,
+        override_schema_search_path: Option<Vec<SqlIdentifier>>,
+        options: CacheOptions,
+    ) -> (Relation, ChangeList, Vec<SqlIdentifier>) {
+        let name = name.cloned().unwrap_or_else(|| {
+            utils::generate_query_name(statement, self.schema_search_path()).into()
+        });
+        let schema_search_path =
+            override_schema_search_path.unwrap_or_else(|| self.schema_search_path.clone());
+        let changelist = ChangeList::from_change(
+            Change::create_cache(name.clone(), statement.clone(), options),
+            self.dialect,
+        )
+        .with_schema_search_path(schema_search_path.clone());
+        (name, changelist, schema_search_path)
+    }
+
     async fn get_view(
         &mut self,
         q: &nom_sql::SelectStatement,
@@ -1066,7 +1671,7 @@ impl NoriaConnector {
                     }
 
                     let changelist = ChangeList::from_change(
-                        Change::create_cache(qname.clone(), q.clone(), false),
+                        Change::create_cache(qname.clone(), q.clone(), CacheOptions::default()),
                         self.dialect,
                     )
                     .with_schema_search_path(self.schema_search_path.clone());
@@ -1132,16 +1737,74 @@ impl NoriaConnector {
         self.view_cache.view_create_request_from_name(name)
     }
 
+    /// Register the given queries with the ReadySet controller, so that their ids can be resolved
+    /// by other adapters and across restarts
+    pub async fn register_queries(
+        &mut self,
+        queries: Vec<ViewCreateRequest>,
+    ) -> ReadySetResult<()> {
+        noria_await!(
+            self.inner.get_mut()?,
+            self.inner.get_mut()?.noria.register_queries(queries)
+        )
+    }
+
+    /// Check that the given (rewritten) ad-hoc query is the query cached under the name given in
+    /// its `READYSET(cache=...)` hint, so that we never read the query's results from a cache for
+    /// a different query
+    async fn check_hinted_cache(
+        &mut self,
+        name: &Relation,
+        statement: &nom_sql::SelectStatement,
+    ) -> ReadySetResult<()> {
+        let view_request =
+            ViewCreateRequest::new(statement.clone(), self.schema_search_path.clone());
+        if self.view_cache.statement_name(&view_request).as_ref() == Some(name) {
+            return Ok(());
+        }
+
+        let dialect = self.dialect;
+        let matches = noria_await!(
+            self.inner.get_mut()?,
+            self.inner.get_mut()?.noria.query_matches_cache(
+                name.clone(),
+                view_request.clone(),
+                dialect
+            )
+        )?;
+        if !matches {
+            invalid!("Query does not match the query cached as {}", name);
+        }
+        self.view_cache.register_statement(name, view_request);
+        Ok(())
+    }
+
+    /// Look up a query which was previously registered with the ReadySet controller by its id
+    pub async fn query_for_id(&mut self, id: QueryId) -> ReadySetResult<Option<ViewCreateRequest>> {
+        let query = noria_await!(
+            self.inner.get_mut()?,
+            self.inner.get_mut()?.noria.query_for_id(id)
+        )?;
+        Ok(query.map(|(_, request)| request))
+    }
+
+    /// Insert the rows in `data` into the table for the given `INSERT` statement. `update_params`
+    /// are the values of the parameters in its `ON DUPLICATE KEY UPDATE` clause, if any.
     async fn do_insert(
         &mut self,
         q: &InsertStatement,
         data: Vec<Vec<DfValue>>,
+        update_params: &[DfValue],
     ) -> ReadySetResult<QueryResult<'_>> {
         let table = &q.table;
 
         // create a mutator if we don't have one for this table already
+        let mut noria = self.inner.get_mut()?.noria.clone();
         trace!(%table, "insert::access mutator");
         let putter = self.inner.get_mut()?.get_noria_table(table).await?;
+        let table_name = putter.table_name().clone();
+
+        let key_columns = putter.key_columns();
         trace!("insert::extract schema");
         let schema = putter
             .schema()
@@ -1159,147 +1822,148 @@ impl NoriaConnector {
             })
             .collect();
 
-        // handle auto increment
-        trace!("insert::auto-increment");
-        let auto_increment_columns: Vec<_> = schema
+        let auto_increment_column = auto_increment::auto_increment_column(table, schema)?;
+
+        let mut buf = vec![vec![DfValue::None; schema.fields.len()]; data.len()];
+        // The values of the AUTO_INCREMENT column (if any) of each row, or `None` for rows which
+        // need a value generated for them, along with the indices of those rows
+        let mut auto_increment_values = vec![];
+        let mut generate_for = vec![];
+
+        // handle default values: columns the query doesn't specify get their default value,
+        // which is evaluated once for the whole statement
+        trace!("insert::default values");
+        let default_values = schema
             .fields
             .iter()
-            .filter(|c| c.constraints.contains(&ColumnConstraint::AutoIncrement))
-            .collect();
-        if auto_increment_columns.len() > 1 {
-            // can only have zero or one AUTO_INCREMENT columns
-            return Err(table_err(
-                table.clone(),
-                ReadySetError::MultipleAutoIncrement,
-            ));
-        }
+            .enumerate()
+            .filter(|(_, f)| !columns_specified.contains(&f.column))
+            .map(|(idx, f)| Ok((idx, column_default_value(f, self.dialect)?)))
+            .collect::<ReadySetResult<Vec<_>>>()?;
 
-        let ai = &mut self.auto_increments;
-        tokio::task::block_in_place(|| {
-            let ai_lock = ai.read().unwrap();
-            if ai_lock.get(table).is_none() {
-                drop(ai_lock);
-                ai.write()
-                    .unwrap()
-                    .entry(table.clone())
-                    .or_insert_with(|| atomic::AtomicUsize::new(0));
-            }
-        });
-        let mut buf = vec![vec![DfValue::None; schema.fields.len()]; data.len()];
-        let mut first_inserted_id = None;
-        tokio::task::block_in_place(|| -> ReadySetResult<_> {
-            let ai_lock = ai.read().unwrap();
-            let last_insert_id = &ai_lock[table];
-
-            // handle default values
-            trace!("insert::default values");
-            let mut default_value_columns = vec![];
-            for c in &schema.fields {
-                for cc in &c.constraints {
-                    if let ColumnConstraint::DefaultValue(ref def) = *cc {
-                        match def {
-                            Expr::Literal(v) => {
-                                default_value_columns.push((c.column.clone(), v.clone()))
-                            }
-                            _ => {
-                                unsupported!("Only literal values are supported in default values")
-                            }
-                        }
-                    }
-                }
+        trace!("insert::construct ops");
+
+        for (ri, row) in data.iter().enumerate() {
+            for (idx, v) in &default_values {
+                buf[ri][*idx] = v.clone();
             }
 
-            trace!("insert::construct ops");
+            for (ci, c) in columns_specified.iter().enumerate() {
+                let (idx, field) = schema
+                    .fields
+                    .iter()
+                    .find_position(|f| f.column == *c)
+                    .ok_or_else(|| {
+                        table_err(
+                            putter.table_name().clone(),
+                            ReadySetError::NoSuchColumn(c.name.to_string()),
+                        )
+                    })?;
+
+                let target_type = DfType::from_sql_type(&field.sql_type, self.dialect, |_| None)?;
+
+                let value = row
+                    .get(ci)
+                    .ok_or_else(|| {
+                        internal_err!(
+                            "Row returned from readyset-server had the wrong number of columns",
+                        )
+                    })?
+                    .coerce_to(&target_type, &DfType::Unknown)?; // No from_ty, we're inserting literals
+                buf[ri][idx] = value;
+            }
 
-            for (ri, row) in data.iter().enumerate() {
-                if let Some(col) = auto_increment_columns.get(0) {
-                    let idx = schema
-                        .fields
-                        .iter()
-                        .position(|f| f == *col)
-                        .ok_or_else(|| {
-                            table_err(
-                                table.clone(),
-                                ReadySetError::NoSuchColumn(col.column.name.to_string()),
-                            )
-                        })?;
-                    // query can specify an explicit AUTO_INCREMENT value
-                    if !columns_specified.contains(&col.column) {
-                        let id = last_insert_id.fetch_add(1, atomic::Ordering::SeqCst) as i64 + 1;
-                        if first_inserted_id.is_none() {
-                            first_inserted_id = Some(id);
+            if let Some(idx) = auto_increment_column {
+                match i64::try_from(&buf[ri][idx]) {
+                    // query can specify an explicit AUTO_INCREMENT value, which later generated
+                    // values must come after
+                    Ok(id) if id != 0 => {
+                        if let Ok(id) = u64::try_from(id) {
+                            auto_increment_values.push(Some(id));
                         }
-                        buf[ri][idx] = DfValue::from(id);
                     }
-                }
-
-                for (c, v) in default_value_columns.drain(..) {
-                    let idx = schema
-                        .fields
-                        .iter()
-                        .position(|f| f.column == c)
-                        .ok_or_else(|| {
-                            table_err(
-                                table.clone(),
-                                ReadySetError::NoSuchColumn(c.name.to_string()),
-                            )
-                        })?;
-                    // only use default value if query doesn't specify one
-                    if !columns_specified.contains(&c) {
-                        buf[ri][idx] = v.try_into()?;
+                    // otherwise (or if the value is NULL or 0), generate one
+                    _ => {
+                        auto_increment_values.push(None);
+                        generate_for.push(ri);
                     }
                 }
+            }
+        }
 
-                for (ci, c) in columns_specified.iter().enumerate() {
-                    let (idx, field) = schema
-                        .fields
-                        .iter()
-                        .find_position(|f| f.column == *c)
-                        .ok_or_else(|| {
-                            table_err(
-                                putter.table_name().clone(),
-                                ReadySetError::NoSuchColumn(c.name.to_string()),
-                            )
-                        })?;
-
-                    let target_type =
-                        DfType::from_sql_type(&field.sql_type, self.dialect, |_| None)?;
-
-                    let value = row
-                        .get(ci)
-                        .ok_or_else(|| {
-                            internal_err!(
-                                "Row returned from readyset-server had the wrong number of columns",
-                            )
-                        })?
-                        .coerce_to(&target_type, &DfType::Unknown)?; // No from_ty, we're inserting literals
-                    buf[ri][idx] = value;
+        let mut first_inserted_id = None;
+        if let Some(idx) = auto_increment_column.filter(|_| !auto_increment_values.is_empty()) {
+            trace!("insert::auto-increment::generate");
+            let ids = auto_increment::generate_auto_increments(
+                &mut noria,
+                &table_name,
+                idx,
+                auto_increment_values,
+            )
+            .await?;
+            first_inserted_id = ids.first().map(|&id| id as i64);
+            for (ri, id) in generate_for.into_iter().zip(ids) {
+                buf[ri][idx] = DfValue::from(id);
+            }
+        }
+
+        let mut oversized_rows = 0;
+        if let Some(limit) = self.row_size_limit {
+            buf.retain_mut(|row| {
+                let check = limit.check(row, &key_columns);
+                if check.action().is_some() {
+                    oversized_rows += 1;
                 }
+                handle_oversized_row(&table_name, &limit, check)
+            });
+            if buf.is_empty() {
+                return Ok(QueryResult::Insert {
+                    num_rows_inserted: 0,
+                    first_inserted_id: 0,
+                    oversized_rows,
+                });
             }
-            Ok(())
-        })?;
+        }
+        let num_rows_inserted = buf.len() as u64;
 
         let result = if let Some(ref update_fields) = q.on_duplicate {
             trace!("insert::complex");
-            invariant_eq!(buf.len(), 1);
-
-            let updates = {
+            let mut updates = {
                 // fake out an update query
                 let mut uq = UpdateStatement {
                     table: table.clone(),
                     fields: update_fields.clone(),
+                    from: vec![],
                     where_clause: None,
                 };
                 utils::extract_update_params_and_fields(
                     &mut uq,
-                    &mut None::<std::iter::Empty<DfValue>>,
+                    &mut Some(update_params.iter().cloned()),
                     schema,
                     self.dialect,
                 )?
             };
+            if let Some(limit) = self.row_size_limit {
+                let check =
+                    limit.check_update(updates.iter_mut().map(|(col, m)| (*col, m)), &key_columns);
+                if !handle_oversized_row(&table_name, &limit, check) {
+                    return Ok(QueryResult::Insert {
+                        num_rows_inserted: 0,
+                        first_inserted_id: 0,
+                        oversized_rows: oversized_rows + num_rows_inserted,
+                    });
+                }
+                if check.action().is_some() {
+                    // The update applies to every row which conflicts with an existing one, so
+                    // count all of them
+                    oversized_rows = num_rows_inserted;
+                }
+            }
 
-            // TODO(malte): why can't I consume buf here?
-            let r = putter.insert_or_update(buf[0].clone(), updates).await;
+            // Each row is inserted, or updates the row with the same key, in turn, so that later
+            // rows see the effect of earlier ones, but all in one batch so that the statement is
+            // applied atomically
+            let r = putter.insert_or_update_many(buf, updates).await;
             trace!("insert::complex::complete");
             r
         } else {
@@ -1309,10 +1973,12 @@ impl NoriaConnector {
             trace!("insert::simple::complete");
             r
         };
+        self.invalidate_micro_cache();
         result?;
         Ok(QueryResult::Insert {
-            num_rows_inserted: data.len() as u64,
+            num_rows_inserted,
             first_inserted_id: first_inserted_id.unwrap_or(0) as u64,
+            oversized_rows,
         })
     }
 
@@ -1325,6 +1991,7 @@ impl NoriaConnector {
         let mutator = self.inner.get_mut()?.get_noria_table(&q.table).await?;
 
         let q = q.into_owned();
+        let table = q.table.clone();
         let (key, updates) = {
             trace!("update::extract schema");
             let schema = if let Some(cts) = mutator.schema() {
@@ -1344,13 +2011,16 @@ impl NoriaConnector {
         };
 
         trace!("update::update");
-        mutator.update(key, updates).await?;
+        let res = self.update_with_cascade(&table, vec![key], updates).await;
+        self.invalidate_micro_cache();
+        let check = res?;
         trace!("update::complete");
         // TODO: return meaningful fields for (num_rows_updated, last_inserted_id) rather than
         // hardcoded (1,0)
         Ok(QueryResult::Update {
             num_rows_updated: 1,
             last_inserted_id: 0,
+            oversized_rows: u64::from(check.action().is_some()),
         })
     }
 
@@ -1363,6 +2033,7 @@ impl NoriaConnector {
         let mutator = self.inner.get_mut()?.get_noria_table(&q.table).await?;
 
         let q = q.into_owned();
+        let table = q.table.clone();
         let key = {
             trace!("delete::extract schema");
             let schema = if let Some(cts) = mutator.schema() {
@@ -1377,7 +2048,9 @@ impl NoriaConnector {
         };
 
         trace!("delete::delete");
-        mutator.delete(key).await?;
+        let res = self.delete_with_cascade(&table, vec![key]).await;
+        self.invalidate_micro_cache();
+        res?;
         trace!("delete::complete");
         // TODO: return meaningful fields for (num_rows_deleted, last_inserted_id) rather than
         // hardcoded (1,0)
@@ -1398,9 +2071,9 @@ impl NoriaConnector {
         // note that we have to do this *before* processing the query, otherwise the
         // client will be confused about the number of parameters it's supposed to
         // give.
-        let client_param_columns: Vec<_> = utils::select_statement_parameter_columns(&statement)
+        let client_param_columns: Vec<_> = utils::select_statement_placeholder_columns(&statement)
             .into_iter()
-            .cloned()
+            .map(|col| col.cloned())
             .collect();
 
         let limit_columns: Vec<_> = utils::get_limit_parameters(&statement)
@@ -1449,15 +2122,29 @@ impl NoriaConnector {
             .insert(statement_id, PreparedStatement::Select(ps));
 
         if let Some(getter_schema) = getter_schema {
-            let mut params: Vec<_> = getter_schema
-                .to_cols(&client_param_columns, SchemaType::ProjectedSchema)?
-                .into_iter()
-                .map(|cs| {
-                    let mut cs = cs.clone();
+            let mut params = client_param_columns
+                .iter()
+                .map(|col| -> ReadySetResult<_> {
+                    let mut cs = match col {
+                        Some(col) => getter_schema
+                            .to_cols(iter::once(col), SchemaType::ProjectedSchema)?
+                            .remove(0)
+                            .clone(),
+                        // We can't infer a type for placeholders that aren't compared directly
+                        // against a column, such as arguments to functions
+                        None => ColumnSchema {
+                            column: Column {
+                                name: "?".into(),
+                                table: None,
+                            },
+                            column_type: DfType::Unknown,
+                            base: None,
+                        },
+                    };
                     cs.column.table = Some(qname.clone());
-                    cs
+                    Ok(cs)
                 })
-                .collect();
+                .collect::<ReadySetResult<Vec<_>>>()?;
 
             params.extend(limit_columns);
             Ok(PrepareResult::Select(SelectPrepareResult::Schema(
@@ -1481,6 +2168,7 @@ impl NoriaConnector {
         ticket: Option<Timestamp>,
         event: &mut readyset_client_metrics::QueryExecutionEvent,
     ) -> ReadySetResult<QueryResult<'_>> {
+        let mut read_behavior = self.read_behavior;
         let (qname, statement, processed_query_params, params) = match ctx {
             ExecuteSelectContext::Prepared { q_id, params } => {
                 let PreparedSelectStatement {
@@ -1505,11 +2193,22 @@ impl NoriaConnector {
                 mut statement,
                 query,
                 create_if_missing,
+                hints,
             } => {
                 verify_no_placeholders(&mut statement, query)?;
                 let processed_query_params =
                     rewrite::process_query(&mut statement, self.server_supports_pagination())?;
-                let name = self.get_view(&statement, false, create_if_missing).await?;
+                if hints.replay {
+                    read_behavior = ReadBehavior::Blocking;
+                }
+                let name = match &hints.cache {
+                    Some(cache) => {
+                        let name = cache.clone().into();
+                        self.check_hinted_cache(&name, &statement).await?;
+                        name
+                    }
+                    None => self.get_view(&statement, false, create_if_missing).await?,
+                };
                 (
                     Cow::Owned(name),
                     Cow::Owned(statement),
@@ -1519,6 +2218,23 @@ impl NoriaConnector {
             }
         };
 
+        if let Some(namespace_limiter) = &self.namespace_limiter {
+            namespace_limiter.check_read(statement.as_ref(), &self.schema_search_path)?;
+        }
+
+        if self.snapshot_reads && self.snapshot.is_none() {
+            let snapshot = self.replication_timestamp().await?;
+            trace!(%snapshot, "Taking snapshot for snapshot reads");
+            self.snapshot = Some(snapshot);
+        }
+
+        // Cached results may be older than the snapshot, or than the writes the ticket requires the
+        // read to reflect, so reads with either neither use nor populate the micro cache
+        let micro_cache = self
+            .micro_cache
+            .as_deref()
+            .filter(|_| self.snapshot.is_none() && ticket.is_none());
+
         let view_failed = self.failed_views.take(qname.as_ref()).is_some();
         let getter = self
             .inner
@@ -1532,8 +2248,12 @@ impl NoriaConnector {
             params,
             statement.as_ref(),
             ticket,
-            self.read_behavior,
+            self.snapshot.clone(),
+            read_behavior,
             self.read_request_handler.as_mut(),
+            self.index_advisor.as_deref(),
+            self.replication_lag.as_deref(),
+            micro_cache,
             event,
             self.dialect,
         )
@@ -1602,21 +2322,25 @@ fn build_view_query<'a>(
     params: &[DfValue],
     q: &nom_sql::SelectStatement,
     ticket: Option<Timestamp>,
+    snapshot: Option<Timestamp>,
     read_behavior: ReadBehavior,
     dialect: Dialect,
 ) -> ReadySetResult<Option<(&'a mut ReaderHandle, ViewQuery)>> {
     let (limit, offset) = processed_query_params.limit_offset_params(params)?;
     let raw_keys = processed_query_params.make_keys(params)?;
 
-    getter.build_view_query(
-        raw_keys,
-        limit,
-        offset,
-        ticket,
-        read_behavior.is_blocking(),
-        dialect,
-        utils::get_select_statement_binops(q),
-    )
+    Ok(getter
+        .build_view_query(
+            raw_keys,
+            limit,
+            offset,
+            ticket,
+            read_behavior,
+            dialect,
+            utils::get_select_statement_binops(q),
+            processed_query_params.post_lookup_predicates(),
+        )?
+        .map(|(reader_handle, vq)| (reader_handle, ViewQuery { snapshot, ..vq })))
 }
 
 /// Run the supplied [`SelectStatement`] on the supplied [`View`]
@@ -1629,8 +2353,12 @@ async fn do_read<'a>(
     params: &[DfValue],
     q: &nom_sql::SelectStatement,
     ticket: Option<Timestamp>,
+    snapshot: Option<Timestamp>,
     read_behavior: ReadBehavior,
-    read_request_handler: Option<&'a mut ReadRequestHandler>,
+    mut read_request_handler: Option<&'a mut ReadRequestHandler>,
+    index_advisor: Option<&IndexAdvisor>,
+    replication_lag: Option<&ReplicationLagMonitor>,
+    micro_cache: Option<&MicroCache>,
     event: &mut readyset_client_metrics::QueryExecutionEvent,
     dialect: Dialect,
 ) -> ReadySetResult<QueryResult<'a>> {
@@ -1640,6 +2368,7 @@ async fn do_read<'a>(
         params,
         q,
         ticket,
+        snapshot,
         read_behavior,
         dialect,
     )? {
@@ -1648,7 +2377,63 @@ async fn do_read<'a>(
     };
 
     event.num_keys = Some(vq.key_comparisons.len() as _);
+    event.cache_name = Some(reader_handle.name().clone());
+    if event.record_keys {
+        event.keys = Some(vq.key_comparisons.clone());
+    }
 
+    if let Some(replication_lag) = replication_lag {
+        replication_lag.check(reader_handle.name(), reader_handle.freshness())?;
+    }
+
+    if let Some(index_advisor) = index_advisor {
+        index_advisor.record(reader_handle.name(), &vq.filtered_columns());
+    }
+
+    // Only point reads with no post-processing after the lookup are cached in the micro cache
+    let micro_cache = micro_cache
+        .filter(|_| vq.filter.is_none() && vq.limit.is_none() && vq.offset.is_none())
+        .and_then(|micro_cache| {
+            let keys = vq
+                .key_comparisons
+                .iter()
+                .map(|k| match k {
+                    KeyComparison::Equal(key) => Some(key.to_vec()),
+                    KeyComparison::Range(_) => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some((micro_cache, MicroCache::key(reader_handle.name(), keys)))
+        });
+    let mut micro_cache_updates = vec![];
+    if let Some((micro_cache, key)) = &micro_cache {
+        if let Some((rows, encoded)) = micro_cache.get(key) {
+            trace!("select::micro_cache_hit");
+            event.result_rows = Some(rows.len() as _);
+            return Ok(QueryResult::from_iter(
+                select_schema(reader_handle),
+                ResultIterator::shared(rows),
+            )
+            .with_encoded(Some(encoded)));
+        }
+        // Subscribe to updates to every shard of a local reader *before* reading from it, so that
+        // we can't miss an update that happens between the read and inserting the results into
+        // the cache
+        if let Some(rh) = read_request_handler.as_mut() {
+            micro_cache_updates = (0..reader_handle.num_shards())
+                .map(|shard| {
+                    rh.reader_updates(&ReaderAddress {
+                        node: *reader_handle.node(),
+                        name: reader_handle.name().clone(),
+                        shard,
+                    })
+                })
+                .collect::<ReadySetResult<Vec<_>>>()
+                .unwrap_or_default();
+        }
+    }
+
+    // Stats of reads from a local reader are returned separately from the results
+    let mut local_stats = None;
     let data = if let Some(rh) = read_request_handler {
         let request = readyset_client::Tagged::from(ReadQuery::Normal {
             target: ReaderAddress {
@@ -1669,12 +2454,15 @@ async fn do_read<'a>(
                 CallResult::Async(chan) => chan.await?,
             };
 
-            result
-                .v
-                .into_normal()
-                .ok_or_else(|| internal_err!("Unexpected response type from reader service"))??
-                .into_results()
-                .ok_or(ReadySetError::ReaderMissingKey)?
+            let (mut results, stats) =
+                match result.v.into_normal().ok_or_else(|| {
+                    internal_err!("Unexpected response type from reader service")
+                })?? {
+                    LookupResult::Results(results, stats) => (results, stats),
+                    LookupResult::NonBlockingMiss => return Err(ReadySetError::ReaderMissingKey),
+                };
+            local_stats = Some(stats);
+            results
                 .pop()
                 .ok_or_else(|| internal_err!("Expected a single result set for local reader"))?
                 .into_unserialized()
@@ -1686,24 +2474,78 @@ async fn do_read<'a>(
         reader_handle.raw_lookup(vq).await?
     };
 
-    event.cache_misses = data.total_stats().map(|s| s.cache_misses);
+    let stats = local_stats.or_else(|| data.total_stats());
+    event.cache_misses = stats.as_ref().map(|s| s.cache_misses);
+    event.replay_wait = stats
+        .as_ref()
+        .map(|s| Duration::from_micros(s.replay_wait_us));
+    event.result_rows = stats.as_ref().map(|s| s.rows);
+    let truncated = stats.as_ref().map_or(false, |s| s.truncated);
 
     trace!("select::complete");
 
-    Ok(QueryResult::from_iter(
-        SelectSchema {
-            // TODO(vlad): looks like poor `use_bogo` is unused except in js? Should just remove it.
-            use_bogo: false,
-            schema: Cow::Borrowed(
-                reader_handle
-                    .schema()
-                    .unwrap()
-                    .schema(SchemaType::ReturnedSchema),
-            ), /* Safe because we already unwrapped above */
-            columns: Cow::Borrowed(reader_handle.columns()),
-        },
-        data,
-    ))
+    // Don't cache truncated results, since reads from the micro cache can't report the truncation
+    if let Some((micro_cache, key)) = micro_cache.filter(|_| !truncated) {
+        // The rows are shared between the cache entry and the results, rather than copied
+        let rows = Arc::new(data.into_vec());
+        let encoded = micro_cache.insert(key, rows.clone(), micro_cache_updates);
+        return Ok(QueryResult::from_iter(
+            select_schema(reader_handle),
+            ResultIterator::shared(rows),
+        )
+        .with_encoded(Some(encoded)));
+    }
+
+    Ok(QueryResult::from_iter(select_schema(reader_handle), data).with_truncated(truncated))
+}
+
+/// Formats a single record of a CSV file, as described in RFC 4180, without the line break which
+/// terminates it. `None` values (SQL `NULL`s) are written as empty fields.
+fn csv_record<I>(fields: I) -> String
+where
+    I: IntoIterator<Item = Option<String>>,
+{
+    fields
+        .into_iter()
+        .map(|field| match field {
+            Some(field) if field.contains(|c| matches!(c, ',' | '"' | '\r' | '\n')) => {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            }
+            Some(field) => field,
+            None => String::new(),
+        })
+        .join(",")
+}
+
+/// Returns the schema of results consisting of a single column with the given name and type
+fn single_column_schema(name: &str, column_type: DfType) -> SelectSchema<'static> {
+    SelectSchema {
+        use_bogo: false,
+        schema: Cow::Owned(vec![ColumnSchema {
+            column: nom_sql::Column {
+                name: name.into(),
+                table: None,
+            },
+            column_type,
+            base: None,
+        }]),
+        columns: Cow::Owned(vec![name.into()]),
+    }
+}
+
+/// Returns the schema of the results of a read from `reader_handle`
+fn select_schema(reader_handle: &ReaderHandle) -> SelectSchema<'_> {
+    SelectSchema {
+        // TODO(vlad): looks like poor `use_bogo` is unused except in js? Should just remove it.
+        use_bogo: false,
+        schema: Cow::Borrowed(
+            reader_handle
+                .schema()
+                .unwrap()
+                .schema(SchemaType::ReturnedSchema),
+        ), /* Safe because building the view query fails for views without a schema */
+        columns: Cow::Borrowed(reader_handle.columns()),
+    }
 }
 
 #[cfg(test)]
@@ -1788,4 +2630,18 @@ mod tests {
             _ => panic!(),
         }
     }
+
+    #[test]
+    fn csv_record_quoting() {
+        assert_eq!(
+            csv_record([
+                Some("plain".to_owned()),
+                None,
+                Some("a,b".to_owned()),
+                Some("say \"hi\"".to_owned()),
+                Some("two\nlines".to_owned()),
+            ]),
+            "plain,,\"a,b\",\"say \"\"hi\"\"\",\"two\nlines\""
+        );
+    }
 }
@@ -0,0 +1,118 @@
+//! Generating values for `AUTO_INCREMENT` columns in writes made directly to base tables.
+//!
+//! Values are generated by the controller rather than by each adapter, so that they're unique
+//! across every adapter writing to a table. The controller keeps a counter for every base table
+//! with an `AUTO_INCREMENT` column which holds the last value generated for the column, and is
+//! initialized the first time it's needed to the largest value of the column that's already in the
+//! table (which is read from the table's state directly), so that values generated after a restart
+//! continue on from the rows in the table's snapshot. When a table is created through the adapter,
+//! its counter is additionally advanced to just before the value of the `AUTO_INCREMENT` table
+//! option, if one is given.
+//!
+//! As in MySQL, explicit values inserted into an `AUTO_INCREMENT` column advance the counter past
+//! them, and inserting `NULL` or `0` generates a new value.
+
+use nom_sql::{ColumnConstraint, CreateTableBody, CreateTableStatement, Relation};
+use readyset_client::ReadySetHandle;
+use readyset_errors::{table_err, ReadySetError, ReadySetResult};
+
+use super::{NoriaConnector, QueryResult};
+
+/// Returns the index of the `AUTO_INCREMENT` column in `schema`, if any, or an error if the table
+/// has more than one
+pub(super) fn auto_increment_column(
+    table: &Relation,
+    schema: &CreateTableBody,
+) -> ReadySetResult<Option<usize>> {
+    let mut columns = schema
+        .fields
+        .iter()
+        .enumerate()
+        .filter(|(_, f)| f.constraints.contains(&ColumnConstraint::AutoIncrement))
+        .map(|(i, _)| i);
+    let column = columns.next();
+    if columns.next().is_some() {
+        // can only have zero or one AUTO_INCREMENT columns
+        return Err(table_err(
+            table.clone(),
+            ReadySetError::MultipleAutoIncrement,
+        ));
+    }
+    Ok(column)
+}
+
+/// Generate values for the `AUTO_INCREMENT` column at index `column` of `table`, for a write of
+/// rows whose values for that column are given by `values`: `Some` for rows with an explicit value,
+/// or `None` for rows which need a value generated. Returns the generated values, in order.
+pub(super) async fn generate_auto_increments(
+    noria: &mut ReadySetHandle,
+    table: &Relation,
+    column: usize,
+    values: Vec<Option<u64>>,
+) -> ReadySetResult<Vec<u64>> {
+    futures_util::future::poll_fn(|cx| noria.poll_ready(cx)).await?;
+    noria.auto_increments(table.clone(), column, values).await
+}
+
+impl NoriaConnector {
+    /// Creates the table described by the given `CREATE TABLE` statement, and advances the auto
+    /// increment counter for it to just before the value of the statement's `AUTO_INCREMENT` table
+    /// option, if it has one and the table has an `AUTO_INCREMENT` column.
+    pub(crate) async fn handle_create_table(
+        &mut self,
+        q: &CreateTableStatement,
+    ) -> ReadySetResult<QueryResult<'_>> {
+        self.handle_table_operation(q.clone()).await?;
+
+        let column = match &q.body {
+            Ok(body) => auto_increment_column(&q.table, body)?,
+            Err(_) => None,
+        };
+        let column = match column {
+            Some(column) => column,
+            None => return Ok(QueryResult::Empty),
+        };
+
+        // The table was created in the first schema in the search path, unless the statement
+        // names a schema explicitly
+        let mut table = q.table.clone();
+        if table.schema.is_none() {
+            table.schema = self.schema_search_path.first().cloned();
+        }
+        let putter = self.inner.get_mut()?.get_noria_table(&table).await?;
+        let table = putter.table_name().clone();
+
+        if let Some(start) = q.get_autoincrement() {
+            let last = start.saturating_sub(1);
+            let mut noria = self.inner.get_mut()?.noria.clone();
+            generate_auto_increments(&mut noria, &table, column, vec![Some(last)]).await?;
+        }
+
+        Ok(QueryResult::Empty)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::{parse_create_table, Dialect};
+
+    use super::*;
+
+    fn schema(sql: &str) -> (Relation, CreateTableBody) {
+        let stmt = parse_create_table(Dialect::MySQL, sql).unwrap();
+        (stmt.table, stmt.body.unwrap())
+    }
+
+    #[test]
+    fn finds_auto_increment_column() {
+        let (table, body) = schema("CREATE TABLE t (a int, b int AUTO_INCREMENT PRIMARY KEY)");
+        assert_eq!(auto_increment_column(&table, &body).unwrap(), Some(1));
+
+        let (table, body) = schema("CREATE TABLE t (a int PRIMARY KEY, b int)");
+        assert_eq!(auto_increment_column(&table, &body).unwrap(), None);
+
+        let (table, body) =
+            schema("CREATE TABLE t (a int AUTO_INCREMENT, b int AUTO_INCREMENT PRIMARY KEY)");
+        auto_increment_column(&table, &body).unwrap_err();
+    }
+}
@@ -0,0 +1,663 @@
+//! Applying the referential actions of foreign keys (`ON DELETE` and `ON UPDATE`) to writes made
+//! directly to base tables.
+//!
+//! When writes go through the adapter rather than being replicated from an upstream database,
+//! nothing else enforces foreign keys. Deleting a row from a table referenced by a foreign key
+//! declared with `ON DELETE CASCADE` (or changing the referenced columns of a row, for `ON UPDATE
+//! CASCADE`) would then leave dangling rows in the referencing table. To avoid that, before writing
+//! to a base table we look up the rows which reference the rows being changed (directly in the
+//! state of the referencing table, so that no caches are created and the rows are never stale)
+//! and compute the writes needed to apply the `CASCADE` and `SET NULL` referential actions to
+//! them. This happens recursively, since those writes may need to cascade too. All the writes to
+//! each table are then applied in a single batch.
+//!
+//! Along with each write we compute the write that undoes it. If the batch for one table fails,
+//! the batches already applied to other tables are undone, so a failed write never leaves only
+//! some of its cascades applied. The batches are still applied one table at a time, so readers
+//! may briefly observe a cascade that has only been applied to some of the tables.
+//!
+//! Other referential actions (`RESTRICT`, `NO ACTION`, and `SET DEFAULT`) are not enforced.
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use database_utils::row_size::RowSizeCheck;
+use nom_sql::{Column, CreateTableBody, ReferentialAction, Relation, TableKey};
+use readyset_client::{Modification, Operation, TableOperation};
+use readyset_data::DfValue;
+use readyset_errors::{internal_err, unsupported, ReadySetResult};
+use readyset_tracing::error;
+
+use super::{handle_oversized_row, NoriaConnector};
+use crate::utils;
+
+/// A foreign key in one table which references another table, and which has at least one
+/// referential action that we apply
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ReferencingKey {
+    /// The table containing the foreign key
+    table: Relation,
+    /// The indices of the columns of the foreign key in `table`
+    columns: Vec<usize>,
+    /// The indices of the referenced columns in the referenced table
+    target_columns: Vec<usize>,
+    on_delete: Option<ReferentialAction>,
+    on_update: Option<ReferentialAction>,
+}
+
+/// The foreign keys in all base tables which have a referential action we apply, grouped by the
+/// table they reference.
+///
+/// Finding these requires the schema of every base table, so they're loaded once and cached rather
+/// than being looked up from the controller for every write. The cache is cleared whenever DDL is
+/// run through the connector that owns it.
+#[derive(Debug, Default)]
+pub(super) struct ForeignKeyCache {
+    keys: Option<HashMap<Relation, Vec<ReferencingKey>>>,
+}
+
+impl ForeignKeyCache {
+    /// Clear the cache, so that the foreign keys are loaded again before the next write
+    pub(super) fn invalidate(&mut self) {
+        self.keys = None;
+    }
+}
+
+/// A change to a row in a table, which may need to cascade to tables referencing it
+#[derive(Debug)]
+enum RowChange {
+    Delete {
+        old: Vec<DfValue>,
+    },
+    Update {
+        old: Vec<DfValue>,
+        new: Vec<DfValue>,
+    },
+}
+
+/// Returns true if we apply the given referential action
+fn is_applied(action: Option<ReferentialAction>) -> bool {
+    matches!(
+        action,
+        Some(ReferentialAction::Cascade | ReferentialAction::SetNull)
+    )
+}
+
+/// Returns the indices of the columns named by `columns` in `schema`, or `None` if any of them
+/// don't exist
+fn column_indices(schema: &CreateTableBody, columns: &[Column]) -> Option<Vec<usize>> {
+    columns
+        .iter()
+        .map(|col| schema.fields.iter().position(|f| f.column.name == col.name))
+        .collect()
+}
+
+/// Returns all the foreign keys in `table` (with schema `schema`) which reference the table
+/// `target` (with schema `target_schema`), and have a referential action we apply
+fn referencing_keys(
+    target: &Relation,
+    target_schema: &CreateTableBody,
+    table: &Relation,
+    schema: &CreateTableBody,
+) -> Vec<ReferencingKey> {
+    schema
+        .keys
+        .iter()
+        .flatten()
+        .filter_map(|key| match key {
+            TableKey::ForeignKey {
+                columns,
+                target_table,
+                target_columns,
+                on_delete,
+                on_update,
+                ..
+            } => {
+                // An unqualified referenced table is in the same schema as the referencing table
+                let target_table_schema = target_table.schema.as_ref().or(table.schema.as_ref());
+                if target_table.name != target.name
+                    || target_table_schema != target.schema.as_ref()
+                    || !(is_applied(*on_delete) || is_applied(*on_update))
+                {
+                    return None;
+                }
+
+                let columns = column_indices(schema, columns)?;
+                let target_columns = if target_columns.is_empty() {
+                    // A foreign key without referenced columns references the primary key
+                    utils::get_primary_key(target_schema)
+                        .into_iter()
+                        .map(|(i, _)| i)
+                        .collect()
+                } else {
+                    column_indices(target_schema, target_columns)?
+                };
+                if columns.is_empty() || columns.len() != target_columns.len() {
+                    return None;
+                }
+
+                Some(ReferencingKey {
+                    table: table.clone(),
+                    columns,
+                    target_columns,
+                    on_delete: *on_delete,
+                    on_update: *on_update,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Apply a set of modifications to a row, the same way the base table would
+fn apply_modifications(
+    row: &[DfValue],
+    updates: &[(usize, Modification)],
+) -> ReadySetResult<Vec<DfValue>> {
+    let mut row = row.to_vec();
+    for (col, modification) in updates {
+        let value = row
+            .get_mut(*col)
+            .ok_or_else(|| internal_err!("Column index {col} out of bounds"))?;
+        match modification {
+            Modification::Set(v) => *value = v.clone(),
+            Modification::Apply(op, v) => {
+                let old = i128::try_from(value.clone())?;
+                let delta = i128::try_from(v.clone())?;
+                *value = DfValue::try_from(match op {
+                    Operation::Add => old + delta,
+                    Operation::Sub => old - delta,
+                })?;
+            }
+            Modification::None => {}
+        }
+    }
+    Ok(row)
+}
+
+/// The writes to apply to each table, along with the writes which undo them
+#[derive(Debug, Default)]
+struct Writes {
+    ops: BTreeMap<Relation, Vec<TableOperation>>,
+    undo: BTreeMap<Relation, Vec<TableOperation>>,
+}
+
+impl Writes {
+    /// Add a write to `table`, which is undone by `undo`
+    fn push(&mut self, table: &Relation, op: TableOperation, undo: TableOperation) {
+        self.ops.entry(table.clone()).or_default().push(op);
+        self.undo.entry(table.clone()).or_default().push(undo);
+    }
+}
+
+/// Returns the write which undoes a change from the row `old` to the row `new` in a table with
+/// the primary key `pkey`
+fn restore_row(pkey: &[usize], old: &[DfValue], new: Vec<DfValue>) -> TableOperation {
+    TableOperation::Update {
+        key: pkey.iter().map(|&i| new[i].clone()).collect(),
+        update: old.iter().cloned().map(Modification::Set).collect(),
+    }
+}
+
+impl NoriaConnector {
+    /// Returns the resolved name of the given table, its schema, and the indices of the columns
+    /// in its primary key
+    pub(super) async fn base_table_info(
+        &mut self,
+        table: &Relation,
+    ) -> ReadySetResult<(Relation, CreateTableBody, Vec<usize>)> {
+        let mutator = self.inner.get_mut()?.get_noria_table(table).await?;
+        let schema = match mutator.schema() {
+            Some(schema) => schema.clone(),
+            None => unsupported!("cannot write to view"),
+        };
+        let pkey = utils::get_primary_key(&schema)
+            .into_iter()
+            .map(|(i, _)| i)
+            .collect();
+        Ok((mutator.table_name().clone(), schema, pkey))
+    }
+
+    /// Returns all the foreign keys in base tables which reference the table `target`, and have a
+    /// referential action we apply, loading them into the [`ForeignKeyCache`] if necessary
+    async fn referencing_keys(&mut self, target: &Relation) -> ReadySetResult<Vec<ReferencingKey>> {
+        if self.foreign_keys.keys.is_none() {
+            let keys = self.load_referencing_keys().await?;
+            self.foreign_keys.keys = Some(keys);
+        }
+        Ok(self
+            .foreign_keys
+            .keys
+            .as_ref()
+            .and_then(|keys| keys.get(target))
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    /// Look up the foreign keys in all base tables which have a referential action we apply,
+    /// grouped by the table they reference
+    async fn load_referencing_keys(
+        &mut self,
+    ) -> ReadySetResult<HashMap<Relation, Vec<ReferencingKey>>> {
+        let tables = noria_await!(self.inner.get_mut()?, self.inner.get_mut()?.noria.tables())?;
+
+        let mut res: HashMap<Relation, Vec<ReferencingKey>> = HashMap::new();
+        for table in tables.into_keys() {
+            let mutator = self.inner.get_mut()?.get_noria_table(&table).await?;
+            let schema = match mutator.schema() {
+                Some(schema) => schema.clone(),
+                None => continue,
+            };
+
+            let targets = schema
+                .keys
+                .iter()
+                .flatten()
+                .filter_map(|key| match key {
+                    TableKey::ForeignKey {
+                        target_table,
+                        on_delete,
+                        on_update,
+                        ..
+                    } if is_applied(*on_delete) || is_applied(*on_update) => Some(Relation {
+                        // An unqualified referenced table is in the same schema as the referencing
+                        // table
+                        schema: target_table.schema.clone().or_else(|| table.schema.clone()),
+                        name: target_table.name.clone(),
+                    }),
+                    _ => None,
+                })
+                .collect::<HashSet<_>>();
+
+            for target in targets {
+                let (target, target_schema, _) = match self.base_table_info(&target).await {
+                    Ok(info) => info,
+                    // Foreign keys referencing tables which don't exist (yet) can't cascade
+                    Err(e) if e.caused_by_table_not_found() => continue,
+                    Err(e) => return Err(e),
+                };
+                let keys = referencing_keys(&target, &target_schema, &table, &schema);
+                res.entry(target).or_default().extend(keys);
+            }
+        }
+        Ok(res)
+    }
+
+    /// Look up all the rows in the base table `table` where the columns at the indices `columns`
+    /// are equal to `key`, directly from the table's state
+    pub(super) async fn lookup_rows(
+        &mut self,
+        table: &Relation,
+        columns: &[usize],
+        key: Vec<DfValue>,
+    ) -> ReadySetResult<Vec<Vec<DfValue>>> {
+        noria_await!(
+            self.inner.get_mut()?,
+            self.inner
+                .get_mut()?
+                .noria
+                .base_rows(table.clone(), columns.to_vec(), key)
+        )
+    }
+
+    /// Compute the writes (grouped by table) needed to apply the referential actions of all the
+    /// foreign keys that reference the given changes to rows in `table`, recursively.
+    ///
+    /// `visited` contains the keys of the rows that are already being written to in each table,
+    /// which will not be written to again.
+    async fn cascade(
+        &mut self,
+        table: &Relation,
+        changes: Vec<RowChange>,
+        mut visited: HashSet<(Relation, Vec<DfValue>)>,
+    ) -> ReadySetResult<Writes> {
+        let mut writes = Writes::default();
+        let mut queue = changes
+            .into_iter()
+            .map(|change| (table.clone(), change))
+            .collect::<VecDeque<_>>();
+
+        while let Some((table, change)) = queue.pop_front() {
+            for fk in self.referencing_keys(&table).await? {
+                let (old, new, action) = match &change {
+                    RowChange::Delete { old } => (old, None, fk.on_delete),
+                    RowChange::Update { old, new } => (old, Some(new), fk.on_update),
+                };
+                if !is_applied(action) {
+                    continue;
+                }
+
+                let old_key = fk
+                    .target_columns
+                    .iter()
+                    .map(|&i| old[i].clone())
+                    .collect::<Vec<_>>();
+                let new_key = new.map(|new| {
+                    fk.target_columns
+                        .iter()
+                        .map(|&i| new[i].clone())
+                        .collect::<Vec<_>>()
+                });
+                if old_key.iter().any(DfValue::is_none) || new_key.as_ref() == Some(&old_key) {
+                    continue;
+                }
+
+                let (_, _, pkey) = self.base_table_info(&fk.table).await?;
+                for row in self.lookup_rows(&fk.table, &fk.columns, old_key).await? {
+                    let key = if pkey.is_empty() {
+                        row.clone()
+                    } else {
+                        pkey.iter().map(|&i| row[i].clone()).collect()
+                    };
+                    if !visited.insert((fk.table.clone(), key.clone())) {
+                        continue;
+                    }
+
+                    let values = match (action, &new_key) {
+                        (Some(ReferentialAction::Cascade), None) => {
+                            let op = if pkey.is_empty() {
+                                TableOperation::DeleteRow { row: row.clone() }
+                            } else {
+                                TableOperation::DeleteByKey { key }
+                            };
+                            writes.push(&fk.table, op, TableOperation::Insert(row.clone()));
+                            queue.push_back((fk.table.clone(), RowChange::Delete { old: row }));
+                            continue;
+                        }
+                        (Some(ReferentialAction::Cascade), Some(new_key)) => new_key.clone(),
+                        _ => vec![DfValue::None; fk.columns.len()],
+                    };
+
+                    let mut new_row = row.clone();
+                    let mut update = vec![Modification::None; row.len()];
+                    for (&col, value) in fk.columns.iter().zip(values) {
+                        new_row[col] = value.clone();
+                        update[col] = Modification::Set(value);
+                    }
+                    if pkey.is_empty() {
+                        writes.push(
+                            &fk.table,
+                            TableOperation::DeleteRow { row: row.clone() },
+                            TableOperation::Insert(row.clone()),
+                        );
+                        writes.push(
+                            &fk.table,
+                            TableOperation::Insert(new_row.clone()),
+                            TableOperation::DeleteRow {
+                                row: new_row.clone(),
+                            },
+                        );
+                    } else {
+                        writes.push(
+                            &fk.table,
+                            TableOperation::Update { key, update },
+                            restore_row(&pkey, &row, new_row.clone()),
+                        );
+                    }
+                    queue.push_back((
+                        fk.table.clone(),
+                        RowChange::Update {
+                            old: row,
+                            new: new_row,
+                        },
+                    ));
+                }
+            }
+        }
+
+        Ok(writes)
+    }
+
+    /// Apply a batch of writes to each table in `writes`.
+    ///
+    /// The handles for all the tables are resolved before any writes are made, so that a table
+    /// which can't be found doesn't leave only some of the writes applied. If the writes to any
+    /// table fail, the writes already applied to other tables are undone (in reverse order) before
+    /// returning the error.
+    async fn apply_ops(&mut self, mut writes: Writes) -> ReadySetResult<()> {
+        let mut batches = Vec::with_capacity(writes.ops.len());
+        for (table, ops) in writes.ops {
+            let mutator = self.inner.get_mut()?.get_noria_table(&table).await?.clone();
+            let undo = writes.undo.remove(&table).unwrap_or_default();
+            batches.push((mutator, ops, undo));
+        }
+
+        let mut applied = Vec::with_capacity(batches.len());
+        for (mut mutator, ops, undo) in batches {
+            if let Err(e) = mutator.perform_all(ops).await {
+                for (mut mutator, mut undo) in applied.into_iter().rev() {
+                    undo.reverse();
+                    if let Err(undo_error) = mutator.perform_all(undo).await {
+                        error!(
+                            table = %mutator.table_name(),
+                            error = %undo_error,
+                            "Failed to undo cascaded writes"
+                        );
+                    }
+                }
+                return Err(e);
+            }
+            applied.push((mutator, undo));
+        }
+        Ok(())
+    }
+
+    /// Delete the rows with the given primary keys from `table`, cascading the deletes to any
+    /// tables with foreign keys referencing it
+    pub(super) async fn delete_with_cascade(
+        &mut self,
+        table: &Relation,
+        keys: Vec<Vec<DfValue>>,
+    ) -> ReadySetResult<()> {
+        let (table, _, pkey) = self.base_table_info(table).await?;
+        let fks = self.referencing_keys(&table).await?;
+        if !fks.iter().any(|fk| is_applied(fk.on_delete)) {
+            let mutator = self.inner.get_mut()?.get_noria_table(&table).await?;
+            return mutator
+                .perform_all(
+                    keys.into_iter()
+                        .map(|key| TableOperation::DeleteByKey { key }),
+                )
+                .await;
+        }
+
+        let mut rows = vec![];
+        for key in &keys {
+            rows.extend(self.lookup_rows(&table, &pkey, key.clone()).await?);
+        }
+
+        let visited = keys
+            .iter()
+            .map(|key| (table.clone(), key.clone()))
+            .collect();
+        let changes = rows
+            .iter()
+            .map(|old| RowChange::Delete { old: old.clone() })
+            .collect();
+        let mut writes = self.cascade(&table, changes, visited).await?;
+        for row in rows {
+            let key = pkey.iter().map(|&i| row[i].clone()).collect();
+            writes.push(
+                &table,
+                TableOperation::DeleteByKey { key },
+                TableOperation::Insert(row),
+            );
+        }
+        self.apply_ops(writes).await
+    }
+
+    /// Update the rows with the given primary keys in `table`, cascading the updates to any tables
+    /// with foreign keys referencing them.
+    ///
+    /// All the writes needed for every key are computed before any of them are applied, so that
+    /// an error while looking up the rows to cascade to doesn't leave some of the keys updated.
+    ///
+    /// Returns the result of checking the new values against the configured row size limit (if
+    /// any).
+    pub(super) async fn update_with_cascade(
+        &mut self,
+        table: &Relation,
+        keys: Vec<Vec<DfValue>>,
+        mut updates: Vec<(usize, Modification)>,
+    ) -> ReadySetResult<RowSizeCheck> {
+        let (table, schema, pkey) = self.base_table_info(table).await?;
+        if pkey.is_empty() {
+            unsupported!("update operations can only be applied to base nodes with key columns")
+        }
+
+        let mut check = RowSizeCheck::Fits;
+        if let Some(limit) = self.row_size_limit {
+            let key_columns = self
+                .inner
+                .get_mut()?
+                .get_noria_table(&table)
+                .await?
+                .key_columns();
+            check = limit.check_update(updates.iter_mut().map(|(col, m)| (*col, m)), &key_columns);
+            if !handle_oversized_row(&table, &limit, check) {
+                return Ok(check);
+            }
+        }
+
+        let mut update = vec![Modification::None; schema.fields.len()];
+        for (col, modification) in &updates {
+            *update
+                .get_mut(*col)
+                .ok_or_else(|| internal_err!("Column index {col} out of bounds"))? =
+                modification.clone();
+        }
+        let table_ops = keys
+            .iter()
+            .map(|key| TableOperation::Update {
+                key: key.clone(),
+                update: update.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        let fks = self.referencing_keys(&table).await?;
+        if !fks.iter().any(|fk| {
+            is_applied(fk.on_update)
+                && updates
+                    .iter()
+                    .any(|(col, _)| fk.target_columns.contains(col))
+        }) {
+            let mutator = self.inner.get_mut()?.get_noria_table(&table).await?;
+            mutator.perform_all(table_ops).await?;
+            return Ok(check);
+        }
+
+        let mut rows = vec![];
+        for key in &keys {
+            for old in self.lookup_rows(&table, &pkey, key.clone()).await? {
+                let new = apply_modifications(&old, &updates)?;
+                rows.push((old, new));
+            }
+        }
+
+        let visited = keys.into_iter().map(|key| (table.clone(), key)).collect();
+        let changes = rows
+            .iter()
+            .map(|(old, new)| RowChange::Update {
+                old: old.clone(),
+                new: new.clone(),
+            })
+            .collect();
+        let mut writes = self.cascade(&table, changes, visited).await?;
+        for (old, new) in rows {
+            let key = pkey.iter().map(|&i| old[i].clone()).collect();
+            let undo = restore_row(&pkey, &old, new);
+            writes.push(
+                &table,
+                TableOperation::Update {
+                    key,
+                    update: update.clone(),
+                },
+                undo,
+            );
+        }
+        self.apply_ops(writes).await?;
+        Ok(check)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::{parse_create_table, Dialect};
+
+    use super::*;
+
+    fn schema(sql: &str) -> (Relation, CreateTableBody) {
+        let stmt = parse_create_table(Dialect::MySQL, sql).unwrap();
+        (stmt.table, stmt.body.unwrap())
+    }
+
+    #[test]
+    fn finds_referencing_keys() {
+        let (parent, parent_schema) = schema("CREATE TABLE parent (x int, id int PRIMARY KEY)");
+        let (child, child_schema) = schema(
+            "CREATE TABLE child (id int PRIMARY KEY, a int, b int,
+             FOREIGN KEY (b) REFERENCES parent (id) ON DELETE CASCADE,
+             FOREIGN KEY (a) REFERENCES parent (x) ON UPDATE SET NULL,
+             FOREIGN KEY (a) REFERENCES parent (id) ON DELETE RESTRICT,
+             FOREIGN KEY (a) REFERENCES other (id) ON DELETE CASCADE)",
+        );
+
+        assert_eq!(
+            referencing_keys(&parent, &parent_schema, &child, &child_schema),
+            vec![
+                ReferencingKey {
+                    table: child.clone(),
+                    columns: vec![2],
+                    target_columns: vec![1],
+                    on_delete: Some(ReferentialAction::Cascade),
+                    on_update: None,
+                },
+                ReferencingKey {
+                    table: child,
+                    columns: vec![1],
+                    target_columns: vec![0],
+                    on_delete: None,
+                    on_update: Some(ReferentialAction::SetNull),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn referencing_keys_match_schema() {
+        let (_, parent_schema) = schema("CREATE TABLE parent (id int PRIMARY KEY)");
+        let parent = Relation {
+            schema: Some("s1".into()),
+            name: "parent".into(),
+        };
+        let (_, child_schema) = schema(
+            "CREATE TABLE child (id int PRIMARY KEY,
+             FOREIGN KEY (id) REFERENCES parent (id) ON DELETE CASCADE)",
+        );
+        let child = |schema: &str| Relation {
+            schema: Some(schema.into()),
+            name: "child".into(),
+        };
+
+        assert_eq!(
+            referencing_keys(&parent, &parent_schema, &child("s1"), &child_schema).len(),
+            1
+        );
+        assert!(referencing_keys(&parent, &parent_schema, &child("s2"), &child_schema).is_empty());
+    }
+
+    #[test]
+    fn applies_modifications() {
+        let row = vec![DfValue::from(1), DfValue::from("a"), DfValue::from(10)];
+        assert_eq!(
+            apply_modifications(
+                &row,
+                &[
+                    (0, Modification::Set(DfValue::from(2))),
+                    (2, Modification::Apply(Operation::Sub, DfValue::from(3))),
+                ]
+            )
+            .unwrap(),
+            vec![DfValue::from(2), DfValue::from("a"), DfValue::from(7)]
+        );
+    }
+}
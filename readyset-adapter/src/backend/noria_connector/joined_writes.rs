@@ -0,0 +1,257 @@
+//! Writing directly to base tables with PostgreSQL's multi-table `UPDATE ... FROM` and
+//! `DELETE ... USING` statements.
+//!
+//! Writes to base tables are made by primary key, so to apply a joined write we first find the
+//! primary keys of the rows being written to by reading them from the joined table (directly from
+//! the table's state, without creating a cache), then write to each of those rows as if by a
+//! single-table statement. Only the simple case of a single join is supported, where:
+//!
+//! * exactly one other base table is joined against, and it isn't a subquery,
+//! * the table being written to has a single-column primary key,
+//! * the `WHERE` clause is a conjunction of equalities, exactly one of which compares the primary
+//!   key of the table being written to with a column of the joined table, and
+//! * all the others (of which there must be at least one) compare a column of the joined table with
+//!   a literal.
+//!
+//! All columns in the `WHERE` clause must be qualified with the name (or alias) of their table.
+
+use std::collections::HashSet;
+
+use nom_sql::{
+    BinaryOperator, Column, Expr, Literal, Relation, SqlIdentifier, TableExpr, TableExprInner,
+};
+use readyset_data::DfValue;
+use readyset_errors::{internal_err, unsupported, ReadySetResult};
+
+use super::NoriaConnector;
+
+/// The lookup into the joined table used to find the primary keys of the rows written to by a
+/// joined write
+#[derive(Debug, PartialEq)]
+struct KeyLookup {
+    /// The joined table
+    table: Relation,
+    /// The column of the joined table which is equal to the primary key of the rows being
+    /// written to
+    join_column: SqlIdentifier,
+    /// The columns of the joined table to look up the rows by
+    columns: Vec<SqlIdentifier>,
+    /// The values of `columns` in the rows to look up
+    key: Vec<DfValue>,
+}
+
+/// Which of the tables in a joined write a column belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Target,
+    Joined,
+}
+
+/// Split `expr` into the expressions joined together with `AND`
+fn conjuncts(expr: &Expr) -> Vec<&Expr> {
+    match expr {
+        Expr::BinaryOp {
+            lhs,
+            op: BinaryOperator::And,
+            rhs,
+        } => {
+            let mut res = conjuncts(lhs);
+            res.extend(conjuncts(rhs));
+            res
+        }
+        expr => vec![expr],
+    }
+}
+
+/// Plan the lookup of the primary keys of the rows in `target` (whose primary key is the single
+/// column `pkey`) written to by a joined write with the given joined tables and `WHERE` clause.
+/// See the [module documentation](self) for the joined writes which are supported.
+fn plan_key_lookup(
+    target: &Relation,
+    pkey: &SqlIdentifier,
+    joined: &[TableExpr],
+    where_clause: Option<&Expr>,
+) -> ReadySetResult<KeyLookup> {
+    let (joined_table, joined_name) = match joined {
+        [TableExpr {
+            inner: TableExprInner::Table(table),
+            alias,
+        }] => (table, alias.as_ref().unwrap_or(&table.name)),
+        [_] => unsupported!("Joined writes against subqueries are not supported"),
+        _ => unsupported!("Joined writes are only supported against a single table"),
+    };
+    let where_clause = match where_clause {
+        Some(where_clause) => where_clause,
+        None => unsupported!("Joined writes are only supported with a WHERE clause"),
+    };
+
+    let side = |column: &Column| match &column.table {
+        Some(table) if table.name == *joined_name => Some(Side::Joined),
+        Some(table) if table.name == target.name => Some(Side::Target),
+        _ => None,
+    };
+
+    let mut join_column = None;
+    let mut filters = vec![];
+    for cond in conjuncts(where_clause) {
+        match cond {
+            Expr::BinaryOp {
+                lhs,
+                op: BinaryOperator::Equal,
+                rhs,
+            } => match (lhs.as_ref(), rhs.as_ref()) {
+                (Expr::Column(c1), Expr::Column(c2)) => {
+                    let (target_column, joined_column) = match (side(c1), side(c2)) {
+                        (Some(Side::Target), Some(Side::Joined)) => (c1, c2),
+                        (Some(Side::Joined), Some(Side::Target)) => (c2, c1),
+                        _ => unsupported!(
+                            "Joined writes only support comparing columns of different tables"
+                        ),
+                    };
+                    if target_column.name != *pkey {
+                        unsupported!(
+                            "Joined writes are only supported when joining on the primary key"
+                        );
+                    }
+                    if join_column.replace(joined_column.name.clone()).is_some() {
+                        unsupported!("Joined writes only support a single join condition");
+                    }
+                }
+                (Expr::Column(column), Expr::Literal(value))
+                | (Expr::Literal(value), Expr::Column(column))
+                    if side(column) == Some(Side::Joined)
+                        && !matches!(value, Literal::Placeholder(_)) =>
+                {
+                    filters.push((column.name.clone(), DfValue::try_from(value)?));
+                }
+                _ => unsupported!(
+                    "Joined writes only support comparing columns of the joined table with literals"
+                ),
+            },
+            _ => unsupported!("Joined writes only support equality conditions"),
+        }
+    }
+
+    let join_column = match join_column {
+        Some(join_column) => join_column,
+        None => unsupported!("Joined writes must join on the primary key"),
+    };
+    if filters.is_empty() {
+        unsupported!("Joined writes must filter the joined table by at least one column");
+    }
+
+    let (columns, key) = filters.into_iter().unzip();
+    Ok(KeyLookup {
+        table: joined_table.clone(),
+        join_column,
+        columns,
+        key,
+    })
+}
+
+impl NoriaConnector {
+    /// Returns the primary keys of the rows in `table` written to by a joined write with the given
+    /// joined tables and `WHERE` clause
+    pub(super) async fn joined_write_keys(
+        &mut self,
+        table: &Relation,
+        joined: &[TableExpr],
+        where_clause: Option<&Expr>,
+    ) -> ReadySetResult<Vec<Vec<DfValue>>> {
+        let (_, schema, pkey) = self.base_table_info(table).await?;
+        let pkey = match pkey.as_slice() {
+            [i] => &schema.fields[*i].column.name,
+            _ => unsupported!(
+                "Joined writes are only supported for tables with a single-column primary key"
+            ),
+        };
+        let KeyLookup {
+            table: joined_table,
+            join_column,
+            columns,
+            key,
+        } = plan_key_lookup(table, pkey, joined, where_clause)?;
+
+        let (joined_table, joined_schema, _) = self.base_table_info(&joined_table).await?;
+        let column_index = |name: &SqlIdentifier| {
+            joined_schema
+                .fields
+                .iter()
+                .position(|f| f.column.name == *name)
+                .ok_or_else(|| internal_err!("Column {name} not found in table {joined_table}"))
+        };
+        let join_column = column_index(&join_column)?;
+        let columns = columns
+            .iter()
+            .map(column_index)
+            .collect::<ReadySetResult<Vec<_>>>()?;
+
+        let mut seen = HashSet::new();
+        Ok(self
+            .lookup_rows(&joined_table, &columns, key)
+            .await?
+            .into_iter()
+            .filter_map(|mut row| (join_column < row.len()).then(|| row.swap_remove(join_column)))
+            .filter(|value| !value.is_none() && seen.insert(value.clone()))
+            .map(|value| vec![value])
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::{parse_query, Dialect, SqlQuery};
+
+    use super::*;
+
+    fn plan(query: &str) -> ReadySetResult<KeyLookup> {
+        let (table, joined, where_clause) = match parse_query(Dialect::PostgreSQL, query).unwrap() {
+            SqlQuery::Update(stmt) => (stmt.table, stmt.from, stmt.where_clause),
+            SqlQuery::Delete(stmt) => (stmt.table, stmt.using, stmt.where_clause),
+            q => panic!("Unexpected query: {q}"),
+        };
+        plan_key_lookup(&table, &"id".into(), &joined, where_clause.as_ref())
+    }
+
+    #[test]
+    fn plans_single_join() {
+        let expected = KeyLookup {
+            table: "u".into(),
+            join_column: "t_id".into(),
+            columns: vec!["status".into(), "region".into()],
+            key: vec![DfValue::from("done"), DfValue::from(3)],
+        };
+        assert_eq!(
+            plan(
+                "UPDATE t SET x = 1 FROM u \
+                 WHERE t.id = u.t_id AND u.status = 'done' AND 3 = u.region"
+            )
+            .unwrap(),
+            expected
+        );
+        assert_eq!(
+            plan(
+                "DELETE FROM t USING u AS v \
+                 WHERE v.status = 'done' AND v.t_id = t.id AND v.region = 3"
+            )
+            .unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_joins() {
+        for query in [
+            "DELETE FROM t USING u, v WHERE t.id = u.t_id AND u.x = 1",
+            "DELETE FROM t USING u WHERE t.id = u.t_id",
+            "DELETE FROM t USING u WHERE t.x = u.t_id AND u.x = 1",
+            "DELETE FROM t USING u WHERE id = t_id AND x = 1",
+            "DELETE FROM t USING u WHERE t.id = u.t_id AND u.x > 1",
+            "DELETE FROM t USING u WHERE t.id = u.t_id AND t.id = u.y AND u.x = 1",
+            "DELETE FROM t USING u WHERE t.id = u.t_id OR u.x = 1",
+            "UPDATE t SET x = 1 FROM (SELECT * FROM u) v WHERE t.id = v.t_id AND v.x = 1",
+        ] {
+            assert!(plan(query).is_err(), "{query}");
+        }
+    }
+}
@@ -0,0 +1,363 @@
+//! Tracking of the client connections to an adapter, so that they can be managed the same way as
+//! connections to the upstream database.
+//!
+//! Every connection accepted by the adapter is registered with a [`ConnectionRegistry`], shared
+//! between all the connections of an adapter, which assigns it an id. The registry enforces a
+//! limit on the number of connections open at once, and lets a connection terminate another (with
+//! `KILL [CONNECTION] <id>`) by its id. Connections can also be closed once they've been idle for
+//! too long; a connection is considered idle while it isn't executing a statement.
+//!
+//! The registry also records which user each connection authenticated as, so that (like MySQL)
+//! only the admin user can kill connections belonging to other users, and the id of each
+//! connection's upstream database connection, so that `KILL QUERY <id>` can be forwarded to the
+//! upstream database.
+//!
+//! Ids are only unique within a single adapter. When there's an upstream database, each
+//! connection takes the id of its upstream connection (see [`RegisteredConnection::set_id`]), so
+//! that the id reported by `CONNECTION_ID()`, which is proxied upstream, can be passed to `KILL`.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use tokio::sync::watch;
+
+/// The state of a single connection, shared between its [`ConnectionHandle`]s and the registry
+#[derive(Debug)]
+struct ConnectionState {
+    /// Set to `true` once the connection has been killed
+    killed: watch::Sender<bool>,
+    /// The number of statements the connection is currently executing
+    active_commands: AtomicUsize,
+    /// When the connection last finished executing a statement, or when it was opened
+    last_active: Mutex<Instant>,
+    /// The user the connection authenticated as, once it has
+    user: Mutex<Option<String>>,
+    /// The id of the connection to the upstream database used by the connection, if any
+    upstream_id: Mutex<Option<u32>>,
+}
+
+/// All the client connections open to an adapter. See the [module-level documentation](self) for
+/// more information.
+#[derive(Debug)]
+pub struct ConnectionRegistry {
+    max_connections: Option<usize>,
+    next_id: AtomicU32,
+    connections: Mutex<HashMap<u32, Arc<ConnectionState>>>,
+}
+
+impl ConnectionRegistry {
+    /// Construct a new [`ConnectionRegistry`] allowing at most `max_connections` connections to be
+    /// open at once, or any number of connections if `max_connections` is `None`
+    pub fn new(max_connections: Option<usize>) -> Self {
+        Self {
+            max_connections,
+            // Start from 1, since some clients treat a connection id of 0 as unset
+            next_id: AtomicU32::new(1),
+            connections: Default::default(),
+        }
+    }
+
+    /// Register a newly-accepted connection, or return `None` if the limit on the number of open
+    /// connections has been reached. The connection stays registered until the returned
+    /// [`RegisteredConnection`] is dropped.
+    pub fn register(self: &Arc<Self>) -> Option<RegisteredConnection> {
+        let mut connections = self.connections.lock();
+        if matches!(self.max_connections, Some(max) if connections.len() >= max) {
+            return None;
+        }
+
+        let id = loop {
+            let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+            if id != 0 && !connections.contains_key(&id) {
+                break id;
+            }
+        };
+        let state = Arc::new(ConnectionState {
+            killed: watch::channel(false).0,
+            active_commands: AtomicUsize::new(0),
+            last_active: Mutex::new(Instant::now()),
+            user: Mutex::new(None),
+            upstream_id: Mutex::new(None),
+        });
+        connections.insert(id, state.clone());
+
+        Some(RegisteredConnection {
+            handle: ConnectionHandle {
+                id,
+                state,
+                registry: self.clone(),
+            },
+        })
+    }
+
+    /// Returns the number of connections currently open
+    pub fn len(&self) -> usize {
+        self.connections.lock().len()
+    }
+
+    /// Returns `true` if no connections are open
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns `true` if a connection with the given id is open
+    pub fn contains(&self, id: u32) -> bool {
+        self.connections.lock().contains_key(&id)
+    }
+
+    /// Returns the user the connection with the given id authenticated as, or `None` if there is
+    /// no such connection or it hasn't authenticated
+    pub fn user(&self, id: u32) -> Option<String> {
+        self.connections
+            .lock()
+            .get(&id)
+            .and_then(|state| state.user.lock().clone())
+    }
+
+    /// Returns the id of the upstream database connection used by the connection with the given
+    /// id, or `None` if there is no such connection or it doesn't have an upstream connection
+    pub fn upstream_id(&self, id: u32) -> Option<u32> {
+        self.connections
+            .lock()
+            .get(&id)
+            .and_then(|state| *state.upstream_id.lock())
+    }
+
+    /// Kill the connection with the given id, returning `false` if there is no such connection
+    pub fn kill(&self, id: u32) -> bool {
+        match self.connections.lock().get(&id) {
+            Some(state) => {
+                state.killed.send_replace(true);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// A connection registered with a [`ConnectionRegistry`], which is unregistered when this is
+/// dropped
+#[derive(Debug)]
+pub struct RegisteredConnection {
+    handle: ConnectionHandle,
+}
+
+impl RegisteredConnection {
+    /// Change the id of this connection to `id`, such as the id of the connection's upstream
+    /// database connection. Returns `false`, leaving the id unchanged, if `id` is already used by
+    /// another connection. Handles returned from [`RegisteredConnection::handle`] before this is
+    /// called keep the old id, so this should be called before taking any handles.
+    pub fn set_id(&mut self, id: u32) -> bool {
+        let mut connections = self.handle.registry.connections.lock();
+        if id == self.handle.id {
+            return true;
+        }
+        if id == 0 || connections.contains_key(&id) {
+            return false;
+        }
+        if let Some(state) = connections.remove(&self.handle.id) {
+            connections.insert(id, state);
+        }
+        self.handle.id = id;
+        true
+    }
+
+    /// Returns a handle to this connection
+    pub fn handle(&self) -> ConnectionHandle {
+        self.handle.clone()
+    }
+}
+
+impl Drop for RegisteredConnection {
+    fn drop(&mut self) {
+        self.handle
+            .registry
+            .connections
+            .lock()
+            .remove(&self.handle.id);
+    }
+}
+
+/// A handle to a connection registered with a [`ConnectionRegistry`]
+#[derive(Debug, Clone)]
+pub struct ConnectionHandle {
+    id: u32,
+    state: Arc<ConnectionState>,
+    registry: Arc<ConnectionRegistry>,
+}
+
+/// Marks a connection as executing a statement until dropped. Returned from
+/// [`ConnectionHandle::start_command`].
+#[must_use]
+pub struct ActiveCommand {
+    state: Arc<ConnectionState>,
+}
+
+impl Drop for ActiveCommand {
+    fn drop(&mut self) {
+        *self.state.last_active.lock() = Instant::now();
+        self.state.active_commands.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+impl ConnectionHandle {
+    /// Returns the id of this connection
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the registry this connection is registered with
+    pub fn registry(&self) -> &ConnectionRegistry {
+        &self.registry
+    }
+
+    /// Record the user this connection authenticated as
+    pub fn set_user(&self, user: &str) {
+        *self.state.user.lock() = Some(user.to_owned());
+    }
+
+    /// Record the id of the upstream database connection used by this connection
+    pub fn set_upstream_id(&self, upstream_id: Option<u32>) {
+        *self.state.upstream_id.lock() = upstream_id;
+    }
+
+    /// Record that this connection has started executing a statement, which it's considered to be
+    /// executing until the returned [`ActiveCommand`] is dropped
+    pub fn start_command(&self) -> ActiveCommand {
+        self.state.active_commands.fetch_add(1, Ordering::AcqRel);
+        ActiveCommand {
+            state: self.state.clone(),
+        }
+    }
+
+    /// Returns `true` if this connection has been killed
+    pub fn is_killed(&self) -> bool {
+        *self.state.killed.borrow()
+    }
+
+    /// Wait for this connection to be killed
+    pub async fn killed(&self) {
+        let mut killed = self.state.killed.subscribe();
+        while !*killed.borrow_and_update() {
+            // The sender lives as long as `self`, so this can't fail
+            let _ = killed.changed().await;
+        }
+    }
+
+    /// Wait for this connection to have been idle (not executing a statement) for `timeout`
+    pub async fn idle(&self, timeout: Duration) {
+        loop {
+            // Statements update `last_active` before they stop being counted as active, so it has
+            // to be read after checking for active statements
+            let active = self.state.active_commands.load(Ordering::Acquire) != 0;
+            let deadline = *self.state.last_active.lock() + timeout;
+            if active {
+                tokio::time::sleep(timeout).await;
+            } else if Instant::now() < deadline {
+                tokio::time::sleep_until(deadline.into()).await;
+            } else {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_connections() {
+        let registry = Arc::new(ConnectionRegistry::new(Some(2)));
+        let c1 = registry.register().unwrap();
+        let c2 = registry.register().unwrap();
+        assert_ne!(c1.handle().id(), c2.handle().id());
+        assert!(registry.register().is_none());
+        assert_eq!(registry.len(), 2);
+
+        drop(c1);
+        assert_eq!(registry.len(), 1);
+        let c3 = registry.register().unwrap();
+        assert_ne!(c3.handle().id(), c2.handle().id());
+    }
+
+    #[tokio::test]
+    async fn kill_connection() {
+        let registry = Arc::new(ConnectionRegistry::new(None));
+        let conn = registry.register().unwrap();
+        let handle = conn.handle();
+        assert!(!handle.is_killed());
+
+        let killed = tokio::spawn({
+            let handle = handle.clone();
+            async move { handle.killed().await }
+        });
+        assert!(registry.kill(handle.id()));
+        killed.await.unwrap();
+        assert!(handle.is_killed());
+
+        drop(conn);
+        assert!(!registry.kill(handle.id()));
+    }
+
+    #[test]
+    fn set_id() {
+        let registry = Arc::new(ConnectionRegistry::new(None));
+        let mut c1 = registry.register().unwrap();
+        let c2 = registry.register().unwrap();
+        let old_id = c1.handle().id();
+
+        assert!(c1.set_id(1234));
+        assert_eq!(c1.handle().id(), 1234);
+        assert!(registry.contains(1234));
+        assert!(!registry.contains(old_id));
+
+        assert!(!c1.set_id(c2.handle().id()));
+        assert!(!c1.set_id(0));
+        assert_eq!(c1.handle().id(), 1234);
+
+        drop(c1);
+        assert!(!registry.contains(1234));
+        assert_eq!(registry.len(), 1);
+    }
+
+    #[test]
+    fn connection_info() {
+        let registry = Arc::new(ConnectionRegistry::new(None));
+        let conn = registry.register().unwrap();
+        let handle = conn.handle();
+        assert!(registry.contains(handle.id()));
+        assert_eq!(registry.user(handle.id()), None);
+        assert_eq!(registry.upstream_id(handle.id()), None);
+
+        handle.set_user("alice");
+        handle.set_upstream_id(Some(17));
+        assert_eq!(registry.user(handle.id()).as_deref(), Some("alice"));
+        assert_eq!(registry.upstream_id(handle.id()), Some(17));
+
+        drop(conn);
+        assert!(!registry.contains(handle.id()));
+        assert_eq!(registry.user(handle.id()), None);
+    }
+
+    #[tokio::test]
+    async fn idle_timeout() {
+        let registry = Arc::new(ConnectionRegistry::new(None));
+        let conn = registry.register().unwrap();
+        let handle = conn.handle();
+        let timeout = Duration::from_millis(50);
+
+        let command = handle.start_command();
+        tokio::time::timeout(timeout * 3, handle.idle(timeout))
+            .await
+            .unwrap_err();
+
+        drop(command);
+        tokio::time::timeout(timeout * 3, handle.idle(timeout))
+            .await
+            .unwrap();
+    }
+}
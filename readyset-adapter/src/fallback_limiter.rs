@@ -0,0 +1,458 @@
+//! Protection for the upstream database against floods of proxied reads.
+//!
+//! When many queries miss the cache at once (for example right after a deployment, or when a cache
+//! is dropped), every adapter connection will proxy its reads upstream at the same time. A
+//! [`FallbackLimiter`], shared between all the connections of an adapter, bounds the number of
+//! fallback reads that can be in flight at once and the rate at which they're sent (queueing the
+//! reads over either limit until they can be sent), and acts as a circuit breaker: once the
+//! upstream database has failed a configured number of fallback reads in a row, fallback reads are
+//! rejected with [`ReadySetError::FallbackUnavailable`] until a cooldown period has passed, after
+//! which a single read is let through to check whether the upstream has recovered.
+//!
+//! Writes, and any other statements which must be executed upstream for correctness, are never
+//! limited.
+//!
+//! All limits other than the concurrency limit can be changed while the adapter is running, with
+//! [`FallbackLimiter::set_limits`].
+
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use readyset_client_metrics::recorded;
+use readyset_errors::{ReadySetError, ReadySetResult};
+use readyset_tracing::warn;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::upstream_database::IsFatalError;
+
+/// Configuration for a [`FallbackLimiter`]. Limits which are `None` (or zero) are not enforced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FallbackLimits {
+    /// The maximum number of fallback reads that may be executing against the upstream database
+    /// at once. Reads over this limit wait for a running read to finish.
+    pub max_concurrent: Option<usize>,
+    /// The maximum number of fallback reads sent to the upstream database per second. Reads over
+    /// this limit wait until they can be sent without exceeding it.
+    pub max_per_second: Option<u32>,
+    /// The number of consecutive fallback reads which must fail with a connection-level error
+    /// before the circuit breaker opens.
+    pub circuit_breaker_failures: Option<u32>,
+    /// How long the circuit breaker stays open before letting a read through to check whether the
+    /// upstream database has recovered.
+    pub circuit_breaker_cooldown: Duration,
+}
+
+impl FallbackLimits {
+    /// Returns these limits with any limits of zero replaced by `None`, since a limit of zero
+    /// means no limit (rather than never proxying reads, or waiting forever to)
+    fn without_zeros(self) -> Self {
+        Self {
+            max_concurrent: self.max_concurrent.filter(|&n| n != 0),
+            max_per_second: self.max_per_second.filter(|&n| n != 0),
+            circuit_breaker_failures: self.circuit_breaker_failures.filter(|&n| n != 0),
+            ..self
+        }
+    }
+}
+
+/// A token bucket, refilled continuously at `per_second` tokens per second, holding at most one
+/// second's worth of tokens
+#[derive(Debug)]
+pub(crate) struct RateLimit {
+    per_second: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimit {
+    pub(crate) fn new(per_second: u32) -> Self {
+        Self {
+            per_second: per_second as f64,
+            tokens: per_second as f64,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.per_second);
+        self.last_refill = now;
+    }
+
+    pub(crate) fn try_take(&mut self, now: Instant) -> bool {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Take a token, going into debt if there are none left, and return how long the caller must
+    /// wait before the token it took has been refilled. Callers are served in the order they call
+    /// this, since each one waits for the tokens taken before it to be refilled first.
+    fn reserve(&mut self, now: Instant) -> Duration {
+        self.refill(now);
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.per_second)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BreakerState {
+    /// Fallback reads are allowed
+    Closed { consecutive_failures: u32 },
+    /// Fallback reads are rejected until the given time
+    Open { until: Instant },
+    /// A single trial read was let through at the given time, and all other reads are rejected
+    /// until it completes (or until another cooldown period has passed, in case the trial was
+    /// never completed)
+    HalfOpen { trial_started: Instant },
+}
+
+/// Limits the rate and concurrency of reads proxied to the upstream database, and stops proxying
+/// them altogether while the upstream is unhealthy. See the [module-level
+/// documentation](self) for more information.
+#[derive(Debug)]
+pub struct FallbackLimiter {
+    limits: Mutex<FallbackLimits>,
+    concurrency: Option<Semaphore>,
+    rate: Mutex<Option<RateLimit>>,
+    breaker: Mutex<BreakerState>,
+}
+
+/// Permission to proxy a single read to the upstream database, returned from
+/// [`FallbackLimiter::acquire`]. The outcome of the read should be reported with
+/// [`FallbackPermit::record`].
+#[must_use]
+pub struct FallbackPermit<'a> {
+    limiter: &'a FallbackLimiter,
+    _concurrency: Option<SemaphorePermit<'a>>,
+}
+
+impl<'a> FallbackPermit<'a> {
+    /// Record the result of the read this permit was acquired for, to inform the circuit breaker.
+    ///
+    /// Only connection-level ([fatal](IsFatalError::is_fatal)) errors count as failures: a query
+    /// which is rejected by a healthy upstream database says nothing about its health.
+    pub fn record<T, E: IsFatalError>(self, result: &Result<T, E>) {
+        self.limiter
+            .record(!matches!(result, Err(e) if e.is_fatal()), Instant::now());
+    }
+}
+
+impl FallbackLimiter {
+    /// Construct a new [`FallbackLimiter`] enforcing the given limits
+    pub fn new(limits: FallbackLimits) -> Self {
+        let limits = limits.without_zeros();
+        Self {
+            limits: Mutex::new(limits),
+            concurrency: limits.max_concurrent.map(Semaphore::new),
+            rate: Mutex::new(limits.max_per_second.map(RateLimit::new)),
+            breaker: Mutex::new(BreakerState::Closed {
+                consecutive_failures: 0,
+            }),
+        }
+    }
+
+    /// Returns the limits currently being enforced
+    pub fn limits(&self) -> FallbackLimits {
+        *self.limits.lock()
+    }
+
+    /// Change the limits being enforced, for reads proxied from now on.
+    ///
+    /// The concurrency limit can't be changed after the limiter is constructed, so
+    /// `limits.max_concurrent` is ignored.
+    pub fn set_limits(&self, limits: FallbackLimits) {
+        let limits = limits.without_zeros();
+        let mut current = self.limits.lock();
+        if current.max_per_second != limits.max_per_second {
+            *self.rate.lock() = limits.max_per_second.map(RateLimit::new);
+        }
+        *current = FallbackLimits {
+            max_concurrent: current.max_concurrent,
+            ..limits
+        };
+    }
+
+    /// Wait for permission to proxy a read to the upstream database, or return
+    /// [`ReadySetError::FallbackUnavailable`] if the read should not be proxied at all.
+    pub async fn acquire(&self) -> ReadySetResult<FallbackPermit<'_>> {
+        let now = Instant::now();
+        self.check_breaker(now)?;
+
+        let delay = self
+            .rate
+            .lock()
+            .as_mut()
+            .map_or(Duration::ZERO, |rate| rate.reserve(now));
+        if !delay.is_zero() {
+            metrics::increment_counter!(recorded::FALLBACK_QUERIES_DELAYED);
+            tokio::time::sleep(delay).await;
+        }
+
+        let concurrency = match &self.concurrency {
+            Some(semaphore) => Some(semaphore.acquire().await.map_err(|_| {
+                ReadySetError::Internal("Fallback concurrency limit was closed".to_owned())
+            })?),
+            None => None,
+        };
+
+        Ok(FallbackPermit {
+            limiter: self,
+            _concurrency: concurrency,
+        })
+    }
+
+    fn check_breaker(&self, now: Instant) -> ReadySetResult<()> {
+        let cooldown = self.limits.lock().circuit_breaker_cooldown;
+        let mut breaker = self.breaker.lock();
+        match *breaker {
+            BreakerState::Closed { .. } => return Ok(()),
+            BreakerState::Open { until } if now >= until => {
+                *breaker = BreakerState::HalfOpen { trial_started: now };
+                return Ok(());
+            }
+            BreakerState::HalfOpen { trial_started } if now >= trial_started + cooldown => {
+                *breaker = BreakerState::HalfOpen { trial_started: now };
+                return Ok(());
+            }
+            BreakerState::Open { .. } | BreakerState::HalfOpen { .. } => {}
+        }
+        drop(breaker);
+
+        metrics::increment_counter!(
+            recorded::FALLBACK_QUERIES_REJECTED,
+            "reason" => "circuit_open"
+        );
+        Err(ReadySetError::FallbackUnavailable(
+            "the upstream database is unhealthy".to_owned(),
+        ))
+    }
+
+    fn record(&self, succeeded: bool, now: Instant) {
+        let limits = self.limits();
+        let threshold = match limits.circuit_breaker_failures {
+            Some(threshold) => threshold,
+            None => return,
+        };
+
+        let mut breaker = self.breaker.lock();
+        let previous = *breaker;
+        *breaker = match (previous, succeeded) {
+            (_, true) => BreakerState::Closed {
+                consecutive_failures: 0,
+            },
+            (
+                BreakerState::Closed {
+                    consecutive_failures,
+                },
+                false,
+            ) if consecutive_failures + 1 < threshold => BreakerState::Closed {
+                consecutive_failures: consecutive_failures + 1,
+            },
+            // Reads which were already in flight when the breaker opened don't extend the cooldown
+            (BreakerState::Open { until }, false) => BreakerState::Open { until },
+            (BreakerState::Closed { .. } | BreakerState::HalfOpen { .. }, false) => {
+                BreakerState::Open {
+                    until: now + limits.circuit_breaker_cooldown,
+                }
+            }
+        };
+
+        let was_open = !matches!(previous, BreakerState::Closed { .. });
+        let is_open = !matches!(*breaker, BreakerState::Closed { .. });
+        if was_open != is_open {
+            if is_open {
+                warn!(
+                    cooldown = ?limits.circuit_breaker_cooldown,
+                    "Upstream database is failing, no longer proxying reads to it"
+                );
+            } else {
+                warn!("Upstream database has recovered, proxying reads to it again");
+            }
+            metrics::gauge!(
+                recorded::FALLBACK_CIRCUIT_OPEN,
+                if is_open { 1.0 } else { 0.0 }
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct TestError {
+        fatal: bool,
+    }
+
+    impl IsFatalError for TestError {
+        fn is_fatal(&self) -> bool {
+            self.fatal
+        }
+    }
+
+    fn fatal() -> Result<(), TestError> {
+        Err(TestError { fatal: true })
+    }
+
+    #[test]
+    fn rate_limit_reservations_queue() {
+        let start = Instant::now();
+        let mut rate = RateLimit::new(10);
+        for _ in 0..10 {
+            assert_eq!(rate.reserve(start), Duration::ZERO);
+        }
+        // Each reservation past the limit waits for the ones before it
+        let first = rate.reserve(start);
+        let second = rate.reserve(start);
+        assert!((first.as_secs_f64() - 0.1).abs() < 1e-6);
+        assert!((second.as_secs_f64() - 0.2).abs() < 1e-6);
+        // Once the debt has been refilled, reads go through immediately again
+        assert_eq!(
+            rate.reserve(start + Duration::from_millis(350)),
+            Duration::ZERO
+        );
+    }
+
+    #[tokio::test]
+    async fn rate_limit_delays_excess_reads() {
+        let limiter = FallbackLimiter::new(FallbackLimits {
+            max_per_second: Some(20),
+            ..Default::default()
+        });
+        let start = Instant::now();
+        for _ in 0..21 {
+            limiter
+                .acquire()
+                .await
+                .unwrap()
+                .record(&Ok::<_, TestError>(()));
+        }
+        assert!(start.elapsed() >= Duration::from_millis(40));
+    }
+
+    #[tokio::test]
+    async fn rate_limit_can_be_changed() {
+        let limiter = FallbackLimiter::new(FallbackLimits {
+            max_per_second: Some(1),
+            ..Default::default()
+        });
+        limiter
+            .acquire()
+            .await
+            .unwrap()
+            .record(&Ok::<_, TestError>(()));
+        tokio::time::timeout(Duration::from_millis(50), limiter.acquire())
+            .await
+            .unwrap_err();
+
+        limiter.set_limits(FallbackLimits {
+            max_per_second: None,
+            ..limiter.limits()
+        });
+        limiter
+            .acquire()
+            .await
+            .unwrap()
+            .record(&Ok::<_, TestError>(()));
+        limiter
+            .acquire()
+            .await
+            .unwrap()
+            .record(&Ok::<_, TestError>(()));
+    }
+
+    #[tokio::test]
+    async fn concurrency_limit_waits_for_permits() {
+        let limiter = FallbackLimiter::new(FallbackLimits {
+            max_concurrent: Some(1),
+            ..Default::default()
+        });
+        let permit = limiter.acquire().await.unwrap();
+        tokio::time::timeout(Duration::from_millis(50), limiter.acquire())
+            .await
+            .unwrap_err();
+        drop(permit);
+        limiter.acquire().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn zero_limits_are_not_enforced() {
+        let limits = FallbackLimits {
+            max_concurrent: Some(0),
+            max_per_second: Some(0),
+            circuit_breaker_failures: Some(0),
+            ..Default::default()
+        };
+        let limiter = FallbackLimiter::new(limits);
+        assert_eq!(limiter.limits(), FallbackLimits::default());
+        for _ in 0..3 {
+            let permit = tokio::time::timeout(Duration::from_millis(50), limiter.acquire())
+                .await
+                .unwrap()
+                .unwrap();
+            permit.record(&fatal());
+        }
+
+        let limiter = FallbackLimiter::new(FallbackLimits {
+            max_per_second: Some(1),
+            ..Default::default()
+        });
+        limiter.set_limits(limits);
+        assert_eq!(limiter.limits().max_per_second, None);
+        for _ in 0..3 {
+            tokio::time::timeout(Duration::from_millis(50), limiter.acquire())
+                .await
+                .unwrap()
+                .unwrap()
+                .record(&Ok::<_, TestError>(()));
+        }
+    }
+
+    #[tokio::test]
+    async fn circuit_breaker_opens_and_recovers() {
+        let limiter = FallbackLimiter::new(FallbackLimits {
+            circuit_breaker_failures: Some(2),
+            circuit_breaker_cooldown: Duration::from_millis(50),
+            ..Default::default()
+        });
+
+        // Non-fatal errors don't count towards opening the breaker
+        limiter
+            .acquire()
+            .await
+            .unwrap()
+            .record(&Err::<(), _>(TestError { fatal: false }));
+        limiter.acquire().await.unwrap().record(&fatal());
+        limiter.acquire().await.unwrap().record(&fatal());
+        limiter.acquire().await.unwrap_err();
+
+        // After the cooldown, a single trial read is let through
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        let trial = limiter.acquire().await.unwrap();
+        limiter.acquire().await.unwrap_err();
+
+        // A failed trial opens the breaker again, and a successful one closes it
+        trial.record(&fatal());
+        limiter.acquire().await.unwrap_err();
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        limiter
+            .acquire()
+            .await
+            .unwrap()
+            .record(&Ok::<_, TestError>(()));
+        limiter.acquire().await.unwrap();
+        limiter.acquire().await.unwrap();
+    }
+}
@@ -0,0 +1,654 @@
+//! An [Apache Arrow Flight][flight] server exposing the rows stored in caches as Arrow record
+//! batches, so analytical consumers (such as BI tools, or dataframe libraries like pandas or
+//! polars, via pyarrow) can pull large results from ReadySet efficiently, without going through
+//! the row-oriented MySQL or PostgreSQL protocols.
+//!
+//! Every cache is exposed as a flight whose descriptor is the path `[<cache name>]`, and whose
+//! single endpoint has a ticket containing the cache name as UTF-8. `DoGet` with that ticket
+//! streams every row currently stored in the reader of the cache, read with
+//! [`ReaderHandle::columnar_batches`].
+//!
+//! # Authentication
+//!
+//! Unless the server is created without any users (which is only allowed if it listens on a
+//! loopback address), clients must first call `Handshake` with an `authorization` header containing
+//! the username and password of one of the users allowed to connect to the adapter, as HTTP basic
+//! credentials. The response carries a bearer token in its own `authorization` header, which must
+//! be sent in the `authorization` header of every other request. This is the flow implemented by
+//! eg pyarrow's `FlightClient.authenticate_basic_token`.
+//!
+//! [flight]: https://arrow.apache.org/docs/format/Flight.html
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use arrow::array::{
+    ArrayRef, BinaryArray, BooleanArray, Float64Array, Int64Array, StringArray, UInt64Array,
+};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use arrow::error::ArrowError;
+use arrow::ipc::writer::IpcWriteOptions;
+use arrow::record_batch::RecordBatch;
+use arrow_flight::flight_descriptor::DescriptorType;
+use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+use arrow_flight::utils::flight_data_from_arrow_batch;
+use arrow_flight::{
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightEndpoint, FlightInfo,
+    HandshakeRequest, HandshakeResponse, IpcMessage, PutResult, SchemaAsIpc, SchemaResult, Ticket,
+};
+use futures::{Stream, TryStreamExt};
+use nom_sql::Relation;
+use parking_lot::Mutex;
+use petgraph::graph::NodeIndex;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use readyset_client::{
+    ColumnData, ColumnSchema, ColumnarBatch, ReaderHandle, ReadySetHandle, SchemaType, View,
+};
+use readyset_data::DfValue;
+use readyset_errors::{ReadySetError, ReadySetResult};
+use readyset_tracing::{info, warn};
+use stream_cancel::Valve;
+use subtle::ConstantTimeEq;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::metadata::{MetadataMap, MetadataValue};
+use tonic::transport::Server;
+use tonic::{Request, Response, Status, Streaming};
+
+type BoxStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send + 'static>>;
+
+/// The number of messages buffered for each `DoGet` request before reading from the cache waits
+/// for the client to catch up
+const DO_GET_BUFFER: usize = 4;
+
+/// How long the bearer tokens returned by `Handshake` can be used for
+const TOKEN_LIFETIME: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The length of the bearer tokens returned by `Handshake`
+const TOKEN_LENGTH: usize = 32;
+
+/// An Arrow Flight server exposing the rows stored in caches. See the [module
+/// documentation](self) for more information.
+#[derive(Clone)]
+pub struct ArrowFlightServer {
+    /// Handle to the ReadySet deployment, used to look up the readers of caches
+    readyset_handle: ReadySetHandle,
+    /// The (maximum) number of rows in each record batch returned by `DoGet`
+    batch_size: usize,
+    /// Checks the credentials and bearer tokens sent by clients
+    auth: Arc<Authenticator>,
+    /// The flight info for each cache, and the node of the reader it was built from, so that
+    /// listing flights only needs to look up the readers of caches which have changed
+    flight_infos: Arc<Mutex<HashMap<Relation, (NodeIndex, Option<FlightInfo>)>>>,
+}
+
+impl ArrowFlightServer {
+    /// Create a new [`ArrowFlightServer`] reading from caches in the given ReadySet deployment,
+    /// and returning records in batches of `batch_size` rows.
+    ///
+    /// Clients must authenticate as one of the given `users` (a map from username to password).
+    /// If `users` is empty, the server can only listen on a loopback address.
+    pub fn new(
+        readyset_handle: ReadySetHandle,
+        batch_size: usize,
+        users: HashMap<String, String>,
+    ) -> Self {
+        Self {
+            readyset_handle,
+            batch_size,
+            auth: Arc::new(Authenticator {
+                users,
+                tokens: Default::default(),
+            }),
+            flight_infos: Default::default(),
+        }
+    }
+
+    /// Serve Arrow Flight requests on `listener`, until `valve` is closed.
+    ///
+    /// Returns an error if the server has no users, and `listener` isn't bound to a loopback
+    /// address, since anyone able to connect to it could then read every cache.
+    pub async fn serve(self, listener: TcpListener, valve: Valve) -> anyhow::Result<()> {
+        let address = listener.local_addr()?;
+        if self.auth.users.is_empty() && !address.ip().is_loopback() {
+            anyhow::bail!(
+                "Refusing to serve Arrow Flight requests without authentication on non-loopback \
+                 address {address}"
+            );
+        }
+        info!(%address, "Serving Arrow Flight requests");
+        Server::builder()
+            .add_service(FlightServiceServer::new(self))
+            .serve_with_incoming(valve.wrap(TcpListenerStream::new(listener)))
+            .await?;
+        Ok(())
+    }
+
+    /// Bind to `address`, and serve Arrow Flight requests on it until `valve` is closed
+    pub async fn listen(self, address: SocketAddr, valve: Valve) -> anyhow::Result<()> {
+        let listener = TcpListener::bind(address).await?;
+        self.serve(listener, valve).await
+    }
+
+    async fn reader_handle(&self, name: &str) -> Result<ReaderHandle, Status> {
+        match self
+            .readyset_handle
+            .clone()
+            .view(name)
+            .await
+            .map_err(status)?
+        {
+            View::Single(reader_handle) => Ok(reader_handle),
+            View::MultipleReused(_) => Err(Status::unimplemented(
+                "Reading caches which reuse other caches over Arrow Flight is not supported",
+            )),
+        }
+    }
+
+    async fn flight_info(&self, name: &str) -> Result<FlightInfo, Status> {
+        let reader_handle = self.reader_handle(name).await?;
+        let schema = arrow_schema(view_schema(&reader_handle)?).map_err(status)?;
+        let schema: IpcMessage = SchemaAsIpc::new(&schema, &IpcWriteOptions::default())
+            .try_into()
+            .map_err(arrow_status)?;
+
+        Ok(FlightInfo {
+            schema: schema.0,
+            flight_descriptor: Some(FlightDescriptor {
+                r#type: DescriptorType::Path as i32,
+                cmd: Default::default(),
+                path: vec![name.to_owned()],
+            }),
+            endpoint: vec![FlightEndpoint {
+                ticket: Some(Ticket {
+                    ticket: name.as_bytes().to_vec(),
+                }),
+                location: vec![],
+            }],
+            total_records: -1,
+            total_bytes: -1,
+        })
+    }
+}
+
+/// Authenticates requests to an [`ArrowFlightServer`]. See the [module
+/// documentation](self#authentication) for more information.
+struct Authenticator {
+    /// The usernames and passwords of the users allowed to read caches. If empty, requests don't
+    /// need to be authenticated.
+    users: HashMap<String, String>,
+    /// The bearer tokens handed out by `Handshake`, and when each one expires
+    tokens: Mutex<HashMap<String, Instant>>,
+}
+
+impl Authenticator {
+    /// Check the username and password sent in the basic `authorization` header of a `Handshake`
+    /// request, returning a new bearer token for the client to authenticate later requests with
+    fn authenticate(&self, metadata: &MetadataMap) -> Result<String, Status> {
+        let unauthenticated = || Status::unauthenticated("Invalid username or password");
+        let credentials = metadata
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Basic "))
+            .and_then(|credentials| base64::decode(credentials.trim()).ok())
+            .and_then(|credentials| String::from_utf8(credentials).ok())
+            .ok_or_else(unauthenticated)?;
+        let (username, password) = credentials.split_once(':').ok_or_else(unauthenticated)?;
+        match self.users.get(username) {
+            Some(expected) if bool::from(expected.as_bytes().ct_eq(password.as_bytes())) => {}
+            _ => return Err(unauthenticated()),
+        }
+
+        let token: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(TOKEN_LENGTH)
+            .map(char::from)
+            .collect();
+        let now = Instant::now();
+        let mut tokens = self.tokens.lock();
+        tokens.retain(|_, expires| *expires > now);
+        tokens.insert(token.clone(), now + TOKEN_LIFETIME);
+        Ok(token)
+    }
+
+    /// Check that a request carries a bearer token returned by `Handshake` which hasn't expired,
+    /// if the server requires authentication
+    fn check_token(&self, metadata: &MetadataMap) -> Result<(), Status> {
+        if self.users.is_empty() {
+            return Ok(());
+        }
+        let token = metadata
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| Status::unauthenticated("Missing bearer token"))?;
+        match self.tokens.lock().get(token.trim()) {
+            Some(expires) if *expires > Instant::now() => Ok(()),
+            _ => Err(Status::unauthenticated("Invalid or expired bearer token")),
+        }
+    }
+}
+
+/// Convert an error returned by ReadySet into the gRPC status returned to the client
+fn status(error: ReadySetError) -> Status {
+    if error.caused_by_view_not_found() {
+        Status::not_found(error.to_string())
+    } else {
+        Status::internal(error.to_string())
+    }
+}
+
+fn arrow_status(error: ArrowError) -> Status {
+    Status::internal(error.to_string())
+}
+
+/// Returns the name of the cache described by a flight descriptor, which must be a path with a
+/// single element
+fn descriptor_cache_name(descriptor: &FlightDescriptor) -> Result<&str, Status> {
+    match (descriptor.r#type(), descriptor.path.as_slice()) {
+        (DescriptorType::Path, [name]) => Ok(name),
+        _ => Err(Status::invalid_argument(
+            "Flight descriptors must be a path containing the name of a cache",
+        )),
+    }
+}
+
+fn view_schema(reader_handle: &ReaderHandle) -> Result<&[ColumnSchema], Status> {
+    Ok(reader_handle
+        .schema()
+        .ok_or_else(|| Status::internal("Cache has no schema"))?
+        .schema(SchemaType::ReturnedSchema))
+}
+
+/// Returns the Arrow data type used to represent values in the given column data
+fn data_type(data: &ColumnData) -> DataType {
+    match data {
+        ColumnData::Boolean(_) => DataType::Boolean,
+        ColumnData::Int64(_) => DataType::Int64,
+        ColumnData::UInt64(_) => DataType::UInt64,
+        ColumnData::Float64(_) => DataType::Float64,
+        ColumnData::Binary(_) => DataType::Binary,
+        ColumnData::Utf8(_) => DataType::Utf8,
+    }
+}
+
+/// Returns the Arrow schema of the record batches read from a view with the given schema
+fn arrow_schema(schema: &[ColumnSchema]) -> ReadySetResult<Schema> {
+    let empty = ColumnarBatch::from_rows::<Vec<DfValue>>(schema, &[])?;
+    Ok(Schema::new(
+        empty
+            .columns
+            .iter()
+            .map(|col| Field::new(col.name.as_str(), data_type(&col.data), true))
+            .collect(),
+    ))
+}
+
+/// Convert a [`ColumnarBatch`] into an Arrow [`RecordBatch`] with the given schema
+fn record_batch(schema: &SchemaRef, batch: &ColumnarBatch) -> Result<RecordBatch, ArrowError> {
+    fn nullable<'a, T>(
+        values: &'a [T],
+        validity: &'a [bool],
+    ) -> impl Iterator<Item = Option<&'a T>> + 'a {
+        values
+            .iter()
+            .zip(validity)
+            .map(|(value, valid)| valid.then_some(value))
+    }
+
+    let arrays = batch
+        .columns
+        .iter()
+        .map(|col| -> ArrayRef {
+            let validity = &col.validity;
+            match &col.data {
+                ColumnData::Boolean(values) => Arc::new(
+                    nullable(values, validity)
+                        .map(|value| value.copied())
+                        .collect::<BooleanArray>(),
+                ),
+                ColumnData::Int64(values) => Arc::new(
+                    nullable(values, validity)
+                        .map(|value| value.copied())
+                        .collect::<Int64Array>(),
+                ),
+                ColumnData::UInt64(values) => Arc::new(
+                    nullable(values, validity)
+                        .map(|value| value.copied())
+                        .collect::<UInt64Array>(),
+                ),
+                ColumnData::Float64(values) => Arc::new(
+                    nullable(values, validity)
+                        .map(|value| value.copied())
+                        .collect::<Float64Array>(),
+                ),
+                ColumnData::Binary(values) => {
+                    Arc::new(nullable(values, validity).collect::<BinaryArray>())
+                }
+                ColumnData::Utf8(values) => {
+                    Arc::new(nullable(values, validity).collect::<StringArray>())
+                }
+            }
+        })
+        .collect();
+
+    RecordBatch::try_new(schema.clone(), arrays)
+}
+
+/// Read every row stored in the reader of a cache, and send it to `tx` as Flight data: first the
+/// schema, followed by one record batch of at most `batch_size` rows at a time
+async fn send_rows(
+    mut reader_handle: ReaderHandle,
+    batch_size: usize,
+    tx: &mpsc::Sender<Result<FlightData, Status>>,
+) -> Result<(), Status> {
+    let options = IpcWriteOptions::default();
+    let schema = Arc::new(arrow_schema(view_schema(&reader_handle)?).map_err(status)?);
+    if tx
+        .send(Ok(SchemaAsIpc::new(&schema, &options).into()))
+        .await
+        .is_err()
+    {
+        return Ok(());
+    }
+
+    let batches = reader_handle
+        .columnar_batches(batch_size)
+        .await
+        .map_err(status)?;
+    futures::pin_mut!(batches);
+    while let Some(batch) = batches.try_next().await.map_err(status)? {
+        let batch = record_batch(&schema, &batch).map_err(arrow_status)?;
+        let (dictionaries, data) = flight_data_from_arrow_batch(&batch, &options);
+        for data in dictionaries.into_iter().chain(std::iter::once(data)) {
+            if tx.send(Ok(data)).await.is_err() {
+                // The client went away
+                return Ok(());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[tonic::async_trait]
+impl FlightService for ArrowFlightServer {
+    type HandshakeStream = BoxStream<HandshakeResponse>;
+    type ListFlightsStream = BoxStream<FlightInfo>;
+    type DoGetStream = BoxStream<FlightData>;
+    type DoPutStream = BoxStream<PutResult>;
+    type DoActionStream = BoxStream<arrow_flight::Result>;
+    type ListActionsStream = BoxStream<ActionType>;
+    type DoExchangeStream = BoxStream<FlightData>;
+
+    async fn handshake(
+        &self,
+        request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<Response<Self::HandshakeStream>, Status> {
+        let token = self.auth.authenticate(request.metadata())?;
+        let mut response: Response<Self::HandshakeStream> = Response::new(Box::pin(
+            futures::stream::once(futures::future::ready(Ok(HandshakeResponse {
+                protocol_version: 0,
+                payload: token.clone().into_bytes(),
+            }))),
+        ));
+        response.metadata_mut().insert(
+            "authorization",
+            MetadataValue::try_from(format!("Bearer {token}"))
+                .map_err(|_| Status::internal("Invalid bearer token"))?,
+        );
+        Ok(response)
+    }
+
+    async fn list_flights(
+        &self,
+        request: Request<Criteria>,
+    ) -> Result<Response<Self::ListFlightsStream>, Status> {
+        self.auth.check_token(request.metadata())?;
+        let views = self.readyset_handle.clone().views().await.map_err(status)?;
+
+        // Only look up the readers of caches we haven't built the flight info for yet, or which
+        // have been recreated since
+        let stale = {
+            let mut flight_infos = self.flight_infos.lock();
+            flight_infos.retain(|name, (node, _)| views.get(name) == Some(node));
+            views
+                .iter()
+                .filter(|(name, _)| !flight_infos.contains_key(*name))
+                .map(|(name, node)| (name.clone(), *node))
+                .collect::<Vec<_>>()
+        };
+        for (name, node) in stale {
+            let info = match self.flight_info(name.name.as_str()).await {
+                Ok(info) => Some(info),
+                // Caches which reuse other caches can't be read over Arrow Flight
+                Err(status) if status.code() == tonic::Code::Unimplemented => None,
+                // The cache was dropped since we listed the caches
+                Err(status) if status.code() == tonic::Code::NotFound => continue,
+                Err(status) => return Err(status),
+            };
+            self.flight_infos.lock().insert(name, (node, info));
+        }
+
+        let flights = {
+            let flight_infos = self.flight_infos.lock();
+            views
+                .keys()
+                .filter_map(|name| flight_infos.get(name)?.1.clone())
+                .map(Ok)
+                .collect::<Vec<Result<FlightInfo, Status>>>()
+        };
+        Ok(Response::new(Box::pin(futures::stream::iter(flights))))
+    }
+
+    async fn get_flight_info(
+        &self,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        self.auth.check_token(request.metadata())?;
+        let descriptor = request.into_inner();
+        let name = descriptor_cache_name(&descriptor)?;
+        Ok(Response::new(self.flight_info(name).await?))
+    }
+
+    async fn get_schema(
+        &self,
+        request: Request<FlightDescriptor>,
+    ) -> Result<Response<SchemaResult>, Status> {
+        self.auth.check_token(request.metadata())?;
+        let descriptor = request.into_inner();
+        let reader_handle = self
+            .reader_handle(descriptor_cache_name(&descriptor)?)
+            .await?;
+        let schema = arrow_schema(view_schema(&reader_handle)?).map_err(status)?;
+        let result: SchemaResult = SchemaAsIpc::new(&schema, &IpcWriteOptions::default())
+            .try_into()
+            .map_err(arrow_status)?;
+        Ok(Response::new(result))
+    }
+
+    async fn do_get(
+        &self,
+        request: Request<Ticket>,
+    ) -> Result<Response<Self::DoGetStream>, Status> {
+        self.auth.check_token(request.metadata())?;
+        let ticket = request.into_inner().ticket;
+        let name = std::str::from_utf8(&ticket)
+            .map_err(|_| Status::invalid_argument("Tickets must be the name of a cache"))?;
+        let reader_handle = self.reader_handle(name).await?;
+
+        // Rows are read on a separate task which owns the reader handle, and sent to the client
+        // through a bounded channel, so that reading doesn't get ahead of the client
+        let (tx, rx) = mpsc::channel(DO_GET_BUFFER);
+        let batch_size = self.batch_size;
+        tokio::spawn(async move {
+            if let Err(error) = send_rows(reader_handle, batch_size, &tx).await {
+                warn!(%error, "Error reading cache for Arrow Flight");
+                let _ = tx.send(Err(error)).await;
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+
+    async fn do_put(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoPutStream>, Status> {
+        Err(Status::unimplemented("Caches are read-only"))
+    }
+
+    async fn do_action(
+        &self,
+        _request: Request<Action>,
+    ) -> Result<Response<Self::DoActionStream>, Status> {
+        Err(Status::unimplemented("No actions are supported"))
+    }
+
+    async fn list_actions(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<Self::ListActionsStream>, Status> {
+        Ok(Response::new(Box::pin(futures::stream::empty())))
+    }
+
+    async fn do_exchange(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoExchangeStream>, Status> {
+        Err(Status::unimplemented("Caches are read-only"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow::array::Array;
+    use nom_sql::Column;
+    use readyset_data::{Collation, DfType};
+
+    use super::*;
+
+    fn schema() -> Vec<ColumnSchema> {
+        [
+            ("id", DfType::UnsignedBigInt),
+            ("ok", DfType::Bool),
+            ("name", DfType::Text(Collation::default())),
+        ]
+        .into_iter()
+        .map(|(name, column_type)| ColumnSchema {
+            column: Column {
+                name: name.into(),
+                table: None,
+            },
+            column_type,
+            base: None,
+        })
+        .collect()
+    }
+
+    fn authenticator() -> Authenticator {
+        Authenticator {
+            users: [("user".to_owned(), "pass".to_owned())].into(),
+            tokens: Default::default(),
+        }
+    }
+
+    fn metadata(authorization: &str) -> MetadataMap {
+        let mut metadata = MetadataMap::new();
+        metadata.insert("authorization", authorization.parse().unwrap());
+        metadata
+    }
+
+    #[test]
+    fn handshake_requires_credentials() {
+        let auth = authenticator();
+        assert!(auth.authenticate(&MetadataMap::new()).is_err());
+        assert!(auth
+            .authenticate(&metadata(&format!(
+                "Basic {}",
+                base64::encode("user:wrong")
+            )))
+            .is_err());
+        assert!(auth
+            .authenticate(&metadata(&format!(
+                "Basic {}",
+                base64::encode("other:pass")
+            )))
+            .is_err());
+        assert!(auth
+            .authenticate(&metadata(&format!("Basic {}", base64::encode("user:pass"))))
+            .is_ok());
+    }
+
+    #[test]
+    fn requests_require_token_from_handshake() {
+        let auth = authenticator();
+        assert!(auth.check_token(&MetadataMap::new()).is_err());
+        assert!(auth.check_token(&metadata("Bearer made-up")).is_err());
+
+        let token = auth
+            .authenticate(&metadata(&format!("Basic {}", base64::encode("user:pass"))))
+            .unwrap();
+        auth.check_token(&metadata(&format!("Bearer {token}")))
+            .unwrap();
+    }
+
+    #[test]
+    fn no_users_allows_unauthenticated_requests() {
+        let auth = Authenticator {
+            users: Default::default(),
+            tokens: Default::default(),
+        };
+        auth.check_token(&MetadataMap::new()).unwrap();
+    }
+
+    #[test]
+    fn schema_for_view() {
+        let schema = arrow_schema(&schema()).unwrap();
+        assert_eq!(
+            schema
+                .fields()
+                .iter()
+                .map(|f| (f.name().as_str(), f.data_type().clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("id", DataType::UInt64),
+                ("ok", DataType::Boolean),
+                ("name", DataType::Utf8)
+            ]
+        );
+        assert!(schema.fields().iter().all(|f| f.is_nullable()));
+    }
+
+    #[test]
+    fn batch_to_record_batch() {
+        let rows = vec![
+            vec![DfValue::from(1u64), DfValue::from(true), "a".into()],
+            vec![DfValue::from(2u64), DfValue::None, DfValue::None],
+        ];
+        let batch = ColumnarBatch::from_rows(&schema(), &rows).unwrap();
+        let record_batch =
+            record_batch(&Arc::new(arrow_schema(&schema()).unwrap()), &batch).unwrap();
+
+        assert_eq!(record_batch.num_rows(), 2);
+        let ids = record_batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap();
+        assert_eq!((ids.value(0), ids.value(1)), (1, 2));
+        assert!(record_batch.column(1).is_null(1));
+        let names = record_batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(names.value(0), "a");
+        assert!(names.is_null(1));
+    }
+}
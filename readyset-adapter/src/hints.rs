@@ -0,0 +1,156 @@
+//! Parsing of optimizer-style comment hints, which allow overriding how the adapter routes an
+//! individual ad-hoc `SELECT` query.
+//!
+//! Hints are written in a comment starting with `/*+`, conventionally placed immediately after the
+//! `SELECT` keyword (the same place MySQL accepts optimizer hints) or at the very beginning of the
+//! query, and consist of a whitespace-separated list of:
+//!
+//! - `NOCACHE`, which forces the query to be proxied to the upstream database
+//! - `READYSET(<option>, ...)`, where each option is one of:
+//!   - `cache=<name>`, which forces the query to be executed against the cache with the given name,
+//!     bypassing both automatic matching of the query to a cache and any decision to proxy the
+//!     query upstream
+//!   - `replay`, which forces the read to block until any keys it misses on have been replayed into
+//!     the cache, rather than falling back to the upstream database on a miss
+//!
+//! Unrecognized hints are ignored, like MySQL does for optimizer hints it doesn't understand.
+//!
+//! The hint comments of a query are kept in its AST by [`nom_sql::parse_query_with_comments`] when
+//! the query is parsed.
+
+use nom_sql::{Comments, SqlIdentifier};
+use readyset_tracing::warn;
+
+/// The hints parsed from the comments of a query. See the [module documentation](self) for more
+/// information.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(crate) struct QueryHints {
+    /// Force the query to be proxied to the upstream database
+    pub(crate) no_cache: bool,
+    /// Force the query to be executed against the cache with this name
+    pub(crate) cache: Option<SqlIdentifier>,
+    /// Force the read to block until missed keys have been replayed
+    pub(crate) replay: bool,
+}
+
+impl QueryHints {
+    /// Parse the hints from the hint comments of a query, returning the default (empty) set of
+    /// hints if the query has none
+    pub(crate) fn from_comments(comments: &Comments) -> Self {
+        let mut hints = Self::default();
+        for comment in comments.hints() {
+            for hint in split_hints(comment.body()) {
+                hints.add(hint);
+            }
+        }
+        hints
+    }
+
+    fn add(&mut self, hint: &str) {
+        if hint.eq_ignore_ascii_case("nocache") {
+            self.no_cache = true;
+            return;
+        }
+
+        let args = match hint
+            .get(.."readyset(".len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case("readyset("))
+            .and_then(|_| hint["readyset(".len()..].strip_suffix(')'))
+        {
+            Some(args) => args,
+            None => {
+                warn!(%hint, "Ignoring unrecognized query hint");
+                return;
+            }
+        };
+
+        for arg in args.split(',').map(str::trim).filter(|a| !a.is_empty()) {
+            match arg.split_once('=').map(|(k, v)| (k.trim(), v.trim())) {
+                Some((key, name)) if key.eq_ignore_ascii_case("cache") && !name.is_empty() => {
+                    self.cache = Some(name.trim_matches('`').trim_matches('"').into())
+                }
+                None if arg.eq_ignore_ascii_case("replay") => self.replay = true,
+                _ => warn!(%arg, "Ignoring unrecognized READYSET query hint option"),
+            }
+        }
+    }
+}
+
+/// Split the body of a hint comment into individual hints, keeping parenthesized arguments
+/// (which may contain whitespace) together with the hint they belong to
+fn split_hints(comment: &str) -> Vec<&str> {
+    let mut hints = vec![];
+    let mut depth = 0usize;
+    let mut start = None;
+    for (i, c) in comment.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            c if c.is_whitespace() && depth == 0 => {
+                if let Some(s) = start.take() {
+                    hints.push(&comment[s..i]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(i);
+    }
+    if let Some(s) = start {
+        hints.push(&comment[s..]);
+    }
+    hints
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::{parse_query_with_comments, Dialect, SqlQuery};
+
+    use super::*;
+
+    fn parse(query: &str) -> QueryHints {
+        match parse_query_with_comments(Dialect::MySQL, query).unwrap() {
+            SqlQuery::Select(select) => QueryHints::from_comments(&select.comments),
+            _ => QueryHints::default(),
+        }
+    }
+
+    #[test]
+    fn no_hints() {
+        for query in [
+            "SELECT * FROM t",
+            "SELECT /* NOCACHE */ * FROM t",
+            "SELECT * FROM t WHERE x = '/*+ NOCACHE */'",
+        ] {
+            assert_eq!(parse(query), QueryHints::default());
+        }
+    }
+
+    #[test]
+    fn nocache() {
+        let hints = parse("SELECT /*+ NOCACHE */ * FROM t");
+        assert!(hints.no_cache);
+        assert_eq!(parse("/*+ nocache */ SELECT * FROM t"), hints);
+    }
+
+    #[test]
+    fn readyset_options() {
+        let hints = parse("select /*+ READYSET(cache=foo, replay) */ * FROM t");
+        assert_eq!(
+            hints,
+            QueryHints {
+                no_cache: false,
+                cache: Some("foo".into()),
+                replay: true,
+            }
+        );
+    }
+
+    #[test]
+    fn multiple_hints() {
+        let hints = parse("SELECT /*+ BKA(t) READYSET( cache = `foo` ) */ * FROM t");
+        assert_eq!(hints.cache, Some("foo".into()));
+        assert!(!hints.no_cache);
+        assert!(!hints.replay);
+    }
+}
@@ -10,9 +10,11 @@ use futures::TryFutureExt;
 use health_reporter::{HealthReporter as AdapterHealthReporter, State};
 use hyper::header::CONTENT_TYPE;
 use hyper::service::make_service_fn;
-use hyper::{self, Body, Method, Request, Response};
+use hyper::{self, Body, Method, Request, Response, StatusCode};
 use metrics_exporter_prometheus::PrometheusHandle;
 use readyset_client::query::DeniedQuery;
+use readyset_client::status::SnapshotStatus;
+use readyset_client::ReadySetHandle;
 use readyset_client_metrics::recorded;
 use readyset_sql_passes::anonymize::Anonymizer;
 use stream_cancel::Valve;
@@ -36,6 +38,8 @@ pub struct NoriaAdapterHttpRouter {
     pub valve: Valve,
     /// Used to retrieve the current health of the adapter.
     pub health_reporter: AdapterHealthReporter,
+    /// Handle to the ReadySet deployment, used to check whether it has finished snapshotting.
+    pub readyset_handle: ReadySetHandle,
     /// Used to communicate externally that a failpoint request has been received and successfully
     /// handled.
     /// Most commonly used to block on further startup action if --wait-for-failpoint is supplied
@@ -113,6 +117,55 @@ impl Service<Request<Body>> for NoriaAdapterHttpRouter {
     ///
     ///   `curl -X GET <adapter>:<adapter-port>/health`
     ///
+    /// ## Readiness Check
+    ///
+    /// Get whether the adapter is ready to serve queries. Returns 200 if the adapter is healthy and
+    /// ReadySet has finished snapshotting the upstream database, or 503 otherwise - including
+    /// while the adapter is draining. Intended to be used as a Kubernetes readiness probe, with
+    /// `/health` as the liveness probe.
+    ///
+    /// * **URL**
+    ///
+    ///   `/readiness`
+    ///
+    /// * **Method:**
+    ///
+    ///   `GET`
+    ///
+    /// * **Success Response:**
+    ///
+    ///     * **Code:** 200 <br />
+    ///
+    /// * **Error Response:**
+    ///
+    ///     * **Code:** 503 Service Unavailable <br />
+    ///
+    /// * **Sample Call:**
+    ///
+    ///   `curl -X GET <adapter>:<adapter-port>/readiness`
+    ///
+    /// ## Drain
+    ///
+    /// Stop accepting new client connections, in preparation for shutting down the adapter.
+    /// Connections that are already established keep being served until the adapter exits. Intended
+    /// to be used as a Kubernetes `preStop` hook.
+    ///
+    /// * **URL**
+    ///
+    ///   `/drain`
+    ///
+    /// * **Method:**
+    ///
+    ///   `POST`
+    ///
+    /// * **Success Response:**
+    ///
+    ///     * **Code:** 200 <br />
+    ///
+    /// * **Sample Call:**
+    ///
+    ///   `curl -X POST <adapter>:<adapter-port>/drain`
+    ///
     /// ## Allow List
     ///
     /// List of SQL queries that will be handled by ReadySet as opposed to being passed through to
@@ -290,7 +343,7 @@ impl Service<Request<Body>> for NoriaAdapterHttpRouter {
                 Box::pin(async move {
                     let body = format!("Adapter is in {} state", &state).into();
                     let res = match state {
-                        State::Healthy | State::ShuttingDown => res
+                        State::Healthy | State::Draining | State::ShuttingDown => res
                             .status(200)
                             .header(CONTENT_TYPE, "text/plain")
                             .body(body),
@@ -303,6 +356,41 @@ impl Service<Request<Body>> for NoriaAdapterHttpRouter {
                     Ok(res.unwrap())
                 })
             }
+            (&Method::GET, "/readiness") => {
+                let state = self.health_reporter.health().state;
+                let mut rh = self.readyset_handle.clone();
+                Box::pin(async move {
+                    let snapshot_status = if state == State::Healthy {
+                        rh.status().await.map(|s| s.snapshot_status).ok()
+                    } else {
+                        None
+                    };
+                    let res = res.header(CONTENT_TYPE, "text/plain");
+                    let res = match snapshot_status {
+                        Some(SnapshotStatus::Completed) => {
+                            res.status(200).body("Adapter is ready".into())
+                        }
+                        Some(SnapshotStatus::InProgress) => res
+                            .status(StatusCode::SERVICE_UNAVAILABLE)
+                            .body("Snapshotting is in progress".into()),
+                        None => res
+                            .status(StatusCode::SERVICE_UNAVAILABLE)
+                            .body(format!("Adapter is in {} state", &state).into()),
+                    };
+                    Ok(res.unwrap())
+                })
+            }
+            (&Method::POST, "/drain") => {
+                let mut health_reporter = self.health_reporter.clone();
+                Box::pin(async move {
+                    health_reporter.set_state(State::Draining);
+                    let res = res
+                        .status(200)
+                        .header(CONTENT_TYPE, "text/plain")
+                        .body("Adapter is draining".into());
+                    Ok(res.unwrap())
+                })
+            }
             (&Method::GET, "/metrics") => {
                 let body = self.prometheus_handle.as_ref().map(|x| x.render());
                 let res = res.header(CONTENT_TYPE, "text/plain");
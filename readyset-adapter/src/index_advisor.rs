@@ -0,0 +1,119 @@
+//! The index advisor watches the filters that have to be applied to the results of reads from
+//! ReadySet after the lookup into a reader (because the predicates they come from can't be turned
+//! into reader keys), and asks ReadySet to add an index on the filtered columns to the state
+//! feeding that reader once the same set of columns has been filtered on often enough.
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use dashmap::DashMap;
+use nom_sql::Relation;
+use readyset_client::ReadySetHandle;
+use readyset_tracing::{debug, info, warn};
+
+/// Counts how many times reads from each view have been filtered on each set of columns.
+#[derive(Debug)]
+struct FilterCounts {
+    /// The number of times a set of columns must be filtered on before we consider it worth
+    /// indexing
+    threshold: u64,
+    /// Map from view, to the sets of columns reads from that view have been filtered on, to the
+    /// number of those reads
+    counts: DashMap<Relation, HashMap<Vec<usize>, AtomicU64>>,
+}
+
+impl FilterCounts {
+    fn new(threshold: u64) -> Self {
+        Self {
+            threshold,
+            counts: DashMap::new(),
+        }
+    }
+
+    /// Record a read from `view` that was filtered on the given `columns`, returning `true` if
+    /// this is the read that brought the number of such reads up to the threshold. This will only
+    /// ever return `true` once for any given view and set of columns.
+    fn record(&self, view: &Relation, columns: &[usize]) -> bool {
+        // Every read but the first one filtered on a given set of columns only needs a read lock
+        // on the map, and doesn't allocate
+        if let Some(view_counts) = self.counts.get(view) {
+            if let Some(count) = view_counts.get(columns) {
+                return count.fetch_add(1, Ordering::Relaxed) + 1 == self.threshold;
+            }
+        }
+
+        let mut view_counts = self.counts.entry(view.clone()).or_default();
+        let count = view_counts.entry(columns.to_vec()).or_default();
+        count.fetch_add(1, Ordering::Relaxed) + 1 == self.threshold
+    }
+}
+
+/// Tracks the columns that reads from views get filtered on after the lookup into the reader, and
+/// requests additional indices from ReadySet for sets of columns that are filtered on frequently.
+///
+/// A single [`IndexAdvisor`] is intended to be shared between all the connections to an adapter.
+pub struct IndexAdvisor {
+    filters: FilterCounts,
+    noria: ReadySetHandle,
+}
+
+impl IndexAdvisor {
+    /// Create a new [`IndexAdvisor`] which will ask ReadySet (via `noria`) to add an index once a
+    /// set of columns in a view has been filtered on `threshold` times.
+    pub fn new(threshold: u64, noria: ReadySetHandle) -> Self {
+        Self {
+            filters: FilterCounts::new(threshold),
+            noria,
+        }
+    }
+
+    /// Record a read from `view` whose results were filtered on the given `columns` after the
+    /// lookup into the reader.
+    ///
+    /// If this brings the number of reads filtered on those columns up to the configured
+    /// threshold, spawns a background task to request an index on those columns.
+    pub fn record(&self, view: &Relation, columns: &[usize]) {
+        if columns.is_empty() || !self.filters.record(view, columns) {
+            return;
+        }
+
+        let columns = columns.to_vec();
+        debug!(%view, ?columns, "Requesting additional index for frequently filtered columns");
+        let mut noria = self.noria.clone();
+        let view = view.clone();
+        tokio::spawn(async move {
+            match noria.add_index(&view, columns.clone()).await {
+                Ok(true) => info!(%view, ?columns, "Added index for frequently filtered columns"),
+                Ok(false) => debug!(%view, ?columns, "View not eligible for additional index"),
+                Err(error) => warn!(%error, %view, ?columns, "Failed to add index"),
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_reaches_threshold_once() {
+        let counts = FilterCounts::new(3);
+        let view = Relation::from("q");
+        assert!(!counts.record(&view, &[1]));
+        assert!(!counts.record(&view, &[1]));
+        assert!(counts.record(&view, &[1]));
+        assert!(!counts.record(&view, &[1]));
+    }
+
+    #[test]
+    fn record_counts_views_and_columns_separately() {
+        let counts = FilterCounts::new(2);
+        let q1 = Relation::from("q1");
+        let q2 = Relation::from("q2");
+        assert!(!counts.record(&q1, &[1]));
+        assert!(!counts.record(&q2, &[1]));
+        assert!(!counts.record(&q1, &[1, 2]));
+        assert!(counts.record(&q2, &[1]));
+        assert!(counts.record(&q1, &[1, 2]));
+        assert!(counts.record(&q1, &[1]));
+    }
+}
@@ -0,0 +1,764 @@
+//! Answering schema introspection queries from ReadySet's own schema catalog.
+//!
+//! Many clients and tools query `information_schema.tables` and `information_schema.columns`, or
+//! run `SHOW TABLES`, `SHOW COLUMNS` and `SHOW CREATE TABLE`, as soon as they connect. When
+//! there's an upstream database those queries are proxied to it, but without one they'd otherwise
+//! fail. Instead, we answer them from the schemas of the base tables ReadySet knows about: each is
+//! treated as a small virtual table, whose rows are built from the catalog and then projected,
+//! filtered, ordered and limited according to the query.
+//!
+//! Only the subset of SQL that introspection queries tend to use is supported: projecting
+//! columns, filtering with `AND`, `OR`, `=`, `!=`, `LIKE` and `IN` over columns, literals and
+//! `DATABASE()`, ordering by columns, and limits. As with the case-insensitive collation MySQL
+//! uses for these tables, `=`, `IN` and `LIKE` compare strings case-insensitively.
+//!
+//! The rows we produce are shaped like MySQL's, so these queries are only answered for MySQL
+//! clients (see [`supports_dialect`]).
+use std::cmp::Ordering;
+
+use dataflow_expression::like::{CaseSensitivityMode, LikePattern};
+use nom_sql::{
+    BinaryOperator, ColumnConstraint, CreateTableBody, CreateTableStatement, Dialect, Expr,
+    FieldDefinitionExpr, FieldReference, FilterPredicate, FunctionExpr, InValue, Literal,
+    OrderType, Relation, SelectStatement, ShowColumns, SqlIdentifier, TableExprInner, TableKey,
+};
+use readyset_data::{DfType, DfValue};
+use readyset_errors::{unsupported, ReadySetError, ReadySetResult};
+
+use crate::utils;
+
+/// The name of the schema containing the virtual tables
+const INFORMATION_SCHEMA: &str = "information_schema";
+
+/// The value of the `TABLE_CATALOG` column, which is always `def` in MySQL
+const CATALOG: &str = "def";
+
+/// The schemas of the base tables known to ReadySet, along with their names
+pub(crate) type Catalog = [(Relation, CreateTableBody)];
+
+/// The tables in `information_schema` which we can answer queries against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SystemTable {
+    Tables,
+    Columns,
+}
+
+/// The columns and rows of a virtual table, or of the results of a query against one
+#[derive(Debug)]
+pub(crate) struct VirtualTable {
+    pub(crate) columns: Vec<(SqlIdentifier, DfType)>,
+    pub(crate) rows: Vec<Vec<DfValue>>,
+}
+
+impl VirtualTable {
+    fn new(columns: &[(&str, DfType)]) -> Self {
+        Self {
+            columns: columns
+                .iter()
+                .map(|(name, ty)| ((*name).into(), ty.clone()))
+                .collect(),
+            rows: vec![],
+        }
+    }
+
+    /// Returns the index of the column with the given name, ignoring case
+    fn column_index(&self, name: &str) -> ReadySetResult<usize> {
+        self.columns
+            .iter()
+            .position(|(col, _)| col.eq_ignore_ascii_case(name))
+            .ok_or_else(|| ReadySetError::NoSuchColumn(name.to_owned()))
+    }
+
+    /// Removes the rows which don't match `filter`
+    fn filter(&mut self, filter: &Expr, database: Option<&str>) -> ReadySetResult<()> {
+        let mut rows = Vec::with_capacity(self.rows.len());
+        for row in std::mem::take(&mut self.rows) {
+            if eval(filter, self, &row, database)?.is_truthy() {
+                rows.push(row);
+            }
+        }
+        self.rows = rows;
+        Ok(())
+    }
+}
+
+/// Information about a column of a base table, in the format used by both
+/// `information_schema.columns` and `SHOW COLUMNS`
+struct ColumnInfo {
+    name: SqlIdentifier,
+    column_type: String,
+    data_type: String,
+    nullable: bool,
+    key: &'static str,
+    default: Option<String>,
+    extra: &'static str,
+}
+
+fn column_info(schema: &CreateTableBody) -> Vec<ColumnInfo> {
+    let primary_key = utils::get_primary_key(schema)
+        .into_iter()
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    schema
+        .fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| {
+            let is_primary = primary_key.contains(&i);
+            let is_unique = field.constraints.contains(&ColumnConstraint::Unique)
+                || schema.keys.iter().flatten().any(|key| {
+                    matches!(
+                        key,
+                        TableKey::UniqueKey { columns, .. }
+                            if columns.as_slice() == std::slice::from_ref(&field.column)
+                    )
+                });
+            let column_type = field.sql_type.to_string().to_ascii_lowercase();
+            let data_type = column_type
+                .split(|c: char| c == '(' || c.is_whitespace())
+                .next()
+                .unwrap_or_default()
+                .to_owned();
+            ColumnInfo {
+                name: field.column.name.clone(),
+                column_type,
+                data_type,
+                nullable: !is_primary && !field.constraints.contains(&ColumnConstraint::NotNull),
+                key: if is_primary {
+                    "PRI"
+                } else if is_unique {
+                    "UNI"
+                } else {
+                    ""
+                },
+                default: field.constraints.iter().find_map(|c| match c {
+                    ColumnConstraint::DefaultValue(Expr::Literal(Literal::Null)) => None,
+                    // MySQL shows string defaults as their value, not as a quoted literal
+                    ColumnConstraint::DefaultValue(Expr::Literal(Literal::String(s))) => {
+                        Some(s.clone())
+                    }
+                    ColumnConstraint::DefaultValue(expr) => Some(expr.to_string()),
+                    _ => None,
+                }),
+                extra: if field.constraints.contains(&ColumnConstraint::AutoIncrement) {
+                    "auto_increment"
+                } else {
+                    ""
+                },
+            }
+        })
+        .collect()
+}
+
+fn schema_name(table: &Relation) -> DfValue {
+    table.schema.as_ref().map(|s| s.as_str()).into()
+}
+
+fn tables_table(catalog: &Catalog) -> VirtualTable {
+    let mut res = VirtualTable::new(&[
+        ("TABLE_CATALOG", DfType::DEFAULT_TEXT),
+        ("TABLE_SCHEMA", DfType::DEFAULT_TEXT),
+        ("TABLE_NAME", DfType::DEFAULT_TEXT),
+        ("TABLE_TYPE", DfType::DEFAULT_TEXT),
+    ]);
+    res.rows = catalog
+        .iter()
+        .map(|(table, _)| {
+            vec![
+                CATALOG.into(),
+                schema_name(table),
+                table.name.as_str().into(),
+                "BASE TABLE".into(),
+            ]
+        })
+        .collect();
+    res
+}
+
+fn columns_table(catalog: &Catalog) -> VirtualTable {
+    let mut res = VirtualTable::new(&[
+        ("TABLE_CATALOG", DfType::DEFAULT_TEXT),
+        ("TABLE_SCHEMA", DfType::DEFAULT_TEXT),
+        ("TABLE_NAME", DfType::DEFAULT_TEXT),
+        ("COLUMN_NAME", DfType::DEFAULT_TEXT),
+        ("ORDINAL_POSITION", DfType::UnsignedBigInt),
+        ("COLUMN_DEFAULT", DfType::DEFAULT_TEXT),
+        ("IS_NULLABLE", DfType::DEFAULT_TEXT),
+        ("DATA_TYPE", DfType::DEFAULT_TEXT),
+        ("COLUMN_TYPE", DfType::DEFAULT_TEXT),
+        ("COLUMN_KEY", DfType::DEFAULT_TEXT),
+        ("EXTRA", DfType::DEFAULT_TEXT),
+    ]);
+    for (table, schema) in catalog {
+        for (i, info) in column_info(schema).into_iter().enumerate() {
+            res.rows.push(vec![
+                CATALOG.into(),
+                schema_name(table),
+                table.name.as_str().into(),
+                info.name.as_str().into(),
+                DfValue::from(i as u64 + 1),
+                info.default.into(),
+                if info.nullable { "YES" } else { "NO" }.into(),
+                info.data_type.into(),
+                info.column_type.into(),
+                info.key.into(),
+                info.extra.into(),
+            ]);
+        }
+    }
+    res
+}
+
+/// Returns the system table which the given query reads from, if it's a query against a single
+/// table in `information_schema` we know how to answer
+fn system_table(stmt: &SelectStatement) -> Option<SystemTable> {
+    let [table] = &stmt.tables[..] else {
+        return None;
+    };
+    let TableExprInner::Table(Relation {
+        schema: Some(schema),
+        name,
+    }) = &table.inner else {
+        return None;
+    };
+    if !stmt.join.is_empty() || !schema.eq_ignore_ascii_case(INFORMATION_SCHEMA) {
+        return None;
+    }
+    if name.eq_ignore_ascii_case("tables") {
+        Some(SystemTable::Tables)
+    } else if name.eq_ignore_ascii_case("columns") {
+        Some(SystemTable::Columns)
+    } else {
+        None
+    }
+}
+
+/// Returns `true` if queries against `information_schema` and `SHOW` statements from clients using
+/// the given dialect can be answered by this module. PostgreSQL's `information_schema` has
+/// different columns (and a different meaning of `table_catalog`) than MySQL's, and PostgreSQL
+/// has no `SHOW TABLES` or `SHOW COLUMNS`, so only MySQL is supported.
+pub(crate) fn supports_dialect(dialect: Dialect) -> bool {
+    dialect == Dialect::MySQL
+}
+
+/// Returns `true` if the given query reads from a table in `information_schema` that can be
+/// answered by [`select`]
+pub(crate) fn is_information_schema_query(stmt: &SelectStatement) -> bool {
+    system_table(stmt).is_some()
+}
+
+/// Evaluate `expr` against a row of `table`
+fn eval(
+    expr: &Expr,
+    table: &VirtualTable,
+    row: &[DfValue],
+    database: Option<&str>,
+) -> ReadySetResult<DfValue> {
+    let compare = |lhs: &Expr, rhs: &Expr| -> ReadySetResult<Option<Ordering>> {
+        let lhs = eval(lhs, table, row, database)?;
+        let rhs = eval(rhs, table, row, database)?;
+        Ok(match (lhs.as_str(), rhs.as_str()) {
+            (Some(lhs), Some(rhs)) => Some(
+                lhs.bytes()
+                    .map(|b| b.to_ascii_lowercase())
+                    .cmp(rhs.bytes().map(|b| b.to_ascii_lowercase())),
+            ),
+            _ => (!lhs.is_none() && !rhs.is_none()).then(|| lhs.cmp(&rhs)),
+        })
+    };
+    let like = |lhs: &Expr, rhs: &Expr, mode: CaseSensitivityMode| -> ReadySetResult<bool> {
+        let lhs = eval(lhs, table, row, database)?;
+        let rhs = eval(rhs, table, row, database)?;
+        Ok(match (lhs.as_str(), rhs.as_str()) {
+            (Some(s), Some(pattern)) => LikePattern::new(pattern, mode).matches(s),
+            _ => false,
+        })
+    };
+
+    Ok(match expr {
+        Expr::Column(column) => row[table.column_index(&column.name)?].clone(),
+        Expr::Literal(literal) => DfValue::try_from(literal)?,
+        Expr::Call(FunctionExpr::Call { name, arguments })
+            if arguments.is_empty()
+                && ["database", "schema", "current_schema"]
+                    .iter()
+                    .any(|f| name.eq_ignore_ascii_case(f)) =>
+        {
+            database.into()
+        }
+        Expr::BinaryOp { lhs, op, rhs } => match op {
+            BinaryOperator::And => (eval(lhs, table, row, database)?.is_truthy()
+                && eval(rhs, table, row, database)?.is_truthy())
+            .into(),
+            BinaryOperator::Or => (eval(lhs, table, row, database)?.is_truthy()
+                || eval(rhs, table, row, database)?.is_truthy())
+            .into(),
+            BinaryOperator::Equal => (compare(lhs, rhs)? == Some(Ordering::Equal)).into(),
+            BinaryOperator::NotEqual => {
+                matches!(compare(lhs, rhs)?, Some(Ordering::Less | Ordering::Greater)).into()
+            }
+            BinaryOperator::Like | BinaryOperator::ILike => {
+                like(lhs, rhs, CaseSensitivityMode::CaseInsensitive)?.into()
+            }
+            BinaryOperator::NotLike | BinaryOperator::NotILike => {
+                (!like(lhs, rhs, CaseSensitivityMode::CaseInsensitive)?).into()
+            }
+            _ => unsupported!("Operator {op} is not supported in queries against system tables"),
+        },
+        Expr::In {
+            lhs,
+            rhs: InValue::List(list),
+            negated,
+        } => {
+            let mut found = false;
+            for value in list {
+                if compare(lhs, value)? == Some(Ordering::Equal) {
+                    found = true;
+                    break;
+                }
+            }
+            (found != *negated).into()
+        }
+        _ => unsupported!("Expression {expr} is not supported in queries against system tables"),
+    })
+}
+
+/// Answer a query against a table in `information_schema` (see [`is_information_schema_query`])
+/// from the given catalog, with `database` as the currently selected database
+pub(crate) fn select(
+    stmt: &SelectStatement,
+    catalog: &Catalog,
+    database: Option<&str>,
+) -> ReadySetResult<VirtualTable> {
+    let Some(system_table) = system_table(stmt) else {
+        unsupported!("Query does not read from a system table");
+    };
+    if stmt.distinct || stmt.group_by.is_some() || stmt.having.is_some() || !stmt.ctes.is_empty() {
+        unsupported!(
+            "Aggregates and common table expressions are not supported in queries against system \
+             tables"
+        );
+    }
+
+    let mut table = match system_table {
+        SystemTable::Tables => tables_table(catalog),
+        SystemTable::Columns => columns_table(catalog),
+    };
+    if let Some(filter) = &stmt.where_clause {
+        table.filter(filter, database)?;
+    }
+
+    // The index in the virtual table and the name of each projected column
+    let mut projection = vec![];
+    for field in &stmt.fields {
+        match field {
+            FieldDefinitionExpr::All | FieldDefinitionExpr::AllInTable(_) => projection.extend(
+                table
+                    .columns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (name, _))| (i, name.clone())),
+            ),
+            FieldDefinitionExpr::Expr {
+                expr: Expr::Column(column),
+                alias,
+            } => projection.push((
+                table.column_index(&column.name)?,
+                alias.clone().unwrap_or_else(|| column.name.clone()),
+            )),
+            FieldDefinitionExpr::Expr { expr, .. } => {
+                unsupported!("Expression {expr} is not supported in queries against system tables")
+            }
+        }
+    }
+
+    if let Some(order) = &stmt.order {
+        let mut keys = vec![];
+        for (field, order_type) in &order.order_by {
+            let index = match field {
+                FieldReference::Numeric(n) => projection
+                    .get((*n as usize).wrapping_sub(1))
+                    .map(|(i, _)| *i)
+                    .ok_or_else(|| ReadySetError::NoSuchColumn(n.to_string()))?,
+                FieldReference::Expr(Expr::Column(column)) => {
+                    match projection
+                        .iter()
+                        .find(|(_, name)| name.eq_ignore_ascii_case(&column.name))
+                    {
+                        Some((i, _)) => *i,
+                        None => table.column_index(&column.name)?,
+                    }
+                }
+                FieldReference::Expr(expr) => {
+                    unsupported!("Cannot order by {expr} in queries against system tables")
+                }
+            };
+            keys.push((index, order_type.unwrap_or(OrderType::OrderAscending)));
+        }
+        table.rows.sort_by(|a, b| {
+            keys.iter()
+                .map(|(i, order_type)| order_type.apply(a[*i].cmp(&b[*i])))
+                .find(|ord| ord.is_ne())
+                .unwrap_or(Ordering::Equal)
+        });
+    }
+
+    if stmt.limit_clause.offset().is_some() {
+        unsupported!("OFFSET is not supported in queries against system tables");
+    }
+    if let Some(limit) = stmt.limit_clause.limit() {
+        match limit {
+            Literal::Integer(n) => table.rows.truncate((*n).max(0) as usize),
+            Literal::UnsignedInteger(n) => table.rows.truncate(*n as usize),
+            _ => unsupported!("Invalid LIMIT {limit} in query against system table"),
+        }
+    }
+
+    Ok(VirtualTable {
+        columns: projection
+            .iter()
+            .map(|(i, name)| (name.clone(), table.columns[*i].1.clone()))
+            .collect(),
+        rows: table
+            .rows
+            .into_iter()
+            .map(|row| projection.iter().map(|(i, _)| row[*i].clone()).collect())
+            .collect(),
+    })
+}
+
+/// Apply the `LIKE` or `WHERE` clause of a `SHOW` statement to `table`, with the `LIKE` pattern
+/// matched against the first column
+fn apply_filter_predicate(
+    table: &mut VirtualTable,
+    filter: Option<&FilterPredicate>,
+    database: Option<&str>,
+) -> ReadySetResult<()> {
+    match filter {
+        Some(FilterPredicate::Like(pattern)) => {
+            let pattern = LikePattern::new(pattern, CaseSensitivityMode::CaseInsensitive);
+            table
+                .rows
+                .retain(|row| row[0].as_str().map_or(false, |s| pattern.matches(s)));
+        }
+        Some(FilterPredicate::Where(filter)) => table.filter(filter, database)?,
+        None => {}
+    }
+    Ok(())
+}
+
+/// Answer a `SHOW [FULL] TABLES [FROM <from_db>] [LIKE '<pattern>' | WHERE <expr>]` statement
+/// from the given catalog, with `database` as the currently selected database
+pub(crate) fn show_tables(
+    full: bool,
+    from_db: Option<&str>,
+    filter: Option<&FilterPredicate>,
+    catalog: &Catalog,
+    database: Option<&str>,
+) -> ReadySetResult<VirtualTable> {
+    let Some(db) = from_db.or(database) else {
+        return Err(ReadySetError::BadRequest("No database selected".into()));
+    };
+
+    let name_column = format!("Tables_in_{db}");
+    let mut res = if full {
+        VirtualTable::new(&[
+            (name_column.as_str(), DfType::DEFAULT_TEXT),
+            ("Table_type", DfType::DEFAULT_TEXT),
+        ])
+    } else {
+        VirtualTable::new(&[(name_column.as_str(), DfType::DEFAULT_TEXT)])
+    };
+    res.rows = catalog
+        .iter()
+        .filter(|(table, _)| table.schema.as_deref() == Some(db))
+        .map(|(table, _)| {
+            let mut row = vec![table.name.as_str().into()];
+            if full {
+                row.push("BASE TABLE".into());
+            }
+            row
+        })
+        .collect();
+    res.rows.sort();
+
+    apply_filter_predicate(&mut res, filter, database)?;
+    Ok(res)
+}
+
+/// Returns the schema of `table` in the given catalog, resolving it in `database` if it's
+/// unqualified
+fn find_table<'a>(
+    table: &Relation,
+    catalog: &'a Catalog,
+    database: Option<&str>,
+) -> ReadySetResult<&'a CreateTableBody> {
+    let schema = table.schema.as_deref().or(database);
+    catalog
+        .iter()
+        .find(|(t, _)| t.name == table.name && t.schema.as_deref() == schema)
+        .map(|(_, body)| body)
+        .ok_or_else(|| ReadySetError::TableNotFound {
+            name: table.name.to_string(),
+            schema: schema.map(|s| s.to_owned()),
+        })
+}
+
+/// Answer a `SHOW COLUMNS` statement from the given catalog, with `database` as the currently
+/// selected database
+pub(crate) fn show_columns(
+    stmt: &ShowColumns,
+    catalog: &Catalog,
+    database: Option<&str>,
+) -> ReadySetResult<VirtualTable> {
+    let table_schema = find_table(&stmt.table, catalog, database)?;
+    let mut res = VirtualTable::new(&[
+        ("Field", DfType::DEFAULT_TEXT),
+        ("Type", DfType::DEFAULT_TEXT),
+        ("Null", DfType::DEFAULT_TEXT),
+        ("Key", DfType::DEFAULT_TEXT),
+        ("Default", DfType::DEFAULT_TEXT),
+        ("Extra", DfType::DEFAULT_TEXT),
+    ]);
+    res.rows = column_info(table_schema)
+        .into_iter()
+        .map(|info| {
+            vec![
+                info.name.as_str().into(),
+                info.column_type.into(),
+                if info.nullable { "YES" } else { "NO" }.into(),
+                info.key.into(),
+                info.default.into(),
+                info.extra.into(),
+            ]
+        })
+        .collect();
+
+    apply_filter_predicate(&mut res, stmt.filter.as_ref(), database)?;
+    Ok(res)
+}
+
+/// Answer a `SHOW CREATE TABLE` statement from the given catalog, with `database` as the currently
+/// selected database, by rendering the `CREATE TABLE` statement for the table's schema.
+///
+/// Table options aren't part of the catalog, so are never included.
+pub(crate) fn show_create_table(
+    table: &Relation,
+    catalog: &Catalog,
+    database: Option<&str>,
+) -> ReadySetResult<VirtualTable> {
+    let body = find_table(table, catalog, database)?;
+    let stmt = CreateTableStatement {
+        if_not_exists: false,
+        table: table.name.clone().into(),
+        body: Ok(body.clone()),
+        options: Ok(vec![]),
+    };
+
+    let mut res = VirtualTable::new(&[
+        ("Table", DfType::DEFAULT_TEXT),
+        ("Create Table", DfType::DEFAULT_TEXT),
+    ]);
+    res.rows = vec![vec![table.name.as_str().into(), stmt.to_string().into()]];
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::{parse_create_table, parse_query, parse_select_statement, Dialect, SqlQuery};
+
+    use super::*;
+
+    fn catalog() -> Vec<(Relation, CreateTableBody)> {
+        [
+            (
+                "db1",
+                "CREATE TABLE t1 (id INT PRIMARY KEY, name VARCHAR(255) NOT NULL, x INT DEFAULT 3, \
+                 s VARCHAR(8) NOT NULL DEFAULT 'it''s')",
+            ),
+            ("db1", "CREATE TABLE t2 (a TEXT, UNIQUE KEY (a))"),
+            ("db2", "CREATE TABLE t3 (b INT AUTO_INCREMENT PRIMARY KEY)"),
+        ]
+        .into_iter()
+        .map(|(schema, create)| {
+            let stmt = parse_create_table(Dialect::MySQL, create).unwrap();
+            (
+                Relation {
+                    schema: Some(schema.into()),
+                    name: stmt.table.name,
+                },
+                stmt.body.unwrap(),
+            )
+        })
+        .collect()
+    }
+
+    fn query(q: &str) -> VirtualTable {
+        let stmt = parse_select_statement(Dialect::MySQL, q).unwrap();
+        assert!(is_information_schema_query(&stmt));
+        select(&stmt, &catalog(), Some("db1")).unwrap()
+    }
+
+    #[test]
+    fn select_tables_in_current_database() {
+        let res = query(
+            "SELECT table_name AS name FROM information_schema.TABLES \
+             WHERE table_schema = DATABASE() ORDER BY table_name DESC",
+        );
+        assert_eq!(res.columns, vec![("name".into(), DfType::DEFAULT_TEXT)]);
+        assert_eq!(
+            res.rows,
+            vec![vec![DfValue::from("t2")], vec![DfValue::from("t1")]]
+        );
+    }
+
+    #[test]
+    fn select_columns() {
+        let res = query(
+            "SELECT column_name, is_nullable, column_key, column_default, data_type \
+             FROM information_schema.columns \
+             WHERE table_schema IN ('db1', 'db3') AND table_name LIKE 't%' \
+             AND ordinal_position != 2 \
+             ORDER BY 1 LIMIT 2",
+        );
+        assert_eq!(
+            res.rows,
+            vec![
+                vec![
+                    DfValue::from("a"),
+                    DfValue::from("YES"),
+                    DfValue::from("UNI"),
+                    DfValue::None,
+                    DfValue::from("text"),
+                ],
+                vec![
+                    DfValue::from("id"),
+                    DfValue::from("NO"),
+                    DfValue::from("PRI"),
+                    DfValue::None,
+                    DfValue::from("int"),
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn unsupported_queries_are_rejected() {
+        assert!(!is_information_schema_query(
+            &parse_select_statement(
+                Dialect::MySQL,
+                "SELECT * FROM information_schema.processlist"
+            )
+            .unwrap()
+        ));
+        let stmt = parse_select_statement(
+            Dialect::MySQL,
+            "SELECT count(*) FROM information_schema.tables",
+        )
+        .unwrap();
+        select(&stmt, &catalog(), None).unwrap_err();
+    }
+
+    #[test]
+    fn show_tables_and_columns() {
+        let res = show_tables(true, None, None, &catalog(), Some("db1")).unwrap();
+        assert_eq!(res.columns[0].0, "Tables_in_db1");
+        assert_eq!(
+            res.rows,
+            vec![
+                vec![DfValue::from("t1"), DfValue::from("BASE TABLE")],
+                vec![DfValue::from("t2"), DfValue::from("BASE TABLE")],
+            ]
+        );
+        let res = show_tables(
+            false,
+            Some("db2"),
+            Some(&FilterPredicate::Like("t_".into())),
+            &catalog(),
+            Some("db1"),
+        )
+        .unwrap();
+        assert_eq!(res.rows, vec![vec![DfValue::from("t3")]]);
+
+        let SqlQuery::Show(nom_sql::ShowStatement::Columns(stmt)) =
+            parse_query(Dialect::MySQL, "SHOW COLUMNS FROM t1 WHERE `Null` = 'YES'").unwrap()
+        else {
+            unreachable!()
+        };
+        let res = show_columns(&stmt, &catalog(), Some("db1")).unwrap();
+        assert_eq!(
+            res.rows,
+            vec![vec![
+                DfValue::from("x"),
+                DfValue::from("int"),
+                DfValue::from("YES"),
+                DfValue::from(""),
+                DfValue::from("3"),
+                DfValue::from(""),
+            ]]
+        );
+        show_columns(&stmt, &catalog(), Some("db2")).unwrap_err();
+    }
+
+    #[test]
+    fn string_defaults_are_unquoted() {
+        let res = query(
+            "SELECT column_default FROM information_schema.columns \
+             WHERE table_name = 't1' AND column_name = 's'",
+        );
+        assert_eq!(res.rows, vec![vec![DfValue::from("it's")]]);
+    }
+
+    #[test]
+    fn string_comparisons_ignore_case() {
+        let res = query(
+            "SELECT table_name FROM information_schema.tables \
+             WHERE table_schema = 'DB1' AND table_name LIKE 'T%' ORDER BY table_name",
+        );
+        assert_eq!(
+            res.rows,
+            vec![vec![DfValue::from("t1")], vec![DfValue::from("t2")]]
+        );
+
+        let res =
+            query("SELECT column_name FROM information_schema.columns WHERE table_name IN ('T2')");
+        assert_eq!(res.rows, vec![vec![DfValue::from("a")]]);
+
+        let res = query(
+            "SELECT column_name FROM information_schema.columns \
+             WHERE table_name = 't2' AND column_name != 'A'",
+        );
+        assert!(res.rows.is_empty());
+
+        let res = show_tables(
+            false,
+            None,
+            Some(&FilterPredicate::Like("T_".into())),
+            &catalog(),
+            Some("db1"),
+        )
+        .unwrap();
+        assert_eq!(
+            res.rows,
+            vec![vec![DfValue::from("t1")], vec![DfValue::from("t2")]]
+        );
+    }
+
+    #[test]
+    fn only_mysql_is_supported() {
+        assert!(supports_dialect(Dialect::MySQL));
+        assert!(!supports_dialect(Dialect::PostgreSQL));
+    }
+
+    #[test]
+    fn show_create_table_round_trips() {
+        let res = show_create_table(&Relation::from("t1"), &catalog(), Some("db1")).unwrap();
+        assert_eq!(res.rows[0][0], DfValue::from("t1"));
+        let create = String::try_from(res.rows[0][1].clone()).unwrap();
+        let stmt = parse_create_table(Dialect::MySQL, create).unwrap();
+        assert_eq!(stmt.table, Relation::from("t1"));
+        assert_eq!(stmt.body.unwrap(), catalog().into_iter().next().unwrap().1);
+
+        show_create_table(&Relation::from("t3"), &catalog(), Some("db1")).unwrap_err();
+    }
+}
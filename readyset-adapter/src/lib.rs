@@ -7,16 +7,28 @@
 #![feature(if_let_guard)]
 #![feature(arc_unwrap_or_clone)]
 #![feature(generic_associated_types)]
+#![feature(let_else)]
 #![deny(unreachable_pub)]
 
 pub mod backend;
+pub mod connections;
 pub mod fallback_cache;
+pub mod fallback_limiter;
+pub mod flight;
+mod hints;
 pub mod http_router;
+pub mod index_advisor;
+mod information_schema;
+pub mod micro_cache;
 pub mod migration_handler;
+pub mod namespace_limiter;
 pub mod proxied_queries_reporter;
 mod query_handler;
 pub mod query_status_cache;
+mod readyset_variables;
+pub mod replication_lag;
 pub mod rewrite;
+pub mod slow_read_log;
 pub mod upstream_database;
 mod utils;
 pub mod views_synchronizer;
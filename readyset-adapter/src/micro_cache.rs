@@ -0,0 +1,225 @@
+//! The micro cache is a small, in-process cache of the results of point reads from ReadySet, kept
+//! for a very short (sub-second) amount of time. For extremely hot, identical reads, a hit in the
+//! micro cache avoids even the round trip to the reader.
+//!
+//! Entries are invalidated:
+//!
+//! * once they are older than the configured TTL,
+//! * whenever this adapter writes to any table, either in ReadySet or by proxying the write to the
+//!   upstream database (since the adapter doesn't know which caches a write may affect, all entries
+//!   are dropped),
+//! * for reads served by readers running in the same process as the adapter, whenever any shard of
+//!   the reader the entry was read from is updated, via the reader's update notifications, and
+//! * once the cache is full, least recently used entries first.
+//!
+//! Writes that don't go through this adapter (for example, writes made directly against the
+//! upstream database by other clients), reaching a reader running on another host, are only
+//! picked up once the entry expires, so the TTL bounds how stale a result returned from the micro
+//! cache can be.
+//!
+//! Alongside the rows, each entry keeps the rows as they were last encoded on the wire, for each
+//! [`ResultEncoding`] they've been sent to clients in, so that a hit in the micro cache can be
+//! written straight to the client without re-encoding each value. Encoded rows are dropped along
+//! with the entry they belong to.
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use lru::LruCache;
+use nom_sql::Relation;
+use parking_lot::Mutex;
+use readyset_data::DfValue;
+use readyset_server::worker::readers::ReaderUpdatedNotifier;
+
+/// The encoding of a resultset on the wire, which rows are cached in by [`EncodedResults`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResultEncoding {
+    /// The MySQL text protocol, used for the results of queries
+    MySqlText {
+        /// The character set the columns of the resultset are sent in
+        charset: u16,
+    },
+    /// The MySQL binary protocol, used for the results of prepared statements
+    MySqlBinary {
+        /// The character set the columns of the resultset are sent in
+        charset: u16,
+    },
+}
+
+/// The rows of an entry in the micro cache, as they were encoded on the wire for each
+/// [`ResultEncoding`] they've been written in
+#[derive(Debug, Default)]
+pub struct EncodedResults {
+    encodings: DashMap<ResultEncoding, Arc<Vec<Vec<u8>>>>,
+}
+
+impl EncodedResults {
+    /// Returns the encoding of each of the rows in `encoding`, if they've been encoded in it before
+    pub fn get(&self, encoding: ResultEncoding) -> Option<Arc<Vec<Vec<u8>>>> {
+        self.encodings.get(&encoding).map(|rows| rows.clone())
+    }
+
+    /// Record the encoding of each of the rows in `encoding`
+    pub fn insert(&self, encoding: ResultEncoding, rows: Vec<Vec<u8>>) {
+        self.encodings.insert(encoding, Arc::new(rows));
+    }
+}
+
+/// The key for an entry in the micro cache: the name of the reader that was read from, and the
+/// point keys that were looked up in it
+type Key = (Relation, Vec<Vec<DfValue>>);
+
+struct Entry {
+    /// When this entry was inserted
+    inserted: Instant,
+    /// Notified when each shard of the reader this entry was read from is updated, if all of its
+    /// shards run in the same process
+    updates: Vec<ReaderUpdatedNotifier>,
+    rows: Arc<Vec<Vec<DfValue>>>,
+    encoded: Arc<EncodedResults>,
+}
+
+impl Entry {
+    fn is_valid(&self, ttl: Duration, now: Instant) -> bool {
+        now.duration_since(self.inserted) < ttl && self.updates.iter().all(|u| u.is_empty())
+    }
+}
+
+/// A small, short-lived cache of the results of point reads.
+///
+/// A single [`MicroCache`] is intended to be shared between all the connections to an adapter.
+pub struct MicroCache {
+    ttl: Duration,
+    entries: Mutex<LruCache<Key, Entry>>,
+}
+
+impl MicroCache {
+    /// Create a new [`MicroCache`] which keeps the results of reads for `ttl`, and holds at most
+    /// `max_entries` results at once
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(LruCache::new(max_entries.max(1))),
+        }
+    }
+
+    /// Build the key for a point read of `keys` from the reader named `view`
+    pub(crate) fn key(view: &Relation, keys: Vec<Vec<DfValue>>) -> Key {
+        (view.clone(), keys)
+    }
+
+    /// Look up the rows returned by a previous read with the given `key`, along with their
+    /// encodings, if there is still a valid entry for it
+    pub(crate) fn get(&self, key: &Key) -> Option<(Arc<Vec<Vec<DfValue>>>, Arc<EncodedResults>)> {
+        let mut entries = self.entries.lock();
+        let entry = entries.get(key)?;
+        if entry.is_valid(self.ttl, Instant::now()) {
+            return Some((entry.rows.clone(), entry.encoded.clone()));
+        }
+        entries.pop(key);
+        None
+    }
+
+    /// Record the `rows` returned by a read with the given `key`, evicting the least recently used
+    /// entry if the cache is full. `updates` must have been subscribed to before the read was
+    /// performed, so that no update to the reader is missed.
+    ///
+    /// Returns the (initially empty) encodings of the rows of the new entry.
+    pub(crate) fn insert(
+        &self,
+        key: Key,
+        rows: Arc<Vec<Vec<DfValue>>>,
+        updates: Vec<ReaderUpdatedNotifier>,
+    ) -> Arc<EncodedResults> {
+        let encoded = Arc::new(EncodedResults::default());
+        self.entries.lock().put(
+            key,
+            Entry {
+                inserted: Instant::now(),
+                updates,
+                rows,
+                encoded: encoded.clone(),
+            },
+        );
+        encoded
+    }
+
+    /// Drop all entries in the cache. Called whenever the adapter writes to ReadySet or to the
+    /// upstream database
+    pub(crate) fn invalidate_all(&self) {
+        self.entries.lock().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(k: i32) -> Key {
+        MicroCache::key(&Relation::from("q"), vec![vec![k.into()]])
+    }
+
+    fn rows(v: i32) -> Arc<Vec<Vec<DfValue>>> {
+        Arc::new(vec![vec![v.into()]])
+    }
+
+    fn rows_for(cache: &MicroCache, key: &Key) -> Option<Arc<Vec<Vec<DfValue>>>> {
+        cache.get(key).map(|(rows, _)| rows)
+    }
+
+    #[test]
+    fn expires_after_ttl() {
+        let cache = MicroCache::new(Duration::from_millis(50), 10);
+        cache.insert(key(1), rows(1), vec![]);
+        assert_eq!(rows_for(&cache, &key(1)), Some(rows(1)));
+        assert_eq!(rows_for(&cache, &key(2)), None);
+        std::thread::sleep(Duration::from_millis(60));
+        assert_eq!(rows_for(&cache, &key(1)), None);
+        assert!(cache.entries.lock().is_empty());
+    }
+
+    #[test]
+    fn invalidated_by_writes_and_updates() {
+        let cache = MicroCache::new(Duration::from_secs(10), 10);
+        cache.insert(key(1), rows(1), vec![]);
+        cache.invalidate_all();
+        assert_eq!(rows_for(&cache, &key(1)), None);
+
+        // An update to any shard of the reader invalidates the entry
+        let (_tx0, rx0) = tokio::sync::broadcast::channel(1);
+        let (tx1, rx1) = tokio::sync::broadcast::channel(1);
+        cache.insert(key(1), rows(1), vec![rx0, rx1]);
+        assert_eq!(rows_for(&cache, &key(1)), Some(rows(1)));
+        tx1.send(0).unwrap();
+        assert_eq!(rows_for(&cache, &key(1)), None);
+    }
+
+    #[test]
+    fn evicts_least_recently_used() {
+        let cache = MicroCache::new(Duration::from_secs(10), 2);
+        cache.insert(key(1), rows(1), vec![]);
+        cache.insert(key(2), rows(2), vec![]);
+        assert_eq!(rows_for(&cache, &key(1)), Some(rows(1)));
+        cache.insert(key(3), rows(3), vec![]);
+        assert_eq!(rows_for(&cache, &key(2)), None);
+        assert_eq!(rows_for(&cache, &key(1)), Some(rows(1)));
+        assert_eq!(rows_for(&cache, &key(3)), Some(rows(3)));
+    }
+
+    #[test]
+    fn encoded_results() {
+        let cache = MicroCache::new(Duration::from_secs(10), 10);
+        let text = ResultEncoding::MySqlText { charset: 33 };
+        let binary = ResultEncoding::MySqlBinary { charset: 33 };
+
+        let encoded = cache.insert(key(1), rows(1), vec![]);
+        encoded.insert(text, vec![vec![1, b'1']]);
+        let (_, encoded) = cache.get(&key(1)).unwrap();
+        assert_eq!(encoded.get(text), Some(Arc::new(vec![vec![1, b'1']])));
+        assert_eq!(encoded.get(binary), None);
+
+        // Replacing the entry drops its encodings
+        let encoded = cache.insert(key(1), rows(2), vec![]);
+        assert_eq!(encoded.get(text), None);
+    }
+}
@@ -5,13 +5,13 @@
 //!
 //! The migration handler may change a queries state based on the
 //! response from ReadySet.
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::time::Instant;
 
 use dataflow_expression::Dialect;
 use metrics::{counter, register_counter};
-use readyset_client::query::{MigrationState, Query};
-use readyset_client::recipe::changelist::{Change, ChangeList};
+use readyset_client::query::{MigrationState, Query, QueryId, QueryStatus};
+use readyset_client::recipe::changelist::{CacheOptions, Change, ChangeList};
 use readyset_client::{ReadySetHandle, ReadySetResult, ViewCreateRequest};
 use readyset_client_metrics::recorded;
 use readyset_tracing::{error, info, warn};
@@ -62,6 +62,10 @@ pub struct MigrationHandler<DB> {
     /// Queries are removed when a migration yields success or unsupported
     /// and re-added when they are found in the pending migration list.
     start_time: HashMap<ViewCreateRequest, Instant>,
+
+    /// The ids of all the queries we have registered with the controller, so that we only register
+    /// each query once.
+    registered: HashSet<QueryId>,
 }
 
 impl<DB> MigrationHandler<DB>
@@ -91,6 +95,7 @@ where
             max_retry,
             shutdown_recv,
             start_time: HashMap::new(),
+            registered: HashSet::new(),
         }
     }
 
@@ -103,7 +108,12 @@ where
         loop {
             select! {
                 _ = interval.tick() => {
-                    let to_process = self.query_status_cache.pending_migration();
+                    let to_process = self
+                        .query_status_cache
+                        .pending_migration()
+                        .into_iter()
+                        .collect::<Vec<_>>();
+                    self.register_queries(&to_process).await;
                     let has_controller = self.controller.is_some();
                     let mut successes = 0;
                     let mut failures = 0;
@@ -136,6 +146,30 @@ where
         Ok(())
     }
 
+    /// Register any of the given queries that we haven't registered yet with the controller, so
+    /// that their ids can be resolved by other adapters and across restarts.
+    async fn register_queries(&mut self, queries: &[(Query, QueryStatus)]) {
+        let new_queries = queries
+            .iter()
+            .filter_map(|(q, _)| match q {
+                Query::Parsed(req) if !self.registered.contains(&QueryId::from(&**req)) => {
+                    Some((**req).clone())
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        if new_queries.is_empty() {
+            return;
+        }
+
+        let ids = new_queries.iter().map(QueryId::from).collect::<Vec<_>>();
+        match self.noria.register_queries(new_queries).await {
+            Ok(()) => self.registered.extend(ids),
+            // Not fatal: we'll try again on the next poll
+            Err(error) => warn!(%error, "Failed to register queries with the controller"),
+        }
+    }
+
     async fn perform_migration(&mut self, view_request: &ViewCreateRequest) {
         // If this is the first migration we are performing, add the query to the
         // start_time map.
@@ -289,7 +323,11 @@ where
         let qname =
             utils::generate_query_name(&view_request.statement, &view_request.schema_search_path);
         let changelist = ChangeList::from_change(
-            Change::create_cache(qname, view_request.statement.clone(), false),
+            Change::create_cache(
+                qname,
+                view_request.statement.clone(),
+                CacheOptions::default(),
+            ),
             self.dialect,
         )
         .with_schema_search_path(view_request.schema_search_path.clone());
@@ -0,0 +1,102 @@
+//! Enforcement of the per-namespace limit on reads from caches.
+//!
+//! A [`NamespaceLimiter`], shared between all the connections of an adapter, limits the number of
+//! reads per second from the caches in each namespace (see [`readyset_client::namespace`]) to that
+//! namespace's quota. Reads over the limit fail with [`ReadySetError::NamespaceQuotaExceeded`],
+//! which causes them to be proxied to the upstream database (subject to the
+//! [`FallbackLimiter`](crate::fallback_limiter::FallbackLimiter)) rather than served from ReadySet,
+//! so that reads from one namespace's caches can't starve the others.
+use std::time::Instant;
+
+use dashmap::DashMap;
+use nom_sql::{SelectStatement, SqlIdentifier};
+use readyset_client::namespace::{namespace_of, NamespaceLimit, NamespaceQuotas};
+use readyset_errors::ReadySetResult;
+
+use crate::fallback_limiter::RateLimit;
+
+/// Limits the rate of reads from the caches in each namespace.
+///
+/// A single [`NamespaceLimiter`] is intended to be shared between all the connections to an
+/// adapter.
+#[derive(Debug)]
+pub struct NamespaceLimiter {
+    quotas: NamespaceQuotas,
+    rate_limits: DashMap<SqlIdentifier, RateLimit>,
+}
+
+impl NamespaceLimiter {
+    /// Create a new [`NamespaceLimiter`] enforcing the read limits in the given quotas
+    pub fn new(quotas: NamespaceQuotas) -> Self {
+        Self {
+            quotas,
+            rate_limits: DashMap::new(),
+        }
+    }
+
+    /// Returns `true` if no namespace has a limit on its reads, in which case there's no need to
+    /// check reads against this limiter
+    pub fn is_unlimited(&self) -> bool {
+        self.quotas.default.max_reads_per_second.is_none()
+            && self
+                .quotas
+                .overrides
+                .values()
+                .all(|quota| quota.max_reads_per_second.is_none())
+    }
+
+    /// Record a read of `statement` from a cache, returning an error if the namespace of the
+    /// cache has exceeded its limit on reads
+    pub(crate) fn check_read(
+        &self,
+        statement: &SelectStatement,
+        search_path: &[SqlIdentifier],
+    ) -> ReadySetResult<()> {
+        let Some(namespace) = namespace_of(statement, search_path) else {
+            return Ok(());
+        };
+        let Some(per_second) = self.quotas.quota(&namespace).max_reads_per_second else {
+            return Ok(());
+        };
+
+        let allowed = self
+            .rate_limits
+            .entry(namespace.clone())
+            .or_insert_with(|| RateLimit::new(per_second))
+            .try_take(Instant::now());
+        if allowed {
+            Ok(())
+        } else {
+            Err(NamespaceLimit::ReadsPerSecond(per_second).exceeded(&namespace))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::{parse_select_statement, Dialect};
+    use readyset_client::namespace::NamespaceQuotaOverride;
+
+    use super::*;
+
+    #[test]
+    fn limits_reads_per_namespace() {
+        let limiter = NamespaceLimiter::new(
+            ["limited:qps=2".parse::<NamespaceQuotaOverride>().unwrap()]
+                .into_iter()
+                .collect(),
+        );
+        assert!(!limiter.is_unlimited());
+
+        let limited = parse_select_statement(Dialect::MySQL, "SELECT * FROM t").unwrap();
+        let unlimited = parse_select_statement(Dialect::MySQL, "SELECT * FROM other.t").unwrap();
+        let search_path = ["limited".into()];
+
+        assert!(limiter.check_read(&limited, &search_path).is_ok());
+        assert!(limiter.check_read(&limited, &search_path).is_ok());
+        assert!(limiter.check_read(&limited, &search_path).is_err());
+        for _ in 0..10 {
+            assert!(limiter.check_read(&unlimited, &search_path).is_ok());
+        }
+    }
+}
@@ -14,6 +14,14 @@ pub enum SetBehavior {
     SetAutocommit(bool),
     /// This `SET` statement represents the current schema search path being changed
     SetSearchPath(Vec<SqlIdentifier>),
+    /// This `SET` statement sets the minimum consistency token (as returned by
+    /// `LAST_WRITE_TOKEN()`) that the results of subsequent reads must reflect
+    SetMinToken(String),
+    /// This `SET` statement enables or disables snapshot reads for the current session
+    SetSnapshotReads(bool),
+    /// This `SET` statement sets the [`ReadBehavior`](noria_connector::ReadBehavior) for reads in
+    /// the current session
+    SetReadBehavior(noria_connector::ReadBehavior),
 }
 
 impl SetBehavior {
@@ -10,7 +10,6 @@ use anyhow::anyhow;
 use dashmap::DashMap;
 use readyset_client::query::*;
 use readyset_tracing::error;
-use readyset_util::hash::hash;
 
 /// A metadata cache for all queries that have been processed by this
 /// adapter. Thread-safe.
@@ -21,9 +20,8 @@ pub struct QueryStatusCache {
     /// from this hash map.
     statuses: DashMap<Query, QueryStatus>,
 
-    /// A thread-safe hash map that maps a query's id to the query. The id is a string formatted as
-    /// q_<16-digit-query-hash>. The id is stored as a string instead of a u64 to allow for
-    /// different id formats in the future.
+    /// A thread-safe hash map that maps a query's id to the query. See [`QueryId`] for how ids are
+    /// computed.
     ids: DashMap<QueryId, Query>,
 
     /// Holds the current style of migration, whether async or explicit, which may change the
@@ -83,7 +81,7 @@ impl QueryStatusCache {
                 status
             }
         };
-        let id = QueryId::new(hash(&q));
+        let id = QueryId::from(&q);
         self.ids.insert(id, q.clone());
         self.statuses.insert(q, status);
         id
@@ -105,11 +103,13 @@ impl QueryStatusCache {
         Q: Into<Query> + Hash + Eq + Clone,
         Query: Borrow<Q>,
     {
-        let query_state = self.statuses.get(q).map(|m| m.migration_state);
-        let id = QueryId::new(hash(&q));
+        let query_state = self
+            .statuses
+            .get(q)
+            .map(|m| (QueryId::from(m.key()), m.migration_state));
 
         match query_state {
-            Some(s) => {
+            Some((id, s)) => {
                 debug_assert!(
                     *self.ids.get(&id).expect("query not found") == q.clone().into(),
                     "mismatch between calculated and cached id/query"
@@ -290,11 +290,11 @@ impl QueryStatusCache {
         }
     }
 
-    /// Clear all queries currently marked as successful from the cache.
+    /// Clear all queries currently marked as successful (or warming) from the cache.
     pub fn clear(&self) {
         self.statuses
             .iter_mut()
-            .filter(|v| v.is_successful())
+            .filter(|v| v.is_successful() || v.is_warming())
             .for_each(|mut v| {
                 v.migration_state = MigrationState::Pending;
                 v.always = false;
@@ -366,9 +366,9 @@ impl QueryStatusCache {
         }
     }
 
-    /// Returns a query given a query hash
+    /// Returns a query given a query id
     pub fn query(&self, id: &str) -> Option<Query> {
-        let id = QueryId::new(u64::from_str_radix(id.strip_prefix("q_")?, 16).ok()?);
+        let id = id.parse::<QueryId>().ok()?;
         self.ids.get(&id).map(|r| (*r.value()).clone())
     }
 }
@@ -403,6 +403,7 @@ impl FromStr for MigrationStyle {
 mod tests {
     use nom_sql::{SelectStatement, SqlQuery};
     use readyset_client::ViewCreateRequest;
+    use readyset_util::hash::hash;
 
     use super::*;
 
@@ -470,8 +471,8 @@ mod tests {
         cache.query_migration_state(&q1);
         cache.update_query_migration_state(&q2, MigrationState::Successful);
 
-        let h1 = QueryId::new(hash(&q1));
-        let h2 = QueryId::new(hash(&q2));
+        let h1 = QueryId::from(&q1);
+        let h2 = QueryId::from(&q2);
 
         let r1 = cache.query(&h1.to_string()).unwrap();
         let r2 = cache.query(&h2.to_string()).unwrap();
@@ -485,10 +486,7 @@ mod tests {
         let cache = QueryStatusCache::new();
         let query = ViewCreateRequest::new(select_statement("SELECT * FROM t1").unwrap(), vec![]);
 
-        assert_eq!(
-            cache.query_migration_state(&query).0,
-            QueryId::new(hash(&Into::<Query>::into(query.clone())))
-        );
+        assert_eq!(cache.query_migration_state(&query).0, QueryId::from(&query));
         assert_eq!(
             cache.query_migration_state(&query).1,
             MigrationState::Pending
@@ -0,0 +1,267 @@
+//! Support for changing the settings of a running deployment with SQL.
+//!
+//! Every setting that can be changed without restarting ReadySet is exposed as a global variable
+//! named `readyset.<setting>`, where `<setting>` is the name of the command-line option used to set
+//! it at startup with dashes replaced by underscores. Settings are changed with
+//! `SET GLOBAL readyset.<setting> = <value>`, and their current values can be listed with
+//! `SHOW VARIABLES LIKE 'readyset.%'`.
+//!
+//! Settings of the adapter's [`FallbackLimiter`] apply to the adapter the statement was run
+//! against. All other settings are forwarded to the controller, and so apply to the whole
+//! deployment (see
+//! [`ReadySetHandle::reload_config`](readyset_client::ReadySetHandle::reload_config)).
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use dataflow_expression::like::{CaseSensitivityMode, LikePattern};
+use nom_sql::{Expr, Literal, SetVariables, Variable, VariableScope};
+use readyset_errors::{ReadySetError, ReadySetResult};
+
+use crate::fallback_limiter::FallbackLimits;
+
+/// The prefix of the names of all ReadySet variables
+const PREFIX: &str = "readyset.";
+
+/// See `--max-fallback-queries-per-second`. 0 removes the limit.
+const MAX_FALLBACK_QUERIES_PER_SECOND: &str = "max-fallback-queries-per-second";
+/// See `--fallback-circuit-breaker-failures`. 0 disables the circuit breaker.
+const FALLBACK_CIRCUIT_BREAKER_FAILURES: &str = "fallback-circuit-breaker-failures";
+/// See `--fallback-circuit-breaker-cooldown-seconds`
+const FALLBACK_CIRCUIT_BREAKER_COOLDOWN_SECONDS: &str = "fallback-circuit-breaker-cooldown-seconds";
+/// See `--max-concurrent-fallback-queries`. Can't be changed without a restart.
+const MAX_CONCURRENT_FALLBACK_QUERIES: &str = "max-concurrent-fallback-queries";
+
+/// Returns `true` if the given variable is a ReadySet setting, rather than an upstream database
+/// variable
+fn is_readyset_variable(variable: &Variable) -> bool {
+    variable.scope == VariableScope::Global && variable.name.starts_with(PREFIX)
+}
+
+/// Returns `true` if the given `SET` statement sets any ReadySet settings, and so should be
+/// handled by [`ReadySetSettings::parse`] rather than proxied upstream
+pub(crate) fn is_readyset_set(set: &SetVariables) -> bool {
+    set.variables
+        .iter()
+        .any(|(variable, _)| is_readyset_variable(variable))
+}
+
+/// Returns `true` if a `SHOW VARIABLES LIKE` statement with the given pattern can only match
+/// ReadySet settings, and so should be answered by ReadySet rather than proxied upstream
+pub(crate) fn is_readyset_pattern(pattern: &str) -> bool {
+    pattern.to_ascii_lowercase().starts_with(PREFIX)
+}
+
+/// Convert the value assigned to a ReadySet variable into the string format the setting is parsed
+/// from on the command line
+fn setting_value(name: &str, value: &Expr) -> ReadySetResult<String> {
+    match value {
+        Expr::Literal(Literal::String(s)) => Ok(s.clone()),
+        Expr::Literal(Literal::Integer(i)) => Ok(i.to_string()),
+        Expr::Literal(Literal::UnsignedInteger(i)) => Ok(i.to_string()),
+        Expr::Literal(Literal::Boolean(b)) => Ok(b.to_string()),
+        // Allow unquoted values, such as `SET GLOBAL readyset.eviction_policy = lru`
+        Expr::Column(column) if column.table.is_none() => Ok(column.name.to_string()),
+        _ => Err(ReadySetError::BadRequest(format!(
+            "Unsupported value {value} for {PREFIX}{name}: expected a string or a number"
+        ))),
+    }
+}
+
+/// The changes to ReadySet settings requested by a `SET GLOBAL readyset.<setting> = <value>`
+/// statement
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct ReadySetSettings {
+    /// The new limits for the adapter's fallback limiter, if any of them are being set
+    pub(crate) fallback_limits: Option<FallbackLimits>,
+    /// Settings to forward to the controller, keyed by the name of their command-line option
+    pub(crate) controller_settings: BTreeMap<String, String>,
+}
+
+impl ReadySetSettings {
+    /// Validate the settings of the adapter's fallback limiter being changed by the given `SET`
+    /// statement, starting from the `current` limits, and collect the rest of the settings to be
+    /// validated by the controller.
+    pub(crate) fn parse(set: &SetVariables, current: FallbackLimits) -> ReadySetResult<Self> {
+        let invalid = |name: &str, value: &str, reason: &dyn std::fmt::Display| {
+            ReadySetError::BadRequest(format!(
+                "Invalid value {value:?} for {PREFIX}{name}: {reason}"
+            ))
+        };
+
+        let mut res = Self::default();
+        let mut limits = None;
+        for (variable, value) in &set.variables {
+            if !is_readyset_variable(variable) {
+                return Err(ReadySetError::BadRequest(format!(
+                    "Cannot set {variable} in the same statement as ReadySet settings"
+                )));
+            }
+            let name = variable.name[PREFIX.len()..].replace('_', "-");
+            let value = setting_value(&name, value)?;
+            match name.as_str() {
+                MAX_FALLBACK_QUERIES_PER_SECOND => {
+                    let n = value
+                        .parse::<u32>()
+                        .map_err(|e| invalid(&name, &value, &e))?;
+                    limits.get_or_insert(current).max_per_second = (n != 0).then_some(n);
+                }
+                FALLBACK_CIRCUIT_BREAKER_FAILURES => {
+                    let n = value
+                        .parse::<u32>()
+                        .map_err(|e| invalid(&name, &value, &e))?;
+                    limits.get_or_insert(current).circuit_breaker_failures = (n != 0).then_some(n);
+                }
+                FALLBACK_CIRCUIT_BREAKER_COOLDOWN_SECONDS => {
+                    let secs = value
+                        .parse::<u64>()
+                        .map_err(|e| invalid(&name, &value, &e))?;
+                    limits.get_or_insert(current).circuit_breaker_cooldown =
+                        Duration::from_secs(secs);
+                }
+                MAX_CONCURRENT_FALLBACK_QUERIES => {
+                    return Err(ReadySetError::BadRequest(format!(
+                        "{PREFIX}{name} can only be changed by restarting ReadySet"
+                    )));
+                }
+                _ => {
+                    res.controller_settings.insert(name, value);
+                }
+            }
+        }
+
+        res.fallback_limits = limits;
+        Ok(res)
+    }
+}
+
+/// List the current values of the settings of the adapter's fallback limiter (if it has one), and
+/// of the deployment-wide `controller_settings`, as `(variable name, value)` pairs, sorted by name
+/// and filtered to those whose variable name matches the given `LIKE` pattern
+pub(crate) fn show_settings(
+    fallback_limits: Option<FallbackLimits>,
+    controller_settings: BTreeMap<String, String>,
+    pattern: &str,
+) -> Vec<(String, String)> {
+    let mut settings = controller_settings;
+    settings.extend(fallback_limits.into_iter().flat_map(|fallback_limits| {
+        [
+            (
+                MAX_FALLBACK_QUERIES_PER_SECOND.to_owned(),
+                fallback_limits.max_per_second.unwrap_or(0).to_string(),
+            ),
+            (
+                FALLBACK_CIRCUIT_BREAKER_FAILURES.to_owned(),
+                fallback_limits
+                    .circuit_breaker_failures
+                    .unwrap_or(0)
+                    .to_string(),
+            ),
+            (
+                FALLBACK_CIRCUIT_BREAKER_COOLDOWN_SECONDS.to_owned(),
+                fallback_limits
+                    .circuit_breaker_cooldown
+                    .as_secs()
+                    .to_string(),
+            ),
+            (
+                MAX_CONCURRENT_FALLBACK_QUERIES.to_owned(),
+                fallback_limits.max_concurrent.unwrap_or(0).to_string(),
+            ),
+        ]
+    }));
+
+    // Variable names are case-insensitive in MySQL
+    let pattern = LikePattern::new(pattern, CaseSensitivityMode::CaseInsensitive);
+    settings
+        .into_iter()
+        .map(|(name, value)| (format!("{PREFIX}{}", name.replace('-', "_")), value))
+        .filter(|(name, _)| pattern.matches(name))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::{parse_query, Dialect, SetStatement, SqlQuery};
+
+    use super::*;
+
+    fn parse_set(query: &str) -> SetVariables {
+        match parse_query(Dialect::MySQL, query).unwrap() {
+            SqlQuery::Set(SetStatement::Variable(set)) => set,
+            q => panic!("Unexpected query: {q}"),
+        }
+    }
+
+    #[test]
+    fn parse_adapter_and_controller_settings() {
+        let set = parse_set(
+            "SET GLOBAL readyset.max_fallback_queries_per_second = 100, \
+             @@GLOBAL.readyset.eviction_policy = lru, GLOBAL readyset.memory = '1024'",
+        );
+        assert!(is_readyset_set(&set));
+
+        let settings = ReadySetSettings::parse(&set, FallbackLimits::default()).unwrap();
+        assert_eq!(
+            settings.fallback_limits,
+            Some(FallbackLimits {
+                max_per_second: Some(100),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            settings.controller_settings.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("eviction-policy".to_owned(), "lru".to_owned()),
+                ("memory".to_owned(), "1024".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        let limits = FallbackLimits::default();
+        assert!(!is_readyset_set(&parse_set(
+            "SET GLOBAL max_connections = 1"
+        )));
+        ReadySetSettings::parse(
+            &parse_set("SET GLOBAL readyset.fallback_circuit_breaker_failures = 'x'"),
+            limits,
+        )
+        .unwrap_err();
+        ReadySetSettings::parse(
+            &parse_set("SET GLOBAL readyset.max_concurrent_fallback_queries = 1"),
+            limits,
+        )
+        .unwrap_err();
+        ReadySetSettings::parse(
+            &parse_set("SET GLOBAL readyset.memory = 1, GLOBAL max_connections = 1"),
+            limits,
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn show_filters_by_pattern() {
+        let settings = show_settings(
+            Some(FallbackLimits {
+                circuit_breaker_failures: Some(3),
+                ..Default::default()
+            }),
+            [("eviction-policy".to_owned(), "random".to_owned())].into(),
+            "readyset.%CIRCUIT%",
+        );
+        assert_eq!(
+            settings,
+            vec![
+                (
+                    "readyset.fallback_circuit_breaker_cooldown_seconds".to_owned(),
+                    "0".to_owned()
+                ),
+                (
+                    "readyset.fallback_circuit_breaker_failures".to_owned(),
+                    "3".to_owned()
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//! Tracking how far replication is behind the upstream database, so that reads from caches created
+//! with a maximum replication lag (`CREATE CACHE MAX REPLICATION LAG <seconds> ...`) can be refused
+//! rather than returning very stale results while replication has fallen behind (for example
+//! during a burst of writes upstream, or while the dataflow graph is busy with replays).
+//!
+//! The replication lag is measured by the replicator against the commit timestamps the upstream
+//! database records in its binlog (or WAL), and periodically polled from the controller by
+//! [`ReplicationLagMonitor::run`], so the lag reads are checked against may be up to one polling
+//! interval old.
+//!
+//! A read refused because the lag exceeds the cache's maximum either fails with
+//! [`ReadySetError::ReplicationLagExceeded`], or (by default) is proxied to the upstream database,
+//! according to the cache's [`ReplicationLagPolicy`]. While the lag isn't known - because
+//! replication is still snapshotting or has failed, or the controller can't be reached - it can't
+//! be shown to be within the maximum, so reads from caches with a maximum replication lag are
+//! refused with [`ReadySetError::ReplicationLagUnknown`] and always proxied to the upstream
+//! database.
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use metrics::increment_counter;
+use nom_sql::{CacheFreshness, Relation, ReplicationLagPolicy};
+use readyset_client::metrics::recorded;
+use readyset_client::ReadySetHandle;
+use readyset_errors::{ReadySetError, ReadySetResult};
+use readyset_tracing::{info, warn};
+use tokio::select;
+
+/// The value of [`ReplicationLagMonitor::lag_ms`] while the replication lag isn't known
+const UNKNOWN_LAG: u64 = u64::MAX;
+
+/// The most recently observed replication lag.
+///
+/// A single [`ReplicationLagMonitor`] is intended to be shared between all the connections to an
+/// adapter.
+#[derive(Debug)]
+pub struct ReplicationLagMonitor {
+    lag_ms: AtomicU64,
+}
+
+impl Default for ReplicationLagMonitor {
+    fn default() -> Self {
+        Self {
+            lag_ms: AtomicU64::new(UNKNOWN_LAG),
+        }
+    }
+}
+
+impl ReplicationLagMonitor {
+    /// Returns the most recently observed replication lag, or `None` if it isn't known
+    pub fn lag(&self) -> Option<Duration> {
+        match self.lag_ms.load(Ordering::Relaxed) {
+            UNKNOWN_LAG => None,
+            lag_ms => Some(Duration::from_millis(lag_ms)),
+        }
+    }
+
+    fn set_lag(&self, lag: Option<Duration>) {
+        let lag_ms = lag.map_or(UNKNOWN_LAG, |lag| {
+            u64::try_from(lag.as_millis()).unwrap_or(UNKNOWN_LAG - 1)
+        });
+        self.lag_ms.store(lag_ms, Ordering::Relaxed);
+    }
+
+    /// Check whether a read from the cache named `cache` may be served from the cache, given its
+    /// `freshness`. Returns [`ReadySetError::ReplicationLagExceeded`] if the replication lag
+    /// exceeds the cache's maximum replication lag, or [`ReadySetError::ReplicationLagUnknown`] if
+    /// the cache has a maximum replication lag but the replication lag isn't known.
+    pub(crate) fn check(&self, cache: &Relation, freshness: CacheFreshness) -> ReadySetResult<()> {
+        let Some(max_lag_seconds) = freshness.max_replication_lag else {
+            return Ok(());
+        };
+        let Some(lag) = self.lag() else {
+            increment_counter!(recorded::SERVER_VIEW_QUERY_REPLICATION_LAG_UNKNOWN);
+            return Err(ReadySetError::ReplicationLagUnknown {
+                cache: cache.to_string(),
+            });
+        };
+        if lag <= Duration::from_secs(max_lag_seconds) {
+            return Ok(());
+        }
+
+        increment_counter!(recorded::SERVER_VIEW_QUERY_REPLICATION_LAG_EXCEEDED);
+        Err(ReadySetError::ReplicationLagExceeded {
+            cache: cache.to_string(),
+            lag_ms: lag.as_millis() as u64,
+            max_lag_seconds,
+            proxy: freshness.on_exceeded == ReplicationLagPolicy::Proxy,
+        })
+    }
+
+    /// Poll the replication lag from the controller every `poll_interval`, until a shutdown signal
+    /// is received on `shutdown_recv`
+    pub async fn run(
+        &self,
+        mut controller: ReadySetHandle,
+        poll_interval: Duration,
+        mut shutdown_recv: tokio::sync::broadcast::Receiver<()>,
+    ) {
+        let mut interval = tokio::time::interval(poll_interval);
+        loop {
+            select! {
+                _ = interval.tick() => {
+                    match controller.replication_lag().await {
+                        Ok(lag) => self.set_lag(lag),
+                        Err(error) => {
+                            warn!(%error, "Failed to poll replication lag");
+                            self.set_lag(None);
+                        }
+                    }
+                }
+                _ = shutdown_recv.recv() => {
+                    info!("Replication lag monitor shutting down after shut down signal received");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn freshness(max_replication_lag: u64, on_exceeded: ReplicationLagPolicy) -> CacheFreshness {
+        CacheFreshness {
+            max_replication_lag: Some(max_replication_lag),
+            on_exceeded,
+        }
+    }
+
+    #[test]
+    fn reads_are_proxied_while_lag_is_unknown() {
+        let monitor = ReplicationLagMonitor::default();
+        assert_eq!(monitor.lag(), None);
+        monitor
+            .check(&"q".into(), CacheFreshness::default())
+            .unwrap();
+        assert!(matches!(
+            monitor.check(&"q".into(), freshness(0, ReplicationLagPolicy::Error)),
+            Err(ReadySetError::ReplicationLagUnknown { .. })
+        ));
+    }
+
+    #[test]
+    fn reads_are_refused_while_lag_exceeds_max() {
+        let monitor = ReplicationLagMonitor::default();
+        monitor.set_lag(Some(Duration::from_secs(5)));
+        assert_eq!(monitor.lag(), Some(Duration::from_secs(5)));
+
+        monitor
+            .check(&"q".into(), freshness(5, ReplicationLagPolicy::Error))
+            .unwrap();
+        monitor
+            .check(&"q".into(), CacheFreshness::default())
+            .unwrap();
+
+        assert!(matches!(
+            monitor.check(&"q".into(), freshness(4, ReplicationLagPolicy::Error)),
+            Err(ReadySetError::ReplicationLagExceeded {
+                lag_ms: 5000,
+                max_lag_seconds: 4,
+                proxy: false,
+                ..
+            })
+        ));
+        assert!(matches!(
+            monitor.check(&"q".into(), freshness(4, ReplicationLagPolicy::Proxy)),
+            Err(ReadySetError::ReplicationLagExceeded { proxy: true, .. })
+        ));
+
+        monitor.set_lag(None);
+        assert!(matches!(
+            monitor.check(&"q".into(), freshness(4, ReplicationLagPolicy::Error)),
+            Err(ReadySetError::ReplicationLagUnknown { .. })
+        ));
+    }
+}
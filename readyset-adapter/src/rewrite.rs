@@ -1,7 +1,9 @@
 use std::borrow::Cow;
 use std::cmp::max;
+use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
 use std::fmt::Debug;
+use std::hash::Hash;
 use std::{iter, mem};
 
 use itertools::{Either, Itertools};
@@ -11,6 +13,7 @@ use nom_sql::{
 };
 use readyset_data::{DfType, DfValue};
 use readyset_errors::{invalid_err, unsupported, ReadySetError, ReadySetResult};
+use readyset_sql_passes::{is_post_lookup_predicate, RewriteBetween};
 use readyset_tracing::trace;
 
 /// Struct storing information about parameters processed from a raw user supplied query, which
@@ -18,14 +21,17 @@ use readyset_tracing::trace;
 /// to ReadySet.
 ///
 /// Construct a [`ProcessedQueryParams`] by calling [`process_query`], then pass the list of
-/// user-provided parameters to [`ProcessedQueryParams::make_keys`] to make a list of lookup keys to
-/// pass to noria.
+/// user-provided parameters to [`ProcessedQueryParams::make_key_batch`] to make a [`KeyBatch`] of
+/// lookup keys to pass to noria.
 #[derive(Debug, Clone)]
 pub struct ProcessedQueryParams {
     reordered_placeholders: Option<Vec<usize>>,
     rewritten_in_conditions: Vec<RewrittenIn>,
     auto_parameters: Vec<(usize, Literal)>,
     pagination_parameters: AdapterPaginationParams,
+    /// Conditions in the query with placeholders that can't be turned into lookup keys, which are
+    /// evaluated against the results of each lookup instead
+    post_lookup_predicates: Vec<Expr>,
 }
 
 #[derive(Debug, Clone)]
@@ -68,8 +74,11 @@ pub fn process_query(
 
     let limit_clause = mem::take(&mut query.limit_clause);
 
+    // Post-lookup predicates are evaluated in the reader, so any LIMIT or OFFSET has to be applied
+    // there as well, after those predicates have filtered the results
     let force_paginate_in_adapter =
-        use_fallback_pagination(server_supports_pagination, &query.limit_clause);
+        use_fallback_pagination(server_supports_pagination, &limit_clause)
+            || !post_lookup_predicates(query).is_empty();
 
     if !force_paginate_in_adapter {
         // If adapter pagination shouldn't be used reinstate the limit clause
@@ -79,6 +88,7 @@ pub fn process_query(
     let auto_parameters = auto_parametrize_query(query);
     let rewritten_in_conditions = collapse_where_in(query)?;
     number_placeholders(query)?;
+    let post_lookup_predicates = post_lookup_predicates(query);
     Ok(ProcessedQueryParams {
         reordered_placeholders,
         rewritten_in_conditions,
@@ -87,10 +97,20 @@ pub fn process_query(
             limit_clause,
             force_paginate_in_adapter,
         },
+        post_lookup_predicates,
     })
 }
 
 impl ProcessedQueryParams {
+    /// Returns the conditions in the query with placeholders that can't be turned into lookup keys,
+    /// which should be passed to [`View::build_view_query`] to be evaluated against the results of
+    /// each lookup
+    ///
+    /// [`View::build_view_query`]: readyset_client::View::build_view_query
+    pub(crate) fn post_lookup_predicates(&self) -> &[Expr] {
+        &self.post_lookup_predicates
+    }
+
     /// If the query has values for OFFSET or LIMIT, get their values, returning a tuple of `limit,
     /// offset`
     pub(crate) fn limit_offset_params(
@@ -161,12 +181,30 @@ impl ProcessedQueryParams {
         }
     }
 
+    /// Make the list of lookup keys to pass to noria for the given list of user-provided
+    /// parameters. See [`ProcessedQueryParams::make_key_batch`].
     pub(crate) fn make_keys<'param, T>(
         &self,
         params: &'param [T],
     ) -> ReadySetResult<Vec<Cow<'param, [T]>>>
     where
-        T: Clone + TryFrom<Literal, Error = ReadySetError> + Debug + Default + PartialEq,
+        T: Clone + TryFrom<Literal, Error = ReadySetError> + Debug + Default + Eq + Hash,
+    {
+        Ok(self.make_key_batch(params)?.into_keys())
+    }
+
+    /// Make the [`KeyBatch`] of lookup keys to pass to noria for the given list of user-provided
+    /// parameters.
+    ///
+    /// If the query had any parameterized IN conditions rewritten to equality conditions, this
+    /// makes one key for each combination of values in the IN lists, deduplicated so that the
+    /// results of the lookups are the same as the results of the original query.
+    pub fn make_key_batch<'param, T>(
+        &self,
+        params: &'param [T],
+    ) -> ReadySetResult<KeyBatch<'param, T>>
+    where
+        T: Clone + TryFrom<Literal, Error = ReadySetError> + Debug + Default + Eq + Hash,
     {
         let params = if let Some(order_map) = &self.reordered_placeholders {
             Cow::Owned(reorder_params(params, order_map)?)
@@ -195,7 +233,7 @@ impl ProcessedQueryParams {
         }
 
         if params.is_empty() && self.auto_parameters.is_empty() {
-            return Ok(vec![]);
+            return Ok(KeyBatch::new(vec![]));
         }
 
         let auto_parameters = self
@@ -208,14 +246,117 @@ impl ProcessedQueryParams {
         let params = splice_auto_parameters(params, &auto_parameters);
 
         if self.rewritten_in_conditions.is_empty() {
-            return Ok(vec![Cow::Owned(params.into_owned())]);
+            return Ok(KeyBatch::new(vec![Cow::Owned(params.into_owned())]));
         }
 
-        Ok(
+        Ok(KeyBatch::new(
             explode_params(params.as_ref(), &self.rewritten_in_conditions)
                 .map(|k| Cow::Owned(k.into_owned()))
                 .collect(),
-        )
+        ))
+    }
+}
+
+/// The lookup keys for a single execution of a query, made from the parameters supplied by the user
+/// by [`ProcessedQueryParams::make_key_batch`].
+///
+/// Rewriting a parameterized IN condition into one lookup per value in the list has to be
+/// transparent to the user, so the keys in a batch are shaped to match how the upstream database
+/// evaluates the original condition:
+///
+/// - A value which appears more than once in an IN list (or a combination of values which appears
+///   more than once across multiple IN lists) is only looked up once, since upstream returns each
+///   row matching the condition once no matter how many values in the list it matches
+/// - Keys are kept in the order in which their values first appear in the parameters, so that
+///   (absent an `ORDER BY`) the results of the lookups are grouped by key in the same order as the
+///   values in the IN list, and don't depend on which values were duplicated
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBatch<'param, T: Clone> {
+    keys: Vec<Cow<'param, [T]>>,
+    /// The number of keys made from the parameters, including duplicates
+    requested: usize,
+}
+
+/// Which of the keys in a [`KeyBatch`] matched any rows in the results of looking them up, as
+/// returned by [`KeyBatch::lookup_report`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyLookupReport<'a, T> {
+    /// The keys which matched at least one row, in the order of the batch
+    pub found: Vec<&'a [T]>,
+    /// The keys which didn't match any rows, in the order of the batch
+    pub missing: Vec<&'a [T]>,
+}
+
+impl<'param, T> KeyBatch<'param, T>
+where
+    T: Clone + Eq + Hash,
+{
+    fn new(keys: Vec<Cow<'param, [T]>>) -> Self {
+        let requested = keys.len();
+        Self {
+            keys: keys.into_iter().unique().collect(),
+            requested,
+        }
+    }
+
+    /// Returns the (deduplicated) keys in this batch, in the order in which they should be looked
+    /// up
+    pub fn keys(&self) -> &[Cow<'param, [T]>] {
+        &self.keys
+    }
+
+    /// Convert this batch into its (deduplicated) list of keys
+    pub fn into_keys(self) -> Vec<Cow<'param, [T]>> {
+        self.keys
+    }
+
+    /// Returns the number of keys which were made from the parameters but not included in this
+    /// batch, because they were duplicates of an earlier key
+    pub fn num_duplicates(&self) -> usize {
+        self.requested - self.keys.len()
+    }
+
+    /// Report which of the keys in this batch matched any of the given result `rows`, given the
+    /// index in each row of the column that was compared against each element of the keys.
+    pub fn lookup_report<'a, R>(
+        &'a self,
+        rows: impl IntoIterator<Item = R>,
+        key_columns: &[usize],
+    ) -> KeyLookupReport<'a, T>
+    where
+        R: AsRef<[T]>,
+    {
+        let positions = self
+            .keys
+            .iter()
+            .enumerate()
+            .map(|(i, key)| (key.as_ref(), i))
+            .collect::<HashMap<_, _>>();
+        let mut found = vec![false; self.keys.len()];
+        for row in rows {
+            let row = row.as_ref();
+            let key = match key_columns
+                .iter()
+                .map(|idx| row.get(*idx).cloned())
+                .collect::<Option<Vec<_>>>()
+            {
+                Some(key) => key,
+                None => continue,
+            };
+            if let Some(pos) = positions.get(key.as_slice()) {
+                found[*pos] = true;
+            }
+        }
+
+        let (found, missing) = self
+            .keys
+            .iter()
+            .zip(found)
+            .partition::<Vec<_>, _>(|(_, found)| *found);
+        KeyLookupReport {
+            found: found.into_iter().map(|(key, _)| key.as_ref()).collect(),
+            missing: missing.into_iter().map(|(key, _)| key.as_ref()).collect(),
+        }
     }
 }
 
@@ -521,6 +662,40 @@ pub fn number_placeholders(query: &mut SelectStatement) -> ReadySetResult<()> {
     Ok(())
 }
 
+/// Returns the conditions in the WHERE clause of the given query with placeholders that can't be
+/// turned into lookup keys (see [`is_post_lookup_predicate`]). The conditions are classified the
+/// same way as when the query is migrated, after `BETWEEN` has been rewritten to a pair of
+/// comparisons, so that the placeholders in them line up with the ones the server excluded from the
+/// query's lookup keys.
+fn post_lookup_predicates(query: &SelectStatement) -> Vec<Expr> {
+    fn collect(expr: Expr, out: &mut Vec<Expr>) {
+        match expr {
+            Expr::BinaryOp {
+                lhs,
+                op: BinaryOperator::And,
+                rhs,
+            } => {
+                collect(*lhs, out);
+                collect(*rhs, out);
+            }
+            expr if is_post_lookup_predicate(&expr) => out.push(expr),
+            _ => {}
+        }
+    }
+
+    let mut res = vec![];
+    let where_clause = SelectStatement {
+        where_clause: query.where_clause.clone(),
+        ..Default::default()
+    }
+    .rewrite_between()
+    .where_clause;
+    if let Some(where_clause) = where_clause {
+        collect(where_clause, &mut res);
+    }
+    res
+}
+
 #[derive(Default)]
 struct AutoParametrizeVisitor {
     out: Vec<(usize, Literal)>,
@@ -1379,6 +1554,51 @@ mod tests {
             );
         }
 
+        #[test]
+        fn where_in_with_duplicate_values() {
+            let (keys, _) = process_and_make_keys(
+                "SELECT * FROM t WHERE x IN (?, ?, ?, ?)",
+                vec![3.into(), 1.into(), 3.into(), 2.into()],
+            );
+            assert_eq!(keys, vec![vec![3.into()], vec![1.into()], vec![2.into()]]);
+        }
+
+        #[test]
+        fn multiple_where_in_with_duplicate_values() {
+            let (keys, _) = process_and_make_keys(
+                "SELECT * FROM t WHERE x IN (?, ?) AND y IN (?, ?)",
+                vec![1.into(), 1.into(), 2.into(), 3.into()],
+            );
+            assert_eq!(
+                keys,
+                vec![vec![1.into(), 2.into()], vec![1.into(), 3.into()]]
+            );
+        }
+
+        #[test]
+        fn key_batch_lookup_report() {
+            let mut query = parse_select_statement("SELECT x, y FROM t WHERE x IN (?, ?, ?, ?)");
+            let processed = process_query(&mut query, false).unwrap();
+            let params: Vec<DfValue> = vec![1.into(), 2.into(), 1.into(), 3.into()];
+            let batch = processed.make_key_batch(&params).unwrap();
+            assert_eq!(batch.keys().len(), 3);
+            assert_eq!(batch.num_duplicates(), 1);
+
+            let rows: Vec<Vec<DfValue>> = vec![
+                vec![3.into(), "a".into()],
+                vec![1.into(), "b".into()],
+                vec![3.into(), "c".into()],
+            ];
+            let report = batch.lookup_report(&rows, &[0]);
+            assert_eq!(
+                report,
+                KeyLookupReport {
+                    found: vec![&[DfValue::from(1)][..], &[DfValue::from(3)][..]],
+                    missing: vec![&[DfValue::from(2)][..]],
+                }
+            );
+        }
+
         #[test]
         fn numbered_not_in_order() {
             let (keys, query) = process_and_make_keys(
@@ -1521,5 +1741,24 @@ mod tests {
                 (Some(4), Some(2))
             );
         }
+
+        #[test]
+        fn post_lookup_predicates() {
+            let mut query = parse_select_statement(
+                "SELECT * FROM t WHERE x = ? AND round(y, ?) > ? AND z BETWEEN ? AND ? + 1",
+            );
+            let processed = process_query(&mut query, false).unwrap();
+            assert_eq!(
+                processed.post_lookup_predicates(),
+                [
+                    nom_sql::parse_expr(Dialect::MySQL, "round(y, $2) > $3").unwrap(),
+                    nom_sql::parse_expr(Dialect::MySQL, "z <= $5 + 1").unwrap()
+                ]
+            );
+
+            let mut query = parse_select_statement("SELECT * FROM t WHERE x = ? AND y = 4");
+            let processed = process_query(&mut query, false).unwrap();
+            assert!(processed.post_lookup_predicates().is_empty());
+        }
     }
 }
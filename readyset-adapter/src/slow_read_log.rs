@@ -0,0 +1,220 @@
+//! A log of slow reads from caches, analogous to MySQL's slow query log.
+//!
+//! Every read from a cache which takes longer than the configured threshold (including any time
+//! spent falling back to the upstream database) is written to the log as a single line of JSON,
+//! recording:
+//!
+//! - the cache that was read from, and the ID of the query
+//! - the keys that were read
+//! - a breakdown of where the time went: waiting for replays to fill missed keys (`replay_us`),
+//!   falling back to the upstream database (`upstream_fallback_us`), and everything else, such as
+//!   queueing for and sending the read to the reader (`queue_us`)
+//! - the number of rows looked up from the cache
+//!
+//! Keys are redacted if the `redact_sensitive` feature is enabled, in the same way as queries are
+//! in log messages.
+//!
+//! Entries are written to the log file by a dedicated background thread, so that logging a slow
+//! read never blocks the connection which made it on file IO. If that thread falls behind by more
+//! than `WRITE_BUFFER_SIZE` entries, further entries are dropped (and counted in a warning)
+//! rather than applying backpressure to reads.
+use std::fs::OpenOptions;
+use std::io::{self, LineWriter, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+use chrono::{DateTime, Utc};
+use readyset_client::KeyComparison;
+use readyset_client_metrics::{QueryDestination, QueryExecutionEvent};
+use readyset_tracing::warn;
+use readyset_util::redacted::Sensitive;
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+/// The maximum number of entries buffered for the background writer before entries are dropped
+const WRITE_BUFFER_SIZE: usize = 1024;
+
+/// A single entry in the slow read log
+#[derive(Debug, Serialize)]
+struct SlowRead {
+    /// When the read completed, in RFC 3339 format
+    timestamp: String,
+    cache: String,
+    query_id: Option<String>,
+    keys: Vec<String>,
+    total_us: u64,
+    queue_us: u64,
+    replay_us: u64,
+    upstream_fallback_us: u64,
+    result_rows: Option<u64>,
+}
+
+fn micros(duration: Duration) -> u64 {
+    duration.as_micros() as u64
+}
+
+fn format_key(key: &KeyComparison) -> String {
+    match key {
+        KeyComparison::Equal(key) => format!(
+            "({})",
+            key.iter()
+                .map(|value| Sensitive(value).to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        KeyComparison::Range(_) => format!("{:?}", Sensitive(key)),
+    }
+}
+
+/// Writes reads from caches which take longer than a threshold to a log file, as JSON lines.
+///
+/// A single [`SlowReadLog`] is intended to be shared between all the connections to an adapter.
+#[derive(Debug)]
+pub struct SlowReadLog {
+    threshold: Duration,
+    /// Sends serialized entries to the background writer. Only `None` once the log is dropped.
+    lines: Option<mpsc::Sender<Vec<u8>>>,
+    /// The number of entries dropped because the background writer fell behind, since the
+    /// writer last reported them
+    dropped: Arc<AtomicU64>,
+    writer: Option<JoinHandle<()>>,
+}
+
+impl SlowReadLog {
+    /// Open the slow read log at `path` (appending to it if it already exists), to log reads
+    /// which take longer than `threshold`
+    pub fn open<P>(path: P, threshold: Duration) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let (tx, mut rx) = mpsc::channel::<Vec<u8>>(WRITE_BUFFER_SIZE);
+        let dropped = Arc::new(AtomicU64::new(0));
+        let writer = thread::Builder::new().name("Slow read log".into()).spawn({
+            let dropped = Arc::clone(&dropped);
+            move || {
+                let mut file = LineWriter::new(file);
+                while let Some(line) = rx.blocking_recv() {
+                    if let Err(error) = file.write_all(&line) {
+                        warn!(%error, "Error writing to the slow read log");
+                    }
+                    let dropped = dropped.swap(0, Ordering::Relaxed);
+                    if dropped > 0 {
+                        warn!(%dropped, "Slow read log fell behind; dropped entries");
+                    }
+                }
+            }
+        })?;
+
+        Ok(Self {
+            threshold,
+            lines: Some(tx),
+            dropped,
+            writer: Some(writer),
+        })
+    }
+
+    /// Returns the minimum duration of the reads written to the log
+    pub fn threshold(&self) -> Duration {
+        self.threshold
+    }
+
+    /// Write the read described by `event` to the log if it read from a cache and took longer
+    /// than the threshold
+    pub(crate) fn log(&self, event: &QueryExecutionEvent) {
+        let Some(cache) = &event.cache_name else {
+            return;
+        };
+        let readyset_time = event.readyset_duration.unwrap_or_default();
+        let upstream_fallback_time = match event.destination {
+            Some(QueryDestination::ReadysetThenUpstream) => {
+                event.upstream_duration.unwrap_or_default()
+            }
+            _ => Duration::ZERO,
+        };
+        let total = readyset_time + upstream_fallback_time;
+        if total <= self.threshold {
+            return;
+        }
+        let replay_time = event.replay_wait.unwrap_or_default();
+
+        let entry = SlowRead {
+            timestamp: DateTime::<Utc>::from(SystemTime::now()).to_rfc3339(),
+            cache: cache.to_string(),
+            query_id: event.query_id.map(|id| id.to_string()),
+            keys: event.keys.iter().flatten().map(format_key).collect(),
+            total_us: micros(total),
+            queue_us: micros(readyset_time.saturating_sub(replay_time)),
+            replay_us: micros(replay_time),
+            upstream_fallback_us: micros(upstream_fallback_time),
+            result_rows: event.result_rows,
+        };
+
+        let mut line = match serde_json::to_vec(&entry) {
+            Ok(line) => line,
+            Err(error) => {
+                warn!(%error, "Error serializing slow read log entry");
+                return;
+            }
+        };
+        line.push(b'\n');
+        if let Some(lines) = &self.lines {
+            if let Err(mpsc::error::TrySendError::Full(_)) = lines.try_send(line) {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+impl Drop for SlowReadLog {
+    /// Wait for the background writer to write all the buffered entries to the log
+    fn drop(&mut self) {
+        self.lines.take();
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use readyset_client_metrics::EventType;
+    use readyset_data::DfValue;
+    use vec1::vec1;
+
+    use super::*;
+
+    #[test]
+    fn logs_reads_over_threshold() {
+        let path = std::env::temp_dir().join(format!("slow_reads_{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let log = SlowReadLog::open(&path, Duration::from_millis(10)).unwrap();
+
+        let mut event = QueryExecutionEvent::new(EventType::Execute);
+        event.cache_name = Some("q1".into());
+        event.keys = Some(vec![KeyComparison::Equal(vec1![DfValue::from(1)])]);
+        event.readyset_duration = Some(Duration::from_millis(5));
+        log.log(&event);
+
+        event.readyset_duration = Some(Duration::from_millis(50));
+        event.replay_wait = Some(Duration::from_millis(30));
+        event.result_rows = Some(3);
+        log.log(&event);
+        drop(log);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines = contents.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 1);
+        let entry: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(entry["total_us"], 50_000);
+        assert_eq!(entry["replay_us"], 30_000);
+        assert_eq!(entry["queue_us"], 20_000);
+        assert_eq!(entry["upstream_fallback_us"], 0);
+        assert_eq!(entry["result_rows"], 3);
+        assert_eq!(entry["keys"][0], "(1)");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -104,6 +104,12 @@ pub trait UpstreamDatabase: Sized + Send {
     /// [`connect`]
     fn url(&self) -> &str;
 
+    /// Return the id the upstream database uses to identify this connection, if it has one which
+    /// can be used to interrupt the statement the connection is executing
+    fn connection_id(&self) -> Option<u32> {
+        None
+    }
+
     /// Returns a database name if it was included in the original connection string, or None if no
     /// database name was included in the original connection string.
     fn database(&self) -> Option<&str> {
@@ -4,15 +4,17 @@ use std::iter;
 
 use nom_sql::analysis::visit::{self, Visitor};
 use nom_sql::{
-    BinaryOperator, Column, ColumnConstraint, CreateTableBody, DeleteStatement, Expr,
+    BinaryOperator, Column, ColumnConstraint, CreateTableBody, DeleteStatement, Expr, FunctionExpr,
     InsertStatement, Literal, SelectStatement, SqlIdentifier, SqlQuery, TableKey, UpdateStatement,
 };
+use readyset_client::query::QueryId;
 use readyset_client::{Modification, Operation};
-use readyset_data::{DfType, DfValue, Dialect};
+use readyset_data::{eval_volatile_function, DfType, DfValue, Dialect};
 use readyset_errors::{
     bad_request_err, invariant, invariant_eq, unsupported, unsupported_err, ReadySetResult,
 };
-use readyset_util::hash::hash;
+use readyset_sql_passes::expr::const_eval;
+use readyset_sql_passes::{is_post_lookup_predicate, is_predicate};
 
 // Helper for flatten_conditional - returns true if the
 // expression is "valid" (i.e. not something like `a = 1 AND a = 2`.
@@ -188,21 +190,16 @@ impl<'ast> Visitor<'ast> for BinopsParameterColumnsVisitor<'ast> {
     type Error = !;
 
     /// Extracts columns and binops when one side of the Expr contains [`Expr::Column`] and the
-    /// other side contains [`Expr::Literal(Literal::Placeholder)`]
+    /// other side contains [`Expr::Literal(Literal::Placeholder)`]. Placeholders in post-lookup
+    /// predicates (see [`is_post_lookup_predicate`]) aren't used as lookup keys, so they're
+    /// skipped.
     fn visit_expr(&mut self, expr: &'ast Expr) -> Result<(), Self::Error> {
         match expr {
             Expr::BinaryOp {
                 lhs: box Expr::Column(ref c),
                 rhs: box Expr::Literal(Literal::Placeholder(_)),
                 op: binop,
-            } => self.parameter_cols.push((c, *binop)),
-            Expr::BinaryOp {
-                lhs: box Expr::Literal(Literal::Placeholder(_)),
-                rhs: box Expr::Column(ref c),
-                op: binop,
-            } => self
-                .parameter_cols
-                .push((c, binop.flip_comparison().unwrap_or(*binop))),
+            } if !is_post_lookup_predicate(expr) => self.parameter_cols.push((c, *binop)),
             Expr::In {
                 lhs: box Expr::Column(ref c),
                 rhs: nom_sql::InValue::List(ref exprs),
@@ -214,53 +211,23 @@ impl<'ast> Visitor<'ast> for BinopsParameterColumnsVisitor<'ast> {
                 self.parameter_cols
                     .extend(iter::repeat((c, BinaryOperator::Equal)).take(exprs.len()))
             }
-            Expr::In {
-                lhs: box Expr::Column(ref c),
-                rhs: nom_sql::InValue::List(ref exprs),
-                negated: true,
-            } if exprs
-                .iter()
-                .all(|expr| matches!(expr, Expr::Literal(Literal::Placeholder(_)))) =>
-            {
-                self.parameter_cols
-                    .extend(iter::repeat((c, BinaryOperator::NotEqual)).take(exprs.len()))
-            }
-            Expr::Between {
-                operand: box Expr::Column(ref col),
-                min: box Expr::Literal(Literal::Placeholder(_)),
-                max: box Expr::Literal(Literal::Placeholder(_)),
-                ..
-            } => self.parameter_cols.extend_from_slice(&[
-                (col, BinaryOperator::GreaterOrEqual),
-                (col, BinaryOperator::LessOrEqual),
-            ]),
-
+            // Non-negated BETWEEN is rewritten to a pair of comparisons, either of which can be a
+            // lookup key
             Expr::Between {
                 operand: box Expr::Column(ref col),
-                min: box Expr::Literal(Literal::Placeholder(_)),
+                min,
+                max,
                 negated: false,
-                ..
-            }
-            | Expr::Between {
-                operand: box Expr::Column(ref col),
-                max: box Expr::Literal(Literal::Placeholder(_)),
-                negated: true,
-                ..
-            } => self
-                .parameter_cols
-                .push((col, BinaryOperator::GreaterOrEqual)),
-            Expr::Between {
-                operand: box Expr::Column(ref col),
-                max: box Expr::Literal(Literal::Placeholder(_)),
-                negated: false,
-                ..
+            } => {
+                if matches!(**min, Expr::Literal(Literal::Placeholder(_))) {
+                    self.parameter_cols
+                        .push((col, BinaryOperator::GreaterOrEqual));
+                }
+                if matches!(**max, Expr::Literal(Literal::Placeholder(_))) {
+                    self.parameter_cols.push((col, BinaryOperator::LessOrEqual));
+                }
             }
-            | Expr::Between {
-                operand: box Expr::Column(ref col),
-                min: box Expr::Literal(Literal::Placeholder(_)),
-                negated: true,
-                ..
-            } => self.parameter_cols.push((col, BinaryOperator::LessOrEqual)),
+            _ if is_post_lookup_predicate(expr) => {}
             _ => visit::walk_expr(self, expr)?,
         }
         Ok(())
@@ -292,6 +259,74 @@ pub(crate) fn select_statement_parameter_columns(query: &SelectStatement) -> Vec
         .collect()
 }
 
+/// Visitor to find the column each placeholder in an expression is directly compared against, if
+/// any
+struct PlaceholderColumnsVisitor<'ast> {
+    placeholder_cols: Vec<Option<&'ast Column>>,
+}
+
+impl<'ast> Visitor<'ast> for PlaceholderColumnsVisitor<'ast> {
+    type Error = !;
+
+    fn visit_expr(&mut self, expr: &'ast Expr) -> Result<(), Self::Error> {
+        let (col, operands): (&Column, Vec<&Expr>) = match expr {
+            Expr::BinaryOp {
+                lhs: box Expr::Column(c),
+                op,
+                rhs,
+            } if is_predicate(op) => (c, vec![rhs]),
+            Expr::BinaryOp {
+                lhs,
+                op,
+                rhs: box Expr::Column(c),
+            } if is_predicate(op) => (c, vec![lhs]),
+            Expr::In {
+                lhs: box Expr::Column(c),
+                rhs: nom_sql::InValue::List(exprs),
+                ..
+            } => (c, exprs.iter().collect()),
+            Expr::Between {
+                operand: box Expr::Column(c),
+                min,
+                max,
+                ..
+            } => (c, vec![min, max]),
+            _ => return visit::walk_expr(self, expr),
+        };
+
+        for operand in operands {
+            if matches!(operand, Expr::Literal(Literal::Placeholder(_))) {
+                self.placeholder_cols.push(Some(col));
+            } else {
+                self.visit_expr(operand)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn visit_literal(&mut self, literal: &'ast Literal) -> Result<(), Self::Error> {
+        if matches!(literal, Literal::Placeholder(_)) {
+            self.placeholder_cols.push(None);
+        }
+        Ok(())
+    }
+}
+
+/// Returns, for each placeholder in the WHERE clause of the given query in order, the column it's
+/// directly compared against, or `None` if there isn't one (for example, if the placeholder is an
+/// argument to a function)
+pub(crate) fn select_statement_placeholder_columns(
+    query: &SelectStatement,
+) -> Vec<Option<&Column>> {
+    let mut visitor = PlaceholderColumnsVisitor {
+        placeholder_cols: Vec::new(),
+    };
+    if let Some(wc) = &query.where_clause {
+        let Ok(_) = visitor.visit_where_clause(wc);
+    }
+    visitor.placeholder_cols
+}
+
 pub(crate) fn get_limit_parameters(query: &SelectStatement) -> Vec<Column> {
     let mut limit_params = vec![];
     if let Some(Literal::Placeholder(_)) = query.limit_clause.limit() {
@@ -320,6 +355,77 @@ pub(crate) fn insert_statement_parameter_columns(query: &InsertStatement) -> Vec
                 _ => None,
             })
         })
+        .chain(on_duplicate_parameter_columns(query))
+        .collect()
+}
+
+/// Returns the columns set to a parameter in the `ON DUPLICATE KEY UPDATE` clause of the given
+/// `INSERT` statement, which come after all the parameters in the inserted values
+fn on_duplicate_parameter_columns(query: &InsertStatement) -> impl Iterator<Item = &Column> {
+    query
+        .on_duplicate
+        .iter()
+        .flatten()
+        .filter_map(|(col, expr)| match expr {
+            Expr::Literal(Literal::Placeholder(_)) => Some(col),
+            _ => None,
+        })
+}
+
+/// Returns the values of the parameters in the `ON DUPLICATE KEY UPDATE` clause of the given
+/// `INSERT` statement, out of the values of all of its parameters
+pub(crate) fn on_duplicate_params<'a>(
+    query: &InsertStatement,
+    params: &'a [DfValue],
+) -> ReadySetResult<&'a [DfValue]> {
+    let num_params = on_duplicate_parameter_columns(query).count();
+    if num_params > params.len() {
+        return Err(bad_request_err(
+            "Not enough parameter values given in EXECUTE",
+        ));
+    }
+    Ok(&params[(params.len() - num_params)..])
+}
+
+/// Evaluate the rows of values inserted by the given `INSERT` statement, substituting `params` for
+/// its placeholders in the order they appear in the statement.
+///
+/// Besides literals and placeholders, values can be any constant expression, or a call to one of
+/// the functions supported by [`eval_volatile_function`] (such as `NOW()` or `UUID()`), which is
+/// evaluated separately for each row.
+pub(crate) fn insert_values(
+    query: &InsertStatement,
+    params: &[DfValue],
+    dialect: Dialect,
+) -> ReadySetResult<Vec<Vec<DfValue>>> {
+    let eval = |expr: &Expr| {
+        const_eval(expr, dialect).map_err(|e| {
+            unsupported_err!("Only constant expressions are supported in inserted values: {e}")
+        })
+    };
+
+    let mut params = params.iter();
+    query
+        .data
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|expr| match expr {
+                    Expr::Literal(Literal::Placeholder(_)) => params
+                        .next()
+                        .cloned()
+                        .ok_or_else(|| bad_request_err("Missing value for query parameter")),
+                    Expr::Literal(lit) => DfValue::try_from(lit),
+                    Expr::Call(FunctionExpr::Call { name, arguments }) => {
+                        match eval_volatile_function(name, arguments)? {
+                            Some(value) => Ok(value),
+                            None => eval(expr),
+                        }
+                    }
+                    _ => eval(expr),
+                })
+                .collect::<ReadySetResult<Vec<_>>>()
+        })
         .collect()
 }
 
@@ -552,7 +658,7 @@ pub(crate) fn generate_query_name(
     statement: &nom_sql::SelectStatement,
     schema_search_path: &[SqlIdentifier],
 ) -> String {
-    format!("q_{:x}", hash(&(statement, schema_search_path)))
+    QueryId::from_select(statement, schema_search_path).to_string()
 }
 
 #[cfg(test)]
@@ -878,6 +984,63 @@ mod tests {
         );
     }
 
+    #[test]
+    fn insert_values_with_expressions() {
+        let insert = match nom_sql::parse_query(
+            Dialect::MySQL,
+            "INSERT INTO t (a, b, c, d) VALUES (?, -1, 1 + 2, UUID()), (?, 'x', NULL, NOW())",
+        )
+        .unwrap()
+        {
+            SqlQuery::Insert(insert) => insert,
+            _ => unreachable!(),
+        };
+
+        let rows = insert_values(
+            &insert,
+            &[DfValue::from(10), DfValue::from(20)],
+            readyset_data::Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(
+            rows[0][..3],
+            [DfValue::from(10), DfValue::from(-1), DfValue::from(3)]
+        );
+        assert_eq!(<&str>::try_from(&rows[0][3]).unwrap().len(), 36);
+        assert_eq!(
+            rows[1][..3],
+            [DfValue::from(20), DfValue::from("x"), DfValue::None]
+        );
+        assert!(matches!(rows[1][3], DfValue::TimestampTz(_)));
+
+        insert_values(&insert, &[], readyset_data::Dialect::DEFAULT_MYSQL).unwrap_err();
+    }
+
+    #[test]
+    fn on_duplicate_key_update_params() {
+        let insert = match nom_sql::parse_query(
+            Dialect::MySQL,
+            "INSERT INTO t (a, b) VALUES (?, 1), (?, 2) ON DUPLICATE KEY UPDATE b = ?, c = 3",
+        )
+        .unwrap()
+        {
+            SqlQuery::Insert(insert) => insert,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(
+            insert_statement_parameter_columns(&insert),
+            vec![&Column::from("a"), &Column::from("a"), &Column::from("b")]
+        );
+        let params = [DfValue::from(10), DfValue::from(20), DfValue::from(30)];
+        assert_eq!(
+            on_duplicate_params(&insert, &params).unwrap(),
+            &[DfValue::from(30)]
+        );
+        on_duplicate_params(&insert, &[]).unwrap_err();
+    }
+
     mod build_view_query {
         use nom_sql::parse_select_statement;
 
@@ -1078,6 +1241,40 @@ mod tests {
                     BinaryOperator::Equal
                 ),]
             );
+
+            let stmt = parse_select_statement(
+                Dialect::MySQL,
+                "SELECT t.x FROM t WHERE round(t.x, $1) > $2 AND t.y = $3 AND $4 = t.x",
+            )
+            .unwrap();
+            let binops = get_select_statement_binops(&stmt);
+            assert_eq!(
+                binops,
+                vec![(
+                    &Column {
+                        name: "y".into(),
+                        table: Some("t".into())
+                    },
+                    BinaryOperator::Equal
+                ),]
+            );
         }
     }
+
+    #[test]
+    fn select_placeholder_columns() {
+        let stmt = nom_sql::parse_select_statement(
+            Dialect::MySQL,
+            "SELECT x FROM t WHERE x = ? AND round(y, ?) > ? AND z IN (?, ?) \
+             AND w BETWEEN ? AND ? + 1",
+        )
+        .unwrap();
+        let x = Column::from("x");
+        let z = Column::from("z");
+        let w = Column::from("w");
+        assert_eq!(
+            select_statement_placeholder_columns(&stmt),
+            vec![Some(&x), None, None, Some(&z), Some(&z), Some(&w), None]
+        );
+    }
 }
@@ -4,9 +4,9 @@ use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use metrics::SharedString;
-use nom_sql::SqlQuery;
+use nom_sql::{Relation, SqlQuery};
 use readyset_client::query::QueryId;
-use readyset_client::ReadySetError;
+use readyset_client::{KeyComparison, ReadySetError};
 use serde::Serialize;
 
 pub mod recorded;
@@ -44,6 +44,22 @@ pub struct QueryExecutionEvent {
 
     /// Number of cache misses which occurred as part of a query
     pub cache_misses: Option<u64>,
+
+    /// The name of the cache the query read from, if it was executed against ReadySet
+    pub cache_name: Option<Relation>,
+
+    /// The keys that were read from the cache, if [`Self::record_keys`] is set
+    pub keys: Option<Vec<KeyComparison>>,
+
+    /// Whether to record the keys read from the cache in [`Self::keys`]. Only set when something
+    /// (such as the slow read log) needs them, to avoid copying the keys of every read otherwise.
+    pub record_keys: bool,
+
+    /// How long the read waited for replays to fill the keys it missed on
+    pub replay_wait: Option<Duration>,
+
+    /// The number of rows looked up from the cache
+    pub result_rows: Option<u64>,
 }
 
 #[derive(Debug, PartialEq, Eq, Serialize, Clone, Copy, Default)]
@@ -162,6 +178,11 @@ impl QueryExecutionEvent {
             destination: None,
             cache_misses: None,
             num_keys: None,
+            cache_name: None,
+            keys: None,
+            record_keys: false,
+            replay_wait: None,
+            result_rows: None,
         }
     }
 
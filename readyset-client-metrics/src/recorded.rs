@@ -62,3 +62,19 @@ pub const ADAPTER_EXTERNAL_REQUESTS: &str = "noria-client.external_requests";
 
 /// Gauge: The number of currently connected SQL clients
 pub const CONNECTED_CLIENTS: &str = "noria-client.connected_clients";
+
+/// Counter: The number of reads which were rejected instead of being proxied to the upstream
+/// database.
+///
+/// | Tag | Description |
+/// | --- | ----------- |
+/// | reason | Currently always `circuit_open`. |
+pub const FALLBACK_QUERIES_REJECTED: &str = "noria-client.fallback_queries_rejected";
+
+/// Counter: The number of reads which had to wait before being proxied to the upstream database,
+/// because of the limit on the rate of proxied reads.
+pub const FALLBACK_QUERIES_DELAYED: &str = "noria-client.fallback_queries_delayed";
+
+/// Gauge: Set to 1 while reads are not being proxied to the upstream database because it has been
+/// failing, and 0 otherwise.
+pub const FALLBACK_CIRCUIT_OPEN: &str = "noria-client.fallback_circuit_open";
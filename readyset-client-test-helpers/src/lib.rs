@@ -2,7 +2,6 @@
 
 use std::collections::HashMap;
 use std::env;
-use std::sync::atomic::AtomicUsize;
 use std::sync::{Arc, RwLock};
 use std::time::Duration;
 
@@ -10,6 +9,8 @@ use async_trait::async_trait;
 use nom_sql::Relation;
 use readyset_adapter::backend::noria_connector::{NoriaConnector, ReadBehavior};
 use readyset_adapter::backend::{BackendBuilder, MigrationMode};
+use readyset_adapter::connections::ConnectionRegistry;
+use readyset_adapter::micro_cache::MicroCache;
 use readyset_adapter::query_status_cache::QueryStatusCache;
 use readyset_adapter::{Backend, QueryHandler, UpstreamConfig, UpstreamDatabase};
 use readyset_client::consensus::{Authority, LocalAuthorityStore};
@@ -64,6 +65,8 @@ pub struct TestBuilder {
     migration_mode: MigrationMode,
     recreate_database: bool,
     query_status_cache: Option<&'static QueryStatusCache>,
+    connections: Option<Arc<ConnectionRegistry>>,
+    micro_cache: Option<Arc<MicroCache>>,
 }
 
 impl Default for TestBuilder {
@@ -84,6 +87,8 @@ impl TestBuilder {
             migration_mode: MigrationMode::InRequestPath,
             recreate_database: true,
             query_status_cache: None,
+            connections: None,
+            micro_cache: None,
         }
     }
 
@@ -123,6 +128,19 @@ impl TestBuilder {
         self
     }
 
+    /// Register every connection to the adapter with the given registry, as the adapter binary
+    /// does, so that connections can be limited and killed
+    pub fn connections(mut self, connections: Arc<ConnectionRegistry>) -> Self {
+        self.connections = Some(connections);
+        self
+    }
+
+    /// Serve point reads from the given micro cache, shared by every connection to the adapter
+    pub fn micro_cache(mut self, micro_cache: Arc<MicroCache>) -> Self {
+        self.micro_cache = Some(micro_cache);
+        self
+    }
+
     pub async fn build<A>(self) -> (A::ConnectionOpts, Handle)
     where
         A: Adapter + 'static,
@@ -165,7 +183,6 @@ impl TestBuilder {
             handle.backend_ready().await;
         }
 
-        let auto_increments: Arc<RwLock<HashMap<Relation, AtomicUsize>>> = Arc::default();
         let query_cache: Arc<RwLock<HashMap<ViewCreateRequest, Relation>>> = Arc::default();
         let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
         let addr = listener.local_addr().unwrap();
@@ -174,8 +191,15 @@ impl TestBuilder {
             loop {
                 let (s, _) = listener.accept().await.unwrap();
                 let query_cache = query_cache.clone();
-                let backend_builder = self.backend_builder.clone();
-                let auto_increments = auto_increments.clone();
+                let mut backend_builder = self.backend_builder.clone();
+                let mut registration = match &self.connections {
+                    Some(connections) => match connections.register() {
+                        Some(registration) => Some(registration),
+                        // Too many connections
+                        None => continue,
+                    },
+                    None => None,
+                };
                 let authority = authority.clone();
 
                 // backend either has upstream or noria writer
@@ -185,6 +209,13 @@ impl TestBuilder {
                     None
                 };
 
+                if let Some(registration) = &mut registration {
+                    if let Some(upstream_id) = upstream.as_ref().and_then(|u| u.connection_id()) {
+                        registration.set_id(upstream_id);
+                    }
+                    backend_builder = backend_builder.connection(registration.handle());
+                }
+
                 let schema_search_path = if let Some(upstream) = &mut upstream {
                     upstream.schema_search_path().await.unwrap()
                 } else {
@@ -193,9 +224,8 @@ impl TestBuilder {
 
                 let mut rh = ReadySetHandle::new(authority).await;
                 let server_supports_pagination = rh.supports_pagination().await.unwrap();
-                let noria = NoriaConnector::new(
+                let mut noria = NoriaConnector::new(
                     rh,
-                    auto_increments,
                     query_cache,
                     self.read_behavior,
                     A::EXPR_DIALECT,
@@ -203,13 +233,29 @@ impl TestBuilder {
                     server_supports_pagination,
                 )
                 .await;
+                if let Some(micro_cache) = &self.micro_cache {
+                    noria.set_micro_cache(micro_cache.clone());
+                }
 
                 let backend = backend_builder
                     .dialect(A::DIALECT)
                     .migration_mode(self.migration_mode)
                     .build(noria, upstream, query_status_cache);
 
-                tokio::spawn(A::run_backend(backend, s));
+                tokio::spawn(async move {
+                    match &registration {
+                        Some(registration) => {
+                            let handle = registration.handle();
+                            tokio::select! {
+                                _ = A::run_backend(backend, s) => {}
+                                _ = handle.killed() => {}
+                            }
+                        }
+                        None => A::run_backend(backend, s).await,
+                    }
+                    // Only unregister the connection once it's closed
+                    drop(registration);
+                });
             }
         });
 
@@ -90,9 +90,14 @@ pub async fn last_query_info(conn: &Client) -> QueryInfo {
 
     let destination = QueryDestination::try_from(row.get("Query_destination").unwrap()).unwrap();
     let noria_error = row.get("ReadySet_error").unwrap().to_owned();
+    let unsupported_feature = match row.get("Unsupported_feature").unwrap() {
+        "none" => None,
+        feature => Some(feature.parse().unwrap()),
+    };
 
     QueryInfo {
         destination,
         noria_error,
+        unsupported_feature,
     }
 }
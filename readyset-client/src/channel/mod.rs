@@ -1,6 +1,10 @@
 //! A wrapper around TCP channels that ReadySet uses to communicate between clients and servers, and
 //! inside the data-flow graph. At this point, this is mostly a thin wrapper around
 //! [`async-bincode`](https://docs.rs/async-bincode/), and it might go away in the long run.
+//!
+//! Connections to domains start with a versioned preamble (see [`PROTOCOL_VERSION`]), so that the
+//! encoding of packets can change between releases without workers running different versions
+//! during a rolling upgrade misinterpreting each other's packets.
 
 use std::borrow::Borrow;
 use std::collections::HashMap;
@@ -11,18 +15,215 @@ use std::pin::Pin;
 use std::sync::RwLock;
 use std::task::{Context, Poll};
 
-use async_bincode::{AsyncBincodeWriter, AsyncDestination};
 use futures_util::sink::{Sink, SinkExt};
-use tokio::io::BufWriter;
+use futures_util::FutureExt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
+pub mod secure;
 pub mod tcp;
+pub mod vectored;
 
+pub use self::secure::{
+    AuthenticatedStream, ChannelSecurity, ChannelStream, HandshakingStream, TlsFiles,
+    REQUEST_SIGNATURE_HEADER, REQUEST_TIMESTAMP_HEADER,
+};
 pub use self::tcp::{DualTcpStream, TcpSender};
+pub use self::vectored::VectoredBincodeWriter;
 use crate::internal::ReplicaAddress;
 use crate::{ReadySetError, ReadySetResult};
 
 pub const CONNECTION_FROM_BASE: u8 = 1;
 pub const CONNECTION_FROM_DOMAIN: u8 = 2;
+/// Sent (always with [`VERSIONED_CONNECTION`] set) as the first byte of connections to readers.
+/// See [`reader_handshake`].
+pub const CONNECTION_TO_READER: u8 = 3;
+
+/// Set on the first byte of a connection's preamble if it's followed by a byte containing the
+/// version of the encoding used for packets sent on the connection. Connections using
+/// [`LEGACY_PROTOCOL_VERSION`] never set this bit, so that their preamble is the single tag byte
+/// that older versions of ReadySet send and expect.
+const VERSIONED_CONNECTION: u8 = 0x80;
+
+/// The version of the packet encoding used by connections whose preamble doesn't include a version
+pub const LEGACY_PROTOCOL_VERSION: u8 = 0;
+
+/// The version of the packet encoding used for connections opened by this version of ReadySet.
+///
+/// This must be bumped whenever the encoding of packets sent over TCP channels changes, so that
+/// workers running different versions of ReadySet during a rolling upgrade can detect (and
+/// reject, rather than silently misinterpret) packets they don't know how to decode. Connections
+/// using [`LEGACY_PROTOCOL_VERSION`] are opened with the legacy preamble, which workers that
+/// predate versioned preambles can still read.
+///
+/// Versions:
+///
+/// * 0: the legacy encoding
+/// * 1: writes to base tables are acknowledged with a `Tagged<ReadySetResult<()>>` rather than a
+///   `Tagged<()>`, so that rejected writes can be reported back to the client
+/// * 2: [`ViewQuery`](crate::ViewQuery) carries a [`ReadBehavior`](crate::ReadBehavior) rather than
+///   a flag for whether the read should block
+/// * 3: [`ReadReplyStats`](crate::ReadReplyStats) carries how long the read waited for replays and
+///   the number of rows it looked up, for the slow read log. Like every change to reads and their
+///   replies, this is only detected by the reader handshake added in version 5, so readers and
+///   clients older than that must not be mixed with this version
+/// * 4: [`PacketPayload`](crate::PacketPayload) has a `Barrier` variant, which is injected into
+///   base tables to quiesce the dataflow graph and forwarded between domains
+/// * 5: connections to readers start with a version handshake (see [`reader_handshake`])
+/// * 6: [`PacketData`](crate::PacketData), and the dataflow updates sent between domains, carry the
+///   timestamp of the [`WriteBatch`](crate::WriteBatch) they're part of, if any
+/// * 7: the dataflow updates and replays sent between domains have a field for provenance tags,
+///   which are only set when provenance recording is enabled
+pub const PROTOCOL_VERSION: u8 = 7;
+
+/// The oldest version of the packet encoding which domains accept for connections from base tables.
+///
+/// Domains only know how to decode the packets sent by base tables in the encoding of their own
+/// [`PROTOCOL_VERSION`], and acknowledge them in that encoding too, so this is the version in which
+/// either of those last changed.
+pub const MIN_BASE_PROTOCOL_VERSION: u8 = 6;
+
+/// The oldest version of the packet encoding which domains accept for connections from other
+/// domains.
+///
+/// Domains only know how to decode the packets sent by other domains in the encoding of their own
+/// [`PROTOCOL_VERSION`], so this is the version in which that encoding last changed.
+pub const MIN_DOMAIN_PROTOCOL_VERSION: u8 = 7;
+
+/// The first bytes sent on every TCP connection to a domain, identifying where the connection came
+/// from and how the packets sent on it are encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Preamble {
+    /// Whether the connection is from a base table (rather than from another domain)
+    pub is_base: bool,
+    /// The version of the packet encoding used on the connection
+    pub version: u8,
+}
+
+impl Preamble {
+    /// Construct a preamble for a new connection using the current [`PROTOCOL_VERSION`]
+    pub fn new(is_base: bool) -> Self {
+        Self {
+            is_base,
+            version: PROTOCOL_VERSION,
+        }
+    }
+
+    /// Encode this preamble into the bytes that should be written to the start of a connection.
+    ///
+    /// Preambles for [`LEGACY_PROTOCOL_VERSION`] are encoded as just the tag byte, exactly as
+    /// older versions of ReadySet encode them.
+    pub fn to_bytes(self) -> Vec<u8> {
+        let tag = if self.is_base {
+            CONNECTION_FROM_BASE
+        } else {
+            CONNECTION_FROM_DOMAIN
+        };
+        if self.version == LEGACY_PROTOCOL_VERSION {
+            vec![tag]
+        } else {
+            vec![tag | VERSIONED_CONNECTION, self.version]
+        }
+    }
+
+    /// Read a preamble from the start of a connection, returning an error if the connection uses
+    /// a version of the packet encoding which is newer than the one we know how to decode
+    pub async fn read<R>(stream: &mut R) -> io::Result<Self>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let tag = stream.read_u8().await?;
+        let version = if tag & VERSIONED_CONNECTION != 0 {
+            stream.read_u8().await?
+        } else {
+            LEGACY_PROTOCOL_VERSION
+        };
+
+        if version > PROTOCOL_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "peer uses channel protocol version {version}, but the newest supported \
+                     version is {PROTOCOL_VERSION}"
+                ),
+            ));
+        }
+
+        Ok(Self {
+            is_base: tag & !VERSIONED_CONNECTION == CONNECTION_FROM_BASE,
+            version,
+        })
+    }
+}
+
+/// Perform the client side of the version handshake at the start of a connection to a worker's
+/// readers, returning an error if the worker uses a different version of the packet encoding.
+///
+/// Unlike connections to domains, where the receiving end decodes packets according to the
+/// version in the connection's [`Preamble`], reads and their replies are always encoded using the
+/// [`PROTOCOL_VERSION`] of each end, so both ends have to use the same version. The client sends
+/// [`CONNECTION_TO_READER`] (with [`VERSIONED_CONNECTION`] set) and its version, and the worker
+/// replies with its own version (see [`accept_reader_handshake`]). Workers that predate the
+/// handshake close the connection, since the first bytes sent by the client can't be the length
+/// prefix of a valid request.
+pub async fn reader_handshake<S>(stream: &mut S) -> io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    stream
+        .write_all(&[
+            CONNECTION_TO_READER | VERSIONED_CONNECTION,
+            PROTOCOL_VERSION,
+        ])
+        .await?;
+    stream.flush().await?;
+    let version = stream.read_u8().await.map_err(|e| {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "reader closed the connection during the version handshake; it likely predates \
+                 versioned reader connections",
+            )
+        } else {
+            e
+        }
+    })?;
+    check_reader_version(version)
+}
+
+/// Perform the worker side of the version handshake at the start of a connection to its readers.
+/// See [`reader_handshake`].
+pub async fn accept_reader_handshake<S>(stream: &mut S) -> io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut preamble = [0; 2];
+    stream.read_exact(&mut preamble).await?;
+    if preamble[0] != CONNECTION_TO_READER | VERSIONED_CONNECTION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "client didn't start the connection with a version handshake; it likely predates \
+             versioned reader connections",
+        ));
+    }
+    // Reply with our version even if it doesn't match, so the client can report why the
+    // connection failed
+    stream.write_all(&[PROTOCOL_VERSION]).await?;
+    stream.flush().await?;
+    check_reader_version(preamble[1])
+}
+
+fn check_reader_version(version: u8) -> io::Result<()> {
+    if version != PROTOCOL_VERSION {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "peer uses channel protocol version {version} for reads, but this version of \
+                 ReadySet uses version {PROTOCOL_VERSION}"
+            ),
+        ));
+    }
+    Ok(())
+}
 
 pub struct Remote;
 pub struct MaybeLocal;
@@ -33,6 +234,7 @@ pub struct DomainConnectionBuilder<D, T> {
     addr: SocketAddr,
     chan: Option<tokio::sync::mpsc::UnboundedSender<T>>,
     is_for_base: bool,
+    security: Option<ChannelSecurity>,
     _marker: D,
 }
 
@@ -65,6 +267,7 @@ impl<T> DomainConnectionBuilder<Remote, T> {
             chan: None,
             addr,
             is_for_base: true,
+            security: None,
             _marker: Remote,
         }
     }
@@ -80,6 +283,12 @@ impl<D, T> DomainConnectionBuilder<D, T> {
         self.sport = Some(sport);
         self
     }
+
+    /// Secure the connection with the given [`ChannelSecurity`], if any
+    pub fn with_security(mut self, security: Option<ChannelSecurity>) -> Self {
+        self.security = security;
+        self
+    }
 }
 
 impl<T> DomainConnectionBuilder<Remote, T>
@@ -89,32 +298,56 @@ where
     /// Establishes a TCP sink for an asynchronous context. The function may block for a long
     /// time while the connection is being established, be careful not to call it on our main Tokio
     /// executer, but only from inside a Domain thread.
+    ///
+    /// Packets sent on the returned sink are buffered until it's flushed, and then written to the
+    /// connection with vectored writes.
     pub fn build_async(
         self,
-    ) -> io::Result<AsyncBincodeWriter<BufWriter<tokio::net::TcpStream>, T, AsyncDestination>> {
+    ) -> io::Result<VectoredBincodeWriter<HandshakingStream<tokio::net::TcpStream>, T>> {
+        if let Some(security) = self.security {
+            // The TLS and secret handshakes can't be moved from a blocking connection to an
+            // asynchronous one, so perform them (and send the preamble) asynchronously, before the
+            // first packet is written
+            let s = tcp::connect_socket(self.sport, &self.addr)?;
+            s.set_nonblocking(true)?;
+            let s = tokio::net::TcpStream::from_std(s)?;
+            let preamble = Preamble::new(self.is_for_base).to_bytes();
+            let handshake = async move {
+                let mut s = security.connect(s).await?;
+                s.write_all(&preamble).await?;
+                s.flush().await?;
+                Ok::<_, io::Error>(s)
+            };
+            return Ok(HandshakingStream::new(handshake.boxed()).into());
+        }
+
         // TODO: async
         // we must currently write and call flush, because the remote end (currently) does a
         // synchronous read upon accepting a connection.
-        let s = self.build_sync()?.into_inner().into_inner()?;
+        let s = match self.build_sync()?.into_inner().into_inner()? {
+            secure::SyncChannelStream::Plain(s) => s,
+            secure::SyncChannelStream::Tls(_) | secure::SyncChannelStream::Authenticated(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Other,
+                    "unsecured connection was unexpectedly secured",
+                ))
+            }
+        };
 
         tokio::net::TcpStream::from_std(s)
-            .map(BufWriter::new)
-            .map(AsyncBincodeWriter::from)
-            .map(AsyncBincodeWriter::for_async)
+            .map(ChannelStream::Plain)
+            .map(HandshakingStream::from)
+            .map(VectoredBincodeWriter::from)
     }
 
     /// Establishes a TCP sink for a synchronous context. The function may block for a long
     /// time while the connection is being established, be careful not to call it on our main Tokio
     /// executer, but only from inside a Domain thread.
     pub fn build_sync(self) -> io::Result<TcpSender<T>> {
-        let mut s = TcpSender::connect_from(self.sport, &self.addr)?;
+        let mut s = TcpSender::connect_from(self.sport, &self.addr, self.security.as_ref())?;
         {
             let s = s.get_mut();
-            s.write_all(&[if self.is_for_base {
-                CONNECTION_FROM_BASE
-            } else {
-                CONNECTION_FROM_DOMAIN
-            }])?;
+            s.write_all(&Preamble::new(self.is_for_base).to_bytes())?;
             s.flush()?;
         }
 
@@ -159,6 +392,7 @@ where
                 chan: None,
                 addr: self.addr,
                 is_for_base: false,
+                security: self.security,
                 _marker: Remote,
             }
             .build_async()
@@ -175,6 +409,7 @@ where
                 chan: None,
                 addr: self.addr,
                 is_for_base: false,
+                security: self.security,
                 _marker: Remote,
             }
             .build_sync()
@@ -192,6 +427,9 @@ struct ChannelCoordinatorInner<K: Eq + Hash + Clone, T> {
 
 pub struct ChannelCoordinator<K: Eq + Hash + Clone, T> {
     inner: RwLock<ChannelCoordinatorInner<K, T>>,
+    /// Used to secure all remote connections made through, or accepted by users of, this
+    /// coordinator
+    security: Option<ChannelSecurity>,
 }
 
 impl<K: Eq + Hash + Clone, T> Default for ChannelCoordinator<K, T> {
@@ -202,14 +440,26 @@ impl<K: Eq + Hash + Clone, T> Default for ChannelCoordinator<K, T> {
 
 impl<K: Eq + Hash + Clone, T> ChannelCoordinator<K, T> {
     pub fn new() -> Self {
+        Self::with_security(None)
+    }
+
+    /// Construct a new [`ChannelCoordinator`] which secures remote connections with the given
+    /// [`ChannelSecurity`], if any
+    pub fn with_security(security: Option<ChannelSecurity>) -> Self {
         Self {
             inner: RwLock::new(ChannelCoordinatorInner {
                 addrs: Default::default(),
                 locals: Default::default(),
             }),
+            security,
         }
     }
 
+    /// Returns the [`ChannelSecurity`] used to secure remote connections, if any
+    pub fn security(&self) -> Option<&ChannelSecurity> {
+        self.security.as_ref()
+    }
+
     pub fn insert_remote(&self, key: K, addr: SocketAddr) -> ReadySetResult<()> {
         #[allow(clippy::expect_used)]
         // This can only fail if the mutex is poisoned, in which case we can't recover,
@@ -291,14 +541,143 @@ impl<K: Eq + Hash + Clone, T> ChannelCoordinator<K, T> {
                 addr: *addrs,
                 chan: guard.locals.get(key).cloned(),
                 is_for_base: false,
+                security: self.security.clone(),
                 _marker: MaybeLocal,
             }),
         }
     }
 
+    pub fn remove<Q>(&self, key: &Q)
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        #[allow(clippy::expect_used)]
+        // This can only fail if the mutex is poisoned, in which case we can't recover,
+        // so we allow to panic if that happens.
+        let mut guard = self.inner.write().expect("poisoned mutex");
+        guard.addrs.remove(key);
+        guard.locals.remove(key);
+    }
+
     pub fn clear(&self) {
         let mut guard = self.inner.write().expect("poisoned mutex");
         guard.addrs.clear();
         guard.locals.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn preamble_round_trip() {
+        for is_base in [true, false] {
+            let preamble = Preamble::new(is_base);
+            let bytes = preamble.to_bytes();
+            assert_eq!(Preamble::read(&mut &bytes[..]).await.unwrap(), preamble);
+        }
+    }
+
+    #[tokio::test]
+    async fn legacy_preamble() {
+        assert_eq!(
+            Preamble::read(&mut &[CONNECTION_FROM_BASE][..])
+                .await
+                .unwrap(),
+            Preamble {
+                is_base: true,
+                version: LEGACY_PROTOCOL_VERSION
+            }
+        );
+        assert_eq!(
+            Preamble::read(&mut &[CONNECTION_FROM_DOMAIN][..])
+                .await
+                .unwrap(),
+            Preamble {
+                is_base: false,
+                version: LEGACY_PROTOCOL_VERSION
+            }
+        );
+    }
+
+    #[test]
+    fn legacy_version_uses_legacy_preamble() {
+        assert_eq!(
+            Preamble {
+                is_base: true,
+                version: LEGACY_PROTOCOL_VERSION
+            }
+            .to_bytes(),
+            vec![CONNECTION_FROM_BASE]
+        );
+        assert_eq!(
+            Preamble {
+                is_base: false,
+                version: LEGACY_PROTOCOL_VERSION
+            }
+            .to_bytes(),
+            vec![CONNECTION_FROM_DOMAIN]
+        );
+    }
+
+    #[tokio::test]
+    async fn reader_handshake_same_version() {
+        let (mut client, mut server) = tokio::io::duplex(16);
+        let (client, server) = tokio::join!(
+            reader_handshake(&mut client),
+            accept_reader_handshake(&mut server)
+        );
+        client.unwrap();
+        server.unwrap();
+    }
+
+    #[tokio::test]
+    async fn reader_handshake_rejects_other_versions() {
+        for version in [PROTOCOL_VERSION - 1, PROTOCOL_VERSION + 1] {
+            let (mut client, mut server) = tokio::io::duplex(16);
+            client
+                .write_all(&[CONNECTION_TO_READER | VERSIONED_CONNECTION, version])
+                .await
+                .unwrap();
+            assert_eq!(
+                accept_reader_handshake(&mut server)
+                    .await
+                    .unwrap_err()
+                    .kind(),
+                io::ErrorKind::InvalidData
+            );
+            assert_eq!(client.read_u8().await.unwrap(), PROTOCOL_VERSION);
+        }
+    }
+
+    #[tokio::test]
+    async fn reader_handshake_rejects_unversioned_clients() {
+        let (mut client, mut server) = tokio::io::duplex(16);
+        // The start of the length prefix of a request sent by a client that predates the
+        // handshake
+        client.write_all(&[0, 0, 0, 42]).await.unwrap();
+        assert!(accept_reader_handshake(&mut server).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn reader_handshake_reports_unversioned_readers() {
+        let (mut client, server) = tokio::io::duplex(16);
+        drop(server);
+        assert!(reader_handshake(&mut client).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn rejects_newer_version() {
+        let bytes = Preamble {
+            is_base: false,
+            version: PROTOCOL_VERSION + 1,
+        }
+        .to_bytes();
+        assert_eq!(
+            Preamble::read(&mut &bytes[..]).await.unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
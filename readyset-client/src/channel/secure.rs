@@ -0,0 +1,1166 @@
+//! Authentication and encryption for connections to domains and readers.
+//!
+//! By default, connections between domains (and from clients writing to base tables or reading
+//! from readers) are plaintext, and anyone who can reach a domain's or reader's port can send
+//! packets to it. A [`ChannelSecurity`],
+//! configured identically on every ReadySet process in a deployment, can secure these connections
+//! in two ways:
+//!
+//! * With a **shared secret**, both ends of every connection prove to each other that they know the
+//!   secret with an HMAC-SHA256 challenge-response handshake before any packets are sent, so that
+//!   only processes configured with the secret can talk to domains.
+//! * With **TLS**, every connection is encrypted. Each process presents the configured certificate
+//!   when accepting a connection, and only accepts certificates signed by the configured CA, for
+//!   the configured server name, when opening one. The certificate, key and CA files are re-read
+//!   whenever they change on disk, so certificates can be rotated without restarting: new
+//!   connections use the new certificate, and existing connections are unaffected.
+//!
+//! When both are configured the secret handshake happens inside the TLS session, which together
+//! authenticates both ends of the connection. When only a secret is configured, the handshake
+//! also derives a key for each direction of the connection, and every frame sent after it carries
+//! an HMAC-SHA256 tag over its contents and position in the stream, so that packets can't be
+//! injected into, modified in or reordered on an authenticated connection. Those connections are
+//! still not encrypted.
+//!
+//! HTTP requests to the controller and worker endpoints of a ReadySet server are authenticated with
+//! the same secret: see [`ChannelSecurity::sign_request`] and [`ChannelSecurity::verify_request`].
+
+use std::fmt;
+use std::future::Future;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures_util::future::BoxFuture;
+use futures_util::ready;
+use hmac::{Hmac, Mac};
+use hyper::header::HeaderValue;
+use hyper::{HeaderMap, Method};
+use parking_lot::Mutex;
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The length of the random nonces exchanged during the secret handshake
+const NONCE_LEN: usize = 32;
+
+/// The length of the proofs of knowledge of the secret exchanged during the secret handshake, and
+/// of the tags of frames sent over connections authenticated only with the secret
+const PROOF_LEN: usize = 32;
+
+/// The length of the header of a frame sent over a connection authenticated only with the secret,
+/// which holds the length of the frame's payload
+const FRAME_HEADER_LEN: usize = 4;
+
+/// The maximum length of the payload of a single frame sent over a connection authenticated only
+/// with the secret. Larger writes are split into several frames.
+const MAX_FRAME_LEN: usize = 64 * 1024;
+
+/// The header carrying the time, in milliseconds since the Unix epoch, at which an HTTP request was
+/// signed with [`ChannelSecurity::sign_request`]
+pub const REQUEST_TIMESTAMP_HEADER: &str = "x-readyset-timestamp";
+
+/// The header carrying the signature added to an HTTP request by
+/// [`ChannelSecurity::sign_request`]
+pub const REQUEST_SIGNATURE_HEADER: &str = "x-readyset-signature";
+
+/// How far from the current time the time at which an HTTP request was signed may be for
+/// [`ChannelSecurity::verify_request`] to accept it
+const MAX_REQUEST_AGE: Duration = Duration::from_secs(60);
+
+/// The paths of the files used to set up TLS for channel connections. See the [module-level
+/// documentation](self) for more information.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsFiles {
+    /// The PEM-encoded certificate chain presented to peers
+    pub cert: PathBuf,
+    /// The PEM-encoded PKCS#8 private key for `cert`
+    pub key: PathBuf,
+    /// The PEM-encoded certificate of the CA which must have signed peers' certificates
+    pub ca: PathBuf,
+    /// The name which peers' certificates must be valid for
+    pub server_name: String,
+}
+
+/// TLS acceptor and connector built from a [`TlsFiles`], along with the modification times of
+/// the files at the time they were read
+struct LoadedTls {
+    modified: Vec<Option<SystemTime>>,
+    acceptor: native_tls::TlsAcceptor,
+    connector: native_tls::TlsConnector,
+}
+
+struct Tls {
+    files: TlsFiles,
+    loaded: Mutex<Option<LoadedTls>>,
+}
+
+impl Tls {
+    fn modified(&self) -> Vec<Option<SystemTime>> {
+        [&self.files.cert, &self.files.key, &self.files.ca]
+            .iter()
+            .map(|path| path.metadata().and_then(|m| m.modified()).ok())
+            .collect()
+    }
+
+    /// Returns a TLS acceptor and connector for the current contents of the configured files,
+    /// re-reading them if they've changed since they were last read
+    fn load(&self) -> io::Result<(native_tls::TlsAcceptor, native_tls::TlsConnector)> {
+        let modified = self.modified();
+        let mut loaded = self.loaded.lock();
+        if let Some(loaded) = &*loaded {
+            if loaded.modified == modified {
+                return Ok((loaded.acceptor.clone(), loaded.connector.clone()));
+            }
+        }
+
+        let read = |path: &Path| {
+            std::fs::read(path).map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("Could not read {}: {}", path.display(), e),
+                )
+            })
+        };
+        let tls_err = |e: native_tls::Error| io::Error::new(io::ErrorKind::InvalidInput, e);
+        let identity =
+            native_tls::Identity::from_pkcs8(&read(&self.files.cert)?, &read(&self.files.key)?)
+                .map_err(tls_err)?;
+        let ca = native_tls::Certificate::from_pem(&read(&self.files.ca)?).map_err(tls_err)?;
+
+        let acceptor = native_tls::TlsAcceptor::new(identity.clone()).map_err(tls_err)?;
+        let connector = native_tls::TlsConnector::builder()
+            .identity(identity)
+            .add_root_certificate(ca)
+            .disable_built_in_roots(true)
+            .build()
+            .map_err(tls_err)?;
+
+        *loaded = Some(LoadedTls {
+            modified,
+            acceptor: acceptor.clone(),
+            connector: connector.clone(),
+        });
+        Ok((acceptor, connector))
+    }
+}
+
+/// Configuration for authenticating and encrypting connections to domains. See the [module-level
+/// documentation](self) for more information.
+#[derive(Clone)]
+pub struct ChannelSecurity {
+    secret: Option<Arc<[u8]>>,
+    tls: Option<Arc<Tls>>,
+}
+
+impl fmt::Debug for ChannelSecurity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChannelSecurity")
+            .field("secret", &self.secret.as_ref().map(|_| "<redacted>"))
+            .field("tls", &self.tls.as_ref().map(|tls| &tls.files))
+            .finish()
+    }
+}
+
+impl PartialEq for ChannelSecurity {
+    fn eq(&self, other: &Self) -> bool {
+        self.secret == other.secret
+            && self.tls.as_ref().map(|tls| &tls.files) == other.tls.as_ref().map(|tls| &tls.files)
+    }
+}
+
+impl Eq for ChannelSecurity {}
+
+impl ChannelSecurity {
+    /// Construct a new [`ChannelSecurity`] authenticating connections with the given shared
+    /// secret, if any, and encrypting them with TLS using the given files, if any. Returns `None`
+    /// if neither is given.
+    pub fn new(secret: Option<&str>, tls: Option<TlsFiles>) -> Option<Self> {
+        if secret.is_none() && tls.is_none() {
+            return None;
+        }
+        Some(Self {
+            secret: secret.map(|s| Arc::from(s.as_bytes())),
+            tls: tls.map(|files| {
+                Arc::new(Tls {
+                    files,
+                    loaded: Default::default(),
+                })
+            }),
+        })
+    }
+
+    /// Secure a newly accepted connection, performing the server side of the TLS and secret
+    /// handshakes
+    pub async fn accept<S>(&self, stream: S) -> io::Result<ChannelStream<S>>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut stream = match &self.tls {
+            Some(tls) => {
+                let (acceptor, _) = tls.load()?;
+                let stream = tokio_native_tls::TlsAcceptor::from(acceptor)
+                    .accept(stream)
+                    .await
+                    .map_err(|e| io::Error::new(io::ErrorKind::ConnectionAborted, e))?;
+                ChannelStream::Tls(Box::new(stream))
+            }
+            None => ChannelStream::Plain(stream),
+        };
+
+        if let Some(secret) = &self.secret {
+            let mut client_nonce = [0; NONCE_LEN];
+            stream.read_exact(&mut client_nonce).await?;
+            let server_nonce: [u8; NONCE_LEN] = rand::random();
+            stream.write_all(&server_nonce).await?;
+            stream
+                .write_all(&prove(secret, b"server", &client_nonce, &server_nonce))
+                .await?;
+            stream.flush().await?;
+
+            let mut client_proof = [0; PROOF_LEN];
+            stream.read_exact(&mut client_proof).await?;
+            verify(
+                secret,
+                b"client",
+                &server_nonce,
+                &client_nonce,
+                &client_proof,
+            )?;
+
+            stream = stream.authenticate_frames(FrameAuth::new(
+                secret,
+                false,
+                &client_nonce,
+                &server_nonce,
+            ));
+        }
+
+        Ok(stream)
+    }
+
+    /// Secure a newly opened connection, performing the client side of the TLS and secret
+    /// handshakes
+    pub async fn connect<S>(&self, stream: S) -> io::Result<ChannelStream<S>>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut stream = match &self.tls {
+            Some(tls) => {
+                let (_, connector) = tls.load()?;
+                let stream = tokio_native_tls::TlsConnector::from(connector)
+                    .connect(&tls.files.server_name, stream)
+                    .await
+                    .map_err(|e| io::Error::new(io::ErrorKind::ConnectionAborted, e))?;
+                ChannelStream::Tls(Box::new(stream))
+            }
+            None => ChannelStream::Plain(stream),
+        };
+
+        if let Some(secret) = &self.secret {
+            let client_nonce: [u8; NONCE_LEN] = rand::random();
+            stream.write_all(&client_nonce).await?;
+            stream.flush().await?;
+
+            let mut server_nonce = [0; NONCE_LEN];
+            stream.read_exact(&mut server_nonce).await?;
+            let mut server_proof = [0; PROOF_LEN];
+            stream.read_exact(&mut server_proof).await?;
+            verify(
+                secret,
+                b"server",
+                &client_nonce,
+                &server_nonce,
+                &server_proof,
+            )?;
+
+            stream
+                .write_all(&prove(secret, b"client", &server_nonce, &client_nonce))
+                .await?;
+            stream.flush().await?;
+
+            stream = stream.authenticate_frames(FrameAuth::new(
+                secret,
+                true,
+                &client_nonce,
+                &server_nonce,
+            ));
+        }
+
+        Ok(stream)
+    }
+
+    /// Secure a newly opened blocking connection, performing the client side of the TLS and secret
+    /// handshakes
+    pub fn connect_sync(&self, stream: TcpStream) -> io::Result<SyncChannelStream> {
+        let mut stream = match &self.tls {
+            Some(tls) => {
+                let (_, connector) = tls.load()?;
+                let stream = connector
+                    .connect(&tls.files.server_name, stream)
+                    .map_err(|e| io::Error::new(io::ErrorKind::ConnectionAborted, e.to_string()))?;
+                SyncChannelStream::Tls(Box::new(stream))
+            }
+            None => SyncChannelStream::Plain(stream),
+        };
+
+        if let Some(secret) = &self.secret {
+            let client_nonce: [u8; NONCE_LEN] = rand::random();
+            stream.write_all(&client_nonce)?;
+            stream.flush()?;
+
+            let mut server_nonce = [0; NONCE_LEN];
+            stream.read_exact(&mut server_nonce)?;
+            let mut server_proof = [0; PROOF_LEN];
+            stream.read_exact(&mut server_proof)?;
+            verify(
+                secret,
+                b"server",
+                &client_nonce,
+                &server_nonce,
+                &server_proof,
+            )?;
+
+            stream.write_all(&prove(secret, b"client", &server_nonce, &client_nonce))?;
+            stream.flush()?;
+
+            stream = stream.authenticate_frames(FrameAuth::new(
+                secret,
+                true,
+                &client_nonce,
+                &server_nonce,
+            ));
+        }
+
+        Ok(stream)
+    }
+
+    /// Sign an HTTP request to the controller or worker endpoints of a ReadySet server with the
+    /// shared secret, if one is configured, by adding the [`REQUEST_TIMESTAMP_HEADER`] and
+    /// [`REQUEST_SIGNATURE_HEADER`] headers to `headers`. The signature covers the request's
+    /// method, path (including its query string), body and the time at which it was signed.
+    pub fn sign_request(
+        &self,
+        method: &Method,
+        path_and_query: &str,
+        body: &[u8],
+        headers: &mut HeaderMap,
+    ) {
+        let secret = match &self.secret {
+            Some(secret) => secret,
+            None => return,
+        };
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let signature =
+            request_signature(secret, method, path_and_query, &timestamp.to_string(), body);
+        #[allow(clippy::expect_used)] // Hex digits are all valid in header values
+        let signature =
+            HeaderValue::from_str(&signature).expect("hex strings are valid header values");
+        headers.insert(REQUEST_TIMESTAMP_HEADER, HeaderValue::from(timestamp));
+        headers.insert(REQUEST_SIGNATURE_HEADER, signature);
+    }
+
+    /// Returns true if HTTP requests are signed by [`Self::sign_request`], and so must be checked
+    /// with [`Self::verify_request`]
+    pub fn signs_requests(&self) -> bool {
+        self.secret.is_some()
+    }
+
+    /// Check that an HTTP request was signed by [`Self::sign_request`] with the shared secret, if
+    /// one is configured, less than a minute ago (to limit how long a captured request can be
+    /// replayed for)
+    pub fn verify_request(
+        &self,
+        method: &Method,
+        path_and_query: &str,
+        body: &[u8],
+        headers: &HeaderMap,
+    ) -> io::Result<()> {
+        let secret = match &self.secret {
+            Some(secret) => secret,
+            None => return Ok(()),
+        };
+        let denied = |msg: &str| io::Error::new(io::ErrorKind::PermissionDenied, msg.to_owned());
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|v: &HeaderValue| v.to_str().ok())
+                .ok_or_else(|| denied("request is not signed"))
+        };
+        let timestamp = header(REQUEST_TIMESTAMP_HEADER)?;
+        let signature = header(REQUEST_SIGNATURE_HEADER)?;
+
+        let expected = request_signature(secret, method, path_and_query, timestamp, body);
+        if !bool::from(expected.as_bytes().ct_eq(signature.as_bytes())) {
+            return Err(denied("request signature is invalid"));
+        }
+
+        let signed_at = timestamp
+            .parse::<u64>()
+            .map(|millis| UNIX_EPOCH + Duration::from_millis(millis))
+            .map_err(|_| denied("request timestamp is invalid"))?;
+        let now = SystemTime::now();
+        let age = now
+            .duration_since(signed_at)
+            .or_else(|_| signed_at.duration_since(now))
+            .unwrap_or_default();
+        if age > MAX_REQUEST_AGE {
+            return Err(denied("request was signed too long ago"));
+        }
+        Ok(())
+    }
+}
+
+/// The signature of an HTTP request, as a hex string. See [`ChannelSecurity::sign_request`].
+fn request_signature(
+    secret: &[u8],
+    method: &Method,
+    path_and_query: &str,
+    timestamp: &str,
+    body: &[u8],
+) -> String {
+    #[allow(clippy::expect_used)] // HMAC accepts keys of any length
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC can take a key of any size");
+    for part in [
+        b"request".as_slice(),
+        method.as_str().as_bytes(),
+        path_and_query.as_bytes(),
+        timestamp.as_bytes(),
+    ] {
+        mac.update(&(part.len() as u64).to_be_bytes());
+        mac.update(part);
+    }
+    mac.update(body);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn mac(secret: &[u8], role: &[u8], first_nonce: &[u8], second_nonce: &[u8]) -> HmacSha256 {
+    #[allow(clippy::expect_used)] // HMAC accepts keys of any length
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC can take a key of any size");
+    mac.update(role);
+    mac.update(first_nonce);
+    mac.update(second_nonce);
+    mac
+}
+
+/// Prove knowledge of `secret` to a peer, in the given role, over the given pair of nonces
+fn prove(secret: &[u8], role: &[u8], first_nonce: &[u8], second_nonce: &[u8]) -> [u8; PROOF_LEN] {
+    let mut proof = [0; PROOF_LEN];
+    proof.copy_from_slice(
+        &mac(secret, role, first_nonce, second_nonce)
+            .finalize()
+            .into_bytes(),
+    );
+    proof
+}
+
+/// Check, in constant time, that `proof` proves knowledge of `secret` in the given role over the
+/// given pair of nonces
+fn verify(
+    secret: &[u8],
+    role: &[u8],
+    first_nonce: &[u8],
+    second_nonce: &[u8],
+    proof: &[u8],
+) -> io::Result<()> {
+    let expected = prove(secret, role, first_nonce, second_nonce);
+    if bool::from(expected.ct_eq(proof)) {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "peer failed to authenticate with the channel secret",
+        ))
+    }
+}
+
+/// The keys and sequence numbers used to tag and check the frames sent in each direction over a
+/// connection authenticated only with the shared secret
+struct FrameAuth {
+    send_key: [u8; PROOF_LEN],
+    send_seq: u64,
+    recv_key: [u8; PROOF_LEN],
+    recv_seq: u64,
+}
+
+impl FrameAuth {
+    /// Derive the frame keys for one end of a connection from the secret and the nonces exchanged
+    /// during the secret handshake
+    fn new(secret: &[u8], is_client: bool, client_nonce: &[u8], server_nonce: &[u8]) -> Self {
+        let client_key = prove(secret, b"client frames", client_nonce, server_nonce);
+        let server_key = prove(secret, b"server frames", client_nonce, server_nonce);
+        let (send_key, recv_key) = if is_client {
+            (client_key, server_key)
+        } else {
+            (server_key, client_key)
+        };
+        Self {
+            send_key,
+            send_seq: 0,
+            recv_key,
+            recv_seq: 0,
+        }
+    }
+
+    fn tag(key: &[u8], seq: u64, payload: &[u8]) -> [u8; PROOF_LEN] {
+        #[allow(clippy::expect_used)] // HMAC accepts keys of any length
+        let mut mac = HmacSha256::new_from_slice(key).expect("HMAC can take a key of any size");
+        mac.update(&seq.to_be_bytes());
+        mac.update(payload);
+        let mut tag = [0; PROOF_LEN];
+        tag.copy_from_slice(&mac.finalize().into_bytes());
+        tag
+    }
+
+    /// Append a frame holding up to [`MAX_FRAME_LEN`] bytes of the concatenation of `bufs` to
+    /// `out`, returning the number of bytes in the frame
+    fn seal(&mut self, bufs: &[io::IoSlice<'_>], out: &mut Vec<u8>) -> usize {
+        let header = out.len();
+        out.extend_from_slice(&[0; FRAME_HEADER_LEN]);
+        let start = out.len();
+        for buf in bufs {
+            let remaining = MAX_FRAME_LEN - (out.len() - start);
+            out.extend_from_slice(&buf[..buf.len().min(remaining)]);
+        }
+        let len = out.len() - start;
+        out[header..start].copy_from_slice(&(len as u32).to_be_bytes());
+        let tag = Self::tag(&self.send_key, self.send_seq, &out[start..]);
+        out.extend_from_slice(&tag);
+        self.send_seq += 1;
+        len
+    }
+
+    /// If `buf` starts with a complete frame, check its tag, remove it from `buf`, and return its
+    /// payload
+    fn open(&mut self, buf: &mut Vec<u8>) -> io::Result<Option<Vec<u8>>> {
+        if buf.len() < FRAME_HEADER_LEN {
+            return Ok(None);
+        }
+        let mut len = [0; FRAME_HEADER_LEN];
+        len.copy_from_slice(&buf[..FRAME_HEADER_LEN]);
+        let len = u32::from_be_bytes(len) as usize;
+        if len > MAX_FRAME_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "peer sent an oversized frame",
+            ));
+        }
+        if buf.len() < FRAME_HEADER_LEN + len + PROOF_LEN {
+            return Ok(None);
+        }
+
+        let mut frame = buf.drain(..FRAME_HEADER_LEN + len + PROOF_LEN);
+        let payload: Vec<u8> = frame.by_ref().skip(FRAME_HEADER_LEN).take(len).collect();
+        let tag: Vec<u8> = frame.collect();
+        let expected = Self::tag(&self.recv_key, self.recv_seq, &payload);
+        if !bool::from(expected.ct_eq(tag.as_slice())) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "peer sent a frame which failed authentication",
+            ));
+        }
+        self.recv_seq += 1;
+        Ok(Some(payload))
+    }
+}
+
+/// A connection authenticated only with the shared secret, which splits the data written to it
+/// into frames tagged with [`FrameAuth`], and checks the tag of every frame read from it
+pub struct AuthenticatedStream<S> {
+    inner: S,
+    auth: FrameAuth,
+    /// Sealed frames which haven't been written to `inner` yet
+    write_buf: Vec<u8>,
+    /// How much of `write_buf` has been written to `inner`
+    write_pos: usize,
+    /// Data read from `inner` which doesn't make up a complete frame yet
+    read_buf: Vec<u8>,
+    /// The payload of the last frame read, and how much of it has been returned to the reader
+    payload: Vec<u8>,
+    payload_pos: usize,
+}
+
+impl<S: fmt::Debug> fmt::Debug for AuthenticatedStream<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthenticatedStream")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> AuthenticatedStream<S> {
+    fn new(inner: S, auth: FrameAuth) -> Self {
+        Self {
+            inner,
+            auth,
+            write_buf: Vec::new(),
+            write_pos: 0,
+            read_buf: Vec::new(),
+            payload: Vec::new(),
+            payload_pos: 0,
+        }
+    }
+
+    /// Returns a reference to the underlying connection
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Copy as much of the payload of the last frame read as fits into `buf`, returning the
+    /// number of bytes copied
+    fn read_payload(&mut self, buf: &mut [u8]) -> usize {
+        let n = buf.len().min(self.payload.len() - self.payload_pos);
+        buf[..n].copy_from_slice(&self.payload[self.payload_pos..self.payload_pos + n]);
+        self.payload_pos += n;
+        n
+    }
+
+    /// Check and unwrap the next complete frame in `read_buf`, returning false if there isn't one
+    fn open_frame(&mut self) -> io::Result<bool> {
+        match self.auth.open(&mut self.read_buf)? {
+            Some(payload) => {
+                self.payload = payload;
+                self.payload_pos = 0;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Buffer `chunk`, which was just read from `inner`, returning false if it's empty because the
+    /// stream has ended
+    fn buffer_read(&mut self, chunk: &[u8]) -> io::Result<bool> {
+        if chunk.is_empty() {
+            if self.read_buf.is_empty() {
+                return Ok(false);
+            }
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.read_buf.extend_from_slice(chunk);
+        Ok(true)
+    }
+
+    /// Seal up to [`MAX_FRAME_LEN`] bytes of `bufs` into a frame, returning the number of bytes
+    /// sealed
+    fn seal_frame(&mut self, bufs: &[io::IoSlice<'_>]) -> usize {
+        if self.write_pos == self.write_buf.len() {
+            self.write_buf.clear();
+            self.write_pos = 0;
+        }
+        self.auth.seal(bufs, &mut self.write_buf)
+    }
+}
+
+impl<S> AuthenticatedStream<S>
+where
+    S: AsyncWrite + Unpin,
+{
+    /// Write all the sealed frames in `write_buf` to `inner`
+    fn poll_write_frames(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.write_pos < self.write_buf.len() {
+            let n = ready!(
+                Pin::new(&mut self.inner).poll_write(cx, &self.write_buf[self.write_pos..])
+            )?;
+            if n == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.write_pos += n;
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S> AsyncRead for AuthenticatedStream<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        loop {
+            if this.payload_pos < this.payload.len() {
+                let n = this.read_payload(buf.initialize_unfilled());
+                buf.advance(n);
+                return Poll::Ready(Ok(()));
+            }
+            if this.open_frame()? {
+                continue;
+            }
+
+            let mut chunk = [0; 8192];
+            let mut chunk_buf = ReadBuf::new(&mut chunk);
+            ready!(Pin::new(&mut this.inner).poll_read(cx, &mut chunk_buf))?;
+            if !this.buffer_read(chunk_buf.filled())? {
+                return Poll::Ready(Ok(()));
+            }
+        }
+    }
+}
+
+impl<S> AsyncWrite for AuthenticatedStream<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_write_vectored(cx, &[io::IoSlice::new(buf)])
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_frames(cx))?;
+        if bufs.iter().all(|buf| buf.is_empty()) {
+            return Poll::Ready(Ok(0));
+        }
+        let n = this.seal_frame(bufs);
+        // Start writing the frame now, but it's buffered if that would block, so the write has
+        // succeeded either way
+        if let Poll::Ready(Err(e)) = this.poll_write_frames(cx) {
+            return Poll::Ready(Err(e));
+        }
+        Poll::Ready(Ok(n))
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        true
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_frames(cx))?;
+        Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_frames(cx))?;
+        Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+impl<S: Read> Read for AuthenticatedStream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.payload_pos < self.payload.len() {
+                return Ok(self.read_payload(buf));
+            }
+            if self.open_frame()? {
+                continue;
+            }
+
+            let mut chunk = [0; 8192];
+            let n = self.inner.read(&mut chunk)?;
+            if !self.buffer_read(&chunk[..n])? {
+                return Ok(0);
+            }
+        }
+    }
+}
+
+impl<S: Write> Write for AuthenticatedStream<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let n = self.seal_frame(&[io::IoSlice::new(buf)]);
+        self.inner.write_all(&self.write_buf[self.write_pos..])?;
+        self.write_pos = self.write_buf.len();
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// An asynchronous connection to or from a domain, which may be encrypted with TLS
+#[derive(Debug)]
+pub enum ChannelStream<S> {
+    /// An unencrypted connection
+    Plain(S),
+    /// A connection encrypted with TLS
+    Tls(Box<tokio_native_tls::TlsStream<S>>),
+    /// An unencrypted connection whose frames are authenticated with the shared secret
+    Authenticated(Box<AuthenticatedStream<S>>),
+}
+
+impl<S> ChannelStream<S> {
+    /// Authenticate every frame sent over this connection with `auth`, if it isn't encrypted
+    fn authenticate_frames(self, auth: FrameAuth) -> Self {
+        match self {
+            ChannelStream::Plain(s) => {
+                ChannelStream::Authenticated(Box::new(AuthenticatedStream::new(s, auth)))
+            }
+            stream => stream,
+        }
+    }
+}
+
+impl<S> AsyncRead for ChannelStream<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            ChannelStream::Plain(s) => Pin::new(s).poll_read(cx, buf),
+            ChannelStream::Tls(s) => Pin::new(s).poll_read(cx, buf),
+            ChannelStream::Authenticated(s) => Pin::new(s).poll_read(cx, buf),
+        }
+    }
+}
+
+impl<S> AsyncWrite for ChannelStream<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            ChannelStream::Plain(s) => Pin::new(s).poll_write(cx, buf),
+            ChannelStream::Tls(s) => Pin::new(s).poll_write(cx, buf),
+            ChannelStream::Authenticated(s) => Pin::new(s).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            ChannelStream::Plain(s) => Pin::new(s).poll_write_vectored(cx, bufs),
+            ChannelStream::Tls(s) => Pin::new(s).poll_write_vectored(cx, bufs),
+            ChannelStream::Authenticated(s) => Pin::new(s).poll_write_vectored(cx, bufs),
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        match self {
+            ChannelStream::Plain(s) => s.is_write_vectored(),
+            ChannelStream::Tls(s) => s.is_write_vectored(),
+            ChannelStream::Authenticated(s) => s.is_write_vectored(),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            ChannelStream::Plain(s) => Pin::new(s).poll_flush(cx),
+            ChannelStream::Tls(s) => Pin::new(s).poll_flush(cx),
+            ChannelStream::Authenticated(s) => Pin::new(s).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            ChannelStream::Plain(s) => Pin::new(s).poll_shutdown(cx),
+            ChannelStream::Tls(s) => Pin::new(s).poll_shutdown(cx),
+            ChannelStream::Authenticated(s) => Pin::new(s).poll_shutdown(cx),
+        }
+    }
+}
+
+/// A [`ChannelStream`] which may still be performing its handshakes. Reads and writes wait for the
+/// handshakes to finish.
+pub struct HandshakingStream<S> {
+    state: HandshakeState<S>,
+}
+
+enum HandshakeState<S> {
+    Handshaking(BoxFuture<'static, io::Result<ChannelStream<S>>>),
+    Ready(ChannelStream<S>),
+    Failed,
+}
+
+impl<S> From<ChannelStream<S>> for HandshakingStream<S> {
+    fn from(stream: ChannelStream<S>) -> Self {
+        Self {
+            state: HandshakeState::Ready(stream),
+        }
+    }
+}
+
+impl<S> HandshakingStream<S> {
+    /// Construct a new [`HandshakingStream`] which becomes ready once `handshake` resolves
+    pub fn new(handshake: BoxFuture<'static, io::Result<ChannelStream<S>>>) -> Self {
+        Self {
+            state: HandshakeState::Handshaking(handshake),
+        }
+    }
+
+    fn poll_stream(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<&mut ChannelStream<S>>> {
+        if let HandshakeState::Handshaking(handshake) = &mut self.state {
+            match ready!(handshake.as_mut().poll(cx)) {
+                Ok(stream) => self.state = HandshakeState::Ready(stream),
+                Err(e) => {
+                    self.state = HandshakeState::Failed;
+                    return Poll::Ready(Err(e));
+                }
+            }
+        }
+
+        match &mut self.state {
+            HandshakeState::Ready(stream) => Poll::Ready(Ok(stream)),
+            _ => Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "channel handshake previously failed",
+            ))),
+        }
+    }
+}
+
+impl<S> AsyncWrite for HandshakingStream<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let stream = ready!(self.get_mut().poll_stream(cx))?;
+        Pin::new(stream).poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let stream = ready!(self.get_mut().poll_stream(cx))?;
+        Pin::new(stream).poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        match &self.state {
+            HandshakeState::Ready(stream) => stream.is_write_vectored(),
+            // Plain, TLS and authenticated streams over TCP all support vectored writes
+            _ => true,
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let stream = ready!(self.get_mut().poll_stream(cx))?;
+        Pin::new(stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let stream = ready!(self.get_mut().poll_stream(cx))?;
+        Pin::new(stream).poll_shutdown(cx)
+    }
+}
+
+/// A blocking connection to a domain, which may be encrypted with TLS
+pub enum SyncChannelStream {
+    /// An unencrypted connection
+    Plain(TcpStream),
+    /// A connection encrypted with TLS
+    Tls(Box<native_tls::TlsStream<TcpStream>>),
+    /// An unencrypted connection whose frames are authenticated with the shared secret
+    Authenticated(Box<AuthenticatedStream<TcpStream>>),
+}
+
+impl SyncChannelStream {
+    /// Authenticate every frame sent over this connection with `auth`, if it isn't encrypted
+    fn authenticate_frames(self, auth: FrameAuth) -> Self {
+        match self {
+            SyncChannelStream::Plain(s) => {
+                SyncChannelStream::Authenticated(Box::new(AuthenticatedStream::new(s, auth)))
+            }
+            stream => stream,
+        }
+    }
+
+    /// Returns a reference to the underlying TCP stream
+    pub fn get_ref(&self) -> &TcpStream {
+        match self {
+            SyncChannelStream::Plain(s) => s,
+            SyncChannelStream::Tls(s) => s.get_ref(),
+            SyncChannelStream::Authenticated(s) => s.get_ref(),
+        }
+    }
+}
+
+impl Read for SyncChannelStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            SyncChannelStream::Plain(s) => s.read(buf),
+            SyncChannelStream::Tls(s) => s.read(buf),
+            SyncChannelStream::Authenticated(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for SyncChannelStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            SyncChannelStream::Plain(s) => s.write(buf),
+            SyncChannelStream::Tls(s) => s.write(buf),
+            SyncChannelStream::Authenticated(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            SyncChannelStream::Plain(s) => s.flush(),
+            SyncChannelStream::Tls(s) => s.flush(),
+            SyncChannelStream::Authenticated(s) => s.flush(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn handshake(
+        server_secret: &str,
+        client_secret: &str,
+    ) -> (io::Result<()>, io::Result<()>) {
+        let server = ChannelSecurity::new(Some(server_secret), None).unwrap();
+        let client = ChannelSecurity::new(Some(client_secret), None).unwrap();
+        let (server_stream, client_stream) = tokio::io::duplex(1024);
+        let (server_res, client_res) =
+            tokio::join!(server.accept(server_stream), client.connect(client_stream));
+        (server_res.map(|_| ()), client_res.map(|_| ()))
+    }
+
+    #[tokio::test]
+    async fn secret_handshake_succeeds_with_same_secret() {
+        let (server, client) = handshake("secret", "secret").await;
+        server.unwrap();
+        client.unwrap();
+    }
+
+    #[tokio::test]
+    async fn secret_handshake_fails_with_different_secret() {
+        let (server, client) = handshake("secret", "wrong").await;
+        assert_eq!(client.unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+        // The client hangs up without proving itself once it fails to authenticate the server
+        server.unwrap_err();
+    }
+
+    #[tokio::test]
+    async fn secret_only_connections_authenticate_frames() {
+        let server = ChannelSecurity::new(Some("secret"), None).unwrap();
+        let client = ChannelSecurity::new(Some("secret"), None).unwrap();
+        let (server_stream, client_stream) = tokio::io::duplex(1024);
+        let (server_res, client_res) =
+            tokio::join!(server.accept(server_stream), client.connect(client_stream));
+        let mut server_stream = server_res.unwrap();
+        let mut client_stream = client_res.unwrap();
+        assert!(matches!(server_stream, ChannelStream::Authenticated(_)));
+        assert!(matches!(client_stream, ChannelStream::Authenticated(_)));
+
+        // Larger than a single frame, and than the duplex buffer
+        let data = (0..MAX_FRAME_LEN * 2 + 10)
+            .map(|i| i as u8)
+            .collect::<Vec<_>>();
+        let write = async {
+            client_stream.write_all(&data).await.unwrap();
+            client_stream.flush().await.unwrap();
+        };
+        let mut received = vec![0; data.len()];
+        let read = server_stream.read_exact(&mut received);
+        let (_, read_res) = tokio::join!(write, read);
+        read_res.unwrap();
+        assert_eq!(received, data);
+
+        server_stream.write_all(b"reply").await.unwrap();
+        server_stream.flush().await.unwrap();
+        let mut reply = [0; 5];
+        client_stream.read_exact(&mut reply).await.unwrap();
+        assert_eq!(&reply, b"reply");
+    }
+
+    #[test]
+    fn modified_or_replayed_frames_are_rejected() {
+        let mut client = FrameAuth::new(b"secret", true, b"client nonce", b"server nonce");
+        let mut server = FrameAuth::new(b"secret", false, b"client nonce", b"server nonce");
+        let mut frame = vec![];
+        client.seal(&[io::IoSlice::new(b"packet")], &mut frame);
+
+        let mut modified = frame.clone();
+        modified[FRAME_HEADER_LEN] ^= 1;
+        assert_eq!(
+            server.open(&mut modified).unwrap_err().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+
+        let mut buf = frame.clone();
+        assert_eq!(server.open(&mut buf).unwrap().unwrap(), b"packet");
+        assert!(buf.is_empty());
+        // The frame can't be sent again
+        let mut replayed = frame;
+        assert_eq!(
+            server.open(&mut replayed).unwrap_err().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+    }
+
+    #[test]
+    fn signed_requests() {
+        let security = ChannelSecurity::new(Some("secret"), None).unwrap();
+        let mut headers = HeaderMap::new();
+        security.sign_request(&Method::POST, "/extend_recipe", b"body", &mut headers);
+        security
+            .verify_request(&Method::POST, "/extend_recipe", b"body", &headers)
+            .unwrap();
+
+        for (method, path, body) in [
+            (Method::GET, "/extend_recipe", b"body".as_slice()),
+            (Method::POST, "/remove_query", b"body".as_slice()),
+            (Method::POST, "/extend_recipe", b"other".as_slice()),
+        ] {
+            assert_eq!(
+                security
+                    .verify_request(&method, path, body, &headers)
+                    .unwrap_err()
+                    .kind(),
+                io::ErrorKind::PermissionDenied
+            );
+        }
+
+        let other = ChannelSecurity::new(Some("wrong"), None).unwrap();
+        other
+            .verify_request(&Method::POST, "/extend_recipe", b"body", &headers)
+            .unwrap_err();
+        security
+            .verify_request(&Method::POST, "/extend_recipe", b"body", &HeaderMap::new())
+            .unwrap_err();
+    }
+
+    #[test]
+    fn no_security() {
+        assert!(ChannelSecurity::new(None, None).is_none());
+    }
+}
@@ -13,13 +13,14 @@ use futures_util::ready;
 use futures_util::sink::Sink;
 use futures_util::stream::Stream;
 use pin_project::pin_project;
-use readyset_errors::ReadySetError;
+use readyset_errors::{ReadySetError, ReadySetResult};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use socket2::{Domain, Protocol, SockAddr, Socket, Type};
 use thiserror::Error;
 use tokio::io::{AsyncRead, AsyncWrite};
 
+use super::secure::{ChannelSecurity, SyncChannelStream};
 use crate::Tagged;
 
 #[derive(Debug, Error)]
@@ -65,7 +66,7 @@ macro_rules! poisoning_try {
 }
 
 pub struct TcpSender<T> {
-    stream: BufStream<std::net::TcpStream>,
+    stream: BufStream<SyncChannelStream>,
     poisoned: bool,
 
     phantom: PhantomData<T>,
@@ -73,41 +74,48 @@ pub struct TcpSender<T> {
 
 impl<T: Serialize> TcpSender<T> {
     pub fn new(stream: std::net::TcpStream) -> Result<Self, io::Error> {
-        stream.set_nodelay(true).map(|_| Self {
+        stream.set_nodelay(true)?;
+        Ok(Self::from_channel_stream(SyncChannelStream::Plain(stream)))
+    }
+
+    fn from_channel_stream(stream: SyncChannelStream) -> Self {
+        Self {
             stream: BufStream::new(stream),
             poisoned: false,
             phantom: PhantomData,
-        })
+        }
     }
 
-    pub(crate) fn connect_from(sport: Option<u16>, addr: &SocketAddr) -> Result<Self, io::Error> {
-        let bind_addr = std::net::SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, sport.unwrap_or(0));
-        let s = Socket::new(Domain::IPV4, Type::STREAM, Some(Protocol::TCP))?;
-        s.set_reuse_address(true)?;
-        s.bind(&SockAddr::from(bind_addr))?;
-        s.set_nodelay(true)?;
-        s.connect(&SockAddr::from(*addr))?;
-        Self::new(std::net::TcpStream::from(s))
+    pub(crate) fn connect_from(
+        sport: Option<u16>,
+        addr: &SocketAddr,
+        security: Option<&ChannelSecurity>,
+    ) -> Result<Self, io::Error> {
+        let s = connect_socket(sport, addr)?;
+        match security {
+            Some(security) => Ok(Self::from_channel_stream(security.connect_sync(s)?)),
+            None => Self::new(s),
+        }
     }
 
     pub fn connect(addr: &SocketAddr) -> Result<Self, io::Error> {
-        Self::connect_from(None, addr)
+        Self::connect_from(None, addr, None)
     }
 
-    pub fn get_mut(&mut self) -> &mut BufStream<std::net::TcpStream> {
+    pub fn get_mut(&mut self) -> &mut BufStream<SyncChannelStream> {
         &mut self.stream
     }
 
-    pub(crate) fn into_inner(self) -> BufStream<std::net::TcpStream> {
+    pub(crate) fn into_inner(self) -> BufStream<SyncChannelStream> {
         self.stream
     }
 
     pub fn local_addr(&self) -> io::Result<SocketAddr> {
-        self.stream.get_ref().local_addr()
+        self.stream.get_ref().get_ref().local_addr()
     }
 
     pub fn peer_addr(&self) -> io::Result<SocketAddr> {
-        self.stream.get_ref().peer_addr()
+        self.stream.get_ref().get_ref().peer_addr()
     }
 
     /// Send a message on this channel. Ownership isn't actually required, but is taken anyway to
@@ -141,6 +149,20 @@ impl<T: Serialize> TcpSender<T> {
     }
 }
 
+/// Open a blocking TCP connection to `addr`, optionally from the given source port
+pub(crate) fn connect_socket(
+    sport: Option<u16>,
+    addr: &SocketAddr,
+) -> Result<std::net::TcpStream, io::Error> {
+    let bind_addr = std::net::SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, sport.unwrap_or(0));
+    let s = Socket::new(Domain::IPV4, Type::STREAM, Some(Protocol::TCP))?;
+    s.set_reuse_address(true)?;
+    s.bind(&SockAddr::from(bind_addr))?;
+    s.set_nodelay(true)?;
+    s.connect(&SockAddr::from(*addr))?;
+    Ok(std::net::TcpStream::from(s))
+}
+
 impl<T: Serialize> super::Sender for TcpSender<T> {
     type Item = T;
     fn send(&mut self, t: T) -> Result<(), SendError> {
@@ -161,11 +183,13 @@ pub enum RecvError {
     DeserializationError(bincode::Error),
 }
 
+/// A stream of packets sent to a domain. Writes to base tables sent on the stream are acknowledged
+/// with the result of processing them.
 #[pin_project(project = DualTcpStreamProj)]
 pub enum DualTcpStream<S, T, T2, D> {
-    Passthrough(#[pin] AsyncBincodeStream<S, T, Tagged<()>, D>),
+    Passthrough(#[pin] AsyncBincodeStream<S, T, Tagged<ReadySetResult<()>>, D>),
     Upgrade(
-        #[pin] AsyncBincodeStream<S, T2, Tagged<()>, D>,
+        #[pin] AsyncBincodeStream<S, T2, Tagged<ReadySetResult<()>>, D>,
         Box<dyn FnMut(T2) -> T + Send + Sync>,
     ),
 }
@@ -178,7 +202,7 @@ impl<S, T, T2> From<S> for DualTcpStream<S, T, T2, AsyncDestination> {
 
 impl<S, T, T2> DualTcpStream<S, T, T2, AsyncDestination> {
     pub fn upgrade<F: 'static + FnMut(T2) -> T + Send + Sync>(stream: S, f: F) -> Self {
-        let s: AsyncBincodeStream<S, T2, Tagged<()>, AsyncDestination> =
+        let s: AsyncBincodeStream<S, T2, Tagged<ReadySetResult<()>>, AsyncDestination> =
             AsyncBincodeStream::from(stream).for_async();
         DualTcpStream::Upgrade(s, Box::new(f))
     }
@@ -191,11 +215,13 @@ impl<S, T, T2> DualTcpStream<S, T, T2, AsyncDestination> {
     }
 }
 
-impl<S, T, T2, D> Sink<Tagged<()>> for DualTcpStream<S, T, T2, D>
+impl<S, T, T2, D> Sink<Tagged<ReadySetResult<()>>> for DualTcpStream<S, T, T2, D>
 where
     S: AsyncWrite,
-    AsyncBincodeStream<S, T, Tagged<()>, D>: Sink<Tagged<()>, Error = bincode::Error>,
-    AsyncBincodeStream<S, T2, Tagged<()>, D>: Sink<Tagged<()>, Error = bincode::Error>,
+    AsyncBincodeStream<S, T, Tagged<ReadySetResult<()>>, D>:
+        Sink<Tagged<ReadySetResult<()>>, Error = bincode::Error>,
+    AsyncBincodeStream<S, T2, Tagged<ReadySetResult<()>>, D>:
+        Sink<Tagged<ReadySetResult<()>>, Error = bincode::Error>,
 {
     type Error = bincode::Error;
 
@@ -206,7 +232,10 @@ where
         }
     }
 
-    fn start_send(self: Pin<&mut Self>, item: Tagged<()>) -> Result<(), Self::Error> {
+    fn start_send(
+        self: Pin<&mut Self>,
+        item: Tagged<ReadySetResult<()>>,
+    ) -> Result<(), Self::Error> {
         match self.project() {
             DualTcpStreamProj::Passthrough(abs) => abs.start_send(item),
             DualTcpStreamProj::Upgrade(abs, _) => abs.start_send(item),
@@ -233,8 +262,10 @@ where
     T: DeserializeOwned,
     T2: DeserializeOwned,
     S: AsyncRead,
-    AsyncBincodeStream<S, T, Tagged<()>, D>: Stream<Item = Result<T, bincode::Error>>,
-    AsyncBincodeStream<S, T2, Tagged<()>, D>: Stream<Item = Result<T2, bincode::Error>>,
+    AsyncBincodeStream<S, T, Tagged<ReadySetResult<()>>, D>:
+        Stream<Item = Result<T, bincode::Error>>,
+    AsyncBincodeStream<S, T2, Tagged<ReadySetResult<()>>, D>:
+        Stream<Item = Result<T2, bincode::Error>>,
 {
     type Item = Result<T, bincode::Error>;
 
@@ -0,0 +1,218 @@
+//! A [`Sink`] which writes bincode-encoded packets to a stream with vectored writes.
+//!
+//! Packets are encoded as soon as they're sent, and buffered until the sink is flushed (or until
+//! too many bytes are buffered), at which point all the buffered packets are written to the
+//! stream with as few vectored write calls as possible. Small packets are additionally coalesced
+//! into shared buffers, so that a flush of a large batch of small packets doesn't need more
+//! buffers than a single vectored write can take.
+//!
+//! The encoding of each packet is the same as the one used by [`TcpSender`](super::TcpSender), and
+//! expected by [`AsyncBincodeStream`](async_bincode::AsyncBincodeStream) on the receiving end.
+
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::io::{self, IoSlice};
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bincode::{ErrorKind, Options};
+use futures_util::ready;
+use futures_util::sink::Sink;
+use serde::Serialize;
+use tokio::io::AsyncWrite;
+
+/// The maximum number of buffers passed to a single vectored write. Linux rejects vectored writes
+/// of more than `IOV_MAX` (1024) buffers.
+const MAX_IO_SLICES: usize = 1024;
+
+/// Packets whose encoding is smaller than this are appended to the buffer of the previous packet,
+/// if that buffer is also smaller than this, rather than being given their own buffer.
+const COALESCE_THRESHOLD: usize = 16 * 1024;
+
+/// The number of bytes of encoded packets which can be buffered before [`Sink::poll_ready`] starts
+/// writing them to the stream.
+const MAX_BUFFERED_BYTES: usize = 4 * 1024 * 1024;
+
+/// A [`Sink`] of `T`s, which writes them to a stream of type `S` using vectored writes. See the
+/// [module-level documentation](self) for more information.
+pub struct VectoredBincodeWriter<S, T> {
+    stream: S,
+    /// Buffers of encoded packets waiting to be written, oldest first
+    buffers: VecDeque<Vec<u8>>,
+    /// The number of bytes at the start of the first buffer in `buffers` that have already been
+    /// written
+    written: usize,
+    /// The total number of bytes in `buffers` that have not been written yet
+    buffered: usize,
+    _marker: PhantomData<fn(T)>,
+}
+
+impl<S, T> From<S> for VectoredBincodeWriter<S, T> {
+    fn from(stream: S) -> Self {
+        Self {
+            stream,
+            buffers: VecDeque::new(),
+            written: 0,
+            buffered: 0,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<S, T> VectoredBincodeWriter<S, T> {
+    /// Returns a reference to the underlying stream
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    /// Encode `item` onto the end of the buffered packets
+    fn encode(&mut self, item: &T) -> Result<(), bincode::Error>
+    where
+        T: Serialize,
+    {
+        // NOTE: this *has* to match exactly what the AsyncBincodeReader expects on the other end,
+        // just like in `TcpSender::send_ref`
+        let c = bincode::options()
+            .with_limit(u32::max_value() as u64)
+            .allow_trailing_bytes();
+        let size = c
+            .serialized_size(item)
+            .and_then(|s| u32::try_from(s).map_err(|_| Box::new(ErrorKind::SizeLimit)))?;
+        let frame_len = 4 + size as usize;
+
+        let coalesce = frame_len < COALESCE_THRESHOLD
+            && matches!(self.buffers.back(), Some(buf) if buf.len() < COALESCE_THRESHOLD);
+        if !coalesce {
+            self.buffers.push_back(Vec::with_capacity(frame_len));
+        }
+        #[allow(clippy::unwrap_used)] // there's always at least one buffer at this point
+        let buf = self.buffers.back_mut().unwrap();
+        let start = buf.len();
+        buf.extend_from_slice(&size.to_be_bytes());
+        if let Err(e) = c.serialize_into(&mut *buf, item) {
+            buf.truncate(start);
+            if buf.is_empty() {
+                self.buffers.pop_back();
+            }
+            return Err(e);
+        }
+        self.buffered += frame_len;
+        Ok(())
+    }
+
+    /// Mark `n` bytes at the start of the buffered packets as written
+    fn advance(&mut self, mut n: usize) {
+        self.buffered -= n;
+        while n > 0 {
+            let remaining = match self.buffers.front() {
+                Some(buf) => buf.len() - self.written,
+                None => return,
+            };
+            if n >= remaining {
+                self.buffers.pop_front();
+                self.written = 0;
+                n -= remaining;
+            } else {
+                self.written += n;
+                n = 0;
+            }
+        }
+    }
+}
+
+impl<S, T> VectoredBincodeWriter<S, T>
+where
+    S: AsyncWrite + Unpin,
+{
+    /// Write all the buffered packets to the stream
+    fn poll_write_buffered(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.buffers.is_empty() {
+            let slices = self
+                .buffers
+                .iter()
+                .take(MAX_IO_SLICES)
+                .enumerate()
+                .map(|(i, buf)| IoSlice::new(if i == 0 { &buf[self.written..] } else { buf }))
+                .collect::<Vec<_>>();
+            let n = ready!(Pin::new(&mut self.stream).poll_write_vectored(cx, &slices))?;
+            if n == 0 {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::WriteZero,
+                    "failed to write buffered packets",
+                )));
+            }
+            self.advance(n);
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<S, T> Sink<T> for VectoredBincodeWriter<S, T>
+where
+    S: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    type Error = bincode::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        if this.buffered >= MAX_BUFFERED_BYTES {
+            ready!(this.poll_write_buffered(cx))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        self.get_mut().encode(&item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_buffered(cx))?;
+        Poll::Ready(Ok(ready!(Pin::new(&mut this.stream).poll_flush(cx))?))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_buffered(cx))?;
+        Poll::Ready(Ok(ready!(Pin::new(&mut this.stream).poll_shutdown(cx))?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_bincode::AsyncBincodeReader;
+    use futures_util::{SinkExt, StreamExt};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn round_trip() {
+        let (tx, rx) = tokio::io::duplex(64);
+        let mut writer = VectoredBincodeWriter::<_, Vec<u8>>::from(tx);
+        let mut reader = AsyncBincodeReader::<_, Vec<u8>>::from(rx);
+
+        // A mix of packets small enough to be coalesced and packets large enough to get their own
+        // buffer, written through a pipe small enough to force partial writes
+        let packets = (0..100)
+            .map(|i| vec![i as u8; if i % 10 == 0 { COALESCE_THRESHOLD } else { i }])
+            .collect::<Vec<_>>();
+
+        let expected = packets.clone();
+        let read = tokio::spawn(async move {
+            let mut received = vec![];
+            while received.len() < expected.len() {
+                received.push(reader.next().await.unwrap().unwrap());
+            }
+            assert_eq!(received, expected);
+        });
+
+        for packet in packets {
+            writer.feed(packet).await.unwrap();
+        }
+        writer.flush().await.unwrap();
+        assert_eq!(writer.buffered, 0);
+        read.await.unwrap();
+    }
+}
@@ -0,0 +1,94 @@
+//! Types for changing the configuration of a running ReadySet deployment without restarting it.
+//!
+//! Settings are identified by the name of the command-line option used to set them at startup (eg
+//! `memory`, or `eviction-policy`), and are passed to
+//! [`ReadySetHandle::reload_config`](crate::ReadySetHandle::reload_config) as strings, in the same
+//! format accepted on the command line.
+use std::collections::BTreeMap;
+
+use readyset_errors::{ReadySetError, ReadySetResult};
+use serde::{Deserialize, Serialize};
+
+/// The outcome of a successful call to
+/// [`ReadySetHandle::reload_config`](crate::ReadySetHandle::reload_config)
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfigReloadResult {
+    /// The names of the settings which were changed, and have taken effect
+    pub applied: Vec<String>,
+    /// The names of the settings which can only be changed by restarting ReadySet, and so were
+    /// left unchanged
+    pub requires_restart: Vec<String>,
+}
+
+/// Parse a configuration file consisting of `name = value` lines into a map from setting name to
+/// value.
+///
+/// Blank lines, and lines starting with `#`, are ignored. Values may optionally be surrounded by
+/// double quotes.
+pub fn parse_settings(contents: &str) -> ReadySetResult<BTreeMap<String, String>> {
+    let mut settings = BTreeMap::new();
+    for (lineno, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (name, value) = line.split_once('=').ok_or_else(|| {
+            ReadySetError::BadRequest(format!(
+                "Malformed setting on line {}: expected `name = value`",
+                lineno + 1
+            ))
+        })?;
+        let name = name.trim();
+        let value = value.trim();
+        let value = value
+            .strip_prefix('"')
+            .and_then(|v| v.strip_suffix('"'))
+            .unwrap_or(value);
+        if name.is_empty() {
+            return Err(ReadySetError::BadRequest(format!(
+                "Missing setting name on line {}",
+                lineno + 1
+            )));
+        }
+        if settings.insert(name.to_owned(), value.to_owned()).is_some() {
+            return Err(ReadySetError::BadRequest(format!(
+                "Setting {name} specified more than once"
+            )));
+        }
+    }
+
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_settings_file() {
+        let settings = parse_settings(
+            "# memory settings
+            memory = 1073741824
+            memory-check-every=5
+
+            eviction-policy = \"lru\"",
+        )
+        .unwrap();
+        assert_eq!(
+            settings.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("eviction-policy".to_owned(), "lru".to_owned()),
+                ("memory".to_owned(), "1073741824".to_owned()),
+                ("memory-check-every".to_owned(), "5".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_settings_errors() {
+        parse_settings("memory").unwrap_err();
+        parse_settings("= 5").unwrap_err();
+        parse_settings("memory = 1\nmemory = 2").unwrap_err();
+    }
+}
@@ -0,0 +1,821 @@
+//! # Coordination in etcd
+//!
+//! All keys written by an [`EtcdAuthority`] are prefixed with `/<deployment>/`.
+//!
+//! ## Sessions
+//! Each authority holds an etcd lease, granted in [`AuthorityControl::init`] and kept alive by
+//! [`AuthorityControl::worker_heartbeat`]. The lease plays the role of a Consul session: the
+//! leader key and the worker and adapter registrations are all attached to the lease of the
+//! authority that wrote them, so etcd deletes them once that authority stops heartbeating. The id
+//! of the lease (in decimal) is used as the id of the worker or adapter.
+//!
+//! ## Leadership
+//! The leader is whichever authority managed to create the `/controller` key, which is only ever
+//! created if it doesn't already exist. Every write that requires leadership is issued as a
+//! transaction conditioned on the `/controller` key still being attached to the writer's lease, so
+//! an authority that has lost leadership can never write.
+//!
+//! ## Controller state
+//! etcd limits the size of requests (1.5 MiB by default), and the controller state can be much
+//! larger than that, so, as with Consul, the compressed controller state is split into chunks if
+//! it doesn't fit in a single value. The `/state` key holds a [`StateValue`], which either holds
+//! the state itself or points to the prefix `/state/<version>/` under which its chunks are stored.
+//! A new state is written by first writing all its chunks under a fresh, randomly chosen version,
+//! then swapping the `/state` key to point at that version and deleting the chunks of every other
+//! version in a single transaction, so readers only ever see complete states. Chunks left behind
+//! by a write that failed (or by a leader that crashed mid-write) are swept by the next successful
+//! write.
+
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Error};
+use async_trait::async_trait;
+use etcd_client::{
+    Client, Compare, CompareOp, DeleteOptions, GetOptions, KeyValue, PutOptions, Txn, TxnOp,
+    TxnOpResponse,
+};
+use failpoint_macros::set_failpoint;
+use futures::future::try_join_all;
+use metrics::gauge;
+use readyset_errors::internal_err;
+use readyset_tracing::error;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use thiserror::Error as ThisError;
+
+use super::{
+    AdapterId, AuthorityControl, AuthorityWorkerHeartbeatResponse, GetLeaderResult, LeaderPayload,
+    WorkerDescriptor, WorkerId,
+};
+#[cfg(feature = "failure_injection")]
+use crate::failpoints;
+use crate::metrics::recorded;
+use crate::{ReadySetError, ReadySetResult};
+
+/// Path to the worker registrations.
+pub const WORKER_PREFIX: &str = "workers/";
+/// Path to the leader key.
+pub const CONTROLLER_KEY: &str = "controller";
+/// Path to the controller state.
+pub const STATE_KEY: &str = "state";
+/// Path to the adapter http endpoints.
+pub const ADAPTER_PREFIX: &str = "adapters/";
+/// The time to live of the lease held by each authority, in seconds. Keys attached to the lease
+/// are deleted if it isn't kept alive for this long.
+const LEASE_TTL: i64 = 20;
+/// The size of each chunk of the controller state, comfortably below etcd's default limit on the
+/// size of requests.
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Errors returned by the etcd authority, in addition to the errors returned by the etcd client
+#[derive(ThisError, Debug)]
+enum EtcdAuthorityError {
+    /// The authority tried to perform an operation requiring a lease before calling `init`.
+    #[error("Authority has not been initialized")]
+    NotInitialized,
+
+    /// The authority tried to perform a write requiring leadership, but etcd failed the write
+    /// due to loss of leadership.
+    #[error("An authority that has lost leadership attempted to issue a write")]
+    WriteIssuedFromLostLeader,
+
+    /// The etcd API failed to perform compression/decompression.
+    #[error("Error during (de)compression")]
+    CompressionFailed,
+}
+
+struct EtcdAuthorityInner {
+    /// The lease held by this authority, once initialized
+    lease: Option<i64>,
+    /// The revision at which the leader key was last modified, when last read
+    controller_revision: Option<i64>,
+}
+
+/// The value of the controller state key: either the compressed controller state itself, or a
+/// pointer to the chunks it has been split into.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+enum StateValue {
+    Data(Vec<u8>),
+    Chunks { version: u64, num_chunks: usize },
+}
+
+/// Coordinator that shares connection information between workers and clients using etcd.
+pub struct EtcdAuthority {
+    /// The etcd client. Cloning the client is cheap, and shares the underlying connection.
+    client: Client,
+
+    /// Deployment associated with this authority.
+    deployment: String,
+
+    /// Internal authority state required to handle operations.
+    inner: RwLock<EtcdAuthorityInner>,
+}
+
+/// Returns the first key after every key prefixed with `prefix`, for use as the end of a range.
+/// `prefix` always ends in `/`, so this just bumps that last byte.
+fn prefix_end(prefix: &str) -> String {
+    let mut end = prefix.trim_end_matches('/').to_owned();
+    end.push('0');
+    end
+}
+
+fn key_to_id(key: &[u8]) -> String {
+    let key = String::from_utf8_lossy(key);
+    key[(key.rfind('/').map_or(0, |i| i + 1))..].to_owned()
+}
+
+impl EtcdAuthority {
+    /// Create a new instance.
+    ///
+    /// The connect string should be in the format of `<address>:<port>[,...]/<deployment>`, with
+    /// one or more comma-separated etcd endpoints.
+    pub async fn new(connect_string: &str) -> ReadySetResult<Self> {
+        // We artificially create a namespace for each deployment by prefixing the
+        // deployment to each keys path.
+        let split_idx = connect_string.rfind('/').ok_or_else(|| {
+            ReadySetError::Internal("etcd connect string missing deployment".to_owned())
+        })?;
+        let deployment = connect_string[(split_idx + 1)..].to_owned();
+        let endpoints = connect_string[..split_idx]
+            .split(',')
+            .map(|e| e.trim().to_owned())
+            .collect::<Vec<_>>();
+
+        let client = Client::connect(endpoints, None)
+            .await
+            .map_err(|e| internal_err!("Failed to connect to etcd: {}", e))?;
+
+        Ok(Self {
+            client,
+            deployment,
+            inner: RwLock::new(EtcdAuthorityInner {
+                lease: None,
+                controller_revision: None,
+            }),
+        })
+    }
+
+    fn read_inner(&self) -> Result<RwLockReadGuard<'_, EtcdAuthorityInner>, Error> {
+        self.inner
+            .read()
+            .map_err(|e| anyhow!(internal_err!("rwlock is poisoned: '{}'", e)))
+    }
+
+    fn write_inner(&self) -> Result<RwLockWriteGuard<'_, EtcdAuthorityInner>, Error> {
+        self.inner
+            .write()
+            .map_err(|e| anyhow!(internal_err!("rwlock is poisoned: '{}'", e)))
+    }
+
+    fn get_lease(&self) -> Result<i64, Error> {
+        Ok(self
+            .read_inner()?
+            .lease
+            .ok_or(EtcdAuthorityError::NotInitialized)?)
+    }
+
+    fn prefix_with_deployment(&self, path: &str) -> String {
+        format!("/{}/{}", &self.deployment, path.trim_start_matches('/'))
+    }
+
+    /// Returns a comparison which only succeeds if this authority is currently the leader
+    fn is_leader(&self, lease: i64) -> Compare {
+        Compare::lease(
+            self.prefix_with_deployment(CONTROLLER_KEY),
+            CompareOp::Equal,
+            lease,
+        )
+    }
+
+    /// Reads the single key at `path`, if it exists
+    async fn get(&self, path: &str) -> Result<Option<KeyValue>, Error> {
+        let mut resp = self
+            .client
+            .clone()
+            .get(self.prefix_with_deployment(path), None)
+            .await?;
+        Ok(resp.take_kvs().into_iter().next())
+    }
+
+    /// Reads all the keys under the prefix `path`
+    async fn get_prefix(&self, path: &str) -> Result<Vec<KeyValue>, Error> {
+        let mut resp = self
+            .client
+            .clone()
+            .get(
+                self.prefix_with_deployment(path),
+                Some(GetOptions::new().with_prefix()),
+            )
+            .await?;
+        Ok(resp.take_kvs())
+    }
+
+    /// Writes `value` to `path`, attached to our lease
+    async fn put_with_lease(&self, path: &str, value: Vec<u8>) -> Result<(), Error> {
+        let lease = self.get_lease()?;
+        self.client
+            .clone()
+            .put(
+                self.prefix_with_deployment(path),
+                value,
+                Some(PutOptions::new().with_lease(lease)),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Reads the controller state, returning it along with the revision of the state key it was
+    /// read from (or a revision of 0 if there is no controller state yet)
+    async fn get_controller_state<P: DeserializeOwned>(&self) -> Result<(Option<P>, i64), Error> {
+        let kv = match self.get(STATE_KEY).await? {
+            Some(kv) => kv,
+            None => return Ok((None, 0)),
+        };
+        let value: StateValue = rmp_serde::from_slice(kv.value())?;
+        let compressed = match value {
+            StateValue::Data(data) => data,
+            StateValue::Chunks {
+                version,
+                num_chunks,
+            } => {
+                let chunks = try_join_all((0..num_chunks).map(|i| async move {
+                    self.get(&format!("{}/{}/{}", STATE_KEY, version, i))
+                        .await?
+                        .map(|kv| kv.value().to_vec())
+                        .ok_or_else(|| anyhow!("Missing controller state chunk {}", i))
+                }))
+                .await?;
+                chunks.concat()
+            }
+        };
+        let data = cloudflare_zlib::inflate(&compressed)
+            .map_err(|_| EtcdAuthorityError::CompressionFailed)?;
+        Ok((Some(rmp_serde::from_slice(&data)?), kv.mod_revision()))
+    }
+
+    /// Writes `controller_state` to etcd, replacing the state read at `revision`, as long as we're
+    /// still the leader. Returns false if the state has been changed since it was read.
+    async fn write_controller_state<P: Serialize>(
+        &self,
+        revision: i64,
+        controller_state: &P,
+    ) -> Result<bool, Error> {
+        let lease = self.get_lease()?;
+        let compressed = super::Compressor::compress(&rmp_serde::to_vec(controller_state)?);
+        gauge!(recorded::DATAFLOW_STATE_SERIALIZED, compressed.len() as f64);
+
+        let chunks_prefix = self.prefix_with_deployment(&format!("{}/", STATE_KEY));
+        let value = if compressed.len() > CHUNK_SIZE {
+            // Every attempt writes its chunks under a version of its own, so a failed or retried
+            // write can never overwrite the chunks of another
+            let version = rand::random::<u64>();
+            let chunks = compressed.chunks(CHUNK_SIZE).collect::<Vec<_>>();
+            let written = try_join_all(chunks.iter().enumerate().map(|(i, chunk)| {
+                let key = self.prefix_with_deployment(&format!("{}/{}/{}", STATE_KEY, version, i));
+                let txn = Txn::new()
+                    .when([self.is_leader(lease)])
+                    .and_then([TxnOp::put(key, chunk.to_vec(), None)]);
+                async move {
+                    if !self.client.clone().txn(txn).await?.succeeded() {
+                        bail!(EtcdAuthorityError::WriteIssuedFromLostLeader);
+                    }
+                    Ok(())
+                }
+            }))
+            .await;
+            if let Err(e) = written {
+                self.delete_state_chunks(version).await;
+                return Err(e);
+            }
+            StateValue::Chunks {
+                version,
+                num_chunks: chunks.len(),
+            }
+        } else {
+            StateValue::Data(compressed)
+        };
+
+        // Along with the new state, delete the chunks of every version but the one just written,
+        // including any left behind by earlier writes that never made it to the state key
+        let state_key = self.prefix_with_deployment(STATE_KEY);
+        let mut ops = vec![TxnOp::put(
+            state_key.clone(),
+            rmp_serde::to_vec(&value)?,
+            None,
+        )];
+        let version = match value {
+            StateValue::Chunks { version, .. } => Some(version),
+            StateValue::Data(_) => None,
+        };
+        let chunks_end = prefix_end(&chunks_prefix);
+        match version {
+            Some(version) => {
+                let own_prefix = format!("{}{}/", chunks_prefix, version);
+                ops.push(TxnOp::delete(
+                    chunks_prefix,
+                    Some(DeleteOptions::new().with_range(own_prefix.clone())),
+                ));
+                ops.push(TxnOp::delete(
+                    prefix_end(&own_prefix),
+                    Some(DeleteOptions::new().with_range(chunks_end)),
+                ));
+            }
+            None => ops.push(TxnOp::delete(
+                chunks_prefix,
+                Some(DeleteOptions::new().with_range(chunks_end)),
+            )),
+        }
+        let txn = Txn::new()
+            .when([
+                self.is_leader(lease),
+                Compare::mod_revision(state_key, CompareOp::Equal, revision),
+            ])
+            .and_then(ops)
+            .or_else([TxnOp::get(
+                self.prefix_with_deployment(CONTROLLER_KEY),
+                None,
+            )]);
+        // If the request itself fails we can't tell whether the swap happened, so our chunks are
+        // only deleted once etcd tells us it didn't
+        let resp = self.client.clone().txn(txn).await?;
+        if resp.succeeded() {
+            return Ok(true);
+        }
+        if let Some(version) = version {
+            self.delete_state_chunks(version).await;
+        }
+
+        // Figure out which of the two conditions failed
+        let still_leader = resp.op_responses().into_iter().any(|op| match op {
+            TxnOpResponse::Get(get) => get.kvs().iter().any(|kv| kv.lease() == lease),
+            _ => false,
+        });
+        if !still_leader {
+            bail!(EtcdAuthorityError::WriteIssuedFromLostLeader);
+        }
+        Ok(false)
+    }
+
+    /// Deletes the chunks written under `version`, after a write of the controller state that
+    /// didn't make it to the state key. This is best-effort: anything left behind is swept by the
+    /// next successful write.
+    async fn delete_state_chunks(&self, version: u64) {
+        if let Err(error) = self
+            .client
+            .clone()
+            .delete(
+                self.prefix_with_deployment(&format!("{}/{}/", STATE_KEY, version)),
+                Some(DeleteOptions::new().with_prefix()),
+            )
+            .await
+        {
+            error!(%error, version, "Failed to delete controller state chunks");
+        }
+    }
+
+    #[cfg(test)]
+    async fn delete_all_keys(&self) {
+        self.client
+            .clone()
+            .delete(
+                self.prefix_with_deployment(""),
+                Some(DeleteOptions::new().with_prefix()),
+            )
+            .await
+            .unwrap();
+    }
+
+    #[cfg(test)]
+    async fn revoke_lease(&self) {
+        let lease = self.get_lease().unwrap();
+        self.client.clone().lease_revoke(lease).await.unwrap();
+    }
+}
+
+#[async_trait]
+impl AuthorityControl for EtcdAuthority {
+    async fn init(&self) -> Result<(), Error> {
+        // Reuse our current lease if it's still alive, otherwise grant a new one
+        let lease = self.read_inner()?.lease;
+        if let Some(lease) = lease {
+            let resp = self.client.clone().lease_time_to_live(lease, None).await?;
+            if resp.ttl() > 0 {
+                return Ok(());
+            }
+        }
+
+        let resp = self.client.clone().lease_grant(LEASE_TTL, None).await?;
+        self.write_inner()?.lease = Some(resp.id());
+        Ok(())
+    }
+
+    async fn become_leader(&self, payload: LeaderPayload) -> Result<Option<LeaderPayload>, Error> {
+        let lease = self.get_lease()?;
+        let key = self.prefix_with_deployment(CONTROLLER_KEY);
+
+        // Only create the leader key if there isn't already a leader. The key is deleted once
+        // the leader's lease expires.
+        let txn = Txn::new()
+            .when([Compare::version(key.clone(), CompareOp::Equal, 0)])
+            .and_then([TxnOp::put(
+                key,
+                serde_json::to_vec(&payload)?,
+                Some(PutOptions::new().with_lease(lease)),
+            )]);
+        let resp = self.client.clone().txn(txn).await?;
+        if !resp.succeeded() {
+            return Ok(None);
+        }
+
+        if let Some(header) = resp.header() {
+            self.write_inner()?.controller_revision = Some(header.revision());
+        }
+        Ok(Some(payload))
+    }
+
+    async fn surrender_leadership(&self) -> Result<(), Error> {
+        let lease = self.get_lease()?;
+
+        // If we currently hold the leader key, we will delete it.
+        let txn = Txn::new()
+            .when([self.is_leader(lease)])
+            .and_then([TxnOp::delete(
+                self.prefix_with_deployment(CONTROLLER_KEY),
+                None,
+            )]);
+        self.client.clone().txn(txn).await?;
+        Ok(())
+    }
+
+    // Block until there is any leader.
+    async fn get_leader(&self) -> Result<LeaderPayload, Error> {
+        loop {
+            match self.get(CONTROLLER_KEY).await {
+                Ok(Some(kv)) => return Ok(serde_json::from_slice(kv.value())?),
+                Ok(None) => {}
+                Err(error) => error!(%error, "Failed to read leader from etcd"),
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    async fn try_get_leader(&self) -> Result<GetLeaderResult, Error> {
+        let current_revision = self.read_inner()?.controller_revision;
+
+        match self.get(CONTROLLER_KEY).await {
+            Ok(Some(kv)) => {
+                if current_revision.map_or(true, |current| kv.mod_revision() > current) {
+                    self.write_inner()?.controller_revision = Some(kv.mod_revision());
+                    Ok(GetLeaderResult::NewLeader(serde_json::from_slice(
+                        kv.value(),
+                    )?))
+                } else {
+                    Ok(GetLeaderResult::Unchanged)
+                }
+            }
+            Ok(None) => Ok(GetLeaderResult::NoLeader),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn can_watch(&self) -> bool {
+        false
+    }
+
+    async fn watch_leader(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn watch_workers(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    async fn try_read<P: DeserializeOwned>(&self, path: &str) -> Result<Option<P>, Error> {
+        Ok(match self.get(path).await? {
+            Some(kv) => Some(serde_json::from_slice(kv.value())?),
+            None => None,
+        })
+    }
+
+    async fn try_read_raw(&self, path: &str) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self.get(path).await?.map(|kv| kv.value().to_vec()))
+    }
+
+    async fn read_modify_write<F, P, E>(&self, path: &str, mut f: F) -> Result<Result<P, E>, Error>
+    where
+        F: Send + FnMut(Option<P>) -> Result<P, E>,
+        P: Send + Serialize + DeserializeOwned,
+        E: Send,
+    {
+        let key = self.prefix_with_deployment(path);
+        loop {
+            let (current_val, revision) = match self.get(path).await? {
+                Some(kv) => (Some(serde_json::from_slice(kv.value())?), kv.mod_revision()),
+                None => (None, 0),
+            };
+
+            let modified = match f(current_val) {
+                Ok(modified) => modified,
+                Err(e) => return Ok(Err(e)),
+            };
+
+            // Only write back if nobody else has written to the key since we read it
+            let txn = Txn::new()
+                .when([Compare::mod_revision(
+                    key.clone(),
+                    CompareOp::Equal,
+                    revision,
+                )])
+                .and_then([TxnOp::put(
+                    key.clone(),
+                    serde_json::to_vec(&modified)?,
+                    None,
+                )]);
+            if self.client.clone().txn(txn).await?.succeeded() {
+                return Ok(Ok(modified));
+            }
+        }
+    }
+
+    async fn register_worker(&self, payload: WorkerDescriptor) -> Result<Option<WorkerId>, Error>
+    where
+        WorkerDescriptor: Serialize,
+    {
+        // Each worker is associated with the key `WORKER_PREFIX`/<lease>, which is deleted when
+        // the lease expires
+        let id = self.get_lease()?.to_string();
+        self.put_with_lease(
+            &(WORKER_PREFIX.to_owned() + &id),
+            serde_json::to_vec(&payload)?,
+        )
+        .await?;
+        Ok(Some(id))
+    }
+
+    async fn worker_heartbeat(
+        &self,
+        id: WorkerId,
+    ) -> Result<AuthorityWorkerHeartbeatResponse, Error> {
+        set_failpoint!(failpoints::AUTHORITY, |_| {
+            Ok(AuthorityWorkerHeartbeatResponse::Failed)
+        });
+
+        let lease = id
+            .parse::<i64>()
+            .map_err(|_| anyhow!("Invalid etcd worker id {}", id))?;
+        let res: Result<bool, etcd_client::Error> = async {
+            let (mut keeper, mut responses) = self.client.clone().lease_keep_alive(lease).await?;
+            keeper.keep_alive().await?;
+            Ok(matches!(responses.message().await?, Some(resp) if resp.ttl() > 0))
+        }
+        .await;
+
+        Ok(match res {
+            Ok(true) => AuthorityWorkerHeartbeatResponse::Alive,
+            Ok(false) => {
+                error!("Authority failed to heartbeat: lease has expired");
+                AuthorityWorkerHeartbeatResponse::Failed
+            }
+            Err(e) => {
+                error!("Authority failed to heartbeat: {}", e.to_string());
+                AuthorityWorkerHeartbeatResponse::Failed
+            }
+        })
+    }
+
+    async fn get_workers(&self) -> Result<HashSet<WorkerId>, Error> {
+        set_failpoint!(failpoints::AUTHORITY, |_| bail!(
+            "authority->server failure injected"
+        ));
+
+        Ok(self
+            .get_prefix(WORKER_PREFIX)
+            .await?
+            .iter()
+            .map(|kv| key_to_id(kv.key()))
+            .collect())
+    }
+
+    async fn worker_data(
+        &self,
+        worker_ids: Vec<WorkerId>,
+    ) -> Result<HashMap<WorkerId, WorkerDescriptor>, Error> {
+        set_failpoint!(failpoints::AUTHORITY, |_| bail!(
+            "authority->server failure injected"
+        ));
+
+        let mut worker_descriptors: HashMap<WorkerId, WorkerDescriptor> = HashMap::new();
+        for w in worker_ids {
+            if let Some(kv) = self.get(&(WORKER_PREFIX.to_owned() + &w)).await? {
+                worker_descriptors.insert(w, serde_json::from_slice(kv.value())?);
+            }
+        }
+
+        Ok(worker_descriptors)
+    }
+
+    /// Updates the controller state only if we are the leader, which is checked atomically with
+    /// each write by comparing the lease of the leader key to our own.
+    async fn update_controller_state<F, U, P, E>(
+        &self,
+        mut f: F,
+        _: U,
+    ) -> Result<Result<P, E>, Error>
+    where
+        F: Send + FnMut(Option<P>) -> Result<P, E>,
+        U: Send,
+        P: Send + Serialize + DeserializeOwned,
+        E: Send,
+    {
+        loop {
+            let (current_state, revision) = self.get_controller_state().await?;
+
+            let r = match f(current_state) {
+                Ok(r) => r,
+                Err(e) => return Ok(Err(e)),
+            };
+            if self.write_controller_state(revision, &r).await? {
+                return Ok(Ok(r));
+            }
+        }
+    }
+
+    async fn register_adapter(&self, endpoint: SocketAddr) -> Result<Option<AdapterId>, Error> {
+        set_failpoint!(failpoints::AUTHORITY, |_| bail!(
+            "authority->server failure injected"
+        ));
+
+        // Each adapter is associated with the key `ADAPTER_PREFIX`/<lease>, which is deleted
+        // when the lease expires
+        let id = self.get_lease()?.to_string();
+        self.put_with_lease(
+            &(ADAPTER_PREFIX.to_owned() + &id),
+            serde_json::to_vec(&endpoint)?,
+        )
+        .await?;
+        Ok(Some(id))
+    }
+
+    async fn get_adapters(&self) -> Result<HashSet<SocketAddr>, Error> {
+        set_failpoint!(failpoints::AUTHORITY, |_| bail!(
+            "authority->server failure injected"
+        ));
+
+        self.get_prefix(ADAPTER_PREFIX)
+            .await?
+            .iter()
+            .map(|kv| Ok(serde_json::from_slice(kv.value())?))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use serial_test::serial;
+
+    use super::*;
+
+    async fn test_authority(deployment: &str) -> EtcdAuthority {
+        let address = format!(
+            "{}/{}",
+            std::env::var("ETCD_ADDRESS").unwrap_or_else(|_| "127.0.0.1:2379".to_string()),
+            deployment
+        );
+        let authority = EtcdAuthority::new(&address).await.unwrap();
+        authority.init().await.unwrap();
+        authority
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn read_write_operations() {
+        let authority = test_authority("etcd_read_write").await;
+        authority.delete_all_keys().await;
+
+        assert!(authority.try_read::<Duration>("a").await.unwrap().is_none());
+        assert_eq!(
+            authority
+                .read_modify_write("a", |_: Option<Duration>| -> Result<Duration, Duration> {
+                    Ok(Duration::from_secs(10))
+                })
+                .await
+                .unwrap(),
+            Ok(Duration::from_secs(10))
+        );
+        assert_eq!(
+            authority.try_read::<Duration>("a").await.unwrap(),
+            Some(Duration::from_secs(10))
+        );
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn leader_election_operations() {
+        let authority = test_authority("etcd_leader_election").await;
+        authority.delete_all_keys().await;
+
+        let payload = LeaderPayload {
+            controller_uri: url::Url::parse("http://127.0.0.1:2181").unwrap(),
+            nonce: 1,
+        };
+        assert_eq!(
+            authority.become_leader(payload.clone()).await.unwrap(),
+            Some(payload.clone())
+        );
+        assert_eq!(authority.get_leader().await.unwrap(), payload);
+
+        // A second authority can't become the leader while the first one's lease is alive
+        let authority_2 = test_authority("etcd_leader_election").await;
+        let payload_2 = LeaderPayload {
+            controller_uri: url::Url::parse("http://127.0.0.1:2182").unwrap(),
+            nonce: 2,
+        };
+        assert_eq!(
+            authority_2.become_leader(payload_2.clone()).await.unwrap(),
+            None
+        );
+        assert!(matches!(
+            authority_2.try_get_leader().await.unwrap(),
+            GetLeaderResult::NewLeader(p) if p == payload
+        ));
+
+        // Regicide.
+        authority.revoke_lease().await;
+        assert_eq!(
+            authority_2.become_leader(payload_2.clone()).await.unwrap(),
+            Some(payload_2.clone())
+        );
+        assert_eq!(authority_2.get_leader().await.unwrap(), payload_2);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn workers_and_adapters() {
+        let authority = test_authority("etcd_workers").await;
+        authority.delete_all_keys().await;
+
+        let worker_id = authority
+            .register_worker(WorkerDescriptor {
+                worker_uri: url::Url::parse("http://127.0.0.1").unwrap(),
+                reader_addr: "127.0.0.1:2181".parse().unwrap(),
+                leader_eligible: true,
+                domain_scheduling_config: Default::default(),
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            authority.get_workers().await.unwrap(),
+            HashSet::from([worker_id.clone()])
+        );
+        assert_eq!(
+            authority.worker_heartbeat(worker_id).await.unwrap(),
+            AuthorityWorkerHeartbeatResponse::Alive
+        );
+
+        let endpoint = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), 6033);
+        authority.register_adapter(endpoint).await.unwrap();
+        assert_eq!(
+            authority.get_adapters().await.unwrap(),
+            HashSet::from([endpoint])
+        );
+
+        // Registrations go away along with the lease
+        authority.revoke_lease().await;
+        assert!(authority.get_workers().await.unwrap().is_empty());
+        assert!(authority.get_adapters().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn chunked_state_roundtrip() {
+        let authority = test_authority("etcd_state").await;
+        authority.delete_all_keys().await;
+        let payload = LeaderPayload {
+            controller_uri: url::Url::parse("http://127.0.0.1:2181").unwrap(),
+            nonce: 1,
+        };
+        authority.become_leader(payload).await.unwrap().unwrap();
+
+        // Random bytes don't compress, so this is split into several chunks
+        for len in [3 * CHUNK_SIZE, 10, 2 * CHUNK_SIZE] {
+            let state: Vec<u8> = (0..len).map(|_| rand::random()).collect();
+            let expected = state.clone();
+            authority
+                .update_controller_state(
+                    |_: Option<Vec<u8>>| -> Result<Vec<u8>, ()> { Ok(state.clone()) },
+                    |_| {},
+                )
+                .await
+                .unwrap()
+                .unwrap();
+            let (read, _) = authority.get_controller_state::<Vec<u8>>().await.unwrap();
+            assert_eq!(read, Some(expected));
+        }
+    }
+}
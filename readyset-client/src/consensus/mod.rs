@@ -9,19 +9,23 @@ use std::str::FromStr;
 use anyhow::{anyhow, Error};
 use async_trait::async_trait;
 use enum_dispatch::enum_dispatch;
+use readyset_errors::{internal_err, ReadySetResult};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use url::Url;
 
 mod consul;
+mod etcd;
 mod local;
 mod standalone;
 pub mod zk;
 
 pub use self::consul::ConsulAuthority;
+pub use self::etcd::EtcdAuthority;
 pub use self::local::{LocalAuthority, LocalAuthorityStore};
 pub use self::standalone::StandaloneAuthority;
 pub use self::zk::ZookeeperAuthority;
+use crate::placement::WorkerResources;
 use crate::ControllerDescriptor;
 
 // This should be an associated type on Authority but since Authority will only have one possible
@@ -81,6 +85,11 @@ pub struct WorkerSchedulingConfig {
     /// Configuration for how domains containing or not containing reader nodes may be scheduled
     /// onto this worker
     pub reader_nodes: NodeTypeSchedulingRestriction,
+    /// The resources and other properties of this worker, which are checked against the
+    /// [`PlacementConstraints`](crate::placement::PlacementConstraints) of domains scheduled onto
+    /// it
+    #[serde(default)]
+    pub resources: WorkerResources,
 }
 
 /// Initial registration request body, sent from workers to controllers.
@@ -229,6 +238,7 @@ pub trait AuthorityControl: Send + Sync {
 pub enum Authority {
     ZookeeperAuthority,
     ConsulAuthority,
+    EtcdAuthority,
     LocalAuthority,
     StandaloneAuthority,
 }
@@ -238,6 +248,7 @@ pub enum Authority {
 pub enum AuthorityType {
     Zookeeper,
     Consul,
+    Etcd,
     Local,
     Standalone,
 }
@@ -248,6 +259,7 @@ impl FromStr for AuthorityType {
         match s {
             "zookeeper" => Ok(AuthorityType::Zookeeper),
             "consul" => Ok(AuthorityType::Consul),
+            "etcd" => Ok(AuthorityType::Etcd),
             "local" => Ok(AuthorityType::Local),
             "standalone" => Ok(AuthorityType::Standalone),
             other => Err(anyhow!("Invalid authority type: {}", other)),
@@ -260,6 +272,7 @@ impl ToString for AuthorityType {
         match &self {
             AuthorityType::Zookeeper => "zookeeper".to_string(),
             AuthorityType::Consul => "consul".to_string(),
+            AuthorityType::Etcd => "etcd".to_string(),
             AuthorityType::Local => "local".to_string(),
             AuthorityType::Standalone => "standalone".to_string(),
         }
@@ -267,21 +280,25 @@ impl ToString for AuthorityType {
 }
 
 impl AuthorityType {
-    pub async fn to_authority(&self, addr: &str, deployment: &str) -> Authority {
-        match self {
-            AuthorityType::Zookeeper => Authority::from(
-                ZookeeperAuthority::new(&format!("{}/{}", addr, deployment))
-                    .await
-                    .unwrap(),
-            ),
-            AuthorityType::Consul => Authority::from(
-                ConsulAuthority::new(&format!("http://{}/{}", addr, deployment)).unwrap(),
-            ),
-            AuthorityType::Local => Authority::from(LocalAuthority::new()),
-            AuthorityType::Standalone => {
-                Authority::from(StandaloneAuthority::new(addr, deployment).unwrap())
+    /// Connects to an authority of this type at `addr`, scoped to `deployment`.
+    pub async fn to_authority(&self, addr: &str, deployment: &str) -> ReadySetResult<Authority> {
+        Ok(match self {
+            AuthorityType::Zookeeper => {
+                Authority::from(ZookeeperAuthority::new(&format!("{}/{}", addr, deployment)).await?)
             }
-        }
+            AuthorityType::Consul => Authority::from(ConsulAuthority::new(&format!(
+                "http://{}/{}",
+                addr, deployment
+            ))?),
+            AuthorityType::Etcd => {
+                Authority::from(EtcdAuthority::new(&format!("{}/{}", addr, deployment)).await?)
+            }
+            AuthorityType::Local => Authority::from(LocalAuthority::new()),
+            AuthorityType::Standalone => Authority::from(
+                StandaloneAuthority::new(addr, deployment)
+                    .map_err(|e| internal_err!("Could not open standalone authority: {}", e))?,
+            ),
+        })
     }
 }
 
@@ -1,8 +1,12 @@
 //! Primitives and structs related to maintaining different consistency
 //! models within the ReadySet dataflow graph.
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 
+use itertools::Itertools;
 use proptest::arbitrary::Arbitrary;
+use readyset_errors::ReadySetError;
 use serde::{Deserialize, Serialize};
 
 use crate::LocalNodeIndex;
@@ -40,6 +44,45 @@ impl Arbitrary for Timestamp {
     }
 }
 
+/// Timestamps are displayed as a consistency token which can be handed to clients and parsed
+/// back (via [`FromStr`]) later, consisting of a comma-separated list of
+/// `<base table index>:<timestamp>` pairs, ordered by base table index.
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            self.map
+                .iter()
+                .sorted_by_key(|(table, _)| **table)
+                .map(|(table, timestamp)| format!("{}:{}", table.id(), timestamp))
+                .join(",")
+        )
+    }
+}
+
+impl FromStr for Timestamp {
+    type Err = ReadySetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ReadySetError::InvalidConsistencyToken {
+            token: s.to_owned(),
+        };
+
+        let mut ret = Timestamp::default();
+        for entry in s.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let (table, timestamp) = entry.split_once(':').ok_or_else(invalid)?;
+            let table = LocalNodeIndex::make(table.trim().parse().map_err(|_| invalid())?);
+            let timestamp = timestamp.trim().parse().map_err(|_| invalid())?;
+            if ret.map.insert(table, timestamp).is_some() {
+                return Err(invalid());
+            }
+        }
+
+        Ok(ret)
+    }
+}
+
 // TODO(justin): Remove #[allow(dead_code)] when the impl functions are being used.
 #[allow(dead_code)]
 impl Timestamp {
@@ -112,6 +155,20 @@ impl Timestamp {
         }
         true
     }
+
+    /// A timestamp `self` reflects a snapshot `snapshot` (as taken for snapshot reads) if every
+    /// base table timestamp in `self` is at least the respective base table timestamp in
+    /// `snapshot`. Unlike [`satisfies`](Self::satisfies), base tables in `snapshot` without a
+    /// value in `self` are ignored, since a snapshot covers every base table in the graph but
+    /// the timestamp of a node only has values for the base tables it's derived from.
+    pub fn reflects_snapshot(&self, snapshot: &Timestamp) -> bool {
+        self.map.iter().all(|(table, timestamp)| {
+            snapshot
+                .map
+                .get(table)
+                .map_or(true, |snapshot_timestamp| timestamp >= snapshot_timestamp)
+        })
+    }
 }
 
 #[cfg(test)]
@@ -169,6 +226,18 @@ mod tests {
         assert!(!t2.satisfies(&t1));
     }
 
+    #[test]
+    fn reflects_snapshot_ignores_other_tables() {
+        let b1 = LocalNodeIndex::make(0);
+        let b2 = LocalNodeIndex::make(1);
+        let b3 = LocalNodeIndex::make(2);
+        let snapshot = create_timestamp(vec![(b1, 2), (b2, 2), (b3, 2)]);
+
+        assert!(create_timestamp(vec![(b1, 3), (b2, 2)]).reflects_snapshot(&snapshot));
+        assert!(!create_timestamp(vec![(b1, 3), (b2, 1)]).reflects_snapshot(&snapshot));
+        assert!(create_timestamp(vec![(b1, 2)]).reflects_snapshot(&Timestamp::default()));
+    }
+
     #[proptest]
     fn min_with_empty(t: Timestamp) {
         assert_eq!(&t, &Timestamp::min(&[&t, &Timestamp::default()]));
@@ -228,6 +297,26 @@ mod tests {
         assert_eq!(&t, &Timestamp::join(&t, &t));
     }
 
+    #[proptest]
+    fn token_round_trip(t: Timestamp) {
+        assert_eq!(t.to_string().parse::<Timestamp>().unwrap(), t);
+    }
+
+    #[test]
+    fn parse_token() {
+        assert_eq!(
+            "3:17, 1:42".parse::<Timestamp>().unwrap(),
+            create_timestamp(vec![
+                (LocalNodeIndex::make(1), 42),
+                (LocalNodeIndex::make(3), 17)
+            ])
+        );
+        assert_eq!("".parse::<Timestamp>().unwrap(), Timestamp::default());
+        "1".parse::<Timestamp>().unwrap_err();
+        "1:x".parse::<Timestamp>().unwrap_err();
+        "1:2,1:3".parse::<Timestamp>().unwrap_err();
+    }
+
     #[test]
     fn join_calculates_max() {
         let b1 = LocalNodeIndex::make(0);
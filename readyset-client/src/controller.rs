@@ -8,11 +8,13 @@ use std::time::{Duration, Instant};
 
 use futures_util::future;
 use hyper::client::HttpConnector;
-use nom_sql::{Relation, SelectStatement};
+use nom_sql::{CreateCacheStatement, Relation, SelectStatement};
 use parking_lot::RwLock;
 use petgraph::graph::NodeIndex;
+use readyset_data::DfValue;
 use readyset_errors::{
     internal, internal_err, rpc_err, rpc_err_no_downcast, ReadySetError, ReadySetResult,
+    UnsupportedFeature,
 };
 use readyset_tracing::trace;
 use serde::de::DeserializeOwned;
@@ -22,16 +24,23 @@ use tower::ServiceExt;
 use tower_service::Service;
 use url::Url;
 
+use crate::channel::ChannelSecurity;
+use crate::config::ConfigReloadResult;
 use crate::consensus::{Authority, AuthorityControl};
+use crate::consistency::Timestamp;
 use crate::debug::info::GraphInfo;
+use crate::debug::provenance::RowProvenance;
 use crate::debug::stats;
 use crate::metrics::MetricsDump;
+use crate::placement::PlacementConstraints;
+use crate::query::QueryId;
 use crate::recipe::changelist::ChangeList;
 use crate::recipe::ExtendRecipeSpec;
 use crate::replication::ReplicationOffsets;
 use crate::status::ReadySetStatus;
 use crate::table::{Table, TableBuilder, TableRpc};
-use crate::view::{View, ViewBuilder, ViewRpc};
+use crate::view::replicas::ReadLocality;
+use crate::view::{View, ViewBuilder, ViewRpc, ViewStats};
 use crate::{NodeSize, ReplicationOffset, TableStatus, ViewCreateRequest, ViewFilter, ViewRequest};
 
 mod rpc;
@@ -53,6 +62,9 @@ struct Controller {
     /// The last valid leader URL seen by this service. Used to circumvent requests to Consul in
     /// the happy-path.
     leader_url: Arc<RwLock<Option<Url>>>,
+    /// Used to sign requests, if the server requires it. Shared with the [`ReadySetHandle`] so
+    /// that it can be set after the handle is created.
+    security: Arc<RwLock<Option<ChannelSecurity>>>,
 }
 
 #[derive(Debug)]
@@ -91,6 +103,7 @@ impl Service<ControllerRequest> for Controller {
         let client = self.client.clone();
         let auth = self.authority.clone();
         let leader_url = self.leader_url.clone();
+        let security = self.security.read().clone();
         let request_timeout = req.timeout.unwrap_or(Duration::MAX);
         let path = req.path;
         let body = req.request;
@@ -122,9 +135,22 @@ impl Service<ControllerRequest> for Controller {
                 //             (if you try and use the `url` directly instead of stringifying)
                 #[allow(clippy::unwrap_used)]
                 let string_url = url.as_ref().unwrap().join(path)?.to_string();
-                let r = hyper::Request::post(string_url)
+                let mut r = hyper::Request::post(string_url)
                     .body(hyper::Body::from(body.clone()))
                     .map_err(|e| internal_err!("http request failed: {}", e))?;
+                if let Some(security) = &security {
+                    let path_and_query = r
+                        .uri()
+                        .path_and_query()
+                        .map_or("/", |p| p.as_str())
+                        .to_owned();
+                    security.sign_request(
+                        &hyper::Method::POST,
+                        &path_and_query,
+                        &body,
+                        r.headers_mut(),
+                    );
+                }
 
                 let res = match tokio::time::timeout(request_timeout - elapsed, client.request(r))
                     .await
@@ -198,6 +224,10 @@ pub struct ReadySetHandle {
     tracer: tracing::Dispatch,
     request_timeout: Option<Duration>,
     migration_timeout: Option<Duration>,
+    channel_security: Option<ChannelSecurity>,
+    /// The [`ChannelSecurity`] used by the [`Controller`] service to sign requests
+    controller_security: Arc<RwLock<Option<ChannelSecurity>>>,
+    read_locality: ReadLocality,
 }
 
 impl Clone for ReadySetHandle {
@@ -209,6 +239,9 @@ impl Clone for ReadySetHandle {
             tracer: self.tracer.clone(),
             request_timeout: self.request_timeout,
             migration_timeout: self.migration_timeout,
+            channel_security: self.channel_security.clone(),
+            controller_security: self.controller_security.clone(),
+            read_locality: self.read_locality.clone(),
         }
     }
 }
@@ -231,6 +264,7 @@ impl ReadySetHandle {
         let tracer = tracing::dispatcher::get_default(|d| d.clone());
         let mut http_connector = HttpConnector::new();
         http_connector.set_connect_timeout(request_timeout);
+        let controller_security = Arc::new(RwLock::new(None));
         ReadySetHandle {
             views: Default::default(),
             domains: Default::default(),
@@ -245,15 +279,36 @@ impl ReadySetHandle {
                         )
                         .build(http_connector),
                     leader_url: Arc::new(RwLock::new(None)),
+                    security: controller_security.clone(),
                 },
                 CONTROLLER_BUFFER_SIZE,
             ),
             tracer,
             request_timeout,
             migration_timeout,
+            channel_security: None,
+            controller_security,
+            read_locality: Default::default(),
         }
     }
 
+    /// Secure the connections this handle makes to domains, to write to tables, and to readers,
+    /// to read from views, and sign the requests it makes to the controller, with the given
+    /// [`ChannelSecurity`]. This must match the security configured for the ReadySet server.
+    ///
+    /// Requests to the controller are signed with `security` by every clone of this handle.
+    pub fn set_channel_security(&mut self, security: Option<ChannelSecurity>) {
+        *self.controller_security.write() = security.clone();
+        self.channel_security = security;
+    }
+
+    /// Set the zone (for example, the cloud availability zone) this handle is running in. Views
+    /// obtained from this handle prefer to read from replicas of readers running in the same zone,
+    /// and otherwise from whichever replicas have responded fastest.
+    pub fn set_read_locality_zone(&mut self, zone: Option<String>) {
+        self.read_locality = ReadLocality::new(zone);
+    }
+
     /// Check that the `ReadySetHandle` can accept another request.
     ///
     /// Note that this method _must_ return `Poll::Ready` before any other methods that return
@@ -348,6 +403,25 @@ impl ReadySetHandle {
         self.simple_get_request("verbose_views").await
     }
 
+    /// Returns the `CREATE CACHE` statements for all the caches which have been created, ordered by
+    /// name. These can be used to recreate the same caches on another deployment, eg by passing
+    /// them to [`Self::extend_recipe`].
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub async fn cache_definitions(&mut self) -> ReadySetResult<Vec<CreateCacheStatement>> {
+        self.simple_get_request("cache_definitions").await
+    }
+
+    /// Returns the name and (JSON-serialized) MIR plan of every cache whose plan has been replaced
+    /// via [`Self::set_mir_plan`], ordered by name. Recreating the caches returned by
+    /// [`Self::cache_definitions`] and then replaying these plans recreates the same dataflow on
+    /// another deployment.
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub async fn mir_plan_overrides(&mut self) -> ReadySetResult<Vec<(Relation, String)>> {
+        self.simple_get_request("mir_plan_overrides").await
+    }
+
     /// For each of the given list of queries, determine whether that query (or a semantically
     /// equivalent query) has been created as a `View`.
     ///
@@ -363,6 +437,63 @@ impl ReadySetHandle {
             .await
     }
 
+    /// Determine whether the given query (or a semantically equivalent query) is the query cached
+    /// under the given name.
+    pub async fn query_matches_cache(
+        &mut self,
+        name: Relation,
+        query: ViewCreateRequest,
+        dialect: dataflow_expression::Dialect,
+    ) -> ReadySetResult<bool> {
+        self.rpc(
+            "query_matches_cache",
+            (name, query, dialect),
+            self.request_timeout,
+        )
+        .await
+    }
+
+    /// Register the given queries with the controller, so that they can later be looked up by their
+    /// [`QueryId`] using [`Self::query_for_id`], including by other adapters or after a restart.
+    pub async fn register_queries(
+        &mut self,
+        queries: Vec<ViewCreateRequest>,
+    ) -> ReadySetResult<()> {
+        self.rpc("register_queries", queries, self.request_timeout)
+            .await
+    }
+
+    /// Look up a query previously registered with [`Self::register_queries`] by its [`QueryId`].
+    ///
+    /// Ids computed for the query by previous versions of ReadySet (see
+    /// [`QUERY_ID_VERSION`](crate::query::QUERY_ID_VERSION)) also resolve to the query. Returns the
+    /// current id of the query along with the query itself, or `None` if no query with the given id
+    /// has been registered.
+    pub async fn query_for_id(
+        &mut self,
+        id: QueryId,
+    ) -> ReadySetResult<Option<(QueryId, ViewCreateRequest)>> {
+        self.rpc("query_for_id", id, self.request_timeout).await
+    }
+
+    /// Returns the id, query, the reason why it's unsupported, and the SQL feature that made it
+    /// unsupported (if known) for all the queries which the controller has found to be
+    /// unsupported.
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub async fn unsupported_queries(
+        &mut self,
+    ) -> ReadySetResult<
+        Vec<(
+            QueryId,
+            ViewCreateRequest,
+            String,
+            Option<UnsupportedFeature>,
+        )>,
+    > {
+        self.simple_get_request("unsupported_queries").await
+    }
+
     /// Obtain a `View` that allows you to query the given external view.
     ///
     /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
@@ -445,6 +576,7 @@ impl ReadySetHandle {
         view_request: ViewRequest,
     ) -> impl Future<Output = ReadySetResult<View>> + '_ {
         let views = self.views.clone();
+        let read_locality = self.read_locality.clone();
         async move {
             let replica = if let Some(ViewFilter::Replica(replica)) = &view_request.filter {
                 Some(*replica)
@@ -452,7 +584,12 @@ impl ReadySetHandle {
                 None
             };
             let view_builder = self.view_builder(view_request).await?;
-            view_builder.build(replica, views)
+            view_builder.build(
+                replica,
+                views,
+                &read_locality,
+                self.channel_security.as_ref(),
+            )
         }
     }
 
@@ -498,6 +635,7 @@ impl ReadySetHandle {
         req: ControllerRequest,
     ) -> impl Future<Output = ReadySetResult<Option<Table>>> + '_ {
         let domains = self.domains.clone();
+        let security = self.channel_security.clone();
         async move {
             let body: hyper::body::Bytes = self
                 .handle
@@ -511,7 +649,7 @@ impl ReadySetHandle {
             Ok(
                 bincode::deserialize::<ReadySetResult<Option<TableBuilder>>>(&body)?
                     .map_err(|e| rpc_err_no_downcast("ReadySetHandle::table", e))?
-                    .map(|tb| tb.build(domains)),
+                    .map(|tb| tb.build(domains, security.as_ref())),
             )
         }
     }
@@ -523,6 +661,25 @@ impl ReadySetHandle {
         self.rpc("get_statistics", (), self.request_timeout)
     }
 
+    /// Get the number of reads, the number of misses, and the read latency of each cache for every
+    /// minute of the last 24 hours, oldest first.
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub fn cache_read_history(
+        &mut self,
+    ) -> impl Future<Output = ReadySetResult<HashMap<Relation, Vec<stats::ReadStatsBucket>>>> + '_
+    {
+        self.rpc("cache_read_history", (), self.request_timeout)
+    }
+
+    /// Reset the cumulative event counters reported by [`Self::statistics`] for all domains and
+    /// nodes back to zero.
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub fn reset_statistics(&mut self) -> impl Future<Output = ReadySetResult<()>> + '_ {
+        self.rpc("reset_statistics", (), self.request_timeout)
+    }
+
     /// Flush all partial state, evicting all rows present.
     ///
     /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
@@ -554,6 +711,35 @@ impl ReadySetHandle {
         self.rpc("extend_recipe", request, self.migration_timeout)
     }
 
+    /// Extend the existing recipe with the given set of queries, without waiting for the initial
+    /// state of any new fully materialized caches to be replayed. The change is committed once
+    /// those replays (the change's "backfill") have been set up, and the backfill then runs in the
+    /// background. Resolves to an identifier for the backfill which can be passed to
+    /// [`Self::backfill_done`], or `None` if there was nothing to backfill.
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub fn extend_recipe_concurrently(
+        &mut self,
+        changes: ChangeList,
+    ) -> impl Future<Output = ReadySetResult<Option<u64>>> + '_ {
+        let request = ExtendRecipeSpec::from(changes);
+
+        self.rpc(
+            "extend_recipe_concurrently",
+            request,
+            self.migration_timeout,
+        )
+    }
+
+    /// Returns whether the backfill with the given identifier, as returned by
+    /// [`Self::extend_recipe_concurrently`], has completed, or the error it failed with. The
+    /// completion of each backfill is only reported once.
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub fn backfill_done(&mut self, id: u64) -> impl Future<Output = ReadySetResult<bool>> + '_ {
+        self.rpc("backfill_done", id, self.request_timeout)
+    }
+
     /// Extend the existing recipe with the given set of queries and don't require leader ready.
     ///
     /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
@@ -582,6 +768,24 @@ impl ReadySetHandle {
             changes,
             replication_offset: Some(Cow::Borrowed(replication_offset)),
             require_leader_ready,
+            placement_constraints: Default::default(),
+        };
+
+        self.rpc("extend_recipe", request, self.migration_timeout)
+    }
+
+    /// Extend the existing recipe with the given set of queries, scheduling any new domains
+    /// containing readers or base tables only onto workers which meet the given constraints
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub fn extend_recipe_with_placement_constraints(
+        &mut self,
+        changes: ChangeList,
+        placement_constraints: PlacementConstraints,
+    ) -> impl Future<Output = ReadySetResult<()>> + '_ {
+        let request = ExtendRecipeSpec {
+            placement_constraints,
+            ..changes.into()
         };
 
         self.rpc("extend_recipe", request, self.migration_timeout)
@@ -597,6 +801,22 @@ impl ReadySetHandle {
         self.rpc("remove_query", name, self.migration_timeout)
     }
 
+    /// Add an index on the given columns (given as indices into the rows returned by the view's
+    /// reader) to the fully materialized state feeding the view with the given name.
+    ///
+    /// Resolves to `true` if a new index was created, or `false` if the state feeding the view
+    /// can't be given an additional index (for example because it's partially materialized) or
+    /// already has one on those columns.
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub fn add_index(
+        &mut self,
+        view: &Relation,
+        columns: Vec<usize>,
+    ) -> impl Future<Output = ReadySetResult<bool>> + '_ {
+        self.rpc("add_index", (view, columns), self.migration_timeout)
+    }
+
     /// Remove all non-base nodes from the graph
     ///
     /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
@@ -632,6 +852,128 @@ impl ReadySetHandle {
         self.rpc("simple_graphviz", (), self.request_timeout)
     }
 
+    /// Fetch the canonical plan for the cache with the given name: a description of the operators,
+    /// columns, sharding and indexes of the dataflow nodes making up the cache, which doesn't
+    /// depend on the numbering of nodes and domains and so can be diffed across deployments.
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub fn canonical_plan<N>(
+        &mut self,
+        name: N,
+    ) -> impl Future<Output = ReadySetResult<String>> + '_
+    where
+        N: Into<Relation>,
+    {
+        let name = name.into();
+        self.rpc("canonical_plan", name, self.request_timeout)
+    }
+
+    /// Fetch the MIR plan for the cache with the given name, as JSON: the nodes of the
+    /// intermediate representation the cache was compiled to, in topological order, after
+    /// optimization.
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub fn mir_plan<N>(&mut self, name: N) -> impl Future<Output = ReadySetResult<String>> + '_
+    where
+        N: Into<Relation>,
+    {
+        let name = name.into();
+        self.rpc("mir_plan", name, self.request_timeout)
+    }
+
+    /// Fetch the provenance of every row with the given key in the cache with the given name: the
+    /// base table, primary key and replication offset of the writes in the update which last wrote
+    /// each row. Provenance is only recorded if the server was started with `--record-provenance`;
+    /// otherwise, this always returns an empty list.
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub fn row_provenance<N>(
+        &mut self,
+        name: N,
+        key: Vec<DfValue>,
+    ) -> impl Future<Output = ReadySetResult<Vec<RowProvenance>>> + '_
+    where
+        N: Into<Relation>,
+    {
+        let name = name.into();
+        self.rpc("row_provenance", (name, key), self.request_timeout)
+    }
+
+    /// Generate values for the `AUTO_INCREMENT` column at index `column` of the base table with
+    /// the given name, for a write of rows whose values for that column are given by `values`:
+    /// `Some` for rows with an explicit value, which later generated values must come after, or
+    /// `None` for rows which need a value generated. Returns the generated values, in order.
+    ///
+    /// Values are generated by the controller, so they're unique across every adapter writing to
+    /// the table.
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub fn auto_increments<N>(
+        &mut self,
+        table: N,
+        column: usize,
+        values: Vec<Option<u64>>,
+    ) -> impl Future<Output = ReadySetResult<Vec<u64>>> + '_
+    where
+        N: Into<Relation>,
+    {
+        let table = table.into();
+        self.rpc(
+            "auto_increments",
+            (table, column, values),
+            self.request_timeout,
+        )
+    }
+
+    /// Fetch all the rows in the base table with the given name where the values of the columns
+    /// at the indices `columns` are equal to `key`. This reads the table's state directly, so it
+    /// doesn't require (or create) a cache, and always reflects every write the table has
+    /// acknowledged.
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub fn base_rows<N>(
+        &mut self,
+        table: N,
+        columns: Vec<usize>,
+        key: Vec<DfValue>,
+    ) -> impl Future<Output = ReadySetResult<Vec<Vec<DfValue>>>> + '_
+    where
+        N: Into<Relation>,
+    {
+        let table = table.into();
+        self.rpc("base_rows", (table, columns, key), self.request_timeout)
+    }
+
+    /// Replace the dataflow for the cache with the given name with dataflow built from the given
+    /// MIR plan, in the JSON format returned by [`Self::mir_plan`]. This can be used to override
+    /// decisions made by the query planner, such as the order of joins or the key of the cache.
+    ///
+    /// The plan is validated, and is used as-is without being optimized further. It must return
+    /// the same columns as the cache's current plan, and any base tables it references must exist
+    /// with the same schema. If the plan is invalid, the cache is left unchanged. The cache keeps
+    /// the settings it was created with, and the plan is kept until the cache is dropped (see
+    /// [`Self::mir_plan_overrides`]).
+    ///
+    /// Expressions in the plan are lowered using the semantics of the given SQL `dialect`.
+    ///
+    /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
+    pub fn set_mir_plan<N>(
+        &mut self,
+        name: N,
+        plan: String,
+        dialect: dataflow_expression::Dialect,
+    ) -> impl Future<Output = ReadySetResult<()>> + '_
+    where
+        N: Into<Relation>,
+    {
+        let name = name.into();
+        self.rpc(
+            "set_mir_plan",
+            (name, plan, dialect),
+            self.migration_timeout,
+        )
+    }
+
     /// Replicate the readers associated with the list of queries to the given worker.
     ///
     /// `Self::poll_ready` must have returned `Async::Ready` before you call this method.
@@ -719,6 +1061,12 @@ impl ReadySetHandle {
         self.rpc("status", (), self.request_timeout)
     }
 
+    /// Move all the domains running on the given worker onto the other workers in the
+    /// deployment, and stop scheduling new domains onto it, in preparation for shutting it down.
+    pub fn drain_worker(&mut self, worker: Url) -> impl Future<Output = ReadySetResult<()>> + '_ {
+        self.rpc("drain_worker", worker, self.migration_timeout)
+    }
+
     /// Returns true if topk and pagination support are enabled on the server
     pub fn supports_pagination(&mut self) -> impl Future<Output = ReadySetResult<bool>> + '_ {
         self.rpc("supports_pagination", (), self.request_timeout)
@@ -743,6 +1091,83 @@ impl ReadySetHandle {
         self.rpc("set_memory_limit", (period, limit), self.request_timeout)
     }
 
+    /// Change the given settings (identified by the name of their command-line option) on the
+    /// running deployment, without restarting it.
+    ///
+    /// All the settings are validated before any of them are applied, so if any of them are
+    /// unknown or invalid an error is returned and nothing is changed. Settings which can only be
+    /// changed by restarting ReadySet are left unchanged, and reported in the returned
+    /// [`ConfigReloadResult`].
+    pub fn reload_config(
+        &mut self,
+        settings: BTreeMap<String, String>,
+    ) -> impl Future<Output = ReadySetResult<ConfigReloadResult>> + '_ {
+        self.rpc("reload_config", settings, self.request_timeout)
+    }
+
+    /// Pause replication from the upstream database, leaving the data replicated so far in place
+    /// to be read from. Replication can be resumed with [`Self::resume_replication`].
+    ///
+    /// Replication stays paused across controller restarts and changes of leader.
+    pub fn pause_replication(&mut self) -> impl Future<Output = ReadySetResult<()>> + '_ {
+        self.rpc("pause_replication", (), self.request_timeout)
+    }
+
+    /// Resume replication from the upstream database, after it was paused with
+    /// [`Self::pause_replication`], from the position it was paused at.
+    pub fn resume_replication(&mut self) -> impl Future<Output = ReadySetResult<()>> + '_ {
+        self.rpc("resume_replication", (), self.request_timeout)
+    }
+
+    /// Fetch the number of keys and rows currently stored in every view, keyed by the name of the
+    /// view. For partially materialized views, only the keys which are currently filled are
+    /// counted.
+    pub fn view_stats(
+        &mut self,
+    ) -> impl Future<Output = ReadySetResult<BTreeMap<Relation, ViewStats>>> + '_ {
+        self.rpc("view_stats", (), self.request_timeout)
+    }
+
+    /// Wait for every write made to a base table before this method is called to be reflected in
+    /// every cache, by injecting a barrier into every base table and waiting for it to reach every
+    /// reader. Returns the barrier timestamp, in microseconds since the UNIX epoch.
+    ///
+    /// Returns an error if the barrier doesn't reach every reader within a minute.
+    pub fn quiesce(&mut self) -> impl Future<Output = ReadySetResult<u64>> + '_ {
+        self.rpc("quiesce", (), self.request_timeout)
+    }
+
+    /// Return the latest timestamp propagated through the dataflow graph by replication, for every
+    /// base table that has been replicated to with a timestamp, to read at as a snapshot (see
+    /// [`ViewQuery::snapshot`](crate::ViewQuery::snapshot)).
+    ///
+    /// The timestamp is persisted in the authority before it's returned, so a snapshot taken after
+    /// the controller fails over is never older than one taken before.
+    pub fn replication_timestamp(
+        &mut self,
+    ) -> impl Future<Output = ReadySetResult<Timestamp>> + '_ {
+        self.rpc("replication_timestamp", (), self.request_timeout)
+    }
+
+    /// Return how far replication is behind the upstream database: how long ago the upstream
+    /// database committed the oldest replication event that has been received but not yet
+    /// applied, or zero if replication has caught up. Returns `None` if ReadySet isn't streaming
+    /// replication from an upstream database, in which case the lag isn't known.
+    pub fn replication_lag(
+        &mut self,
+    ) -> impl Future<Output = ReadySetResult<Option<Duration>>> + '_ {
+        self.rpc("replication_lag", (), self.request_timeout)
+    }
+
+    /// Return the current values of the settings which can be changed with
+    /// [`Self::reload_config`], keyed by the name of their command-line option, in the same format
+    /// accepted by [`Self::reload_config`].
+    pub fn config_settings(
+        &mut self,
+    ) -> impl Future<Output = ReadySetResult<BTreeMap<String, String>>> + '_ {
+        self.rpc("config_settings", (), self.request_timeout)
+    }
+
     #[cfg(feature = "failure_injection")]
     /// Set a failpoint with provided name and action
     pub fn failpoint(
@@ -1,4 +1,6 @@
 /// Types related to graph information.
 pub mod info;
+/// Types related to the provenance of rows in caches.
+pub mod provenance;
 /// Types related to graph statistics.
 pub mod stats;
@@ -0,0 +1,38 @@
+use nom_sql::Relation;
+use readyset_data::DfValue;
+use serde::{Deserialize, Serialize};
+
+use crate::replication::ReplicationOffset;
+
+/// A compact description of a single write to a base table, attached to the updates which result
+/// from that write as they flow through the dataflow graph when provenance recording is enabled.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProvenanceTag {
+    /// The base table that was written to
+    pub table: Relation,
+    /// The primary key of the row that was written, or the whole row if the table has no primary
+    /// key
+    pub key: Vec<DfValue>,
+    /// The replication offset of the write, if it was replicated from the upstream database
+    pub offset: Option<ReplicationOffset>,
+}
+
+/// The provenance of a single row in a cache's reader: the writes to base tables in the last update
+/// that wrote the row to the reader.
+///
+/// Since updates are tagged as a whole rather than per record, if that update resulted from a
+/// batch of several writes, `tags` contains all of them - not just the ones the row derives from.
+/// If the row was last written by a replay of a key out of a base table, `tags` contains a single
+/// tag whose `key` is the replayed key (rather than a primary key of the base table).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RowProvenance {
+    /// The row, as stored in the reader (before any post-lookup processing)
+    pub row: Vec<DfValue>,
+    /// The writes in the update which last wrote the row. Empty if the row was last written by a
+    /// full replay, a replay of a range of keys, or a replay which started at a materialized node
+    /// other than a base table.
+    pub tags: Vec<ProvenanceTag>,
+    /// Whether the row was last written by a replay (such as to fill a key in a partial cache),
+    /// rather than by a write to a base table
+    pub replay: bool,
+}
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
+use std::time::SystemTime;
 
 use petgraph::graph::NodeIndex;
+use readyset_data::DfValue;
 use serde::{Deserialize, Serialize};
 
 use crate::internal::*;
@@ -8,6 +10,30 @@ use crate::MaterializationStatus;
 
 type DomainMap = HashMap<ReplicaAddress, (DomainStats, HashMap<NodeIndex, NodeStats>)>;
 
+/// Cumulative counters of events that happened in a domain or a node.
+///
+/// Counters only ever increase, until they are reset with
+/// [`ReadySetHandle::reset_statistics`](crate::ReadySetHandle::reset_statistics). Taking two
+/// snapshots of the counters along with their [`DomainStats::collected_at`] timestamps allows
+/// computing rates over the time window between the two.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Counters {
+    /// Number of keys for which a replay was requested.
+    pub replays_requested: u64,
+    /// Number of keys for which a requested partial replay completed.
+    pub replays_satisfied: u64,
+    /// Number of times state was evicted.
+    pub evictions: u64,
+    /// Number of keys that missed during a lookup into partial state.
+    ///
+    /// For a whole domain, each key is only counted once, in the reader or remote replay request
+    /// where the miss happened, even if the resulting replay misses again in further upstream
+    /// nodes in the domain.
+    pub misses: u64,
+    /// Number of packets processed.
+    pub packets_processed: u64,
+}
+
 /// Statistics about a domain.
 ///
 /// All times are in nanoseconds.
@@ -23,6 +49,125 @@ pub struct DomainStats {
     pub total_forward_time: u64,
     /// Total wall-clock time spent waiting for work in this domain.
     pub wait_time: u64,
+    /// Total CPU time consumed by the thread running this domain, if it could be measured.
+    pub cpu_time: Option<u64>,
+    /// The average fraction of a single CPU used by the thread running this domain since the
+    /// domain started, if it could be measured.
+    pub cpu_utilization: Option<f64>,
+    /// Cumulative event counters for the whole domain.
+    pub counters: Counters,
+    /// The time at which [`Self::counters`] (and the counters of all the domain's nodes) started
+    /// counting, either when the domain was created or when the counters were last reset.
+    pub counters_reset_at: SystemTime,
+    /// The time at which these statistics were collected.
+    pub collected_at: SystemTime,
+}
+
+/// Summary of a distribution of sizes, in bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SizeDistribution {
+    /// The smallest size.
+    pub min: u64,
+    /// The median size.
+    pub p50: u64,
+    /// The 90th percentile size.
+    pub p90: u64,
+    /// The 99th percentile size.
+    pub p99: u64,
+    /// The largest size.
+    pub max: u64,
+    /// The mean size.
+    pub mean: u64,
+}
+
+impl SizeDistribution {
+    /// Summarize the given sizes, which need not be sorted.
+    pub fn from_sizes(mut sizes: Vec<u64>) -> Self {
+        if sizes.is_empty() {
+            return Self::default();
+        }
+        sizes.sort_unstable();
+        let percentile = |p: usize| sizes[(sizes.len() - 1) * p / 100];
+        Self {
+            min: sizes[0],
+            p50: percentile(50),
+            p90: percentile(90),
+            p99: percentile(99),
+            max: sizes[sizes.len() - 1],
+            mean: sizes.iter().sum::<u64>() / sizes.len() as u64,
+        }
+    }
+}
+
+/// Counters of the reads of a reader during a single minute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReadStatsBucket {
+    /// The start of the minute these counters cover.
+    pub minute: SystemTime,
+    /// Number of reads from the reader.
+    pub reads: u64,
+    /// Number of reads which missed on at least one key.
+    pub misses: u64,
+    /// Number of reads which failed with an error.
+    #[serde(default)]
+    pub errors: u64,
+    /// Number of reads which timed out waiting for their misses to be filled.
+    #[serde(default)]
+    pub timeouts: u64,
+    /// Total time taken to answer all the reads, including waiting for replays to fill misses, in
+    /// microseconds.
+    pub total_latency_us: u64,
+    /// Time taken to answer the slowest read, in microseconds.
+    pub max_latency_us: u64,
+}
+
+impl ReadStatsBucket {
+    /// Create a new, empty bucket for the minute starting at `minute`.
+    pub fn new(minute: SystemTime) -> Self {
+        Self {
+            minute,
+            reads: 0,
+            misses: 0,
+            errors: 0,
+            timeouts: 0,
+            total_latency_us: 0,
+            max_latency_us: 0,
+        }
+    }
+
+    /// The mean time taken to answer a read during this minute, in microseconds.
+    pub fn mean_latency_us(&self) -> u64 {
+        self.total_latency_us.checked_div(self.reads).unwrap_or(0)
+    }
+
+    /// Add the counters of `other`, which should cover the same minute, to these counters.
+    pub fn merge(&mut self, other: &ReadStatsBucket) {
+        self.reads += other.reads;
+        self.misses += other.misses;
+        self.errors += other.errors;
+        self.timeouts += other.timeouts;
+        self.total_latency_us += other.total_latency_us;
+        self.max_latency_us = self.max_latency_us.max(other.max_latency_us);
+    }
+}
+
+/// Statistics about the keys stored in a reader node, used to identify caches whose reads are
+/// skewed towards a small number of keys.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReaderStats {
+    /// The number of keys present in the reader.
+    pub key_count: u64,
+    /// The most frequently read keys, along with the (approximate, over-estimated) number of
+    /// times each was read since the counters were last reset, most read first.
+    pub hottest_keys: Vec<(Vec<DfValue>, u64)>,
+    /// The distribution of the sizes of the rows stored for each key, estimated from a sample of
+    /// the keys for readers with many keys.
+    pub value_sizes: SizeDistribution,
+    /// Counters of the reads from this reader for each minute of the last 24 hours in which it was
+    /// read from, oldest first. Unlike the other counters, these aren't reset when statistics are
+    /// reset, and instead only expire once they're older than 24 hours.
+    #[serde(default)]
+    pub read_history: Vec<ReadStatsBucket>,
 }
 
 /// Statistics about a node.
@@ -42,6 +187,10 @@ pub struct NodeStats {
     pub materialized: MaterializationStatus,
     /// The value returned from Ingredient::probe.
     pub probe_result: HashMap<String, String>,
+    /// Cumulative event counters for this node.
+    pub counters: Counters,
+    /// Key statistics, if this node is a reader.
+    pub reader: Option<ReaderStats>,
 }
 
 /// Statistics about the Soup data-flow.
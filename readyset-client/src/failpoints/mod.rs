@@ -14,3 +14,9 @@ pub const READ_QUERY: &str = "read-query";
 pub const UPSTREAM: &str = "upstream";
 /// Imitates a failure during the `handle_action` in replication
 pub const REPLICATION_ACTION: &str = "replication-action";
+/// Panics the domain with the index given as the failpoint's argument the next time it handles a
+/// packet. The failpoint is removed once it's triggered.
+pub const DOMAIN_PANIC: &str = "domain-panic";
+/// Holds every backfill of a cache created concurrently back from starting its replays for as long
+/// as the failpoint is enabled
+pub const BACKFILL: &str = "backfill";
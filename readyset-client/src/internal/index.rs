@@ -43,7 +43,7 @@ impl IndexType {
     pub fn for_operator(operator: BinaryOperator) -> Option<Self> {
         use BinaryOperator::*;
         match operator {
-            Equal | Is => Some(Self::HashMap),
+            Equal | NullSafeEqual | Is => Some(Self::HashMap),
             Greater | GreaterOrEqual | Less | LessOrEqual => Some(Self::BTreeMap),
             _ => None,
         }
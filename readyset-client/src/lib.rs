@@ -250,9 +250,12 @@ use tokio_tower::multiplex;
 #[cfg(feature = "failure_injection")]
 pub mod failpoints;
 
+pub mod config;
 pub mod consistency;
 mod controller;
 pub mod metrics;
+pub mod namespace;
+pub mod placement;
 pub mod query;
 pub mod status;
 mod table;
@@ -283,7 +286,7 @@ use serde::{Deserialize, Serialize};
 use tokio::task_local;
 pub use view::{
     ColumnBase, ColumnSchema, KeyColumnIdx, PlaceholderIdx, ReaderHandle, ViewPlaceholder,
-    ViewSchema,
+    ViewSchema, ViewStats,
 };
 
 pub use crate::consensus::ZookeeperAuthority;
@@ -297,7 +300,9 @@ pub mod prelude {
 
 /// Wrapper types for ReadySet query results.
 pub mod results {
-    pub use super::view::results::{Key, ResultIterator, Results, Row, SharedResults, SharedRows};
+    pub use super::view::results::{
+        Key, ResultChunks, ResultIterator, Results, Row, SharedResults, SharedRows,
+    };
 }
 
 task_local! {
@@ -367,16 +372,22 @@ pub use crate::consensus::WorkerDescriptor;
 pub use crate::controller::{ControllerDescriptor, ReadySetHandle};
 pub use crate::table::{
     Modification, Operation, Table, TableOperation, TableReplicationStatus, TableRequest,
-    TableStatus,
+    TableStatus, WriteBatch,
 };
 #[doc(hidden)]
 pub use crate::table::{PacketData, PacketPayload, PacketTrace};
-pub use crate::view::View;
+pub use crate::view::columnar::{ColumnData, ColumnarBatch, ColumnarColumn};
+#[cfg(unix)]
+#[doc(hidden)]
+pub use crate::view::local::{create_reader_socket_dir, reader_socket_path, verify_local_peer};
+pub use crate::view::parquet::ParquetWriter;
+pub use crate::view::replicas::ReadLocality;
 #[doc(hidden)]
 pub use crate::view::{
     KeyComparison, LookupResult, ReadQuery, ReadReply, ReadReplyBatch, ReadReplyStats, SchemaType,
     ViewCreateRequest, ViewQuery,
 };
+pub use crate::view::{ReadBehavior, View};
 
 #[doc(hidden)]
 pub mod builders {
@@ -446,6 +457,13 @@ pub enum KeyCount {
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct NodeMaterializedSize(usize);
 
+impl NodeMaterializedSize {
+    /// Returns the approximate size of the materialized state in bytes
+    pub fn bytes(self) -> usize {
+        self.0
+    }
+}
+
 impl Display for KeyCount {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -104,6 +104,17 @@ pub mod recorded {
     pub const DOMAIN_READER_TOTAL_REPLAY_REQUEST_TIME: &str =
         "domain.reader_total_replay_request_time_us";
 
+    /// Counter: The number of keys in reader replay requests that were dropped because a replay
+    /// for that key had already been requested by an earlier miss, and was still in flight.
+    /// Recorded at the domain during RequestReaderReplay packet handling.
+    ///
+    /// | Tag | Description |
+    /// | --- | ----------- |
+    /// | domain | The index of the domain the reader replay request is recorded in. |
+    /// | shard | The shard the reader replay request is recorded in. |
+    /// | node | The LocalNodeIndex of the reader node handling the packet. |
+    pub const DOMAIN_READER_REPLAYS_COALESCED: &str = "domain.reader_replays_coalesced";
+
     /// Histogram: The time in microseconds that a domain spends
     /// handling a RequestPartialReplay packet. Recorded at the domain
     /// following RequestPartialReplay packet handling.
@@ -238,6 +249,36 @@ pub mod recorded {
     /// is in progress, 0 otherwise.
     pub const CONTROLLER_MIGRATION_IN_PROGRESS: &str = "controller.migration_in_progress";
 
+    /// Counter: The number of queries the controller has found to be unsupported. Incremented
+    /// each time the planner fails to plan a query it hasn't already found to be unsupported.
+    ///
+    /// | Tag | Description |
+    /// | feature | The name of the unsupported feature, or "unknown" if it isn't known. |
+    pub const CONTROLLER_UNSUPPORTED_QUERIES: &str = "controller.unsupported_queries";
+
+    /// Counter: The number of times a replica of a domain was found to have materialized state
+    /// which differs from that of the first replica of its shard. Incremented for each node whose
+    /// state differs each time the leader compares the state of domain replicas.
+    ///
+    /// | Tag | Description |
+    /// | domain | The index of the domain. |
+    /// | node | The index of the node whose state differs. |
+    pub const CONTROLLER_STATE_DIVERGENCES: &str = "controller.state_divergences";
+
+    /// Counter: The number of times a domain replica failed (by returning an error or panicking)
+    /// and was stopped by the worker running it.
+    ///
+    /// | Tag | Description |
+    /// | domain | The index of the domain. |
+    pub const WORKER_DOMAIN_FAILURES: &str = "worker.domain_failures";
+
+    /// Counter: The number of times the controller restarted a domain after one of its replicas
+    /// failed.
+    ///
+    /// | Tag | Description |
+    /// | domain | The index of the domain. |
+    pub const CONTROLLER_DOMAIN_RESTARTS: &str = "controller.domain_restarts";
+
     /// Counter: The number of evicitons performed at a worker. Incremented each
     /// time `do_eviction` is called at the worker.
     ///
@@ -317,6 +358,24 @@ pub mod recorded {
     /// | node | The LocalNodeIndex of the base table node handling the packet. |
     pub const BASE_TABLE_LOOKUP_REQUESTS: &str = "base_table.lookup_requests";
 
+    /// Counter: The number of replicated writes to a base table which updated or deleted a row
+    /// that doesn't exist in the table.
+    ///
+    /// | Tag | Description |
+    /// | --- | ----------- |
+    /// | table_name | The name of the base table. |
+    pub const BASE_TABLE_REPLICATION_CONFLICTS: &str = "base_table.replication_conflicts";
+
+    /// Counter: The number of rows written to a base table which exceeded the configured maximum
+    /// row size.
+    ///
+    /// | Tag | Description |
+    /// | --- | ----------- |
+    /// | table_name | The name of the base table. |
+    /// | source | Where the row was written from: `replication` or `adapter`. |
+    /// | action | What was done with the row: `truncated` or `skipped`. |
+    pub const BASE_TABLE_OVERSIZED_ROWS: &str = "base_table.oversized_rows";
+
     /// Counter: The number of packets dropped by an egress node.
     ///
     ///
@@ -355,10 +414,47 @@ pub mod recorded {
     /// Counter: The number of times a query required at least a partial replay.
     pub const SERVER_VIEW_QUERY_MISS: &str = "server.view_query_result_miss";
 
+    /// Counter: The number of keys missed on by a query which did not trigger a new replay,
+    /// because a replay for that key was already in flight from a concurrent miss.
+    pub const SERVER_VIEW_QUERY_COALESCED_REPLAY: &str = "server.view_query_coalesced_replay";
+
+    /// Counter: The number of times a query which missed was answered with stale results from a
+    /// cache with a `MAX STALENESS`, while the missed keys were replayed in the background.
+    pub const SERVER_VIEW_QUERY_STALE_HIT: &str = "server.view_query_result_stale_hit";
+
+    /// Counter: The number of times the results of a query were truncated because they exceeded
+    /// the result limits of the cache.
+    pub const SERVER_VIEW_QUERY_RESULT_TRUNCATED: &str = "server.view_query_result_truncated";
+
+    /// Counter: The number of reads from a cache which weren't served from the cache, because
+    /// replication lag exceeded the cache's `MAX REPLICATION LAG`.
+    pub const SERVER_VIEW_QUERY_REPLICATION_LAG_EXCEEDED: &str =
+        "server.view_query_replication_lag_exceeded";
+
+    /// Counter: The number of reads from a cache with a `MAX REPLICATION LAG` which weren't served
+    /// from the cache, because the replication lag wasn't known.
+    pub const SERVER_VIEW_QUERY_REPLICATION_LAG_UNKNOWN: &str =
+        "server.view_query_replication_lag_unknown";
+
     /// Histogram: The amount of time in microseconds spent waiting for an upquery during a read
     /// request.
     pub const SERVER_VIEW_UPQUERY_DURATION: &str = "server.view_query_upquery_duration_us";
 
+    /// Counter: The number of text values received by domains in writes or replays which were
+    /// replaced with an identical, already-interned value from the worker's text interning pool.
+    pub const SERVER_TEXT_POOL_HITS: &str = "server.text_pool_hits";
+
+    /// Counter: The number of text values received by domains in writes or replays which were not
+    /// already present in the worker's text interning pool.
+    pub const SERVER_TEXT_POOL_MISSES: &str = "server.text_pool_misses";
+
+    /// Counter: The number of bytes of text which did not need to be kept in memory because an
+    /// identical, already-interned value was found in the worker's text interning pool.
+    pub const SERVER_TEXT_POOL_BYTES_SAVED: &str = "server.text_pool_bytes_saved";
+
+    /// Gauge: The total size in bytes of the text values held in the worker's text interning pool.
+    pub const SERVER_TEXT_POOL_SIZE_BYTES: &str = "server.text_pool_size_bytes";
+
     /// Counter: The number of times a dataflow node type is added to the
     /// dataflow graph. Recorded at the time the new graph is committed.
     ///
@@ -419,6 +515,18 @@ pub mod recorded {
     /// log.
     pub const REPLICATOR_FAILURE: &str = "replicator.update_failure";
 
+    /// Counter: Number of times tables were found to have a different schema in ReadySet than
+    /// in the upstream database, and were resnapshotted.
+    ///
+    /// | Tag | Description |
+    /// | schema | Schema of the table |
+    /// | name | Name of the table |
+    pub const REPLICATOR_SCHEMA_DRIFT: &str = "replicator.schema_drift";
+
+    /// Counter: Number of times the replicator reconnected to the upstream database after its
+    /// replication connection was lost or stopped receiving heartbeats.
+    pub const REPLICATOR_RECONNECTS: &str = "replicator.reconnects";
+
     /// Counter: Number of tables that failed to replicate and are ignored
     pub const TABLE_FAILED_TO_REPLICATE: &str = "replicator.table_failed";
 
@@ -0,0 +1,224 @@
+//! Namespaces, and the quotas enforced on them.
+//!
+//! Every cache belongs to a *namespace*, which is the upstream schema (the database, in MySQL) of
+//! the first table the cache's query reads from. On a cluster shared between several teams or
+//! applications, each of which uses its own schema, quotas can be set on each namespace to prevent
+//! the caches of one namespace from starving those of another:
+//!
+//! * the number of caches in the namespace, and the total number of bytes materialized for them,
+//!   are limited by the controller when creating new caches, and
+//! * the number of reads per second from the caches in the namespace is limited by each adapter;
+//!   reads over the limit are proxied to the upstream database, as if the query wasn't cached.
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use nom_sql::{SelectStatement, SqlIdentifier, TableExprInner};
+use readyset_errors::{ReadySetError, ReadySetResult};
+use serde::{Deserialize, Serialize};
+
+/// Returns the namespace of a cache for the given query: the schema of the first table in the
+/// query's `FROM` clause (looking inside subqueries), or the first schema in `search_path` if that
+/// table isn't qualified with a schema.
+///
+/// Returns `None` for queries which don't read from any tables.
+pub fn namespace_of(
+    stmt: &SelectStatement,
+    search_path: &[SqlIdentifier],
+) -> Option<SqlIdentifier> {
+    let table = stmt.tables.iter().find_map(|expr| match &expr.inner {
+        TableExprInner::Table(table) => Some(table.schema.clone()),
+        TableExprInner::Subquery(subquery) => namespace_of(subquery, search_path).map(Some),
+    })?;
+    table.or_else(|| search_path.first().cloned())
+}
+
+/// The limits placed on a single namespace. Limits which are `None` are not enforced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct NamespaceQuota {
+    /// The maximum number of caches in the namespace
+    pub max_caches: Option<usize>,
+    /// The maximum number of bytes of state materialized for the caches in the namespace. Once
+    /// this is reached, no new caches can be created in the namespace.
+    pub max_materialized_bytes: Option<usize>,
+    /// The maximum number of reads per second, per adapter, from the caches in the namespace
+    pub max_reads_per_second: Option<u32>,
+}
+
+impl NamespaceQuota {
+    /// Returns `true` if none of the limits in this quota are enforced
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The quota for a single, named namespace, overriding the default quota for all namespaces.
+///
+/// Parsed from strings of the form `<namespace>:<limit>=<value>[,<limit>=<value>...]`, where each
+/// limit is one of `caches`, `bytes` or `qps`, for example `analytics:caches=10,qps=500`. Limits
+/// which aren't given are not enforced for the namespace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamespaceQuotaOverride {
+    /// The name of the namespace
+    pub namespace: SqlIdentifier,
+    /// The quota for the namespace
+    pub quota: NamespaceQuota,
+}
+
+impl FromStr for NamespaceQuotaOverride {
+    type Err = ReadySetError;
+
+    fn from_str(s: &str) -> ReadySetResult<Self> {
+        let invalid = |reason: &str| {
+            ReadySetError::BadRequest(format!("Invalid namespace quota `{s}`: {reason}"))
+        };
+
+        let (namespace, limits) = s
+            .split_once(':')
+            .ok_or_else(|| invalid("expected `<namespace>:<limit>=<value>,...`"))?;
+        if namespace.is_empty() {
+            return Err(invalid("namespace must not be empty"));
+        }
+
+        let mut quota = NamespaceQuota::default();
+        for limit in limits.split(',') {
+            let (name, value) = limit
+                .split_once('=')
+                .ok_or_else(|| invalid("expected `<limit>=<value>`"))?;
+            let value = value.trim();
+            match name.trim() {
+                "caches" => quota.max_caches = Some(value.parse().map_err(|_| invalid(value))?),
+                "bytes" => {
+                    quota.max_materialized_bytes = Some(value.parse().map_err(|_| invalid(value))?)
+                }
+                "qps" => {
+                    quota.max_reads_per_second = Some(value.parse().map_err(|_| invalid(value))?)
+                }
+                name => {
+                    return Err(invalid(&format!(
+                        "unknown limit `{name}`, expected one of `caches`, `bytes` or `qps`"
+                    )))
+                }
+            }
+        }
+
+        Ok(Self {
+            namespace: namespace.into(),
+            quota,
+        })
+    }
+}
+
+/// The quotas for all namespaces in a deployment
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct NamespaceQuotas {
+    /// The quota for all namespaces which don't have their own quota in `overrides`
+    pub default: NamespaceQuota,
+    /// Quotas for individual namespaces
+    pub overrides: HashMap<SqlIdentifier, NamespaceQuota>,
+}
+
+impl NamespaceQuotas {
+    /// Returns the quota for the given namespace
+    pub fn quota(&self, namespace: &SqlIdentifier) -> &NamespaceQuota {
+        self.overrides.get(namespace).unwrap_or(&self.default)
+    }
+
+    /// Returns `true` if no limits are enforced on any namespace
+    pub fn is_unlimited(&self) -> bool {
+        self.default.is_unlimited() && self.overrides.values().all(NamespaceQuota::is_unlimited)
+    }
+}
+
+impl FromIterator<NamespaceQuotaOverride> for NamespaceQuotas {
+    fn from_iter<I: IntoIterator<Item = NamespaceQuotaOverride>>(iter: I) -> Self {
+        Self {
+            default: Default::default(),
+            overrides: iter.into_iter().map(|o| (o.namespace, o.quota)).collect(),
+        }
+    }
+}
+
+/// A limit of a [`NamespaceQuota`] that was exceeded, used in the message of
+/// [`ReadySetError::NamespaceQuotaExceeded`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NamespaceLimit {
+    /// [`NamespaceQuota::max_caches`]
+    Caches(usize),
+    /// [`NamespaceQuota::max_materialized_bytes`]
+    MaterializedBytes(usize),
+    /// [`NamespaceQuota::max_reads_per_second`]
+    ReadsPerSecond(u32),
+}
+
+impl Display for NamespaceLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Caches(n) => write!(f, "at most {n} caches"),
+            Self::MaterializedBytes(n) => write!(f, "at most {n} bytes of materialized state"),
+            Self::ReadsPerSecond(n) => write!(f, "at most {n} reads per second"),
+        }
+    }
+}
+
+impl NamespaceLimit {
+    /// Build the error returned when this limit has been exceeded for `namespace`
+    pub fn exceeded(self, namespace: &SqlIdentifier) -> ReadySetError {
+        ReadySetError::NamespaceQuotaExceeded {
+            namespace: namespace.to_string(),
+            limit: self.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::{parse_select_statement, Dialect};
+
+    use super::*;
+
+    fn namespace(query: &str) -> Option<SqlIdentifier> {
+        namespace_of(
+            &parse_select_statement(Dialect::MySQL, query).unwrap(),
+            &["db".into()],
+        )
+    }
+
+    #[test]
+    fn namespace_of_query() {
+        assert_eq!(namespace("SELECT * FROM t"), Some("db".into()));
+        assert_eq!(
+            namespace("SELECT * FROM other.t JOIN u ON other.t.x = u.x"),
+            Some("other".into())
+        );
+        assert_eq!(
+            namespace("SELECT * FROM (SELECT * FROM other.t) sq"),
+            Some("other".into())
+        );
+        assert_eq!(namespace("SELECT 1"), None);
+    }
+
+    #[test]
+    fn parse_override() {
+        assert_eq!(
+            "analytics:caches=10, qps=500"
+                .parse::<NamespaceQuotaOverride>()
+                .unwrap(),
+            NamespaceQuotaOverride {
+                namespace: "analytics".into(),
+                quota: NamespaceQuota {
+                    max_caches: Some(10),
+                    max_materialized_bytes: None,
+                    max_reads_per_second: Some(500),
+                },
+            }
+        );
+        assert!("analytics".parse::<NamespaceQuotaOverride>().is_err());
+        assert!("analytics:rows=1"
+            .parse::<NamespaceQuotaOverride>()
+            .is_err());
+        assert!("analytics:caches=x"
+            .parse::<NamespaceQuotaOverride>()
+            .is_err());
+    }
+}
@@ -0,0 +1,231 @@
+//! Worker resources, and constraints on which workers domains can be placed onto.
+//!
+//! Every worker advertises its [`WorkerResources`] to the controller when it registers: how much
+//! memory and how many cores it has, whether it stores base tables on disk, which zone it's
+//! running in, and arbitrary labels. Deployments (and individual migrations, such as the creation
+//! of a cache) can then specify [`PlacementConstraints`] on the workers that domains containing
+//! readers or base tables may be scheduled onto, for example to keep readers in the same zone as
+//! the adapters reading from them, or to only run base tables on workers that persist them to
+//! disk.
+//!
+//! Constraints are parsed from strings of the form:
+//!
+//! * `zone=<zone>` - the worker must be running in the given zone
+//! * `label:<key>=<value>` - the worker must have the given label
+//! * `disk-backed` - the worker must store base tables on disk
+//! * `min-memory=<bytes>` - the worker must have at least the given amount of memory
+//! * `min-cores=<cores>` - the worker must have at least the given number of cores
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+use readyset_errors::{ReadySetError, ReadySetResult};
+use serde::{Deserialize, Serialize};
+
+/// The resources and other properties of a worker, advertised to the controller when the worker
+/// registers
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct WorkerResources {
+    /// The amount of memory available to the worker, in bytes, if known
+    pub memory_bytes: Option<usize>,
+    /// The number of cores available to the worker, if known
+    pub cores: Option<usize>,
+    /// Whether the worker stores base tables on disk
+    pub disk_backed: bool,
+    /// The zone (for example, the cloud availability zone) the worker is running in, if known
+    pub zone: Option<String>,
+    /// Arbitrary labels attached to the worker
+    pub labels: BTreeMap<String, String>,
+}
+
+/// A single constraint on the workers a domain may be scheduled onto. See the
+/// [module documentation](self) for the string format.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub enum WorkerConstraint {
+    /// The worker must be running in the given zone
+    Zone(String),
+    /// The worker must have a label with the given key and value
+    Label {
+        /// The key of the label
+        key: String,
+        /// The value the label must have
+        value: String,
+    },
+    /// The worker must store base tables on disk
+    DiskBacked,
+    /// The worker must have at least the given number of bytes of memory
+    MinMemory(usize),
+    /// The worker must have at least the given number of cores
+    MinCores(usize),
+}
+
+impl WorkerConstraint {
+    /// Returns `true` if a worker with the given resources meets this constraint. Workers which
+    /// don't know their memory or number of cores never meet a constraint on them.
+    pub fn is_met_by(&self, resources: &WorkerResources) -> bool {
+        match self {
+            WorkerConstraint::Zone(zone) => resources.zone.as_ref() == Some(zone),
+            WorkerConstraint::Label { key, value } => resources.labels.get(key) == Some(value),
+            WorkerConstraint::DiskBacked => resources.disk_backed,
+            WorkerConstraint::MinMemory(min) => resources
+                .memory_bytes
+                .map_or(false, |memory| memory >= *min),
+            WorkerConstraint::MinCores(min) => resources.cores.map_or(false, |cores| cores >= *min),
+        }
+    }
+}
+
+impl FromStr for WorkerConstraint {
+    type Err = ReadySetError;
+
+    fn from_str(s: &str) -> ReadySetResult<Self> {
+        let invalid = |reason: &str| {
+            ReadySetError::BadRequest(format!("Invalid placement constraint `{s}`: {reason}"))
+        };
+        let number = |value: &str| value.trim().parse().map_err(|_| invalid(value));
+
+        if s.trim() == "disk-backed" {
+            return Ok(WorkerConstraint::DiskBacked);
+        }
+
+        let (name, value) = s
+            .split_once('=')
+            .ok_or_else(|| invalid("expected `<constraint>=<value>` or `disk-backed`"))?;
+        match name.trim() {
+            "zone" => Ok(WorkerConstraint::Zone(value.trim().to_owned())),
+            "min-memory" => Ok(WorkerConstraint::MinMemory(number(value)?)),
+            "min-cores" => Ok(WorkerConstraint::MinCores(number(value)?)),
+            name => match name.strip_prefix("label:") {
+                Some(key) if !key.is_empty() => Ok(WorkerConstraint::Label {
+                    key: key.to_owned(),
+                    value: value.trim().to_owned(),
+                }),
+                _ => Err(invalid(&format!(
+                    "unknown constraint `{name}`, expected one of `zone`, `label:<key>`, \
+                     `disk-backed`, `min-memory` or `min-cores`"
+                ))),
+            },
+        }
+    }
+}
+
+impl Display for WorkerConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkerConstraint::Zone(zone) => write!(f, "zone={zone}"),
+            WorkerConstraint::Label { key, value } => write!(f, "label:{key}={value}"),
+            WorkerConstraint::DiskBacked => f.write_str("disk-backed"),
+            WorkerConstraint::MinMemory(min) => write!(f, "min-memory={min}"),
+            WorkerConstraint::MinCores(min) => write!(f, "min-cores={min}"),
+        }
+    }
+}
+
+/// Constraints on the workers that domains may be scheduled onto, depending on the kinds of nodes
+/// they contain
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct PlacementConstraints {
+    /// Constraints on the workers domains containing reader nodes may be scheduled onto
+    pub readers: Vec<WorkerConstraint>,
+    /// Constraints on the workers domains containing base table nodes may be scheduled onto
+    pub base_tables: Vec<WorkerConstraint>,
+}
+
+impl PlacementConstraints {
+    /// Returns `true` if there are no constraints on any kind of domain
+    pub fn is_empty(&self) -> bool {
+        self.readers.is_empty() && self.base_tables.is_empty()
+    }
+
+    /// Add all the constraints in `other` to these constraints
+    pub fn extend(&mut self, other: PlacementConstraints) {
+        self.readers.extend(other.readers);
+        self.base_tables.extend(other.base_tables);
+    }
+
+    /// Returns `true` if a domain which does or doesn't contain reader nodes and base table nodes
+    /// may be scheduled onto a worker with the given resources
+    pub fn allow(
+        &self,
+        resources: &WorkerResources,
+        is_reader_domain: bool,
+        is_base_table_domain: bool,
+    ) -> bool {
+        (!is_reader_domain || self.readers.iter().all(|c| c.is_met_by(resources)))
+            && (!is_base_table_domain || self.base_tables.iter().all(|c| c.is_met_by(resources)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resources() -> WorkerResources {
+        WorkerResources {
+            memory_bytes: Some(1 << 30),
+            cores: Some(4),
+            disk_backed: false,
+            zone: Some("us-east-1a".to_owned()),
+            labels: [("tier".to_owned(), "gold".to_owned())]
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn parse_constraints() {
+        for (s, constraint) in [
+            (
+                "zone=us-east-1a",
+                WorkerConstraint::Zone("us-east-1a".into()),
+            ),
+            (
+                "label:tier=gold",
+                WorkerConstraint::Label {
+                    key: "tier".into(),
+                    value: "gold".into(),
+                },
+            ),
+            ("disk-backed", WorkerConstraint::DiskBacked),
+            ("min-memory=1024", WorkerConstraint::MinMemory(1024)),
+            ("min-cores=8", WorkerConstraint::MinCores(8)),
+        ] {
+            assert_eq!(s.parse::<WorkerConstraint>().unwrap(), constraint);
+            assert_eq!(constraint.to_string(), s);
+        }
+
+        for s in ["zone", "min-cores=many", "label:=x", "color=blue"] {
+            assert!(s.parse::<WorkerConstraint>().is_err(), "{s}");
+        }
+    }
+
+    #[test]
+    fn constraints_met_by_resources() {
+        let resources = resources();
+        assert!(WorkerConstraint::Zone("us-east-1a".into()).is_met_by(&resources));
+        assert!(!WorkerConstraint::Zone("us-east-1b".into()).is_met_by(&resources));
+        assert!("label:tier=gold"
+            .parse::<WorkerConstraint>()
+            .unwrap()
+            .is_met_by(&resources));
+        assert!(!WorkerConstraint::DiskBacked.is_met_by(&resources));
+        assert!(WorkerConstraint::MinMemory(1 << 20).is_met_by(&resources));
+        assert!(!WorkerConstraint::MinCores(8).is_met_by(&resources));
+        assert!(!WorkerConstraint::MinCores(1).is_met_by(&WorkerResources::default()));
+    }
+
+    #[test]
+    fn constraints_by_domain_kind() {
+        let constraints = PlacementConstraints {
+            readers: vec![WorkerConstraint::Zone("us-east-1a".into())],
+            base_tables: vec![WorkerConstraint::DiskBacked],
+        };
+        let resources = resources();
+
+        assert!(constraints.allow(&resources, true, false));
+        assert!(!constraints.allow(&resources, false, true));
+        assert!(!constraints.allow(&resources, true, true));
+        assert!(constraints.allow(&resources, false, false));
+        assert!(PlacementConstraints::default().allow(&WorkerResources::default(), true, true));
+    }
+}
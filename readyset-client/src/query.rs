@@ -8,17 +8,45 @@
 use std::borrow::Borrow;
 use std::fmt::{self, Display};
 use std::hash::{Hash, Hasher};
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
+use nom_sql::{SelectStatement, SqlIdentifier};
+use readyset_errors::ReadySetError;
 use readyset_sql_passes::anonymize::{Anonymize, Anonymizer};
 use serde::ser::{SerializeSeq, SerializeTuple};
 use serde::{Deserialize, Serialize, Serializer};
+use sha1::{Digest, Sha1};
 
 use crate::ViewCreateRequest;
 
-/// A QueryId is a string with the prefix `q_` and the suffix of the hash of the query
-#[derive(Clone, Copy, Debug, Serialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// The version of the fingerprint used to compute [`QueryId`]s.
+///
+/// This must be incremented whenever a change is made which would change the id computed for any
+/// query - either to the canonical form described in the documentation for [`QueryId`], or to the
+/// way queries are rewritten by the adapter before their ids are computed. The controller uses
+/// this version to map ids computed by previous versions to the ids of the same queries under the
+/// current version.
+pub const QUERY_ID_VERSION: u32 = 1;
+
+/// A stable identifier for a query, displayed as the prefix `q_` followed by a 64-bit fingerprint
+/// of the query in hexadecimal.
+///
+/// The fingerprint is the first 8 bytes (as a little-endian integer) of the SHA-1 digest of the
+/// query's canonical form, which consists of:
+///
+/// 1. [`QUERY_ID_VERSION`], as 4 little-endian bytes
+/// 2. For queries that parsed successfully, the byte `P`, followed by each schema in the query's
+///    schema search path terminated by a NUL byte, followed by another NUL byte, followed by the
+///    [`Display`] representation of the query's [`SelectStatement`] (after it has been rewritten by
+///    the adapter)
+/// 3. For queries that failed to parse, the byte `U`, followed by the text of the query
+///
+/// Since the canonical form is defined in terms of the SQL text of the query rather than the
+/// in-memory structure of its AST, ids are stable across changes to the AST types which don't
+/// change how queries are displayed.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
 #[repr(transparent)]
 pub struct QueryId(u64);
 
@@ -27,6 +55,60 @@ impl QueryId {
     pub fn new(id: u64) -> Self {
         QueryId(id)
     }
+
+    fn from_canonical_form(kind: u8, parts: &[&[u8]]) -> Self {
+        let mut hasher = Sha1::new();
+        hasher.update(QUERY_ID_VERSION.to_le_bytes());
+        hasher.update([kind]);
+        for part in parts {
+            hasher.update(part);
+        }
+        // Sha1 digest is 20 bytes long, so it is safe to consume only 8 bytes
+        let mut id = [0; 8];
+        id.copy_from_slice(&hasher.finalize()[..8]);
+        QueryId(u64::from_le_bytes(id))
+    }
+
+    /// Compute the [`QueryId`] for the given (rewritten) select statement, executed with the given
+    /// schema search path
+    pub fn from_select(statement: &SelectStatement, schema_search_path: &[SqlIdentifier]) -> Self {
+        let mut parts: Vec<&[u8]> = Vec::with_capacity(schema_search_path.len() * 2 + 2);
+        for schema in schema_search_path {
+            parts.push(schema.as_bytes());
+            parts.push(b"\0");
+        }
+        parts.push(b"\0");
+        // Comments don't change the query, so they're left out of its id
+        let statement = if statement.comments.is_empty() {
+            statement.to_string()
+        } else {
+            SelectStatement {
+                comments: Default::default(),
+                ..statement.clone()
+            }
+            .to_string()
+        };
+        parts.push(statement.as_bytes());
+        Self::from_canonical_form(b'P', &parts)
+    }
+
+    /// Compute the [`QueryId`] for the given query which failed to parse
+    pub fn from_unparsed_select(query: &str) -> Self {
+        Self::from_canonical_form(b'U', &[query.as_bytes()])
+    }
+
+    /// Compute the id that was assigned to the given query by versions of ReadySet prior to the
+    /// introduction of [`QUERY_ID_VERSION`], which hashed the in-memory representation of the
+    /// query. Used to map ids generated by those versions to current ids.
+    pub fn legacy(request: &ViewCreateRequest) -> Self {
+        QueryId(readyset_util::hash::hash(request))
+    }
+}
+
+impl From<QueryId> for u64 {
+    fn from(id: QueryId) -> Self {
+        id.0
+    }
 }
 
 impl Display for QueryId {
@@ -35,6 +117,38 @@ impl Display for QueryId {
     }
 }
 
+impl FromStr for QueryId {
+    type Err = ReadySetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.strip_prefix("q_")
+            .and_then(|id| u64::from_str_radix(id, 16).ok())
+            .map(QueryId)
+            .ok_or_else(|| ReadySetError::NoQueryForId { id: s.to_owned() })
+    }
+}
+
+impl From<&ViewCreateRequest> for QueryId {
+    fn from(request: &ViewCreateRequest) -> Self {
+        Self::from_select(&request.statement, &request.schema_search_path)
+    }
+}
+
+impl From<&String> for QueryId {
+    fn from(query: &String) -> Self {
+        Self::from_unparsed_select(query)
+    }
+}
+
+impl From<&Query> for QueryId {
+    fn from(query: &Query) -> Self {
+        match query {
+            Query::Parsed(request) => Self::from(request.as_ref()),
+            Query::ParseFailed(query) => Self::from(query.as_ref()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Eq)]
 /// A Query that was made against readyset, which could have either been parsed successfully or
 /// failed to parse.
@@ -209,6 +323,14 @@ impl QueryStatus {
         self.migration_state == MigrationState::Successful
     }
 
+    /// Returns true if this query status represents a [warming][] query
+    ///
+    /// [warming]: MigrationState::Warming
+    #[must_use]
+    pub fn is_warming(&self) -> bool {
+        self.migration_state == MigrationState::Warming
+    }
+
     /// Returns true if this query status represents an [unsupported][] query
     ///
     /// [unsupported]: MigrationState::Unsupported
@@ -228,7 +350,10 @@ impl QueryStatus {
     /// Returns true if the query should be considered "denied"
     #[must_use]
     pub fn is_denied(&self) -> bool {
-        self.is_unsupported() || self.is_pending() || self.is_dry_run_succeeded()
+        self.is_unsupported()
+            || self.is_pending()
+            || self.is_dry_run_succeeded()
+            || self.is_warming()
     }
 }
 
@@ -247,6 +372,9 @@ pub enum MigrationState {
     /// Indicates that a dry run of the query has succeeded. It's very likely but not guaranteed
     /// that migration of the query will succeed if it's attempted.
     DryRunSucceeded,
+    /// A cache for this query is being created concurrently, and its initial state is still being
+    /// backfilled in the background. Reads are proxied upstream until the backfill completes.
+    Warming,
 }
 
 impl MigrationState {
@@ -268,6 +396,16 @@ impl MigrationState {
             MigrationState::Pending | MigrationState::DryRunSucceeded
         )
     }
+
+    /// Returns true if a cache for the query has been created, but is still being backfilled.
+    ///
+    /// Warming queries are tracked separately from [pending][] queries, since there's nothing left
+    /// for the migration handler to do for them.
+    ///
+    /// [pending]: MigrationState::is_pending
+    pub fn is_warming(&self) -> bool {
+        *self == MigrationState::Warming
+    }
 }
 
 impl Display for MigrationState {
@@ -275,6 +413,7 @@ impl Display for MigrationState {
         match self {
             MigrationState::Pending => write!(f, "pending"),
             MigrationState::Successful => write!(f, "successful"),
+            MigrationState::Warming => write!(f, "warming"),
             MigrationState::Unsupported => write!(f, "unsupported"),
             MigrationState::DryRunSucceeded => write!(f, "dry run succeeded"),
         }
@@ -469,4 +608,72 @@ mod tests {
     }
 
     hash_laws!(Query);
+
+    fn request(query: &str, schema_search_path: Vec<SqlIdentifier>) -> ViewCreateRequest {
+        ViewCreateRequest::new(
+            nom_sql::parse_select_statement(nom_sql::Dialect::MySQL, query).unwrap(),
+            schema_search_path,
+        )
+    }
+
+    /// Query ids are persisted and relied on by users, so they must not change unless
+    /// [`QUERY_ID_VERSION`] is bumped. If this test fails, either revert the change that changed
+    /// the id, or bump [`QUERY_ID_VERSION`] and update the expected ids.
+    #[test]
+    fn query_ids_are_stable() {
+        assert_eq!(QUERY_ID_VERSION, 1);
+        assert_eq!(
+            QueryId::from(&request(
+                "SELECT a, count(*) FROM t WHERE b = ? GROUP BY a",
+                vec!["s1".into(), "public".into()]
+            ))
+            .to_string(),
+            "q_c0de8e7805ce36b8"
+        );
+        assert_eq!(
+            QueryId::from(&"SELECT * FROM t WHERE x = ?".to_owned()).to_string(),
+            "q_7869b0dfb51366d"
+        );
+    }
+
+    #[test]
+    fn query_id_depends_on_schema_search_path() {
+        let q1 = request("SELECT * FROM t", vec!["s1".into(), "s2".into()]);
+        let q2 = request("SELECT * FROM t", vec!["s1s2".into()]);
+        assert_ne!(QueryId::from(&q1), QueryId::from(&q2));
+    }
+
+    #[test]
+    fn query_id_ignores_comments() {
+        let commented = match nom_sql::parse_query_with_comments(
+            nom_sql::Dialect::MySQL,
+            "/* dashboard */ SELECT * FROM t WHERE x = ? -- by x",
+        )
+        .unwrap()
+        {
+            nom_sql::SqlQuery::Select(select) => select,
+            _ => unreachable!(),
+        };
+        assert!(!commented.comments.is_empty());
+        assert_eq!(
+            QueryId::from(&ViewCreateRequest::new(commented, vec![])),
+            QueryId::from(&request("SELECT * FROM t WHERE x = ?", vec![]))
+        );
+    }
+
+    #[test]
+    fn query_id_round_trips_through_string() {
+        let id = QueryId::from(&request("SELECT * FROM t", vec![]));
+        assert_eq!(id.to_string().parse::<QueryId>().unwrap(), id);
+        "q_xyz".parse::<QueryId>().unwrap_err();
+        "12345".parse::<QueryId>().unwrap_err();
+    }
+
+    #[test]
+    fn warming_is_not_pending() {
+        assert!(MigrationState::Warming.is_warming());
+        assert!(!MigrationState::Warming.is_pending());
+        assert!(MigrationState::Pending.is_pending());
+        assert!(!MigrationState::Pending.is_warming());
+    }
 }
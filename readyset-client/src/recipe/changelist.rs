@@ -8,6 +8,7 @@
 //! - `CREATE VIEW`
 //! - `DROP CACHED QUERY`
 //! - `DROP TABLE`
+//! - `ALTER READYSET SET MIR PLAN`
 //! Said list of [`Change`]s are sorted in the same order as the queries came in. This guarantees
 //! that within the same request we can have queries like these:
 //! ```SQL
@@ -34,9 +35,9 @@
 use dataflow_expression::Dialect;
 use nom_locate::LocatedSpan;
 use nom_sql::{
-    AlterTableStatement, CacheInner, CreateCacheStatement, CreateTableStatement,
-    CreateViewStatement, DropTableStatement, DropViewStatement, Relation, SelectStatement,
-    SqlIdentifier, SqlQuery,
+    AlterReadysetStatement, AlterTableStatement, CacheFreshness, CacheInner, CacheResultLimits,
+    CreateCacheStatement, CreateTableStatement, CreateViewStatement, DropTableStatement,
+    DropViewStatement, Relation, SelectStatement, SqlIdentifier, SqlQuery,
 };
 use readyset_data::DfType;
 use readyset_errors::{unsupported, ReadySetError, ReadySetResult};
@@ -202,6 +203,10 @@ impl ChangeList {
                                 name: dcs.name,
                                 if_exists: false,
                             }),
+                            SqlQuery::AlterReadyset(AlterReadysetStatement::SetMirPlan {
+                                cache,
+                                plan,
+                            }) => changes.push(Change::SetMirPlan { name: cache, plan }),
                             _ => unsupported!(
                                 "Only DDL statements supported in ChangeList (got {})",
                                 parsed.query_type()
@@ -332,19 +337,89 @@ pub enum Change {
         /// If `false`, then an error should be thrown if the relation is not found.
         if_exists: bool,
     },
+    /// Replace the dataflow for an existing cached query with dataflow built from a MIR plan,
+    /// represented by the given `ALTER READYSET SET MIR PLAN` statement
+    SetMirPlan {
+        /// The name of the cached query
+        name: Relation,
+        /// The MIR plan for the query, serialized as JSON
+        plan: String,
+    },
+}
+
+/// The options a cached query is created with, as specified in its `CREATE CACHE` statement. See
+/// the fields of the same names on [`CreateCacheStatement`] for what each of them means.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheOptions {
+    /// Whether reads of the cache are always attempted against ReadySet (`ALWAYS`)
+    pub always: bool,
+    /// Whether filters may be pushed below joins (false with `NO FILTER PUSHDOWN`)
+    pub filter_pushdown: bool,
+    /// Whether aggregates are estimated from sketches (`APPROXIMATE AGGREGATES`)
+    pub approximate_aggregates: bool,
+    /// Whether joins against dimension tables are performed at read time (`LAZY JOINS`)
+    pub lazy_joins: bool,
+    /// Constraints on the workers the readers of the cache may be placed on (`PLACE READERS ON`)
+    pub reader_placement: Vec<String>,
+    /// How long evicted keys may be served stale, in seconds (`MAX STALENESS`)
+    pub max_staleness: Option<u64>,
+    /// How long keys containing a timestamp are retained, in seconds (`BUCKET RETENTION`)
+    pub bucket_retention: Option<u64>,
+    /// A bound on the replication lag at which reads are served from the cache
+    pub freshness: CacheFreshness,
+    /// Limits on the size of the results read from the cache
+    pub result_limits: CacheResultLimits,
+}
+
+impl Default for CacheOptions {
+    fn default() -> Self {
+        Self {
+            always: false,
+            filter_pushdown: true,
+            approximate_aggregates: false,
+            lazy_joins: false,
+            reader_placement: vec![],
+            max_staleness: None,
+            bucket_retention: None,
+            freshness: Default::default(),
+            result_limits: Default::default(),
+        }
+    }
 }
 
 impl Change {
-    /// Creates a new [`Change::CreateCache`] from the given `name` and
-    /// [`SelectStatement`].
-    pub fn create_cache<N>(name: N, statement: SelectStatement, always: bool) -> Self
+    /// Creates a new [`Change::CreateCache`] from the given `name`, [`SelectStatement`], and
+    /// [`CacheOptions`].
+    pub fn create_cache<N>(name: N, statement: SelectStatement, options: CacheOptions) -> Self
     where
         N: Into<Relation>,
     {
+        let CacheOptions {
+            always,
+            filter_pushdown,
+            approximate_aggregates,
+            lazy_joins,
+            reader_placement,
+            max_staleness,
+            bucket_retention,
+            freshness,
+            result_limits,
+        } = options;
         Self::CreateCache(CreateCacheStatement {
             name: Some(name.into()),
             inner: CacheInner::Statement(Box::new(statement)),
             always,
+            filter_pushdown,
+            approximate_aggregates,
+            lazy_joins,
+            reader_placement,
+            // Concurrent creation is handled by the adapter; the server always migrates the cache
+            // synchronously
+            concurrently: false,
+            max_staleness,
+            bucket_retention,
+            freshness,
+            result_limits,
         })
     }
 
@@ -400,6 +475,7 @@ impl Change {
             | Change::CreateCache(_)
             | Change::CreateType { .. }
             | Change::Drop { .. }
+            | Change::SetMirPlan { .. }
             | Change::AddNonReplicatedRelation(_) => false,
         }
     }
@@ -530,6 +606,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn it_handles_set_mir_plan() {
+        let queries = "CREATE CACHE q_0 FROM SELECT a FROM b;\
+                       ALTER READYSET SET MIR PLAN FOR q_0 TO '{\"nodes\": []}';";
+
+        let changelist = ChangeList::from_str(queries, Dialect::DEFAULT_MYSQL).unwrap();
+        assert_eq!(changelist.changes.len(), 2);
+        assert!(matches!(
+            &changelist.changes[1],
+            Change::SetMirPlan { name, plan }
+                if name == &Relation::from("q_0") && plan == r#"{"nodes": []}"#
+        ));
+    }
+
+    #[test]
+    fn create_cache_with_default_options() {
+        let queries = "CREATE CACHE q_0 FROM SELECT a FROM b;";
+
+        let changelist = ChangeList::from_str(queries, Dialect::DEFAULT_MYSQL).unwrap();
+        let parsed = match &changelist.changes[..] {
+            [Change::CreateCache(parsed)] => parsed,
+            _ => panic!(
+                "expected a single CREATE CACHE, got {:?}",
+                changelist.changes
+            ),
+        };
+        let statement = match &parsed.inner {
+            CacheInner::Statement(statement) => (**statement).clone(),
+            CacheInner::Id(_) => panic!("expected a statement"),
+        };
+        match Change::create_cache("q_0", statement, CacheOptions::default()) {
+            Change::CreateCache(created) => assert_eq!(&created, parsed),
+            change => panic!("expected a CREATE CACHE, got {:?}", change),
+        }
+    }
+
     mod requires_resnapshot {
         use super::*;
 
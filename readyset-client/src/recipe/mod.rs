@@ -6,6 +6,7 @@ use std::borrow::Cow;
 
 use serde::{Deserialize, Serialize};
 
+use crate::placement::PlacementConstraints;
 pub use crate::recipe::changelist::ChangeList;
 use crate::ReplicationOffset;
 
@@ -20,6 +21,10 @@ pub struct ExtendRecipeSpec<'a> {
     /// this RecipeSpec.
     /// Defaults to true.
     pub require_leader_ready: bool,
+    /// Constraints on which workers the domains created by this change may be scheduled onto, in
+    /// addition to any constraints configured for the whole deployment
+    #[serde(default)]
+    pub placement_constraints: PlacementConstraints,
 }
 
 impl<'a> From<ChangeList> for ExtendRecipeSpec<'a> {
@@ -28,6 +33,7 @@ impl<'a> From<ChangeList> for ExtendRecipeSpec<'a> {
             changes,
             replication_offset: None,
             require_leader_ready: true,
+            placement_constraints: Default::default(),
         }
     }
 }
@@ -11,11 +11,13 @@ use std::convert::TryFrom;
 use std::fmt::{self, Display};
 
 use mysql_common::row::Row;
-use readyset_errors::{internal, ReadySetError};
+use readyset_errors::{internal, internal_err, ReadySetError};
 use serde::{Deserialize, Serialize};
 
 // Consts for variable names.
 const SNAPSHOT_STATUS_VARIABLE: &str = "Snapshot Status";
+const REPLICATION_PAUSED_VARIABLE: &str = "Replication Paused";
+const REPLICATION_RECONNECTS_VARIABLE: &str = "Replication Reconnects";
 
 /// ReadySetStatus holds information regarding the status of ReadySet, similar to
 /// [`SHOW STATUS`](https://dev.mysql.com/doc/refman/8.0/en/show-status.html) in MySQL.
@@ -25,6 +27,13 @@ const SNAPSHOT_STATUS_VARIABLE: &str = "Snapshot Status";
 pub struct ReadySetStatus {
     /// The snapshot status of the current leader.
     pub snapshot_status: SnapshotStatus,
+    /// Whether replication from the upstream database has been paused.
+    #[serde(default)]
+    pub replication_paused: bool,
+    /// The number of times the replicator has reconnected to the upstream database after its
+    /// replication connection was lost or went idle.
+    #[serde(default)]
+    pub replication_reconnects: u64,
     //TODO: Include binlog position and other fields helpful for evaluating a ReadySet cluster.
 }
 
@@ -33,10 +42,24 @@ impl TryFrom<Vec<(String, String)>> for ReadySetStatus {
     fn try_from(vars: Vec<(String, String)>) -> Result<Self, Self::Error> {
         let mut res = ReadySetStatus {
             snapshot_status: SnapshotStatus::InProgress,
+            replication_paused: false,
+            replication_reconnects: 0,
         };
         for v in vars {
             match (v.0.as_str(), v.1) {
                 (SNAPSHOT_STATUS_VARIABLE, v) => res.snapshot_status = SnapshotStatus::try_from(v)?,
+                (REPLICATION_PAUSED_VARIABLE, v) => {
+                    res.replication_paused = match v.as_str() {
+                        "Yes" => true,
+                        "No" => false,
+                        _ => internal!("Invalid replication paused status"),
+                    }
+                }
+                (REPLICATION_RECONNECTS_VARIABLE, v) => {
+                    res.replication_reconnects = v
+                        .parse()
+                        .map_err(|_| internal_err!("Invalid replication reconnects count"))?
+                }
                 (_, _) => {
                     internal!("Invalid ReadySetStatus variable")
                 }
@@ -49,10 +72,25 @@ impl TryFrom<Vec<(String, String)>> for ReadySetStatus {
 
 impl From<ReadySetStatus> for Vec<(String, String)> {
     fn from(status: ReadySetStatus) -> Vec<(String, String)> {
-        vec![(
-            SNAPSHOT_STATUS_VARIABLE.to_string(),
-            status.snapshot_status.to_string(),
-        )]
+        vec![
+            (
+                SNAPSHOT_STATUS_VARIABLE.to_string(),
+                status.snapshot_status.to_string(),
+            ),
+            (
+                REPLICATION_PAUSED_VARIABLE.to_string(),
+                if status.replication_paused {
+                    "Yes"
+                } else {
+                    "No"
+                }
+                .to_string(),
+            ),
+            (
+                REPLICATION_RECONNECTS_VARIABLE.to_string(),
+                status.replication_reconnects.to_string(),
+            ),
+        ]
     }
 }
 
@@ -116,6 +154,8 @@ mod tests {
     fn readyset_status_round_trip() {
         let original = ReadySetStatus {
             snapshot_status: SnapshotStatus::Completed,
+            replication_paused: true,
+            replication_reconnects: 3,
         };
         let intermediate: Vec<(String, String)> = original.clone().into();
         let round_tripped = ReadySetStatus::try_from(intermediate).unwrap();
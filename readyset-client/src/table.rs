@@ -15,9 +15,9 @@ use futures_util::stream::futures_unordered::FuturesUnordered;
 use futures_util::stream::TryStreamExt;
 use futures_util::{future, ready};
 use itertools::Either;
-use nom_sql::{CreateTableBody, Relation, SqlIdentifier};
+use nom_sql::{ColumnConstraint, CreateTableBody, Relation, SqlIdentifier, TableKey};
 use petgraph::graph::NodeIndex;
-use readyset_data::DfValue;
+use readyset_data::{column_on_update_value, DfType, DfValue};
 use readyset_errors::{
     internal, internal_err, rpc_err, table_err, unsupported, ReadySetError, ReadySetResult,
 };
@@ -31,7 +31,7 @@ use tower::limit::concurrency::ConcurrencyLimit;
 use tower_service::Service;
 use vec_map::VecMap;
 
-use crate::channel::CONNECTION_FROM_BASE;
+use crate::channel::{ChannelSecurity, ChannelStream, Preamble};
 use crate::internal::*;
 use crate::replication::ReplicationOffset;
 use crate::{consistency, Tagged, Tagger};
@@ -160,13 +160,18 @@ impl From<Vec<DfValue>> for TableOperation {
     }
 }
 
-type Transport =
-    AsyncBincodeStream<tokio::net::TcpStream, Tagged<()>, Tagged<PacketData>, AsyncDestination>;
+type Transport = AsyncBincodeStream<
+    ChannelStream<tokio::net::TcpStream>,
+    Tagged<ReadySetResult<()>>,
+    Tagged<PacketData>,
+    AsyncDestination,
+>;
 
 #[derive(Debug)]
 struct Endpoint {
     addr: SocketAddr,
     timeout: Duration,
+    security: Option<ChannelSecurity>,
 }
 
 type InnerService = multiplex::Client<
@@ -188,10 +193,15 @@ impl Service<()> for Endpoint {
     fn call(&mut self, _: ()) -> Self::Future {
         let f = tokio::net::TcpStream::connect(self.addr);
         let timeout = self.timeout;
+        let security = self.security.clone();
         async move {
-            let mut s = tokio::time::timeout(timeout, f).await??;
+            let s = tokio::time::timeout(timeout, f).await??;
             s.set_nodelay(true)?;
-            s.write_all(&[CONNECTION_FROM_BASE]).await?;
+            let mut s = match security {
+                Some(security) => security.connect(s).await?,
+                None => ChannelStream::Plain(s),
+            };
+            s.write_all(&Preamble::new(true).to_bytes()).await?;
             s.flush().await?;
             let s = AsyncBincodeStream::from(s).for_async();
             let t = multiplex::MultiplexTransport::new(s, Tagger::default());
@@ -206,6 +216,7 @@ impl Service<()> for Endpoint {
 fn make_table_stream(
     addr: SocketAddr,
     timeout: Duration,
+    security: Option<ChannelSecurity>,
 ) -> impl futures_util::stream::TryStream<
     Ok = tower::discover::Change<usize, InnerService>,
     Error = tokio::io::Error,
@@ -213,15 +224,28 @@ fn make_table_stream(
     // TODO: use whatever comes out of https://github.com/tower-rs/tower/issues/456 instead of
     // creating _all_ the connections every time.
     (0..crate::TABLE_POOL_SIZE)
-        .map(|i| async move {
-            let svc = Endpoint { addr, timeout }.call(()).await?;
-            Ok(tower::discover::Change::Insert(i, svc))
+        .map(|i| {
+            let security = security.clone();
+            async move {
+                let svc = Endpoint {
+                    addr,
+                    timeout,
+                    security,
+                }
+                .call(())
+                .await?;
+                Ok(tower::discover::Change::Insert(i, svc))
+            }
         })
         .collect::<futures_util::stream::FuturesUnordered<_>>()
 }
 
-fn make_table_discover(addr: SocketAddr, timeout: Duration) -> Discover {
-    make_table_stream(addr, timeout)
+fn make_table_discover(
+    addr: SocketAddr,
+    timeout: Duration,
+    security: Option<ChannelSecurity>,
+) -> Discover {
+    make_table_stream(addr, timeout, security)
 }
 
 // Unpin + Send bounds are needed due to https://github.com/rust-lang/rust/issues/55997
@@ -253,6 +277,37 @@ pub struct PacketData {
     pub data: PacketPayload,
     /// Optional packet trace to associate with the packet.
     pub trace: Option<PacketTrace>,
+    /// If this packet is an input which is part of a [`WriteBatch`], the timestamp the batch will
+    /// be committed with. Readers don't make any writes visible between receiving the first
+    /// write in the batch and having that timestamp propagated to them.
+    pub batch: Option<consistency::Timestamp>,
+}
+
+impl PacketData {
+    /// Returns an input undoing the writes in this input, which must only insert rows and delete
+    /// whole rows (see [`WriteBatch`])
+    fn undo(&self) -> PacketData {
+        let ops = match &self.data {
+            PacketPayload::Input(ops) => ops
+                .iter()
+                .rev()
+                .filter_map(|op| match op {
+                    TableOperation::Insert(row) => {
+                        Some(TableOperation::DeleteRow { row: row.clone() })
+                    }
+                    TableOperation::DeleteRow { row } => Some(TableOperation::Insert(row.clone())),
+                    _ => None,
+                })
+                .collect(),
+            _ => vec![],
+        };
+        PacketData {
+            dst: self.dst,
+            data: PacketPayload::Input(ops),
+            trace: None,
+            batch: self.batch.clone(),
+        }
+    }
 }
 
 /// Wrapper around types that can be propagated to base tables
@@ -264,6 +319,10 @@ pub enum PacketPayload {
     Input(Vec<TableOperation>),
     /// A new timestamp to update the base table.
     Timestamp(consistency::Timestamp),
+    /// A barrier, which is propagated through the dataflow graph in the same way as a timestamp,
+    /// but only reaches each node once it has reached all of the node's inputs. Once a reader has
+    /// received a barrier, all writes made to base tables before the barrier are reflected in it.
+    Barrier(u64),
 }
 
 impl fmt::Debug for PacketData {
@@ -312,6 +371,8 @@ pub struct TableBuilder {
 
     pub table_name: Relation,
     pub columns: Vec<SqlIdentifier>,
+    /// The types of `columns`, as determined by the dialect the table was created in
+    pub column_types: Vec<DfType>,
     pub schema: Option<CreateTableBody>,
 
     /// The amount of time before a table request RPC is terminated.
@@ -319,7 +380,11 @@ pub struct TableBuilder {
 }
 
 impl TableBuilder {
-    pub(crate) fn build(self, rpcs: Arc<Mutex<HashMap<(SocketAddr, usize), TableRpc>>>) -> Table {
+    pub(crate) fn build(
+        self,
+        rpcs: Arc<Mutex<HashMap<(SocketAddr, usize), TableRpc>>>,
+        security: Option<&ChannelSecurity>,
+    ) -> Table {
         let mut addrs = Vec::with_capacity(self.txs.len());
         let mut conns = Vec::with_capacity(self.txs.len());
         for (shardi, &addr) in self.txs.iter().enumerate() {
@@ -340,7 +405,11 @@ impl TableBuilder {
                     // TODO: maybe always use the same local port?
                     let (c, w) = Buffer::pair(
                         ConcurrencyLimit::new(
-                            Balance::new(make_table_discover(addr, self.table_request_timeout)),
+                            Balance::new(make_table_discover(
+                                addr,
+                                self.table_request_timeout,
+                                security.cloned(),
+                            )),
                             crate::PENDING_LIMIT,
                         ),
                         crate::BUFFER_TO_POOL,
@@ -364,6 +433,7 @@ impl TableBuilder {
             key: self.key,
             key_is_primary: self.key_is_primary,
             columns: self.columns,
+            column_types: self.column_types,
             dropped: self.dropped,
             table_name: self.table_name,
             schema: self.schema,
@@ -389,6 +459,7 @@ pub struct Table {
     key_is_primary: bool,
     key: Vec<usize>,
     columns: Vec<SqlIdentifier>,
+    column_types: Vec<DfType>,
     dropped: VecMap<DfValue>,
     table_name: Relation,
     schema: Option<CreateTableBody>,
@@ -431,64 +502,10 @@ impl Table {
 
         // NOTE: this is really just a try block
         let immediate_err = || {
-            let ncols = self.columns.len() + self.dropped.len();
             let ops: &Vec<TableOperation> = (&i.data)
                 .try_into()
                 .map_err(|_| ReadySetError::WrongPacketDataType)?;
-            for op in ops {
-                match op {
-                    TableOperation::Insert(ref row) | TableOperation::DeleteRow { ref row } => {
-                        if row.len() != ncols {
-                            return Err(ReadySetError::WrongColumnCount(ncols, row.len()));
-                        }
-                    }
-                    TableOperation::DeleteByKey { ref key } => {
-                        if key.len() != self.key.len() {
-                            return Err(ReadySetError::WrongKeyColumnCount(
-                                self.key.len(),
-                                key.len(),
-                            ));
-                        }
-                    }
-                    TableOperation::InsertOrUpdate {
-                        ref row,
-                        ref update,
-                    } => {
-                        if row.len() != ncols {
-                            return Err(ReadySetError::WrongColumnCount(ncols, row.len()));
-                        }
-                        if update.len() > self.columns.len() {
-                            // NOTE: < is okay to allow dropping tailing no-ops
-                            return Err(ReadySetError::WrongColumnCount(
-                                self.columns.len(),
-                                update.len(),
-                            ));
-                        }
-                    }
-                    TableOperation::Update {
-                        ref update,
-                        ref key,
-                    } => {
-                        if key.len() != self.key.len() {
-                            return Err(ReadySetError::WrongKeyColumnCount(
-                                self.key.len(),
-                                key.len(),
-                            ));
-                        }
-                        if update.len() > self.columns.len() {
-                            // NOTE: < is okay to allow dropping tailing no-ops
-                            return Err(ReadySetError::WrongColumnCount(
-                                self.columns.len(),
-                                update.len(),
-                            ));
-                        }
-                    }
-                    TableOperation::SetReplicationOffset(_)
-                    | TableOperation::SetSnapshotMode(_)
-                    | TableOperation::Truncate => {}
-                }
-            }
-            Ok(())
+            self.check_ops(ops)
         };
 
         if let Err(e) = immediate_err() {
@@ -502,7 +519,12 @@ impl Table {
                 let _guard = span.as_ref().map(tracing::Span::enter);
                 trace!("submit request");
                 future::Either::Left(future::Either::Right(
-                    table_rpc.call(request).map_err(rpc_err!("Table::input")),
+                    table_rpc
+                        .call(request)
+                        .map_err(rpc_err!("Table::input"))
+                        .and_then(|Tagged { tag, v }| {
+                            future::ready(v.map(|()| Tagged { tag, v: () }))
+                        }),
                 ))
             }
             _ => {
@@ -553,6 +575,7 @@ impl Table {
                             dst: i.dst,
                             data: PacketPayload::Input(rs),
                             trace: i.trace.clone(),
+                            batch: i.batch.clone(),
                         };
 
                         let request = Tagged::from(new_i);
@@ -587,8 +610,8 @@ impl Table {
 
                 future::Either::Right(
                     wait_for
-                        .try_for_each(|_| async { Ok(()) })
                         .map_err(rpc_err!("Table::input"))
+                        .try_for_each(|Tagged { v, .. }| future::ready(v))
                         .map_ok(Tagged::from),
                 )
             }
@@ -608,7 +631,10 @@ impl Table {
                 future::Either::Left(
                     table_rpc
                         .call(request)
-                        .map_err(rpc_err!("Table::timestamp")),
+                        .map_err(rpc_err!("Table::timestamp"))
+                        .and_then(|Tagged { tag, v }| {
+                            future::ready(v.map(|()| Tagged { tag, v: () }))
+                        }),
                 )
             }
             _ => {
@@ -651,7 +677,7 @@ pub enum TableRequest {
 
 impl Service<TableRequest> for Table {
     type Error = ReadySetError;
-    type Response = <TableRpc as Service<Tagged<PacketData>>>::Response;
+    type Response = Tagged<()>;
 
     type Future = impl Future<Output = Result<Tagged<()>, ReadySetError>> + Send;
 
@@ -678,6 +704,7 @@ impl Service<TableRequest> for Table {
                     dst: self.node,
                     data: PacketPayload::Timestamp(t),
                     trace: None,
+                    batch: None,
                 };
                 future::Either::Right(self.timestamp(p).map_err(|e| table_err(table, e)))
             }
@@ -707,6 +734,44 @@ impl Table {
         self.schema.as_ref()
     }
 
+    /// Returns true if this base table has a primary key
+    pub fn has_primary_key(&self) -> bool {
+        self.key_is_primary && !self.key.is_empty()
+    }
+
+    /// Returns the indices of all the columns in this base table which are part of its primary
+    /// key, or of any other key or index in its schema, in ascending order.
+    pub fn key_columns(&self) -> Vec<usize> {
+        let mut key_columns = self.key.clone();
+        if let Some(schema) = &self.schema {
+            let keys = schema.keys.as_deref().unwrap_or_default();
+            key_columns.extend(
+                schema
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, field)| {
+                        field.constraints.iter().any(|c| {
+                            matches!(c, ColumnConstraint::PrimaryKey | ColumnConstraint::Unique)
+                        }) || keys.iter().any(|key| match key {
+                            TableKey::PrimaryKey { columns, .. }
+                            | TableKey::UniqueKey { columns, .. }
+                            | TableKey::FulltextKey { columns, .. }
+                            | TableKey::Key { columns, .. }
+                            | TableKey::ForeignKey { columns, .. } => {
+                                columns.iter().any(|c| c.name == field.column.name)
+                            }
+                            TableKey::CheckConstraint { .. } => false,
+                        })
+                    })
+                    .map(|(i, _)| i),
+            );
+        }
+        key_columns.sort_unstable();
+        key_columns.dedup();
+        key_columns
+    }
+
     fn inject_dropped_cols(&self, r: &mut TableOperation) -> ReadySetResult<()> {
         use std::mem;
         let ndropped = self.dropped.len();
@@ -815,8 +880,42 @@ impl Table {
         })
     }
 
+    /// Set the columns declared with `ON UPDATE CURRENT_TIMESTAMP` to the current time in the given
+    /// update (or insert-or-update), unless the update gives them a value explicitly.
+    ///
+    /// Unlike MySQL, this happens for any update that modifies at least one column, even if that
+    /// doesn't actually change the row.
+    fn set_on_update_cols(&self, r: &mut TableOperation) -> ReadySetResult<()> {
+        let (schema, update) = match (&self.schema, r) {
+            (
+                Some(schema),
+                TableOperation::Update { update, .. }
+                | TableOperation::InsertOrUpdate { update, .. },
+            ) => (schema, update),
+            _ => return Ok(()),
+        };
+        if update.iter().all(|m| *m == Modification::None) {
+            return Ok(());
+        }
+
+        for ((spec, ty), m) in schema
+            .fields
+            .iter()
+            .zip(&self.column_types)
+            .zip(update.iter_mut())
+        {
+            if *m == Modification::None {
+                if let Some(now) = column_on_update_value(spec, ty)? {
+                    *m = Modification::Set(now);
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn prep_records(&mut self, mut ops: Vec<TableOperation>) -> ReadySetResult<PacketData> {
         for r in &mut ops {
+            self.set_on_update_cols(r)?;
             self.inject_dropped_cols(r)?;
         }
 
@@ -824,9 +923,69 @@ impl Table {
             dst: self.node,
             data: PacketPayload::Input(ops),
             trace: self.generate_trace_info(),
+            batch: None,
         })
     }
 
+    /// Check that the given operations are valid for this table, without performing them
+    fn check_ops(&self, ops: &[TableOperation]) -> ReadySetResult<()> {
+        let ncols = self.columns.len() + self.dropped.len();
+        for op in ops {
+            match op {
+                TableOperation::Insert(ref row) | TableOperation::DeleteRow { ref row } => {
+                    if row.len() != ncols {
+                        return Err(ReadySetError::WrongColumnCount(ncols, row.len()));
+                    }
+                }
+                TableOperation::DeleteByKey { ref key } => {
+                    if key.len() != self.key.len() {
+                        return Err(ReadySetError::WrongKeyColumnCount(
+                            self.key.len(),
+                            key.len(),
+                        ));
+                    }
+                }
+                TableOperation::InsertOrUpdate {
+                    ref row,
+                    ref update,
+                } => {
+                    if row.len() != ncols {
+                        return Err(ReadySetError::WrongColumnCount(ncols, row.len()));
+                    }
+                    if update.len() > self.columns.len() {
+                        // NOTE: < is okay to allow dropping tailing no-ops
+                        return Err(ReadySetError::WrongColumnCount(
+                            self.columns.len(),
+                            update.len(),
+                        ));
+                    }
+                }
+                TableOperation::Update {
+                    ref update,
+                    ref key,
+                } => {
+                    if key.len() != self.key.len() {
+                        return Err(ReadySetError::WrongKeyColumnCount(
+                            self.key.len(),
+                            key.len(),
+                        ));
+                    }
+                    if update.len() > self.columns.len() {
+                        // NOTE: < is okay to allow dropping tailing no-ops
+                        return Err(ReadySetError::WrongColumnCount(
+                            self.columns.len(),
+                            update.len(),
+                        ));
+                    }
+                }
+                TableOperation::SetReplicationOffset(_)
+                | TableOperation::SetSnapshotMode(_)
+                | TableOperation::Truncate => {}
+            }
+        }
+        Ok(())
+    }
+
     async fn quick_n_dirty<Request, R>(
         &mut self,
         r: Request,
@@ -947,6 +1106,20 @@ impl Table {
     ) -> ReadySetResult<()>
     where
         V: IntoIterator<Item = (usize, Modification)>,
+    {
+        self.insert_or_update_many(vec![insert], update).await
+    }
+
+    /// Perform an insert-or-update of each of the given rows on this base table, in order, as a
+    /// single batch of operations, so that either all of them are applied or none are.
+    ///
+    /// If a row already exists for the key of a row in `inserts` (including one written by an
+    /// earlier row in the batch), the existing row will instead be updated with the modifications
+    /// in `update` (as documented in `Table::update`).
+    pub async fn insert_or_update_many<I, V>(&mut self, inserts: I, update: V) -> ReadySetResult<()>
+    where
+        I: IntoIterator<Item = Vec<DfValue>>,
+        V: IntoIterator<Item = (usize, Modification)>,
     {
         if self.key.is_empty() || !self.key_is_primary {
             unsupported!("update operations can only be applied to base nodes with key columns")
@@ -965,12 +1138,15 @@ impl Table {
             }
         }
 
-        self.quick_n_dirty_with_timeout(TableRequest::TableOperations(vec![
-            TableOperation::InsertOrUpdate {
-                row: insert,
-                update: set,
-            },
-        ]))
+        self.quick_n_dirty_with_timeout(TableRequest::TableOperations(
+            inserts
+                .into_iter()
+                .map(|row| TableOperation::InsertOrUpdate {
+                    row,
+                    update: set.clone(),
+                })
+                .collect(),
+        ))
         .await
     }
 
@@ -1014,4 +1190,145 @@ impl Table {
         ]))
         .await
     }
+
+    /// Send the given prepared input (see [`prep_records`](Self::prep_records)) to this base
+    /// table
+    async fn send_input(&mut self, input: PacketData) -> ReadySetResult<()> {
+        future::poll_fn(|cx| self.poll_ready(cx)).await?;
+        let table = self.table_name.clone();
+        tokio::time::timeout(self.request_timeout, self.input(input))
+            .await
+            .map_err(|_| internal_err!("Timeout during table request"))?
+            .map_err(|e| table_err(table, e))?;
+        Ok(())
+    }
+}
+
+/// A batch of writes to any number of base tables, which are made visible to readers atomically.
+///
+/// All the writes in a batch are stamped with the same timestamp when the batch is committed, and
+/// readers don't make any of them visible until that timestamp has been propagated to them from
+/// every base table in the batch they're derived from. Views derived from more than one table (such
+/// as joins) therefore never expose some of the writes in a batch without the others.
+///
+/// The writes in a batch are checked before any of them are performed, so a batch containing an
+/// invalid write isn't performed at all. If performing the writes fails for any other reason (for
+/// example, if a worker can't be reached), the writes which were performed are undone before the
+/// batch's timestamp is propagated, so that readers don't expose any of them. Because of that,
+/// batches can only insert rows ([`TableOperation::Insert`]) and delete whole rows
+/// ([`TableOperation::DeleteRow`]): undoing a deletion inserts the row again, so batches should
+/// only delete rows which exist. The writes to a table whose outcome is unknown (for example
+/// because sending them timed out) can't be undone.
+///
+/// If a client goes away before it finishes committing a batch, readers make the writes in the
+/// batch which reached them visible after a timeout, rather than holding them back forever.
+pub struct WriteBatch {
+    timestamp: u64,
+    writes: Vec<(Table, Vec<TableOperation>)>,
+}
+
+impl WriteBatch {
+    /// Create a new, empty write batch, which will be committed with the given timestamp.
+    ///
+    /// The timestamp must be greater than any timestamp previously given to any of the tables the
+    /// batch writes to, either by another write batch or by [`Table::update_timestamp`].
+    pub fn new(timestamp: u64) -> Self {
+        Self {
+            timestamp,
+            writes: Vec::new(),
+        }
+    }
+
+    /// Add the given operations on the given base table to this batch
+    pub fn add<I, V>(&mut self, table: &Table, ops: I)
+    where
+        I: IntoIterator<Item = V>,
+        V: Into<TableOperation>,
+    {
+        let ops = ops.into_iter().map(Into::into);
+        match self.writes.iter_mut().find(|(t, _)| t.ni == table.ni) {
+            Some((_, table_ops)) => table_ops.extend(ops),
+            None => self.writes.push((table.clone(), ops.collect())),
+        }
+    }
+
+    /// Perform all the writes in this batch, then make them visible to readers.
+    pub async fn commit(self) -> ReadySetResult<()> {
+        let batch = consistency::Timestamp {
+            map: self
+                .writes
+                .iter()
+                .map(|(table, _)| (table.node, self.timestamp))
+                .collect(),
+        };
+
+        // Check and prepare all the writes before performing any of them, so that a batch
+        // containing an invalid write isn't partially performed
+        let mut inputs = Vec::with_capacity(self.writes.len());
+        for (mut table, ops) in self.writes {
+            table
+                .check_ops(&ops)
+                .map_err(|e| table_err(table.table_name.clone(), e))?;
+            if let Some(op) = ops.iter().find(|op| {
+                !matches!(
+                    op,
+                    TableOperation::Insert(_) | TableOperation::DeleteRow { .. }
+                )
+            }) {
+                unsupported!(
+                    "Write batches can only insert and delete whole rows, so that they can be \
+                     undone if performing them fails, but got {op:?}"
+                );
+            }
+            let input = PacketData {
+                batch: Some(batch.clone()),
+                ..table.prep_records(ops)?
+            };
+            inputs.push((table, input));
+        }
+
+        let mut tables = Vec::with_capacity(inputs.len());
+        let mut undo = Vec::new();
+        let mut res = Ok(());
+        for (mut table, input) in inputs {
+            if res.is_ok() {
+                let undo_input = input.undo();
+                res = table.send_input(input).await;
+                if res.is_ok() {
+                    undo.push((tables.len(), undo_input));
+                }
+            }
+            tables.push(table);
+        }
+
+        // If some of the writes failed, undo the ones which were performed. The undoing writes are
+        // part of the batch too, so readers make them visible together with the writes they undo.
+        if res.is_err() {
+            for (i, undo_input) in undo.into_iter().rev() {
+                #[allow(clippy::indexing_slicing)] // `i` is an index into `tables`
+                let table = &mut tables[i];
+                if let Err(error) = table.send_input(undo_input).await {
+                    error!(
+                        %error,
+                        table = %table.table_name,
+                        "Could not undo writes of failed write batch"
+                    );
+                }
+            }
+        }
+
+        // Propagate the batch's timestamp to every table even if some of the writes failed, since
+        // readers which have received any of the writes hold writes to the same keys until then
+        for mut table in tables {
+            let timestamp_res = table
+                .update_timestamp(consistency::Timestamp {
+                    map: HashMap::from([(table.node, self.timestamp)]),
+                })
+                .await;
+            if res.is_ok() {
+                res = timestamp_res;
+            }
+        }
+        res
+    }
 }
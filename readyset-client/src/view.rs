@@ -1,43 +1,48 @@
 use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::convert::TryFrom;
-use std::fmt;
 use std::future::Future;
 use std::hash::{Hash, Hasher};
 use std::net::SocketAddr;
 use std::ops::{Bound, Range, RangeBounds};
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use std::{fmt, iter, mem};
 
 use array2::Array2;
 use async_bincode::{AsyncBincodeStream, AsyncDestination};
-use dataflow_expression::{BinaryOperator as DfBinaryOperator, Dialect, Expr as DfExpr};
+use dataflow_expression::like::literal_prefix;
+use dataflow_expression::{
+    BinaryOperator as DfBinaryOperator, Dialect, Expr as DfExpr, LowerContext,
+};
 use futures_util::future::TryFutureExt;
 use futures_util::stream::futures_unordered::FuturesUnordered;
-use futures_util::stream::{StreamExt, TryStreamExt};
+use futures_util::stream::{Stream, StreamExt, TryStreamExt};
 use futures_util::{future, ready};
 use itertools::Itertools;
+use nom_sql::analysis::visit_mut::VisitorMut;
 use nom_sql::{
-    BinaryOperator, Column, ColumnConstraint, ColumnSpecification, ItemPlaceholder, Literal,
-    Relation, SelectStatement, SqlIdentifier,
+    BinaryOperator, CacheFreshness, Column, ColumnConstraint, ColumnSpecification, Expr,
+    ItemPlaceholder, Literal, Relation, SelectStatement, SqlIdentifier,
 };
 use petgraph::graph::NodeIndex;
 use proptest::arbitrary::Arbitrary;
-use rand::prelude::IteratorRandom;
-use rand::thread_rng;
-use readyset_data::{DfType, DfValue};
+use readyset_data::{Collation, DfType, DfValue};
 use readyset_errors::{
     internal, internal_err, rpc_err, unsupported, view_err, ReadySetError, ReadySetResult,
 };
 use readyset_sql_passes::anonymize::{Anonymize, Anonymizer};
 use readyset_tracing::presampled::instrument_if_enabled;
 use readyset_tracing::propagation::Instrumented;
-use readyset_tracing::{error, trace};
+use readyset_tracing::{error, trace, warn};
 use readyset_util::intervals::{cmp_start_end, BoundPair};
 use readyset_util::redacted::Sensitive;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
 use tokio_tower::multiplex;
 use tower::balance::p2c::Balance;
 use tower::buffer::Buffer;
@@ -48,14 +53,28 @@ use tracing::instrument;
 use tracing_futures::Instrument;
 use vec1::{vec1, Vec1};
 
+pub(crate) mod columnar;
+pub(crate) mod local;
+pub(crate) mod parquet;
+pub(crate) mod replicas;
 pub(crate) mod results;
+pub(crate) mod typed;
 
+use self::columnar::ColumnarBatch;
+use self::local::ReaderStream;
+use self::replicas::{AddrStats, ReadLocality};
 use self::results::{ResultIterator, Results};
+use crate::channel::{self, ChannelSecurity};
 use crate::consistency::Timestamp;
 use crate::{ReaderAddress, Tagged, Tagger};
 
+/// The largest number of ranges the keys of a view are divided into by [`ReaderHandle::scan`].
+/// Every range is read with a separate pass over the reader, so this bounds the amount of work
+/// a scan does, at the cost of holding more keys in memory at once for very large views.
+const MAX_SCAN_KEY_RANGES: usize = 64;
+
 type Transport = AsyncBincodeStream<
-    tokio::net::TcpStream,
+    ReaderStream,
     Tagged<ReadReply>,
     Instrumented<Tagged<ReadQuery>>,
     AsyncDestination,
@@ -146,6 +165,7 @@ impl From<Option<PlaceholderIdx>> for ViewPlaceholder {
 struct Endpoint {
     addr: SocketAddr,
     timeout: Duration,
+    security: Option<ChannelSecurity>,
 }
 
 /// Identifies the source base table column for a projected column
@@ -235,11 +255,17 @@ impl Service<()> for Endpoint {
     }
 
     fn call(&mut self, _: ()) -> Self::Future {
-        let f = tokio::net::TcpStream::connect(self.addr);
         let timeout = self.timeout;
+        let addr = self.addr;
+        let security = self.security.clone();
         async move {
+            let f = async {
+                let mut s = ReaderStream::connect(addr, security.as_ref()).await?;
+                channel::reader_handshake(&mut s).await?;
+                Ok::<_, tokio::io::Error>(s)
+            };
             let s = tokio::time::timeout(timeout, f).await??;
-            s.set_nodelay(true)?;
+            trace!(%addr, local = s.is_local(), "connected to reader");
             let s = AsyncBincodeStream::from(s).for_async();
             let t = multiplex::MultiplexTransport::new(s, Tagger::default());
             Ok(multiplex::Client::with_error_handler(
@@ -344,6 +370,7 @@ impl ColumnSchema {
 fn make_views_stream(
     addr: SocketAddr,
     timeout: Duration,
+    security: Option<ChannelSecurity>,
 ) -> impl futures_util::stream::TryStream<
     Ok = tower::discover::Change<usize, InnerService>,
     Error = tokio::io::Error,
@@ -351,15 +378,28 @@ fn make_views_stream(
     // TODO: use whatever comes out of https://github.com/tower-rs/tower/issues/456 instead of
     // creating _all_ the connections every time.
     (0..crate::VIEW_POOL_SIZE)
-        .map(|i| async move {
-            let svc = Endpoint { addr, timeout }.call(()).await?;
-            Ok(tower::discover::Change::Insert(i, svc))
+        .map(|i| {
+            let security = security.clone();
+            async move {
+                let svc = Endpoint {
+                    addr,
+                    timeout,
+                    security,
+                }
+                .call(())
+                .await?;
+                Ok(tower::discover::Change::Insert(i, svc))
+            }
         })
         .collect::<futures_util::stream::FuturesUnordered<_>>()
 }
 
-fn make_views_discover(addr: SocketAddr, timeout: Duration) -> Discover {
-    make_views_stream(addr, timeout)
+fn make_views_discover(
+    addr: SocketAddr,
+    timeout: Duration,
+    security: Option<ChannelSecurity>,
+) -> Discover {
+    make_views_stream(addr, timeout, security)
 }
 
 // Unpin + Send bounds are needed due to https://github.com/rust-lang/rust/issues/55997
@@ -399,7 +439,7 @@ impl KeyComparison {
             GreaterOrEqual => (Bound::Included(key), Bound::Unbounded),
             Less => (Bound::Unbounded, Bound::Excluded(key)),
             LessOrEqual => (Bound::Unbounded, Bound::Included(key)),
-            Equal => return Ok(key.into()),
+            Equal | NullSafeEqual => return Ok(key.into()),
             _ => unsupported!("Unsupported operator `{operator}` in key"),
         };
         Ok(Self::Range(inner))
@@ -803,12 +843,20 @@ pub enum ReadQuery {
         /// Where to read from
         target: ReaderAddress,
     },
-    /// Read all keys from a leaf view (for debugging)
-    /// TODO(alex): queries with this value are not totally implemented, and might not actually
-    /// work
+    /// Read the keys from a leaf view which fall in one of `ranges` disjoint ranges of its keys,
+    /// selected by the hash of each key
     Keys {
         /// Where to read from
         target: ReaderAddress,
+        /// Which of the ranges to read the keys from, in `0..ranges`
+        index: u32,
+        /// The number of ranges the keys are divided into. If this is 1, all the keys are read
+        ranges: u32,
+    },
+    /// Read the [`ViewStats`] of a leaf view
+    Stats {
+        /// Where to read from
+        target: ReaderAddress,
     },
 }
 
@@ -850,6 +898,13 @@ impl<D> LookupResult<D> {
 pub struct ReadReplyStats {
     /// The count of cache misses which have occurred
     pub cache_misses: u64,
+    /// True if the results were truncated because they exceeded the limits of the cache
+    pub truncated: bool,
+    /// How long the read waited for replays to fill the keys it missed on, in microseconds
+    pub replay_wait_us: u64,
+    /// The number of rows looked up from the reader, before any filter, limit or offset in the
+    /// query was applied
+    pub rows: u64,
 }
 
 impl ReadReplyStats {
@@ -858,6 +913,35 @@ impl ReadReplyStats {
     pub fn merge(&self, other: &Self) -> Self {
         Self {
             cache_misses: self.cache_misses + other.cache_misses,
+            truncated: self.truncated || other.truncated,
+            // Shards are read in parallel
+            replay_wait_us: self.replay_wait_us.max(other.replay_wait_us),
+            rows: self.rows + other.rows,
+        }
+    }
+}
+
+/// Statistics about the contents of a view, which can be fetched without reading its rows
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct ViewStats {
+    /// The number of keys stored in the view
+    pub keys: usize,
+    /// The number of rows stored in the view
+    pub rows: usize,
+    /// True if the view is partially materialized, in which case `keys` and `rows` only count the
+    /// keys which are currently filled, and so are a lower bound on the number of keys and rows
+    /// the view's query would return
+    pub partial: bool,
+}
+
+impl ViewStats {
+    /// Combine the stats of two shards of the same view
+    #[must_use]
+    pub fn merge(&self, other: &Self) -> Self {
+        Self {
+            keys: self.keys + other.keys,
+            rows: self.rows + other.rows,
+            partial: self.partial || other.partial,
         }
     }
 }
@@ -871,6 +955,8 @@ pub enum ReadReply<D = ReadReplyBatch> {
     Size(usize),
     // Read keys of view
     Keys(Vec<Vec<DfValue>>),
+    /// Read stats of view
+    Stats(ViewStats),
 }
 
 impl<D> ReadReply<D> {
@@ -896,12 +982,20 @@ pub struct ReaderHandleBuilder {
     /// replica -> shard index -> addr
     pub replica_shard_addrs: Array2<SocketAddr>,
 
+    /// The zone each replica is running in, if known and if all of its shards are running in the
+    /// same zone, indexed by replica
+    #[serde(default)]
+    pub replica_zones: Vec<Option<String>>,
+
     /// (view_placeholder, key_column_index) pairs according to their mapping. Contains exactly one
     /// entry for each key column at the reader.
     pub key_mapping: Vec<(ViewPlaceholder, KeyColumnIdx)>,
 
     /// The amount of time before a view request RPC is terminated.
     pub view_request_timeout: Duration,
+
+    /// The bound on replication lag that reads from the view are subject to
+    pub freshness: CacheFreshness,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -935,40 +1029,91 @@ impl ReaderHandleBuilder {
     /// Build a [`ReaderHandle`] out of a [`ReaderHandleBuilder`].
     ///
     /// If `replica` is specified, this selects the reader replica with that index, returning an
-    /// error if the index is out of bounds. Otherwise, a replica is selected at random
+    /// error if the index is out of bounds. Otherwise, replicas are ranked by `locality` (see
+    /// [the documentation of that module](self::replicas)): the best replica is selected, and the
+    /// others are failed over to if reads from it fail.
+    ///
+    /// Connections to the reader are secured with `security`, if any, which must match the
+    /// security configured for the ReadySet server.
     #[doc(hidden)]
     pub fn build(
         &self,
         replica: Option<usize>,
         rpcs: Arc<Mutex<HashMap<(SocketAddr, usize), ViewRpc>>>,
+        locality: &ReadLocality,
+        security: Option<&ChannelSecurity>,
     ) -> ReadySetResult<ReaderHandle> {
-        let shards = match replica {
-            Some(replica) => self.replica_shard_addrs.get(replica),
-            None if self.replica_shard_addrs.num_rows() == 1 => Some(&self.replica_shard_addrs[0]),
-            None => self.replica_shard_addrs.rows().choose(&mut thread_rng()),
-        }
-        .ok_or_else(|| ReadySetError::ViewReplicaOutOfBounds {
-            replica: replica.unwrap_or(0),
-            view_name: self.name.clone().to_string(),
-            num_replicas: self.replica_shard_addrs.num_rows(),
-        })?;
+        let mut replicas: VecDeque<usize> = match replica {
+            Some(replica) => [replica].into(),
+            None if self.replica_shard_addrs.num_rows() == 1 => [0].into(),
+            None => locality
+                .rank_replicas(&self.replica_shard_addrs, &self.replica_zones)
+                .into(),
+        };
+        let shards = replicas
+            .pop_front()
+            .and_then(|replica| self.replica_shard_addrs.get(replica))
+            .ok_or_else(|| ReadySetError::ViewReplicaOutOfBounds {
+                replica: replica.unwrap_or(0),
+                view_name: self.name.clone().to_string(),
+                num_replicas: self.replica_shard_addrs.num_rows(),
+            })?;
+
+        let mut failover = ReplicaFailover {
+            fallbacks: replicas
+                .into_iter()
+                .map(|replica| self.replica_shard_addrs[replica].to_vec())
+                .collect(),
+            locality: locality.clone(),
+            rpcs,
+            view_request_timeout: self.view_request_timeout,
+            security: security.cloned(),
+            current: vec![],
+        };
+        let conns = failover.connect(&self.name, shards)?;
+        failover.track(shards);
 
-        let node = self.node;
-        let columns = self.columns.clone();
-        let schema = self.schema.clone();
-        let key_mapping = self.key_mapping.clone();
+        Ok(ReaderHandle {
+            name: self.name.clone(),
+            node: self.node,
+            schema: self.schema.clone(),
+            columns: self.columns.clone(),
+            key_mapping: self.key_mapping.clone(),
+            freshness: self.freshness,
+            shard_addrs: shards.to_vec(),
+            shards: conns,
+            failover,
+        })
+    }
+}
 
-        let mut addrs = Vec::with_capacity(shards.len());
+/// The state a [`ReaderHandle`] needs to fail over to another replica of its reader
+#[derive(Clone, Default)]
+struct ReplicaFailover {
+    /// The addresses of the shards of each of the other replicas of the reader, in the order they
+    /// should be failed over to
+    fallbacks: VecDeque<Vec<SocketAddr>>,
+    locality: ReadLocality,
+    /// The read statistics of each of the shards of the replica currently being read from
+    current: Vec<Arc<AddrStats>>,
+    rpcs: Arc<Mutex<HashMap<(SocketAddr, usize), ViewRpc>>>,
+    view_request_timeout: Duration,
+    security: Option<ChannelSecurity>,
+}
+
+impl ReplicaFailover {
+    /// Get (or create) connections to each of the given shards of the reader for the view with the
+    /// given name
+    fn connect(&self, name: &Relation, shards: &[SocketAddr]) -> ReadySetResult<Vec1<ViewRpc>> {
         let mut conns = Vec::with_capacity(shards.len());
 
         for (shardi, shard_addr) in shards.iter().enumerate() {
             use std::collections::hash_map::Entry;
 
-            addrs.push(*shard_addr);
-
             // one entry per shard so that we can send sharded requests in parallel even if
             // they happen to be targeting the same machine.
-            let mut rpcs = rpcs
+            let mut rpcs = self
+                .rpcs
                 .lock()
                 .map_err(|e| internal_err!("mutex was poisoned: '{}'", e))?;
             #[allow(clippy::significant_drop_in_scrutinee)]
@@ -982,6 +1127,7 @@ impl ReaderHandleBuilder {
                                 Balance::new(make_views_discover(
                                     *shard_addr,
                                     self.view_request_timeout,
+                                    self.security.clone(),
                                 )),
                                 crate::PENDING_LIMIT,
                             ),
@@ -1001,16 +1147,30 @@ impl ReaderHandleBuilder {
             conns.push(s);
         }
 
-        Ok(ReaderHandle {
-            name: self.name.clone(),
-            node,
-            schema,
-            columns,
-            key_mapping,
-            shard_addrs: addrs,
-            shards: Vec1::try_from_vec(conns)
-                .map_err(|_| internal_err!("cannot create view '{}' without shards", self.name))?,
-        })
+        Vec1::try_from_vec(conns)
+            .map_err(|_| internal_err!("cannot create view '{}' without shards", name))
+    }
+
+    /// Start recording the outcome of reads against the replica with the given shard addresses
+    fn track(&mut self, shard_addrs: &[SocketAddr]) {
+        self.current = shard_addrs
+            .iter()
+            .map(|addr| self.locality.addr_stats(*addr))
+            .collect();
+    }
+
+    /// Record that a read from the current replica succeeded, and took `latency`
+    fn record_latency(&self, latency: Duration) {
+        for stats in &self.current {
+            stats.record_latency(latency);
+        }
+    }
+
+    /// Record that a read from the current replica failed
+    fn record_failure(&self) {
+        for stats in &self.current {
+            stats.record_failure();
+        }
     }
 }
 
@@ -1020,9 +1180,13 @@ impl ViewBuilder {
         &self,
         replica: Option<usize>,
         rpcs: Arc<Mutex<HashMap<(SocketAddr, usize), ViewRpc>>>,
+        locality: &ReadLocality,
+        security: Option<&ChannelSecurity>,
     ) -> ReadySetResult<View> {
         match self {
-            ViewBuilder::Single(builder) => Ok(View::Single(builder.build(replica, rpcs)?)),
+            ViewBuilder::Single(builder) => Ok(View::Single(
+                builder.build(replica, rpcs, locality, security)?,
+            )),
             ViewBuilder::MultipleReused(builders) => {
                 Ok(View::MultipleReused(builders.try_mapped_ref(
                     |ReusedReaderHandleBuilder {
@@ -1030,13 +1194,13 @@ impl ViewBuilder {
                          key_remapping,
                          required_values,
                      }| {
-                        builder.build(replica, rpcs.clone()).map(|reader_handle| {
-                            ReusedReaderHandle {
+                        builder
+                            .build(replica, rpcs.clone(), locality, security)
+                            .map(|reader_handle| ReusedReaderHandle {
                                 reader_handle,
                                 key_remapping: key_remapping.clone(),
                                 required_values: required_values.clone(),
-                            }
-                        })
+                            })
                     },
                 )?))
             }
@@ -1057,8 +1221,11 @@ pub struct ReaderHandle {
     /// (view_placeholder, key_column_index) pairs according to their mapping. Contains exactly
     /// one entry for each key column at the reader.
     key_mapping: Vec<(ViewPlaceholder, KeyColumnIdx)>,
+    /// The bound on replication lag that reads from this view are subject to
+    freshness: CacheFreshness,
     shards: Vec1<ViewRpc>,
     shard_addrs: Vec<SocketAddr>,
+    failover: ReplicaFailover,
 }
 
 impl fmt::Debug for ReaderHandle {
@@ -1103,13 +1270,84 @@ pub enum View {
     MultipleReused(Vec1<ReusedReaderHandle>),
 }
 
+/// What a read should do if it can't be answered immediately, either because some of the keys it
+/// reads are missing from the reader or because the reader's data isn't yet recent enough to
+/// satisfy the read's timestamp.
+///
+/// Missing keys are replayed regardless of the read behavior.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReadBehavior {
+    /// Block until the replays of the missing keys complete, and the reader's data is recent
+    /// enough
+    #[default]
+    Blocking,
+    /// Return a miss immediately
+    NonBlocking,
+    /// If the reader has rows for all the missing keys from before they were evicted (which is
+    /// only the case for caches created with `MAX STALENESS`), return those immediately, and
+    /// otherwise block as for [`Blocking`](Self::Blocking). Reads whose timestamp the reader's
+    /// data isn't recent enough for always block.
+    StaleIfAvailable,
+}
+
+impl ReadBehavior {
+    /// Returns true if reads with this behavior should block if they can't be answered
+    /// immediately
+    pub fn is_blocking(&self) -> bool {
+        !matches!(self, Self::NonBlocking)
+    }
+
+    /// Returns true if reads with this behavior should be answered with stale rows, if the reader
+    /// has any for the keys they missed on
+    pub fn allows_stale(&self) -> bool {
+        matches!(self, Self::StaleIfAvailable)
+    }
+}
+
+impl From<bool> for ReadBehavior {
+    /// Convert a flag for whether a read should block into the corresponding read behavior
+    fn from(block: bool) -> Self {
+        if block {
+            Self::Blocking
+        } else {
+            Self::NonBlocking
+        }
+    }
+}
+
+impl FromStr for ReadBehavior {
+    type Err = ReadySetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('-', "_").as_str() {
+            "blocking" => Ok(Self::Blocking),
+            "non_blocking" => Ok(Self::NonBlocking),
+            "stale_if_available" => Ok(Self::StaleIfAvailable),
+            _ => Err(ReadySetError::BadRequest(format!(
+                "Invalid read behavior {s:?}: expected one of blocking, non_blocking or \
+                 stale_if_available"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ReadBehavior {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Blocking => f.write_str("blocking"),
+            Self::NonBlocking => f.write_str("non_blocking"),
+            Self::StaleIfAvailable => f.write_str("stale_if_available"),
+        }
+    }
+}
+
 /// A read query to be run against a [`View`].
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ViewQuery {
     /// Key comparisons to read with.
     pub key_comparisons: Vec<KeyComparison>,
-    /// Whether the query should block.
-    pub block: bool,
+    /// What to do if the query can't be answered immediately.
+    pub read_behavior: ReadBehavior,
     /// Expression to use to filter values after they're returned from the underlying reader.
     ///
     /// This expression will be evaluaated on each of the rows returned from the reader, and any
@@ -1127,34 +1365,86 @@ pub struct ViewQuery {
     // TODO(justin): Verify reads block on timestamps once timestamps have a definition
     // with Ord.
     pub timestamp: Option<Timestamp>,
+    /// Snapshot to read at, if the read is a snapshot read. A snapshot read will only return once
+    /// the data reflects every write to the base tables the view is derived from up to the
+    /// snapshot (see [`Timestamp::reflects_snapshot`]). This is a lower bound for this view only:
+    /// the data may also reflect later writes, so reads of different views at the same snapshot
+    /// aren't necessarily consistent with each other.
+    #[serde(default)]
+    pub snapshot: Option<Timestamp>,
 }
 
 // TODO(andrew): consolidate From impls once RYW fully adopted
-impl From<(Vec<KeyComparison>, bool, Option<Timestamp>)> for ViewQuery {
+impl<B> From<(Vec<KeyComparison>, B, Option<Timestamp>)> for ViewQuery
+where
+    B: Into<ReadBehavior>,
+{
     fn from(
-        (key_comparisons, block, ticket): (Vec<KeyComparison>, bool, Option<Timestamp>),
+        (key_comparisons, read_behavior, ticket): (Vec<KeyComparison>, B, Option<Timestamp>),
     ) -> Self {
         Self {
             key_comparisons,
-            block,
+            read_behavior: read_behavior.into(),
             limit: None,
             offset: None,
             filter: None,
             timestamp: ticket,
+            snapshot: None,
         }
     }
 }
 
-impl From<(Vec<KeyComparison>, bool)> for ViewQuery {
-    fn from((key_comparisons, block): (Vec<KeyComparison>, bool)) -> Self {
+impl<B> From<(Vec<KeyComparison>, B)> for ViewQuery
+where
+    B: Into<ReadBehavior>,
+{
+    fn from((key_comparisons, read_behavior): (Vec<KeyComparison>, B)) -> Self {
         Self {
             key_comparisons,
-            block,
+            read_behavior: read_behavior.into(),
             filter: None,
             limit: None,
             offset: None,
             timestamp: None,
+            snapshot: None,
+        }
+    }
+}
+
+impl ViewQuery {
+    /// Returns the (sorted, deduplicated) indices of the columns that are compared against in the
+    /// [`filter`](Self::filter) for this query, if the filter is a conjunction of comparisons
+    /// against columns.
+    ///
+    /// This is called for every read, so it avoids allocating for filters on a few columns.
+    pub fn filtered_columns(&self) -> SmallVec<[usize; 4]> {
+        fn collect(expr: &DfExpr, columns: &mut SmallVec<[usize; 4]>) {
+            match expr {
+                DfExpr::Op {
+                    op: DfBinaryOperator::And,
+                    left,
+                    right,
+                    ..
+                } => {
+                    collect(left, columns);
+                    collect(right, columns);
+                }
+                DfExpr::Op { left, .. } => {
+                    if let DfExpr::Column { index, .. } = **left {
+                        columns.push(index);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let mut columns = SmallVec::new();
+        if let Some(filter) = &self.filter {
+            collect(filter, &mut columns);
         }
+        columns.sort_unstable();
+        columns.dedup();
+        columns
     }
 }
 
@@ -1174,7 +1464,19 @@ impl Service<ViewQuery> for ReaderHandle {
         Poll::Ready(Ok(()))
     }
 
-    fn call(&mut self, mut query: ViewQuery) -> Self::Future {
+    fn call(&mut self, query: ViewQuery) -> Self::Future {
+        self.send(Cow::Owned(query))
+    }
+}
+
+impl ReaderHandle {
+    /// Send `query` to the shards of the reader. The query is only cloned if it's borrowed and has
+    /// to be sent whole to a single shard, so that callers which might have to resend the query to
+    /// another replica can hold on to it without cloning it up front.
+    fn send(
+        &mut self,
+        query: Cow<'_, ViewQuery>,
+    ) -> impl Future<Output = ReadySetResult<LookupResult<Results>>> + Send {
         let ni = self.node;
         let span = readyset_tracing::child_span!(
             INFO,
@@ -1191,7 +1493,7 @@ impl Service<ViewQuery> for ReaderHandle {
                         name: self.name.clone(),
                         shard: 0,
                     },
-                    query,
+                    query: query.into_owned(),
                 }))
             });
 
@@ -1224,7 +1526,7 @@ impl Service<ViewQuery> for ReaderHandle {
 
         span.in_scope(|| trace!("shard request"));
         let mut shard_queries = vec![Vec::new(); self.shards.len()];
-        for comparison in query.key_comparisons.drain(..) {
+        for comparison in &query.key_comparisons {
             for shard in comparison.shard_keys(self.shards.len()) {
                 #[allow(clippy::indexing_slicing)]
                 // We built `shard_queries` to be the correct length, so it's safe to access
@@ -1235,6 +1537,16 @@ impl Service<ViewQuery> for ReaderHandle {
 
         let node = self.node;
         let name = self.name.clone();
+        // Everything but the key comparisons is the same for each shard
+        let template = ViewQuery {
+            key_comparisons: vec![],
+            read_behavior: query.read_behavior,
+            filter: query.filter.clone(),
+            limit: query.limit,
+            offset: query.offset,
+            timestamp: query.timestamp.clone(),
+            snapshot: query.snapshot.clone(),
+        };
         future::Either::Right(
             self.shards
                 .iter_mut()
@@ -1269,11 +1581,7 @@ impl Service<ViewQuery> for ReaderHandle {
                         },
                         query: ViewQuery {
                             key_comparisons: shard_queries,
-                            block: query.block,
-                            filter: query.filter.clone(),
-                            limit: query.limit,
-                            offset: query.offset,
-                            timestamp: query.timestamp.clone(),
+                            ..template.clone()
                         },
                     }));
 
@@ -1323,6 +1631,16 @@ impl ReaderHandle {
         &self.columns
     }
 
+    /// Get the columns of this view which are returned to clients: all of its columns, other than
+    /// any key columns which were only added to the view so it could be looked up by them, which
+    /// come last.
+    fn returned_columns(&self) -> &[SqlIdentifier] {
+        let returned = self.schema.as_ref().map_or(self.columns.len(), |schema| {
+            schema.schema(SchemaType::ReturnedSchema).len()
+        });
+        &self.columns[..returned.min(self.columns.len())]
+    }
+
     /// Get a slice of the columns.
     pub fn column_slice(&self) -> Arc<[SqlIdentifier]> {
         Arc::clone(&self.columns)
@@ -1344,6 +1662,12 @@ impl ReaderHandle {
         &self.name
     }
 
+    /// Returns the bound on replication lag that reads from this view are subject to. It's up to
+    /// the caller to check the current replication lag against it before reading.
+    pub fn freshness(&self) -> CacheFreshness {
+        self.freshness
+    }
+
     /// Returns a reference to the list of socket addresses for the view's shards
     #[must_use]
     pub fn shard_addrs(&self) -> &[SocketAddr] {
@@ -1406,6 +1730,17 @@ impl ReaderHandle {
     /// Get the current keys of this view. For debugging only.
     #[instrument(level = "info", skip(self))]
     pub async fn keys(&mut self) -> ReadySetResult<Vec<Vec<DfValue>>> {
+        self.keys_in_range(0, 1).await
+    }
+
+    /// Get the current keys of this view which fall in the range with the given `index`, when its
+    /// keys are divided into `ranges` disjoint ranges by the hash of each key
+    #[instrument(level = "info", skip(self))]
+    pub async fn keys_in_range(
+        &mut self,
+        index: u32,
+        ranges: u32,
+    ) -> ReadySetResult<Vec<Vec<DfValue>>> {
         future::poll_fn(|cx| self.poll_ready(cx)).await?;
 
         let node = self.node;
@@ -1421,6 +1756,8 @@ impl ReaderHandle {
                         name: name.clone(),
                         shard: shardi,
                     },
+                    index,
+                    ranges,
                 })))
             })
             .collect::<FuturesUnordered<_>>();
@@ -1442,80 +1779,306 @@ impl ReaderHandle {
         Ok(vec)
     }
 
+    /// Get the number of keys and rows currently stored in this view, without reading the rows
+    /// themselves. For partially materialized views, this only counts the keys which are
+    /// currently filled.
+    #[instrument(level = "info", skip(self))]
+    pub async fn stats(&mut self) -> ReadySetResult<ViewStats> {
+        future::poll_fn(|cx| self.poll_ready(cx)).await?;
+
+        let node = self.node;
+        let name = self.name.clone();
+        let mut rsps = self
+            .shards
+            .iter_mut()
+            .enumerate()
+            .map(|(shardi, shard)| {
+                shard.call(Instrumented::from(Tagged::from(ReadQuery::Stats {
+                    target: ReaderAddress {
+                        node,
+                        name: name.clone(),
+                        shard: shardi,
+                    },
+                })))
+            })
+            .collect::<FuturesUnordered<_>>();
+
+        let mut stats = ViewStats::default();
+        while let Some(reply) = rsps
+            .next()
+            .await
+            .transpose()
+            .map_err(rpc_err!("View::stats"))?
+        {
+            if let ReadReply::Stats(shard_stats) = reply.v {
+                stats = stats.merge(&shard_stats);
+            } else {
+                unreachable!();
+            }
+        }
+
+        Ok(stats)
+    }
+
+    /// Read every row currently stored in this view, as a stream of the rows for at most
+    /// `keys_per_chunk` keys at a time, so the whole contents of the view never have to be held in
+    /// memory at once.
+    ///
+    /// Nor are all of the view's keys: they're divided into disjoint ranges by the hash of each key
+    /// (roughly one range per chunk, up to [`MAX_SCAN_KEY_RANGES`]), and the keys in each range
+    /// are only read once the rows for the keys in the previous range have been returned. Since
+    /// every key falls in exactly one range, a key which stays in the view for the whole scan is
+    /// read exactly once.
+    ///
+    /// Keys which aren't filled in the reader of a partial view aren't read (or filled).
+    pub async fn scan(
+        &mut self,
+        keys_per_chunk: usize,
+    ) -> ReadySetResult<impl Stream<Item = ReadySetResult<Vec<Vec<DfValue>>>> + '_> {
+        let node = self.node;
+        let keys_per_chunk = keys_per_chunk.max(1);
+        let num_keys = self.stats().await?.keys;
+        let ranges = (num_keys / keys_per_chunk).clamp(1, MAX_SCAN_KEY_RANGES) as u32;
+
+        Ok(futures_util::stream::try_unfold(
+            (self, 0, Vec::new().into_iter()),
+            move |(this, mut index, mut keys)| async move {
+                loop {
+                    let chunk = keys.by_ref().take(keys_per_chunk).collect::<Vec<_>>();
+                    if !chunk.is_empty() {
+                        let rows = this
+                            .multi_lookup(chunk, ReadBehavior::Blocking)
+                            .await?
+                            .into_vec();
+                        return Ok(Some((rows, (this, index, keys))));
+                    }
+                    if index == ranges {
+                        return Ok(None);
+                    }
+
+                    keys = this
+                        .keys_in_range(index, ranges)
+                        .await?
+                        .into_iter()
+                        .map(|key| {
+                            Vec1::try_from_vec(key)
+                                .map(KeyComparison::Equal)
+                                .map_err(|_| view_err(node, ReadySetError::EmptyKey))
+                        })
+                        .collect::<ReadySetResult<Vec<_>>>()?
+                        .into_iter();
+                    index += 1;
+                }
+            },
+        ))
+    }
+
+    /// Read every row currently stored in this view, as a stream of [`ColumnarBatch`]es of
+    /// `batch_size` rows each (apart from the last), for consumers which work with
+    /// column-oriented data.
+    ///
+    /// Keys which aren't filled in the reader of a partial view aren't read (or filled).
+    pub async fn columnar_batches(
+        &mut self,
+        batch_size: usize,
+    ) -> ReadySetResult<impl Stream<Item = ReadySetResult<ColumnarBatch>> + '_> {
+        let schema = self
+            .schema()
+            .ok_or_else(|| internal_err!("View {} has no schema", self.name))?
+            .schema(SchemaType::ReturnedSchema)
+            .to_vec();
+        let batch_size = batch_size.max(1);
+        let chunks = Box::pin(self.scan(batch_size).await?);
+
+        Ok(futures_util::stream::try_unfold(
+            (chunks, schema, Vec::<Vec<DfValue>>::new(), false),
+            move |(mut chunks, schema, mut rows, mut done)| async move {
+                // Rows are buffered across chunks, since the number of rows for each key varies
+                while !done && rows.len() < batch_size {
+                    match chunks.try_next().await? {
+                        Some(chunk) => rows.extend(chunk),
+                        None => done = true,
+                    }
+                }
+                if rows.is_empty() {
+                    return Ok(None);
+                }
+
+                let rest = rows.split_off(batch_size.min(rows.len()));
+                let batch = ColumnarBatch::from_rows(&schema, &rows)?;
+                Ok(Some((batch, (chunks, schema, rest, done))))
+            },
+        ))
+    }
+
     // TODO(andrew): consolidate RYW and normal reads into cohesive API once API design is settled.
     // RYW functionality currently added as duplicate methods so as not to disrupt current
     // reader usage until RYW is fully adopted
 
     /// Issue a raw `ViewQuery` against this view, and return the results.
     ///
-    /// The method will block if the results are not yet available only when `block` is `true`.
-    /// If `block` is false, misses will be returned as empty results. Any requested keys that have
-    /// missing state will be backfilled (asynchronously if `block` is `false`).
+    /// If the results are not yet available, what the method does is determined by the query's
+    /// [`ReadBehavior`]: for [`ReadBehavior::NonBlocking`], misses will be returned as
+    /// [`ReadySetError::ReaderMissingKey`]. Any requested keys that have missing state will be
+    /// backfilled (asynchronously if the read doesn't block).
+    ///
+    /// If the read fails with a networking error and the reader has other replicas, the read is
+    /// retried against each of them in turn, and later reads go to the first one that succeeds.
     pub async fn raw_lookup(&mut self, query: ViewQuery) -> ReadySetResult<ResultIterator> {
-        future::poll_fn(|cx| self.poll_ready(cx)).await?;
-        match self.call(query).await? {
-            LookupResult::NonBlockingMiss => Err(ReadySetError::ReaderMissingKey),
-            LookupResult::Results(results, _) => Ok(ResultIterator::owned(results)),
+        // Try each replica of the reader at most once. The query is only lent out while there's
+        // another replica to retry it against, so that the last attempt can send it without
+        // cloning it.
+        for _ in 0..self.failover.fallbacks.len() {
+            match self.lookup_once(Cow::Borrowed(&query)).await {
+                Err(error) if error.is_networking_related() => {
+                    warn!(
+                        %error,
+                        view = %self.name,
+                        "Read from reader replica failed, failing over to another replica"
+                    );
+                    self.fail_over()?;
+                }
+                res => return res,
+            }
         }
+        self.lookup_once(Cow::Owned(query)).await
+    }
+
+    /// Send a single read to the replica currently being read from, recording how it went
+    async fn lookup_once(&mut self, query: Cow<'_, ViewQuery>) -> ReadySetResult<ResultIterator> {
+        let start = Instant::now();
+        let res = async {
+            future::poll_fn(|cx| self.poll_ready(cx)).await?;
+            self.send(query).await
+        }
+        .await;
+
+        match res {
+            Err(error) if error.is_networking_related() => {
+                self.failover.record_failure();
+                Err(error)
+            }
+            res => {
+                self.failover.record_latency(start.elapsed());
+                match res? {
+                    LookupResult::NonBlockingMiss => Err(ReadySetError::ReaderMissingKey),
+                    LookupResult::Results(results, _) => Ok(ResultIterator::owned(results)),
+                }
+            }
+        }
+    }
+
+    /// Switch to reading from the next replica of the reader, in order of preference. The replica
+    /// previously read from becomes the last one to fail over to.
+    fn fail_over(&mut self) -> ReadySetResult<()> {
+        let shard_addrs = match self.failover.fallbacks.pop_front() {
+            Some(shard_addrs) => shard_addrs,
+            None => internal!("No replicas of view {} to fail over to", self.name),
+        };
+        self.shards = self.failover.connect(&self.name, &shard_addrs)?;
+        self.failover.track(&shard_addrs);
+        let failed = mem::replace(&mut self.shard_addrs, shard_addrs);
+        self.failover.fallbacks.push_back(failed);
+        Ok(())
     }
 
     /// Retrieve the query results for the given parameter value.
     ///
-    /// The method will block if the results are not yet available only when `block` is `true`.
-    pub async fn lookup(&mut self, key: &[DfValue], block: bool) -> ReadySetResult<ResultIterator> {
-        self.lookup_ryw(key, block, None).await
+    /// If the results are not yet available, what the method does is determined by
+    /// `read_behavior`, which can also be given as a `bool` for whether to block.
+    pub async fn lookup(
+        &mut self,
+        key: &[DfValue],
+        read_behavior: impl Into<ReadBehavior>,
+    ) -> ReadySetResult<ResultIterator> {
+        self.lookup_ryw(key, read_behavior, None).await
+    }
+
+    /// Retrieve the query results for the given parameter value, deserializing each row into a
+    /// `T` by matching the names of the view's columns to the names of its fields. Columns which
+    /// don't match any field of `T` are ignored, as are any key columns which aren't returned by
+    /// the query the view was created for.
+    ///
+    /// Integers can be deserialized into any integer type they fit in (or into `bool`), text into
+    /// strings, binary data into byte buffers, and `NULL` into `None`. All other values are
+    /// deserialized from their text representation.
+    pub async fn lookup_typed<T>(
+        &mut self,
+        key: &[DfValue],
+        read_behavior: impl Into<ReadBehavior>,
+    ) -> ReadySetResult<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let rows = self.lookup(key, read_behavior).await?;
+        typed::deserialize_rows::<T, false>(self.returned_columns(), rows)
+    }
+
+    /// Like [`lookup_typed`](Self::lookup_typed), but returns an error if any of the columns
+    /// returned by the query the view was created for don't match a field of `T`. Key columns
+    /// which were only added to the view so it could be looked up by them aren't required to
+    /// match.
+    pub async fn lookup_typed_strict<T>(
+        &mut self,
+        key: &[DfValue],
+        read_behavior: impl Into<ReadBehavior>,
+    ) -> ReadySetResult<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let rows = self.lookup(key, read_behavior).await?;
+        typed::deserialize_rows::<T, true>(self.returned_columns(), rows)
     }
 
     /// Retrieve the query results for the given parameter values.
     ///
-    /// The method will block if the results are not yet available only when `block` is `true`.
-    /// If `block` is false, misses will be returned as empty results. Any requested keys that have
-    /// missing state will be backfilled (asynchronously if `block` is `false`).
+    /// If the results are not yet available, what the method does is determined by
+    /// `read_behavior` (see [`raw_lookup`](Self::raw_lookup)).
     pub async fn multi_lookup(
         &mut self,
         key_comparisons: Vec<KeyComparison>,
-        block: bool,
+        read_behavior: impl Into<ReadBehavior>,
     ) -> ReadySetResult<ResultIterator> {
-        self.raw_lookup((key_comparisons, block, None).into()).await
+        self.raw_lookup((key_comparisons, read_behavior.into(), None).into())
+            .await
     }
 
     /// Retrieve the query results for the given parameter value.
     ///
-    /// The method will block if the results are not yet available or do not have a timestamp
-    /// satisfying the `ticket ` requirement only when `block` is `true`.
-    /// If `block` is false, misses will be returned as empty results. Any requested keys that have
-    /// missing state will be backfilled (asynchronously if `block` is `false`).
+    /// If the results are not yet available or do not have a timestamp satisfying the `ticket`
+    /// requirement, what the method does is determined by `read_behavior` (see
+    /// [`raw_lookup`](Self::raw_lookup)).
     pub async fn lookup_ryw(
         &mut self,
         key: &[DfValue],
-        block: bool,
+        read_behavior: impl Into<ReadBehavior>,
         ticket: Option<Timestamp>,
     ) -> ReadySetResult<ResultIterator> {
         // TODO: Optimized version of this function?
         let key = Vec1::try_from_vec(key.into())
             .map_err(|_| view_err(self.node, ReadySetError::EmptyKey))?;
-        self.multi_lookup_ryw(vec![KeyComparison::Equal(key)], block, ticket)
+        self.multi_lookup_ryw(vec![KeyComparison::Equal(key)], read_behavior, ticket)
             .await
     }
 
     /// Retrieve the query results for the given parameter values
     ///
-    /// The method will block if the results are not yet available or do not have a timestamp
-    /// satisfying the `ticket ` requirement only when `block` is `true`.
-    /// If `block` is false, misses will be returned as empty results. Any requested keys that have
-    /// missing state will be backfilled (asynchronously if `block` is `false`).
+    /// If the results are not yet available or do not have a timestamp satisfying the `ticket`
+    /// requirement, what the method does is determined by `read_behavior` (see
+    /// [`raw_lookup`](Self::raw_lookup)).
     pub async fn multi_lookup_ryw(
         &mut self,
         key_comparisons: Vec<KeyComparison>,
-        block: bool,
+        read_behavior: impl Into<ReadBehavior>,
         ticket: Option<Timestamp>,
     ) -> ReadySetResult<ResultIterator> {
-        self.raw_lookup((key_comparisons, block, ticket).into())
+        self.raw_lookup((key_comparisons, read_behavior.into(), ticket).into())
             .await
     }
 
-    /// Build a [`ViewQuery`] for performing a lookup against this [`ReaderHandle`] given keys and
-    /// binops used for post-read filters
+    /// Build a [`ViewQuery`] for performing a lookup against this [`ReaderHandle`] given keys,
+    /// binops used for post-read filters, and predicates with placeholders that can't be turned
+    /// into lookup keys (see [`readyset_sql_passes::is_post_lookup_predicate`])
     #[allow(clippy::too_many_arguments)]
     fn build_view_query(
         &self,
@@ -1524,9 +2087,10 @@ impl ReaderHandle {
         limit: Option<usize>,
         offset: Option<usize>,
         ticket: Option<Timestamp>,
-        blocking_read: bool,
+        read_behavior: ReadBehavior,
         dialect: Dialect,
         mut binops: Vec<(&Column, BinaryOperator)>,
+        post_lookup_predicates: &[Expr],
     ) -> ReadySetResult<Option<ViewQuery>> {
         let schema = self
             .schema()
@@ -1541,13 +2105,56 @@ impl ReaderHandle {
             SchemaType::ProjectedSchema,
         )?;
 
+        // Placeholders in post-lookup predicates don't have corresponding binops or key columns, so
+        // we need to skip over them when mapping between binops and placeholders
+        let post_lookup_placeholders = post_lookup_placeholders(post_lookup_predicates);
+        let binop_placeholder = |binop_idx: usize| -> PlaceholderIdx {
+            let mut placeholder = binop_idx + 1;
+            for idx in &post_lookup_placeholders {
+                if *idx <= placeholder {
+                    placeholder += 1;
+                }
+            }
+            placeholder
+        };
+        let placeholder_binop = |placeholder: PlaceholderIdx| -> usize {
+            placeholder
+                - 1
+                - post_lookup_placeholders
+                    .iter()
+                    .filter(|idx| **idx < placeholder)
+                    .count()
+        };
+
+        let mut filters = match raw_keys.first() {
+            Some(key) if !post_lookup_predicates.is_empty() => {
+                if raw_keys.iter().any(|k| {
+                    post_lookup_placeholders
+                        .iter()
+                        .any(|idx| k.get(idx - 1) != key.get(idx - 1))
+                }) {
+                    unsupported!(
+                        "Placeholders in positions other than direct comparisons with columns must \
+                         have the same value for all lookup keys"
+                    );
+                }
+                lower_post_lookup_predicates(
+                    post_lookup_predicates,
+                    key,
+                    projected_schema,
+                    dialect,
+                )?
+            }
+            _ => vec![],
+        };
+
         trace!("select::lookup");
         let bogo = vec![vec1![DfValue::from(0i32)].into()];
         let mut filter_op_idx = None;
-        let mut filters = binops
+        let like_filters = binops
             .iter()
             .enumerate()
-            .filter(|(_, (_, binop))| matches!(binop, BinaryOperator::Like | BinaryOperator::ILike))
+            .filter(|(_, (_, binop))| *binop == BinaryOperator::ILike)
             .map(|(idx, (col, op))| -> ReadySetResult<_> {
                 let key = raw_keys.drain(0..1).next().ok_or(ReadySetError::EmptyKey)?;
                 if !raw_keys.is_empty() {
@@ -1561,7 +2168,10 @@ impl ReaderHandle {
                     .ok_or_else(|| ReadySetError::NoSuchColumn(col.name.to_string()))?;
 
                 let key_type = key_types.remove(idx);
-                let value = key[idx].coerce_to(key_type, &DfType::Unknown)?; // No from_ty, key values are literals
+                let value = key
+                    .get(binop_placeholder(idx) - 1)
+                    .ok_or(ReadySetError::EmptyKey)?
+                    .coerce_to(key_type, &DfType::Unknown)?; // No from_ty, key values are literals
 
                 if !key.is_empty() {
                     // the LIKE/ILIKE isn't our only key, add the rest back to `keys`
@@ -1582,9 +2192,12 @@ impl ReaderHandle {
                         ty: key_type.clone(),
                     }),
                     ty: DfType::Bool,
+                    dialect,
+                    non_null_operands: false,
                 })
             })
             .collect::<Result<Vec<_>, _>>()?;
+        filters.extend(like_filters);
 
         if let Some(filter_op_idx) = filter_op_idx {
             // if we're using a column for a post-lookup filter, remove it from our list of binops
@@ -1592,12 +2205,21 @@ impl ReaderHandle {
             binops.remove(filter_op_idx);
         }
 
-        let keys = if raw_keys.is_empty() {
+        let keys = if raw_keys.is_empty()
+            || self
+                .key_map()
+                .iter()
+                .all(|(placeholder, _)| *placeholder == ViewPlaceholder::Generated)
+        {
+            // Either the query has no parameters, or all of its parameters are in post-lookup
+            // predicates
             bogo
         } else {
             let mut unique_binops = binops.iter().map(|(_, b)| *b).unique();
             let binop_to_use = unique_binops.next().unwrap_or(BinaryOperator::Equal);
-            let mixed_binops = unique_binops.next().is_some();
+            // LIKE is looked up as a range between two bounds, like mixed binops
+            let mixed_binops = unique_binops.next().is_some()
+                || binops.iter().any(|(_, b)| *b == BinaryOperator::Like);
 
             let key_types: HashMap<usize, &DfType> = self
                 .key_map()
@@ -1638,7 +2260,7 @@ impl ReaderHandle {
 
             raw_keys
                 .into_iter()
-                .map(|key| {
+                .map(|key| -> ReadySetResult<Option<KeyComparison>> {
                     let mut k = vec![];
                     let mut bounds: Option<(Vec<DfValue>, Vec<DfValue>)> = if mixed_binops {
                         Some((vec![], vec![]))
@@ -1661,6 +2283,24 @@ impl ReaderHandle {
 
                                 let value = remap_key(key.as_ref(), idx, key_type)?;
 
+                                let binop = if bounds.is_some() {
+                                    binops
+                                        .get(placeholder_binop(*idx))
+                                        .ok_or_else(|| {
+                                            internal_err!("Missing binop for placeholder {idx}")
+                                        })?
+                                        .1
+                                } else {
+                                    binop_to_use
+                                };
+
+                                // Comparing NULL with anything other than `<=>` can never be
+                                // true, so a key with a NULL value in any of those positions
+                                // can't match any rows
+                                if value.is_none() && binop != BinaryOperator::NullSafeEqual {
+                                    return Ok(None);
+                                }
+
                                 let make_op = |op: DfBinaryOperator| DfExpr::Op {
                                     left: Box::new(DfExpr::Column {
                                         index: *key_column_idx,
@@ -1672,19 +2312,50 @@ impl ReaderHandle {
                                         ty: key_type.clone(),
                                     }),
                                     ty: DfType::Bool, // TODO: infer type
+                                    dialect,
+                                    non_null_operands: false,
                                 };
 
                                 if let Some((lower_bound, upper_bound)) = &mut bounds {
-                                    let binop = binops[*idx - 1].1;
                                     match binop {
-                                        BinaryOperator::Like
-                                        | BinaryOperator::NotLike
+                                        BinaryOperator::Like => {
+                                            // Every value matching the pattern starts with its
+                                            // literal prefix, so look up the range of values
+                                            // starting with that prefix and filter that range with
+                                            // the full pattern
+                                            let pattern = <&str>::try_from(&value)?;
+                                            let prefix = literal_prefix(pattern);
+                                            if prefix.is_empty() {
+                                                return Err(ReadySetError::UnprefixedLikePattern(
+                                                    pattern.to_owned(),
+                                                ));
+                                            }
+                                            let collation = value.collation().unwrap_or_default();
+                                            // The successor of the prefix is only an upper bound
+                                            // for the values starting with it if values are
+                                            // compared by their characters
+                                            let upper = match collation {
+                                                Collation::Utf8 => prefix_successor(&prefix)
+                                                    .map_or(DfValue::Max, |upper| {
+                                                        DfValue::from_str_and_collation(
+                                                            &upper, collation,
+                                                        )
+                                                    }),
+                                                Collation::Citext => DfValue::Max,
+                                            };
+                                            filters.push(make_op(DfBinaryOperator::Like));
+                                            lower_bound.push(DfValue::from_str_and_collation(
+                                                &prefix, collation,
+                                            ));
+                                            upper_bound.push(upper);
+                                        }
+                                        BinaryOperator::NotLike
                                         | BinaryOperator::ILike
                                         | BinaryOperator::NotILike => {
                                             internal!("Already should have matched on LIKE above")
                                         }
 
-                                        BinaryOperator::Equal => {
+                                        BinaryOperator::Equal | BinaryOperator::NullSafeEqual => {
                                             lower_bound.push(value.clone());
                                             upper_bound.push(value);
                                         }
@@ -1733,7 +2404,10 @@ impl ReaderHandle {
                                     ) || (
                                         // Or all other range keys beyond the *first* key within a
                                         // compound range
-                                        binop_to_use != BinaryOperator::Equal && !k.is_empty()
+                                        !matches!(
+                                            binop_to_use,
+                                            BinaryOperator::Equal | BinaryOperator::NullSafeEqual
+                                        ) && !k.is_empty()
                                     ) {
                                         filters.push(make_op(DfBinaryOperator::from_sql_op(
                                             binop_to_use,
@@ -1750,6 +2424,9 @@ impl ReaderHandle {
 
                                 let lower_value = remap_key(key.as_ref(), lower_idx, key_type)?;
                                 let upper_value = remap_key(key.as_ref(), upper_idx, key_type)?;
+                                if lower_value.is_none() || upper_value.is_none() {
+                                    return Ok(None);
+                                }
                                 let (lower_key, upper_key) =
                                     bounds.get_or_insert_with(Default::default);
                                 lower_key.push(lower_value);
@@ -1776,14 +2453,15 @@ impl ReaderHandle {
 
                     if let Some((lower, upper)) = bounds {
                         debug_assert!(k.is_empty());
-                        Ok(KeyComparison::Range((
+                        Ok(Some(KeyComparison::Range((
                             Bound::Included(lower.try_into()?),
                             Bound::Included(upper.try_into()?),
-                        )))
+                        ))))
                     } else {
-                        KeyComparison::from_key_and_operator(k, binop_to_use)
+                        KeyComparison::from_key_and_operator(k, binop_to_use).map(Some)
                     }
                 })
+                .filter_map(Result::transpose)
                 .collect::<ReadySetResult<Vec<_>>>()?
         };
 
@@ -1791,20 +2469,138 @@ impl ReaderHandle {
 
         Ok(Some(ViewQuery {
             key_comparisons: keys,
-            block: blocking_read,
+            read_behavior,
             filter: filters.into_iter().reduce(|expr1, expr2| DfExpr::Op {
                 left: Box::new(expr1),
                 op: DfBinaryOperator::And,
                 right: Box::new(expr2),
                 ty: DfType::Bool, // AND is a boolean operator
+                dialect,
+                non_null_operands: false,
             }),
             limit,
             offset,
             timestamp: ticket,
+            snapshot: None,
         }))
     }
 }
 
+/// Returns the smallest string which is greater than every string starting with `prefix` (comparing
+/// by characters), or `None` if there is no such string
+fn prefix_successor(prefix: &str) -> Option<String> {
+    let mut successor = prefix.to_owned();
+    while let Some(last) = successor.pop() {
+        // Skip over the surrogate range, which isn't made up of valid chars
+        let next = match last {
+            '\u{d7ff}' => Some('\u{e000}'),
+            c => char::from_u32(c as u32 + 1),
+        };
+        if let Some(next) = next {
+            successor.push(next);
+            return Some(successor);
+        }
+    }
+    None
+}
+
+/// Context for lowering post-lookup predicates, which resolves columns in the projected schema of a
+/// reader
+#[derive(Clone)]
+struct ReaderLowerContext<'a> {
+    schema: &'a [ColumnSchema],
+}
+
+impl<'a> LowerContext for ReaderLowerContext<'a> {
+    fn resolve_column(&self, col: Column) -> ReadySetResult<(usize, DfType)> {
+        let index = self
+            .schema
+            .iter()
+            .position(|cs| {
+                cs.column.name == col.name
+                    && (col.table.is_none()
+                        || cs.column.table.is_none()
+                        || cs.column.table == col.table)
+            })
+            .or_else(|| {
+                self.schema.iter().position(|cs| {
+                    cs.base.as_ref().map_or(false, |b| {
+                        b.column == col.name
+                            && col.table.as_ref().map_or(true, |t| b.table.name == t.name)
+                    })
+                })
+            })
+            .ok_or_else(|| ReadySetError::NoSuchColumn(col.name.to_string()))?;
+        #[allow(clippy::indexing_slicing)] // just found the index
+        Ok((index, self.schema[index].column_type.clone()))
+    }
+
+    fn resolve_type(&self, _ty: Relation) -> Option<DfType> {
+        None
+    }
+}
+
+/// Returns the (sorted, deduplicated) indices of all the numbered placeholders in the given
+/// post-lookup predicates
+fn post_lookup_placeholders(predicates: &[Expr]) -> Vec<PlaceholderIdx> {
+    predicates
+        .iter()
+        .flat_map(|p| iter::once(p).chain(p.recursive_subexpressions()))
+        .filter_map(|expr| match expr {
+            Expr::Literal(Literal::Placeholder(ItemPlaceholder::DollarNumber(idx))) => {
+                Some(*idx as PlaceholderIdx)
+            }
+            _ => None,
+        })
+        .sorted()
+        .dedup()
+        .collect()
+}
+
+/// Lower the given post-lookup predicates to expressions that can be evaluated against the rows of
+/// a reader with the given projected schema, substituting the values in `key` for their
+/// placeholders
+fn lower_post_lookup_predicates(
+    predicates: &[Expr],
+    key: &[DfValue],
+    projected_schema: &[ColumnSchema],
+    dialect: Dialect,
+) -> ReadySetResult<Vec<DfExpr>> {
+    struct SubstitutePlaceholders<'a> {
+        key: &'a [DfValue],
+    }
+
+    impl<'ast, 'a> VisitorMut<'ast> for SubstitutePlaceholders<'a> {
+        type Error = ReadySetError;
+
+        fn visit_literal(&mut self, literal: &'ast mut Literal) -> Result<(), Self::Error> {
+            if let Literal::Placeholder(ItemPlaceholder::DollarNumber(idx)) = literal {
+                let value = self
+                    .key
+                    .get(*idx as usize - 1)
+                    .ok_or_else(|| internal_err!("Missing value for placeholder ${idx}"))?;
+                *literal = Literal::try_from(value.clone())?;
+            }
+            Ok(())
+        }
+    }
+
+    predicates
+        .iter()
+        .map(|predicate| {
+            let mut predicate = predicate.clone();
+            SubstitutePlaceholders { key }.visit_expr(&mut predicate)?;
+            DfExpr::lower(
+                predicate,
+                dialect,
+                ReaderLowerContext {
+                    schema: projected_schema,
+                },
+            )
+        })
+        .collect()
+}
+
 impl ReusedReaderHandle {
     /// Get a reference to the reused [`ReaderHandle`].
     pub fn inner(&self) -> &ReaderHandle {
@@ -1836,10 +2632,18 @@ impl ReusedReaderHandle {
         limit: Option<usize>,
         offset: Option<usize>,
         ticket: Option<Timestamp>,
-        blocking_read: bool,
+        read_behavior: ReadBehavior,
         dialect: Dialect,
         binops: Vec<(&Column, BinaryOperator)>,
+        post_lookup_predicates: &[Expr],
     ) -> ReadySetResult<Option<ViewQuery>> {
+        if !post_lookup_predicates.is_empty() {
+            unsupported!(
+                "Placeholders in positions other than direct comparisons with columns are not \
+                 supported when reusing caches"
+            );
+        }
+
         // If any placeholders in our query correspond to inlined values in the migrated query,
         // verify that we are executing our query with these values.
         for params in raw_keys.iter() {
@@ -1871,9 +2675,10 @@ impl ReusedReaderHandle {
             limit,
             offset,
             ticket,
-            blocking_read,
+            read_behavior,
             dialect,
             binops,
+            post_lookup_predicates,
         )
     }
 }
@@ -1890,9 +2695,10 @@ impl View {
         limit: Option<usize>,
         offset: Option<usize>,
         ticket: Option<Timestamp>,
-        blocking_read: bool,
+        read_behavior: ReadBehavior,
         dialect: Dialect,
         binops: Vec<(&Column, BinaryOperator)>,
+        post_lookup_predicates: &[Expr],
     ) -> ReadySetResult<Option<(&mut ReaderHandle, ViewQuery)>> {
         // If any placeholders in our query correspond to inlined values in the migrated query,
         // verify that we are executing our query with these values.
@@ -1904,9 +2710,10 @@ impl View {
                     limit,
                     offset,
                     ticket,
-                    blocking_read,
+                    read_behavior,
                     dialect,
                     binops,
+                    post_lookup_predicates,
                 )
                 .map(|r| r.map(|vq| (handle, vq))),
             View::MultipleReused(handles) => {
@@ -1917,9 +2724,10 @@ impl View {
                         limit,
                         offset,
                         ticket.clone(),
-                        blocking_read,
+                        read_behavior,
                         dialect,
                         binops.clone(),
+                        post_lookup_predicates,
                     ) {
                         Ok(Some(vq)) => {
                             return Ok(Some((reused_handle.inner_mut(), vq)));
@@ -1942,6 +2750,43 @@ impl View {
         }
     }
 
+    /// Retrieve the query results for the given parameter value, deserializing each row into a
+    /// `T`. See [`ReaderHandle::lookup_typed`].
+    ///
+    /// Only supported for [`View::Single`].
+    pub async fn lookup_typed<T>(
+        &mut self,
+        key: &[DfValue],
+        read_behavior: impl Into<ReadBehavior>,
+    ) -> ReadySetResult<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        match self {
+            View::Single(rh) => rh.lookup_typed(key, read_behavior).await,
+            View::MultipleReused(_) => unsupported!("Typed lookups into reused caches"),
+        }
+    }
+
+    /// Retrieve the query results for the given parameter value, deserializing each row into a
+    /// `T`, and returning an error if any of the columns of the view don't match a field of `T`.
+    /// See [`ReaderHandle::lookup_typed_strict`].
+    ///
+    /// Only supported for [`View::Single`].
+    pub async fn lookup_typed_strict<T>(
+        &mut self,
+        key: &[DfValue],
+        read_behavior: impl Into<ReadBehavior>,
+    ) -> ReadySetResult<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        match self {
+            View::Single(rh) => rh.lookup_typed_strict(key, read_behavior).await,
+            View::MultipleReused(_) => unsupported!("Typed lookups into reused caches"),
+        }
+    }
+
     /// Returns a single ReaderHandle if Self is [`View::Single`]
     pub fn into_reader_handle(self) -> Option<ReaderHandle> {
         match self {
@@ -2066,6 +2911,25 @@ mod tests {
         eq_laws!(KeyComparison);
     }
 
+    #[test]
+    fn read_behavior_round_trips_through_strings() {
+        for read_behavior in [
+            ReadBehavior::Blocking,
+            ReadBehavior::NonBlocking,
+            ReadBehavior::StaleIfAvailable,
+        ] {
+            assert_eq!(
+                read_behavior.to_string().parse::<ReadBehavior>().unwrap(),
+                read_behavior
+            );
+        }
+        assert_eq!(
+            "Stale-If-Available".parse::<ReadBehavior>().unwrap(),
+            ReadBehavior::StaleIfAvailable
+        );
+        "always".parse::<ReadBehavior>().unwrap_err();
+    }
+
     mod build_view_query {
         use std::net::{IpAddr, Ipv4Addr};
 
@@ -2083,6 +2947,47 @@ mod tests {
             dialect: Dialect,
             binops: Vec<(&Column, BinaryOperator)>,
         ) -> ViewQuery {
+            make_build_query_with_post_lookup(
+                raw_keys,
+                limit,
+                offset,
+                key_map,
+                dialect,
+                binops,
+                &[],
+            )
+        }
+
+        fn make_build_query_with_post_lookup(
+            raw_keys: Vec<Cow<'_, [DfValue]>>,
+            limit: Option<usize>,
+            offset: Option<usize>,
+            key_map: &[(ViewPlaceholder, KeyColumnIdx)],
+            dialect: Dialect,
+            binops: Vec<(&Column, BinaryOperator)>,
+            post_lookup_predicates: &[Expr],
+        ) -> ViewQuery {
+            try_make_build_query(
+                raw_keys,
+                limit,
+                offset,
+                key_map,
+                dialect,
+                binops,
+                post_lookup_predicates,
+            )
+            .unwrap()
+        }
+
+        fn try_make_build_query(
+            raw_keys: Vec<Cow<'_, [DfValue]>>,
+            limit: Option<usize>,
+            offset: Option<usize>,
+            key_map: &[(ViewPlaceholder, KeyColumnIdx)],
+            dialect: Dialect,
+            binops: Vec<(&Column, BinaryOperator)>,
+            post_lookup_predicates: &[Expr],
+        ) -> ReadySetResult<ViewQuery> {
             let schema = ViewSchema::new(
                 vec![
                     ColumnSchema {
@@ -2158,8 +3063,10 @@ mod tests {
                 columns: Arc::new([]),        // Not used for test
                 schema: Some(schema),
                 key_mapping: key_map.to_vec(),
+                freshness: Default::default(),
                 shards: Vec1::new(c), // Not used for test
                 shard_addrs: vec![],  // Not used for test
+                failover: Default::default(),
             };
             let dataflow_dialect = match dialect {
                 Dialect::MySQL => DfDialect::DEFAULT_MYSQL,
@@ -2171,13 +3078,12 @@ mod tests {
                 limit,
                 offset,
                 None,
-                true,
+                ReadBehavior::Blocking,
                 dataflow_dialect,
                 binops,
+                post_lookup_predicates,
             )
-            .unwrap()
-            .unwrap()
-            .1
+            .map(|res| res.unwrap().1)
         }
 
         #[test]
@@ -2205,6 +3111,59 @@ mod tests {
             );
         }
 
+        #[test]
+        fn null_point_lookup() {
+            // "SELECT t.x FROM t WHERE t.x IN ($1, $2)"
+            let query = make_build_query(
+                vec![
+                    Cow::Owned(vec![DfValue::None]),
+                    Cow::Owned(vec![DfValue::from(1)]),
+                ],
+                None,
+                None,
+                &[(ViewPlaceholder::OneToOne(1), 0)],
+                Dialect::MySQL,
+                vec![(
+                    &Column {
+                        name: "x".into(),
+                        table: Some("t".into()),
+                    },
+                    BinaryOperator::Equal,
+                )],
+            );
+
+            assert!(query.filter.is_none());
+            assert_eq!(
+                query.key_comparisons,
+                vec![KeyComparison::from(vec1![DfValue::from(1)])]
+            );
+        }
+
+        #[test]
+        fn null_safe_equal_lookup() {
+            // "SELECT t.x FROM t WHERE t.x <=> $1"
+            let query = make_build_query(
+                vec![Cow::Owned(vec![DfValue::None])],
+                None,
+                None,
+                &[(ViewPlaceholder::OneToOne(1), 0)],
+                Dialect::MySQL,
+                vec![(
+                    &Column {
+                        name: "x".into(),
+                        table: Some("t".into()),
+                    },
+                    BinaryOperator::NullSafeEqual,
+                )],
+            );
+
+            assert!(query.filter.is_none());
+            assert_eq!(
+                query.key_comparisons,
+                vec![KeyComparison::from(vec1![DfValue::None])]
+            );
+        }
+
         #[test]
         fn single_between() {
             // "SELECT t.x FROM t WHERE t.x BETWEEN $1 AND $2"
@@ -2284,10 +3243,86 @@ mod tests {
                         ty: DfType::DEFAULT_TEXT
                     }),
                     ty: DfType::Bool,
+                    dialect: DfDialect::DEFAULT_MYSQL,
+                    non_null_operands: false,
                 })
             );
         }
 
+        #[test]
+        fn like_prefix() {
+            // "SELECT t.x FROM t WHERE t.y LIKE $1"
+            let query = make_build_query(
+                vec![Cow::Owned(vec![DfValue::from("ab%c")])],
+                None,
+                None,
+                &[(ViewPlaceholder::OneToOne(1), 1)],
+                Dialect::MySQL,
+                vec![(
+                    &Column {
+                        name: "y".into(),
+                        table: Some("t".into()),
+                    },
+                    BinaryOperator::Like,
+                )],
+            );
+
+            assert_eq!(
+                query.key_comparisons,
+                vec![KeyComparison::Range((
+                    Bound::Included(vec1![DfValue::from("ab")]),
+                    Bound::Included(vec1![DfValue::from("ac")])
+                ))]
+            );
+            assert_eq!(
+                query.filter,
+                Some(DfExpr::Op {
+                    left: Box::new(DfExpr::Column {
+                        index: 1,
+                        ty: DfType::DEFAULT_TEXT
+                    }),
+                    op: DfBinaryOperator::Like,
+                    right: Box::new(DfExpr::Literal {
+                        val: DfValue::from("ab%c"),
+                        ty: DfType::DEFAULT_TEXT
+                    }),
+                    ty: DfType::Bool,
+                    dialect: DfDialect::DEFAULT_MYSQL,
+                    non_null_operands: false,
+                })
+            );
+        }
+
+        #[test]
+        fn like_without_prefix() {
+            // "SELECT t.x FROM t WHERE t.y LIKE $1"
+            let err = try_make_build_query(
+                vec![Cow::Owned(vec![DfValue::from("%ab")])],
+                None,
+                None,
+                &[(ViewPlaceholder::OneToOne(1), 1)],
+                Dialect::MySQL,
+                vec![(
+                    &Column {
+                        name: "y".into(),
+                        table: Some("t".into()),
+                    },
+                    BinaryOperator::Like,
+                )],
+                &[],
+            )
+            .unwrap_err();
+            assert_eq!(err, ReadySetError::UnprefixedLikePattern("%ab".into()));
+        }
+
+        #[test]
+        fn prefix_successors() {
+            assert_eq!(prefix_successor("ab").as_deref(), Some("ac"));
+            assert_eq!(prefix_successor("a\u{10ffff}").as_deref(), Some("b"));
+            assert_eq!(prefix_successor("\u{d7ff}").as_deref(), Some("\u{e000}"));
+            assert_eq!(prefix_successor("\u{10ffff}"), None);
+        }
+
         #[test]
         fn mixed_equal_and_inclusive() {
             // "SELECT t.x FROM t WHERE t.x >= $1 AND t.y = $2"
@@ -2421,6 +3456,8 @@ mod tests {
                         ty: DfType::Int
                     }),
                     ty: DfType::Bool,
+                    dialect: DfDialect::DEFAULT_MYSQL,
+                    non_null_operands: false,
                 })
             );
             assert_eq!(
@@ -2475,7 +3512,9 @@ mod tests {
                             val: 1.into(),
                             ty: DfType::Int
                         }),
-                        ty: DfType::Bool
+                        ty: DfType::Bool,
+                        dialect: DfDialect::DEFAULT_MYSQL,
+                        non_null_operands: false,
                     }),
                     op: DfBinaryOperator::And,
                     right: Box::new(DfExpr::Op {
@@ -2488,11 +3527,16 @@ mod tests {
                             val: "a".into(),
                             ty: DfType::DEFAULT_TEXT
                         }),
-                        ty: DfType::Bool
+                        ty: DfType::Bool,
+                        dialect: DfDialect::DEFAULT_MYSQL,
+                        non_null_operands: false,
                     }),
-                    ty: DfType::Bool
+                    ty: DfType::Bool,
+                    dialect: DfDialect::DEFAULT_MYSQL,
+                    non_null_operands: false,
                 })
             );
+            assert_eq!(query.filtered_columns().as_slice(), &[0, 1]);
 
             assert_eq!(
                 query.key_comparisons,
@@ -2545,7 +3589,9 @@ mod tests {
                         val: "a".into(),
                         ty: DfType::DEFAULT_TEXT
                     }),
-                    ty: DfType::Bool
+                    ty: DfType::Bool,
+                    dialect: DfDialect::DEFAULT_MYSQL,
+                    non_null_operands: false,
                 })
             );
 
@@ -2596,5 +3642,99 @@ mod tests {
                 .into()]
             );
         }
+
+        fn passes_filter(query: &ViewQuery, row: &[DfValue]) -> bool {
+            query
+                .filter
+                .as_ref()
+                .unwrap()
+                .eval(row)
+                .unwrap()
+                .is_truthy()
+        }
+
+        #[test]
+        fn only_post_lookup_predicates() {
+            // "SELECT t.x FROM t WHERE t.x * $1 > $2"
+            let query = make_build_query_with_post_lookup(
+                vec![Cow::Owned(vec![DfValue::from(2), DfValue::from(5)])],
+                None,
+                None,
+                &[(ViewPlaceholder::Generated, 0)],
+                Dialect::MySQL,
+                vec![],
+                &[nom_sql::parse_expr(Dialect::MySQL, "t.x * $1 > $2").unwrap()],
+            );
+
+            assert_eq!(
+                query.key_comparisons,
+                vec![KeyComparison::from(vec1![DfValue::from(0i32)])]
+            );
+            assert!(passes_filter(
+                &query,
+                &[DfValue::from(3), DfValue::from("a")]
+            ));
+            assert!(!passes_filter(
+                &query,
+                &[DfValue::from(2), DfValue::from("a")]
+            ));
+        }
+
+        #[test]
+        fn post_lookup_predicates_and_keys() {
+            // "SELECT t.x FROM t WHERE t.x * $1 > $2 AND t.y = $3 AND t.x < $4"
+            let query = make_build_query_with_post_lookup(
+                vec![Cow::Owned(vec![
+                    DfValue::from(2),
+                    DfValue::from(5),
+                    DfValue::from("a"),
+                    DfValue::from(10),
+                ])],
+                None,
+                None,
+                &[
+                    (ViewPlaceholder::OneToOne(3), 1),
+                    (ViewPlaceholder::OneToOne(4), 0),
+                ],
+                Dialect::MySQL,
+                vec![
+                    (
+                        &Column {
+                            name: "y".into(),
+                            table: Some("t".into()),
+                        },
+                        BinaryOperator::Equal,
+                    ),
+                    (
+                        &Column {
+                            name: "x".into(),
+                            table: Some("t".into()),
+                        },
+                        BinaryOperator::Less,
+                    ),
+                ],
+                &[nom_sql::parse_expr(Dialect::MySQL, "t.x * $1 > $2").unwrap()],
+            );
+
+            assert_eq!(
+                query.key_comparisons,
+                vec![KeyComparison::from_range(
+                    &(vec1![DfValue::from("a"), DfValue::None]
+                        ..=vec1![DfValue::from("a"), DfValue::from(10)])
+                )]
+            );
+            assert!(passes_filter(
+                &query,
+                &[DfValue::from(3), DfValue::from("a")]
+            ));
+            assert!(!passes_filter(
+                &query,
+                &[DfValue::from(2), DfValue::from("a")]
+            ));
+            assert!(!passes_filter(
+                &query,
+                &[DfValue::from(10), DfValue::from("a")]
+            ));
+        }
     }
 }
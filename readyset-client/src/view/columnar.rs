@@ -0,0 +1,228 @@
+//! Conversion of the rows read from a reader into column-oriented batches.
+//!
+//! Readers store (and return) their state row by row, as [`DfValue`]s, but analytical consumers
+//! (such as BI tools, or dataframe libraries pulling large results) work most efficiently with
+//! results laid out column by column, with every value in a column having the same physical type.
+//! [`ColumnarBatch::from_rows`] converts a set of rows into that layout, using the
+//! [`ColumnSchema`] of the view to pick a [`ColumnData`] representation for each column, in the
+//! same way an Apache Arrow record batch would be laid out.
+
+use readyset_data::{DfType, DfValue};
+use readyset_errors::{internal_err, ReadySetResult};
+use serde::{Deserialize, Serialize};
+
+use super::ColumnSchema;
+use crate::SqlIdentifier;
+
+/// The values of a single column of a [`ColumnarBatch`], all of the same physical type.
+///
+/// Values which are `NULL` in the original rows are stored as the default value of the physical
+/// type, and marked as null in the [`ColumnarColumn::validity`] of the column.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ColumnData {
+    /// Boolean values
+    Boolean(Vec<bool>),
+    /// Signed integers of any width
+    Int64(Vec<i64>),
+    /// Unsigned integers of any width
+    UInt64(Vec<u64>),
+    /// Single or double-precision floating point numbers
+    Float64(Vec<f64>),
+    /// Binary strings
+    Binary(Vec<Vec<u8>>),
+    /// Text, along with the values of every other type (such as dates, decimals, and JSON), in
+    /// their text representation
+    Utf8(Vec<String>),
+}
+
+impl ColumnData {
+    /// Create a new, empty [`ColumnData`] with the physical type used to store values of the given
+    /// SQL type
+    pub(crate) fn for_type(ty: &DfType) -> Self {
+        match ty {
+            DfType::Bool => ColumnData::Boolean(vec![]),
+            DfType::TinyInt | DfType::SmallInt | DfType::Int | DfType::BigInt => {
+                ColumnData::Int64(vec![])
+            }
+            DfType::UnsignedTinyInt
+            | DfType::UnsignedSmallInt
+            | DfType::UnsignedInt
+            | DfType::UnsignedBigInt => ColumnData::UInt64(vec![]),
+            DfType::Float | DfType::Double => ColumnData::Float64(vec![]),
+            DfType::Blob | DfType::Binary(_) | DfType::VarBinary(_) => ColumnData::Binary(vec![]),
+            _ => ColumnData::Utf8(vec![]),
+        }
+    }
+
+    /// Append a value to the end of this column, or the default value of its physical type if
+    /// `value` is `None`
+    fn push(&mut self, value: Option<&DfValue>) -> ReadySetResult<()> {
+        match self {
+            ColumnData::Boolean(values) => {
+                values.push(value.map(bool::try_from).transpose()?.unwrap_or_default())
+            }
+            ColumnData::Int64(values) => {
+                values.push(value.map(i64::try_from).transpose()?.unwrap_or_default())
+            }
+            ColumnData::UInt64(values) => {
+                values.push(value.map(u64::try_from).transpose()?.unwrap_or_default())
+            }
+            ColumnData::Float64(values) => {
+                values.push(value.map(f64::try_from).transpose()?.unwrap_or_default())
+            }
+            ColumnData::Binary(values) => values.push(
+                value
+                    .map(|v| v.as_bytes().map(<[u8]>::to_vec))
+                    .transpose()?
+                    .unwrap_or_default(),
+            ),
+            ColumnData::Utf8(values) => {
+                values.push(value.map(DfValue::to_string).unwrap_or_default())
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A single named column of a [`ColumnarBatch`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ColumnarColumn {
+    /// The name of the column
+    pub name: SqlIdentifier,
+    /// The SQL type of the column
+    pub column_type: DfType,
+    /// The values in the column
+    pub data: ColumnData,
+    /// For each value in the column, `false` if the value is `NULL`, and `true` otherwise
+    pub validity: Vec<bool>,
+}
+
+/// A batch of rows read from a view, laid out column by column
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ColumnarBatch {
+    /// The columns in the batch, in the order they appear in the schema of the view
+    pub columns: Vec<ColumnarColumn>,
+    /// The number of rows in the batch
+    pub num_rows: usize,
+}
+
+impl ColumnarBatch {
+    /// Convert `rows` into a single [`ColumnarBatch`] with the given schema.
+    ///
+    /// Rows may contain more values than there are columns in the schema (such as the bogokey
+    /// column of a reader), in which case the extra values are ignored. Returns an error if any
+    /// row has fewer values than there are columns in the schema, or if a value can't be
+    /// represented as the physical type of its column.
+    pub fn from_rows<R>(schema: &[ColumnSchema], rows: &[R]) -> ReadySetResult<Self>
+    where
+        R: AsRef<[DfValue]>,
+    {
+        let mut columns = schema
+            .iter()
+            .map(|col| ColumnarColumn {
+                name: col.column.name.clone(),
+                column_type: col.column_type.clone(),
+                data: ColumnData::for_type(&col.column_type),
+                validity: Vec::with_capacity(rows.len()),
+            })
+            .collect::<Vec<_>>();
+
+        for row in rows {
+            let row = row.as_ref();
+            if row.len() < columns.len() {
+                return Err(internal_err!(
+                    "Row has {} values, but the schema has {} columns",
+                    row.len(),
+                    columns.len()
+                ));
+            }
+            for (column, value) in columns.iter_mut().zip(row) {
+                let value = (!value.is_none()).then_some(value);
+                column.data.push(value)?;
+                column.validity.push(value.is_some());
+            }
+        }
+
+        Ok(Self {
+            columns,
+            num_rows: rows.len(),
+        })
+    }
+
+    /// Convert `rows` into a sequence of [`ColumnarBatch`]es with the given schema, each
+    /// containing at most `batch_size` rows
+    pub fn batches<R>(
+        schema: &[ColumnSchema],
+        rows: &[R],
+        batch_size: usize,
+    ) -> ReadySetResult<Vec<Self>>
+    where
+        R: AsRef<[DfValue]>,
+    {
+        rows.chunks(batch_size.max(1))
+            .map(|chunk| Self::from_rows(schema, chunk))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::Column;
+    use readyset_data::Collation;
+
+    use super::*;
+
+    fn schema() -> Vec<ColumnSchema> {
+        [
+            ("id", DfType::Int),
+            ("score", DfType::Double),
+            ("name", DfType::Text(Collation::default())),
+        ]
+        .into_iter()
+        .map(|(name, column_type)| ColumnSchema {
+            column: Column {
+                name: name.into(),
+                table: None,
+            },
+            column_type,
+            base: None,
+        })
+        .collect()
+    }
+
+    #[test]
+    fn rows_to_columns() {
+        let rows = vec![
+            vec![1.into(), DfValue::try_from(1.5).unwrap(), "a".into()],
+            vec![2.into(), DfValue::None, "b".into(), DfValue::from(0)],
+        ];
+        let batch = ColumnarBatch::from_rows(&schema(), &rows).unwrap();
+
+        assert_eq!(batch.num_rows, 2);
+        assert_eq!(batch.columns[0].data, ColumnData::Int64(vec![1, 2]));
+        assert_eq!(batch.columns[1].data, ColumnData::Float64(vec![1.5, 0.0]));
+        assert_eq!(batch.columns[1].validity, vec![true, false]);
+        assert_eq!(
+            batch.columns[2].data,
+            ColumnData::Utf8(vec!["a".into(), "b".into()])
+        );
+    }
+
+    #[test]
+    fn short_rows_are_an_error() {
+        let rows = vec![vec![DfValue::from(1)]];
+        ColumnarBatch::from_rows(&schema(), &rows).unwrap_err();
+    }
+
+    #[test]
+    fn splits_into_batches() {
+        let rows = (0..5)
+            .map(|i| vec![DfValue::from(i), DfValue::None, DfValue::None])
+            .collect::<Vec<_>>();
+        let batches = ColumnarBatch::batches(&schema(), &rows, 2).unwrap();
+        assert_eq!(
+            batches.iter().map(|b| b.num_rows).collect::<Vec<_>>(),
+            vec![2, 2, 1]
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! A fast path for reading from readers running on the same host as the client.
+//!
+//! In addition to their TCP listener, workers on unix platforms listen for connections to their
+//! readers on a unix domain socket, at a path derived from the externally visible address of
+//! their TCP listener (see [`reader_socket_path`]). Before opening a TCP connection to a reader, a
+//! client first tries to connect to the socket for that address - which only exists if the worker
+//! is running on the same host - and falls back to TCP if that fails. This avoids a trip through
+//! the network stack for the most common deployment topology, with the adapter co-located with a
+//! worker, without any configuration.
+//!
+//! The sockets live in a directory only accessible by the user running the worker (see
+//! [`reader_socket_dir`]), and both ends of a connection check that the process at the other end
+//! is running as the same user (see [`verify_local_peer`]), so other users on the host can
+//! neither read from the readers nor impersonate them. Clients running as a different user than
+//! the worker fall back to TCP.
+
+use std::io;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+#[cfg(unix)]
+use tokio::net::UnixStream;
+
+use crate::channel::{ChannelSecurity, ChannelStream};
+
+/// Returns the directory containing the unix domain sockets for readers on this host:
+/// `$XDG_RUNTIME_DIR/readyset` if `XDG_RUNTIME_DIR` is set, and a directory in the temp directory
+/// specific to the current user otherwise
+#[cfg(unix)]
+pub fn reader_socket_dir() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir).join("readyset"),
+        None => std::env::temp_dir().join(format!(
+            "readyset-{}",
+            readyset_util::process::current_uid()
+        )),
+    }
+}
+
+/// Returns the path of the unix domain socket that a worker whose readers are reachable over TCP
+/// at `addr` listens on for connections from clients on the same host
+#[cfg(unix)]
+pub fn reader_socket_path(addr: SocketAddr) -> PathBuf {
+    reader_socket_dir().join(format!("readers-{}-{}.sock", addr.ip(), addr.port()))
+}
+
+/// Create [`reader_socket_dir`] with mode 0700 if it doesn't already exist, and check that it's
+/// owned by the current user and not accessible by anyone else, so that it's safe to create reader
+/// sockets in. Returns the path of the directory.
+#[cfg(unix)]
+pub fn create_reader_socket_dir() -> io::Result<PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+
+    let dir = reader_socket_dir();
+    match std::fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e),
+    }
+
+    // Don't follow symlinks, which anyone could have planted at the path if it's in the temp
+    // directory
+    let metadata = std::fs::symlink_metadata(&dir)?;
+    if !metadata.is_dir() || metadata.uid() != readyset_util::process::current_uid() {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!(
+                "{} is not a directory owned by the current user",
+                dir.display()
+            ),
+        ));
+    }
+    if metadata.permissions().mode() & 0o077 != 0 {
+        std::fs::set_permissions(&dir, std::fs::Permissions::from_mode(0o700))?;
+    }
+
+    Ok(dir)
+}
+
+/// Check that the process at the other end of `stream` is running as the same user as the current
+/// process
+#[cfg(unix)]
+pub fn verify_local_peer(stream: &UnixStream) -> io::Result<()> {
+    let peer = stream.peer_cred()?;
+    if peer.uid() != readyset_util::process::current_uid() {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("peer is running as a different user (uid {})", peer.uid()),
+        ));
+    }
+    Ok(())
+}
+
+/// A connection to a reader, either over TCP or over the local unix domain socket for the reader
+#[derive(Debug)]
+pub(crate) enum ReaderStream {
+    Tcp(ChannelStream<TcpStream>),
+    #[cfg(unix)]
+    Local(ChannelStream<UnixStream>),
+}
+
+impl ReaderStream {
+    /// Connect to the readers at `addr`, over the local unix domain socket for `addr` if there is
+    /// one and over TCP otherwise, and secure the connection with `security`, if any. The local
+    /// socket is secured the same way as TCP connections, since any user on the host can connect
+    /// to it.
+    pub(crate) async fn connect(
+        addr: SocketAddr,
+        security: Option<&ChannelSecurity>,
+    ) -> io::Result<Self> {
+        #[cfg(unix)]
+        if let Ok(s) = UnixStream::connect(reader_socket_path(addr)).await {
+            if verify_local_peer(&s).is_ok() {
+                return Ok(Self::Local(secure(s, security).await?));
+            }
+        }
+
+        let s = TcpStream::connect(addr).await?;
+        s.set_nodelay(true)?;
+        Ok(Self::Tcp(secure(s, security).await?))
+    }
+
+    /// Returns true if this is a connection over a local unix domain socket
+    pub(crate) fn is_local(&self) -> bool {
+        !matches!(self, Self::Tcp(_))
+    }
+}
+
+/// Perform the client side of the handshakes for `security` on `stream`, if any
+async fn secure<S>(stream: S, security: Option<&ChannelSecurity>) -> io::Result<ChannelStream<S>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    match security {
+        Some(security) => security.connect(stream).await,
+        None => Ok(ChannelStream::Plain(stream)),
+    }
+}
+
+impl AsyncRead for ReaderStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(s) => Pin::new(s).poll_read(cx, buf),
+            #[cfg(unix)]
+            Self::Local(s) => Pin::new(s).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for ReaderStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Tcp(s) => Pin::new(s).poll_write(cx, buf),
+            #[cfg(unix)]
+            Self::Local(s) => Pin::new(s).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Self::Tcp(s) => Pin::new(s).poll_write_vectored(cx, bufs),
+            #[cfg(unix)]
+            Self::Local(s) => Pin::new(s).poll_write_vectored(cx, bufs),
+        }
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        match self {
+            Self::Tcp(s) => s.is_write_vectored(),
+            #[cfg(unix)]
+            Self::Local(s) => s.is_write_vectored(),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(s) => Pin::new(s).poll_flush(cx),
+            #[cfg(unix)]
+            Self::Local(s) => Pin::new(s).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Self::Tcp(s) => Pin::new(s).poll_shutdown(cx),
+            #[cfg(unix)]
+            Self::Local(s) => Pin::new(s).poll_shutdown(cx),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, UnixListener};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn prefers_local_socket() {
+        let tcp = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = tcp.local_addr().unwrap();
+
+        let conn = ReaderStream::connect(addr, None).await.unwrap();
+        assert!(!conn.is_local());
+
+        create_reader_socket_dir().unwrap();
+        let path = reader_socket_path(addr);
+        let unix = UnixListener::bind(&path).unwrap();
+        let mut conn = ReaderStream::connect(addr, None).await.unwrap();
+        assert!(conn.is_local());
+        conn.write_all(b"hi").await.unwrap();
+        let (mut server, _) = unix.accept().await.unwrap();
+        let mut buf = [0; 2];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hi");
+
+        // A stale socket left behind by a worker that's gone away falls back to TCP
+        drop(unix);
+        let conn = ReaderStream::connect(addr, None).await.unwrap();
+        assert!(!conn.is_local());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reader_socket_dir_is_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = create_reader_socket_dir().unwrap();
+        let mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+}
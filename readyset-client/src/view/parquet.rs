@@ -0,0 +1,539 @@
+//! Serialization of [`ColumnarBatch`]es into the Apache Parquet file format.
+//!
+//! [`ParquetWriter`] writes a file incrementally: every batch passed to
+//! [`ParquetWriter::write_batch`] becomes a single row group, and the bytes for each row group are
+//! returned as soon as it's written, so a file can be streamed to a client without ever holding
+//! the whole file in memory. The file footer, which describes the schema and the location of every
+//! row group, is returned by [`ParquetWriter::finish`].
+//!
+//! Only the subset of the format needed to write flat, uncompressed files is implemented: every
+//! column is `OPTIONAL`, and is written as a single `PLAIN`-encoded data page per row group.
+//! Metadata is serialized with the Thrift compact protocol, as required by the format
+//! specification.
+
+use readyset_errors::{internal, ReadySetResult};
+
+use super::columnar::{ColumnData, ColumnarBatch};
+use super::ColumnSchema;
+use crate::SqlIdentifier;
+
+/// The magic number at the start and end of every Parquet file
+const MAGIC: &[u8] = b"PAR1";
+
+// Parquet physical types
+const TYPE_BOOLEAN: i32 = 0;
+const TYPE_INT64: i32 = 2;
+const TYPE_DOUBLE: i32 = 5;
+const TYPE_BYTE_ARRAY: i32 = 6;
+
+// Parquet converted (logical) types
+const CONVERTED_UTF8: i32 = 0;
+const CONVERTED_UINT_64: i32 = 14;
+
+// Parquet encodings
+const ENCODING_PLAIN: i32 = 0;
+const ENCODING_RLE: i32 = 3;
+
+const REPETITION_OPTIONAL: i32 = 1;
+const PAGE_TYPE_DATA_PAGE: i32 = 0;
+const CODEC_UNCOMPRESSED: i32 = 0;
+
+/// A value which can be serialized with the Thrift compact protocol
+#[derive(Debug, Clone, PartialEq)]
+enum Thrift {
+    I32(i32),
+    I64(i64),
+    Binary(Vec<u8>),
+    /// A list of values, all of which have the given compact protocol type
+    List(u8, Vec<Thrift>),
+    /// A struct, as a list of field ids and values, in increasing order of field id
+    Struct(Vec<(i16, Thrift)>),
+}
+
+impl Thrift {
+    const TYPE_I32: u8 = 5;
+    const TYPE_I64: u8 = 6;
+    const TYPE_BINARY: u8 = 8;
+    const TYPE_LIST: u8 = 9;
+    const TYPE_STRUCT: u8 = 12;
+
+    fn binary<B: AsRef<[u8]>>(bytes: B) -> Self {
+        Thrift::Binary(bytes.as_ref().to_vec())
+    }
+
+    fn compact_type(&self) -> u8 {
+        match self {
+            Thrift::I32(_) => Self::TYPE_I32,
+            Thrift::I64(_) => Self::TYPE_I64,
+            Thrift::Binary(_) => Self::TYPE_BINARY,
+            Thrift::List(..) => Self::TYPE_LIST,
+            Thrift::Struct(_) => Self::TYPE_STRUCT,
+        }
+    }
+
+    fn write(&self, out: &mut Vec<u8>) {
+        match self {
+            Thrift::I32(v) => write_varint(out, zigzag(*v as i64)),
+            Thrift::I64(v) => write_varint(out, zigzag(*v)),
+            Thrift::Binary(bytes) => {
+                write_varint(out, bytes.len() as u64);
+                out.extend_from_slice(bytes);
+            }
+            Thrift::List(elem_type, elems) => {
+                if elems.len() < 15 {
+                    out.push((elems.len() as u8) << 4 | elem_type);
+                } else {
+                    out.push(0xf0 | elem_type);
+                    write_varint(out, elems.len() as u64);
+                }
+                for elem in elems {
+                    elem.write(out);
+                }
+            }
+            Thrift::Struct(fields) => {
+                let mut last_id = 0;
+                for (id, value) in fields {
+                    let delta = id - last_id;
+                    if (1..=15).contains(&delta) {
+                        out.push((delta as u8) << 4 | value.compact_type());
+                    } else {
+                        out.push(value.compact_type());
+                        write_varint(out, zigzag(*id as i64));
+                    }
+                    value.write(out);
+                    last_id = *id;
+                }
+                // Field stop
+                out.push(0);
+            }
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = vec![];
+        self.write(&mut out);
+        out
+    }
+}
+
+fn zigzag(v: i64) -> u64 {
+    ((v << 1) ^ (v >> 63)) as u64
+}
+
+fn write_varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+/// Pack `bits` into bytes, least significant bit first, as used by both the `PLAIN` encoding of
+/// booleans and bit-packed runs of the RLE/bit-packing hybrid encoding
+fn bit_pack<I>(bits: I) -> Vec<u8>
+where
+    I: IntoIterator<Item = bool>,
+{
+    let mut out = vec![];
+    for (i, bit) in bits.into_iter().enumerate() {
+        if i % 8 == 0 {
+            out.push(0);
+        }
+        if bit {
+            *out.last_mut().unwrap() |= 1 << (i % 8);
+        }
+    }
+    out
+}
+
+/// The physical and converted type used to store a column in a Parquet file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ParquetType {
+    physical: i32,
+    converted: Option<i32>,
+}
+
+impl ParquetType {
+    fn of(data: &ColumnData) -> Self {
+        let (physical, converted) = match data {
+            ColumnData::Boolean(_) => (TYPE_BOOLEAN, None),
+            ColumnData::Int64(_) => (TYPE_INT64, None),
+            ColumnData::UInt64(_) => (TYPE_INT64, Some(CONVERTED_UINT_64)),
+            ColumnData::Float64(_) => (TYPE_DOUBLE, None),
+            ColumnData::Binary(_) => (TYPE_BYTE_ARRAY, None),
+            ColumnData::Utf8(_) => (TYPE_BYTE_ARRAY, Some(CONVERTED_UTF8)),
+        };
+        Self {
+            physical,
+            converted,
+        }
+    }
+}
+
+/// Encode the non-null values of `data` with the `PLAIN` encoding
+fn plain_values(data: &ColumnData, validity: &[bool]) -> Vec<u8> {
+    fn valid<'a, T>(values: &'a [T], validity: &'a [bool]) -> impl Iterator<Item = &'a T> {
+        values
+            .iter()
+            .zip(validity)
+            .filter_map(|(value, valid)| valid.then_some(value))
+    }
+
+    let mut out = vec![];
+    match data {
+        ColumnData::Boolean(values) => out = bit_pack(valid(values, validity).copied()),
+        ColumnData::Int64(values) => {
+            valid(values, validity).for_each(|v| out.extend_from_slice(&v.to_le_bytes()))
+        }
+        ColumnData::UInt64(values) => {
+            valid(values, validity).for_each(|v| out.extend_from_slice(&v.to_le_bytes()))
+        }
+        ColumnData::Float64(values) => {
+            valid(values, validity).for_each(|v| out.extend_from_slice(&v.to_le_bytes()))
+        }
+        ColumnData::Binary(values) => valid(values, validity).for_each(|v| {
+            out.extend_from_slice(&(v.len() as u32).to_le_bytes());
+            out.extend_from_slice(v);
+        }),
+        ColumnData::Utf8(values) => valid(values, validity).for_each(|v| {
+            out.extend_from_slice(&(v.len() as u32).to_le_bytes());
+            out.extend_from_slice(v.as_bytes());
+        }),
+    }
+    out
+}
+
+/// Writes [`ColumnarBatch`]es as the row groups of a Parquet file. See the [module
+/// documentation](self) for more information.
+#[derive(Debug)]
+pub struct ParquetWriter {
+    /// The name and type of every column in the file
+    columns: Vec<(SqlIdentifier, ParquetType)>,
+    /// The number of bytes of the file returned so far
+    offset: usize,
+    /// The total number of rows written so far
+    num_rows: usize,
+    /// The metadata for every row group written so far
+    row_groups: Vec<Thrift>,
+}
+
+impl ParquetWriter {
+    /// Create a new [`ParquetWriter`] for a file containing rows with the given schema
+    pub fn new(schema: &[ColumnSchema]) -> Self {
+        Self {
+            columns: schema
+                .iter()
+                .map(|col| {
+                    (
+                        col.column.name.clone(),
+                        ParquetType::of(&ColumnData::for_type(&col.column_type)),
+                    )
+                })
+                .collect(),
+            offset: 0,
+            num_rows: 0,
+            row_groups: vec![],
+        }
+    }
+
+    /// Return the bytes to write before the next part of the file, which is the magic number if
+    /// nothing has been written yet
+    fn header(&mut self) -> Vec<u8> {
+        if self.offset == 0 {
+            MAGIC.to_vec()
+        } else {
+            vec![]
+        }
+    }
+
+    /// Write `batch` as a single row group, and return the bytes to append to the file.
+    ///
+    /// Returns an error if the columns of `batch` don't match the schema the writer was created
+    /// with.
+    pub fn write_batch(&mut self, batch: &ColumnarBatch) -> ReadySetResult<Vec<u8>> {
+        let mut out = self.header();
+        if batch.num_rows == 0 {
+            self.offset += out.len();
+            return Ok(out);
+        }
+        if batch.columns.len() != self.columns.len() {
+            internal!(
+                "Batch has {} columns, but the schema has {}",
+                batch.columns.len(),
+                self.columns.len()
+            );
+        }
+
+        let mut column_chunks = Vec::with_capacity(self.columns.len());
+        let mut total_byte_size = 0;
+        for (column, (name, ty)) in batch.columns.iter().zip(&self.columns) {
+            if ParquetType::of(&column.data) != *ty {
+                internal!("Column {} has a different type than in the schema", name);
+            }
+
+            // Every column is optional and not nested, so the maximum definition level is 1, and
+            // there are no repetition levels. Definition levels are written as a single
+            // bit-packed run of the RLE/bit-packing hybrid encoding, prefixed by its length.
+            let mut levels = vec![];
+            write_varint(&mut levels, ((batch.num_rows as u64 + 7) / 8) << 1 | 1);
+            levels.extend(bit_pack(column.validity.iter().copied()));
+
+            let mut page = Vec::with_capacity(4 + levels.len());
+            page.extend_from_slice(&(levels.len() as u32).to_le_bytes());
+            page.extend(levels);
+            page.extend(plain_values(&column.data, &column.validity));
+
+            let page_header = Thrift::Struct(vec![
+                (1, Thrift::I32(PAGE_TYPE_DATA_PAGE)),
+                (2, Thrift::I32(page.len() as i32)),
+                (3, Thrift::I32(page.len() as i32)),
+                (
+                    5,
+                    Thrift::Struct(vec![
+                        (1, Thrift::I32(batch.num_rows as i32)),
+                        (2, Thrift::I32(ENCODING_PLAIN)),
+                        (3, Thrift::I32(ENCODING_RLE)),
+                        (4, Thrift::I32(ENCODING_RLE)),
+                    ]),
+                ),
+            ])
+            .to_bytes();
+
+            let data_page_offset = self.offset + out.len();
+            let chunk_size = page_header.len() + page.len();
+            out.extend(page_header);
+            out.extend(page);
+            total_byte_size += chunk_size;
+
+            column_chunks.push(Thrift::Struct(vec![
+                (2, Thrift::I64((data_page_offset + chunk_size) as i64)),
+                (
+                    3,
+                    Thrift::Struct(vec![
+                        (1, Thrift::I32(ty.physical)),
+                        (
+                            2,
+                            Thrift::List(
+                                Thrift::TYPE_I32,
+                                vec![Thrift::I32(ENCODING_PLAIN), Thrift::I32(ENCODING_RLE)],
+                            ),
+                        ),
+                        (
+                            3,
+                            Thrift::List(Thrift::TYPE_BINARY, vec![Thrift::binary(name.as_str())]),
+                        ),
+                        (4, Thrift::I32(CODEC_UNCOMPRESSED)),
+                        (5, Thrift::I64(batch.num_rows as i64)),
+                        (6, Thrift::I64(chunk_size as i64)),
+                        (7, Thrift::I64(chunk_size as i64)),
+                        (9, Thrift::I64(data_page_offset as i64)),
+                    ]),
+                ),
+            ]));
+        }
+
+        self.row_groups.push(Thrift::Struct(vec![
+            (1, Thrift::List(Thrift::TYPE_STRUCT, column_chunks)),
+            (2, Thrift::I64(total_byte_size as i64)),
+            (3, Thrift::I64(batch.num_rows as i64)),
+        ]));
+        self.num_rows += batch.num_rows;
+        self.offset += out.len();
+        Ok(out)
+    }
+
+    /// Finish writing the file, and return the bytes of its footer
+    pub fn finish(mut self) -> Vec<u8> {
+        let mut out = self.header();
+
+        let schema = std::iter::once(Thrift::Struct(vec![
+            (4, Thrift::binary("schema")),
+            (5, Thrift::I32(self.columns.len() as i32)),
+        ]))
+        .chain(self.columns.iter().map(|(name, ty)| {
+            let mut fields = vec![
+                (1, Thrift::I32(ty.physical)),
+                (3, Thrift::I32(REPETITION_OPTIONAL)),
+                (4, Thrift::binary(name.as_str())),
+            ];
+            if let Some(converted) = ty.converted {
+                fields.push((6, Thrift::I32(converted)));
+            }
+            Thrift::Struct(fields)
+        }))
+        .collect();
+
+        let metadata = Thrift::Struct(vec![
+            (1, Thrift::I32(1)),
+            (2, Thrift::List(Thrift::TYPE_STRUCT, schema)),
+            (3, Thrift::I64(self.num_rows as i64)),
+            (4, Thrift::List(Thrift::TYPE_STRUCT, self.row_groups)),
+            (6, Thrift::binary("readyset")),
+        ])
+        .to_bytes();
+
+        out.extend_from_slice(&metadata);
+        out.extend_from_slice(&(metadata.len() as u32).to_le_bytes());
+        out.extend_from_slice(MAGIC);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::Column;
+    use readyset_data::{DfType, DfValue};
+
+    use super::*;
+
+    fn schema() -> Vec<ColumnSchema> {
+        [("id", DfType::BigInt), ("ok", DfType::Bool)]
+            .into_iter()
+            .map(|(name, column_type)| ColumnSchema {
+                column: Column {
+                    name: name.into(),
+                    table: None,
+                },
+                column_type,
+                base: None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn thrift_compact_encoding() {
+        let value = Thrift::Struct(vec![
+            (1, Thrift::I32(-1)),
+            (2, Thrift::List(Thrift::TYPE_I32, vec![Thrift::I32(1)])),
+            (20, Thrift::I64(300)),
+            (21, Thrift::binary("a")),
+        ]);
+        assert_eq!(
+            value.to_bytes(),
+            vec![
+                0x15, 0x01, // field 1, i32, zigzag(-1)
+                0x19, 0x15, 0x02, // field 2, list of 1 i32, zigzag(1)
+                0x06, 0x28, 0xd8, 0x04, // field 20 (long form), i64, zigzag(300)
+                0x18, 0x01, b'a', // field 21, binary of length 1
+                0x00, // stop
+            ]
+        );
+    }
+
+    #[test]
+    fn definition_levels_and_values() {
+        let rows = vec![
+            vec![DfValue::from(1), DfValue::from(true)],
+            vec![DfValue::None, DfValue::from(false)],
+            vec![DfValue::from(3), DfValue::None],
+        ];
+        let batch = ColumnarBatch::from_rows(&schema(), &rows).unwrap();
+        assert_eq!(
+            plain_values(&batch.columns[0].data, &batch.columns[0].validity),
+            [1i64.to_le_bytes(), 3i64.to_le_bytes()].concat()
+        );
+        assert_eq!(
+            plain_values(&batch.columns[1].data, &batch.columns[1].validity),
+            vec![0b01]
+        );
+        assert_eq!(
+            bit_pack(batch.columns[0].validity.iter().copied()),
+            vec![0b101]
+        );
+    }
+
+    #[test]
+    fn file_layout() {
+        let rows = vec![vec![DfValue::from(1), DfValue::from(true)]];
+        let batch = ColumnarBatch::from_rows(&schema(), &rows).unwrap();
+
+        let mut writer = ParquetWriter::new(&schema());
+        let mut file = writer.write_batch(&batch).unwrap();
+        let second_group = writer.write_batch(&batch).unwrap();
+        // The magic number is only written before the first row group
+        assert_eq!(second_group.len(), file.len() - MAGIC.len());
+        file.extend(second_group);
+        file.extend(writer.finish());
+
+        assert!(file.starts_with(MAGIC));
+        assert!(file.ends_with(MAGIC));
+        let footer_len =
+            u32::from_le_bytes(file[file.len() - 8..file.len() - 4].try_into().unwrap()) as usize;
+        let footer = &file[file.len() - 8 - footer_len..file.len() - 8];
+        // version 1, followed by the schema list
+        assert_eq!(&footer[..3], &[0x15, 0x02, 0x19]);
+    }
+
+    #[test]
+    fn read_back() {
+        use std::io::{Seek, SeekFrom, Write};
+
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+        use parquet::record::Field;
+
+        let first = vec![
+            vec![DfValue::from(1), DfValue::from(true)],
+            vec![DfValue::None, DfValue::from(false)],
+        ];
+        let second = vec![vec![DfValue::from(3), DfValue::None]];
+
+        let mut writer = ParquetWriter::new(&schema());
+        let mut file = tempfile::tempfile().unwrap();
+        for rows in [&first, &second] {
+            let batch = ColumnarBatch::from_rows(&schema(), rows).unwrap();
+            file.write_all(&writer.write_batch(&batch).unwrap())
+                .unwrap();
+        }
+        file.write_all(&writer.finish()).unwrap();
+        file.seek(SeekFrom::Start(0)).unwrap();
+
+        let reader = SerializedFileReader::new(file).unwrap();
+        let metadata = reader.metadata();
+        assert_eq!(metadata.num_row_groups(), 2);
+        assert_eq!(metadata.file_metadata().num_rows(), 3);
+        assert_eq!(metadata.row_group(0).num_rows(), 2);
+        assert_eq!(metadata.row_group(1).num_rows(), 1);
+
+        let rows = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| {
+                row.get_column_iter()
+                    .map(|(name, field)| (name.clone(), field.clone()))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    ("id".to_owned(), Field::Long(1)),
+                    ("ok".to_owned(), Field::Bool(true))
+                ],
+                vec![
+                    ("id".to_owned(), Field::Null),
+                    ("ok".to_owned(), Field::Bool(false))
+                ],
+                vec![
+                    ("id".to_owned(), Field::Long(3)),
+                    ("ok".to_owned(), Field::Null)
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_file() {
+        let file = ParquetWriter::new(&schema()).finish();
+        assert!(file.starts_with(MAGIC));
+        assert!(file.ends_with(MAGIC));
+    }
+
+    #[test]
+    fn mismatched_batch() {
+        let rows = vec![vec![DfValue::from(1), DfValue::from(true)]];
+        let batch = ColumnarBatch::from_rows(&schema(), &rows).unwrap();
+        let mut writer = ParquetWriter::new(&schema()[..1]);
+        writer.write_batch(&batch).unwrap_err();
+    }
+}
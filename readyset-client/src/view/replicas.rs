@@ -0,0 +1,243 @@
+//! Choosing which replica of a reader to read from.
+//!
+//! When a reader is replicated onto workers in more than one zone, reads are sent to a replica
+//! running in the same zone as the client (the zone each worker is running in is part of the
+//! [`WorkerResources`] it advertises) if there is one, and only go to replicas in other zones
+//! otherwise. Among replicas that are equally close, the one with the lowest observed read latency
+//! is preferred, with ties broken at random so that clients which haven't read from any replica
+//! yet spread their reads out.
+//!
+//! If a read from a replica fails with a networking error, the [`ReaderHandle`] records the failure
+//! and fails over to the next replica in order of preference. Replicas which failed recently are
+//! only chosen if there's no other replica to read from.
+//!
+//! [`WorkerResources`]: crate::placement::WorkerResources
+//! [`ReaderHandle`]: crate::ReaderHandle
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use array2::Array2;
+use parking_lot::RwLock;
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+
+/// How long a reader address which failed a read is avoided for
+const FAILURE_BACKOFF: Duration = Duration::from_secs(30);
+
+/// The weight given to each new sample in the moving average of the read latency of an address
+const LATENCY_SMOOTHING: f64 = 0.2;
+
+/// Statistics about the reads sent to a single reader address.
+///
+/// These are updated after every read, so they're kept in atomics rather than behind a lock.
+#[derive(Debug)]
+pub(crate) struct AddrStats {
+    /// Exponentially weighted moving average of the latency of successful reads, in nanoseconds,
+    /// or 0 if no reads have succeeded yet
+    latency_nanos: AtomicU64,
+    /// One more than the number of nanoseconds after `epoch` at which the last read failed with a
+    /// networking error, or 0 if no read has failed since the last successful one
+    last_failure: AtomicU64,
+    epoch: Instant,
+}
+
+impl Default for AddrStats {
+    fn default() -> Self {
+        Self {
+            latency_nanos: AtomicU64::new(0),
+            last_failure: AtomicU64::new(0),
+            epoch: Instant::now(),
+        }
+    }
+}
+
+impl AddrStats {
+    /// Record that a read succeeded, and took `latency`
+    pub(crate) fn record_latency(&self, latency: Duration) {
+        let sample = latency.as_nanos().min(u64::MAX as u128) as u64;
+        // The closure always returns `Some`, so this can't fail
+        let _ = self
+            .latency_nanos
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |avg| {
+                Some(if avg == 0 {
+                    sample.max(1)
+                } else {
+                    ((avg as f64) * (1.0 - LATENCY_SMOOTHING) + (sample as f64) * LATENCY_SMOOTHING)
+                        as u64
+                })
+            });
+        self.last_failure.store(0, Ordering::Relaxed);
+    }
+
+    /// Record that a read failed with a networking error
+    pub(crate) fn record_failure(&self) {
+        let since_epoch = self.epoch.elapsed().as_nanos().min(u64::MAX as u128 - 1) as u64;
+        self.last_failure.store(since_epoch + 1, Ordering::Relaxed);
+    }
+
+    fn latency(&self) -> Option<Duration> {
+        match self.latency_nanos.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(Duration::from_nanos(nanos)),
+        }
+    }
+
+    fn last_failure(&self) -> Option<Instant> {
+        match self.last_failure.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(self.epoch + Duration::from_nanos(nanos - 1)),
+        }
+    }
+}
+
+/// The zone a client is running in, and how reads from each reader address have performed so far.
+///
+/// Cloning a [`ReadLocality`] shares the latency statistics between the clones, so that all the
+/// views built from the same [`ReadySetHandle`](crate::ReadySetHandle) learn from each other's
+/// reads.
+#[derive(Debug, Default, Clone)]
+pub struct ReadLocality {
+    zone: Option<String>,
+    stats: Arc<RwLock<HashMap<SocketAddr, Arc<AddrStats>>>>,
+}
+
+impl ReadLocality {
+    /// Create a new [`ReadLocality`] for a client running in the given zone, if known
+    pub fn new(zone: Option<String>) -> Self {
+        Self {
+            zone,
+            stats: Default::default(),
+        }
+    }
+
+    /// Returns the zone the client is running in, if known
+    pub fn zone(&self) -> Option<&str> {
+        self.zone.as_deref()
+    }
+
+    /// Returns the statistics for reads from the reader at `addr`, which can be held on to and
+    /// updated without going through the [`ReadLocality`] again
+    pub(crate) fn addr_stats(&self, addr: SocketAddr) -> Arc<AddrStats> {
+        if let Some(stats) = self.stats.read().get(&addr) {
+            return Arc::clone(stats);
+        }
+        Arc::clone(self.stats.write().entry(addr).or_default())
+    }
+
+    /// Record that a read from the reader at `addr` succeeded, and took `latency`
+    pub fn record_latency(&self, addr: SocketAddr, latency: Duration) {
+        self.addr_stats(addr).record_latency(latency)
+    }
+
+    /// Record that a read from the reader at `addr` failed with a networking error
+    pub fn record_failure(&self, addr: SocketAddr) {
+        self.addr_stats(addr).record_failure()
+    }
+
+    /// Returns the moving average of the latency of successful reads from the reader at `addr`, if
+    /// any reads from it have succeeded
+    pub fn latency(&self, addr: SocketAddr) -> Option<Duration> {
+        self.stats
+            .read()
+            .get(&addr)
+            .and_then(|stats| stats.latency())
+    }
+
+    /// Returns the indices of the replicas of a reader, given the addresses of each of their shards
+    /// and the zone each is running in, in the order they should be read from.
+    pub(crate) fn rank_replicas(
+        &self,
+        replica_shard_addrs: &Array2<SocketAddr>,
+        replica_zones: &[Option<String>],
+    ) -> Vec<usize> {
+        let now = Instant::now();
+        let stats = self.stats.read();
+        // A replica is only as fast as its slowest shard, and failed if any of its shards did
+        let replica_stats = |replica: usize| {
+            replica_shard_addrs[replica].iter().fold(
+                (None, None),
+                |(latency, last_failure): (Option<Duration>, Option<Instant>), addr| match stats
+                    .get(addr)
+                {
+                    Some(stats) => (
+                        latency.max(stats.latency()),
+                        last_failure.max(stats.last_failure()),
+                    ),
+                    None => (latency, last_failure),
+                },
+            )
+        };
+
+        let mut replicas = (0..replica_shard_addrs.num_rows()).collect::<Vec<_>>();
+        replicas.shuffle(&mut thread_rng());
+        replicas.sort_by_cached_key(|&replica| {
+            let (latency, last_failure) = replica_stats(replica);
+            let recently_failed = last_failure.map_or(false, |failure| {
+                now.duration_since(failure) < FAILURE_BACKOFF
+            });
+            let local = self.zone.is_some()
+                && replica_zones.get(replica).map(Option::as_deref) == Some(self.zone());
+            // Replicas we haven't read from yet sort first among equally close replicas, so that
+            // we find out how fast they are
+            (recently_failed, !local, latency.unwrap_or_default())
+        });
+        replicas
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addrs(n: usize) -> Array2<SocketAddr> {
+        Array2::from_rows(
+            (0..n)
+                .map(|i| vec![SocketAddr::from(([10, 0, 0, i as u8], 4000))])
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn prefers_local_zone() {
+        let locality = ReadLocality::new(Some("us-east-1b".into()));
+        let zones = vec![
+            Some("us-east-1a".to_owned()),
+            Some("us-east-1b".to_owned()),
+            None,
+        ];
+        for _ in 0..10 {
+            assert_eq!(locality.rank_replicas(&addrs(3), &zones)[0], 1);
+        }
+    }
+
+    #[test]
+    fn prefers_lower_latency() {
+        let locality = ReadLocality::new(None);
+        let addrs = addrs(3);
+        locality.record_latency(addrs[0][0], Duration::from_millis(10));
+        locality.record_latency(addrs[1][0], Duration::from_millis(1));
+        locality.record_latency(addrs[2][0], Duration::from_millis(5));
+        assert_eq!(locality.rank_replicas(&addrs, &[]), vec![1, 2, 0]);
+
+        locality.record_latency(addrs[1][0], Duration::from_millis(100));
+        let latency = locality.latency(addrs[1][0]).unwrap();
+        assert!(latency > Duration::from_millis(20) && latency < Duration::from_millis(21));
+        assert_eq!(locality.rank_replicas(&addrs, &[]), vec![2, 0, 1]);
+    }
+
+    #[test]
+    fn avoids_failed_replicas() {
+        let locality = ReadLocality::new(Some("us-east-1a".into()));
+        let addrs = addrs(2);
+        let zones = vec![Some("us-east-1a".to_owned()), Some("us-east-1b".to_owned())];
+        locality.record_failure(addrs[0][0]);
+        assert_eq!(locality.rank_replicas(&addrs, &zones), vec![1, 0]);
+
+        locality.record_latency(addrs[0][0], Duration::from_millis(1));
+        assert_eq!(locality.rank_replicas(&addrs, &zones), vec![0, 1]);
+    }
+}
@@ -4,6 +4,7 @@ use std::sync::Arc;
 use dataflow_expression::{Expr, PostLookup, PostLookupAggregates};
 use nom_sql::OrderType;
 use readyset_data::DfValue;
+use readyset_errors::{ReadySetError, ReadySetResult};
 use readyset_util::nonmaxusize::NonMaxUsize;
 use smallvec::SmallVec;
 use streaming_iterator::StreamingIterator;
@@ -23,6 +24,10 @@ pub type SharedRows = triomphe::Arc<SmallVec<[Row; 1]>>;
 /// A list of [`SharedRows`], combining the lookup results for multiple keys
 pub type SharedResults = SmallVec<[SharedRows; 1]>;
 
+/// The receiving end of a channel of chunks of rows, for a result set which is produced while it's
+/// being returned (see [`ResultIterator::streamed`])
+pub type ResultChunks = tokio::sync::mpsc::Receiver<ReadySetResult<Vec<Vec<DfValue>>>>;
+
 /// A set of uniquely owned results
 #[derive(Debug)]
 pub struct Results {
@@ -78,6 +83,8 @@ pub struct ResultIterator {
 enum ResultIteratorInner {
     /// Owned results returned from noria server
     OwnedResults(OwnedResultIterator),
+    /// Results shared with another owner, such as a cache of results in the adapter
+    SharedOwnedResults(SharedOwnedResultIterator),
     /// Cached results returned from a ['CachingView`] for more than one key
     MultiKey(MultiKeyIterator),
     /// Cached results returned from a ['CachingView`] for more than one key, merging the set with
@@ -85,6 +92,8 @@ enum ResultIteratorInner {
     MultiKeyMerge(MergeIterator),
     /// Cached results returned for more than one key, with an aggregate function
     MultiKeyAggregateMerge(AggregateIterator),
+    /// Results received in chunks as they're produced
+    Streamed(StreamedResultIterator),
 }
 
 /// Iterator over owned results returned from noria server
@@ -97,6 +106,30 @@ struct OwnedResultIterator {
     row: Option<NonMaxUsize>,
 }
 
+/// Iterator over results shared with another owner
+#[derive(Debug)]
+struct SharedOwnedResultIterator {
+    // The underlying data we iterate over
+    data: Arc<Vec<Vec<DfValue>>>,
+    // The next row to return
+    row: Option<NonMaxUsize>,
+}
+
+/// Iterator over results received in chunks from a channel as they're produced, so that only one
+/// chunk is held in memory at a time
+#[derive(Debug)]
+struct StreamedResultIterator {
+    chunks: ResultChunks,
+    // The chunk currently being iterated over
+    chunk: Vec<Vec<DfValue>>,
+    // Current position in the chunk
+    row: Option<usize>,
+    // Set once the channel has been closed, or has returned an error
+    done: bool,
+    // The error which ended the results early, if any
+    error: Option<ReadySetError>,
+}
+
 /// An iterator over a single set of cached results
 #[derive(Debug)]
 struct SingleKeyIterator {
@@ -308,6 +341,53 @@ impl ResultIterator {
         }
     }
 
+    /// Create from rows that are shared with another owner, without copying them
+    pub fn shared(data: Arc<Vec<Vec<DfValue>>>) -> Self {
+        ResultIterator {
+            inner: ResultIteratorInner::SharedOwnedResults(SharedOwnedResultIterator {
+                data,
+                row: None,
+            }),
+            limit: None,
+            offset: None,
+            default_row: None,
+            non_empty: false,
+            filter: None,
+            cols: usize::MAX,
+        }
+    }
+
+    /// Create from chunks of rows received from a channel as they're produced. Rows are only
+    /// received as the iterator is advanced, which blocks the current thread (using
+    /// [`tokio::task::block_in_place`]) until the next chunk is available. If an error is received,
+    /// the iterator ends early, and the error can be retrieved with [`Self::take_error`].
+    pub fn streamed(chunks: ResultChunks) -> Self {
+        ResultIterator {
+            inner: ResultIteratorInner::Streamed(StreamedResultIterator {
+                chunks,
+                chunk: vec![],
+                row: None,
+                done: false,
+                error: None,
+            }),
+            limit: None,
+            offset: None,
+            default_row: None,
+            non_empty: false,
+            filter: None,
+            cols: usize::MAX,
+        }
+    }
+
+    /// If these results were [streamed](Self::streamed), and ended early because of an error,
+    /// returns that error
+    pub fn take_error(&mut self) -> Option<ReadySetError> {
+        match &mut self.inner {
+            ResultIteratorInner::Streamed(i) => i.error.take(),
+            _ => None,
+        }
+    }
+
     /// Get aggregated stats for all results in the set
     pub fn total_stats(&self) -> Option<ReadReplyStats> {
         match &self.inner {
@@ -315,9 +395,9 @@ impl ResultIterator {
                 .iter()
                 .map(|r| &r.stats)
                 .fold(None, |total, cur| match cur {
-                    Some(stats) => Some(ReadReplyStats {
-                        cache_misses: stats.cache_misses
-                            + total.map(|s| s.cache_misses).unwrap_or(0),
+                    Some(stats) => Some(match total {
+                        Some(total) => total.merge(stats),
+                        None => stats.clone(),
                     }),
                     None => total,
                 }),
@@ -371,6 +451,53 @@ impl StreamingIterator for OwnedResultIterator {
     }
 }
 
+impl StreamingIterator for SharedOwnedResultIterator {
+    type Item = [DfValue];
+
+    #[inline(always)]
+    fn advance(&mut self) {
+        match self.row.as_mut() {
+            None => self.row = Some(NonMaxUsize::zero()),
+            Some(row) => row.inc(),
+        }
+    }
+
+    #[inline(always)]
+    fn get(&self) -> Option<&Self::Item> {
+        self.row
+            .and_then(|row| self.data.get(*row))
+            .map(|v| v.as_slice())
+    }
+}
+
+impl StreamingIterator for StreamedResultIterator {
+    type Item = [DfValue];
+
+    fn advance(&mut self) {
+        let mut row = self.row.map_or(0, |row| row + 1);
+        while row >= self.chunk.len() && !self.done {
+            match tokio::task::block_in_place(|| self.chunks.blocking_recv()) {
+                Some(Ok(chunk)) => {
+                    self.chunk = chunk;
+                    row = 0;
+                }
+                Some(Err(error)) => {
+                    self.error = Some(error);
+                    self.done = true;
+                }
+                None => self.done = true,
+            }
+        }
+        self.row = Some(row);
+    }
+
+    fn get(&self) -> Option<&Self::Item> {
+        self.row
+            .and_then(|row| self.chunk.get(row))
+            .map(|v| v.as_slice())
+    }
+}
+
 impl SingleKeyIterator {
     pub(crate) fn new(data: SharedRows) -> Self {
         SingleKeyIterator { data, row: None }
@@ -536,9 +663,11 @@ impl StreamingIterator for ResultIteratorInner {
     fn advance(&mut self) {
         match self {
             ResultIteratorInner::OwnedResults(i) => i.advance(),
+            ResultIteratorInner::SharedOwnedResults(i) => i.advance(),
             ResultIteratorInner::MultiKey(i) => i.advance(),
             ResultIteratorInner::MultiKeyMerge(i) => i.advance(),
             ResultIteratorInner::MultiKeyAggregateMerge(i) => i.advance(),
+            ResultIteratorInner::Streamed(i) => i.advance(),
         }
     }
 
@@ -546,9 +675,11 @@ impl StreamingIterator for ResultIteratorInner {
     fn get(&self) -> Option<&Self::Item> {
         match &self {
             ResultIteratorInner::OwnedResults(i) => i.get(),
+            ResultIteratorInner::SharedOwnedResults(i) => i.get(),
             ResultIteratorInner::MultiKey(i) => i.get(),
             ResultIteratorInner::MultiKeyMerge(i) => i.get(),
             ResultIteratorInner::MultiKeyAggregateMerge(i) => i.get(),
+            ResultIteratorInner::Streamed(i) => i.get(),
         }
     }
 }
@@ -0,0 +1,358 @@
+//! Deserializing the rows returned by a lookup into a view into user-defined types, using
+//! [`serde`].
+//!
+//! Each row is deserialized as a map from the names of the view's columns to the values in the row,
+//! so the fields of a struct which derives [`Deserialize`](serde::Deserialize) are matched to
+//! columns by name (use `#[serde(rename = "...")]` for columns whose names aren't valid field
+//! names). Rows can also be deserialized positionally, into tuples or sequences.
+//!
+//! Values are converted according to the type they're deserialized into: integers can be
+//! deserialized into any integer type they fit in, or into `bool`; text into strings; binary data
+//! into byte buffers; `NULL` into `None`; and all other values (such as timestamps and decimals)
+//! into strings, using the same text representation as SQL clients get.
+//!
+//! Columns which don't correspond to any field of the type being deserialized into are ignored,
+//! unless the rows are deserialized strictly (see [`ReaderHandle::lookup_typed_strict`]), in which
+//! case they're an error. Strictness is chosen at compile time, by which method is called.
+//!
+//! [`ReaderHandle::lookup_typed_strict`]: crate::ReaderHandle::lookup_typed_strict
+
+use std::{fmt, slice};
+
+use nom_sql::SqlIdentifier;
+use readyset_data::DfValue;
+use readyset_errors::{ReadySetError, ReadySetResult};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+
+/// An error deserializing a row
+#[derive(Debug)]
+struct Error(String);
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error(msg.to_string())
+    }
+}
+
+impl From<Error> for ReadySetError {
+    fn from(e: Error) -> Self {
+        ReadySetError::RowDeserializationFailed(e.0)
+    }
+}
+
+/// Deserialize each of `rows`, whose columns are named by `columns`, into a `T`. If `STRICT` is
+/// true, it's an error for any of the columns not to be deserialized into a field of `T`. Any
+/// values in the rows past the end of `columns` are ignored.
+pub(crate) fn deserialize_rows<T, const STRICT: bool>(
+    columns: &[SqlIdentifier],
+    rows: impl IntoIterator<Item = Vec<DfValue>>,
+) -> ReadySetResult<Vec<T>>
+where
+    T: DeserializeOwned,
+{
+    rows.into_iter()
+        .map(|row| {
+            Ok(T::deserialize(RowDeserializer::<STRICT> {
+                columns,
+                row: &row,
+            })?)
+        })
+        .collect()
+}
+
+/// Deserializes a single row, as a map from column names to values or as a sequence of values
+struct RowDeserializer<'a, const STRICT: bool> {
+    columns: &'a [SqlIdentifier],
+    row: &'a [DfValue],
+}
+
+impl<'de, 'a, const STRICT: bool> de::Deserializer<'de> for RowDeserializer<'a, STRICT> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_map(RowMap::<STRICT> {
+            columns: self.columns.iter().zip(self.row.iter()),
+            value: None,
+        })
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let mut seq = RowSeq::<STRICT> {
+            columns: self.columns.iter().zip(self.row.iter()),
+        };
+        let res = visitor.visit_seq(&mut seq)?;
+        if let Some((column, _)) = seq.columns.next().filter(|_| STRICT) {
+            return Err(Error(format!(
+                "column `{column}` doesn't match any element"
+            )));
+        }
+        Ok(res)
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct map struct enum identifier ignored_any
+    }
+}
+
+type Columns<'a> = std::iter::Zip<slice::Iter<'a, SqlIdentifier>, slice::Iter<'a, DfValue>>;
+
+struct RowMap<'a, const STRICT: bool> {
+    columns: Columns<'a>,
+    /// The column whose name was most recently returned as a key, and its value
+    value: Option<(&'a SqlIdentifier, &'a DfValue)>,
+}
+
+impl<'de, 'a, const STRICT: bool> de::MapAccess<'de> for RowMap<'a, STRICT> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.columns.next() {
+            Some((column, value)) => {
+                self.value = Some((column, value));
+                seed.deserialize(column.as_str().into_deserializer())
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let (column, value) = self
+            .value
+            .take()
+            .ok_or_else(|| Error("value requested before key".into()))?;
+        seed.deserialize(ValueDeserializer::<STRICT> { column, value })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.columns.len())
+    }
+}
+
+struct RowSeq<'a, const STRICT: bool> {
+    columns: Columns<'a>,
+}
+
+impl<'de, 'a, const STRICT: bool> de::SeqAccess<'de> for RowSeq<'a, STRICT> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        self.columns
+            .next()
+            .map(|(column, value)| seed.deserialize(ValueDeserializer::<STRICT> { column, value }))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.columns.len())
+    }
+}
+
+/// Deserializes the value of a single column in a row
+struct ValueDeserializer<'a, const STRICT: bool> {
+    column: &'a SqlIdentifier,
+    value: &'a DfValue,
+}
+
+impl<'a, const STRICT: bool> ValueDeserializer<'a, STRICT> {
+    fn error(&self, msg: impl fmt::Display) -> Error {
+        Error(format!("column `{}`: {msg}", self.column))
+    }
+}
+
+impl<'de, 'a, const STRICT: bool> de::Deserializer<'de> for ValueDeserializer<'a, STRICT> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let res = match self.value {
+            DfValue::None => visitor.visit_unit(),
+            DfValue::Int(i) => visitor.visit_i64(*i),
+            DfValue::UnsignedInt(i) => visitor.visit_u64(*i),
+            DfValue::Float(f) => visitor.visit_f32(*f),
+            DfValue::Double(f) => visitor.visit_f64(*f),
+            DfValue::Text(_) | DfValue::TinyText(_) => {
+                visitor.visit_str(<&str>::try_from(self.value).map_err(|e| self.error(e))?)
+            }
+            DfValue::ByteArray(bytes) => visitor.visit_bytes(bytes),
+            DfValue::Max => return Err(self.error("unexpected MAX value")),
+            value => visitor.visit_string(value.to_string()),
+        };
+        res.map_err(|e| self.error(e))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            DfValue::None => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.value {
+            DfValue::Int(i) => visitor.visit_bool(*i != 0),
+            DfValue::UnsignedInt(i) => visitor.visit_bool(*i != 0),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if STRICT {
+            Err(Error(format!(
+                "column `{}` doesn't match any field",
+                self.column
+            )))
+        } else {
+            visitor.visit_unit()
+        }
+    }
+
+    forward_to_deserialize_any! {
+        i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct seq tuple tuple_struct map struct enum identifier
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq, Deserialize)]
+    struct Article {
+        id: u32,
+        #[serde(rename = "article title")]
+        title: String,
+        published: bool,
+        score: Option<i8>,
+    }
+
+    fn columns() -> Vec<SqlIdentifier> {
+        vec![
+            "id".into(),
+            "article title".into(),
+            "published".into(),
+            "score".into(),
+            "author".into(),
+        ]
+    }
+
+    fn row(id: i32, score: Option<i32>) -> Vec<DfValue> {
+        vec![
+            id.into(),
+            "Hello".into(),
+            1.into(),
+            score.map_or(DfValue::None, DfValue::from),
+            "someone".into(),
+        ]
+    }
+
+    #[test]
+    fn matches_fields_by_column_name() {
+        let articles: Vec<Article> =
+            deserialize_rows::<_, false>(&columns(), vec![row(1, Some(-5)), row(2, None)]).unwrap();
+        assert_eq!(
+            articles,
+            vec![
+                Article {
+                    id: 1,
+                    title: "Hello".into(),
+                    published: true,
+                    score: Some(-5),
+                },
+                Article {
+                    id: 2,
+                    title: "Hello".into(),
+                    published: true,
+                    score: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn strict_rejects_unmatched_columns() {
+        let err = deserialize_rows::<Article, true>(&columns(), vec![row(1, None)]).unwrap_err();
+        assert!(
+            matches!(&err, ReadySetError::RowDeserializationFailed(msg) if msg.contains("author")),
+            "{err}"
+        );
+
+        let columns = &columns()[..4];
+        let mut row = row(1, None);
+        row.pop();
+        deserialize_rows::<Article, true>(columns, vec![row]).unwrap();
+    }
+
+    #[test]
+    fn values_past_columns_are_ignored() {
+        // Hidden key columns come after the columns which are returned
+        let articles: Vec<Article> =
+            deserialize_rows::<_, true>(&columns()[..4], vec![row(1, Some(2))]).unwrap();
+        assert_eq!(articles.len(), 1);
+
+        let rows: Vec<(u32, String, bool, Option<i8>)> =
+            deserialize_rows::<_, true>(&columns()[..4], vec![row(1, None)]).unwrap();
+        assert_eq!(rows, vec![(1, "Hello".to_owned(), true, None)]);
+    }
+
+    #[test]
+    fn conversion_errors_name_the_column() {
+        let err =
+            deserialize_rows::<Article, false>(&columns(), vec![row(1, Some(1000))]).unwrap_err();
+        assert!(
+            matches!(&err, ReadySetError::RowDeserializationFailed(msg) if msg.contains("`score`")),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn tuples() {
+        let rows: Vec<(u32, String)> =
+            deserialize_rows::<_, false>(&columns(), vec![row(1, None)]).unwrap();
+        assert_eq!(rows, vec![(1, "Hello".to_owned())]);
+
+        deserialize_rows::<(u32, String), true>(&columns(), vec![row(1, None)]).unwrap_err();
+    }
+}
@@ -315,6 +315,8 @@ pub struct ServerParams {
     /// Only allow domains containing readers to run on this server. Corresponds to the
     /// `--reader-only` flag to the readyset server binary
     reader_only: bool,
+    /// The zone the server is running in, passed in via `--worker-zone`.
+    zone: Option<String>,
 }
 
 impl ServerParams {
@@ -335,6 +337,12 @@ impl ServerParams {
         self.reader_only = true;
         self
     }
+
+    /// Sets the zone the server advertises that it's running in, passed in via --worker-zone.
+    pub fn with_zone(mut self, zone: &str) -> Self {
+        self.zone = Some(zone.to_string());
+        self
+    }
 }
 
 #[must_use]
@@ -757,7 +765,7 @@ impl DeploymentBuilder {
         let authority = self
             .authority
             .to_authority(&self.authority_address, &self.name)
-            .await;
+            .await?;
         let handle = ReadySetHandle::new(authority).await;
 
         let metrics = MetricsClient::new(handle.clone()).unwrap();
@@ -1438,6 +1446,9 @@ async fn start_server(
     if server_params.no_readers {
         builder = builder.no_readers();
     }
+    if let Some(zone) = server_params.zone.as_ref() {
+        builder = builder.worker_zone(zone);
+    }
     if let Some(upstream_addr) = upstream_addr {
         builder = builder.upstream_addr(upstream_addr);
     }
@@ -1503,6 +1514,9 @@ async fn start_adapter(
         if server_params.no_readers {
             builder = builder.no_readers();
         }
+        if let Some(zone) = server_params.zone.as_ref() {
+            builder = builder.worker_zone(zone);
+        }
         if let Some(t) = server_start_params.replicator_restart_timeout_secs {
             builder = builder.replicator_restart_timeout(t);
         }
@@ -1,4 +1,5 @@
 use ::readyset_client::metrics::{recorded, DumpedMetricValue};
+use ::readyset_client::placement::{PlacementConstraints, WorkerConstraint};
 use ::readyset_client::recipe::changelist::ChangeList;
 use ::readyset_client::{failpoints, get_metric};
 use readyset_data::{DfValue, Dialect};
@@ -437,6 +438,69 @@ async fn no_readers_worker_doesnt_get_readers() {
     deployment.teardown().await.unwrap();
 }
 
+#[clustertest]
+async fn placement_constraints_kept_when_rescheduling() {
+    let mut deployment = DeploymentBuilder::new("ct_placement_constraints_kept_when_rescheduling")
+        .add_server(ServerParams::default().with_zone("a"))
+        .start()
+        .await
+        .unwrap();
+    let first_server = deployment.server_addrs()[0].clone();
+    let lh = deployment.leader_handle();
+
+    lh.extend_recipe(
+        ChangeList::from_str("CREATE TABLE t (id int, val int);", Dialect::DEFAULT_MYSQL).unwrap(),
+    )
+    .await
+    .unwrap();
+    lh.extend_recipe_with_placement_constraints(
+        ChangeList::from_str(
+            "CREATE CACHE q FROM SELECT val FROM t WHERE id = ?;",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+        PlacementConstraints {
+            readers: vec![WorkerConstraint::Zone("a".into())],
+            base_tables: vec![],
+        },
+    )
+    .await
+    .unwrap();
+
+    // The reader can't be moved onto a server in another zone
+    deployment
+        .start_server(ServerParams::default().with_zone("b"), true)
+        .await
+        .unwrap();
+    let err = deployment
+        .leader_handle()
+        .drain_worker(first_server.clone())
+        .await
+        .unwrap_err();
+    assert!(format!("{err:?}").contains("NoAvailableWorkers"), "{err}");
+
+    // But it can be moved onto another server in the same zone
+    deployment
+        .start_server(ServerParams::default().with_zone("a"), true)
+        .await
+        .unwrap();
+    deployment
+        .leader_handle()
+        .drain_worker(first_server)
+        .await
+        .unwrap();
+    let mut view = deployment
+        .leader_handle()
+        .view("q")
+        .await
+        .unwrap()
+        .into_reader_handle()
+        .unwrap();
+    view.lookup(&[1.into()], true).await.unwrap();
+
+    deployment.teardown().await.unwrap();
+}
+
 #[clustertest]
 async fn server_and_adapter_auto_restart() {
     let mut deployment = DeploymentBuilder::new("ct_adapter_restart")
@@ -145,6 +145,10 @@ impl ReadysetServerBuilder {
         self.push_arg("--no-readers")
     }
 
+    pub fn worker_zone(self, zone: &str) -> Self {
+        self.push_arg_kv("--worker-zone", zone)
+    }
+
     pub fn authority_addr(self, authority_addr: &str) -> Self {
         self.push_arg_kv("--authority-address", authority_addr)
     }
@@ -314,6 +318,10 @@ impl AdapterBuilder {
         self.push_arg("--no-readers")
     }
 
+    pub fn worker_zone(self, zone: &str) -> Self {
+        self.push_arg_kv("--worker-zone", zone)
+    }
+
     pub fn shards(self, shards: usize) -> Self {
         self.push_arg_kv("--shards", &shards.to_string())
     }
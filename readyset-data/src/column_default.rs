@@ -0,0 +1,226 @@
+use std::convert::TryFrom;
+
+use chrono::{NaiveDateTime, Timelike, Utc};
+use nom_sql::{ColumnConstraint, ColumnSpecification, Expr, FunctionExpr, Literal};
+use readyset_errors::{unsupported, unsupported_err, ReadySetResult};
+use uuid::Uuid;
+
+use crate::{DfType, DfValue, Dialect};
+
+/// Returns the current time in UTC, truncated to `precision` fractional digits of
+/// seconds (and at most to microseconds)
+fn now(precision: u32) -> NaiveDateTime {
+    truncate_subseconds(Utc::now().naive_utc(), precision)
+}
+
+/// Truncate `now` to `precision` fractional digits of seconds (and at most to microseconds)
+fn truncate_subseconds(now: NaiveDateTime, precision: u32) -> NaiveDateTime {
+    let factor = 10u32.pow(9 - precision.min(6));
+    now.with_nanosecond(now.nanosecond() / factor * factor)
+        .unwrap_or(now)
+}
+
+/// Evaluate a call to one of the functions whose result changes from call to call, and so can't be
+/// evaluated by dataflow expressions: the functions returning the current date or time (in UTC),
+/// and the functions generating a random UUID.
+///
+/// Returns `None` if `name` isn't one of these functions.
+pub fn eval_volatile_function(name: &str, arguments: &[Expr]) -> ReadySetResult<Option<DfValue>> {
+    eval_volatile_function_at(name, arguments, Utc::now().naive_utc())
+}
+
+/// Like [`eval_volatile_function`], but with the functions returning the current date or time
+/// returning `now` instead.
+fn eval_volatile_function_at(
+    name: &str,
+    arguments: &[Expr],
+    now: NaiveDateTime,
+) -> ReadySetResult<Option<DfValue>> {
+    let precision = || -> ReadySetResult<u32> {
+        Ok(match arguments.first() {
+            None => 0,
+            Some(Expr::Literal(Literal::UnsignedInteger(p))) => u32::try_from(*p).unwrap_or(6),
+            Some(Expr::Literal(Literal::Integer(p))) => u32::try_from(*p).unwrap_or(6),
+            Some(_) => unsupported!("Non-literal precision in call to {name}"),
+        })
+    };
+
+    Ok(Some(match name.to_ascii_lowercase().as_str() {
+        "now" | "current_timestamp" | "localtime" | "localtimestamp" => {
+            truncate_subseconds(now, precision()?).into()
+        }
+        "current_date" | "curdate" => now.date().into(),
+        "current_time" | "curtime" => truncate_subseconds(now, precision()?).time().into(),
+        "uuid" | "gen_random_uuid" => Uuid::new_v4().to_string().into(),
+        _ => return Ok(None),
+    }))
+}
+
+/// Returns the value to write to the column described by `spec` for rows that don't specify a value
+/// for it: the result of evaluating the column's `DEFAULT` expression if it has one, or `NULL`
+/// otherwise.
+///
+/// Besides literals, the only supported default expressions are calls to the functions supported by
+/// [`eval_volatile_function`] (such as `CURRENT_TIMESTAMP` or `NOW()`), which are evaluated at the
+/// time of the call and converted to the type of the column.
+pub fn column_default_value(
+    spec: &ColumnSpecification,
+    dialect: Dialect,
+) -> ReadySetResult<DfValue> {
+    column_default_value_at(spec, dialect, Utc::now().naive_utc())
+}
+
+/// Like [`column_default_value`], but with default expressions returning the current date or time
+/// (such as `CURRENT_TIMESTAMP`) evaluated as of `now`, in UTC. Used to compute defaults for rows
+/// written at some other time, such as rows read from a replication log.
+pub fn column_default_value_at(
+    spec: &ColumnSpecification,
+    dialect: Dialect,
+    now: NaiveDateTime,
+) -> ReadySetResult<DfValue> {
+    let default = spec.constraints.iter().find_map(|c| match c {
+        ColumnConstraint::DefaultValue(expr) => Some(expr),
+        _ => None,
+    });
+
+    match default {
+        None => Ok(DfValue::None),
+        Some(Expr::Literal(lit)) => DfValue::try_from(lit.clone()),
+        Some(Expr::Call(FunctionExpr::Call { name, arguments })) => {
+            let target_type = DfType::from_sql_type(&spec.sql_type, dialect, |_| None)?;
+            eval_volatile_function_at(name, arguments, now)?
+                .ok_or_else(|| {
+                    unsupported_err!("Function {name} is not supported in default values")
+                })?
+                .coerce_to(&target_type, &DfType::Unknown)
+        }
+        Some(_) => unsupported!("Only literal values are supported in default values"),
+    }
+}
+
+/// Returns the value to set the column described by `spec` to whenever a row is updated without
+/// giving it an explicit value, if the column was declared with `ON UPDATE CURRENT_TIMESTAMP`: the
+/// current time in UTC, converted to `ty`, the type of the column in the dialect of its table.
+pub fn column_on_update_value(
+    spec: &ColumnSpecification,
+    ty: &DfType,
+) -> ReadySetResult<Option<DfValue>> {
+    spec.constraints
+        .iter()
+        .find_map(|c| match c {
+            ColumnConstraint::OnUpdateCurrentTimestamp(precision) => Some(*precision),
+            _ => None,
+        })
+        .map(|precision| {
+            DfValue::from(now(precision.unwrap_or(0).into())).coerce_to(ty, &DfType::Unknown)
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use nom_sql::{parse_create_table, Dialect as ParseDialect};
+
+    use super::*;
+
+    fn column(sql: &str) -> ColumnSpecification {
+        parse_create_table(ParseDialect::MySQL, format!("CREATE TABLE t ({sql})"))
+            .unwrap()
+            .body
+            .unwrap()
+            .fields
+            .remove(0)
+    }
+
+    #[test]
+    fn literal_default() {
+        assert_eq!(
+            column_default_value(&column("x int DEFAULT 4"), Dialect::DEFAULT_MYSQL).unwrap(),
+            DfValue::from(4)
+        );
+        assert_eq!(
+            column_default_value(&column("x int"), Dialect::DEFAULT_MYSQL).unwrap(),
+            DfValue::None
+        );
+    }
+
+    #[test]
+    fn current_timestamp_default() {
+        let before = now(0);
+        let val = column_default_value(
+            &column("x datetime DEFAULT CURRENT_TIMESTAMP"),
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap();
+        let after = now(0);
+        let val = NaiveDateTime::try_from(&val).unwrap();
+        assert!(before <= val && val <= after);
+        assert_eq!(val.nanosecond(), 0);
+    }
+
+    #[test]
+    fn current_timestamp_default_at() {
+        let at = NaiveDate::from_ymd(2022, 10, 1).and_hms_micro(1, 2, 3, 456_789);
+        let val = column_default_value_at(
+            &column("x datetime(3) DEFAULT CURRENT_TIMESTAMP(3)"),
+            Dialect::DEFAULT_MYSQL,
+            at,
+        )
+        .unwrap();
+        assert_eq!(
+            NaiveDateTime::try_from(&val).unwrap(),
+            NaiveDate::from_ymd(2022, 10, 1).and_hms_milli(1, 2, 3, 456)
+        );
+    }
+
+    #[test]
+    fn current_date_default() {
+        let val = column_default_value(
+            &column("x date DEFAULT (CURDATE())"),
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap();
+        assert!(matches!(val, DfValue::TimestampTz(_)));
+    }
+
+    #[test]
+    fn on_update_current_timestamp() {
+        let val = column_on_update_value(
+            &column("x datetime(3) ON UPDATE CURRENT_TIMESTAMP(3)"),
+            &DfType::DateTime {
+                subsecond_digits: 3,
+            },
+        )
+        .unwrap()
+        .unwrap();
+        let val = NaiveDateTime::try_from(&val).unwrap();
+        assert_eq!(val.nanosecond() % 1_000_000, 0);
+
+        assert_eq!(
+            column_on_update_value(
+                &column("x datetime"),
+                &DfType::DateTime {
+                    subsecond_digits: 0
+                }
+            )
+            .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn uuid_default() {
+        let val = column_default_value(
+            &column("x char(36) DEFAULT (UUID())"),
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap();
+        assert_eq!(<&str>::try_from(&val).unwrap().len(), 36);
+    }
+
+    #[test]
+    fn unsupported_default() {
+        column_default_value(&column("x int DEFAULT (1 + 2)"), Dialect::DEFAULT_MYSQL).unwrap_err();
+    }
+}
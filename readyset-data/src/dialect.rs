@@ -84,6 +84,31 @@ impl Dialect {
         }
     }
 
+    /// Returns whether dividing by zero evaluates to `NULL`, rather than raising an error.
+    ///
+    /// This returns true for MySQL (which only emits a warning unless `ERROR_FOR_DIVISION_BY_ZERO`
+    /// is set in the SQL mode, which is not yet implemented), and false for Postgres.
+    pub fn division_by_zero_is_null(self) -> bool {
+        match self.engine {
+            SqlEngine::MySQL => true,
+            SqlEngine::PostgreSQL => false,
+        }
+    }
+
+    /// Returns whether strings used as numbers (in arithmetic, or as boolean conditions) are
+    /// converted leniently, by taking the longest prefix of the string which is a valid number
+    /// and treating strings without one as 0.
+    ///
+    /// This returns true for MySQL, which only emits a warning for strings which aren't valid
+    /// numbers. Postgres raises an error for those instead, and converts strings used as boolean
+    /// conditions by parsing them as boolean literals (`'t'`, `'yes'`, `'off'`, etc.)
+    pub fn lenient_string_to_number(self) -> bool {
+        match self.engine {
+            SqlEngine::MySQL => true,
+            SqlEngine::PostgreSQL => false,
+        }
+    }
+
     /// Return the [`DfType`] corresponding to the SQL `FLOAT` type for this dialect
     pub(crate) fn float_type(&self) -> DfType {
         match self.engine {
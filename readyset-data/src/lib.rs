@@ -31,6 +31,7 @@ use uuid::Uuid;
 
 mod array;
 mod collation;
+mod column_default;
 pub mod dialect;
 mod r#enum;
 mod float;
@@ -44,6 +45,9 @@ pub use ndarray::{ArrayD, IxDyn};
 
 pub use crate::array::Array;
 pub use crate::collation::Collation;
+pub use crate::column_default::{
+    column_default_value, column_default_value_at, column_on_update_value, eval_volatile_function,
+};
 pub use crate::dialect::Dialect;
 pub use crate::r#type::{DfType, PgEnumMetadata, PgTypeCategory};
 pub use crate::text::{Text, TinyText};
@@ -1030,24 +1034,64 @@ where
     }
 }
 
-impl TryFrom<i128> for DfValue {
-    type Error = ReadySetError;
+/// Returns the error for a failed conversion of `value` into the Rust type named by
+/// `target_type`.
+///
+/// All the `TryFrom<DfValue>` and `TryFrom<&DfValue>` implementations in this crate fail with an
+/// error built by this function, so that the errors are consistent: `src_type` is the SQL type of
+/// the value (or `Null`), and `details` is either [`INCOMPATIBLE_TYPE`], if values of that type
+/// can't be converted to `target_type` at all, or a description of why this particular value
+/// couldn't be converted (such as [`OUT_OF_BOUNDS`]).
+fn conversion_error(
+    value: &DfValue,
+    target_type: &str,
+    details: impl Into<String>,
+) -> ReadySetError {
+    ReadySetError::DfValueConversionError {
+        src_type: match value.sql_type() {
+            Some(ty) => ty.to_string(),
+            None => "Null".to_string(),
+        },
+        target_type: target_type.to_string(),
+        details: details.into(),
+    }
+}
+
+/// The details of a conversion error for a value whose type can't be converted to the target type
+const INCOMPATIBLE_TYPE: &str = "incompatible type";
+
+/// The details of a conversion error for a value which is outside the range of the target type
+const OUT_OF_BOUNDS: &str = "out of bounds";
+
+/// Implements `TryFrom` for [`DfValue`] for a 128-bit integer type, which succeeds for values in
+/// the range of either `i64` or `u64`
+macro_rules! wide_integer_into_value {
+    ($int:ty) => {
+        impl TryFrom<$int> for DfValue {
+            type Error = ReadySetError;
 
-    fn try_from(i: i128) -> Result<Self, Self::Error> {
-        if let Ok(i) = i64::try_from(i) {
-            Ok(i.into())
-        } else if let Ok(i) = u64::try_from(i) {
-            Ok(i.into())
-        } else {
-            Err(ReadySetError::DfValueConversionError {
-                src_type: "i128".to_string(),
-                target_type: "DfValue".to_string(),
-                details: "unsupported".to_string(),
-            })
+            fn try_from(i: $int) -> Result<Self, Self::Error> {
+                if let Ok(i) = i64::try_from(i) {
+                    Ok(i.into())
+                } else if let Ok(i) = u64::try_from(i) {
+                    Ok(i.into())
+                } else {
+                    Err(ReadySetError::DfValueConversionError {
+                        src_type: stringify!($int).to_string(),
+                        target_type: "DfValue".to_string(),
+                        details: OUT_OF_BOUNDS.to_string(),
+                    })
+                }
+            }
         }
-    }
+    };
+    ($($int:ty),+) => {
+        $(wide_integer_into_value!($int);)+
+    };
 }
 
+wide_integer_into_value!(i128, u128);
+
 macro_rules! signed_integer_into_value {
     ($int:ty) => {
         impl From<$int> for DfValue {
@@ -1124,30 +1168,24 @@ impl<'a> TryFrom<&'a DfValue> for Decimal {
         match dt {
             DfValue::Int(i) => Ok(Decimal::from(*i)),
             DfValue::UnsignedInt(i) => Ok(Decimal::from(*i)),
-            DfValue::Float(value) => {
-                Decimal::from_f32(*value).ok_or_else(|| Self::Error::DfValueConversionError {
-                    src_type: "DfValue".to_string(),
-                    target_type: "Decimal".to_string(),
-                    details: "".to_string(),
-                })
-            }
-            DfValue::Double(value) => {
-                Decimal::from_f64(*value).ok_or_else(|| Self::Error::DfValueConversionError {
-                    src_type: "DfValue".to_string(),
-                    target_type: "Decimal".to_string(),
-                    details: "".to_string(),
-                })
-            }
+            DfValue::Float(value) => Decimal::from_f32(*value)
+                .ok_or_else(|| conversion_error(dt, "Decimal", OUT_OF_BOUNDS)),
+            DfValue::Double(value) => Decimal::from_f64(*value)
+                .ok_or_else(|| conversion_error(dt, "Decimal", OUT_OF_BOUNDS)),
             DfValue::Numeric(d) => Ok(*d.as_ref()),
-            _ => Err(Self::Error::DfValueConversionError {
-                src_type: "DfValue".to_string(),
-                target_type: "Decimal".to_string(),
-                details: "".to_string(),
-            }),
+            _ => Err(conversion_error(dt, "Decimal", INCOMPATIBLE_TYPE)),
         }
     }
 }
 
+impl TryFrom<DfValue> for Decimal {
+    type Error = ReadySetError;
+
+    fn try_from(dt: DfValue) -> Result<Self, Self::Error> {
+        Decimal::try_from(&dt)
+    }
+}
+
 /// Bit vectors are represented as [`BitVec`].
 impl From<BitVec> for DfValue {
     fn from(b: BitVec) -> Self {
@@ -1161,11 +1199,7 @@ impl<'a> TryFrom<&'a DfValue> for BitVec {
     fn try_from(dt: &'a DfValue) -> Result<Self, Self::Error> {
         match dt {
             DfValue::BitVector(ref bits) => Ok(bits.as_ref().clone()),
-            _ => Err(Self::Error::DfValueConversionError {
-                src_type: "DfValue".to_string(),
-                target_type: "Decimal".to_string(),
-                details: "".to_string(),
-            }),
+            _ => Err(conversion_error(dt, "BitVec", INCOMPATIBLE_TYPE)),
         }
     }
 }
@@ -1296,11 +1330,7 @@ impl<'a> TryFrom<&'a DfValue> for NaiveDateTime {
     fn try_from(data: &'a DfValue) -> Result<Self, Self::Error> {
         match *data {
             DfValue::TimestampTz(ref dt) => Ok(dt.to_chrono().naive_utc()),
-            _ => Err(Self::Error::DfValueConversionError {
-                src_type: "DfValue".to_string(),
-                target_type: "NaiveDateTime".to_string(),
-                details: "".to_string(),
-            }),
+            _ => Err(conversion_error(data, "NaiveDateTime", INCOMPATIBLE_TYPE)),
         }
     }
 }
@@ -1310,11 +1340,11 @@ impl<'a> TryFrom<&'a DfValue> for DateTime<FixedOffset> {
     fn try_from(data: &'a DfValue) -> Result<Self, Self::Error> {
         match *data {
             DfValue::TimestampTz(dt) => Ok(dt.to_chrono()),
-            _ => Err(Self::Error::DfValueConversionError {
-                src_type: "DfValue".to_string(),
-                target_type: "DateTime<FixedOffset>".to_string(),
-                details: "".to_string(),
-            }),
+            _ => Err(conversion_error(
+                data,
+                "DateTime<FixedOffset>",
+                INCOMPATIBLE_TYPE,
+            )),
         }
     }
 }
@@ -1325,11 +1355,7 @@ impl<'a> TryFrom<&'a DfValue> for NaiveDate {
     fn try_from(data: &'a DfValue) -> Result<Self, Self::Error> {
         match *data {
             DfValue::TimestampTz(ref dt) => Ok(dt.to_chrono().naive_local().date()),
-            _ => Err(Self::Error::DfValueConversionError {
-                src_type: "DfValue".to_string(),
-                target_type: "NaiveDate".to_string(),
-                details: "".to_string(),
-            }),
+            _ => Err(conversion_error(data, "NaiveDate", INCOMPATIBLE_TYPE)),
         }
     }
 }
@@ -1340,15 +1366,66 @@ impl<'a> TryFrom<&'a DfValue> for MySqlTime {
     fn try_from(data: &'a DfValue) -> Result<Self, Self::Error> {
         match *data {
             DfValue::Time(ref mysql_time) => Ok(*mysql_time),
-            _ => Err(Self::Error::DfValueConversionError {
-                src_type: "DfValue".to_string(),
-                target_type: "MySqlTime".to_string(),
-                details: "".to_string(),
-            }),
+            _ => Err(conversion_error(data, "MySqlTime", INCOMPATIBLE_TYPE)),
+        }
+    }
+}
+
+/// Times are converted to a [`NaiveTime`] only if they're within a single day (MySQL `TIME` values
+/// may be negative, or span more than 24 hours). Timestamps are converted to their time of day.
+impl<'a> TryFrom<&'a DfValue> for NaiveTime {
+    type Error = ReadySetError;
+
+    fn try_from(data: &'a DfValue) -> Result<Self, Self::Error> {
+        match *data {
+            DfValue::Time(ref t) if *t >= MySqlTime::default() => NaiveTime::from_hms_micro_opt(
+                t.hour().into(),
+                t.minutes().into(),
+                t.seconds().into(),
+                t.microseconds(),
+            )
+            .ok_or_else(|| conversion_error(data, "NaiveTime", OUT_OF_BOUNDS)),
+            DfValue::Time(_) => Err(conversion_error(data, "NaiveTime", OUT_OF_BOUNDS)),
+            DfValue::TimestampTz(ref dt) => Ok(dt.to_chrono().naive_local().time()),
+            _ => Err(conversion_error(data, "NaiveTime", INCOMPATIBLE_TYPE)),
         }
     }
 }
 
+impl TryFrom<DfValue> for NaiveTime {
+    type Error = ReadySetError;
+
+    fn try_from(data: DfValue) -> Result<Self, Self::Error> {
+        NaiveTime::try_from(&data)
+    }
+}
+
+/// UUIDs are represented as text, in their hyphenated form
+impl From<Uuid> for DfValue {
+    fn from(uuid: Uuid) -> Self {
+        DfValue::from(uuid.to_string())
+    }
+}
+
+impl<'a> TryFrom<&'a DfValue> for Uuid {
+    type Error = ReadySetError;
+
+    fn try_from(data: &'a DfValue) -> Result<Self, Self::Error> {
+        let s = data
+            .as_str()
+            .ok_or_else(|| conversion_error(data, "Uuid", INCOMPATIBLE_TYPE))?;
+        Uuid::parse_str(s).map_err(|e| conversion_error(data, "Uuid", e.to_string()))
+    }
+}
+
+impl TryFrom<DfValue> for Uuid {
+    type Error = ReadySetError;
+
+    fn try_from(data: DfValue) -> Result<Self, Self::Error> {
+        Uuid::try_from(&data)
+    }
+}
+
 impl<'a> TryFrom<&'a DfValue> for Vec<u8> {
     type Error = ReadySetError;
 
@@ -1357,11 +1434,7 @@ impl<'a> TryFrom<&'a DfValue> for Vec<u8> {
             DfValue::Text(ref t) => Ok(t.as_bytes().to_vec()),
             DfValue::TinyText(ref tt) => Ok(tt.as_str().as_bytes().to_vec()),
             DfValue::ByteArray(ref array) => Ok(array.as_ref().clone()),
-            _ => Err(Self::Error::DfValueConversionError {
-                src_type: "DfValue".to_string(),
-                target_type: "Vec<u8>".to_string(),
-                details: "".to_string(),
-            }),
+            _ => Err(conversion_error(data, "Vec<u8>", INCOMPATIBLE_TYPE)),
         }
     }
 }
@@ -1371,14 +1444,7 @@ impl<'a> TryFrom<&'a DfValue> for &'a str {
 
     fn try_from(data: &'a DfValue) -> Result<Self, Self::Error> {
         data.as_str()
-            .ok_or_else(|| ReadySetError::DfValueConversionError {
-                src_type: match data.sql_type() {
-                    Some(ty) => ty.to_string(),
-                    None => "Null".to_string(),
-                },
-                target_type: "&str".to_string(),
-                details: "".to_string(),
-            })
+            .ok_or_else(|| conversion_error(data, "&str", INCOMPATIBLE_TYPE))
     }
 }
 
@@ -1390,14 +1456,7 @@ impl TryFrom<DfValue> for Vec<u8> {
             DfValue::Text(t) => Ok(t.as_bytes().to_vec()),
             DfValue::TinyText(tt) => Ok(tt.as_str().as_bytes().to_vec()),
             DfValue::ByteArray(bytes) => Ok(bytes.as_ref().clone()),
-            _ => Err(Self::Error::DfValueConversionError {
-                src_type: match data.sql_type() {
-                    Some(ty) => ty.to_string(),
-                    None => "Null".to_string(),
-                },
-                target_type: "&str".to_string(),
-                details: "".to_string(),
-            }),
+            _ => Err(conversion_error(&data, "Vec<u8>", INCOMPATIBLE_TYPE)),
         }
     }
 }
@@ -1410,17 +1469,13 @@ macro_rules! integer_try_from {
             type Error = ReadySetError;
 
             fn try_from(data: &DfValue) -> Result<Self, Self::Error> {
-                let error = |details: String| ReadySetError::DfValueConversionError {
-                    src_type: "DfValue".to_owned(),
-                    target_type: stringify!($int).to_owned(),
-                    details,
-                };
+                let error = |details| conversion_error(data, stringify!($int), details);
 
                 data.as_int()
-                    .ok_or_else(|| error("unsupported".to_owned()))
+                    .ok_or_else(|| error(INCOMPATIBLE_TYPE.to_owned()))
                     .and_then(|i| {
                         Self::try_from(i)
-                            .map_err(|_| error(format!("out of bounds {}", Sensitive(&i))))
+                            .map_err(|_| error(format!("{} {}", OUT_OF_BOUNDS, Sensitive(&i))))
                     })
             }
         }
@@ -1455,11 +1510,7 @@ impl TryFrom<&DfValue> for bool {
     fn try_from(data: &DfValue) -> Result<Self, Self::Error> {
         data.as_int()
             .map(|i| i != 0)
-            .ok_or_else(|| ReadySetError::DfValueConversionError {
-                src_type: "DfValue".to_owned(),
-                target_type: "bool".to_owned(),
-                details: "".to_owned(),
-            })
+            .ok_or_else(|| conversion_error(data, "bool", INCOMPATIBLE_TYPE))
     }
 }
 
@@ -1478,21 +1529,12 @@ impl TryFrom<&'_ DfValue> for f32 {
         match *data {
             DfValue::Float(f) => Ok(f),
             DfValue::Double(f) => Ok(f as f32),
-            DfValue::Numeric(ref d) => {
-                d.to_f32()
-                    .ok_or_else(|| Self::Error::DfValueConversionError {
-                        src_type: "DfValue".to_string(),
-                        target_type: "f32".to_string(),
-                        details: "".to_string(),
-                    })
-            }
+            DfValue::Numeric(ref d) => d
+                .to_f32()
+                .ok_or_else(|| conversion_error(data, "f32", OUT_OF_BOUNDS)),
             DfValue::UnsignedInt(i) => Ok(i as f32),
             DfValue::Int(i) => Ok(i as f32),
-            _ => Err(Self::Error::DfValueConversionError {
-                src_type: "DfValue".to_string(),
-                target_type: "f32".to_string(),
-                details: "".to_string(),
-            }),
+            _ => Err(conversion_error(data, "f32", INCOMPATIBLE_TYPE)),
         }
     }
 }
@@ -1512,21 +1554,12 @@ impl TryFrom<&'_ DfValue> for f64 {
         match *data {
             DfValue::Float(f) => Ok(f as f64),
             DfValue::Double(f) => Ok(f),
-            DfValue::Numeric(ref d) => {
-                d.to_f64()
-                    .ok_or_else(|| Self::Error::DfValueConversionError {
-                        src_type: "DfValue".to_string(),
-                        target_type: "f32".to_string(),
-                        details: "".to_string(),
-                    })
-            }
+            DfValue::Numeric(ref d) => d
+                .to_f64()
+                .ok_or_else(|| conversion_error(data, "f64", OUT_OF_BOUNDS)),
             DfValue::UnsignedInt(i) => Ok(i as f64),
             DfValue::Int(i) => Ok(i as f64),
-            _ => Err(Self::Error::DfValueConversionError {
-                src_type: "DfValue".to_string(),
-                target_type: "f64".to_string(),
-                details: "".to_string(),
-            }),
+            _ => Err(conversion_error(data, "f64", INCOMPATIBLE_TYPE)),
         }
     }
 }
@@ -2876,6 +2909,77 @@ mod tests {
         )
     }
 
+    #[test]
+    fn wide_integer_conversion() {
+        assert_eq!(DfValue::try_from(-1i128).unwrap(), DfValue::Int(-1));
+        assert_eq!(
+            DfValue::try_from(u64::MAX as u128).unwrap(),
+            DfValue::UnsignedInt(u64::MAX)
+        );
+        assert!(DfValue::try_from(u128::MAX).is_err());
+        assert!(DfValue::try_from(i128::MIN).is_err());
+    }
+
+    #[test]
+    fn naive_time_conversion() {
+        let time = NaiveTime::from_hms_micro(12, 34, 56, 789);
+        assert_eq!(
+            NaiveTime::try_from(DfValue::Time(MySqlTime::from(time))).unwrap(),
+            time
+        );
+        assert_eq!(
+            NaiveTime::try_from(&DfValue::from(
+                NaiveDate::from_ymd(2022, 1, 2).and_time(time)
+            ))
+            .unwrap(),
+            time
+        );
+        assert!(
+            NaiveTime::try_from(DfValue::Time(MySqlTime::from_hmsus(false, 1, 0, 0, 0))).is_err()
+        );
+        assert!(
+            NaiveTime::try_from(DfValue::Time(MySqlTime::from_hmsus(true, 25, 0, 0, 0))).is_err()
+        );
+    }
+
+    #[test]
+    fn uuid_conversion_roundtrip() {
+        let uuid = Uuid::new_v4();
+        let value = DfValue::from(uuid);
+        assert_eq!(value, DfValue::from(uuid.to_string()));
+        assert_eq!(Uuid::try_from(&value).unwrap(), uuid);
+        assert_eq!(Uuid::try_from(value).unwrap(), uuid);
+        assert!(Uuid::try_from(DfValue::from("not a uuid")).is_err());
+    }
+
+    #[test]
+    fn conversion_errors_are_consistent() {
+        assert_eq!(
+            u8::try_from(DfValue::from(256)).unwrap_err(),
+            ReadySetError::DfValueConversionError {
+                src_type: "BIGINT".to_string(),
+                target_type: "u8".to_string(),
+                details: format!("{} {}", OUT_OF_BOUNDS, Sensitive(&256)),
+            }
+        );
+        assert_eq!(
+            Decimal::try_from(DfValue::from("abc")).unwrap_err(),
+            ReadySetError::DfValueConversionError {
+                src_type: "TINYTEXT".to_string(),
+                target_type: "Decimal".to_string(),
+                details: INCOMPATIBLE_TYPE.to_string(),
+            }
+        );
+        assert_eq!(
+            f64::try_from(&DfValue::None).unwrap_err(),
+            ReadySetError::DfValueConversionError {
+                src_type: "Null".to_string(),
+                target_type: "f64".to_string(),
+                details: INCOMPATIBLE_TYPE.to_string(),
+            }
+        );
+    }
+
     #[test]
     fn data_type_passthrough_same_type_roundtrip() -> Result<(), Box<dyn Error + Sync + Send>> {
         let original_type = Type::new(
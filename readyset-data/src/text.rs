@@ -251,6 +251,16 @@ impl Text {
     pub fn collation(&self) -> Collation {
         self.inner.header.header.collation
     }
+
+    /// Returns true if this is the only reference to the underlying allocation
+    pub fn is_unique(&self) -> bool {
+        self.inner.with_arc(|arc| arc.is_unique())
+    }
+
+    /// Returns true if both values point to the same underlying allocation
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        self.inner.ptr() == other.inner.ptr()
+    }
 }
 
 impl TryFrom<&[u8]> for Text {
@@ -238,6 +238,7 @@ impl DfType {
             // FIXME(ENG-1650): Convert to `tinyint(1)` for MySQL.
             Bool => Self::Bool,
 
+            SmallSerial => Self::SmallInt,
             Serial => dialect.serial_type(),
             BigSerial => Self::BigInt,
 
@@ -284,12 +285,13 @@ impl DfType {
             Jsonb => Self::Jsonb,
 
             Date => Self::Date,
-            #[allow(clippy::or_fun_call)]
             DateTime(subsecond_digits) => Self::DateTime {
-                subsecond_digits: subsecond_digits.unwrap_or(dialect.default_subsecond_digits()),
+                subsecond_digits: subsecond_digits
+                    .unwrap_or_else(|| dialect.default_subsecond_digits()),
             },
-            Time => Self::Time {
-                subsecond_digits: dialect.default_subsecond_digits(),
+            Time(subsecond_digits) => Self::Time {
+                subsecond_digits: subsecond_digits
+                    .unwrap_or_else(|| dialect.default_subsecond_digits()),
             },
             Timestamp => Self::Timestamp {
                 subsecond_digits: dialect.default_subsecond_digits(),
@@ -302,6 +304,9 @@ impl DfType {
             MacAddr => Self::MacAddr,
             Inet => Self::Inet,
             Citext => Self::Text(Collation::Citext),
+            // The citext extension may be installed in a schema other than the one the table is
+            // in, in which case the type name is qualified with that schema
+            Other(ref id) if id.name == "citext" => Self::Text(Collation::Citext),
             Other(ref id) => resolve_custom_type(id.clone())
                 .ok_or_else(|| unsupported_err!("Unsupported type: {id}"))?,
         })
@@ -666,4 +671,35 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn schema_qualified_citext() {
+        let ty = SqlType::Other(Relation {
+            schema: Some("extensions".into()),
+            name: "citext".into(),
+        });
+        assert_eq!(
+            DfType::from_sql_type(&ty, Dialect::DEFAULT_POSTGRESQL, |_| None).unwrap(),
+            DfType::Text(Collation::Citext)
+        );
+    }
+
+    #[test]
+    fn time_precision() {
+        assert_eq!(
+            DfType::from_sql_type(&SqlType::Time(Some(3)), Dialect::DEFAULT_POSTGRESQL, |_| {
+                None
+            })
+            .unwrap(),
+            DfType::Time {
+                subsecond_digits: 3
+            }
+        );
+        assert_eq!(
+            DfType::from_sql_type(&SqlType::Time(None), Dialect::DEFAULT_MYSQL, |_| None).unwrap(),
+            DfType::Time {
+                subsecond_digits: Dialect::DEFAULT_MYSQL.default_subsecond_digits()
+            }
+        );
+    }
 }
@@ -0,0 +1,211 @@
+//! Approximate tracking of the most frequently read keys in a reader.
+
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::sync::Mutex;
+
+use ahash::RandomState;
+
+use crate::prelude::*;
+
+/// The number of independently locked shards the tracked keys are split into, so that concurrent
+/// reads of different keys rarely contend on the same lock
+const SHARDS: usize = 16;
+
+/// The number of distinct keys tracked at once by each shard. Keys which are read more often than
+/// roughly `1 / CAPACITY` of the reads of the keys in their shard are guaranteed to be tracked.
+const CAPACITY: usize = 16;
+
+/// Tracks the most frequently read keys of a reader.
+///
+/// Keys are split between [`SHARDS`] shards by their hash, each of which is counted separately
+/// under its own lock. Since a key always belongs to the same shard, merging the shards' counts
+/// gives the same answer as counting all keys together (with [`SHARDS`] times the capacity).
+pub(super) struct HotKeys {
+    shards: Box<[Mutex<Shard>]>,
+    hasher: RandomState,
+}
+
+impl Default for HotKeys {
+    fn default() -> Self {
+        Self {
+            shards: (0..SHARDS).map(|_| Mutex::default()).collect(),
+            hasher: RandomState::new(),
+        }
+    }
+}
+
+impl HotKeys {
+    /// Record a single read of `key`. This is best-effort: if another read of a key in the same
+    /// shard is being counted concurrently, this read isn't counted, rather than waiting for it.
+    pub(super) fn record(&self, key: &[DfValue]) {
+        let mut hasher = self.hasher.build_hasher();
+        key.hash(&mut hasher);
+        #[allow(clippy::indexing_slicing)] // the index is reduced modulo the number of shards
+        let shard = &self.shards[hasher.finish() as usize % self.shards.len()];
+        if let Ok(mut shard) = shard.try_lock() {
+            shard.record(key);
+        }
+    }
+
+    /// Returns the `n` most read keys, along with their approximate read counts, most read first
+    pub(super) fn top(&self, n: usize) -> Vec<(Vec<DfValue>, u64)> {
+        let mut top = self
+            .shards
+            .iter()
+            .flat_map(|shard| {
+                #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+                shard.lock().unwrap().top(n)
+            })
+            .collect::<Vec<_>>();
+        top.sort_by(|(_, c1), (_, c2)| c2.cmp(c1));
+        top.truncate(n);
+        top
+    }
+
+    pub(super) fn clear(&self) {
+        for shard in self.shards.iter() {
+            #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+            shard.lock().unwrap().clear();
+        }
+    }
+}
+
+/// Tracks the most frequently read keys in a single shard using the "space-saving" algorithm: a
+/// fixed number of keys are counted, and reading an untracked key replaces the least-read tracked
+/// key, inheriting its count. Counts are therefore upper bounds on the actual number of reads of
+/// each key, but the most frequently read keys are always tracked.
+///
+/// The tracked keys are kept in a binary min-heap ordered by their counts, so that both counting a
+/// read and finding the least-read key to replace take `O(log CAPACITY)` time.
+#[derive(Default)]
+struct Shard {
+    /// The tracked keys and their counts, as a min-heap on the count
+    heap: Vec<(Vec<DfValue>, u64)>,
+    /// The index in `heap` of each tracked key
+    positions: HashMap<Vec<DfValue>, usize, RandomState>,
+}
+
+impl Shard {
+    /// Record a single read of `key`
+    fn record(&mut self, key: &[DfValue]) {
+        if let Some(&pos) = self.positions.get(key) {
+            if let Some((_, count)) = self.heap.get_mut(pos) {
+                *count += 1;
+            }
+            self.sift_down(pos);
+            return;
+        }
+
+        if self.heap.len() < CAPACITY {
+            self.positions.insert(key.to_vec(), self.heap.len());
+            self.heap.push((key.to_vec(), 1));
+            self.sift_up(self.heap.len() - 1);
+        } else if let Some(least_read) = self.heap.first_mut() {
+            let (evicted, count) = std::mem::replace(least_read, (key.to_vec(), 0));
+            least_read.1 = count + 1;
+            self.positions.remove(&evicted);
+            self.positions.insert(key.to_vec(), 0);
+            self.sift_down(0);
+        }
+    }
+
+    fn count(&self, pos: usize) -> u64 {
+        self.heap.get(pos).map_or(u64::MAX, |(_, count)| *count)
+    }
+
+    /// Swap the entries at `a` and `b` in the heap, keeping `positions` up to date
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        for pos in [a, b] {
+            if let Some((key, _)) = self.heap.get(pos) {
+                if let Some(p) = self.positions.get_mut(key) {
+                    *p = pos;
+                }
+            }
+        }
+    }
+
+    fn sift_up(&mut self, mut pos: usize) {
+        while pos > 0 {
+            let parent = (pos - 1) / 2;
+            if self.count(parent) <= self.count(pos) {
+                break;
+            }
+            self.swap(pos, parent);
+            pos = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut pos: usize) {
+        loop {
+            let smallest = [2 * pos + 1, 2 * pos + 2]
+                .into_iter()
+                .filter(|child| *child < self.heap.len())
+                .min_by_key(|child| self.count(*child));
+            match smallest {
+                Some(child) if self.count(child) < self.count(pos) => {
+                    self.swap(pos, child);
+                    pos = child;
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// Returns the `n` most read keys, along with their approximate read counts, most read first
+    fn top(&self, n: usize) -> Vec<(Vec<DfValue>, u64)> {
+        let mut top = self.heap.clone();
+        top.sort_by(|(_, c1), (_, c2)| c2.cmp(c1));
+        top.truncate(n);
+        top
+    }
+
+    fn clear(&mut self) {
+        self.heap.clear();
+        self.positions.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracks_most_read_keys() {
+        let hot_keys = HotKeys::default();
+        for i in 0..1000 {
+            hot_keys.record(&[DfValue::from(i)]);
+            hot_keys.record(&[DfValue::from(-1)]);
+            if i % 2 == 0 {
+                hot_keys.record(&[DfValue::from(-2)]);
+            }
+        }
+
+        let top = hot_keys.top(2);
+        assert_eq!(top[0].0, vec![DfValue::from(-1)]);
+        assert!(top[0].1 >= 1000);
+        assert_eq!(top[1].0, vec![DfValue::from(-2)]);
+        assert!(top[1].1 >= 500);
+    }
+
+    #[test]
+    fn shard_keeps_heap_order() {
+        let mut shard = Shard::default();
+        for i in 0..(CAPACITY as i32 * 10) {
+            for _ in 0..(i % 7) {
+                shard.record(&[DfValue::from(i % 23)]);
+            }
+            shard.record(&[DfValue::from(i)]);
+
+            assert!(shard.heap.len() <= CAPACITY);
+            assert_eq!(shard.positions.len(), shard.heap.len());
+            for (pos, (key, count)) in shard.heap.iter().enumerate() {
+                assert_eq!(shard.positions[key], pos);
+                if pos > 0 {
+                    assert!(shard.heap[(pos - 1) / 2].1 <= *count);
+                }
+            }
+        }
+    }
+}
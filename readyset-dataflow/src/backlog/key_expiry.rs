@@ -0,0 +1,235 @@
+//! Expiry of the keys of a partial reader a fixed (but jittered) amount of time after they're
+//! filled.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::time::{Duration, Instant};
+
+use ahash::RandomState;
+use readyset_client::KeyComparison;
+
+/// Tracks when each key filled in a partial reader expires.
+///
+/// The time-to-live of each key is varied by up to `jitter_percent` percent of the configured TTL
+/// in either direction, so that keys which were filled at the same time (such as when a cache is
+/// warmed) expire spread out over time rather than all at once, which would trigger a storm of
+/// replays as soon as they're read again. The jitter is derived from a hash of the key, so that
+/// each key always gets the same jitter, and the order keys expire in is reproducible.
+pub(super) struct KeyExpiry {
+    ttl: Duration,
+    jitter_percent: u8,
+    /// The time each filled key expires
+    deadlines: HashMap<KeyComparison, Instant, RandomState>,
+    /// Filled keys, by the time they expire, and then in the order they were filled. Keys which
+    /// have been filled again since they were added (and so have a later deadline) are skipped
+    /// when they're taken out of the queue.
+    queue: BTreeMap<Instant, VecDeque<KeyComparison>>,
+    /// Used to derive the jitter of each key's time-to-live from the key, with fixed seeds so that
+    /// the jitter is the same across runs
+    jitter_hasher: RandomState,
+}
+
+impl KeyExpiry {
+    pub(super) fn new(ttl: Duration, jitter_percent: u8) -> Self {
+        Self {
+            ttl,
+            jitter_percent: jitter_percent.min(100),
+            deadlines: Default::default(),
+            queue: Default::default(),
+            jitter_hasher: RandomState::with_seeds(0, 0, 0, 0),
+        }
+    }
+
+    /// Returns the time-to-live for a newly filled `key`, with jitter applied
+    fn jittered_ttl(&self, key: &KeyComparison) -> Duration {
+        if self.jitter_percent == 0 {
+            return self.ttl;
+        }
+        let mut hasher = self.jitter_hasher.build_hasher();
+        key.hash(&mut hasher);
+        // A number in [0, 1), derived from the key
+        let unit = (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64;
+        let jitter = self.jitter_percent as f64 / 100.0;
+        self.ttl.mul_f64(1.0 - jitter + 2.0 * jitter * unit)
+    }
+
+    /// Record that `key` was filled at `now`
+    pub(super) fn filled(&mut self, key: KeyComparison, now: Instant) {
+        let deadline = now + self.jittered_ttl(&key);
+        self.deadlines.insert(key.clone(), deadline);
+        self.queue.entry(deadline).or_default().push(key);
+    }
+
+    /// Remove and return up to `max` of the keys which expired at or before `now`, earliest first.
+    /// Keys which expired but weren't returned (because `max` was reached) are returned by later
+    /// calls.
+    pub(super) fn take_expired(&mut self, now: Instant, max: usize) -> Vec<KeyComparison> {
+        let mut expired = vec![];
+        while expired.len() < max {
+            let (deadline, key) = match self.queue.range_mut(..=now).next() {
+                Some((deadline, keys)) => (*deadline, keys.pop_front()),
+                None => break,
+            };
+            if self.queue.get(&deadline).map_or(false, VecDeque::is_empty) {
+                self.queue.remove(&deadline);
+            }
+            let key = match key {
+                Some(key) => key,
+                None => continue,
+            };
+
+            if self.deadlines.get(&key) == Some(&deadline) {
+                self.deadlines.remove(&key);
+                expired.push(key);
+            }
+        }
+        expired
+    }
+
+    /// Returns the deadline of the key which expired earliest at or before `now`, if any, dropping
+    /// any keys at the front of the queue which have been filled again since
+    pub(super) fn next_expired(&mut self, now: Instant) -> Option<Instant> {
+        loop {
+            let (&deadline, keys) = self.queue.range_mut(..=now).next()?;
+            match keys.front() {
+                Some(key) if self.deadlines.get(key) == Some(&deadline) => return Some(deadline),
+                Some(_) => {
+                    keys.pop_front();
+                }
+                None => {}
+            }
+            if keys.is_empty() {
+                self.queue.remove(&deadline);
+            }
+        }
+    }
+
+    /// Returns the number of keys currently tracked
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.deadlines.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use vec1::vec1;
+
+    use super::*;
+    use crate::prelude::*;
+
+    fn key(i: i32) -> KeyComparison {
+        KeyComparison::Equal(vec1![DfValue::from(i)])
+    }
+
+    #[test]
+    fn expires_after_ttl() {
+        let mut expiry = KeyExpiry::new(Duration::from_secs(10), 0);
+        let now = Instant::now();
+        expiry.filled(key(1), now);
+        expiry.filled(key(2), now + Duration::from_secs(5));
+
+        assert!(expiry.take_expired(now, usize::MAX).is_empty());
+        assert_eq!(
+            expiry.take_expired(now + Duration::from_secs(10), usize::MAX),
+            vec![key(1)]
+        );
+        assert_eq!(
+            expiry.take_expired(now + Duration::from_secs(20), usize::MAX),
+            vec![key(2)]
+        );
+        assert_eq!(expiry.len(), 0);
+    }
+
+    #[test]
+    fn refilled_keys_expire_from_last_fill() {
+        let mut expiry = KeyExpiry::new(Duration::from_secs(10), 0);
+        let now = Instant::now();
+        expiry.filled(key(1), now);
+        expiry.filled(key(1), now + Duration::from_secs(5));
+
+        assert!(expiry
+            .take_expired(now + Duration::from_secs(10), usize::MAX)
+            .is_empty());
+        assert_eq!(
+            expiry.take_expired(now + Duration::from_secs(15), usize::MAX),
+            vec![key(1)]
+        );
+    }
+
+    #[test]
+    fn takes_at_most_max() {
+        let mut expiry = KeyExpiry::new(Duration::from_secs(10), 0);
+        let now = Instant::now();
+        for i in 0..10 {
+            expiry.filled(key(i), now);
+        }
+
+        let later = now + Duration::from_secs(10);
+        assert_eq!(expiry.take_expired(later, 4).len(), 4);
+        assert_eq!(expiry.take_expired(later, 4).len(), 4);
+        assert_eq!(expiry.take_expired(later, 4).len(), 2);
+        assert_eq!(expiry.len(), 0);
+    }
+
+    #[test]
+    fn keys_with_the_same_deadline_expire_in_fill_order() {
+        let mut expiry = KeyExpiry::new(Duration::from_secs(10), 0);
+        let now = Instant::now();
+        for i in 0..5 {
+            expiry.filled(key(i), now);
+        }
+
+        assert_eq!(
+            expiry.take_expired(now + Duration::from_secs(10), usize::MAX),
+            (0..5).map(key).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn next_expired_skips_refilled_keys() {
+        let mut expiry = KeyExpiry::new(Duration::from_secs(10), 0);
+        let now = Instant::now();
+        expiry.filled(key(1), now);
+        expiry.filled(key(2), now + Duration::from_secs(1));
+        expiry.filled(key(1), now + Duration::from_secs(5));
+
+        let later = now + Duration::from_secs(12);
+        assert_eq!(expiry.next_expired(now), None);
+        assert_eq!(
+            expiry.next_expired(later),
+            Some(now + Duration::from_secs(11))
+        );
+        assert_eq!(expiry.take_expired(later, usize::MAX), vec![key(2)]);
+        assert_eq!(expiry.next_expired(later), None);
+    }
+
+    #[test]
+    fn jitter_is_deterministic() {
+        let expiry = KeyExpiry::new(Duration::from_secs(100), 20);
+        for i in 0..10 {
+            assert_eq!(expiry.jittered_ttl(&key(i)), expiry.jittered_ttl(&key(i)));
+        }
+    }
+
+    #[test]
+    fn jitter_spreads_deadlines() {
+        let mut expiry = KeyExpiry::new(Duration::from_secs(100), 20);
+        let now = Instant::now();
+        for i in 0..100 {
+            expiry.filled(key(i), now);
+        }
+
+        assert!(expiry
+            .take_expired(now + Duration::from_secs(79), usize::MAX)
+            .is_empty());
+        let halfway = expiry.take_expired(now + Duration::from_secs(100), usize::MAX);
+        assert!(!halfway.is_empty() && halfway.len() < 100);
+        assert_eq!(
+            expiry
+                .take_expired(now + Duration::from_secs(120), usize::MAX)
+                .len(),
+            100 - halfway.len()
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Read-time joins against small, fully materialized dimension readers.
+//!
+//! For star-schema queries joining one large fact table to many tiny dimension tables, a regular
+//! dataflow join materializes a full copy of every dimension column alongside every fact row in
+//! each downstream reader. A [`LazyJoin`] instead stores only the fact-side rows in the reader, and
+//! looks up the matching dimension rows in a separate (fully materialized) dimension reader when
+//! the fact reader is read from. This trades a little read-time work for a (potentially massive)
+//! reduction in the number of bytes that need to be kept in memory.
+
+use readyset_client::results::{SharedResults, SharedRows};
+use readyset_client::{KeyComparison, ReaderAddress};
+use serde::{Deserialize, Serialize};
+use vec1::Vec1;
+
+use super::{LookupError, SingleReadHandle};
+use crate::ops::join::JoinType;
+use crate::prelude::*;
+
+/// A join against a dimension reader that is performed lazily, when rows are read out of a fact
+/// reader, rather than being materialized in the fact reader itself.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LazyJoin {
+    /// The address of the reader for the dimension side of the join.
+    ///
+    /// This reader must be fully materialized, keyed on exactly the dimension-side join columns,
+    /// and live on the same worker as the fact reader.
+    pub dimension: ReaderAddress,
+    /// Columns of the fact-side rows which make up the lookup key into the dimension reader
+    pub on: Vec<usize>,
+    /// Columns of the dimension rows to append to each fact-side row
+    pub emit: Vec<usize>,
+    /// Whether fact rows without a matching dimension row are dropped ([`JoinType::Inner`]) or
+    /// extended with NULLs ([`JoinType::Left`]). For [`JoinType::Anti`], only the fact rows
+    /// *without* a matching dimension row are returned, and `emit` must be empty.
+    pub kind: JoinType,
+}
+
+impl LazyJoin {
+    /// Join every row in `results` against the given handle to the dimension reader
+    pub fn apply(
+        &self,
+        results: SharedResults,
+        dimension: &SingleReadHandle,
+    ) -> ReadySetResult<SharedResults> {
+        if self.kind == JoinType::Anti && !self.emit.is_empty() {
+            internal!("lazy anti-join emits columns from the dimension reader");
+        }
+
+        results
+            .into_iter()
+            .map(|rows| {
+                let mut joined = Vec::new();
+                for row in rows.iter() {
+                    let matches = self.lookup(row, dimension)?;
+                    let mut matches = matches.iter().flat_map(|rows| rows.iter()).peekable();
+                    if matches.peek().is_none() {
+                        if matches!(self.kind, JoinType::Left | JoinType::Anti) {
+                            joined.push(
+                                row.iter()
+                                    .cloned()
+                                    .chain(self.emit.iter().map(|_| DfValue::None))
+                                    .collect(),
+                            );
+                        }
+                        continue;
+                    }
+                    if self.kind == JoinType::Anti {
+                        continue;
+                    }
+
+                    for other in matches {
+                        let extra = self
+                            .emit
+                            .iter()
+                            .map(|&col| {
+                                other.get(col).cloned().ok_or_else(|| {
+                                    internal_err!("lazy join emits nonexistent column {col}")
+                                })
+                            })
+                            .collect::<ReadySetResult<Vec<_>>>()?;
+                        joined.push(row.iter().cloned().chain(extra).collect());
+                    }
+                }
+                Ok(SharedRows::new(joined.into()))
+            })
+            .collect()
+    }
+
+    /// Look up the dimension rows matching the given fact-side row
+    fn lookup(
+        &self,
+        row: &[DfValue],
+        dimension: &SingleReadHandle,
+    ) -> ReadySetResult<SharedResults> {
+        let key = self
+            .on
+            .iter()
+            .map(|&col| {
+                row.get(col)
+                    .cloned()
+                    .ok_or_else(|| internal_err!("lazy join keys on nonexistent column {col}"))
+            })
+            .collect::<ReadySetResult<Vec<_>>>()?;
+
+        // NULL never compares equal to anything, including NULL
+        if key.iter().any(DfValue::is_none) {
+            return Ok(SharedResults::new());
+        }
+
+        let key =
+            [KeyComparison::Equal(Vec1::try_from(key).map_err(|_| {
+                internal_err!("lazy join has no key columns")
+            })?)];
+        dimension.get_multi(&key).map_err(|e| match e {
+            LookupError::NotReady => ReadySetError::ViewNotYetAvailable,
+            LookupError::Destroyed => ReadySetError::ViewDestroyed,
+            LookupError::Error(e) => e,
+            LookupError::Miss(_) => {
+                internal_err!("lazy join dimension reader {} missed", self.dimension.name)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dataflow_expression::ReaderProcessing;
+    use petgraph::graph::NodeIndex;
+
+    use super::*;
+    use crate::backlog;
+
+    fn dimension() -> (SingleReadHandle, backlog::WriteHandle) {
+        let (r, mut w) = backlog::new(2, Index::hash_map(vec![0]), ReaderProcessing::default());
+        w.add(vec![
+            Record::Positive(vec![1.into(), "one".into()]),
+            Record::Positive(vec![2.into(), "two".into()]),
+            Record::Positive(vec![2.into(), "deux".into()]),
+        ]);
+        w.swap();
+        (r, w)
+    }
+
+    fn join(kind: JoinType) -> LazyJoin {
+        LazyJoin {
+            dimension: ReaderAddress {
+                node: NodeIndex::new(0),
+                name: "dim".into(),
+                shard: 0,
+            },
+            on: vec![1],
+            emit: vec![1],
+            kind,
+        }
+    }
+
+    fn facts() -> SharedResults {
+        let rows = vec![
+            vec![DfValue::from("a"), 1.into()].into_boxed_slice(),
+            vec![DfValue::from("b"), 2.into()].into_boxed_slice(),
+            vec![DfValue::from("c"), 3.into()].into_boxed_slice(),
+            vec![DfValue::from("d"), DfValue::None].into_boxed_slice(),
+        ];
+        SharedResults::from_elem(SharedRows::new(rows.into()), 1)
+    }
+
+    fn rows(results: SharedResults) -> Vec<Vec<DfValue>> {
+        let mut rows = results
+            .iter()
+            .flat_map(|rows| rows.iter().map(|row| row.to_vec()))
+            .collect::<Vec<_>>();
+        rows.sort();
+        rows
+    }
+
+    #[test]
+    fn inner() {
+        let res = join(JoinType::Inner)
+            .apply(facts(), &dimension().0)
+            .unwrap();
+        assert_eq!(
+            rows(res),
+            vec![
+                vec!["a".into(), 1.into(), "one".into()],
+                vec!["b".into(), 2.into(), "deux".into()],
+                vec!["b".into(), 2.into(), "two".into()],
+            ]
+        );
+    }
+
+    #[test]
+    fn left() {
+        let res = join(JoinType::Left).apply(facts(), &dimension().0).unwrap();
+        assert_eq!(
+            rows(res),
+            vec![
+                vec!["a".into(), 1.into(), "one".into()],
+                vec!["b".into(), 2.into(), "deux".into()],
+                vec!["b".into(), 2.into(), "two".into()],
+                vec!["c".into(), 3.into(), DfValue::None],
+                vec!["d".into(), DfValue::None, DfValue::None],
+            ]
+        );
+    }
+
+    #[test]
+    fn anti() {
+        let res = LazyJoin {
+            emit: vec![],
+            ..join(JoinType::Anti)
+        }
+        .apply(facts(), &dimension().0)
+        .unwrap();
+        assert_eq!(
+            rows(res),
+            vec![vec!["c".into(), 3.into()], vec!["d".into(), DfValue::None]]
+        );
+
+        join(JoinType::Anti)
+            .apply(facts(), &dimension().0)
+            .unwrap_err();
+    }
+}
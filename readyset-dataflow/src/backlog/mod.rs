@@ -1,18 +1,34 @@
 use std::borrow::Cow;
 use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::ops::Bound;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 
 use ahash::RandomState;
+use chrono::{DateTime, Utc};
 use common::SizeOf;
-use dataflow_expression::{PostLookup, ReaderProcessing};
+use dataflow_expression::{Expr, PostLookup, ReaderProcessing};
+use metrics::{register_counter, Counter};
+use nom_sql::{CacheResultLimits, ResultLimitPolicy};
 use reader_map::EvictionStrategy;
 use readyset_client::consistency::Timestamp;
-use readyset_client::results::SharedResults;
-use readyset_client::KeyComparison;
+use readyset_client::debug::provenance::{ProvenanceTag, RowProvenance};
+use readyset_client::debug::stats::{ReadStatsBucket, ReaderStats, SizeDistribution};
+use readyset_client::metrics::recorded;
+use readyset_client::results::{ResultIterator, Results, SharedResults, SharedRows};
+use readyset_client::{KeyComparison, ReaderAddress, ViewStats};
 use vec1::Vec1;
 
+use self::hot_keys::HotKeys;
+use self::key_expiry::KeyExpiry;
+pub use self::lazy_join::LazyJoin;
 pub use self::multir::LookupError;
+use self::provenance::Provenance;
+use self::read_history::ReadHistory;
+pub use self::read_history::ReadOutcome;
+use self::time_buckets::TimeBuckets;
+use crate::checksum::{KeyRange, StateChecksum};
 use crate::prelude::*;
 
 /// The kind of reader update notification, currently the eviction epoch of the writer
@@ -25,6 +41,106 @@ pub(crate) type ReaderUpdatedSender = tokio::sync::broadcast::Sender<ReaderNotif
 pub(crate) trait Trigger =
     Fn(&mut dyn Iterator<Item = KeyComparison>) -> bool + 'static + Send + Sync;
 
+/// The point keys for which a replay has been triggered by a reader, but which have not yet been
+/// filled (or evicted), along with the time the replay was triggered. Shared between a
+/// [`WriteHandle`] and all of its [`SingleReadHandle`]s, so that concurrent misses on the same key
+/// only trigger a single upquery.
+type PendingKeys = Arc<Mutex<HashMap<Vec1<DfValue>, Instant, RandomState>>>;
+
+/// How long a replay triggered by a reader can be pending before it's assumed to have been lost
+/// (for example because a domain along the replay path failed), and the next miss on the same key
+/// triggers a new replay rather than waiting on the old one.
+pub const PENDING_REPLAY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Rows which were recently evicted from a partial reader, along with the time they were evicted.
+/// Shared between a [`WriteHandle`] and all of its [`SingleReadHandle`]s, so that misses on those
+/// keys can be answered with the stale rows while the keys are replayed.
+type StaleValues = Arc<Mutex<StaleValuesInner>>;
+
+/// The number of most frequently read keys reported in [`ReaderStats`]
+const HOTTEST_KEYS_REPORTED: usize = 10;
+
+/// The maximum number of keys whose value sizes are measured for [`ReaderStats::value_sizes`], so
+/// that collecting statistics for a large reader doesn't hold up its domain
+const VALUE_SIZES_SAMPLED: usize = 1024;
+
+/// The maximum number of evicted keys whose rows are retained for stale reads by a single reader.
+/// Once this many are retained, the rows evicted longest ago are dropped first.
+const MAX_STALE_KEYS: usize = 65_536;
+
+/// How long writes which are part of a write batch are held back from readers waiting for the rest
+/// of the batch to reach the reader, before they're made visible anyway. This stops a batch whose
+/// commit never completed (for example because the client went away partway through) from holding
+/// back writes to the keys it touched forever.
+pub(crate) const BATCH_HOLD_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Default)]
+struct StaleValuesInner {
+    /// How long evicted rows may be served for. If `None`, evicted rows are not retained at all
+    max_staleness: Option<Duration>,
+    values: HashMap<Vec<DfValue>, (SharedRows, Instant), RandomState>,
+    /// The keys inserted into `values`, in the order they were inserted (and so, in order of when
+    /// they were evicted). May contain keys which have since been removed from `values`, or
+    /// replaced there by a later insert.
+    order: VecDeque<(Vec<DfValue>, Instant)>,
+}
+
+impl StaleValuesInner {
+    fn is_enabled(&self) -> bool {
+        self.max_staleness.is_some()
+    }
+
+    fn insert(&mut self, key: Vec<DfValue>, rows: SharedRows, now: Instant) {
+        if self.is_enabled() {
+            self.values.insert(key.clone(), (rows, now));
+            self.order.push_back((key, now));
+            while self.order.len() > MAX_STALE_KEYS {
+                self.pop_oldest();
+            }
+        }
+    }
+
+    /// Drop the rows for the key which was inserted longest ago, if they haven't been replaced
+    /// since
+    fn pop_oldest(&mut self) {
+        if let Some((key, inserted_at)) = self.order.pop_front() {
+            if self
+                .values
+                .get(&key)
+                .map_or(false, |(_, evicted_at)| *evicted_at == inserted_at)
+            {
+                self.values.remove(&key);
+            }
+        }
+    }
+
+    /// Returns the rows evicted for `key`, if they are no older than the maximum staleness
+    fn get(&self, key: &[DfValue], now: Instant) -> Option<SharedRows> {
+        let max_staleness = self.max_staleness?;
+        self.values
+            .get(key)
+            .filter(|(_, evicted_at)| now.saturating_duration_since(*evicted_at) <= max_staleness)
+            .map(|(rows, _)| rows.clone())
+    }
+
+    /// Drop all rows which are older than the maximum staleness
+    fn prune(&mut self, now: Instant) {
+        match self.max_staleness {
+            Some(max_staleness) => {
+                while self.order.front().map_or(false, |(_, evicted_at)| {
+                    now.saturating_duration_since(*evicted_at) > max_staleness
+                }) {
+                    self.pop_oldest();
+                }
+            }
+            None => {
+                self.values.clear();
+                self.order.clear();
+            }
+        }
+    }
+}
+
 /// Allocate a new end-user facing result table.
 ///
 /// # Invariants:
@@ -137,6 +253,10 @@ fn new_inner(
     };
 
     let (notifier, receiver) = tokio::sync::broadcast::channel(1);
+    let pending = PendingKeys::default();
+    let stale = StaleValues::default();
+    let hot_keys = Arc::new(HotKeys::default());
+    let read_history = Arc::new(ReadHistory::default());
 
     let w = WriteHandle {
         partial: trigger.is_some(),
@@ -147,6 +267,17 @@ fn new_inner(
         mem_size: 0,
         notifier,
         eviction_epoch: 0,
+        pending: pending.clone(),
+        stale: stale.clone(),
+        hot_keys: hot_keys.clone(),
+        read_history: read_history.clone(),
+        held: HeldWrites::default(),
+        base_tables: HashSet::new(),
+        barrier: 0,
+        pending_barrier: None,
+        key_expiry: None,
+        time_buckets: None,
+        provenance: Provenance::default(),
     };
 
     let r = SingleReadHandle {
@@ -154,15 +285,29 @@ fn new_inner(
         trigger,
         index,
         post_lookup: post_processing,
+        lazy_joins: Vec::new(),
+        lazy_join_dimensions: Vec::new(),
+        result_limits: Default::default(),
         receiver,
         eviction_epoch: 0,
+        pending,
+        stale,
+        hot_keys,
+        read_history,
+        coalesced_replays: register_counter!(recorded::SERVER_VIEW_QUERY_COALESCED_REPLAY),
     };
 
     (r, w)
 }
 
+mod hot_keys;
+mod key_expiry;
+mod lazy_join;
 mod multir;
 mod multiw;
+mod provenance;
+mod read_history;
+mod time_buckets;
 
 fn key_to_single(k: Key) -> Cow<DfValue> {
     assert_eq!(k.len(), 1);
@@ -183,6 +328,47 @@ pub(crate) struct WriteHandle {
     notifier: ReaderUpdatedSender,
     /// How many eviction rounds this handle had
     eviction_epoch: usize,
+    /// Point keys that readers have triggered replays for which haven't been filled yet
+    pending: PendingKeys,
+    /// Recently evicted rows, which readers may serve while the evicted keys are replayed
+    stale: StaleValues,
+    /// The most frequently read keys, recorded by readers
+    hot_keys: Arc<HotKeys>,
+    /// Per-minute counters of the reads from this reader, recorded by readers
+    read_history: Arc<ReadHistory>,
+    /// Writes which aren't yet visible to readers, because they're part of (or touch the same keys
+    /// as) a write batch which hasn't yet reached this reader from every base table
+    held: HeldWrites,
+    /// The base tables this reader is derived from. A held write batch is only released once the
+    /// reader's timestamp has reached it for every one of these tables which is in the batch.
+    base_tables: HashSet<LocalNodeIndex>,
+    /// The latest barrier (see [`PacketPayload::Barrier`](readyset_client::PacketPayload)) to have
+    /// reached this reader with every write before it visible to readers
+    barrier: u64,
+    /// The latest barrier to have reached this reader while writes were held, which is only
+    /// recorded in `barrier` once the writes before it are made visible
+    pending_barrier: Option<u64>,
+    /// When each of the keys filled in this reader expires, if keys have a time-to-live
+    key_expiry: Option<KeyExpiry>,
+    /// The filled keys of this reader containing timestamps, by time, if keys are evicted once
+    /// their timestamps get too old. See [`WriteHandle::evict_keys_before`].
+    time_buckets: Option<TimeBuckets>,
+    /// If provenance recording is enabled, where the rows in this reader came from. See
+    /// [`WriteHandle::row_provenance`].
+    provenance: Provenance,
+}
+
+/// Writes held back from readers by a [`WriteHandle`] until the write batches they're part of have
+/// reached the reader from every base table. See [`WriteHandle::add_batch`].
+#[derive(Default)]
+struct HeldWrites {
+    /// The timestamps of the write batches which have had writes reach this reader, but haven't
+    /// yet reached it from every base table they're derived from, and when each was first held
+    batches: Vec<(Timestamp, Instant)>,
+    /// The held records, by the key of the reader they're in. Any write to one of these keys which
+    /// reaches the reader before the batches are released is held too, so that writes to a key
+    /// are still made visible in the order they were made.
+    records: HashMap<Vec<DfValue>, Vec<Record>>,
 }
 
 type Key<'a> = Cow<'a, [DfValue]>;
@@ -223,17 +409,18 @@ impl<'a> MutWriteHandleEntry<'a> {
     }
 
     pub(crate) fn mark_hole(self) {
-        let size = self
-            .handle
-            .handle
-            .read()
-            .get(&self.key)
+        let rows = self.handle.handle.read().get(&self.key).ok();
+        let size = rows
+            .as_ref()
             .map(|rs| rs.iter().map(SizeOf::deep_size_of).sum())
             .unwrap_or(0);
         self.handle.mem_size = self
             .handle
             .mem_size
             .saturating_sub(size as usize + self.key_value_size(&self.key));
+        if let Some(rows) = rows {
+            self.handle.retain_stale(&self.key, rows);
+        }
         self.handle.handle.empty(self.key)
     }
 }
@@ -281,18 +468,209 @@ impl WriteHandle {
         self.handle.read().contains_key(key)
     }
 
+    /// Make all the writes made since the last call to `swap()` visible to readers, except for
+    /// those being held for write batches (see [`add_batch`](Self::add_batch))
     pub(crate) fn swap(&mut self) {
         self.handle.refresh();
+        if self.held.batches.is_empty() {
+            if let Some(barrier) = self.pending_barrier.take() {
+                self.barrier = self.barrier.max(barrier);
+            }
+        }
+    }
+
+    /// Record that `barrier` has reached this reader, and make the writes before it visible. If
+    /// writes are being held for write batches, the barrier is only recorded once they've been
+    /// released and are visible.
+    pub(crate) fn reach_barrier(&mut self, barrier: u64) {
+        self.pending_barrier = Some(self.pending_barrier.map_or(barrier, |b| b.max(barrier)));
+        self.swap();
+    }
+
+    /// Returns the latest barrier to have reached this reader with every write before it visible
+    /// to readers, or 0 if no barrier has yet
+    pub(crate) fn barrier(&self) -> u64 {
+        self.barrier
+    }
+
+    /// Returns the key of this reader that `record` is in
+    fn key_of(&self, record: &[DfValue]) -> Vec<DfValue> {
+        self.index
+            .columns
+            .iter()
+            .map(|&c| record[c].clone())
+            .collect()
+    }
+
+    /// Add records which are part of the write batch with the given timestamp to the backlog.
+    ///
+    /// The records aren't made visible to readers until the batch has been released by
+    /// [`release_batches`](Self::release_batches), since some of the writes in the batch have
+    /// reached this reader but others might not have yet. Until then, later writes to the same
+    /// keys are held too, but writes to other keys are made visible as usual. If the batch isn't
+    /// released within [`BATCH_HOLD_TIMEOUT`], its writes are made visible anyway by
+    /// [`release_timed_out_batches`](Self::release_timed_out_batches).
+    pub(crate) fn add_batch<I>(&mut self, batch: &Timestamp, rs: I)
+    where
+        I: IntoIterator<Item = Record>,
+    {
+        if !self.held.batches.iter().any(|(b, _)| b == batch) {
+            self.held.batches.push((batch.clone(), Instant::now()));
+        }
+        for record in rs {
+            let key = self.key_of(&record);
+            self.held.records.entry(key).or_default().push(record);
+        }
+    }
+
+    /// Set the base tables this reader is derived from (see
+    /// [`release_batches`](Self::release_batches))
+    pub(crate) fn set_base_tables(&mut self, base_tables: HashSet<LocalNodeIndex>) {
+        self.base_tables = base_tables;
+    }
+
+    /// Release all held write batches whose writes have all reached this reader, given the
+    /// reader's new timestamp. Once no batches are held, all held writes are added to the backlog,
+    /// to be made visible by the next call to `swap()`.
+    ///
+    /// A batch is released once `timestamp` is at least the batch's timestamp for every base table
+    /// in the batch which this reader is derived from. Base tables this reader isn't derived from
+    /// are ignored, but base tables it is derived from which are missing from `timestamp` (because
+    /// no timestamp has reached the reader from them yet) keep the batch held.
+    ///
+    /// Readers whose base tables were never set (which can only be readers created before base
+    /// tables were recorded) fall back to only comparing the tables present in `timestamp`.
+    pub(crate) fn release_batches(&mut self, timestamp: &Timestamp) {
+        if self.held.batches.is_empty() {
+            return;
+        }
+
+        let base_tables = &self.base_tables;
+        self.held.batches.retain(|(batch, _)| {
+            if base_tables.is_empty() {
+                return !timestamp.reflects_snapshot(batch);
+            }
+            !batch
+                .map
+                .iter()
+                .filter(|(table, _)| base_tables.contains(table))
+                .all(|(table, batch_timestamp)| {
+                    timestamp
+                        .map
+                        .get(table)
+                        .map_or(false, |timestamp| timestamp >= batch_timestamp)
+                })
+        });
+        self.add_released();
+    }
+
+    /// Returns when the write batch which has been held the longest times out, if any are held
+    /// (see [`BATCH_HOLD_TIMEOUT`])
+    pub(crate) fn next_batch_timeout(&self) -> Option<Instant> {
+        self.held
+            .batches
+            .iter()
+            .map(|(_, since)| *since + BATCH_HOLD_TIMEOUT)
+            .min()
+    }
+
+    /// Release all write batches which have been held for longer than [`BATCH_HOLD_TIMEOUT`] at
+    /// `now`, returning the number of batches released. If that leaves no batches held, the held
+    /// writes are added to the backlog, to be made visible by the next call to `swap()`.
+    pub(crate) fn release_timed_out_batches(&mut self, now: Instant) -> usize {
+        let before = self.held.batches.len();
+        self.held
+            .batches
+            .retain(|(_, since)| now.saturating_duration_since(*since) < BATCH_HOLD_TIMEOUT);
+        let released = before - self.held.batches.len();
+        if released > 0 {
+            self.add_released();
+        }
+        released
+    }
+
+    /// If no write batches are held any more, add all the held writes to the backlog
+    fn add_released(&mut self) {
+        if self.held.batches.is_empty() && !self.held.records.is_empty() {
+            let records = std::mem::take(&mut self.held.records);
+            self.add_now(records.into_values().flatten());
+        }
+    }
+
+    /// Drop the writes held for keys covered by `key`, which is being evicted. A replay which
+    /// refills the key will already reflect them.
+    fn drop_held(&mut self, key: &KeyComparison) {
+        if self.held.records.is_empty() {
+            return;
+        }
+        match key {
+            KeyComparison::Equal(k) => {
+                self.held.records.remove(k.as_vec());
+            }
+            KeyComparison::Range(_) => self.held.records.retain(|k, _| !key.contains(k)),
+        }
     }
 
     pub(crate) fn len(&self) -> usize {
         self.handle.read().len()
     }
 
+    /// Returns the number of keys and rows stored in this shard of the reader
+    pub(crate) fn stats(&self) -> ViewStats {
+        let handle = self.handle.read();
+        ViewStats {
+            keys: handle.len(),
+            rows: handle.row_count(),
+            partial: self.partial,
+        }
+    }
+
+    /// Returns the checksum of the rows in this reader with keys in `range`
+    pub(crate) fn checksum(&self, range: KeyRange) -> StateChecksum {
+        let checksums = self.handle.read().key_checksums(range);
+        if self.partial {
+            StateChecksum::Partial(
+                checksums
+                    .into_iter()
+                    .map(|(key, _, checksum)| (key, checksum))
+                    .collect(),
+            )
+        } else {
+            let (rows, checksum) = checksums.into_iter().fold(
+                (0, 0u64),
+                |(rows, checksum), (_, key_rows, key_checksum)| {
+                    (rows + key_rows, checksum.wrapping_add(key_checksum))
+                },
+            );
+            StateChecksum::Full { rows, checksum }
+        }
+    }
+
     /// Add a new set of records to the backlog.
     ///
-    /// These will be made visible to readers after the next call to `swap()`.
+    /// These will be made visible to readers after the next call to `swap()`, unless they're to
+    /// keys which have writes held for a write batch (see [`add_batch`](Self::add_batch)), in which
+    /// case they're held until the batch is released.
     pub(crate) fn add<I>(&mut self, rs: I)
+    where
+        I: IntoIterator<Item = Record>,
+    {
+        if self.held.records.is_empty() {
+            return self.add_now(rs);
+        }
+        let mut now = Vec::new();
+        for record in rs {
+            let key = self.key_of(&record);
+            match self.held.records.get_mut(&key) {
+                Some(held) => held.push(record),
+                None => now.push(record),
+            }
+        }
+        self.add_now(now);
+    }
+
+    /// Add a new set of records to the backlog, regardless of whether any writes are held
+    fn add_now<I>(&mut self, rs: I)
     where
         I: IntoIterator<Item = Record>,
     {
@@ -319,6 +697,7 @@ impl WriteHandle {
     /// these keys may not have exactly `bytes` worth of state.
     pub(crate) fn evict_bytes(&mut self, bytes: usize) -> u64 {
         let mut bytes_to_be_freed = 0;
+        let mut provenance_freed = 0;
         if self.mem_size > 0 {
             debug_assert!(
                 !self.handle.is_empty(),
@@ -326,17 +705,43 @@ impl WriteHandle {
                 self.mem_size
             );
 
-            bytes_to_be_freed += self.handle.evict(bytes as f64 / self.mem_size as f64);
+            let ratio = bytes as f64 / (self.mem_size + self.provenance.size()) as f64;
+            #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+            let mut stale = self.stale.lock().unwrap();
+            let retain_stale = stale.is_enabled();
+            let on_evict_needed =
+                retain_stale || !self.provenance.is_empty() || !self.held.records.is_empty();
+            bytes_to_be_freed += if on_evict_needed {
+                let now = Instant::now();
+                if retain_stale {
+                    stale.prune(now);
+                }
+                let provenance = &mut self.provenance;
+                let held = &mut self.held.records;
+                let mut on_evict = |key: Vec<DfValue>, rows: SharedRows| {
+                    provenance_freed += provenance.remove_key(&key);
+                    held.remove(&key);
+                    if retain_stale {
+                        stale.insert(key, rows, now);
+                    }
+                };
+                self.handle.evict(ratio, Some(&mut on_evict))
+            } else {
+                self.handle.evict(ratio, None)
+            };
         }
 
         self.mem_size = self.mem_size.saturating_sub(bytes_to_be_freed as usize);
-        bytes_to_be_freed
+        bytes_to_be_freed + provenance_freed as u64
     }
 
     pub(crate) fn mark_hole(&mut self, key: &KeyComparison) -> ReadySetResult<()> {
         if let Some(len) = key.len() {
             invariant_eq!(len, self.index.len());
         }
+        self.clear_pending(key);
+        self.provenance.remove_keys(key);
+        self.drop_held(key);
         match key {
             KeyComparison::Equal(k) => self.mut_with_key(k.as_vec()).mark_hole(),
             KeyComparison::Range((start, end)) => {
@@ -366,10 +771,56 @@ impl WriteHandle {
         Ok(())
     }
 
+    /// Set how old the timestamps in keys of this reader may get before the keys are evicted. If
+    /// `None`, keys are never evicted because of the timestamps they contain.
+    pub(crate) fn set_bucket_retention(&mut self, bucket_retention: Option<Duration>) {
+        self.time_buckets = bucket_retention.map(TimeBuckets::new);
+    }
+
+    /// Returns how old the timestamps in keys of this reader may get before the keys are evicted
+    pub(crate) fn bucket_retention(&self) -> Option<Duration> {
+        self.time_buckets.as_ref().map(TimeBuckets::retention)
+    }
+
+    /// Evict every key in this reader which contains a timestamp earlier than `cutoff`, returning
+    /// the keys evicted. Used to drop time buckets which have fallen out of the [retention
+    /// window](WriteHandle::set_bucket_retention) of the reader.
+    ///
+    /// Timestamps without a timezone are taken to be in the local timezone.
+    pub(crate) fn evict_keys_before(
+        &mut self,
+        cutoff: DateTime<Utc>,
+    ) -> ReadySetResult<Vec<KeyComparison>> {
+        let mut expired = match &mut self.time_buckets {
+            Some(time_buckets) => time_buckets.take_before(cutoff),
+            None => return Ok(vec![]),
+        };
+        // The key may have already been evicted to free memory
+        expired.retain(|key| self.contains(key).unwrap_or(false));
+        for key in &expired {
+            self.mark_hole(key)?;
+        }
+        Ok(expired)
+    }
+
     pub(crate) fn mark_filled(&mut self, key: KeyComparison) -> ReadySetResult<()> {
+        let expiring = self.key_expiry.is_some().then(|| key.clone());
+        if let Some(time_buckets) = &mut self.time_buckets {
+            time_buckets.filled(&key);
+        }
+        self.fill(key)?;
+        if let (Some(key_expiry), Some(key)) = (&mut self.key_expiry, expiring) {
+            key_expiry.filled(key, Instant::now());
+        }
+        Ok(())
+    }
+
+    fn fill(&mut self, key: KeyComparison) -> ReadySetResult<()> {
         if let Some(len) = key.len() {
             invariant_eq!(len, self.index.len());
         }
+        self.clear_pending(&key);
+        self.clear_stale(&key);
 
         #[allow(clippy::unreachable)] // Documented invariant.
         let range = match (self.index.index_type, &key) {
@@ -397,8 +848,133 @@ impl WriteHandle {
         Ok(())
     }
 
+    /// Remove any pending replays covered by `key`, so that subsequent misses on those keys will
+    /// trigger a new replay
+    pub(crate) fn clear_pending(&self, key: &KeyComparison) {
+        #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+        let mut pending = self.pending.lock().unwrap();
+        if pending.is_empty() {
+            return;
+        }
+        match key {
+            KeyComparison::Equal(k) => {
+                pending.remove(k);
+            }
+            KeyComparison::Range(_) => pending.retain(|k, _| !key.contains(k)),
+        }
+    }
+
+    /// Set how long keys filled in this reader are kept before they're evicted, randomly varied by
+    /// up to `jitter_percent` percent of `ttl` for each key. If `None`, keys don't expire.
+    pub(crate) fn set_key_ttl(&mut self, ttl: Option<Duration>, jitter_percent: u8) {
+        self.key_expiry = ttl.map(|ttl| KeyExpiry::new(ttl, jitter_percent));
+    }
+
+    /// Returns the deadline of the key in this reader which expired earliest at or before `now`,
+    /// if any keys have expired (see [`WriteHandle::set_key_ttl`])
+    pub(crate) fn next_expired_key(&mut self, now: Instant) -> Option<Instant> {
+        self.key_expiry.as_mut()?.next_expired(now)
+    }
+
+    /// Evict up to `max` of the keys in this reader whose time-to-live (see
+    /// [`WriteHandle::set_key_ttl`]) has passed by `now`, returning the number of keys evicted.
+    pub(crate) fn expire_keys(&mut self, now: Instant, max: usize) -> ReadySetResult<usize> {
+        let expired = match &mut self.key_expiry {
+            Some(key_expiry) => key_expiry.take_expired(now, max),
+            None => return Ok(0),
+        };
+        let mut evicted = 0;
+        for key in expired {
+            // The key may have already been evicted to free memory
+            if self.contains(&key).unwrap_or(false) {
+                self.mark_hole(&key)?;
+                evicted += 1;
+            }
+        }
+        Ok(evicted)
+    }
+
+    /// Set how long rows evicted from this reader may be served to readers while the evicted keys
+    /// are replayed. If `None`, evicted rows are discarded immediately.
+    pub(crate) fn set_max_staleness(&mut self, max_staleness: Option<Duration>) {
+        #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+        let mut stale = self.stale.lock().unwrap();
+        stale.max_staleness = max_staleness;
+        stale.prune(Instant::now());
+    }
+
+    /// Retain the rows that were just removed from `key`, if stale reads are enabled
+    fn retain_stale(&self, key: &[DfValue], rows: SharedRows) {
+        #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+        let mut stale = self.stale.lock().unwrap();
+        if stale.is_enabled() {
+            let now = Instant::now();
+            stale.prune(now);
+            stale.insert(key.to_vec(), rows, now);
+        }
+    }
+
+    /// Remove any stale rows covered by `key`, which is about to be filled with fresh rows
+    fn clear_stale(&self, key: &KeyComparison) {
+        #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+        let mut stale = self.stale.lock().unwrap();
+        if stale.values.is_empty() {
+            return;
+        }
+        match key {
+            KeyComparison::Equal(k) => {
+                stale.values.remove(k.as_vec());
+            }
+            KeyComparison::Range(_) => stale.values.retain(|k, _| !key.contains(k)),
+        }
+    }
+
+    /// Record that the rows in `records`, which are about to be added to this reader, were written
+    /// by the update (or replay, if `replay` is true) with the given provenance `tags`
+    pub(crate) fn record_provenance(
+        &mut self,
+        records: &Records,
+        tags: &Arc<[ProvenanceTag]>,
+        replay: bool,
+    ) {
+        self.provenance
+            .record(&self.index.columns, records, tags, replay);
+    }
+
+    /// Returns the recorded provenance of every row in this reader whose key is equal to `key`, if
+    /// provenance recording is enabled
+    pub(crate) fn row_provenance(&self, key: &[DfValue]) -> Vec<RowProvenance> {
+        self.provenance.get(key)
+    }
+
+    /// Collect statistics about the keys stored in and read from this reader
+    pub(crate) fn reader_stats(&self) -> ReaderStats {
+        let handle = self.handle.read();
+        ReaderStats {
+            key_count: handle.len() as u64,
+            hottest_keys: self.hot_keys.top(HOTTEST_KEYS_REPORTED),
+            value_sizes: SizeDistribution::from_sizes(handle.value_sizes(VALUE_SIZES_SAMPLED)),
+            read_history: self.read_history(SystemTime::now()),
+        }
+    }
+
+    /// Returns the per-minute counters of the reads from this reader over the 24 hours up to
+    /// `now`, oldest first
+    pub(crate) fn read_history(&self, now: SystemTime) -> Vec<ReadStatsBucket> {
+        self.read_history.snapshot(now)
+    }
+
+    /// Forget the read counts of all keys, when the domain's statistics are reset
+    pub(crate) fn reset_read_counts(&self) {
+        self.hot_keys.clear();
+    }
+
     /// Increment the eviction epoch, and notify readers
     pub(crate) fn notify_readers_of_eviction(&mut self) -> ReadySetResult<()> {
+        // Readers waiting on evicted keys will retrigger their replays, so make sure those don't
+        // get coalesced onto replays that may never fill
+        #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+        self.pending.lock().unwrap().clear();
         self.eviction_epoch += 1;
         self.notify_readers()
     }
@@ -412,6 +988,46 @@ impl WriteHandle {
     }
 }
 
+/// If a query would return more than `max_rows` rows for the (ordered) `rows` stored for a single
+/// key, returns the number of leading rows which produce the first `max_rows` returned rows.
+///
+/// Only rows which pass `filter` are returned, and if the query aggregates its rows then a row is
+/// only returned for each run of rows with the same values in the `group_by` columns.
+fn returned_rows_end(
+    rows: &[Box<[DfValue]>],
+    max_rows: usize,
+    group_by: Option<&[usize]>,
+    filter: Option<&Expr>,
+) -> Option<usize> {
+    let mut returned = 0;
+    let mut last_returned: Option<&[DfValue]> = None;
+    for (i, row) in rows.iter().enumerate() {
+        if let Some(filter) = filter {
+            if !filter
+                .eval(&row[..])
+                .map(|r| r.is_truthy())
+                .unwrap_or(false)
+            {
+                continue;
+            }
+        }
+        let new_row = match (group_by, last_returned) {
+            (Some(group_by), Some(last)) => {
+                group_by.iter().any(|&col| last.get(col) != row.get(col))
+            }
+            _ => true,
+        };
+        last_returned = Some(&row[..]);
+        if new_row {
+            returned += 1;
+            if returned > max_rows {
+                return Some(i);
+            }
+        }
+    }
+    None
+}
+
 impl SizeOf for WriteHandle {
     fn size_of(&self) -> u64 {
         use std::mem::size_of;
@@ -420,7 +1036,7 @@ impl SizeOf for WriteHandle {
     }
 
     fn deep_size_of(&self) -> u64 {
-        self.mem_size as u64
+        (self.mem_size + self.provenance.size()) as u64
     }
 
     fn is_empty(&self) -> bool {
@@ -434,10 +1050,29 @@ pub struct SingleReadHandle {
     trigger: Option<Arc<dyn Trigger>>,
     index: Index,
     pub post_lookup: PostLookup,
-    /// Receives a notification whenever the [`WriteHandle`] is updated after filling an upquery
+    /// Joins against dimension readers to perform on rows after they're looked up, before
+    /// `post_lookup`
+    pub lazy_joins: Vec<LazyJoin>,
+    /// Handles to the dimension readers of `lazy_joins`, in the same order, once they've been
+    /// resolved by [`Self::apply_lazy_joins`]
+    lazy_join_dimensions: Vec<SingleReadHandle>,
+    /// Limits on the size of the rows returned from a single lookup
+    pub result_limits: CacheResultLimits,
+    /// Receives a notification whenever the [`WriteHandle`] is updated after processing writes or
+    /// filling an upquery
     receiver: ReaderUpdatedNotifier,
     /// Caches the eviction epoch of the associated [`WriteHandle`]
     eviction_epoch: usize,
+    /// Point keys with in-flight replays, shared with the associated [`WriteHandle`]
+    pending: PendingKeys,
+    /// Recently evicted rows, shared with the associated [`WriteHandle`]
+    stale: StaleValues,
+    /// The most frequently read keys, shared with the associated [`WriteHandle`]
+    hot_keys: Arc<HotKeys>,
+    /// Per-minute counters of reads, shared with the associated [`WriteHandle`]
+    read_history: Arc<ReadHistory>,
+    /// Counts misses that were coalesced onto an already in-flight replay
+    coalesced_replays: Counter,
 }
 
 impl Clone for SingleReadHandle {
@@ -447,8 +1082,16 @@ impl Clone for SingleReadHandle {
             trigger: self.trigger.clone(),
             index: self.index.clone(),
             post_lookup: self.post_lookup.clone(),
+            lazy_joins: self.lazy_joins.clone(),
+            lazy_join_dimensions: self.lazy_join_dimensions.clone(),
+            result_limits: self.result_limits,
             receiver: self.receiver.resubscribe(),
             eviction_epoch: self.eviction_epoch,
+            pending: self.pending.clone(),
+            stale: self.stale.clone(),
+            hot_keys: self.hot_keys.clone(),
+            read_history: self.read_history.clone(),
+            coalesced_replays: self.coalesced_replays.clone(),
         }
     }
 }
@@ -465,6 +1108,11 @@ impl std::fmt::Debug for SingleReadHandle {
 
 impl SingleReadHandle {
     /// Trigger a replay of a missing key from a partially materialized view.
+    ///
+    /// Point keys which already have a replay in flight (triggered by a previous, possibly
+    /// concurrent, miss on this reader) are not requested again - the caller will be notified when
+    /// the original replay fills the key, along with every other waiter. Replays which have been in
+    /// flight for longer than [`PENDING_REPLAY_TIMEOUT`] are requested again.
     pub fn trigger<I>(&self, keys: I) -> bool
     where
         I: Iterator<Item = KeyComparison>,
@@ -474,10 +1122,50 @@ impl SingleReadHandle {
             "tried to trigger a replay for a fully materialized view"
         );
 
-        let mut it = keys;
+        let keys = {
+            #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+            let mut pending = self.pending.lock().unwrap();
+            let mut coalesced = 0;
+            let keys = keys
+                .filter(|key| match key {
+                    KeyComparison::Equal(k) => match pending.get_mut(k) {
+                        Some(triggered_at) if triggered_at.elapsed() < PENDING_REPLAY_TIMEOUT => {
+                            coalesced += 1;
+                            false
+                        }
+                        Some(triggered_at) => {
+                            *triggered_at = Instant::now();
+                            true
+                        }
+                        None => {
+                            pending.insert(k.clone(), Instant::now());
+                            true
+                        }
+                    },
+                    _ => true,
+                })
+                .collect::<Vec<_>>();
+            if coalesced > 0 {
+                self.coalesced_replays.increment(coalesced);
+            }
+            keys
+        };
+        let mut it = keys.iter().cloned();
 
         // trigger a replay to populate
-        (*self.trigger.as_ref().unwrap())(&mut it)
+        let triggered = (*self.trigger.as_ref().unwrap())(&mut it);
+        if !triggered {
+            // The replay was never requested, so nothing will ever fill these keys - don't leave
+            // later misses waiting on them
+            #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+            let mut pending = self.pending.lock().unwrap();
+            for key in &keys {
+                if let KeyComparison::Equal(k) = key {
+                    pending.remove(k);
+                }
+            }
+        }
+        triggered
     }
 
     /// Returns None if this handle is not ready, Some(true) if this handle fully contains the given
@@ -505,10 +1193,13 @@ impl SingleReadHandle {
 
     /// Lookup a list of keys under the same reader guard. If missed, will include a notifier that
     /// can tell us when a new hole was filled in the map.
+    ///
+    /// Point keys looked up with this method count towards the reader's most frequently read keys.
     pub fn get_multi_with_notifier<'a>(
         &self,
         keys: &'a [KeyComparison],
     ) -> Result<SharedResults, LookupError<'a, ReaderUpdatedNotifier>> {
+        self.record_reads(keys);
         match self
             .handle
             .get_multi_and_map_error(keys, || self.receiver.resubscribe())
@@ -518,6 +1209,149 @@ impl SingleReadHandle {
         }
     }
 
+    /// Count a read of each of the point keys in `keys`
+    fn record_reads(&self, keys: &[KeyComparison]) {
+        for key in keys {
+            if let KeyComparison::Equal(k) = key {
+                self.hot_keys.record(k.as_vec());
+            }
+        }
+    }
+
+    /// Record a read from this reader which took `latency` to answer with the given `outcome` in
+    /// the per-minute read history of the reader
+    pub fn record_read(&self, latency: Duration, outcome: ReadOutcome) {
+        self.read_history
+            .record(SystemTime::now(), latency, outcome);
+    }
+
+    /// Lookup a list of keys, answering misses on point keys with the rows those keys held before
+    /// they were evicted, as long as those rows are no older than the maximum staleness configured
+    /// for this reader.
+    ///
+    /// Returns `None` if stale reads are disabled for this reader, or if any key misses and has no
+    /// sufficiently fresh stale rows.
+    pub fn get_multi_or_stale(&self, keys: &[KeyComparison]) -> Option<SharedResults> {
+        #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+        let stale = self.stale.lock().unwrap();
+        if !stale.is_enabled() {
+            return None;
+        }
+
+        let now = Instant::now();
+        let mut results = SharedResults::with_capacity(keys.len());
+        for key in keys {
+            match self.handle.get_multi(std::slice::from_ref(key)) {
+                Ok(rows) => results.extend(rows),
+                Err(e) if e.is_miss() => match key {
+                    KeyComparison::Equal(k) => results.push(stale.get(k.as_vec(), now)?),
+                    KeyComparison::Range(_) => return None,
+                },
+                Err(_) => return None,
+            }
+        }
+        Some(results)
+    }
+
+    /// Enforce this reader's [`CacheResultLimits::max_rows_per_key`] on the rows just looked up
+    /// from it, before its [`PostLookup`] operations merge the rows of all the keys together.
+    ///
+    /// A key only exceeds the limit if the query would return more than that many rows for it on
+    /// its own, so rows which don't pass `filter`, rows past the query's own `LIMIT`, and rows
+    /// which are aggregated into a single group don't count towards it. When truncating, the rows
+    /// of a key which exceeds the limit are cut off at the first row that would be returned past
+    /// the limit, which keeps the key's first rows in the query's order.
+    ///
+    /// Returns the rows which fit within the limit, along with whether any rows had to be dropped,
+    /// or an error if the limit is exceeded and the reader is configured to error rather than
+    /// truncate.
+    pub fn limit_rows_per_key(
+        &self,
+        results: SharedResults,
+        filter: Option<&Expr>,
+    ) -> ReadySetResult<(SharedResults, bool)> {
+        let max_rows = match self.result_limits.max_rows_per_key {
+            Some(max_rows) => max_rows as usize,
+            None => return Ok((results, false)),
+        };
+        if self
+            .post_lookup
+            .limit
+            .map_or(false, |limit| limit <= max_rows)
+            || results.iter().all(|rows| rows.len() <= max_rows)
+        {
+            return Ok((results, false));
+        }
+
+        let group_by = self
+            .post_lookup
+            .aggregates
+            .as_ref()
+            .map(|aggregates| aggregates.group_by.as_slice());
+        let mut truncated = false;
+        let mut limited = SharedResults::with_capacity(results.len());
+        for rows in results {
+            let end = match returned_rows_end(&rows, max_rows, group_by, filter) {
+                Some(end) => end,
+                None => {
+                    limited.push(rows);
+                    continue;
+                }
+            };
+            if self.result_limits.on_exceeded == ResultLimitPolicy::Error {
+                return Err(ReadySetError::ResultSetTooLarge(format!(
+                    "more than {} rows for a single key",
+                    max_rows
+                )));
+            }
+            truncated = true;
+            limited.push(SharedRows::new(rows.iter().take(end).cloned().collect()));
+        }
+
+        Ok((limited, truncated))
+    }
+
+    /// Enforce this reader's [`CacheResultLimits::max_result_bytes`] on the results of a read
+    /// from it, after its [`PostLookup`] operations (ORDER BY, LIMIT, aggregates and filters)
+    /// have been applied, so that only rows the query actually returns count towards the limit.
+    ///
+    /// Returns the rows which fit within the limit, along with whether any rows had to be dropped,
+    /// or an error if the limit is exceeded and the reader is configured to error rather than
+    /// truncate.
+    pub fn limit_result_bytes(
+        &self,
+        results: ResultIterator,
+    ) -> ReadySetResult<(ResultIterator, bool)> {
+        let limits = &self.result_limits;
+        let max_bytes = match limits.max_result_bytes {
+            Some(max_bytes) => max_bytes,
+            None => return Ok((results, false)),
+        };
+
+        let mut truncated = false;
+        let mut total_bytes = 0;
+        let mut limited = Vec::new();
+        for row in results {
+            total_bytes += row.deep_size_of();
+            if total_bytes > max_bytes {
+                if limits.on_exceeded == ResultLimitPolicy::Error {
+                    return Err(ReadySetError::ResultSetTooLarge(format!(
+                        "result is larger than {} bytes",
+                        max_bytes
+                    )));
+                }
+                truncated = true;
+                break;
+            }
+            limited.push(row);
+        }
+
+        Ok((
+            ResultIterator::owned(vec![Results::new(limited)]),
+            truncated,
+        ))
+    }
+
     pub fn len(&self) -> usize {
         self.handle.len()
     }
@@ -526,8 +1360,18 @@ impl SingleReadHandle {
         self.handle.len() == 0
     }
 
-    pub fn keys(&self) -> Vec<Vec<DfValue>> {
-        self.handle.keys()
+    /// Returns the keys stored in this shard of the reader which fall in `range`
+    pub fn keys(&self, range: KeyRange) -> Vec<Vec<DfValue>> {
+        self.handle.keys(range)
+    }
+
+    /// Returns the number of keys and rows stored in this shard of the reader
+    pub fn stats(&self) -> ViewStats {
+        ViewStats {
+            keys: self.handle.len(),
+            rows: self.handle.row_count(),
+            partial: self.trigger.is_some(),
+        }
     }
 
     pub fn timestamp(&self) -> Option<Timestamp> {
@@ -539,6 +1383,47 @@ impl SingleReadHandle {
         self.handle.was_dropped()
     }
 
+    /// Performs the [`LazyJoin`]s configured on this reader (if any) on the rows that were just
+    /// looked up from it.
+    ///
+    /// Handles to the dimension readers are kept with this handle once they've been looked up, so
+    /// `resolve` (which looks up a reader by its address) is only called the first time the joins
+    /// are performed, or if one of the dimension readers has since been dropped.
+    pub fn apply_lazy_joins<F>(
+        &mut self,
+        hit: SharedResults,
+        mut resolve: F,
+    ) -> ReadySetResult<SharedResults>
+    where
+        F: FnMut(&ReaderAddress) -> ReadySetResult<SingleReadHandle>,
+    {
+        if self.lazy_joins.is_empty() {
+            return Ok(hit);
+        }
+
+        if self.lazy_join_dimensions.len() != self.lazy_joins.len()
+            || self.lazy_join_dimensions.iter().any(|d| d.was_dropped())
+        {
+            self.lazy_join_dimensions = self
+                .lazy_joins
+                .iter()
+                .map(|join| resolve(&join.dimension))
+                .collect::<ReadySetResult<_>>()?;
+        }
+
+        self.lazy_joins
+            .iter()
+            .zip(&self.lazy_join_dimensions)
+            .try_fold(hit, |hit, (join, dimension)| join.apply(hit, dimension))
+    }
+
+    /// Returns a new [`ReaderUpdatedNotifier`] which will receive a notification the next time the
+    /// associated [`WriteHandle`] makes new data (from writes, upquery fills, or evictions) visible
+    /// to readers
+    pub fn updates(&self) -> ReaderUpdatedNotifier {
+        self.receiver.resubscribe()
+    }
+
     pub fn eviction_epoch(&mut self) -> usize {
         while !self.receiver.is_empty() {
             if let Ok(epoch) = self.receiver.try_recv() {
@@ -555,6 +1440,7 @@ impl SingleReadHandle {
 mod tests {
     use std::ops::Bound;
 
+    use chrono::TimeZone;
     use readyset_client::results::SharedRows;
 
     use super::*;
@@ -591,6 +1477,191 @@ mod tests {
         assert_eq!(r.get(&a[0..1]).unwrap()[0], a);
     }
 
+    #[test]
+    fn held_batches_are_not_visible() {
+        let a = vec![1i32.into(), "a".into()].into_boxed_slice();
+        let b = vec![1i32.into(), "b".into()].into_boxed_slice();
+        let c = vec![2i32.into(), "c".into()].into_boxed_slice();
+        let batch = Timestamp {
+            map: HashMap::from([(LocalNodeIndex::make(0), 2), (LocalNodeIndex::make(1), 2)]),
+        };
+
+        let (r, mut w) = new(2, Index::hash_map(vec![0]), ReaderProcessing::default());
+        w.set_base_tables(HashSet::from([
+            LocalNodeIndex::make(0),
+            LocalNodeIndex::make(1),
+        ]));
+        w.swap();
+
+        w.add_batch(&batch, vec![Record::Positive(a.to_vec())]);
+        w.swap();
+        assert_eq!(r.get(&a[0..1]).unwrap().len(), 0);
+
+        // Only one of the tables in the batch has reached the reader. Later writes to the same
+        // key are held too, but writes to other keys aren't.
+        w.release_batches(&Timestamp {
+            map: HashMap::from([(LocalNodeIndex::make(0), 2), (LocalNodeIndex::make(1), 1)]),
+        });
+        w.add(vec![
+            Record::Positive(b.to_vec()),
+            Record::Positive(c.to_vec()),
+        ]);
+        w.swap();
+        assert_eq!(r.get(&a[0..1]).unwrap().len(), 0);
+        assert_eq!(r.get(&c[0..1]).unwrap().len(), 1);
+
+        w.release_batches(&batch);
+        w.swap();
+        assert_eq!(r.get(&a[0..1]).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn held_batches_time_out() {
+        let a = vec![1i32.into(), "a".into()].into_boxed_slice();
+        let batch = Timestamp {
+            map: HashMap::from([(LocalNodeIndex::make(0), 2)]),
+        };
+
+        let (r, mut w) = new(2, Index::hash_map(vec![0]), ReaderProcessing::default());
+        w.set_base_tables(HashSet::from([LocalNodeIndex::make(0)]));
+        w.swap();
+
+        let now = Instant::now();
+        w.add_batch(&batch, vec![Record::Positive(a.to_vec())]);
+        let timeout = w.next_batch_timeout().unwrap();
+        assert!(timeout >= now + BATCH_HOLD_TIMEOUT);
+
+        assert_eq!(w.release_timed_out_batches(now), 0);
+        w.swap();
+        assert_eq!(r.get(&a[0..1]).unwrap().len(), 0);
+
+        assert_eq!(w.release_timed_out_batches(timeout), 1);
+        w.swap();
+        assert_eq!(r.get(&a[0..1]).unwrap().len(), 1);
+        assert!(w.next_batch_timeout().is_none());
+    }
+
+    #[test]
+    fn held_writes_are_dropped_on_eviction() {
+        let a = vec![1i32.into(), "a".into()].into_boxed_slice();
+        let batch = Timestamp {
+            map: HashMap::from([(LocalNodeIndex::make(0), 2)]),
+        };
+
+        let (r, mut w) = new_partial(
+            2,
+            Index::hash_map(vec![0]),
+            |_: &mut dyn Iterator<Item = KeyComparison>| true,
+            EvictionKind::Random,
+            ReaderProcessing::default(),
+        );
+        w.set_base_tables(HashSet::from([LocalNodeIndex::make(0)]));
+        let key = KeyComparison::Equal(vec1![1i32.into()]);
+        w.mark_filled(key.clone()).unwrap();
+        w.swap();
+
+        w.add_batch(&batch, vec![Record::Positive(a.to_vec())]);
+        w.mark_hole(&key).unwrap();
+
+        // A replay refilling the key already reflects the held write
+        w.mark_filled(key).unwrap();
+        w.add(vec![Record::Positive(a.to_vec())]);
+        w.release_batches(&batch);
+        w.swap();
+        assert_eq!(r.get(&a[0..1]).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn held_batches_wait_for_missing_tables() {
+        let a = vec![1i32.into(), "a".into()].into_boxed_slice();
+        let batch = Timestamp {
+            map: HashMap::from([(LocalNodeIndex::make(0), 2), (LocalNodeIndex::make(1), 2)]),
+        };
+
+        let (r, mut w) = new(2, Index::hash_map(vec![0]), ReaderProcessing::default());
+        w.set_base_tables(HashSet::from([
+            LocalNodeIndex::make(0),
+            LocalNodeIndex::make(1),
+        ]));
+        w.swap();
+
+        w.add_batch(&batch, vec![Record::Positive(a.to_vec())]);
+
+        // No timestamp has reached the reader from table 1 yet
+        w.release_batches(&Timestamp {
+            map: HashMap::from([(LocalNodeIndex::make(0), 2)]),
+        });
+        w.swap();
+        assert_eq!(r.get(&a[0..1]).unwrap().len(), 0);
+
+        w.release_batches(&Timestamp::default());
+        w.swap();
+        assert_eq!(r.get(&a[0..1]).unwrap().len(), 0);
+
+        w.release_batches(&batch);
+        w.swap();
+        assert_eq!(r.get(&a[0..1]).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn held_batches_ignore_tables_not_derived_from() {
+        let a = vec![1i32.into(), "a".into()].into_boxed_slice();
+        let batch = Timestamp {
+            map: HashMap::from([
+                (LocalNodeIndex::make(0), 2),
+                (LocalNodeIndex::make(1), 2),
+                (LocalNodeIndex::make(2), 2),
+            ]),
+        };
+
+        let (r, mut w) = new(2, Index::hash_map(vec![0]), ReaderProcessing::default());
+        w.set_base_tables(HashSet::from([
+            LocalNodeIndex::make(0),
+            LocalNodeIndex::make(1),
+        ]));
+        w.swap();
+
+        w.add_batch(&batch, vec![Record::Positive(a.to_vec())]);
+
+        w.release_batches(&Timestamp {
+            map: HashMap::from([(LocalNodeIndex::make(0), 3), (LocalNodeIndex::make(1), 1)]),
+        });
+        w.swap();
+        assert_eq!(r.get(&a[0..1]).unwrap().len(), 0);
+
+        // Table 2 is in the batch, but this reader isn't derived from it
+        w.release_batches(&Timestamp {
+            map: HashMap::from([(LocalNodeIndex::make(0), 3), (LocalNodeIndex::make(1), 2)]),
+        });
+        w.swap();
+        assert_eq!(r.get(&a[0..1]).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn barriers_wait_for_held_batches() {
+        let a = vec![1i32.into(), "a".into()].into_boxed_slice();
+        let batch = Timestamp {
+            map: HashMap::from([(LocalNodeIndex::make(0), 2)]),
+        };
+
+        let (r, mut w) = new(2, Index::hash_map(vec![0]), ReaderProcessing::default());
+        w.set_base_tables(HashSet::from([LocalNodeIndex::make(0)]));
+        w.swap();
+
+        w.reach_barrier(1);
+        assert_eq!(w.barrier(), 1);
+
+        w.add_batch(&batch, vec![Record::Positive(a.to_vec())]);
+        w.reach_barrier(2);
+        assert_eq!(r.get(&a[0..1]).unwrap().len(), 0);
+        assert_eq!(w.barrier(), 1);
+
+        w.release_batches(&batch);
+        w.swap();
+        assert_eq!(r.get(&a[0..1]).unwrap().len(), 1);
+        assert_eq!(w.barrier(), 2);
+    }
+
     #[test]
     fn busybusybusy() {
         use std::thread;
@@ -651,6 +1722,26 @@ mod tests {
         assert_eq!(r.get(&a[0..1]).unwrap()[1], b);
     }
 
+    #[test]
+    fn null_keys() {
+        let a = vec![DfValue::None, "a".into()].into_boxed_slice();
+        let b = vec![1i32.into(), "b".into()].into_boxed_slice();
+
+        let (r, mut w) = new(2, Index::hash_map(vec![0]), ReaderProcessing::default());
+        w.add(vec![
+            Record::Positive(a.to_vec()),
+            Record::Positive(b.to_vec()),
+        ]);
+        w.swap();
+
+        let res = r
+            .get_multi(&[KeyComparison::Equal(vec1![DfValue::None])])
+            .unwrap();
+        assert_eq!(res.len(), 1);
+        assert_eq!(res[0].len(), 1);
+        assert_eq!(res[0][0], a);
+    }
+
     #[test]
     fn absorb_negative_immediate() {
         let a = vec![1i32.into(), "a".into()].into_boxed_slice();
@@ -781,6 +1872,324 @@ mod tests {
         }
     }
 
+    mod trigger {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use super::*;
+
+        fn counting_trigger() -> (Arc<AtomicUsize>, impl Trigger) {
+            let triggered = Arc::new(AtomicUsize::new(0));
+            let trigger = {
+                let triggered = Arc::clone(&triggered);
+                move |keys: &mut dyn Iterator<Item = KeyComparison>| {
+                    triggered.fetch_add(keys.count(), Ordering::SeqCst);
+                    true
+                }
+            };
+            (triggered, trigger)
+        }
+
+        #[test]
+        fn coalesces_pending_keys() {
+            let (triggered, trigger) = counting_trigger();
+            let (r, mut w) = new_partial(
+                1,
+                Index::hash_map(vec![0]),
+                trigger,
+                EvictionKind::Random,
+                ReaderProcessing::default(),
+            );
+            w.swap();
+
+            let key = KeyComparison::from(vec1![DfValue::from(0)]);
+            let other_key = KeyComparison::from(vec1![DfValue::from(1)]);
+            assert!(r.trigger(vec![key.clone()].into_iter()));
+            assert!(r.trigger(vec![key.clone(), other_key].into_iter()));
+            assert_eq!(triggered.load(Ordering::SeqCst), 2);
+
+            // Once the key is filled, a later miss on it has to trigger a new replay
+            w.mark_filled(key.clone()).unwrap();
+            w.swap();
+            w.mark_hole(&key).unwrap();
+            w.swap();
+            assert!(r.trigger(vec![key].into_iter()));
+            assert_eq!(triggered.load(Ordering::SeqCst), 3);
+        }
+
+        #[test]
+        fn failed_trigger_clears_pending_keys() {
+            let triggered = Arc::new(AtomicUsize::new(0));
+            let trigger = {
+                let triggered = Arc::clone(&triggered);
+                move |keys: &mut dyn Iterator<Item = KeyComparison>| {
+                    triggered.fetch_add(keys.count(), Ordering::SeqCst);
+                    false
+                }
+            };
+            let (r, mut w) = new_partial(
+                1,
+                Index::hash_map(vec![0]),
+                trigger,
+                EvictionKind::Random,
+                ReaderProcessing::default(),
+            );
+            w.swap();
+
+            let key = KeyComparison::from(vec1![DfValue::from(0)]);
+            assert!(!r.trigger(vec![key.clone()].into_iter()));
+            assert!(!r.trigger(vec![key].into_iter()));
+            assert_eq!(triggered.load(Ordering::SeqCst), 2);
+        }
+
+        #[test]
+        fn clear_pending_allows_retrigger() {
+            let (triggered, trigger) = counting_trigger();
+            let (r, mut w) = new_partial(
+                1,
+                Index::hash_map(vec![0]),
+                trigger,
+                EvictionKind::Random,
+                ReaderProcessing::default(),
+            );
+            w.swap();
+
+            let key = KeyComparison::from(vec1![DfValue::from(0)]);
+            assert!(r.trigger(vec![key.clone()].into_iter()));
+            w.clear_pending(&key);
+            assert!(r.trigger(vec![key].into_iter()));
+            assert_eq!(triggered.load(Ordering::SeqCst), 2);
+        }
+
+        #[test]
+        fn expired_pending_keys_are_retriggered() {
+            let (triggered, trigger) = counting_trigger();
+            let (r, mut w) = new_partial(
+                1,
+                Index::hash_map(vec![0]),
+                trigger,
+                EvictionKind::Random,
+                ReaderProcessing::default(),
+            );
+            w.swap();
+
+            let key = KeyComparison::from(vec1![DfValue::from(0)]);
+            assert!(r.trigger(vec![key.clone()].into_iter()));
+            assert!(r.trigger(vec![key.clone()].into_iter()));
+            assert_eq!(triggered.load(Ordering::SeqCst), 1);
+
+            // Pretend the replay was lost
+            for triggered_at in r.pending.lock().unwrap().values_mut() {
+                *triggered_at -= PENDING_REPLAY_TIMEOUT;
+            }
+            assert!(r.trigger(vec![key.clone()].into_iter()));
+            assert_eq!(triggered.load(Ordering::SeqCst), 2);
+            assert!(r.trigger(vec![key].into_iter()));
+            assert_eq!(triggered.load(Ordering::SeqCst), 2);
+        }
+
+        #[test]
+        fn eviction_clears_pending_keys() {
+            let (triggered, trigger) = counting_trigger();
+            let (r, mut w) = new_partial(
+                1,
+                Index::hash_map(vec![0]),
+                trigger,
+                EvictionKind::Random,
+                ReaderProcessing::default(),
+            );
+            w.swap();
+
+            let key = KeyComparison::from(vec1![DfValue::from(0)]);
+            assert!(r.trigger(vec![key.clone()].into_iter()));
+            w.notify_readers_of_eviction().unwrap();
+            assert!(r.trigger(vec![key].into_iter()));
+            assert_eq!(triggered.load(Ordering::SeqCst), 2);
+        }
+    }
+
+    mod limit_results {
+        use nom_sql::OrderType;
+
+        use super::*;
+
+        fn results(rows_per_key: &[usize]) -> SharedResults {
+            rows_per_key
+                .iter()
+                .map(|n| {
+                    SharedRows::new(
+                        (0..*n)
+                            .map(|i| vec![DfValue::from(i as i64)].into_boxed_slice())
+                            .collect(),
+                    )
+                })
+                .collect()
+        }
+
+        fn lookup(rows_per_key: &[usize], post_lookup: &PostLookup) -> ResultIterator {
+            ResultIterator::new(results(rows_per_key), post_lookup, None, None, None)
+        }
+
+        #[test]
+        fn truncates_rows_per_key() {
+            let (mut r, _w) = new(1, Index::hash_map(vec![0]), ReaderProcessing::default());
+            r.result_limits = CacheResultLimits {
+                max_rows_per_key: Some(2),
+                ..Default::default()
+            };
+
+            let (limited, truncated) = r.limit_rows_per_key(results(&[1, 4]), None).unwrap();
+            assert!(truncated);
+            assert_eq!(
+                limited.iter().map(|rows| rows.len()).collect::<Vec<_>>(),
+                vec![1, 2]
+            );
+
+            let (_, truncated) = r.limit_rows_per_key(results(&[2, 2]), None).unwrap();
+            assert!(!truncated);
+        }
+
+        #[test]
+        fn limits_rows_returned_for_each_key() {
+            let (mut r, _w) = new(1, Index::hash_map(vec![0]), ReaderProcessing::default());
+            r.result_limits = CacheResultLimits {
+                max_rows_per_key: Some(2),
+                on_exceeded: ResultLimitPolicy::Error,
+                ..Default::default()
+            };
+
+            assert!(matches!(
+                r.limit_rows_per_key(results(&[5]), None),
+                Err(ReadySetError::ResultSetTooLarge(_))
+            ));
+
+            // The key has more rows than the limit, but the query only returns the top two
+            r.post_lookup = PostLookup {
+                order_by: Some(vec![(0, OrderType::OrderAscending)]),
+                limit: Some(2),
+                ..Default::default()
+            };
+            let (limited, truncated) = r.limit_rows_per_key(results(&[5]), None).unwrap();
+            assert!(!truncated);
+            assert_eq!(
+                ResultIterator::new(limited, &r.post_lookup, None, None, None).into_vec(),
+                vec![vec![DfValue::from(0)], vec![DfValue::from(1)]]
+            );
+        }
+
+        #[test]
+        fn errors_on_too_many_bytes() {
+            let (mut r, _w) = new(1, Index::hash_map(vec![0]), ReaderProcessing::default());
+            let row_size = results(&[1])[0][0].to_vec().deep_size_of();
+            r.result_limits = CacheResultLimits {
+                max_result_bytes: Some(row_size * 2),
+                on_exceeded: ResultLimitPolicy::Error,
+                ..Default::default()
+            };
+
+            r.limit_result_bytes(lookup(&[1, 1], &Default::default()))
+                .unwrap();
+            assert!(matches!(
+                r.limit_result_bytes(lookup(&[2, 1], &Default::default())),
+                Err(ReadySetError::ResultSetTooLarge(_))
+            ));
+        }
+    }
+
+    mod stale_reads {
+        use super::*;
+
+        #[test]
+        fn serves_evicted_rows_until_refilled() {
+            let (r, mut w) = new_partial(
+                2,
+                Index::hash_map(vec![0]),
+                |_: &mut dyn Iterator<Item = KeyComparison>| true,
+                EvictionKind::Random,
+                ReaderProcessing::default(),
+            );
+            w.set_max_staleness(Some(Duration::from_secs(60)));
+            w.swap();
+
+            let row = vec![DfValue::from(0), DfValue::from("a")];
+            let key = KeyComparison::from(vec1![DfValue::from(0)]);
+            let other_key = KeyComparison::from(vec1![DfValue::from(1)]);
+            w.mark_filled(key.clone()).unwrap();
+            w.add(vec![Record::Positive(row.clone())]);
+            w.swap();
+
+            w.mark_hole(&key).unwrap();
+            w.swap();
+            assert!(r.get_multi(std::slice::from_ref(&key)).unwrap_err().is_miss());
+            let stale = r.get_multi_or_stale(std::slice::from_ref(&key)).unwrap();
+            assert_eq!(stale.len(), 1);
+            assert_eq!(&*stale[0][0], &row[..]);
+
+            // Keys which were never filled can't be served stale
+            assert!(r.get_multi_or_stale(&[key.clone(), other_key]).is_none());
+
+            // Once the key is refilled, its stale rows are dropped
+            w.mark_filled(key.clone()).unwrap();
+            w.swap();
+            assert!(r.get_multi_or_stale(std::slice::from_ref(&key)).unwrap()[0].is_empty());
+        }
+
+        #[test]
+        fn bounded() {
+            let mut stale = StaleValuesInner {
+                max_staleness: Some(Duration::from_secs(60)),
+                ..Default::default()
+            };
+            let start = Instant::now();
+            let mut now = start;
+            for i in 0..=MAX_STALE_KEYS {
+                now += Duration::from_nanos(1);
+                stale.insert(vec![DfValue::from(i)], Default::default(), now);
+            }
+            // Re-inserting a key keeps it around for longer
+            now += Duration::from_nanos(1);
+            stale.insert(vec![DfValue::from(1usize)], Default::default(), now);
+            now += Duration::from_nanos(1);
+            stale.insert(
+                vec![DfValue::from(MAX_STALE_KEYS + 1)],
+                Default::default(),
+                now,
+            );
+
+            assert_eq!(stale.values.len(), MAX_STALE_KEYS);
+            assert_eq!(stale.order.len(), MAX_STALE_KEYS);
+            assert!(stale.get(&[DfValue::from(0usize)], now).is_none());
+            assert!(stale.get(&[DfValue::from(1usize)], now).is_some());
+            assert!(stale.get(&[DfValue::from(2usize)], now).is_none());
+            assert!(stale
+                .get(&[DfValue::from(MAX_STALE_KEYS + 1)], now)
+                .is_some());
+
+            stale.prune(now + Duration::from_secs(61));
+            assert!(stale.values.is_empty());
+            assert!(stale.order.is_empty());
+        }
+
+        #[test]
+        fn disabled_by_default() {
+            let (r, mut w) = new_partial(
+                1,
+                Index::hash_map(vec![0]),
+                |_: &mut dyn Iterator<Item = KeyComparison>| true,
+                EvictionKind::Random,
+                ReaderProcessing::default(),
+            );
+            w.swap();
+
+            let key = KeyComparison::from(vec1![DfValue::from(0)]);
+            w.mark_filled(key.clone()).unwrap();
+            w.add(vec![Record::Positive(vec![DfValue::from(0)])]);
+            w.swap();
+            w.mark_hole(&key).unwrap();
+            w.swap();
+            assert!(r.get_multi_or_stale(&[key]).is_none());
+        }
+    }
+
     mod mark_hole {
         use super::*;
 
@@ -836,4 +2245,70 @@ mod tests {
             assert!(r.get_multi(range_key).err().unwrap().is_miss());
         }
     }
+
+    #[test]
+    fn evict_keys_before() {
+        let (r, mut w) = new_partial(
+            1,
+            Index::hash_map(vec![0]),
+            |_: &mut dyn Iterator<Item = KeyComparison>| true,
+            EvictionKind::Random,
+            ReaderProcessing::default(),
+        );
+        w.set_bucket_retention(Some(Duration::from_secs(3600)));
+        w.swap();
+
+        let hour =
+            |h| Utc.from_utc_datetime(&chrono::NaiveDate::from_ymd(2022, 10, 1).and_hms(h, 0, 0));
+        let bucket = |h| DfValue::from(hour(h).with_timezone(&chrono::FixedOffset::west(3600)));
+        let keys = [vec1![bucket(1)], vec1![bucket(2)], vec1![DfValue::from(1)]];
+        for key in &keys {
+            w.mark_filled(key.clone().into()).unwrap();
+        }
+        w.swap();
+
+        assert_eq!(
+            w.evict_keys_before(hour(2)).unwrap(),
+            vec![KeyComparison::from(keys[0].clone())]
+        );
+        w.swap();
+        assert!(r.get(&keys[0]).err().unwrap().is_miss());
+        r.get(&keys[1]).unwrap();
+        r.get(&keys[2]).unwrap();
+        assert!(w.evict_keys_before(hour(2)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn expire_keys() {
+        let (r, mut w) = new_partial(
+            1,
+            Index::hash_map(vec![0]),
+            |_: &mut dyn Iterator<Item = KeyComparison>| true,
+            EvictionKind::Random,
+            ReaderProcessing::default(),
+        );
+        w.set_key_ttl(Some(Duration::from_secs(60)), 0);
+        w.swap();
+
+        let keys = (0..3).map(|i| vec1![DfValue::from(i)]).collect::<Vec<_>>();
+        for key in &keys {
+            w.mark_filled(key.clone().into()).unwrap();
+        }
+        w.swap();
+
+        assert_eq!(w.expire_keys(Instant::now(), usize::MAX).unwrap(), 0);
+        let later = Instant::now() + Duration::from_secs(60);
+        assert_eq!(w.expire_keys(later, 2).unwrap(), 2);
+        w.swap();
+        assert_eq!(
+            keys.iter()
+                .filter(|key| r.get(key).err().iter().any(LookupError::is_miss))
+                .count(),
+            2
+        );
+
+        assert_eq!(w.expire_keys(later, 2).unwrap(), 1);
+        w.swap();
+        assert!(keys.iter().all(|key| r.get(key).err().unwrap().is_miss()));
+    }
 }
@@ -4,7 +4,7 @@ use std::convert::TryInto;
 use std::ops::RangeBounds;
 
 use ahash::RandomState;
-use common::DfValue;
+use common::{DfValue, SizeOf};
 use dataflow_expression::PreInsertion;
 use reader_map::refs::Miss;
 use readyset_client::consistency::Timestamp;
@@ -15,6 +15,8 @@ use readyset_tracing::warn;
 use serde::{Deserialize, Serialize};
 use vec1::{vec1, Vec1};
 
+use crate::checksum::{rows_checksum, stable_hash, KeyRange};
+
 /// A [`ReadHandle`] to a map whose key is a single [`DfValue`], for faster lookup (compared to a
 /// Vec with len == 1)
 type HandleSingle = reader_map::handles::ReadHandle<
@@ -102,13 +104,83 @@ impl Handle {
         }
     }
 
-    pub(super) fn keys(&self) -> Vec<Vec<DfValue>> {
+    /// Returns the keys in `range`
+    pub(super) fn keys(&self, range: KeyRange) -> Vec<Vec<DfValue>> {
+        let keys: Vec<Option<Vec<DfValue>>> = match *self {
+            Handle::Single(ref h) => h.map_into(|k, _| {
+                let key = std::slice::from_ref(k);
+                range.contains_hash(stable_hash(key)).then(|| key.to_vec())
+            }),
+            Handle::Many(ref h) => {
+                h.map_into(|ks, _| range.contains_hash(stable_hash(ks)).then(|| ks.clone()))
+            }
+        };
+        keys.into_iter().flatten().collect()
+    }
+
+    /// Returns the total number of rows stored for all keys
+    pub(super) fn row_count(&self) -> usize {
         match *self {
-            Handle::Single(ref h) => h.map_into(|k, _| vec![k.clone()]),
-            Handle::Many(ref h) => h.map_into(|ks, _| ks.clone()),
+            Handle::Single(ref h) => h
+                .enter()
+                .map_or(0, |map| map.iter().fold(0, |n, (_, v)| n + v.len())),
+            Handle::Many(ref h) => h
+                .enter()
+                .map_or(0, |map| map.iter().fold(0, |n, (_, v)| n + v.len())),
         }
     }
 
+    /// Returns the total size of the rows stored for at most `sample` keys, spread evenly over
+    /// all the keys in the map
+    pub(super) fn value_sizes(&self, sample: usize) -> Vec<u64> {
+        fn size(v: &reader_map::refs::Values<Box<[DfValue]>>) -> u64 {
+            v.iter().map(|r| r.deep_size_of()).sum()
+        }
+        let stride = (self.len() / sample.max(1)).max(1);
+        match *self {
+            Handle::Single(ref h) => h.enter().map_or_else(
+                |_| vec![],
+                |map| {
+                    map.iter()
+                        .step_by(stride)
+                        .take(sample)
+                        .map(|(_, v)| size(v))
+                        .collect()
+                },
+            ),
+            Handle::Many(ref h) => h.enter().map_or_else(
+                |_| vec![],
+                |map| {
+                    map.iter()
+                        .step_by(stride)
+                        .take(sample)
+                        .map(|(_, v)| size(v))
+                        .collect()
+                },
+            ),
+        }
+    }
+
+    /// Returns the hash of each key in `range`, along with the number of rows stored for that key
+    /// and their checksum
+    pub(super) fn key_checksums(&self, range: KeyRange) -> Vec<(u64, usize, u64)> {
+        fn checksum(
+            key: &[DfValue],
+            rows: &reader_map::refs::Values<Box<[DfValue]>>,
+            range: KeyRange,
+        ) -> Option<(u64, usize, u64)> {
+            let hash = stable_hash(key);
+            range
+                .contains_hash(hash)
+                .then(|| (hash, rows.len(), rows_checksum(rows.iter())))
+        }
+        let checksums: Vec<_> = match *self {
+            Handle::Single(ref h) => h.map_into(|k, v| checksum(std::slice::from_ref(k), v, range)),
+            Handle::Many(ref h) => h.map_into(|ks, v| checksum(ks, v, range)),
+        };
+        checksums.into_iter().flatten().collect()
+    }
+
     fn get_multi_single_handle<'a, T, F: Fn() -> T>(
         handle: &HandleSingle,
         keys: &'a [KeyComparison],
@@ -125,11 +197,6 @@ impl Handle {
                 continue;
             }
             match key {
-                KeyComparison::Equal(k) if k.iter().any(|v| v.is_none()) => {
-                    // NULL can never compare equal to anything, so if the key contains nulls we can
-                    // short-circuit with an empty result set without actually doing a lookup
-                    hits.push(Default::default())
-                }
                 KeyComparison::Equal(k) => match map.get(&k[0]) {
                     Some(v) => hits.push(v.as_ref().clone()),
                     None => misses.push(Cow::Borrowed(key)),
@@ -179,11 +246,6 @@ impl Handle {
                 continue;
             }
             match key {
-                KeyComparison::Equal(k) if k.iter().any(|v| v.is_none()) => {
-                    // NULL can never compare equal to anything, so if the key contains nulls we can
-                    // short-circuit with an empty result set without actually doing a lookup
-                    hits.push(Default::default())
-                }
                 KeyComparison::Equal(k) => match map.get(k.as_slice()) {
                     Some(v) => hits.push(v.as_ref().clone()),
                     None => misses.push(Cow::Borrowed(key)),
@@ -398,6 +460,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn keys_in_ranges() {
+        let (mut w, handle) = make_single();
+        for n in 0i32..100 {
+            w.insert(n.into(), vec![n.into()].into_boxed_slice());
+        }
+        w.publish();
+
+        let mut all = handle.keys(KeyRange::ALL);
+        all.sort();
+        assert_eq!(all.len(), 100);
+
+        let mut keys = (0..4)
+            .flat_map(|index| handle.keys(KeyRange { index, ranges: 4 }))
+            .collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, all);
+    }
+
     #[test]
     fn get_single_range() {
         let (mut w, handle) = make_single();
@@ -3,6 +3,7 @@ use std::ops::{Bound, RangeBounds};
 use ahash::RandomState;
 use dataflow_expression::PreInsertion;
 use readyset_client::consistency::Timestamp;
+use readyset_client::results::SharedRows;
 
 use super::{key_to_single, Key};
 use crate::prelude::*;
@@ -89,15 +90,27 @@ impl Handle {
 
     /// Evict keys that were selected by the assigned eviction strategy from the state, and return
     /// the number of bytes freed. The amount of keys evicted will be ceil(len() * ratio)
-    pub fn evict(&mut self, ratio: f64) -> u64 {
+    ///
+    /// If `on_evict` is provided, it is called with each evicted key and the rows it held.
+    pub fn evict(
+        &mut self,
+        ratio: f64,
+        mut on_evict: Option<&mut dyn FnMut(Vec<DfValue>, SharedRows)>,
+    ) -> u64 {
         let base_value_size = self.base_value_size() as u64;
         match *self {
             Handle::Single(ref mut h) => h.evict_keys(ratio, |k, v| {
+                if let Some(on_evict) = on_evict.as_mut() {
+                    on_evict(vec![k.clone()], v.as_ref().clone());
+                }
                 // Each row's state is composed of: The key, the set of Values in the row (DfValues)
                 // and the bytes required to hold the Row data structure.
                 k.deep_size_of() + v.iter().map(|r| r.deep_size_of()).sum::<u64>() + base_value_size
             }),
             Handle::Many(ref mut h) => h.evict_keys(ratio, |k, v| {
+                if let Some(on_evict) = on_evict.as_mut() {
+                    on_evict(k.clone(), v.as_ref().clone());
+                }
                 k.deep_size_of() + v.iter().map(|r| r.deep_size_of()).sum::<u64>() + base_value_size
             }),
         }
@@ -0,0 +1,266 @@
+//! Recording of where the rows in a reader came from, when provenance recording is enabled (see
+//! [`DomainConfig::record_provenance`](crate::DomainConfig::record_provenance)).
+
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::sync::Arc;
+
+use ahash::RandomState;
+use common::SizeOf;
+use readyset_client::debug::provenance::{ProvenanceTag, RowProvenance};
+use readyset_client::KeyComparison;
+
+use crate::prelude::*;
+
+/// The maximum number of rows each reader records the provenance of. Rows written to a reader
+/// which has already recorded this many aren't recorded until others are removed or evicted.
+const MAX_PROVENANCE_ROWS: usize = 100_000;
+
+/// The recorded provenance of a single row
+struct Entry {
+    tags: Arc<[ProvenanceTag]>,
+    /// Whether the row was last written by a replay, rather than by a write to a base table
+    replay: bool,
+    /// The number of bytes this entry is accounted as taking up
+    size: usize,
+}
+
+/// The provenance of the rows in a reader, grouped by the reader key the rows are stored under so
+/// that the provenance of evicted keys can be forgotten along with their rows.
+#[derive(Default)]
+pub(super) struct Provenance {
+    keys: HashMap<Vec<DfValue>, HashMap<Vec<DfValue>, Entry, RandomState>, RandomState>,
+    /// The total number of rows recorded, across all keys
+    rows: usize,
+    /// The approximate number of bytes taken up by the recorded provenance
+    size: usize,
+}
+
+impl Provenance {
+    /// Returns the approximate number of bytes taken up by the recorded provenance
+    pub(super) fn size(&self) -> usize {
+        self.size
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Record that the rows in `records`, stored under the reader key formed by `key_columns`,
+    /// were last written by the update with the given `tags`. The memory taken up by the tags is
+    /// shared out between the rows they're recorded for.
+    ///
+    /// Replays are tagged with one tag for each key they replayed (see
+    /// [`ReplayProvenance::tags`](crate::node::ReplayProvenance::tags)), so each row written by a
+    /// replay is only recorded with the tag for its own key, if there is one.
+    pub(super) fn record(
+        &mut self,
+        key_columns: &[usize],
+        records: &Records,
+        tags: &Arc<[ProvenanceTag]>,
+        replay: bool,
+    ) {
+        let tags_size_per_row = tags_size(tags) / records.len().max(1);
+        let replay_tags: HashMap<&[DfValue], (Arc<[ProvenanceTag]>, usize)> = if replay {
+            tags.iter()
+                .map(|tag| {
+                    let tags: Arc<[ProvenanceTag]> = Arc::new([tag.clone()]);
+                    let size = tags_size(&tags);
+                    (tag.key.as_slice(), (tags, size))
+                })
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        for record in records.iter() {
+            let key: Vec<DfValue> = key_columns.iter().map(|&c| record[c].clone()).collect();
+            let (tags, tags_size) = if replay {
+                replay_tags
+                    .get(key.as_slice())
+                    .cloned()
+                    .unwrap_or_else(|| (Vec::new().into(), 0))
+            } else {
+                (tags.clone(), tags_size_per_row)
+            };
+            match record {
+                Record::Positive(row) => {
+                    if self.rows >= MAX_PROVENANCE_ROWS
+                        && !self
+                            .keys
+                            .get(&key)
+                            .map_or(false, |rows| rows.contains_key(row))
+                    {
+                        continue;
+                    }
+                    let size = size_of::<Entry>()
+                        + row.iter().map(SizeOf::deep_size_of).sum::<u64>() as usize
+                        + tags_size;
+                    let entry = Entry { tags, replay, size };
+                    self.size += size;
+                    match self.keys.entry(key).or_default().insert(row.clone(), entry) {
+                        Some(old) => self.size -= old.size,
+                        None => self.rows += 1,
+                    }
+                }
+                Record::Negative(row) => {
+                    if let Some(rows) = self.keys.get_mut(&key) {
+                        if let Some(old) = rows.remove(row) {
+                            self.size -= old.size;
+                            self.rows -= 1;
+                        }
+                        if rows.is_empty() {
+                            self.keys.remove(&key);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Forget the provenance of the rows stored under `key`, which has been evicted. Returns the
+    /// number of bytes freed.
+    pub(super) fn remove_key(&mut self, key: &[DfValue]) -> usize {
+        match self.keys.remove(key) {
+            Some(rows) => self.forget(rows),
+            None => 0,
+        }
+    }
+
+    /// Forget the provenance of the rows stored under every key covered by `key`, which has been
+    /// evicted. Returns the number of bytes freed.
+    pub(super) fn remove_keys(&mut self, key: &KeyComparison) -> usize {
+        match key {
+            KeyComparison::Equal(k) => self.remove_key(k.as_vec()),
+            KeyComparison::Range(_) => {
+                let evicted = self
+                    .keys
+                    .keys()
+                    .filter(|k| key.contains(*k))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                evicted.iter().map(|k| self.remove_key(k)).sum()
+            }
+        }
+    }
+
+    fn forget(&mut self, rows: HashMap<Vec<DfValue>, Entry, RandomState>) -> usize {
+        let freed = rows.values().map(|entry| entry.size).sum::<usize>();
+        self.rows -= rows.len();
+        self.size -= freed;
+        freed
+    }
+
+    /// Returns the recorded provenance of every row stored under `key`
+    pub(super) fn get(&self, key: &[DfValue]) -> Vec<RowProvenance> {
+        self.keys
+            .get(key)
+            .into_iter()
+            .flatten()
+            .map(|(row, entry)| RowProvenance {
+                row: row.clone(),
+                tags: entry.tags.to_vec(),
+                replay: entry.replay,
+            })
+            .collect()
+    }
+}
+
+/// Returns the approximate number of bytes taken up by `tags`
+fn tags_size(tags: &[ProvenanceTag]) -> usize {
+    tags.iter()
+        .map(|tag| {
+            size_of::<ProvenanceTag>() as u64
+                + tag.key.iter().map(SizeOf::deep_size_of).sum::<u64>()
+        })
+        .sum::<u64>() as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::Relation;
+
+    use super::*;
+
+    fn tags(keys: &[i32]) -> Arc<[ProvenanceTag]> {
+        keys.iter()
+            .map(|&k| ProvenanceTag {
+                table: Relation::from("t"),
+                key: vec![k.into()],
+                offset: None,
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    #[test]
+    fn records_and_removes_rows() {
+        let mut provenance = Provenance::default();
+        let records: Records = vec![
+            Record::Positive(vec![1.into(), 2.into()]),
+            Record::Positive(vec![1.into(), 3.into()]),
+        ]
+        .into();
+        provenance.record(&[0], &records, &tags(&[2, 3]), false);
+        assert_eq!(provenance.get(&[1.into()]).len(), 2);
+        assert!(provenance.size() > 0);
+
+        let records: Records = vec![Record::Negative(vec![1.into(), 2.into()])].into();
+        provenance.record(&[0], &records, &tags(&[2]), false);
+        let rows = provenance.get(&[1.into()]);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].row, vec![DfValue::from(1), DfValue::from(3)]);
+        assert!(!rows[0].replay);
+
+        let records: Records = vec![Record::Negative(vec![1.into(), 3.into()])].into();
+        provenance.record(&[0], &records, &tags(&[3]), false);
+        assert!(provenance.is_empty());
+        assert_eq!(provenance.size(), 0);
+    }
+
+    #[test]
+    fn replayed_rows_only_get_their_own_tag() {
+        let mut provenance = Provenance::default();
+        let records: Records = (0i32..10)
+            .map(|i| Record::Positive(vec![i.into(), i.into()]))
+            .collect::<Vec<_>>()
+            .into();
+        provenance.record(&[0], &records, &tags(&[2, 3]), true);
+
+        let rows = provenance.get(&[2.into()]);
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].replay);
+        assert_eq!(rows[0].tags, tags(&[2]).to_vec());
+
+        // Rows whose key wasn't replayed by key (such as rows filled by a full replay) aren't
+        // attributed to anything
+        let rows = provenance.get(&[4.into()]);
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].tags.is_empty());
+    }
+
+    #[test]
+    fn evicting_keys_frees_memory() {
+        let mut provenance = Provenance::default();
+        let records: Records = (0i32..10)
+            .map(|i| Record::Positive(vec![i.into(), i.into()]))
+            .collect::<Vec<_>>()
+            .into();
+        provenance.record(&[0], &records, &tags(&[]), true);
+        let size = provenance.size();
+        assert!(provenance.get(&[4.into()])[0].replay);
+
+        let freed = provenance.remove_key(&[4.into()]);
+        assert!(freed > 0);
+        assert_eq!(provenance.size(), size - freed);
+        assert!(provenance.get(&[4.into()]).is_empty());
+
+        let range = KeyComparison::Range((
+            std::ops::Bound::Included(vec1::vec1![DfValue::from(0)]),
+            std::ops::Bound::Excluded(vec1::vec1![DfValue::from(5)]),
+        ));
+        provenance.remove_keys(&range);
+        assert!((0..5).all(|i| provenance.get(&[i.into()]).is_empty()));
+        assert_eq!(provenance.get(&[7.into()]).len(), 1);
+    }
+}
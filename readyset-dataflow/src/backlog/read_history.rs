@@ -0,0 +1,229 @@
+//! Retention of per-minute counters of the reads from a reader, for the last 24 hours.
+//!
+//! Reads are recorded on the hot path of every read from a reader, concurrently from every thread
+//! serving reads, so recording a read never takes a lock: every counter is a single atomic which
+//! holds both the counter itself and (the low bits of) the minute it's counting, so that moving a
+//! counter on to a new minute and updating it is a single atomic operation.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use readyset_client::debug::stats::ReadStatsBucket;
+
+/// The granularity at which reads are counted
+const BUCKET_WIDTH: Duration = Duration::from_secs(60);
+
+/// The number of buckets retained, covering the last 24 hours
+const RETAINED_BUCKETS: usize = 24 * 60;
+
+/// The number of (high) bits of each counter which hold the minute it's counting. Minutes are
+/// only ever compared for equality with a minute in the last 24 hours, so only the low bits of
+/// the minute are needed.
+const MINUTE_BITS: u32 = 24;
+
+/// The mask for the bits of each counter which hold its value
+const VALUE_MASK: u64 = (1 << (64 - MINUTE_BITS)) - 1;
+
+/// How a read from a reader was answered
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadOutcome {
+    /// The read hit on all its keys
+    Hit,
+    /// The read missed on at least one key, and was answered once the misses were filled (or
+    /// with stale rows, or without results for non-blocking reads)
+    Miss,
+    /// The read failed with an error
+    Error,
+    /// The read timed out waiting for its misses to be filled
+    Timeout,
+}
+
+/// A counter of some quantity over a single minute, along with the minute it's counting
+#[derive(Default)]
+struct MinuteCounter(AtomicU64);
+
+impl MinuteCounter {
+    /// Returns the bits of a counter which identify `minute`
+    fn tag(minute: u64) -> u64 {
+        minute << (64 - MINUTE_BITS)
+    }
+
+    /// Combine `value` into the counter for `minute` with `f`, first resetting the counter to
+    /// zero if it's counting a different minute
+    fn update(&self, minute: u64, value: u64, f: impl Fn(u64, u64) -> u64) {
+        let tag = Self::tag(minute);
+        #[allow(clippy::unwrap_used)] // the closure always returns Some
+        self.0
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |packed| {
+                let current = if packed & !VALUE_MASK == tag {
+                    packed & VALUE_MASK
+                } else {
+                    0
+                };
+                Some(tag | f(current, value).min(VALUE_MASK))
+            })
+            .unwrap();
+    }
+
+    fn add(&self, minute: u64, value: u64) {
+        self.update(minute, value, u64::saturating_add)
+    }
+
+    fn max(&self, minute: u64, value: u64) {
+        self.update(minute, value, u64::max)
+    }
+
+    /// Returns the value of the counter for `minute`, or zero if it's counting a different minute
+    fn get(&self, minute: u64) -> u64 {
+        let packed = self.0.load(Ordering::Relaxed);
+        if packed & !VALUE_MASK == Self::tag(minute) {
+            packed & VALUE_MASK
+        } else {
+            0
+        }
+    }
+}
+
+/// The counters for a single minute
+#[derive(Default)]
+struct Bucket {
+    reads: MinuteCounter,
+    misses: MinuteCounter,
+    errors: MinuteCounter,
+    timeouts: MinuteCounter,
+    total_latency_us: MinuteCounter,
+    max_latency_us: MinuteCounter,
+}
+
+/// A ring buffer of counters for each minute of the last 24 hours. Each bucket is reused for the
+/// same minute of every day, and is reset the first time a read is recorded in it for a new day.
+///
+/// The buckets are only allocated the first time a read is recorded, since many readers are never
+/// read from directly.
+#[derive(Default)]
+pub(super) struct ReadHistory {
+    buckets: OnceLock<Box<[Bucket]>>,
+}
+
+/// Returns the number of whole minutes between the unix epoch and `time`
+fn minute_of(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / BUCKET_WIDTH.as_secs()
+}
+
+impl ReadHistory {
+    /// Record a read which completed at `now` and took `latency` to answer
+    pub(super) fn record(&self, now: SystemTime, latency: Duration, outcome: ReadOutcome) {
+        let buckets = self
+            .buckets
+            .get_or_init(|| (0..RETAINED_BUCKETS).map(|_| Bucket::default()).collect());
+        let minute = minute_of(now);
+        let bucket = &buckets[minute as usize % RETAINED_BUCKETS];
+
+        let latency_us = latency.as_micros() as u64;
+        bucket.reads.add(minute, 1);
+        match outcome {
+            ReadOutcome::Hit => {}
+            ReadOutcome::Miss => bucket.misses.add(minute, 1),
+            ReadOutcome::Error => bucket.errors.add(minute, 1),
+            ReadOutcome::Timeout => bucket.timeouts.add(minute, 1),
+        }
+        bucket.total_latency_us.add(minute, latency_us);
+        bucket.max_latency_us.max(minute, latency_us);
+    }
+
+    /// Returns the counters for each minute of the last 24 hours as of `now` in which any reads
+    /// were recorded, oldest first
+    pub(super) fn snapshot(&self, now: SystemTime) -> Vec<ReadStatsBucket> {
+        let buckets = match self.buckets.get() {
+            Some(buckets) => buckets,
+            None => return vec![],
+        };
+        let current = minute_of(now);
+        (current.saturating_sub(RETAINED_BUCKETS as u64 - 1)..=current)
+            .filter_map(|minute| {
+                let bucket = &buckets[minute as usize % RETAINED_BUCKETS];
+                let reads = bucket.reads.get(minute);
+                (reads > 0).then(|| ReadStatsBucket {
+                    minute: UNIX_EPOCH + BUCKET_WIDTH * minute as u32,
+                    reads,
+                    misses: bucket.misses.get(minute),
+                    errors: bucket.errors.get(minute),
+                    timeouts: bucket.timeouts.get(minute),
+                    total_latency_us: bucket.total_latency_us.get(minute),
+                    max_latency_us: bucket.max_latency_us.get(minute),
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn counts_reads_per_minute() {
+        let history = ReadHistory::default();
+        history.record(at(60), Duration::from_micros(10), ReadOutcome::Hit);
+        history.record(at(90), Duration::from_micros(30), ReadOutcome::Miss);
+        history.record(at(125), Duration::from_micros(5), ReadOutcome::Error);
+        history.record(at(130), Duration::from_micros(50), ReadOutcome::Timeout);
+
+        let buckets = history.snapshot(at(130));
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].minute, at(60));
+        assert_eq!(buckets[0].reads, 2);
+        assert_eq!(buckets[0].misses, 1);
+        assert_eq!(buckets[0].mean_latency_us(), 20);
+        assert_eq!(buckets[0].max_latency_us, 30);
+        assert_eq!(buckets[1].minute, at(120));
+        assert_eq!(buckets[1].reads, 2);
+        assert_eq!(buckets[1].errors, 1);
+        assert_eq!(buckets[1].timeouts, 1);
+        assert_eq!(buckets[1].max_latency_us, 50);
+    }
+
+    #[test]
+    fn drops_buckets_older_than_a_day() {
+        let history = ReadHistory::default();
+        for minute in 0..(RETAINED_BUCKETS as u64 + 10) {
+            history.record(at(minute * 60), Duration::ZERO, ReadOutcome::Hit);
+        }
+
+        let now = at((RETAINED_BUCKETS as u64 + 9) * 60);
+        let buckets = history.snapshot(now);
+        assert_eq!(buckets.len(), RETAINED_BUCKETS);
+        assert_eq!(buckets[0].minute, at(10 * 60));
+        assert!(buckets.iter().all(|bucket| bucket.reads == 1));
+
+        assert!(history
+            .snapshot(now + Duration::from_secs(24 * 60 * 60))
+            .is_empty());
+    }
+
+    #[test]
+    fn concurrent_reads() {
+        let history = ReadHistory::default();
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    for _ in 0..1000 {
+                        history.record(at(60), Duration::from_micros(1), ReadOutcome::Hit);
+                    }
+                });
+            }
+        });
+
+        let buckets = history.snapshot(at(60));
+        assert_eq!(buckets[0].reads, 4000);
+        assert_eq!(buckets[0].total_latency_us, 4000);
+    }
+}
@@ -0,0 +1,147 @@
+//! Eviction of the keys of a partial reader which contain time buckets that have fallen out of the
+//! reader's retention window.
+
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Bound;
+use std::time::Duration;
+
+use ahash::RandomState;
+use chrono::{DateTime, Local, TimeZone, Utc};
+use readyset_client::KeyComparison;
+use readyset_data::DfValue;
+
+/// Returns the instant a timestamp in a reader key refers to, or `None` if the value isn't a
+/// timestamp.
+///
+/// Values with a timezone (such as PostgreSQL's `timestamptz`) are compared as the instants they
+/// represent. Values without one (`timestamp`, `datetime` and `date`) are wall-clock times in the
+/// timezone of the upstream database, which we take to be the same as the local timezone.
+pub(super) fn timestamp_instant(value: &DfValue) -> Option<DateTime<Utc>> {
+    let ts = match value {
+        DfValue::TimestampTz(ts) => ts,
+        _ => return None,
+    };
+    let dt = ts.to_chrono();
+    if ts.has_timezone() {
+        Some(dt.with_timezone(&Utc))
+    } else {
+        Local
+            .from_local_datetime(&dt.naive_local())
+            .earliest()
+            .map(|dt| dt.with_timezone(&Utc))
+    }
+}
+
+/// Returns the instant of the earliest timestamp in `key`, which is the time that determines when
+/// the key falls out of the retention window. For range keys, the timestamps in the upper bound of
+/// the range are used, so that a range is only evicted once everything in it is out of the window.
+fn key_time(key: &KeyComparison) -> Option<DateTime<Utc>> {
+    let values = match key {
+        KeyComparison::Equal(key) => key.as_vec(),
+        KeyComparison::Range((_, Bound::Included(end) | Bound::Excluded(end))) => end.as_vec(),
+        KeyComparison::Range((_, Bound::Unbounded)) => return None,
+    };
+    values.iter().filter_map(timestamp_instant).min()
+}
+
+/// Tracks the filled keys of a partial reader which contain timestamps, by the time they fall out
+/// of the reader's retention window, so that expired keys can be found without scanning every key
+/// in the reader.
+///
+/// Keys aren't removed when they're evicted for other reasons, since they drop out of the window
+/// (and so get removed) within the retention period anyway, and evicting them again is a no-op.
+pub(super) struct TimeBuckets {
+    retention: Duration,
+    /// Filled keys containing timestamps, by the instant of their earliest timestamp
+    keys: BTreeMap<DateTime<Utc>, HashSet<KeyComparison, RandomState>>,
+}
+
+impl TimeBuckets {
+    pub(super) fn new(retention: Duration) -> Self {
+        Self {
+            retention,
+            keys: Default::default(),
+        }
+    }
+
+    /// Returns how old the timestamps in keys may get before the keys are evicted
+    pub(super) fn retention(&self) -> Duration {
+        self.retention
+    }
+
+    /// Record that `key` was filled
+    pub(super) fn filled(&mut self, key: &KeyComparison) {
+        if let Some(time) = key_time(key) {
+            self.keys.entry(time).or_default().insert(key.clone());
+        }
+    }
+
+    /// Remove and return every key containing a timestamp earlier than `cutoff`
+    pub(super) fn take_before(&mut self, cutoff: DateTime<Utc>) -> Vec<KeyComparison> {
+        let unexpired = self.keys.split_off(&cutoff);
+        std::mem::replace(&mut self.keys, unexpired)
+            .into_values()
+            .flatten()
+            .collect()
+    }
+
+    /// Returns the number of keys being tracked
+    #[cfg(test)]
+    pub(super) fn len(&self) -> usize {
+        self.keys.values().map(HashSet::len).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{FixedOffset, NaiveDate};
+    use vec1::vec1;
+
+    use super::*;
+
+    fn hour(h: u32) -> DateTime<Utc> {
+        Utc.from_utc_datetime(&NaiveDate::from_ymd(2022, 10, 1).and_hms(h, 0, 0))
+    }
+
+    #[test]
+    fn timestamps_with_timezones() {
+        let tz = FixedOffset::east(3600)
+            .from_local_datetime(&NaiveDate::from_ymd(2022, 10, 1).and_hms(2, 0, 0))
+            .unwrap();
+        assert_eq!(timestamp_instant(&DfValue::from(tz)), Some(hour(1)));
+        assert_eq!(timestamp_instant(&DfValue::from(1)), None);
+    }
+
+    #[test]
+    fn takes_keys_before_cutoff() {
+        let mut buckets = TimeBuckets::new(Duration::from_secs(3600));
+        let key = |h| {
+            KeyComparison::Equal(vec1![DfValue::from(
+                hour(h).with_timezone(&FixedOffset::east(0))
+            )])
+        };
+        buckets.filled(&key(1));
+        buckets.filled(&key(1));
+        buckets.filled(&key(3));
+        buckets.filled(&KeyComparison::Equal(vec1![DfValue::from(1)]));
+        assert_eq!(buckets.len(), 2);
+
+        assert_eq!(buckets.take_before(hour(2)), vec![key(1)]);
+        assert!(buckets.take_before(hour(2)).is_empty());
+        assert_eq!(buckets.len(), 1);
+    }
+
+    #[test]
+    fn ranges_expire_by_upper_bound() {
+        let ts = |h| DfValue::from(hour(h).with_timezone(&FixedOffset::east(0)));
+        let range = KeyComparison::from_range(&(vec1![ts(1)]..=vec1![ts(3)]));
+        let mut buckets = TimeBuckets::new(Duration::from_secs(3600));
+        buckets.filled(&range);
+        buckets.filled(&KeyComparison::Range((
+            Bound::Included(vec1![ts(1)]),
+            Bound::Unbounded,
+        )));
+        assert!(buckets.take_before(hour(2)).is_empty());
+        assert_eq!(buckets.take_before(hour(4)), vec![range]);
+    }
+}
@@ -0,0 +1,281 @@
+//! Checksums of the materialized state of nodes, used to detect replicas of a domain whose state
+//! has silently diverged.
+//!
+//! Checksums are computed for one [`KeyRange`] of a node's state at a time, so that a divergence
+//! can be narrowed down to a subset of the node's keys, and so that checksumming a large node
+//! doesn't block its domain for too long. The checksum of a set of rows is the (wrapping) sum of
+//! the hashes of the rows, which doesn't depend on the order the rows are stored in. Rows are
+//! hashed with XXH3, whose output is specified (unlike that of
+//! [`DefaultHasher`](std::collections::hash_map::DefaultHasher)), so replicas built with different
+//! versions of Rust compute the same checksums.
+//!
+//! The checksums of fully materialized nodes are kept up to date as rows are added to and removed
+//! from the node's state (see [`RowChecksums`]), so that checksumming a key range doesn't need to
+//! scan the state.
+//!
+//! Replicas of a partially materialized node can legitimately have different sets of keys filled,
+//! so rather than a single checksum, the state of a partial node is summarized as a checksum of
+//! the rows for each filled key. Two such summaries are only compared on the keys they have in
+//! common.
+
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use common::{DfValue, Records};
+use dataflow_state::{MaterializedNodeState, State};
+use serde::{Deserialize, Serialize};
+use xxhash_rust::xxh3::Xxh3;
+
+/// The number of buckets [`RowChecksums`] divides the rows of a node into. Key ranges made up of
+/// whole buckets (those whose number of ranges divides this) can be checksummed without scanning
+/// the state.
+const BUCKETS: u32 = 256;
+
+/// One of a fixed number of disjoint ranges of a node's keys, selected by the hash of the key
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct KeyRange {
+    /// Which of the ranges this is, in `0..ranges`
+    pub index: u32,
+    /// The total number of ranges the keys are divided into
+    pub ranges: u32,
+}
+
+impl KeyRange {
+    /// The single range containing every key
+    pub const ALL: KeyRange = KeyRange {
+        index: 0,
+        ranges: 1,
+    };
+
+    /// Returns `true` if a key (or row) with the given hash falls within this range
+    pub fn contains_hash(&self, hash: u64) -> bool {
+        self.ranges <= 1 || hash % u64::from(self.ranges) == u64::from(self.index)
+    }
+}
+
+/// A checksum of the rows materialized in one [`KeyRange`] of a node's state
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StateChecksum {
+    /// The node is fully materialized
+    Full {
+        /// The number of rows in the key range
+        rows: usize,
+        /// The checksum of all the rows in the key range
+        checksum: u64,
+    },
+    /// The node is partially materialized. Maps the hash of each filled key in the range to the
+    /// checksum of the rows for that key.
+    Partial(HashMap<u64, u64>),
+}
+
+impl StateChecksum {
+    /// Returns `true` if `self` and `other` are checksums of different state. Checksums of
+    /// partially materialized state are only compared on the keys which are filled in both.
+    pub fn diverges_from(&self, other: &StateChecksum) -> bool {
+        match (self, other) {
+            (StateChecksum::Partial(keys), StateChecksum::Partial(other_keys)) => keys
+                .iter()
+                .any(|(key, checksum)| matches!(other_keys.get(key), Some(c) if c != checksum)),
+            _ => self != other,
+        }
+    }
+}
+
+/// Returns a hash of a key or row which is stable across processes and builds
+pub(crate) fn stable_hash(values: &[DfValue]) -> u64 {
+    let mut hasher = Xxh3::new();
+    values.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returns the checksum of a set of rows
+pub(crate) fn rows_checksum<'a, I, R>(rows: I) -> u64
+where
+    I: IntoIterator<Item = &'a R>,
+    R: AsRef<[DfValue]> + ?Sized + 'a,
+{
+    rows.into_iter()
+        .fold(0u64, |acc, row| acc.wrapping_add(stable_hash(row.as_ref())))
+}
+
+/// Returns the checksum of the rows in `range` of the given fully materialized state by scanning
+/// it, or `None` if the state is partial. Since a node's state may be indexed in several ways, rows
+/// are assigned to key ranges by the hash of the whole row rather than of any one index's key.
+pub(crate) fn state_checksum(
+    state: &MaterializedNodeState,
+    range: KeyRange,
+) -> Option<StateChecksum> {
+    if state.is_partial() {
+        return None;
+    }
+    let mut rows = 0;
+    let mut checksum = 0u64;
+    state.for_each_record(&mut |row| {
+        let hash = stable_hash(row);
+        if range.contains_hash(hash) {
+            rows += 1;
+            checksum = checksum.wrapping_add(hash);
+        }
+    });
+    Some(StateChecksum::Full { rows, checksum })
+}
+
+/// The checksums of the rows of a fully materialized node's state, divided into [`BUCKETS`]
+/// buckets by the hash of each row, and updated with the records applied to the state
+#[derive(Clone, Debug)]
+pub(crate) struct RowChecksums {
+    /// The number of rows in, and the checksum of, each bucket
+    buckets: Vec<(usize, u64)>,
+}
+
+impl RowChecksums {
+    /// Compute the checksums of the rows of the given state by scanning it, or return `None` if
+    /// the state is partial
+    pub(crate) fn new(state: &MaterializedNodeState) -> Option<Self> {
+        if state.is_partial() {
+            return None;
+        }
+        let mut checksums = Self {
+            buckets: vec![(0, 0); BUCKETS as usize],
+        };
+        state.for_each_record(&mut |row| checksums.add(stable_hash(row)));
+        Some(checksums)
+    }
+
+    fn bucket(&mut self, hash: u64) -> &mut (usize, u64) {
+        #[allow(clippy::indexing_slicing)] // there are always `BUCKETS` buckets
+        &mut self.buckets[(hash % u64::from(BUCKETS)) as usize]
+    }
+
+    fn add(&mut self, hash: u64) {
+        let (rows, checksum) = self.bucket(hash);
+        *rows += 1;
+        *checksum = checksum.wrapping_add(hash);
+    }
+
+    fn remove(&mut self, hash: u64) {
+        let (rows, checksum) = self.bucket(hash);
+        *rows = rows.saturating_sub(1);
+        *checksum = checksum.wrapping_sub(hash);
+    }
+
+    /// Update the checksums with records which have just been applied to the state
+    pub(crate) fn apply(&mut self, records: &Records) {
+        for record in records.iter() {
+            let hash = stable_hash(record.rec());
+            if record.is_positive() {
+                self.add(hash);
+            } else {
+                self.remove(hash);
+            }
+        }
+    }
+
+    /// Returns the checksum of the rows in `range`, or `None` if the range isn't made up of whole
+    /// buckets
+    pub(crate) fn checksum(&self, range: KeyRange) -> Option<StateChecksum> {
+        if BUCKETS % range.ranges.max(1) != 0 {
+            return None;
+        }
+        let (rows, checksum) = self
+            .buckets
+            .iter()
+            .enumerate()
+            .filter(|(bucket, _)| range.contains_hash(*bucket as u64))
+            .fold(
+                (0, 0u64),
+                |(rows, checksum), (_, (bucket_rows, bucket_checksum))| {
+                    (rows + bucket_rows, checksum.wrapping_add(*bucket_checksum))
+                },
+            );
+        Some(StateChecksum::Full { rows, checksum })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::Record;
+    use dataflow_state::MemoryState;
+    use readyset_client::internal::Index;
+
+    use super::*;
+
+    #[test]
+    fn rows_checksum_ignores_order() {
+        let rows = vec![
+            vec![DfValue::from(1), DfValue::from("a")],
+            vec![DfValue::from(2), DfValue::from("b")],
+            vec![DfValue::from(2), DfValue::from("b")],
+        ];
+        let mut reversed = rows.clone();
+        reversed.reverse();
+        assert_eq!(rows_checksum(&rows), rows_checksum(&reversed));
+        assert_ne!(rows_checksum(&rows), rows_checksum(&rows[..2]));
+    }
+
+    #[test]
+    fn key_ranges_are_disjoint() {
+        let ranges = (0..4).map(|index| KeyRange { index, ranges: 4 });
+        for hash in 0..100 {
+            assert_eq!(ranges.clone().filter(|r| r.contains_hash(hash)).count(), 1);
+        }
+    }
+
+    #[test]
+    fn partial_checksums_compare_common_keys() {
+        let a = StateChecksum::Partial(HashMap::from([(1, 10), (2, 20)]));
+        let b = StateChecksum::Partial(HashMap::from([(2, 20), (3, 30)]));
+        let c = StateChecksum::Partial(HashMap::from([(2, 21)]));
+        assert!(!a.diverges_from(&b));
+        assert!(a.diverges_from(&c));
+        assert!(!a.diverges_from(&StateChecksum::Partial(HashMap::new())));
+
+        let full = StateChecksum::Full {
+            rows: 1,
+            checksum: 10,
+        };
+        assert!(!full.diverges_from(&full.clone()));
+        assert!(full.diverges_from(&StateChecksum::Full {
+            rows: 1,
+            checksum: 11
+        }));
+    }
+
+    #[test]
+    fn row_checksums_track_state() {
+        let mut state = MemoryState::default();
+        state.add_key(Index::hash_map(vec![0]), None);
+        let mut state = MaterializedNodeState::Memory(state);
+        let row = |i: i32| vec![DfValue::from(i), DfValue::from(format!("row {i}"))];
+
+        let mut records: Records = (0..100).map(|i| Record::Positive(row(i))).collect();
+        state.process_records(&mut records, None, None).unwrap();
+        let mut checksums = RowChecksums::new(&state).unwrap();
+
+        let mut records: Records = (0..50)
+            .map(|i| Record::Negative(row(i)))
+            .chain((100..120).map(|i| Record::Positive(row(i))))
+            .collect();
+        state.process_records(&mut records, None, None).unwrap();
+        checksums.apply(&records);
+
+        for range in (0..4).map(|index| KeyRange { index, ranges: 4 }) {
+            assert_eq!(checksums.checksum(range), state_checksum(&state, range));
+        }
+        assert_eq!(
+            checksums.checksum(KeyRange::ALL),
+            Some(StateChecksum::Full {
+                rows: 70,
+                checksum: rows_checksum(&(50..120).map(row).collect::<Vec<_>>()),
+            })
+        );
+        // Ranges which aren't made up of whole buckets have to be checksummed by scanning
+        assert_eq!(
+            checksums.checksum(KeyRange {
+                index: 0,
+                ranges: 3
+            }),
+            None
+        );
+    }
+}
@@ -0,0 +1,93 @@
+//! Cumulative event counters for a domain and its nodes, reported as part of the domain's
+//! statistics (see [`DomainRequest::GetStatistics`](crate::DomainRequest::GetStatistics)).
+
+use std::time::SystemTime;
+
+use readyset_client::debug::stats::Counters;
+
+use crate::prelude::*;
+
+/// Tracks [`Counters`] for a domain as a whole, as well as for each of the nodes in the domain.
+pub(super) struct DomainCounters {
+    domain: Counters,
+    nodes: NodeMap<Counters>,
+    reset_at: SystemTime,
+}
+
+impl Default for DomainCounters {
+    fn default() -> Self {
+        Self {
+            domain: Counters::default(),
+            nodes: NodeMap::default(),
+            reset_at: SystemTime::now(),
+        }
+    }
+}
+
+impl DomainCounters {
+    /// Apply `f` to the counters for both the given node and the domain as a whole
+    pub(super) fn record<F>(&mut self, node: LocalNodeIndex, f: F)
+    where
+        F: Fn(&mut Counters),
+    {
+        f(&mut self.domain);
+        f(self.nodes.entry(node).or_default());
+    }
+
+    /// Apply `f` to only the counters for the given node, for events which are counted separately
+    /// for the domain as a whole
+    pub(super) fn record_node<F>(&mut self, node: LocalNodeIndex, f: F)
+    where
+        F: FnOnce(&mut Counters),
+    {
+        f(self.nodes.entry(node).or_default());
+    }
+
+    /// Apply `f` to only the counters for the domain as a whole
+    pub(super) fn record_domain<F>(&mut self, f: F)
+    where
+        F: FnOnce(&mut Counters),
+    {
+        f(&mut self.domain);
+    }
+
+    /// Returns the counters for the domain as a whole
+    pub(super) fn domain(&self) -> Counters {
+        self.domain
+    }
+
+    /// Returns the counters for the given node, which will be all zeroes if nothing has been
+    /// recorded for that node yet
+    pub(super) fn node(&self, node: LocalNodeIndex) -> Counters {
+        self.nodes.get(node).copied().unwrap_or_default()
+    }
+
+    /// Returns the time at which the counters started counting
+    pub(super) fn reset_at(&self) -> SystemTime {
+        self.reset_at
+    }
+
+    /// Reset all counters back to zero
+    pub(super) fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn node_only_events_are_not_counted_for_domain() {
+        let mut counters = DomainCounters::default();
+        let node = LocalNodeIndex::make(0);
+        counters.record_domain(|c| c.packets_processed += 1);
+        counters.record_node(node, |c| c.packets_processed += 1);
+        counters.record(node, |c| c.misses += 2);
+
+        assert_eq!(counters.domain().packets_processed, 1);
+        assert_eq!(counters.domain().misses, 2);
+        assert_eq!(counters.node(node).packets_processed, 1);
+        assert_eq!(counters.node(node).misses, 2);
+    }
+}
@@ -45,6 +45,7 @@ pub(super) struct DomainMetrics {
     replay_misses: BTreeMap<(LocalNodeIndex, Tag), Counter>,
 
     reader_replay_request_time: NodeMap<(Counter, Histogram)>,
+    reader_replays_coalesced: NodeMap<Counter>,
     chuncked_replay_time: NodeMap<(Counter, Histogram)>,
     base_table_lookups: NodeMap<Counter>,
     node_state_size: NodeMap<Gauge>,
@@ -104,6 +105,7 @@ impl DomainMetrics {
             replay_misses: Default::default(),
             packets_sent: packets_sent.try_into().ok().unwrap(),
             reader_replay_request_time: Default::default(),
+            reader_replays_coalesced: Default::default(),
             base_table_lookups: Default::default(),
             node_state_size: Default::default(),
             shard,
@@ -311,6 +313,21 @@ impl DomainMetrics {
         }
     }
 
+    pub(super) fn inc_reader_replays_coalesced(&mut self, node: LocalNodeIndex, n: usize) {
+        if let Some(ctr) = self.reader_replays_coalesced.get(node) {
+            ctr.increment(n as u64);
+        } else {
+            let ctr = register_counter!(
+                recorded::DOMAIN_READER_REPLAYS_COALESCED,
+                "domain" => self.index.clone(),
+                "shard" => self.shard.clone(),
+                "node" => node.to_string(),
+            );
+            ctr.increment(n as u64);
+            self.reader_replays_coalesced.insert(node, ctr);
+        }
+    }
+
     pub(super) fn inc_replay_misses(&mut self, miss_in: LocalNodeIndex, needed_for: Tag, n: usize) {
         if let Some(ctr) = self.replay_misses.get(&(miss_in, needed_for)) {
             ctr.increment(n as u64);
@@ -1,13 +1,19 @@
+mod counters;
 mod domain_metrics;
+pub(crate) mod packet_log;
 mod replay_paths;
+mod replay_snapshot;
 
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet, VecDeque};
 use std::convert::{TryFrom, TryInto};
 use std::fmt::Debug;
 use std::ops::Bound;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
 use std::{cell, cmp, mem, time};
 
 use ahash::RandomState;
@@ -20,25 +26,31 @@ use futures_util::stream::StreamExt;
 pub use internal::{DomainIndex, ReplicaAddress};
 use merging_interval_tree::IntervalTreeSet;
 use petgraph::graph::NodeIndex;
+use readyset_client::debug::stats::ReadStatsBucket;
 use readyset_client::internal::Index;
 use readyset_client::replication::ReplicationOffset;
 use readyset_client::{channel, internal, KeyComparison, KeyCount, ReaderAddress, ReadySetError};
 use readyset_errors::{internal, internal_err, ReadySetResult};
 use readyset_tracing::{debug, error, trace, warn};
 use readyset_util::redacted::Sensitive;
-use readyset_util::Indices;
+use readyset_util::{cpu, Indices};
 use serde::{Deserialize, Serialize};
 use timekeeper::{RealTime, SimpleTracker, ThreadTime, Timer, TimerSet};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use vec1::Vec1;
 
+use self::counters::DomainCounters;
+use self::packet_log::{PacketLogRecord, PacketLogWriter};
 pub(crate) use self::replay_paths::ReplayPath;
 use self::replay_paths::{Destination, ReplayPathSpec, ReplayPaths, Target};
-use crate::node::special::EgressTx;
-use crate::node::{NodeProcessingResult, ProcessEnv};
+use self::replay_snapshot::ReplaySnapshot;
+use crate::checksum::{state_checksum, RowChecksums};
+use crate::node::special::{EgressTx, ReplicationConflictPolicy};
+use crate::node::{NodeProcessingResult, ProcessEnv, ReplayProvenance};
 use crate::payload::{PrepareStateKind, PrettyReplayPath, ReplayPieceContext, SourceSelection};
 use crate::prelude::*;
 use crate::processing::ColumnMiss;
+use crate::text_pool::TextPool;
 use crate::{backlog, DomainRequest, Readers};
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -57,10 +69,58 @@ pub struct Config {
 
     #[serde(default)]
     pub eviction_kind: crate::EvictionKind,
+
+    /// If set, full replays keep at most this many rows of the state they replay in memory, and
+    /// spill the rest to a temporary file on disk until they're replayed, bounding the peak memory
+    /// used to backfill very large materializations.
+    #[serde(default)]
+    pub replay_spill_threshold: Option<usize>,
+
+    /// If set, every domain records the packets, domain requests and timeouts it handles to a
+    /// packet log in this directory, so that its behavior can be replayed offline. See the
+    /// [`packet_log`] module for more information.
+    #[serde(default)]
+    pub packet_log_dir: Option<PathBuf>,
+
+    /// How base tables handle replicated writes which update or delete rows that don't exist
+    #[serde(default)]
+    pub replication_conflict_policy: ReplicationConflictPolicy,
+
+    /// If set to `true`, updates originating at base tables are tagged with the table, primary key
+    /// and replication offset of the writes they result from (and replays with the rows they read
+    /// from base tables), and readers record the tags of the update or replay which last wrote
+    /// each of their rows, to be retrieved with [`DomainRequest::RequestRowProvenance`]
+    #[serde(default)]
+    pub record_provenance: bool,
+
+    /// If set, keys of partially materialized readers are evicted this long after they're filled,
+    /// so that they're replayed the next time they're read.
+    #[serde(default)]
+    pub reader_key_ttl: Option<time::Duration>,
+
+    /// How much the time-to-live of each reader key is randomly varied by, as a percentage of
+    /// [`Config::reader_key_ttl`], so that keys which were filled at the same time (such as when a
+    /// cache is warmed) don't all expire, and get replayed, at the same time.
+    #[serde(default)]
+    pub reader_key_ttl_jitter_percent: u8,
+
+    /// If set, the maximum number of expired reader keys each domain evicts per second. Keys which
+    /// expire while the limit has been reached are evicted in later seconds, spreading the replays
+    /// of keys which expire together out over time.
+    #[serde(default)]
+    pub max_key_expirations_per_second: Option<usize>,
 }
 
 const BATCH_SIZE: usize = 256;
 
+/// The longest the domain waits between checks for time buckets which have fallen out of the
+/// bucket retention window of a reader (see
+/// [`Reader::bucket_retention`](crate::node::special::Reader::bucket_retention))
+const TIME_BUCKET_PURGE_INTERVAL: time::Duration = time::Duration::from_secs(60);
+
+/// How often the domain evicts reader keys whose [`Config::reader_key_ttl`] has passed
+const KEY_EXPIRY_INTERVAL: time::Duration = time::Duration::from_secs(1);
+
 #[derive(Debug)]
 enum DomainMode {
     Forwarding,
@@ -381,6 +441,7 @@ impl DomainBuilder {
         readers: Readers,
         channel_coordinator: Arc<ChannelCoordinator>,
         state_size: Arc<AtomicUsize>,
+        text_pool: TextPool,
     ) -> Domain {
         // initially, all nodes are not ready
         let not_ready = self
@@ -390,6 +451,11 @@ impl DomainBuilder {
             .collect();
 
         let address = self.address();
+        let packet_log = self.config.packet_log_dir.as_ref().and_then(|dir| {
+            PacketLogWriter::create(dir, &self)
+                .map_err(|error| error!(%error, "Failed to create domain packet log"))
+                .ok()
+        });
         Domain {
             index: self.index,
             shard: self.shard,
@@ -399,6 +465,7 @@ impl DomainBuilder {
             persistence_parameters: self.persistence_parameters,
             nodes: self.nodes,
             state: StateMap::default(),
+            state_checksums: Default::default(),
             reader_write_handles: Default::default(),
             not_ready,
             mode: DomainMode::Forwarding,
@@ -410,12 +477,14 @@ impl DomainBuilder {
 
             readers,
             channel_coordinator,
+            text_pool,
 
             timed_purges: Default::default(),
 
             delayed_for_self: Default::default(),
 
             state_size,
+            started_at: time::Instant::now(),
             total_time: Timer::new(),
             total_ptime: Timer::new(),
             wait_time: Timer::new(),
@@ -424,14 +493,28 @@ impl DomainBuilder {
 
             total_replay_time: Timer::new(),
             total_forward_time: Timer::new(),
+            counters: Default::default(),
 
             aggressively_update_state_sizes: self.config.aggressively_update_state_sizes,
             replay_completed: false,
+            failed_replay: Default::default(),
 
             metrics: domain_metrics::DomainMetrics::new(address),
 
             eviction_kind: self.config.eviction_kind,
+            replay_spill_threshold: self.config.replay_spill_threshold,
+            replication_conflict_policy: self.config.replication_conflict_policy,
+            record_provenance: self.config.record_provenance,
+            next_time_bucket_purge: None,
+            reader_key_ttl: self.config.reader_key_ttl,
+            reader_key_ttl_jitter_percent: self.config.reader_key_ttl_jitter_percent,
+            max_key_expirations_per_second: self.config.max_key_expirations_per_second,
+            next_key_expiry: self
+                .config
+                .reader_key_ttl
+                .map(|_| time::Instant::now() + KEY_EXPIRY_INTERVAL),
             remapped_keys: Default::default(),
+            packet_log,
         }
     }
 }
@@ -574,6 +657,10 @@ pub struct Domain {
     /// Invariant: All keys of `self.state` must also be keys in `self.nodes`
     state: StateMap,
 
+    /// Checksums of the rows of fully materialized non-reader nodes, computed the first time
+    /// they're requested and kept up to date from then on
+    state_checksums: NodeMap<RowChecksums>,
+
     /// State for all reader nodes managed by this domain
     ///
     /// Invariant: All keys of `self.reader_write_handles` must also be keys in `self.nodes`
@@ -612,6 +699,8 @@ pub struct Domain {
     delayed_for_self: VecDeque<Box<Packet>>,
 
     state_size: Arc<AtomicUsize>,
+    /// The time at which this domain was created, used to compute its CPU utilization
+    started_at: time::Instant,
     total_time: Timer<SimpleTracker, RealTime>,
     total_ptime: Timer<SimpleTracker, ThreadTime>,
     wait_time: Timer<SimpleTracker, RealTime>,
@@ -622,6 +711,8 @@ pub struct Domain {
     total_replay_time: Timer<SimpleTracker, RealTime>,
     /// time spent processing ordinary, forward updates
     total_forward_time: Timer<SimpleTracker, RealTime>,
+    /// cumulative counts of replays, evictions, misses and processed packets
+    counters: DomainCounters,
 
     /// If set to `true`, the metric tracking the in-memory size of materialized state will be
     /// updated after every packet is handled, rather than only when requested by the eviction
@@ -630,9 +721,40 @@ pub struct Domain {
     pub aggressively_update_state_sizes: bool,
 
     replay_completed: bool,
+    /// The first error encountered by a thread sending the records of a full replay started by
+    /// this domain, which is returned by [`DomainRequest::CheckFailedReplays`]
+    failed_replay: Arc<Mutex<Option<ReadySetError>>>,
 
     metrics: domain_metrics::DomainMetrics,
     eviction_kind: crate::EvictionKind,
+    /// See [`Config::replay_spill_threshold`]
+    replay_spill_threshold: Option<usize>,
+
+    /// See [`Config::replication_conflict_policy`]
+    replication_conflict_policy: ReplicationConflictPolicy,
+
+    /// See [`Config::record_provenance`]
+    record_provenance: bool,
+
+    /// When to next check for time buckets which have fallen out of the retention window of a
+    /// reader, if any readers in the domain have one
+    next_time_bucket_purge: Option<time::Instant>,
+
+    /// See [`Config::reader_key_ttl`]
+    reader_key_ttl: Option<time::Duration>,
+    /// See [`Config::reader_key_ttl_jitter_percent`]
+    reader_key_ttl_jitter_percent: u8,
+    /// See [`Config::max_key_expirations_per_second`]
+    max_key_expirations_per_second: Option<usize>,
+    /// When to next evict expired reader keys
+    next_key_expiry: Option<time::Instant>,
+
+    /// Pool of interned text values shared with the other domains on this worker, used for the
+    /// rows in writes and replays received by this domain
+    text_pool: TextPool,
+
+    /// See [`Config::packet_log_dir`]
+    packet_log: Option<PacketLogWriter>,
 }
 
 impl Domain {
@@ -651,6 +773,13 @@ impl Domain {
         self.replica
     }
 
+    /// Construct a new in-memory state for the given node, seeded with the node's global index and
+    /// this domain's shard so that every node evicts different keys, but deterministically.
+    fn new_memory_state(&self, node: LocalNodeIndex) -> MemoryState {
+        let index = self.nodes[node].borrow().global_addr().index() as u64;
+        MemoryState::with_eviction_seed(index << 32 | self.shard() as u64)
+    }
+
     fn snapshotting_base_nodes(&self) -> Vec<LocalNodeIndex> {
         self.state
             .iter()
@@ -706,6 +835,10 @@ impl Domain {
             }
         );
 
+        let num_keys = miss_keys.len() as u64;
+        self.counters
+            .record(dst.0, |c| c.replays_requested += num_keys);
+
         for &tag in &tags {
             // send a message to the source domain(s) responsible
             // for the chosen tag so they'll start replay.
@@ -768,6 +901,12 @@ impl Domain {
 
         self.metrics
             .inc_replay_misses(miss_in, needed_for, missed_keys.len());
+        // Misses during replays are always caused by a miss that was already counted for the
+        // domain, either in one of our readers or in the replay request we received from another
+        // domain, so they only count against the node
+        let num_missed = missed_keys.len() as u64;
+        self.counters
+            .record_node(miss_in, |c| c.misses += num_missed);
 
         let is_generated = self
             .replay_paths
@@ -1020,10 +1159,15 @@ impl Domain {
                 // we also sent that many requests *per key*.
                 requests_satisfied *= num;
                 trace!(num_done = requests_satisfied, "notified of finished replay");
+                self.counters
+                    .record(last.node, |c| c.replays_satisfied += num as u64);
                 Ok(())
             }
             TriggerEndpoint::Local(..) => {
                 // didn't count against our quote, so we're also not decementing
+                let last = self.replay_paths[tag].last_segment().node;
+                self.counters
+                    .record(last, |c| c.replays_satisfied += num as u64);
                 Ok(())
             }
             TriggerEndpoint::Start(..) | TriggerEndpoint::None => {
@@ -1058,6 +1202,9 @@ impl Domain {
             return Ok(());
         }
 
+        // Packets are counted for the domain as a whole once, when they're handled
+        self.counters.record_node(me, |c| c.packets_processed += 1);
+
         let (mut m, evictions) = {
             #[allow(clippy::indexing_slicing)] // we checked the node exists already
             let mut n = self.nodes[me].borrow_mut();
@@ -1073,11 +1220,14 @@ impl Domain {
                 true,
                 ProcessEnv {
                     state: &mut self.state,
+                    state_checksums: &mut self.state_checksums,
                     reader_write_handles: &mut self.reader_write_handles,
                     nodes: &self.nodes,
                     executor,
                     shard: self.shard,
                     replica: self.replica,
+                    replication_conflict_policy: self.replication_conflict_policy,
+                    record_provenance: self.record_provenance,
                 },
             )?;
             assert_eq!(captured.len(), 0);
@@ -1285,6 +1435,9 @@ impl Domain {
         req: DomainRequest,
         executor: &mut dyn Executor,
     ) -> ReadySetResult<Option<Vec<u8>>> {
+        if self.packet_log.is_some() {
+            self.record(PacketLogRecord::Request(req.clone()));
+        }
         let ret = match req {
             DomainRequest::AddNode { node, parents } => {
                 let addr = node.local_addr();
@@ -1311,6 +1464,7 @@ impl Domain {
                     if let Some(state) = self.state.remove(node) {
                         state.tear_down()?;
                     };
+                    self.state_checksums.remove(node);
                     self.reader_write_handles.remove(node);
                     self.metrics.set_node_state_size(node, 0);
                     trace!(local = node.id(), "node removed");
@@ -1438,6 +1592,44 @@ impl Domain {
                     .add_for_filtering(target_node);
                 Ok(None)
             }
+            DomainRequest::SetEgressPredicate {
+                egress_node,
+                target_node,
+                predicate,
+            } => {
+                let mut n = self
+                    .nodes
+                    .get(egress_node)
+                    .ok_or_else(|| ReadySetError::NoSuchNode(egress_node.id()))?
+                    .borrow_mut();
+
+                n.as_mut_egress()
+                    .ok_or(ReadySetError::InvalidNodeType {
+                        node_index: egress_node.id(),
+                        expected_type: NodeType::Egress,
+                    })?
+                    .set_predicate(target_node, predicate);
+                Ok(None)
+            }
+            DomainRequest::SetReaderMaxStaleness {
+                node,
+                max_staleness,
+            } => {
+                self.nodes
+                    .get(node)
+                    .ok_or_else(|| ReadySetError::NoSuchNode(node.id()))?
+                    .borrow_mut()
+                    .as_mut_reader()
+                    .ok_or(ReadySetError::InvalidNodeType {
+                        node_index: node.id(),
+                        expected_type: NodeType::Reader,
+                    })?
+                    .set_max_staleness(max_staleness);
+                if let Some(wh) = self.reader_write_handles.get_mut(node) {
+                    wh.set_max_staleness(Some(max_staleness));
+                }
+                Ok(None)
+            }
             DomainRequest::AddSharderTx {
                 sharder_node,
                 ingress_node,
@@ -1470,10 +1662,9 @@ impl Domain {
                         weak_indices,
                     } => {
                         if !self.state.contains_key(node) {
-                            self.state.insert(
-                                node,
-                                MaterializedNodeState::Memory(MemoryState::default()),
-                            );
+                            let state = self.new_memory_state(node);
+                            self.state
+                                .insert(node, MaterializedNodeState::Memory(state));
                         }
                         let state = self.state.get_mut(node).unwrap();
                         for (index, tags) in strict_indices {
@@ -1496,10 +1687,9 @@ impl Domain {
                         weak_indices,
                     } => {
                         if !self.state.contains_key(node) {
-                            self.state.insert(
-                                node,
-                                MaterializedNodeState::Memory(MemoryState::default()),
-                            );
+                            let state = self.new_memory_state(node);
+                            self.state
+                                .insert(node, MaterializedNodeState::Memory(state));
                         }
                         let state = self.state.get_mut(node).unwrap();
                         for index in strict_indices {
@@ -1577,7 +1767,7 @@ impl Domain {
                         #[allow(clippy::unwrap_used)] // checked it was a reader above
                         let r = n.as_mut_reader().unwrap();
 
-                        let (r_part, w_part) = backlog::new_partial(
+                        let (mut r_part, mut w_part) = backlog::new_partial(
                             num_columns,
                             index,
                             move |misses: &mut dyn Iterator<Item = KeyComparison>| {
@@ -1612,6 +1802,20 @@ impl Domain {
                             self.eviction_kind,
                             r.reader_processing().clone(),
                         );
+                        r_part.lazy_joins = r.lazy_joins().to_vec();
+                        r_part.result_limits = r.result_limits();
+                        w_part.set_max_staleness(r.max_staleness());
+                        w_part.set_bucket_retention(r.bucket_retention());
+                        w_part.set_base_tables(r.base_tables().iter().copied().collect());
+                        if let Some(retention) = r.bucket_retention() {
+                            let next =
+                                time::Instant::now() + retention.min(TIME_BUCKET_PURGE_INTERVAL);
+                            self.next_time_bucket_purge = Some(
+                                self.next_time_bucket_purge
+                                    .map_or(next, |purge| purge.min(next)),
+                            );
+                        }
+                        w_part.set_key_ttl(self.reader_key_ttl, self.reader_key_ttl_jitter_percent);
 
                         let shard = *self.shard.as_ref().unwrap_or(&0);
                         // TODO(ENG-838): Don't recreate every single node on leader failure.
@@ -1655,8 +1859,10 @@ impl Domain {
                                     expected_type: NodeType::Reader,
                                 })?;
 
-                        let (r_part, w_part) =
+                        let (mut r_part, w_part) =
                             backlog::new(num_columns, index, r.reader_processing().clone());
+                        r_part.lazy_joins = r.lazy_joins().to_vec();
+                        r_part.result_limits = r.result_limits();
 
                         let shard = *self.shard.as_ref().unwrap_or(&0);
                         // TODO(ENG-838): Don't recreate every single node on leader failure.
@@ -1791,14 +1997,17 @@ impl Domain {
                 // we clone the entire state so that we can continue to occasionally
                 // process incoming updates to the domain without disturbing the state that
                 // is being replayed.
-                let state = self
-                    .state
-                    .get(from)
-                    .expect("migration replay path started with non-materialized node")
-                    .cloned_records();
+                let state = ReplaySnapshot::take(
+                    self.state
+                        .get(from)
+                        .expect("migration replay path started with non-materialized node"),
+                    self.replay_spill_threshold,
+                    BATCH_SIZE,
+                )?;
 
                 debug!(
                     μs = %start.elapsed().as_micros(),
+                    spilled_chunks = state.spilled_chunks(),
                     "current state cloned for replay"
                 );
 
@@ -1820,6 +2029,7 @@ impl Domain {
                         last: state.is_empty(),
                     },
                     data: Vec::<Record>::new().into(),
+                    provenance: None,
                 });
 
                 if !state.is_empty() {
@@ -1849,6 +2059,9 @@ impl Domain {
                         r
                     };
 
+                    let chunks = state.into_chunks()?;
+                    let replay_provenance = self.replay_provenance(from);
+                    let failed_replay = self.failed_replay.clone();
                     let replay_tx_desc = self.channel_coordinator.builder_for(&self.address())?;
 
                     // Have to get metrics here so we can move them to the thread
@@ -1869,8 +2082,6 @@ impl Domain {
                             link.src
                         ))
                         .spawn(move || {
-                            use itertools::Itertools;
-
                             // TODO: make async
                             let mut chunked_replay_tx = match replay_tx_desc.build_sync() {
                                 Ok(r) => r,
@@ -1883,20 +2094,33 @@ impl Domain {
                             let start = time::Instant::now();
                             debug!(node = %link.dst, "starting state chunker");
 
-                            let iter = state.into_iter().chunks(BATCH_SIZE);
-                            let mut iter = iter.into_iter().enumerate().peekable();
+                            let mut iter = chunks.enumerate().peekable();
 
                             // process all records in state to completion within domain
                             // and then forward on tx (if there is one)
                             while let Some((i, chunk)) = iter.next() {
-                                let chunk = Records::from_iter(chunk.map(&fix));
+                                let chunk = match chunk {
+                                    Ok(chunk) => Records::from_iter(chunk.into_iter().map(&fix)),
+                                    Err(error) => {
+                                        error!(%error, "Error reading spilled state for replay");
+                                        // The rest of the replay will never arrive, so make sure
+                                        // whoever's waiting for it finds out
+                                        #[allow(clippy::unwrap_used)]
+                                        // lock poisoning is unrecoverable
+                                        let mut failed_replay = failed_replay.lock().unwrap();
+                                        failed_replay.get_or_insert(error);
+                                        break;
+                                    }
+                                };
                                 let len = chunk.len();
                                 let last = iter.peek().is_none();
+                                let provenance = replay_provenance.as_ref().map(|rp| rp.tags([]));
                                 let p = Box::new(Packet::ReplayPiece {
                                     tag,
                                     link, // to is overwritten by receiver
                                     context: ReplayPieceContext::Regular { last },
                                     data: chunk,
+                                    provenance,
                                 });
 
                                 trace!(num = i, len, "sending batch");
@@ -1963,7 +2187,7 @@ impl Domain {
                                     &self.persistence_parameters,
                                 ))
                             }
-                            _ => MaterializedNodeState::Memory(MemoryState::default()),
+                            _ => MaterializedNodeState::Memory(self.new_memory_state(node)),
                         }
                     };
                     for idx in index {
@@ -1989,12 +2213,23 @@ impl Domain {
                 Ok(None)
             }
             DomainRequest::GetStatistics => {
+                // Domains each run on their own thread, and statistics requests are handled on
+                // that thread, so the CPU time of the current thread is the CPU time of the domain
+                let cpu_time = cpu::thread_cpu_time();
+                let cpu_utilization = cpu_time.map(|cpu_time| {
+                    cpu_time.as_secs_f64() / self.started_at.elapsed().as_secs_f64().max(1e-9)
+                });
                 let domain_stats = readyset_client::debug::stats::DomainStats {
                     total_time: self.total_time.num_nanoseconds(),
                     total_ptime: self.total_ptime.num_nanoseconds(),
                     total_replay_time: self.total_replay_time.num_nanoseconds(),
                     total_forward_time: self.total_forward_time.num_nanoseconds(),
                     wait_time: self.wait_time.num_nanoseconds(),
+                    cpu_time: cpu_time.map(|t| t.as_nanos() as u64),
+                    cpu_utilization,
+                    counters: self.counters.domain(),
+                    counters_reset_at: self.counters.reset_at(),
+                    collected_at: SystemTime::now(),
                 };
 
                 let node_stats: HashMap<
@@ -2062,6 +2297,11 @@ impl Domain {
                                     mem_size,
                                     materialized: mat_state,
                                     probe_result,
+                                    counters: self.counters.node(local_index),
+                                    reader: self
+                                        .reader_write_handles
+                                        .get(local_index)
+                                        .map(|wh| wh.reader_stats()),
                                 },
                             ))
                         } else {
@@ -2073,6 +2313,27 @@ impl Domain {
                 let ret = (domain_stats, node_stats);
                 Ok(Some(bincode::serialize(&ret)?))
             }
+            DomainRequest::ResetStatistics => {
+                self.counters.reset();
+                for wh in self.reader_write_handles.values() {
+                    wh.reset_read_counts();
+                }
+                Ok(None)
+            }
+            DomainRequest::GetReadHistory => {
+                let now = SystemTime::now();
+                let res: HashMap<NodeIndex, Vec<ReadStatsBucket>> = self
+                    .reader_write_handles
+                    .iter()
+                    .map(|(local, wh)| {
+                        (
+                            self.nodes[*local].borrow().global_addr(),
+                            wh.read_history(now),
+                        )
+                    })
+                    .collect();
+                Ok(Some(bincode::serialize(&res)?))
+            }
             DomainRequest::UpdateStateSize => {
                 self.update_state_sizes();
                 Ok(None)
@@ -2102,25 +2363,152 @@ impl Domain {
                 }
                 Ok(Some(bincode::serialize(&res)?))
             }
+            DomainRequest::RequestStateChecksums { range } => {
+                let mut res = Vec::new();
+                for (local_index, node_ref) in self.nodes.iter() {
+                    let node = node_ref.borrow();
+                    if node.is_reader() {
+                        if let Some(wh) = self.reader_write_handles.get(local_index) {
+                            res.push((node.global_addr(), wh.checksum(range)));
+                        }
+                    } else if !node.is_base() {
+                        let state = match self.state.get(local_index) {
+                            Some(state) => state,
+                            None => continue,
+                        };
+                        // Checksums are computed by scanning the state the first time they're
+                        // requested, and kept up to date as records are materialized from then on
+                        if !self.state_checksums.contains_key(local_index) {
+                            if let Some(checksums) = RowChecksums::new(state) {
+                                self.state_checksums.insert(local_index, checksums);
+                            }
+                        }
+                        if let Some(checksum) = self
+                            .state_checksums
+                            .get(local_index)
+                            .and_then(|checksums| checksums.checksum(range))
+                            .or_else(|| state_checksum(state, range))
+                        {
+                            res.push((node.global_addr(), checksum));
+                        }
+                    }
+                }
+                Ok(Some(bincode::serialize(&res)?))
+            }
+            DomainRequest::RequestViewStats { node } => {
+                let stats = self
+                    .reader_write_handles
+                    .get(node)
+                    .ok_or(ReadySetError::InvalidNodeType {
+                        node_index: node.id(),
+                        expected_type: NodeType::Reader,
+                    })?
+                    .stats();
+                Ok(Some(bincode::serialize(&stats)?))
+            }
+            DomainRequest::RequestColumnMax { node, column } => {
+                let state = self
+                    .state
+                    .get(node)
+                    .ok_or_else(|| internal_err!("Base table node {node} has no state"))?;
+                let mut max: Option<DfValue> = None;
+                // Only clone the values which are larger than the largest value seen so far
+                state.for_each_record(&mut |row| {
+                    if let Some(value) = row.get(column) {
+                        if !value.is_none() && max.as_ref().map_or(true, |max| value > max) {
+                            max = Some(value.clone());
+                        }
+                    }
+                });
+                Ok(Some(bincode::serialize(&max)?))
+            }
+            DomainRequest::RequestBaseRows { node, columns, key } => {
+                let state = self
+                    .state
+                    .get(node)
+                    .ok_or_else(|| internal_err!("Base table node {node} has no state"))?;
+                let primary_key = self
+                    .nodes
+                    .get(node)
+                    .and_then(|n| n.borrow().get_base()?.primary_key().map(<[usize]>::to_vec));
+                let rows = if !key.is_empty() && primary_key.as_deref() == Some(&columns) {
+                    match state.lookup(&columns, &PointKey::from(key)) {
+                        LookupResult::Some(rows) => rows.into_iter().map(Cow::into_owned).collect(),
+                        LookupResult::Missing => {
+                            internal!("Lookup into fully materialized base table state missed")
+                        }
+                    }
+                } else {
+                    let mut rows = vec![];
+                    state.for_each_record(&mut |row| {
+                        if columns
+                            .iter()
+                            .zip(&key)
+                            .all(|(&i, v)| row.get(i) == Some(v))
+                        {
+                            rows.push(row.to_vec());
+                        }
+                    });
+                    rows
+                };
+                Ok(Some(bincode::serialize(&rows)?))
+            }
+            DomainRequest::RequestReaderBarriers => {
+                let res = self
+                    .nodes
+                    .values()
+                    .map(|node| node.borrow())
+                    .filter(|node| node.is_reader() && !node.is_dropped())
+                    .map(|node| {
+                        let barrier = self
+                            .reader_write_handles
+                            .get(node.local_addr())
+                            .map_or(node.barrier(), |wh| wh.barrier());
+                        (node.global_addr(), barrier)
+                    })
+                    .collect::<Vec<_>>();
+                Ok(Some(bincode::serialize(&res)?))
+            }
+            DomainRequest::RequestRowProvenance { node, key } => {
+                let provenance = self
+                    .reader_write_handles
+                    .get(node)
+                    .ok_or(ReadySetError::InvalidNodeType {
+                        node_index: node.id(),
+                        expected_type: NodeType::Reader,
+                    })?
+                    .row_provenance(&key);
+                Ok(Some(bincode::serialize(&provenance)?))
+            }
             DomainRequest::Packet(pkt) => {
                 self.handle_packet(Box::new(pkt), executor)?;
                 Ok(None)
             }
-            DomainRequest::QueryReplayDone => {
+            DomainRequest::QueryReplayDone { .. } => {
                 let ret = self.replay_completed;
                 self.replay_completed = false;
                 Ok(Some(bincode::serialize(&ret)?))
             }
+            DomainRequest::CheckFailedReplays => {
+                #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+                let failed_replay = self.failed_replay.lock().unwrap().take();
+                match failed_replay {
+                    Some(error) => Err(error),
+                    None => Ok(None),
+                }
+            }
             DomainRequest::GeneratedColumns { node, index, tag } => {
                 // Record that these columns are generated...
                 self.replay_paths
                     .insert_generated_columns(node, index.columns.clone(), tag);
                 // ...and also make sure we use that tag to index those columns in this node, so we
                 // know what hole to fill when we've satisfied replays to those columns
-                self.state
-                    .entry(node)
-                    .or_insert_with(|| MaterializedNodeState::Memory(MemoryState::default()))
-                    .add_key(index, Some(vec![tag]));
+                if !self.state.contains_key(node) {
+                    let state = self.new_memory_state(node);
+                    self.state
+                        .insert(node, MaterializedNodeState::Memory(state));
+                }
+                self.state[node].add_key(index, Some(vec![tag]));
                 Ok(None)
             }
         };
@@ -2140,6 +2528,7 @@ impl Domain {
         // want.
 
         self.metrics.inc_packets_sent(&m);
+        self.counters.record_domain(|c| c.packets_processed += 1);
 
         match *m {
             Packet::Message { .. } | Packet::Input { .. } => {
@@ -2177,6 +2566,8 @@ impl Domain {
             } => {
                 let start = time::Instant::now();
                 self.total_replay_time.start();
+                let num_missed = keys.len() as u64;
+                self.counters.record(node, |c| c.misses += num_missed);
 
                 let mut n = self
                     .nodes
@@ -2198,11 +2589,15 @@ impl Domain {
                 // ensure that all writes have been applied
                 w.swap();
 
-                // don't request keys that have been filled since the request was sent
-                let mut keys = keys
+                // don't request keys that have been filled since the request was sent. Since
+                // nothing will fill those keys again, they're no longer pending in the reader.
+                let mut keys: Vec<KeyComparison> = keys
                     .drain(..)
                     .filter_map(|k| match k {
-                        key @ KeyComparison::Equal(_) if w.contains(&key) == Ok(true) => None,
+                        key @ KeyComparison::Equal(_) if w.contains(&key) == Ok(true) => {
+                            w.clear_pending(&key);
+                            None
+                        }
                         key @ KeyComparison::Equal(_) => Some(vec![key]),
                         key @ KeyComparison::Range(_) => w.interval_difference(key),
                     })
@@ -2219,7 +2614,12 @@ impl Domain {
                     .reader_triggered
                     .entry(node)
                     .or_insert_with(|| RequestedKeys::new(reader_index_type));
+                let requested = keys.len();
                 already_requested.extend(&mut keys);
+                if keys.len() < requested {
+                    self.metrics
+                        .inc_reader_replays_coalesced(node, requested - keys.len());
+                }
                 if !keys.is_empty() {
                     self.find_tags_and_replay(
                         keys,
@@ -2269,6 +2669,204 @@ impl Domain {
         Ok(())
     }
 
+    /// Evict keys containing time buckets older than the bucket retention window of their reader
+    /// (see [`Reader::bucket_retention`](crate::node::special::Reader::bucket_retention)) from
+    /// every partially materialized reader in the domain which has one, and schedule the next
+    /// check.
+    ///
+    /// Only partial readers are purged, since evicted keys can be replayed again if they're read.
+    fn purge_expired_time_buckets(&mut self, executor: &mut dyn Executor) -> ReadySetResult<()> {
+        let now = time::Instant::now();
+        self.next_time_bucket_purge = None;
+        let mut expired = vec![];
+        for (node, wh) in self.reader_write_handles.iter_mut() {
+            let retention = match wh.bucket_retention() {
+                Some(retention) if wh.is_partial() => retention,
+                _ => continue,
+            };
+            let next = now + retention.min(TIME_BUCKET_PURGE_INTERVAL);
+            self.next_time_bucket_purge = Some(
+                self.next_time_bucket_purge
+                    .map_or(next, |purge| purge.min(next)),
+            );
+
+            let cutoff = chrono::Utc::now()
+                - chrono::Duration::from_std(retention)
+                    .map_err(|e| internal_err!("Invalid time bucket retention: {e}"))?;
+            let size_before = wh.deep_size_of();
+            let keys = wh.evict_keys_before(cutoff)?;
+            if keys.is_empty() {
+                continue;
+            }
+            wh.swap();
+            wh.notify_readers_of_eviction()?;
+
+            let freed = size_before.saturating_sub(wh.deep_size_of());
+            debug!(
+                node = %node,
+                evicted = %keys.len(),
+                %freed,
+                "Evicted expired time buckets from reader"
+            );
+            self.counters.record(node, |c| c.evictions += 1);
+            self.state_size.fetch_sub(freed as usize, Ordering::AcqRel);
+            expired.push((node, keys));
+        }
+
+        for (reader, keys) in expired {
+            self.evict_expired_buckets_upstream(reader, keys, executor)?;
+        }
+        Ok(())
+    }
+
+    /// Evict `keys`, which have just been evicted from `reader` because their time buckets expired,
+    /// from the partially materialized node in this domain which the reader is filled from, so
+    /// that the intermediate state for the expired buckets (such as the values of aggregates) is
+    /// dropped along with the keys themselves.
+    ///
+    /// Like any other eviction, this also evicts the keys from everything else filled from that
+    /// node, which will replay them again if they're read. If the reader is filled from a node in
+    /// another domain, only the reader's keys are evicted.
+    fn evict_expired_buckets_upstream(
+        &mut self,
+        reader: LocalNodeIndex,
+        keys: Vec<KeyComparison>,
+        executor: &mut dyn Executor,
+    ) -> ReadySetResult<()> {
+        let sources = self
+            .replay_paths
+            .into_iter()
+            .filter(|(_, path)| path.last_segment().node == reader)
+            .filter_map(|(_, path)| match (path.source, &path.trigger) {
+                (Some(source), TriggerEndpoint::Local(index)) => Some((source, index.clone())),
+                _ => None,
+            })
+            .collect::<HashSet<_>>();
+
+        for (source, index) in sources {
+            if !self
+                .state
+                .get(source)
+                .map_or(false, |state| state.is_partial())
+            {
+                continue;
+            }
+            let tag = match self
+                .replay_paths
+                .tags_for_index(Destination(source), Target(source), &index)
+                .and_then(|tags| tags.first())
+            {
+                Some(tag) => *tag,
+                None => continue,
+            };
+            trace!(%reader, %source, ?tag, "Evicting expired time buckets from reader's source");
+            self.handle_eviction(
+                Packet::EvictKeys {
+                    link: Link {
+                        src: source,
+                        dst: source,
+                    },
+                    tag,
+                    keys: keys.clone(),
+                },
+                executor,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Evict keys of partially materialized readers whose [`Config::reader_key_ttl`] has passed, up
+    /// to [`Config::max_key_expirations_per_second`] keys, and schedule the next eviction.
+    ///
+    /// Keys are evicted in order of when they expired across all readers (with ties broken by the
+    /// reader's index), so when more keys expire than the limit allows, the ones which have been
+    /// waiting the longest are evicted first and no reader's keys are starved by another's.
+    ///
+    /// Since this runs once every [`KEY_EXPIRY_INTERVAL`], the limit is applied per interval
+    /// rather than over a sliding window.
+    fn expire_reader_keys(&mut self) -> ReadySetResult<()> {
+        if self.reader_key_ttl.is_none() {
+            return Ok(());
+        }
+        let now = time::Instant::now();
+        self.next_key_expiry = Some(now + KEY_EXPIRY_INTERVAL);
+
+        let mut next_expired = BinaryHeap::new();
+        for (node, wh) in self.reader_write_handles.iter_mut() {
+            if !wh.is_partial() {
+                continue;
+            }
+            if let Some(deadline) = wh.next_expired_key(now) {
+                next_expired.push(Reverse((deadline, node)));
+            }
+        }
+
+        // For each reader we evict keys from, its size before evicting and the number of keys
+        // evicted
+        let mut evicted_from: BTreeMap<LocalNodeIndex, (u64, usize)> = BTreeMap::new();
+        let mut budget = self.max_key_expirations_per_second.unwrap_or(usize::MAX);
+        while budget > 0 {
+            let node = match next_expired.pop() {
+                Some(Reverse((_, node))) => node,
+                None => break,
+            };
+            #[allow(clippy::unwrap_used)] // we got the node from reader_write_handles
+            let wh = self.reader_write_handles.get_mut(node).unwrap();
+            let (_, evicted) = evicted_from
+                .entry(node)
+                .or_insert_with(|| (wh.deep_size_of(), 0));
+            let n = wh.expire_keys(now, 1)?;
+            *evicted += n;
+            budget -= n;
+            if let Some(deadline) = wh.next_expired_key(now) {
+                next_expired.push(Reverse((deadline, node)));
+            }
+        }
+
+        for (node, (size_before, evicted)) in evicted_from {
+            if evicted == 0 {
+                continue;
+            }
+            #[allow(clippy::unwrap_used)] // we got the node from reader_write_handles
+            let wh = self.reader_write_handles.get_mut(node).unwrap();
+            wh.swap();
+            wh.notify_readers_of_eviction()?;
+
+            let freed = size_before.saturating_sub(wh.deep_size_of());
+            trace!(
+                node = %node,
+                %evicted,
+                %freed,
+                "Evicted expired keys from reader"
+            );
+            self.counters.record(node, |c| c.evictions += 1);
+            self.state_size.fetch_sub(freed as usize, Ordering::AcqRel);
+        }
+        Ok(())
+    }
+
+    /// Make the writes held by readers for write batches which have been held for longer than
+    /// [`backlog::BATCH_HOLD_TIMEOUT`] visible, since the rest of those batches will likely never
+    /// arrive
+    fn release_timed_out_batches(&mut self) -> ReadySetResult<()> {
+        let now = time::Instant::now();
+        for (node, wh) in self.reader_write_handles.iter_mut() {
+            let released = wh.release_timed_out_batches(now);
+            if released == 0 {
+                continue;
+            }
+            warn!(
+                %node,
+                %released,
+                "Write batches timed out before reaching reader from every base table; making \
+                 their writes visible"
+            );
+            wh.swap();
+            wh.notify_readers()?;
+        }
+        Ok(())
+    }
+
     /// Timed purges happen when [`FrontierStrategy`] is not None, in which case all keys
     /// are purged from the node after a given amount of time
     fn handle_timed_purges(&mut self) -> ReadySetResult<()> {
@@ -2306,6 +2904,16 @@ impl Domain {
         Ok(())
     }
 
+    /// If provenance recording is enabled, returns where the rows replayed out of `node` came from
+    fn replay_provenance(&self, node: LocalNodeIndex) -> Option<ReplayProvenance> {
+        if !self.record_provenance {
+            return None;
+        }
+        self.nodes
+            .get(node)
+            .map(|n| ReplayProvenance::for_node(&n.borrow()))
+    }
+
     fn seed_row(&self, source: LocalNodeIndex, row: Cow<[DfValue]>) -> ReadySetResult<Record> {
         if let Some(&(start, ref defaults)) = self.ingress_inject.get(source) {
             let mut v = Vec::with_capacity(start + defaults.len());
@@ -2480,8 +3088,18 @@ impl Domain {
         let dst = path[0].node;
         let index = index.clone(); // Clone to free the immutable reference at the top
         let src = *source;
+        #[allow(clippy::indexing_slicing)] // tag checked above
+        let requested_remotely =
+            matches!(self.replay_paths[tag].trigger, TriggerEndpoint::Start(..));
 
         if !replay_keys.is_empty() {
+            if requested_remotely {
+                // Locally-triggered replays only happen after a miss in one of our own nodes, which
+                // has already been counted for the domain
+                let num_missed = replay_keys.len() as u64;
+                self.counters.record_domain(|c| c.misses += num_missed);
+            }
+
             // we have missed in our lookup, so we have a partial replay through a partial replay
             // trigger a replay to source node, and enqueue this request.
             trace!(
@@ -2516,6 +3134,8 @@ impl Domain {
                 "satisfied replay request"
             );
 
+            let data = Records::from(records);
+            let provenance = self.replay_provenance(src).map(|rp| rp.tags(&found_keys));
             self.handle_replay(
                 Packet::ReplayPiece {
                     link: Link::new(src, dst),
@@ -2526,7 +3146,8 @@ impl Domain {
                         requesting_shard,
                         requesting_replica,
                     },
-                    data: records.into(),
+                    data,
+                    provenance,
                 },
                 ex,
             )?;
@@ -2590,14 +3211,14 @@ impl Domain {
                 }
             }
 
-            let (tag, link, mut data, mut context) = match m {
+            let (tag, link, mut data, mut context, provenance) = match m {
                 Packet::ReplayPiece {
                     tag,
                     link,
-
                     data,
                     context,
-                } => (tag, link, data, context),
+                    provenance,
+                } => (tag, link, data, context, provenance),
                 _ => internal!(),
             };
 
@@ -2697,6 +3318,7 @@ impl Domain {
                 tag,
                 data,
                 context,
+                provenance,
             });
             let mut m = Some(m);
 
@@ -2730,6 +3352,8 @@ impl Domain {
                 #[allow(clippy::indexing_slicing)]
                 // we know replay paths only contain real nodes
                 let mut n = self.nodes[segment.node].borrow_mut();
+                self.counters
+                    .record_node(segment.node, |c| c.packets_processed += 1);
 
                 // keep track of whether we're filling any partial holes
                 let partial_key_cols = segment.partial_index.as_ref();
@@ -2843,11 +3467,14 @@ impl Domain {
                     false,
                     ProcessEnv {
                         state: &mut self.state,
+                        state_checksums: &mut self.state_checksums,
                         reader_write_handles: &mut self.reader_write_handles,
                         nodes: &self.nodes,
                         executor: ex,
                         shard: self.shard,
                         replica: self.replica,
+                        replication_conflict_policy: self.replication_conflict_policy,
+                        record_provenance: self.record_provenance,
                     },
                 )?;
 
@@ -3621,6 +4248,8 @@ impl Domain {
                         );
                         #[allow(clippy::indexing_slicing)] // nodes in replay paths must exist
                         if state[dest.node].evict_keys(tag, &keys).is_some() {
+                            #[allow(clippy::indexing_slicing)] // nodes in replay paths must exist
+                            nodes[dest.node].borrow_mut().process_state_eviction(&keys);
                             #[allow(clippy::unwrap_used)]
                             // we can only evict from partial replay paths, so we must have a
                             // partial key
@@ -3710,7 +4339,7 @@ impl Domain {
                                 self.state
                                     .get(local_index)
                                     .filter(|state| state.is_partial())
-                                    .map(|state| state.deep_size_of())
+                                    .map(|state| state.deep_size_of() + n.auxiliary_state_size())
                             }
                             .map(|s| (local_index, s))
                         })
@@ -3761,7 +4390,7 @@ impl Domain {
                 for (node, num_bytes) in nodes {
                     let mut freed = 0u64;
                     #[allow(clippy::indexing_slicing)] // we got the node from self.nodes
-                    let n = self.nodes[node].borrow_mut();
+                    let mut n = self.nodes[node].borrow_mut();
 
                     if n.is_dropped() {
                         continue; // Node was dropped. Skip.
@@ -3786,6 +4415,10 @@ impl Domain {
 
                         freed += bytes_freed;
                         if !keys.is_empty() {
+                            let auxiliary_size_before = n.auxiliary_state_size();
+                            n.process_state_eviction(&keys);
+                            freed += auxiliary_size_before.saturating_sub(n.auxiliary_state_size());
+
                             let index = index.clone();
                             trigger_downstream_evictions(
                                 &index,
@@ -3808,6 +4441,7 @@ impl Domain {
                     }
 
                     debug!(%freed, node = ?n, "evicted from node");
+                    self.counters.record(node, |c| c.evictions += 1);
                     self.state_size.fetch_sub(freed as usize, Ordering::AcqRel);
                     total_freed += freed;
                 }
@@ -3830,6 +4464,7 @@ impl Domain {
                     .iter()
                     .position(|ps| ps.node == dst)
                     .ok_or_else(|| ReadySetError::NoSuchNode(dst.id()))?;
+                self.counters.record(dst, |c| c.evictions += 1);
                 #[allow(clippy::indexing_slicing)]
                 // i is definitely in bounds, since it came from a call to position
                 walk_path(
@@ -3866,6 +4501,10 @@ impl Domain {
                         trace!(local = %target, ?keys, ?tag, "Evicting keys");
                         #[allow(clippy::indexing_slicing)] // came from replay paths
                         if self.state[target].evict_keys(tag, &keys).is_some() {
+                            #[allow(clippy::indexing_slicing)] // came from replay paths
+                            self.nodes[target]
+                                .borrow_mut()
+                                .process_state_eviction(&keys);
                             trigger_downstream_evictions(
                                 &index,
                                 &keys[..],
@@ -3921,21 +4560,30 @@ impl Domain {
                     }
                     size
                 } else {
-                    // Not a reader, state is with domain
+                    // Not a reader, state is with domain (along with any auxiliary state kept by
+                    // the node's operator)
                     self.state
                         .get(local_index)
                         .filter(|state| state.is_partial())
-                        .map(|s| s.deep_size_of())
+                        .map(|s| s.deep_size_of() + n.auxiliary_state_size())
                         .unwrap_or(0)
                 }
             })
             .sum();
 
-        let Domain { state, metrics, .. } = self; // Help borrowchk
+        let Domain {
+            state,
+            nodes,
+            metrics,
+            ..
+        } = self; // Help borrowchk
         let total_node_state: u64 = state
             .iter()
             .map(|(ni, state)| {
-                let ret = state.deep_size_of();
+                let ret = state.deep_size_of()
+                    + nodes
+                        .get(ni)
+                        .map_or(0, |n| n.borrow().auxiliary_state_size());
                 metrics.set_node_state_size(ni, ret);
                 ret
             })
@@ -3966,31 +4614,75 @@ impl Domain {
             .collect()
     }
 
-    /// If there is a pending timed purge, return the duration until it needs
-    /// to happen
+    /// Returns the number of rows in the materialized state of each node in this domain which has
+    /// one, excluding readers
+    pub fn state_row_counts(&self) -> Vec<(LocalNodeIndex, usize)> {
+        self.state
+            .iter()
+            .map(|(ni, state)| (ni, state.row_count()))
+            .collect()
+    }
+
+    /// Returns all the rows in the materialized state of the given node, or `None` if the node
+    /// isn't materialized in this domain (or is a reader)
+    pub fn materialized_rows(&self, node: LocalNodeIndex) -> Option<Vec<Vec<DfValue>>> {
+        self.state.get(node).map(|state| state.cloned_records())
+    }
+
+    /// Record `record` in this domain's packet log, if it has one
+    fn record(&mut self, record: PacketLogRecord) {
+        if let Some(packet_log) = &mut self.packet_log {
+            if !packet_log.record(&record) {
+                self.packet_log = None;
+            }
+        }
+    }
+
+    /// If there is a pending timed purge, time bucket purge, reader key expiry or write batch
+    /// timeout, return the duration until it needs to happen
     pub fn next_poll_duration(&mut self) -> Option<time::Duration> {
         // when do we need to be woken up again?
         let now = time::Instant::now();
-        self.timed_purges.front().map(|tp| {
-            if tp.time > now {
-                tp.time - now
-            } else {
-                time::Duration::from_millis(0)
-            }
-        })
+        self.timed_purges
+            .front()
+            .map(|tp| tp.time)
+            .into_iter()
+            .chain(self.next_time_bucket_purge)
+            .chain(self.next_key_expiry)
+            .chain(
+                self.reader_write_handles
+                    .values()
+                    .filter_map(|wh| wh.next_batch_timeout()),
+            )
+            .min()
+            .map(|time| time.saturating_duration_since(now))
     }
 
     /// Handle a single message for this domain
     #[failpoint("handle-packet")]
     pub fn handle_packet(
         &mut self,
-        packet: Box<Packet>,
+        mut packet: Box<Packet>,
         executor: &mut dyn Executor,
     ) -> ReadySetResult<()> {
         if self.wait_time.is_running() {
             self.wait_time.stop();
         }
 
+        if self.packet_log.is_some() {
+            self.record(PacketLogRecord::Packet(packet.clone()));
+        }
+        self.text_pool.intern_packet(&mut packet);
+
+        #[cfg(feature = "failure_injection")]
+        if fail::eval(readyset_client::failpoints::DOMAIN_PANIC, |index| {
+            index.and_then(|index| index.parse().ok()) == Some(self.index.index())
+        }) == Some(true)
+        {
+            fail::remove(readyset_client::failpoints::DOMAIN_PANIC);
+            panic!("domain {} panicked at failpoint", self.index.index());
+        }
+
         self.handle(packet, executor)?;
         // After we handle an external packet, the domain may have accumulated a bunch of packets to
         // itself we need to process them all next;
@@ -4011,15 +4703,35 @@ impl Domain {
     }
 
     /// Handle an expired timeout from `next_poll_duration`
-    pub fn handle_timeout(&mut self) -> ReadySetResult<()> {
+    pub fn handle_timeout(&mut self, executor: &mut dyn Executor) -> ReadySetResult<()> {
         if self.wait_time.is_running() {
             self.wait_time.stop();
         }
 
+        let purge_time_buckets = self
+            .next_time_bucket_purge
+            .map_or(false, |next| next <= time::Instant::now());
+        let expire_keys = self
+            .next_key_expiry
+            .map_or(false, |next| next <= time::Instant::now());
+        if !self.timed_purges.is_empty() || purge_time_buckets || expire_keys {
+            self.record(PacketLogRecord::Timeout);
+        }
+
         if !self.timed_purges.is_empty() {
             self.handle_timed_purges()?;
         }
 
+        if purge_time_buckets {
+            self.purge_expired_time_buckets(executor)?;
+        }
+
+        if expire_keys {
+            self.expire_reader_keys()?;
+        }
+
+        self.release_timed_out_batches()?;
+
         if self.aggressively_update_state_sizes {
             self.update_state_sizes();
         }
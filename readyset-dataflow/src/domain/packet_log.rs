@@ -0,0 +1,292 @@
+//! Recording and replaying everything a domain is asked to do, for reproducing bugs in
+//! materialization offline.
+//!
+//! The behavior of a domain is (modulo timing) a deterministic function of the nodes it's started
+//! with and the sequence of packets, domain requests and timeouts it handles afterwards. If
+//! [`Config::packet_log_dir`] is set, every domain writes that sequence to a *packet log* file in
+//! that directory, starting with the [`DomainBuilder`] it was started from. A [`PacketLogReplayer`]
+//! reads a packet log back, rebuilds the domain and feeds it the recorded records one at a time,
+//! so the state of the domain's materializations can be inspected after any step (for example to
+//! bisect the step at which a materialization diverges from what it should contain).
+//!
+//! Packets sent by the domain, to other domains or to itself, aren't recorded: their effects on
+//! this domain are captured by the packets it received as a result. When replaying, anything the
+//! domain sends is discarded.
+//!
+//! A packet log is a sequence of bincode-encoded [`PacketLogRecord`]s. Each record is flushed to
+//! disk as soon as it's written so that a log survives its domain crashing, which makes recording
+//! expensive; it's intended to be enabled temporarily, to capture a bug as it happens.
+//!
+//! [`Config::packet_log_dir`]: super::Config::packet_log_dir
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicUsize;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use readyset_errors::{internal_err, ReadySetResult};
+use readyset_tracing::{error, info};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+use super::{Domain, DomainBuilder};
+use crate::prelude::*;
+use crate::{DomainRequest, Readers, TextPool};
+
+/// A single entry in a packet log
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[allow(clippy::large_enum_variant)]
+pub enum PacketLogRecord {
+    /// The domain was started from the given builder. Always the first record in a log.
+    Start(Box<DomainBuilder>),
+    /// The domain handled a packet
+    Packet(Box<Packet>),
+    /// The domain handled a domain request
+    Request(DomainRequest),
+    /// The domain handled an expired timeout
+    Timeout,
+}
+
+/// Writes the records handled by a running domain to its packet log
+pub(super) struct PacketLogWriter {
+    path: PathBuf,
+    file: BufWriter<File>,
+}
+
+impl PacketLogWriter {
+    /// Create a new packet log in `dir` for the domain being started from `builder`, and record
+    /// the builder as its first record
+    pub(super) fn create(dir: &Path, builder: &DomainBuilder) -> ReadySetResult<Self> {
+        fs::create_dir_all(dir)?;
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let path = dir.join(format!(
+            "domain-{}.{}.{}-{started}.packets",
+            builder.index.index(),
+            builder.shard(),
+            builder.replica
+        ));
+        let mut builder = builder.clone();
+        // Don't record the domain again when replaying it
+        builder.config.packet_log_dir = None;
+
+        let mut res = Self {
+            file: BufWriter::new(File::create(&path)?),
+            path,
+        };
+        res.write(&PacketLogRecord::Start(Box::new(builder)))?;
+        info!(path = %res.path.display(), "Recording domain packet log");
+        Ok(res)
+    }
+
+    fn write(&mut self, record: &PacketLogRecord) -> ReadySetResult<()> {
+        bincode::serialize_into(&mut self.file, record)?;
+        self.file.flush()?;
+        Ok(())
+    }
+
+    /// Record that the domain is about to handle `record`. Returns `false` if the record couldn't
+    /// be written, in which case the log is unusable and recording should stop.
+    pub(super) fn record(&mut self, record: &PacketLogRecord) -> bool {
+        match self.write(record) {
+            Ok(()) => true,
+            Err(error) => {
+                error!(
+                    %error,
+                    path = %self.path.display(),
+                    "Failed to write to domain packet log; stopping recording"
+                );
+                false
+            }
+        }
+    }
+}
+
+/// Reads the records in a packet log, in the order they were recorded
+pub struct PacketLogReader {
+    file: BufReader<File>,
+}
+
+impl PacketLogReader {
+    /// Open the packet log at the given path
+    pub fn open<P: AsRef<Path>>(path: P) -> ReadySetResult<Self> {
+        Ok(Self {
+            file: BufReader::new(File::open(path)?),
+        })
+    }
+}
+
+impl Iterator for PacketLogReader {
+    type Item = ReadySetResult<PacketLogRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match bincode::deserialize_from(&mut self.file) {
+            Ok(record) => Some(Ok(record)),
+            Err(e) => match *e {
+                bincode::ErrorKind::Io(e) if e.kind() == io::ErrorKind::UnexpectedEof => None,
+                _ => Some(Err(e.into())),
+            },
+        }
+    }
+}
+
+/// Discards all the packets a domain sends while it's being replayed
+struct DiscardingExecutor;
+
+impl Executor for DiscardingExecutor {
+    fn send(&mut self, _dest: ReplicaAddress, _m: Box<Packet>) {}
+}
+
+/// Rebuilds a domain from its packet log, and replays the records in the log against it one at a
+/// time. See the [module documentation](self) for more information.
+pub struct PacketLogReplayer {
+    domain: Domain,
+    records: PacketLogReader,
+    /// Receives (and discards) the packets the domain sends to itself
+    self_rx: UnboundedReceiver<Box<Packet>>,
+    steps: usize,
+}
+
+impl PacketLogReplayer {
+    /// Open the packet log at the given path, and build the domain recorded in it.
+    ///
+    /// Base tables are always rebuilt in memory, regardless of the durability mode they were
+    /// recorded with.
+    pub fn open<P: AsRef<Path>>(path: P) -> ReadySetResult<Self> {
+        let mut records = PacketLogReader::open(path)?;
+        let mut builder = match records.next().transpose()? {
+            Some(PacketLogRecord::Start(builder)) => *builder,
+            _ => {
+                return Err(internal_err!(
+                    "Packet log doesn't start with a domain builder"
+                ))
+            }
+        };
+        builder.persistence_parameters.mode = DurabilityMode::MemoryOnly;
+
+        // Full replays are sent by the domain to itself through the channel coordinator, so it
+        // needs to know about the domain. The replayed packets are recorded in the log anyway.
+        let address = builder.address();
+        let channel_coordinator = Arc::new(ChannelCoordinator::new());
+        let (self_tx, self_rx) = unbounded_channel();
+        channel_coordinator.insert_local(address, self_tx)?;
+        channel_coordinator.insert_remote(address, ([127, 0, 0, 1], 0).into())?;
+
+        let domain = builder.build(
+            Readers::default(),
+            channel_coordinator,
+            Arc::new(AtomicUsize::new(0)),
+            TextPool::new(0),
+        );
+
+        Ok(Self {
+            domain,
+            records,
+            self_rx,
+            steps: 0,
+        })
+    }
+
+    /// Returns the domain being replayed
+    pub fn domain(&self) -> &Domain {
+        &self.domain
+    }
+
+    /// Returns the number of records replayed so far
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Replay the next record in the log against the domain, returning the record, or `None` if
+    /// the end of the log has been reached. Errors returned by the domain when handling the record
+    /// are returned along with the record, since they may be what's being reproduced.
+    pub fn step(&mut self) -> ReadySetResult<Option<(PacketLogRecord, ReadySetResult<()>)>> {
+        let record = match self.records.next().transpose()? {
+            Some(record) => record,
+            None => return Ok(None),
+        };
+        let executor = &mut DiscardingExecutor;
+        let res = match record.clone() {
+            PacketLogRecord::Start(_) => Err(internal_err!("Unexpected domain builder in log")),
+            PacketLogRecord::Packet(packet) => self.domain.handle_packet(packet, executor),
+            PacketLogRecord::Request(req) => self.domain.domain_request(req, executor).map(|_| ()),
+            PacketLogRecord::Timeout => self.domain.handle_timeout(executor),
+        };
+        while self.self_rx.try_recv().is_ok() {}
+        self.steps += 1;
+        Ok(Some((record, res)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Config;
+    use super::*;
+
+    #[test]
+    fn write_and_read_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let builder = DomainBuilder {
+            index: 3.into(),
+            shard: None,
+            replica: 0,
+            nshards: 1,
+            nodes: Default::default(),
+            persistence_parameters: Default::default(),
+            config: Config {
+                aggressively_update_state_sizes: false,
+                view_request_timeout: Default::default(),
+                table_request_timeout: Default::default(),
+                eviction_kind: Default::default(),
+                replay_spill_threshold: None,
+                packet_log_dir: Some(dir.path().to_owned()),
+                replication_conflict_policy: Default::default(),
+                record_provenance: false,
+                reader_key_ttl: None,
+                reader_key_ttl_jitter_percent: 0,
+                max_key_expirations_per_second: None,
+            },
+        };
+
+        let mut writer = PacketLogWriter::create(dir.path(), &builder).unwrap();
+        assert!(writer.record(&PacketLogRecord::Timeout));
+        assert!(writer.record(&PacketLogRecord::Packet(Box::new(Packet::Spin))));
+        drop(writer);
+
+        let path = fs::read_dir(dir.path())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        assert!(path
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("domain-3.0.0-"));
+
+        let records = PacketLogReader::open(&path)
+            .unwrap()
+            .collect::<ReadySetResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(records.len(), 3);
+        assert!(matches!(
+            &records[0],
+            PacketLogRecord::Start(builder)
+                if builder.index == 3.into() && builder.config.packet_log_dir.is_none()
+        ));
+        assert!(matches!(records[1], PacketLogRecord::Timeout));
+        assert!(matches!(&records[2], PacketLogRecord::Packet(p) if **p == Packet::Spin));
+
+        let mut replayer = PacketLogReplayer::open(&path).unwrap();
+        while let Some((_, res)) = replayer.step().unwrap() {
+            res.unwrap();
+        }
+        assert_eq!(replayer.steps(), 2);
+    }
+}
@@ -0,0 +1,196 @@
+//! Snapshots of fully materialized state, taken when a full replay is started.
+//!
+//! To start a full replay, a domain clones the entire state of the node the replay originates at,
+//! so that the clone can be chunked up and sent down the replay path by a separate thread while
+//! the domain keeps processing updates. For a very large materialization, holding that entire
+//! clone in memory until it's been replayed can use an enormous amount of memory, so a snapshot
+//! can optionally keep only a bounded number of rows in memory, and write the remaining rows to a
+//! temporary file on disk in chunks, which are read back one at a time as they're replayed.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Seek, SeekFrom};
+use std::{iter, mem};
+
+use dataflow_state::{MaterializedNodeState, State};
+use readyset_data::DfValue;
+use readyset_errors::ReadySetResult;
+
+/// Rows written to disk once a snapshot grows beyond its spill threshold
+struct Spill {
+    file: BufWriter<File>,
+    /// The number of chunks written to `file`
+    chunks: usize,
+}
+
+/// A copy of all the rows in a fully materialized state, some of which may have been spilled to
+/// disk. See the [module documentation](self) for more information.
+pub(super) struct ReplaySnapshot {
+    /// The rows kept in memory, which are replayed first
+    rows: Vec<Vec<DfValue>>,
+    spill: Option<Spill>,
+    chunk_size: usize,
+}
+
+impl ReplaySnapshot {
+    /// Take a snapshot of all the rows in `state`, to be replayed in chunks of `chunk_size` rows.
+    ///
+    /// If `spill_threshold` is set, only that many rows are kept in memory, and the rest are
+    /// written to a temporary file.
+    pub(super) fn take(
+        state: &MaterializedNodeState,
+        spill_threshold: Option<usize>,
+        chunk_size: usize,
+    ) -> ReadySetResult<Self> {
+        let mut snapshot = Self {
+            rows: vec![],
+            spill: None,
+            chunk_size,
+        };
+        let mut chunk = vec![];
+        let mut res = Ok(());
+        state.for_each_record(&mut |row| {
+            if res.is_err() {
+                return;
+            }
+            if spill_threshold.map_or(true, |threshold| snapshot.rows.len() < threshold) {
+                snapshot.rows.push(row.to_vec());
+                return;
+            }
+            chunk.push(row.to_vec());
+            if chunk.len() >= chunk_size {
+                res = snapshot.spill(mem::take(&mut chunk));
+            }
+        });
+        res?;
+        if !chunk.is_empty() {
+            snapshot.spill(chunk)?;
+        }
+        Ok(snapshot)
+    }
+
+    fn spill(&mut self, chunk: Vec<Vec<DfValue>>) -> ReadySetResult<()> {
+        let spill = match &mut self.spill {
+            Some(spill) => spill,
+            None => self.spill.insert(Spill {
+                file: BufWriter::new(tempfile::tempfile()?),
+                chunks: 0,
+            }),
+        };
+        bincode::serialize_into(&mut spill.file, &chunk)?;
+        spill.chunks += 1;
+        Ok(())
+    }
+
+    /// Returns true if the snapshot contains no rows
+    pub(super) fn is_empty(&self) -> bool {
+        self.rows.is_empty() && self.spill.is_none()
+    }
+
+    /// Returns the number of chunks of rows which were spilled to disk
+    pub(super) fn spilled_chunks(&self) -> usize {
+        self.spill.as_ref().map_or(0, |spill| spill.chunks)
+    }
+
+    /// Consume the snapshot, returning an iterator over all of its rows in chunks of at most
+    /// `chunk_size` rows. Rows spilled to disk are only read back into memory one chunk at a time,
+    /// as the iterator is advanced.
+    pub(super) fn into_chunks(
+        self,
+    ) -> ReadySetResult<impl Iterator<Item = ReadySetResult<Vec<Vec<DfValue>>>>> {
+        let spilled = match self.spill {
+            Some(Spill { file, chunks }) => {
+                let mut file = file.into_inner().map_err(|e| e.into_error())?;
+                file.seek(SeekFrom::Start(0))?;
+                let mut reader = BufReader::new(file);
+                Some((0..chunks).map(move |_| Ok(bincode::deserialize_from(&mut reader)?)))
+            }
+            None => None,
+        };
+
+        let chunk_size = self.chunk_size;
+        let mut rows = self.rows.into_iter();
+        let in_memory = iter::from_fn(move || {
+            let chunk = rows.by_ref().take(chunk_size).collect::<Vec<_>>();
+            (!chunk.is_empty()).then_some(Ok(chunk))
+        });
+
+        Ok(in_memory.chain(spilled.into_iter().flatten()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dataflow_state::MemoryState;
+    use readyset_client::internal::Index;
+
+    use super::*;
+
+    fn state(rows: usize) -> MaterializedNodeState {
+        let mut state = MemoryState::default();
+        state.add_key(Index::hash_map(vec![0]), None);
+        let mut records = (0..rows)
+            .map(|i| vec![DfValue::from(i as i64), DfValue::from("a row")])
+            .collect::<Vec<_>>()
+            .into();
+        state.process_records(&mut records, None, None).unwrap();
+        MaterializedNodeState::Memory(state)
+    }
+
+    fn replayed(snapshot: ReplaySnapshot) -> Vec<Vec<Vec<DfValue>>> {
+        snapshot
+            .into_chunks()
+            .unwrap()
+            .collect::<ReadySetResult<Vec<_>>>()
+            .unwrap()
+    }
+
+    fn sorted(chunks: Vec<Vec<Vec<DfValue>>>) -> Vec<Vec<DfValue>> {
+        let mut rows = chunks.into_iter().flatten().collect::<Vec<_>>();
+        rows.sort();
+        rows
+    }
+
+    #[test]
+    fn empty() {
+        let snapshot = ReplaySnapshot::take(&state(0), Some(0), 4).unwrap();
+        assert!(snapshot.is_empty());
+        assert!(replayed(snapshot).is_empty());
+    }
+
+    #[test]
+    fn in_memory() {
+        let state = state(10);
+        let snapshot = ReplaySnapshot::take(&state, None, 4).unwrap();
+        assert_eq!(snapshot.spilled_chunks(), 0);
+
+        let chunks = replayed(snapshot);
+        assert_eq!(
+            chunks.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![4, 4, 2]
+        );
+        assert_eq!(sorted(chunks), {
+            let mut rows = state.cloned_records();
+            rows.sort();
+            rows
+        });
+    }
+
+    #[test]
+    fn spills_beyond_threshold() {
+        let state = state(10);
+        let snapshot = ReplaySnapshot::take(&state, Some(3), 4).unwrap();
+        assert!(!snapshot.is_empty());
+        assert_eq!(snapshot.spilled_chunks(), 2);
+
+        let chunks = replayed(snapshot);
+        assert_eq!(
+            chunks.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![3, 4, 3]
+        );
+        assert_eq!(sorted(chunks), {
+            let mut rows = state.cloned_records();
+            rows.sort();
+            rows
+        });
+    }
+}
@@ -16,7 +16,8 @@
     drain_filter,
     hash_drain_filter,
     option_get_or_insert_default,
-    box_patterns
+    box_patterns,
+    once_cell
 )]
 // Only used in a `debug_assert!` in `ops/grouped/mod.rs` therefore I added it
 // conditionally to avoid requiring another unstable feature for release builds.
@@ -34,8 +35,10 @@ pub mod payload; // it makes me _really_ sad that this has to be pub
 pub mod prelude;
 pub mod utils;
 
+mod checksum;
 mod domain;
 mod node_map;
+mod text_pool;
 
 use std::collections::HashMap;
 use std::ops::{Deref, DerefMut};
@@ -44,7 +47,10 @@ use std::sync::{Arc, Mutex};
 use readyset_client::ReaderAddress;
 use serde::{Deserialize, Serialize};
 
-pub use crate::backlog::{LookupError, ReaderUpdatedNotifier, SingleReadHandle};
+pub use crate::backlog::{
+    LazyJoin, LookupError, ReadOutcome, ReaderUpdatedNotifier, SingleReadHandle,
+    PENDING_REPLAY_TIMEOUT,
+};
 
 /// A [`ReaderMap`] maps a [`ReaderAddress`] to the [`SingleReadHandle`] to access the reader at
 /// that address.
@@ -59,12 +65,15 @@ pub use dataflow_expression::{
     BinaryOperator, BuiltinFunction, Expr, LowerContext, PostLookup, PostLookupAggregate,
     PostLookupAggregateFunction, PostLookupAggregates, ReaderProcessing,
 };
-pub use dataflow_state::{DurabilityMode, PersistenceParameters};
+pub use dataflow_state::{DurabilityMode, PersistenceParameters, ROW_OVERHEAD_BYTES};
 
+pub use crate::checksum::{KeyRange, StateChecksum};
+pub use crate::domain::packet_log::{PacketLogReader, PacketLogRecord, PacketLogReplayer};
 pub use crate::domain::{Domain, DomainBuilder, DomainIndex};
 pub use crate::node_map::NodeMap;
 pub use crate::payload::{DomainRequest, Packet, PacketDiscriminants};
 pub use crate::processing::LookupIndex;
+pub use crate::text_pool::TextPool;
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum Sharding {
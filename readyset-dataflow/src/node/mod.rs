@@ -1,6 +1,6 @@
 use std::collections::{HashMap, HashSet};
 
-use nom_sql::{ColumnSpecification, Relation, SqlIdentifier};
+use nom_sql::{ColumnConstraint, ColumnSpecification, Relation, SqlIdentifier};
 use readyset_client::consistency::Timestamp;
 use readyset_data::{DfType, Dialect};
 use serde::{Deserialize, Serialize};
@@ -12,7 +12,7 @@ use crate::processing::LookupIndex;
 mod process;
 #[cfg(test)]
 pub(crate) use self::process::materialize;
-pub(crate) use self::process::{NodeProcessingResult, ProcessEnv};
+pub(crate) use self::process::{NodeProcessingResult, ProcessEnv, ReplayProvenance};
 
 pub mod special;
 
@@ -40,11 +40,20 @@ pub struct Column {
     /// TODO: Use this information to lookup the column specification required for returning a
     /// resultset to the client.
     source: Option<Relation>,
+    /// Whether this column is known to never contain NULL values, such as a base table column
+    /// declared `NOT NULL`
+    #[serde(default)]
+    non_null: bool,
 }
 
 impl Column {
     pub fn new(name: SqlIdentifier, ty: DfType, source: Option<Relation>) -> Self {
-        Self { name, ty, source }
+        Self {
+            name,
+            ty,
+            source,
+            non_null: false,
+        }
     }
 
     /// Creates a dataflow column from the [`nom_sql`] specification.
@@ -57,11 +66,17 @@ impl Column {
     where
         F: Fn(Relation) -> Option<DfType>,
     {
-        Ok(Self::new(
+        let non_null = spec
+            .constraints
+            .iter()
+            .any(|c| matches!(c, ColumnConstraint::NotNull | ColumnConstraint::PrimaryKey));
+        let mut col = Self::new(
             spec.column.name,
             DfType::from_sql_type(&spec.sql_type, dialect, resolve_type)?,
             spec.column.table,
-        ))
+        );
+        col.set_non_null(non_null);
+        Ok(col)
     }
 
     /// Column name
@@ -79,10 +94,20 @@ impl Column {
         self.source.as_ref()
     }
 
+    /// Whether this column is known to never contain NULL values
+    pub fn is_non_null(&self) -> bool {
+        self.non_null
+    }
+
     /// Sets this column's name
     pub fn set_name(&mut self, name: SqlIdentifier) {
         self.name = name;
     }
+
+    /// Sets whether this column is known to never contain NULL values
+    pub fn set_non_null(&mut self, non_null: bool) {
+        self.non_null = non_null;
+    }
 }
 
 #[must_use]
@@ -114,6 +139,13 @@ pub struct Node {
     // We skip serde since we don't want the state of the node, just the configuration.
     #[serde(skip)]
     timestamps: HashMap<LocalNodeIndex, Timestamp>,
+
+    // The latest barrier to have reached this node from each of its inputs, and the latest barrier
+    // to have reached this node from all of its inputs (see `PacketPayload::Barrier`).
+    #[serde(skip)]
+    barriers: HashMap<LocalNodeIndex, u64>,
+    #[serde(skip)]
+    barrier: u64,
 }
 
 // constructors
@@ -139,6 +171,8 @@ impl Node {
 
             sharded_by: Sharding::None,
             timestamps: HashMap::new(),
+            barriers: HashMap::new(),
+            barrier: 0,
         }
     }
 
@@ -150,13 +184,15 @@ impl Node {
         Self::new(name, self.columns.clone(), n)
     }
 
-    /// Duplicates the existing node, clearing the index, taken flag, and timestamps
+    /// Duplicates the existing node, clearing the index, taken flag, timestamps, and barriers
     /// Used to create fully materialized duplicates of partially materialized nodes
     pub fn duplicate(&self) -> Node {
         Self {
             index: None,
             taken: false,
             timestamps: HashMap::new(),
+            barriers: HashMap::new(),
+            barrier: 0,
             ..self.clone()
         }
     }
@@ -504,6 +540,12 @@ impl Node {
     pub(crate) fn parents(&self) -> &[LocalNodeIndex] {
         &self.parents
     }
+
+    /// Returns the latest barrier to have reached this node from all of its inputs, or 0 if no
+    /// barrier has yet
+    pub(crate) fn barrier(&self) -> u64 {
+        self.barrier
+    }
 }
 
 // attributes
@@ -676,4 +718,13 @@ impl Node {
             false
         }
     }
+
+    /// If this node is a shard merger, returns the number of shards it merges
+    pub fn merged_shards(&self) -> Option<usize> {
+        if let NodeType::Internal(NodeOperator::Union(ref u)) = self.inner {
+            u.merged_shards()
+        } else {
+            None
+        }
+    }
 }
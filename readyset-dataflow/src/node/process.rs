@@ -1,16 +1,20 @@
 use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
 use std::mem;
+use std::sync::Arc;
 
 use dataflow_state::{MaterializedNodeState, SnapshotMode};
+use nom_sql::Relation;
 use readyset_client::consistency::Timestamp;
+use readyset_client::debug::provenance::ProvenanceTag;
 use readyset_client::replication::ReplicationOffset;
 use readyset_client::{KeyComparison, PacketData, ReadySetError};
 use readyset_errors::ReadySetResult;
 use readyset_tracing::trace;
 use tracing::debug_span;
 
-use crate::node::special::base::{BaseWrite, SetSnapshotMode};
+use crate::checksum::RowChecksums;
+use crate::node::special::base::{BaseWrite, ReplicationConflictPolicy, SetSnapshotMode};
 use crate::node::NodeType;
 use crate::prelude::*;
 use crate::processing::{MissLookupKey, MissReplayKey};
@@ -105,11 +109,15 @@ impl NodeProcessingResult {
 /// Information about the domain required by [`Node::process`].
 pub(crate) struct ProcessEnv<'domain> {
     pub(crate) state: &'domain mut StateMap,
+    pub(crate) state_checksums: &'domain mut NodeMap<RowChecksums>,
     pub(crate) reader_write_handles: &'domain mut NodeMap<backlog::WriteHandle>,
     pub(crate) nodes: &'domain DomainNodes,
     pub(crate) executor: &'domain mut dyn Executor,
     pub(crate) shard: Option<usize>,
     pub(crate) replica: usize,
+    pub(crate) replication_conflict_policy: ReplicationConflictPolicy,
+    /// If true, updates originating at base tables are tagged with their provenance
+    pub(crate) record_provenance: bool,
 }
 
 impl Node {
@@ -132,13 +140,24 @@ impl Node {
             NodeType::Ingress => {
                 let m = m.as_mut().unwrap();
                 let tag = m.tag();
-                materialize(m.mut_data(), None, tag, env.state.get_mut(addr))?;
+                materialize(
+                    m.mut_data(),
+                    None,
+                    tag,
+                    env.state.get_mut(addr),
+                    env.state_checksums.get_mut(addr),
+                )?;
             }
             NodeType::Base(ref mut b) => {
                 // NOTE: bases only accept BaseOperations
                 match m.take().map(|p| *p) {
                     Some(Packet::Input { inner, .. }) => {
-                        let PacketData { dst, data, trace } = inner;
+                        let PacketData {
+                            dst,
+                            data,
+                            trace,
+                            batch,
+                        } = inner;
                         let ops = data
                             .try_into()
                             .expect("Payload of Input packet was not of Input type");
@@ -161,6 +180,7 @@ impl Node {
                             &*env.state,
                             snapshot_mode,
                             self.name.clone(),
+                            env.replication_conflict_policy,
                         )?;
 
                         if let (Some(SetSnapshotMode::EnterSnapshotMode), Some(s)) = (
@@ -170,6 +190,15 @@ impl Node {
                             s.set_snapshot_mode(SnapshotMode::SnapshotModeEnabled);
                         }
 
+                        let provenance = env.record_provenance.then(|| {
+                            provenance_tags(
+                                &self.name,
+                                b.primary_key(),
+                                &rs,
+                                replication_offset.as_ref(),
+                            )
+                        });
+
                         // When a replay originates at a base node, we replay the data *through*
                         // that same base node because its column set may
                         // have changed. However, this replay through the
@@ -184,6 +213,7 @@ impl Node {
                                 replication_offset,
                                 None,
                                 env.state.get_mut(addr),
+                                env.state_checksums.get_mut(addr),
                             )?;
                         }
 
@@ -198,6 +228,8 @@ impl Node {
                             link: Link::new(dst, dst),
                             data: rs,
                             trace,
+                            batch,
+                            provenance,
                         }));
                     }
                     Some(ref p) => {
@@ -415,7 +447,13 @@ impl Node {
                     }
                     _ => None,
                 };
-                materialize(m.mut_data(), None, tag, env.state.get_mut(addr))?;
+                materialize(
+                    m.mut_data(),
+                    None,
+                    tag,
+                    env.state.get_mut(addr),
+                    env.state_checksums.get_mut(addr),
+                )?;
 
                 for miss in misses.iter_mut() {
                     if miss.on != addr {
@@ -506,6 +544,23 @@ impl Node {
         Ok(())
     }
 
+    /// Notify the operator of this node that `keys` have been evicted from its materialization, so
+    /// that it can evict any auxiliary state it keeps for them
+    pub(crate) fn process_state_eviction(&mut self, keys: &[KeyComparison]) {
+        if let NodeType::Internal(ref mut i) = self.inner {
+            i.on_state_eviction(keys);
+        }
+    }
+
+    /// Returns the size in bytes of any auxiliary state kept by the operator of this node, other
+    /// than what's stored in its materialization
+    pub(crate) fn auxiliary_state_size(&self) -> u64 {
+        match self.inner {
+            NodeType::Internal(ref i) => i.auxiliary_state_size(),
+            _ => 0,
+        }
+    }
+
     // When we miss in can_query_through, that miss is *really* in the can_query_through node's
     // ancestor. We need to ensure that a replay is done to there, not the query_through node
     // itself, by translating the Miss into the right parent.
@@ -568,6 +623,44 @@ impl Node {
         Ok(())
     }
 
+    /// Returns the inputs which a timestamp or barrier arriving at this node from `src` must reach
+    /// it from before the node can forward it: its parents, or every shard if the node is a shard
+    /// merger (whose inputs are identified by shard), or just `src` itself if the node has no
+    /// parents in its domain (base tables and ingress nodes).
+    fn timestamp_inputs(&self, src: LocalNodeIndex) -> Vec<LocalNodeIndex> {
+        if let Some(shards) = self.merged_shards() {
+            (0..shards)
+                .map(|shard| LocalNodeIndex::make(shard as u32))
+                .collect()
+        } else if self.parents().is_empty() {
+            vec![src]
+        } else {
+            self.parents().to_vec()
+        }
+    }
+
+    /// Record that `barrier` has reached this node from `src`, and return the barrier the node
+    /// should forward, if any: the latest barrier to have reached it from all of its inputs, if
+    /// that's a barrier it hasn't already forwarded.
+    fn process_barrier(&mut self, src: LocalNodeIndex, barrier: u64) -> Option<u64> {
+        let latest = self.barriers.entry(src).or_default();
+        *latest = (*latest).max(barrier);
+
+        let inputs = self.timestamp_inputs(src);
+        let reached = inputs
+            .iter()
+            .map(|input| self.barriers.get(input).copied())
+            .min()
+            .flatten()?;
+        // Nodes with a single input forward every barrier they receive, since nodes without
+        // parents may receive the same barrier from several sources
+        if reached <= self.barrier && inputs.len() > 1 {
+            return None;
+        }
+        self.barrier = self.barrier.max(reached);
+        Some(reached)
+    }
+
     pub(crate) fn process_timestamp(
         &mut self,
         m: Packet,
@@ -576,7 +669,6 @@ impl Node {
         reader_write_handles: &mut NodeMap<backlog::WriteHandle>,
         executor: &mut dyn Executor,
     ) -> ReadySetResult<Option<Box<Packet>>> {
-        // TODO: not error handling compliant!
         let src_node = m.src();
         let addr = self.local_addr();
         match m {
@@ -587,55 +679,73 @@ impl Node {
             } => {
                 let PacketData { dst, data, .. } = timestamp;
 
-                let timestamp: Timestamp =
-                    data.try_into().expect("Packet data not of timestamp type");
-
-                // Set the incoming timestamp in the current nodes map of
-                // upstream timestamps.
-                self.timestamps
-                    .entry(src_node)
-                    .and_modify(|e| {
-                        *e = Timestamp::join(e, &timestamp);
-                    })
-                    .or_insert_with(|| timestamp.clone());
-
-                // Calculate the minimum timestamp over all timestamps for each parent
-                // of the node. If the node does not have a timestamp for any parent,
-                // then the minimum timestamp is returned.
-                let mut parent_timestamps: Vec<&Timestamp> = Vec::with_capacity(self.parents.len());
-                let mut parent_without_timestamp = false;
-                for parent in self.parents() {
-                    match self.timestamps.get(parent) {
-                        Some(t) => {
-                            parent_timestamps.push(t);
+                let data = match data {
+                    PacketPayload::Timestamp(timestamp) => {
+                        // Set the incoming timestamp in the current nodes map of
+                        // upstream timestamps.
+                        self.timestamps
+                            .entry(src_node)
+                            .and_modify(|e| {
+                                *e = Timestamp::join(e, &timestamp);
+                            })
+                            .or_insert_with(|| timestamp.clone());
+
+                        // Calculate the minimum timestamp over all timestamps for each input
+                        // of the node. If the node does not have a timestamp for any input,
+                        // then the minimum timestamp is returned.
+                        let mut input_timestamps = Vec::new();
+                        let mut input_without_timestamp = false;
+                        for input in self.timestamp_inputs(src_node) {
+                            match self.timestamps.get(&input) {
+                                Some(t) => {
+                                    input_timestamps.push(t);
+                                }
+                                None => {
+                                    input_without_timestamp = true;
+                                    break;
+                                }
+                            }
                         }
-                        None => {
-                            parent_without_timestamp = true;
-                            break;
+
+                        let timestamp = if input_without_timestamp {
+                            // The empty timestamp is a placeholder for the minimum timestamp.
+                            Timestamp::default()
+                        } else {
+                            Timestamp::min(&input_timestamps[..])
+                        };
+
+                        if self.is_reader() {
+                            if let Some(state) = reader_write_handles.get_mut(addr) {
+                                state.release_batches(&timestamp);
+                                state.set_timestamp(timestamp);
+
+                                // Ensure the write is published.
+                                state.swap();
+                            }
+                            return Ok(None);
                         }
-                    }
-                }
 
-                // If a node has no parents it is a base table node, we pass the
-                // timestamp in the packet along.
-                let timestamp = if self.parents().is_empty() {
-                    self.timestamps.get(&src_node).unwrap().clone()
-                } else if parent_without_timestamp {
-                    // The empty timestamp is a placeholder for the minimum timestamp.
-                    Timestamp::default()
-                } else {
-                    Timestamp::min(&parent_timestamps[..])
-                };
+                        PacketPayload::Timestamp(timestamp)
+                    }
+                    PacketPayload::Barrier(barrier) => {
+                        let barrier = match self.process_barrier(src_node, barrier) {
+                            Some(barrier) => barrier,
+                            None => return Ok(None),
+                        };
 
-                if self.is_reader() {
-                    if let Some(state) = reader_write_handles.get_mut(addr) {
-                        state.set_timestamp(timestamp);
+                        if self.is_reader() {
+                            // Every write before the barrier has reached the reader, so make sure
+                            // they've been published before recording that the barrier has too.
+                            if let Some(state) = reader_write_handles.get_mut(addr) {
+                                state.reach_barrier(barrier);
+                            }
+                            return Ok(None);
+                        }
 
-                        // Ensure the write is published.
-                        state.swap();
+                        PacketPayload::Barrier(barrier)
                     }
-                    return Ok(None);
-                }
+                    PacketPayload::Input(_) => internal!("Packet data not of timestamp type"),
+                };
 
                 // Create a link if one does not already exist. This only happens
                 // at the base table. The domain is responsible for setting the
@@ -649,8 +759,9 @@ impl Node {
                     src,
                     timestamp: PacketData {
                         dst,
-                        data: PacketPayload::Timestamp(timestamp),
+                        data,
                         trace: None,
+                        batch: None,
                     },
                 });
 
@@ -662,6 +773,10 @@ impl Node {
                         e.process(p, None, on_shard.unwrap_or(0), on_replica, executor)?;
                         None
                     }
+                    NodeType::Sharder(ref s) => {
+                        s.process_timestamp(&p, addr, on_replica, executor)?;
+                        None
+                    }
                     NodeType::Base(_) => Some(p),
                     _ => Some(p),
                 })
@@ -671,6 +786,81 @@ impl Node {
     }
 }
 
+/// Build the provenance tags for the records in a write to the base table `table`, identifying each
+/// written row by its primary key (or by the whole row, if the table has no primary key)
+fn provenance_tags(
+    table: &Relation,
+    primary_key: Option<&[usize]>,
+    records: &Records,
+    offset: Option<&ReplicationOffset>,
+) -> Arc<[ProvenanceTag]> {
+    let mut tags: Vec<ProvenanceTag> = Vec::new();
+    for record in records.iter() {
+        let key = match primary_key {
+            Some(primary_key) => primary_key.iter().map(|&i| record[i].clone()).collect(),
+            None => record.to_vec(),
+        };
+        // Updates are written as a negative and a positive record for the same key
+        if tags.last().map_or(false, |tag| tag.key == key) {
+            continue;
+        }
+        tags.push(ProvenanceTag {
+            table: table.clone(),
+            key,
+            offset: offset.cloned(),
+        });
+    }
+    tags.into()
+}
+
+/// Where the rows replayed out of a materialized node came from, used to tag replays with their
+/// provenance when provenance recording is enabled
+#[derive(Clone)]
+pub(crate) enum ReplayProvenance {
+    /// The replay starts at a base table, so the replayed rows are tagged with the table and the
+    /// key they were replayed for
+    Base { table: Relation },
+    /// The replay starts at a materialized node other than a base table, which doesn't know the
+    /// writes its rows result from
+    Derived,
+}
+
+impl ReplayProvenance {
+    pub(crate) fn for_node(node: &Node) -> Self {
+        match node.get_base() {
+            Some(_) => ReplayProvenance::Base {
+                table: node.name().clone(),
+            },
+            None => ReplayProvenance::Derived,
+        }
+    }
+
+    /// Build the provenance tags for a replay of `keys` out of the node: one tag for each point
+    /// key, which readers record for the replayed rows stored under that key.
+    ///
+    /// The rows read from the base table are changed by the operators between it and the reader,
+    /// so they can't be individually attributed to the reader's rows, and aren't tagged. Range
+    /// keys, and full replays (which have no keys), aren't tagged either.
+    pub(crate) fn tags<'a, I>(&self, keys: I) -> Arc<[ProvenanceTag]>
+    where
+        I: IntoIterator<Item = &'a KeyComparison>,
+    {
+        match self {
+            ReplayProvenance::Base { table } => keys
+                .into_iter()
+                .filter_map(KeyComparison::equal)
+                .map(|key| ProvenanceTag {
+                    table: table.clone(),
+                    key: key.as_vec().clone(),
+                    offset: None,
+                })
+                .collect::<Vec<_>>()
+                .into(),
+            ReplayProvenance::Derived => Vec::new().into(),
+        }
+    }
+}
+
 #[allow(clippy::borrowed_box)]
 // crate visibility due to use by tests
 pub(crate) fn materialize(
@@ -678,10 +868,14 @@ pub(crate) fn materialize(
     replication_offset: Option<ReplicationOffset>,
     partial: Option<Tag>,
     state: Option<&mut MaterializedNodeState>,
+    checksums: Option<&mut RowChecksums>,
 ) -> ReadySetResult<()> {
     if let Some(state) = state {
         trace!(?rs, ?replication_offset, "materializing");
         state.process_records(rs, partial, replication_offset)?;
+        if let Some(checksums) = checksums {
+            checksums.apply(rs);
+        }
     }
 
     Ok(())
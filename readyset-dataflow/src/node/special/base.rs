@@ -2,16 +2,20 @@ use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::mem;
 
-use dataflow_state::{PointKey, SnapshotMode};
+use dataflow_expression::Expr;
+use dataflow_state::{MaterializedNodeState, PointKey, SnapshotMode};
 use itertools::Itertools;
 use maplit::hashmap;
-use nom_sql::Relation;
+use metrics::counter;
+use nom_sql::{Relation, SqlIdentifier};
+use readyset_client::metrics::recorded;
 use readyset_client::replication::ReplicationOffset;
 use readyset_client::{Modification, Operation, TableOperation};
 use readyset_data::{DfValue, DfValueKind};
 use readyset_errors::ReadySetResult;
-use readyset_tracing::{debug, error, trace};
+use readyset_tracing::{debug, error, trace, warn};
 use readyset_util::redacted::Sensitive;
 use readyset_util::Indices;
 use serde::{Deserialize, Serialize};
@@ -56,6 +60,60 @@ impl From<Records> for BaseWrite {
     }
 }
 
+/// A `CHECK` constraint on a base table, which must be satisfied by every row written to the table
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckConstraint {
+    /// The name of the constraint, used to report violations
+    pub name: SqlIdentifier,
+    /// The condition of the constraint, which must not evaluate to false for any row
+    pub expr: Expr,
+}
+
+/// How a [`Base`] handles writes which would result in two rows with the same value for the table's
+/// primary key or for one of its unique keys
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DuplicateKeyBehavior {
+    /// Inserts of a row with a primary key that already exists are dropped (and logged), and
+    /// unique keys are not checked at all. This is the behavior for replicated tables, where the
+    /// upstream database has already enforced the table's keys.
+    #[default]
+    Ignore,
+    /// Writes which would duplicate the primary key or a unique key are rejected with
+    /// [`ReadySetError::DuplicateEntry`]
+    Reject,
+    /// Inserts of a row with a primary key that already exists replace the existing row. Writes
+    /// which would duplicate one of the unique keys are still rejected with
+    /// [`ReadySetError::DuplicateEntry`]
+    Upsert,
+}
+
+/// How a [`Base`] handles replicated writes which update or delete a row that doesn't exist in the
+/// table, which can happen if the table's state has drifted from the upstream database (for
+/// example, because rows were filtered out of its snapshot).
+///
+/// Only applies to tables which are replicated from an upstream database and have a primary key;
+/// without an upstream, such writes are always no-ops, and tables without a primary key can't look
+/// up the rows being deleted.
+#[derive(
+    Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, clap::ArgEnum,
+)]
+pub enum ReplicationConflictPolicy {
+    /// The writes are dropped, and counted by the [`BASE_TABLE_REPLICATION_CONFLICTS`] metric
+    ///
+    /// [`BASE_TABLE_REPLICATION_CONFLICTS`]: recorded::BASE_TABLE_REPLICATION_CONFLICTS
+    Ignore,
+    /// Updates of a row that doesn't exist insert the new row instead, if the update includes
+    /// every column of the new row (as updates replicated as a delete of the old row followed by
+    /// an insert of the new one do). Other conflicting writes are dropped, as with
+    /// [`Ignore`](Self::Ignore).
+    Insert,
+    /// The whole write is rejected with [`ReadySetError::ReplicationConflict`], which causes the
+    /// replicator to re-snapshot the table. This is the default, since it's the only policy which
+    /// doesn't leave the table's state diverged from the upstream database.
+    #[default]
+    Resnapshot,
+}
+
 /// Base is used to represent the root nodes of the ReadySet data flow graph.
 ///
 /// These nodes perform no computation, and their job is merely to persist all received updates and
@@ -71,6 +129,8 @@ pub struct Base {
     dropped: Vec<usize>,
     unmodified: bool,
     permissive_writes: bool,
+    check_constraints: Vec<CheckConstraint>,
+    duplicate_keys: DuplicateKeyBehavior,
 }
 
 impl Base {
@@ -104,6 +164,20 @@ impl Base {
         self
     }
 
+    /// Add a set of `CHECK` constraints to the base, which will reject any write containing a row
+    /// which doesn't satisfy them
+    pub fn with_check_constraints(mut self, check_constraints: Vec<CheckConstraint>) -> Self {
+        self.check_constraints = check_constraints;
+        self
+    }
+
+    /// Set how the base handles writes which would duplicate its primary key or one of its unique
+    /// keys
+    pub fn with_duplicate_key_behavior(mut self, duplicate_keys: DuplicateKeyBehavior) -> Self {
+        self.duplicate_keys = duplicate_keys;
+        self
+    }
+
     pub fn primary_key(&self) -> Option<&[usize]> {
         self.primary_key.as_deref()
     }
@@ -117,6 +191,15 @@ impl Base {
             .chain(self.unique_keys.iter().map(AsRef::as_ref))
     }
 
+    /// Return the list of unique keys (not including the primary key) which this base checks writes
+    /// against, and which therefore must be indexed in the base's state.
+    pub fn enforced_unique_keys(&self) -> impl Iterator<Item = &[usize]> {
+        self.unique_keys
+            .iter()
+            .map(AsRef::as_ref)
+            .filter(move |_| self.duplicate_keys != DuplicateKeyBehavior::Ignore)
+    }
+
     /// Add a new column to this base node.
     pub fn add_column(&mut self, default: DfValue) -> ReadySetResult<usize> {
         invariant!(
@@ -165,6 +248,96 @@ impl Base {
         }
     }
 
+    /// Ensure that every row being added to the base table satisfies all of the base's `CHECK`
+    /// constraints, returning an error naming the first violated constraint otherwise.
+    ///
+    /// Per the SQL standard, a constraint is only violated if its condition evaluates to false -
+    /// conditions that evaluate to NULL are satisfied.
+    fn check_constraints(&self, table: &Relation, records: &[Record]) -> ReadySetResult<()> {
+        if self.check_constraints.is_empty() {
+            return Ok(());
+        }
+
+        for row in records.iter().filter_map(|r| match r {
+            Record::Positive(row) => Some(row),
+            Record::Negative(_) => None,
+        }) {
+            for check in &self.check_constraints {
+                let satisfied = match check.expr.eval(row) {
+                    Ok(DfValue::None) => true,
+                    Ok(v) => v.is_truthy(),
+                    Err(error) => {
+                        warn!(
+                            %error,
+                            %table,
+                            constraint = %check.name,
+                            "Failed to evaluate check constraint"
+                        );
+                        false
+                    }
+                };
+                if !satisfied {
+                    return Err(ReadySetError::CheckConstraintViolated {
+                        table: table.clone(),
+                        constraint: check.name.to_string(),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Ensure that applying `records` to the base's state won't result in two rows with the same
+    /// value for any of the base's enforced unique keys, returning an error naming the first
+    /// duplicated key otherwise.
+    ///
+    /// Per the SQL standard, keys containing NULL values are never considered duplicates.
+    fn check_unique_keys(
+        &self,
+        table: &Relation,
+        columns: &[Column],
+        records: &[Record],
+        db: &MaterializedNodeState,
+    ) -> ReadySetResult<()> {
+        for key_cols in self.enforced_unique_keys() {
+            // Net change in the number of rows for each value of the key
+            let mut deltas: HashMap<Vec<DfValue>, isize> = HashMap::new();
+            for record in records {
+                let key = key_cols
+                    .iter()
+                    .map(|&col| record.get(col).cloned())
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or(ReadySetError::InvalidRecordLength)?;
+                if key.iter().any(|v| v.is_none()) {
+                    continue;
+                }
+                *deltas.entry(key).or_default() += if record.is_positive() { 1 } else { -1 };
+            }
+
+            for (key, delta) in deltas {
+                if delta <= 0 {
+                    continue;
+                }
+                let existing = match db.lookup(key_cols, &PointKey::from(key)) {
+                    LookupResult::Some(rows) => rows.len() as isize,
+                    LookupResult::Missing => internal!("unique key index missing in base state"),
+                };
+                if existing + delta > 1 {
+                    return Err(ReadySetError::DuplicateEntry {
+                        table: table.clone(),
+                        key: key_cols
+                            .iter()
+                            .map(|&col| columns.get(col).map(Column::name).unwrap_or_default())
+                            .join(","),
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// Process table operations for a base table that doesn't have a key, such tables can
     /// have multiple copies of the same row, and delete operations are free to remove any of them
     fn process_unkeyed(&mut self, operations: Vec<TableOperation>) -> ReadySetResult<BaseWrite> {
@@ -217,6 +390,7 @@ impl Base {
         state: &StateMap,
         snapshot_mode: SnapshotMode,
         name: Relation,
+        conflict_policy: ReplicationConflictPolicy,
     ) -> ReadySetResult<BaseWrite> {
         trace!(node = %our_index, base_ops = ?ops);
         for op in ops.iter_mut() {
@@ -225,7 +399,18 @@ impl Base {
 
         let key_cols = match &self.primary_key {
             Some(key) if !ops.is_empty() => key.as_ref(),
-            _ => return self.process_unkeyed(ops),
+            _ => {
+                let write = self.process_unkeyed(ops)?;
+                self.check_constraints(&name, &write.records)?;
+                if !snapshot_mode.is_enabled() && self.enforced_unique_keys().next().is_some() {
+                    let db = match state.get(our_index) {
+                        Some(x) => x,
+                        None => internal!("base must be materialized"),
+                    };
+                    self.check_unique_keys(&name, columns, &write.records, db)?;
+                }
+                return Ok(write);
+            }
         };
 
         let mut n_ops = ops.len();
@@ -302,15 +487,20 @@ impl Base {
             Inserted(Cow<'a, [DfValue]>),
         }
         let mut touched_keys: HashMap<Vec<DfValue>, TouchedKey> = HashMap::new();
-        let mut failed_log = FailedOpLogger::new(name);
+        let mut failed_log = FailedOpLogger::new(name.clone());
 
         for (key, ops) in &ops {
+            let ops = ops.collect::<Vec<_>>();
             // It is not enough to check the persisted value for the key, as it may have been
             // changed in previous iteration, therefore we have to check it was not
             // changed in one of the outstanding records
-            let stored_value = if snapshot_mode.is_enabled() {
-                // In snapshot mode don't check the currently store values as it doesn't matter for
-                // correctness but imposes a heavy toll on batched writes
+            let stored_value = if snapshot_mode.is_enabled()
+                && ops.iter().all(|op| matches!(op, TableOperation::Insert(_)))
+            {
+                // In snapshot mode don't check the currently store values for inserts as it doesn't
+                // matter for correctness but imposes a heavy toll on batched writes. Updates and
+                // deletes still need the stored value, both to be applied and to detect
+                // replication conflicts.
                 None
             } else {
                 match touched_keys.get(&key) {
@@ -335,20 +525,49 @@ impl Base {
             // Current value for the given key following the operations that were already applied to
             // it
             let mut value = stored_value.clone();
+            // Set if the last operation was a replicated delete of a row which doesn't exist. The
+            // replicators send updates as a delete of the old row followed by an insert of the new
+            // one, so an insert right after such a delete is a conflicting update.
+            let mut conflicting_delete = false;
 
             for op in ops {
+                let after_conflicting_delete = mem::take(&mut conflicting_delete);
                 match op {
-                    TableOperation::Insert(row) if value.is_none() => value = Some(Cow::Owned(row)),
-                    TableOperation::Insert(_) => {
-                        failed_log.failed_insert();
+                    TableOperation::Insert(_)
+                        if after_conflicting_delete
+                            && conflict_policy == ReplicationConflictPolicy::Ignore =>
+                    {
+                        // The delete was already counted as a conflict, so just drop the insert
+                    }
+                    TableOperation::Insert(row) if value.is_none() => {
+                        if after_conflicting_delete {
+                            failed_log.conflicting_update_inserted();
+                        }
+                        value = Some(Cow::Owned(row))
                     }
+                    TableOperation::Insert(row) => match self.duplicate_keys {
+                        DuplicateKeyBehavior::Ignore => failed_log.failed_insert(),
+                        DuplicateKeyBehavior::Reject => {
+                            return Err(ReadySetError::DuplicateEntry {
+                                table: name,
+                                key: "PRIMARY".into(),
+                            })
+                        }
+                        DuplicateKeyBehavior::Upsert => value = Some(Cow::Owned(row)),
+                    },
                     TableOperation::DeleteRow { row } if value == Some(Cow::Borrowed(&row)) => {
                         // Delete the row, but only if it fully matches the current row
                         value = None;
                     }
                     TableOperation::DeleteRow { row } => {
+                        conflicting_delete = !self.permissive_writes && value.is_none();
                         failed_log.failed_delete(row, value.as_deref());
                     }
+                    TableOperation::DeleteByKey { .. }
+                        if !self.permissive_writes && value.is_none() =>
+                    {
+                        failed_log.failed_delete_by_key();
+                    }
                     TableOperation::DeleteByKey { .. } => value = None,
 
                     TableOperation::InsertOrUpdate { row, .. } if value.is_none() => {
@@ -376,6 +595,24 @@ impl Base {
                             }
                         }
                     }
+                    TableOperation::Update { update, .. }
+                        if !self.permissive_writes
+                            && conflict_policy == ReplicationConflictPolicy::Insert
+                            && update.len() == columns.len()
+                            && update.iter().all(|m| matches!(m, Modification::Set(_))) =>
+                    {
+                        // The update sets every column, so we know the whole row
+                        value = Some(Cow::Owned(
+                            update
+                                .into_iter()
+                                .map(|m| match m {
+                                    Modification::Set(v) => v,
+                                    _ => DfValue::None,
+                                })
+                                .collect(),
+                        ));
+                        failed_log.conflicting_update_inserted();
+                    }
                     TableOperation::Update { .. } => {
                         failed_log.failed_update();
                     }
@@ -413,6 +650,11 @@ impl Base {
             self.fix(r);
         }
 
+        self.check_constraints(&name, &results)?;
+        if !snapshot_mode.is_enabled() {
+            self.check_unique_keys(&name, columns, &results, db)?;
+        }
+
         // We allow permissive writes if we are running without an upstream.
         // If we are allowing permissive writes, we treat failed writes as no-ops
         // instead of errors, using our base tables as a pseudo-upstream
@@ -420,6 +662,8 @@ impl Base {
         // as an error.
         if self.permissive_writes {
             failed_log.ensure_no_failed_ops()?;
+        } else {
+            failed_log.handle_replication_conflicts(conflict_policy)?;
         }
 
         Ok(BaseWrite {
@@ -452,6 +696,8 @@ impl Clone for Base {
             dropped: self.dropped.clone(),
             unmodified: self.unmodified,
             permissive_writes: self.permissive_writes,
+            check_constraints: self.check_constraints.clone(),
+            duplicate_keys: self.duplicate_keys,
         }
     }
 }
@@ -465,6 +711,8 @@ impl Default for Base {
             dropped: Vec::new(),
             unmodified: true,
             permissive_writes: false,
+            check_constraints: Vec::new(),
+            duplicate_keys: DuplicateKeyBehavior::Ignore,
         }
     }
 }
@@ -512,6 +760,9 @@ fn apply_table_op_coercions(op: &mut TableOperation, columns: &[Column]) -> Read
 pub(crate) struct FailedOpLogger {
     insert_existing: usize,
     update_non_existing: usize,
+    // Updates of rows which didn't exist, which were inserted instead per
+    // [`ReplicationConflictPolicy::Insert`]
+    update_inserted: usize,
     delete_non_existing: usize,
     // Maps from (column index, deleted data type, actual data type) to count for columns with
     // different types
@@ -526,6 +777,7 @@ impl FailedOpLogger {
         Self {
             insert_existing: Default::default(),
             update_non_existing: Default::default(),
+            update_inserted: Default::default(),
             delete_non_existing: Default::default(),
             delete_type_mismatch: Default::default(),
             delete_row_data_mismatch: Default::default(),
@@ -567,6 +819,10 @@ impl FailedOpLogger {
         }
     }
 
+    fn failed_delete_by_key(&mut self) {
+        self.delete_non_existing += 1;
+    }
+
     fn failed_insert(&mut self) {
         self.insert_existing += 1;
     }
@@ -586,6 +842,42 @@ impl FailedOpLogger {
             && self.delete_row_data_mismatch.is_empty())
     }
 
+    fn conflicting_update_inserted(&mut self) {
+        self.update_inserted += 1;
+    }
+
+    /// Apply `policy` to the updates and deletes of rows which don't exist in a replicated table
+    fn handle_replication_conflicts(self, policy: ReplicationConflictPolicy) -> ReadySetResult<()> {
+        let conflicts = self.update_non_existing
+            + self.update_inserted
+            + self.delete_non_existing
+            + self.delete_type_mismatch.values().sum::<usize>()
+            + self.delete_row_data_mismatch.values().sum::<usize>();
+        if conflicts == 0 {
+            return Ok(());
+        }
+
+        if policy == ReplicationConflictPolicy::Resnapshot {
+            self.log_errors();
+            return Err(ReadySetError::ReplicationConflict { table: self.table });
+        }
+
+        warn!(
+            table = %self.table,
+            update_non_existing = %self.update_non_existing,
+            update_inserted = %self.update_inserted,
+            delete_non_existing = %self.delete_non_existing,
+            ?policy,
+            "Replicated writes conflicted with the state of the table"
+        );
+        counter!(
+            recorded::BASE_TABLE_REPLICATION_CONFLICTS,
+            conflicts as u64,
+            "table_name" => self.table.to_string()
+        );
+        Ok(())
+    }
+
     fn ensure_no_failed_ops(self) -> ReadySetResult<()> {
         if self.has_failed_deletes_or_updates() || self.has_failed_inserts() {
             self.log_errors();
@@ -655,7 +947,7 @@ mod tests {
         use std::convert::TryInto;
 
         use dataflow_state::MaterializedNodeState;
-        use readyset_data::DfType;
+        use readyset_data::{DfType, Dialect};
 
         use super::*;
         use crate::node::Column as DfColumn;
@@ -720,10 +1012,11 @@ mod tests {
                         &states,
                         SnapshotMode::SnapshotModeDisabled,
                         name,
+                        ReplicationConflictPolicy::Ignore,
                     )
                     .unwrap()
                     .records;
-                node::materialize(&mut m, None, None, states.get_mut(local)).unwrap();
+                node::materialize(&mut m, None, None, states.get_mut(local), None).unwrap();
                 m
             };
 
@@ -831,6 +1124,7 @@ mod tests {
                     &state_map,
                     SnapshotMode::SnapshotModeDisabled,
                     table,
+                    ReplicationConflictPolicy::Ignore,
                 )
                 .unwrap(),
                 BaseWrite {
@@ -882,6 +1176,7 @@ mod tests {
                     &state_map,
                     SnapshotMode::SnapshotModeDisabled,
                     table,
+                    ReplicationConflictPolicy::Ignore,
                 )
                 .unwrap(),
                 BaseWrite {
@@ -940,6 +1235,7 @@ mod tests {
                     &state_map,
                     SnapshotMode::SnapshotModeDisabled,
                     table,
+                    ReplicationConflictPolicy::Ignore,
                 )
                 .unwrap(),
                 BaseWrite {
@@ -955,6 +1251,347 @@ mod tests {
             )
         }
 
+        #[test]
+        fn check_constraints() {
+            let check = CheckConstraint {
+                name: "t_chk_1".into(),
+                expr: Expr::Op {
+                    left: Box::new(Expr::Column {
+                        index: 1,
+                        ty: DfType::Int,
+                    }),
+                    op: crate::BinaryOperator::Greater,
+                    right: Box::new(Expr::Literal {
+                        val: 0.into(),
+                        ty: DfType::Int,
+                    }),
+                    ty: DfType::Bool,
+                    dialect: Dialect::DEFAULT_MYSQL,
+                    non_null_operands: false,
+                },
+            };
+            let mut b = Base::new()
+                .with_primary_key([0])
+                .with_check_constraints(vec![check]);
+            let ni = LocalNodeIndex::make(0u32);
+            let mut state = MaterializedNodeState::Persistent(PersistentState::new(
+                "check_constraints".into(),
+                Vec::<Box<[usize]>>::new(),
+                &PersistenceParameters::default(),
+            ));
+            state.add_key(Index::hash_map(vec![0]), None);
+
+            let mut recs = vec![Record::Positive(vec![1.into(), 1.into()])].into();
+            state.process_records(&mut recs, None, None).unwrap();
+
+            let mut state_map = NodeMap::new();
+            state_map.insert(ni, state);
+
+            let table = Relation {
+                name: "t".into(),
+                schema: None,
+            };
+            let mut process = |ops| {
+                b.process_ops(
+                    ni,
+                    &[],
+                    ops,
+                    &state_map,
+                    SnapshotMode::SnapshotModeDisabled,
+                    table.clone(),
+                    ReplicationConflictPolicy::Ignore,
+                )
+            };
+
+            // NULL satisfies the constraint, and rows being deleted aren't checked
+            process(vec![
+                TableOperation::Insert(vec![2.into(), 2.into()]),
+                TableOperation::Insert(vec![3.into(), DfValue::None]),
+            ])
+            .unwrap();
+
+            let err = process(vec![
+                TableOperation::Insert(vec![4.into(), 4.into()]),
+                TableOperation::Insert(vec![5.into(), 0.into()]),
+            ])
+            .unwrap_err();
+            assert_eq!(
+                err,
+                ReadySetError::CheckConstraintViolated {
+                    table: table.clone(),
+                    constraint: "t_chk_1".into(),
+                }
+            );
+
+            let err = process(vec![TableOperation::Update {
+                key: vec![1.into()],
+                update: vec![Modification::None, Modification::Set((-1).into())],
+            }])
+            .unwrap_err();
+            assert!(err.is_check_constraint_violated());
+        }
+
+        #[test]
+        fn duplicate_keys() {
+            let ni = LocalNodeIndex::make(0u32);
+            let mut state = MaterializedNodeState::Persistent(PersistentState::new(
+                "duplicate_keys".into(),
+                Vec::<Box<[usize]>>::new(),
+                &PersistenceParameters::default(),
+            ));
+            state.add_key(Index::hash_map(vec![0]), None);
+            state.add_key(Index::hash_map(vec![1]), None);
+
+            let mut recs = vec![Record::Positive(vec![1.into(), 10.into()])].into();
+            state.process_records(&mut recs, None, None).unwrap();
+
+            let mut state_map = NodeMap::new();
+            state_map.insert(ni, state);
+
+            let table = Relation {
+                name: "t".into(),
+                schema: None,
+            };
+            let columns = [
+                DfColumn::new("id".into(), DfType::Int, None),
+                DfColumn::new("email".into(), DfType::Int, None),
+            ];
+            let process = |b: &mut Base, ops| {
+                b.process_ops(
+                    ni,
+                    &columns,
+                    ops,
+                    &state_map,
+                    SnapshotMode::SnapshotModeDisabled,
+                    table.clone(),
+                    ReplicationConflictPolicy::Ignore,
+                )
+            };
+
+            let mut b = Base::new()
+                .with_primary_key([0])
+                .with_unique_keys([[1]])
+                .with_duplicate_key_behavior(DuplicateKeyBehavior::Reject);
+
+            let err = process(
+                &mut b,
+                vec![TableOperation::Insert(vec![1.into(), 20.into()])],
+            )
+            .unwrap_err();
+            assert_eq!(
+                err,
+                ReadySetError::DuplicateEntry {
+                    table: table.clone(),
+                    key: "PRIMARY".into(),
+                }
+            );
+
+            let err = process(
+                &mut b,
+                vec![TableOperation::Insert(vec![2.into(), 10.into()])],
+            )
+            .unwrap_err();
+            assert_eq!(
+                err,
+                ReadySetError::DuplicateEntry {
+                    table: table.clone(),
+                    key: "email".into(),
+                }
+            );
+
+            // Conflicts within the same batch are rejected too
+            let err = process(
+                &mut b,
+                vec![
+                    TableOperation::Insert(vec![2.into(), 20.into()]),
+                    TableOperation::Insert(vec![3.into(), 20.into()]),
+                ],
+            )
+            .unwrap_err();
+            assert!(err.is_duplicate_entry());
+
+            // NULLs are never duplicates, and a key value freed up by a delete can be reused
+            process(
+                &mut b,
+                vec![
+                    TableOperation::Insert(vec![2.into(), DfValue::None]),
+                    TableOperation::Insert(vec![3.into(), DfValue::None]),
+                    TableOperation::DeleteByKey {
+                        key: vec![1.into()],
+                    },
+                    TableOperation::Insert(vec![4.into(), 10.into()]),
+                ],
+            )
+            .unwrap();
+
+            let mut b = b.with_duplicate_key_behavior(DuplicateKeyBehavior::Upsert);
+            let write = process(
+                &mut b,
+                vec![TableOperation::Insert(vec![1.into(), 30.into()])],
+            )
+            .unwrap();
+            assert_eq!(
+                write.records,
+                vec![
+                    Record::Negative(vec![1.into(), 10.into()]),
+                    Record::Positive(vec![1.into(), 30.into()]),
+                ]
+                .into()
+            );
+
+            // Upserts still can't duplicate unique keys
+            let err = process(
+                &mut b,
+                vec![TableOperation::Insert(vec![2.into(), 10.into()])],
+            )
+            .unwrap_err();
+            assert!(err.is_duplicate_entry());
+        }
+
+        #[test]
+        fn replication_conflicts() {
+            let mut b = Base::new().with_primary_key([0]);
+            let ni = LocalNodeIndex::make(0u32);
+            let mut state = MaterializedNodeState::Persistent(PersistentState::new(
+                "replication_conflicts".into(),
+                Vec::<Box<[usize]>>::new(),
+                &PersistenceParameters::default(),
+            ));
+            state.add_key(Index::hash_map(vec![0]), None);
+
+            let mut state_map = NodeMap::new();
+            state_map.insert(ni, state);
+
+            let table = Relation {
+                name: "t".into(),
+                schema: None,
+            };
+            let columns = [
+                DfColumn::new("id".into(), DfType::Int, None),
+                DfColumn::new("x".into(), DfType::Int, None),
+            ];
+            let mut process = |ops, policy| {
+                b.process_ops(
+                    ni,
+                    &columns,
+                    ops,
+                    &state_map,
+                    SnapshotMode::SnapshotModeDisabled,
+                    table.clone(),
+                    policy,
+                )
+            };
+            let full_update = || TableOperation::Update {
+                key: vec![1.into()],
+                update: vec![Modification::Set(1.into()), Modification::Set(2.into())],
+            };
+            let partial_update = || TableOperation::Update {
+                key: vec![1.into()],
+                update: vec![Modification::None, Modification::Set(2.into())],
+            };
+
+            let write = process(vec![full_update()], ReplicationConflictPolicy::Ignore).unwrap();
+            assert!(write.records.is_empty());
+
+            let write = process(vec![full_update()], ReplicationConflictPolicy::Insert).unwrap();
+            assert_eq!(
+                write.records,
+                vec![Record::Positive(vec![1.into(), 2.into()])].into()
+            );
+
+            // We don't know the whole row, so we can't insert it
+            let write = process(vec![partial_update()], ReplicationConflictPolicy::Insert).unwrap();
+            assert!(write.records.is_empty());
+
+            let err = process(
+                vec![
+                    TableOperation::Insert(vec![2.into(), 2.into()]),
+                    TableOperation::DeleteRow {
+                        row: vec![3.into(), 3.into()],
+                    },
+                ],
+                ReplicationConflictPolicy::Resnapshot,
+            )
+            .unwrap_err();
+            assert_eq!(
+                err,
+                ReadySetError::ReplicationConflict {
+                    table: table.clone()
+                }
+            );
+            assert!(err.caused_by_rejected_write());
+        }
+
+        #[test]
+        fn replicated_update_conflicts() {
+            let mut b = Base::new().with_primary_key([0]);
+            let ni = LocalNodeIndex::make(0u32);
+            let mut state = MaterializedNodeState::Persistent(PersistentState::new(
+                "replicated_update_conflicts".into(),
+                Vec::<Box<[usize]>>::new(),
+                &PersistenceParameters::default(),
+            ));
+            state.add_key(Index::hash_map(vec![0]), None);
+
+            let mut state_map = NodeMap::new();
+            state_map.insert(ni, state);
+
+            let table = Relation {
+                name: "t".into(),
+                schema: None,
+            };
+            let columns = [
+                DfColumn::new("id".into(), DfType::Int, None),
+                DfColumn::new("x".into(), DfType::Int, None),
+            ];
+            let mut process = |ops, policy, snapshot_mode| {
+                b.process_ops(
+                    ni,
+                    &columns,
+                    ops,
+                    &state_map,
+                    snapshot_mode,
+                    table.clone(),
+                    policy,
+                )
+            };
+            // Updates are replicated as a delete of the old row and an insert of the new one
+            let update = || {
+                vec![
+                    TableOperation::DeleteRow {
+                        row: vec![1.into(), 1.into()],
+                    },
+                    TableOperation::Insert(vec![1.into(), 2.into()]),
+                ]
+            };
+
+            for snapshot_mode in [
+                SnapshotMode::SnapshotModeDisabled,
+                SnapshotMode::SnapshotModeEnabled,
+            ] {
+                let write =
+                    process(update(), ReplicationConflictPolicy::Ignore, snapshot_mode).unwrap();
+                assert!(write.records.is_empty());
+
+                let write =
+                    process(update(), ReplicationConflictPolicy::Insert, snapshot_mode).unwrap();
+                assert_eq!(
+                    write.records,
+                    vec![Record::Positive(vec![1.into(), 2.into()])].into()
+                );
+
+                let err = process(
+                    vec![TableOperation::DeleteByKey {
+                        key: vec![1.into()],
+                    }],
+                    ReplicationConflictPolicy::Resnapshot,
+                    snapshot_mode,
+                )
+                .unwrap_err();
+                assert!(err.caused_by_replication_conflict());
+            }
+        }
+
         #[test]
         fn truncate() {
             let mut b = Base::new().with_primary_key([0]);
@@ -991,6 +1628,7 @@ mod tests {
                     &state_map,
                     SnapshotMode::SnapshotModeDisabled,
                     table,
+                    ReplicationConflictPolicy::Ignore,
                 )
                 .unwrap();
             assert_eq!(
@@ -1,10 +1,12 @@
 use std::collections::HashMap;
 
+use dataflow_expression::Expr;
 use readyset_client::metrics::recorded;
 use readyset_errors::{internal_err, invariant, ReadySetResult};
 use serde::{Deserialize, Serialize};
 
 use crate::node::special::packet_filter::PacketFilter;
+use crate::node::special::predicate_index::PredicateIndex;
 use crate::payload::{ReplayPieceContext, SenderReplication};
 use crate::prelude::*;
 
@@ -72,6 +74,7 @@ pub struct Egress {
     #[serde(with = "serde_with::rust::hashmap_as_tuple_list")]
     tags: HashMap<Tag, NodeIndex>,
     packet_filter: PacketFilter,
+    predicate_index: PredicateIndex,
 }
 
 impl Clone for Egress {
@@ -82,6 +85,7 @@ impl Clone for Egress {
             txs: Vec::new(),
             tags: self.tags.clone(),
             packet_filter: self.packet_filter.clone(),
+            predicate_index: self.predicate_index.clone(),
         }
     }
 }
@@ -95,6 +99,12 @@ impl Egress {
         self.packet_filter.add_for_filtering(target);
     }
 
+    /// Set the predicate which records sent to `target` must satisfy, or remove it if `predicate`
+    /// is `None`. See [`PredicateIndex`] for more information.
+    pub fn set_predicate(&mut self, target: NodeIndex, predicate: Option<Expr>) {
+        self.predicate_index.set_predicate(target, predicate);
+    }
+
     pub fn add_tag(&mut self, tag: Tag, dst: NodeIndex) {
         self.tags.insert(tag, dst);
     }
@@ -111,6 +121,7 @@ impl Egress {
             txs,
             tags,
             packet_filter,
+            predicate_index,
         } = self;
 
         // send any queued updates to all external children
@@ -131,6 +142,9 @@ impl Egress {
             })
             .transpose()?;
 
+        // Evaluate each distinct predicate only once per record, rather than once per target
+        let predicate_matches = predicate_index.evaluate(message.as_ref().unwrap())?;
+
         for (txi, ref mut tx) in txs.iter_mut().enumerate() {
             let mut take = txi == txn;
             if let Some(replay_to) = replay_to.as_ref() {
@@ -156,6 +170,11 @@ impl Egress {
             m.link_mut().src = LocalNodeIndex::make(shard as u32);
             m.link_mut().dst = tx.local;
 
+            if !predicate_index.process(m.as_mut(), predicate_matches.as_ref(), tx.node) {
+                tx.inc_dropped();
+                continue;
+            }
+
             // Take the packet through the filter. The filter will make any necessary modifications
             // to the packet to be sent, and tell us if we should send the packet or drop it.
             if !packet_filter.process(m.as_mut(), keyed_by, tx.node)? {
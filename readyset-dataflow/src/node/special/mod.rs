@@ -1,6 +1,7 @@
 pub(crate) mod base;
 mod egress;
 mod packet_filter;
+mod predicate_index;
 pub(crate) mod reader;
 mod sharder;
 
@@ -9,8 +10,9 @@ pub struct Ingress;
 /// Source to all base table nodes.
 pub struct Source;
 
-pub use self::base::Base;
+pub use self::base::{Base, CheckConstraint, DuplicateKeyBehavior, ReplicationConflictPolicy};
 pub use self::egress::{Egress, EgressTx};
 pub use self::packet_filter::PacketFilter;
+pub use self::predicate_index::PredicateIndex;
 pub use self::reader::Reader;
 pub use self::sharder::Sharder;
@@ -297,6 +297,7 @@ mod test {
                     requesting_replica: 0,
                     unishard: false,
                 },
+                provenance: None,
             };
 
             let mut packet_filter = PacketFilter::default();
@@ -512,6 +513,8 @@ mod test {
                 link: create_link(),
                 data: records.into(),
                 trace: None,
+                batch: None,
+                provenance: None,
             }
         }
     }
@@ -625,6 +628,7 @@ mod test {
                 tag: Tag::new(1),
                 data: Default::default(),
                 context,
+                provenance: None,
             }
         }
     }
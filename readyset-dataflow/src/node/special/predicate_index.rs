@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use dataflow_expression::Expr;
+use readyset_errors::ReadySetResult;
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::NodeIndex;
+use crate::Packet;
+
+/// The results of evaluating every predicate in a [`PredicateIndex`] against the records of a
+/// single update. `matches[p][r]` is true if the record at position `r` satisfies the predicate
+/// with id `p`.
+pub(crate) struct PredicateMatches {
+    matches: Vec<Vec<bool>>,
+}
+
+/// The [`PredicateIndex`] lets an egress node drop records which the target domain would
+/// immediately discard anyway, because the only thing the target's ingress node feeds into is a
+/// filter those records don't pass.
+///
+/// Many caches over the same table often only differ in what they project out of the same filtered
+/// rows, so the same filter predicate is frequently set for several targets of a single egress.
+/// Identical predicates are stored only once, and each distinct predicate is evaluated exactly once
+/// per record (by [`PredicateIndex::evaluate`]), no matter how many targets share it; that single
+/// evaluation then decides which targets the record is sent to.
+///
+/// Only updates ([`Packet::Message`]s) are filtered - replays and evictions are always sent on
+/// unmodified. Since the filter in the target domain still runs on the records which do get sent,
+/// the index is purely an optimization, and the set of records which make it past the filter is
+/// unchanged.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Default, Debug)]
+pub struct PredicateIndex {
+    /// The distinct predicates in the index, indexed by predicate id
+    predicates: Vec<Expr>,
+    /// The id of the predicate for each target node which has one
+    #[serde(with = "serde_with::rust::hashmap_as_tuple_list")]
+    targets: HashMap<NodeIndex, usize>,
+}
+
+impl PredicateIndex {
+    /// Set the predicate which records sent to `target` must satisfy, or remove the predicate for
+    /// `target` if `predicate` is `None`.
+    pub fn set_predicate(&mut self, target: NodeIndex, predicate: Option<Expr>) {
+        self.targets.remove(&target);
+        if let Some(predicate) = predicate {
+            let id = match self.predicates.iter().position(|p| *p == predicate) {
+                Some(id) => id,
+                None => {
+                    self.predicates.push(predicate);
+                    self.predicates.len() - 1
+                }
+            };
+            self.targets.insert(target, id);
+        }
+
+        // Drop any predicates which are no longer used by any target, so we don't keep evaluating
+        // them
+        let mut used = vec![false; self.predicates.len()];
+        for &id in self.targets.values() {
+            used[id] = true;
+        }
+        if used.iter().all(|used| *used) {
+            return;
+        }
+        let mut new_ids = Vec::with_capacity(used.len());
+        let mut next_id = 0;
+        for &used in &used {
+            new_ids.push(next_id);
+            if used {
+                next_id += 1;
+            }
+        }
+        let mut used = used.into_iter();
+        self.predicates.retain(|_| used.next().unwrap_or(false));
+        for id in self.targets.values_mut() {
+            *id = new_ids[*id];
+        }
+    }
+
+    /// Returns true if no targets have a predicate set
+    pub fn is_empty(&self) -> bool {
+        self.targets.is_empty()
+    }
+
+    /// Evaluate every distinct predicate in the index once against each record in `packet`.
+    ///
+    /// Returns `None` if there's nothing to filter, either because the packet isn't an update or
+    /// because no targets have a predicate set.
+    pub(crate) fn evaluate(&self, packet: &Packet) -> ReadySetResult<Option<PredicateMatches>> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        let data = match packet {
+            Packet::Message { data, .. } => data,
+            _ => return Ok(None),
+        };
+        let matches = self
+            .predicates
+            .iter()
+            .map(|predicate| {
+                data.iter()
+                    .map(|record| Ok(predicate.eval(record.rec())?.is_truthy()))
+                    .collect::<ReadySetResult<Vec<_>>>()
+            })
+            .collect::<ReadySetResult<Vec<_>>>()?;
+        Ok(Some(PredicateMatches { matches }))
+    }
+
+    /// Remove any records from `packet` which don't satisfy the predicate for `target`, given the
+    /// `matches` returned by [`evaluate`](PredicateIndex::evaluate) for the same packet. Returns
+    /// `false` if no records are left, and the packet should be dropped rather than sent.
+    pub(crate) fn process(
+        &self,
+        packet: &mut Packet,
+        matches: Option<&PredicateMatches>,
+        target: NodeIndex,
+    ) -> bool {
+        let (matches, id) = match (matches, self.targets.get(&target)) {
+            (Some(matches), Some(id)) => (matches, id),
+            _ => return true,
+        };
+        if let Packet::Message { data, .. } = packet {
+            let mut matches = matches.matches[*id].iter();
+            data.retain(|_| matches.next().copied().unwrap_or(true));
+            !data.is_empty()
+        } else {
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use dataflow_expression::{BinaryOperator, Dialect};
+    use readyset_data::{DfType, DfValue};
+
+    use super::*;
+    use crate::prelude::*;
+
+    fn greater_than(col: usize, value: i32) -> Expr {
+        Expr::Op {
+            left: Box::new(Expr::Column {
+                index: col,
+                ty: DfType::Int,
+            }),
+            op: BinaryOperator::Greater,
+            right: Box::new(Expr::Literal {
+                val: value.into(),
+                ty: DfType::Int,
+            }),
+            ty: DfType::Bool,
+            dialect: Dialect::DEFAULT_MYSQL,
+            non_null_operands: false,
+        }
+    }
+
+    fn message(rows: Vec<Vec<DfValue>>) -> Packet {
+        Packet::Message {
+            link: Link::new(LocalNodeIndex::make(0), LocalNodeIndex::make(0)),
+            data: rows.into(),
+            trace: None,
+            batch: None,
+            provenance: None,
+        }
+    }
+
+    fn rows(packet: &Packet) -> Vec<Record> {
+        match packet {
+            Packet::Message { data, .. } => data.iter().cloned().collect(),
+            _ => vec![],
+        }
+    }
+
+    #[test]
+    fn deduplicates_identical_predicates() {
+        let mut index = PredicateIndex::default();
+        index.set_predicate(NodeIndex::new(1), Some(greater_than(0, 1)));
+        index.set_predicate(NodeIndex::new(2), Some(greater_than(0, 1)));
+        index.set_predicate(NodeIndex::new(3), Some(greater_than(1, 1)));
+        assert_eq!(index.predicates.len(), 2);
+
+        index.set_predicate(NodeIndex::new(3), None);
+        assert_eq!(index.predicates.len(), 1);
+        index.set_predicate(NodeIndex::new(1), Some(greater_than(1, 5)));
+        assert_eq!(index.predicates.len(), 2);
+        assert_eq!(
+            index.predicates[index.targets[&NodeIndex::new(2)]],
+            greater_than(0, 1)
+        );
+    }
+
+    #[test]
+    fn filters_messages_per_target() {
+        let mut index = PredicateIndex::default();
+        index.set_predicate(NodeIndex::new(1), Some(greater_than(0, 1)));
+        index.set_predicate(NodeIndex::new(2), Some(greater_than(0, 1)));
+        index.set_predicate(NodeIndex::new(3), Some(greater_than(0, 5)));
+
+        let packet = message(vec![vec![1.into()], vec![2.into()], vec![3.into()]]);
+        let matches = index.evaluate(&packet).unwrap();
+        assert!(matches.is_some());
+
+        for target in [1, 2] {
+            let mut m = packet.clone_data();
+            assert!(index.process(&mut m, matches.as_ref(), NodeIndex::new(target)));
+            assert_eq!(
+                rows(&m),
+                vec![
+                    Record::from(vec![DfValue::from(2)]),
+                    Record::from(vec![DfValue::from(3)])
+                ]
+            );
+        }
+
+        let mut m = packet.clone_data();
+        assert!(!index.process(&mut m, matches.as_ref(), NodeIndex::new(3)));
+
+        // Targets without a predicate get everything
+        let mut m = packet.clone_data();
+        assert!(index.process(&mut m, matches.as_ref(), NodeIndex::new(4)));
+        assert_eq!(rows(&m).len(), 3);
+    }
+}
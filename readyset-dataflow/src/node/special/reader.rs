@@ -1,14 +1,15 @@
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 use dataflow_expression::ReaderProcessing;
 use failpoint_macros::failpoint;
 use metrics::histogram;
+use nom_sql::{CacheFreshness, CacheResultLimits};
 use readyset_client::metrics::recorded;
 use readyset_client::{KeyColumnIdx, ViewPlaceholder};
 use readyset_tracing::{trace, warn};
 use serde::{Deserialize, Serialize};
 
-use crate::backlog;
+use crate::backlog::{self, LazyJoin};
 use crate::prelude::*;
 
 #[derive(Serialize, Deserialize)]
@@ -25,6 +26,37 @@ pub struct Reader {
     ///
     /// The data is stored in this manner instead of in a Hashmap to support ordered iteration.
     placeholder_map: Vec<(ViewPlaceholder, KeyColumnIdx)>,
+
+    /// Joins against dimension readers to perform when rows are read out of this reader, rather
+    /// than materializing the dimension columns in the reader itself
+    lazy_joins: Vec<LazyJoin>,
+
+    /// If true, this reader is always fully materialized (because it's the dimension side of a
+    /// [`LazyJoin`] in another reader, which can't trigger replays)
+    requires_full_materialization: bool,
+
+    /// If set, keys evicted from this reader keep being served with their evicted rows for up to
+    /// this long, while they're replayed in the background
+    #[serde(default)]
+    max_staleness: Option<Duration>,
+
+    /// If set, keys of this reader which contain a timestamp older than this (such as the time
+    /// buckets of a `GROUP BY date_trunc('hour', ts)` rollup) are periodically evicted
+    #[serde(default)]
+    bucket_retention: Option<Duration>,
+
+    /// Limits on the size of the rows returned from a single lookup into this reader
+    #[serde(default)]
+    result_limits: CacheResultLimits,
+
+    /// The bound on replication lag that reads from this reader are subject to. This is enforced
+    /// by clients, which know the current replication lag.
+    freshness: CacheFreshness,
+
+    /// The local indices of the base tables this reader is derived from, which must all have
+    /// reached a write batch before the batch is made visible in this reader
+    #[serde(default)]
+    base_tables: Vec<LocalNodeIndex>,
 }
 
 impl Clone for Reader {
@@ -34,6 +66,13 @@ impl Clone for Reader {
             reader_processing: self.reader_processing.clone(),
             index: self.index.clone(),
             placeholder_map: self.placeholder_map.clone(),
+            lazy_joins: self.lazy_joins.clone(),
+            requires_full_materialization: self.requires_full_materialization,
+            max_staleness: self.max_staleness,
+            bucket_retention: self.bucket_retention,
+            result_limits: self.result_limits,
+            freshness: self.freshness,
+            base_tables: self.base_tables.clone(),
         }
     }
 }
@@ -45,6 +84,13 @@ impl Reader {
             reader_processing,
             index: None,
             placeholder_map: Default::default(),
+            lazy_joins: Default::default(),
+            requires_full_materialization: false,
+            max_staleness: None,
+            bucket_retention: None,
+            result_limits: Default::default(),
+            freshness: Default::default(),
+            base_tables: Default::default(),
         }
     }
 
@@ -60,6 +106,13 @@ impl Reader {
             reader_processing: self.reader_processing.clone(),
             index: self.index.clone(),
             placeholder_map: self.placeholder_map.clone(),
+            lazy_joins: self.lazy_joins.clone(),
+            requires_full_materialization: self.requires_full_materialization,
+            max_staleness: self.max_staleness,
+            bucket_retention: self.bucket_retention,
+            result_limits: self.result_limits,
+            freshness: self.freshness,
+            base_tables: self.base_tables.clone(),
         }
     }
 
@@ -115,6 +168,81 @@ impl Reader {
         self.placeholder_map.as_ref()
     }
 
+    /// Sets the joins against dimension readers to perform when rows are read out of this reader
+    pub fn set_lazy_joins(&mut self, lazy_joins: Vec<LazyJoin>) {
+        self.lazy_joins = lazy_joins;
+    }
+
+    /// Returns the joins against dimension readers to perform when rows are read out of this
+    /// reader
+    pub fn lazy_joins(&self) -> &[LazyJoin] {
+        &self.lazy_joins
+    }
+
+    /// Force this reader to be fully materialized
+    pub fn set_requires_full_materialization(&mut self) {
+        self.requires_full_materialization = true;
+    }
+
+    /// Returns true if this reader must be fully materialized
+    pub fn requires_full_materialization(&self) -> bool {
+        self.requires_full_materialization
+    }
+
+    /// Allow keys evicted from this reader to be served with their evicted rows for up to
+    /// `max_staleness`, while they're replayed
+    pub fn set_max_staleness(&mut self, max_staleness: Duration) {
+        self.max_staleness = Some(max_staleness);
+    }
+
+    /// Returns how long keys evicted from this reader may be served with their evicted rows, if
+    /// at all
+    pub fn max_staleness(&self) -> Option<Duration> {
+        self.max_staleness
+    }
+
+    /// Periodically evict keys of this reader which contain a timestamp older than
+    /// `bucket_retention`
+    pub fn set_bucket_retention(&mut self, bucket_retention: Duration) {
+        self.bucket_retention = Some(bucket_retention);
+    }
+
+    /// Returns how old the timestamps in keys of this reader may get before the keys are evicted,
+    /// if they're evicted at all
+    pub fn bucket_retention(&self) -> Option<Duration> {
+        self.bucket_retention
+    }
+
+    /// Sets the local indices of the base tables this reader is derived from
+    pub fn set_base_tables(&mut self, base_tables: Vec<LocalNodeIndex>) {
+        self.base_tables = base_tables;
+    }
+
+    /// Returns the local indices of the base tables this reader is derived from
+    pub fn base_tables(&self) -> &[LocalNodeIndex] {
+        &self.base_tables
+    }
+
+    /// Sets the limits on the size of the rows returned from a single lookup into this reader
+    pub fn set_result_limits(&mut self, result_limits: CacheResultLimits) {
+        self.result_limits = result_limits;
+    }
+
+    /// Returns the limits on the size of the rows returned from a single lookup into this reader
+    pub fn result_limits(&self) -> CacheResultLimits {
+        self.result_limits
+    }
+
+    /// Sets the bound on replication lag that reads from this reader are subject to
+    pub fn set_freshness(&mut self, freshness: CacheFreshness) {
+        self.freshness = freshness;
+    }
+
+    /// Returns the bound on replication lag that reads from this reader are subject to
+    pub fn freshness(&self) -> CacheFreshness {
+        self.freshness
+    }
+
     #[allow(clippy::unreachable)]
     #[failpoint("reader-handle-packet")]
     pub(in crate::node) fn process(
@@ -199,11 +327,24 @@ impl Reader {
             });
         }
 
-        state.add(m.take_data());
+        if let Some(tags) = m.provenance().cloned() {
+            let replay = !m.is_regular();
+            state.record_provenance(m.mut_data(), &tags, replay);
+        }
+
+        // don't make writes that are part of a write batch visible until all the writes in the
+        // batch have reached this reader
+        match m.batch().cloned() {
+            Some(batch) => state.add_batch(&batch, m.take_data()),
+            None => state.add(m.take_data()),
+        }
 
         if swap {
             // TODO: avoid doing the pointer swap if we didn't modify anything (inc. ts)
             state.swap();
+            // Let anything caching the results of reads from this reader know that they may be
+            // stale. There may be nobody listening, which is fine.
+            let _ = state.notify_readers();
         }
     }
 
@@ -240,6 +240,24 @@ impl Sharder {
         Ok(())
     }
 
+    /// Send a timestamp or barrier packet on to every shard, since each shard needs to know that
+    /// all the updates before it have been sent
+    pub fn process_timestamp(
+        &self,
+        m: &Packet,
+        index: LocalNodeIndex,
+        replica: usize,
+        output: &mut dyn Executor,
+    ) -> ReadySetResult<()> {
+        for tx in &self.txs {
+            let mut m = Box::new(m.clone_data());
+            m.link_mut().src = index;
+            m.link_mut().dst = tx.node;
+            tx.send(m, replica, output)?;
+        }
+        Ok(())
+    }
+
     #[allow(clippy::unreachable)]
     #[allow(clippy::too_many_arguments)]
     pub fn process_eviction(
@@ -27,6 +27,11 @@ impl Filter {
             expression,
         }
     }
+
+    /// Returns the expression that records must satisfy to be emitted by this filter
+    pub fn expression(&self) -> &Expr {
+        &self.expression
+    }
 }
 
 impl Ingredient for Filter {
@@ -134,7 +139,7 @@ impl Ingredient for Filter {
 #[cfg(test)]
 mod tests {
     use dataflow_expression::utils::{column_with_type, make_literal};
-    use dataflow_expression::BinaryOperator;
+    use dataflow_expression::{BinaryOperator, Dialect};
     use readyset_data::DfType;
     use Expr::Op;
 
@@ -154,6 +159,8 @@ mod tests {
                     op: BinaryOperator::Equal,
                     right: Box::new(make_literal(DfValue::from("a"))),
                     ty: DfType::Bool,
+                    dialect: Dialect::DEFAULT_MYSQL,
+                    non_null_operands: false,
                 }),
             ),
             materialized,
@@ -199,6 +206,8 @@ mod tests {
                     op: BinaryOperator::Equal,
                     right: Box::new(make_literal(DfValue::from(1))),
                     ty: DfType::Bool,
+                    dialect: Dialect::DEFAULT_MYSQL,
+                    non_null_operands: false,
                 }),
                 op: BinaryOperator::And,
                 right: Box::new(Op {
@@ -206,8 +215,12 @@ mod tests {
                     op: BinaryOperator::Equal,
                     right: Box::new(make_literal(DfValue::from("a"))),
                     ty: DfType::Bool,
+                    dialect: Dialect::DEFAULT_MYSQL,
+                    non_null_operands: false,
                 }),
                 ty: DfType::Bool,
+                dialect: Dialect::DEFAULT_MYSQL,
+                non_null_operands: false,
             }),
         );
 
@@ -268,6 +281,8 @@ mod tests {
                     op: BinaryOperator::LessOrEqual,
                     right: Box::new(make_literal(DfValue::from(2))),
                     ty: DfType::Bool,
+                    dialect: Dialect::DEFAULT_MYSQL,
+                    non_null_operands: false,
                 }),
                 op: BinaryOperator::And,
                 right: Box::new(Op {
@@ -275,8 +290,12 @@ mod tests {
                     op: BinaryOperator::NotEqual,
                     right: Box::new(make_literal(DfValue::from("a"))),
                     ty: DfType::Bool,
+                    dialect: Dialect::DEFAULT_MYSQL,
+                    non_null_operands: false,
                 }),
                 ty: DfType::Bool,
+                dialect: Dialect::DEFAULT_MYSQL,
+                non_null_operands: false,
             }),
         );
 
@@ -306,6 +325,8 @@ mod tests {
                 op: BinaryOperator::Equal,
                 right: Box::new(column_with_type(1, DfType::Int)),
                 ty: DfType::Bool,
+                dialect: Dialect::DEFAULT_MYSQL,
+                non_null_operands: false,
             }),
         );
 
@@ -8,6 +8,7 @@ use readyset_data::{Collation, DfType};
 use readyset_errors::{invariant, ReadySetResult};
 use serde::{Deserialize, Serialize};
 
+use crate::ops::grouped::approximate::ApproximateAggregation;
 use crate::ops::grouped::{GroupedOperation, GroupedOperator};
 use crate::prelude::*;
 
@@ -22,6 +23,8 @@ pub enum Aggregation {
     Avg,
     /// Concatenates using the given separator between values.
     GroupConcat { separator: String },
+    /// Estimates the given aggregate from a sketch of the values, see [`ApproximateAggregation`].
+    Approximate(ApproximateAggregation),
 }
 
 impl Aggregation {
@@ -50,6 +53,9 @@ impl Aggregation {
                 }
             }
             Aggregation::GroupConcat { .. } => DfType::Text(/* TODO */ Collation::default()),
+            Aggregation::Approximate(_) => internal!(
+                "Approximate aggregates are separate from the other aggregations in the dataflow."
+            ),
         };
 
         Ok(GroupedOperator::new(
@@ -228,6 +234,10 @@ impl GroupedOperation for Aggregator {
                     Aggregation::GroupConcat { separator: _ } => internal!(
                         "GroupConcats are separate from the other aggregations in the dataflow."
                     ),
+                    Aggregation::Approximate(_) => internal!(
+                        "Approximate aggregates are separate from the other aggregations in the \
+                         dataflow."
+                    ),
                 }
             };
 
@@ -245,6 +255,7 @@ impl GroupedOperation for Aggregator {
                 Aggregation::GroupConcat { separator: ref s } => {
                     format!("||({})", s)
                 }
+                Aggregation::Approximate(_) => "~".to_owned(),
             };
         }
 
@@ -253,6 +264,7 @@ impl GroupedOperation for Aggregator {
             Aggregation::Sum => format!("𝛴({})", self.over),
             Aggregation::Avg => format!("Avg({})", self.over),
             Aggregation::GroupConcat { separator: ref s } => format!("||({}, {})", s, self.over),
+            Aggregation::Approximate(_) => format!("~({})", self.over),
         };
         let group_cols = self
             .group
@@ -286,6 +298,24 @@ impl GroupedOperation for Aggregator {
             _ => false,
         }
     }
+
+    fn combiner(&self) -> Option<Self> {
+        match self.op {
+            // Partial counts and partial sums are both combined by summing them, keeping the
+            // output type of the original aggregate
+            Aggregation::Count { .. } | Aggregation::Sum => Some(Aggregator {
+                op: Aggregation::Sum,
+                over: self.group.len(),
+                group: (0..self.group.len()).collect(),
+                count_sum_map: RefCell::new(Default::default()),
+                over_else: None,
+                out_ty: self.out_ty.clone(),
+            }),
+            Aggregation::Avg | Aggregation::GroupConcat { .. } | Aggregation::Approximate(_) => {
+                None
+            }
+        }
+    }
 }
 
 // TODO: These unit tests are lengthy, repetitive, and hard to read.
@@ -346,6 +376,21 @@ mod tests {
         assert_eq!(a.description(true), "Avg(1) γ[2, 0]");
     }
 
+    #[test]
+    fn combines_partial_counts() {
+        let count = Aggregation::Count
+            .over(0.into(), 1, &[2, 0], &DfType::Unknown)
+            .unwrap();
+        let combiner = count.combiner(1.into()).unwrap();
+        assert_eq!(combiner.description(true), "𝛴(2) γ[0, 1]");
+        assert_eq!(combiner.output_col_type(), DfType::BigInt);
+
+        let avg = Aggregation::Avg
+            .over(0.into(), 1, &[0], &DfType::Unknown)
+            .unwrap();
+        assert!(avg.combiner(1.into()).is_none());
+    }
+
     /// Testing count emits correct records with single column group and single over column
     /// Records are in the form of (GroupCol, OverCol).
     /// Includes adding and removing records from different groups independently and in batch.
@@ -0,0 +1,975 @@
+//! Approximate aggregates, computed from fixed-size sketches of the values in each group rather
+//! than from all the values themselves.
+//!
+//! Maintaining an exact `COUNT(DISTINCT)` requires materializing every distinct value in every
+//! group, which for very high-cardinality groups can be prohibitively expensive. Caches created
+//! with `APPROXIMATE AGGREGATES` instead estimate `COUNT(DISTINCT)` using a [`HyperLogLog`]
+//! sketch, and `PERCENTILE_CONT` using a [`TDigest`].
+//!
+//! Sketches can't have values removed from them, so whenever a value is removed from a group the
+//! sketch for that group is rebuilt from all the values in the group. Sketches are mergeable
+//! though, so approximate aggregates can be split up across the shards of a sharded input: each
+//! shard runs an aggregate in [`Stage::Partial`], which emits the serialized sketch of the values
+//! in its shard, and the sketches for each group are then merged by an aggregate in
+//! [`Stage::Combine`], which emits the estimate.
+//!
+//! The sketches kept for each group count towards the size of the aggregate's state, and are
+//! evicted along with the group's row. A sketch that's been evicted (or lost) is rebuilt from the
+//! aggregate's parent the next time the group is written to.
+
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::sync::Arc;
+
+use readyset_client::KeyComparison;
+use readyset_data::DfType;
+use readyset_errors::{internal_err, invariant, invariant_eq, ReadySetResult};
+use serde::{Deserialize, Serialize};
+
+use crate::ops::grouped::{GroupedOperation, GroupedOperator};
+use crate::prelude::*;
+
+/// The number of bits of the hash of each value used to select a [`HyperLogLog`] register. There
+/// are `2^HLL_PRECISION` registers, giving a standard error of about `1.04 / sqrt(2^12)`, or 1.6%.
+const HLL_PRECISION: u32 = 12;
+
+/// The largest number of non-zero registers a [`HyperLogLog`] stores sparsely, before switching to
+/// storing every register. A sparse register takes 4 bytes to a dense register's 1, so past this
+/// point the dense representation is smaller.
+const HLL_SPARSE_MAX_REGISTERS: usize = (1 << HLL_PRECISION) / 4;
+
+/// The compression parameter of a [`TDigest`], which bounds the number of centroids it keeps.
+/// Larger values use more memory, and give more accurate estimates.
+const TDIGEST_COMPRESSION: f64 = 100.0;
+
+/// The registers of a [`HyperLogLog`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+enum Registers {
+    /// The index and value of each non-zero register, sorted by index. Used while few registers
+    /// are set, so that the sketches of small sets are small.
+    Sparse(Vec<(u16, u8)>),
+    /// The value of every register
+    Dense(Vec<u8>),
+}
+
+/// A HyperLogLog sketch, used to estimate the number of distinct values in a set.
+///
+/// See <https://algo.inria.fr/flajolet/Publications/FlFuGaMe07.pdf>
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HyperLogLog {
+    registers: Registers,
+}
+
+impl Default for HyperLogLog {
+    fn default() -> Self {
+        Self {
+            registers: Registers::Sparse(vec![]),
+        }
+    }
+}
+
+impl HyperLogLog {
+    /// Add a value to the set
+    pub fn insert(&mut self, value: &DfValue) {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        let hash = hasher.finish();
+
+        let register = (hash >> (64 - HLL_PRECISION)) as u16;
+        // Set the lowest bit that isn't used to select the register, so that the rank is bounded
+        // even if the remaining bits of the hash are all zero
+        let rank = ((hash << HLL_PRECISION) | (1 << (HLL_PRECISION - 1))).leading_zeros() + 1;
+        self.set_register(register, rank as u8);
+    }
+
+    /// Set the register with the given index to `value`, if it's not already larger
+    fn set_register(&mut self, index: u16, value: u8) {
+        match &mut self.registers {
+            Registers::Sparse(registers) => {
+                match registers.binary_search_by_key(&index, |&(i, _)| i) {
+                    Ok(pos) => {
+                        if let Some((_, r)) = registers.get_mut(pos) {
+                            *r = (*r).max(value);
+                        }
+                    }
+                    Err(pos) => {
+                        registers.insert(pos, (index, value));
+                        if registers.len() > HLL_SPARSE_MAX_REGISTERS {
+                            self.make_dense();
+                        }
+                    }
+                }
+            }
+            Registers::Dense(registers) => {
+                if let Some(r) = registers.get_mut(usize::from(index)) {
+                    *r = (*r).max(value);
+                }
+            }
+        }
+    }
+
+    /// Switch to storing every register
+    fn make_dense(&mut self) {
+        if let Registers::Sparse(sparse) = &self.registers {
+            let mut registers = vec![0; 1 << HLL_PRECISION];
+            for &(i, r) in sparse {
+                #[allow(clippy::indexing_slicing)] // i < 2^HLL_PRECISION
+                {
+                    registers[usize::from(i)] = r;
+                }
+            }
+            self.registers = Registers::Dense(registers);
+        }
+    }
+
+    /// Merge another sketch into this one, so that it estimates the number of distinct values in
+    /// the union of the two sets
+    pub fn merge(&mut self, other: &HyperLogLog) {
+        match &other.registers {
+            Registers::Sparse(other) => {
+                for &(i, r) in other {
+                    self.set_register(i, r);
+                }
+            }
+            Registers::Dense(other) => {
+                self.make_dense();
+                if let Registers::Dense(registers) = &mut self.registers {
+                    for (r, other) in registers.iter_mut().zip(other) {
+                        *r = (*r).max(*other);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the number of bytes of memory used by the sketch's registers
+    pub fn size_bytes(&self) -> usize {
+        match &self.registers {
+            Registers::Sparse(registers) => registers.len() * mem::size_of::<(u16, u8)>(),
+            Registers::Dense(registers) => registers.len(),
+        }
+    }
+
+    /// Estimate the number of distinct values in the set
+    pub fn estimate(&self) -> u64 {
+        let num_registers = 1usize << HLL_PRECISION;
+        let m = num_registers as f64;
+        let (sum, zeros) = match &self.registers {
+            Registers::Sparse(registers) => (
+                registers
+                    .iter()
+                    .map(|&(_, r)| 2f64.powi(-i32::from(r)))
+                    .sum::<f64>()
+                    // Every register that isn't stored is zero, and contributes 2^0
+                    + (num_registers - registers.len()) as f64,
+                num_registers - registers.len(),
+            ),
+            Registers::Dense(registers) => (
+                registers.iter().map(|&r| 2f64.powi(-i32::from(r))).sum(),
+                registers.iter().filter(|&&r| r == 0).count(),
+            ),
+        };
+        let alpha = 0.7213 / (1.0 + 1.079 / m);
+        let raw = alpha * m * m / sum;
+        // Small cardinalities are estimated much more accurately by linear counting
+        let estimate = if raw <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        };
+        estimate.round() as u64
+    }
+}
+
+/// A cluster of values in a [`TDigest`], summarized by their mean
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct Centroid {
+    mean: f64,
+    weight: f64,
+}
+
+/// A t-digest, used to estimate quantiles of a set of numbers.
+///
+/// Values are clustered into centroids, which are kept small near the extremes of the set (where
+/// quantile estimates are most sensitive to error) and allowed to grow towards the median.
+///
+/// See <https://arxiv.org/abs/1902.04023>
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TDigest {
+    /// The centroids of the digest. Sorted by mean, unless values have been added since the
+    /// digest was last [compressed](TDigest::compress).
+    centroids: Vec<Centroid>,
+}
+
+impl TDigest {
+    /// Add a value to the set
+    pub fn insert(&mut self, value: f64) {
+        self.centroids.push(Centroid {
+            mean: value,
+            weight: 1.0,
+        });
+    }
+
+    /// Merge another digest into this one, so that it summarizes the union of the two sets
+    pub fn merge(&mut self, other: &TDigest) {
+        self.centroids.extend_from_slice(&other.centroids);
+    }
+
+    /// Sort the centroids of the digest, and merge adjacent centroids which together are still
+    /// small enough for their position in the set
+    pub fn compress(&mut self) {
+        self.centroids.sort_by(|a, b| a.mean.total_cmp(&b.mean));
+        let total: f64 = self.centroids.iter().map(|c| c.weight).sum();
+
+        let mut merged: Vec<Centroid> = Vec::with_capacity(self.centroids.len());
+        // The total weight of the centroids before the last merged centroid
+        let mut seen = 0.0;
+        for c in self.centroids.drain(..) {
+            if let Some(last) = merged.last_mut() {
+                let weight = last.weight + c.weight;
+                let q = (seen + weight / 2.0) / total;
+                if weight <= 4.0 * total * q * (1.0 - q) / TDIGEST_COMPRESSION {
+                    last.mean += (c.mean - last.mean) * c.weight / weight;
+                    last.weight = weight;
+                    continue;
+                }
+                seen += last.weight;
+            }
+            merged.push(c);
+        }
+        self.centroids = merged;
+    }
+
+    /// Estimate the value at the given quantile of the set, interpolating between values in the
+    /// same way as `PERCENTILE_CONT`, or return `None` if the set is empty. The digest must have
+    /// been [compressed](TDigest::compress) since values were last added to it.
+    ///
+    /// As long as every centroid holds a single value, the estimate is exact.
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        let total: f64 = self.centroids.iter().map(|c| c.weight).sum();
+        // The (zero-based) rank among the values of the value we're looking for
+        let rank = q * (total - 1.0);
+
+        let mut seen = 0.0;
+        // The rank of the middle of the previous centroid, and its mean
+        let mut prev: Option<(f64, f64)> = None;
+        for c in &self.centroids {
+            let center = seen + (c.weight - 1.0) / 2.0;
+            if rank <= center {
+                return Some(match prev {
+                    Some((prev_center, prev_mean)) => {
+                        prev_mean
+                            + (c.mean - prev_mean) * (rank - prev_center) / (center - prev_center)
+                    }
+                    None => c.mean,
+                });
+            }
+            prev = Some((center, c.mean));
+            seen += c.weight;
+        }
+        prev.map(|(_, mean)| mean)
+    }
+}
+
+/// Supported kinds of approximate aggregates
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ApproximateAggregation {
+    /// Estimate the number of distinct non-null values in the `over` column in each group, using a
+    /// [`HyperLogLog`]
+    CountDistinct,
+    /// Estimate the value at `fraction` (between 0 and 1) of the way through the non-null values
+    /// of the `over` column in each group, interpolating between values as `PERCENTILE_CONT`
+    /// does, using a [`TDigest`]
+    PercentileCont { fraction: f64 },
+}
+
+// The fraction of a percentile is never NaN
+impl Eq for ApproximateAggregation {}
+
+impl ApproximateAggregation {
+    /// Construct a new [`ApproximateAggregator`] that performs this operation.
+    ///
+    /// The aggregation will aggregate the value in column number `over` from its inputs (i.e.,
+    /// from the `src` node in the graph), and use the columns in the `group_by` array as a group
+    /// identifier.
+    pub fn over(
+        self,
+        src: NodeIndex,
+        over: usize,
+        group_by: &[usize],
+    ) -> GroupedOperator<ApproximateAggregator> {
+        GroupedOperator::new(
+            src,
+            ApproximateAggregator {
+                op: self,
+                over,
+                group: group_by.into(),
+                stage: Stage::Complete,
+                groups: Default::default(),
+                groups_size: Default::default(),
+            },
+        )
+    }
+
+    fn new_sketch(&self) -> Sketch {
+        match self {
+            ApproximateAggregation::CountDistinct => Sketch::HyperLogLog(Default::default()),
+            ApproximateAggregation::PercentileCont { .. } => Sketch::TDigest(Default::default()),
+        }
+    }
+}
+
+/// The sketch of the values in a single group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+enum Sketch {
+    HyperLogLog(HyperLogLog),
+    TDigest(TDigest),
+}
+
+impl Sketch {
+    fn insert(&mut self, value: &DfValue) -> ReadySetResult<()> {
+        if value.is_none() {
+            return Ok(());
+        }
+        match self {
+            Sketch::HyperLogLog(hll) => hll.insert(value),
+            Sketch::TDigest(digest) => digest.insert(f64::try_from(value)?),
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, other: &Sketch) -> ReadySetResult<()> {
+        match (self, other) {
+            (Sketch::HyperLogLog(hll), Sketch::HyperLogLog(other)) => hll.merge(other),
+            (Sketch::TDigest(digest), Sketch::TDigest(other)) => digest.merge(other),
+            _ => return Err(internal_err!("Can't merge sketches of different kinds")),
+        }
+        Ok(())
+    }
+
+    fn compress(&mut self) {
+        if let Sketch::TDigest(digest) = self {
+            digest.compress()
+        }
+    }
+
+    /// Returns the number of bytes of memory used by the sketch
+    fn size_bytes(&self) -> usize {
+        mem::size_of::<Self>()
+            + match self {
+                Sketch::HyperLogLog(hll) => hll.size_bytes(),
+                Sketch::TDigest(digest) => digest.centroids.len() * mem::size_of::<Centroid>(),
+            }
+    }
+
+    fn estimate(&self, op: &ApproximateAggregation) -> DfValue {
+        match (self, op) {
+            (Sketch::HyperLogLog(hll), _) => DfValue::Int(hll.estimate() as i64),
+            (Sketch::TDigest(digest), ApproximateAggregation::PercentileCont { fraction }) => {
+                digest
+                    .quantile(*fraction)
+                    .map_or(DfValue::None, DfValue::Double)
+            }
+            (Sketch::TDigest(_), _) => DfValue::None,
+        }
+    }
+
+    fn to_df_value(&self) -> ReadySetResult<DfValue> {
+        Ok(DfValue::ByteArray(Arc::new(bincode::serialize(self)?)))
+    }
+
+    fn from_df_value(value: &DfValue) -> ReadySetResult<Self> {
+        match value {
+            DfValue::ByteArray(bytes) => Ok(bincode::deserialize(bytes)?),
+            _ => Err(internal_err!(
+                "Partial approximate aggregate is not a sketch"
+            )),
+        }
+    }
+}
+
+/// Which part of an approximate aggregate an [`ApproximateAggregator`] computes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Stage {
+    /// Sketch the values in the `over` column, and emit the estimate
+    Complete,
+    /// Sketch the values in the `over` column, and emit the serialized sketch, to be merged with
+    /// the sketches of other disjoint subsets of the input by a [`Stage::Combine`]
+    Partial,
+    /// Merge the serialized sketches emitted by several [`Stage::Partial`] aggregates, and emit
+    /// the estimate
+    Combine,
+}
+
+/// `ApproximateAggregator` implements a Soup node that computes approximate aggregates. See the
+/// [module documentation](self) for more information.
+///
+/// `ApproximateAggregator` nodes are constructed through `ApproximateAggregation` variants using
+/// `ApproximateAggregation::over`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApproximateAggregator {
+    op: ApproximateAggregation,
+    over: usize,
+    group: Vec<usize>,
+    stage: Stage,
+    // The sketches for each group. Only needed for stages which emit an estimate, since a partial
+    // aggregate's output is the sketch itself.
+    // We skip serde since we don't want the state, just the configuration.
+    #[serde(skip)]
+    groups: RefCell<HashMap<GroupHash, GroupSketches>>,
+    // The total size of `groups`, in bytes
+    #[serde(skip)]
+    groups_size: Cell<u64>,
+}
+
+type GroupHash = u64;
+
+/// The sketches an [`ApproximateAggregator`] keeps for a group, along with the value it last
+/// emitted for the group so that it can tell if they're still valid
+#[derive(Debug, Clone)]
+struct GroupSketches {
+    /// In [`Stage::Complete`], the sketch of the values in the group. In [`Stage::Combine`], the
+    /// sketch currently emitted for the group by each partial aggregate, which are merged to
+    /// compute the estimate. Keeping these means that when a partial aggregate's sketch changes,
+    /// its old sketch can be replaced without rebuilding the group from all the others.
+    sketches: Vec<Sketch>,
+    last: DfValue,
+}
+
+impl GroupSketches {
+    /// Returns the number of bytes of memory used by the group's entry in the aggregator's groups
+    fn size_bytes(&self) -> u64 {
+        (mem::size_of::<(GroupHash, Self)>()
+            + self.sketches.iter().map(Sketch::size_bytes).sum::<usize>()) as u64
+    }
+}
+
+/// Diff type for approximate aggregates
+#[derive(Debug, Clone)]
+pub struct ApproximateDiff {
+    /// Value of the `over` column
+    value: DfValue,
+    /// True if positive record, false if negative
+    positive: bool,
+    /// Hash of the values of the group by columns
+    group_hash: GroupHash,
+}
+
+impl ApproximateAggregator {
+    fn group_hash(&self, rec: &[DfValue]) -> ReadySetResult<GroupHash> {
+        let mut hasher = DefaultHasher::new();
+        for &col in self.group.iter() {
+            rec.get(col)
+                .ok_or(ReadySetError::InvalidRecordLength)?
+                .hash(&mut hasher)
+        }
+        Ok(hasher.finish())
+    }
+
+    /// Remove and return the sketches for a group, if we have any
+    fn take_group(&self, group_hash: GroupHash) -> Option<GroupSketches> {
+        let group = self.groups.borrow_mut().remove(&group_hash)?;
+        self.groups_size
+            .set(self.groups_size.get().saturating_sub(group.size_bytes()));
+        Some(group)
+    }
+
+    fn insert_group(&self, group_hash: GroupHash, group: GroupSketches) {
+        let mut size = self.groups_size.get() + group.size_bytes();
+        if let Some(old) = self.groups.borrow_mut().insert(group_hash, group) {
+            size = size.saturating_sub(old.size_bytes());
+        }
+        self.groups_size.set(size);
+    }
+}
+
+impl GroupedOperation for ApproximateAggregator {
+    type Diff = ApproximateDiff;
+
+    fn setup(&mut self, parent: &Node) -> ReadySetResult<()> {
+        invariant!(
+            self.over < parent.columns().len(),
+            "cannot aggregate over non-existing column"
+        );
+        Ok(())
+    }
+
+    fn group_by(&self) -> &[usize] {
+        &self.group[..]
+    }
+
+    fn to_diff(&self, r: &[DfValue], pos: bool) -> ReadySetResult<Self::Diff> {
+        Ok(ApproximateDiff {
+            value: r
+                .get(self.over)
+                .ok_or(ReadySetError::InvalidRecordLength)?
+                .clone(),
+            positive: pos,
+            group_hash: self.group_hash(r)?,
+        })
+    }
+
+    fn apply(
+        &self,
+        current: Option<&DfValue>,
+        diffs: &mut dyn Iterator<Item = Self::Diff>,
+    ) -> ReadySetResult<Option<DfValue>> {
+        let mut diffs = diffs.peekable();
+        let group_hash = diffs
+            .peek()
+            .ok_or_else(|| internal_err!("approximate aggregate got no diffs"))?
+            .group_hash;
+
+        let mut sketches = match (current, self.stage) {
+            (None, Stage::Combine) => vec![],
+            (None, _) => vec![self.op.new_sketch()],
+            (Some(current), Stage::Partial) => vec![Sketch::from_df_value(current)?],
+            (Some(current), Stage::Complete | Stage::Combine) => {
+                match self.take_group(group_hash) {
+                    Some(group) if group.last == *current => group.sketches,
+                    // if we don't have sketches that match our output, we need to rebuild them
+                    _ => return Ok(None),
+                }
+            }
+        };
+
+        for diff in diffs {
+            invariant_eq!(diff.group_hash, group_hash);
+            match (self.stage, diff.positive) {
+                (Stage::Combine, true) => sketches.push(Sketch::from_df_value(&diff.value)?),
+                (Stage::Combine, false) => {
+                    // A partial aggregate's sketch for the group has changed or been removed, so
+                    // drop the old one
+                    let old = Sketch::from_df_value(&diff.value)?;
+                    match sketches.iter().position(|s| *s == old) {
+                        Some(pos) => {
+                            sketches.swap_remove(pos);
+                        }
+                        None => return Ok(None),
+                    }
+                }
+                // Values can't be removed from a sketch, so it has to be rebuilt from scratch
+                (_, false) => return Ok(None),
+                (_, true) => {
+                    for sketch in sketches.iter_mut() {
+                        sketch.insert(&diff.value)?;
+                    }
+                }
+            }
+        }
+        for sketch in sketches.iter_mut() {
+            sketch.compress();
+        }
+
+        if self.stage == Stage::Partial {
+            return sketches
+                .first()
+                .ok_or_else(|| internal_err!("Partial approximate aggregate has no sketch"))?
+                .to_df_value()
+                .map(Some);
+        }
+        let estimate = match sketches.as_slice() {
+            [sketch] => sketch.estimate(&self.op),
+            _ => {
+                let mut merged = self.op.new_sketch();
+                for sketch in &sketches {
+                    merged.merge(sketch)?;
+                }
+                merged.compress();
+                merged.estimate(&self.op)
+            }
+        };
+        self.insert_group(
+            group_hash,
+            GroupSketches {
+                sketches,
+                last: estimate.clone(),
+            },
+        );
+        Ok(Some(estimate))
+    }
+
+    fn description(&self, detailed: bool) -> String {
+        let op_string = match self.op {
+            ApproximateAggregation::CountDistinct => "~|d|".to_owned(),
+            ApproximateAggregation::PercentileCont { fraction } => format!("~%{}", fraction),
+        };
+        let stage = match self.stage {
+            Stage::Complete => "",
+            Stage::Partial => " (partial)",
+            Stage::Combine => " (combine)",
+        };
+        if !detailed {
+            return format!("{}{}", op_string, stage);
+        }
+
+        let group_cols = self
+            .group
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{}({}){} γ[{}]", op_string, self.over, stage, group_cols)
+    }
+
+    fn over_column(&self) -> usize {
+        self.over
+    }
+
+    fn output_col_type(&self) -> DfType {
+        match (self.stage, self.op) {
+            (Stage::Partial, _) => DfType::Blob,
+            (_, ApproximateAggregation::CountDistinct) => DfType::BigInt,
+            (_, ApproximateAggregation::PercentileCont { .. }) => DfType::Double,
+        }
+    }
+
+    fn partial(&self) -> Self {
+        ApproximateAggregator {
+            stage: Stage::Partial,
+            groups: Default::default(),
+            groups_size: Default::default(),
+            ..self.clone()
+        }
+    }
+
+    fn combiner(&self) -> Option<Self> {
+        // The sketches of each partial aggregate are merged, and the estimate computed from the
+        // merged sketch
+        Some(ApproximateAggregator {
+            op: self.op,
+            over: self.group.len(),
+            group: (0..self.group.len()).collect(),
+            stage: Stage::Combine,
+            groups: Default::default(),
+            groups_size: Default::default(),
+        })
+    }
+
+    fn evict_groups(&mut self, keys: &[KeyComparison]) {
+        for key in keys {
+            match key {
+                KeyComparison::Equal(key) => {
+                    let mut hasher = DefaultHasher::new();
+                    for value in key.iter() {
+                        value.hash(&mut hasher);
+                    }
+                    self.take_group(hasher.finish());
+                }
+                // We only know the hashes of the groups we keep sketches for, so we can't tell
+                // which of them are in the range
+                KeyComparison::Range(_) => {
+                    self.groups.get_mut().clear();
+                    self.groups_size.set(0);
+                }
+            }
+        }
+    }
+
+    fn state_size(&self) -> u64 {
+        self.groups_size.get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ops, LookupIndex};
+
+    fn setup(op: ApproximateAggregation, mat: bool) -> ops::test::MockGraph {
+        let mut g = ops::test::MockGraph::new();
+        let s = g.add_base("source", &["x", "y"]);
+        g.set_op("agg", &["x", "ys"], op.over(s.as_global(), 1, &[0]), mat);
+        g
+    }
+
+    fn output(rs: Records) -> Vec<(bool, Vec<DfValue>)> {
+        rs.into_iter()
+            .map(|r| (r.is_positive(), r.rec()[..2].to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn hyperloglog_estimates() {
+        let mut hll = HyperLogLog::default();
+        for i in 0..3 {
+            hll.insert(&i.into());
+            hll.insert(&i.into());
+        }
+        assert_eq!(hll.estimate(), 3);
+
+        for i in 0..100_000 {
+            hll.insert(&i.into());
+        }
+        let estimate = hll.estimate() as f64;
+        assert!(
+            (estimate - 100_000.0).abs() < 100_000.0 * 0.05,
+            "{}",
+            estimate
+        );
+    }
+
+    #[test]
+    fn hyperloglog_is_sparse_for_small_sets() {
+        let mut hll = HyperLogLog::default();
+        for i in 0..100 {
+            hll.insert(&i.into());
+        }
+        assert!(matches!(hll.registers, Registers::Sparse(_)));
+        assert!(bincode::serialize(&hll).unwrap().len() < 1024);
+        assert_eq!(hll.estimate(), 100);
+
+        let mut dense = hll.clone();
+        dense.make_dense();
+        assert_eq!(dense.estimate(), hll.estimate());
+
+        for i in 0..10_000 {
+            hll.insert(&i.into());
+        }
+        assert!(matches!(hll.registers, Registers::Dense(_)));
+        assert_eq!(hll.size_bytes(), 1 << HLL_PRECISION);
+    }
+
+    #[test]
+    fn hyperloglog_merges() {
+        let mut a = HyperLogLog::default();
+        let mut b = HyperLogLog::default();
+        let mut all = HyperLogLog::default();
+        for i in 0..10_000 {
+            let v = DfValue::from(i);
+            if i % 3 == 0 {
+                a.insert(&v);
+            } else {
+                b.insert(&v);
+            }
+            all.insert(&v);
+        }
+        a.merge(&b);
+        assert_eq!(a, all);
+
+        // Merging sparse sketches into a dense one
+        let mut small = HyperLogLog::default();
+        small.insert(&DfValue::from(1_000_000));
+        all.insert(&DfValue::from(1_000_000));
+        a.merge(&small);
+        assert_eq!(a, all);
+    }
+
+    #[test]
+    fn tdigest_is_exact_for_small_sets() {
+        let mut digest = TDigest::default();
+        for v in [4.0, 1.0, 3.0, 2.0] {
+            digest.insert(v);
+        }
+        digest.compress();
+        assert_eq!(digest.quantile(0.0), Some(1.0));
+        assert_eq!(digest.quantile(0.5), Some(2.5));
+        assert_eq!(digest.quantile(0.75), Some(3.25));
+        assert_eq!(digest.quantile(1.0), Some(4.0));
+        assert_eq!(TDigest::default().quantile(0.5), None);
+    }
+
+    #[test]
+    fn tdigest_estimates_and_merges() {
+        let mut a = TDigest::default();
+        let mut b = TDigest::default();
+        for i in 0..10_000 {
+            if i % 2 == 0 {
+                a.insert(i as f64);
+            } else {
+                b.insert(i as f64);
+            }
+        }
+        a.compress();
+        b.compress();
+        assert!(a.centroids.len() < 1_000);
+        a.merge(&b);
+        a.compress();
+        for (q, expected) in [(0.01, 99.99), (0.5, 4999.5), (0.99, 9899.01)] {
+            let estimate = a.quantile(q).unwrap();
+            assert!((estimate - expected).abs() < 50.0, "{}: {}", q, estimate);
+        }
+    }
+
+    #[test]
+    fn it_describes() {
+        let agg = ApproximateAggregation::CountDistinct.over(0.into(), 1, &[2, 0]);
+        assert_eq!(agg.description(true), "~|d|(1) γ[2, 0]");
+        let combiner = agg.partial().combiner(1.into()).unwrap();
+        assert_eq!(combiner.description(true), "~|d|(2) (combine) γ[0, 1]");
+        assert_eq!(combiner.output_col_type(), DfType::BigInt);
+    }
+
+    #[test]
+    fn count_distinct_forwards() {
+        let mut c = setup(ApproximateAggregation::CountDistinct, true);
+
+        let rs = c.narrow_one_row(vec![1.into(), 1.into()], true);
+        assert_eq!(output(rs), vec![(true, vec![1.into(), 1.into()])]);
+
+        // Duplicate values don't change the estimate
+        let rs = c.narrow_one_row(vec![1.into(), 1.into()], true);
+        assert!(output(rs).iter().all(|(_, r)| r[1] == 1.into()));
+
+        let rs = c.narrow_one_row(vec![1.into(), 2.into()], true);
+        assert_eq!(
+            output(rs),
+            vec![
+                (false, vec![1.into(), 1.into()]),
+                (true, vec![1.into(), 2.into()])
+            ]
+        );
+
+        // Removing a value rebuilds the sketch from the remaining values in the group
+        let rs = c.narrow_one_row(vec![1.into(), 2.into()], false);
+        assert_eq!(
+            output(rs),
+            vec![
+                (false, vec![1.into(), 2.into()]),
+                (true, vec![1.into(), 1.into()])
+            ]
+        );
+    }
+
+    #[test]
+    fn percentile_forwards() {
+        let mut c = setup(
+            ApproximateAggregation::PercentileCont { fraction: 0.5 },
+            true,
+        );
+
+        c.narrow_one_row(vec![1.into(), 1.into()], true);
+        let rs = c.narrow_one_row(vec![1.into(), 2.into()], true);
+        assert_eq!(
+            output(rs),
+            vec![
+                (false, vec![1.into(), DfValue::Double(1.0)]),
+                (true, vec![1.into(), DfValue::Double(1.5)])
+            ]
+        );
+
+        // Nulls are ignored
+        let rs = c.narrow_one_row(vec![1.into(), DfValue::None], true);
+        assert!(output(rs).iter().all(|(_, r)| r[1] == DfValue::Double(1.5)));
+    }
+
+    #[test]
+    fn partials_are_combined() {
+        let op = ApproximateAggregation::CountDistinct.over(0.into(), 1, &[0]);
+        let partial = op.partial();
+        let combiner = partial.combiner(1.into()).unwrap();
+
+        let sketch = |values: &[i32]| {
+            let diffs = values
+                .iter()
+                .map(|&v| partial.inner.to_diff(&[1.into(), v.into()], true).unwrap())
+                .collect::<Vec<_>>();
+            partial
+                .inner
+                .apply(None, &mut diffs.into_iter())
+                .unwrap()
+                .unwrap()
+        };
+        let a = sketch(&[1, 2, 3]);
+        let b = sketch(&[3, 4]);
+        assert!(matches!(a, DfValue::ByteArray(_)));
+
+        let diffs = [a, b]
+            .into_iter()
+            .map(|s| combiner.inner.to_diff(&[1.into(), s], true).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            combiner.inner.apply(None, &mut diffs.into_iter()).unwrap(),
+            Some(4.into())
+        );
+    }
+
+    #[test]
+    fn combiner_replaces_partial_sketches() {
+        let op = ApproximateAggregation::CountDistinct.over(0.into(), 1, &[0]);
+        let partial = op.partial();
+        let combiner = partial.combiner(1.into()).unwrap();
+
+        let sketch = |values: &[i32]| {
+            let diffs = values
+                .iter()
+                .map(|&v| partial.inner.to_diff(&[1.into(), v.into()], true).unwrap())
+                .collect::<Vec<_>>();
+            partial
+                .inner
+                .apply(None, &mut diffs.into_iter())
+                .unwrap()
+                .unwrap()
+        };
+        let a = sketch(&[1, 2, 3]);
+        let b = sketch(&[3, 4]);
+        let new_a = sketch(&[1, 2, 3, 5]);
+        let diff = |s: &DfValue, pos| combiner.inner.to_diff(&[1.into(), s.clone()], pos).unwrap();
+
+        let current = combiner
+            .inner
+            .apply(None, &mut vec![diff(&a, true), diff(&b, true)].into_iter())
+            .unwrap()
+            .unwrap();
+        assert_eq!(current, 4.into());
+
+        // A changed sketch from one of the partials is swapped in, without rebuilding the group
+        assert_eq!(
+            combiner
+                .inner
+                .apply(
+                    Some(&current),
+                    &mut vec![diff(&a, false), diff(&new_a, true)].into_iter()
+                )
+                .unwrap(),
+            Some(5.into())
+        );
+    }
+
+    #[test]
+    fn sketches_are_counted_and_evicted() {
+        let mut op = ApproximateAggregation::CountDistinct
+            .over(0.into(), 1, &[0])
+            .inner;
+        assert_eq!(op.state_size(), 0);
+
+        for group in [1, 2] {
+            let diffs = (0..10)
+                .map(|v| op.to_diff(&[group.into(), v.into()], true).unwrap())
+                .collect::<Vec<_>>();
+            op.apply(None, &mut diffs.into_iter()).unwrap();
+        }
+        let size = op.state_size();
+        assert!(size > 0);
+        // Sparse sketches are much smaller than dense ones
+        assert!(size < 2 * (1 << HLL_PRECISION));
+
+        op.evict_groups(&[KeyComparison::Equal(vec1![1.into()])]);
+        assert!(op.state_size() < size);
+        assert_eq!(op.groups.borrow().len(), 1);
+
+        // The sketch for an evicted group has to be rebuilt
+        let diff = op.to_diff(&[1.into(), 10.into()], true).unwrap();
+        assert_eq!(
+            op.apply(Some(&10.into()), &mut std::iter::once(diff))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn it_suggests_indices() {
+        let me = 1.into();
+        let c = setup(ApproximateAggregation::CountDistinct, false);
+        let idx = c.node().suggest_indexes(me);
+        assert_eq!(idx.len(), 1);
+        assert_eq!(*idx.iter().next().unwrap().0, me);
+        assert_eq!(
+            *idx.iter().next().unwrap().1,
+            LookupIndex::Strict(Index::hash_map(vec![0]))
+        );
+    }
+}
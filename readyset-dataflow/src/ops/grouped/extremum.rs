@@ -1,3 +1,9 @@
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+
+use indexmap::IndexMap;
 use readyset_data::DfType;
 use readyset_errors::{invariant, ReadySetResult};
 use serde::{Deserialize, Serialize};
@@ -36,6 +42,7 @@ impl Extremum {
                 op: self,
                 over,
                 group: group_by.into(),
+                group_values: Default::default(),
             },
         )
     }
@@ -58,6 +65,99 @@ pub struct ExtremumOperator {
     op: Extremum,
     over: usize,
     group: Vec<usize>,
+    // The most extreme values of (up to MAX_BUFFERED_GROUPS) groups, so that removing the current
+    // extremum of a group can usually be handled without re-reading the whole group from our
+    // parent.
+    // We skip serde since we don't want the state, just the configuration.
+    #[serde(skip)]
+    group_values: RefCell<IndexMap<GroupHash, GroupValues>>,
+}
+
+type GroupHash = u64;
+
+/// The maximum number of distinct values buffered for each group in [`GroupValues`]
+const MAX_GROUP_VALUES: usize = 16;
+
+/// The maximum number of groups to buffer [`GroupValues`] for. Once this many groups are buffered,
+/// an arbitrary group's values are discarded to make room for each new group, which only means that
+/// removing that group's extremum falls back to re-reading the group.
+const MAX_BUFFERED_GROUPS: usize = 1 << 14;
+
+/// The (up to [`MAX_GROUP_VALUES`]) most extreme values in a group, along with the number of times
+/// each of them occurs.
+///
+/// Every value in the group that is at least as extreme as the least extreme buffered value is
+/// buffered, so as long as there are buffered values left, the most extreme of them is the extremum
+/// of the group.
+#[derive(Debug, Clone, Default)]
+struct GroupValues {
+    values: BTreeMap<DfValue, usize>,
+    /// If true, values in the group that are less extreme than all the buffered values may have
+    /// been discarded
+    truncated: bool,
+}
+
+impl GroupValues {
+    /// Construct a new [`GroupValues`] for a group whose current extremum is `current`, if any.
+    ///
+    /// We don't know about any of the other values in the group, so only the current extremum is
+    /// buffered.
+    fn new(current: Option<&DfValue>) -> Self {
+        match current {
+            Some(current) => Self {
+                values: BTreeMap::from([(current.clone(), 1)]),
+                truncated: true,
+            },
+            None => Self::default(),
+        }
+    }
+
+    fn most_extreme(&self, op: &Extremum) -> Option<&DfValue> {
+        match op {
+            Extremum::Min => self.values.keys().next(),
+            Extremum::Max => self.values.keys().next_back(),
+        }
+    }
+
+    fn least_extreme(&self, op: &Extremum) -> Option<&DfValue> {
+        match op {
+            Extremum::Min => self.values.keys().next_back(),
+            Extremum::Max => self.values.keys().next(),
+        }
+    }
+
+    fn insert(&mut self, op: &Extremum, value: DfValue) {
+        if self.truncated {
+            // If values may have been discarded, we can only buffer values which are at least as
+            // extreme as all the discarded values
+            let buffer = match (self.least_extreme(op), op) {
+                (Some(least), Extremum::Min) => value <= *least,
+                (Some(least), Extremum::Max) => value >= *least,
+                (None, _) => false,
+            };
+            if !buffer {
+                return;
+            }
+        }
+
+        *self.values.entry(value).or_default() += 1;
+        if self.values.len() > MAX_GROUP_VALUES {
+            let least = self.least_extreme(op).cloned();
+            if let Some(least) = least {
+                self.values.remove(&least);
+            }
+            self.truncated = true;
+        }
+    }
+
+    fn remove(&mut self, value: &DfValue) {
+        if let Some(count) = self.values.get_mut(value) {
+            *count -= 1;
+            if *count == 0 {
+                self.values.remove(value);
+            }
+        }
+    }
 }
 
 pub enum DiffType {
@@ -66,8 +166,19 @@ pub enum DiffType {
     None,
 }
 
+impl ExtremumOperator {
+    fn group_hash(&self, rec: &[DfValue]) -> GroupHash {
+        let mut hasher = DefaultHasher::new();
+        for &col in self.group.iter() {
+            #[allow(clippy::indexing_slicing)] // Group columns always exist in our input
+            rec[col].hash(&mut hasher)
+        }
+        hasher.finish()
+    }
+}
+
 impl GroupedOperation for ExtremumOperator {
-    type Diff = DiffType;
+    type Diff = (GroupHash, DiffType);
 
     fn setup(&mut self, parent: &Node) -> ReadySetResult<()> {
         invariant!(
@@ -84,13 +195,14 @@ impl GroupedOperation for ExtremumOperator {
     fn to_diff(&self, r: &[DfValue], pos: bool) -> ReadySetResult<Self::Diff> {
         #[allow(clippy::indexing_slicing)] // Invariant documented.
         let v = &r[self.over];
-        if let DfValue::None = *v {
-            Ok(DiffType::None)
+        let diff = if let DfValue::None = *v {
+            DiffType::None
         } else if pos {
-            Ok(DiffType::Insert(v.clone()))
+            DiffType::Insert(v.clone())
         } else {
-            Ok(DiffType::Remove(v.clone()))
-        }
+            DiffType::Remove(v.clone())
+        };
+        Ok((self.group_hash(r), diff))
     }
 
     fn apply(
@@ -98,41 +210,40 @@ impl GroupedOperation for ExtremumOperator {
         current: Option<&DfValue>,
         diffs: &mut dyn Iterator<Item = Self::Diff>,
     ) -> ReadySetResult<Option<DfValue>> {
-        // Extreme values are those that are at least as extreme as the current min/max (if any).
-        // let mut is_extreme_value : Box<dyn Fn(i64) -> bool> = Box::new(|_|true);
-        let mut extreme_values: Vec<DfValue> = vec![];
-        if let Some(d) = current {
-            extreme_values.push(d.clone());
-        }
-
-        let is_extreme_value = |x: &DfValue| {
-            if let Some(n) = current {
-                match self.op {
-                    Extremum::Max => x >= n,
-                    Extremum::Min => x <= n,
-                }
-            } else {
-                true
-            }
+        let mut diffs = diffs.peekable();
+        let group_hash = match diffs.peek() {
+            Some((group_hash, _)) => *group_hash,
+            None => return Ok(current.cloned()),
         };
 
-        for d in diffs {
-            match d {
-                DiffType::Insert(v) if is_extreme_value(&v) => extreme_values.push(v),
-                DiffType::Remove(v) if is_extreme_value(&v) => {
-                    if let Some(i) = extreme_values.iter().position(|x| *x == v) {
-                        extreme_values.swap_remove(i);
-                    }
-                }
-                _ => {}
-            };
+        let mut group_values = self.group_values.borrow_mut();
+        if group_values.len() >= MAX_BUFFERED_GROUPS && !group_values.contains_key(&group_hash) {
+            group_values.swap_remove_index(0);
+        }
+        let values = group_values
+            .entry(group_hash)
+            .or_insert_with(|| GroupValues::new(current));
+        if values.most_extreme(&self.op) != current {
+            // Either the group is new (or is being recomputed from scratch), or we've lost track
+            // of its values - either way, all we can trust is the current extremum
+            *values = GroupValues::new(current);
         }
 
-        let extreme = match self.op {
-            Extremum::Min => extreme_values.into_iter().min(),
-            Extremum::Max => extreme_values.into_iter().max(),
-        };
+        for (_, d) in diffs {
+            match d {
+                DiffType::Insert(v) => values.insert(&self.op, v),
+                DiffType::Remove(v) => values.remove(&v),
+                DiffType::None => {}
+            }
+        }
 
+        let extreme = values.most_extreme(&self.op).cloned();
+        if extreme.is_none() {
+            // If we ran out of buffered values, we need to re-read the group from our parent
+            // (which will start the group afresh), and if the group is now empty we don't need
+            // to remember anything about it
+            group_values.swap_remove(&group_hash);
+        }
         Ok(extreme)
     }
 
@@ -165,6 +276,16 @@ impl GroupedOperation for ExtremumOperator {
         // Type of extremum relies on col type.
         DfType::Unknown
     }
+
+    fn combiner(&self) -> Option<Self> {
+        // The extreme value of all the partial extreme values is the extreme value overall
+        Some(ExtremumOperator {
+            op: self.op.clone(),
+            over: self.group.len(),
+            group: (0..self.group.len()).collect(),
+            group_values: Default::default(),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -375,6 +496,72 @@ mod tests {
         assert_eq!(c.node().resolve(1), None);
     }
 
+    #[test]
+    fn removing_extremum_uses_buffered_values() {
+        let op = ExtremumOperator {
+            op: Extremum::Max,
+            over: 1,
+            group: vec![0],
+            group_values: Default::default(),
+        };
+        let apply = |current: Option<i32>, values: &[(i32, bool)]| {
+            let current = current.map(DfValue::from);
+            let diffs = values
+                .iter()
+                .map(|&(v, pos)| op.to_diff(&[1.into(), v.into()], pos).unwrap())
+                .collect::<Vec<_>>();
+            op.apply(current.as_ref(), &mut diffs.into_iter()).unwrap()
+        };
+
+        assert_eq!(
+            apply(None, &[(1, true), (5, true), (3, true)]),
+            Some(5.into())
+        );
+        // Removing the maximum falls back to the next largest buffered value
+        assert_eq!(apply(Some(5), &[(5, false)]), Some(3.into()));
+        assert_eq!(apply(Some(3), &[(3, false)]), Some(1.into()));
+        // Once all the buffered values are gone, we have to re-read the group
+        assert_eq!(apply(Some(1), &[(1, false)]), None);
+
+        // Only the largest values are buffered
+        let many = (0..(MAX_GROUP_VALUES as i32 + 4))
+            .map(|v| (v, true))
+            .collect::<Vec<_>>();
+        let max = MAX_GROUP_VALUES as i32 + 3;
+        assert_eq!(apply(None, &many), Some(max.into()));
+        let removed = (4..=max).map(|v| (v, false)).collect::<Vec<_>>();
+        assert_eq!(apply(Some(max), &removed), None);
+
+        // Without buffered values (eg after a restart) we only know about the current value
+        let op = ExtremumOperator {
+            group_values: Default::default(),
+            ..op.clone()
+        };
+        let diffs = vec![op.to_diff(&[1.into(), 7.into()], false).unwrap()];
+        assert_eq!(
+            op.apply(Some(&7.into()), &mut diffs.into_iter()).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn buffered_groups_are_bounded() {
+        let op = ExtremumOperator {
+            op: Extremum::Min,
+            over: 1,
+            group: vec![0],
+            group_values: Default::default(),
+        };
+        for group in 0..(MAX_BUFFERED_GROUPS + 10) {
+            let diffs = vec![op.to_diff(&[group.into(), 1.into()], true).unwrap()];
+            assert_eq!(
+                op.apply(None, &mut diffs.into_iter()).unwrap(),
+                Some(1.into())
+            );
+        }
+        assert_eq!(op.group_values.borrow().len(), MAX_BUFFERED_GROUPS);
+    }
+
     #[test]
     fn it_works_with_varying_types() {
         let mut c = setup(Extremum::Max, true);
@@ -5,6 +5,7 @@ use std::fmt;
 
 use dataflow_state::PointKey;
 use maplit::hashmap;
+use readyset_client::KeyComparison;
 use readyset_data::DfType;
 use readyset_errors::{internal_err, ReadySetResult};
 use serde::{Deserialize, Serialize};
@@ -14,6 +15,7 @@ use crate::processing::{ColumnSource, IngredientLookupResult, LookupIndex, Looku
 
 // pub mod latest;
 pub mod aggregate;
+pub mod approximate;
 pub mod concat;
 pub mod extremum;
 
@@ -92,6 +94,35 @@ pub trait GroupedOperation: fmt::Debug + Clone {
     fn emit_empty(&self) -> bool {
         false
     }
+
+    /// Returns an operation which combines the outputs of several copies of this operation, each
+    /// run over a disjoint subset of the input (such as a single shard of a sharded input), into
+    /// the output of this operation over the whole input - or [`None`] if this operation can't be
+    /// split up that way.
+    ///
+    /// The returned operation runs over the output of this operation, so it must group by the
+    /// leading group columns and aggregate over the last column.
+    fn combiner(&self) -> Option<Self> {
+        None
+    }
+
+    /// Returns the operation to run over each disjoint subset of the input, whose outputs are
+    /// combined by the operation returned by [`combiner`](Self::combiner). Most operations are
+    /// split up by running copies of themselves, which is the default.
+    fn partial(&self) -> Self {
+        self.clone()
+    }
+
+    /// Called when the groups with the given keys (the values of the group by columns, in order)
+    /// have been evicted from the operator's materialization, to allow the operation to evict any
+    /// state it keeps for those groups.
+    fn evict_groups(&mut self, _keys: &[KeyComparison]) {}
+
+    /// Returns the size in bytes of any state the operation keeps for its groups, other than the
+    /// operator's materialization.
+    fn state_size(&self) -> u64 {
+        0
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -130,6 +161,25 @@ impl<T: GroupedOperation> GroupedOperator<T> {
     pub fn output_col_type(&self) -> DfType {
         self.inner.output_col_type()
     }
+
+    /// Construct an operator over `partials`, a node containing the outputs of copies of this
+    /// operator each run over a disjoint subset of its input, which combines those outputs into
+    /// the output of this operator. See [`GroupedOperation::combiner`].
+    pub fn combiner(&self, partials: NodeIndex) -> Option<GroupedOperator<T>> {
+        self.inner
+            .combiner()
+            .map(|inner| GroupedOperator::new(partials, inner))
+    }
+
+    /// Construct the operator to run over each disjoint subset of the input of this operator,
+    /// whose outputs are combined by the operator returned by [`combiner`](Self::combiner). See
+    /// [`GroupedOperation::partial`].
+    pub fn partial(&self) -> GroupedOperator<T> {
+        GroupedOperator {
+            inner: self.inner.partial(),
+            ..self.clone()
+        }
+    }
 }
 
 /// Extract a copy of all values in the record being targeted by the group
@@ -433,6 +483,14 @@ where
         self.inner.description(detailed)
     }
 
+    fn on_state_eviction(&mut self, keys: &[KeyComparison]) {
+        self.inner.evict_groups(keys)
+    }
+
+    fn auxiliary_state_size(&self) -> u64 {
+        self.inner.state_size()
+    }
+
     fn is_selective(&self) -> bool {
         true
     }
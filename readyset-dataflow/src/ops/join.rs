@@ -2,6 +2,7 @@ use std::collections::{HashMap, HashSet};
 use std::convert::{TryFrom, TryInto};
 
 use dataflow_state::PointKey;
+use indexmap::IndexMap;
 use itertools::Itertools;
 use maplit::hashmap;
 use readyset_client::KeyComparison;
@@ -24,6 +25,18 @@ pub enum JoinType {
     Left,
     /// Inner join between two views
     Inner,
+    /// Anti-join between two views: emits the rows in the left which have *no* matching rows in
+    /// the right. Anti-joins must only emit columns from the left.
+    Anti,
+}
+
+impl JoinType {
+    /// Returns true if this join emits rows from the left for which there are no matching rows in
+    /// the right, and so has to keep track of when the number of matching rows in the right
+    /// changes to or from zero
+    fn emits_unmatched(&self) -> bool {
+        matches!(self, JoinType::Left | JoinType::Anti)
+    }
 }
 
 /// Where to source a join column
@@ -37,7 +50,7 @@ pub enum JoinSource {
     B(usize, usize),
 }
 
-/// Join provides a left outer join between two views.
+/// Join provides an inner, left outer, or anti-join between two views.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Join {
     left: IndexPair,
@@ -194,6 +207,18 @@ impl Join {
             .collect()
     }
 
+    /// Returns the columns in the left and right parents (by global node index) that output
+    /// column `col` is a copy of. Unlike [`Ingredient::column_source`], which only ever resolves
+    /// columns to a single parent, columns that are part of the join key resolve to the key column
+    /// in *both* parents.
+    pub fn parent_columns(&self, col: usize) -> Vec<(NodeIndex, usize)> {
+        let (left, right) = self.resolve_col(col);
+        left.map(|c| (self.left.as_global(), c))
+            .into_iter()
+            .chain(right.map(|c| (self.right.as_global(), c)))
+            .collect()
+    }
+
     fn resolve_col(&self, col: usize) -> (Option<usize>, Option<usize>) {
         let (side, pcol) = self.emit[col];
 
@@ -354,9 +379,17 @@ impl Ingredient for Join {
 
         let mut ret: Vec<Record> = Vec::with_capacity(rs.len());
 
-        let grouped_records = rs
-            .into_iter()
-            .group_by(|rec| from_key.iter().map(|i| rec[*i].clone()).collect::<Vec<_>>());
+        // Group the records by join key across the whole batch, rather than just runs of
+        // consecutive records, since the parent's state already reflects every record in the
+        // batch - for a left join, a key's records from the right have to be processed together to
+        // see whether the number of rows in the right for that key changed to or from zero.
+        let mut grouped_records: IndexMap<Vec<DfValue>, Vec<Record>> = IndexMap::new();
+        for rec in rs {
+            grouped_records
+                .entry(from_key.iter().map(|i| rec[*i].clone()).collect())
+                .or_default()
+                .push(rec);
+        }
 
         let is_replay = replay_key_cols.is_some();
 
@@ -382,11 +415,13 @@ impl Ingredient for Join {
             let nulls = join_key.iter().any(|v| v.is_none());
 
             // The difference between a left join and an inner join, is that for the former we must
-            // emit rows with nulls even if we later get no match in the other side.
+            // emit rows with nulls even if we later get no match in the other side. An anti-join
+            // emits *only* those rows (without the nulls, since it only emits columns from the
+            // left).
 
             let mut new_right_count = None;
 
-            if self.kind == JoinType::Left && !from_left {
+            if self.kind.emits_unmatched() && !from_left {
                 let rc = self.lookup(
                     *self.right,
                     &self.on_right(),
@@ -442,7 +477,7 @@ impl Ingredient for Join {
             let other_records = match other_lookup.take() {
                 IngredientLookupResult::Records(recs) => recs,
                 IngredientLookupResult::Miss => {
-                    misses.extend(group.map(|record| {
+                    misses.extend(group.into_iter().map(|record| {
                         Miss::builder()
                             .on(other)
                             .lookup_idx(other_key.clone())
@@ -475,11 +510,11 @@ impl Ingredient for Join {
                 rc_diff += if positive { 1 } else { -1 };
 
                 if other_rows.is_empty() {
-                    if self.kind == JoinType::Left && from_left {
+                    if self.kind.emits_unmatched() && from_left {
                         // left join, got a thing from left, no rows in right == NULL
                         ret.push((self.generate_null(&row), positive).into());
                     }
-                } else {
+                } else if self.kind != JoinType::Anti {
                     for other in other_rows.iter() {
                         if from == *self.left {
                             ret.push((self.generate_row(&row, other), positive).into());
@@ -490,8 +525,8 @@ impl Ingredient for Join {
                 }
             }
 
-            // For a left join with updates from the right side, we also have to emit/delete NULL
-            // rows if row count changed to/from zero
+            // For a left join or anti-join with updates from the right side, we also have to
+            // emit/delete NULL rows if row count changed to/from zero
             if let Some(new_rc) = new_right_count {
                 let old_rc = new_rc as isize - rc_diff;
                 if new_rc == 0 && old_rc != 0 {
@@ -529,6 +564,7 @@ impl Ingredient for Join {
             return String::from(match self.kind {
                 JoinType::Left => "⋉",
                 JoinType::Inner => "⋈",
+                JoinType::Anti => "▷",
             });
         }
 
@@ -548,6 +584,7 @@ impl Ingredient for Join {
         let op = match self.kind {
             JoinType::Left => "⋉",
             JoinType::Inner => "⋈",
+            JoinType::Anti => "▷",
         };
 
         format!(
@@ -763,6 +800,23 @@ mod tests {
         (g, l, r)
     }
 
+    fn setup_anti() -> (ops::test::MockGraph, IndexPair, IndexPair) {
+        let mut g = ops::test::MockGraph::new();
+        let l = g.add_base("left", &["l0", "l1"]);
+        let r = g.add_base("right", &["r0", "r1"]);
+
+        use self::JoinSource::*;
+        let j = Join::new(
+            l.as_global(),
+            r.as_global(),
+            JoinType::Anti,
+            vec![B(0, 0), L(1)],
+        );
+
+        g.set_op("anti_join", &["j0", "j1"], j, false);
+        (g, l, r)
+    }
+
     #[test]
     fn it_describes() {
         let (j, l, r) = setup();
@@ -944,6 +998,53 @@ mod tests {
         assert_eq!(rs, Records::default());
     }
 
+    #[test]
+    fn anti_join() {
+        let (mut j, l, r) = setup_anti();
+        let l_a1 = vec![1.into(), "a".try_into().unwrap()];
+        let l_b2 = vec![2.into(), "b".try_into().unwrap()];
+        let r_x1 = vec![1.into(), "x".try_into().unwrap()];
+        let r_y1 = vec![1.into(), "y".try_into().unwrap()];
+
+        j.seed(r, r_x1.clone());
+        j.one_row(r, r_x1.clone(), false);
+
+        // rows from the left are only emitted if they have no match in the right
+        j.seed(l, l_a1.clone());
+        assert_eq!(j.one_row(l, l_a1.clone(), false), Records::default());
+        j.seed(l, l_b2.clone());
+        assert_eq!(j.one_row(l, l_b2.clone(), false), vec![l_b2].into());
+
+        // a second match in the right doesn't change anything
+        j.seed(r, r_y1.clone());
+        assert_eq!(j.one_row(r, r_y1.clone(), false), Records::default());
+
+        // removing the last match in the right emits the row from the left
+        j.unseed(r);
+        assert_eq!(
+            j.one(r, vec![(r_x1.clone(), false), (r_y1.clone(), false)], false),
+            vec![l_a1.clone()].into()
+        );
+
+        // and adding one back retracts it
+        j.seed(r, r_x1.clone());
+        assert_eq!(j.one_row(r, r_x1, false), vec![(l_a1, false)].into());
+    }
+
+    #[test]
+    fn anti_join_null_keys() {
+        let (mut j, l, r) = setup_anti();
+
+        let r_nullx = vec![DfValue::None, "x".try_into().unwrap()];
+        j.seed(r, r_nullx.clone());
+        assert_eq!(j.one_row(r, r_nullx, false), Records::default());
+
+        // NULL never matches anything, including NULL
+        let l_nulla = vec![DfValue::None, "a".try_into().unwrap()];
+        j.seed(l, l_nulla.clone());
+        assert_eq!(j.one_row(l, l_nulla.clone(), false), vec![l_nulla].into());
+    }
+
     #[test]
     fn it_suggests_indices() {
         let me = 2.into();
@@ -962,6 +1063,21 @@ mod tests {
         assert_eq!(res, vec![(l.as_global(), Some(0))]);
     }
 
+    #[test]
+    fn join_key_columns_resolve_to_both_parents() {
+        let (g, l, r) = setup();
+        let join = match g.node().as_internal() {
+            Some(NodeOperator::Join(join)) => join,
+            _ => unreachable!("node is a join"),
+        };
+        assert_eq!(
+            join.parent_columns(0),
+            vec![(l.as_global(), 0), (r.as_global(), 0)]
+        );
+        assert_eq!(join.parent_columns(1), vec![(l.as_global(), 1)]);
+        assert_eq!(join.parent_columns(2), vec![(r.as_global(), 1)]);
+    }
+
     mod handle_upquery {
         use std::ops::Bound;
 
@@ -1247,4 +1363,163 @@ mod tests {
             );
         }
     }
+
+    /// A brute-force model of left joins and anti-joins, and property tests showing that the join
+    /// operator's output always matches it
+    mod join_model {
+        use proptest::prelude::*;
+        use test_strategy::{proptest, Arbitrary};
+
+        use super::*;
+
+        #[derive(Debug, Clone, Arbitrary)]
+        struct Op {
+            /// The join key, where `None` is NULL
+            #[strategy(prop::option::of(0..3i32))]
+            key: Option<i32>,
+            #[strategy(0..2i32)]
+            value: i32,
+            positive: bool,
+        }
+
+        #[derive(Debug, Clone, Arbitrary)]
+        struct Batch {
+            from_left: bool,
+            #[strategy(prop::collection::vec(any::<Op>(), 1..8))]
+            ops: Vec<Op>,
+        }
+
+        type Bag = HashMap<Vec<DfValue>, isize>;
+
+        fn update(bag: &mut Bag, row: Vec<DfValue>, diff: isize) {
+            *bag.entry(row).or_default() += diff;
+        }
+
+        /// Compute the result of left joining (or, if `anti`, anti-joining) `left` to `right` from
+        /// scratch
+        fn join(left: &Bag, right: &Bag, anti: bool) -> Bag {
+            let mut res = Bag::new();
+            for (l, &l_count) in left {
+                let matches = right
+                    .iter()
+                    .filter(|(r, _)| !l[0].is_none() && r[0] == l[0])
+                    .collect::<Vec<_>>();
+                if matches.iter().all(|(_, &r_count)| r_count == 0) {
+                    let row = if anti {
+                        l.clone()
+                    } else {
+                        vec![l[0].clone(), l[1].clone(), DfValue::None]
+                    };
+                    update(&mut res, row, l_count);
+                }
+                if anti {
+                    continue;
+                }
+                for (r, &r_count) in matches {
+                    update(
+                        &mut res,
+                        vec![l[0].clone(), l[1].clone(), r[1].clone()],
+                        l_count * r_count,
+                    );
+                }
+            }
+            res.retain(|_, count| *count != 0);
+            res
+        }
+
+        fn check_matches_model(anti: bool, batches: Vec<Batch>) {
+            let (mut j, l, r) = if anti { setup_anti() } else { setup() };
+            let mut left = Bag::new();
+            let mut right = Bag::new();
+            let mut output = Bag::new();
+
+            for batch in batches {
+                let (parent, rows) = if batch.from_left {
+                    (l, &mut left)
+                } else {
+                    (r, &mut right)
+                };
+
+                let mut records = Vec::new();
+                for op in batch.ops {
+                    let row = vec![op.key.map_or(DfValue::None, DfValue::from), op.value.into()];
+                    // Only delete rows which exist
+                    if !op.positive && rows.get(&row).copied().unwrap_or(0) == 0 {
+                        continue;
+                    }
+                    update(rows, row.clone(), if op.positive { 1 } else { -1 });
+                    records.push(Record::from((row, op.positive)));
+                }
+                let records = Records::from(records);
+
+                // The parent's state reflects the whole batch before the join sees it
+                j.states
+                    .get_mut(*parent)
+                    .unwrap()
+                    .process_records(&mut records.clone(), None, None)
+                    .unwrap();
+                for rec in j.one(parent, records, false) {
+                    let (row, positive) = rec.extract();
+                    update(&mut output, row, if positive { 1 } else { -1 });
+                }
+
+                output.retain(|_, count| *count != 0);
+                assert_eq!(output, join(&left, &right, anti));
+            }
+        }
+
+        #[proptest]
+        fn left_join_matches_model(
+            #[strategy(prop::collection::vec(any::<Batch>(), 1..12))] batches: Vec<Batch>,
+        ) {
+            check_matches_model(false, batches);
+        }
+
+        #[proptest]
+        fn anti_join_matches_model(
+            #[strategy(prop::collection::vec(any::<Batch>(), 1..12))] batches: Vec<Batch>,
+        ) {
+            check_matches_model(true, batches);
+        }
+
+        #[test]
+        fn interleaved_keys_from_right() {
+            let (mut j, l, r) = setup();
+            let l_a1 = vec![1.into(), "a".try_into().unwrap()];
+            j.seed(l, l_a1.clone());
+            j.one_row(l, l_a1, false);
+
+            // The first and last records in the batch have the same key, but aren't consecutive
+            let records: Records = vec![
+                (vec![1.into(), "x".try_into().unwrap()], true),
+                (vec![2.into(), "y".try_into().unwrap()], true),
+                (vec![1.into(), "z".try_into().unwrap()], true),
+            ]
+            .into();
+            j.states
+                .get_mut(*r)
+                .unwrap()
+                .process_records(&mut records.clone(), None, None)
+                .unwrap();
+            let rs = j.one(r, records, false);
+            assert_eq!(
+                rs,
+                vec![
+                    (
+                        vec![1.into(), "a".try_into().unwrap(), "x".try_into().unwrap()],
+                        true
+                    ),
+                    (
+                        vec![1.into(), "a".try_into().unwrap(), "z".try_into().unwrap()],
+                        true
+                    ),
+                    (
+                        vec![1.into(), "a".try_into().unwrap(), DfValue::None],
+                        false
+                    ),
+                ]
+                .into()
+            );
+        }
+    }
 }
@@ -47,6 +47,7 @@ pub enum NodeOperator {
     // Aggregation supports both filtered and normal Aggregations
     Aggregation(grouped::GroupedOperator<grouped::aggregate::Aggregator>),
     Extremum(grouped::GroupedOperator<grouped::extremum::ExtremumOperator>),
+    Approximate(grouped::GroupedOperator<grouped::approximate::ApproximateAggregator>),
     Concat(grouped::GroupedOperator<GroupConcat>),
     Join(join::Join),
     Latest(latest::Latest),
@@ -63,6 +64,7 @@ impl ToString for NodeOperator {
         match *self {
             NodeOperator::Aggregation(_) => "Aggregation",
             NodeOperator::Extremum(_) => "Extermum",
+            NodeOperator::Approximate(_) => "Approximate",
             NodeOperator::Concat(_) => "Concat",
             NodeOperator::Join(_) => "Join",
             NodeOperator::Latest(_) => "Latest",
@@ -82,6 +84,7 @@ macro_rules! impl_ingredient_fn_mut {
         match *$self {
             NodeOperator::Aggregation(ref mut i) => i.$fn($($arg),*),
             NodeOperator::Extremum(ref mut i) => i.$fn($($arg),*),
+            NodeOperator::Approximate(ref mut i) => i.$fn($($arg),*),
             NodeOperator::Concat(ref mut i) => i.$fn($($arg),*),
             NodeOperator::Join(ref mut i) => i.$fn($($arg),*),
             NodeOperator::Latest(ref mut i) => i.$fn($($arg),*),
@@ -95,11 +98,41 @@ macro_rules! impl_ingredient_fn_mut {
     }
 }
 
+impl NodeOperator {
+    /// If this operator is an aggregate that can be computed by first aggregating disjoint subsets
+    /// of its input separately (such as each shard of a sharded input), construct the operator
+    /// that combines those partial results, which are read from the node `partials`.
+    ///
+    /// Partial results are produced by the operator returned by [`partial`](Self::partial) (usually
+    /// a copy of this operator), and are combined by the returned operator into the same output as
+    /// this operator would produce over its entire input.
+    pub fn combiner(&self, partials: NodeIndex) -> Option<NodeOperator> {
+        match self {
+            NodeOperator::Aggregation(op) => op.combiner(partials).map(Into::into),
+            NodeOperator::Extremum(op) => op.combiner(partials).map(Into::into),
+            NodeOperator::Approximate(op) => op.combiner(partials).map(Into::into),
+            _ => None,
+        }
+    }
+
+    /// Construct the operator which computes each of the partial results combined by the operator
+    /// returned by [`combiner`](Self::combiner), over the same input as this operator.
+    pub fn partial(&self) -> NodeOperator {
+        match self {
+            NodeOperator::Aggregation(op) => op.partial().into(),
+            NodeOperator::Extremum(op) => op.partial().into(),
+            NodeOperator::Approximate(op) => op.partial().into(),
+            op => op.clone(),
+        }
+    }
+}
+
 macro_rules! impl_ingredient_fn_ref {
     ($self:ident, $fn:ident, $( $arg:ident ),* ) => {
         match *$self {
             NodeOperator::Aggregation(ref i) => i.$fn($($arg),*),
             NodeOperator::Extremum(ref i) => i.$fn($($arg),*),
+            NodeOperator::Approximate(ref i) => i.$fn($($arg),*),
             NodeOperator::Concat(ref i) => i.$fn($($arg),*),
             NodeOperator::Join(ref i) => i.$fn($($arg),*),
             NodeOperator::Latest(ref i) => i.$fn($($arg),*),
@@ -173,6 +206,12 @@ impl Ingredient for NodeOperator {
     fn on_eviction(&mut self, from: LocalNodeIndex, tag: Tag, keys: &[KeyComparison]) {
         impl_ingredient_fn_mut!(self, on_eviction, from, tag, keys)
     }
+    fn on_state_eviction(&mut self, keys: &[KeyComparison]) {
+        impl_ingredient_fn_mut!(self, on_state_eviction, keys)
+    }
+    fn auxiliary_state_size(&self) -> u64 {
+        impl_ingredient_fn_ref!(self, auxiliary_state_size,)
+    }
     fn can_query_through(&self) -> bool {
         impl_ingredient_fn_ref!(self, can_query_through,)
     }
@@ -444,6 +483,7 @@ pub mod test {
                     None,
                     tag,
                     self.states.get_mut(*self.nut.unwrap()),
+                    None,
                 )
                 .unwrap();
             }
@@ -249,7 +249,7 @@ impl Ingredient for Project {
 #[cfg(test)]
 mod tests {
     use dataflow_expression::utils::{make_int_column, make_literal};
-    use dataflow_expression::BinaryOperator;
+    use dataflow_expression::{BinaryOperator, Dialect};
     use dataflow_state::MaterializedNodeState;
     use readyset_data::DfType;
     use Expr::Op;
@@ -301,6 +301,8 @@ mod tests {
             right: Box::new(make_int_column(1)),
             op,
             ty: DfType::Int,
+            dialect: Dialect::DEFAULT_MYSQL,
+            non_null_operands: false,
         };
 
         setup_arithmetic(expression)
@@ -444,6 +446,8 @@ mod tests {
             right: Box::new(make_literal(number)),
             op: BinaryOperator::Multiply,
             ty: DfType::Int,
+            dialect: Dialect::DEFAULT_MYSQL,
+            non_null_operands: false,
         };
 
         let mut p = setup_arithmetic(expression);
@@ -463,6 +467,8 @@ mod tests {
             right: Box::new(make_literal(b)),
             op: BinaryOperator::Divide,
             ty: DfType::Int,
+            dialect: Dialect::DEFAULT_MYSQL,
+            non_null_operands: false,
         };
 
         let mut p = setup_arithmetic(expression);
@@ -591,6 +597,8 @@ mod tests {
             right: Box::new(make_int_column(1)),
             op: BinaryOperator::Add,
             ty: DfType::Int,
+            dialect: Dialect::DEFAULT_MYSQL,
+            non_null_operands: false,
         }]);
 
         let state = MaterializedNodeState::Memory(MemoryState::default());
@@ -607,6 +615,8 @@ mod tests {
             right: Box::new(make_int_column(1)),
             op: BinaryOperator::Add,
             ty: DfType::Int,
+            dialect: Dialect::DEFAULT_MYSQL,
+            non_null_operands: false,
         }]);
 
         let state = MaterializedNodeState::Persistent(PersistentState::new(
@@ -629,9 +639,13 @@ mod tests {
                 right: Box::new(make_int_column(1)),
                 op: BinaryOperator::Add,
                 ty: DfType::Int,
+                dialect: Dialect::DEFAULT_MYSQL,
+                non_null_operands: false,
             }),
             right: Box::new(make_literal(DfValue::Int(2))),
             ty: DfType::Int,
+            dialect: Dialect::DEFAULT_MYSQL,
+            non_null_operands: false,
         };
 
         let state = MaterializedNodeState::Persistent(PersistentState::new(
@@ -288,6 +288,14 @@ impl Union {
     pub fn is_shard_merger(&self) -> bool {
         matches!(self.emit, Emit::AllFrom(..))
     }
+
+    /// If this union is a shard merger, returns the number of shards it merges
+    pub fn merged_shards(&self) -> Option<usize> {
+        match self.emit {
+            Emit::AllFrom(_, ref sharding) => sharding.shards(),
+            Emit::Project { .. } => None,
+        }
+    }
 }
 
 impl Ingredient for Union {
@@ -1,13 +1,19 @@
 use std::collections::HashSet;
 use std::fmt::{self, Display};
+use std::sync::Arc;
+use std::time::Duration;
 
+use dataflow_expression::Expr;
 use itertools::Itertools;
+use readyset_client::consistency::Timestamp;
+use readyset_client::debug::provenance::ProvenanceTag;
 use readyset_client::{self, KeyComparison, PacketData, PacketTrace};
 use readyset_data::DfType;
 use serde::{Deserialize, Serialize};
 use strum_macros::{EnumCount, EnumDiscriminants, EnumIter, IntoStaticStr};
 use vec1::Vec1;
 
+use crate::checksum::KeyRange;
 use crate::node::Column;
 use crate::prelude::*;
 
@@ -207,6 +213,14 @@ pub enum DomainRequest {
     /// Request that a domain send usage statistics.
     GetStatistics,
 
+    /// Request that a domain reset the cumulative event counters it reports as part of its
+    /// statistics.
+    ResetStatistics,
+
+    /// Request the per-minute read counters of each of the readers in a domain, for the last 24
+    /// hours.
+    GetReadHistory,
+
     /// Add a new column to an existing `Base` node.
     AddBaseColumn {
         node: LocalNodeIndex,
@@ -267,6 +281,23 @@ pub enum DomainRequest {
         target_node: NodeIndex,
     },
 
+    /// Set the predicate which records sent from the given egress node to the target ingress node
+    /// must satisfy, or remove it if `predicate` is `None`. Only valid if every record that
+    /// doesn't satisfy the predicate would be discarded by the target domain.
+    SetEgressPredicate {
+        egress_node: LocalNodeIndex,
+        target_node: NodeIndex,
+        predicate: Option<Expr>,
+    },
+
+    /// Serve keys evicted from the given reader with their evicted rows for up to
+    /// `max_staleness`, while they're replayed. Used to change the setting of a reader which
+    /// already exists, when a new cache reuses it.
+    SetReaderMaxStaleness {
+        node: LocalNodeIndex,
+        max_staleness: Duration,
+    },
+
     /// Tell a Sharder node about its corresponding ingress node in the next domain, and how it
     /// should shard messages when sending to shards of that domain.
     ///
@@ -324,7 +355,16 @@ pub enum DomainRequest {
     StartReplay { tag: Tag, from: LocalNodeIndex },
 
     /// Query whether a domain has finished replaying.
-    QueryReplayDone,
+    QueryReplayDone {
+        /// The domains the replays being waited for were started from. While waiting, these are
+        /// checked (with [`DomainRequest::CheckFailedReplays`]) for replays which failed before
+        /// sending all of their records, which would otherwise never finish.
+        sources: Vec<DomainIndex>,
+    },
+
+    /// Return an error if any full replay started by this domain (with
+    /// [`DomainRequest::StartReplay`]) failed before sending all of its records.
+    CheckFailedReplays,
 
     /// Sent to instruct a domain that a particular node should be considered ready to process
     /// updates.
@@ -344,6 +384,40 @@ pub enum DomainRequest {
     /// bytes
     RequestNodeSizes,
 
+    /// Request the checksums of the given key range of the state of every materialized node in
+    /// the domain, other than base tables, as a list of node indexes and
+    /// [`StateChecksum`](crate::StateChecksum)s
+    RequestStateChecksums { range: KeyRange },
+
+    /// Request the [`RowProvenance`](readyset_client::debug::provenance::RowProvenance) of every
+    /// row in the given reader node whose key is equal to `key`. See
+    /// [`DomainConfig::record_provenance`](crate::DomainConfig::record_provenance).
+    RequestRowProvenance {
+        node: LocalNodeIndex,
+        key: Vec<DfValue>,
+    },
+
+    /// Request the [`ViewStats`](readyset_client::ViewStats) of the given reader node
+    RequestViewStats { node: LocalNodeIndex },
+
+    /// Request the largest value of the column at index `column` in the state of the given base
+    /// table node, or `None` if the state has no rows
+    RequestColumnMax { node: LocalNodeIndex, column: usize },
+
+    /// Request all the rows in the state of the given base table node where the values of
+    /// `columns` are equal to `key`. Looks the rows up in the node's primary key index if
+    /// `columns` is its primary key, and scans the whole state otherwise
+    RequestBaseRows {
+        node: LocalNodeIndex,
+        columns: Vec<usize>,
+        key: Vec<DfValue>,
+    },
+
+    /// Request the latest barrier (see [`PacketPayload::Barrier`](readyset_client::PacketPayload))
+    /// to have reached each reader node in the domain with the writes before it visible, as a list
+    /// of node indexes and barriers
+    RequestReaderBarriers,
+
     /// Process the packet, as per usual
     Packet(Packet),
 
@@ -380,6 +454,11 @@ pub enum Packet {
         link: Link,
         data: Records,
         trace: Option<PacketTrace>,
+        /// If this update results from writes that are part of a write batch, the timestamp of
+        /// that batch (see [`PacketData::batch`])
+        batch: Option<Timestamp>,
+        /// If provenance recording is enabled, the writes to base tables this update results from
+        provenance: Option<Arc<[ProvenanceTag]>>,
     },
 
     /// Update that is part of a tagged data-flow replay path.
@@ -388,6 +467,9 @@ pub enum Packet {
         tag: Tag,
         data: Records,
         context: ReplayPieceContext,
+        /// If provenance recording is enabled, the writes to the base table the replayed rows were
+        /// read from. Empty if the replay started at a materialized node other than a base table.
+        provenance: Option<Arc<[ProvenanceTag]>>,
     },
 
     /// Trigger an eviction from the target node.
@@ -522,6 +604,25 @@ impl Packet {
         }
     }
 
+    /// If this packet is a regular data-flow update resulting from writes that are part of a write
+    /// batch, returns the timestamp of that batch
+    pub(crate) fn batch(&self) -> Option<&Timestamp> {
+        match *self {
+            Packet::Message { ref batch, .. } => batch.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// If this packet is a data-flow update or replay with provenance tags, returns those tags
+    pub(crate) fn provenance(&self) -> Option<&Arc<[ProvenanceTag]>> {
+        match *self {
+            Packet::Message { ref provenance, .. } | Packet::ReplayPiece { ref provenance, .. } => {
+                provenance.as_ref()
+            }
+            _ => None,
+        }
+    }
+
     pub(crate) fn is_regular(&self) -> bool {
         matches!(*self, Packet::Message { .. })
     }
@@ -548,21 +649,27 @@ impl Packet {
                 link,
                 ref data,
                 ref trace,
+                ref batch,
+                ref provenance,
             } => Packet::Message {
                 link,
                 data: data.clone(),
                 trace: trace.clone(),
+                batch: batch.clone(),
+                provenance: provenance.clone(),
             },
             Packet::ReplayPiece {
                 link,
                 tag,
                 ref data,
                 ref context,
+                ref provenance,
             } => Packet::ReplayPiece {
                 link,
                 tag,
                 data: data.clone(),
                 context: context.clone(),
+                provenance: provenance.clone(),
             },
             Packet::Timestamp {
                 ref timestamp,
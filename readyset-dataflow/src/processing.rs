@@ -752,6 +752,16 @@ where
     /// auxillary state other than what is stored in its materialization.
     fn on_eviction(&mut self, _from: LocalNodeIndex, _tag: Tag, _keys: &[KeyComparison]) {}
 
+    /// Triggered whenever keys are evicted from the operator's own materialization, to allow the
+    /// operator to evict any auxiliary state it keeps for those keys.
+    fn on_state_eviction(&mut self, _keys: &[KeyComparison]) {}
+
+    /// Returns the size in bytes of any auxiliary state the operator keeps other than what is
+    /// stored in its materialization, which is counted towards the size of its state.
+    fn auxiliary_state_size(&self) -> u64 {
+        0
+    }
+
     fn can_query_through(&self) -> bool {
         false
     }
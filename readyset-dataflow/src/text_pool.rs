@@ -0,0 +1,319 @@
+//! A bounded pool of interned text values, shared between all the domains running on a worker.
+//!
+//! Low-cardinality text columns (statuses, country codes, etc.) tend to show up as millions of
+//! identical strings across materialized state. Since [`Text`] is reference-counted, replacing
+//! every value received in a write or a replay with an identical value from the pool lets all of
+//! those rows share a single allocation.
+//!
+//! Values short enough to be stored inline as [`TinyText`](readyset_data::TinyText) never
+//! allocate, so they're never interned.
+//!
+//! The pool is split into [`SHARDS`] independently locked shards by the hash of each value, so
+//! that domains interning values concurrently rarely wait for each other, and purging values from
+//! a full shard only blocks the values in that shard.
+
+use std::collections::HashSet;
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+use ahash::RandomState;
+use metrics::{register_counter, register_gauge, Counter, Gauge};
+use readyset_client::metrics::recorded;
+use readyset_client::{PacketPayload, TableOperation};
+use readyset_data::{DfValue, Text};
+
+use crate::payload::Packet;
+
+/// A [`Text`] value, hashed and compared by its bytes *and* its collation (unlike [`Text`] itself)
+struct PooledText(Text);
+
+impl Hash for PooledText {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.0.collation() as u8).hash(state);
+        self.0.as_bytes().hash(state);
+    }
+}
+
+impl PartialEq for PooledText {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.collation() == other.0.collation() && self.0.as_bytes() == other.0.as_bytes()
+    }
+}
+
+impl Eq for PooledText {}
+
+/// The number of shards the pool is split into
+const SHARDS: usize = 16;
+
+/// One shard of the pool
+struct Pool {
+    texts: HashSet<PooledText, RandomState>,
+    /// The total size in bytes of all the values in `texts`
+    size: usize,
+    /// The maximum value for `size`
+    capacity: usize,
+    /// The number of misses since values that were no longer referenced anywhere else were last
+    /// purged from the pool, used to amortize the cost of purging once the pool is full
+    misses_since_purge: usize,
+
+    hits: Counter,
+    misses: Counter,
+    bytes_saved: Counter,
+    size_bytes: Gauge,
+}
+
+impl Pool {
+    fn new(capacity: usize) -> Self {
+        Self {
+            texts: Default::default(),
+            size: 0,
+            capacity,
+            misses_since_purge: 0,
+            hits: register_counter!(recorded::SERVER_TEXT_POOL_HITS),
+            misses: register_counter!(recorded::SERVER_TEXT_POOL_MISSES),
+            bytes_saved: register_counter!(recorded::SERVER_TEXT_POOL_BYTES_SAVED),
+            size_bytes: register_gauge!(recorded::SERVER_TEXT_POOL_SIZE_BYTES),
+        }
+    }
+
+    fn intern(&mut self, text: &mut Text) {
+        if let Some(PooledText(pooled)) = self.texts.get(&PooledText(text.clone())) {
+            self.hits.increment(1);
+            if !pooled.ptr_eq(text) {
+                self.bytes_saved.increment(text.as_bytes().len() as u64);
+                *text = pooled.clone();
+            }
+            return;
+        }
+
+        self.misses.increment(1);
+        self.misses_since_purge += 1;
+        let len = text.as_bytes().len();
+        if self.size + len > self.capacity {
+            self.purge();
+        }
+        if self.size + len <= self.capacity {
+            self.texts.insert(PooledText(text.clone()));
+            self.size += len;
+            self.size_bytes.increment(len as f64);
+        }
+    }
+
+    /// Remove all the values from the pool that aren't referenced anywhere else, unless we've
+    /// already done so within the last `texts.len()` misses
+    fn purge(&mut self) {
+        if self.misses_since_purge < self.texts.len() {
+            return;
+        }
+
+        let mut purged = 0;
+        self.texts.retain(|PooledText(text)| {
+            let unique = text.is_unique();
+            if unique {
+                purged += text.as_bytes().len();
+            }
+            !unique
+        });
+        self.size -= purged;
+        self.size_bytes.decrement(purged as f64);
+        self.misses_since_purge = 0;
+    }
+}
+
+struct Shards {
+    /// Used to pick the shard for each value
+    hasher: RandomState,
+    shards: Vec<Mutex<Pool>>,
+}
+
+impl Shards {
+    fn intern(&self, text: &mut Text) {
+        let mut hasher = self.hasher.build_hasher();
+        PooledText(text.clone()).hash(&mut hasher);
+        let shard = &self.shards[hasher.finish() as usize % SHARDS];
+        #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+        shard.lock().unwrap().intern(text);
+    }
+}
+
+/// A handle to a (possibly disabled) pool of interned text values.
+///
+/// Cloning a `TextPool` returns a new handle to the same pool.
+#[derive(Clone, Default)]
+pub struct TextPool {
+    inner: Option<Arc<Shards>>,
+}
+
+impl TextPool {
+    /// Create a new pool which holds at most `capacity` bytes of text, split evenly between its
+    /// shards. A capacity of 0 disables interning entirely.
+    pub fn new(capacity: usize) -> Self {
+        if capacity == 0 {
+            return Self::default();
+        }
+
+        let shards = (0..SHARDS)
+            // Spread the remainder over the first shards, so the capacities add up
+            .map(|i| {
+                Mutex::new(Pool::new(
+                    capacity / SHARDS + usize::from(i < capacity % SHARDS),
+                ))
+            })
+            .collect();
+
+        Self {
+            inner: Some(Arc::new(Shards {
+                hasher: Default::default(),
+                shards,
+            })),
+        }
+    }
+
+    /// Returns true if this pool interns values at all
+    pub fn is_enabled(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Replace every text value in the given rows with an identical value from the pool, adding
+    /// values which aren't in the pool yet (if there's room for them).
+    pub fn intern_rows<'a, I>(&self, rows: I)
+    where
+        I: IntoIterator<Item = &'a mut Vec<DfValue>>,
+    {
+        let shards = match &self.inner {
+            Some(shards) => shards,
+            None => return,
+        };
+
+        for row in rows {
+            for value in row.iter_mut() {
+                if let DfValue::Text(text) = value {
+                    shards.intern(text);
+                }
+            }
+        }
+    }
+
+    /// Intern all the text values in the rows written to base tables by the given packet, if it's
+    /// an input packet, or in the rows being replayed by the given packet, if it's a replay piece.
+    pub(crate) fn intern_packet(&self, packet: &mut Packet) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        match packet {
+            Packet::Input { inner, .. } => {
+                if let PacketPayload::Input(ops) = &mut inner.data {
+                    self.intern_rows(ops.iter_mut().filter_map(|op| match op {
+                        TableOperation::Insert(row)
+                        | TableOperation::InsertOrUpdate { row, .. } => Some(row),
+                        _ => None,
+                    }))
+                }
+            }
+            Packet::ReplayPiece { data, .. } => {
+                self.intern_rows(data.iter_mut().map(|record| &mut **record))
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::panic)]
+mod tests {
+    use readyset_data::Collation;
+
+    use super::*;
+
+    fn text(s: &str) -> DfValue {
+        DfValue::Text(s.into())
+    }
+
+    fn as_text(value: &DfValue) -> &Text {
+        match value {
+            DfValue::Text(t) => t,
+            _ => panic!("not text: {value:?}"),
+        }
+    }
+
+    #[test]
+    fn interns_identical_values() {
+        let pool = TextPool::new(1024);
+        let mut rows = vec![
+            vec![text("a long enough string"), 1.into()],
+            vec![text("a long enough string"), 2.into()],
+            vec![text("a different long string"), 3.into()],
+        ];
+        pool.intern_rows(rows.iter_mut());
+
+        assert!(as_text(&rows[0][0]).ptr_eq(as_text(&rows[1][0])));
+        assert!(!as_text(&rows[0][0]).ptr_eq(as_text(&rows[2][0])));
+        assert_eq!(rows[1][1], 2.into());
+    }
+
+    #[test]
+    fn distinguishes_collations() {
+        let pool = TextPool::new(1024);
+        let mut rows = vec![
+            vec![text("a long enough string")],
+            vec![DfValue::Text(Text::from_str_with_collation(
+                "a long enough string",
+                Collation::Citext,
+            ))],
+        ];
+        pool.intern_rows(rows.iter_mut());
+
+        assert!(!as_text(&rows[0][0]).ptr_eq(as_text(&rows[1][0])));
+        assert_eq!(as_text(&rows[1][0]).collation(), Collation::Citext);
+    }
+
+    #[test]
+    fn respects_capacity() {
+        let mut pool = Pool::new(25);
+        let mut first = Text::from("a long enough string");
+        pool.intern(&mut first);
+
+        // Doesn't fit while the first value is still referenced
+        let mut a = Text::from("another long string");
+        let mut b = Text::from("another long string");
+        pool.intern(&mut a);
+        pool.intern(&mut b);
+        assert!(!a.ptr_eq(&b));
+
+        // Once nothing else references the first value, it can be purged to make room
+        drop(first);
+        pool.intern(&mut a);
+        pool.intern(&mut b);
+        assert!(a.ptr_eq(&b));
+        assert_eq!(pool.size, "another long string".len());
+    }
+
+    #[test]
+    fn splits_capacity_between_shards() {
+        let pool = TextPool::new(SHARDS * 100 + 3);
+        let capacities = pool
+            .inner
+            .as_ref()
+            .unwrap()
+            .shards
+            .iter()
+            .map(|shard| shard.lock().unwrap().capacity)
+            .collect::<Vec<_>>();
+        assert_eq!(capacities.iter().sum::<usize>(), SHARDS * 100 + 3);
+        assert_eq!(capacities[0], 101);
+        assert_eq!(capacities[SHARDS - 1], 100);
+    }
+
+    #[test]
+    fn disabled() {
+        let pool = TextPool::new(0);
+        assert!(!pool.is_enabled());
+        let mut rows = vec![
+            vec![text("a long enough string")],
+            vec![text("a long enough string")],
+        ];
+        pool.intern_rows(rows.iter_mut());
+        assert!(!as_text(&rows[0][0]).ptr_eq(as_text(&rows[1][0])));
+    }
+}
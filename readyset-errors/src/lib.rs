@@ -56,6 +56,92 @@ pub enum NodeType {
     Sharder,
 }
 
+/// The features of SQL which ReadySet doesn't (yet) support caching queries with, attached to
+/// [`ReadySetError::UnsupportedFeature`] so that we can track which missing features prevent the
+/// most queries from being cached.
+#[derive(Eq, PartialEq, Serialize, Deserialize, Debug, Clone, Copy, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum UnsupportedFeature {
+    /// Queries which don't read from any tables, such as `SELECT 1`
+    NoTables,
+    /// Subqueries which refer to columns of the outer query, and couldn't be decorrelated
+    CorrelatedSubquery,
+    /// Subqueries in positions other than the `FROM` clause or the right-hand side of `IN`
+    Subquery,
+    /// Join conditions other than equality comparisons between columns, combined with `AND`
+    NonEquiJoin,
+    /// `OR` between join conditions
+    DisjunctiveJoin,
+    /// `OR` between conditions on query parameters
+    DisjunctiveParameters,
+    /// Query parameters compared with columns using several different operators
+    MixedParameterOperators,
+    /// Query parameters in positions other than direct comparisons with columns, in queries which
+    /// can't evaluate them after the lookup
+    ParameterPosition,
+    /// `LIMIT` and `OFFSET` clauses that ReadySet can't maintain, or pagination when it's disabled
+    Pagination,
+    /// Expressions other than column references in `GROUP BY`
+    GroupByExpression,
+    /// Aggregates which can't be re-aggregated after the lookup into the cache
+    PostLookupAggregate,
+    /// Joins of a table with itself on the same column
+    SelfJoin,
+}
+
+impl UnsupportedFeature {
+    /// All [`UnsupportedFeature`]s
+    pub const ALL: [Self; 12] = [
+        Self::NoTables,
+        Self::CorrelatedSubquery,
+        Self::Subquery,
+        Self::NonEquiJoin,
+        Self::DisjunctiveJoin,
+        Self::DisjunctiveParameters,
+        Self::MixedParameterOperators,
+        Self::ParameterPosition,
+        Self::Pagination,
+        Self::GroupByExpression,
+        Self::PostLookupAggregate,
+        Self::SelfJoin,
+    ];
+
+    /// Returns the name of this feature, as displayed to users
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::NoTables => "no_tables",
+            Self::CorrelatedSubquery => "correlated_subquery",
+            Self::Subquery => "subquery",
+            Self::NonEquiJoin => "non_equi_join",
+            Self::DisjunctiveJoin => "disjunctive_join",
+            Self::DisjunctiveParameters => "disjunctive_parameters",
+            Self::MixedParameterOperators => "mixed_parameter_operators",
+            Self::ParameterPosition => "parameter_position",
+            Self::Pagination => "pagination",
+            Self::GroupByExpression => "group_by_expression",
+            Self::PostLookupAggregate => "post_lookup_aggregate",
+            Self::SelfJoin => "self_join",
+        }
+    }
+}
+
+impl std::fmt::Display for UnsupportedFeature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for UnsupportedFeature {
+    type Err = ReadySetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|feature| feature.name() == s)
+            .ok_or_else(|| ReadySetError::BadRequest(format!("Unknown unsupported feature `{s}`")))
+    }
+}
+
 /// General error type to be used across all of the ReadySet codebase.
 #[derive(Eq, PartialEq, Serialize, Deserialize, Error, Debug, Clone)]
 pub enum ReadySetError {
@@ -86,6 +172,14 @@ pub enum ReadySetError {
         statement: String,
     },
 
+    /// A consistency token passed to the adapter (eg via `SET @readyset_min_token`) could not be
+    /// parsed
+    #[error("Invalid consistency token: {token}")]
+    InvalidConsistencyToken {
+        /// The token that could not be parsed
+        token: String,
+    },
+
     /// Could not connect to the upstream database provided
     #[error("Could not connect to the upstream database provided")]
     InvalidUpstreamDatabase,
@@ -177,6 +271,10 @@ pub enum ReadySetError {
     #[error("Failed to (de)serialize: {0}")]
     SerializationFailed(String),
 
+    /// Deserializing a row returned by a view into a user-provided type failed.
+    #[error("Failed to deserialize row: {0}")]
+    RowDeserializationFailed(String),
+
     /// The wrong number of columns was given when inserting a row or preparing
     /// a statement.
     #[error("wrong number of columns specified: expected {0}, got {1}")]
@@ -283,6 +381,13 @@ pub enum ReadySetError {
     #[error("Existing caches do not satisfy the given query parameters.")]
     NoCacheForQuery,
 
+    /// The value passed for a `LIKE` pattern parameter can't be looked up in the reader for a
+    /// query, since it doesn't start with a literal prefix (for example, `%foo`).
+    ///
+    /// This error should not reach the client when an upstream database is present.
+    #[error("LIKE pattern `{0}` has no literal prefix to look up")]
+    UnprefixedLikePattern(String),
+
     /// A reader could not be found at the given worker.
     #[error("Reader not found")]
     ReaderNotFound,
@@ -295,6 +400,15 @@ pub enum ReadySetError {
     #[error("Upquery timeout")]
     UpqueryTimeout,
 
+    /// A barrier injected to quiesce the dataflow graph didn't reach every reader in time.
+    #[error("Timed out waiting for the dataflow graph to quiesce")]
+    QuiesceTimeout,
+
+    /// A request to quiesce the dataflow graph was made while another one was still waiting for
+    /// its barrier to reach every reader.
+    #[error("The dataflow graph is already being quiesced")]
+    QuiesceInProgress,
+
     /// The query specified an empty lookup key.
     #[error("the query specified an empty lookup key")]
     EmptyKey,
@@ -323,12 +437,28 @@ pub enum ReadySetError {
     #[error("Bad request: {0}")]
     BadRequest(String),
 
+    /// The user isn't allowed to perform an operation
+    #[error("Access denied: {0}")]
+    AccessDenied(String),
+
     /// An operation isn't supported by ReadySet yet, but might be in the future.
     ///
     /// This is produced by the [`unsupported!`] macro.
     #[error("Operation unsupported: {0}")]
     Unsupported(String),
 
+    /// An operation isn't supported by ReadySet yet because it requires a SQL feature that we
+    /// track in [`UnsupportedFeature`].
+    ///
+    /// This is produced by the [`unsupported_feature!`] macro.
+    #[error("Operation unsupported: {message}")]
+    UnsupportedFeature {
+        /// The feature that isn't supported
+        feature: UnsupportedFeature,
+        /// A description of what exactly isn't supported
+        message: String,
+    },
+
     /// The query provided by the user could not be parsed by `nom-sql`.
     ///
     /// TODO(eta): extend nom-sql to be able to provide more granular parse failure information.
@@ -622,6 +752,9 @@ pub enum ReadySetError {
     #[error("Root certificate must be a valid DER or PEM encoded certificate")]
     InvalidRootCertificate,
 
+    #[error("Client identity must be a valid PKCS #12 archive: {0}")]
+    InvalidClientIdentity(String),
+
     /// Error when a node couldn't be found in MIR.
     #[error("Could not find MIR node {index}")]
     MirNodeNotFound { index: usize },
@@ -637,6 +770,35 @@ pub enum ReadySetError {
         table: Relation,
     },
 
+    /// Error when a replicated write to a base table updated or deleted rows which don't exist in
+    /// the table, and the table is configured to be re-snapshotted when that happens
+    #[error("Replicated write conflicted with the state of table {table}")]
+    ReplicationConflict {
+        /// The base table being written to.
+        table: Relation,
+    },
+
+    /// Error when a write to a base table was rejected because a row did not satisfy one of the
+    /// table's `CHECK` constraints
+    #[error("Check constraint '{constraint}' is violated for table {table}")]
+    CheckConstraintViolated {
+        /// The base table being written to.
+        table: Relation,
+        /// The name of the violated constraint.
+        constraint: String,
+    },
+
+    /// Error when a write to a base table was rejected because it would have resulted in two rows
+    /// with the same value for the table's primary key or one of its unique keys
+    #[error("Duplicate entry for key '{key}' in table {table}")]
+    DuplicateEntry {
+        /// The base table being written to.
+        table: Relation,
+        /// The key which would have been duplicated - either `PRIMARY` for the primary key, or the
+        /// names of the columns of the unique key.
+        key: String,
+    },
+
     /// Error when a MIR node does not have dataflow node assigned, in contexts
     /// where it should had one.
     #[error("MIR node should have a dataflow node assigned: {mir_node_index}")]
@@ -649,6 +811,69 @@ pub enum ReadySetError {
     /// Error that the upstream database reports a server version the ReadySet could not parse.
     #[error("Upstream server version could not be parsed")]
     UnparseableServerVersion,
+
+    /// Error returned instead of proxying a read to the upstream database, either because too many
+    /// reads are being proxied or because the upstream database is failing.
+    #[error("Query could not be proxied to the upstream database: {0}")]
+    FallbackUnavailable(String),
+
+    /// Error returned instead of the results of a read from a cache, when the results exceed the
+    /// limits configured for that cache.
+    #[error("Result set exceeds the limits of the cache: {0}")]
+    ResultSetTooLarge(String),
+
+    /// Error returned when creating a cache or reading from a cache would exceed the quota of the
+    /// namespace the cache belongs to.
+    #[error("Namespace {namespace} is limited to {limit}")]
+    NamespaceQuotaExceeded {
+        /// The name of the namespace
+        namespace: String,
+        /// A description of the limit that was exceeded
+        limit: String,
+    },
+
+    /// Error returned instead of the results of a read from a cache, when replication is further
+    /// behind the upstream database than the maximum replication lag configured for that cache.
+    #[error(
+        "Replication lag of {lag_ms}ms exceeds the maximum of {max_lag_seconds}s for cache {cache}"
+    )]
+    ReplicationLagExceeded {
+        /// The name of the cache
+        cache: String,
+        /// The current replication lag, in milliseconds
+        lag_ms: u64,
+        /// The maximum replication lag configured for the cache, in seconds
+        max_lag_seconds: u64,
+        /// If true, the read should be proxied to the upstream database instead
+        proxy: bool,
+    },
+
+    /// Error returned instead of the results of a read from a cache that was created with a
+    /// maximum replication lag, when how far replication is behind the upstream database isn't
+    /// known. The read should be proxied to the upstream database instead.
+    #[error(
+        "Replication lag is unknown, so it can't be checked against the maximum for cache {cache}"
+    )]
+    ReplicationLagUnknown {
+        /// The name of the cache
+        cache: String,
+    },
+
+    /// Error returned when creating a cache would fully materialize a node whose state is
+    /// estimated to be larger than the budget for full materializations, and the cache wasn't
+    /// created with `CREATE CACHE ALWAYS`.
+    #[error(
+        "Fully materializing {node} would take an estimated {estimated_bytes} bytes, exceeding \
+         the budget of {budget_bytes} bytes (use CREATE CACHE ALWAYS to override)"
+    )]
+    FullMaterializationOverBudget {
+        /// The name of the node which would have been fully materialized
+        node: String,
+        /// The estimated size of the node's state, in bytes
+        estimated_bytes: usize,
+        /// The maximum size of the state of a single fully materialized node, in bytes
+        budget_bytes: usize,
+    },
 }
 
 impl ReadySetError {
@@ -699,9 +924,12 @@ impl ReadySetError {
         self.any_cause(|e| e.is_unparseable_query())
     }
 
-    /// Returns `true` if the error is [`Unsupported`].
+    /// Returns `true` if the error is [`Unsupported`] or [`UnsupportedFeature`].
     pub fn is_unsupported(&self) -> bool {
-        matches!(self, Self::Unsupported(..))
+        matches!(
+            self,
+            Self::Unsupported(..) | Self::UnsupportedFeature { .. }
+        )
     }
 
     /// Returns true if the error either *is* [`Unsupported`], or was *caused by*
@@ -710,6 +938,26 @@ impl ReadySetError {
         self.any_cause(|e| e.is_unsupported())
     }
 
+    /// If `self` either *is* [`Unsupported`] or was *caused by* [`Unsupported`], returns the
+    /// message of the unsupported error. Otherwise, returns `None`
+    pub fn unsupported_cause(&self) -> Option<&str> {
+        self.find_map_cause(|e| match e {
+            Self::Unsupported(msg) | Self::UnsupportedFeature { message: msg, .. } => {
+                Some(msg.as_str())
+            }
+            _ => None,
+        })
+    }
+
+    /// If `self` either *is* [`UnsupportedFeature`] or was *caused by* [`UnsupportedFeature`],
+    /// returns the feature that isn't supported. Otherwise, returns `None`
+    pub fn unsupported_feature(&self) -> Option<UnsupportedFeature> {
+        self.find_map_cause(|e| match e {
+            Self::UnsupportedFeature { feature, .. } => Some(*feature),
+            _ => None,
+        })
+    }
+
     /// Returns `true` if self is ['ViewNotFound'].
     pub fn is_view_not_found(&self) -> bool {
         matches!(self, Self::ViewNotFound(..))
@@ -751,6 +999,53 @@ impl ReadySetError {
         self.any_cause(|e| e.is_table_not_replicated())
     }
 
+    /// Returns `true` if self is [`CheckConstraintViolated`].
+    pub fn is_check_constraint_violated(&self) -> bool {
+        matches!(self, Self::CheckConstraintViolated { .. })
+    }
+
+    /// Returns `true` if self either *is* [`CheckConstraintViolated`], or was *caused by*
+    /// [`CheckConstraintViolated`].
+    pub fn caused_by_check_constraint_violated(&self) -> bool {
+        self.any_cause(|e| e.is_check_constraint_violated())
+    }
+
+    /// Returns `true` if self is [`DuplicateEntry`].
+    pub fn is_duplicate_entry(&self) -> bool {
+        matches!(self, Self::DuplicateEntry { .. })
+    }
+
+    /// Returns `true` if self either *is* [`DuplicateEntry`], or was *caused by*
+    /// [`DuplicateEntry`].
+    pub fn caused_by_duplicate_entry(&self) -> bool {
+        self.any_cause(|e| e.is_duplicate_entry())
+    }
+
+    /// Returns `true` if self is [`ReplicationConflict`].
+    pub fn is_replication_conflict(&self) -> bool {
+        matches!(self, Self::ReplicationConflict { .. })
+    }
+
+    /// Returns `true` if self either *is* [`ReplicationConflict`], or was *caused by*
+    /// [`ReplicationConflict`].
+    pub fn caused_by_replication_conflict(&self) -> bool {
+        self.any_cause(|e| e.is_replication_conflict())
+    }
+
+    /// Returns `true` if self either is or was caused by an error which rejects a write to a base
+    /// table because the write violates one of the table's constraints, or conflicts with the
+    /// table's state.
+    ///
+    /// Such errors are reported back to the client which performed the write, rather than being
+    /// treated as fatal to the domain processing the write.
+    pub fn caused_by_rejected_write(&self) -> bool {
+        self.any_cause(|e| {
+            e.is_check_constraint_violated()
+                || e.is_duplicate_entry()
+                || e.is_replication_conflict()
+        })
+    }
+
     /// Returns `true` if the error could have been caused by a networking problem.
     pub fn is_networking_related(&self) -> bool {
         self.any_cause(|e| {
@@ -780,6 +1075,18 @@ impl ReadySetError {
     pub fn is_invalid_query(&self) -> bool {
         matches!(self, Self::InvalidQuery(..))
     }
+
+    /// Returns the innermost error in the chain of errors which caused `self`, or `self` if it
+    /// wasn't caused by another error.
+    ///
+    /// This is the error which should be used to decide which error code to report to clients,
+    /// since errors are frequently wrapped (eg in [`RpcFailed`] or [`Context`]) on their way out
+    /// of ReadySet.
+    pub fn root_cause(&self) -> &Self {
+        self.source()
+            .and_then(|e| e.downcast_ref::<Box<ReadySetError>>())
+            .map_or(self, |e| e.root_cause())
+    }
 }
 
 /// Make a new [`ReadySetError::Internal`] with the provided format arguments.
@@ -873,6 +1180,38 @@ macro_rules! unsupported_err {
     }
 }
 
+/// Make a new [`ReadySetError::UnsupportedFeature`] for the given variant of
+/// [`UnsupportedFeature`], with the provided format arguments as its message.
+///
+/// When building in debug mode, the returned error also captures file, line, and column information
+/// for futher debugging purposes
+///
+/// # Examples
+///
+/// ```
+/// use readyset_errors::{unsupported_feature_err, UnsupportedFeature};
+///
+/// let my_err = unsupported_feature_err!(NonEquiJoin, "Can't join on {}", "t1.x > t2.y");
+/// assert_eq!(
+///     my_err.unsupported_feature(),
+///     Some(UnsupportedFeature::NonEquiJoin)
+/// );
+/// assert!(my_err.to_string().contains("Can't join on t1.x > t2.y"));
+/// ```
+#[macro_export]
+macro_rules! unsupported_feature_err {
+    ($feature: ident, $($format_args:tt)*) => {
+        $crate::ReadySetError::UnsupportedFeature {
+            feature: $crate::UnsupportedFeature::$feature,
+            message: format!(
+                "{}{}",
+                $crate::__location_info!("in {}: "),
+                format_args!($($format_args)*)
+            ),
+        }
+    }
+}
+
 /// Make a new [`ReadySetError::BadRequest`] with the provided string-able argument.
 pub fn bad_request_err<T: Into<String>>(err: T) -> ReadySetError {
     ReadySetError::BadRequest(err.into())
@@ -948,6 +1287,17 @@ macro_rules! unsupported {
     };
 }
 
+/// Return a [`ReadySetError::UnsupportedFeature`] from the current function.
+///
+/// The first argument is the name of a variant of [`UnsupportedFeature`], and the rest are a
+/// format string and arguments as for [`panic!`].
+#[macro_export]
+macro_rules! unsupported_feature {
+    ($($args:tt)*) => {
+        return Err($crate::unsupported_feature_err!($($args)*).into())
+    };
+}
+
 /// Return a [`ReadySetError::Internal`] from the current function, if and only if the argument
 /// evaluates to false.
 ///
@@ -1202,7 +1552,9 @@ macro_rules! set_failpoint_return_err {
 
 #[cfg(test)]
 mod test {
-    use crate::{internal, ReadySetError, ReadySetResult};
+    use crate::{
+        internal, unsupported_feature_err, ReadySetError, ReadySetResult, UnsupportedFeature,
+    };
 
     #[test]
     #[should_panic(expected = "errors/src/lib.rs")]
@@ -1224,6 +1576,36 @@ mod test {
         assert!(err.caused_by_unsupported());
     }
 
+    #[test]
+    fn unsupported_feature_two_deep() {
+        let err = ReadySetError::RpcFailed {
+            during: "test".to_owned(),
+            source: Box::new(ReadySetError::MigrationPlanFailed {
+                source: Box::new(unsupported_feature_err!(CorrelatedSubquery, "Test")),
+            }),
+        };
+        assert!(err.caused_by_unsupported());
+        assert_eq!(
+            err.unsupported_feature(),
+            Some(UnsupportedFeature::CorrelatedSubquery)
+        );
+        assert!(err.unsupported_cause().unwrap().ends_with("Test"));
+        assert_eq!(
+            ReadySetError::Unsupported("Test".to_owned()).unsupported_feature(),
+            None
+        );
+    }
+
+    #[test]
+    fn unsupported_feature_names_round_trip() {
+        for feature in UnsupportedFeature::ALL {
+            assert_eq!(
+                feature.to_string().parse::<UnsupportedFeature>().unwrap(),
+                feature
+            );
+        }
+    }
+
     #[test]
     fn context_caused_by_table_not_replicated() {
         let err = ReadySetError::TableNotReplicated {
@@ -1263,4 +1645,19 @@ mod test {
             ("t2_view", Some("public"))
         )
     }
+
+    #[test]
+    fn root_cause_two_deep() {
+        let err = ReadySetError::RpcFailed {
+            during: "view_builder".into(),
+            source: Box::new(ReadySetError::ViewNotFound("q_1".into()).context("some context")),
+        };
+        assert!(matches!(
+            err.root_cause(),
+            ReadySetError::ViewNotFound(name) if name == "q_1"
+        ));
+
+        let err = ReadySetError::UpqueryTimeout;
+        assert!(matches!(err.root_cause(), ReadySetError::UpqueryTimeout));
+    }
 }
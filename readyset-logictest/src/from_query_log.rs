@@ -99,7 +99,11 @@ fn is_ddl(query: &SqlQuery) -> bool {
         | SqlQuery::Commit(_)
         | SqlQuery::Rollback(_)
         | SqlQuery::Show(_)
-        | SqlQuery::Explain(_) => false,
+        | SqlQuery::DumpCaches(_)
+        | SqlQuery::CopyCache(_)
+        | SqlQuery::AlterReadyset(_)
+        | SqlQuery::Explain(_)
+        | SqlQuery::Kill(_) => false,
         SqlQuery::CreateTable(_)
         | SqlQuery::CreateView(_)
         | SqlQuery::DropTable(_)
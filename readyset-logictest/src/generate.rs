@@ -352,6 +352,7 @@ impl Seed {
                         .take(rows_to_delete)
                         .map(|row| DeleteStatement {
                             table: table.clone(),
+                            using: vec![],
                             where_clause: Some(Expr::BinaryOp {
                                 lhs: Box::new(Expr::Column(pk.clone().into())),
                                 op: BinaryOperator::Equal,
@@ -417,7 +417,7 @@ impl Verify {
             let authority = Arc::new(
                 self.authority
                     .to_authority(&self.authority_address, &deployment_name)
-                    .await,
+                    .await?,
             );
 
             let noria_opts = NoriaOptions { authority };
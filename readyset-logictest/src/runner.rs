@@ -4,7 +4,6 @@ use std::fs::File;
 use std::io::Write;
 use std::iter::FromIterator;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::AtomicUsize;
 use std::sync::{Arc, RwLock};
 use std::time::{Duration, Instant};
 use std::{io, mem};
@@ -497,7 +496,6 @@ impl TestScript {
     ) -> (tokio::task::JoinHandle<()>, DatabaseURL) {
         let database_type = run_opts.database_type;
         let replication_url = run_opts.replication_url.clone();
-        let auto_increments: Arc<RwLock<HashMap<Relation, AtomicUsize>>> = Arc::default();
         let query_cache: Arc<RwLock<HashMap<ViewCreateRequest, Relation>>> = Arc::default();
         let mut retry: usize = 0;
         let listener = loop {
@@ -523,7 +521,6 @@ impl TestScript {
 
             let noria = NoriaConnector::new(
                 rh,
-                auto_increments,
                 query_cache,
                 ReadBehavior::Blocking,
                 match database_type {
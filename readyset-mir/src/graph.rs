@@ -142,6 +142,7 @@ impl MirGraph {
                 order_by,
                 returned_cols,
                 aggregates,
+                post_lookup_predicates,
                 ..
             } => {
                 let mut columns = self.columns(node);
@@ -155,7 +156,14 @@ impl MirGraph {
                                 .clone()
                                 .into_iter()
                                 .chain(aggs.aggregates.iter().map(|agg| agg.column.clone()))
-                        })),
+                        }))
+                        .chain(
+                            post_lookup_predicates
+                                .iter()
+                                .flat_map(|p| p.referred_columns())
+                                .cloned()
+                                .map(MirColumn::from),
+                        ),
                 );
                 columns
             }
@@ -168,6 +176,20 @@ impl MirGraph {
                 }
                 columns
             }
+            MirNodeInner::AntiJoin { on, project }
+            | MirNodeInner::DependentAntiJoin { on, project } => {
+                // Anti-joins don't project any columns from the right, but still need the join
+                // keys from both sides
+                let mut columns = project.clone();
+                for (l, r) in on {
+                    for c in [l, r] {
+                        if !columns.contains(c) {
+                            columns.push(c.clone());
+                        }
+                    }
+                }
+                columns
+            }
             _ => self.columns(node),
         }
     }
@@ -214,7 +236,9 @@ impl MirGraph {
                 .collect(),
             MirNodeInner::Join { project, .. }
             | MirNodeInner::LeftJoin { project, .. }
-            | MirNodeInner::DependentJoin { project, .. } => project.clone(),
+            | MirNodeInner::AntiJoin { project, .. }
+            | MirNodeInner::DependentJoin { project, .. }
+            | MirNodeInner::DependentAntiJoin { project, .. } => project.clone(),
             MirNodeInner::JoinAggregates => {
                 let cols = self
                     // see note [edge-ordering]
@@ -275,6 +299,15 @@ impl MirGraph {
             }
         }
 
+        if self.graph[node].inner.is_anti_join() {
+            // Anti-joins can only project columns from their left parent
+            return self.columns(node).contains(column)
+                || self
+                    .sorted_ancestors(node)
+                    .next()
+                    .map_or(false, |left| self.provides_column(left, column));
+        }
+
         self.columns(node).contains(column)
             || self
                 .graph
@@ -382,7 +415,7 @@ impl MirGraph {
         None
     }
 
-    fn sorted_ancestors(&self, node: NodeIndex) -> impl Iterator<Item = NodeIndex> + '_ {
+    pub(crate) fn sorted_ancestors(&self, node: NodeIndex) -> impl Iterator<Item = NodeIndex> + '_ {
         self.graph
             .edges_directed(node, Direction::Incoming)
             .sorted_by_key(|e| e.weight())
@@ -53,6 +53,7 @@ use serde::{Deserialize, Serialize};
 mod column;
 pub mod graph;
 pub mod node;
+pub mod plan;
 pub mod query;
 pub(crate) mod rewrite;
 pub mod visualize;
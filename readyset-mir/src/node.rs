@@ -149,6 +149,8 @@ mod tests {
                     ],
                     primary_key: None,
                     unique_keys: vec![].into(),
+                    check_constraints: vec![],
+                    duplicate_keys: Default::default(),
                 },
             ))
         }
@@ -173,6 +175,8 @@ mod tests {
                     ],
                     primary_key: None,
                     unique_keys: vec![].into(),
+                    check_constraints: vec![],
+                    duplicate_keys: Default::default(),
                 },
             ))
         }
@@ -350,6 +354,7 @@ mod tests {
                 returned_cols: None,
                 default_row: None,
                 aggregates: None,
+                post_lookup_predicates: vec![],
             })
         }
 
@@ -557,6 +562,8 @@ mod tests {
                     column_specs: vec![cspec("c1"), cspec("c2"), cspec("c3")],
                     primary_key: Some([Column::from("c1")].into()),
                     unique_keys: Default::default(),
+                    check_constraints: vec![],
+                    duplicate_keys: Default::default(),
                 },
                 df_node_index: None,
             });
@@ -601,6 +608,8 @@ mod tests {
                     column_specs: vec![cspec("c1"), cspec("c2"), cspec("c3")],
                     primary_key: Some([Column::from("c1")].into()),
                     unique_keys: Default::default(),
+                    check_constraints: vec![],
+                    duplicate_keys: Default::default(),
                 },
                 df_node_index: None,
             });
@@ -639,6 +648,8 @@ mod tests {
                     column_specs: vec![cspec("c1")],
                     primary_key: Some([Column::from("c1")].into()),
                     unique_keys: Default::default(),
+                    check_constraints: vec![],
+                    duplicate_keys: Default::default(),
                 },
                 df_node_index: None,
             });
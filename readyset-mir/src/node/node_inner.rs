@@ -1,7 +1,9 @@
 use std::fmt::Debug;
 
 use common::{DfValue, IndexType};
+use dataflow::node::special::DuplicateKeyBehavior;
 use dataflow::ops::grouped::aggregate::Aggregation;
+use dataflow::ops::grouped::approximate::ApproximateAggregation;
 use dataflow::ops::grouped::extremum::Extremum;
 use dataflow::ops::union;
 use dataflow::PostLookupAggregates;
@@ -42,6 +44,12 @@ pub enum MirNodeInner {
         column_specs: Vec<ColumnSpecification>,
         primary_key: Option<Box<[Column]>>,
         unique_keys: Box<[Box<[Column]>]>,
+        /// `CHECK` constraints to enforce on writes to the table, as pairs of the name of the
+        /// constraint and its condition
+        check_constraints: Vec<(SqlIdentifier, Expr)>,
+        /// How to handle writes which would duplicate the table's primary key or one of its
+        /// unique keys
+        duplicate_keys: DuplicateKeyBehavior,
     },
     /// Node that computes the extreme value (minimum or maximum) of a column grouped by another
     /// set of columns, outputting its result as an additional column.
@@ -118,6 +126,21 @@ pub enum MirNodeInner {
         /// Columns (from both parents) to project in the output.
         project: Vec<Column>,
     },
+    /// Node which computes an *anti*-join on its two parents, by finding all rows in the left for
+    /// which there are *no* rows in the right where the values in `on_right` are equal to the
+    /// values of `on_left`. Used to implement `NOT EXISTS` and `NOT IN` with subqueries.
+    ///
+    /// Converted to [`Join`] with [`JoinType::Anti`] when lowering to dataflow.
+    ///
+    /// [`Join`]: dataflow::ops::join::Join
+    /// [`JoinType::Anti`]: dataflow::ops::join::JoinType::Anti
+    AntiJoin {
+        /// Columns to use as the join keys. Each tuple corresponds to a column in the left parent
+        /// and column in the right parent.
+        on: Vec<(Column, Column)>,
+        /// Columns (from the left parent only) to project in the output.
+        project: Vec<Column>,
+    },
     /// Join where nodes in the right-hand side depend on columns in the left-hand side
     /// (referencing tables in `dependent_tables`). These are created during compilation for
     /// correlated subqueries, and must be removed entirely by rewrite passes before lowering
@@ -135,6 +158,17 @@ pub enum MirNodeInner {
         /// Columns (from both parents) to project in the output.
         project: Vec<Column>,
     },
+    /// [Anti-join](MirNodeInner::AntiJoin) where nodes in the right-hand side depend on columns in
+    /// the left-hand side. Like [`DependentJoin`](MirNodeInner::DependentJoin)s, these are created
+    /// for correlated subqueries and must be removed by rewrite passes before lowering to
+    /// dataflow.
+    DependentAntiJoin {
+        /// Columns to use as the join keys. Each tuple corresponds to a column in the left parent
+        /// and column in the right parent.
+        on: Vec<(Column, Column)>,
+        /// Columns (from the left parent only) to project in the output.
+        project: Vec<Column>,
+    },
     /// group columns
     // currently unused
     #[allow(dead_code)]
@@ -242,6 +276,11 @@ pub enum MirNodeInner {
         default_row: Option<Vec<DfValue>>,
         /// Aggregates to perform in the reader on result sets for keys after performing the lookup
         aggregates: Option<PostLookupAggregates<Column>>,
+        /// Residual predicates from the query which couldn't be turned into lookup keys, and are
+        /// instead evaluated against the rows in the reader after each lookup. These are only
+        /// recorded here so they can be shown in the query's plan - the predicates themselves are
+        /// sent along with each lookup.
+        post_lookup_predicates: Vec<Expr>,
     },
 }
 
@@ -258,6 +297,7 @@ impl MirNodeInner {
             returned_cols: None,
             default_row: None,
             aggregates: None,
+            post_lookup_predicates: vec![],
         }
     }
 
@@ -283,7 +323,9 @@ impl MirNodeInner {
             }
             MirNodeInner::Join { project, .. }
             | MirNodeInner::LeftJoin { project, .. }
-            | MirNodeInner::DependentJoin { project, .. } => {
+            | MirNodeInner::AntiJoin { project, .. }
+            | MirNodeInner::DependentJoin { project, .. }
+            | MirNodeInner::DependentAntiJoin { project, .. } => {
                 if !project.contains(&c) {
                     project.push(c);
                 }
@@ -315,11 +357,24 @@ impl MirNodeInner {
         }
     }
 
-    /// Returns `true` if self is a [`DependentJoin`].
+    /// Returns `true` if self is a [`DependentJoin`] or a [`DependentAntiJoin`].
     ///
     /// [`DependentJoin`]: MirNodeInner::DependentJoin
+    /// [`DependentAntiJoin`]: MirNodeInner::DependentAntiJoin
     pub fn is_dependent_join(&self) -> bool {
-        matches!(self, Self::DependentJoin { .. })
+        matches!(
+            self,
+            Self::DependentJoin { .. } | Self::DependentAntiJoin { .. }
+        )
+    }
+
+    /// Returns `true` if self is an [`AntiJoin`] or a [`DependentAntiJoin`], which only project
+    /// columns from their left parent.
+    ///
+    /// [`AntiJoin`]: MirNodeInner::AntiJoin
+    /// [`DependentAntiJoin`]: MirNodeInner::DependentAntiJoin
+    pub fn is_anti_join(&self) -> bool {
+        matches!(self, Self::AntiJoin { .. } | Self::DependentAntiJoin { .. })
     }
 
     pub(crate) fn description(&self) -> String {
@@ -337,6 +392,12 @@ impl MirNodeInner {
                     Aggregation::GroupConcat { separator: ref s } => {
                         format!("||([{}], \"{}\")", on.name.as_str(), s.as_str())
                     }
+                    Aggregation::Approximate(ApproximateAggregation::CountDistinct) => {
+                        format!("~|d|({})", on.name.as_str())
+                    }
+                    Aggregation::Approximate(ApproximateAggregation::PercentileCont {
+                        fraction,
+                    }) => format!("~%{}({})", fraction, on.name.as_str()),
                 };
                 let group_cols = group_by
                     .iter()
@@ -448,6 +509,30 @@ impl MirNodeInner {
                         .join(", ")
                 )
             }
+            MirNodeInner::AntiJoin {
+                ref on,
+                ref project,
+            } => {
+                format!(
+                    "▷ [{} on {}]",
+                    project.iter().map(|c| &c.name).join(", "),
+                    on.iter()
+                        .map(|(l, r)| format!("{}:{}", l.name, r.name))
+                        .join(", ")
+                )
+            }
+            MirNodeInner::DependentAntiJoin {
+                ref on,
+                ref project,
+            } => {
+                format!(
+                    "⧑▷ | {} on: {}",
+                    project.iter().map(|c| &c.name).join(", "),
+                    on.iter()
+                        .map(|(l, r)| format!("{}:{}", l.name, r.name))
+                        .join(", ")
+                )
+            }
             MirNodeInner::Latest { ref group_by } => {
                 let key_cols = group_by
                     .iter()
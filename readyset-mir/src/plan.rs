@@ -0,0 +1,409 @@
+//! Serializable MIR plans for individual queries.
+//!
+//! A [`MirPlan`] is a self-contained description of the (optimized) MIR graph for a single cached
+//! query: every node the query's leaf depends on, listed in topological order, with each node
+//! referring to its parents by their position in the list. Since MIR refers to columns by name
+//! rather than by index, plans can be inspected and edited by hand - for example to swap the sides
+//! of a join, or to change the key of the leaf - and then submitted back to the controller, which
+//! validates the plan and migrates the query using it in place of the plan it would have generated
+//! itself.
+//!
+//! Base tables are included in the plan so that it describes the schema the query was planned
+//! against, but they're never created from a plan: when a plan is added to the graph, base table
+//! nodes are resolved by name to the existing base tables, whose schema must match the plan.
+
+use std::collections::HashMap;
+
+use nom_sql::Relation;
+use readyset_errors::{invalid, invalid_err, ReadySetResult};
+use serde::{Deserialize, Serialize};
+
+use crate::graph::MirGraph;
+use crate::node::{MirNode, MirNodeInner};
+use crate::{NodeIndex, PAGE_NUMBER_COL};
+
+/// A single node in a [`MirPlan`]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MirPlanNode {
+    pub name: Relation,
+    pub inner: MirNodeInner,
+    /// The positions of the parents of this node in [`MirPlan::nodes`], in order. Every parent
+    /// must come before this node in the plan.
+    pub parents: Vec<usize>,
+}
+
+/// A serializable description of the MIR graph for a single query. See the [module
+/// documentation](self) for more information.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MirPlan {
+    /// The nodes in the plan, in topological order. The last node is the leaf of the query.
+    pub nodes: Vec<MirPlanNode>,
+}
+
+impl MirPlan {
+    /// Build a plan describing the query whose leaf node in `graph` is `leaf`, including all of
+    /// the ancestors of the leaf
+    pub fn for_leaf(graph: &MirGraph, leaf: NodeIndex) -> MirPlan {
+        fn visit(
+            graph: &MirGraph,
+            node: NodeIndex,
+            positions: &mut HashMap<NodeIndex, usize>,
+            nodes: &mut Vec<MirPlanNode>,
+        ) -> usize {
+            if let Some(position) = positions.get(&node) {
+                return *position;
+            }
+            let parents = graph
+                .sorted_ancestors(node)
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|parent| visit(graph, parent, positions, nodes))
+                .collect();
+            nodes.push(MirPlanNode {
+                name: graph[node].name().clone(),
+                inner: graph[node].inner.clone(),
+                parents,
+            });
+            positions.insert(node, nodes.len() - 1);
+            nodes.len() - 1
+        }
+
+        let mut nodes = vec![];
+        visit(graph, leaf, &mut HashMap::new(), &mut nodes);
+        MirPlan { nodes }
+    }
+
+    /// Check that the plan is structurally valid: that it's in topological order, ends in a single
+    /// leaf node which every other node feeds into, and that each node has the right number of
+    /// parents for its type.
+    pub fn validate(&self) -> ReadySetResult<()> {
+        let last = match self.nodes.last() {
+            Some(last) => last,
+            None => invalid!("MIR plan must contain at least one node"),
+        };
+        if !matches!(last.inner, MirNodeInner::Leaf { .. }) {
+            invalid!("The last node in a MIR plan must be a leaf");
+        }
+
+        let mut has_children = vec![false; self.nodes.len()];
+        for (i, node) in self.nodes.iter().enumerate() {
+            if i != self.nodes.len() - 1 && matches!(node.inner, MirNodeInner::Leaf { .. }) {
+                invalid!(
+                    "Node {i} ({}) is a leaf, but is not last in the plan",
+                    node.name
+                );
+            }
+            for &parent in &node.parents {
+                if parent >= i {
+                    invalid!(
+                        "Node {i} ({}) has parent {parent}, which does not come before it",
+                        node.name
+                    );
+                }
+                has_children[parent] = true;
+            }
+
+            let num_parents = node.parents.len();
+            let valid = match &node.inner {
+                MirNodeInner::Base { .. } => num_parents == 0,
+                MirNodeInner::Join { .. }
+                | MirNodeInner::LeftJoin { .. }
+                | MirNodeInner::AntiJoin { .. }
+                | MirNodeInner::DependentJoin { .. }
+                | MirNodeInner::DependentAntiJoin { .. }
+                | MirNodeInner::JoinAggregates => num_parents == 2,
+                MirNodeInner::Union { emit, .. } => num_parents > 0 && num_parents == emit.len(),
+                _ => num_parents == 1,
+            };
+            if !valid {
+                invalid!(
+                    "Node {i} ({}) has the wrong number of parents ({num_parents}) for a {} node",
+                    node.name,
+                    node.inner.description()
+                );
+            }
+        }
+
+        if let Some(i) = has_children
+            .iter()
+            .take(self.nodes.len() - 1)
+            .position(|has_children| !has_children)
+        {
+            invalid!(
+                "Node {i} ({}) is not used by any other node in the plan",
+                self.nodes[i].name
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Validate this plan, then add its nodes to `graph` as nodes owned by `query_name`, returning
+    /// the index of the new leaf node.
+    ///
+    /// Base table nodes in the plan are resolved to existing nodes in the graph by calling
+    /// `resolve_base` with their name, and must have the same columns as the existing base table.
+    /// Every column referenced by each of the other nodes must be provided by that node's parents.
+    /// If the plan is invalid, returns an error - in which case some of the plan's nodes may have
+    /// already been added to `graph`, so it should be discarded.
+    pub fn add_to_graph<F>(
+        &self,
+        query_name: &Relation,
+        graph: &mut MirGraph,
+        resolve_base: F,
+    ) -> ReadySetResult<NodeIndex>
+    where
+        F: Fn(&Relation) -> Option<NodeIndex>,
+    {
+        self.validate()?;
+
+        let mut bases = HashMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            let column_specs = match &node.inner {
+                MirNodeInner::Base { column_specs, .. } => column_specs,
+                _ => continue,
+            };
+            let base = resolve_base(&node.name)
+                .filter(|&ni| graph.contains_node(ni) && graph[ni].is_base())
+                .ok_or_else(|| invalid_err!("Base table {} does not exist", node.name))?;
+            match &graph[base].inner {
+                MirNodeInner::Base {
+                    column_specs: existing,
+                    ..
+                } if existing == column_specs => {}
+                _ => invalid!(
+                    "Columns of base table {} do not match its current schema",
+                    node.name
+                ),
+            }
+            bases.insert(i, base);
+        }
+
+        let mut added = Vec::with_capacity(self.nodes.len());
+        for (i, node) in self.nodes.iter().enumerate() {
+            let ni = match bases.get(&i) {
+                Some(base) => *base,
+                None => {
+                    let mut inner = node.inner.clone();
+                    // The plan may have been taken from a query that was already lowered, but the
+                    // nodes we're adding here haven't been
+                    if let MirNodeInner::Leaf { lowered_to_df, .. } = &mut inner {
+                        *lowered_to_df = false;
+                    }
+                    let mut mir_node = MirNode::new(node.name.clone(), inner);
+                    mir_node.add_owner(query_name.clone());
+                    let ni = graph.add_node(mir_node);
+                    for (edge, &parent) in node.parents.iter().enumerate() {
+                        graph.add_edge(added[parent], ni, edge);
+                    }
+                    ni
+                }
+            };
+            added.push(ni);
+        }
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            if bases.contains_key(&i) {
+                continue;
+            }
+            let parents = node
+                .parents
+                .iter()
+                .map(|&parent| graph.columns(added[parent]))
+                .collect::<Vec<_>>();
+            let referenced = match &node.inner {
+                // Alias tables rename all the columns of their parent
+                MirNodeInner::AliasTable { .. } => vec![],
+                // Each parent of a union emits its own set of columns
+                MirNodeInner::Union { emit, .. } => {
+                    for (parent, (columns, provided)) in emit.iter().zip(&parents).enumerate() {
+                        if let Some(column) = columns.iter().find(|c| !provided.contains(*c)) {
+                            invalid!(
+                                "Column {column} emitted by node {i} ({}) for parent {parent} is \
+                                 not provided by that parent",
+                                node.name
+                            );
+                        }
+                    }
+                    vec![]
+                }
+                // Paginate and distinct nodes generate a column of their own
+                MirNodeInner::Paginate { .. } => graph
+                    .referenced_columns(added[i])
+                    .into_iter()
+                    .filter(|c| c.name != *PAGE_NUMBER_COL)
+                    .collect(),
+                MirNodeInner::Distinct { group_by } => group_by.clone(),
+                _ => graph.referenced_columns(added[i]),
+            };
+            if let Some(column) = referenced
+                .iter()
+                .find(|c| !parents.iter().any(|columns| columns.contains(*c)))
+            {
+                invalid!(
+                    "Column {column} referenced by node {i} ({}) is not provided by its parents",
+                    node.name
+                );
+            }
+        }
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            let on = match &node.inner {
+                MirNodeInner::Join { on, .. }
+                | MirNodeInner::LeftJoin { on, .. }
+                | MirNodeInner::AntiJoin { on, .. } => on,
+                _ => continue,
+            };
+            let left = graph.columns(added[node.parents[0]]);
+            let right = graph.columns(added[node.parents[1]]);
+            for (l, r) in on {
+                if !left.contains(l) || !right.contains(r) {
+                    invalid!(
+                        "Join key ({l}, {r}) of node {i} ({}) does not match the columns of its \
+                         left and right parents",
+                        node.name
+                    );
+                }
+            }
+        }
+
+        #[allow(clippy::unwrap_used)] // validate checks the plan isn't empty
+        Ok(*added.last().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::IndexType;
+    use nom_sql::{ColumnSpecification, SqlType};
+
+    use super::*;
+    use crate::Column;
+
+    fn base(name: &str, columns: &[&str]) -> MirNode {
+        MirNode::new(
+            name.into(),
+            MirNodeInner::Base {
+                column_specs: columns
+                    .iter()
+                    .map(|col| ColumnSpecification {
+                        column: nom_sql::Column {
+                            name: (*col).into(),
+                            table: Some(name.into()),
+                        },
+                        sql_type: SqlType::Int(None),
+                        constraints: vec![],
+                        comment: None,
+                    })
+                    .collect(),
+                primary_key: None,
+                unique_keys: Default::default(),
+                check_constraints: vec![],
+                duplicate_keys: Default::default(),
+            },
+        )
+    }
+
+    fn column(table: &str, name: &str) -> Column {
+        Column::new(Some(table.into()), name)
+    }
+
+    /// Build the graph for `SELECT t1.a, t2.b FROM t1 JOIN t2 ON t1.a = t2.a`, returning the
+    /// graph and the query's leaf
+    fn join_query() -> (MirGraph, NodeIndex) {
+        let query_name: Relation = "q".into();
+        let mut graph = MirGraph::new();
+        let t1 = graph.add_node(base("t1", &["a"]));
+        let t2 = graph.add_node(base("t2", &["a", "b"]));
+
+        let mut join = MirNode::new(
+            "q_join".into(),
+            MirNodeInner::Join {
+                on: vec![(column("t1", "a"), column("t2", "a"))],
+                project: vec![column("t1", "a"), column("t2", "b")],
+            },
+        );
+        join.add_owner(query_name.clone());
+        let join = graph.add_node(join);
+        graph.add_edge(t1, join, 0);
+        graph.add_edge(t2, join, 1);
+
+        let mut leaf = MirNode::new("q".into(), MirNodeInner::leaf(vec![], IndexType::HashMap));
+        leaf.add_owner(query_name);
+        let leaf = graph.add_node(leaf);
+        graph.add_edge(join, leaf, 0);
+
+        (graph, leaf)
+    }
+
+    fn resolve_base(graph: &MirGraph) -> impl Fn(&Relation) -> Option<NodeIndex> + '_ {
+        |name| graph.node_indices().find(|&ni| graph[ni].name() == name)
+    }
+
+    #[test]
+    fn round_trip() {
+        let (mut graph, leaf) = join_query();
+        let plan = MirPlan::for_leaf(&graph, leaf);
+        assert_eq!(plan.nodes.len(), 4);
+        assert_eq!(plan.nodes[2].parents, vec![0, 1]);
+        assert_eq!(plan.nodes[3].parents, vec![2]);
+        plan.validate().unwrap();
+
+        let bases = graph.clone();
+        let new_leaf = plan
+            .add_to_graph(&"q2".into(), &mut graph, resolve_base(&bases))
+            .unwrap();
+        assert_eq!(graph.node_count(), 6);
+        assert!(graph[new_leaf].is_owned_by(&"q2".into()));
+        assert_eq!(graph.columns(new_leaf), graph.columns(leaf));
+    }
+
+    #[test]
+    fn swapped_join_parents_must_swap_keys() {
+        let (mut graph, leaf) = join_query();
+        let mut plan = MirPlan::for_leaf(&graph, leaf);
+        plan.nodes[2].parents.reverse();
+        let bases = graph.clone();
+        plan.clone()
+            .add_to_graph(&"q2".into(), &mut graph.clone(), resolve_base(&bases))
+            .unwrap_err();
+
+        if let MirNodeInner::Join { on, .. } = &mut plan.nodes[2].inner {
+            for (l, r) in on {
+                std::mem::swap(l, r);
+            }
+        }
+        plan.add_to_graph(&"q2".into(), &mut graph, resolve_base(&bases))
+            .unwrap();
+    }
+
+    #[test]
+    fn invalid_structure() {
+        let (mut graph, leaf) = join_query();
+        let plan = MirPlan::for_leaf(&graph, leaf);
+
+        let mut missing_parent = plan.clone();
+        missing_parent.nodes[2].parents.pop();
+        missing_parent.validate().unwrap_err();
+
+        let mut out_of_order = plan.clone();
+        out_of_order.nodes.swap(2, 3);
+        out_of_order.validate().unwrap_err();
+
+        let mut unknown_base = plan.clone();
+        unknown_base.nodes[0].name = "t3".into();
+        let bases = graph.clone();
+        unknown_base
+            .add_to_graph(&"q2".into(), &mut graph, resolve_base(&bases))
+            .unwrap_err();
+
+        let mut unknown_column = plan;
+        if let MirNodeInner::Join { project, .. } = &mut unknown_column.nodes[2].inner {
+            project.push(column("t1", "c"));
+        }
+        unknown_column.validate().unwrap();
+        unknown_column
+            .add_to_graph(&"q2".into(), &mut graph, resolve_base(&bases))
+            .unwrap_err();
+    }
+}
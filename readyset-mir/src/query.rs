@@ -9,6 +9,7 @@ use readyset_errors::{internal_err, ReadySetError, ReadySetResult};
 use crate::graph::MirGraph;
 use crate::node::{MirNode, MirNodeInner};
 use crate::rewrite::decorrelate::eliminate_dependent_joins;
+use crate::rewrite::predicate_pushdown::push_filters_below_joins;
 use crate::rewrite::pull_columns::pull_all_required_columns;
 use crate::{DfNodeIndex, NodeIndex};
 
@@ -246,8 +247,14 @@ impl MirQuery<'_> {
 
     /// Run a set of rewrite and optimization passes on this [`MirQuery`],
     /// and returns the modified query.
-    pub fn rewrite(mut self) -> ReadySetResult<Self> {
+    ///
+    /// If `filter_pushdown` is false, filters are left where they were placed during SQL-to-MIR
+    /// conversion, rather than being pushed below joins.
+    pub fn rewrite(mut self, filter_pushdown: bool) -> ReadySetResult<Self> {
         eliminate_dependent_joins(&mut self)?;
+        if filter_pushdown {
+            push_filters_below_joins(&mut self)?;
+        }
         pull_all_required_columns(&mut self)?;
         Ok(self)
     }
@@ -3,7 +3,7 @@ use std::iter;
 use itertools::{Either, Itertools};
 use nom_sql::analysis::ReferredColumns;
 use nom_sql::{BinaryOperator, Expr};
-use readyset_errors::{internal, invariant, unsupported, ReadySetResult};
+use readyset_errors::{internal, invariant, unsupported_feature, ReadySetResult};
 use readyset_tracing::trace;
 use tracing::instrument;
 
@@ -20,6 +20,9 @@ use crate::{Column, NodeIndex};
 /// - [`Project`], [`Join`], [`LeftJoin`], and dependent joins *other* than the one this filter
 ///   depends on are all totally commutative with filters, so can be swapped in position with those
 ///   filters with impunity
+/// - Anti-joins (dependent or otherwise) are commutative with filters on their left-hand side, but
+///   not their right-hand side, since they don't project any columns from the right. A dependent
+///   anti-join can only absorb dependent filters which can be turned into join keys.
 /// - Grouped nodes ([`Aggregation`] and [`Extremum`]) require adding any *non* dependent columns
 ///   mentioned in the filter to the group-by of the node.
 /// - All other nodes currently return an [unsupported error][] - it *is* theoretically possible to
@@ -45,7 +48,10 @@ fn push_dependent_filter(
     if children.len() != 1 {
         // TODO: this probably happens for unions; we should try to deal with that at some point
         // (see what the HyPer and SQL Server papers say about disjunctive predicates)
-        unsupported!();
+        unsupported_feature!(
+            CorrelatedSubquery,
+            "Can't push a dependent filter below a node with more than one child"
+        );
     }
 
     let child_idx = *children.get(0).unwrap();
@@ -83,6 +89,7 @@ fn push_dependent_filter(
         node_idx.index(),
         child_idx.index()
     );
+    let from_left = query.ancestors(child_idx)?.first() == Some(&node_idx);
     let should_insert = match &mut query.get_node_mut(child_idx).unwrap().inner {
         MirNodeInner::DependentJoin { on, .. } if child_idx == dependent_join_idx => {
             match dependency {
@@ -95,6 +102,26 @@ fn push_dependent_filter(
                 DependentCondition::FullyDependent { .. } => true,
             }
         }
+        MirNodeInner::DependentAntiJoin { on, .. } if child_idx == dependent_join_idx => {
+            match dependency {
+                DependentCondition::JoinKey { lhs, rhs } => {
+                    // Anti-joins don't project columns from the right, so only the left side of
+                    // the new join key needs to be projected
+                    on.push((lhs.clone(), rhs));
+                    query.graph.add_column(child_idx, lhs)?;
+                    false
+                }
+                // Rows from the right of an anti-join aren't in its output, so there's nowhere to
+                // lift the filter to
+                DependentCondition::FullyDependent { .. } => unsupported_feature!(
+                    CorrelatedSubquery,
+                    "NOT EXISTS subqueries can only be correlated by equality between columns"
+                ),
+            }
+        }
+        // Filters can only be lifted above an anti-join from its left, since it doesn't project
+        // any columns from its right
+        MirNodeInner::AntiJoin { .. } | MirNodeInner::DependentAntiJoin { .. } if from_left => true,
         MirNodeInner::Project { .. }
         | MirNodeInner::Filter { .. }
         | MirNodeInner::Join { .. }
@@ -107,7 +134,8 @@ fn push_dependent_filter(
             }
             true
         }
-        inner => unsupported!(
+        inner => unsupported_feature!(
+            CorrelatedSubquery,
             "Don't know how to push filter below {} to decorrelate",
             inner.description()
         ),
@@ -273,6 +301,10 @@ pub(crate) fn eliminate_dependent_joins(query: &mut MirQuery<'_>) -> ReadySetRes
                     on: on.clone(),
                     project: project.clone(),
                 },
+                MirNodeInner::DependentAntiJoin { on, project } => MirNodeInner::AntiJoin {
+                    on: on.clone(),
+                    project: project.clone(),
+                },
                 _ => unreachable!("Already checked is_dependent_join above"),
             };
             query.get_node_mut(join).unwrap().inner = new_inner;
@@ -346,6 +378,8 @@ mod tests {
                 }],
                 primary_key: Some([Column::new(Some("t2"), "a")].into()),
                 unique_keys: Default::default(),
+                check_constraints: vec![],
+                duplicate_keys: Default::default(),
             },
         ));
         graph[t2].add_owner(query_name.clone());
@@ -432,6 +466,8 @@ mod tests {
                 }],
                 primary_key: Some([Column::from("a")].into()),
                 unique_keys: Default::default(),
+                check_constraints: vec![],
+                duplicate_keys: Default::default(),
             },
         ));
         graph[t1].add_owner(query_name.clone());
@@ -551,6 +587,8 @@ mod tests {
                 ],
                 primary_key: Some([Column::new(Some("t2"), "a")].into()),
                 unique_keys: Default::default(),
+                check_constraints: vec![],
+                duplicate_keys: Default::default(),
             },
         ));
         graph[t2].add_owner(query_name.clone());
@@ -672,6 +710,8 @@ mod tests {
                 }],
                 primary_key: Some([Column::from("a")].into()),
                 unique_keys: Default::default(),
+                check_constraints: vec![],
+                duplicate_keys: Default::default(),
             },
         ));
         graph[t1].add_owner(query_name.clone());
@@ -1,2 +1,3 @@
 pub mod decorrelate;
+pub mod predicate_pushdown;
 pub mod pull_columns;
@@ -0,0 +1,402 @@
+//! A MIR rewrite pass that pushes [filter][] nodes below the [joins][] they sit on top of, so that
+//! rows which are going to be discarded anyway are discarded before they are joined (and before
+//! they end up materialized in the join's state).
+//!
+//! The SQL-to-MIR conversion process places all of a query's filters (including those that only
+//! mention a single table) after all of its joins, so without this pass every row of every table
+//! in a query flows through each of that query's joins.
+//!
+//! [filter]: MirNodeInner::Filter
+//! [joins]: MirNodeInner::Join
+use nom_sql::analysis::ReferredColumns;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use readyset_errors::{internal_err, ReadySetResult};
+use readyset_tracing::trace;
+use tracing::instrument;
+
+use crate::node::MirNodeInner;
+use crate::query::MirQuery;
+use crate::{Column, NodeIndex};
+
+/// Returns true if the given node is only used by a single query, and hasn't been converted to
+/// dataflow yet, meaning it's safe to move around in the graph without changing the results of
+/// other queries or the shape of an existing part of the graph.
+fn exclusively_owned(query: &MirQuery<'_>, node_idx: NodeIndex) -> bool {
+    let node = &query.graph[node_idx];
+    node.owners().len() == 1 && node.df_node_index().is_none()
+}
+
+/// Find the join that the given `filter_idx` could be pushed below, and the parent of that join it
+/// should be pushed onto, if any. Returns a tuple of `(join, parent)`.
+///
+/// A filter can be pushed below a join if:
+///
+/// - The filter is separated from the join only by a (possibly empty) chain of other filters.
+///   Filters are commutative with each other, so the order they're applied in doesn't matter.
+/// - The filter, the join, and every filter in between are exclusively owned by this query, and
+///   each of them has exactly one child
+/// - All of the columns the filter references are provided by a single parent of the join
+/// - For [`LeftJoin`]s, that parent is the left parent. Filtering on columns from the right side of
+///   a left join *after* the join also discards rows that didn't match anything on the right, which
+///   filtering before the join would not.
+/// - For [`AntiJoin`]s, that parent is also the left parent, since anti-joins don't project any
+///   columns from the right.
+///
+/// [`LeftJoin`]: MirNodeInner::LeftJoin
+/// [`AntiJoin`]: MirNodeInner::AntiJoin
+fn push_target(
+    query: &MirQuery<'_>,
+    filter_idx: NodeIndex,
+) -> ReadySetResult<Option<(NodeIndex, NodeIndex)>> {
+    let columns = match &query.graph[filter_idx].inner {
+        MirNodeInner::Filter { conditions } => conditions
+            .referred_columns()
+            .map(|c| Column::from(c.clone()))
+            .collect::<Vec<_>>(),
+        _ => return Ok(None),
+    };
+    if columns.is_empty() || !exclusively_owned(query, filter_idx) {
+        return Ok(None);
+    }
+
+    let mut node = filter_idx;
+    let join_idx = loop {
+        let parent = match query.ancestors(node)?.as_slice() {
+            [parent] => *parent,
+            _ => return Ok(None),
+        };
+        if !exclusively_owned(query, parent) || query.descendants(parent)? != [node] {
+            return Ok(None);
+        }
+        match query.graph[parent].inner {
+            MirNodeInner::Filter { .. } => node = parent,
+            MirNodeInner::Join { .. }
+            | MirNodeInner::LeftJoin { .. }
+            | MirNodeInner::AntiJoin { .. } => break parent,
+            _ => return Ok(None),
+        }
+    };
+
+    let parents = query.ancestors(join_idx)?;
+    let candidates = if matches!(
+        query.graph[join_idx].inner,
+        MirNodeInner::LeftJoin { .. } | MirNodeInner::AntiJoin { .. }
+    ) {
+        &parents[..1.min(parents.len())]
+    } else {
+        &parents[..]
+    };
+
+    Ok(candidates
+        .iter()
+        .copied()
+        .find(|&parent| {
+            columns
+                .iter()
+                .all(|c| query.graph.provides_column(parent, c))
+        })
+        .map(|parent| (join_idx, parent)))
+}
+
+/// Move the filter node `filter_idx` out of its current position in the graph, to sit between
+/// `join_idx` and its parent `parent_idx`.
+fn push_below_join(
+    query: &mut MirQuery<'_>,
+    filter_idx: NodeIndex,
+    join_idx: NodeIndex,
+    parent_idx: NodeIndex,
+) -> ReadySetResult<()> {
+    trace!(
+        filter = %filter_idx.index(),
+        join = %join_idx.index(),
+        parent = %parent_idx.index(),
+        "Pushing filter below join"
+    );
+
+    // filter_parent -> filter -> [children]  =>  filter_parent -> [children]
+    let filter_parent_edge = query
+        .graph
+        .edges_directed(filter_idx, Direction::Incoming)
+        .next()
+        .ok_or_else(|| internal_err!("Filter has no parent"))?;
+    let (filter_parent_edge, filter_parent) =
+        (filter_parent_edge.id(), filter_parent_edge.source());
+    query.graph.remove_edge(filter_parent_edge);
+    let child_edges = query
+        .graph
+        .edges_directed(filter_idx, Direction::Outgoing)
+        .map(|e| (e.id(), e.target(), *e.weight()))
+        .collect::<Vec<_>>();
+    for (edge, child, weight) in child_edges {
+        query.graph.remove_edge(edge);
+        query.graph.add_edge(filter_parent, child, weight);
+    }
+
+    // parent -> join  =>  parent -> filter -> join
+    let parent_edge = query
+        .graph
+        .find_edge(parent_idx, join_idx)
+        .ok_or_else(|| internal_err!("Node is not a parent of the join"))?;
+    #[allow(clippy::unwrap_used)] // we just found the edge
+    let weight = query.graph.remove_edge(parent_edge).unwrap();
+    query.graph.add_edge(parent_idx, filter_idx, 0);
+    query.graph.add_edge(filter_idx, join_idx, weight);
+
+    Ok(())
+}
+
+/// Push all filters in the given query as far below the joins in the query as they can go without
+/// changing the results of the query.
+///
+/// This runs before [`pull_all_required_columns`][], so doesn't need to make sure the columns the
+/// filters reference are projected by the nodes they end up below.
+///
+/// [`pull_all_required_columns`]: crate::rewrite::pull_columns::pull_all_required_columns
+#[instrument(skip_all, fields(query = %query.name()))]
+pub(crate) fn push_filters_below_joins(query: &mut MirQuery<'_>) -> ReadySetResult<()> {
+    // Every push moves a filter strictly further up the graph, so this terminates - but pushing
+    // one filter can make another one pushable (eg if the first filter was pushed below a join
+    // which is itself the child of another join), so we have to keep looking until there's
+    // nothing left to do.
+    'outer: loop {
+        for node in query.topo_nodes() {
+            if node == query.leaf() {
+                continue;
+            }
+
+            if let Some((join, parent)) = push_target(query, node)? {
+                push_below_join(query, node, join, parent)?;
+                continue 'outer;
+            }
+        }
+
+        break;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use common::IndexType;
+    use nom_sql::{BinaryOperator, ColumnSpecification, Expr, Literal, Relation, SqlType};
+
+    use super::*;
+    use crate::graph::MirGraph;
+    use crate::node::MirNode;
+    use crate::rewrite::pull_columns::pull_all_required_columns;
+
+    fn base(graph: &mut MirGraph, query_name: &Relation, table: &str) -> NodeIndex {
+        let node = graph.add_node(MirNode::new(
+            table.into(),
+            MirNodeInner::Base {
+                column_specs: ["a", "b"]
+                    .into_iter()
+                    .map(|col| ColumnSpecification {
+                        column: nom_sql::Column {
+                            name: col.into(),
+                            table: Some(table.into()),
+                        },
+                        sql_type: SqlType::Int(None),
+                        constraints: vec![],
+                        comment: None,
+                    })
+                    .collect(),
+                primary_key: Some([Column::new(Some(table), "a")].into()),
+                unique_keys: Default::default(),
+                check_constraints: vec![],
+                duplicate_keys: Default::default(),
+            },
+        ));
+        graph[node].add_owner(query_name.clone());
+        node
+    }
+
+    fn equals_one(column: &str) -> Expr {
+        Expr::BinaryOp {
+            lhs: Box::new(Expr::Column(column.into())),
+            op: BinaryOperator::Equal,
+            rhs: Box::new(Expr::Literal(Literal::Integer(1))),
+        }
+    }
+
+    struct TestQuery {
+        graph: MirGraph,
+        query_name: Relation,
+        t1: NodeIndex,
+        t2: NodeIndex,
+        join: NodeIndex,
+        filters: Vec<NodeIndex>,
+        leaf: NodeIndex,
+    }
+
+    impl TestQuery {
+        /// Builds a query that looks like:
+        ///
+        /// ```sql
+        /// SELECT * FROM t1 [LEFT] JOIN t2 ON t1.a = t2.a WHERE <conditions>
+        /// ```
+        ///
+        /// with each of the `conditions` in its own filter node, in a chain after the join.
+        fn new(left: bool, conditions: Vec<Expr>) -> Self {
+            let mut graph = MirGraph::new();
+            let query_name = Relation::from("q");
+
+            let t1 = base(&mut graph, &query_name, "t1");
+            let t2 = base(&mut graph, &query_name, "t2");
+
+            let on = vec![(Column::new(Some("t1"), "a"), Column::new(Some("t2"), "a"))];
+            let project = vec![
+                Column::new(Some("t1"), "a"),
+                Column::new(Some("t1"), "b"),
+                Column::new(Some("t2"), "a"),
+                Column::new(Some("t2"), "b"),
+            ];
+            let join = graph.add_node(MirNode::new(
+                "join".into(),
+                if left {
+                    MirNodeInner::LeftJoin { on, project }
+                } else {
+                    MirNodeInner::Join { on, project }
+                },
+            ));
+            graph[join].add_owner(query_name.clone());
+            graph.add_edge(t1, join, 0);
+            graph.add_edge(t2, join, 1);
+
+            let mut prev = join;
+            let mut filters = vec![];
+            for (i, conditions) in conditions.into_iter().enumerate() {
+                let filter = graph.add_node(MirNode::new(
+                    format!("filter_{i}").into(),
+                    MirNodeInner::Filter { conditions },
+                ));
+                graph[filter].add_owner(query_name.clone());
+                graph.add_edge(prev, filter, 0);
+                filters.push(filter);
+                prev = filter;
+            }
+
+            let leaf = graph.add_node(MirNode::new(
+                "q".into(),
+                MirNodeInner::leaf(vec![], IndexType::HashMap),
+            ));
+            graph[leaf].add_owner(query_name.clone());
+            graph.add_edge(prev, leaf, 0);
+
+            TestQuery {
+                graph,
+                query_name,
+                t1,
+                t2,
+                join,
+                filters,
+                leaf,
+            }
+        }
+
+        fn push(&mut self) -> MirQuery<'_> {
+            let mut query = MirQuery::new(self.query_name.clone(), self.leaf, &mut self.graph);
+            push_filters_below_joins(&mut query).unwrap();
+            pull_all_required_columns(&mut query).unwrap();
+            query
+        }
+    }
+
+    #[test]
+    fn inner_join_left_side() {
+        let mut q = TestQuery::new(false, vec![equals_one("t1.b")]);
+        let (t1, t2, join, filter, leaf) = (q.t1, q.t2, q.join, q.filters[0], q.leaf);
+        let query = q.push();
+
+        assert_eq!(query.descendants(t1).unwrap(), vec![filter]);
+        assert_eq!(query.ancestors(join).unwrap(), vec![filter, t2]);
+        assert_eq!(query.ancestors(leaf).unwrap(), vec![join]);
+    }
+
+    #[test]
+    fn inner_join_right_side() {
+        let mut q = TestQuery::new(false, vec![equals_one("t2.b")]);
+        let (t1, t2, join, filter, leaf) = (q.t1, q.t2, q.join, q.filters[0], q.leaf);
+        let query = q.push();
+
+        assert_eq!(query.descendants(t2).unwrap(), vec![filter]);
+        assert_eq!(query.ancestors(join).unwrap(), vec![t1, filter]);
+        assert_eq!(query.ancestors(leaf).unwrap(), vec![join]);
+    }
+
+    #[test]
+    fn left_join_left_side() {
+        let mut q = TestQuery::new(true, vec![equals_one("t1.b")]);
+        let (t2, join, filter) = (q.t2, q.join, q.filters[0]);
+        let query = q.push();
+
+        assert_eq!(query.ancestors(join).unwrap(), vec![filter, t2]);
+    }
+
+    #[test]
+    fn left_join_right_side() {
+        let mut q = TestQuery::new(true, vec![equals_one("t2.b")]);
+        let (t1, t2, join, filter, leaf) = (q.t1, q.t2, q.join, q.filters[0], q.leaf);
+        let query = q.push();
+
+        // Filtering the right side of a left join before the join would turn rows that are
+        // currently filtered out into rows with nulls on the right, so the filter has to stay put
+        assert_eq!(query.ancestors(join).unwrap(), vec![t1, t2]);
+        assert_eq!(query.ancestors(filter).unwrap(), vec![join]);
+        assert_eq!(query.ancestors(leaf).unwrap(), vec![filter]);
+    }
+
+    #[test]
+    fn both_sides() {
+        let mut q = TestQuery::new(
+            false,
+            vec![Expr::BinaryOp {
+                lhs: Box::new(Expr::Column("t1.b".into())),
+                op: BinaryOperator::Equal,
+                rhs: Box::new(Expr::Column("t2.b".into())),
+            }],
+        );
+        let (t1, t2, join, filter) = (q.t1, q.t2, q.join, q.filters[0]);
+        let query = q.push();
+
+        assert_eq!(query.ancestors(join).unwrap(), vec![t1, t2]);
+        assert_eq!(query.ancestors(filter).unwrap(), vec![join]);
+    }
+
+    #[test]
+    fn chained_filters() {
+        let mut q = TestQuery::new(
+            false,
+            vec![
+                equals_one("t1.b"),
+                Expr::BinaryOp {
+                    lhs: Box::new(Expr::Column("t1.b".into())),
+                    op: BinaryOperator::Equal,
+                    rhs: Box::new(Expr::Column("t2.b".into())),
+                },
+                equals_one("t2.b"),
+            ],
+        );
+        let (t1, t2, join, filters, leaf) = (q.t1, q.t2, q.join, q.filters.clone(), q.leaf);
+        let query = q.push();
+
+        assert_eq!(query.descendants(t1).unwrap(), vec![filters[0]]);
+        assert_eq!(query.descendants(t2).unwrap(), vec![filters[2]]);
+        assert_eq!(query.ancestors(join).unwrap(), vec![filters[0], filters[2]]);
+        assert_eq!(query.ancestors(filters[1]).unwrap(), vec![join]);
+        assert_eq!(query.ancestors(leaf).unwrap(), vec![filters[1]]);
+    }
+
+    #[test]
+    fn shared_join() {
+        let mut q = TestQuery::new(false, vec![equals_one("t1.b")]);
+        let (t1, t2, join, filter) = (q.t1, q.t2, q.join, q.filters[0]);
+        q.graph[join].add_owner("other_query".into());
+        let query = q.push();
+
+        assert_eq!(query.ancestors(join).unwrap(), vec![t1, t2]);
+        assert_eq!(query.ancestors(filter).unwrap(), vec![join]);
+    }
+}
@@ -83,6 +83,8 @@ mod tests {
                 ],
                 primary_key: Some([Column::from("a")].into()),
                 unique_keys: Default::default(),
+                check_constraints: vec![],
+                duplicate_keys: Default::default(),
             },
         ))
     }
@@ -1,6 +1,7 @@
 use std::fmt::{self, Display, Formatter};
 
 use dataflow::ops::grouped::aggregate::Aggregation as AggregationKind;
+use dataflow::ops::grouped::approximate::ApproximateAggregation;
 use dataflow::ops::grouped::extremum::Extremum as ExtremumKind;
 use dataflow::ops::union;
 use dataflow::PostLookupAggregateFunction;
@@ -162,6 +163,12 @@ impl GraphViz for MirNodeInner {
                     AggregationKind::GroupConcat { separator: s } => {
                         format!("||({}, \"{}\")", on, s)
                     }
+                    AggregationKind::Approximate(ApproximateAggregation::CountDistinct) => {
+                        format!("~\\|d\\|({})", on)
+                    }
+                    AggregationKind::Approximate(ApproximateAggregation::PercentileCont {
+                        fraction,
+                    }) => format!("~%{}({})", fraction, on),
                 };
                 let group_cols = group_by.iter().join(", ");
                 write!(f, "{} | γ: {}", op_string, group_cols)
@@ -216,6 +223,7 @@ impl GraphViz for MirNodeInner {
                 limit,
                 returned_cols,
                 aggregates,
+                post_lookup_predicates,
                 ..
             } => {
                 let key_cols = keys.iter().map(|k| &k.0).join(", ");
@@ -263,12 +271,24 @@ impl GraphViz for MirNodeInner {
                     )?;
                 }
 
+                if !post_lookup_predicates.is_empty() {
+                    write!(
+                        f,
+                        "\\nresidual: {}",
+                        post_lookup_predicates.iter().join(" AND ")
+                    )?;
+                }
+
                 Ok(())
             }
             MirNodeInner::LeftJoin { ref on, .. } => {
                 let jc = on.iter().map(|(l, r)| format!("{}:{}", l, r)).join(", ");
                 write!(f, "⋉  | on: {}", jc)
             }
+            MirNodeInner::AntiJoin { ref on, .. } => {
+                let jc = on.iter().map(|(l, r)| format!("{}:{}", l, r)).join(", ");
+                write!(f, "▷  | on: {}", jc)
+            }
             MirNodeInner::DependentJoin { ref on, .. } => {
                 write!(
                     f,
@@ -276,6 +296,13 @@ impl GraphViz for MirNodeInner {
                     on.iter().map(|(l, r)| format!("{}:{}", l, r)).join(", ")
                 )
             }
+            MirNodeInner::DependentAntiJoin { ref on, .. } => {
+                write!(
+                    f,
+                    "⧑▷ | on: {}",
+                    on.iter().map(|(l, r)| format!("{}:{}", l, r)).join(", ")
+                )
+            }
             MirNodeInner::Latest { ref group_by } => {
                 let key_cols = group_by.iter().join(", ");
                 write!(f, "⧖ | γ: {}", key_cols)
@@ -4,6 +4,7 @@ use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::fmt::Formatter;
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 
 use async_trait::async_trait;
 use futures_util::StreamExt;
@@ -12,7 +13,7 @@ use mysql_async::consts::StatusFlags;
 use mysql_common::bigdecimal03::ToPrimitive;
 use mysql_srv::{
     CachedSchema, Column, ColumnFlags, ColumnType, InitWriter, MsqlSrvError, MySqlShim,
-    QueryResultWriter, RowWriter, StatementMetaWriter,
+    QueryResultWriter, RowSource, RowWriter, StatementMetaWriter,
 };
 use readyset_adapter::backend::noria_connector::{
     MetaVariable, SelectPrepareResult, SelectPrepareResultInner,
@@ -20,6 +21,8 @@ use readyset_adapter::backend::noria_connector::{
 use readyset_adapter::backend::{
     noria_connector, QueryResult, SinglePrepareResult, UpstreamPrepare,
 };
+use readyset_adapter::micro_cache::{EncodedResults, ResultEncoding};
+use readyset_client::results::ResultIterator;
 use readyset_data::{DfType, DfValue, DfValueKind};
 use readyset_errors::{internal, ReadySetError};
 use readyset_tracing::{error, trace};
@@ -52,7 +55,7 @@ impl<'a> fmt::Display for BinaryDisplay<'a> {
     }
 }
 
-async fn write_column<W: AsyncWrite + Unpin>(
+fn write_column<W: AsyncWrite + Unpin>(
     rw: &mut RowWriter<'_, W>,
     c: &DfValue,
     cs: &mysql_srv::Column,
@@ -160,6 +163,101 @@ async fn write_column<W: AsyncWrite + Unpin>(
     Ok(written?)
 }
 
+/// Write the rows of a read from ReadySet to `rw`.
+///
+/// If the rows came from the micro cache (`encoded` is set) and have already been encoded in the
+/// protocol `rw` writes, the cached encoding of the rows is written as-is. Otherwise, each value is
+/// encoded, and if the rows came from the micro cache their encoding is cached for subsequent
+/// reads. Encodings are cached separately for each of the connection character sets (`charset`)
+/// they're written in.
+async fn write_rows<W: AsyncWrite + Unpin>(
+    rw: &mut RowWriter<'_, W>,
+    rows: &mut ResultIterator,
+    mysql_schema: &[mysql_srv::Column],
+    column_types: &[DfType],
+    encoded: Option<Arc<EncodedResults>>,
+    charset: u16,
+) -> Result<(), Error> {
+    let encoding = if rw.is_binary() {
+        ResultEncoding::MySqlBinary { charset }
+    } else {
+        ResultEncoding::MySqlText { charset }
+    };
+    if let Some(encoded_rows) = encoded.as_ref().and_then(|encoded| encoded.get(encoding)) {
+        trace!("select::encoded_rows_hit");
+        for row in encoded_rows.iter() {
+            rw.write_encoded_row(row).await?;
+        }
+        return Ok(());
+    }
+
+    if encoded.is_some() {
+        rw.record_rows();
+    }
+    while let Some(row) = rows.next() {
+        for (c, ty, val) in izip!(mysql_schema.iter(), column_types.iter(), row.iter()) {
+            write_column(rw, val, c, ty)?;
+        }
+        rw.end_row().await?;
+    }
+    // Results which are streamed can end early if producing the rest of them fails
+    if let Some(e) = rows.take_error() {
+        return Err(e.into());
+    }
+    if let (Some(encoded), Some(recorded)) = (encoded, rw.take_recorded_rows()) {
+        encoded.insert(encoding, recorded);
+    }
+    Ok(())
+}
+
+/// The rows of a cached query returned through a read-only cursor, which are only encoded as the
+/// client fetches them
+struct CursorRows {
+    rows: ResultIterator,
+    mysql_schema: Vec<mysql_srv::Column>,
+    column_types: Vec<DfType>,
+}
+
+#[async_trait]
+impl<W: AsyncWrite + Unpin + Send> RowSource<W> for CursorRows {
+    async fn write_next_row(&mut self, rw: &mut RowWriter<'_, W>) -> io::Result<bool> {
+        let row = match self.rows.next() {
+            Some(row) => row,
+            None => {
+                return match self.rows.take_error() {
+                    Some(e) => Err(io::Error::new(io::ErrorKind::Other, e.to_string())),
+                    None => Ok(false),
+                }
+            }
+        };
+        for (c, ty, val) in izip!(
+            self.mysql_schema.iter(),
+            self.column_types.iter(),
+            row.iter()
+        ) {
+            write_column(rw, val, c, ty).map_err(|e| match e {
+                Error::Io(e) => e,
+                e => io::Error::new(io::ErrorKind::InvalidData, e.to_string()),
+            })?;
+        }
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl<W: AsyncWrite + Unpin + Send> RowSource<W> for upstream::CursorRows {
+    async fn write_next_row(&mut self, rw: &mut RowWriter<'_, W>) -> io::Result<bool> {
+        let row = match self.next().await {
+            Some(row) => row.map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?,
+            None => return Ok(false),
+        };
+        for (i, _) in row.columns_ref().iter().enumerate() {
+            rw.write_col(row.as_ref(i).expect("Must match column number"))?;
+        }
+        Ok(true)
+    }
+}
+
 async fn write_query_results<W: AsyncWrite + Unpin>(
     r: Result<(u64, u64), Error>,
     results: QueryResultWriter<'_, W>,
@@ -278,12 +376,17 @@ async fn write_meta_with_header<W: AsyncWrite + Unpin>(
 pub struct Backend {
     /// Handle to the backing noria client
     noria: readyset_adapter::Backend<MySqlUpstream, MySqlQueryHandler>,
+    /// The character set the client asked to use for the connection in its handshake
+    charset: u16,
 }
 
 impl Backend {
     #[allow(dead_code)]
     pub fn new(noria: readyset_adapter::Backend<MySqlUpstream, MySqlQueryHandler>) -> Self {
-        Backend { noria }
+        Backend {
+            noria,
+            charset: DEFAULT_CHARACTER_SET,
+        }
     }
 }
 
@@ -364,6 +467,7 @@ macro_rules! handle_error {
 async fn handle_readyset_result<'a, W>(
     result: noria_connector::QueryResult<'a>,
     writer: QueryResultWriter<'_, W>,
+    charset: u16,
 ) -> io::Result<()>
 where
     W: AsyncWrite + Unpin,
@@ -373,11 +477,19 @@ where
         noria_connector::QueryResult::Insert {
             num_rows_inserted,
             first_inserted_id,
-        } => write_query_results(Ok((num_rows_inserted, first_inserted_id)), writer, None).await,
+            oversized_rows,
+        } => {
+            let writer = writer.set_warnings(oversized_rows.try_into().unwrap_or(u16::MAX));
+            write_query_results(Ok((num_rows_inserted, first_inserted_id)), writer, None).await
+        }
         noria_connector::QueryResult::Update {
             num_rows_updated,
             last_inserted_id,
-        } => write_query_results(Ok((num_rows_updated, last_inserted_id)), writer, None).await,
+            oversized_rows,
+        } => {
+            let writer = writer.set_warnings(oversized_rows.try_into().unwrap_or(u16::MAX));
+            write_query_results(Ok((num_rows_updated, last_inserted_id)), writer, None).await
+        }
         noria_connector::QueryResult::Delete { num_rows_deleted } => {
             writer.completed(num_rows_deleted, 0, None).await
         }
@@ -388,22 +500,33 @@ where
         noria_connector::QueryResult::MetaWithHeader(vars) => {
             write_meta_with_header(vars, writer).await
         }
-        noria_connector::QueryResult::Select { mut rows, schema } => {
+        noria_connector::QueryResult::Select {
+            mut rows,
+            schema,
+            encoded,
+            truncated,
+        } => {
             let mysql_schema = convert_columns!(schema.schema, writer);
-            let mut rw = writer.start(&mysql_schema).await?;
-            while let Some(row) = rows.next() {
-                for (coli, (val, c)) in row.iter().zip(&mysql_schema).enumerate() {
-                    let ty = schema
-                        .schema
-                        .get(coli)
-                        .map(|cs| cs.column_type.clone())
-                        .unwrap_or_default();
-
-                    if let Err(e) = write_column(&mut rw, val, c, &ty).await {
-                        return handle_column_write_err(e, rw).await;
-                    }
-                }
-                rw.end_row().await?;
+            let column_types = schema
+                .schema
+                .iter()
+                .map(|cs| cs.column_type.clone())
+                .collect::<Vec<_>>();
+            let mut rw = writer
+                .start(&mysql_schema)
+                .await?
+                .set_warnings(truncated.into());
+            if let Err(e) = write_rows(
+                &mut rw,
+                &mut rows,
+                &mysql_schema,
+                &column_types,
+                encoded,
+                charset,
+            )
+            .await
+            {
+                return handle_column_write_err(e, rw).await;
             }
             rw.finish().await
         }
@@ -415,7 +538,7 @@ async fn handle_upstream_result<'a, W>(
     writer: QueryResultWriter<'_, W>,
 ) -> io::Result<()>
 where
-    W: AsyncWrite + Unpin,
+    W: AsyncWrite + Unpin + Send,
 {
     match result {
         upstream::QueryResult::Command { status_flags } => {
@@ -434,6 +557,20 @@ where
             )
             .await
         }
+        upstream::QueryResult::ReadResult {
+            stream: upstream::ReadResultStream::Cursor(rows),
+            columns,
+        } if writer.is_cursor() => {
+            // The rows are only read from upstream as the client fetches them
+            let formatted_cols = columns.iter().map(|c| c.into()).collect::<Vec<_>>();
+            let status_flags = rows.status_flags();
+            writer
+                .start(&formatted_cols)
+                .await?
+                .set_status_flags(status_flags)
+                .finish_with_source(Box::new(rows))
+                .await
+        }
         upstream::QueryResult::ReadResult {
             mut stream,
             columns,
@@ -484,12 +621,13 @@ where
 async fn handle_query_result<'a, W>(
     result: Result<QueryResult<'a, MySqlUpstream>, Error>,
     writer: QueryResultWriter<'_, W>,
+    charset: u16,
 ) -> io::Result<()>
 where
-    W: AsyncWrite + Unpin,
+    W: AsyncWrite + Unpin + Send,
 {
     match result {
-        Ok(QueryResult::Noria(result)) => handle_readyset_result(result, writer).await,
+        Ok(QueryResult::Noria(result)) => handle_readyset_result(result, writer, charset).await,
         Ok(QueryResult::Upstream(result)) => handle_upstream_result(result, writer).await,
         Err(error) => handle_error!(error, writer),
     }
@@ -611,8 +749,20 @@ where
             }
         };
 
+        // If the statement is proxied upstream, stream its rows from upstream as the client fetches
+        // them rather than reading them all up front
+        if let Some(upstream) = self.upstream_mut() {
+            upstream.set_cursor_execute(results.is_cursor());
+        }
+
+        let charset = self.charset;
         match self.execute(id, &value_params).await {
-            Ok(QueryResult::Noria(noria_connector::QueryResult::Select { mut rows, schema })) => {
+            Ok(QueryResult::Noria(noria_connector::QueryResult::Select {
+                mut rows,
+                schema,
+                encoded,
+                truncated,
+            })) => {
                 let CachedSchema {
                     mysql_schema,
                     column_types,
@@ -641,19 +791,31 @@ where
 
                 let mut rw = results
                     .start_with_cache(mysql_schema, preencoded_schema.clone())
-                    .await?;
-                while let Some(row) = rows.next() {
-                    for (c, ty, val) in izip!(mysql_schema.iter(), column_types.iter(), row.iter())
-                    {
-                        if let Err(e) = write_column(&mut rw, val, c, ty).await {
-                            return handle_column_write_err(e, rw).await;
-                        };
-                    }
-                    rw.end_row().await?;
+                    .await?
+                    .set_warnings(truncated.into());
+                if rw.is_cursor() {
+                    let source = CursorRows {
+                        rows,
+                        mysql_schema: mysql_schema.clone(),
+                        column_types: column_types.clone(),
+                    };
+                    return rw.finish_with_source(Box::new(source)).await;
+                }
+                if let Err(e) = write_rows(
+                    &mut rw,
+                    &mut rows,
+                    mysql_schema,
+                    column_types,
+                    encoded,
+                    charset,
+                )
+                .await
+                {
+                    return handle_column_write_err(e, rw).await;
                 }
                 rw.finish().await
             }
-            execute_result => handle_query_result(execute_result, results).await,
+            execute_result => handle_query_result(execute_result, results, charset).await,
         }
     }
 
@@ -683,8 +845,9 @@ where
     async fn on_close(&mut self, _: u32) {}
 
     async fn on_query(&mut self, query: &str, results: QueryResultWriter<'_, W>) -> io::Result<()> {
+        let charset = self.charset;
         let query_result = self.query(query).await;
-        handle_query_result(query_result, results).await
+        handle_query_result(query_result, results, charset).await
     }
 
     fn password_for_username(&self, username: &str) -> Option<Vec<u8>> {
@@ -695,6 +858,18 @@ where
         self.does_require_authentication()
     }
 
+    fn on_authenticated(&mut self, username: &str) {
+        self.set_user(username)
+    }
+
+    fn on_charset(&mut self, charset: u16) {
+        self.charset = charset;
+    }
+
+    fn connection_id(&self) -> u32 {
+        self.noria.connection_id().unwrap_or(8)
+    }
+
     fn version(&self) -> String {
         self.noria.version()
     }
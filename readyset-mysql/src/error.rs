@@ -24,23 +24,8 @@ impl Error {
     /// Transforms each error to the closest mysql error.
     /// Sometimes, there is not a good one and UNKNOWN is used.
     pub fn error_kind(&self) -> mysql_srv::ErrorKind {
-        // TODO(peter): Implement the rest of this error translation logic.
-        /*
-        match self {
-            MySqlError(_) => ErrorKind::ER_UNKNOWN_ERROR,
-            MySqlAsyncError(_) => ErrorKind::ER_UNKNOWN_ERROR,
-            NoriaReadError(_) => ErrorKind::ER_ERROR_ON_READ,
-            NoriaWriteError(_) => ErrorKind::ER_ERROR_ON_WRITE,
-            NoriaRecipeError(_) => ErrorKind::ER_UNKNOWN_ERROR,
-            ParseError(_) => ErrorKind::ER_PARSE_ERROR,
-            IOError(_) => ErrorKind::ER_IO_WRITE_ERROR,
-            UnimplementedError(_) => ErrorKind::ER_NOT_SUPPORTED_YET,
-            UnsupportedError(_) => ErrorKind::ER_NOT_SUPPORTED_YET,
-            Internal(_) => ErrorKind::ER_INTERNAL_ERROR,
-            MissingPreparedStatement => ErrorKind::ER_NEED_REPREPARE,
-        }
-         */
         match self {
+            Self::ReadySet(e) => readyset_error_kind(e),
             Self::MySql(mysql_async::Error::Server(e)) => e.code.into(),
             Self::MySql(_) => {
                 // TODO(peter): We need to translate these to appropriate
@@ -52,8 +37,83 @@ impl Error {
     }
 }
 
+/// Returns the mysql error (and hence SQLSTATE) which most closely matches the root cause of the
+/// given [`ReadySetError`], so that clients can react to the error (eg by retrying, or by
+/// re-preparing a statement) the same way they would if it came from MySQL itself.
+fn readyset_error_kind(e: &ReadySetError) -> mysql_srv::ErrorKind {
+    use mysql_srv::ErrorKind::*;
+
+    match e.root_cause() {
+        ReadySetError::UnparseableQuery { .. } => ER_PARSE_ERROR,
+        ReadySetError::Unsupported(_) | ReadySetError::UnsupportedFeature { .. } => {
+            ER_NOT_SUPPORTED_YET
+        }
+        ReadySetError::PreparedStatementMissing { .. } => ER_UNKNOWN_STMT_HANDLER,
+        ReadySetError::TableNotFound { .. }
+        | ReadySetError::TableNotReplicated { .. }
+        | ReadySetError::ViewNotFound(_)
+        | ReadySetError::ViewNotFoundInWorkers { .. }
+        | ReadySetError::ViewDestroyed => ER_NO_SUCH_TABLE,
+        ReadySetError::ViewAlreadyExists(_) => ER_TABLE_EXISTS_ERROR,
+        ReadySetError::NoSuchColumn(_) => ER_BAD_FIELD_ERROR,
+        ReadySetError::NoSuchFunction(_) => ER_SP_DOES_NOT_EXIST,
+        ReadySetError::ArityError(_) => ER_WRONG_PARAMCOUNT_TO_NATIVE_FCT,
+        ReadySetError::WrongColumnCount(..) => ER_WRONG_VALUE_COUNT_ON_ROW,
+        ReadySetError::NonNullable { .. } | ReadySetError::ColumnRequired { .. } => {
+            ER_BAD_NULL_ERROR
+        }
+        ReadySetError::ExprNotInGroupBy { .. } => ER_WRONG_FIELD_WITH_GROUP,
+        ReadySetError::CheckConstraintViolated { .. } => ER_CHECK_CONSTRAINT_VIOLATED,
+        ReadySetError::DuplicateEntry { .. } => ER_DUP_ENTRY,
+        ReadySetError::UpqueryTimeout => ER_QUERY_INTERRUPTED,
+        ReadySetError::ServerShuttingDown => ER_SERVER_SHUTDOWN,
+        ReadySetError::FallbackUnavailable(_) | ReadySetError::NamespaceQuotaExceeded { .. } => {
+            ER_OUT_OF_RESOURCES
+        }
+        ReadySetError::ResultSetTooLarge(_) => ER_TOO_BIG_SELECT,
+        ReadySetError::AccessDenied(_) => ER_SPECIFIC_ACCESS_DENIED_ERROR,
+        ReadySetError::Internal(_) => ER_INTERNAL_ERROR,
+        _ => ER_UNKNOWN_ERROR,
+    }
+}
+
 impl IsFatalError for Error {
     fn is_fatal(&self) -> bool {
         matches!(self, Self::MySql(e) if e.is_fatal())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapped_readyset_errors_use_root_cause() {
+        let err = Error::from(ReadySetError::RpcFailed {
+            during: "extend_recipe".into(),
+            source: Box::new(ReadySetError::SelectQueryCreationFailed {
+                qname: "q_1".into(),
+                source: Box::new(ReadySetError::Unsupported("nope".into())),
+            }),
+        });
+        assert_eq!(err.error_kind(), mysql_srv::ErrorKind::ER_NOT_SUPPORTED_YET);
+        assert_eq!(err.error_kind().sqlstate(), b"42000");
+    }
+
+    #[test]
+    fn readyset_error_sqlstates() {
+        let sqlstate = |e: ReadySetError| Error::from(e).error_kind().sqlstate();
+
+        assert_eq!(
+            sqlstate(ReadySetError::ViewNotFound("q_1".into())),
+            b"42S02"
+        );
+        assert_eq!(sqlstate(ReadySetError::NoSuchColumn("x".into())), b"42S22");
+        assert_eq!(sqlstate(ReadySetError::WrongColumnCount(2, 3)), b"21S01");
+        assert_eq!(sqlstate(ReadySetError::UpqueryTimeout), b"70100");
+        assert_eq!(
+            sqlstate(ReadySetError::ColumnRequired { col: "x".into() }),
+            b"23000"
+        );
+    }
+}
@@ -16,6 +16,16 @@ use readyset_tracing::warn;
 const MAX_ALLOWED_PACKET_VARIABLE_NAME: &str = "max_allowed_packet";
 const MAX_ALLOWED_PACKET_DEFAULT: DfValue = DfValue::UnsignedInt(67108864);
 
+/// The user variable which sets the minimum consistency token for reads in the current session
+const MIN_TOKEN_VARIABLE: &str = "readyset_min_token";
+
+/// The user variable which enables or disables snapshot reads in the current session
+const SNAPSHOT_READS_VARIABLE: &str = "readyset_snapshot_reads";
+
+/// The user variable which sets the read behavior (one of `blocking`, `non_blocking` or
+/// `stale_if_available`) for reads in the current session
+const READ_BEHAVIOR_VARIABLE: &str = "readyset_read_behavior";
+
 /// The list of mysql `SQL_MODE`s that *must* be set by a client
 const REQUIRED_SQL_MODES: [SqlMode; 3] = [
     SqlMode::NoZeroDate,
@@ -890,6 +900,43 @@ impl QueryHandler for MySqlQueryHandler {
                     );
                 }
 
+                if let [(var, val)] = &set.variables[..] {
+                    if var.scope == VariableScope::User
+                        && var.name.as_str().eq_ignore_ascii_case(MIN_TOKEN_VARIABLE)
+                    {
+                        return match val {
+                            Expr::Literal(Literal::String(token)) => SetMinToken(token.clone()),
+                            _ => Unsupported,
+                        };
+                    }
+                    if var.scope == VariableScope::User
+                        && var
+                            .name
+                            .as_str()
+                            .eq_ignore_ascii_case(SNAPSHOT_READS_VARIABLE)
+                    {
+                        return match val {
+                            Expr::Literal(Literal::UnsignedInteger(i)) => SetSnapshotReads(*i != 0),
+                            Expr::Literal(Literal::Integer(i)) => SetSnapshotReads(*i != 0),
+                            Expr::Literal(Literal::Boolean(b)) => SetSnapshotReads(*b),
+                            _ => Unsupported,
+                        };
+                    }
+                    if var.scope == VariableScope::User
+                        && var
+                            .name
+                            .as_str()
+                            .eq_ignore_ascii_case(READ_BEHAVIOR_VARIABLE)
+                    {
+                        return match val {
+                            Expr::Literal(Literal::String(read_behavior)) => {
+                                read_behavior.parse().map_or(Unsupported, SetReadBehavior)
+                            }
+                            _ => Unsupported,
+                        };
+                    }
+                }
+
                 SetBehavior::proxy_if(set.variables.iter().all(|(variable, value)| {
                     if variable.scope == VariableScope::User {
                         return false;
@@ -939,6 +986,7 @@ impl QueryHandler for MySqlQueryHandler {
 #[cfg(test)]
 mod tests {
     use nom_sql::{SetStatement, SetVariables, Variable};
+    use readyset_adapter::backend::noria_connector::ReadBehavior;
 
     use super::*;
 
@@ -978,6 +1026,64 @@ mod tests {
         );
     }
 
+    #[test]
+    fn set_min_token() {
+        let stmt = SetStatement::Variable(SetVariables {
+            variables: vec![(
+                Variable {
+                    scope: VariableScope::User,
+                    name: "readyset_min_token".into(),
+                },
+                Expr::Literal(Literal::from("1:42")),
+            )],
+        });
+        assert_eq!(
+            MySqlQueryHandler::handle_set_statement(&stmt),
+            SetBehavior::SetMinToken("1:42".into())
+        );
+    }
+
+    #[test]
+    fn set_snapshot_reads() {
+        let stmt = SetStatement::Variable(SetVariables {
+            variables: vec![(
+                Variable {
+                    scope: VariableScope::User,
+                    name: "readyset_snapshot_reads".into(),
+                },
+                Expr::Literal(Literal::UnsignedInteger(1)),
+            )],
+        });
+        assert_eq!(
+            MySqlQueryHandler::handle_set_statement(&stmt),
+            SetBehavior::SetSnapshotReads(true)
+        );
+    }
+
+    #[test]
+    fn set_read_behavior() {
+        let set_read_behavior = |value: &str| {
+            MySqlQueryHandler::handle_set_statement(&SetStatement::Variable(SetVariables {
+                variables: vec![(
+                    Variable {
+                        scope: VariableScope::User,
+                        name: "readyset_read_behavior".into(),
+                    },
+                    Expr::Literal(Literal::from(value)),
+                )],
+            }))
+        };
+        assert_eq!(
+            set_read_behavior("stale_if_available"),
+            SetBehavior::SetReadBehavior(ReadBehavior::StaleIfAvailable)
+        );
+        assert_eq!(
+            set_read_behavior("NON_BLOCKING"),
+            SetBehavior::SetReadBehavior(ReadBehavior::NonBlocking)
+        );
+        assert_eq!(set_read_behavior("sometimes"), SetBehavior::Unsupported);
+    }
+
     #[test]
     fn all_required_sql_modes_are_allowed() {
         for mode in REQUIRED_SQL_MODES {
@@ -5,15 +5,14 @@ use std::convert::TryInto;
 #[cfg(feature = "fallback_cache")]
 use std::hash::{Hash, Hasher};
 use std::sync::Arc;
+use std::task::{Context, Poll};
 
 use async_trait::async_trait;
-use futures_util::Stream;
-#[cfg(feature = "fallback_cache")]
-use futures_util::StreamExt;
+use futures_util::{ready, Stream, StreamExt};
 use mysql_async::consts::{CapabilityFlags, StatusFlags};
 use mysql_async::prelude::Queryable;
 use mysql_async::{
-    Column, Conn, Opts, OptsBuilder, ResultSetStream, Row, SslOpts, TxOpts, UrlError,
+    Column, Conn, Opts, OptsBuilder, ResultSetStream, Row, Statement, TxOpts, UrlError,
 };
 use nom_sql::SqlIdentifier;
 use pin_project::pin_project;
@@ -25,8 +24,10 @@ use readyset_adapter::{UpstreamConfig, UpstreamDatabase, UpstreamPrepare};
 use readyset_client::ColumnSchema;
 use readyset_client_metrics::QueryDestination;
 use readyset_data::DfValue;
-use readyset_errors::{internal_err, ReadySetError};
+use readyset_errors::{internal, internal_err, ReadySetError};
 use readyset_tracing::{error, info};
+use tokio::sync::{mpsc, oneshot, Semaphore};
+use tokio::task::JoinHandle;
 use tracing::{info_span, Instrument};
 
 use crate::schema::{convert_column, is_subtype};
@@ -38,6 +39,10 @@ type StatementID = u32;
 /// during connection phase if the version for the upstream server is too low.
 const MIN_UPSTREAM_VERSION: u16 = 8;
 
+/// The number of rows of a resultset read through a cursor which are read from the upstream
+/// database ahead of the client fetching them
+const CURSOR_READ_AHEAD: usize = 1024;
+
 fn dt_to_value_params(
     dt: &[DfValue],
 ) -> Result<Vec<mysql_async::Value>, readyset_client::ReadySetError> {
@@ -49,6 +54,48 @@ fn dt_to_value_params(
 pub enum ReadResultStream<'a> {
     Text(#[pin] ResultSetStream<'a, 'a, 'static, Row, mysql_async::TextProtocol>),
     Binary(#[pin] ResultSetStream<'a, 'a, 'static, Row, mysql_async::BinaryProtocol>),
+    Cursor(CursorRows),
+}
+
+/// The rows of a resultset read through a cursor, which are read from the upstream database by
+/// the task the connection has been lent to as they're consumed. See [`UpstreamConn::lend`].
+#[derive(Debug)]
+pub struct CursorRows {
+    rows: mpsc::UnboundedReceiver<Result<Row, mysql_async::Error>>,
+    /// Allows the task to read one more row from the upstream database for each row consumed
+    read_ahead: Arc<Semaphore>,
+    /// The status flags of the connection when the statement was executed. The ones sent by the
+    /// upstream database at the end of the resultset aren't known until all the rows are read.
+    status_flags: StatusFlags,
+}
+
+impl CursorRows {
+    pub fn status_flags(&self) -> StatusFlags {
+        self.status_flags
+    }
+}
+
+impl Stream for CursorRows {
+    type Item = Result<Row, mysql_async::Error>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let row = ready!(self.rows.poll_recv(cx));
+        if row.is_some() {
+            self.read_ahead.add_permits(1);
+        }
+        Poll::Ready(row)
+    }
+}
+
+impl Drop for CursorRows {
+    fn drop(&mut self) {
+        // Let the task read the rest of the rows without waiting for them to be consumed, so it
+        // gives the connection back
+        self.read_ahead.close();
+    }
 }
 
 impl<'a> From<ResultSetStream<'a, 'a, 'static, Row, mysql_async::TextProtocol>>
@@ -149,11 +196,184 @@ impl<'a> QueryResult<'a> {
     }
 }
 
+/// The connection to the upstream database, which is lent to a task while the rows of a
+/// resultset read through a cursor are streamed to the client
+struct UpstreamConn {
+    conn: Option<Conn>,
+    lent: Option<LentConn>,
+    opts: Opts,
+    id: u32,
+    server_version: (u16, u16, u16),
+}
+
+struct LentConn {
+    /// Closed to make the task read the rest of the rows without waiting for them to be consumed
+    read_ahead: Arc<Semaphore>,
+    /// Returns the connection once all the rows have been read
+    task: JoinHandle<Conn>,
+}
+
+impl UpstreamConn {
+    fn new(conn: Conn) -> Self {
+        UpstreamConn {
+            opts: conn.opts().clone(),
+            id: conn.id(),
+            server_version: conn.server_version(),
+            conn: Some(conn),
+            lent: None,
+        }
+    }
+
+    /// Returns the connection, first waiting for it to be given back if it's been lent out. Any
+    /// rows which haven't been consumed yet are read and kept until they are.
+    async fn get(&mut self) -> Result<&mut Conn, Error> {
+        if let Some(lent) = self.lent.take() {
+            lent.read_ahead.close();
+            let conn = lent
+                .task
+                .await
+                .map_err(|e| internal_err!("Failed to read rows through cursor: {}", e))?;
+            self.conn = Some(conn);
+        }
+        self.conn
+            .as_mut()
+            .ok_or_else(|| internal_err!("Lost the connection to the upstream database").into())
+    }
+
+    /// Lends the connection to a task which executes `statement` and streams the rows of its
+    /// resultset, reading at most [`CURSOR_READ_AHEAD`] rows ahead of the ones consumed. This
+    /// lets the rows be fetched through a cursor without buffering them all in memory first.
+    async fn lend<'a>(
+        &mut self,
+        statement: Statement,
+        params: Vec<mysql_async::Value>,
+    ) -> Result<QueryResult<'a>, Error> {
+        self.get().await?;
+        let mut conn = match self.conn.take() {
+            Some(conn) => conn,
+            None => internal!("Lost the connection to the upstream database"),
+        };
+        let read_ahead = Arc::new(Semaphore::new(CURSOR_READ_AHEAD));
+        let (result_tx, result_rx) = oneshot::channel();
+        let task = tokio::spawn({
+            let read_ahead = read_ahead.clone();
+            async move {
+                read_through_cursor(&mut conn, statement, params, result_tx, read_ahead).await;
+                conn
+            }
+        });
+        self.lent = Some(LentConn { read_ahead, task });
+        let result = result_rx
+            .await
+            .map_err(|_| internal_err!("Failed to execute statement through cursor"))??;
+        Ok(match result {
+            LentResult::Rows { rows, columns } => QueryResult::ReadResult {
+                stream: ReadResultStream::Cursor(rows),
+                columns,
+            },
+            LentResult::Write {
+                num_rows_affected,
+                last_inserted_id,
+                status_flags,
+            } => QueryResult::WriteResult {
+                num_rows_affected,
+                last_inserted_id,
+                status_flags,
+            },
+        })
+    }
+
+    async fn disconnect(self) -> Result<(), mysql_async::Error> {
+        match self.conn {
+            Some(conn) => conn.disconnect().await,
+            None => Ok(()),
+        }
+    }
+}
+
+/// The result of executing a statement on a lent connection, which doesn't borrow the connection
+enum LentResult {
+    Rows {
+        rows: CursorRows,
+        columns: Arc<[Column]>,
+    },
+    Write {
+        num_rows_affected: u64,
+        last_inserted_id: u64,
+        status_flags: StatusFlags,
+    },
+}
+
+/// Executes `statement` on `conn`, sending the result to `result`. If it's a resultset, its rows
+/// are then read and sent on as they're consumed, until all of them have been read.
+async fn read_through_cursor(
+    conn: &mut Conn,
+    statement: Statement,
+    params: Vec<mysql_async::Value>,
+    result: oneshot::Sender<Result<LentResult, Error>>,
+    read_ahead: Arc<Semaphore>,
+) {
+    let status_flags = conn.status();
+    let (mut stream, columns) = match execute_statement(conn, &statement, params).await {
+        Ok(QueryResult::ReadResult { stream, columns }) => (stream, columns),
+        Ok(QueryResult::WriteResult {
+            num_rows_affected,
+            last_inserted_id,
+            status_flags,
+        }) => {
+            let _ = result.send(Ok(LentResult::Write {
+                num_rows_affected,
+                last_inserted_id,
+                status_flags,
+            }));
+            return;
+        }
+        Ok(QueryResult::Command { .. } | QueryResult::CachedReadResult(_)) => {
+            let _ = result.send(Err(internal_err!(
+                "Unexpected result executing statement through cursor"
+            )
+            .into()));
+            return;
+        }
+        Err(e) => {
+            let _ = result.send(Err(e));
+            return;
+        }
+    };
+
+    let (rows_tx, rows) = mpsc::unbounded_channel();
+    let _ = result.send(Ok(LentResult::Rows {
+        rows: CursorRows {
+            rows,
+            read_ahead: read_ahead.clone(),
+            status_flags,
+        },
+        columns,
+    }));
+    loop {
+        // Once the semaphore is closed, the rest of the rows are read right away
+        if let Ok(permit) = read_ahead.acquire().await {
+            permit.forget();
+        }
+        match stream.next().await {
+            Some(row) => {
+                // If the rows are no longer wanted they're still read, so the connection can be
+                // used again
+                let _ = rows_tx.send(row);
+            }
+            None => break,
+        }
+    }
+}
+
 /// A connector to an underlying mysql store. This is really just a wrapper for the mysql crate.
 pub struct MySqlUpstream {
-    conn: Conn,
+    conn: UpstreamConn,
     prepared_statements: HashMap<StatementID, mysql_async::Statement>,
     upstream_config: UpstreamConfig,
+    /// Whether to execute the next statement through a read-only cursor. See
+    /// [`MySqlUpstream::set_cursor_execute`].
+    cursor_execute: bool,
     #[cfg(feature = "fallback_cache")]
     fallback_cache: Option<FallbackCache<CachedReadResult>>,
 }
@@ -199,6 +419,7 @@ impl<'a> Stream for ReadResultStream<'a> {
         match self.project() {
             ReadResultStreamProj::Text(s) => s.poll_next(cx),
             ReadResultStreamProj::Binary(s) => s.poll_next(cx),
+            ReadResultStreamProj::Cursor(s) => s.poll_next_unpin(cx),
         }
     }
 }
@@ -208,6 +429,7 @@ impl<'a> ReadResultStream<'a> {
         match self {
             ReadResultStream::Text(s) => s.ok_packet().map(|o| o.status_flags()),
             ReadResultStream::Binary(s) => s.ok_packet().map(|o| o.status_flags()),
+            ReadResultStream::Cursor(s) => Some(s.status_flags()),
         }
     }
 }
@@ -267,6 +489,15 @@ macro_rules! handle_query_result {
     }};
 }
 
+async fn execute_statement<'a>(
+    conn: &'a mut Conn,
+    statement: &Statement,
+    params: Vec<mysql_async::Value>,
+) -> Result<QueryResult<'a>, Error> {
+    let result = conn.exec_iter(statement, params).await?;
+    handle_query_result!(result)
+}
+
 impl MySqlUpstream {
     async fn connect_inner(
         upstream_config: UpstreamConfig,
@@ -290,10 +521,7 @@ impl MySqlUpstream {
         let mut opts =
             Opts::from_url(url).map_err(|e: UrlError| Error::MySql(mysql_async::Error::Url(e)))?;
 
-        if let Some(cert_path) = upstream_config.ssl_root_cert.clone() {
-            let ssl_opts = SslOpts::default().with_root_cert_path(Some(cert_path));
-            opts = OptsBuilder::from_opts(opts).ssl_opts(ssl_opts).into();
-        }
+        opts = upstream_config.apply_mysql_ssl_opts(opts);
 
         let span = info_span!(
             "Connecting to MySQL upstream",
@@ -302,17 +530,13 @@ impl MySqlUpstream {
             user = %opts.user().unwrap_or("<NO USER>"),
         );
         span.in_scope(|| info!("Establishing connection"));
-        let conn = if cfg!(feature = "ryw") {
-            Conn::new(
-                OptsBuilder::from_opts(opts).add_capability(CapabilityFlags::CLIENT_SESSION_TRACK),
-            )
-            .instrument(span.clone())
-            .await?
-        } else {
-            Conn::new(OptsBuilder::from_opts(opts))
-                .instrument(span.clone())
-                .await?
-        };
+        // Session state tracking is needed to read back the GTIDs of writes which are tracked for
+        // read-your-writes consistency (see `handle_ryw_write`)
+        let conn = Conn::new(
+            OptsBuilder::from_opts(opts).add_capability(CapabilityFlags::CLIENT_SESSION_TRACK),
+        )
+        .instrument(span.clone())
+        .await?;
 
         // Check that the server version is supported.
         let (major, minor, _) = conn.server_version();
@@ -328,6 +552,30 @@ impl MySqlUpstream {
         let prepared_statements = HashMap::new();
         Ok((conn, prepared_statements, upstream_config))
     }
+
+    /// Sets whether the next statement executed is executed through a read-only cursor, in which
+    /// case the rows of its resultset are read from the upstream database as they're consumed,
+    /// rather than all at once. The connection can still be used while the rows are being
+    /// consumed, but any rows not consumed by then are read and kept in memory until they are.
+    pub fn set_cursor_execute(&mut self, cursor_execute: bool) {
+        self.cursor_execute = cursor_execute;
+    }
+
+    async fn execute_through_cursor<'a>(
+        &mut self,
+        id: u32,
+        params: &[DfValue],
+    ) -> Result<QueryResult<'a>, Error> {
+        let params = dt_to_value_params(params)?;
+        let statement = self
+            .prepared_statements
+            .get(&id)
+            .cloned()
+            .ok_or(Error::ReadySet(ReadySetError::PreparedStatementMissing {
+                statement_id: id,
+            }))?;
+        self.conn.lend(statement, params).await
+    }
 }
 
 #[async_trait]
@@ -346,9 +594,10 @@ impl UpstreamDatabase for MySqlUpstream {
         let (conn, prepared_statements, upstream_config) =
             Self::connect_inner(upstream_config).await?;
         Ok(Self {
-            conn,
+            conn: UpstreamConn::new(conn),
             prepared_statements,
             upstream_config,
+            cursor_execute: false,
             fallback_cache,
         })
     }
@@ -361,9 +610,10 @@ impl UpstreamDatabase for MySqlUpstream {
         let (conn, prepared_statements, upstream_config) =
             Self::connect_inner(upstream_config).await?;
         Ok(Self {
-            conn,
+            conn: UpstreamConn::new(conn),
             prepared_statements,
             upstream_config,
+            cursor_execute: false,
         })
     }
 
@@ -372,7 +622,11 @@ impl UpstreamDatabase for MySqlUpstream {
     }
 
     fn database(&self) -> Option<&str> {
-        self.conn.opts().db_name()
+        self.conn.opts.db_name()
+    }
+
+    fn connection_id(&self) -> Option<u32> {
+        Some(self.conn.id)
     }
 
     fn version(&self) -> String {
@@ -380,13 +634,13 @@ impl UpstreamDatabase for MySqlUpstream {
         // clients will interpret the version numbers and use that to dictate which dialect they
         // send us. Anything after the version can be any text we desire. Additionally, the version
         // string must be null terminated.
-        let (major, minor, patch) = self.conn.server_version();
+        let (major, minor, patch) = self.conn.server_version;
         format!("{major}.{minor}.{patch}-readyset\0")
     }
 
     #[cfg(feature = "fallback_cache")]
     async fn reset(&mut self) -> Result<(), Error> {
-        let opts = self.conn.opts().clone();
+        let opts = self.conn.opts.clone();
         let conn = Conn::new(opts).await?;
         let prepared_statements = HashMap::new();
         let upstream_config = self.upstream_config.clone();
@@ -399,9 +653,10 @@ impl UpstreamDatabase for MySqlUpstream {
         let old_self = std::mem::replace(
             self,
             Self {
-                conn,
+                conn: UpstreamConn::new(conn),
                 prepared_statements,
                 upstream_config,
+                cursor_execute: false,
                 fallback_cache,
             },
         );
@@ -411,16 +666,17 @@ impl UpstreamDatabase for MySqlUpstream {
 
     #[cfg(not(feature = "fallback_cache"))]
     async fn reset(&mut self) -> Result<(), Error> {
-        let opts = self.conn.opts().clone();
+        let opts = self.conn.opts.clone();
         let conn = Conn::new(opts).await?;
         let prepared_statements = HashMap::new();
         let upstream_config = self.upstream_config.clone();
         let old_self = std::mem::replace(
             self,
             Self {
-                conn,
+                conn: UpstreamConn::new(conn),
                 prepared_statements,
                 upstream_config,
+                cursor_execute: false,
             },
         );
         let _ = old_self.conn.disconnect().await as Result<(), _>;
@@ -433,7 +689,7 @@ impl UpstreamDatabase for MySqlUpstream {
     where
         S: AsRef<str> + Send + Sync + 'a,
     {
-        let statement = self.conn.prep(query).await?;
+        let statement = self.conn.get().await?.prep(query).await?;
         self.prepared_statements
             .insert(statement.id(), statement.clone());
         Ok(UpstreamPrepare {
@@ -451,6 +707,9 @@ impl UpstreamDatabase for MySqlUpstream {
         id: u32,
         params: &[DfValue],
     ) -> Result<Self::QueryResult<'a>, Error> {
+        if std::mem::take(&mut self.cursor_execute) {
+            return self.execute_through_cursor(id, params).await;
+        }
         if let Some(ref mut cache) = self.fallback_cache {
             let mut s = DefaultHasher::new();
             (id, params).hash(&mut s);
@@ -459,15 +718,10 @@ impl UpstreamDatabase for MySqlUpstream {
                 return Ok(query_r.into());
             }
             let params = dt_to_value_params(params)?;
-            let result = self
-                .conn
-                .exec_iter(
-                    self.prepared_statements.get(&id).ok_or(Error::ReadySet(
-                        ReadySetError::PreparedStatementMissing { statement_id: id },
-                    ))?,
-                    params,
-                )
-                .await?;
+            let statement = self.prepared_statements.get(&id).ok_or(Error::ReadySet(
+                ReadySetError::PreparedStatementMissing { statement_id: id },
+            ))?;
+            let result = self.conn.get().await?.exec_iter(statement, params).await?;
             let r = handle_query_result!(result);
             match r {
                 Ok(query_result @ QueryResult::ReadResult { .. }) => {
@@ -479,15 +733,10 @@ impl UpstreamDatabase for MySqlUpstream {
             }
         } else {
             let params = dt_to_value_params(params)?;
-            let result = self
-                .conn
-                .exec_iter(
-                    self.prepared_statements.get(&id).ok_or(Error::ReadySet(
-                        ReadySetError::PreparedStatementMissing { statement_id: id },
-                    ))?,
-                    params,
-                )
-                .await?;
+            let statement = self.prepared_statements.get(&id).ok_or(Error::ReadySet(
+                ReadySetError::PreparedStatementMissing { statement_id: id },
+            ))?;
+            let result = self.conn.get().await?.exec_iter(statement, params).await?;
             handle_query_result!(result)
         }
     }
@@ -498,16 +747,14 @@ impl UpstreamDatabase for MySqlUpstream {
         id: u32,
         params: &[DfValue],
     ) -> Result<Self::QueryResult<'a>, Error> {
+        if std::mem::take(&mut self.cursor_execute) {
+            return self.execute_through_cursor(id, params).await;
+        }
         let params = dt_to_value_params(params)?;
-        let result = self
-            .conn
-            .exec_iter(
-                self.prepared_statements.get(&id).ok_or(Error::ReadySet(
-                    ReadySetError::PreparedStatementMissing { statement_id: id },
-                ))?,
-                params,
-            )
-            .await?;
+        let statement = self.prepared_statements.get(&id).ok_or(Error::ReadySet(
+            ReadySetError::PreparedStatementMissing { statement_id: id },
+        ))?;
+        let result = self.conn.get().await?.exec_iter(statement, params).await?;
         handle_query_result!(result)
     }
 
@@ -521,7 +768,7 @@ impl UpstreamDatabase for MySqlUpstream {
                 return Ok(query_r.into());
             }
             let query_str = query.as_ref().to_owned();
-            let result = self.conn.query_iter(query).await?;
+            let result = self.conn.get().await?.query_iter(query).await?;
             let r = handle_query_result!(result);
             match r {
                 Ok(query_result @ QueryResult::ReadResult { .. }) => {
@@ -532,7 +779,7 @@ impl UpstreamDatabase for MySqlUpstream {
                 _ => r,
             }
         } else {
-            let result = self.conn.query_iter(query).await?;
+            let result = self.conn.get().await?.query_iter(query).await?;
             handle_query_result!(result)
         }
     }
@@ -542,7 +789,7 @@ impl UpstreamDatabase for MySqlUpstream {
     where
         S: AsRef<str> + Send + Sync + 'a,
     {
-        let result = self.conn.query_iter(query).await?;
+        let result = self.conn.get().await?.query_iter(query).await?;
         handle_query_result!(result)
     }
 
@@ -554,7 +801,12 @@ impl UpstreamDatabase for MySqlUpstream {
     where
         S: AsRef<str> + Send + Sync + 'a,
     {
-        let mut transaction = self.conn.start_transaction(TxOpts::default()).await?;
+        let mut transaction = self
+            .conn
+            .get()
+            .await?
+            .start_transaction(TxOpts::default())
+            .await?;
         transaction.query_drop(query).await.map_err(|e| {
             error!("Could not execute query in mysql : {:?}", e);
             e
@@ -580,37 +832,40 @@ impl UpstreamDatabase for MySqlUpstream {
     }
 
     async fn start_tx<'a>(&'a mut self) -> Result<Self::QueryResult<'a>, Error> {
-        self.conn.query_drop("START TRANSACTION").await?;
+        let conn = self.conn.get().await?;
+        conn.query_drop("START TRANSACTION").await?;
 
         Ok(QueryResult::Command {
-            status_flags: self.conn.status(),
+            status_flags: conn.status(),
         })
     }
 
     async fn commit<'a>(&'a mut self) -> Result<Self::QueryResult<'a>, Error> {
-        let result = self.conn.query_iter("COMMIT").await?;
+        let conn = self.conn.get().await?;
+        let result = conn.query_iter("COMMIT").await?;
         result.drop_result().await?;
 
         Ok(QueryResult::Command {
-            status_flags: self.conn.status(),
+            status_flags: conn.status(),
         })
     }
 
     async fn rollback<'a>(&'a mut self) -> Result<Self::QueryResult<'a>, Error> {
-        let result = self.conn.query_iter("ROLLBACK").await?;
+        let conn = self.conn.get().await?;
+        let result = conn.query_iter("ROLLBACK").await?;
         result.drop_result().await?;
 
         Ok(QueryResult::Command {
-            status_flags: self.conn.status(),
+            status_flags: conn.status(),
         })
     }
 
     async fn schema_dump(&mut self) -> Result<Vec<u8>, anyhow::Error> {
-        let tables: Vec<String> = self.conn.query_iter("SHOW TABLES").await?.collect().await?;
+        let conn = self.conn.get().await?;
+        let tables: Vec<String> = conn.query_iter("SHOW TABLES").await?.collect().await?;
         let mut dump = String::with_capacity(tables.len());
         for table in &tables {
-            if let Some(create) = self
-                .conn
+            if let Some(create) = conn
                 .query_first(format!("SHOW CREATE TABLE `{}`", &table))
                 .await?
                 .map(|row: (String, String)| row.1)
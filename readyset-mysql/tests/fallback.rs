@@ -1,5 +1,8 @@
+use std::sync::Arc;
+
 use mysql_async::prelude::*;
 use readyset_adapter::backend::UnsupportedSetMode;
+use readyset_adapter::connections::ConnectionRegistry;
 use readyset_adapter::BackendBuilder;
 use readyset_client::query::QueryId;
 use readyset_client_metrics::QueryDestination;
@@ -662,6 +665,35 @@ async fn transaction_proxies() {
     );
 }
 
+#[tokio::test(flavor = "multi_thread")]
+#[serial]
+async fn kill_connection_by_upstream_id() {
+    let connections = Arc::new(ConnectionRegistry::new(None));
+    let (opts, _handle) = TestBuilder::new(BackendBuilder::new().require_authentication(false))
+        .fallback(true)
+        .connections(connections.clone())
+        .build::<MySQLAdapter>()
+        .await;
+    let mut conn = mysql_async::Conn::new(opts.clone()).await.unwrap();
+    let mut victim = mysql_async::Conn::new(opts).await.unwrap();
+
+    // `CONNECTION_ID()` is proxied upstream, and has to match the id in the handshake and the id
+    // `KILL` takes
+    let id: u32 = victim
+        .query_first("SELECT CONNECTION_ID()")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(id, victim.id());
+    assert!(connections.contains(id));
+
+    conn.query_drop(format!("KILL {id}")).await.unwrap();
+    sleep().await;
+
+    victim.query_drop("SELECT 1").await.unwrap_err();
+    assert!(!connections.contains(id));
+}
+
 #[tokio::test(flavor = "multi_thread")]
 #[serial]
 async fn valid_sql_parsing_failed_shows_proxied() {
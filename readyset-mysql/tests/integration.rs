@@ -1,12 +1,21 @@
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::sync::Arc;
 use std::time::Duration;
 
+use arrow::array::{Array, Int64Array};
+use arrow::datatypes::Schema;
+use arrow_flight::flight_descriptor::DescriptorType;
+use arrow_flight::flight_service_client::FlightServiceClient;
+use arrow_flight::utils::flight_data_to_arrow_batch;
+use arrow_flight::FlightDescriptor;
 use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
 use mysql_async::prelude::Queryable;
 use mysql_async::OptsBuilder;
 use readyset_adapter::backend::noria_connector::ReadBehavior;
 use readyset_adapter::backend::{MigrationMode, QueryInfo};
+use readyset_adapter::flight::ArrowFlightServer;
+use readyset_adapter::micro_cache::MicroCache;
 use readyset_adapter::proxied_queries_reporter::ProxiedQueriesReporter;
 use readyset_adapter::query_status_cache::{MigrationStyle, QueryStatusCache};
 use readyset_adapter::BackendBuilder;
@@ -17,6 +26,7 @@ use readyset_client_test_helpers::{sleep, TestBuilder};
 use readyset_errors::ReadySetError;
 use readyset_server::Handle;
 use readyset_telemetry_reporter::{TelemetryEvent, TelemetryInitializer, TelemetryReporter};
+use stream_cancel::Valve;
 
 async fn setup() -> (mysql_async::Opts, Handle) {
     readyset_tracing::init_test_logging();
@@ -445,6 +455,307 @@ async fn update_basic_prepared() {
     assert_eq!(name, String::from("Bob"));
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn delete_cascades_to_foreign_keys() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE Owners (id int PRIMARY KEY)")
+        .await
+        .unwrap();
+    conn.query_drop(
+        "CREATE TABLE Cats (id int PRIMARY KEY, owner_id int,
+         FOREIGN KEY (owner_id) REFERENCES Owners (id) ON DELETE CASCADE)",
+    )
+    .await
+    .unwrap();
+    conn.query_drop(
+        "CREATE TABLE Toys (id int PRIMARY KEY, cat_id int,
+         FOREIGN KEY (cat_id) REFERENCES Cats (id) ON DELETE SET NULL)",
+    )
+    .await
+    .unwrap();
+    sleep().await;
+
+    conn.query_drop("INSERT INTO Owners (id) VALUES (1), (2)")
+        .await
+        .unwrap();
+    conn.query_drop("INSERT INTO Cats (id, owner_id) VALUES (1, 1), (2, 1), (3, 2)")
+        .await
+        .unwrap();
+    conn.query_drop("INSERT INTO Toys (id, cat_id) VALUES (1, 1), (2, 3)")
+        .await
+        .unwrap();
+    sleep().await;
+
+    conn.query_drop("DELETE FROM Owners WHERE Owners.id = 1")
+        .await
+        .unwrap();
+    sleep().await;
+
+    // The rows to cascade to are read from the base tables directly, without creating caches
+    let caches: Vec<(String, String, String)> = conn.query("SHOW CACHES").await.unwrap();
+    assert!(caches.is_empty(), "{caches:?}");
+
+    let cats: Vec<i32> = conn
+        .query("SELECT Cats.id FROM Cats ORDER BY Cats.id")
+        .await
+        .unwrap();
+    assert_eq!(cats, vec![3]);
+
+    let toys: Vec<(i32, Option<i32>)> = conn
+        .query("SELECT Toys.id, Toys.cat_id FROM Toys ORDER BY Toys.id")
+        .await
+        .unwrap();
+    assert_eq!(toys, vec![(1, None), (2, Some(3))]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn update_cascades_to_foreign_keys() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE Owners (id int PRIMARY KEY, name VARCHAR(255))")
+        .await
+        .unwrap();
+    conn.query_drop(
+        "CREATE TABLE Cats (id int PRIMARY KEY, owner_id int,
+         FOREIGN KEY (owner_id) REFERENCES Owners (id) ON UPDATE CASCADE)",
+    )
+    .await
+    .unwrap();
+    sleep().await;
+
+    conn.query_drop("INSERT INTO Owners (id, name) VALUES (1, \"Alice\")")
+        .await
+        .unwrap();
+    conn.query_drop("INSERT INTO Cats (id, owner_id) VALUES (1, 1)")
+        .await
+        .unwrap();
+    sleep().await;
+
+    conn.exec_drop("UPDATE Owners SET Owners.id = ? WHERE Owners.id = ?", (2, 1))
+        .await
+        .unwrap();
+    sleep().await;
+
+    let owner_id: i32 = conn
+        .query_first("SELECT Cats.owner_id FROM Cats WHERE Cats.id = 1")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(owner_id, 2);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn check_constraint_violation() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE Cats (id int PRIMARY KEY, lives int, CHECK (lives <= 9))")
+        .await
+        .unwrap();
+    sleep().await;
+
+    conn.query_drop("INSERT INTO Cats (id, lives) VALUES (1, 9)")
+        .await
+        .unwrap();
+
+    let err = conn
+        .query_drop("INSERT INTO Cats (id, lives) VALUES (2, 10)")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, mysql_async::Error::Server(e) if e.code == 3819));
+
+    let err = conn
+        .query_drop("UPDATE Cats SET Cats.lives = 10 WHERE Cats.id = 1")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, mysql_async::Error::Server(e) if e.code == 3819));
+    sleep().await;
+
+    let rows: Vec<(i32, i32)> = conn
+        .query("SELECT Cats.id, Cats.lives FROM Cats")
+        .await
+        .unwrap();
+    assert_eq!(rows, vec![(1, 9)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn insert_on_duplicate_key_update_is_atomic() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE Cats (id int PRIMARY KEY, lives int, CHECK (lives <= 9))")
+        .await
+        .unwrap();
+    sleep().await;
+
+    conn.query_drop("INSERT INTO Cats (id, lives) VALUES (1, 1)")
+        .await
+        .unwrap();
+
+    // The second row's update violates the constraint, so the first row isn't inserted either
+    let err = conn
+        .query_drop(
+            "INSERT INTO Cats (id, lives) VALUES (2, 1), (1, 1) ON DUPLICATE KEY UPDATE lives = 10",
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, mysql_async::Error::Server(e) if e.code == 3819));
+    sleep().await;
+
+    let rows: Vec<(i32, i32)> = conn
+        .query("SELECT Cats.id, Cats.lives FROM Cats")
+        .await
+        .unwrap();
+    assert_eq!(rows, vec![(1, 1)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn duplicate_entry() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE Cats (id int PRIMARY KEY, name VARCHAR(255), UNIQUE KEY (name))")
+        .await
+        .unwrap();
+    sleep().await;
+
+    conn.query_drop("INSERT INTO Cats (id, name) VALUES (1, 'Bob')")
+        .await
+        .unwrap();
+
+    let err = conn
+        .query_drop("INSERT INTO Cats (id, name) VALUES (1, 'Jane')")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, mysql_async::Error::Server(e) if e.code == 1062));
+
+    let err = conn
+        .query_drop("INSERT INTO Cats (id, name) VALUES (2, 'Bob')")
+        .await
+        .unwrap_err();
+    assert!(matches!(err, mysql_async::Error::Server(e) if e.code == 1062));
+
+    conn.query_drop("INSERT INTO Cats (id, name) VALUES (2, 'Jane')")
+        .await
+        .unwrap();
+    sleep().await;
+
+    let mut rows: Vec<(i32, String)> = conn
+        .query("SELECT Cats.id, Cats.name FROM Cats")
+        .await
+        .unwrap();
+    rows.sort();
+    assert_eq!(rows, vec![(1, "Bob".to_owned()), (2, "Jane".to_owned())]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn auto_increment() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop(
+        "CREATE TABLE Cats (id int AUTO_INCREMENT PRIMARY KEY, name VARCHAR(255)) \
+         AUTO_INCREMENT=10",
+    )
+    .await
+    .unwrap();
+    sleep().await;
+
+    conn.query_drop("INSERT INTO Cats (name) VALUES ('Bob'), ('Jane')")
+        .await
+        .unwrap();
+    assert_eq!(conn.last_insert_id(), Some(10));
+
+    // Explicit values move the counter past them, and NULL generates a new value
+    conn.query_drop("INSERT INTO Cats (id, name) VALUES (20, 'Alice')")
+        .await
+        .unwrap();
+    conn.query_drop("INSERT INTO Cats (id, name) VALUES (NULL, 'Eve')")
+        .await
+        .unwrap();
+    assert_eq!(conn.last_insert_id(), Some(21));
+    sleep().await;
+
+    let mut rows: Vec<(i32, String)> = conn
+        .query("SELECT Cats.id, Cats.name FROM Cats")
+        .await
+        .unwrap();
+    rows.sort();
+    assert_eq!(
+        rows,
+        vec![
+            (10, "Bob".to_owned()),
+            (11, "Jane".to_owned()),
+            (20, "Alice".to_owned()),
+            (21, "Eve".to_owned()),
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn insert_default_values() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop(
+        "CREATE TABLE Cats (id int PRIMARY KEY, name VARCHAR(255) DEFAULT 'Unnamed', \
+         created_at DATETIME DEFAULT CURRENT_TIMESTAMP)",
+    )
+    .await
+    .unwrap();
+    sleep().await;
+
+    conn.query_drop("INSERT INTO Cats (id) VALUES (1), (2)")
+        .await
+        .unwrap();
+    sleep().await;
+
+    let mut rows: Vec<(i32, String, Option<NaiveDateTime>)> = conn
+        .query("SELECT Cats.id, Cats.name, Cats.created_at FROM Cats")
+        .await
+        .unwrap();
+    rows.sort();
+    assert_eq!(rows.len(), 2);
+    for (row, id) in rows.into_iter().zip([1, 2]) {
+        assert_eq!(row.0, id);
+        assert_eq!(row.1, "Unnamed");
+        assert!(row.2.is_some());
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn update_on_update_current_timestamp() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop(
+        "CREATE TABLE Cats (id int PRIMARY KEY, name VARCHAR(255), \
+         updated_at DATETIME DEFAULT NULL ON UPDATE CURRENT_TIMESTAMP)",
+    )
+    .await
+    .unwrap();
+    sleep().await;
+
+    conn.query_drop("INSERT INTO Cats (id, name) VALUES (1, 'Bob')")
+        .await
+        .unwrap();
+    sleep().await;
+
+    let row: Option<(String, Option<NaiveDateTime>)> = conn
+        .query_first("SELECT Cats.name, Cats.updated_at FROM Cats WHERE Cats.id = 1")
+        .await
+        .unwrap();
+    assert_eq!(row, Some(("Bob".to_owned(), None)));
+
+    conn.query_drop("UPDATE Cats SET name = 'Rusty' WHERE Cats.id = 1")
+        .await
+        .unwrap();
+    sleep().await;
+
+    let row: Option<(String, Option<NaiveDateTime>)> = conn
+        .query_first("SELECT Cats.name, Cats.updated_at FROM Cats WHERE Cats.id = 1")
+        .await
+        .unwrap();
+    let (name, updated_at) = row.unwrap();
+    assert_eq!(name, "Rusty");
+    assert!(updated_at.is_some());
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn update_compound_primary_key() {
     let (opts, _handle) = setup().await;
@@ -772,6 +1083,108 @@ async fn select_collapse_where_in() {
     assert!(names.iter().any(|s| s == "Bob"));
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn select_placeholders_in_expressions() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE items (id int, cat int, price double, PRIMARY KEY(id))")
+        .await
+        .unwrap();
+    sleep().await;
+
+    conn.query_drop(
+        "INSERT INTO items (id, cat, price) VALUES (1, 1, 1.25), (2, 1, 2.5), (3, 2, 3.75)",
+    )
+    .await
+    .unwrap();
+    sleep().await;
+
+    let mut ids: Vec<i32> = conn
+        .exec(
+            "SELECT items.id FROM items WHERE round(items.price, ?) > ?",
+            (0, 2),
+        )
+        .await
+        .unwrap();
+    ids.sort_unstable();
+    assert_eq!(ids, vec![2, 3]);
+
+    let ids: Vec<i32> = conn
+        .exec(
+            "SELECT items.id FROM items WHERE items.price * ? > ? AND items.cat = ?",
+            (2, 4, 1),
+        )
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![2]);
+
+    let ids: Vec<i32> = conn
+        .exec(
+            "SELECT items.id FROM items WHERE items.cat = ? AND items.price * ? > ? \
+             ORDER BY items.id LIMIT ?",
+            (1, 4, 1, 1),
+        )
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![1]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn select_null_safe_equal() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE t (id int, v int, PRIMARY KEY(id))")
+        .await
+        .unwrap();
+    sleep().await;
+
+    conn.query_drop("INSERT INTO t (id, v) VALUES (1, 1), (2, NULL), (3, NULL), (4, 2)")
+        .await
+        .unwrap();
+    sleep().await;
+
+    let mut ids: Vec<i32> = conn
+        .exec("SELECT t.id FROM t WHERE t.v <=> ?", (None::<i32>,))
+        .await
+        .unwrap();
+    ids.sort_unstable();
+    assert_eq!(ids, vec![2, 3]);
+    assert_eq!(
+        last_query_info(&mut conn).await.destination,
+        QueryDestination::Readyset
+    );
+
+    let ids: Vec<i32> = conn
+        .exec("SELECT t.id FROM t WHERE t.v <=> ?", (Some(1),))
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![1]);
+    assert_eq!(
+        last_query_info(&mut conn).await.destination,
+        QueryDestination::Readyset
+    );
+
+    // Plain equality with NULL never matches anything
+    let ids: Vec<i32> = conn
+        .exec("SELECT t.id FROM t WHERE t.v = ?", (None::<i32>,))
+        .await
+        .unwrap();
+    assert!(ids.is_empty());
+
+    // NULL keys are kept up to date as rows are written
+    conn.query_drop("UPDATE t SET v = NULL WHERE id = 1")
+        .await
+        .unwrap();
+    sleep().await;
+
+    let mut ids: Vec<i32> = conn
+        .exec("SELECT t.id FROM t WHERE t.v <=> ?", (None::<i32>,))
+        .await
+        .unwrap();
+    ids.sort_unstable();
+    assert_eq!(ids, vec![1, 2, 3]);
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn multiple_in() {
     let (opts, _handle) = setup().await;
@@ -1349,8 +1762,54 @@ async fn ilike() {
 
     let rows: Vec<(i32, String)> = conn
         .exec(
-            "SELECT id, title FROM notes WHERE title ILIKE ? ORDER BY id ASC",
-            ("%a%",),
+            "SELECT id, title FROM notes WHERE title ILIKE ? ORDER BY id ASC",
+            ("%a%",),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            (2, "bar".to_string()),
+            (3, "baz".to_string()),
+            (4, "BAZ".to_string())
+        ]
+    );
+
+    let with_other_constraint: Vec<(i32, String)> = conn
+        .exec(
+            "SELECT id, title FROM notes WHERE title ILIKE ? AND id >= ? ORDER BY id ASC",
+            ("%a%", 3),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        with_other_constraint,
+        vec![(3, "baz".to_string()), (4, "BAZ".to_string()),]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn like_prefix() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE notes(id INTEGER PRIMARY KEY, title TEXT)")
+        .await
+        .unwrap();
+    sleep().await;
+
+    conn.query_drop(
+        "INSERT INTO notes (id, title) VALUES (1, 'foo'), (2, 'bar'), (3, 'baz'), (4, 'BAZ'), \
+         (5, 'bay')",
+    )
+    .await
+    .unwrap();
+    sleep().await;
+
+    let rows: Vec<(i32, String)> = conn
+        .exec(
+            "SELECT id, title FROM notes WHERE title LIKE ? ORDER BY id ASC",
+            ("ba%",),
         )
         .await
         .unwrap();
@@ -1359,21 +1818,34 @@ async fn ilike() {
         vec![
             (2, "bar".to_string()),
             (3, "baz".to_string()),
-            (4, "BAZ".to_string())
+            (5, "bay".to_string())
         ]
     );
 
-    let with_other_constraint: Vec<(i32, String)> = conn
+    let rows: Vec<(i32, String)> = conn
         .exec(
-            "SELECT id, title FROM notes WHERE title ILIKE ? AND id >= ? ORDER BY id ASC",
-            ("%a%", 3),
+            "SELECT id, title FROM notes WHERE title LIKE ? ORDER BY id ASC",
+            ("ba_",),
         )
         .await
         .unwrap();
     assert_eq!(
-        with_other_constraint,
-        vec![(3, "baz".to_string()), (4, "BAZ".to_string()),]
+        rows,
+        vec![
+            (2, "bar".to_string()),
+            (3, "baz".to_string()),
+            (5, "bay".to_string())
+        ]
     );
+
+    let with_other_constraint: Vec<(i32, String)> = conn
+        .exec(
+            "SELECT id, title FROM notes WHERE id = ? AND title LIKE ?",
+            (3, "b%z"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(with_other_constraint, vec![(3, "baz".to_string())]);
 }
 
 #[tokio::test(flavor = "multi_thread")]
@@ -1645,6 +2117,254 @@ async fn create_query_cache_where_in() {
     assert_eq!(new_queries.len(), queries.len());
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn create_cache_concurrently() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE t (id INT, val INT);")
+        .await
+        .unwrap();
+    conn.query_drop("INSERT INTO t (id, val) VALUES (1, 2), (3, 4);")
+        .await
+        .unwrap();
+    sleep().await;
+
+    conn.query_drop("CREATE CACHE CONCURRENTLY conc FROM SELECT id, val FROM t WHERE id = ?;")
+        .await
+        .unwrap();
+    sleep().await;
+
+    let queries: Vec<(String, String, String)> = conn.query("SHOW CACHES;").await.unwrap();
+    assert!(queries
+        .iter()
+        .any(|(query_name, _, _)| query_name == "`conc`"));
+
+    let rows: Vec<(i32, i32)> = conn
+        .query("SELECT id, val FROM t WHERE id = 3")
+        .await
+        .unwrap();
+    assert_eq!(rows, vec![(3, 4)]);
+    assert_eq!(
+        last_query_info(&mut conn).await.destination,
+        QueryDestination::Readyset
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn show_unsupported_queries() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE t (id INT);").await.unwrap();
+    sleep().await;
+
+    let queries: Vec<(String, String, String, Option<String>)> =
+        conn.query("SHOW UNSUPPORTED QUERIES").await.unwrap();
+    assert!(queries.is_empty());
+
+    conn.query_drop("CREATE CACHE FROM SELECT 1")
+        .await
+        .unwrap_err();
+
+    let queries: Vec<(String, String, String, Option<String>)> =
+        conn.query("SHOW UNSUPPORTED QUERIES").await.unwrap();
+    assert_eq!(queries.len(), 1);
+    let (query_id, query, _reason, feature) = &queries[0];
+    assert!(query_id.starts_with("q_"));
+    assert_eq!(query, "SELECT 1");
+    assert_eq!(feature.as_deref(), Some("no_tables"));
+
+    let filtered: Vec<(String, String, String, Option<String>)> = conn
+        .query(format!(
+            "SHOW UNSUPPORTED QUERIES WHERE query_id = '{query_id}'"
+        ))
+        .await
+        .unwrap();
+    assert_eq!(filtered, queries);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn dump_caches() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE t (x INT, y INT);")
+        .await
+        .unwrap();
+    sleep().await;
+
+    conn.query_drop("CREATE CACHE q1 FROM SELECT x FROM t WHERE y = ?")
+        .await
+        .unwrap();
+    conn.query_drop("CREATE CACHE ALWAYS q2 FROM SELECT y FROM t WHERE x = ?")
+        .await
+        .unwrap();
+
+    let statements: Vec<String> = conn.query("DUMP CACHES").await.unwrap();
+    assert_eq!(statements.len(), 2);
+    assert!(statements[0].starts_with("CREATE CACHE `q1` FROM SELECT"));
+    assert!(statements[1].starts_with("CREATE CACHE ALWAYS `q2` FROM SELECT"));
+    assert!(statements.iter().all(|stmt| stmt.ends_with(';')));
+
+    // The dumped statements can be replayed as-is
+    conn.query_drop("DROP ALL CACHES").await.unwrap();
+    for stmt in &statements {
+        conn.query_drop(stmt).await.unwrap();
+    }
+    let replayed: Vec<String> = conn.query("DUMP CACHES").await.unwrap();
+    assert_eq!(replayed, statements);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn copy_cache() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE t (x INT, y TEXT);")
+        .await
+        .unwrap();
+    conn.query_drop("INSERT INTO t (x, y) VALUES (1, 'a'), (2, 'b,c');")
+        .await
+        .unwrap();
+    sleep().await;
+
+    conn.query_drop("CREATE CACHE q FROM SELECT x, y FROM t WHERE x = ?")
+        .await
+        .unwrap();
+    for x in [1, 2] {
+        let _: Vec<(i32, String)> = conn
+            .exec("SELECT x, y FROM t WHERE x = ?", (x,))
+            .await
+            .unwrap();
+    }
+
+    let mut rows: Vec<(i32, String)> = conn.query("COPY CACHE q TO STDOUT").await.unwrap();
+    rows.sort();
+    assert_eq!(rows, vec![(1, "a".into()), (2, "b,c".into())]);
+
+    let mut csv: Vec<String> = conn
+        .query("COPY CACHE q TO STDOUT FORMAT CSV")
+        .await
+        .unwrap();
+    assert_eq!(csv.remove(0), "x,y");
+    csv.sort();
+    assert_eq!(csv, vec!["1,a".to_owned(), "2,\"b,c\"".to_owned()]);
+
+    let parquet: Vec<Vec<u8>> = conn
+        .query("COPY CACHE q TO STDOUT FORMAT PARQUET")
+        .await
+        .unwrap();
+    let file = parquet.concat();
+    assert!(file.starts_with(b"PAR1"));
+    assert!(file.ends_with(b"PAR1"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn arrow_flight() {
+    let (opts, handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE t (x INT, y INT);")
+        .await
+        .unwrap();
+    conn.query_drop("INSERT INTO t (x, y) VALUES (1, 1), (2, 2), (3, 2), (4, 3);")
+        .await
+        .unwrap();
+    sleep().await;
+
+    conn.query_drop("CREATE CACHE q FROM SELECT x FROM t WHERE y = ?")
+        .await
+        .unwrap();
+    for y in 1..=3 {
+        let _: Vec<i32> = conn
+            .exec("SELECT x FROM t WHERE y = ?", (y,))
+            .await
+            .unwrap();
+    }
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let (_valve_handle, valve) = Valve::new();
+    tokio::spawn(ArrowFlightServer::new((*handle).clone(), 2).serve(listener, valve));
+
+    let mut client = FlightServiceClient::connect(format!("http://{address}"))
+        .await
+        .unwrap();
+    let info = client
+        .get_flight_info(FlightDescriptor {
+            r#type: DescriptorType::Path as i32,
+            cmd: Default::default(),
+            path: vec!["q".into()],
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let ticket = info.endpoint[0].ticket.clone().unwrap();
+
+    let mut stream = client.do_get(ticket).await.unwrap().into_inner();
+    let schema = Arc::new(Schema::try_from(&stream.message().await.unwrap().unwrap()).unwrap());
+    let mut batch_sizes = vec![];
+    let mut xs = vec![];
+    while let Some(data) = stream.message().await.unwrap() {
+        let batch = flight_data_to_arrow_batch(&data, schema.clone(), &HashMap::new()).unwrap();
+        batch_sizes.push(batch.num_rows());
+        xs.extend(
+            batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<Int64Array>()
+                .unwrap()
+                .iter()
+                .flatten(),
+        );
+    }
+
+    // Batches are cut at the batch size, regardless of how many rows each key has
+    assert_eq!(batch_sizes, vec![2, 2]);
+    xs.sort_unstable();
+    assert_eq!(xs, vec![1, 2, 3, 4]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn select_query_hints() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE t (id INT, val INT);")
+        .await
+        .unwrap();
+    conn.query_drop("INSERT INTO t (id, val) VALUES (1, 2), (3, 4);")
+        .await
+        .unwrap();
+    sleep().await;
+
+    conn.query_drop("CREATE CACHE hinted FROM SELECT id, val FROM t WHERE id = ?;")
+        .await
+        .unwrap();
+    sleep().await;
+
+    let rows: Vec<(i32, i32)> = conn
+        .query("SELECT /*+ READYSET(cache=hinted, replay) */ id, val FROM t WHERE id = 3")
+        .await
+        .unwrap();
+    assert_eq!(rows, vec![(3, 4)]);
+    assert_eq!(
+        last_query_info(&mut conn).await.destination,
+        QueryDestination::Readyset
+    );
+
+    // Hinting a cache for a different query is an error, rather than returning that query's
+    // results
+    conn.query_drop("SELECT /*+ READYSET(cache=hinted) */ id, val FROM t WHERE val = 4")
+        .await
+        .unwrap_err();
+
+    // Hinting a cache that doesn't exist is an error, rather than silently being ignored
+    conn.query_drop("SELECT /*+ READYSET(cache=nonexistent) */ id, val FROM t WHERE id = 3")
+        .await
+        .unwrap_err();
+
+    // There's nowhere to proxy to without an upstream database
+    conn.query_drop("SELECT /*+ NOCACHE */ id, val FROM t WHERE id = 3")
+        .await
+        .unwrap_err();
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn show_caches_with_always() {
     let (opts, _handle) = setup().await;
@@ -1679,6 +2399,137 @@ async fn show_readyset_version() {
         .expect("should be OK");
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn min_token_round_trip() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+
+    let token: Option<String> = conn.query_first("SELECT LAST_WRITE_TOKEN()").await.unwrap();
+    assert_eq!(token.as_deref(), Some(""));
+
+    conn.query_drop("SET @readyset_min_token = '3:17,1:42'")
+        .await
+        .unwrap();
+    let token: Option<String> = conn.query_first("SELECT LAST_WRITE_TOKEN()").await.unwrap();
+    assert_eq!(token.as_deref(), Some("1:42,3:17"));
+
+    conn.query_drop("SET @readyset_min_token = 'not a token'")
+        .await
+        .unwrap_err();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn min_token_blocks_reads() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE t (id int, val int)")
+        .await
+        .unwrap();
+    conn.query_drop("INSERT INTO t (id, val) VALUES (1, 2)")
+        .await
+        .unwrap();
+    sleep().await;
+    conn.query_drop("CREATE CACHE q FROM SELECT val FROM t WHERE id = ?")
+        .await
+        .unwrap();
+
+    let val: Option<i32> = conn
+        .exec_first("SELECT val FROM t WHERE id = ?", (1,))
+        .await
+        .unwrap();
+    assert_eq!(val, Some(2));
+
+    // No timestamps are propagated for `t`, so the cache can never reflect this token, and reads
+    // have to wait for it
+    conn.query_drop("SET @readyset_min_token = '1:1'")
+        .await
+        .unwrap();
+    let res = tokio::time::timeout(
+        Duration::from_secs(1),
+        conn.exec_first::<i32, _, _>("SELECT val FROM t WHERE id = ?", (1,)),
+    )
+    .await;
+    assert!(res.is_err(), "read should block on the token, got {res:?}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn min_token_reads_bypass_micro_cache() {
+    readyset_tracing::init_test_logging();
+    let (opts, _handle) = TestBuilder::default()
+        .micro_cache(Arc::new(MicroCache::new(Duration::from_secs(60), 100)))
+        .build::<MySQLAdapter>()
+        .await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE t (id int, val int)")
+        .await
+        .unwrap();
+    conn.query_drop("INSERT INTO t (id, val) VALUES (1, 2)")
+        .await
+        .unwrap();
+    sleep().await;
+    conn.query_drop("CREATE CACHE q FROM SELECT val FROM t WHERE id = ?")
+        .await
+        .unwrap();
+
+    // Populate the micro cache
+    for _ in 0..2 {
+        let val: Option<i32> = conn
+            .exec_first("SELECT val FROM t WHERE id = ?", (1,))
+            .await
+            .unwrap();
+        assert_eq!(val, Some(2));
+    }
+
+    // The token stands for a write the cached result can't reflect, so the read has to go to the
+    // reader (and wait for the token there) rather than being answered from the micro cache
+    conn.query_drop("SET @readyset_min_token = '1:1'")
+        .await
+        .unwrap();
+    let res = tokio::time::timeout(
+        Duration::from_secs(1),
+        conn.exec_first::<i32, _, _>("SELECT val FROM t WHERE id = ?", (1,)),
+    )
+    .await;
+    assert!(res.is_err(), "read should block on the token, got {res:?}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn snapshot_reads() {
+    let (opts, _handle) = setup().await;
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+    conn.query_drop("CREATE TABLE t1 (x int, y int)")
+        .await
+        .unwrap();
+    conn.query_drop("CREATE TABLE t2 (x int, z int)")
+        .await
+        .unwrap();
+    conn.query_drop("INSERT INTO t1 (x, y) VALUES (1, 2)")
+        .await
+        .unwrap();
+    conn.query_drop("INSERT INTO t2 (x, z) VALUES (1, 3)")
+        .await
+        .unwrap();
+    sleep().await;
+
+    conn.query_drop("SET @readyset_snapshot_reads = 1")
+        .await
+        .unwrap();
+    let y: Vec<i32> = conn
+        .exec("SELECT y FROM t1 WHERE x = ?", (1,))
+        .await
+        .unwrap();
+    assert_eq!(y, vec![2]);
+    let z: Vec<i32> = conn
+        .exec("SELECT z FROM t2 WHERE x = ?", (1,))
+        .await
+        .unwrap();
+    assert_eq!(z, vec![3]);
+
+    conn.query_drop("SET @readyset_snapshot_reads = 0")
+        .await
+        .unwrap();
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn simple_nonblocking_select() {
     let (opts, _handle) = TestBuilder::default()
@@ -1740,6 +2591,59 @@ async fn non_default_db_in_connection_opts() {
     conn.query_drop("SELECT a from s1.t").await.unwrap();
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn information_schema_without_upstream() {
+    let (opts, _handle) = setup().await;
+    let opts = OptsBuilder::from_opts(opts).db_name(Some("s1"));
+    let mut conn = mysql_async::Conn::new(opts).await.unwrap();
+
+    conn.query_drop("CREATE TABLE t1 (id int primary key, x text)")
+        .await
+        .unwrap();
+    conn.query_drop("CREATE TABLE t2 (y int)").await.unwrap();
+    sleep().await;
+
+    let tables: Vec<String> = conn.query("SHOW TABLES").await.unwrap();
+    assert_eq!(tables, vec!["t1".to_owned(), "t2".to_owned()]);
+
+    let columns: Vec<(String, String, String)> = conn
+        .query(
+            "SELECT column_name, data_type, column_key FROM information_schema.columns \
+             WHERE table_schema = DATABASE() AND table_name = 't1' ORDER BY ordinal_position",
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        columns,
+        vec![
+            ("id".to_owned(), "int".to_owned(), "PRI".to_owned()),
+            ("x".to_owned(), "text".to_owned(), "".to_owned()),
+        ]
+    );
+
+    let fields: Vec<(String, String, String, String, Option<String>, String)> =
+        conn.query("SHOW COLUMNS FROM t2").await.unwrap();
+    assert_eq!(
+        fields,
+        vec![(
+            "y".to_owned(),
+            "int".to_owned(),
+            "YES".to_owned(),
+            "".to_owned(),
+            None,
+            "".to_owned()
+        )]
+    );
+
+    let (table, create): (String, String) = conn
+        .query_first("SHOW CREATE TABLE t2")
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(table, "t2");
+    assert_eq!(create, "CREATE TABLE `t2` (`y` INT)");
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn test_show_proxied_queries_telemetry() {
     readyset_tracing::init_test_logging();
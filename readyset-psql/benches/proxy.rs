@@ -126,6 +126,7 @@ impl psql_srv::Backend for Backend {
                         .collect()
                 })
                 .collect(),
+            warning: None,
         })
     }
 
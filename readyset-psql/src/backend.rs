@@ -12,8 +12,7 @@ use crate::error::Error;
 use crate::query_handler::PostgreSqlQueryHandler;
 use crate::response::{PrepareResponse, QueryResponse};
 use crate::resultset::Resultset;
-use crate::row::Row;
-use crate::value::Value;
+use crate::row::{Row, RowValue};
 use crate::PostgreSqlUpstream;
 
 /// A `noria_client` `Backend` wrapper that implements `psql_srv::Backend`. PostgreSQL client
@@ -46,7 +45,7 @@ impl Backend {
 
 #[async_trait]
 impl ps::Backend for Backend {
-    type Value = Value;
+    type Value = RowValue;
     type Row = Row;
     type Resultset = Resultset;
 
@@ -90,6 +89,7 @@ impl ps::Backend for Backend {
         match credentials {
             ps::Credentials::Cleartext { user, password } => {
                 if self.users.get(&user) == Some(&password) {
+                    self.set_user(&user);
                     return Ok(());
                 }
                 return Err(ps::Error::AuthenticationFailure(user));
@@ -27,12 +27,57 @@ impl From<Error> for ps::Error {
                 ps::Error::MissingPreparedStatement(statement_id.to_string())
             }
             ReadySet(ReadySetError::Unsupported(s)) => ps::Error::Unsupported(s),
-            ReadySet(e) => ps::Error::Unknown(e.to_string()),
+            ReadySet(ReadySetError::UnsupportedFeature { message, .. }) => {
+                ps::Error::Unsupported(message)
+            }
+            ReadySet(e) => readyset_error(e),
             PostgreSql(e) => e.into(),
         }
     }
 }
 
+/// Converts the given [`ReadySetError`] into the [`ps::Error`] whose SQLSTATE most closely matches
+/// the root cause of the error, so that clients can react to the error (eg by retrying) the same
+/// way they would if it came from PostgreSQL itself. The full error, including any context it was
+/// wrapped in, is used as the message.
+fn readyset_error(e: ReadySetError) -> ps::Error {
+    let message = e.to_string();
+    match e.root_cause() {
+        ReadySetError::UnparseableQuery { .. } => ps::Error::ParseError(message),
+        ReadySetError::Unsupported(_) | ReadySetError::UnsupportedFeature { .. } => {
+            ps::Error::Unsupported(message)
+        }
+        ReadySetError::PreparedStatementMissing { .. } => {
+            ps::Error::MissingPreparedStatement(message)
+        }
+        ReadySetError::TableNotFound { .. }
+        | ReadySetError::TableNotReplicated { .. }
+        | ReadySetError::ViewNotFound(_)
+        | ReadySetError::ViewNotFoundInWorkers { .. }
+        | ReadySetError::ViewDestroyed => ps::Error::UndefinedTable(message),
+        ReadySetError::ViewAlreadyExists(_) => ps::Error::DuplicateTable(message),
+        ReadySetError::NoSuchColumn(_) => ps::Error::UndefinedColumn(message),
+        ReadySetError::NoSuchFunction(_) | ReadySetError::ArityError(_) => {
+            ps::Error::UndefinedFunction(message)
+        }
+        ReadySetError::WrongColumnCount(..) => ps::Error::SyntaxError(message),
+        ReadySetError::NonNullable { .. } | ReadySetError::ColumnRequired { .. } => {
+            ps::Error::NotNullViolation(message)
+        }
+        ReadySetError::ExprNotInGroupBy { .. } => ps::Error::GroupingError(message),
+        ReadySetError::CheckConstraintViolated { .. } => ps::Error::CheckViolation(message),
+        ReadySetError::DuplicateEntry { .. } => ps::Error::UniqueViolation(message),
+        ReadySetError::UpqueryTimeout => ps::Error::QueryCanceled(message),
+        ReadySetError::FallbackUnavailable(_) | ReadySetError::NamespaceQuotaExceeded { .. } => {
+            ps::Error::InsufficientResources(message)
+        }
+        ReadySetError::ResultSetTooLarge(_) => ps::Error::ProgramLimitExceeded(message),
+        ReadySetError::AccessDenied(_) => ps::Error::InsufficientPrivilege(message),
+        ReadySetError::Internal(_) => ps::Error::InternalError(message),
+        _ => ps::Error::Unknown(message),
+    }
+}
+
 impl IsFatalError for Error {
     fn is_fatal(&self) -> bool {
         // For now we have no way of matching on the inner error kind ofr postgres errors, so
@@ -40,3 +85,27 @@ impl IsFatalError for Error {
         matches!(self, Self::PostgreSql(e) if e.is_closed())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrapped_readyset_errors_use_root_cause() {
+        let err = ps::Error::from(Error::from(ReadySetError::RpcFailed {
+            during: "view_builder".into(),
+            source: Box::new(ReadySetError::ViewNotFound("q_1".into())),
+        }));
+        assert!(matches!(err, ps::Error::UndefinedTable(_)));
+        assert!(err.to_string().contains("q_1"));
+    }
+
+    #[test]
+    fn upquery_timeout_is_query_canceled() {
+        let err = ps::Error::from(Error::from(
+            ReadySetError::UpqueryTimeout.context("while looking up key"),
+        ));
+        assert!(matches!(err, ps::Error::QueryCanceled(_)));
+        assert!(err.to_string().contains("while looking up key"));
+    }
+}
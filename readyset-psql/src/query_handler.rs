@@ -11,6 +11,10 @@ use readyset_adapter::backend::{noria_connector, SelectSchema};
 use readyset_adapter::{QueryHandler, SetBehavior};
 use readyset_client::ReadySetResult;
 
+/// The parameter which sets the read behavior (one of `blocking`, `non_blocking` or
+/// `stale_if_available`) for reads in the current session
+const READ_BEHAVIOR_PARAMETER: &str = "readyset_read_behavior";
+
 enum AllowedParameterValue {
     Literal(PostgresParameterValue),
     OneOf(HashSet<PostgresParameterValue>),
@@ -390,6 +394,19 @@ impl QueryHandler for PostgreSqlQueryHandler {
 
                     SetBehavior::SetSearchPath(search_path)
                 }
+                name if name.eq_ignore_ascii_case(READ_BEHAVIOR_PARAMETER) => match value {
+                    SetPostgresParameterValue::Value(PostgresParameterValue::Single(
+                        PostgresParameterValueInner::Identifier(read_behavior),
+                    )) => read_behavior
+                        .parse()
+                        .map_or(SetBehavior::Unsupported, SetBehavior::SetReadBehavior),
+                    SetPostgresParameterValue::Value(PostgresParameterValue::Single(
+                        PostgresParameterValueInner::Literal(Literal::String(read_behavior)),
+                    )) => read_behavior
+                        .parse()
+                        .map_or(SetBehavior::Unsupported, SetBehavior::SetReadBehavior),
+                    _ => SetBehavior::Unsupported,
+                },
                 _ => {
                     if let Some(allowed_value) = ALLOWED_PARAMETERS_WITH_VALUE.get(name.as_str()) {
                         SetBehavior::proxy_if(allowed_value.set_value_is_allowed(value))
@@ -409,6 +426,7 @@ impl QueryHandler for PostgreSqlQueryHandler {
 #[cfg(test)]
 mod tests {
     use nom_sql::{parse_query, Dialect};
+    use readyset_adapter::backend::noria_connector::ReadBehavior;
 
     use super::*;
 
@@ -453,6 +471,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn set_read_behavior() {
+        let set_read_behavior =
+            |stmt: &str| PostgreSqlQueryHandler::handle_set_statement(&parse_set_statement(stmt));
+        assert_eq!(
+            set_read_behavior("SET readyset_read_behavior = 'stale_if_available'"),
+            SetBehavior::SetReadBehavior(ReadBehavior::StaleIfAvailable)
+        );
+        assert_eq!(
+            set_read_behavior("SET readyset_read_behavior = NON_BLOCKING"),
+            SetBehavior::SetReadBehavior(ReadBehavior::NonBlocking)
+        );
+        assert_eq!(
+            set_read_behavior("SET readyset_read_behavior = 'sometimes'"),
+            SetBehavior::Unsupported
+        );
+        assert_eq!(
+            set_read_behavior("SET readyset_read_behavior = DEFAULT"),
+            SetBehavior::Unsupported
+        );
+    }
+
     mod search_path {
         use super::*;
 
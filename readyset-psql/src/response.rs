@@ -62,6 +62,16 @@ impl<'a> PrepareResponse<'a> {
     }
 }
 
+/// Returns a warning to send to the client if any of the rows written by a query exceeded the
+/// maximum row size
+fn oversized_rows_warning(oversized_rows: u64) -> Option<String> {
+    (oversized_rows > 0).then(|| {
+        format!(
+            "{oversized_rows} row(s) exceeded the maximum row size, and were truncated or skipped"
+        )
+    })
+}
+
 /// A simple wrapper around `noria_client`'s `QueryResult`, facilitating conversion to
 /// `psql_srv::QueryResponse`.
 pub struct QueryResponse<'a>(pub cl::QueryResult<'a, PostgreSqlUpstream>);
@@ -77,19 +87,39 @@ impl<'a> TryFrom<QueryResponse<'a>> for ps::QueryResponse<Resultset> {
         match r.0 {
             Noria(NoriaResult::Empty) => Ok(Command),
             Noria(NoriaResult::Insert {
-                num_rows_inserted, ..
-            }) => Ok(Insert(num_rows_inserted)),
-            Noria(NoriaResult::Select { rows, schema }) => {
+                num_rows_inserted,
+                oversized_rows,
+                ..
+            }) => Ok(Insert {
+                rows: num_rows_inserted,
+                warning: oversized_rows_warning(oversized_rows),
+            }),
+            Noria(NoriaResult::Select {
+                rows,
+                schema,
+                truncated,
+                ..
+            }) => {
                 let select_schema = SelectSchema(schema);
                 let resultset = Resultset::try_new(rows, &select_schema)?;
                 Ok(Select {
                     schema: select_schema.try_into()?,
                     resultset,
+                    warning: truncated.then(|| {
+                        "the result was truncated because it exceeded the result limits of the \
+                         cache"
+                            .to_string()
+                    }),
                 })
             }
             Noria(NoriaResult::Update {
-                num_rows_updated, ..
-            }) => Ok(Update(num_rows_updated)),
+                num_rows_updated,
+                oversized_rows,
+                ..
+            }) => Ok(Update {
+                rows: num_rows_updated,
+                warning: oversized_rows_warning(oversized_rows),
+            }),
             Noria(NoriaResult::Delete { num_rows_deleted }) => Ok(Delete(num_rows_deleted)),
             Noria(NoriaResult::Meta(vars)) => {
                 let columns = vars.iter().map(|v| v.name.clone()).collect::<Vec<_>>();
@@ -121,6 +151,7 @@ impl<'a> TryFrom<QueryResponse<'a>> for ps::QueryResponse<Resultset> {
                 Ok(Select {
                     schema: select_schema.try_into()?,
                     resultset,
+                    warning: None,
                 })
             }
             Noria(NoriaResult::MetaVariables(vars)) => {
@@ -158,6 +189,7 @@ impl<'a> TryFrom<QueryResponse<'a>> for ps::QueryResponse<Resultset> {
                 Ok(Select {
                     schema: select_schema.try_into()?,
                     resultset,
+                    warning: None,
                 })
             }
             Noria(NoriaResult::MetaWithHeader(vars)) => {
@@ -197,6 +229,7 @@ impl<'a> TryFrom<QueryResponse<'a>> for ps::QueryResponse<Resultset> {
                 Ok(Select {
                     schema: select_schema.try_into()?,
                     resultset,
+                    warning: None,
                 })
             }
             Upstream(upstream::QueryResult::Read { data: rows }) => {
@@ -214,11 +247,13 @@ impl<'a> TryFrom<QueryResponse<'a>> for ps::QueryResponse<Resultset> {
                 Ok(ps::QueryResponse::Select {
                     schema,
                     resultset: Resultset::try_from(rows)?,
+                    warning: None,
                 })
             }
-            Upstream(upstream::QueryResult::Write { num_rows_affected }) => {
-                Ok(Insert(num_rows_affected))
-            }
+            Upstream(upstream::QueryResult::Write { num_rows_affected }) => Ok(Insert {
+                rows: num_rows_affected,
+                warning: None,
+            }),
             Upstream(upstream::QueryResult::Command) => Ok(Command),
             Upstream(upstream::QueryResult::SimpleQuery(resp)) => Ok(SimpleQuery(resp)),
         }
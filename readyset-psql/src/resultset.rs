@@ -5,6 +5,7 @@ use std::sync::Arc;
 use psql_srv as ps;
 use readyset_client::results::{ResultIterator, Results};
 use readyset_data::DfValue;
+use streaming_iterator::StreamingIterator;
 use tokio_postgres::types::Type;
 
 use crate::row::Row;
@@ -45,13 +46,24 @@ impl IntoIterator for Resultset {
     type IntoIter = impl Iterator<Item = Row>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.results
-            .into_iter()
-            .zip(iter::repeat(self.project_field_types))
-            .map(|(values, project_field_types)| Row {
-                values,
-                project_field_types,
-            })
+        let project_field_types = self.project_field_types;
+        let mut results = self.results;
+        iter::from_fn(move || {
+            let project_field_types = Arc::clone(&project_field_types);
+            match results.next() {
+                Some(values) => Some(Row {
+                    values: values.to_vec(),
+                    project_field_types,
+                    error: None,
+                }),
+                // Results which are streamed can end early if producing the rest of them fails
+                None => results.take_error().map(|e| Row {
+                    values: vec![],
+                    project_field_types,
+                    error: Some(ps::Error::InternalError(e.to_string())),
+                }),
+            }
+        })
     }
 }
 
@@ -94,6 +106,7 @@ mod tests {
     use readyset_adapter::backend as cl;
     use readyset_client::ColumnSchema;
     use readyset_data::{DfType, DfValue};
+    use readyset_errors::ReadySetError;
 
     use super::*;
 
@@ -170,4 +183,32 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn iterate_streamed_resultset_with_error() {
+        let (tx, rx) = tokio::sync::mpsc::channel(2);
+        tx.try_send(Ok(vec![vec![DfValue::Int(10)], vec![DfValue::Int(11)]]))
+            .unwrap();
+        tx.try_send(Err(ReadySetError::Internal("oh no".into())))
+            .unwrap();
+        drop(tx);
+        let schema = SelectSchema(cl::SelectSchema {
+            use_bogo: false,
+            schema: Cow::Owned(vec![ColumnSchema {
+                column: "tab1.col1".into(),
+                column_type: DfType::BigInt,
+                base: None,
+            }]),
+            columns: Cow::Owned(vec!["col1".into()]),
+        });
+        let resultset = Resultset::try_new(ResultIterator::streamed(rx), &schema).unwrap();
+        let rows = resultset
+            .into_iter()
+            .map(|r| r.into_iter().map(ps::Value::try_from).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0][0].as_ref().unwrap(), &ps::Value::BigInt(10));
+        assert_eq!(rows[1][0].as_ref().unwrap(), &ps::Value::BigInt(11));
+        assert!(rows[2][0].is_err());
+    }
 }
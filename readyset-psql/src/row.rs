@@ -1,5 +1,7 @@
+use std::convert::TryFrom;
 use std::sync::Arc;
 
+use psql_srv as ps;
 use readyset_data::DfValue;
 use tokio_postgres::types::Type;
 
@@ -15,10 +17,31 @@ pub struct Row {
 
     /// The data types of the projected fields for this row.
     pub project_field_types: Arc<Vec<Type>>,
+
+    /// An error which ended the results this row is part of early, in place of the row's values.
+    pub error: Option<ps::Error>,
+}
+
+/// A value yielded when iterating over a `Row`: either a `Value`, or the error which ended the
+/// results the row is part of early, to be returned to the client when it's encoded.
+pub enum RowValue {
+    Value(Value),
+    Error(ps::Error),
+}
+
+impl TryFrom<RowValue> for ps::Value {
+    type Error = ps::Error;
+
+    fn try_from(v: RowValue) -> Result<Self, Self::Error> {
+        match v {
+            RowValue::Value(v) => ps::Value::try_from(v),
+            RowValue::Error(e) => Err(e),
+        }
+    }
 }
 
 impl IntoIterator for Row {
-    type Item = Value;
+    type Item = RowValue;
     type IntoIter = RowIterator;
 
     fn into_iter(self) -> Self::IntoIter {
@@ -37,13 +60,16 @@ pub struct RowIterator {
 }
 
 impl Iterator for RowIterator {
-    type Item = Value;
+    type Item = RowValue;
 
-    fn next(&mut self) -> Option<Value> {
+    fn next(&mut self) -> Option<RowValue> {
+        if let Some(error) = self.row.error.take() {
+            return Some(RowValue::Error(error));
+        }
         let col_type = self.row.project_field_types.get(self.pos)?.clone();
         let value = self.row.values.get(self.pos)?.clone();
         self.pos += 1;
-        Some(Value { col_type, value })
+        Some(RowValue::Value(Value { col_type, value }))
     }
 }
 
@@ -67,6 +93,7 @@ mod tests {
         let row = Row {
             values: vec![],
             project_field_types: Arc::new(vec![]),
+            error: None,
         };
         assert_eq!(collect_row_values(row), Vec::<ps::Value>::new());
     }
@@ -76,6 +103,7 @@ mod tests {
         let row = Row {
             values: vec![DfValue::Int(43)],
             project_field_types: Arc::new(vec![Type::INT4]),
+            error: None,
         };
         assert_eq!(collect_row_values(row), vec![ps::Value::Int(43)]);
     }
@@ -97,6 +125,7 @@ mod tests {
                 Type::FLOAT4,
                 Type::NUMERIC,
             ]),
+            error: None,
         };
         assert_eq!(
             collect_row_values(row),
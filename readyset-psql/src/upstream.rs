@@ -130,16 +130,7 @@ impl UpstreamDatabase for PostgreSqlUpstream {
 
         let pg_config = pgsql::Config::from_str(url)?;
         let user = pg_config.get_user().map(|s| s.to_owned());
-        let connector = {
-            let mut builder = native_tls::TlsConnector::builder();
-            if upstream_config.disable_upstream_ssl_verification {
-                builder.danger_accept_invalid_certs(true);
-            }
-            if let Some(cert) = upstream_config.get_root_cert().await {
-                builder.add_root_certificate(cert?);
-            }
-            builder.build().unwrap() // Never returns an error
-        };
+        let connector = upstream_config.native_tls_connector().await?;
         let tls = postgres_native_tls::MakeTlsConnector::new(connector);
         let span = info_span!(
             "Connecting to PostgreSQL upstream",
@@ -573,6 +573,64 @@ async fn update_pkey() {
     assert!(old_row.is_none());
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn joined_writes() {
+    let (opts, _handle) = setup().await;
+    let conn = connect(opts).await;
+    conn.simple_query("CREATE TABLE Cats (id int PRIMARY KEY, name VARCHAR(255))")
+        .await
+        .unwrap();
+    conn.simple_query("CREATE TABLE Adoptions (id int PRIMARY KEY, cat_id int, status int)")
+        .await
+        .unwrap();
+    sleep().await;
+
+    conn.simple_query("INSERT INTO Cats (id, name) VALUES (1, 'Bob'), (2, 'Jane'), (3, 'Tom')")
+        .await
+        .unwrap();
+    conn.simple_query(
+        "INSERT INTO Adoptions (id, cat_id, status) VALUES (1, 1, 1), (2, 2, 1), (3, 3, 2)",
+    )
+    .await
+    .unwrap();
+    sleep().await;
+
+    let updated = conn
+        .execute(
+            "UPDATE Cats SET name = 'Adopted' FROM Adoptions \
+             WHERE Cats.id = Adoptions.cat_id AND Adoptions.status = 2",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(updated, 1);
+    let deleted = conn
+        .execute(
+            "DELETE FROM Cats USING Adoptions \
+             WHERE Cats.id = Adoptions.cat_id AND Adoptions.status = 1",
+            &[],
+        )
+        .await
+        .unwrap();
+    assert_eq!(deleted, 2);
+    sleep().await;
+
+    // The keys to write to are read from the joined table directly, without creating a cache
+    let caches = conn.simple_query("SHOW CACHES").await.unwrap();
+    assert!(!caches
+        .iter()
+        .any(|msg| matches!(msg, SimpleQueryMessage::Row(_))));
+
+    let rows = conn
+        .query("SELECT Cats.id, Cats.name FROM Cats", &[])
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|row| (row.get::<_, i32>(0), row.get::<_, String>(1)))
+        .collect::<Vec<_>>();
+    assert_eq!(rows, vec![(3, "Adopted".to_owned())]);
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn update_separate() {
     let (opts, _handle) = setup().await;
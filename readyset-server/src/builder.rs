@@ -1,18 +1,26 @@
 use std::future::Future;
 use std::net::{IpAddr, SocketAddr};
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{self, Duration};
 
 use database_utils::UpstreamConfig;
-use dataflow::PersistenceParameters;
+use dataflow::node::special::ReplicationConflictPolicy;
+use dataflow::{DurabilityMode, PersistenceParameters};
+use readyset_client::channel::ChannelSecurity;
 use readyset_client::consensus::{
     Authority, LocalAuthority, LocalAuthorityStore, NodeTypeSchedulingRestriction,
     WorkerSchedulingConfig,
 };
+use readyset_client::namespace::NamespaceQuotas;
+use readyset_client::placement::{PlacementConstraints, WorkerResources};
 use readyset_telemetry_reporter::TelemetrySender;
+use replicators::ReplicationTransformHook;
 
 use crate::controller::replication::ReplicationStrategy;
 use crate::handle::Handle;
+use crate::startup::ListenConfig;
 use crate::{Config, FrontierStrategy, ReuseConfigType, VolumeId};
 
 /// Used to construct a worker.
@@ -21,8 +29,10 @@ pub struct Builder {
     config: Config,
     memory_limit: Option<usize>,
     memory_check_frequency: Option<time::Duration>,
-    listen_addr: IpAddr,
+    listen_addrs: Vec<IpAddr>,
+    internal_listen_addr: Option<IpAddr>,
     external_addr: SocketAddr,
+    internal_external_addr: Option<IpAddr>,
     leader_eligible: bool,
     domain_scheduling_config: WorkerSchedulingConfig,
     /// The telelemetry sender
@@ -35,8 +45,10 @@ impl Default for Builder {
         #[allow(clippy::unwrap_used)] // hardcoded literals
         Self {
             config: Config::default(),
-            listen_addr: "127.0.0.1".parse().unwrap(),
+            listen_addrs: vec!["127.0.0.1".parse().unwrap()],
+            internal_listen_addr: None,
             external_addr: "127.0.0.1:6033".parse().unwrap(),
+            internal_external_addr: None,
             memory_limit: None,
             memory_check_frequency: None,
             leader_eligible: true,
@@ -55,6 +67,32 @@ impl Builder {
             builder.set_memory_limit(opts.memory, Duration::from_secs(opts.memory_check_freq));
         }
         builder.set_eviction_kind(opts.eviction_kind);
+        builder.set_text_interning_pool_size(opts.text_interning_pool_size);
+        if let Some(cpus) = opts.domain_cpus {
+            builder.set_domain_cpus(cpus.0);
+        }
+        if let Some(cpus) = opts.reader_cpus {
+            builder.set_reader_cpus(cpus.0);
+        }
+        builder.set_channel_security(opts.channel_security.security());
+        builder.set_namespace_quotas(opts.namespace_quotas.quotas());
+        builder.set_replay_spill_threshold(opts.replay_spill_threshold);
+        builder.set_domain_packet_log_dir(opts.domain_packet_log_dir);
+        builder.set_replication_conflict_policy(opts.replication_conflict_policy);
+        builder.set_record_provenance(opts.record_provenance);
+        builder.set_reader_key_ttl(
+            opts.reader_key_ttl_seconds.map(Duration::from_secs),
+            opts.reader_key_ttl_jitter_percent,
+        );
+        builder.set_max_key_expirations_per_second(opts.max_key_expirations_per_second);
+        builder.set_state_checksum_interval(
+            opts.state_checksum_interval_seconds
+                .map(Duration::from_secs),
+        );
+        builder.set_domain_restart_budget(
+            opts.domain_restart_budget,
+            Duration::from_secs(opts.domain_restart_window_seconds),
+        );
 
         builder.set_sharding(match opts.shards {
             0 | 1 => None,
@@ -67,6 +105,7 @@ impl Builder {
         if opts.forbid_full_materialization {
             builder.forbid_full_materialization();
         }
+        builder.set_max_full_materialization_bytes(opts.max_full_materialization_bytes);
         if opts.enable_packet_filters {
             builder.enable_packet_filters();
         }
@@ -78,6 +117,7 @@ impl Builder {
         builder.set_allow_topk(opts.enable_experimental_topk_support);
         builder.set_allow_paginate(opts.enable_experimental_paginate_support);
         builder.set_allow_mixed_comparisons(opts.enable_experimental_mixed_comparisons);
+        builder.set_upsert_duplicate_keys(opts.upsert_duplicate_keys);
 
         builder.set_replication_strategy(opts.domain_replication_options.into());
 
@@ -85,6 +125,17 @@ impl Builder {
             builder.set_volume_id(volume_id);
         }
 
+        builder.set_worker_resources(WorkerResources {
+            memory_bytes: (opts.memory > 0).then_some(opts.memory),
+            cores: std::thread::available_parallelism()
+                .ok()
+                .map(NonZeroUsize::get),
+            disk_backed: opts.durability != DurabilityMode::MemoryOnly,
+            zone: opts.placement.worker_zone.clone(),
+            labels: opts.placement.worker_labels.iter().cloned().collect(),
+        });
+        builder.set_placement_constraints(opts.placement.constraints());
+
         let persistence_params = PersistenceParameters::new(
             opts.durability,
             Some(deployment.into()),
@@ -137,6 +188,17 @@ impl Builder {
             .allow_full_materialization = false;
     }
 
+    /// Limit the estimated size of the state of each new fully materialized node to `bytes`.
+    ///
+    /// After this is called, any migrations that add fully materialized nodes estimated to be
+    /// larger than that, other than for caches created with `CREATE CACHE ALWAYS`, will return
+    /// [`ReadySetError::FullMaterializationOverBudget`]
+    pub fn set_max_full_materialization_bytes(&mut self, bytes: Option<usize>) {
+        self.config
+            .materialization_config
+            .max_full_materialization_bytes = bytes;
+    }
+
     /// Set sharding policy for all subsequent migrations; `None` or `Some(x)` where x <= 1 disables
     pub fn set_sharding(&mut self, shards: Option<usize>) {
         self.config.sharding = shards.filter(|s| *s > 1);
@@ -159,7 +221,21 @@ impl Builder {
 
     /// Set the IP address that the worker should use for listening.
     pub fn set_listen_addr(&mut self, listen_addr: IpAddr) {
-        self.listen_addr = listen_addr;
+        self.listen_addrs = vec![listen_addr];
+    }
+
+    /// Set the IP addresses that the worker should listen on for traffic from clients (the HTTP
+    /// API and readers). The same port is used on every address.
+    pub fn set_listen_addrs(&mut self, listen_addrs: Vec<IpAddr>) {
+        assert!(!listen_addrs.is_empty());
+        self.listen_addrs = listen_addrs;
+    }
+
+    /// Set the IP address that the worker should listen on for traffic from other workers
+    /// (connections between domains). Defaults to the first of the addresses set by
+    /// [`set_listen_addrs`](Self::set_listen_addrs).
+    pub fn set_internal_listen_addr(&mut self, internal_listen_addr: IpAddr) {
+        self.internal_listen_addr = Some(internal_listen_addr);
     }
 
     /// Set the external IP address and port that the worker should advertise to
@@ -168,6 +244,32 @@ impl Builder {
         self.external_addr = external_addr;
     }
 
+    /// Returns the external IP address and port that the worker advertises to other noria
+    /// instances
+    pub fn external_addr(&self) -> SocketAddr {
+        self.external_addr
+    }
+
+    /// Set the IP address that the worker should advertise to other workers for connections
+    /// between domains, if it differs from the external address (for example, if clients reach
+    /// the worker through NAT, but other workers don't). Defaults to the IP of the address set by
+    /// [`set_external_addr`](Self::set_external_addr).
+    pub fn set_internal_external_addr(&mut self, internal_external_addr: IpAddr) {
+        self.internal_external_addr = Some(internal_external_addr);
+    }
+
+    /// Returns the addresses the worker listens on and advertises
+    fn listen_config(&self) -> ListenConfig {
+        ListenConfig {
+            listen_addrs: self.listen_addrs.clone(),
+            internal_listen_addr: self.internal_listen_addr.unwrap_or(self.listen_addrs[0]),
+            external_addr: self.external_addr,
+            internal_external_addr: self
+                .internal_external_addr
+                .unwrap_or_else(|| self.external_addr.ip()),
+        }
+    }
+
     /// Set the reuse policy for all subsequent migrations
     pub fn set_reuse(&mut self, reuse_type: Option<ReuseConfigType>) {
         self.config.reuse = reuse_type;
@@ -188,6 +290,11 @@ impl Builder {
         self.config.mir_config.allow_mixed_comparisons = allow_mixed_comparisons;
     }
 
+    /// Set the value of [`controller::sql::Config::upsert_duplicate_keys`]
+    pub fn set_upsert_duplicate_keys(&mut self, upsert_duplicate_keys: bool) {
+        self.config.mir_config.upsert_duplicate_keys = upsert_duplicate_keys;
+    }
+
     /// Set the value of [`DomainConfig::aggressively_update_state_sizes`][0]. See the documentation
     /// of that field for more information
     ///
@@ -206,6 +313,12 @@ impl Builder {
         self.config.replicator_config = config;
     }
 
+    /// Sets a hook to transform rows replicated from the upstream database before they're written
+    /// to base tables. See [`replicators::ReplicationTransform`] for more information.
+    pub fn set_replication_transform(&mut self, transform: ReplicationTransformHook) {
+        self.config.replication_transform = transform;
+    }
+
     /// Sets the value of [`replicators::Config::disable_upstream_ssl_verification`]
     pub fn set_disable_upstream_ssl_verification(&mut self, value: bool) {
         self.config
@@ -251,6 +364,18 @@ impl Builder {
         self.domain_scheduling_config.volume_id = Some(volume_id);
     }
 
+    /// Sets the resources and other properties this server advertises to the controller when it
+    /// registers, which are checked against any placement constraints when scheduling domains
+    pub fn set_worker_resources(&mut self, resources: WorkerResources) {
+        self.domain_scheduling_config.resources = resources;
+    }
+
+    /// Sets the constraints on which servers domains containing readers or base tables may be
+    /// scheduled onto, for all migrations. See [`readyset_client::placement`].
+    pub fn set_placement_constraints(&mut self, constraints: PlacementConstraints) {
+        self.config.placement_constraints = constraints;
+    }
+
     /// Set the value of [`Config::abort_on_task_failure`]. See the documentation of that field for
     /// more information.
     pub fn set_abort_on_task_failure(&mut self, abort_on_task_failure: bool) {
@@ -287,6 +412,88 @@ impl Builder {
         self.config.domain_config.eviction_kind = value;
     }
 
+    /// Sets the value of [`Config::domain_config::replay_spill_threshold`]. See documentation of
+    /// that field for more information.
+    pub fn set_replay_spill_threshold(&mut self, value: Option<usize>) {
+        self.config.domain_config.replay_spill_threshold = value;
+    }
+
+    /// Sets the value of [`Config::domain_config::packet_log_dir`]. See documentation of that
+    /// field for more information.
+    pub fn set_domain_packet_log_dir(&mut self, value: Option<PathBuf>) {
+        self.config.domain_config.packet_log_dir = value;
+    }
+
+    /// Sets the value of [`Config::domain_config::replication_conflict_policy`]. See documentation
+    /// of that field for more information.
+    pub fn set_replication_conflict_policy(&mut self, value: ReplicationConflictPolicy) {
+        self.config.domain_config.replication_conflict_policy = value;
+    }
+
+    /// Sets the value of [`Config::domain_config::record_provenance`]. See documentation of that
+    /// field for more information.
+    pub fn set_record_provenance(&mut self, value: bool) {
+        self.config.domain_config.record_provenance = value;
+    }
+
+    /// Sets the values of [`Config::domain_config::reader_key_ttl`] and
+    /// [`Config::domain_config::reader_key_ttl_jitter_percent`]. See documentation of those fields
+    /// for more information.
+    pub fn set_reader_key_ttl(&mut self, ttl: Option<Duration>, jitter_percent: u8) {
+        self.config.domain_config.reader_key_ttl = ttl;
+        self.config.domain_config.reader_key_ttl_jitter_percent = jitter_percent;
+    }
+
+    /// Sets the value of [`Config::domain_config::max_key_expirations_per_second`]. See
+    /// documentation of that field for more information.
+    pub fn set_max_key_expirations_per_second(&mut self, value: Option<usize>) {
+        self.config.domain_config.max_key_expirations_per_second = value;
+    }
+
+    /// Sets how often the leader compares checksums of the state of the replicas of each
+    /// replicated domain. `None` disables the checks.
+    pub fn set_state_checksum_interval(&mut self, value: Option<Duration>) {
+        self.config.state_checksum_interval = value;
+    }
+
+    /// Sets the maximum number of times the controller restarts each domain after it fails, within
+    /// any `window` of time. A `max_restarts` of 0 disables restarting failed domains.
+    pub fn set_domain_restart_budget(&mut self, max_restarts: usize, window: Duration) {
+        self.config.domain_restart_budget = max_restarts;
+        self.config.domain_restart_window = window;
+    }
+
+    /// Sets the maximum size in bytes of each worker's pool of interned text values. A size of 0
+    /// disables interning.
+    pub fn set_text_interning_pool_size(&mut self, value: usize) {
+        self.config.text_interning_pool_size = value;
+    }
+
+    /// Sets the CPUs that each worker's domain threads are pinned to, assigned round-robin. An
+    /// empty list disables pinning.
+    pub fn set_domain_cpus(&mut self, cpus: Vec<usize>) {
+        self.config.domain_cpus = cpus;
+    }
+
+    /// Sets the CPUs that the threads serving reads are pinned to, assigned round-robin. An empty
+    /// list disables pinning.
+    pub fn set_reader_cpus(&mut self, cpus: Vec<usize>) {
+        self.config.reader_cpus = cpus;
+    }
+
+    /// Sets the [`ChannelSecurity`] used to authenticate and encrypt connections to domains. This
+    /// must be the same for all servers and adapters in the deployment.
+    pub fn set_channel_security(&mut self, security: Option<ChannelSecurity>) {
+        self.config.channel_security = security;
+    }
+
+    /// Sets the quotas on the caches in each namespace enforced by the controller. Only the limits
+    /// on the number of caches and materialized bytes are enforced by the controller; read limits
+    /// are enforced by adapters.
+    pub fn set_namespace_quotas(&mut self, quotas: NamespaceQuotas) {
+        self.config.namespace_quotas = quotas;
+    }
+
     /// Assigns a telemetry reporter to this ReadySet server
     pub fn set_telemetry_sender(&mut self, value: TelemetrySender) {
         self.telemetry = value;
@@ -303,9 +510,8 @@ impl Builder {
         self,
         authority: Arc<Authority>,
     ) -> impl Future<Output = Result<Handle, anyhow::Error>> {
+        let listen = self.listen_config();
         let Builder {
-            listen_addr,
-            external_addr,
             ref config,
             memory_limit,
             memory_check_frequency,
@@ -313,14 +519,14 @@ impl Builder {
             leader_eligible,
             telemetry,
             wait_for_failpoint,
+            ..
         } = self;
 
         let config = config.clone();
 
         crate::startup::start_instance(
             authority,
-            listen_addr,
-            external_addr,
+            listen,
             config,
             memory_limit,
             memory_check_frequency,
@@ -340,9 +546,8 @@ impl Builder {
         valve: stream_cancel::Valve,
         trigger: stream_cancel::Trigger,
     ) -> impl Future<Output = Result<Handle, anyhow::Error>> {
+        let listen = self.listen_config();
         let Builder {
-            listen_addr,
-            external_addr,
             ref config,
             memory_limit,
             memory_check_frequency,
@@ -350,14 +555,14 @@ impl Builder {
             leader_eligible,
             telemetry,
             wait_for_failpoint,
+            ..
         } = self;
 
         let config = config.clone();
 
         crate::startup::start_instance_inner(
             authority,
-            listen_addr,
-            external_addr,
+            listen,
             config,
             memory_limit,
             memory_check_frequency,
@@ -395,3 +600,43 @@ impl Builder {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listen_config_defaults() {
+        let mut builder = Builder::default();
+        builder.set_listen_addrs(vec!["10.0.0.1".parse().unwrap(), "::1".parse().unwrap()]);
+        builder.set_external_addr("192.168.0.1:6033".parse().unwrap());
+        assert_eq!(
+            builder.listen_config(),
+            ListenConfig {
+                listen_addrs: vec!["10.0.0.1".parse().unwrap(), "::1".parse().unwrap()],
+                internal_listen_addr: "10.0.0.1".parse().unwrap(),
+                external_addr: "192.168.0.1:6033".parse().unwrap(),
+                internal_external_addr: "192.168.0.1".parse().unwrap(),
+            }
+        );
+    }
+
+    #[test]
+    fn listen_config_internal_addresses() {
+        let mut builder = Builder::default();
+        builder.set_listen_addrs(vec!["10.0.0.1".parse().unwrap(), "::1".parse().unwrap()]);
+        builder.set_internal_listen_addr("10.1.0.1".parse().unwrap());
+        builder.set_external_addr("192.168.0.1:6033".parse().unwrap());
+        builder.set_internal_external_addr("10.1.0.2".parse().unwrap());
+        let config = builder.listen_config();
+        assert_eq!(
+            config.internal_listen_addr,
+            "10.1.0.1".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(
+            config.internal_external_addr,
+            "10.1.0.2".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(config.listen_addrs.len(), 2);
+    }
+}
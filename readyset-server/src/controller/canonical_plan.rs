@@ -0,0 +1,223 @@
+//! A canonical, textual serialization of the dataflow plan for a single cache.
+//!
+//! The indices of the nodes and domains which make up a cache depend on everything that was
+//! migrated before the cache was created, so the same `CREATE CACHE` statement can end up as
+//! differently-numbered graphs in two deployments. The plan produced by [`canonical_plan`] instead
+//! identifies nodes by their position in a traversal of the cache's ancestors which depends only
+//! on the structure of the graph, and describes each node by its operator, columns, sharding and
+//! materialization, so that plans can be checked in and diffed across deployments and versions.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use dataflow::prelude::*;
+use lazy_static::lazy_static;
+use readyset_client::internal::MaterializationStatus;
+use regex::{Captures, Regex};
+
+use crate::controller::migrate::materialization::Materializations;
+
+struct Planner<'a> {
+    graph: &'a Graph,
+    /// A hash of the subgraph rooted at each node which doesn't depend on node indices, used to
+    /// order the parents of nodes (such as unions) whose parents aren't ordered
+    signatures: HashMap<NodeIndex, u64>,
+    /// The canonical index of each node, in the order nodes were visited
+    ids: HashMap<NodeIndex, usize>,
+    order: Vec<NodeIndex>,
+}
+
+impl<'a> Planner<'a> {
+    /// Returns the parents of `ni` in canonical order: the order of the operator's ancestors if
+    /// the operator orders them (such as the left and right sides of a join), and by signature
+    /// otherwise
+    fn parents(&mut self, ni: NodeIndex) -> Vec<NodeIndex> {
+        #[allow(clippy::indexing_slicing)] // nodes come from the graph
+        let node = &self.graph[ni];
+        let ancestors = if node.is_union() {
+            vec![]
+        } else {
+            node.ancestors().unwrap_or_default()
+        };
+        #[allow(clippy::indexing_slicing)] // nodes come from the graph
+        let parents = self
+            .graph
+            .neighbors_directed(ni, petgraph::EdgeDirection::Incoming)
+            .filter(|&p| !self.graph[p].is_source())
+            .collect::<Vec<_>>();
+
+        let mut keyed = parents
+            .into_iter()
+            .map(|p| {
+                let position = ancestors.iter().position(|&a| a == p).unwrap_or(usize::MAX);
+                (position, self.signature(p), p)
+            })
+            .collect::<Vec<_>>();
+        keyed.sort_by(|(pos1, sig1, _), (pos2, sig2, _)| (pos1, sig1).cmp(&(pos2, sig2)));
+        keyed.into_iter().map(|(_, _, p)| p).collect()
+    }
+
+    /// Returns the signature of `ni`, which hashes the node along with the signatures of its
+    /// parents. Hashing the parents' signatures rather than including them keeps signatures a
+    /// fixed size no matter how many paths there are through the node's ancestors.
+    fn signature(&mut self, ni: NodeIndex) -> u64 {
+        if let Some(signature) = self.signatures.get(&ni) {
+            return *signature;
+        }
+        let parents = self
+            .parents(ni)
+            .into_iter()
+            .map(|p| self.signature(p))
+            .collect::<Vec<_>>();
+        #[allow(clippy::indexing_slicing)] // nodes come from the graph
+        let node = &self.graph[ni];
+        let mut hasher = DefaultHasher::new();
+        node.description(false).hash(&mut hasher);
+        if node.is_base() || node.is_reader() {
+            node.name().to_string().hash(&mut hasher);
+        }
+        column_list(node).hash(&mut hasher);
+        parents.hash(&mut hasher);
+        let signature = hasher.finish();
+        self.signatures.insert(ni, signature);
+        signature
+    }
+
+    /// Assign canonical indices to `ni` and all of its ancestors, in post-order
+    fn visit(&mut self, ni: NodeIndex) {
+        if self.ids.contains_key(&ni) {
+            return;
+        }
+        for parent in self.parents(ni) {
+            self.visit(parent);
+        }
+        self.ids.insert(ni, self.order.len());
+        self.order.push(ni);
+    }
+
+    fn id(&self, ni: NodeIndex) -> String {
+        self.ids
+            .get(&ni)
+            .map_or_else(|| "?".to_owned(), |id| format!("n{id}"))
+    }
+
+    /// Returns the detailed description of the operator of `node`, with any node indices replaced
+    /// by canonical indices
+    #[allow(clippy::unwrap_used)] // regex is hardcoded and valid
+    fn description(&self, node: &Node) -> String {
+        lazy_static! {
+            static ref NODE_INDEX_RE: Regex = Regex::new(r"\b(\d+):").unwrap();
+        }
+        if node.is_union() {
+            // Union descriptions list their parents by node index, so describe their inputs with
+            // the sources of each column instead
+            node.description(false)
+        } else if node.is_join().unwrap_or(false) {
+            NODE_INDEX_RE
+                .replace_all(&node.description(true), |caps: &Captures| {
+                    caps[1]
+                        .parse::<usize>()
+                        .map(|ni| format!("{}:", self.id(NodeIndex::new(ni))))
+                        .unwrap_or_else(|_| caps[0].to_owned())
+                })
+                .into_owned()
+        } else {
+            node.description(true)
+        }
+    }
+}
+
+fn column_list(node: &Node) -> String {
+    node.columns()
+        .iter()
+        .map(|col| format!("{} {}", col.name(), col.ty()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Build the canonical plan for the cache whose reader node is `reader`, covering the reader and
+/// all of its ancestors. See the [module documentation](self) for more information.
+pub(super) fn canonical_plan(
+    graph: &Graph,
+    materializations: &Materializations,
+    reader: NodeIndex,
+) -> String {
+    let mut planner = Planner {
+        graph,
+        signatures: Default::default(),
+        ids: Default::default(),
+        order: vec![],
+    };
+    planner.visit(reader);
+
+    let mut lines = vec![];
+    for ni in planner.order.clone() {
+        #[allow(clippy::indexing_slicing)] // nodes come from the graph
+        let node = &graph[ni];
+        lines.push(format!(
+            "{}: {} {}",
+            planner.id(ni),
+            node.node_type_string(),
+            planner.description(node)
+        ));
+        if node.is_base() || node.is_reader() {
+            lines.push(format!("    name: {}", node.name()));
+        }
+        let parents = planner.parents(ni);
+        if !parents.is_empty() {
+            lines.push(format!(
+                "    parents: {}",
+                parents
+                    .iter()
+                    .map(|&p| planner.id(p))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+        for (i, col) in node.columns().iter().enumerate() {
+            let mut line = format!("    column {i}: {} {}", col.name(), col.ty());
+            if node.is_internal() {
+                let mut sources = node
+                    .parent_columns(i)
+                    .into_iter()
+                    .map(|(p, col)| match col {
+                        Some(col) => format!("{}.{col}", planner.id(p)),
+                        None => planner.id(p),
+                    })
+                    .collect::<Vec<_>>();
+                sources.sort();
+                line.push_str(&format!(" <- {}", sources.join(" | ")));
+            }
+            lines.push(line);
+        }
+        lines.push(format!("    sharding: {:?}", node.sharded_by()));
+        let materialization = match materializations.get_status(ni, node) {
+            MaterializationStatus::Not => "none",
+            MaterializationStatus::Full => "full",
+            MaterializationStatus::Partial {
+                beyond_materialization_frontier: false,
+            } => "partial",
+            MaterializationStatus::Partial {
+                beyond_materialization_frontier: true,
+            } => "partial (beyond materialization frontier)",
+        };
+        lines.push(format!("    materialization: {materialization}"));
+        for index in materializations.indexes(ni) {
+            lines.push(format!(
+                "    index: {:?} {:?}",
+                index.index_type, index.columns
+            ));
+        }
+        if let Some(index) = node.as_reader().and_then(|r| r.index()) {
+            lines.push(format!(
+                "    reader index: {:?} {:?}",
+                index.index_type, index.columns
+            ));
+        }
+    }
+
+    let mut plan = lines.join("\n");
+    plan.push('\n');
+    plan
+}
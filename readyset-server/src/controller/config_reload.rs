@@ -0,0 +1,233 @@
+//! Validation of the configuration changes requested via the `/reload_config` RPC, which allows
+//! changing some settings of a running deployment without restarting it, and reporting of the
+//! current values of those settings via the `/config_settings` RPC.
+//!
+//! Settings are identified by the name of the command-line option used to set them at startup (see
+//! [`WorkerOptions`](crate::WorkerOptions)), and their values are parsed the same way as on the
+//! command line.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use clap::ArgEnum;
+use dataflow::EvictionKind;
+use readyset_client::config::ConfigReloadResult;
+use readyset_errors::{ReadySetError, ReadySetResult};
+
+/// Memory limit, in bytes, for partially materialized state (0 = unlimited)
+const MEMORY: &str = "memory";
+/// How often to check the size of partially materialized state against the memory limit, in
+/// seconds
+const MEMORY_CHECK_EVERY: &str = "memory-check-every";
+/// The strategy to use when evicting from reader nodes. Only applies to caches created after the
+/// setting is changed.
+const EVICTION_POLICY: &str = "eviction-policy";
+
+/// Settings which are only read at startup, and so can't be changed without a restart
+const RESTART_REQUIRED: &[&str] = &[
+    "durability",
+    "persistence-threads",
+    "text-interning-pool-size",
+    "replay-spill-threshold",
+    "nopartial",
+    "forbid-full-materialization",
+    "enable-packet-filters",
+    "quorum",
+    "shards",
+    "volume-id",
+    "worker-zone",
+    "worker-label",
+    "reader-placement-constraint",
+    "base-table-placement-constraint",
+    "db-dir",
+    "upstream-db-url",
+    "disable-upstream-ssl-verification",
+    "ssl-root-cert",
+    "ssl-client-identity",
+    "ssl-client-identity-password",
+    "ssl-verify-mode",
+    "disable-setup-ddl-replication",
+    "replication-server-id",
+    "replicator-restart-timeout",
+    "replication-tables",
+    "replication-tables-ignore",
+    "snapshot-report-interval-secs",
+    "replication-pool-size",
+    "replication-heartbeat-period-secs",
+    "max-row-size",
+    "oversized-row-policy",
+    "state-checksum-interval-seconds",
+    "replication-conflict-policy",
+    "reader-key-ttl-seconds",
+    "reader-key-ttl-jitter-percent",
+    "max-key-expirations-per-second",
+];
+
+/// A validated set of changes to the configuration of a running deployment
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(super) struct ConfigChanges {
+    /// The new memory limit for each worker, if it's being changed. `Some(None)` removes the
+    /// limit.
+    pub(super) memory_limit: Option<Option<usize>>,
+    /// The new frequency with which workers check their memory usage, if it's being changed
+    pub(super) memory_check_frequency: Option<Duration>,
+    /// The new eviction strategy for reader nodes, if it's being changed
+    pub(super) eviction_kind: Option<EvictionKind>,
+    /// Settings which were requested to be changed, but which require a restart
+    requires_restart: Vec<String>,
+}
+
+impl ConfigChanges {
+    /// Validate the given map from setting name to value, returning an error if any of the settings
+    /// are unknown or have invalid values.
+    pub(super) fn parse(settings: BTreeMap<String, String>) -> ReadySetResult<Self> {
+        let invalid = |name: &str, value: &str, reason: &dyn std::fmt::Display| {
+            ReadySetError::BadRequest(format!(
+                "Invalid value {value:?} for setting {name}: {reason}"
+            ))
+        };
+
+        let mut changes = Self::default();
+        for (name, value) in settings {
+            match name.as_str() {
+                MEMORY => {
+                    let limit = value
+                        .parse::<usize>()
+                        .map_err(|e| invalid(&name, &value, &e))?;
+                    changes.memory_limit = Some(if limit == 0 { None } else { Some(limit) });
+                }
+                MEMORY_CHECK_EVERY => {
+                    let secs = value
+                        .parse::<u64>()
+                        .map_err(|e| invalid(&name, &value, &e))?;
+                    if secs == 0 {
+                        return Err(invalid(&name, &value, &"must be greater than 0"));
+                    }
+                    changes.memory_check_frequency = Some(Duration::from_secs(secs));
+                }
+                EVICTION_POLICY => {
+                    changes.eviction_kind = Some(
+                        EvictionKind::from_str(&value, true)
+                            .map_err(|e| invalid(&name, &value, &e))?,
+                    );
+                }
+                _ if RESTART_REQUIRED.contains(&name.as_str()) => {
+                    changes.requires_restart.push(name);
+                }
+                _ => {
+                    return Err(ReadySetError::BadRequest(format!("Unknown setting {name}")));
+                }
+            }
+        }
+
+        Ok(changes)
+    }
+
+    /// Returns `true` if any of the memory limit settings of workers are being changed
+    pub(super) fn changes_memory_limit(&self) -> bool {
+        self.memory_limit.is_some() || self.memory_check_frequency.is_some()
+    }
+
+    /// Summarize which settings were applied, and which require a restart, for the client
+    pub(super) fn into_result(self) -> ConfigReloadResult {
+        let applied = [
+            (MEMORY, self.memory_limit.is_some()),
+            (MEMORY_CHECK_EVERY, self.memory_check_frequency.is_some()),
+            (EVICTION_POLICY, self.eviction_kind.is_some()),
+        ]
+        .into_iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(name, _)| name.to_owned())
+        .collect();
+
+        ConfigReloadResult {
+            applied,
+            requires_restart: self.requires_restart,
+        }
+    }
+}
+
+/// Describe the current values of the settings which can be changed without a restart, in the
+/// same format accepted by [`ConfigChanges::parse`].
+///
+/// `memory_limit` is the `(memory check frequency, memory limit)` of the workers in the
+/// deployment, or `None` if there are no workers. Since all workers are sent the same changes to
+/// these settings, it's enough to ask any one of them.
+pub(super) fn current_settings(
+    memory_limit: Option<(Option<Duration>, Option<usize>)>,
+    eviction_kind: EvictionKind,
+) -> BTreeMap<String, String> {
+    let mut settings = BTreeMap::new();
+    if let Some((memory_check_frequency, memory_limit)) = memory_limit {
+        settings.insert(MEMORY.to_owned(), memory_limit.unwrap_or(0).to_string());
+        if let Some(frequency) = memory_check_frequency {
+            settings.insert(
+                MEMORY_CHECK_EVERY.to_owned(),
+                frequency.as_secs().to_string(),
+            );
+        }
+    }
+    if let Some(value) = eviction_kind.to_possible_value() {
+        settings.insert(EVICTION_POLICY.to_owned(), value.get_name().to_owned());
+    }
+    settings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(settings: &[(&str, &str)]) -> BTreeMap<String, String> {
+        settings
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn parse_live_and_restart_settings() {
+        let changes = ConfigChanges::parse(settings(&[
+            ("memory", "0"),
+            ("eviction-policy", "lru"),
+            ("upstream-db-url", "mysql://root@localhost/db"),
+        ]))
+        .unwrap();
+        assert_eq!(changes.memory_limit, Some(None));
+        assert_eq!(changes.memory_check_frequency, None);
+        assert_eq!(changes.eviction_kind, Some(EvictionKind::LRU));
+        assert!(changes.changes_memory_limit());
+
+        let result = changes.into_result();
+        assert_eq!(result.applied, vec!["memory", "eviction-policy"]);
+        assert_eq!(result.requires_restart, vec!["upstream-db-url"]);
+    }
+
+    #[test]
+    fn invalid_settings_are_rejected() {
+        ConfigChanges::parse(settings(&[("memory", "lots")])).unwrap_err();
+        ConfigChanges::parse(settings(&[("memory-check-every", "0")])).unwrap_err();
+        ConfigChanges::parse(settings(&[("eviction-policy", "fifo")])).unwrap_err();
+        ConfigChanges::parse(settings(&[("memory", "1"), ("not-a-setting", "1")])).unwrap_err();
+    }
+
+    #[test]
+    fn current_settings_round_trip() {
+        let current = current_settings(
+            Some((Some(Duration::from_secs(5)), None)),
+            EvictionKind::Generational,
+        );
+        assert_eq!(
+            current,
+            settings(&[
+                ("eviction-policy", "generational"),
+                ("memory", "0"),
+                ("memory-check-every", "5"),
+            ])
+        );
+
+        let changes = ConfigChanges::parse(current).unwrap();
+        assert_eq!(changes.memory_limit, Some(None));
+        assert_eq!(changes.memory_check_frequency, Some(Duration::from_secs(5)));
+        assert_eq!(changes.eviction_kind, Some(EvictionKind::Generational));
+    }
+}
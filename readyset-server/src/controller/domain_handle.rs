@@ -60,6 +60,26 @@ impl DomainHandle {
             })
     }
 
+    /// Returns the address of each replica of each shard of this domain, along with the worker it's
+    /// assigned to
+    pub(super) fn replicas(&self) -> impl Iterator<Item = (ReplicaAddress, &WorkerIdentifier)> {
+        let domain_index = self.idx;
+        self.shards()
+            .enumerate()
+            .flat_map(move |(shard, replicas)| {
+                replicas.iter().enumerate().map(move |(replica, worker)| {
+                    (
+                        ReplicaAddress {
+                            domain_index,
+                            shard,
+                            replica,
+                        },
+                        worker,
+                    )
+                })
+            })
+    }
+
     pub(super) fn is_assigned_to_worker(&self, worker: &WorkerIdentifier) -> bool {
         self.shards.cells().iter().any(|s| s == worker)
     }
@@ -0,0 +1,108 @@
+//! Automatic restarts of domains which fail.
+//!
+//! When a domain replica fails - by returning an error, or by panicking in one of its operators -
+//! the worker running it stops the replica and reports the failure to the controller with a
+//! [`DomainFailure`](crate::worker::DomainFailure). The controller then restarts the whole domain,
+//! rebuilding its state by replaying from its ancestors in the same way as when a worker fails.
+//!
+//! Since a failure caused by (say) a bug in an operator is likely to recur as soon as the domain
+//! processes the same data again, the number of restarts of each domain is bounded by a
+//! [`RestartBudget`]. Once a domain exhausts its budget it's left stopped, and reads and writes
+//! which go through it return errors until the controller is restarted.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use readyset_client::internal::DomainIndex;
+
+/// Tracks the restarts of each domain, to limit each domain to at most a fixed number of restarts
+/// in any window of time.
+pub(super) struct RestartBudget {
+    /// The maximum number of times each domain may be restarted within `window`
+    max_restarts: usize,
+    window: Duration,
+    /// The times at which each domain was restarted within the last `window`, oldest first
+    restarts: Mutex<HashMap<DomainIndex, VecDeque<Instant>>>,
+}
+
+impl RestartBudget {
+    /// Create a new [`RestartBudget`] allowing each domain to be restarted at most `max_restarts`
+    /// times within any `window`. A `max_restarts` of 0 disables restarts entirely.
+    pub(super) fn new(max_restarts: usize, window: Duration) -> Self {
+        Self {
+            max_restarts,
+            window,
+            restarts: Default::default(),
+        }
+    }
+
+    /// If `domain` has any restarts left in its budget as of `now`, record a restart of it and
+    /// return `true`. Otherwise, return `false`.
+    pub(super) fn try_restart(&self, domain: DomainIndex, now: Instant) -> bool {
+        #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+        let mut restarts = self.restarts.lock().unwrap();
+        let domain_restarts = restarts.entry(domain).or_default();
+        while domain_restarts
+            .front()
+            .map_or(false, |restart| now.duration_since(*restart) >= self.window)
+        {
+            domain_restarts.pop_front();
+        }
+
+        if domain_restarts.len() >= self.max_restarts {
+            return false;
+        }
+        domain_restarts.push_back(now);
+        true
+    }
+
+    /// Forget the most recent restart of `domain` recorded by [`Self::try_restart`], so that it
+    /// doesn't count towards the budget, if that restart failed.
+    pub(super) fn cancel_restart(&self, domain: DomainIndex) {
+        #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+        if let Some(domain_restarts) = self.restarts.lock().unwrap().get_mut(&domain) {
+            domain_restarts.pop_back();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_restarts_within_window() {
+        let budget = RestartBudget::new(2, Duration::from_secs(60));
+        let start = Instant::now();
+        let domain = DomainIndex::from(0);
+
+        assert!(budget.try_restart(domain, start));
+        assert!(budget.try_restart(domain, start + Duration::from_secs(10)));
+        assert!(!budget.try_restart(domain, start + Duration::from_secs(20)));
+        // Other domains have their own budget
+        assert!(budget.try_restart(DomainIndex::from(1), start + Duration::from_secs(20)));
+
+        // The first restart has fallen out of the window
+        assert!(budget.try_restart(domain, start + Duration::from_secs(61)));
+        assert!(!budget.try_restart(domain, start + Duration::from_secs(62)));
+    }
+
+    #[test]
+    fn cancelled_restarts_dont_count() {
+        let budget = RestartBudget::new(1, Duration::from_secs(60));
+        let start = Instant::now();
+        let domain = DomainIndex::from(0);
+
+        assert!(budget.try_restart(domain, start));
+        budget.cancel_restart(domain);
+        assert!(budget.try_restart(domain, start + Duration::from_secs(1)));
+        assert!(!budget.try_restart(domain, start + Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn zero_budget_disables_restarts() {
+        let budget = RestartBudget::new(0, Duration::from_secs(60));
+        assert!(!budget.try_restart(DomainIndex::from(0), Instant::now()));
+    }
+}
@@ -7,32 +7,57 @@
     clippy::unreachable
 )]
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use database_utils::UpstreamConfig;
 use failpoint_macros::failpoint;
 use hyper::Method;
-use readyset_client::consensus::Authority;
+use nom_sql::Relation;
+use readyset_client::channel::ChannelSecurity;
+use readyset_client::consensus::{Authority, AuthorityControl};
+use readyset_client::consistency::Timestamp;
 use readyset_client::internal::ReplicaAddress;
+use readyset_client::metrics::recorded;
+use readyset_client::query::QueryId;
 use readyset_client::recipe::ExtendRecipeSpec;
 use readyset_client::replication::ReplicationOffset;
 use readyset_client::status::{ReadySetStatus, SnapshotStatus};
-use readyset_client::WorkerDescriptor;
-use readyset_errors::{ReadySetError, ReadySetResult};
+use readyset_client::{ViewCreateRequest, WorkerDescriptor};
+use readyset_data::DfValue;
+use readyset_errors::{internal_err, ReadySetError, ReadySetResult, UnsupportedFeature};
 use readyset_telemetry_reporter::TelemetrySender;
 use readyset_tracing::{error, info, warn};
 use readyset_util::futures::abort_on_panic;
 use readyset_version::RELEASE_VERSION;
+use replicators::{ReplicationControl, ReplicationTransformHook};
 use reqwest::Url;
 use tokio::sync::mpsc::UnboundedSender;
 use tokio::sync::Notify;
 
+use crate::controller::config_reload::{self, ConfigChanges};
+use crate::controller::domain_restarts::RestartBudget;
 use crate::controller::state::{DfState, DfStateHandle};
-use crate::controller::{ControllerRequest, ControllerState, Worker, WorkerIdentifier};
+use crate::controller::{
+    query_ids, state_checksums, ControllerRequest, ControllerState, Worker, WorkerIdentifier,
+};
 use crate::coordination::DomainDescriptor;
-use crate::worker::WorkerRequestKind;
+use crate::worker::{DomainFailure, WorkerRequestKind};
+
+/// How long to wait for a barrier to reach every reader when quiescing the graph
+const QUIESCE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Returns the path in the authority that the replication timestamp is persisted at
+fn replication_timestamp_path(authority: &Authority) -> &'static str {
+    match authority {
+        // Consul paths are relative to the deployment
+        Authority::ConsulAuthority(_) => "replication_timestamp",
+        _ => "/replication_timestamp",
+    }
+}
 
 /// The ReadySet leader, responsible for making control-plane decisions for the whole of a ReadySet
 /// cluster.
@@ -44,7 +69,9 @@ use crate::worker::WorkerRequestKind;
 /// `Migration`, which can be performed using `Leader::migrate`. Only one `Migration` can
 /// occur at any given point in time.
 pub struct Leader {
-    pub(super) dataflow_state_handle: DfStateHandle,
+    pub(super) dataflow_state_handle: Arc<DfStateHandle>,
+    /// The queries adapters have assigned ids to
+    query_ids: query_ids::QueryIdsHandle,
 
     pending_recovery: bool,
 
@@ -55,10 +82,34 @@ pub struct Leader {
     worker_request_timeout: Duration,
     /// Configuration for the replicator
     pub(super) replicator_config: UpstreamConfig,
+    /// Transforms rows replicated by the replicator
+    replication_transform: ReplicationTransformHook,
+    /// Used to pause and resume the replicator
+    replication_control: ReplicationControl,
+    /// The latest replication timestamp persisted in the authority. Held while the timestamp is
+    /// being persisted, so that it's written in order.
+    persisted_replication_timestamp: tokio::sync::Mutex<Timestamp>,
+    /// Used by the replicator to secure its connections to base table domains, and to sign
+    /// requests to workers
+    channel_security: Option<ChannelSecurity>,
     /// A handle to the replicator task
     pub(super) replicator_task: Option<tokio::task::JoinHandle<()>>,
+    /// How often to compare the state of the replicas of each replicated domain, if at all
+    state_checksum_interval: Option<Duration>,
+    /// A handle to the task comparing the state of domain replicas
+    state_checksum_task: Option<tokio::task::JoinHandle<()>>,
     /// A client to the current authority.
     pub(super) authority: Arc<Authority>,
+    /// Limits how often each domain is restarted after it fails
+    domain_restarts: RestartBudget,
+    /// The last barrier injected into the graph to quiesce it
+    last_barrier: AtomicU64,
+    /// Whether a request to quiesce the graph is currently waiting for its barrier. Each one
+    /// blocks a request thread for up to [`QUIESCE_TIMEOUT`], so only one is allowed at a time.
+    quiescing: AtomicBool,
+    /// Held while a configuration reload is being applied, so that reloads are applied one at a
+    /// time
+    reloading_config: tokio::sync::Mutex<()>,
 }
 
 impl Leader {
@@ -71,14 +122,111 @@ impl Leader {
         replication_error: UnboundedSender<ReadySetError>,
         telemetry_sender: TelemetrySender,
     ) {
+        // Replication stays paused if it was paused under a previous leader
+        if self.dataflow_state_handle.read().await.replication_paused {
+            self.replication_control.pause();
+        }
+
+        if let Err(error) = self.query_ids.load(&self.authority).await {
+            warn!(%error, "Failed to load query ids");
+        }
+
+        // Snapshots handed out by a previous leader must not be newer than ours
+        match self
+            .authority
+            .try_read::<Timestamp>(replication_timestamp_path(&self.authority))
+            .await
+        {
+            Ok(Some(timestamp)) => {
+                self.replication_control.advance_timestamp(&timestamp);
+                *self.persisted_replication_timestamp.get_mut() = timestamp;
+            }
+            Ok(None) => {}
+            Err(error) => warn!(%error, "Failed to load replication timestamp"),
+        }
+
         // When the controller becomes the leader, we need to read updates
         // from the binlog.
         self.start_replication_task(ready_notification, replication_error, telemetry_sender)
             .await;
+
+        if let Some(interval) = self.state_checksum_interval {
+            self.state_checksum_task =
+                Some(tokio::spawn(state_checksums::check_state_periodically(
+                    self.dataflow_state_handle.clone(),
+                    interval,
+                )));
+        }
     }
 
     pub(super) async fn stop(&mut self) {
         self.stop_replication_task().await;
+        if let Some(handle) = self.state_checksum_task.take() {
+            handle.abort();
+            let _ = handle.await;
+        }
+    }
+
+    /// Returns a new barrier to inject into the graph to quiesce it: the current time in
+    /// microseconds since the UNIX epoch, or one more than the last barrier if that's later, since
+    /// barriers must increase
+    fn next_barrier(&self) -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since_epoch| since_epoch.as_micros() as u64);
+        let last = self.last_barrier.fetch_max(now, Ordering::SeqCst);
+        if last < now {
+            now
+        } else {
+            self.last_barrier.fetch_add(1, Ordering::SeqCst) + 1
+        }
+    }
+
+    /// Inject a new barrier into every base table, and wait for it to reach every reader derived
+    /// from a base table, at which point every write acknowledged before this was called is
+    /// visible in every cache. Returns the barrier.
+    ///
+    /// The dataflow state is only locked while talking to domains, not while waiting between
+    /// checks of whether the barrier has reached the readers, so that other requests (including
+    /// ones which change the graph) aren't held up for as long as it takes the barrier to arrive.
+    ///
+    /// Returns [`ReadySetError::QuiesceTimeout`] if the barrier hasn't reached every reader within
+    /// [`QUIESCE_TIMEOUT`], and [`ReadySetError::QuiesceInProgress`] if another call to this
+    /// method hasn't returned yet.
+    async fn quiesce(&self) -> ReadySetResult<u64> {
+        if self.quiescing.swap(true, Ordering::SeqCst) {
+            return Err(ReadySetError::QuiesceInProgress);
+        }
+        let res = self.quiesce_inner().await;
+        self.quiescing.store(false, Ordering::SeqCst);
+        res
+    }
+
+    async fn quiesce_inner(&self) -> ReadySetResult<u64> {
+        let barrier = self.next_barrier();
+        let readers = self
+            .dataflow_state_handle
+            .read()
+            .await
+            .inject_barrier(barrier)
+            .await?;
+
+        let deadline = Instant::now() + QUIESCE_TIMEOUT;
+        loop {
+            if self
+                .dataflow_state_handle
+                .read()
+                .await
+                .barrier_reached(barrier, &readers)
+                .await?
+            {
+                return Ok(barrier);
+            }
+            if Instant::now() >= deadline {
+                return Err(ReadySetError::QuiesceTimeout);
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
     }
 
     async fn stop_replication_task(&mut self) {
@@ -109,6 +257,9 @@ impl Leader {
         let authority = Arc::clone(&self.authority);
         let replicator_restart_timeout = self.replicator_config.replicator_restart_timeout;
         let config = self.replicator_config.clone();
+        let transform = self.replication_transform.clone();
+        let control = self.replication_control.clone();
+        let channel_security = self.channel_security.clone();
 
         // The replication task ideally won't panic, but if it does and we arent replicating, that
         // will mean the data we return, will be more and more stale, and the transaction logs on
@@ -116,14 +267,17 @@ impl Leader {
         // So, we abort on any panic of the replicator task.
         self.replicator_task = Some(tokio::spawn(abort_on_panic(async move {
             loop {
-                let noria: readyset_client::ReadySetHandle =
+                let mut noria: readyset_client::ReadySetHandle =
                     readyset_client::ReadySetHandle::new(Arc::clone(&authority)).await;
+                noria.set_channel_security(channel_security.clone());
 
                 match replicators::NoriaAdapter::start(
                     noria,
                     config.clone(),
                     Some(ready_notification.clone()),
                     telemetry_sender.clone(),
+                    transform.clone(),
+                    control.clone(),
                 )
                 .await
                 {
@@ -150,6 +304,93 @@ impl Leader {
         })));
     }
 
+    /// Register the query being migrated, so that its id can be resolved later
+    async fn register_query(&self, query: ViewCreateRequest, authority: &Authority) {
+        if let Err(error) = self
+            .query_ids
+            .update(authority, |ids| ids.register(query))
+            .await
+        {
+            warn!(%error, "Failed to register query");
+        }
+    }
+
+    /// If `error` was caused by `query` being unsupported, record that in the query id registry
+    /// so that we don't try to plan the query again until the planner changes
+    async fn mark_unsupported(
+        &self,
+        query: ViewCreateRequest,
+        error: &ReadySetError,
+        authority: &Arc<Authority>,
+    ) {
+        let reason = match error.unsupported_cause() {
+            Some(reason) => reason.to_owned(),
+            None => return,
+        };
+        let feature = error.unsupported_feature();
+        metrics::increment_counter!(
+            recorded::CONTROLLER_UNSUPPORTED_QUERIES,
+            "feature" => feature.map_or("unknown", UnsupportedFeature::name)
+        );
+        if let Err(error) = self
+            .query_ids
+            .update(authority, |ids| {
+                ids.mark_unsupported(query, reason, feature)
+            })
+            .await
+        {
+            warn!(%error, "Failed to record query as unsupported");
+        }
+    }
+
+    /// Return the latest timestamp propagated through the dataflow graph by replication, to be
+    /// used as the snapshot for snapshot reads. The timestamp is persisted in the authority before
+    /// it's returned, so that snapshots taken after a new leader is elected are never older than
+    /// the ones taken before.
+    async fn replication_timestamp(&self, authority: &Authority) -> ReadySetResult<Timestamp> {
+        let timestamp = self.replication_control.timestamp();
+        let mut persisted = self.persisted_replication_timestamp.lock().await;
+        if persisted.satisfies(&timestamp) {
+            return Ok(timestamp);
+        }
+
+        let written = authority
+            .read_modify_write(
+                replication_timestamp_path(authority),
+                |current: Option<Timestamp>| -> Result<_, Infallible> {
+                    Ok(Timestamp::join(&current.unwrap_or_default(), &timestamp))
+                },
+            )
+            .await
+            .map_err(|e| internal_err!("Unable to persist replication timestamp: {}", e))?;
+        *persisted = match written {
+            Ok(written) => written,
+            Err(never) => match never {},
+        };
+        Ok(timestamp)
+    }
+
+    /// Pause or resume replication, recording whether it's paused in the dataflow state so that
+    /// it stays that way if the controller restarts or a new leader is elected
+    async fn set_replication_paused(
+        &self,
+        paused: bool,
+        authority: &Arc<Authority>,
+    ) -> ReadySetResult<()> {
+        let mut writer = self.dataflow_state_handle.write().await;
+        writer.as_mut().replication_paused = paused;
+        self.dataflow_state_handle.commit(writer, authority).await?;
+
+        if paused {
+            if self.replication_control.pause() {
+                info!("Pausing replication");
+            }
+        } else if self.replication_control.resume() {
+            info!("Resuming replication");
+        }
+        Ok(())
+    }
+
     #[failpoint("controller-request")]
     #[allow(clippy::let_unit_value)]
     pub(super) fn external_request(
@@ -211,13 +452,92 @@ impl Leader {
                     })?;
                     return_serialized!(ds.graphviz(true, Some(node_sizes)));
                 }
+                (&Method::POST, "/canonical_plan") => {
+                    let name: Relation = bincode::deserialize(&body)?;
+                    let ds = futures::executor::block_on(self.dataflow_state_handle.read());
+                    return_serialized!(ds.canonical_plan(&name)?);
+                }
+                (&Method::POST, "/mir_plan") => {
+                    let name: Relation = bincode::deserialize(&body)?;
+                    let ds = futures::executor::block_on(self.dataflow_state_handle.read());
+                    return_serialized!(ds.mir_plan(&name)?);
+                }
+                (&Method::POST, "/row_provenance") => {
+                    let (name, key): (Relation, Vec<DfValue>) = bincode::deserialize(&body)?;
+                    let ret = futures::executor::block_on(async move {
+                        let ds = self.dataflow_state_handle.read().await;
+                        ds.row_provenance(&name, key).await
+                    })?;
+                    return_serialized!(ret);
+                }
+                (&Method::POST, "/auto_increments") => {
+                    let (table, column, values): (Relation, usize, Vec<Option<u64>>) =
+                        bincode::deserialize(&body)?;
+                    let ret = futures::executor::block_on(async move {
+                        let ds = self.dataflow_state_handle.read().await;
+                        ds.generate_auto_increments(&table, column, values).await
+                    })?;
+                    return_serialized!(ret);
+                }
+                (&Method::POST, "/base_rows") => {
+                    let (table, columns, key): (Relation, Vec<usize>, Vec<DfValue>) =
+                        bincode::deserialize(&body)?;
+                    let ret = futures::executor::block_on(async move {
+                        let ds = self.dataflow_state_handle.read().await;
+                        ds.base_rows(&table, columns, key).await
+                    })?;
+                    return_serialized!(ret);
+                }
+                (&Method::GET | &Method::POST, "/view_stats") => {
+                    let ret = futures::executor::block_on(async move {
+                        let request = self
+                            .dataflow_state_handle
+                            .read()
+                            .await
+                            .view_stats_request()?;
+                        request.send().await
+                    })?;
+                    return_serialized!(ret);
+                }
+                (&Method::POST, "/quiesce") => {
+                    let barrier = futures::executor::block_on(self.quiesce())?;
+                    return_serialized!(barrier);
+                }
                 (&Method::GET | &Method::POST, "/get_statistics") => {
                     let ret = futures::executor::block_on(async move {
                         let ds = self.dataflow_state_handle.read().await;
                         ds.get_statistics().await
-                    });
+                    })?;
+                    return_serialized!(ret);
+                }
+                (&Method::GET | &Method::POST, "/cache_read_history") => {
+                    let ret = futures::executor::block_on(async move {
+                        let ds = self.dataflow_state_handle.read().await;
+                        ds.cache_read_history().await
+                    })?;
                     return_serialized!(ret);
                 }
+                (&Method::GET | &Method::POST, "/reset_statistics") => {
+                    let ret = futures::executor::block_on(async move {
+                        let ds = self.dataflow_state_handle.read().await;
+                        ds.reset_statistics().await
+                    })?;
+                    return_serialized!(ret);
+                }
+                (&Method::POST, "/query_for_id") => {
+                    let id: QueryId = bincode::deserialize(&body)?;
+                    let ids = futures::executor::block_on(self.query_ids.read());
+                    return_serialized!(ids.get(id).map(|(id, request)| (id, request.clone())))
+                }
+                (&Method::GET | &Method::POST, "/unsupported_queries") => {
+                    let ids = futures::executor::block_on(self.query_ids.read());
+                    return_serialized!(ids
+                        .unsupported_queries()
+                        .map(|(id, request, reason, feature)| {
+                            (id, request.clone(), reason.to_owned(), feature)
+                        })
+                        .collect::<Vec<_>>())
+                }
                 (&Method::GET | &Method::POST, "/instances") => {
                     let ds = futures::executor::block_on(self.dataflow_state_handle.read());
                     return_serialized!(ds.get_instances());
@@ -259,6 +579,41 @@ impl Leader {
                     });
                     return_serialized!(res);
                 }
+                (&Method::POST, "/pause_replication") => {
+                    if self.replicator_config.upstream_db_url.is_none() {
+                        return Err(ReadySetError::BadRequest(
+                            "ReadySet is not replicating from an upstream database".to_owned(),
+                        ));
+                    }
+                    futures::executor::block_on(self.set_replication_paused(true, authority))?;
+                    return_serialized!(());
+                }
+                (&Method::POST, "/resume_replication") => {
+                    futures::executor::block_on(self.set_replication_paused(false, authority))?;
+                    return_serialized!(());
+                }
+                (&Method::GET | &Method::POST, "/replication_timestamp") => {
+                    let timestamp =
+                        futures::executor::block_on(self.replication_timestamp(authority))?;
+                    return_serialized!(timestamp);
+                }
+                (&Method::GET | &Method::POST, "/replication_lag") => {
+                    return_serialized!(self.replication_control.replication_lag());
+                }
+                (&Method::GET | &Method::POST, "/config_settings") => {
+                    let ret = futures::executor::block_on(async move {
+                        let ds = self.dataflow_state_handle.read().await;
+                        let memory_limit = match ds.workers.values().next() {
+                            Some(worker) => Some(worker.rpc(WorkerRequestKind::MemoryLimit).await?),
+                            None => None,
+                        };
+                        ReadySetResult::Ok(config_reload::current_settings(
+                            memory_limit,
+                            ds.domain_config.eviction_kind,
+                        ))
+                    })?;
+                    return_serialized!(ret);
+                }
                 (&Method::GET | &Method::POST, "/version") => {
                     return_serialized!(RELEASE_VERSION);
                 }
@@ -299,12 +654,29 @@ impl Leader {
                     check_quorum!(ds);
                     return_serialized!(ds.verbose_views())
                 }
+                (&Method::GET | &Method::POST, "/cache_definitions") => {
+                    let ds = futures::executor::block_on(self.dataflow_state_handle.read());
+                    check_quorum!(ds);
+                    return_serialized!(ds.cache_definitions())
+                }
+                (&Method::GET | &Method::POST, "/mir_plan_overrides") => {
+                    let ds = futures::executor::block_on(self.dataflow_state_handle.read());
+                    check_quorum!(ds);
+                    return_serialized!(ds.mir_plan_overrides()?)
+                }
                 (&Method::POST, "/view_statuses") => {
                     let (queries, dialect) = bincode::deserialize(&body)?;
                     let ds = futures::executor::block_on(self.dataflow_state_handle.read());
                     check_quorum!(ds);
                     return_serialized!(ds.view_statuses(queries, dialect))
                 }
+                (&Method::POST, "/query_matches_cache") => {
+                    let (name, query, dialect): (Relation, ViewCreateRequest, _) =
+                        bincode::deserialize(&body)?;
+                    let ds = futures::executor::block_on(self.dataflow_state_handle.read());
+                    check_quorum!(ds);
+                    return_serialized!(ds.query_matches_cache(&name, query, dialect)?)
+                }
                 (&Method::GET | &Method::POST, "/instances") => {
                     let ds = futures::executor::block_on(self.dataflow_state_handle.read());
                     check_quorum!(ds);
@@ -430,12 +802,17 @@ impl Leader {
                 (&Method::POST, "/status") => {
                     let status = ReadySetStatus {
                         // Use whether the leader is ready or not as a proxy for if we have
-                        // completed snapshotting.
-                        snapshot_status: if leader_ready {
+                        // completed snapshotting, along with whether any tables are still waiting
+                        // to be snapshotted again (such as after a worker was drained).
+                        snapshot_status: if leader_ready
+                            && !self.replication_control.resnapshot_pending()
+                        {
                             SnapshotStatus::Completed
                         } else {
                             SnapshotStatus::InProgress
                         },
+                        replication_paused: self.replication_control.is_paused(),
+                        replication_reconnects: self.replication_control.reconnects(),
                     };
                     return_serialized!(status);
                 }
@@ -444,16 +821,48 @@ impl Leader {
                     if body.require_leader_ready {
                         require_leader_ready()?;
                     }
+                    let query = query_ids::cached_query(&body.changes);
                     let ret = futures::executor::block_on(async move {
+                        if let Some(error) = match &query {
+                            Some(q) => self.query_ids.read().await.unsupported_error(q),
+                            None => None,
+                        } {
+                            return Err(error);
+                        }
                         let mut state_copy: DfState = {
                             let reader = self.dataflow_state_handle.read().await;
                             check_quorum!(reader);
                             reader.clone()
                         };
-                        state_copy.extend_recipe(body, true).await
+                        if let Some(check) = state_copy.namespace_size_check(&body.changes) {
+                            check.run().await?;
+                        }
+                        if let Some(request) =
+                            state_copy.materialization_sizes_request(&body.changes)
+                        {
+                            let node_sizes = request.send().await?;
+                            state_copy.materializations.set_node_sizes(node_sizes);
+                        }
+                        let res = state_copy
+                            .extend_recipe(body, true, false)
+                            .await
+                            .map(|_| ());
+                        if let Some(query) = query {
+                            match &res {
+                                Ok(_) => self.register_query(query, authority).await,
+                                Err(error) => self.mark_unsupported(query, error, authority).await,
+                            }
+                        }
+                        res
                     })?;
                     return_serialized!(ret);
                 }
+                (&Method::POST, "/backfill_done") => {
+                    let id: u64 = bincode::deserialize(&body)?;
+                    let ds = futures::executor::block_on(self.dataflow_state_handle.read());
+                    let done = ds.backfills.take_result(id)?;
+                    return_serialized!(done);
+                }
                 (&Method::GET | &Method::POST, "/supports_pagination") => {
                     let ds = futures::executor::block_on(self.dataflow_state_handle.read());
                     let supports =
@@ -477,19 +886,91 @@ impl Leader {
                 })?;
                 return_serialized!(ret);
             }
-            (Method::POST, "/extend_recipe") => {
+            (Method::POST, path @ ("/extend_recipe" | "/extend_recipe_concurrently")) => {
+                let concurrently = path == "/extend_recipe_concurrently";
                 let body: ExtendRecipeSpec = bincode::deserialize(&body)?;
                 if body.require_leader_ready {
                     require_leader_ready()?;
                 }
-                let ret = futures::executor::block_on(async move {
+                let query = query_ids::cached_query(&body.changes);
+                let changes_schema = query_ids::changes_schema(&body.changes);
+                let backfill = futures::executor::block_on(async move {
+                    if let Some(error) = match &query {
+                        Some(q) => self.query_ids.read().await.unsupported_error(q),
+                        None => None,
+                    } {
+                        return Err(error);
+                    }
+                    // Asking the domains for the size of their nodes can take a while, so do it
+                    // before taking the write lock rather than block reads of the dataflow state
+                    let (size_check, sizes_request) = {
+                        let reader = self.dataflow_state_handle.read().await;
+                        (
+                            reader.namespace_size_check(&body.changes),
+                            reader.materialization_sizes_request(&body.changes),
+                        )
+                    };
+                    if let Some(check) = size_check {
+                        check.run().await?;
+                    }
+                    let node_sizes = match sizes_request {
+                        Some(request) => Some(request.send().await?),
+                        None => None,
+                    };
                     let mut writer = self.dataflow_state_handle.write().await;
                     check_quorum!(writer.as_ref());
-                    let r = writer.as_mut().extend_recipe(body, false).await?;
+                    if let Some(node_sizes) = node_sizes {
+                        writer.as_mut().materializations.set_node_sizes(node_sizes);
+                    }
+                    let r = match writer
+                        .as_mut()
+                        .extend_recipe(body, false, concurrently)
+                        .await
+                    {
+                        Ok(r) => r,
+                        Err(error) => {
+                            // Discard whatever changes the failed migration made
+                            drop(writer);
+                            if let Some(query) = query {
+                                self.mark_unsupported(query, &error, authority).await;
+                            }
+                            return Err(error);
+                        }
+                    };
                     self.dataflow_state_handle.commit(writer, authority).await?;
+                    if changes_schema {
+                        // Queries found to be unsupported against the previous schema may be
+                        // supported against the new one
+                        if let Err(error) = self
+                            .query_ids
+                            .update(authority, query_ids::QueryIds::clear_unsupported)
+                            .await
+                        {
+                            warn!(%error, "Failed to clear unsupported queries");
+                        }
+                    }
+                    // Register the query here as well as in `/register_queries`, since adapters
+                    // only register queries themselves when migrating them asynchronously
+                    if let Some(query) = query {
+                        self.register_query(query, authority).await;
+                    }
                     Ok(r)
                 })?;
-                return_serialized!(ret);
+
+                if !concurrently {
+                    return_serialized!(());
+                }
+                // The backfill is run once the change has been committed, outside of the write
+                // path, so that other migrations don't have to wait for it
+                let id = backfill.map(|backfill| {
+                    let id = backfill.id();
+                    let ds = futures::executor::block_on(self.dataflow_state_handle.read());
+                    let (domains, workers) = (ds.domains.clone(), ds.workers.clone());
+                    drop(ds);
+                    tokio::spawn(backfill.run(domains, workers));
+                    id
+                });
+                return_serialized!(id);
             }
             (Method::POST, "/remove_query") => {
                 require_leader_ready()?;
@@ -503,6 +984,30 @@ impl Leader {
                 })?;
                 return_serialized!(ret);
             }
+            (Method::POST, "/set_mir_plan") => {
+                require_leader_ready()?;
+                let (name, plan, dialect): (Relation, String, _) = bincode::deserialize(&body)?;
+                let ret = futures::executor::block_on(async move {
+                    let mut writer = self.dataflow_state_handle.write().await;
+                    check_quorum!(writer.as_ref());
+                    let r = writer.as_mut().set_mir_plan(&name, &plan, dialect).await?;
+                    self.dataflow_state_handle.commit(writer, authority).await?;
+                    Ok(r)
+                })?;
+                return_serialized!(ret);
+            }
+            (Method::POST, "/add_index") => {
+                require_leader_ready()?;
+                let (view, columns): (Relation, Vec<usize>) = bincode::deserialize(&body)?;
+                let ret = futures::executor::block_on(async move {
+                    let mut writer = self.dataflow_state_handle.write().await;
+                    check_quorum!(writer.as_ref());
+                    let r = writer.as_mut().add_index(&view, columns).await?;
+                    self.dataflow_state_handle.commit(writer, authority).await?;
+                    Ok(r)
+                })?;
+                return_serialized!(ret);
+            }
             (Method::POST, "/remove_all_queries") => {
                 require_leader_ready()?;
                 let ret = futures::executor::block_on(async move {
@@ -514,6 +1019,27 @@ impl Leader {
                 })?;
                 return_serialized!(ret);
             }
+            (Method::POST, "/register_queries") => {
+                let queries: Vec<ViewCreateRequest> = bincode::deserialize(&body)?;
+                let ret = futures::executor::block_on(async move {
+                    check_quorum!(self.dataflow_state_handle.read().await);
+                    // Avoid taking the write lock if we already know about all the queries
+                    {
+                        let ids = self.query_ids.read().await;
+                        if queries.iter().all(|q| ids.contains(q)) {
+                            return Ok(());
+                        }
+                    }
+                    self.query_ids
+                        .update(authority, |ids| {
+                            for query in queries {
+                                ids.register(query);
+                            }
+                        })
+                        .await
+                })?;
+                return_serialized!(ret);
+            }
             (Method::POST, "/set_schema_replication_offset") => {
                 let body: Option<ReplicationOffset> = bincode::deserialize(&body)?;
                 let ret = futures::executor::block_on(async move {
@@ -524,6 +1050,66 @@ impl Leader {
                 })?;
                 return_serialized!(ret);
             }
+            (Method::POST, "/reload_config") => {
+                require_leader_ready()?;
+                let settings: BTreeMap<String, String> = bincode::deserialize(&body)?;
+                let changes = ConfigChanges::parse(settings)?;
+                let ret = futures::executor::block_on(async move {
+                    // The changes are applied all-or-nothing: only apply one reload at a time, so
+                    // that nothing else changes the settings while we do, and undo any changes
+                    // already made to workers if a later change fails. The dataflow state isn't
+                    // locked while we make requests to the workers, so that other requests aren't
+                    // blocked on them.
+                    let _reloading = self.reloading_config.lock().await;
+                    let workers = {
+                        let reader = self.dataflow_state_handle.read().await;
+                        check_quorum!(reader);
+                        reader.workers.values().cloned().collect::<Vec<_>>()
+                    };
+
+                    let mut updated = vec![];
+                    if changes.changes_memory_limit() {
+                        let mut previous = Vec::with_capacity(workers.len());
+                        for worker in workers {
+                            let settings = worker.rpc(WorkerRequestKind::MemoryLimit).await?;
+                            previous.push((worker, settings));
+                        }
+                        for (worker, settings) in previous {
+                            let res = worker
+                                .rpc::<()>(WorkerRequestKind::UpdateMemoryLimit {
+                                    period: changes.memory_check_frequency,
+                                    limit: changes.memory_limit,
+                                })
+                                .await;
+                            // Undo the change even if the request failed, in case it failed after
+                            // the worker applied it
+                            updated.push((worker, settings));
+                            if let Err(error) = res {
+                                restore_memory_limits(&updated).await;
+                                return Err(error);
+                            }
+                        }
+                    }
+                    if let Some(eviction_kind) = changes.eviction_kind {
+                        let mut writer = self.dataflow_state_handle.write().await;
+                        writer.as_mut().domain_config.eviction_kind = eviction_kind;
+                        if let Err(error) =
+                            self.dataflow_state_handle.commit(writer, authority).await
+                        {
+                            restore_memory_limits(&updated).await;
+                            return Err(error);
+                        }
+                    }
+                    let result = changes.into_result();
+                    info!(
+                        applied = ?result.applied,
+                        requires_restart = ?result.requires_restart,
+                        "Reloaded configuration"
+                    );
+                    Ok(result)
+                })?;
+                return_serialized!(ret);
+            }
             (Method::POST, "/remove_node") => {
                 require_leader_ready()?;
                 let body = bincode::deserialize(&body)?;
@@ -536,6 +1122,83 @@ impl Leader {
                 })?;
                 return_serialized!(ret);
             }
+            (Method::POST, "/domain_failed") => {
+                let failure: DomainFailure = bincode::deserialize(&body)?;
+                let ret =
+                    futures::executor::block_on(self.handle_failed_domain(failure, authority))?;
+                return_serialized!(ret);
+            }
+            (Method::POST, "/drain_worker") => {
+                require_leader_ready()?;
+                let worker_uri: WorkerIdentifier = bincode::deserialize(&body)?;
+                let ret = futures::executor::block_on(async move {
+                    let mut writer = self.dataflow_state_handle.write().await;
+                    check_quorum!(writer.as_ref());
+                    let worker = match writer.as_ref().workers.get(&worker_uri) {
+                        Some(worker) => worker.clone(),
+                        None => {
+                            return Err(ReadySetError::UnknownWorker {
+                                unknown_uri: worker_uri,
+                            })
+                        }
+                    };
+                    if writer.as_ref().workers.len() <= 1 {
+                        return Err(ReadySetError::BadRequest(format!(
+                            "Cannot drain {worker_uri}, as it is the only worker"
+                        )));
+                    }
+
+                    let has_base_tables = writer
+                        .as_ref()
+                        .nodes_on_worker(Some(&worker_uri))
+                        .values()
+                        .flatten()
+                        .any(|ni| writer.as_ref().ingredients[*ni].is_base());
+                    if has_base_tables && self.replicator_config.upstream_db_url.is_none() {
+                        // Without an upstream database to snapshot them from, the contents of the
+                        // base tables would be lost
+                        return Err(ReadySetError::BadRequest(format!(
+                            "Cannot drain {worker_uri}, as it runs base tables and there is no \
+                             upstream database to snapshot them from"
+                        )));
+                    }
+
+                    info!(%worker_uri, "Draining worker");
+                    let emptied_tables = writer.as_mut().drain_worker(&worker_uri).await?;
+                    self.dataflow_state_handle.commit(writer, authority).await?;
+
+                    // The base tables which were running on the worker have been recreated empty,
+                    // without a replication offset, so have the replicator snapshot them again.
+                    // The old copies of the domains are only shut down once that's finished, in
+                    // the background so that the request (and the leader) isn't blocked for the
+                    // duration of the snapshot.
+                    let resnapshot = if emptied_tables.is_empty() {
+                        None
+                    } else {
+                        info!(
+                            %worker_uri,
+                            tables = ?emptied_tables,
+                            "Resnapshotting base tables moved off of drained worker"
+                        );
+                        Some(self.replication_control.request_resnapshot())
+                    };
+                    let control = self.replication_control.clone();
+                    tokio::spawn(async move {
+                        if let Some(request) = resnapshot {
+                            control.wait_for_resnapshot(request).await;
+                        }
+                        // The domains that were running on the worker have all been recreated
+                        // elsewhere, so the old copies can be shut down
+                        if let Err(error) = worker.rpc::<()>(WorkerRequestKind::ClearDomains).await
+                        {
+                            warn!(%worker_uri, %error, "Failed to clear domains on drained worker");
+                        }
+                        info!(%worker_uri, "Finished draining worker");
+                    });
+                    Ok(())
+                })?;
+                return_serialized!(ret);
+            }
             _ => Err(ReadySetError::UnknownEndpoint),
         }
     }
@@ -555,12 +1218,18 @@ impl Leader {
                 ..
             } = desc;
 
+            if ds.drained_workers.contains(&worker_uri) {
+                info!(%worker_uri, "ignoring registration from drained worker");
+                continue;
+            }
+
             info!(%worker_uri, %reader_addr, "received registration payload from worker");
 
             let ws = Worker::new(
                 worker_uri.clone(),
                 domain_scheduling_config,
                 self.worker_request_timeout,
+                self.channel_security.clone(),
             );
 
             let mut domain_addresses = Vec::new();
@@ -640,36 +1309,73 @@ impl Leader {
     ) -> ReadySetResult<()> {
         let mut writer = self.dataflow_state_handle.write().await;
         let ds = writer.as_mut();
-
-        // first, translate from the affected workers to affected data-flow nodes
-        let mut affected_nodes = HashMap::new();
-        for wi in failed {
-            warn!(worker = %wi, "handling failure of worker");
-            let mut domain_nodes_on_worker = ds.nodes_on_worker(Some(&wi));
-            for (domain_index, node_indices) in domain_nodes_on_worker.drain() {
-                ds.domains.remove(&domain_index);
-                ds.materializations.remove_nodes(&node_indices);
-                affected_nodes
-                    .entry(domain_index)
-                    .or_insert_with(|| HashSet::new())
-                    .extend(node_indices);
+        for wi in &failed {
+            if ds.drained_workers.remove(wi) {
+                info!(worker = %wi, "drained worker has shut down");
+            } else {
+                warn!(worker = %wi, "handling failure of worker");
             }
-            ds.workers.remove(&wi);
         }
-
-        ds.recover(&affected_nodes).await?;
+        ds.remove_workers(&failed).await?;
 
         self.dataflow_state_handle
             .commit(writer, &self.authority)
             .await
     }
 
+    /// Restart the domain reported as failed by a worker, if it has any restarts left in its
+    /// budget. Returns whether the domain was restarted.
+    pub(super) async fn handle_failed_domain(
+        &self,
+        failure: DomainFailure,
+        authority: &Arc<Authority>,
+    ) -> ReadySetResult<bool> {
+        let domain = failure.replica_address.domain_index;
+        error!(
+            domain = %failure.replica_address,
+            error = %failure.error,
+            "worker reported domain failure"
+        );
+        let mut writer = self.dataflow_state_handle.write().await;
+        if self.pending_recovery || writer.as_ref().workers.len() < self.quorum {
+            return Err(ReadySetError::NoQuorum);
+        }
+        if !self.domain_restarts.try_restart(domain, Instant::now()) {
+            error!(
+                domain = %domain.index(),
+                "domain has exhausted its restart budget, and will not be restarted"
+            );
+            return Ok(false);
+        }
+
+        warn!(domain = %domain.index(), "restarting failed domain");
+        let res = async {
+            writer.as_mut().restart_domain(domain).await?;
+            self.dataflow_state_handle.commit(writer, authority).await
+        }
+        .await;
+        if let Err(error) = res {
+            // The worker retries reporting the failure until the domain is restarted, so don't
+            // count the failed attempt against the domain's budget
+            warn!(domain = %domain.index(), %error, "failed to restart domain");
+            self.domain_restarts.cancel_restart(domain);
+            return Err(error);
+        }
+        metrics::increment_counter!(
+            recorded::CONTROLLER_DOMAIN_RESTARTS,
+            "domain" => domain.index().to_string()
+        );
+        Ok(true)
+    }
+
     /// Construct `Leader` with a specified listening interface
     pub(super) fn new(
         state: ControllerState,
         controller_uri: Url,
         authority: Arc<Authority>,
         replicator_config: UpstreamConfig,
+        replication_transform: ReplicationTransformHook,
+        channel_security: Option<ChannelSecurity>,
         worker_request_timeout: Duration,
     ) -> Self {
         assert_ne!(state.config.quorum, 0);
@@ -681,10 +1387,11 @@ impl Leader {
         // [`ControllerState`]   itself.
         let pending_recovery = state.dataflow_state.ingredients.node_indices().count() > 1;
 
-        let dataflow_state_handle = DfStateHandle::new(state.dataflow_state);
+        let dataflow_state_handle = Arc::new(DfStateHandle::new(state.dataflow_state));
 
         Leader {
             dataflow_state_handle,
+            query_ids: Default::default(),
             pending_recovery,
 
             quorum: state.config.quorum,
@@ -692,13 +1399,42 @@ impl Leader {
             controller_uri,
 
             replicator_config,
+            replication_transform,
+            replication_control: ReplicationControl::default(),
+            persisted_replication_timestamp: Default::default(),
+            channel_security,
             replicator_task: None,
+            state_checksum_interval: state.config.state_checksum_interval,
+            state_checksum_task: None,
+            domain_restarts: RestartBudget::new(
+                state.config.domain_restart_budget,
+                state.config.domain_restart_window,
+            ),
+            last_barrier: AtomicU64::new(0),
+            quiescing: AtomicBool::new(false),
+            reloading_config: Default::default(),
             authority,
             worker_request_timeout,
         }
     }
 }
 
+/// Restore the memory limit settings of the given workers to the given `(memory check frequency,
+/// memory limit)`, after failing to apply a configuration change to all of them
+async fn restore_memory_limits(workers: &[(Worker, (Option<Duration>, Option<usize>))]) {
+    for (worker, (period, limit)) in workers {
+        if let Err(error) = worker
+            .rpc::<()>(WorkerRequestKind::SetMemoryLimit {
+                period: *period,
+                limit: *limit,
+            })
+            .await
+        {
+            warn!(%error, uri = %worker.uri, "Failed to restore worker memory limit");
+        }
+    }
+}
+
 /// Helper method to distinguish if the given [`ControllerRequest`] actually
 /// requires modifying the dataflow graph state.
 pub(super) fn request_type(req: &ControllerRequest) -> ControllerRequestType {
@@ -706,11 +1442,20 @@ pub(super) fn request_type(req: &ControllerRequest) -> ControllerRequestType {
         (&Method::GET, "/flush_partial")
         | (&Method::GET | &Method::POST, "/controller_uri")
         | (&Method::POST, "/extend_recipe")
+        | (&Method::POST, "/extend_recipe_concurrently")
         | (&Method::POST, "/remove_query")
         | (&Method::POST, "/remove_all_queries")
+        | (&Method::POST, "/register_queries")
+        | (&Method::POST, "/add_index")
+        | (&Method::POST, "/set_mir_plan")
         | (&Method::POST, "/set_replication_offset")
         | (&Method::POST, "/replicate_readers")
-        | (&Method::POST, "/remove_node") => ControllerRequestType::Write,
+        | (&Method::POST, "/remove_node")
+        | (&Method::POST, "/drain_worker")
+        | (&Method::POST, "/pause_replication")
+        | (&Method::POST, "/resume_replication")
+        | (&Method::POST, "/reload_config")
+        | (&Method::POST, "/domain_failed") => ControllerRequestType::Write,
         (&Method::POST, "/dry_run") => ControllerRequestType::DryRun,
         _ => ControllerRequestType::Read,
     }
@@ -25,7 +25,11 @@ use crate::controller::NodeRestrictionKey;
 /// Since readers always re-materialize, sharing a domain doesn't help them much. Having them in
 /// their own domain also means that they get to aggregate reader replay requests in their own
 /// thread, and not interfere as much with other internal traffic.
-/// A reader node is then assigned a new domain.
+/// A reader node is then assigned a new domain, unless it's one side of a
+/// [`LazyJoin`](dataflow::LazyJoin), in which case it's assigned the same domain as the reader on
+/// the other side of the join (if that's been assigned one already). The fact reader of a lazy join
+/// looks up rows in the dimension reader directly when it's read from, so the two have to always
+/// run on the same worker.
 ///
 /// ## [`dataflow::node::NodeType::Base`]
 /// Base nodes are assigned domains depending on sharding.
@@ -89,6 +93,10 @@ pub fn assign(dataflow_state: &mut DfState, new_nodes: &[NodeIndex]) -> ReadySet
             }
 
             if n.is_reader() {
+                if let Some(domain) = lazy_join_domain(graph, node) {
+                    return Ok(domain);
+                }
+
                 // readers always re-materialize, so sharing a domain doesn't help them much.
                 // having them in their own domain also means that they get to aggregate reader
                 // replay requests in their own little thread, and not interfere as much with other
@@ -324,3 +332,38 @@ pub fn assign(dataflow_state: &mut DfState, new_nodes: &[NodeIndex]) -> ReadySet
     dataflow_state.ndomains = ndomains;
     Ok(())
 }
+
+/// If the given reader node is either side of a [`LazyJoin`](dataflow::LazyJoin), returns the
+/// domain of the reader on the other side of the join, if it's been assigned one and has the same
+/// sharding as `node`
+fn lazy_join_domain(graph: &Graph, node: NodeIndex) -> Option<usize> {
+    let n = &graph[node];
+    let r = n.as_reader()?;
+    let same_domain_as = |other: NodeIndex| {
+        let o = graph.node_weight(other)?;
+        (o.has_domain() && o.sharded_by() == n.sharded_by()).then(|| o.domain().index())
+    };
+
+    if let Some(domain) = r
+        .lazy_joins()
+        .iter()
+        .find_map(|join| same_domain_as(join.dimension.node))
+    {
+        return Some(domain);
+    }
+
+    if r.requires_full_materialization() {
+        return graph
+            .node_indices()
+            .filter(|&fact| {
+                graph[fact].as_reader().map_or(false, |fact| {
+                    fact.lazy_joins()
+                        .iter()
+                        .any(|join| join.dimension.node == node)
+                })
+            })
+            .find_map(same_domain_as);
+    }
+
+    None
+}
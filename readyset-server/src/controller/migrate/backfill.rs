@@ -0,0 +1,330 @@
+//! Backfills: the full replays of the state of new materializations which a migration applied with
+//! [`Migration::defer_backfills`](super::Migration::defer_backfills) leaves running in the
+//! background once it's been committed, so that creating a cache with a lot of initial state
+//! doesn't hold up every other migration for as long as that state takes to replay.
+//!
+//! A domain can only be the target of one full replay at a time, so backfills (and the full
+//! replays of migrations which aren't deferred) into the same domain take turns, but backfills into
+//! different domains run concurrently. Replays out of a node whose state is still being backfilled
+//! wait for that backfill to complete first, since they would otherwise replay incomplete state.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use dataflow::prelude::*;
+use dataflow::DomainRequest;
+use readyset_tracing::{debug, warn};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+use super::StoredDomainRequest;
+use crate::controller::domain_handle::DomainHandle;
+use crate::controller::{Worker, WorkerIdentifier};
+
+/// A node in a particular domain
+type DomainNode = (DomainIndex, LocalNodeIndex);
+
+/// The locks taken by backfills and full replays, and the results of backfills which have been
+/// started. Shared by every copy of the [`DfState`](crate::controller::state::DfState).
+#[derive(Default)]
+pub(in crate::controller) struct Backfills {
+    /// A lock for each domain, held for as long as a full replay into that domain is running
+    targets: Mutex<HashMap<DomainIndex, Arc<AsyncMutex<()>>>>,
+    /// A lock for each node which won't be ready until a running backfill completes, held by that
+    /// backfill
+    nodes: Mutex<HashMap<DomainNode, Arc<AsyncMutex<()>>>>,
+    /// The identifier to give to the next backfill
+    next_id: AtomicU64,
+    /// The results of the backfills which have been started and not yet reported by
+    /// [`Backfills::take_result`], or `None` for those which are still running
+    results: Mutex<HashMap<u64, Option<ReadySetResult<()>>>>,
+}
+
+/// Returns the domains which are the targets of the full replays started by `requests`
+fn replay_targets<'a>(
+    requests: impl IntoIterator<Item = &'a StoredDomainRequest>,
+) -> BTreeSet<DomainIndex> {
+    requests
+        .into_iter()
+        .filter(|req| matches!(req.req, DomainRequest::QueryReplayDone { .. }))
+        .map(|req| req.domain)
+        .collect()
+}
+
+/// Returns the nodes which `requests` replay out of
+fn replay_sources<'a>(
+    requests: impl IntoIterator<Item = &'a StoredDomainRequest>,
+) -> HashSet<DomainNode> {
+    requests
+        .into_iter()
+        .filter_map(|req| match req.req {
+            DomainRequest::StartReplay { from, .. } => Some((req.domain, from)),
+            DomainRequest::SetupReplayPath {
+                source: Some(source),
+                ..
+            } => Some((req.domain, source)),
+            _ => None,
+        })
+        .collect()
+}
+
+impl Backfills {
+    /// Lock each of the given domains for a full replay into them, waiting for any other full
+    /// replays into those domains to complete first
+    async fn lock_targets(&self, targets: BTreeSet<DomainIndex>) -> Vec<OwnedMutexGuard<()>> {
+        let mut guards = Vec::with_capacity(targets.len());
+        // Domains are locked in order, so that replays which need the same domains can't deadlock
+        for domain in targets {
+            #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+            let lock = self
+                .targets
+                .lock()
+                .unwrap()
+                .entry(domain)
+                .or_default()
+                .clone();
+            guards.push(lock.lock_owned().await);
+        }
+        guards
+    }
+
+    /// Wait for the backfills of any of `nodes` which are still running to complete
+    async fn wait_for_nodes(&self, nodes: impl IntoIterator<Item = DomainNode>) {
+        for node in nodes {
+            #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+            let lock = self.nodes.lock().unwrap().get(&node).cloned();
+            if let Some(lock) = lock {
+                debug!(
+                    domain = %node.0.index(),
+                    node = node.1.id(),
+                    "waiting for backfill of replay source"
+                );
+                drop(lock.lock().await);
+            }
+        }
+    }
+
+    /// Prepare to send `requests`, which don't include any requests belonging to a backfill, by
+    /// waiting for the backfills of the nodes they replay out of and locking the domains their
+    /// full replays target. The returned guards must be held until the requests have been sent.
+    pub(super) async fn prepare(
+        &self,
+        requests: &[StoredDomainRequest],
+    ) -> Vec<OwnedMutexGuard<()>> {
+        self.wait_for_nodes(replay_sources(requests)).await;
+        self.lock_targets(replay_targets(requests)).await
+    }
+
+    /// Start a backfill which will send `requests` once run, registering the nodes they ready so
+    /// that replays out of those nodes wait for the backfill to complete
+    pub(super) fn start(self: &Arc<Self>, requests: Vec<StoredDomainRequest>) -> Backfill {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut nodes = HashMap::new();
+        for req in &requests {
+            if let DomainRequest::Ready { node, .. } = req.req {
+                let lock = Arc::new(AsyncMutex::new(()));
+                #[allow(clippy::unwrap_used)] // nobody else has the lock yet
+                let guard = lock.clone().try_lock_owned().unwrap();
+                nodes.insert((req.domain, node), (lock, guard));
+            }
+        }
+
+        #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+        self.nodes.lock().unwrap().extend(
+            nodes
+                .iter()
+                .map(|(node, (lock, _))| (*node, Arc::clone(lock))),
+        );
+        #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+        self.results.lock().unwrap().insert(id, None);
+
+        Backfill {
+            id,
+            requests,
+            nodes,
+            backfills: Arc::clone(self),
+        }
+    }
+
+    /// Returns `true` if the backfill with the given identifier completed successfully, `false` if
+    /// it's still running, or the error it failed with. Once a completed backfill has been reported
+    /// its result is forgotten, and it's reported as unknown.
+    pub(in crate::controller) fn take_result(&self, id: u64) -> ReadySetResult<bool> {
+        #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+        let mut results = self.results.lock().unwrap();
+        match results.get(&id) {
+            None => Err(ReadySetError::BadRequest(format!("Unknown backfill {id}"))),
+            Some(None) => Ok(false),
+            Some(Some(_)) => {
+                #[allow(clippy::unwrap_used)] // checked above
+                let result = results.remove(&id).unwrap().unwrap();
+                result.map(|()| true)
+            }
+        }
+    }
+}
+
+/// A backfill started by a migration applied with
+/// [`Migration::defer_backfills`](super::Migration::defer_backfills): the requests of the
+/// migration from its first full replay onwards, which are sent in the background by
+/// [`Backfill::run`]
+pub(in crate::controller) struct Backfill {
+    id: u64,
+    requests: Vec<StoredDomainRequest>,
+    /// The nodes readied by `requests`, along with their locks in [`Backfills::nodes`] and the
+    /// guards held on them until the backfill completes
+    nodes: HashMap<DomainNode, (Arc<AsyncMutex<()>>, OwnedMutexGuard<()>)>,
+    backfills: Arc<Backfills>,
+}
+
+impl Backfill {
+    /// Returns the identifier of this backfill, which can be passed to [`Backfills::take_result`]
+    pub(in crate::controller) fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Send the requests of the backfill to the given domains, recording the result in the
+    /// [`Backfills`] it was started by once they've been sent
+    pub(in crate::controller) async fn run(
+        self,
+        domains: HashMap<DomainIndex, DomainHandle>,
+        workers: HashMap<WorkerIdentifier, Worker>,
+    ) {
+        let Backfill {
+            id,
+            requests,
+            nodes,
+            backfills,
+        } = self;
+
+        let result: ReadySetResult<()> = async {
+            // Nodes this backfill readies don't need to wait for it
+            backfills
+                .wait_for_nodes(
+                    replay_sources(&requests)
+                        .into_iter()
+                        .filter(|node| !nodes.contains_key(node)),
+                )
+                .await;
+            let _targets = backfills.lock_targets(replay_targets(&requests)).await;
+
+            #[cfg(feature = "failure_injection")]
+            while fail::eval(readyset_client::failpoints::BACKFILL, |_| ()).is_some() {
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            }
+
+            for req in requests {
+                req.apply(&domains, &workers).await?;
+            }
+            Ok(())
+        }
+        .await;
+
+        match &result {
+            Ok(()) => debug!(id, "backfill completed"),
+            Err(error) => warn!(id, %error, "backfill failed"),
+        }
+
+        {
+            #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+            let mut registered = backfills.nodes.lock().unwrap();
+            for (node, (lock, _guard)) in nodes {
+                // Only remove the lock if it's still ours
+                if registered
+                    .get(&node)
+                    .map_or(false, |l| Arc::ptr_eq(l, &lock))
+                {
+                    registered.remove(&node);
+                }
+            }
+        }
+        #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+        backfills.results.lock().unwrap().insert(id, Some(result));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn request(domain: usize, req: DomainRequest) -> StoredDomainRequest {
+        StoredDomainRequest {
+            domain: DomainIndex::from(domain),
+            shard: None,
+            req,
+        }
+    }
+
+    fn ready(domain: usize, node: u32) -> StoredDomainRequest {
+        request(
+            domain,
+            DomainRequest::Ready {
+                node: LocalNodeIndex::make(node),
+                purge: false,
+                index: Default::default(),
+            },
+        )
+    }
+
+    fn replay_into(domain: usize) -> StoredDomainRequest {
+        request(domain, DomainRequest::QueryReplayDone { sources: vec![] })
+    }
+
+    fn replay_from(domain: usize, node: u32) -> StoredDomainRequest {
+        request(
+            domain,
+            DomainRequest::StartReplay {
+                tag: Tag::new(0),
+                from: LocalNodeIndex::make(node),
+            },
+        )
+    }
+
+    /// Returns true if `fut` completes within a short time
+    async fn completes<F: std::future::Future>(fut: F) -> bool {
+        tokio::time::timeout(Duration::from_millis(50), fut)
+            .await
+            .is_ok()
+    }
+
+    #[tokio::test]
+    async fn replays_into_different_domains_run_concurrently() {
+        let backfills = Arc::<Backfills>::default();
+        let _first = backfills.prepare(&[replay_into(1)]).await;
+        assert!(completes(backfills.prepare(&[replay_into(2)])).await);
+    }
+
+    #[tokio::test]
+    async fn replays_into_the_same_domain_take_turns() {
+        let backfills = Arc::<Backfills>::default();
+        let first = backfills.prepare(&[replay_into(1)]).await;
+        assert!(!completes(backfills.prepare(&[replay_into(1), replay_into(2)])).await);
+        drop(first);
+        assert!(completes(backfills.prepare(&[replay_into(1), replay_into(2)])).await);
+    }
+
+    #[tokio::test]
+    async fn replays_out_of_backfilling_nodes_wait() {
+        let backfills = Arc::<Backfills>::default();
+        let backfill = backfills.start(vec![replay_from(0, 0), replay_into(1), ready(1, 2)]);
+        let id = backfill.id();
+        assert!(!backfills.take_result(id).unwrap());
+
+        // Replays out of other nodes go ahead
+        assert!(completes(backfills.prepare(&[replay_from(1, 3), replay_into(2)])).await);
+        assert!(!completes(backfills.prepare(&[replay_from(1, 2), replay_into(2)])).await);
+
+        // Running the backfill fails, since there are no domains to send its requests to, but the
+        // nodes it would have readied are released either way
+        backfill.run(Default::default(), Default::default()).await;
+        assert!(completes(backfills.prepare(&[replay_from(1, 2), replay_into(2)])).await);
+        assert!(backfills.take_result(id).is_err());
+        // Results are only reported once
+        assert!(matches!(
+            backfills.take_result(id),
+            Err(ReadySetError::BadRequest(_))
+        ));
+    }
+}
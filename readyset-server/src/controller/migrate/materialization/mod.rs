@@ -7,11 +7,13 @@
 
 use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
+use std::time::{Duration, Instant};
 
 use dataflow::prelude::*;
-use dataflow::{DomainRequest, LookupIndex};
+use dataflow::{DomainRequest, LookupIndex, ROW_OVERHEAD_BYTES};
 use maplit::hashmap;
 use petgraph::graph::NodeIndex;
+use readyset_client::{KeyCount, NodeSize};
 use readyset_errors::{internal, internal_err, invariant, ReadySetError, ReadySetResult};
 use readyset_tracing::{debug, error, trace};
 use serde::{Deserialize, Serialize};
@@ -97,6 +99,19 @@ pub struct Config {
     ///
     /// Defaults to true.
     pub partial_enabled: bool,
+
+    /// The maximum estimated size, in bytes, of the state of a single new fully materialized node
+    /// (other than base tables). Migrations that add a fully materialized node estimated to be
+    /// larger than this return [`ReadySetError::FullMaterializationOverBudget`], unless the node
+    /// is used by a cache created with `CREATE CACHE ALWAYS`.
+    ///
+    /// Sizes are estimated from the sizes of the materialized nodes the node is derived from,
+    /// which are collected at the start of a migration if they haven't been within the last
+    /// [`NODE_SIZES_MAX_AGE`].
+    ///
+    /// Defaults to None, meaning full materializations can be of any size
+    #[serde(default)]
+    pub max_full_materialization_bytes: Option<usize>,
 }
 
 impl Default for Config {
@@ -106,6 +121,7 @@ impl Default for Config {
             allow_full_materialization: true,
             partial_enabled: true,
             frontier_strategy: FrontierStrategy::None,
+            max_full_materialization_bytes: None,
         }
     }
 }
@@ -151,9 +167,27 @@ pub(in crate::controller) struct Materializations {
     #[serde(skip, default = "get_pending_recovery")]
     pub(in crate::controller) pending_recovery: bool,
 
+    /// The approximate size of the state of each materialized node, as of the last time they were
+    /// collected. Used to estimate the size of new full materializations.
+    #[serde(skip)]
+    node_sizes: HashMap<NodeIndex, NodeSize>,
+
+    /// When `node_sizes` were last collected, if they ever have been
+    #[serde(skip)]
+    node_sizes_collected_at: Option<Instant>,
+
+    /// Leaf nodes of caches created with `CREATE CACHE ALWAYS`, whose full materializations aren't
+    /// subject to [`Config::max_full_materialization_bytes`]
+    #[serde(default)]
+    budget_exempt: HashSet<NodeIndex>,
+
     pub(crate) config: Config,
 }
 
+/// How long the sizes of materialized nodes collected to estimate the size of new full
+/// materializations are reused for, before being collected again
+pub(in crate::controller) const NODE_SIZES_MAX_AGE: Duration = Duration::from_secs(60);
+
 /// Helper function to deserialize the `pending_recovery` field
 /// as `true`.
 fn get_pending_recovery() -> bool {
@@ -180,6 +214,11 @@ impl Materializations {
 
             pending_recovery: false,
 
+            node_sizes: HashMap::default(),
+            node_sizes_collected_at: None,
+
+            budget_exempt: HashSet::default(),
+
             config: Default::default(),
         }
     }
@@ -201,6 +240,31 @@ impl Materializations {
     ) {
         self.redundant_partial.extend(new_duplicates);
     }
+
+    /// Set the approximate size of the state of each materialized node, used to estimate the size
+    /// of new full materializations
+    pub(in crate::controller) fn set_node_sizes(
+        &mut self,
+        node_sizes: HashMap<NodeIndex, NodeSize>,
+    ) {
+        self.node_sizes = node_sizes;
+        self.node_sizes_collected_at = Some(Instant::now());
+    }
+
+    /// Returns true if the size of full materializations is limited, and the sizes of materialized
+    /// nodes used to estimate them haven't been collected within the last [`NODE_SIZES_MAX_AGE`]
+    pub(in crate::controller) fn needs_node_sizes(&self) -> bool {
+        self.config.max_full_materialization_bytes.is_some()
+            && self
+                .node_sizes_collected_at
+                .map_or(true, |at| at.elapsed() > NODE_SIZES_MAX_AGE)
+    }
+
+    /// Exempt the full materializations of all the nodes the given leaf node is derived from
+    /// from [`Config::max_full_materialization_bytes`]
+    pub(in crate::controller) fn exempt_from_budget(&mut self, leaf: NodeIndex) {
+        self.budget_exempt.insert(leaf);
+    }
 }
 
 impl Materializations {
@@ -290,6 +354,17 @@ impl Materializations {
                 );
             }
 
+            // base nodes which enforce their unique keys need to be able to look up existing rows
+            // by each of those keys
+            if let Some(base) = n.get_base() {
+                for key in base.enforced_unique_keys() {
+                    lookup_obligations
+                        .entry(ni)
+                        .or_default()
+                        .insert(LookupIndex::Strict(Index::hash_map(key.to_vec())));
+                }
+            }
+
             for (ni, obligation) in indices {
                 trace!(
                     node = %ni.index(),
@@ -487,6 +562,15 @@ impl Materializations {
                 able = false;
             }
 
+            #[allow(clippy::indexing_slicing)] // ordered is built from graph
+            if graph[ni]
+                .as_reader()
+                .map_or(false, |r| r.requires_full_materialization())
+            {
+                debug!(node = %ni.index(), "full because reader is a lazy join dimension");
+                able = false;
+            }
+
             // we are already fully materialized, so can't be made partial
             if !new.contains(&ni)
                 && self.added.get(&ni).map(|i| i.len()).unwrap_or(0)
@@ -619,6 +703,9 @@ impl Materializations {
                     !graph[ni].purge,
                     "full materialization placed beyond materialization frontier"
                 );
+                if !graph[ni].is_base() {
+                    self.check_full_materialization_budget(graph, ni)?;
+                }
             }
 
             // no matter what happens, we're going to have to fulfill our replay obligations.
@@ -735,6 +822,52 @@ impl Materializations {
         }
     }
 
+    /// Returns the indexes on the materialized state of the node `ni`, in sorted order
+    pub(in crate::controller) fn indexes(&self, ni: NodeIndex) -> Vec<Index> {
+        let mut indexes = self
+            .have
+            .get(&ni)
+            .into_iter()
+            .flatten()
+            .cloned()
+            .collect::<Vec<_>>();
+        indexes.sort();
+        indexes
+    }
+
+    /// Record a request to add `index` to the existing, fully materialized state of the node
+    /// `ni`, to be constructed the next time [`commit`](Self::commit) is called.
+    ///
+    /// Like any other index added to existing state, the new index is populated by
+    /// [`commit`](Self::commit) via a full replay along the node's existing replay path, so the
+    /// cost is proportional to the size of the node's state. Returns `false` (and does nothing) if
+    /// the node is not eligible for an additional index - because it is not materialized, is
+    /// partially materialized, is a base table or reader, or already has the index.
+    pub(in crate::controller) fn add_index(
+        &mut self,
+        graph: &Graph,
+        ni: NodeIndex,
+        index: Index,
+    ) -> bool {
+        #[allow(clippy::indexing_slicing)] // caller must pass a node in the graph
+        let node = &graph[ni];
+        if node.is_base() || node.is_reader() || self.partial.contains(&ni) {
+            return false;
+        }
+        if !self.paths.get(&ni).map_or(false, |paths| !paths.is_empty()) {
+            return false;
+        }
+
+        match self.have.get_mut(&ni) {
+            Some(indices) if !indices.contains(&index) => {
+                indices.insert(index.clone());
+                self.added.entry(ni).or_default().insert(index);
+                true
+            }
+            _ => false,
+        }
+    }
+
     /// Validate all graph invariants for the materializations in `self` for all nodes in `new` in
     /// the given `graph`, returning an `Err` if any invariants are violated. This consists of:
     ///
@@ -1225,6 +1358,7 @@ impl Materializations {
         if !pending.is_empty() {
             trace!("all domains ready for replay");
             // prepare for, start, and wait for replays
+            let mut sources = Vec::with_capacity(pending.len());
             for pending in pending {
                 // tell the first domain to start playing
                 debug!(
@@ -1239,6 +1373,9 @@ impl Materializations {
                         from: pending.source,
                     },
                 )?;
+                if !sources.contains(&pending.source_domain) {
+                    sources.push(pending.source_domain);
+                }
             }
             // and then wait for the last domain to receive all the records
             let target = graph[ni].domain();
@@ -1246,7 +1383,7 @@ impl Materializations {
                domain = %target.index(),
                "waiting for done message from target"
             );
-            dmp.add_message(target, DomainRequest::QueryReplayDone)?;
+            dmp.add_message(target, DomainRequest::QueryReplayDone { sources })?;
         }
         Ok(())
     }
@@ -1261,6 +1398,103 @@ impl Materializations {
         for ni in nodes {
             self.have.remove(ni);
             self.redundant_partial.remove(ni);
+            self.node_sizes.remove(ni);
+            self.budget_exempt.remove(ni);
+        }
+    }
+
+    /// Estimate the size, in bytes, of the state of `ni` if it were fully materialized, from the
+    /// nearest nodes above it whose size is known.
+    ///
+    /// Each of those nodes contributes its own size, scaled up by how much wider the rows of `ni`
+    /// are than its rows (since the rows of a join include the columns of every side). The sizes
+    /// of in-memory nodes already count the per-row overhead of their state, but the sizes
+    /// reported by base tables are of their (compressed) on-disk state, so for those each value is
+    /// counted as at least the size of a [`DfValue`], plus [`ROW_OVERHEAD_BYTES`] for each row.
+    /// Joins which produce more rows than their inputs are still underestimated.
+    fn estimate_full_materialization_bytes(&self, graph: &Graph, ni: NodeIndex) -> usize {
+        #[allow(clippy::indexing_slicing)] // graph must contain ni
+        let columns = graph[ni].columns().len().max(1);
+        let mut bytes = 0usize;
+        let mut visited = HashSet::new();
+        let mut queue: Vec<_> = graph
+            .neighbors_directed(ni, petgraph::EdgeDirection::Incoming)
+            .collect();
+        while let Some(parent) = queue.pop() {
+            if !visited.insert(parent) {
+                continue;
+            }
+            match self.node_sizes.get(&parent) {
+                Some(size) => {
+                    #[allow(clippy::indexing_slicing)] // graph must contain parent
+                    let parent_columns = graph[parent].columns().len().max(1);
+                    let (parent_bytes, overhead_bytes) = match size.key_count {
+                        KeyCount::EstimatedRowCount(rows) => (
+                            size.bytes.bytes().max(
+                                rows.saturating_mul(parent_columns)
+                                    .saturating_mul(std::mem::size_of::<DfValue>()),
+                            ),
+                            rows.saturating_mul(ROW_OVERHEAD_BYTES as usize),
+                        ),
+                        KeyCount::ExactKeyCount(_) | KeyCount::ExternalMaterialization => {
+                            (size.bytes.bytes(), 0)
+                        }
+                    };
+                    let widened_bytes = if columns > parent_columns {
+                        parent_bytes.saturating_mul(columns) / parent_columns
+                    } else {
+                        parent_bytes
+                    };
+                    bytes = bytes
+                        .saturating_add(widened_bytes)
+                        .saturating_add(overhead_bytes);
+                }
+                None => queue
+                    .extend(graph.neighbors_directed(parent, petgraph::EdgeDirection::Incoming)),
+            }
+        }
+        bytes
+    }
+
+    /// Returns an error if fully materializing `ni` would exceed
+    /// [`Config::max_full_materialization_bytes`], unless a cache derived from `ni` is exempt from
+    /// the budget.
+    fn check_full_materialization_budget(
+        &self,
+        graph: &Graph,
+        ni: NodeIndex,
+    ) -> ReadySetResult<()> {
+        let Some(budget_bytes) = self.config.max_full_materialization_bytes else {
+            return Ok(());
+        };
+
+        let estimated_bytes = self.estimate_full_materialization_bytes(graph, ni);
+        if estimated_bytes <= budget_bytes {
+            return Ok(());
         }
+
+        let mut exempt = false;
+        let mut dfs = petgraph::visit::Dfs::new(graph, ni);
+        while let Some(descendant) = dfs.next(graph) {
+            if self.budget_exempt.contains(&descendant) {
+                exempt = true;
+                break;
+            }
+        }
+        if exempt {
+            debug!(
+                node = %ni.index(),
+                estimated_bytes,
+                "allowing full materialization over budget for CREATE CACHE ALWAYS"
+            );
+            return Ok(());
+        }
+
+        #[allow(clippy::indexing_slicing)] // graph must contain ni
+        Err(ReadySetError::FullMaterializationOverBudget {
+            node: graph[ni].name().to_string(),
+            estimated_bytes,
+            budget_bytes,
+        })
     }
 }
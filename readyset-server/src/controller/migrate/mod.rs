@@ -32,28 +32,34 @@
 //! Beware, Here be slightly smaller dragons™
 
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use array2::Array2;
 use dataflow::node::Column;
 use dataflow::prelude::*;
-use dataflow::{node, DomainRequest, ReaderProcessing};
+use dataflow::{node, DomainRequest, LazyJoin, ReaderProcessing};
 use metrics::{counter, histogram};
-use nom_sql::Relation;
+use nom_sql::{CacheFreshness, CacheResultLimits, Relation};
+use petgraph::visit::Bfs;
 use readyset_client::metrics::recorded;
-use readyset_client::{KeyColumnIdx, ReadySetError, ViewPlaceholder};
+use readyset_client::placement::PlacementConstraints;
+use readyset_client::{KeyColumnIdx, ReaderAddress, ReadySetError, ViewPlaceholder};
 use readyset_data::{DfType, Dialect};
 use readyset_tracing::{debug, error, info, trace};
 use tracing::{debug_span, info_span, instrument};
 
+use crate::controller::domain_handle::DomainHandle;
+use crate::controller::migrate::backfill::Backfill;
 use crate::controller::migrate::materialization::InvalidEdge;
 use crate::controller::migrate::node_changes::{MigrationNodeChanges, NodeChanges};
 use crate::controller::migrate::scheduling::Scheduler;
 use crate::controller::state::DfState;
-use crate::controller::WorkerIdentifier;
+use crate::controller::{Worker, WorkerIdentifier};
 
 pub(crate) mod assignment;
 mod augmentation;
+pub(in crate::controller) mod backfill;
 pub(crate) mod materialization;
 pub(in crate::controller) mod node_changes;
 pub(in crate::controller) mod routing;
@@ -74,18 +80,20 @@ pub struct StoredDomainRequest {
 }
 
 impl StoredDomainRequest {
-    pub async fn apply(self, mainline: &mut DfState) -> ReadySetResult<()> {
+    pub async fn apply(
+        self,
+        domains: &HashMap<DomainIndex, DomainHandle>,
+        workers: &HashMap<WorkerIdentifier, Worker>,
+    ) -> ReadySetResult<()> {
         trace!(req=?self, "Applying domain request");
-        let dom =
-            mainline
-                .domains
-                .get_mut(&self.domain)
-                .ok_or_else(|| ReadySetError::UnknownDomain {
-                    domain_index: self.domain.index(),
-                })?;
+        let dom = domains
+            .get(&self.domain)
+            .ok_or_else(|| ReadySetError::UnknownDomain {
+                domain_index: self.domain.index(),
+            })?;
 
         match self.req {
-            DomainRequest::QueryReplayDone => {
+            DomainRequest::QueryReplayDone { sources } => {
                 debug!("waiting for a done message");
 
                 invariant!(self.shard.is_none()); // QueryReplayDone isn't ever sent to just one shard
@@ -95,7 +103,12 @@ impl StoredDomainRequest {
                 // FIXME(eta): this is a bit of a hack... (also, timeouts?)
                 loop {
                     if dom
-                        .send_to_healthy::<bool>(DomainRequest::QueryReplayDone, &mainline.workers)
+                        .send_to_healthy::<bool>(
+                            DomainRequest::QueryReplayDone {
+                                sources: sources.clone(),
+                            },
+                            workers,
+                        )
                         .await?
                         .into_iter()
                         .all(|replicas| replicas.into_iter().all(|done| done))
@@ -103,16 +116,28 @@ impl StoredDomainRequest {
                         break;
                     }
 
+                    // A replay that failed part way through will never finish, so bail out with
+                    // its error rather than waiting for it forever
+                    for source in &sources {
+                        domains
+                            .get(source)
+                            .ok_or_else(|| ReadySetError::UnknownDomain {
+                                domain_index: source.index(),
+                            })?
+                            .send_to_healthy::<()>(DomainRequest::CheckFailedReplays, workers)
+                            .await?;
+                    }
+
                     spins += 1;
                     if spins == 10 {
                         info!("waiting for setup()-initiated replay to complete");
                         spins = 0;
                     }
-                    std::thread::sleep(Duration::from_millis(200));
+                    tokio::time::sleep(Duration::from_millis(200)).await;
                 }
             }
             DomainRequest::RemoveNodes { .. } => {
-                match dom.send_to_healthy::<()>(self.req, &mainline.workers).await {
+                match dom.send_to_healthy::<()>(self.req, workers).await {
                     // The worker failing is an even more efficient way to remove nodes.
                     Ok(_) | Err(ReadySetError::WorkerFailed { .. }) => {}
                     Err(e) => return Err(e),
@@ -120,11 +145,10 @@ impl StoredDomainRequest {
             }
             _ => {
                 if let Some(shard) = self.shard {
-                    dom.send_to_healthy_shard::<()>(shard, self.req, &mainline.workers)
+                    dom.send_to_healthy_shard::<()>(shard, self.req, workers)
                         .await?;
                 } else {
-                    dom.send_to_healthy::<()>(self.req, &mainline.workers)
-                        .await?;
+                    dom.send_to_healthy::<()>(self.req, workers).await?;
                 }
             }
         }
@@ -144,6 +168,9 @@ pub struct PlaceRequest {
     shard_replica_workers: Array2<WorkerIdentifier>,
     /// Indices of new nodes to add.
     nodes: Vec<NodeIndex>,
+    /// Constraints on the workers the domain may be scheduled onto, in addition to those
+    /// configured for the whole deployment
+    placement_constraints: PlacementConstraints,
 }
 
 /// Runtime configuration for a domain
@@ -180,8 +207,12 @@ impl<'df> MigrationPlan<'df> {
     /// If the plan fails, the `Leader`'s state is left unchanged; however, no attempt
     /// is made to roll back any destructive changes that may have occurred before the plan failed
     /// to apply.
+    ///
+    /// If `defer_backfills` is set, any full replays of the state of new materializations (and
+    /// everything in the plan after them) are left to the returned [`Backfill`], rather than
+    /// waited for.
     #[instrument(level = "info", name = "apply", skip(self))]
-    pub async fn apply(self) -> ReadySetResult<()> {
+    pub async fn apply(self, defer_backfills: bool) -> ReadySetResult<Option<Backfill>> {
         let MigrationPlan {
             dataflow_state,
             mut dmp,
@@ -195,10 +226,20 @@ impl<'df> MigrationPlan<'df> {
 
         let start = Instant::now();
 
-        match dmp.apply(dataflow_state).await {
-            Ok(_) => {
-                debug!(ms = %start.elapsed().as_millis(), "migration plan applied");
-                Ok(())
+        let res = if defer_backfills {
+            dmp.apply_deferring_backfills(dataflow_state).await
+        } else {
+            dmp.apply(dataflow_state).await.map(|()| None)
+        };
+
+        match res {
+            Ok(backfill) => {
+                debug!(
+                    ms = %start.elapsed().as_millis(),
+                    deferred = backfill.is_some(),
+                    "migration plan applied"
+                );
+                Ok(backfill)
             }
             Err(e) => {
                 error!(error = %e, "migration plan apply failed");
@@ -247,11 +288,13 @@ impl DomainMigrationPlan {
         idx: DomainIndex,
         shard_replica_workers: Array2<WorkerIdentifier>,
         nodes: Vec<NodeIndex>,
+        placement_constraints: PlacementConstraints,
     ) {
         self.place.push(PlaceRequest {
             idx,
             shard_replica_workers,
             nodes,
+            placement_constraints,
         });
     }
 
@@ -281,14 +324,57 @@ impl DomainMigrationPlan {
     /// Apply all stored changes using the given controller object, placing new domains and sending
     /// messages added since the last time this method was called.
     pub async fn apply(&mut self, mainline: &mut DfState) -> ReadySetResult<()> {
+        self.place_domains(mainline).await?;
+        let requests = std::mem::take(&mut self.stored);
+        let backfills = Arc::clone(&mainline.backfills);
+        let _replays = backfills.prepare(&requests).await;
+        for req in requests {
+            req.apply(&mainline.domains, &mainline.workers).await?;
+        }
+        Ok(())
+    }
+
+    /// Like [`DomainMigrationPlan::apply`], but only sends the messages before the first full
+    /// replay of the state of a materialization, returning the rest as a [`Backfill`] to be run
+    /// once the migration has been committed. Returns `None` if nothing needs to be replayed.
+    pub async fn apply_deferring_backfills(
+        &mut self,
+        mainline: &mut DfState,
+    ) -> ReadySetResult<Option<Backfill>> {
+        self.place_domains(mainline).await?;
+        let mut requests = std::mem::take(&mut self.stored);
+        let deferred = match requests
+            .iter()
+            .position(|req| matches!(req.req, DomainRequest::StartReplay { .. }))
+        {
+            Some(first_replay) => requests.split_off(first_replay),
+            None => vec![],
+        };
+
+        let backfills = Arc::clone(&mainline.backfills);
+        {
+            let _replays = backfills.prepare(&requests).await;
+            for req in requests {
+                req.apply(&mainline.domains, &mainline.workers).await?;
+            }
+        }
+        Ok((!deferred.is_empty()).then(|| backfills.start(deferred)))
+    }
+
+    /// Place the new domains added to the plan since the last time it was applied
+    async fn place_domains(&mut self, mainline: &mut DfState) -> ReadySetResult<()> {
         for place in self.place.drain(..) {
             let d = mainline
                 .place_domain(place.idx, place.shard_replica_workers, place.nodes)
                 .await?;
             mainline.domains.insert(place.idx, d);
-        }
-        for req in std::mem::take(&mut self.stored) {
-            req.apply(mainline).await?;
+            if place.placement_constraints.is_empty() {
+                mainline.domain_placement_constraints.remove(&place.idx);
+            } else {
+                mainline
+                    .domain_placement_constraints
+                    .insert(place.idx, place.placement_constraints);
+            }
         }
         Ok(())
     }
@@ -441,8 +527,16 @@ pub struct Migration<'df> {
     pub(in crate::controller) changes: MigrationNodeChanges,
     pub(super) columns: Vec<(NodeIndex, ColumnChange)>,
     pub(super) readers: HashMap<NodeIndex, NodeIndex>,
+    /// Readers added by earlier migrations whose [`max_staleness`](Migration::set_max_staleness)
+    /// was changed by this migration, along with the new value to send to their domains
+    pub(super) max_staleness_changes: Vec<(NodeIndex, Duration)>,
     pub(super) worker: Option<WorkerIdentifier>,
+    /// Constraints on which workers new domains containing readers or base tables may be
+    /// scheduled onto, in addition to [`DfState::placement_constraints`]
+    pub(super) placement_constraints: PlacementConstraints,
     pub(super) dialect: Dialect,
+    /// See [`Migration::defer_backfills`]
+    pub(super) defer_backfills: bool,
 
     pub(super) start: Instant,
 }
@@ -454,12 +548,27 @@ impl<'df> Migration<'df> {
             changes: Default::default(),
             columns: Default::default(),
             readers: Default::default(),
+            max_staleness_changes: Default::default(),
             worker: None,
+            placement_constraints: Default::default(),
             dialect,
+            defer_backfills: false,
             start: Instant::now(),
         }
     }
 
+    /// Only schedule domains created by this migration which contain readers or base tables onto
+    /// workers which meet the given constraints, in addition to any constraints already in place
+    pub fn add_placement_constraints(&mut self, constraints: PlacementConstraints) {
+        self.placement_constraints.extend(constraints);
+    }
+
+    /// Don't wait for the full replays of the state of new materializations to complete when
+    /// committing this migration. See the [`backfill`] module.
+    pub fn defer_backfills(&mut self) {
+        self.defer_backfills = true;
+    }
+
     /// Add the given `Ingredient` to the dataflow graph.
     ///
     /// The returned identifier can later be used to refer to the added ingredient.
@@ -696,7 +805,55 @@ impl<'df> Migration<'df> {
         ri
     }
 
-    /// Set up the given node such that its output can be efficiently queried.
+    /// Set up the given node to be the dimension side of a [`LazyJoin`], by maintaining a fully
+    /// materialized reader for it keyed on the given (dimension-side) join columns, and returning
+    /// the address of that reader.
+    ///
+    /// Since the returned address always refers to shard 0, the dimension node must not be
+    /// sharded.
+    pub fn maintain_lazy_join_dimension(&mut self, n: NodeIndex, on: Vec<usize>) -> ReaderAddress {
+        let ri = self.ensure_reader_for(n, None, Default::default());
+
+        // we know it's a reader - we just made it!
+        #[allow(clippy::indexing_slicing, clippy::unwrap_used)]
+        let r = self.dataflow_state.ingredients[ri].as_mut_reader().unwrap();
+        r.set_index(&Index::hash_map(on));
+        r.set_requires_full_materialization();
+
+        #[allow(clippy::indexing_slicing)] // we just made it!
+        ReaderAddress {
+            node: ri,
+            name: self.dataflow_state.ingredients[ri].name().clone(),
+            shard: 0,
+        }
+    }
+
+    /// Set up the given node such that its output can be efficiently queried, joining every
+    /// looked-up row against the dimension readers of the given [`LazyJoin`]s (see
+    /// [`Migration::maintain_lazy_join_dimension`]) at read time rather than materializing the
+    /// dimension columns in the reader.
+    ///
+    /// To query into the maintained state, use `Leader::get_getter`.
+    pub fn maintain_anonymous_with_lazy_joins(
+        &mut self,
+        n: NodeIndex,
+        index: &Index,
+        reader_processing: ReaderProcessing,
+        lazy_joins: Vec<LazyJoin>,
+    ) -> NodeIndex {
+        let ri = self.ensure_reader_for(n, None, reader_processing);
+
+        // we know it's a reader - we just made it!
+        #[allow(clippy::indexing_slicing, clippy::unwrap_used)]
+        let r = self.dataflow_state.ingredients[ri].as_mut_reader().unwrap();
+        r.set_index(index);
+        r.set_lazy_joins(lazy_joins);
+
+        ri
+    }
+
+    /// Set up the given node such that its output can be efficiently queried, and return the
+    /// index of its reader.
     ///
     /// To query into the maintained state, use `Leader::get_getter`.
     pub fn maintain(
@@ -706,7 +863,7 @@ impl<'df> Migration<'df> {
         index: &Index,
         reader_processing: ReaderProcessing,
         placeholder_map: Vec<(ViewPlaceholder, KeyColumnIdx)>,
-    ) {
+    ) -> NodeIndex {
         let ri = self.ensure_reader_for(n, Some(name), reader_processing);
 
         // we know it's a reader - we just made it!
@@ -715,11 +872,98 @@ impl<'df> Migration<'df> {
 
         r.set_index(index);
         r.set_mapping(placeholder_map);
+
+        ri
+    }
+
+    /// Allow keys evicted from the reader for `n` to be served with their evicted rows for up to
+    /// `max_staleness`, while they're replayed.
+    ///
+    /// If the reader was added by an earlier migration (because a new cache reuses the reader of
+    /// an existing one), its domain is told about the new setting when this migration is applied.
+    /// Does nothing if `n` has no reader.
+    pub fn set_max_staleness(&mut self, n: NodeIndex, max_staleness: Duration) {
+        let ingredients = &mut self.dataflow_state.ingredients;
+        let ri = match self.readers.get(&n) {
+            Some(ri) => *ri,
+            None => {
+                let mut bfs = Bfs::new(&*ingredients, n);
+                let existing = std::iter::from_fn(|| bfs.next(&*ingredients)).find(|child| {
+                    #[allow(clippy::indexing_slicing)] // just came from ingredients
+                    ingredients[*child].is_reader_for(n)
+                });
+                match existing {
+                    Some(ri) => {
+                        self.max_staleness_changes.push((ri, max_staleness));
+                        ri
+                    }
+                    None => return,
+                }
+            }
+        };
+
+        #[allow(clippy::indexing_slicing, clippy::unwrap_used)] // we just found it
+        ingredients[ri]
+            .as_mut_reader()
+            .unwrap()
+            .set_max_staleness(max_staleness);
+    }
+
+    /// Periodically evict keys of the reader added for `n` in this migration which contain a
+    /// timestamp older than `bucket_retention`.
+    ///
+    /// Does nothing if no reader was added for `n` in this migration.
+    pub fn set_bucket_retention(&mut self, n: NodeIndex, bucket_retention: Duration) {
+        if let Some(ri) = self.readers.get(&n) {
+            #[allow(clippy::indexing_slicing, clippy::unwrap_used)] // we made it!
+            self.dataflow_state.ingredients[*ri]
+                .as_mut_reader()
+                .unwrap()
+                .set_bucket_retention(bucket_retention);
+        }
+    }
+
+    /// Set the limits on the size of the rows returned from a single lookup into the reader added
+    /// for `n` in this migration.
+    ///
+    /// Does nothing if no reader was added for `n` in this migration.
+    pub fn set_result_limits(&mut self, n: NodeIndex, result_limits: CacheResultLimits) {
+        if let Some(ri) = self.readers.get(&n) {
+            #[allow(clippy::indexing_slicing, clippy::unwrap_used)] // we made it!
+            self.dataflow_state.ingredients[*ri]
+                .as_mut_reader()
+                .unwrap()
+                .set_result_limits(result_limits);
+        }
+    }
+
+    /// Set the bound on replication lag that reads from the reader added for `n` in this migration
+    /// are subject to.
+    ///
+    /// Does nothing if no reader was added for `n` in this migration.
+    pub fn set_freshness(&mut self, n: NodeIndex, freshness: CacheFreshness) {
+        if let Some(ri) = self.readers.get(&n) {
+            #[allow(clippy::indexing_slicing, clippy::unwrap_used)] // we made it!
+            self.dataflow_state.ingredients[*ri]
+                .as_mut_reader()
+                .unwrap()
+                .set_freshness(freshness);
+        }
+    }
+
+    /// Allow the nodes that `n` is derived from to be fully materialized regardless of their
+    /// estimated size (see [`materialization::Config::max_full_materialization_bytes`]).
+    pub fn exempt_from_materialization_budget(&mut self, n: NodeIndex) {
+        self.dataflow_state.materializations.exempt_from_budget(n);
     }
 
     /// Build a `MigrationPlan` for this migration, and apply it if the planning stage succeeds.
-    pub(super) async fn commit(self, dry_run: bool) -> ReadySetResult<()> {
+    ///
+    /// Returns the [`Backfill`] left to run if the migration [defers
+    /// backfills](Migration::defer_backfills) and has anything to replay.
+    pub(super) async fn commit(self, dry_run: bool) -> ReadySetResult<Option<Backfill>> {
         let start = self.start;
+        let defer_backfills = self.defer_backfills;
 
         let plan = self
             .plan()
@@ -728,9 +972,9 @@ impl<'df> Migration<'df> {
             })?;
         // We skip the actual migration when we run in dry-run mode.
         if dry_run {
-            return Ok(());
+            return Ok(None);
         }
-        plan.apply().await?;
+        let backfill = plan.apply(defer_backfills).await?;
 
         debug!(
             ms = ?start.elapsed().as_millis(),
@@ -742,7 +986,7 @@ impl<'df> Migration<'df> {
             start.elapsed().as_micros() as f64
         );
 
-        Ok(())
+        Ok(backfill)
     }
 
     /// Build a `MigrationPlan` for this migration, computing all necessary changes to the
@@ -761,12 +1005,19 @@ impl<'df> Migration<'df> {
         let mut added = 0;
         let mut dropped = 0;
         let columns = self.columns;
+        let max_staleness_changes = self.max_staleness_changes;
         let worker = self.worker;
+        let placement_constraints = self.placement_constraints;
         for change in self.changes.into_iter() {
             match change {
                 NodeChanges::Add(new_nodes) => {
                     added += new_nodes.len();
-                    dmp.extend(plan_add_nodes(dataflow_state, new_nodes, &worker)?)
+                    dmp.extend(plan_add_nodes(
+                        dataflow_state,
+                        new_nodes,
+                        &worker,
+                        &placement_constraints,
+                    )?)
                 }
                 NodeChanges::Drop(drop_nodes) => {
                     dropped += drop_nodes.len();
@@ -778,6 +1029,17 @@ impl<'df> Migration<'df> {
         // We have successfully made a valid graph! Now we can inform the dmp of all the
         // changes
         inform_col_changes(&mut dmp, &columns, &dataflow_state.ingredients)?;
+        for (ri, max_staleness) in max_staleness_changes {
+            #[allow(clippy::indexing_slicing)] // readers must exist in ingredients
+            let reader = &dataflow_state.ingredients[ri];
+            dmp.add_message(
+                reader.domain(),
+                DomainRequest::SetReaderMaxStaleness {
+                    node: reader.local_addr(),
+                    max_staleness,
+                },
+            )?;
+        }
 
         debug!(
             added_nodes = added,
@@ -797,10 +1059,59 @@ impl<'df> Migration<'df> {
     }
 }
 
+/// Checks that the dimension readers of the lazy joins of all new readers meet the requirements
+/// documented on [`LazyJoin::dimension`], which the fact readers rely on when they're read from
+fn validate_lazy_joins(
+    dataflow_state: &DfState,
+    new_nodes: &HashSet<NodeIndex>,
+) -> ReadySetResult<()> {
+    let graph = &dataflow_state.ingredients;
+    for &ni in new_nodes {
+        let Some(fact) = graph.node_weight(ni) else { continue };
+        let Some(reader) = fact.as_reader() else { continue };
+        for join in reader.lazy_joins() {
+            let name = &join.dimension.name;
+            let Some(dimension) = graph.node_weight(join.dimension.node) else {
+                unsupported!("Lazy join dimension reader {name} does not exist")
+            };
+            let Some(dimension_reader) = dimension.as_reader() else {
+                unsupported!("Lazy join dimension {name} is not a reader")
+            };
+            if !dimension_reader.requires_full_materialization() {
+                unsupported!("Lazy join dimension reader {name} is not fully materialized")
+            }
+            if dimension_reader.key().map(<[usize]>::len) != Some(join.on.len()) {
+                unsupported!(
+                    "Lazy join dimension reader {name} is not keyed on the {} join column(s)",
+                    join.on.len()
+                )
+            }
+            if let Some(&col) = join
+                .emit
+                .iter()
+                .find(|&&col| col >= dimension.columns().len())
+            {
+                unsupported!("Lazy join emits nonexistent column {col} of dimension {name}")
+            }
+            if !fact.sharded_by().is_none() || !dimension.sharded_by().is_none() {
+                unsupported!("Readers with lazy joins and their dimensions can't be sharded")
+            }
+            if fact.domain() != dimension.domain() {
+                unsupported!(
+                    "Lazy join dimension reader {name} could not be placed in the same domain as \
+                     the reader joining against it"
+                )
+            }
+        }
+    }
+    Ok(())
+}
+
 fn plan_add_nodes(
     dataflow_state: &mut DfState,
     mut new_nodes: HashSet<NodeIndex>,
     worker: &Option<WorkerIdentifier>,
+    placement_constraints: &PlacementConstraints,
 ) -> ReadySetResult<DomainMigrationPlan> {
     let mut topo = topo_order(dataflow_state, &new_nodes);
 
@@ -825,6 +1136,7 @@ fn plan_add_nodes(
 
     // Assign domains
     assignment::assign(dataflow_state, &topo)?;
+    validate_lazy_joins(dataflow_state, &new_nodes)?;
 
     // Set up ingress and egress nodes
     let swapped1 = routing::add(dataflow_state, &mut new_nodes, &topo)?;
@@ -984,6 +1296,40 @@ fn plan_add_nodes(
 
         topo = topo_order(dataflow_state, &new_nodes);
 
+        // Now that every node has a local address, tell new readers which base tables they're
+        // derived from, so they know which tables a write batch has to reach before it can be
+        // made visible
+        for &ni in &new_nodes {
+            #[allow(clippy::indexing_slicing)] // Ingredients must contain NodeIndex
+            if !dataflow_state.ingredients[ni].is_reader() {
+                continue;
+            }
+            let mut base_tables = Vec::new();
+            let mut visited = HashSet::new();
+            let mut stack = vec![ni];
+            while let Some(n) = stack.pop() {
+                for parent in dataflow_state
+                    .ingredients
+                    .neighbors_directed(n, petgraph::EdgeDirection::Incoming)
+                {
+                    if parent == dataflow_state.source || !visited.insert(parent) {
+                        continue;
+                    }
+                    #[allow(clippy::indexing_slicing)] // Ingredients must contain NodeIndex
+                    if dataflow_state.ingredients[parent].is_base() {
+                        base_tables.push(dataflow_state.ingredients[parent].local_addr());
+                    } else {
+                        stack.push(parent);
+                    }
+                }
+            }
+            #[allow(clippy::indexing_slicing, clippy::unwrap_used)] // checked is_reader above
+            dataflow_state.ingredients[ni]
+                .as_mut_reader()
+                .unwrap()
+                .set_base_tables(base_tables);
+        }
+
         if let Some(shards) = dataflow_state.sharding {
             sharding::validate(&dataflow_state.ingredients, &topo, shards)?
         };
@@ -1040,7 +1386,7 @@ fn plan_add_nodes(
         // Boot up new domains (they'll ignore all updates for now)
         debug!("booting new domains");
         let mut dmp = DomainMigrationPlan::new(dataflow_state);
-        let mut scheduler = Scheduler::new(dataflow_state, worker)?;
+        let mut scheduler = Scheduler::new(dataflow_state, worker, placement_constraints.clone())?;
 
         for domain in changed_domains {
             if dataflow_state.domains.contains_key(&domain) {
@@ -1055,7 +1401,7 @@ fn plan_add_nodes(
 
             let num_shards = worker_shards.num_rows();
             let num_replicas = worker_shards.row_size();
-            dmp.place_domain(domain, worker_shards, nodes);
+            dmp.place_domain(domain, worker_shards, nodes, placement_constraints.clone());
             dmp.domains.insert(
                 domain,
                 DomainSettings {
@@ -1147,6 +1493,12 @@ fn plan_add_nodes(
             // Set up inter-domain connections
             debug!("bringing up inter-domain connections");
             routing::connect(&dataflow_state.ingredients, &mut dmp, &new_nodes)?;
+            routing::set_egress_predicates(
+                &dataflow_state.ingredients,
+                &dataflow_state.materializations,
+                &mut dmp,
+                &new_nodes,
+            )?;
 
             dataflow_state.materializations.commit(
                 &mut dataflow_state.ingredients,
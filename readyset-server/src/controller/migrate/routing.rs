@@ -5,18 +5,21 @@
 //!  - New nodes that are children of nodes in a different domain must be preceeded by an ingress
 //!  - Egress nodes must be added to nodes that now have children in a different domain
 //!  - Egress nodes that gain new children must gain channels to facilitate forwarding
+//!  - Egress nodes can drop records which an ingress' only child would filter out anyway
 
 use std::collections::{HashMap, HashSet};
 
 use dataflow::payload::SenderReplication;
 use dataflow::prelude::*;
-use dataflow::{node, DomainRequest};
+use dataflow::{node, DomainRequest, Expr as DfExpr};
 use petgraph::graph::NodeIndex;
+use readyset_client::internal::MaterializationStatus;
 use readyset_errors::{
     internal, internal_err, invariant, invariant_eq, ReadySetError, ReadySetResult,
 };
 use readyset_tracing::trace;
 
+use crate::controller::migrate::materialization::Materializations;
 use crate::controller::migrate::DomainMigrationPlan;
 use crate::controller::state::DfState;
 
@@ -388,3 +391,89 @@ pub(in crate::controller) fn connect(
     }
     Ok(())
 }
+
+/// Returns the predicate that records sent to `ingress` must satisfy to not be immediately
+/// discarded by its domain, if there is one: the expression of the ingress' filter child, if that
+/// filter is the ingress' only child.
+///
+/// Materialized ingress nodes never have a predicate, since their state has to hold every record in
+/// case a different child is added to them later.
+fn ingress_predicate(
+    graph: &Graph,
+    materializations: &Materializations,
+    ingress: NodeIndex,
+) -> Option<DfExpr> {
+    if !matches!(
+        materializations.get_status(ingress, &graph[ingress]),
+        MaterializationStatus::Not
+    ) {
+        return None;
+    }
+
+    let mut children = graph
+        .neighbors_directed(ingress, petgraph::EdgeDirection::Outgoing)
+        .filter(|&child| !graph[child].is_dropped());
+    match (children.next(), children.next()) {
+        (Some(child), None) => match graph[child].as_internal() {
+            Some(NodeOperator::Filter(filter)) => Some(filter.expression().clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Tell the egress nodes sending to every ingress node in `new` (or with a child in `new`) which
+/// records they can drop before sending them on to that ingress.
+///
+/// When many caches filter the same table by the same predicate, the egress node for that table
+/// would otherwise send every update to each of their domains just for all of them to evaluate the
+/// same predicate and discard the same records. Egress nodes deduplicate identical predicates, so
+/// the predicate is instead evaluated once per record, before sending (see
+/// [`PredicateIndex`](dataflow::node::special::PredicateIndex)).
+///
+/// Ingress nodes which had a predicate but have since gained a second child have their predicate
+/// removed, since that child may want the records the filter would discard.
+pub(in crate::controller) fn set_egress_predicates(
+    graph: &Graph,
+    materializations: &Materializations,
+    dmp: &mut DomainMigrationPlan,
+    new: &HashSet<NodeIndex>,
+) -> ReadySetResult<()> {
+    let mut ingresses = HashSet::new();
+    for &ni in new {
+        if graph[ni].is_ingress() {
+            ingresses.insert(ni);
+        }
+        ingresses.extend(
+            graph
+                .neighbors_directed(ni, petgraph::EdgeDirection::Incoming)
+                .filter(|&parent| graph[parent].is_ingress()),
+        );
+    }
+
+    for ingress in ingresses {
+        for sender in graph.neighbors_directed(ingress, petgraph::EdgeDirection::Incoming) {
+            let sender_node = &graph[sender];
+            if !sender_node.is_egress() {
+                continue;
+            }
+
+            let predicate = ingress_predicate(graph, materializations, ingress);
+            trace!(
+                egress = sender.index(),
+                ingress = ingress.index(),
+                has_predicate = predicate.is_some(),
+                "setting egress predicate"
+            );
+            dmp.add_message(
+                sender_node.domain(),
+                DomainRequest::SetEgressPredicate {
+                    egress_node: sender_node.local_addr(),
+                    target_node: ingress,
+                    predicate,
+                },
+            )?;
+        }
+    }
+    Ok(())
+}
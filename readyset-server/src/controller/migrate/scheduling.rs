@@ -6,6 +6,10 @@
 //!    a. The worker must be healhty, and
 //!    b. The worker can be [configured to only run reader nodes][reader_only], in which case only
 //!       domains that contain a reader node can run on that worker
+//!    c. If the domain contains reader nodes or base tables, the [resources][] the worker
+//!       advertised must meet the [placement constraints][] for that kind of domain - both those
+//!       configured for the whole deployment, and those given by the migration which created the
+//!       domain (which are kept with the domain, so they still apply if it's rescheduled)
 //! 2. Migrations can optionally [be restricted to a single worker][worker] - if so, all
 //!    replicas of all shards of all domains within the migration will be scheduled to that worker,
 //!    *if* it's valid
@@ -21,6 +25,8 @@
 //! [reader_only]: Worker::reader_only
 //! [worker]: Migration::worker
 //! [placement restrictions]: DomainPlacementRestriction
+//! [resources]: readyset_client::placement::WorkerResources
+//! [placement constraints]: PlacementConstraints
 
 use std::collections::{HashMap, HashSet};
 
@@ -28,6 +34,7 @@ use array2::Array2;
 use dataflow::prelude::*;
 use readyset_client::consensus::NodeTypeSchedulingRestriction;
 use readyset_client::internal::DomainIndex;
+use readyset_client::placement::PlacementConstraints;
 use readyset_tracing::trace;
 use tracing::instrument;
 
@@ -65,14 +72,19 @@ pub(crate) struct Scheduler<'state> {
     valid_workers: Vec<(&'state WorkerIdentifier, &'state Worker)>,
     worker_stats: HashMap<&'state WorkerIdentifier, WorkerStats>,
     scheduled_shards: HashMap<&'state WorkerIdentifier, HashSet<(DomainIndex, usize)>>,
+    placement_constraints: PlacementConstraints,
     dataflow_state: &'state DfState,
 }
 
 impl<'state> Scheduler<'state> {
-    /// Create a new [`Scheduler`], optionally restricted to the given `worker`.
+    /// Create a new [`Scheduler`], optionally restricted to the given `worker`, which only
+    /// schedules domains containing readers or base tables onto workers meeting the given
+    /// `placement_constraints`, in addition to the constraints configured for the whole deployment
+    /// and any constraints kept with the domain being scheduled.
     pub(crate) fn new(
         dataflow_state: &'state DfState,
         worker: &Option<WorkerIdentifier>,
+        placement_constraints: PlacementConstraints,
     ) -> ReadySetResult<Self> {
         let valid_workers = dataflow_state
             .workers
@@ -106,6 +118,7 @@ impl<'state> Scheduler<'state> {
             valid_workers,
             worker_stats,
             scheduled_shards,
+            placement_constraints,
             dataflow_state,
         })
     }
@@ -148,12 +161,28 @@ impl<'state> Scheduler<'state> {
             invariant_eq!(num_replicas, 1);
         }
 
+        let mut placement_constraints = self.dataflow_state.placement_constraints.clone();
+        placement_constraints.extend(self.placement_constraints.clone());
+        if let Some(domain_constraints) = self
+            .dataflow_state
+            .domain_placement_constraints
+            .get(&domain_index)
+        {
+            placement_constraints.extend(domain_constraints.clone());
+        }
+        let placement_constraints = &placement_constraints;
         let workers = self.valid_workers.iter().filter(|(_, worker)| {
-            match worker.domain_scheduling_config.reader_nodes {
+            let meets_node_type_restriction = match worker.domain_scheduling_config.reader_nodes {
                 NodeTypeSchedulingRestriction::None => true,
                 NodeTypeSchedulingRestriction::OnlyWithNodeType => is_reader_domain,
                 NodeTypeSchedulingRestriction::NeverWithNodeType => !is_reader_domain,
-            }
+            };
+            meets_node_type_restriction
+                && placement_constraints.allow(
+                    &worker.domain_scheduling_config.resources,
+                    is_reader_domain,
+                    is_base_table_domain,
+                )
         });
 
         let mut res = Vec::with_capacity(num_shards);
@@ -1,9 +1,9 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use dataflow::prelude::*;
-use dataflow::{node, ops};
+use dataflow::{node, ops, LookupIndex};
 use petgraph::graph::NodeIndex;
-use readyset_errors::{internal, invariant, invariant_eq, ReadySetResult};
+use readyset_errors::{internal, internal_err, invariant, invariant_eq, ReadySetResult};
 use readyset_tracing::{debug, error, trace};
 use tracing::info_span;
 
@@ -23,7 +23,9 @@ pub fn shard(
     'nodes: for &node in topo_list {
         let span = info_span!("sharding node", ?node);
         let _g = span.enter();
-        let mut input_shardings: HashMap<_, _> = graph
+        // Ordered by node index, so that any nodes added to reshard the inputs are added in the
+        // same order (and so get the same indices) every time the same migration is planned
+        let mut input_shardings: BTreeMap<_, _> = graph
             .neighbors_directed(node, petgraph::EdgeDirection::Incoming)
             .map(|ni| (ni, graph[ni].sharded_by()))
             .collect();
@@ -114,6 +116,18 @@ pub fn shard(
             continue;
         }
 
+        if let Some(s) = co_partitioned_sharding(
+            graph,
+            node,
+            &need_sharding,
+            &input_shardings,
+            sharding_factor,
+        ) {
+            debug!(sharding = ?s, "inputs are co-partitioned; sharding node without shuffling");
+            graph.node_weight_mut(node).unwrap().shard_by(s);
+            continue;
+        }
+
         if need_sharding.values().any(|idx| idx.len() != 1) {
             if !graph[node].is_base() {
                 // not supported yet -- force no sharding
@@ -135,6 +149,11 @@ pub fn shard(
             let want_sharding = want_sharding[0];
 
             if graph[node].columns()[want_sharding].name() == "bogokey" {
+                if split_aggregate(new, &mut swaps, graph, node)? {
+                    debug!("aggregating bogokey node per shard before de-sharding");
+                    continue;
+                }
+
                 debug!("de-sharding node that operates on bogokey");
                 for (ni, s) in input_shardings.iter_mut() {
                     reshard(new, &mut swaps, graph, *ni, node, Sharding::ForcedNone)?;
@@ -180,7 +199,7 @@ pub fn shard(
                     continue;
                 }
                 Some(want_sharding_input) => {
-                    let want_sharding_input: HashMap<_, _> =
+                    let want_sharding_input: BTreeMap<_, _> =
                         want_sharding_input.into_iter().collect();
 
                     // we can shard by the ouput column `want_sharding` *only* if we don't do
@@ -389,6 +408,7 @@ pub fn shard(
         .filter(|&&n| graph[n].is_sharder())
         .cloned()
         .collect();
+    new_sharders.sort();
     let mut gone = HashSet::new();
     while !new_sharders.is_empty() {
         'sharders: for n in new_sharders.split_off(0) {
@@ -586,11 +606,12 @@ pub fn shard(
     // will ensure that replays from the first sharding are turned into a single update before
     // arriving at the second sharding, and the merged sharder will ensure that nshards is set
     // correctly.
-    let sharded_sharders: Vec<_> = new
+    let mut sharded_sharders: Vec<_> = new
         .iter()
         .filter(|&&n| graph[n].is_sharder() && !graph[n].sharded_by().is_none())
         .cloned()
         .collect();
+    sharded_sharders.sort();
     for n in sharded_sharders {
         // sharding what?
         let p = {
@@ -625,6 +646,143 @@ pub fn shard(
     Ok((topo_list, swaps))
 }
 
+/// Returns the columns in the parents of `node` which column `col` of `node` is a copy of (or
+/// `None` for parents which the column is generated from).
+///
+/// This is the same as [`Node::parent_columns`], except that the key columns of a join resolve to
+/// the key column in *both* of its parents, since the output of a join whose parents are both
+/// sharded by the join key is sharded by the key.
+fn column_sources(node: &Node, col: usize) -> Vec<(NodeIndex, Option<usize>)> {
+    match node.as_internal() {
+        Some(NodeOperator::Join(join)) => join
+            .parent_columns(col)
+            .into_iter()
+            .map(|(ni, c)| (ni, Some(c)))
+            .collect(),
+        _ => node.parent_columns(col),
+    }
+}
+
+/// Check whether the inputs to `node` are *co-partitioned* with respect to the lookups `node` does:
+/// whether there is a column of `node` which resolves to the column that every input is already
+/// sharded by, and which is part of every index `node` looks up into (including its own). If so,
+/// all the rows any one lookup can find live on the same shard, so `node` can be sharded by that
+/// column without shuffling any of its inputs, even if it looks up by compound keys - for example
+/// a `GROUP BY tenant_id, x` over an input sharded by `tenant_id`, or a join on `(tenant_id, id)`
+/// of two inputs that are both sharded by `tenant_id`.
+///
+/// Returns the sharding for `node`, or `None` if its inputs aren't co-partitioned.
+fn co_partitioned_sharding(
+    graph: &Graph,
+    node: NodeIndex,
+    need_sharding: &HashMap<NodeIndex, LookupIndex>,
+    input_shardings: &BTreeMap<NodeIndex, Sharding>,
+    sharding_factor: usize,
+) -> Option<Sharding> {
+    let n = &graph[node];
+    if input_shardings.is_empty() || n.is_base() {
+        return None;
+    }
+
+    (0..n.columns().len())
+        .find(|&col| {
+            if let Some(index) = need_sharding.get(&node) {
+                if !index.columns().contains(&col) {
+                    return false;
+                }
+            }
+            let srcs = column_sources(n, col);
+            input_shardings.iter().all(|(ni, sharding)| match sharding {
+                Sharding::ByColumn(shard_col, shards) if *shards == sharding_factor => {
+                    srcs.contains(&(*ni, Some(*shard_col)))
+                        && need_sharding
+                            .get(ni)
+                            .map_or(true, |index| index.columns().contains(shard_col))
+                }
+                _ => false,
+            })
+        })
+        .map(|col| Sharding::ByColumn(col, sharding_factor))
+}
+
+/// If `node` is an aggregate over a single sharded input whose result can be computed by combining
+/// the results of aggregating each shard separately (see [`NodeOperator::combiner`]), modify the
+/// graph so that a copy of the aggregate runs on every shard of the input, and replace the operator
+/// in `node` with one that combines the de-sharded partial aggregates.
+///
+/// This avoids funneling every row of the input through a single, unsharded aggregate for
+/// aggregates (such as `COUNT(*)`) that don't group by anything the input is sharded by.
+///
+/// Returns `true` if the aggregate was split, and `false` if the graph was left unmodified.
+fn split_aggregate(
+    new: &mut HashSet<NodeIndex>,
+    swaps: &mut HashMap<(NodeIndex, NodeIndex), NodeIndex>,
+    graph: &mut Graph,
+    node: NodeIndex,
+) -> ReadySetResult<bool> {
+    let mut inputs = graph.neighbors_directed(node, petgraph::EdgeDirection::Incoming);
+    let src = match (inputs.next(), inputs.next()) {
+        (Some(src), None) => src,
+        _ => return Ok(false),
+    };
+    let src_sharding = graph[src].sharded_by();
+    if src_sharding.is_none() || graph[src].is_sharder() {
+        return Ok(false);
+    }
+    let Some(op) = graph[node]
+        .as_internal()
+        .filter(|op| op.combiner(node).is_some())
+        .cloned() else {
+        return Ok(false);
+    };
+
+    // the partial aggregate runs over the same input as the aggregate, so it's already connected
+    // to `src`
+    let mut partial = graph[node].mirror(op.partial());
+    if let Some(NodeOperator::Approximate(op)) = partial.as_internal() {
+        // partial approximate aggregates emit sketches, rather than values of the aggregate's type
+        let ty = op.output_col_type();
+        let col = partial.columns().len() - 1;
+        partial.set_column_type(col, ty)?;
+    }
+    let sharding = match src_sharding {
+        // the partial aggregate's output is only sharded by a column if one of its group columns
+        // is the column its input is sharded by
+        Sharding::ByColumn(c, shards) => (0..partial.columns().len())
+            .find(|&col| partial.parent_columns(col).contains(&(src, Some(c))))
+            .map_or(Sharding::Random(shards), |col| {
+                Sharding::ByColumn(col, shards)
+            }),
+        s => s,
+    };
+    debug!(
+        ?node,
+        ?sharding,
+        "splitting aggregate into per-shard partial aggregates"
+    );
+    partial.shard_by(sharding);
+    let partial = graph.add_node(partial);
+    new.insert(partial);
+
+    let mut combiner = graph[partial]
+        .as_internal()
+        .and_then(|op| op.combiner(partial))
+        .ok_or_else(|| internal_err!("aggregate has no combiner"))?;
+    combiner.on_connected(graph);
+    *graph[node]
+        .as_mut_internal()
+        .ok_or_else(|| internal_err!("aggregate is not an internal node"))? = combiner;
+
+    let old = graph.find_edge(src, node).unwrap();
+    graph.remove_edge(old).unwrap();
+    graph.add_edge(src, partial, ());
+    graph.add_edge(partial, node, ());
+
+    // and then de-shard the partial aggregates into the combiner
+    reshard(new, swaps, graph, partial, node, Sharding::ForcedNone)?;
+    Ok(true)
+}
+
 /// Modify the graph such that the path between `src` and `dst` shuffles the input such that the
 /// records received by `dst` are sharded by sharding `to`.
 fn reshard(
@@ -717,7 +875,7 @@ pub fn validate(
                 if let Sharding::ByColumn(c, shards) = ps {
                     // remap c according to node's semantics
                     let src = (0..nd.columns().len()).try_find(|&col| -> ReadySetResult<bool> {
-                        for pc in nd.parent_columns(col) {
+                        for pc in column_sources(nd, col) {
                             if let (p, Some(src)) = pc {
                                 // found column c in parent pni
                                 if p == pni && src == c {
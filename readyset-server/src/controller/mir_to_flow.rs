@@ -10,12 +10,13 @@ use std::collections::HashMap;
 use std::convert::TryInto;
 
 use common::DfValue;
+use dataflow::node::special::DuplicateKeyBehavior;
 use dataflow::node::Column as DfColumn;
 use dataflow::ops::grouped::concat::GroupConcat;
 use dataflow::ops::join::{Join, JoinType};
 use dataflow::ops::latest::Latest;
 use dataflow::ops::project::Project;
-use dataflow::{node, ops, Expr as DfExpr, PostLookupAggregates, ReaderProcessing};
+use dataflow::{node, ops, Expr as DfExpr, LazyJoin, PostLookupAggregates, ReaderProcessing};
 use itertools::Itertools;
 use mir::graph::MirGraph;
 use mir::node::node_inner::MirNodeInner;
@@ -44,12 +45,28 @@ fn set_names(names: &[&str], columns: &mut [DfColumn]) -> ReadySetResult<()> {
     Ok(())
 }
 
+/// Lower the given MIR query to dataflow, returning the dataflow node for its leaf.
+///
+/// If `lazy_joins` is true and the query has the shape described on [`plan_lazy_join`], its join is
+/// performed by its reader when it's read from rather than by a join node (see [`LazyJoin`]).
+/// Otherwise (including if the dataflow graph is sharded, since lazy joins can only be performed by
+/// unsharded readers), the query is lowered as normal.
 pub(super) fn mir_query_to_flow_parts(
     mir_query: &mut MirQuery<'_>,
     custom_types: &HashMap<Relation, DfType>,
+    lazy_joins: bool,
     mig: &mut Migration<'_>,
 ) -> ReadySetResult<DfNodeIndex> {
+    let lazy_join = if lazy_joins && mig.dataflow_state.sharding.is_none() {
+        plan_lazy_join(mir_query.graph, mir_query.leaf())
+    } else {
+        None
+    };
+
     for n in mir_query.topo_nodes() {
+        if lazy_join.as_ref().map_or(false, |plan| plan.lowers(n)) {
+            continue;
+        }
         mir_node_to_flow_parts(mir_query.graph, n, custom_types, mig).map_err(|e| {
             ReadySetError::MirNodeToDataflowFailed {
                 index: n.index(),
@@ -58,6 +75,15 @@ pub(super) fn mir_query_to_flow_parts(
         })?;
     }
 
+    if let Some(plan) = lazy_join {
+        lower_lazy_join(mir_query.graph, &plan, custom_types, mig).map_err(|e| {
+            ReadySetError::MirNodeToDataflowFailed {
+                index: plan.leaf.index(),
+                source: Box::new(e),
+            }
+        })?;
+    }
+
     let df_leaf = mir_query
         .dataflow_node()
         .ok_or_else(|| internal_err!("Leaf must have a dataflow node assigned by now"))?;
@@ -107,13 +133,16 @@ pub(super) fn mir_node_to_flow_parts(
                     ref column_specs,
                     ref primary_key,
                     ref unique_keys,
-                    ..
+                    ref check_constraints,
+                    duplicate_keys,
                 } => Some(make_base_node(
                     name,
                     column_specs.as_slice(),
                     custom_types,
                     primary_key.as_deref(),
                     unique_keys,
+                    check_constraints,
+                    duplicate_keys,
                     mig,
                 )?),
                 MirNodeInner::Extremum {
@@ -193,7 +222,7 @@ pub(super) fn mir_node_to_flow_parts(
                         mig,
                     )?)
                 }
-                MirNodeInner::DependentJoin { .. } => {
+                MirNodeInner::DependentJoin { .. } | MirNodeInner::DependentAntiJoin { .. } => {
                     // See the docstring for MirNodeInner::DependentJoin
                     internal!("Encountered dependent join when lowering to dataflow")
                 }
@@ -265,6 +294,26 @@ pub(super) fn mir_node_to_flow_parts(
                         mig,
                     )?)
                 }
+                MirNodeInner::AntiJoin {
+                    ref on,
+                    ref project,
+                } => {
+                    invariant_eq!(ancestors.len(), 2);
+                    let left = ancestors[0];
+                    let right = ancestors[1];
+                    Some(make_join_node(
+                        graph,
+                        name,
+                        left,
+                        right,
+                        &graph.columns(mir_node),
+                        on,
+                        project,
+                        JoinType::Anti,
+                        custom_types,
+                        mig,
+                    )?)
+                }
                 MirNodeInner::Project {
                     ref emit,
                     ref literals,
@@ -362,6 +411,250 @@ pub(super) fn mir_node_to_flow_parts(
     }
 }
 
+/// A join in a query which can be performed by the query's reader when it's read from, as a
+/// [`LazyJoin`], rather than by a join node. See [`plan_lazy_join`].
+struct LazyJoinPlan {
+    /// The join node, which is lowered to an identity node over `dimension` (so that the dimension
+    /// reader is removed along with the query)
+    join: MirNodeIndex,
+    /// The left (fact) parent of the join
+    fact: MirNodeIndex,
+    /// The right (dimension) parent of the join, which is always a whole table
+    dimension: MirNodeIndex,
+    /// The project node below the join, which is lowered to a projection of only the columns it
+    /// takes from `fact`
+    project: MirNodeIndex,
+    /// The leaf of the query, which is lowered to a reader on `project`
+    leaf: MirNodeIndex,
+    /// The number of (leading) columns of `project` which come from `fact`. All the columns after
+    /// these come from `dimension`.
+    fact_columns: usize,
+    /// Columns of `project` which make up the fact side of the join key
+    on: Vec<usize>,
+    /// Columns of `dimension` which make up the dimension side of the join key
+    dimension_on: Vec<usize>,
+    /// Columns of `dimension` projected by `project`, in order
+    emit: Vec<usize>,
+    kind: JoinType,
+}
+
+impl LazyJoinPlan {
+    /// Returns true if the given node is lowered by [`lower_lazy_join`] rather than by
+    /// [`mir_node_to_flow_parts`]
+    fn lowers(&self, node: MirNodeIndex) -> bool {
+        node == self.join || node == self.project || node == self.leaf
+    }
+}
+
+/// Returns a plan for performing the join in the query with the given leaf lazily, if the query
+/// has the shape:
+///
+/// ```text
+/// Leaf <- Project <- Join/LeftJoin <- (fact, dimension table)
+/// ```
+///
+/// where the project only emits columns (all the ones from the fact side first, including the
+/// fact side of the join key), and the leaf has no post-lookup ordering or aggregation and is only
+/// keyed on fact-side columns.
+fn plan_lazy_join(graph: &MirGraph, leaf: MirNodeIndex) -> Option<LazyJoinPlan> {
+    use petgraph::visit::EdgeRef;
+
+    let only_parent = |n| {
+        graph
+            .neighbors_directed(n, Direction::Incoming)
+            .exactly_one()
+            .ok()
+    };
+    let only_child = |n| {
+        graph
+            .neighbors_directed(n, Direction::Outgoing)
+            .exactly_one()
+            .ok()
+    };
+
+    let MirNodeInner::Leaf {
+        ref keys,
+        lowered_to_df: false,
+        order_by: None,
+        aggregates: None,
+        ..
+    } = graph[leaf].inner
+    else {
+        return None;
+    };
+
+    let project = only_parent(leaf)?;
+    let MirNodeInner::Project {
+        ref emit,
+        ref expressions,
+        ref literals,
+    } = graph[project].inner
+    else {
+        return None;
+    };
+    if !expressions.is_empty()
+        || !literals.is_empty()
+        || only_child(project) != Some(leaf)
+        || graph[project].df_node_index().is_some()
+    {
+        return None;
+    }
+
+    let join = only_parent(project)?;
+    let (on, kind) = match graph[join].inner {
+        MirNodeInner::Join { ref on, .. } => (on, JoinType::Inner),
+        MirNodeInner::LeftJoin { ref on, .. } => (on, JoinType::Left),
+        _ => return None,
+    };
+    if on.is_empty() || only_child(join) != Some(project) || graph[join].df_node_index().is_some() {
+        return None;
+    }
+    let (fact, dimension) = graph
+        .edges_directed(join, Direction::Incoming)
+        .sorted_by_key(|e| e.weight())
+        .map(|e| e.source())
+        .collect_tuple()?;
+
+    // The dimension reader holds every row of the dimension side, so only join lazily against
+    // whole tables
+    let is_table = |n: MirNodeIndex| matches!(graph[n].inner, MirNodeInner::Base { .. });
+    let dimension_is_table = match graph[dimension].inner {
+        MirNodeInner::AliasTable { .. } => only_parent(dimension).map_or(false, is_table),
+        _ => is_table(dimension),
+    };
+    if !dimension_is_table {
+        return None;
+    }
+
+    let mut fact_sources = vec![];
+    let mut emit_dimension = vec![];
+    for col in emit {
+        match graph.find_source_for_child_column(fact, col) {
+            Some(source) if emit_dimension.is_empty() => fact_sources.push(source),
+            Some(_) => return None,
+            None => emit_dimension.push(graph.find_source_for_child_column(dimension, col)?),
+        }
+    }
+    let fact_columns = fact_sources.len();
+
+    let key_is_fact_side = keys.iter().all(|(col, _)| {
+        graph
+            .column_id_for_column(project, col)
+            .map_or(false, |idx| idx < fact_columns)
+    });
+    if !key_is_fact_side {
+        return None;
+    }
+
+    let mut fact_on = Vec::with_capacity(on.len());
+    let mut dimension_on = Vec::with_capacity(on.len());
+    for (left, right) in on {
+        let source = graph.find_source_for_child_column(fact, left)?;
+        fact_on.push(fact_sources.iter().position(|&s| s == source)?);
+        dimension_on.push(graph.column_id_for_column(dimension, right).ok()?);
+    }
+
+    Some(LazyJoinPlan {
+        join,
+        fact,
+        dimension,
+        project,
+        leaf,
+        fact_columns,
+        on: fact_on,
+        dimension_on,
+        emit: emit_dimension,
+        kind,
+    })
+}
+
+/// Lower the nodes of a query that are replaced by the given [`LazyJoinPlan`]: a projection of the
+/// fact-side columns, a fully materialized dimension reader over an identity of the dimension
+/// table, and a reader on the projection which joins against the dimension reader when it's read
+/// from
+fn lower_lazy_join(
+    graph: &mut MirGraph,
+    plan: &LazyJoinPlan,
+    custom_types: &HashMap<Relation, DfType>,
+    mig: &mut Migration<'_>,
+) -> ReadySetResult<()> {
+    let MirNodeInner::Project { ref emit, .. } = graph[plan.project].inner else {
+        internal!("Lazy join planned without a project node")
+    };
+    let fact_projection = make_project_node(
+        graph,
+        graph[plan.project].name().clone(),
+        plan.fact,
+        &graph.columns(plan.project)[..plan.fact_columns],
+        &emit[..plan.fact_columns],
+        &[],
+        &[],
+        custom_types,
+        mig,
+    )?;
+    graph[plan.project].assign_df_node_index(fact_projection)?;
+
+    let dimension = make_identity_node(
+        graph,
+        graph[plan.join].name().clone(),
+        plan.dimension,
+        &graph.columns(plan.dimension),
+        mig,
+    )?;
+    graph[plan.join].assign_df_node_index(dimension)?;
+    let dimension =
+        mig.maintain_lazy_join_dimension(dimension.address(), plan.dimension_on.clone());
+
+    let name = graph[plan.leaf].name().clone();
+    let MirNodeInner::Leaf {
+        ref keys,
+        index_type,
+        limit,
+        ref returned_cols,
+        ref default_row,
+        ..
+    } = graph[plan.leaf].inner
+    else {
+        internal!("Lazy join planned without a leaf node")
+    };
+    let reader_processing = make_reader_processing(
+        graph,
+        &plan.project,
+        &None,
+        limit,
+        returned_cols,
+        default_row.clone(),
+        &None,
+    )?;
+    let reader = materialize_leaf_node(
+        graph,
+        plan.project,
+        name,
+        keys,
+        index_type,
+        reader_processing,
+        mig,
+    )?;
+    mig.dataflow_state.ingredients[reader]
+        .as_mut_reader()
+        .ok_or_else(|| internal_err!("Leaf must be lowered to a reader"))?
+        .set_lazy_joins(vec![LazyJoin {
+            dimension,
+            on: plan.on.clone(),
+            emit: plan.emit.clone(),
+            kind: plan.kind.clone(),
+        }]);
+
+    if let MirNodeInner::Leaf {
+        ref mut lowered_to_df,
+        ..
+    } = graph[plan.leaf].inner
+    {
+        *lowered_to_df = true;
+    }
+    Ok(())
+}
+
 fn column_names(cs: &[Column]) -> Vec<&str> {
     cs.iter().map(|c| c.name.as_str()).collect()
 }
@@ -372,9 +665,11 @@ fn make_base_node(
     custom_types: &HashMap<Relation, DfType>,
     primary_key: Option<&[Column]>,
     unique_keys: &[Box<[Column]>],
+    check_constraints: &[(SqlIdentifier, Expr)],
+    duplicate_keys: DuplicateKeyBehavior,
     mig: &mut Migration<'_>,
 ) -> ReadySetResult<DfNodeIndex> {
-    let columns = column_specs
+    let mut columns = column_specs
         .iter()
         .map(|cs| DfColumn::from_spec(cs.clone(), mig.dialect, |ty| custom_types.get(&ty).cloned()))
         .collect::<Result<Vec<_>, _>>()?;
@@ -408,14 +703,53 @@ fn make_base_node(
 
     let primary_key = primary_key.map(cols_from_spec).transpose()?;
 
+    // Primary key columns can never be NULL, whether the key was declared on the column itself or
+    // separately (as `PRIMARY KEY (...)`)
+    for &col in primary_key.iter().flatten() {
+        if let Some(col) = columns.get_mut(col) {
+            col.set_non_null(true);
+        }
+    }
+
     let unique_keys = unique_keys
         .iter()
         .map(|u| cols_from_spec(u))
         .collect::<ReadySetResult<Vec<_>>>()?;
 
+    let column_types = columns
+        .iter()
+        .map(|col| col.ty().clone())
+        .collect::<Vec<_>>();
+    let non_null_columns = columns
+        .iter()
+        .map(|col| col.is_non_null())
+        .collect::<Vec<_>>();
+    let check_constraints = check_constraints
+        .iter()
+        .map(|(name, expr)| {
+            let mut expr = DfExpr::lower(
+                expr.clone(),
+                mig.dialect,
+                BaseLowerContext {
+                    column_specs,
+                    columns: &columns,
+                    custom_types,
+                },
+            )?;
+            expr.typecheck(&column_types, mig.dialect)?;
+            expr.analyze_nullability(&non_null_columns);
+            Ok(node::special::CheckConstraint {
+                name: name.clone(),
+                expr,
+            })
+        })
+        .collect::<ReadySetResult<Vec<_>>>()?;
+
     let base = node::special::Base::new()
         .with_default_values(default_values)
-        .with_unique_keys(unique_keys);
+        .with_unique_keys(unique_keys)
+        .with_check_constraints(check_constraints)
+        .with_duplicate_key_behavior(duplicate_keys);
 
     let base = if let Some(pk) = primary_key {
         base.with_primary_key(pk)
@@ -477,6 +811,10 @@ fn make_union_node(
         emit_column_id.insert(ni.address(), emit_cols);
     }
     set_names(&column_names(columns), &mut cols)?;
+    // The columns of the other ancestors may contain NULLs even if those of the first don't
+    for col in &mut cols {
+        col.set_non_null(false);
+    }
 
     let node = mig.add_ingredient(
         name,
@@ -584,6 +922,18 @@ fn make_grouped_node(
             set_names(&column_names(columns), &mut cols)?;
             mig.add_ingredient(name, cols, gc)
         }
+        // Likewise, approximate aggregates are implemented by a separate dataflow operator
+        GroupedNodeType::Aggregation(Aggregation::Approximate(approx)) => {
+            let grouped = approx.over(
+                parent_na.address(),
+                over_col_indx,
+                group_col_indx.as_slice(),
+            );
+            let agg_col = make_agg_col(grouped.output_col_type());
+            cols.push(agg_col);
+            set_names(&column_names(columns), &mut cols)?;
+            mig.add_ingredient(name, cols, grouped)
+        }
         GroupedNodeType::Aggregation(agg) => {
             let grouped = agg.over(
                 parent_na.address(),
@@ -698,15 +1048,20 @@ fn make_join_node(
                     .cloned()
                     .ok_or_else(|| internal_err!("Invalid index"))?,
             );
+        } else if kind == JoinType::Anti {
+            internal!("Anti-joins can't project column {c} from their right parent")
         } else if let Ok(r) = graph.column_id_for_column(right, c) {
             // Column isn't a join key, and comes from the right
             emit.push(JoinSource::R(r));
-            cols.push(
-                right_cols
-                    .get(r)
-                    .cloned()
-                    .ok_or_else(|| internal_err!("Invalid index"))?,
-            );
+            let mut col = right_cols
+                .get(r)
+                .cloned()
+                .ok_or_else(|| internal_err!("Invalid index"))?;
+            // Columns from the right side of a left join are NULL for unmatched rows
+            if kind == JoinType::Left {
+                col.set_non_null(false);
+            }
+            cols.push(col);
         } else {
             internal!("Column {c} not found in either parent")
         }
@@ -912,8 +1267,40 @@ impl<'a> dataflow::LowerContext for LowerContext<'a> {
     }
 }
 
+/// Context for lowering expressions that reference the columns of a base table, such as its `CHECK`
+/// constraints
+#[derive(Clone)]
+struct BaseLowerContext<'a> {
+    column_specs: &'a [ColumnSpecification],
+    columns: &'a [DfColumn],
+    custom_types: &'a HashMap<Relation, DfType>,
+}
+
+impl<'a> dataflow::LowerContext for BaseLowerContext<'a> {
+    fn resolve_column(&self, col: nom_sql::Column) -> ReadySetResult<(usize, DfType)> {
+        let index = self
+            .column_specs
+            .iter()
+            .position(|cs| cs.column.name == col.name)
+            .ok_or_else(|| ReadySetError::NoSuchColumn(col.name.to_string()))?;
+        let ty = self
+            .columns
+            .get(index)
+            .ok_or_else(|| internal_err!("Index exceeds length of base cols, idx={}", index))?
+            .ty()
+            .clone();
+        Ok((index, ty))
+    }
+
+    fn resolve_type(&self, ty: Relation) -> Option<DfType> {
+        self.custom_types.get(&ty).cloned()
+    }
+}
+
 /// Lower the given nom_sql AST expression to a `DfExpr`, resolving columns by looking their
-/// index up in the given parent node.
+/// index up in the given parent node, [type check](DfExpr::typecheck) it against the types of the
+/// columns in the parent node, and [analyze](DfExpr::analyze_nullability) which of its operands are
+/// never NULL.
 fn lower_expression(
     graph: &MirGraph,
     parent: MirNodeIndex,
@@ -922,7 +1309,7 @@ fn lower_expression(
     custom_types: &HashMap<Relation, DfType>,
     dialect: Dialect,
 ) -> ReadySetResult<DfExpr> {
-    DfExpr::lower(
+    let mut expr = DfExpr::lower(
         expr,
         dialect,
         LowerContext {
@@ -931,7 +1318,18 @@ fn lower_expression(
             parent_cols,
             custom_types,
         },
-    )
+    )?;
+    let parent_types = parent_cols
+        .iter()
+        .map(|col| col.ty().clone())
+        .collect::<Vec<_>>();
+    expr.typecheck(&parent_types, dialect)?;
+    let non_null_columns = parent_cols
+        .iter()
+        .map(|col| col.is_non_null())
+        .collect::<Vec<_>>();
+    expr.analyze_nullability(&non_null_columns);
+    Ok(expr)
 }
 
 fn make_project_node(
@@ -1239,7 +1637,7 @@ fn materialize_leaf_node(
     index_type: IndexType,
     reader_processing: ReaderProcessing,
     mig: &mut Migration<'_>,
-) -> ReadySetResult<()> {
+) -> ReadySetResult<NodeIndex> {
     let na = graph.resolve_dataflow_node(parent).ok_or_else(|| {
         ReadySetError::MirNodeMustHaveDfNodeAssigned {
             mir_node_index: parent.index(),
@@ -1253,7 +1651,7 @@ fn materialize_leaf_node(
 
     // TODO(malte): consider the case when the projected columns need reordering
 
-    if !key_cols.is_empty() {
+    let reader = if !key_cols.is_empty() {
         let columns: Vec<_> = key_cols
             .iter()
             .map(|(c, _)| graph.column_id_for_column(parent, c))
@@ -1271,7 +1669,7 @@ fn materialize_leaf_node(
             &Index::new(index_type, columns),
             reader_processing,
             placeholder_map,
-        );
+        )
     } else {
         // if no key specified, default to the first column
         mig.maintain(
@@ -1280,7 +1678,7 @@ fn materialize_leaf_node(
             &Index::new(index_type, vec![0]),
             reader_processing,
             Vec::default(),
-        );
-    }
-    Ok(())
+        )
+    };
+    Ok(reader)
 }
@@ -11,9 +11,10 @@ use failpoint_macros::set_failpoint;
 use futures::future::join_all;
 use futures::stream::FuturesUnordered;
 use futures_util::StreamExt;
-use hyper::http::{Method, StatusCode};
+use hyper::http::{HeaderMap, Method, StatusCode};
 use metrics::{counter, gauge, histogram};
 use nom_sql::Relation;
+use readyset_client::channel::ChannelSecurity;
 use readyset_client::consensus::{
     Authority, AuthorityControl, AuthorityWorkerHeartbeatResponse, GetLeaderResult,
     WorkerDescriptor, WorkerId, WorkerSchedulingConfig,
@@ -43,15 +44,20 @@ use crate::materialization::Materializations;
 use crate::worker::{WorkerRequest, WorkerRequestKind};
 use crate::{Config, ReadySetResult, VolumeId};
 
+mod canonical_plan;
+mod config_reload;
 mod domain_handle;
+mod domain_restarts;
 mod inner;
 mod keys;
 pub(crate) mod migrate; // crate viz for tests
 mod mir_to_flow;
+mod query_ids;
 pub(crate) mod replication;
 pub(crate) mod schema;
 pub(crate) mod sql;
 mod state;
+mod state_checksums;
 
 /// Time between leader state change checks without thread parking.
 const LEADER_STATE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
@@ -106,6 +112,8 @@ pub struct Worker {
     /// Configuration for how domains should be scheduled onto this worker
     domain_scheduling_config: WorkerSchedulingConfig,
     request_timeout: Duration,
+    /// Used to sign requests to the worker, if configured
+    security: Option<ChannelSecurity>,
 }
 
 impl Worker {
@@ -113,6 +121,7 @@ impl Worker {
         instance_uri: Url,
         domain_scheduling_config: WorkerSchedulingConfig,
         request_timeout: Duration,
+        security: Option<ChannelSecurity>,
     ) -> Self {
         Worker {
             healthy: true,
@@ -120,11 +129,17 @@ impl Worker {
             http: reqwest::Client::new(),
             domain_scheduling_config,
             request_timeout,
+            security,
         }
     }
     pub async fn rpc<T: DeserializeOwned>(&self, req: WorkerRequestKind) -> ReadySetResult<T> {
-        let body = hyper::Body::from(bincode::serialize(&req)?);
-        let req = self.http.post(self.uri.join("worker_request")?).body(body);
+        let body = bincode::serialize(&req)?;
+        let url = self.uri.join("worker_request")?;
+        let mut headers = HeaderMap::new();
+        if let Some(security) = &self.security {
+            security.sign_request(&Method::POST, url.path(), &body, &mut headers);
+        }
+        let req = self.http.post(url).headers(headers).body(body);
         let resp = req
             .timeout(self.request_timeout)
             .send()
@@ -442,6 +457,8 @@ impl Controller {
                     self.our_descriptor.controller_uri.clone(),
                     self.authority.clone(),
                     self.config.replicator_config.clone(),
+                    self.config.replication_transform.clone(),
+                    self.config.channel_security.clone(),
                     self.config.worker_request_timeout,
                 );
                 self.leader_ready.store(false, Ordering::Release);
@@ -745,7 +762,7 @@ impl AuthorityLeaderElectionState {
                                     self.permissive_writes,
                                 );
 
-                                let dataflow_state = DfState::new(
+                                let mut dataflow_state = DfState::new(
                                     g,
                                     source,
                                     0,
@@ -760,6 +777,8 @@ impl AuthorityLeaderElectionState {
                                     self.config.keep_prior_recipes,
                                     self.config.replication_strategy,
                                 );
+                                dataflow_state.namespace_quotas = self.config.namespace_quotas.clone();
+                                dataflow_state.placement_constraints = self.config.placement_constraints.clone();
                                 Ok(ControllerState {
                                     config: self.config.clone(),
                                     dataflow_state,
@@ -777,6 +796,8 @@ impl AuthorityLeaderElectionState {
                                 }
                                 state.dataflow_state.domain_config = self.config.domain_config.clone();
                                 state.dataflow_state.replication_strategy = self.config.replication_strategy;
+                                state.dataflow_state.namespace_quotas = self.config.namespace_quotas.clone();
+                                state.dataflow_state.placement_constraints = self.config.placement_constraints.clone();
                                 state.config = self.config.clone();
                                 Ok(state)
                             }
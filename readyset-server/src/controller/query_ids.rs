@@ -0,0 +1,685 @@
+//! Persistent mapping from [`QueryId`]s to the queries they identify.
+//!
+//! Adapters register the queries they see with the controller, so that ids which one adapter (or
+//! a previous run of the same adapter) handed out can still be resolved, eg by a `CREATE CACHE
+//! FROM q_...` statement run after a restart. Each query is stored along with the
+//! [`QUERY_ID_VERSION`] its id was computed with, so that when an upgrade changes how ids are
+//! computed, the ids handed out by the previous version keep resolving to the same query.
+//!
+//! The registry also records which queries the planner has found to be unsupported, so that we can
+//! avoid planning them again (potentially from a different adapter, or after a restart) until the
+//! [`PLANNER_VERSION`] or the schema changes.
+//!
+//! The registry holds at most [`MAX_REGISTERED_QUERIES`] queries. Once it's full, registering a new
+//! query evicts the least recently registered one, along with any aliases for its id.
+//!
+//! The registry is persisted in the authority separately from the rest of the controller state,
+//! split by id into [`PERSISTED_SHARDS`] keys, so that a change to the registry only rewrites the
+//! shards it touched.
+
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::mem;
+
+use nom_sql::CacheInner;
+use readyset_client::consensus::{Authority, AuthorityControl};
+use readyset_client::query::{QueryId, QUERY_ID_VERSION};
+use readyset_client::recipe::changelist::{Change, ChangeList};
+use readyset_client::ViewCreateRequest;
+use readyset_errors::{internal_err, ReadySetError, ReadySetResult, UnsupportedFeature};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, RwLock, RwLockReadGuard};
+
+use crate::controller::sql::PLANNER_VERSION;
+
+/// The maximum number of queries kept in [`QueryIds`]
+const MAX_REGISTERED_QUERIES: usize = 10_000;
+
+/// The number of keys in the authority that [`QueryIds`] is persisted across
+const PERSISTED_SHARDS: usize = 64;
+
+/// Returns the shard that the query with the given (current) id is persisted in
+fn shard_of(id: QueryId) -> usize {
+    (u64::from(id) % PERSISTED_SHARDS as u64) as usize
+}
+
+/// Returns the path in the authority that the given shard is persisted at
+fn shard_path(authority: &Authority, shard: usize) -> String {
+    match authority {
+        // Consul paths are relative to the deployment
+        Authority::ConsulAuthority(_) => format!("query_ids_{shard}"),
+        _ => format!("/query_ids_{shard}"),
+    }
+}
+
+/// The contents of one persisted shard of [`QueryIds`]: the queries whose ids fall in the shard,
+/// and the aliases for those ids
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+struct PersistedShard {
+    queries: Vec<(QueryId, RegisteredQuery)>,
+    aliases: Vec<(QueryId, QueryId)>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct RegisteredQuery {
+    /// The [`QUERY_ID_VERSION`] that the id of this query was computed with
+    version: u32,
+    request: ViewCreateRequest,
+    /// Set if the planner has found this query to be unsupported
+    #[serde(default)]
+    unsupported: Option<UnsupportedVerdict>,
+    /// The value of [`QueryIds::clock`] when this query was last registered, used to pick the
+    /// least recently registered query to evict
+    #[serde(default)]
+    last_used: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct UnsupportedVerdict {
+    /// The [`PLANNER_VERSION`] that found the query to be unsupported. Verdicts from other planner
+    /// versions are ignored.
+    planner_version: u32,
+    /// The reason the query is unsupported
+    reason: String,
+    /// The SQL feature that made the query unsupported, if known
+    #[serde(default)]
+    feature: Option<UnsupportedFeature>,
+}
+
+impl UnsupportedVerdict {
+    /// Build the error returned when the query is migrated again
+    fn error(&self) -> ReadySetError {
+        match self.feature {
+            Some(feature) => ReadySetError::UnsupportedFeature {
+                feature,
+                message: self.reason.clone(),
+            },
+            None => ReadySetError::Unsupported(self.reason.clone()),
+        }
+    }
+}
+
+impl RegisteredQuery {
+    fn unsupported_verdict(&self) -> Option<&UnsupportedVerdict> {
+        self.unsupported
+            .as_ref()
+            .filter(|verdict| verdict.planner_version == PLANNER_VERSION)
+    }
+}
+
+/// If the given changelist consists of a single `CREATE CACHE` statement (as sent by adapters when
+/// migrating a query), returns the query being cached
+pub(super) fn cached_query(changes: &ChangeList) -> Option<ViewCreateRequest> {
+    match changes.changes.as_slice() {
+        [Change::CreateCache(create_cache)] => match &create_cache.inner {
+            CacheInner::Statement(statement) => Some(ViewCreateRequest::new(
+                (**statement).clone(),
+                changes.schema_search_path.clone(),
+            )),
+            CacheInner::Id(_) => None,
+        },
+        _ => None,
+    }
+}
+
+/// Returns `true` if the given changelist changes the schema, rather than just creating caches
+pub(super) fn changes_schema(changes: &ChangeList) -> bool {
+    changes
+        .changes
+        .iter()
+        .any(|change| !matches!(change, Change::CreateCache(_)))
+}
+
+/// Registry of all the queries adapters have assigned ids to.
+#[derive(Clone, Debug, Default)]
+pub(super) struct QueryIds {
+    /// Registered queries, keyed by their current id
+    queries: HashMap<QueryId, RegisteredQuery>,
+    /// Ids computed for registered queries by other versions of the query fingerprint (including
+    /// [`QueryId::legacy`]), mapped to the current id of the same query
+    aliases: HashMap<QueryId, QueryId>,
+    /// Incremented every time a query is registered
+    clock: u64,
+    /// The shards which have changed since they were last persisted
+    dirty: HashSet<usize>,
+}
+
+impl QueryIds {
+    /// Returns `true` if the given query has already been registered
+    pub(super) fn contains(&self, request: &ViewCreateRequest) -> bool {
+        self.queries.contains_key(&QueryId::from(request))
+    }
+
+    /// Register the given query, returning `true` if it was not already registered. If the
+    /// registry is full, the least recently registered query is evicted to make room for it.
+    ///
+    /// Registering a query again only updates when it was last used in memory, rather than in the
+    /// persisted registry, so that adapters re-registering the queries they see doesn't cause
+    /// writes to the authority.
+    pub(super) fn register(&mut self, request: ViewCreateRequest) -> bool {
+        self.clock += 1;
+        let id = QueryId::from(&request);
+        if let Some(query) = self.queries.get_mut(&id) {
+            query.last_used = self.clock;
+            return false;
+        }
+
+        if self.queries.len() >= MAX_REGISTERED_QUERIES {
+            self.evict_least_recently_used();
+        }
+
+        let legacy_id = QueryId::legacy(&request);
+        if legacy_id != id {
+            self.aliases.entry(legacy_id).or_insert(id);
+        }
+        self.queries.insert(
+            id,
+            RegisteredQuery {
+                version: QUERY_ID_VERSION,
+                request,
+                unsupported: None,
+                last_used: self.clock,
+            },
+        );
+        self.dirty.insert(shard_of(id));
+        true
+    }
+
+    /// Remove the least recently registered query, along with any aliases for its id
+    fn evict_least_recently_used(&mut self) {
+        let Some(id) = self
+            .queries
+            .iter()
+            .min_by_key(|(_, query)| query.last_used)
+            .map(|(id, _)| *id)
+        else {
+            return;
+        };
+        self.queries.remove(&id);
+        self.aliases.retain(|_, target| *target != id);
+        self.dirty.insert(shard_of(id));
+    }
+
+    /// Record that the current version of the planner has found the given query to be
+    /// unsupported, for the given reason and (if known) because of the given feature, registering
+    /// the query if necessary
+    pub(super) fn mark_unsupported(
+        &mut self,
+        request: ViewCreateRequest,
+        reason: String,
+        feature: Option<UnsupportedFeature>,
+    ) {
+        let id = QueryId::from(&request);
+        self.register(request);
+        if let Some(query) = self.queries.get_mut(&id) {
+            query.unsupported = Some(UnsupportedVerdict {
+                planner_version: PLANNER_VERSION,
+                reason,
+                feature,
+            });
+            self.dirty.insert(shard_of(id));
+        }
+    }
+
+    /// If the current version of the planner has found the given query to be unsupported, returns
+    /// the error to return when migrating it
+    pub(super) fn unsupported_error(&self, request: &ViewCreateRequest) -> Option<ReadySetError> {
+        self.queries
+            .get(&QueryId::from(request))
+            .and_then(RegisteredQuery::unsupported_verdict)
+            .map(UnsupportedVerdict::error)
+    }
+
+    /// Returns the id, query, reason, and the unsupported feature (if known) for all the queries
+    /// which the current version of the planner has found to be unsupported
+    pub(super) fn unsupported_queries(
+        &self,
+    ) -> impl Iterator<
+        Item = (
+            QueryId,
+            &ViewCreateRequest,
+            &str,
+            Option<UnsupportedFeature>,
+        ),
+    > {
+        self.queries.iter().filter_map(|(id, query)| {
+            query.unsupported_verdict().map(|verdict| {
+                (
+                    *id,
+                    &query.request,
+                    verdict.reason.as_str(),
+                    verdict.feature,
+                )
+            })
+        })
+    }
+
+    /// Recompute the ids of all queries which were registered with a different
+    /// [`QUERY_ID_VERSION`] than the current one, keeping their previous ids around as aliases.
+    ///
+    /// Returns `true` if any queries were updated.
+    pub(super) fn update_version(&mut self) -> bool {
+        let (outdated, current): (HashMap<_, _>, HashMap<_, _>) = mem::take(&mut self.queries)
+            .into_iter()
+            .partition(|(_, query)| query.version != QUERY_ID_VERSION);
+        self.queries = current;
+
+        let changed = !outdated.is_empty();
+        for (old_id, mut query) in outdated {
+            let new_id = QueryId::from(&query.request);
+            query.version = QUERY_ID_VERSION;
+            self.dirty.insert(shard_of(old_id));
+            self.dirty.insert(shard_of(new_id));
+            if new_id != old_id {
+                for target in self.aliases.values_mut() {
+                    if *target == old_id {
+                        *target = new_id;
+                    }
+                }
+                self.aliases.insert(old_id, new_id);
+            }
+            self.queries.insert(new_id, query);
+        }
+        changed
+    }
+
+    /// Look up the query with the given id, which can be either its current id or an id computed
+    /// for it by a previous version of the query fingerprint.
+    ///
+    /// Returns the current id of the query along with the query itself
+    pub(super) fn get(&self, id: QueryId) -> Option<(QueryId, &ViewCreateRequest)> {
+        let id = if self.queries.contains_key(&id) {
+            id
+        } else {
+            *self.aliases.get(&id)?
+        };
+        self.queries.get(&id).map(|query| (id, &query.request))
+    }
+
+    /// Forget every unsupported verdict, since a change to the schema may change whether the
+    /// queries are supported
+    pub(super) fn clear_unsupported(&mut self) {
+        for (id, query) in &mut self.queries {
+            if query.unsupported.take().is_some() {
+                self.dirty.insert(shard_of(*id));
+            }
+        }
+    }
+
+    /// Add the contents of a persisted shard to the registry
+    fn load_shard(&mut self, shard: PersistedShard) {
+        for (id, query) in shard.queries {
+            self.clock = self.clock.max(query.last_used);
+            self.queries.insert(id, query);
+        }
+        self.aliases.extend(shard.aliases);
+    }
+
+    /// Returns the current contents of every shard which has changed since the last call, to be
+    /// persisted
+    fn take_dirty_shards(&mut self) -> Vec<(usize, PersistedShard)> {
+        if self.dirty.is_empty() {
+            return vec![];
+        }
+        let mut shards: HashMap<usize, PersistedShard> = mem::take(&mut self.dirty)
+            .into_iter()
+            .map(|shard| (shard, Default::default()))
+            .collect();
+        for (id, query) in &self.queries {
+            if let Some(shard) = shards.get_mut(&shard_of(*id)) {
+                shard.queries.push((*id, query.clone()));
+            }
+        }
+        for (alias, target) in &self.aliases {
+            if let Some(shard) = shards.get_mut(&shard_of(*target)) {
+                shard.aliases.push((*alias, *target));
+            }
+        }
+        shards.into_iter().collect()
+    }
+}
+
+/// Shared handle to the controller's [`QueryIds`], which persists changes to the registry in the
+/// authority
+#[derive(Default)]
+pub(super) struct QueryIdsHandle {
+    ids: RwLock<QueryIds>,
+    /// Held while changes are being persisted, so that shards are written in the order they
+    /// changed
+    persisting: Mutex<()>,
+}
+
+impl QueryIdsHandle {
+    /// Replace the registry with the one persisted in the given authority, recomputing the ids of
+    /// any queries registered by a previous version of ReadySet
+    pub(super) async fn load(&self, authority: &Authority) -> ReadySetResult<()> {
+        let mut ids = QueryIds::default();
+        for shard in 0..PERSISTED_SHARDS {
+            let persisted = authority
+                .try_read::<PersistedShard>(&shard_path(authority, shard))
+                .await
+                .map_err(|e| internal_err!("Unable to read query ids: {}", e))?;
+            if let Some(persisted) = persisted {
+                ids.load_shard(persisted);
+            }
+        }
+        // Make sure ids handed out by a previous version of ReadySet keep resolving if the way we
+        // compute query ids has changed
+        ids.update_version();
+        self.update(authority, |current| *current = ids).await
+    }
+
+    /// Acquire a read lock on the registry
+    pub(super) async fn read(&self) -> RwLockReadGuard<'_, QueryIds> {
+        self.ids.read().await
+    }
+
+    /// Apply `f` to the registry, then persist the shards it changed.
+    ///
+    /// Changes made while another call is persisting its changes are written together once that
+    /// call is done, so a burst of changes (such as many queries being found to be unsupported at
+    /// once) only writes each shard they touch once or twice.
+    pub(super) async fn update<F, R>(&self, authority: &Authority, f: F) -> ReadySetResult<R>
+    where
+        F: FnOnce(&mut QueryIds) -> R,
+    {
+        let res = f(&mut *self.ids.write().await);
+        let _persisting = self.persisting.lock().await;
+        // If another call persisted our changes while we were waiting, there's nothing left to do
+        let mut shards = self.ids.write().await.take_dirty_shards();
+
+        while let Some((shard, contents)) = shards.pop() {
+            let written = authority
+                .read_modify_write(
+                    &shard_path(authority, shard),
+                    |_: Option<PersistedShard>| -> Result<_, Infallible> { Ok(contents.clone()) },
+                )
+                .await;
+            if let Err(e) = written {
+                // Try again the next time the registry changes
+                let mut ids = self.ids.write().await;
+                ids.dirty.insert(shard);
+                ids.dirty.extend(shards.into_iter().map(|(shard, _)| shard));
+                return Err(internal_err!("Unable to persist query ids: {}", e));
+            }
+        }
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::{parse_select_statement, Dialect};
+    use readyset_client::consensus::LocalAuthority;
+
+    use super::*;
+
+    fn request(query: &str) -> ViewCreateRequest {
+        ViewCreateRequest::new(
+            parse_select_statement(Dialect::MySQL, query).unwrap(),
+            vec!["s1".into()],
+        )
+    }
+
+    #[test]
+    fn register_and_get() {
+        let mut ids = QueryIds::default();
+        let req = request("SELECT * FROM t WHERE x = ?");
+        let id = QueryId::from(&req);
+
+        assert!(ids.get(id).is_none());
+        assert!(ids.register(req.clone()));
+        assert!(!ids.register(req.clone()));
+        assert!(ids.contains(&req));
+        assert_eq!(ids.get(id), Some((id, &req)));
+    }
+
+    #[test]
+    fn legacy_ids_resolve() {
+        let mut ids = QueryIds::default();
+        let req = request("SELECT * FROM t WHERE x = ?");
+        ids.register(req.clone());
+
+        assert_eq!(
+            ids.get(QueryId::legacy(&req)),
+            Some((QueryId::from(&req), &req))
+        );
+    }
+
+    #[test]
+    fn update_version_rekeys_outdated_queries() {
+        let mut ids = QueryIds::default();
+        let req = request("SELECT * FROM t WHERE x = ?");
+        let new_id = QueryId::from(&req);
+        let old_id = QueryId::legacy(&req);
+        let older_id = "q_1".parse::<QueryId>().unwrap();
+
+        // Simulate a query registered by a previous version, which itself had an alias from a
+        // version before that
+        ids.queries.insert(
+            old_id,
+            RegisteredQuery {
+                version: QUERY_ID_VERSION - 1,
+                request: req.clone(),
+                unsupported: None,
+                last_used: 0,
+            },
+        );
+        ids.aliases.insert(older_id, old_id);
+
+        assert!(ids.update_version());
+        assert!(!ids.update_version());
+
+        assert_eq!(ids.get(new_id), Some((new_id, &req)));
+        assert_eq!(ids.get(old_id), Some((new_id, &req)));
+        assert_eq!(ids.get(older_id), Some((new_id, &req)));
+    }
+
+    #[test]
+    fn evicts_least_recently_registered() {
+        let mut ids = QueryIds::default();
+        let first = request("SELECT * FROM t WHERE x = 0");
+        let second = request("SELECT * FROM t WHERE x = 1");
+        ids.register(first.clone());
+        ids.register(second.clone());
+        // Registering a query again marks it as recently used
+        ids.register(first.clone());
+
+        for i in 2..MAX_REGISTERED_QUERIES {
+            ids.register(request(&format!("SELECT * FROM t WHERE x = {i}")));
+        }
+        assert_eq!(ids.queries.len(), MAX_REGISTERED_QUERIES);
+        assert!(ids.contains(&first));
+        assert!(ids.contains(&second));
+
+        ids.register(request("SELECT * FROM t WHERE y = 0"));
+        assert_eq!(ids.queries.len(), MAX_REGISTERED_QUERIES);
+        assert!(ids.contains(&first));
+        assert!(!ids.contains(&second));
+        assert!(ids.get(QueryId::legacy(&second)).is_none());
+        assert!(!ids.aliases.values().any(|id| *id == QueryId::from(&second)));
+    }
+
+    #[test]
+    fn unsupported_verdicts() {
+        let mut ids = QueryIds::default();
+        let req = request("SELECT * FROM t WHERE x = ?");
+        let other = request("SELECT * FROM t WHERE y = ?");
+        ids.register(other.clone());
+        assert_eq!(ids.unsupported_error(&req), None);
+
+        ids.mark_unsupported(req.clone(), "nope".into(), None);
+        assert_eq!(
+            ids.unsupported_error(&req),
+            Some(ReadySetError::Unsupported("nope".into()))
+        );
+        assert_eq!(ids.unsupported_error(&other), None);
+        assert_eq!(
+            ids.unsupported_queries().collect::<Vec<_>>(),
+            vec![(QueryId::from(&req), &req, "nope", None)]
+        );
+        // Marking a query as unsupported registers it
+        assert!(ids.contains(&req));
+    }
+
+    #[test]
+    fn unsupported_verdicts_remember_features() {
+        let mut ids = QueryIds::default();
+        let req = request("SELECT * FROM t WHERE x = ?");
+        ids.mark_unsupported(
+            req.clone(),
+            "nope".into(),
+            Some(UnsupportedFeature::NonEquiJoin),
+        );
+
+        let err = ids.unsupported_error(&req).unwrap();
+        assert_eq!(err.unsupported_cause(), Some("nope"));
+        assert_eq!(
+            err.unsupported_feature(),
+            Some(UnsupportedFeature::NonEquiJoin)
+        );
+        assert_eq!(
+            ids.unsupported_queries().collect::<Vec<_>>(),
+            vec![(
+                QueryId::from(&req),
+                &req,
+                "nope",
+                Some(UnsupportedFeature::NonEquiJoin)
+            )]
+        );
+    }
+
+    #[test]
+    fn unsupported_verdicts_from_other_planner_versions_are_ignored() {
+        let mut ids = QueryIds::default();
+        let req = request("SELECT * FROM t WHERE x = ?");
+        ids.mark_unsupported(req.clone(), "nope".into(), None);
+        ids.queries
+            .get_mut(&QueryId::from(&req))
+            .unwrap()
+            .unsupported
+            .as_mut()
+            .unwrap()
+            .planner_version = PLANNER_VERSION - 1;
+
+        assert_eq!(ids.unsupported_error(&req), None);
+        assert_eq!(ids.unsupported_queries().count(), 0);
+    }
+
+    #[test]
+    fn only_changed_shards_are_dirty() {
+        let mut ids = QueryIds::default();
+        let req = request("SELECT * FROM t WHERE x = ?");
+        let id = QueryId::from(&req);
+        ids.register(request("SELECT * FROM t WHERE x = 0"));
+        ids.take_dirty_shards();
+
+        ids.register(req.clone());
+        let shards = ids.take_dirty_shards();
+        assert_eq!(shards.len(), 1);
+        let (shard, contents) = &shards[0];
+        assert_eq!(*shard, shard_of(id));
+        assert!(contents.queries.iter().any(|(q, _)| *q == id));
+        assert!(contents
+            .aliases
+            .iter()
+            .any(|alias| *alias == (QueryId::legacy(&req), id)));
+
+        // Registering the query again doesn't need to be persisted
+        ids.register(req.clone());
+        assert!(ids.take_dirty_shards().is_empty());
+
+        ids.mark_unsupported(req, "nope".into(), None);
+        assert_eq!(
+            ids.take_dirty_shards()
+                .into_iter()
+                .map(|(shard, _)| shard)
+                .collect::<Vec<_>>(),
+            vec![shard_of(id)]
+        );
+    }
+
+    #[test]
+    fn clear_unsupported_verdicts() {
+        let mut ids = QueryIds::default();
+        let req = request("SELECT * FROM t WHERE x = ?");
+        let other = request("SELECT * FROM t WHERE y = ?");
+        ids.mark_unsupported(req.clone(), "nope".into(), None);
+        ids.register(other);
+        ids.take_dirty_shards();
+
+        ids.clear_unsupported();
+        assert_eq!(ids.unsupported_error(&req), None);
+        assert_eq!(ids.unsupported_queries().count(), 0);
+        assert!(ids.contains(&req));
+        assert_eq!(
+            ids.take_dirty_shards()
+                .into_iter()
+                .map(|(shard, _)| shard)
+                .collect::<Vec<_>>(),
+            vec![shard_of(QueryId::from(&req))]
+        );
+    }
+
+    #[tokio::test]
+    async fn persist_and_load() {
+        let authority = Authority::from(LocalAuthority::new());
+        let req = request("SELECT * FROM t WHERE x = ?");
+        let unsupported = request("SELECT * FROM t WHERE y = ?");
+
+        let handle = QueryIdsHandle::default();
+        handle
+            .update(&authority, |ids| {
+                for i in 0..100 {
+                    ids.register(request(&format!("SELECT * FROM t WHERE x = {i}")));
+                }
+                ids.register(req.clone());
+                ids.mark_unsupported(unsupported.clone(), "nope".into(), None);
+            })
+            .await
+            .unwrap();
+
+        let loaded = QueryIdsHandle::default();
+        loaded.load(&authority).await.unwrap();
+        let ids = loaded.read().await;
+        assert_eq!(ids.queries.len(), 102);
+        assert_eq!(
+            ids.get(QueryId::legacy(&req)),
+            Some((QueryId::from(&req), &req))
+        );
+        assert_eq!(
+            ids.unsupported_error(&unsupported),
+            Some(ReadySetError::Unsupported("nope".into()))
+        );
+        assert!(ids.dirty.is_empty());
+    }
+
+    #[test]
+    fn cached_query_from_changelist() {
+        let changes = ChangeList::from_str(
+            "CREATE CACHE FROM SELECT * FROM t WHERE x = ?",
+            readyset_data::Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap()
+        .with_schema_search_path(vec!["s1".into()]);
+        assert_eq!(
+            cached_query(&changes),
+            Some(request("SELECT * FROM t WHERE x = ?"))
+        );
+
+        let changes = ChangeList::from_str(
+            "CREATE TABLE t (x int); CREATE CACHE FROM SELECT * FROM t WHERE x = ?",
+            readyset_data::Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap();
+        assert_eq!(cached_query(&changes), None);
+        assert!(changes_schema(&changes));
+        assert!(!changes_schema(
+            &ChangeList::from_str(
+                "CREATE CACHE FROM SELECT * FROM t WHERE x = ?",
+                readyset_data::Dialect::DEFAULT_MYSQL,
+            )
+            .unwrap()
+        ));
+    }
+}
@@ -6,12 +6,12 @@ use mir::{Column, NodeIndex};
 use nom_sql::analysis::ReferredColumns;
 use nom_sql::FunctionExpr::*;
 use nom_sql::{self, Expr, FieldDefinitionExpr, Relation, SqlIdentifier};
-use readyset_errors::{unsupported, ReadySetError};
+use readyset_errors::{unsupported_feature, ReadySetError};
 use readyset_sql_passes::is_aggregate;
 
 use crate::controller::sql::mir::join::make_joins_for_aggregates;
 use crate::controller::sql::mir::SqlToMirConverter;
-use crate::controller::sql::query_graph::QueryGraph;
+use crate::controller::sql::query_graph::{OutputColumn, QueryGraph};
 use crate::ReadySetResult;
 
 // Move predicates above grouped_by nodes
@@ -50,8 +50,8 @@ pub(super) fn make_predicates_above_grouped<'a>(
 }
 
 /// Normally, projection happens after grouped nodes - however, if aggregates used in grouped
-/// expressions reference expressions rather than columns directly, we need to project them out
-/// before the grouped nodes.
+/// expressions reference expressions rather than columns directly, or the query groups by
+/// expressions rather than columns, we need to project them out before the grouped nodes.
 ///
 /// This does that projection, and returns a mapping from the expressions themselves to the names of
 /// the columns they have been projected to
@@ -62,7 +62,7 @@ pub(super) fn make_expressions_above_grouped(
     qg: &QueryGraph,
     prev_node: &mut NodeIndex,
 ) -> HashMap<Expr, SqlIdentifier> {
-    let exprs: Vec<_> = qg
+    let mut exprs: Vec<_> = qg
         .aggregates
         .iter()
         .map(|(f, _)| f)
@@ -72,6 +72,15 @@ pub(super) fn make_expressions_above_grouped(
         .filter(|arg| !matches!(arg, Expr::Column(_)))
         .map(|expr| (SqlIdentifier::from(expr.to_string()), expr.clone()))
         .collect();
+    // Expressions in the GROUP BY clause are projected as columns named after the expressions
+    // (see `QueryGraph::group_by_expressions`), which may already be projected if they're also
+    // the argument to an aggregate
+    for expr in &qg.group_by_expressions {
+        let name = SqlIdentifier::from(expr.to_string());
+        if !exprs.iter().any(|(n, _)| *n == name) {
+            exprs.push((name, expr.clone()));
+        }
+    }
 
     if !exprs.is_empty() {
         let cols = mir_converter.columns(*prev_node).to_vec();
@@ -287,13 +296,31 @@ pub(super) fn post_lookup_aggregates(
         return Ok(None);
     }
 
+    // Expressions over aggregates (such as the `sum(x) / count(x)` that `AVG(x)` is rewritten to)
+    // are evaluated before the lookup, so re-aggregating their inputs after the lookup wouldn't
+    // recompute them
+    let aggregate_names = query_graph.aggregates.values().collect::<HashSet<_>>();
+    if query_graph.columns.iter().any(|col| {
+        matches!(col, OutputColumn::Expr(ec) if ec.expression.referred_columns().any(|c| {
+            c.table.is_none() && aggregate_names.contains(&c.name)
+        }))
+    }) {
+        unsupported_feature!(
+            PostLookupAggregate,
+            "Expressions over aggregates are not supported as post-lookup aggregates"
+        )
+    }
+
     let mut aggregates = vec![];
     for (function, alias) in &query_graph.aggregates {
         aggregates.push(PostLookupAggregate {
             column: Column::named(alias.clone()).aliased_as_table(query_name.clone()),
             function: match function {
                 Avg { .. } => {
-                    unsupported!("Average is not supported as a post-lookup aggregate")
+                    unsupported_feature!(
+                        PostLookupAggregate,
+                        "Average is not supported as a post-lookup aggregate"
+                    )
                 }
                 // Count and sum are handled the same way, as re-aggregating counts is
                 // done by just summing the numbers together
@@ -303,6 +330,12 @@ pub(super) fn post_lookup_aggregates(
                 GroupConcat { separator, .. } => PostLookupAggregateFunction::GroupConcat {
                     separator: separator.clone(),
                 },
+                PercentileCont { .. } => {
+                    unsupported_feature!(
+                        PostLookupAggregate,
+                        "Percentiles are not supported as post-lookup aggregates"
+                    )
+                }
                 Call { .. } | Substring { .. } => continue,
             },
         });
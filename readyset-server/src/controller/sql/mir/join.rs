@@ -2,7 +2,7 @@ use std::collections::{HashMap, HashSet};
 
 use mir::NodeIndex;
 use nom_sql::Relation;
-use readyset_errors::{internal, internal_err, invariant};
+use readyset_errors::{internal, internal_err, invariant, unsupported};
 
 use super::JoinKind;
 use crate::controller::sql::mir::SqlToMirConverter;
@@ -49,6 +49,7 @@ pub(super) fn make_joins(
         let (mut join_kind, jps) = match &qg.edges[&(jref.src.clone(), jref.dst.clone())] {
             QueryGraphEdge::Join { on } => (JoinKind::Inner, on),
             QueryGraphEdge::LeftJoin { on } => (JoinKind::Left, on),
+            QueryGraphEdge::AntiJoin { on } => (JoinKind::Anti, on),
         };
 
         let (left_chain, right_chain) =
@@ -64,18 +65,39 @@ pub(super) fn make_joins(
                 JoinKind::Inner => {
                     join_kind = JoinKind::Dependent;
                 }
-                JoinKind::Dependent => {}
+                JoinKind::Anti => {
+                    join_kind = JoinKind::DependentAnti;
+                }
+                JoinKind::Dependent | JoinKind::DependentAnti => {}
             }
         }
 
-        let jn = mir_converter.make_join_node(
-            query_name,
-            mir_converter.generate_label(&name),
-            jps,
-            left_chain.last_node,
-            right_chain.last_node,
-            join_kind,
-        )?;
+        let jn = match join_kind {
+            // Anti-join edges are created for NOT IN, which has to take NULLs into account
+            JoinKind::Anti => {
+                let [on] = jps.as_slice() else {
+                    internal!("NOT IN must have exactly one join predicate")
+                };
+                mir_converter.make_not_in_nodes(
+                    query_name,
+                    &mir_converter.generate_label(&name),
+                    on,
+                    left_chain.last_node,
+                    right_chain.last_node,
+                )?
+            }
+            JoinKind::DependentAnti => {
+                unsupported!("Correlated subqueries are not supported with NOT IN")
+            }
+            _ => mir_converter.make_join_node(
+                query_name,
+                mir_converter.generate_label(&name),
+                jps,
+                left_chain.last_node,
+                right_chain.last_node,
+                join_kind,
+            )?,
+        };
 
         // merge node chains
         let new_chain = left_chain.merge_chain(right_chain, jn);
@@ -5,12 +5,14 @@ use std::vec::Vec;
 
 use ::serde::{Deserialize, Serialize};
 use common::{DfValue, IndexType};
+use dataflow::node::special::DuplicateKeyBehavior;
 use dataflow::ops::grouped::aggregate::Aggregation;
 use dataflow::ops::union;
 use lazy_static::lazy_static;
 use mir::graph::MirGraph;
 use mir::node::node_inner::MirNodeInner;
 use mir::node::{GroupedNodeType, MirNode};
+use mir::plan::MirPlan;
 use mir::query::{MirBase, MirQuery};
 use mir::DfNodeIndex;
 pub use mir::{Column, NodeIndex};
@@ -23,7 +25,8 @@ use nom_sql::{
 use petgraph::visit::Reversed;
 use petgraph::Direction;
 use readyset_errors::{
-    internal, internal_err, invalid_err, invariant, invariant_eq, unsupported, ReadySetError,
+    internal, internal_err, invalid_err, invariant, invariant_eq, unsupported, unsupported_feature,
+    ReadySetError,
 };
 use readyset_sql_passes::is_correlated;
 use readyset_tracing::{debug, trace};
@@ -101,6 +104,10 @@ pub enum JoinKind {
     Left,
     /// Dependent joins - see [`MirNodeInner::DependentJoin`]
     Dependent,
+    /// Anti-joins - see [`MirNodeInner::AntiJoin`]
+    Anti,
+    /// Dependent anti-joins - see [`MirNodeInner::DependentAntiJoin`]
+    DependentAnti,
 }
 
 /// Specification for how to treat the leaf node of a query when converting it to MIR
@@ -152,6 +159,11 @@ pub(crate) struct Config {
     /// Enable support for mixing equality and range comparisons in a query. Support for mixed
     /// comparisons is currently unfinished, so these queries may return incorrect results.
     pub(crate) allow_mixed_comparisons: bool,
+
+    /// If set to `true`, inserts into base tables in write-through mode of rows whose primary key
+    /// already exists replace the existing row, rather than being rejected with a duplicate entry
+    /// error. Defaults to `false`.
+    pub(crate) upsert_duplicate_keys: bool,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -170,6 +182,12 @@ pub(super) struct SqlToMirConverter {
     /// replicated (either due to lack of support, or because the user explicitly opted out from
     /// them being replicated)
     pub(in crate::controller::sql) non_replicated_relations: HashSet<Relation>,
+
+    /// If set to `true`, `COUNT(DISTINCT)` and `PERCENTILE_CONT` aggregates in the query currently
+    /// being converted are computed approximately, rather than exactly. Only set while converting
+    /// a cache created with `APPROXIMATE AGGREGATES`.
+    #[serde(skip)]
+    pub(in crate::controller::sql) approximate_aggregates: bool,
 }
 
 impl SqlToMirConverter {
@@ -343,12 +361,18 @@ impl SqlToMirConverter {
             .and_then(|node| self.mir_graph.resolve_dataflow_node(*node))
     }
 
+    /// Convert the given `CREATE TABLE` body to a MIR base node. If `enforce_constraints` is true,
+    /// the base node will reject writes which violate the table's `CHECK` constraints or which
+    /// duplicate its primary key or unique keys (or upsert on duplicate primary keys, if
+    /// configured to do so).
     pub(super) fn named_base_to_mir(
         &mut self,
         name: Relation,
         body: &CreateTableBody,
+        enforce_constraints: bool,
     ) -> ReadySetResult<MirBase<'_>> {
-        let n = self.make_base_node(&name, &body.fields, body.keys.as_ref())?;
+        let n =
+            self.make_base_node(&name, &body.fields, body.keys.as_ref(), enforce_constraints)?;
         Ok(MirBase {
             name,
             mir_node: n,
@@ -437,6 +461,32 @@ impl SqlToMirConverter {
         MirQuery::new(query_name, mir_leaf, &mut self.mir_graph)
     }
 
+    /// Returns a [`MirPlan`] describing the current MIR for the query with the given name.
+    pub(super) fn mir_plan(&self, query_name: &Relation) -> ReadySetResult<MirPlan> {
+        let leaf =
+            self.get_relation(query_name)
+                .ok_or_else(|| ReadySetError::RelationNotFound {
+                    relation: query_name.to_string(),
+                })?;
+        Ok(MirPlan::for_leaf(&self.mir_graph, leaf))
+    }
+
+    /// Adds the nodes for the query with the given name from a (possibly hand-edited) [`MirPlan`]
+    /// instead of compiling them from SQL, and registers the query, returning the index of its
+    /// leaf node.
+    pub(super) fn add_query_from_plan(
+        &mut self,
+        query_name: Relation,
+        plan: &MirPlan,
+    ) -> ReadySetResult<NodeIndex> {
+        let relations = &self.relations;
+        let leaf = plan.add_to_graph(&query_name, &mut self.mir_graph, |name| {
+            relations.get(name).copied()
+        })?;
+        self.relations.insert(query_name, leaf);
+        Ok(leaf)
+    }
+
     /// Computes the list of columns in the output of this node.
     pub(super) fn columns(&self, node: NodeIndex) -> Vec<Column> {
         self.mir_graph.columns(node)
@@ -522,6 +572,7 @@ impl SqlToMirConverter {
         table_name: &Relation,
         cols: &[ColumnSpecification],
         keys: Option<&Vec<TableKey>>,
+        enforce_constraints: bool,
     ) -> ReadySetResult<NodeIndex> {
         if let Some(ni) = self.get_relation(table_name) {
             match &self.mir_graph[ni].inner {
@@ -587,6 +638,40 @@ impl SqlToMirConverter {
             }
         };
 
+        // Unnamed check constraints are named the same way MySQL names them, by numbering them
+        // within the table
+        let duplicate_keys = if !enforce_constraints {
+            DuplicateKeyBehavior::Ignore
+        } else if self.config.upsert_duplicate_keys {
+            DuplicateKeyBehavior::Upsert
+        } else {
+            DuplicateKeyBehavior::Reject
+        };
+
+        let check_constraints = if enforce_constraints {
+            keys.into_iter()
+                .flatten()
+                .filter_map(|k| match k {
+                    TableKey::CheckConstraint {
+                        constraint_name,
+                        expr,
+                        enforced,
+                    } => Some((constraint_name, expr, *enforced != Some(false))),
+                    _ => None,
+                })
+                .enumerate()
+                .filter(|(_, (_, _, enforced))| *enforced)
+                .map(|(i, (constraint_name, expr, _))| {
+                    let name = constraint_name
+                        .clone()
+                        .unwrap_or_else(|| format!("{}_chk_{}", table_name.name, i + 1).into());
+                    (name, expr.clone())
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+
         // remember the schema for this version
         let node = MirNode::new(
             table_name.clone(),
@@ -594,6 +679,8 @@ impl SqlToMirConverter {
                 column_specs: cols.to_vec(),
                 primary_key,
                 unique_keys,
+                check_constraints,
+                duplicate_keys,
             },
         );
         let ni = self.mir_graph.add_node(node);
@@ -723,6 +810,7 @@ impl SqlToMirConverter {
         parent: NodeIndex,
         projected_exprs: &HashMap<Expr, SqlIdentifier>,
     ) -> ReadySetResult<Vec<NodeIndex>> {
+        use dataflow::ops::grouped::approximate::ApproximateAggregation;
         use dataflow::ops::grouped::extremum::Extremum;
         use nom_sql::FunctionExpr::*;
 
@@ -736,6 +824,7 @@ impl SqlToMirConverter {
         }
 
         let mut out_nodes = Vec::new();
+        let approximate = self.approximate_aggregates;
 
         let mknode = |over: Column, t: GroupedNodeType, distinct: bool| {
             if distinct {
@@ -765,6 +854,10 @@ impl SqlToMirConverter {
             out_nodes
         };
 
+        let approximate_count_distinct = GroupedNodeType::Aggregation(Aggregation::Approximate(
+            ApproximateAggregation::CountDistinct,
+        ));
+
         Ok(match function {
             Sum {
                 expr: box Expr::Column(col),
@@ -790,13 +883,12 @@ impl SqlToMirConverter {
             }
             Count {
                 expr: box Expr::Column(col),
-                distinct,
-            } => mknode(
-                Column::from(col),
-                GroupedNodeType::Aggregation(Aggregation::Count),
-                distinct,
-            ),
-            Count { ref expr, distinct } => mknode(
+                distinct: true,
+            } if approximate => mknode(Column::from(col), approximate_count_distinct, false),
+            Count {
+                ref expr,
+                distinct: true,
+            } if approximate => mknode(
                 // TODO(celine): replace with ParentRef
                 Column::named(
                     projected_exprs
@@ -804,18 +896,18 @@ impl SqlToMirConverter {
                         .cloned()
                         .ok_or_else(|| mk_error!(expr))?,
                 ),
-                GroupedNodeType::Aggregation(Aggregation::Count),
-                distinct,
+                approximate_count_distinct,
+                false,
             ),
-            Avg {
+            Count {
                 expr: box Expr::Column(col),
                 distinct,
             } => mknode(
                 Column::from(col),
-                GroupedNodeType::Aggregation(Aggregation::Avg),
+                GroupedNodeType::Aggregation(Aggregation::Count),
                 distinct,
             ),
-            Avg { ref expr, distinct } => mknode(
+            Count { ref expr, distinct } => mknode(
                 // TODO(celine): replace with ParentRef
                 Column::named(
                     projected_exprs
@@ -823,9 +915,12 @@ impl SqlToMirConverter {
                         .cloned()
                         .ok_or_else(|| mk_error!(expr))?,
                 ),
-                GroupedNodeType::Aggregation(Aggregation::Avg),
+                GroupedNodeType::Aggregation(Aggregation::Count),
                 distinct,
             ),
+            Avg { .. } => {
+                internal!("AVG should have been rewritten earlier!")
+            }
             // TODO(atsakiris): Support Filters for Extremum/GroupConcat
             // CH: https://app.clubhouse.io/readysettech/story/198
             Max(box Expr::Column(col)) => mknode(
@@ -868,6 +963,34 @@ impl SqlToMirConverter {
                 GroupedNodeType::Aggregation(Aggregation::GroupConcat { separator }),
                 false,
             ),
+            PercentileCont { .. } if !approximate => unsupported!(
+                "PERCENTILE_CONT is only supported in caches created with APPROXIMATE AGGREGATES"
+            ),
+            PercentileCont { fraction, expr } => {
+                let fraction = f64::try_from(&DfValue::try_from(fraction)?)?;
+                if !(0.0..=1.0).contains(&fraction) {
+                    return Err(invalid_err!(
+                        "PERCENTILE_CONT fraction must be between 0 and 1"
+                    ));
+                }
+                let over = match *expr {
+                    Expr::Column(col) => Column::from(col),
+                    // TODO(celine): replace with ParentRef
+                    expr => Column::named(
+                        projected_exprs
+                            .get(&expr)
+                            .cloned()
+                            .ok_or_else(|| mk_error!(&expr))?,
+                    ),
+                };
+                mknode(
+                    over,
+                    GroupedNodeType::Aggregation(Aggregation::Approximate(
+                        ApproximateAggregation::PercentileCont { fraction },
+                    )),
+                    false,
+                )
+            }
             _ => {
                 internal!("not an aggregate: {:?}", Sensitive(&function));
             }
@@ -922,12 +1045,11 @@ impl SqlToMirConverter {
         // opportunities. Technically, we need to only project those columns here that the query
         // actually needs; at a minimum, we could start with just the join colums, relying on the
         // automatic column pull-down to retrieve the remaining columns required.
-        let projected_cols_left = self.mir_graph.columns(left_node);
-        let projected_cols_right = self.mir_graph.columns(right_node);
-        let mut project = projected_cols_left
-            .into_iter()
-            .chain(projected_cols_right.into_iter())
-            .collect::<Vec<Column>>();
+        let mut project = self.mir_graph.columns(left_node);
+        // anti-joins don't have any rows from the right to project columns from
+        if !matches!(kind, JoinKind::Anti | JoinKind::DependentAnti) {
+            project.extend(self.mir_graph.columns(right_node));
+        }
 
         // join columns need us to generate join group configs for the operator
         let mut on = Vec::new();
@@ -977,6 +1099,8 @@ impl SqlToMirConverter {
             JoinKind::Inner => MirNodeInner::Join { on, project },
             JoinKind::Left => MirNodeInner::LeftJoin { on, project },
             JoinKind::Dependent => MirNodeInner::DependentJoin { on, project },
+            JoinKind::Anti => MirNodeInner::AntiJoin { on, project },
+            JoinKind::DependentAnti => MirNodeInner::DependentAntiJoin { on, project },
         };
         trace!(?inner, "Added join node");
         Ok(self.add_query_node(
@@ -986,6 +1110,139 @@ impl SqlToMirConverter {
         ))
     }
 
+    /// Make the nodes which only keep the rows of `parent` if there are no rows at all in
+    /// `subquery_leaf`, as for `NOT EXISTS (<subquery>)`. `kind` must be either
+    /// [`JoinKind::Anti`] or [`JoinKind::DependentAnti`].
+    fn make_not_exists_nodes(
+        &mut self,
+        query_name: &Relation,
+        name: &Relation,
+        parent: NodeIndex,
+        subquery_leaf: NodeIndex,
+        kind: JoinKind,
+    ) -> ReadySetResult<NodeIndex> {
+        // -> π[lit: 0]
+        let right_literal_join_key_proj = self.make_project_node(
+            query_name,
+            format!("{}_prj_hlpr{}", name, self.mir_graph.node_count()).into(),
+            subquery_leaf,
+            vec![],
+            vec![],
+            vec![("__not_exists_grp".into(), DfValue::from(0u32))],
+        );
+        // -> [0] for each row
+
+        // left -> π[...left, lit: 0]
+        let parent_columns = self.mir_graph.columns(parent);
+        let left_literal_join_key_proj = self.make_project_node(
+            query_name,
+            format!("{}_join_key{}", name, self.mir_graph.node_count()).into(),
+            parent,
+            parent_columns,
+            vec![],
+            vec![("__not_exists_join_key".into(), DfValue::from(0u32))],
+        );
+
+        // -> ▷ on: l.__not_exists_join_key ≡ r.__not_exists_grp
+        //
+        // The anti-join keeps track of the number of rows in the subquery itself, so (unlike for
+        // EXISTS) there's no need to count them first
+        self.make_join_node(
+            query_name,
+            format!("{}_anti_join{}", name, self.mir_graph.node_count()).into(),
+            &[JoinPredicate {
+                left: Expr::Column("__not_exists_join_key".into()),
+                right: Expr::Column("__not_exists_grp".into()),
+            }],
+            left_literal_join_key_proj,
+            right_literal_join_key_proj,
+            kind,
+        )
+    }
+
+    /// Make the nodes for `<left column> NOT IN (<subquery>)`, given the join predicate between
+    /// the column on the left and the column projected by the subquery, whose leaf is `right`.
+    ///
+    /// Unlike NOT EXISTS, NOT IN is NULL-sensitive: a row on the left is only returned if its
+    /// value is not NULL, no row on the right has that value, *and* there are no NULLs on the
+    /// right (since comparing against those is NULL, rather than false). The exception is when
+    /// the subquery returns no rows at all, in which case every row on the left is returned,
+    /// including those whose value is NULL. We compute this as the union of:
+    ///
+    /// - rows with a non-NULL value, anti-joined to the right on their value, and then anti-joined
+    ///   to the NULLs on the right on a literal key
+    /// - rows with a NULL value, anti-joined to the whole right on a literal key
+    fn make_not_in_nodes(
+        &mut self,
+        query_name: &Relation,
+        name: &Relation,
+        on: &JoinPredicate,
+        left: NodeIndex,
+        right: NodeIndex,
+    ) -> ReadySetResult<NodeIndex> {
+        let kind = JoinKind::Anti;
+        let is_null = |col: &Expr, op| Expr::BinaryOp {
+            lhs: Box::new(col.clone()),
+            op,
+            rhs: Box::new(Expr::Literal(Literal::Null)),
+        };
+        let left_columns = self.mir_graph.columns(left);
+
+        // Non-NULL values which aren't on the right...
+        let left_not_null = self.make_filter_node(
+            query_name,
+            format!("{}_f{}", name, self.mir_graph.node_count()).into(),
+            left,
+            is_null(&on.left, BinaryOperator::IsNot),
+        );
+        let unmatched = self.make_join_node(
+            query_name,
+            format!("{}_anti_join{}", name, self.mir_graph.node_count()).into(),
+            std::slice::from_ref(on),
+            left_not_null,
+            right,
+            kind,
+        )?;
+        // ...as long as there are no NULLs on the right
+        let right_nulls = self.make_filter_node(
+            query_name,
+            format!("{}_f{}", name, self.mir_graph.node_count()).into(),
+            right,
+            is_null(&on.right, BinaryOperator::Is),
+        );
+        let not_null_rows =
+            self.make_not_exists_nodes(query_name, name, unmatched, right_nulls, kind)?;
+
+        // NULL values, as long as there are no rows on the right at all
+        let left_null = self.make_filter_node(
+            query_name,
+            format!("{}_f{}", name, self.mir_graph.node_count()).into(),
+            left,
+            is_null(&on.left, BinaryOperator::Is),
+        );
+        let null_rows = self.make_not_exists_nodes(query_name, name, left_null, right, kind)?;
+
+        // The two sides are disjoint, since every row's value is either NULL or not
+        let mut union_columns = left_columns.clone();
+        union_columns.push(Column::named("__not_exists_join_key"));
+        let union = self.make_union_from_same_base(
+            query_name,
+            format!("{}_un{}", name, self.mir_graph.node_count()).into(),
+            vec![not_null_rows, null_rows],
+            union_columns,
+            union::DuplicateMode::UnionAll,
+        )?;
+
+        Ok(self.make_project_node(
+            query_name,
+            format!("{}_prj{}", name, self.mir_graph.node_count()).into(),
+            union,
+            left_columns,
+            vec![],
+            vec![],
+        ))
+    }
+
     fn make_join_aggregates_node(
         &mut self,
         query_name: &Relation,
@@ -1066,9 +1323,9 @@ impl SqlToMirConverter {
         is_topk: bool,
     ) -> ReadySetResult<Vec<NodeIndex>> {
         if !self.config.allow_topk && is_topk {
-            unsupported!("TopK is not supported");
+            unsupported_feature!(Pagination, "TopK is not supported");
         } else if !self.config.allow_paginate && !is_topk {
-            unsupported!("Paginate is not supported");
+            unsupported_feature!(Pagination, "Paginate is not supported");
         }
 
         // Gather a list of expressions we need to evaluate before the paginate node
@@ -1192,6 +1449,30 @@ impl SqlToMirConverter {
                     union::DuplicateMode::BagUnion,
                 )?
             }
+            Expr::UnaryOp {
+                op: UnaryOperator::Not,
+                rhs: box Expr::Exists(subquery),
+            } => {
+                let query_graph = to_query_graph((**subquery).clone())?;
+                let subquery_leaf = self.named_query_to_mir(
+                    query_name,
+                    &query_graph,
+                    &HashMap::new(),
+                    LeafBehavior::Anonymous,
+                )?;
+
+                self.make_not_exists_nodes(
+                    query_name,
+                    &name,
+                    parent,
+                    subquery_leaf,
+                    if is_correlated(subquery) {
+                        JoinKind::DependentAnti
+                    } else {
+                        JoinKind::Anti
+                    },
+                )?
+            }
             Expr::UnaryOp {
                 op: UnaryOperator::Not | UnaryOperator::Neg,
                 ..
@@ -1285,7 +1566,9 @@ impl SqlToMirConverter {
             Expr::Call(_) => {
                 internal!("Function calls should have been handled by projection earlier")
             }
-            Expr::NestedSelect(_) => unsupported!("Nested selects not supported in filters"),
+            Expr::NestedSelect(_) => {
+                unsupported_feature!(Subquery, "Nested selects not supported in filters")
+            }
             _ => self.make_filter_node(
                 query_name,
                 format!("{}_f{}", name, self.mir_graph.node_count()).into(),
@@ -1782,6 +2065,25 @@ impl SqlToMirConverter {
                         }
                     }
                 }
+
+                // The reader evaluates post-lookup predicates against the rows it returns, so it
+                // needs all the columns they refer to
+                for column in query_graph
+                    .post_lookup_predicates
+                    .iter()
+                    .flat_map(|p| p.referred_columns())
+                    .map(Column::from)
+                {
+                    if !projected_columns.contains(&column) {
+                        projected_columns.push(column)
+                    }
+                }
+            } else if !query_graph.post_lookup_predicates.is_empty() {
+                unsupported_feature!(
+                    ParameterPosition,
+                    "Placeholders in positions other than direct comparisons with columns are not \
+                     supported in subqueries"
+                );
             }
 
             if query_graph.distinct {
@@ -1900,6 +2202,7 @@ impl SqlToMirConverter {
                             returned_cols: Some(returned_cols),
                             default_row: query_graph.default_row.clone(),
                             aggregates,
+                            post_lookup_predicates: query_graph.post_lookup_predicates.clone(),
                         },
                     ),
                     &[leaf_project_reorder_node],
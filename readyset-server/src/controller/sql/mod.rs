@@ -1,14 +1,17 @@
 use std::collections::{HashMap, HashSet};
-use std::str;
+use std::time::Duration;
 use std::vec::Vec;
+use std::{mem, str};
 
+use ::mir::plan::MirPlan;
 use ::mir::visualize::GraphViz;
 use ::mir::DfNodeIndex;
 use ::serde::{Deserialize, Serialize};
+use itertools::Itertools;
 use nom_sql::{
-    CacheInner, CompoundSelectOperator, CompoundSelectStatement, CreateTableBody,
-    FieldDefinitionExpr, Relation, SelectSpecification, SelectStatement, SqlIdentifier, SqlType,
-    TableExpr,
+    CacheFreshness, CacheInner, CacheResultLimits, CompoundSelectOperator, CompoundSelectStatement,
+    CreateTableBody, FieldDefinitionExpr, Relation, SelectSpecification, SelectStatement,
+    SqlIdentifier, SqlType, TableExpr,
 };
 use petgraph::graph::NodeIndex;
 use readyset_client::recipe::changelist::{AlterTypeChange, Change};
@@ -40,6 +43,27 @@ mod query_signature;
 mod recipe;
 mod registry;
 
+/// The version of the SQL planner, used to decide whether queries which were previously found to be
+/// unsupported are worth planning again.
+///
+/// This should be incremented whenever a change is made to the planner which makes some previously
+/// unsupported queries supported, with a note on the change added below.
+///
+/// Versions:
+///
+/// * 1: The initial version
+/// * 2: NULL-safe equality (`<=>`) can be used in reader keys
+/// * 3: Placeholders compared against non-key columns are evaluated as residual post-lookup filters
+///   in the reader, rather than making the query unsupported
+/// * 4: Expressions in `GROUP BY` are projected into columns before grouping, rather than making
+///   the query unsupported
+/// * 5: Placeholders in `LIKE` patterns are looked up by the literal prefix of the pattern, rather
+///   than being evaluated as a filter over every row in the reader. Such queries were already
+///   supported, so this doesn't make any previously unsupported queries supported
+/// * 6: `NOT IN` and `NOT EXISTS` with subqueries are compiled into anti-joins, rather than making
+///   the query unsupported
+pub(crate) const PLANNER_VERSION: u32 = 6;
+
 /// Configuration for converting SQL to dataflow
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[non_exhaustive]
@@ -67,6 +91,24 @@ struct UncompiledView {
     schema_search_path: Vec<SqlIdentifier>,
 }
 
+/// The settings a cache was created with, other than `ALWAYS` (which is stored with the cache's
+/// query in the [`ExprRegistry`]), kept so that the cache's `CREATE CACHE` statement can be
+/// reconstructed with the same settings
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct CacheOptions {
+    pub(crate) filter_pushdown: bool,
+    pub(crate) approximate_aggregates: bool,
+    #[serde(default)]
+    pub(crate) lazy_joins: bool,
+    #[serde(default)]
+    pub(crate) max_staleness: Option<u64>,
+    #[serde(default)]
+    pub(crate) bucket_retention: Option<u64>,
+    pub(crate) freshness: CacheFreshness,
+    #[serde(default)]
+    pub(crate) result_limits: CacheResultLimits,
+}
+
 /// Long-lived struct that holds information about the SQL queries (tables, views, and caches) that
 /// have been incorporated into the dataflow graph.
 ///
@@ -113,8 +155,18 @@ pub(crate) struct SqlIncorporator {
     /// dependencies on each other
     registry: ExprRegistry,
 
-    /// Whether or to treat failed writes to base tables as no-ops
+    /// Whether or to treat failed writes to base tables as no-ops, and to enforce `CHECK`
+    /// constraints on writes to base tables. Set when running without an upstream database.
     permissive_writes: bool,
+
+    /// The settings each cache was created with, keyed by the name the cache was created with
+    #[serde(default)]
+    cache_options: HashMap<Relation, CacheOptions>,
+
+    /// The MIR plans which have replaced the generated plans of caches via
+    /// [`set_mir_plan`][Self::set_mir_plan], keyed by the name of the cache
+    #[serde(default)]
+    mir_plan_overrides: HashMap<Relation, MirPlan>,
 }
 
 impl SqlIncorporator {
@@ -137,6 +189,17 @@ impl SqlIncorporator {
         self.mir_converter.config()
     }
 
+    /// Returns the settings the cache with the given name was created with, if it's a cache
+    pub(crate) fn cache_options(&self, name: &Relation) -> Option<&CacheOptions> {
+        self.cache_options.get(name)
+    }
+
+    /// Returns an iterator over the names of the caches whose MIR plans have been replaced via
+    /// [`set_mir_plan`][Self::set_mir_plan], along with the plans they were replaced with
+    pub(crate) fn mir_plan_overrides(&self) -> impl Iterator<Item = (&Relation, &MirPlan)> + '_ {
+        self.mir_plan_overrides.iter()
+    }
+
     /// Disable node reuse for future migrations.
     #[allow(unused)]
     pub(crate) fn disable_reuse(&mut self) {
@@ -292,7 +355,35 @@ impl SqlIncorporator {
                         }
                     };
 
-                    self.add_query(ccqs.name, statement, ccqs.always, &schema_search_path, mig)?;
+                    let name = self.add_query(
+                        ccqs.name,
+                        statement,
+                        ccqs.always,
+                        ccqs.filter_pushdown,
+                        ccqs.approximate_aggregates,
+                        ccqs.lazy_joins,
+                        &schema_search_path,
+                        mig,
+                    )?;
+
+                    self.cache_options.insert(
+                        name.clone(),
+                        CacheOptions {
+                            filter_pushdown: ccqs.filter_pushdown,
+                            approximate_aggregates: ccqs.approximate_aggregates,
+                            lazy_joins: ccqs.lazy_joins,
+                            max_staleness: ccqs.max_staleness,
+                            bucket_retention: ccqs.bucket_retention,
+                            freshness: ccqs.freshness,
+                            result_limits: ccqs.result_limits,
+                        },
+                    );
+                    self.apply_cache_options(&name, mig);
+                }
+                Change::SetMirPlan { name, plan } => {
+                    let plan = serde_json::from_str(&plan)
+                        .map_err(|e| invalid_err!("Could not parse MIR plan: {e}"))?;
+                    self.set_mir_plan(&name, plan, mig)?;
                 }
                 Change::AlterTable(_) => {
                     // This should not get hit because all ALTER TABLE definitions currently require
@@ -489,25 +580,38 @@ impl SqlIncorporator {
     ///
     /// If `name` is provided, will use that as the name for the query to add, otherwise a unique
     /// name will be generated from the query. In either case, returns the name of the added query.
+    ///
+    /// If `filter_pushdown` is false, filters in the query will not be pushed below joins. If
+    /// `approximate_aggregates` is true, `COUNT(DISTINCT)` and `PERCENTILE_CONT` aggregates in the
+    /// query will be estimated rather than computed exactly. If `lazy_joins` is true, joins
+    /// against unfiltered dimension tables are performed by the query's reader when it's read from
+    /// where possible, rather than being materialized (see [`dataflow::LazyJoin`]).
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn add_query(
         &mut self,
         name: Option<Relation>,
         mut stmt: SelectStatement,
         always: bool,
+        filter_pushdown: bool,
+        approximate_aggregates: bool,
+        lazy_joins: bool,
         schema_search_path: &[SqlIdentifier],
         mig: &mut Migration<'_>,
     ) -> ReadySetResult<Relation> {
         let name = name.unwrap_or_else(|| format!("q_{}", self.num_queries).into());
 
         let mut invalidating_tables = vec![];
-        let mir_query = match self.select_query_to_mir(
+        self.mir_converter.approximate_aggregates = approximate_aggregates;
+        let res = self.select_query_to_mir(
             name.clone(),
             &mut stmt,
             schema_search_path,
             Some(&mut invalidating_tables),
             LeafBehavior::Leaf,
             mig,
-        ) {
+        );
+        self.mir_converter.approximate_aggregates = false;
+        let mir_query = match res {
             Ok(mir_query) => Some(mir_query),
             // If we fail to migrate the query, see if we can reuse an existing cached query.
             Err(err) => {
@@ -530,6 +634,7 @@ impl SqlIncorporator {
             name: name.clone(),
             statement: stmt,
             always,
+            approximate_aggregates,
         })?;
         self.registry
             .insert_invalidating_tables(name.clone(), invalidating_tables.clone())?;
@@ -540,7 +645,8 @@ impl SqlIncorporator {
 
         // Do not add a leaf if we are reusing a query
         if let Some(mir_query) = mir_query {
-            let leaf = self.mir_to_dataflow(name.clone(), mir_query, mig)?;
+            let leaf =
+                self.mir_to_dataflow(name.clone(), mir_query, filter_pushdown, lazy_joins, mig)?;
             self.leaf_addresses.insert(name.clone(), leaf);
         }
 
@@ -775,7 +881,9 @@ impl SqlIncorporator {
         mig: &mut Migration<'_>,
     ) -> ReadySetResult<(Relation, NodeIndex)> {
         // first, compute the MIR representation of the SQL query
-        let mir = self.mir_converter.named_base_to_mir(name.clone(), &body)?;
+        let mir =
+            self.mir_converter
+                .named_base_to_mir(name.clone(), &body, self.permissive_writes)?;
 
         trace!(base_node_mir = ?mir);
 
@@ -1009,7 +1117,10 @@ impl SqlIncorporator {
             schema_search_path,
         } = uncompiled_view;
 
-        let mir_leaf = match &mut definition {
+        // Views are always computed exactly, even if they're compiled while migrating a cache
+        // with approximate aggregates
+        let approximate_aggregates = mem::take(&mut self.mir_converter.approximate_aggregates);
+        let res = match &mut definition {
             SelectSpecification::Compound(stmt) => self.add_compound_query(
                 name.clone(),
                 stmt,
@@ -1017,7 +1128,7 @@ impl SqlIncorporator {
                 None,
                 LeafBehavior::NamedWithoutLeaf,
                 mig,
-            )?,
+            ),
             SelectSpecification::Simple(stmt) => self.select_query_to_mir(
                 name.clone(),
                 stmt,
@@ -1025,8 +1136,10 @@ impl SqlIncorporator {
                 None,
                 LeafBehavior::NamedWithoutLeaf,
                 mig,
-            )?,
+            ),
         };
+        self.mir_converter.approximate_aggregates = approximate_aggregates;
+        let mir_leaf = res?;
 
         if !self.registry.add_query(RecipeExpr::View {
             name: name.clone(),
@@ -1037,7 +1150,7 @@ impl SqlIncorporator {
             return Ok(());
         }
 
-        self.mir_to_dataflow(name, mir_leaf, mig)?;
+        self.mir_to_dataflow(name, mir_leaf, true, false, mig)?;
 
         Ok(())
     }
@@ -1046,6 +1159,8 @@ impl SqlIncorporator {
         &mut self,
         query_name: Relation,
         mir_leaf: MirNodeIndex,
+        filter_pushdown: bool,
+        lazy_joins: bool,
         mig: &mut Migration<'_>,
     ) -> ReadySetResult<NodeIndex> {
         let on_err = |e| ReadySetError::SelectQueryCreationFailed {
@@ -1057,11 +1172,11 @@ impl SqlIncorporator {
             .make_mir_query(query_name.clone(), mir_leaf);
 
         trace!(pre_opt_mir = %mir_query.to_graphviz());
-        let mut opt_mir = mir_query.rewrite().map_err(on_err)?;
+        let mut opt_mir = mir_query.rewrite(filter_pushdown).map_err(on_err)?;
         trace!(post_opt_mir = %opt_mir.to_graphviz());
 
-        let df_leaf =
-            mir_query_to_flow_parts(&mut opt_mir, &self.custom_types, mig).map_err(on_err)?;
+        let df_leaf = mir_query_to_flow_parts(&mut opt_mir, &self.custom_types, lazy_joins, mig)
+            .map_err(on_err)?;
         let fields = opt_mir.fields();
 
         self.register_query(query_name, fields);
@@ -1069,6 +1184,102 @@ impl SqlIncorporator {
         Ok(df_leaf.address())
     }
 
+    /// Returns a [`MirPlan`] describing the MIR for the cached query with the given name (or
+    /// alias), as it was after optimization.
+    pub(crate) fn mir_plan(&self, name: &Relation) -> ReadySetResult<MirPlan> {
+        match self.registry.get(name) {
+            Some(RecipeExpr::Cache { name, .. }) => self.mir_converter.mir_plan(name),
+            _ => Err(ReadySetError::ViewNotFound(name.to_string())),
+        }
+    }
+
+    /// Replace the dataflow for the cached query with the given name (or alias) with dataflow
+    /// lowered from the given [`MirPlan`], which was typically obtained from [`Self::mir_plan`]
+    /// and then edited by hand.
+    ///
+    /// The plan is lowered to dataflow as-is, without running any of the MIR rewrite passes on it,
+    /// and must return the same fields as the query's current plan. The new reader gets the same
+    /// settings the cache was created with, and the plan is recorded so that it can be reapplied
+    /// when the cache is recreated elsewhere (see [`Self::mir_plan_overrides`]).
+    pub(crate) fn set_mir_plan(
+        &mut self,
+        name: &Relation,
+        plan: MirPlan,
+        mig: &mut Migration<'_>,
+    ) -> ReadySetResult<()> {
+        let name = match self.registry.get(name) {
+            Some(RecipeExpr::Cache { name, .. }) => name.clone(),
+            _ => return Err(ReadySetError::ViewNotFound(name.to_string())),
+        };
+        let fields = self
+            .view_schemas
+            .get(&name)
+            .cloned()
+            .ok_or_else(|| ReadySetError::ViewNotFound(name.to_string()))?;
+        let on_err = |e| ReadySetError::SelectQueryCreationFailed {
+            qname: name.to_string(),
+            source: Box::new(e),
+        };
+
+        let mut removal_result = self.mir_converter.remove_query(&name)?;
+        Self::drop_dataflow_nodes(&mut removal_result, mig);
+        self.leaf_addresses.remove(&name);
+
+        let mir_leaf = self
+            .mir_converter
+            .add_query_from_plan(name.clone(), &plan)
+            .map_err(on_err)?;
+        let mut mir_query = self.mir_converter.make_mir_query(name.clone(), mir_leaf);
+        let plan_fields = mir_query.fields();
+        if plan_fields != fields {
+            return Err(on_err(invalid_err!(
+                "MIR plan returns fields ({}), but the query returns ({})",
+                plan_fields.iter().join(", "),
+                fields.iter().join(", ")
+            )));
+        }
+        trace!(mir = %mir_query.to_graphviz());
+
+        let lazy_joins = self
+            .cache_options
+            .get(&name)
+            .map_or(false, |options| options.lazy_joins);
+        let df_leaf = mir_query_to_flow_parts(&mut mir_query, &self.custom_types, lazy_joins, mig)
+            .map_err(on_err)?;
+        self.leaf_addresses.insert(name.clone(), df_leaf.address());
+        self.apply_cache_options(&name, mig);
+        self.mir_plan_overrides.insert(name, plan);
+        Ok(())
+    }
+
+    /// Apply the settings the cache with the given name was created with to the reader for its
+    /// current leaf. If the cache is an alias of another cache with the same query, that's the
+    /// other cache's leaf.
+    fn apply_cache_options(&self, name: &Relation, mig: &mut Migration<'_>) {
+        let leaf = self
+            .registry
+            .resolve_alias(name)
+            .and_then(|original| self.leaf_addresses.get(original));
+        let (Some(leaf), Some(options)) = (leaf, self.cache_options.get(name)) else {
+            return;
+        };
+        if let Some(max_staleness) = options.max_staleness {
+            mig.set_max_staleness(*leaf, Duration::from_secs(max_staleness));
+        }
+        if let Some(bucket_retention) = options.bucket_retention {
+            mig.set_bucket_retention(*leaf, Duration::from_secs(bucket_retention));
+        }
+        if options.result_limits.is_limited() {
+            mig.set_result_limits(*leaf, options.result_limits);
+        }
+        if options.freshness.max_replication_lag.is_some() {
+            mig.set_freshness(*leaf, options.freshness);
+        }
+        if let Some(RecipeExpr::Cache { always: true, .. }) = self.registry.get(name) {
+            mig.exempt_from_materialization_budget(*leaf);
+        }
+    }
+
     pub(super) fn remove_query(
         &mut self,
         query_name: &Relation,
@@ -1109,8 +1320,16 @@ impl SqlIncorporator {
     fn process_removal(&mut self, removal_result: &mut MirRemovalResult, mig: &mut Migration<'_>) {
         for query in removal_result.relations_removed.iter() {
             self.leaf_addresses.remove(query);
+            self.cache_options.remove(query);
+            self.mir_plan_overrides.remove(query);
             self.registry.remove_expression(query);
         }
+        Self::drop_dataflow_nodes(removal_result, mig);
+    }
+
+    /// Drop the dataflow nodes for the MIR nodes in `removal_result`, along with any ingress,
+    /// egress and reader nodes below them, recording all the dropped nodes in `removal_result`.
+    fn drop_dataflow_nodes(removal_result: &mut MirRemovalResult, mig: &mut Migration<'_>) {
         // Sadly, we don't use `DfNodeIndex` for migrations/df state, so we need to map them
         // to `NodeIndex`.
         // TODO(fran): Replace all occurrences of Dataflow node indices for `DfNodeIndex`.
@@ -17,9 +17,12 @@ use nom_sql::{
 use readyset_client::{PlaceholderIdx, ViewPlaceholder};
 use readyset_errors::{
     internal, invalid, invalid_err, invariant, invariant_eq, no_table_for_col, unsupported,
-    unsupported_err, ReadySetResult,
+    unsupported_err, unsupported_feature, unsupported_feature_err, ReadySetResult,
+};
+use readyset_sql_passes::{
+    contains_aggregate, is_aggregate, is_correlated, is_post_lookup_predicate, is_predicate,
+    map_aggregates, LogicalOp,
 };
-use readyset_sql_passes::{is_aggregate, is_correlated, is_predicate, map_aggregates, LogicalOp};
 use serde::{Deserialize, Serialize};
 
 use super::mir::{self, PAGE_NUMBER_COL};
@@ -194,8 +197,17 @@ pub struct QueryGraphNode {
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub enum QueryGraphEdge {
-    Join { on: Vec<JoinPredicate> },
-    LeftJoin { on: Vec<JoinPredicate> },
+    Join {
+        on: Vec<JoinPredicate>,
+    },
+    LeftJoin {
+        on: Vec<JoinPredicate>,
+    },
+    /// Only keep rows on the left which have no matching rows on the right. Created for
+    /// `<column> NOT IN (<subquery>)` conditions in the WHERE clause
+    AntiJoin {
+        on: Vec<JoinPredicate>,
+    },
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
@@ -235,7 +247,14 @@ pub struct QueryGraph {
     /// but both will appear in `self.columns` as [`OutputColumn::Data`] referencing that alias
     pub aggregates: HashMap<FunctionExpr, SqlIdentifier>,
     /// Set of columns that appear in the GROUP BY clause
+    ///
+    /// Expressions other than bare columns in the GROUP BY clause appear here as the columns
+    /// they're projected as (see `group_by_expressions`)
     pub group_by: HashSet<Column>,
+    /// Expressions other than bare columns that appear in the GROUP BY clause, each of which is
+    /// projected before grouping as an unqualified column named after the expression. References
+    /// to these expressions elsewhere in the query are replaced with references to those columns
+    pub group_by_expressions: Vec<Expr>,
     /// Final set of projected columns in this query; may include literals in addition to the
     /// columns reflected in individual relations' `QueryGraphNode` structures.
     pub columns: Vec<OutputColumn>,
@@ -252,6 +271,10 @@ pub struct QueryGraph {
     pub global_predicates: Vec<Expr>,
     /// HAVING predicates (like global predicates, but applied after aggregate functions)
     pub having_predicates: Vec<Expr>,
+    /// Predicates with placeholders in positions that can't be turned into lookup keys (such as
+    /// `round(t.x, ?) > ?`). These are evaluated by the reader against the results of each lookup,
+    /// once the values for the placeholders are known.
+    pub post_lookup_predicates: Vec<Expr>,
     /// The list of columns and directionsj that the query is ordering by, if any
     pub order: Option<Vec<(Column, OrderType)>>,
     /// The pagination (order, limit, offset) for the query, if any
@@ -295,8 +318,8 @@ impl QueryGraph {
             parameters.sort_by(|param1, param2| {
                 match (param1.op, param2.op) {
                     // All equal operators go first
-                    (BinaryOperator::Equal, _) => Ordering::Less,
-                    (_, BinaryOperator::Equal) => Ordering::Greater,
+                    (BinaryOperator::Equal | BinaryOperator::NullSafeEqual, _) => Ordering::Less,
+                    (_, BinaryOperator::Equal | BinaryOperator::NullSafeEqual) => Ordering::Greater,
                     // sort_by is stable, so if we return Equal we just leave things
                     // in the same order
                     (_, _) => Ordering::Equal,
@@ -319,7 +342,10 @@ impl QueryGraph {
                     && index_type.is_some()
                     && new_index_type != index_type
                 {
-                    unsupported!("Conflicting binary operators in query");
+                    unsupported_feature!(
+                        MixedParameterOperators,
+                        "Conflicting binary operators in query"
+                    );
                 } else {
                     index_type = new_index_type;
                 }
@@ -337,7 +363,10 @@ impl QueryGraph {
                                             ViewPlaceholder::Between(lower_idx, upper_idx);
                                         continue;
                                     }
-                                    _ => unsupported!("Conflicting binary operators in query"),
+                                    _ => unsupported_feature!(
+                                        MixedParameterOperators,
+                                        "Conflicting binary operators in query"
+                                    ),
                                 }
                             }
                             (BinaryOperator::LessOrEqual, BinaryOperator::GreaterOrEqual) => {
@@ -347,10 +376,16 @@ impl QueryGraph {
                                             ViewPlaceholder::Between(lower_idx, upper_idx);
                                         continue;
                                     }
-                                    _ => unsupported!("Conflicting binary operators in query"),
+                                    _ => unsupported_feature!(
+                                        MixedParameterOperators,
+                                        "Conflicting binary operators in query"
+                                    ),
                                 }
                             }
-                            _ => unsupported!("Conflicting binary operators in query"),
+                            _ => unsupported_feature!(
+                                MixedParameterOperators,
+                                "Conflicting binary operators in query"
+                            ),
                         }
                     }
                 }
@@ -365,7 +400,10 @@ impl QueryGraph {
 
             if let Some(offset) = offset {
                 if index_type == Some(IndexType::BTreeMap) {
-                    unsupported!("ReadySet does not support Pagination and range queries")
+                    unsupported_feature!(
+                        Pagination,
+                        "ReadySet does not support Pagination and range queries"
+                    )
                 } else {
                     columns.push((mir::Column::named(PAGE_NUMBER_COL.clone()), offset));
                 }
@@ -399,6 +437,7 @@ impl Hash for QueryGraph {
         let mut group_by = self.group_by.iter().collect::<Vec<_>>();
         group_by.sort();
         group_by.hash(state);
+        self.group_by_expressions.hash(state);
 
         let mut aggregates = self.aggregates.iter().collect::<Vec<_>>();
         aggregates.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
@@ -411,6 +450,7 @@ impl Hash for QueryGraph {
         self.join_order.hash(state);
         self.global_predicates.hash(state);
         self.having_predicates.hash(state);
+        self.post_lookup_predicates.hash(state);
         self.order.hash(state);
         self.pagination.hash(state);
         self.is_correlated.hash(state);
@@ -448,6 +488,8 @@ where
 // 2. Extract local predicates
 // 3. Extract join predicates
 // 4. Collect remaining predicates as global predicates
+// 5. Extract any predicates with placeholders that can't be turned into lookup keys, which are
+//    evaluated by the reader after each lookup
 fn classify_conditionals(
     ce: &Expr,
     inner_join_rels: &HashSet<Relation>,
@@ -455,6 +497,7 @@ fn classify_conditionals(
     join: &mut Vec<JoinPredicate>,
     global: &mut Vec<Expr>,
     params: &mut Vec<Parameter>,
+    post_lookup: &mut Vec<Expr>,
 ) -> ReadySetResult<()> {
     // Handling OR and AND expressions requires some care as there are some corner cases.
     //    a) we don't support OR expressions with predicates with placeholder parameters,
@@ -465,6 +508,11 @@ fn classify_conditionals(
     //       table2.y= 42). this is a global predicate according to finkelstein algorithm
     //       and we don't support these yet.
 
+    if is_post_lookup_predicate(ce) {
+        post_lookup.push(ce.clone());
+        return Ok(());
+    }
+
     match ce {
         Expr::BinaryOp { op, lhs, rhs } => {
             if let Ok(op) = LogicalOp::try_from(*op) {
@@ -487,6 +535,7 @@ fn classify_conditionals(
                     &mut new_join,
                     &mut new_global,
                     &mut new_params,
+                    post_lookup,
                 )?;
                 classify_conditionals(
                     rhs.as_ref(),
@@ -495,6 +544,7 @@ fn classify_conditionals(
                     &mut new_join,
                     &mut new_global,
                     &mut new_params,
+                    post_lookup,
                 )?;
 
                 match op {
@@ -528,10 +578,14 @@ fn classify_conditionals(
                     }
                     LogicalOp::Or => {
                         if !new_join.is_empty() {
-                            unsupported!("can't handle OR expressions between JOIN predicates")
+                            unsupported_feature!(
+                                DisjunctiveJoin,
+                                "can't handle OR expressions between JOIN predicates"
+                            )
                         }
                         if !new_params.is_empty() {
-                            unsupported!(
+                            unsupported_feature!(
+                                DisjunctiveParameters,
                                 "can't handle OR expressions between query parameter predicates"
                             );
                         }
@@ -637,7 +691,7 @@ fn classify_conditionals(
                         }
                     }
                     Expr::NestedSelect(_) => {
-                        unsupported!("nested SELECTs are unsupported")
+                        unsupported_feature!(Subquery, "nested SELECTs are unsupported")
                     }
                     Expr::Call(_)
                     | Expr::BinaryOp { .. }
@@ -687,6 +741,24 @@ fn classify_conditionals(
                 }
             }
         }
+        Expr::In {
+            rhs: InValue::Subquery(_),
+            ..
+        } => {
+            unsupported_feature!(
+                Subquery,
+                "IN with a subquery is only supported as NOT IN, at the top level of the WHERE \
+                 clause"
+            )
+        }
+        Expr::UnaryOp {
+            op: UnaryOperator::Not,
+            rhs: box Expr::Exists(_),
+        } => {
+            // NOT EXISTS can't be negated any further, so it's left as-is. Like EXISTS, it's
+            // handled as a global predicate
+            global.push(ce.clone())
+        }
         Expr::UnaryOp {
             op: UnaryOperator::Not,
             ..
@@ -713,6 +785,59 @@ fn classify_conditionals(
     Ok(())
 }
 
+/// Remove all the top-level `<column> NOT IN (<subquery>)` conditions from the given WHERE clause,
+/// returning the column and subquery for each of them, to be converted into anti-joins
+fn extract_anti_joins(
+    where_clause: &mut Option<Expr>,
+) -> ReadySetResult<Vec<(Column, SelectStatement)>> {
+    let Some(cond) = where_clause.take() else {
+        return Ok(vec![]);
+    };
+
+    let mut anti_joins = vec![];
+    let mut remaining = vec![];
+    for conjunct in split_conjunctions(iter::once(&cond)) {
+        match conjunct {
+            Expr::In {
+                lhs,
+                rhs: InValue::Subquery(subquery),
+                negated: true,
+            } => match *lhs {
+                Expr::Column(col) if col.table.is_some() => anti_joins.push((col, *subquery)),
+                lhs => unsupported!(
+                    "Only columns are supported on the left-hand side of NOT IN with a subquery, \
+                     not {lhs}"
+                ),
+            },
+            conjunct => remaining.push(conjunct),
+        }
+    }
+
+    *where_clause = remaining.into_iter().reduce(|lhs, rhs| Expr::BinaryOp {
+        lhs: Box::new(lhs),
+        op: BinaryOperator::And,
+        rhs: Box::new(rhs),
+    });
+
+    Ok(anti_joins)
+}
+
+/// Returns the name of the single column projected by the given subquery, used on the right-hand
+/// side of `NOT IN`
+fn subquery_column_name(subquery: &SelectStatement) -> ReadySetResult<SqlIdentifier> {
+    match subquery.fields.as_slice() {
+        [FieldDefinitionExpr::Expr {
+            alias: Some(alias), ..
+        }] => Ok(alias.clone()),
+        [FieldDefinitionExpr::Expr {
+            expr: Expr::Column(col),
+            ..
+        }] => Ok(col.name.clone()),
+        [FieldDefinitionExpr::Expr { expr, .. }] => Ok(expr.to_string().into()),
+        _ => invalid!("Subqueries used with NOT IN must select exactly one column"),
+    }
+}
+
 /// Convert the given `Expr`, which should be a set of AND-ed together direct
 /// comparison predicates, into a list of predicate expressions
 fn collect_join_predicates(cond: Expr, out: &mut Vec<JoinPredicate>) -> ReadySetResult<()> {
@@ -738,9 +863,81 @@ fn collect_join_predicates(cond: Expr, out: &mut Vec<JoinPredicate>) -> ReadySet
             Ok(())
         }
         _ => {
-            unsupported!("Only direct comparisons combined with AND supported for join conditions")
+            unsupported_feature!(
+                NonEquiJoin,
+                "Only direct comparisons combined with AND supported for join conditions"
+            )
+        }
+    }
+}
+
+/// Replace all references to the unqualified column named `from` in `expr` with references to the
+/// unqualified column named `to`
+fn rename_column(expr: &mut Expr, from: &SqlIdentifier, to: &SqlIdentifier) {
+    struct RenameColumn<'a> {
+        from: &'a SqlIdentifier,
+        to: &'a SqlIdentifier,
+    }
+
+    impl<'ast, 'a> VisitorMut<'ast> for RenameColumn<'a> {
+        type Error = !;
+
+        fn visit_column(&mut self, column: &'ast mut Column) -> Result<(), Self::Error> {
+            if column.table.is_none() && column.name == *self.from {
+                column.name = self.to.clone();
+            }
+            Ok(())
+        }
+    }
+
+    let Ok(()) = RenameColumn { from, to }.visit_expr(expr);
+}
+
+/// Returns the column that `expr`, an expression in the GROUP BY clause other than a bare column,
+/// is projected as before grouping
+fn group_by_expression_column(expr: &Expr) -> Column {
+    Column {
+        name: expr.to_string().into(),
+        table: None,
+    }
+}
+
+/// Replace all occurrences of any of `group_by_expressions` in `expr` (including `expr` itself)
+/// with references to the columns those expressions are projected as before grouping, since the
+/// columns the expressions refer to aren't available after grouping
+fn replace_group_by_expressions(expr: &mut Expr, group_by_expressions: &[Expr]) {
+    struct ReplaceGroupByExpressions<'a> {
+        group_by_expressions: &'a [Expr],
+    }
+
+    impl<'ast, 'a> VisitorMut<'ast> for ReplaceGroupByExpressions<'a> {
+        type Error = !;
+
+        fn visit_expr(&mut self, expr: &'ast mut Expr) -> Result<(), Self::Error> {
+            if self.group_by_expressions.contains(expr) {
+                *expr = Expr::Column(group_by_expression_column(expr));
+                Ok(())
+            } else {
+                walk_expr(self, expr)
+            }
+        }
+
+        fn visit_select_statement(
+            &mut self,
+            _: &'ast mut SelectStatement,
+        ) -> Result<(), Self::Error> {
+            // Don't walk into subqueries
+            Ok(())
         }
     }
+
+    if group_by_expressions.is_empty() {
+        return;
+    }
+    let Ok(()) = ReplaceGroupByExpressions {
+        group_by_expressions,
+    }
+    .visit_expr(expr);
 }
 
 /// Processes the provided HAVING expression by extracting aggregates, splitting predicates, and
@@ -803,7 +1000,7 @@ pub(crate) fn extract_limit_offset(
     limit_clause: &LimitClause,
 ) -> ReadySetResult<Option<(usize, Option<ViewPlaceholder>)>> {
     if limit_clause.limit().is_none() && limit_clause.offset().is_some() {
-        unsupported!("ReadySet does not support OFFSET without LIMIT");
+        unsupported_feature!(Pagination, "ReadySet does not support OFFSET without LIMIT");
     }
 
     let limit = if let Some(limit) = limit_clause.limit() {
@@ -817,7 +1014,10 @@ pub(crate) fn extract_limit_offset(
         Literal::Integer(val) => u64::try_from(*val)
             .map_err(|_| unsupported_err!("LIMIT field cannot have a negative value"))?,
         Literal::Placeholder(_) => {
-            unsupported!("ReadySet does not support parametrized LIMIT fields")
+            unsupported_feature!(
+                Pagination,
+                "ReadySet does not support parametrized LIMIT fields"
+            )
         }
         _ => unsupported!("Invalid LIMIT statement"),
     };
@@ -835,7 +1035,7 @@ pub(crate) fn extract_limit_offset(
                         limit,
                     })
                 }
-                _ => unsupported!("Numeric OFFSETs must be parametrized"),
+                _ => unsupported_feature!(Pagination, "Numeric OFFSETs must be parametrized"),
             }
         })
         .transpose()?;
@@ -877,6 +1077,7 @@ fn default_row_for_select(st: &SelectStatement) -> Option<Vec<DfValue>> {
                     FunctionExpr::Max(..) => DfValue::None,
                     FunctionExpr::Min(..) => DfValue::None,
                     FunctionExpr::GroupConcat { .. } => DfValue::None,
+                    FunctionExpr::PercentileCont { .. } => DfValue::None,
                     FunctionExpr::Call { .. } | FunctionExpr::Substring { .. } => DfValue::None,
                 },
                 _ => DfValue::None,
@@ -886,7 +1087,9 @@ fn default_row_for_select(st: &SelectStatement) -> Option<Vec<DfValue>> {
 }
 
 #[allow(clippy::cognitive_complexity)]
-pub fn to_query_graph(stmt: SelectStatement) -> ReadySetResult<QueryGraph> {
+pub fn to_query_graph(mut stmt: SelectStatement) -> ReadySetResult<QueryGraph> {
+    let anti_joins = extract_anti_joins(&mut stmt.where_clause)?;
+
     // a handy closure for making new relation nodes
     let new_node =
         |rel: Relation, preds: Vec<Expr>, st: &SelectStatement| -> ReadySetResult<QueryGraphNode> {
@@ -1006,10 +1209,9 @@ pub fn to_query_graph(stmt: SelectStatement) -> ReadySetResult<QueryGraph> {
 
     // 2a. Explicit joins
     // The table specified in the query is available for USING joins.
-    let prev_table =
-        table_expr_name(stmt.tables.last().ok_or_else(|| {
-            unsupported_err!("SELECT statements with no tables are unsupported")
-        })?)?;
+    let prev_table = table_expr_name(stmt.tables.last().ok_or_else(|| {
+        unsupported_feature_err!(NoTables, "SELECT statements with no tables are unsupported")
+    })?)?;
 
     for jc in &stmt.join {
         let rhs_relation = match &jc.right {
@@ -1059,11 +1261,19 @@ pub fn to_query_graph(stmt: SelectStatement) -> ReadySetResult<QueryGraph> {
                     // conditions for now.
                     let l = match &pred.left {
                         Expr::Column(f) => f,
-                        ref x => unsupported!("join condition not supported: {:?}", x),
+                        ref x => unsupported_feature!(
+                            NonEquiJoin,
+                            "join condition not supported: {:?}",
+                            x
+                        ),
                     };
                     let r = match &pred.right {
                         Expr::Column(f) => f,
-                        ref x => unsupported!("join condition not supported: {:?}", x),
+                        ref x => unsupported_feature!(
+                            NonEquiJoin,
+                            "join condition not supported: {:?}",
+                            x
+                        ),
                     };
                     if *l.table.as_ref().ok_or_else(|| no_table_for_col())? == right_table
                         && *r.table.as_ref().ok_or_else(|| no_table_for_col())? == left_table
@@ -1112,9 +1322,30 @@ pub fn to_query_graph(stmt: SelectStatement) -> ReadySetResult<QueryGraph> {
         }
     }
 
+    // 2b. Anti-joins, for `NOT IN` conditions with subqueries. Each subquery is added as its own
+    //     relation, anti-joined to the table of the column on the left-hand side of the `NOT IN`
+    for (i, (col, subquery)) in anti_joins.into_iter().enumerate() {
+        let rel = Relation::from(format!("__anti_join_{i}"));
+        #[allow(clippy::unwrap_used)] // checked by extract_anti_joins
+        let left_table = col.table.clone().unwrap();
+        if !relations.contains_key(&left_table) {
+            invalid!("Column {col} references non-existent table {left_table}");
+        }
+
+        let on = vec![JoinPredicate {
+            left: Expr::Column(col),
+            right: col_expr(&rel, &subquery_column_name(&subquery)?),
+        }];
+        let mut node = new_node(rel.clone(), vec![], &stmt)?;
+        node.subgraph = Some(Box::new(to_query_graph(subquery)?));
+        relations.insert(rel.clone(), node);
+        edges.insert((left_table, rel), QueryGraphEdge::AntiJoin { on });
+    }
+
     let mut local_predicates = HashMap::new();
     let mut global_predicates = Vec::new();
     let mut query_parameters = Vec::new();
+    let mut post_lookup_predicates = Vec::new();
     if let Some(ref cond) = stmt.where_clause {
         // Let's classify the predicates we have in the query
         classify_conditionals(
@@ -1124,6 +1355,7 @@ pub fn to_query_graph(stmt: SelectStatement) -> ReadySetResult<QueryGraph> {
             &mut join_predicates,
             &mut global_predicates,
             &mut query_parameters,
+            &mut post_lookup_predicates,
         )?;
 
         for (_, ces) in local_predicates.iter_mut() {
@@ -1201,17 +1433,69 @@ pub fn to_query_graph(stmt: SelectStatement) -> ReadySetResult<QueryGraph> {
                 rel.parameters.push(param.clone());
             }
         }
+
+        // 4. Add any columns referenced by post-lookup predicates, which must also appear in the
+        //    leaf node so that the reader can evaluate those predicates against its results
+        for col in post_lookup_predicates
+            .iter()
+            .flat_map(|p| p.referred_columns())
+        {
+            if let Some(table) = &col.table {
+                let rel = relations.get_mut(table).ok_or_else(|| {
+                    invalid_err!(
+                        "Column {} references non-existent table {}",
+                        col.name,
+                        table
+                    )
+                })?;
+                if !rel.columns.contains(col) {
+                    rel.columns.push(col.clone());
+                }
+            }
+        }
+    }
+
+    // Expressions in the GROUP BY clause other than bare columns are projected as columns before
+    // grouping, and grouped on by those columns
+    let mut group_by = HashSet::new();
+    let mut group_by_expressions = vec![];
+    if let Some(group_by_clause) = &stmt.group_by {
+        for field in &group_by_clause.fields {
+            match field {
+                FieldReference::Numeric(_) => {
+                    internal!("Numeric field references should have been removed")
+                }
+                FieldReference::Expr(Expr::Column(c)) => {
+                    group_by.insert(c.clone());
+                }
+                FieldReference::Expr(expr) if contains_aggregate(expr) => {
+                    unsupported_feature!(
+                        GroupByExpression,
+                        "Aggregates are not supported in GROUP BY"
+                    )
+                }
+                FieldReference::Expr(expr) => {
+                    group_by.insert(group_by_expression_column(expr));
+                    if !group_by_expressions.contains(expr) {
+                        group_by_expressions.push(expr.clone());
+                    }
+                }
+            }
+        }
     }
 
     // Add HAVING predicates and aggregates. Note that unlike below for selected columns, we don't
     // add any found aggregate functions in the HAVING clause to qg.columns, since we don't want to
     // necessarily return these in the query results.
     let mut aggregates = HashMap::new();
-    let having_predicates = if let Some(having_expr) = stmt.having.as_ref() {
+    let mut having_predicates = if let Some(having_expr) = stmt.having.as_ref() {
         extract_having_aggregates(having_expr, &mut aggregates)
     } else {
         vec![]
     };
+    for pred in &mut having_predicates {
+        replace_group_by_expressions(pred, &group_by_expressions);
+    }
 
     let mut columns = Vec::with_capacity(stmt.fields.len());
     for field in stmt.fields.iter() {
@@ -1255,8 +1539,25 @@ pub fn to_query_graph(stmt: SelectStatement) -> ReadySetResult<QueryGraph> {
                     }
                     _ => {
                         let mut expr = expr.clone();
-                        let aggs = map_aggregates(&mut expr);
-                        aggregates.extend(aggs);
+                        replace_group_by_expressions(&mut expr, &group_by_expressions);
+                        if let Expr::Column(column) = expr {
+                            // The whole expression appears in the GROUP BY clause, so it's already
+                            // been projected
+                            columns.push(OutputColumn::Data {
+                                alias: name,
+                                column,
+                            });
+                            continue;
+                        }
+                        for (agg, agg_col) in map_aggregates(&mut expr) {
+                            // If the aggregate already appears elsewhere in the query (such as
+                            // both in a field on its own and in the expansion of an `AVG`),
+                            // refer to it by the name it was already given
+                            let agg_name = aggregates.entry(agg).or_insert_with(|| agg_col.clone());
+                            if *agg_name != agg_col {
+                                rename_column(&mut expr, &agg_col, agg_name);
+                            }
+                        }
 
                         columns.push(OutputColumn::Expr(ExprColumn {
                             name,
@@ -1269,24 +1570,6 @@ pub fn to_query_graph(stmt: SelectStatement) -> ReadySetResult<QueryGraph> {
         }
     }
 
-    let group_by = if let Some(group_by_clause) = &stmt.group_by {
-        group_by_clause
-            .fields
-            .iter()
-            .map(|f| match f {
-                FieldReference::Numeric(_) => {
-                    internal!("Numeric field references should have been removed")
-                }
-                FieldReference::Expr(Expr::Column(c)) => Ok(c.clone()),
-                FieldReference::Expr(_) => {
-                    unsupported!("Only column references are currently supported in GROUP BY")
-                }
-            })
-            .collect::<ReadySetResult<HashSet<_>>>()?
-    } else {
-        Default::default()
-    };
-
     if let Some(ref order) = stmt.order {
         // For each column in the `ORDER BY` clause, check if it needs to be projected
         order
@@ -1323,12 +1606,36 @@ pub fn to_query_graph(stmt: SelectStatement) -> ReadySetResult<QueryGraph> {
                         .or_insert_with(|| func.to_string().into());
                 }
                 FieldReference::Expr(expr) => {
-                    // This is an expression that we need to add to the list of projected columns
-                    columns.push(OutputColumn::Expr(ExprColumn {
-                        name: expr.to_string().into(),
-                        table: None,
-                        expression: expr.clone(),
-                    }));
+                    // This is an expression that we need to add to the list of projected columns,
+                    // unless it's already projected
+                    let name: SqlIdentifier = expr.to_string().into();
+                    let mut expression = expr.clone();
+                    replace_group_by_expressions(&mut expression, &group_by_expressions);
+                    match expression {
+                        Expr::Column(column) => {
+                            let already_projected = columns.iter().any(|e| {
+                                matches!(e, OutputColumn::Data { column: c, .. } if *c == column)
+                            });
+                            if !already_projected {
+                                columns.push(OutputColumn::Data {
+                                    alias: name,
+                                    column,
+                                })
+                            }
+                        }
+                        expression => {
+                            if !columns
+                                .iter()
+                                .any(|e| matches!(e, OutputColumn::Expr(ec) if ec.name == name))
+                            {
+                                columns.push(OutputColumn::Expr(ExprColumn {
+                                    name,
+                                    table: None,
+                                    expression,
+                                }))
+                            }
+                        }
+                    }
                 }
                 // Numeric field references have already been projected, by definition
                 FieldReference::Numeric(_) => {}
@@ -1381,7 +1688,13 @@ pub fn to_query_graph(stmt: SelectStatement) -> ReadySetResult<QueryGraph> {
                                                 "Numeric field references should have been removed"
                                             )
                                         }
-                                        FieldReference::Expr(expr) => expr,
+                                        FieldReference::Expr(mut expr) => {
+                                            replace_group_by_expressions(
+                                                &mut expr,
+                                                &group_by_expressions,
+                                            );
+                                            expr
+                                        }
                                     },
                                     ot.unwrap_or(OrderType::OrderAscending),
                                 ))
@@ -1395,6 +1708,18 @@ pub fn to_query_graph(stmt: SelectStatement) -> ReadySetResult<QueryGraph> {
         })
         .transpose()?;
 
+    // Post-lookup predicates are evaluated against the rows in the reader, so they can't be
+    // combined with anything that has to be computed over the rows that match them
+    if !post_lookup_predicates.is_empty()
+        && (stmt.distinct || !aggregates.is_empty() || !group_by.is_empty() || pagination.is_some())
+    {
+        unsupported_feature!(
+            ParameterPosition,
+            "Placeholders in positions other than direct comparisons with columns are not \
+             supported in queries with DISTINCT, aggregates, GROUP BY, or LIMIT"
+        );
+    }
+
     // create initial join order
     let join_order = {
         let mut sorted_edges: Vec<(&(Relation, Relation), &QueryGraphEdge)> =
@@ -1417,12 +1742,14 @@ pub fn to_query_graph(stmt: SelectStatement) -> ReadySetResult<QueryGraph> {
         edges,
         aggregates,
         group_by,
+        group_by_expressions,
         columns,
         fields: stmt.fields.clone(),
         default_row: default_row_for_select(&stmt),
         join_order,
         global_predicates,
         having_predicates,
+        post_lookup_predicates,
         pagination,
         order,
         is_correlated: is_correlated(&stmt),
@@ -1434,7 +1761,9 @@ pub fn to_query_graph(stmt: SelectStatement) -> ReadySetResult<QueryGraph> {
 #[cfg(test)]
 mod tests {
     use assert_unordered::assert_eq_unordered;
-    use nom_sql::{parse_query, parse_select_statement, Dialect, FunctionExpr, SqlQuery};
+    use nom_sql::{
+        parse_expr, parse_query, parse_select_statement, Dialect, FunctionExpr, SqlQuery,
+    };
 
     use super::*;
 
@@ -1512,6 +1841,40 @@ mod tests {
         );
     }
 
+    #[test]
+    fn same_aggregate_in_field_and_expression() {
+        let qg = make_query_graph("SELECT sum(t.x) AS s, sum(t.x) / count(t.x) AS a FROM t");
+        assert_eq!(
+            qg.aggregates
+                .get(&FunctionExpr::Sum {
+                    expr: Box::new(Expr::Column("t.x".into())),
+                    distinct: false
+                })
+                .unwrap(),
+            "s"
+        );
+        assert_eq!(
+            qg.columns[1],
+            OutputColumn::Expr(ExprColumn {
+                name: "a".into(),
+                table: None,
+                expression: Expr::BinaryOp {
+                    lhs: Box::new(Expr::Column("s".into())),
+                    op: BinaryOperator::Divide,
+                    rhs: Box::new(Expr::Column(Column {
+                        name: FunctionExpr::Count {
+                            expr: Box::new(Expr::Column("t.x".into())),
+                            distinct: false,
+                        }
+                        .to_string()
+                        .into(),
+                        table: None,
+                    })),
+                },
+            })
+        );
+    }
+
     #[test]
     fn having_predicates_and_aggregates() {
         let qg = make_query_graph("select t.x from t having t.x > 2;");
@@ -1636,6 +1999,156 @@ mod tests {
         );
     }
 
+    #[test]
+    fn group_by_expression() {
+        let qg = make_query_graph(
+            "SELECT date(t.created_at), count(t.id) FROM t \
+             GROUP BY date(t.created_at) ORDER BY date(t.created_at)",
+        );
+
+        let date = parse_expr(Dialect::MySQL, "date(t.created_at)").unwrap();
+        let date_column = Column {
+            name: "date(`t`.`created_at`)".into(),
+            table: None,
+        };
+        assert_eq!(qg.group_by_expressions, vec![date]);
+        assert_eq!(qg.group_by, HashSet::from([date_column.clone()]));
+        assert_eq!(
+            qg.columns,
+            vec![
+                OutputColumn::Data {
+                    alias: "date(`t`.`created_at`)".into(),
+                    column: date_column.clone()
+                },
+                OutputColumn::Data {
+                    alias: "count(`t`.`id`)".into(),
+                    column: Column {
+                        name: "count(`t`.`id`)".into(),
+                        table: None
+                    }
+                }
+            ]
+        );
+        assert_eq!(
+            qg.order,
+            Some(vec![(date_column, OrderType::OrderAscending)])
+        );
+    }
+
+    #[test]
+    fn expressions_over_group_by_expressions() {
+        let qg = make_query_graph(
+            "SELECT lower(t.name) AS l, length(lower(t.name)) AS n FROM t \
+             GROUP BY lower(t.name) HAVING lower(t.name) != 'x'",
+        );
+
+        let lower_column = Expr::Column(Column {
+            name: "lower(`t`.`name`)".into(),
+            table: None,
+        });
+        assert_eq!(
+            qg.columns[1],
+            OutputColumn::Expr(ExprColumn {
+                name: "n".into(),
+                table: None,
+                expression: Expr::Call(FunctionExpr::Call {
+                    name: "length".into(),
+                    arguments: vec![lower_column.clone()]
+                })
+            })
+        );
+        assert_eq!(
+            qg.having_predicates,
+            vec![Expr::BinaryOp {
+                lhs: Box::new(lower_column),
+                op: BinaryOperator::NotEqual,
+                rhs: Box::new(Expr::Literal("x".into()))
+            }]
+        );
+    }
+
+    #[test]
+    fn order_by_expression_is_projected_once() {
+        let qg = make_query_graph("SELECT lower(t.name) FROM t ORDER BY lower(t.name)");
+        assert_eq!(qg.columns.len(), 1);
+    }
+
+    #[test]
+    fn aggregates_in_group_by_are_unsupported() {
+        let query = parse_select_statement(Dialect::MySQL, "SELECT t.a FROM t GROUP BY count(t.b)")
+            .unwrap();
+        to_query_graph(query).unwrap_err();
+    }
+
+    #[test]
+    fn post_lookup_predicates() {
+        let qg = make_query_graph(
+            "SELECT t.x FROM t WHERE t.x = $1 AND round(t.y, $2) > $3 AND (t.z = $4 OR t.z = 1)",
+        );
+        assert_eq!(
+            qg.post_lookup_predicates,
+            vec![
+                parse_expr(Dialect::MySQL, "round(t.y, $2) > $3").unwrap(),
+                parse_expr(Dialect::MySQL, "t.z = $4 OR t.z = 1").unwrap(),
+            ]
+        );
+        assert_eq!(qg.parameters().len(), 1);
+        let rel = &qg.relations[&Relation::from("t")];
+        assert!(rel.columns.contains(&Column::from("t.y")));
+        assert!(rel.columns.contains(&Column::from("t.z")));
+    }
+
+    #[test]
+    fn non_key_comparisons_are_post_lookup_predicates() {
+        let qg = make_query_graph(
+            "SELECT t.x FROM t WHERE t.x = $1 AND t.status != $2 AND t.y NOT LIKE $3",
+        );
+        assert_eq!(
+            qg.post_lookup_predicates,
+            vec![
+                parse_expr(Dialect::MySQL, "t.status != $2").unwrap(),
+                parse_expr(Dialect::MySQL, "t.y NOT LIKE $3").unwrap(),
+            ]
+        );
+        assert_eq!(qg.parameters().len(), 1);
+        qg.view_key(&Default::default()).unwrap();
+    }
+
+    #[test]
+    fn not_in_subquery_is_anti_join() {
+        let qg = make_query_graph(
+            "SELECT t1.x FROM t1 WHERE t1.y > 1 AND t1.x NOT IN (SELECT t2.x FROM t2)",
+        );
+        assert_eq!(
+            qg.relations.keys().cloned().collect::<HashSet<_>>(),
+            HashSet::from(["t1".into(), "__anti_join_0".into()])
+        );
+        let edge = &qg.edges[&("t1".into(), "__anti_join_0".into())];
+        assert_eq!(
+            *edge,
+            QueryGraphEdge::AntiJoin {
+                on: vec![JoinPredicate {
+                    left: Expr::Column("t1.x".into()),
+                    right: Expr::Column("__anti_join_0.x".into()),
+                }]
+            }
+        );
+        assert_eq!(
+            qg.relations[&Relation::from("t1")].predicates,
+            vec![parse_expr(Dialect::MySQL, "t1.y > 1").unwrap()]
+        );
+    }
+
+    #[test]
+    fn post_lookup_predicates_with_aggregates() {
+        let query = parse_select_statement(
+            Dialect::MySQL,
+            "SELECT count(t.x) FROM t WHERE round(t.y, $1) > $2",
+        )
+        .unwrap();
+        to_query_graph(query).unwrap_err();
+    }
+
     mod view_key {
         use super::*;
 
@@ -1666,6 +2179,21 @@ mod tests {
             )
         }
 
+        #[test]
+        fn one_to_one_null_safe_equal_key() {
+            let qg = make_query_graph("SELECT t.x FROM t WHERE t.x <=> $1");
+            let key = qg.view_key(&Default::default()).unwrap();
+
+            assert_eq!(key.index_type, IndexType::HashMap);
+            assert_eq!(
+                key.columns,
+                vec![(
+                    mir::Column::new(Some("t"), "x"),
+                    ViewPlaceholder::OneToOne(1)
+                )]
+            )
+        }
+
         #[test]
         fn double_equality_same_column() {
             let qg = make_query_graph("SELECT t.x FROM t WHERE t.x = $1 AND t.x = $2");
@@ -1803,6 +2331,32 @@ mod tests {
             );
         }
 
+        #[test]
+        fn mixed_inclusive_and_null_safe_equal() {
+            let qg = make_query_graph("SELECT t.x FROM t WHERE t.x >= $1 AND t.y <=> $2");
+            let key = qg
+                .view_key(&mir::Config {
+                    allow_mixed_comparisons: true,
+                    ..Default::default()
+                })
+                .unwrap();
+
+            assert_eq!(key.index_type, IndexType::BTreeMap);
+            assert_eq!(
+                key.columns,
+                vec![
+                    (
+                        mir::Column::new(Some("t"), "y"),
+                        ViewPlaceholder::OneToOne(2)
+                    ),
+                    (
+                        mir::Column::new(Some("t"), "x"),
+                        ViewPlaceholder::OneToOne(1)
+                    ),
+                ]
+            );
+        }
+
         #[test]
         fn mixed_opposite_ranges() {
             let qg =
@@ -1,11 +1,13 @@
 use std::str;
 use std::vec::Vec;
 
+use mir::plan::MirPlan;
 use nom_sql::{
     CacheInner, CreateCacheStatement, CreateTableBody, CreateTableStatement, CreateViewStatement,
-    Relation, SqlQuery,
+    Relation, SqlIdentifier, SqlQuery,
 };
 use petgraph::graph::NodeIndex;
+use readyset_client::namespace::namespace_of;
 use readyset_client::recipe::changelist::ChangeList;
 use readyset_client::ViewCreateRequest;
 use readyset_data::Dialect;
@@ -61,11 +63,29 @@ impl Recipe {
                 name,
                 statement,
                 always,
-            } => SqlQuery::CreateCache(CreateCacheStatement {
-                name: Some(name.clone()),
-                inner: CacheInner::Statement(Box::new(statement.clone())),
-                always: *always,
-            }),
+                approximate_aggregates,
+            } => {
+                // Caches created under an alias for an existing cache keep the options they were
+                // created with under the alias
+                let options = self
+                    .inc
+                    .cache_options(name)
+                    .or_else(|| self.inc.cache_options(alias));
+                SqlQuery::CreateCache(CreateCacheStatement {
+                    name: Some(name.clone()),
+                    inner: CacheInner::Statement(Box::new(statement.clone())),
+                    always: *always,
+                    filter_pushdown: options.map_or(true, |o| o.filter_pushdown),
+                    approximate_aggregates: *approximate_aggregates,
+                    lazy_joins: options.map_or(false, |o| o.lazy_joins),
+                    reader_placement: vec![],
+                    concurrently: false,
+                    max_staleness: options.and_then(|o| o.max_staleness),
+                    bucket_retention: options.and_then(|o| o.bucket_retention),
+                    freshness: options.map(|o| o.freshness).unwrap_or_default(),
+                    result_limits: options.map(|o| o.result_limits).unwrap_or_default(),
+                })
+            }
         });
         if expr.is_none() {
             warn!(%alias, "Query not found in expression registry");
@@ -125,6 +145,37 @@ impl Recipe {
         self.inc.registry.cache_names()
     }
 
+    /// Returns the names of all caches in the recipe (not including aliases), along with the
+    /// namespace each cache belongs to
+    pub(in crate::controller) fn cache_namespaces(
+        &self,
+    ) -> impl Iterator<Item = (&Relation, SqlIdentifier)> + '_ {
+        self.cache_names()
+            .filter_map(|name| match self.inc.registry.get(name)? {
+                // Cache statements are stored after rewriting, so all their tables are qualified
+                RecipeExpr::Cache { statement, .. } => Some((name, namespace_of(statement, &[])?)),
+                _ => None,
+            })
+    }
+
+    /// Returns the namespace that a new cache for `query` would belong to, or `None` if the query
+    /// doesn't read from any tables, can't be rewritten, or if the recipe already contains an
+    /// equivalent cache (in which case no new cache would be created for it)
+    pub(in crate::controller) fn namespace_for_new_cache(
+        &self,
+        query: ViewCreateRequest,
+        dialect: Dialect,
+    ) -> Option<SqlIdentifier> {
+        let statement = self
+            .inc
+            .rewrite(query.statement, &query.schema_search_path, dialect, None)
+            .ok()?;
+        if self.inc.registry.contains(&statement) {
+            return None;
+        }
+        namespace_of(&statement, &query.schema_search_path)
+    }
+
     /// Obtains the `NodeIndex` for the node corresponding to a named query or a write type.
     pub(in crate::controller) fn node_addr_for(
         &self,
@@ -165,6 +216,23 @@ impl Recipe {
         self.inc.apply_changelist(changelist, mig)
     }
 
+    /// Returns the MIR plan for the cached query with the given name (or alias).
+    pub(crate) fn mir_plan(&self, name: &Relation) -> ReadySetResult<MirPlan> {
+        self.inc.mir_plan(name)
+    }
+
+    /// Replaces the dataflow for the cached query with the given name (or alias) with dataflow
+    /// lowered from the given MIR plan. See [`SqlIncorporator::set_mir_plan`] for more
+    /// information.
+    pub(crate) fn set_mir_plan(
+        &mut self,
+        mig: &mut Migration<'_>,
+        name: &Relation,
+        plan: MirPlan,
+    ) -> ReadySetResult<()> {
+        self.inc.set_mir_plan(name, plan, mig)
+    }
+
     /// Helper method to reparent a recipe. This is needed for some of t
     pub(crate) fn sql_inc(&self) -> &SqlIncorporator {
         &self.inc
@@ -185,6 +253,25 @@ impl Recipe {
         Ok(self.inc.registry.contains(&statement))
     }
 
+    /// Returns `true` if, after rewriting according to `dialect`, the given `query` is
+    /// semantically equivalent to the query cached under the given name (or alias).
+    ///
+    /// Returns an error if rewriting fails for any reason
+    pub(crate) fn cache_matches(
+        &self,
+        name: &Relation,
+        query: ViewCreateRequest,
+        dialect: Dialect,
+    ) -> ReadySetResult<bool> {
+        let statement =
+            self.inc
+                .rewrite(query.statement, &query.schema_search_path, dialect, None)?;
+        Ok(match self.inc.registry.name_of(&statement) {
+            Some(cache) => self.resolve_alias(name) == Some(cache),
+            None => false,
+        })
+    }
+
     /// Returns the MatchedCaches for the query if they exists.
     pub fn reused_caches(&self, name: &Relation) -> Option<&Vec1<MatchedCache>> {
         self.inc.registry.reused_caches(name)
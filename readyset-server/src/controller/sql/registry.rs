@@ -36,6 +36,10 @@ pub(crate) enum RecipeExpr {
         name: Relation,
         statement: SelectStatement,
         always: bool,
+        /// Whether aggregates in the query are estimated rather than computed exactly. Exact and
+        /// approximate caches of the same query are different expressions.
+        #[serde(default)]
+        approximate_aggregates: bool,
     },
 }
 
@@ -133,7 +137,8 @@ impl RecipeExpr {
 
     /// Calculates a SHA-1 hash of the [`RecipeExpr`], to identify it based on its contents.
     pub(super) fn calculate_hash(&self) -> QueryID {
-        // NOTE: this has to be the same as `<SelectStatement as RegistryExpr>::query_id`
+        // NOTE: this has to be the same as `<SelectStatement as RegistryExpr>::query_id` for
+        // exact caches
         use sha1::{Digest, Sha1};
         let mut hasher = Sha1::new();
         match self {
@@ -145,7 +150,18 @@ impl RecipeExpr {
                 hasher.update(hash(name).to_le_bytes());
                 hasher.update(hash(definition).to_le_bytes());
             }
-            RecipeExpr::Cache { statement, .. } => hasher.update(hash(statement).to_le_bytes()),
+            RecipeExpr::Cache {
+                statement,
+                approximate_aggregates,
+                ..
+            } => {
+                hasher.update(hash(statement).to_le_bytes());
+                // Only hashed for approximate caches, so that the hash of an exact cache stays the
+                // same as the hash of its statement
+                if *approximate_aggregates {
+                    hasher.update(b"approximate_aggregates");
+                }
+            }
         };
         // Sha1 digest is 20 byte long, so it is safe to consume only 16 bytes
         u128::from_le_bytes(hasher.finalize()[..16].try_into().unwrap())
@@ -452,6 +468,17 @@ impl ExprRegistry {
         self.expressions.contains_key(&expression.query_id())
     }
 
+    /// Returns the original name of the expression equal to the given expression, if it exists in
+    /// `self`
+    pub(super) fn name_of<E>(&self, expression: &E) -> Option<&Relation>
+    where
+        E: RegistryExpr,
+    {
+        self.expressions
+            .get(&expression.query_id())
+            .map(RecipeExpr::name)
+    }
+
     /// Retrieves the original name for the query with the given `alias` (which might already be the
     /// original name). Returns `None` is there no [`RecipeExpr`] associated with the
     /// given `alias`.
@@ -693,7 +720,7 @@ impl RegistryExpr for RecipeExpr {
 
 impl RegistryExpr for SelectStatement {
     fn query_id(&self) -> QueryID {
-        // NOTE: this has to be the same as `RecipeExpr::calculate_hash`
+        // NOTE: this has to be the same as `RecipeExpr::calculate_hash` for exact caches
         use sha1::{Digest, Sha1};
         let mut hasher = Sha1::new();
         hasher.update(hash(self).to_le_bytes());
@@ -727,6 +754,7 @@ mod tests {
                 statement: parse_select_statement(Dialect::MySQL, "SELECT * FROM test_table;")
                     .unwrap(),
                 always: false,
+                approximate_aggregates: false,
             };
 
             assert_eq!(cached_query.name(), &query_name);
@@ -757,6 +785,7 @@ mod tests {
                 statement: parse_select_statement(Dialect::MySQL, "SELECT * FROM test_table;")
                     .unwrap(),
                 always: false,
+                approximate_aggregates: false,
             };
 
             let cached_query_table_refs = cached_query.table_references();
@@ -801,6 +830,7 @@ mod tests {
                     name: "test_query".into(),
                     statement: statement.clone(),
                     always: false,
+                    approximate_aggregates: false,
                 })
                 .unwrap();
             registry
@@ -808,6 +838,7 @@ mod tests {
                     name: "test_query_alias".into(),
                     statement,
                     always: false,
+                    approximate_aggregates: false,
                 })
                 .unwrap();
 
@@ -818,6 +849,7 @@ mod tests {
                     name: "test_query".into(),
                     statement: statement.clone(),
                     always: false,
+                    approximate_aggregates: false,
                 })
                 .unwrap();
             registry
@@ -825,6 +857,7 @@ mod tests {
                     name: "test_query_alias".into(),
                     statement,
                     always: false,
+                    approximate_aggregates: false,
                 })
                 .unwrap();
 
@@ -861,6 +894,7 @@ mod tests {
                 )
                 .unwrap(),
                 always: false,
+                approximate_aggregates: false,
             };
 
             assert!(registry.add_query(expr.clone()).unwrap());
@@ -878,6 +912,7 @@ mod tests {
                 statement: parse_select_statement(Dialect::MySQL, "SELECT * FROM test_table;")
                     .unwrap(),
                 always: false,
+                approximate_aggregates: false,
             };
             assert!(!registry.add_query(expr).unwrap());
 
@@ -888,11 +923,34 @@ mod tests {
                     name: "test_query".into(),
                     statement: parse_select_statement(Dialect::MySQL, "SELECT * FROM test_table;")
                         .unwrap(),
-                    always: false
+                    always: false,
+                    approximate_aggregates: false,
                 }
             );
         }
 
+        #[test]
+        fn add_approximate_cached_query() {
+            let mut registry = setup();
+
+            let expr = RecipeExpr::Cache {
+                name: "test_query_approximate".into(),
+                statement: parse_select_statement(Dialect::MySQL, "SELECT * FROM test_table;")
+                    .unwrap(),
+                always: false,
+                approximate_aggregates: true,
+            };
+            // The exact cache of the same query doesn't make this an alias for it
+            assert!(registry.add_query(expr.clone()).unwrap());
+
+            let result = registry.get(&"test_query_approximate".into()).unwrap();
+            assert_eq!(*result, expr);
+            assert_ne!(
+                registry.get(&"test_query".into()).unwrap().calculate_hash(),
+                expr.calculate_hash()
+            );
+        }
+
         #[test]
         fn add_view() {
             let mut registry = setup();
@@ -1027,7 +1085,8 @@ mod tests {
                     name: "test_query".into(),
                     statement: parse_select_statement(Dialect::MySQL, "SELECT * FROM test_table")
                         .unwrap(),
-                    always: false
+                    always: false,
+                    approximate_aggregates: false,
                 }
             );
             assert!(registry.get(&"test_query_alias".into()).is_none())
@@ -1073,6 +1132,7 @@ mod tests {
                     name: "test".into(),
                     statement: stmt.clone(),
                     always: false,
+                    approximate_aggregates: false,
                 })
                 .unwrap();
             assert!(registry.contains(&stmt))
@@ -1103,6 +1163,7 @@ mod tests {
                     statement: parse_select_statement(Dialect::MySQL, "SELECT * FROM test_table")
                         .unwrap(),
                     always: false,
+                    approximate_aggregates: false,
                 })
                 .unwrap();
 
@@ -1189,7 +1250,8 @@ mod tests {
                 .add_query(RecipeExpr::Cache {
                     name: "foo".into(),
                     statement: query.clone(),
-                    always: false
+                    always: false,
+                    approximate_aggregates: false,
                 })
                 .unwrap());
 
@@ -1353,6 +1415,7 @@ mod tests {
                     name: "test_query".into(),
                     statement: statement.clone(),
                     always: false,
+                    approximate_aggregates: false,
                 })
                 .unwrap();
 
@@ -1375,6 +1438,7 @@ mod tests {
                     name: "alias".into(),
                     statement,
                     always: false,
+                    approximate_aggregates: false,
                 })
                 .unwrap();
 
@@ -1416,6 +1480,7 @@ mod tests {
                     name: "query1".into(),
                     statement: statement1.clone(),
                     always: false,
+                    approximate_aggregates: false,
                 })
                 .unwrap();
 
@@ -1424,6 +1489,7 @@ mod tests {
                     name: "query1_alias".into(),
                     statement: statement1,
                     always: false,
+                    approximate_aggregates: false,
                 })
                 .unwrap();
 
@@ -1432,6 +1498,7 @@ mod tests {
                     name: "query2".into(),
                     statement: statement2,
                     always: false,
+                    approximate_aggregates: false,
                 })
                 .unwrap();
 
@@ -18,11 +18,14 @@ use std::collections::{BTreeMap, HashMap, HashSet};
 use std::net::SocketAddr;
 use std::ops::Deref;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime};
 
 use array2::Array2;
 use common::IndexPair;
-use dataflow::prelude::{ChannelCoordinator, DomainIndex, DomainNodes, Graph, NodeIndex};
+use dataflow::payload::SourceChannelIdentifier;
+use dataflow::prelude::{
+    ChannelCoordinator, ColumnRef, ColumnSource, DomainIndex, DomainNodes, Graph, Index, NodeIndex,
+};
 use dataflow::{
     DomainBuilder, DomainConfig, DomainRequest, NodeMap, Packet, PersistenceParameters, Sharding,
 };
@@ -30,27 +33,32 @@ use futures::stream::{self, StreamExt, TryStreamExt};
 use futures::{FutureExt, TryStream};
 use lazy_static::lazy_static;
 use metrics::{gauge, histogram};
+use mir::plan::MirPlan;
 use nom_sql::{
     CacheInner, CreateCacheStatement, Relation, SelectStatement, SqlIdentifier, SqlQuery,
 };
-use petgraph::visit::Bfs;
+use petgraph::visit::{Bfs, Reversed};
 use readyset_client::builders::{
     ReaderHandleBuilder, ReusedReaderHandleBuilder, TableBuilder, ViewBuilder,
 };
 use readyset_client::consensus::{Authority, AuthorityControl};
 use readyset_client::debug::info::GraphInfo;
-use readyset_client::debug::stats::{DomainStats, GraphStats, NodeStats};
+use readyset_client::debug::provenance::RowProvenance;
+use readyset_client::debug::stats::{DomainStats, GraphStats, NodeStats, ReadStatsBucket};
 use readyset_client::internal::{MaterializationStatus, ReplicaAddress};
 use readyset_client::metrics::recorded;
+use readyset_client::namespace::{NamespaceLimit, NamespaceQuotas};
+use readyset_client::placement::{PlacementConstraints, WorkerConstraint};
 use readyset_client::recipe::changelist::{Change, ChangeList};
 use readyset_client::recipe::ExtendRecipeSpec;
 use readyset_client::replication::{ReplicationOffset, ReplicationOffsets};
 use readyset_client::{
-    NodeSize, ReadySetError, ReadySetResult, TableReplicationStatus, TableStatus,
-    ViewCreateRequest, ViewFilter, ViewRequest, ViewSchema,
+    ColumnSchema, NodeSize, PacketData, PacketPayload, ReadySetError, ReadySetResult,
+    TableReplicationStatus, TableStatus, ViewCreateRequest, ViewFilter, ViewRequest, ViewSchema,
+    ViewStats,
 };
-use readyset_data::Dialect;
-use readyset_errors::{internal, internal_err, invariant_eq, NodeType};
+use readyset_data::{DfType, DfValue, Dialect};
+use readyset_errors::{internal, internal_err, invalid_err, invariant_eq, NodeType};
 use readyset_tracing::{debug, error, trace, warn};
 use regex::Regex;
 use serde::de::DeserializeOwned;
@@ -63,13 +71,14 @@ use super::migrate::DomainSettings;
 use super::replication::ReplicationStrategy;
 use super::sql::Recipe;
 use crate::controller::domain_handle::DomainHandle;
+use crate::controller::migrate::backfill::{Backfill, Backfills};
 use crate::controller::migrate::materialization::Materializations;
 use crate::controller::migrate::scheduling::Scheduler;
 use crate::controller::migrate::{routing, DomainMigrationPlan, Migration};
 use crate::controller::sql::Schema;
 use crate::controller::{
-    schema, ControllerState, DomainPlacementRestriction, NodeRestrictionKey, Worker,
-    WorkerIdentifier,
+    canonical_plan, schema, ControllerState, DomainPlacementRestriction, NodeRestrictionKey,
+    Worker, WorkerIdentifier,
 };
 use crate::coordination::{DomainDescriptor, RunDomainResponse};
 use crate::internal::LocalNodeIndex;
@@ -77,7 +86,7 @@ use crate::worker::WorkerRequestKind;
 
 /// Number of concurrent requests to make when making multiple simultaneous requests to domains (eg
 /// for replication offsets)
-const CONCURRENT_REQUESTS: usize = 16;
+pub(super) const CONCURRENT_REQUESTS: usize = 16;
 
 /// This structure holds all the dataflow state.
 /// It's meant to be handled exclusively by the [`DfStateHandle`], which is the structure
@@ -121,6 +130,14 @@ pub struct DfState {
     pub(super) domain_nodes: HashMap<DomainIndex, NodeMap<NodeIndex>>,
     #[serde(skip)]
     pub(super) channel_coordinator: Arc<ChannelCoordinator>,
+    /// The locks taken by backfills and full replays, and the results of backfills, which are
+    /// shared by every copy of the state
+    #[serde(skip)]
+    pub(super) backfills: Arc<Backfills>,
+    /// The last value generated for the `AUTO_INCREMENT` column of each base table, which is
+    /// shared by every copy of the state. See [`DfState::generate_auto_increments`].
+    #[serde(skip)]
+    pub(super) auto_increments: Arc<std::sync::Mutex<HashMap<NodeIndex, u64>>>,
 
     /// Map from worker URI to the address the worker is listening on for reads.
     #[serde(skip)]
@@ -137,6 +154,33 @@ pub struct DfState {
     /// such as logictests where we may OOM from the recipe size.
     // TODO(ENG-838): Remove when dataflow state does not keep entire recipe chain.
     keep_prior_recipes: bool,
+
+    /// Limits on the caches in each namespace, enforced when extending the recipe
+    #[serde(default)]
+    pub(super) namespace_quotas: NamespaceQuotas,
+
+    /// Constraints on which workers domains containing readers or base tables may be scheduled
+    /// onto, applied to every migration
+    #[serde(default)]
+    pub(super) placement_constraints: PlacementConstraints,
+
+    /// Constraints on which workers each domain may be scheduled onto, in addition to
+    /// [`Self::placement_constraints`], as given by the migration which created the domain. Kept
+    /// so that the constraints still apply when the domain is rescheduled.
+    #[serde(default, with = "serde_with::rust::hashmap_as_tuple_list")]
+    pub(super) domain_placement_constraints: HashMap<DomainIndex, PlacementConstraints>,
+
+    /// Workers which have been drained, and so must not be scheduled onto again even if they
+    /// re-register (for example after a change of leader). A worker is removed from this set once
+    /// its registration with the authority expires, since a new process may then be started at the
+    /// same address.
+    #[serde(default)]
+    pub(super) drained_workers: HashSet<WorkerIdentifier>,
+
+    /// Whether replication from the upstream database has been paused, and should stay paused
+    /// until it's explicitly resumed (even across a change of leader)
+    #[serde(default)]
+    pub(super) replication_paused: bool,
 }
 
 impl DfState {
@@ -170,11 +214,18 @@ impl DfState {
             domains: Default::default(),
             domain_nodes: Default::default(),
             channel_coordinator,
+            backfills: Default::default(),
+            auto_increments: Default::default(),
             read_addrs: Default::default(),
             workers: Default::default(),
             remap: Default::default(),
             keep_prior_recipes,
             replication_strategy,
+            namespace_quotas: Default::default(),
+            placement_constraints: Default::default(),
+            domain_placement_constraints: Default::default(),
+            drained_workers: Default::default(),
+            replication_paused: false,
         }
     }
 
@@ -292,6 +343,44 @@ impl DfState {
             .collect()
     }
 
+    /// Returns the `CREATE CACHE` statements for all the caches in the recipe, ordered by name.
+    ///
+    /// Replaying these statements against another deployment (with the same tables) recreates the
+    /// same set of caches, with the same names and settings.
+    pub(super) fn cache_definitions(&self) -> Vec<CreateCacheStatement> {
+        let mut names = self.recipe.cache_names().collect::<Vec<_>>();
+        names.sort();
+        names
+            .into_iter()
+            .filter_map(|name| match self.recipe.expression_by_alias(name)? {
+                SqlQuery::CreateCache(stmt) => Some(stmt),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns the name and JSON-serialized MIR plan of every cache in the recipe whose plan has
+    /// been replaced via [`Self::set_mir_plan`], ordered by name.
+    ///
+    /// Replaying these plans after the statements returned by [`Self::cache_definitions`] recreates
+    /// the same dataflow for those caches.
+    pub(super) fn mir_plan_overrides(&self) -> ReadySetResult<Vec<(Relation, String)>> {
+        let mut overrides = self
+            .recipe
+            .sql_inc()
+            .mir_plan_overrides()
+            .map(|(name, plan)| {
+                Ok((
+                    name.clone(),
+                    serde_json::to_string(plan)
+                        .map_err(|e| internal_err!("Could not serialize MIR plan: {e}"))?,
+                ))
+            })
+            .collect::<ReadySetResult<Vec<_>>>()?;
+        overrides.sort_by(|(n1, _), (n2, _)| n1.cmp(n2));
+        Ok(overrides)
+    }
+
     pub(super) fn view_statuses(
         &self,
         queries: Vec<ViewCreateRequest>,
@@ -303,6 +392,17 @@ impl DfState {
             .collect()
     }
 
+    /// Returns `true` if the given query is semantically equivalent to the query cached under the
+    /// given name
+    pub(super) fn query_matches_cache(
+        &self,
+        name: &Relation,
+        query: ViewCreateRequest,
+        dialect: Dialect,
+    ) -> ReadySetResult<bool> {
+        self.recipe.cache_matches(name, query, dialect)
+    }
+
     pub(super) fn find_reader_for(
         &self,
         node: NodeIndex,
@@ -369,18 +469,20 @@ impl DfState {
                 expected_type: NodeType::Reader,
             }
         })?;
-        #[allow(clippy::indexing_slicing)] // `find_readers_for` returns valid indices
+        let output_columns = self.reader_output_columns(reader_node);
         let returned_cols = reader
             .reader_processing()
             .post_processing
             .returned_cols
             .clone()
-            .unwrap_or_else(|| (0..self.ingredients[reader_node].columns().len()).collect());
-        #[allow(clippy::indexing_slicing)] // just came from self
-        let columns = self.ingredients[reader_node].columns();
+            .unwrap_or_else(|| (0..output_columns.len()).collect());
         let columns = returned_cols
             .iter()
-            .map(|idx| columns.get(*idx).map(|c| c.name().into()))
+            .map(|idx| {
+                let &(node, col) = output_columns.get(*idx)?;
+                let column = self.ingredients.node_weight(node)?.columns().get(col)?;
+                Some(column.name().into())
+            })
             .collect::<Option<Vec<_>>>()
             .ok_or_else(|| internal_err!("Schema expects valid column indices"))?;
 
@@ -410,6 +512,22 @@ impl DfState {
                     .collect::<ReadySetResult<Vec<_>>>()
             })
             .collect::<ReadySetResult<Vec<_>>>()?;
+        // The zone each replica is running in, so that clients can prefer to read from replicas
+        // in their own zone. A replica is only in a zone if all of its shards are running there.
+        let replica_zones = (0..domain.num_replicas())
+            .map(|replica| {
+                let shard_zones = (0..domain.num_shards())
+                    .map(|shard| {
+                        let worker = domain.assignment(shard, replica)?;
+                        Ok(self.workers.get(worker).and_then(|worker| {
+                            worker.domain_scheduling_config.resources.zone.clone()
+                        }))
+                    })
+                    .collect::<ReadySetResult<Vec<_>>>()?;
+                let zone = shard_zones.first().cloned().flatten();
+                Ok(zone.filter(|zone| shard_zones.iter().all(|z| z.as_ref() == Some(zone))))
+            })
+            .collect::<ReadySetResult<Vec<_>>>()?;
 
         Ok(Some(ReaderHandleBuilder {
             name: name.clone(),
@@ -417,8 +535,10 @@ impl DfState {
             columns: columns.into(),
             schema,
             replica_shard_addrs: Array2::from_rows(replicas),
+            replica_zones,
             key_mapping,
             view_request_timeout: self.domain_config.view_request_timeout,
+            freshness: reader.freshness(),
         }))
     }
 
@@ -498,6 +618,24 @@ impl DfState {
         }
     }
 
+    /// Returns the node and column index that each column of the rows read from the given reader
+    /// comes from: all the columns of the reader itself, followed by the columns appended to them
+    /// from the dimension readers of its [`LazyJoin`](dataflow::LazyJoin)s
+    fn reader_output_columns(&self, reader_node: NodeIndex) -> Vec<(NodeIndex, usize)> {
+        let Some(node) = self.ingredients.node_weight(reader_node) else {
+            return vec![];
+        };
+        let lazy_joins = node.as_reader().map(|r| r.lazy_joins()).unwrap_or_default();
+        (0..node.columns().len())
+            .map(|col| (reader_node, col))
+            .chain(
+                lazy_joins
+                    .iter()
+                    .flat_map(|join| join.emit.iter().map(move |&col| (join.dimension.node, col))),
+            )
+            .collect()
+    }
+
     pub(super) fn view_schema(
         &self,
         view_ni: NodeIndex,
@@ -514,22 +652,37 @@ impl DfState {
                 node_index: n.local_addr().id(),
                 expected_type: NodeType::Reader,
             })?;
+        let output_columns = self.reader_output_columns(view_ni);
         let returned_cols = reader
             .reader_processing()
             .post_processing
             .returned_cols
             .clone()
-            .unwrap_or_else(|| (0..n.columns().len()).collect());
+            .unwrap_or_else(|| (0..output_columns.len()).collect());
+
+        // Columns appended by lazy joins are traced from the dimension reader they come from, but
+        // are reported as columns of this reader
+        let column_schema = |idx: usize| -> ReadySetResult<Option<ColumnSchema>> {
+            let &(node, col) = output_columns
+                .get(idx)
+                .ok_or_else(|| internal_err!("Schema expects valid column indices"))?;
+            let mut column_schema =
+                schema::column_schema(&self.ingredients, node, &self.recipe, col)?;
+            if let Some(column_schema) = &mut column_schema {
+                column_schema.column.table = Some(n.name().clone());
+            }
+            Ok(column_schema)
+        };
 
-        let projected_schema = (0..n.columns().len())
-            .map(|i| schema::column_schema(&self.ingredients, view_ni, &self.recipe, i))
+        let projected_schema = (0..output_columns.len())
+            .map(&column_schema)
             .collect::<Result<Vec<_>, ReadySetError>>()?
             .into_iter()
             .collect::<Option<Vec<_>>>();
 
         let returned_schema = returned_cols
             .iter()
-            .map(|idx| schema::column_schema(&self.ingredients, view_ni, &self.recipe, *idx))
+            .map(|idx| column_schema(*idx))
             .collect::<Result<Vec<_>, ReadySetError>>()?
             .into_iter()
             .collect::<Option<Vec<_>>>();
@@ -616,7 +769,7 @@ impl DfState {
         let base_operator = node
             .get_base()
             .ok_or_else(|| internal_err!("asked to get table for non-base node"))?;
-        let columns: Vec<SqlIdentifier> = node
+        let (columns, column_types): (Vec<SqlIdentifier>, Vec<DfType>) = node
             .columns()
             .iter()
             .enumerate()
@@ -624,10 +777,10 @@ impl DfState {
                 if base_operator.get_dropped().contains_key(n) {
                     None
                 } else {
-                    Some(s.name().into())
+                    Some((s.name().into(), s.ty().clone()))
                 }
             })
-            .collect();
+            .unzip();
         invariant_eq!(
             columns.len(),
             node.columns().len() - base_operator.get_dropped().len()
@@ -652,6 +805,7 @@ impl DfState {
             dropped: base_operator.get_dropped(),
             table_name: node.name().clone(),
             columns,
+            column_types,
             schema,
             table_request_timeout: self.domain_config.table_request_timeout,
         }))
@@ -690,6 +844,60 @@ impl DfState {
         Ok(GraphStats { domains })
     }
 
+    /// Get the per-minute counters of the reads from each cache over the last 24 hours, oldest
+    /// first, summed over all the shards and replicas of the reader of the cache.
+    pub(super) async fn cache_read_history(
+        &self,
+    ) -> ReadySetResult<HashMap<Relation, Vec<ReadStatsBucket>>> {
+        let reader_domains: HashSet<DomainIndex> = self
+            .ingredients
+            .node_weights()
+            .filter(|node| node.is_reader() && !node.is_dropped())
+            .map(|node| node.domain())
+            .collect();
+
+        let mut history: HashMap<Relation, BTreeMap<SystemTime, ReadStatsBucket>> = HashMap::new();
+        for domain_index in reader_domains {
+            let Some(domain) = self.domains.get(&domain_index) else {
+                continue;
+            };
+            let replies = domain
+                .send_to_healthy::<HashMap<NodeIndex, Vec<ReadStatsBucket>>>(
+                    DomainRequest::GetReadHistory,
+                    &self.workers,
+                )
+                .await?;
+            for (ni, reader_history) in replies.into_iter().flatten().flatten() {
+                let Some(node) = self.ingredients.node_weight(ni) else {
+                    continue;
+                };
+                let buckets = history.entry(node.name().clone()).or_default();
+                for bucket in &reader_history {
+                    buckets
+                        .entry(bucket.minute)
+                        .or_insert_with(|| ReadStatsBucket::new(bucket.minute))
+                        .merge(bucket);
+                }
+            }
+        }
+
+        Ok(history
+            .into_iter()
+            .map(|(name, buckets)| (name, buckets.into_values().collect()))
+            .collect())
+    }
+
+    /// Reset the cumulative event counters reported by [`Self::get_statistics`] in all domains.
+    pub(super) async fn reset_statistics(&self) -> ReadySetResult<()> {
+        trace!("asked to reset statistics");
+        for (&domain_index, s) in self.domains.iter() {
+            trace!(domain = %domain_index.index(), "resetting stats for domain");
+            s.send_to_healthy::<()>(DomainRequest::ResetStatistics, &self.workers)
+                .await?;
+        }
+        Ok(())
+    }
+
     pub(super) fn get_instances(&self) -> Vec<(WorkerIdentifier, bool)> {
         self.workers
             .iter()
@@ -711,6 +919,318 @@ impl DfState {
         )
     }
 
+    /// Build the canonical plan for the cache with the given name. See the documentation of the
+    /// [`canonical_plan`](crate::controller::canonical_plan) module for more information.
+    pub(super) fn canonical_plan(&self, name: &Relation) -> ReadySetResult<String> {
+        let reader = self
+            .views()
+            .get(name)
+            .and_then(|&node| self.find_reader_for(node, name, &None))
+            .ok_or_else(|| ReadySetError::ViewNotFound(name.to_string()))?;
+        Ok(canonical_plan::canonical_plan(
+            &self.ingredients,
+            &self.materializations,
+            reader,
+        ))
+    }
+
+    /// Returns the MIR plan for the cache with the given name, serialized as JSON. See the
+    /// documentation of the [`mir::plan`] module for more information.
+    pub(super) fn mir_plan(&self, name: &Relation) -> ReadySetResult<String> {
+        let plan = self.recipe.mir_plan(name)?;
+        serde_json::to_string_pretty(&plan)
+            .map_err(|e| internal_err!("Could not serialize MIR plan: {e}"))
+    }
+
+    /// Replace the dataflow for the cache with the given name with dataflow built from the given
+    /// (JSON-serialized) MIR plan, as returned by [`Self::mir_plan`] and possibly edited since.
+    /// Expressions in the plan are lowered using the semantics of `dialect`.
+    pub(super) async fn set_mir_plan(
+        &mut self,
+        name: &Relation,
+        plan: &str,
+        dialect: Dialect,
+    ) -> ReadySetResult<()> {
+        let plan: MirPlan = serde_json::from_str(plan)
+            .map_err(|e| invalid_err!("Could not parse MIR plan: {e}"))?;
+
+        let mut new = self.recipe.clone();
+        self.migrate(false, dialect, |mig| new.set_mir_plan(mig, name, plan))
+            .await?;
+        self.recipe = new;
+        Ok(())
+    }
+
+    /// Returns the provenance of every row with the given key in the reader of the cache with the
+    /// given name, as recorded by the reader's domain if provenance recording is enabled (see
+    /// [`DomainConfig::record_provenance`])
+    pub(super) async fn row_provenance(
+        &self,
+        name: &Relation,
+        key: Vec<DfValue>,
+    ) -> ReadySetResult<Vec<RowProvenance>> {
+        let reader = self
+            .views()
+            .get(name)
+            .and_then(|&node| self.find_reader_for(node, name, &None))
+            .ok_or_else(|| ReadySetError::ViewNotFound(name.to_string()))?;
+        #[allow(clippy::indexing_slicing)] // `find_reader_for` returns valid indices
+        let reader = &self.ingredients[reader];
+        let domain =
+            self.domains
+                .get(&reader.domain())
+                .ok_or_else(|| ReadySetError::UnknownDomain {
+                    domain_index: reader.domain().index(),
+                })?;
+
+        let per_shard = domain
+            .send_to_healthy::<Vec<RowProvenance>>(
+                DomainRequest::RequestRowProvenance {
+                    node: reader.local_addr(),
+                    key,
+                },
+                &self.workers,
+            )
+            .await?;
+        // Every replica of a shard records the same rows, so only take the rows from the first
+        Ok(per_shard
+            .into_iter()
+            .filter_map(|replicas| replicas.into_iter().next())
+            .flatten()
+            .collect())
+    }
+
+    /// Returns a request for the [`ViewStats`] of every view in the graph, which can be sent
+    /// without holding the lock on the dataflow state
+    pub(super) fn view_stats_request(&self) -> ReadySetResult<ViewStatsRequest> {
+        let mut views = Vec::new();
+        for (name, node) in self.views() {
+            let Some(reader) = self.find_reader_for(node, &name, &None) else {
+                continue;
+            };
+            #[allow(clippy::indexing_slicing)] // `find_reader_for` returns valid indices
+            let reader = &self.ingredients[reader];
+            let domain =
+                self.domains
+                    .get(&reader.domain())
+                    .ok_or_else(|| ReadySetError::UnknownDomain {
+                        domain_index: reader.domain().index(),
+                    })?;
+            views.push((name, domain.clone(), reader.local_addr()));
+        }
+        Ok(ViewStatsRequest {
+            views,
+            workers: self.workers.clone(),
+        })
+    }
+
+    /// Generate values for the `AUTO_INCREMENT` column at index `column` of the base table
+    /// `table`, for a write of rows whose values for that column are given by `values`: `Some`
+    /// for rows with an explicit value, which later generated values must come after, or `None`
+    /// for rows which need a value generated. Returns the generated values, in order.
+    ///
+    /// Values are generated here rather than by each adapter so that they're unique across every
+    /// adapter writing to the table. The counter for a table is seeded from the largest value of
+    /// the column in the table's state the first time it's used, including after the controller
+    /// restarts.
+    pub(super) async fn generate_auto_increments(
+        &self,
+        table: &Relation,
+        column: usize,
+        values: Vec<Option<u64>>,
+    ) -> ReadySetResult<Vec<u64>> {
+        let base = self.base_table(table)?;
+        #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+        let seeded = self.auto_increments.lock().unwrap().contains_key(&base);
+        if !seeded {
+            let max = match self.column_max(base, column).await? {
+                None | Some(DfValue::None) => 0,
+                Some(max) => u64::try_from(i64::try_from(&max)?.max(0)).unwrap_or_default(),
+            };
+            // Another request may have seeded the counter while we were looking up the largest
+            // value, in which case it may have already been advanced past it
+            #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+            let mut counters = self.auto_increments.lock().unwrap();
+            let last = counters.entry(base).or_default();
+            *last = (*last).max(max);
+        }
+
+        #[allow(clippy::unwrap_used)] // lock poisoning is unrecoverable
+        let mut counters = self.auto_increments.lock().unwrap();
+        let last = counters.entry(base).or_default();
+        Ok(values
+            .into_iter()
+            .filter_map(|value| match value {
+                Some(value) => {
+                    *last = (*last).max(value);
+                    None
+                }
+                None => {
+                    *last += 1;
+                    Some(*last)
+                }
+            })
+            .collect())
+    }
+
+    /// Returns the index of the base table node for the table `table`
+    fn base_table(&self, table: &Relation) -> ReadySetResult<NodeIndex> {
+        self.tables()
+            .get(table)
+            .copied()
+            .ok_or_else(|| ReadySetError::TableNotFound {
+                name: table.name.clone().into(),
+                schema: table.schema.clone().map(Into::into),
+            })
+    }
+
+    /// Returns the largest value of the column at index `column` in the base table node `base`, or
+    /// `None` if the table has no rows, by scanning the table's state directly rather than via a
+    /// cache
+    async fn column_max(&self, base: NodeIndex, column: usize) -> ReadySetResult<Option<DfValue>> {
+        #[allow(clippy::indexing_slicing)] // `base_table` returns valid indices
+        let base = &self.ingredients[base];
+        let domain =
+            self.domains
+                .get(&base.domain())
+                .ok_or_else(|| ReadySetError::UnknownDomain {
+                    domain_index: base.domain().index(),
+                })?;
+
+        let per_shard = domain
+            .send_to_healthy::<Option<DfValue>>(
+                DomainRequest::RequestColumnMax {
+                    node: base.local_addr(),
+                    column,
+                },
+                &self.workers,
+            )
+            .await?;
+        // Every replica of a shard stores the same rows, so only take the value from the first
+        Ok(per_shard
+            .into_iter()
+            .filter_map(|replicas| replicas.into_iter().next())
+            .flatten()
+            .max())
+    }
+
+    /// Returns all the rows in the base table `table` where the values of the columns at the
+    /// indices `columns` are equal to `key`, read directly from the table's state in every shard
+    pub(super) async fn base_rows(
+        &self,
+        table: &Relation,
+        columns: Vec<usize>,
+        key: Vec<DfValue>,
+    ) -> ReadySetResult<Vec<Vec<DfValue>>> {
+        let base = self.base_table(table)?;
+        #[allow(clippy::indexing_slicing)] // `base_table` returns valid indices
+        let base = &self.ingredients[base];
+        let domain =
+            self.domains
+                .get(&base.domain())
+                .ok_or_else(|| ReadySetError::UnknownDomain {
+                    domain_index: base.domain().index(),
+                })?;
+
+        let per_shard = domain
+            .send_to_healthy::<Vec<Vec<DfValue>>>(
+                DomainRequest::RequestBaseRows {
+                    node: base.local_addr(),
+                    columns,
+                    key,
+                },
+                &self.workers,
+            )
+            .await?;
+        // Every replica of a shard stores the same rows, so only take the rows from the first
+        Ok(per_shard
+            .into_iter()
+            .filter_map(|replicas| replicas.into_iter().next())
+            .flatten()
+            .collect())
+    }
+
+    /// Inject the barrier `barrier` into every shard of every base table. Once it has reached
+    /// every reader in the graph (see [`DfState::barrier_reached`]), every write acknowledged by a
+    /// base table before the barrier was injected is visible in every reader. Barriers must be
+    /// injected in increasing order.
+    ///
+    /// Returns the readers the barrier will reach: every reader derived from at least one base
+    /// table.
+    pub(super) async fn inject_barrier(&self, barrier: u64) -> ReadySetResult<HashSet<NodeIndex>> {
+        for base in self.tables().into_values() {
+            #[allow(clippy::indexing_slicing)] // `tables` returns valid indices
+            let base = &self.ingredients[base];
+            let domain =
+                self.domains
+                    .get(&base.domain())
+                    .ok_or_else(|| ReadySetError::UnknownDomain {
+                        domain_index: base.domain().index(),
+                    })?;
+            domain
+                .send_to_healthy::<()>(
+                    DomainRequest::Packet(Packet::Timestamp {
+                        link: None,
+                        src: SourceChannelIdentifier { token: 0, tag: 0 },
+                        timestamp: PacketData {
+                            dst: base.local_addr(),
+                            data: PacketPayload::Barrier(barrier),
+                            trace: None,
+                            batch: None,
+                        },
+                    }),
+                    &self.workers,
+                )
+                .await?;
+        }
+
+        Ok(self
+            .ingredients
+            .node_indices()
+            .filter(|&ni| {
+                #[allow(clippy::indexing_slicing)] // just came from self.ingredients
+                let node = &self.ingredients[ni];
+                node.is_reader() && !node.is_dropped()
+            })
+            .filter(|&reader| {
+                let mut ancestors = Bfs::new(Reversed(&self.ingredients), reader);
+                while let Some(ni) = ancestors.next(Reversed(&self.ingredients)) {
+                    #[allow(clippy::indexing_slicing)] // just came from self.ingredients
+                    if self.ingredients[ni].is_base() {
+                        return true;
+                    }
+                }
+                false
+            })
+            .collect())
+    }
+
+    /// Returns whether the barrier `barrier` (see [`DfState::inject_barrier`]) has reached every
+    /// one of `readers` which still exists, and the writes before it have been made visible there
+    pub(super) async fn barrier_reached(
+        &self,
+        barrier: u64,
+        readers: &HashSet<NodeIndex>,
+    ) -> ReadySetResult<bool> {
+        for domain in self.domains.values() {
+            let barriers = domain
+                .send_to_healthy::<Vec<(NodeIndex, u64)>>(
+                    DomainRequest::RequestReaderBarriers,
+                    &self.workers,
+                )
+                .await?;
+            if barriers
+                .iter()
+                .flatten()
+                .flatten()
+                .any(|(reader, reached)| readers.contains(reader) && *reached < barrier)
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
     /// List data-flow nodes, on a specific worker if `worker` specified.
     pub(super) fn nodes_on_worker(
         &self,
@@ -901,40 +1421,195 @@ impl DfState {
     /// Return a map of node indices to key counts.
     #[allow(dead_code)]
     pub(super) async fn node_sizes(&self) -> ReadySetResult<HashMap<NodeIndex, NodeSize>> {
-        let counts_per_domain: Vec<(DomainIndex, Vec<Vec<Vec<(NodeIndex, NodeSize)>>>)> = self
-            .query_domains::<_, Vec<(NodeIndex, NodeSize)>>(
-                self.domains
-                    .keys()
-                    .map(|di| (*di, DomainRequest::RequestNodeSizes)),
-            )
-            .try_collect()
-            .await?;
-        let flat_counts = counts_per_domain
-            .into_iter()
-            .flat_map(|(_domain, per_shard_counts)| {
-                per_shard_counts.into_iter().flatten().flatten()
-            });
-        let mut res = HashMap::new();
-        for (node_index, count) in flat_counts {
-            // We may have multiple entries for the same node in the case of sharding, so this code
-            // adds together the key counts for any duplicate nodes we come across:
-            res.entry(node_index)
-                .and_modify(|s| *s += count)
-                .or_insert(count);
+        node_sizes(self.domains.values(), &self.workers).await
+    }
+
+    /// Returns handles to every replicated domain, along with the workers running them, so that
+    /// the state of their replicas can be compared (with
+    /// [`replica_state_divergences`](super::state_checksums::replica_state_divergences)) without
+    /// holding a lock on the dataflow state
+    pub(super) fn replicated_domains(
+        &self,
+    ) -> (Vec<DomainHandle>, HashMap<WorkerIdentifier, Worker>) {
+        (
+            self.domains
+                .values()
+                .filter(|dh| dh.num_replicas() > 1)
+                .cloned()
+                .collect(),
+            self.workers.clone(),
+        )
+    }
+
+    /// Returns the nodes whose state is materialized for the caches in each namespace: every node
+    /// that a cache reads from, other than base tables, and the cache's readers. Nodes shared
+    /// between several caches in the same namespace are only included once.
+    fn nodes_by_namespace(&self) -> HashMap<SqlIdentifier, HashSet<NodeIndex>> {
+        let mut nodes_by_namespace: HashMap<SqlIdentifier, HashSet<NodeIndex>> = HashMap::new();
+        for (name, namespace) in self.recipe.cache_namespaces() {
+            let Ok(leaf) = self.recipe.node_addr_for(name) else {
+                continue;
+            };
+            let nodes = nodes_by_namespace.entry(namespace).or_default();
+            let mut ancestors = Bfs::new(Reversed(&self.ingredients), leaf);
+            while let Some(ni) = ancestors.next(Reversed(&self.ingredients)) {
+                #[allow(clippy::indexing_slicing)] // just came from self.ingredients
+                if !self.ingredients[ni].is_base() {
+                    nodes.insert(ni);
+                }
+            }
+            #[allow(clippy::indexing_slicing)] // just came from self.ingredients
+            nodes.extend(
+                self.ingredients
+                    .neighbors_directed(leaf, petgraph::EdgeDirection::Outgoing)
+                    .filter(|ni| self.ingredients[*ni].is_reader_for(leaf)),
+            );
+        }
+        nodes_by_namespace
+    }
+
+    /// Returns the number of caches in `changelist` that would be created in each namespace
+    fn new_caches_by_namespace(&self, changelist: &ChangeList) -> HashMap<SqlIdentifier, usize> {
+        let mut new_caches: HashMap<SqlIdentifier, usize> = HashMap::new();
+        for change in &changelist.changes {
+            let Change::CreateCache(CreateCacheStatement {
+                inner: CacheInner::Statement(statement),
+                ..
+            }) = change
+            else {
+                continue;
+            };
+            let query = ViewCreateRequest::new(
+                (**statement).clone(),
+                changelist.schema_search_path.clone(),
+            );
+            if let Some(namespace) = self
+                .recipe
+                .namespace_for_new_cache(query, changelist.dialect)
+            {
+                *new_caches.entry(namespace).or_default() += 1;
+            }
+        }
+        new_caches
+    }
+
+    /// Returns an error if creating the caches in `changelist` would exceed the limit on the
+    /// number of caches in any namespace they belong to
+    fn check_namespace_cache_quotas(&self, changelist: &ChangeList) -> ReadySetResult<()> {
+        if self.namespace_quotas.is_unlimited() {
+            return Ok(());
+        }
+
+        for (namespace, num_new) in self.new_caches_by_namespace(changelist) {
+            if let Some(max_caches) = self.namespace_quotas.quota(&namespace).max_caches {
+                let existing = self
+                    .recipe
+                    .cache_namespaces()
+                    .filter(|(_, ns)| *ns == namespace)
+                    .count();
+                if existing + num_new > max_caches {
+                    return Err(NamespaceLimit::Caches(max_caches).exceeded(&namespace));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns a check of the limit on the materialized bytes of each namespace that the caches in
+    /// `changelist` would be created in, if any of them are limited. Running the check requires
+    /// asking every domain for the size of its nodes, so it's returned to be run once the lock on
+    /// the dataflow state has been released.
+    pub(super) fn namespace_size_check(
+        &self,
+        changelist: &ChangeList,
+    ) -> Option<NamespaceSizeCheck> {
+        if self.namespace_quotas.is_unlimited() {
+            return None;
+        }
+
+        let limited = self
+            .new_caches_by_namespace(changelist)
+            .into_keys()
+            .filter_map(|namespace| {
+                let max_bytes = self
+                    .namespace_quotas
+                    .quota(&namespace)
+                    .max_materialized_bytes?;
+                Some((namespace, max_bytes))
+            })
+            .collect::<Vec<_>>();
+        if limited.is_empty() {
+            return None;
+        }
+
+        let mut nodes_by_namespace = self.nodes_by_namespace();
+        Some(NamespaceSizeCheck {
+            namespaces: limited
+                .into_iter()
+                .map(|(namespace, max_bytes)| {
+                    let nodes = nodes_by_namespace.remove(&namespace).unwrap_or_default();
+                    (namespace, max_bytes, nodes)
+                })
+                .collect(),
+            node_sizes: self.node_sizes_request(),
+        })
+    }
+
+    /// Returns a request for the current size of every materialized node, if they're needed to
+    /// estimate the size of any full materializations added for the caches in `changelist` (see
+    /// `materialization::Config::max_full_materialization_bytes`) and haven't been collected
+    /// recently. The request is returned to be sent once the lock on the dataflow state has been
+    /// released, and the sizes it returns passed to `Materializations::set_node_sizes`.
+    pub(super) fn materialization_sizes_request(
+        &self,
+        changelist: &ChangeList,
+    ) -> Option<NodeSizesRequest> {
+        if !self.materializations.needs_node_sizes()
+            || !changelist
+                .changes
+                .iter()
+                .any(|change| matches!(change, Change::CreateCache(_)))
+        {
+            return None;
+        }
+
+        Some(self.node_sizes_request())
+    }
+
+    fn node_sizes_request(&self) -> NodeSizesRequest {
+        NodeSizesRequest {
+            domains: self.domains.values().cloned().collect(),
+            workers: self.workers.clone(),
         }
-        Ok(res)
     }
 
     // ** Modify operations **
 
     /// Perform a new query schema migration.
-    #[instrument(level = "info", name = "migrate", skip(self, f, dialect))]
     pub(crate) async fn migrate<F, T>(
         &mut self,
         dry_run: bool,
         dialect: Dialect,
         f: F,
     ) -> ReadySetResult<T>
+    where
+        F: FnOnce(&mut Migration<'_>) -> ReadySetResult<T>,
+    {
+        self.migrate_with_backfill(dry_run, dialect, f)
+            .await
+            .map(|(r, _)| r)
+    }
+
+    /// Perform a new query schema migration, returning the [`Backfill`] left to run if the
+    /// migration [defers backfills](Migration::defer_backfills)
+    #[instrument(level = "info", name = "migrate", skip(self, f, dialect))]
+    async fn migrate_with_backfill<F, T>(
+        &mut self,
+        dry_run: bool,
+        dialect: Dialect,
+        f: F,
+    ) -> ReadySetResult<(T, Option<Backfill>)>
     where
         F: FnOnce(&mut Migration<'_>) -> ReadySetResult<T>,
     {
@@ -942,10 +1617,10 @@ impl DfState {
         gauge!(recorded::CONTROLLER_MIGRATION_IN_PROGRESS, 1.0);
         let mut m = Migration::new(self, dialect);
         let r = f(&mut m)?;
-        m.commit(dry_run).await?;
+        let backfill = m.commit(dry_run).await?;
         debug!("finished migration");
         gauge!(recorded::CONTROLLER_MIGRATION_IN_PROGRESS, 0.0);
-        Ok(r)
+        Ok((r, backfill))
     }
 
     /// Controls the persistence mode, and parameters related to persistence.
@@ -1135,6 +1810,8 @@ impl DfState {
                 .push(node.local_addr())
         }
 
+        let affected_domains = domain_removals.keys().copied().collect::<Vec<_>>();
+
         // Send messages to domains
         for (domain, nodes) in domain_removals {
             trace!(
@@ -1159,6 +1836,17 @@ impl DfState {
             }
         }
 
+        // Domains left without any live nodes won't be placed again, so their placement
+        // constraints don't need to be kept around
+        for domain in affected_domains {
+            let all_dropped = self.domain_nodes.get(&domain).map_or(true, |nodes| {
+                nodes.values().all(|ni| self.ingredients[*ni].is_dropped())
+            });
+            if all_dropped {
+                self.domain_placement_constraints.remove(&domain);
+            }
+        }
+
         Ok(())
     }
 
@@ -1231,17 +1919,54 @@ impl DfState {
         Ok(total_evicted)
     }
 
+    /// Apply the given changes to the recipe. Any domains created by the migration containing
+    /// readers or base tables are only scheduled onto workers which meet `placement_constraints`,
+    /// in addition to [`Self::placement_constraints`] and any constraints given with `PLACE READERS
+    /// ON` by the `CREATE CACHE` statements in `changelist`
     pub(super) async fn apply_recipe(
         &mut self,
         changelist: ChangeList,
+        placement_constraints: PlacementConstraints,
         dry_run: bool,
     ) -> Result<(), ReadySetError> {
+        self.apply_recipe_with_backfill(changelist, placement_constraints, dry_run, false)
+            .await
+            .map(|_| ())
+    }
+
+    /// Like [`DfState::apply_recipe`], but if `defer_backfills` is set the full replays of the
+    /// state of new materializations are left to the returned [`Backfill`] rather than waited for
+    async fn apply_recipe_with_backfill(
+        &mut self,
+        changelist: ChangeList,
+        placement_constraints: PlacementConstraints,
+        dry_run: bool,
+        defer_backfills: bool,
+    ) -> Result<Option<Backfill>, ReadySetError> {
+        let mut placement_constraints = placement_constraints;
+        for change in &changelist.changes {
+            if let Change::CreateCache(CreateCacheStatement {
+                reader_placement, ..
+            }) = change
+            {
+                for constraint in reader_placement {
+                    placement_constraints
+                        .readers
+                        .push(constraint.parse::<WorkerConstraint>()?);
+                }
+            }
+        }
+
         // I hate this, but there's no way around for now, as migrations
         // are super entangled with the recipe and the graph.
         let mut new = self.recipe.clone();
 
         let r = self
-            .migrate(dry_run, changelist.dialect, |mig| {
+            .migrate_with_backfill(dry_run, changelist.dialect, |mig| {
+                mig.add_placement_constraints(placement_constraints);
+                if defer_backfills {
+                    mig.defer_backfills();
+                }
                 new.activate(mig, changelist)
             })
             .await;
@@ -1254,14 +1979,18 @@ impl DfState {
             }
         }
 
-        r
+        r.map(|(_, backfill)| backfill)
     }
 
+    /// Extend the recipe with the given changes. If `defer_backfills` is set, the full replays of
+    /// the state of any new materializations are left to the returned [`Backfill`], to be run once
+    /// the changes have been committed, rather than waited for.
     pub(super) async fn extend_recipe(
         &mut self,
         recipe_spec: ExtendRecipeSpec<'_>,
         dry_run: bool,
-    ) -> Result<(), ReadySetError> {
+        defer_backfills: bool,
+    ) -> Result<Option<Backfill>, ReadySetError> {
         // Drop recipes from the replicator that we have already processed.
         if let (Some(new), Some(current)) = (
             &recipe_spec.replication_offset,
@@ -1269,11 +1998,21 @@ impl DfState {
         ) {
             if current >= new {
                 // Return an empty ActivationResult as this is a no-op.
-                return Ok(());
+                return Ok(None);
             }
         }
 
-        match self.apply_recipe(recipe_spec.changes, dry_run).await {
+        self.check_namespace_cache_quotas(&recipe_spec.changes)?;
+
+        match self
+            .apply_recipe_with_backfill(
+                recipe_spec.changes,
+                recipe_spec.placement_constraints,
+                dry_run,
+                defer_backfills,
+            )
+            .await
+        {
             Ok(x) => {
                 if let Some(offset) = &recipe_spec.replication_offset {
                     offset.try_max_into(&mut self.schema_replication_offset)?
@@ -1299,7 +2038,10 @@ impl DfState {
             Dialect::DEFAULT_MYSQL,
         );
 
-        if let Err(error) = self.apply_recipe(changelist, false).await {
+        if let Err(error) = self
+            .apply_recipe(changelist, Default::default(), false)
+            .await
+        {
             error!(%error, "Failed to apply recipe");
             return Err(error);
         }
@@ -1307,6 +2049,102 @@ impl DfState {
         Ok(())
     }
 
+    /// Add a new index on the given columns to the fully materialized state that feeds the reader
+    /// for the view with the given name, if that state exists and doesn't already have the index.
+    ///
+    /// The columns are given as indices into the rows returned by the view's reader. Starting at
+    /// the node the reader is materializing, the columns are traced up the graph through any
+    /// non-materialized nodes that pass them through unchanged, and the index is added to the
+    /// first materialized node that is reached. Returns whether a new index was added.
+    pub(super) async fn add_index(
+        &mut self,
+        view: &Relation,
+        columns: Vec<usize>,
+    ) -> ReadySetResult<bool> {
+        let mut node = match self
+            .recipe
+            .node_addr_for(view)
+            .ok()
+            .or_else(|| self.views().get(view).copied())
+        {
+            Some(ni) => ni,
+            None => return Err(ReadySetError::ViewNotFound(view.to_string())),
+        };
+
+        #[allow(clippy::indexing_slicing)] // node_addr_for and views return valid indices
+        let num_columns = self.ingredients[node].columns().len();
+        if columns.is_empty() {
+            return Err(ReadySetError::BadRequest(
+                "Cannot add an index on zero columns".into(),
+            ));
+        }
+        if let Some(column) = columns.iter().find(|&&c| c >= num_columns) {
+            return Err(ReadySetError::NonExistentColumn {
+                column: column.to_string(),
+                node: view.to_string(),
+            });
+        }
+
+        let mut columns = columns;
+        loop {
+            #[allow(clippy::indexing_slicing)] // only ever set to indices from the graph
+            let n = &self.ingredients[node];
+            if !matches!(
+                self.materializations.get_status(node, n),
+                MaterializationStatus::Not
+            ) {
+                break;
+            }
+
+            if !n.is_internal() {
+                // non-internal nodes pass all their columns through to their (only) parent
+                match self
+                    .ingredients
+                    .neighbors_directed(node, petgraph::EdgeDirection::Incoming)
+                    .next()
+                {
+                    Some(parent) => node = parent,
+                    None => return Ok(false),
+                }
+                continue;
+            }
+
+            match n.column_source(&columns) {
+                ColumnSource::ExactCopy(ColumnRef {
+                    node: parent,
+                    columns: parent_columns,
+                }) => {
+                    node = parent;
+                    columns = parent_columns.into_vec();
+                }
+                _ => {
+                    trace!(
+                        ?view,
+                        ?columns,
+                        "columns not traceable to materialized state"
+                    );
+                    return Ok(false);
+                }
+            }
+        }
+
+        let index = Index::btree_map(columns);
+        if !self
+            .materializations
+            .add_index(&self.ingredients, node, index.clone())
+        {
+            trace!(?view, ?index, node = %node.index(), "not adding index to node");
+            return Ok(false);
+        }
+
+        debug!(?view, ?index, node = %node.index(), "adding index to existing state");
+        let mut dmp = DomainMigrationPlan::new(self);
+        self.materializations
+            .commit(&mut self.ingredients, &HashSet::new(), &mut dmp)?;
+        dmp.apply(self).await?;
+        Ok(true)
+    }
+
     pub(super) async fn remove_all_queries(&mut self) -> ReadySetResult<()> {
         let changes = self
             .recipe
@@ -1319,11 +2157,117 @@ impl DfState {
 
         self.apply_recipe(
             ChangeList::from_changes(changes, Dialect::DEFAULT_MYSQL),
+            Default::default(),
             false,
         )
         .await
     }
 
+    /// Remove the given workers, and recover all the domains that were running on them onto the
+    /// remaining workers.
+    pub(super) async fn remove_workers(
+        &mut self,
+        workers: &[WorkerIdentifier],
+    ) -> ReadySetResult<()> {
+        // first, translate from the affected workers to affected data-flow nodes
+        let mut affected_nodes = HashMap::new();
+        for wi in workers {
+            let mut domain_nodes_on_worker = self.nodes_on_worker(Some(wi));
+            for (domain_index, node_indices) in domain_nodes_on_worker.drain() {
+                self.domains.remove(&domain_index);
+                self.materializations.remove_nodes(&node_indices);
+                affected_nodes
+                    .entry(domain_index)
+                    .or_insert_with(HashSet::new)
+                    .extend(node_indices);
+            }
+            self.workers.remove(wi);
+        }
+
+        self.recover(&affected_nodes).await
+    }
+
+    /// Drain the given worker: stop scheduling domains onto it, and recover all the domains that
+    /// were running on it onto the remaining workers.
+    ///
+    /// The state of base tables can't be moved between workers, so base tables which were running
+    /// on the worker are recovered empty. Every other domain downstream of those base tables is
+    /// restarted too, so that no state derived from their old contents is left behind. Returns
+    /// the names of the emptied base tables, which have to be snapshotted again.
+    pub(super) async fn drain_worker(
+        &mut self,
+        worker: &WorkerIdentifier,
+    ) -> ReadySetResult<Vec<Relation>> {
+        let recovered = self.nodes_on_worker(Some(worker));
+        #[allow(clippy::indexing_slicing)] // nodes came from self.domain_nodes
+        let base_nodes = recovered
+            .values()
+            .flatten()
+            .copied()
+            .filter(|ni| self.ingredients[*ni].is_base())
+            .collect::<Vec<_>>();
+        let mut downstream_domains = HashSet::new();
+        for ni in &base_nodes {
+            let mut bfs = Bfs::new(&self.ingredients, *ni);
+            while let Some(child) = bfs.next(&self.ingredients) {
+                #[allow(clippy::indexing_slicing)] // just came from self.ingredients
+                let domain = self.ingredients[child].domain();
+                if !recovered.contains_key(&domain) {
+                    downstream_domains.insert(domain);
+                }
+            }
+        }
+        #[allow(clippy::indexing_slicing)] // checked above
+        let base_tables = base_nodes
+            .iter()
+            .map(|ni| self.ingredients[*ni].name().clone())
+            .collect();
+
+        self.drained_workers.insert(worker.clone());
+        self.remove_workers(&[worker.clone()]).await?;
+        for domain in downstream_domains {
+            self.restart_domain(domain).await?;
+        }
+        Ok(base_tables)
+    }
+
+    /// Remove all the replicas of the given domain, and recover the domain onto the workers,
+    /// rebuilding its state from its ancestors.
+    ///
+    /// This is used to restart a domain after one of its replicas has failed. Any shards or
+    /// replicas of the domain which are still running are stopped, and replaced by the new ones.
+    pub(super) async fn restart_domain(&mut self, domain_index: DomainIndex) -> ReadySetResult<()> {
+        let node_indices = self
+            .domain_nodes
+            .get(&domain_index)
+            .map(|nm| nm.values().copied().collect::<HashSet<_>>())
+            .ok_or(ReadySetError::UnknownDomain {
+                domain_index: domain_index.index(),
+            })?;
+        if let Some(domain) = self.domains.remove(&domain_index) {
+            for (replica_address, worker_uri) in domain.replicas() {
+                let Some(worker) = self.workers.get(worker_uri) else {
+                    continue;
+                };
+                if let Err(error) = worker
+                    .rpc::<()>(WorkerRequestKind::StopDomain(replica_address))
+                    .await
+                {
+                    warn!(
+                        domain = %replica_address,
+                        %worker_uri,
+                        %error,
+                        "failed to stop replica of restarted domain"
+                    );
+                }
+            }
+        }
+        self.materializations.remove_nodes(&node_indices);
+
+        self.recover(&HashMap::from([(domain_index, node_indices)]))
+            .await
+    }
+
     /// Runs all the necessary steps to recover the full [`DfState`], when said state only
     /// has the bare minimum information.
     ///
@@ -1349,12 +2293,17 @@ impl DfState {
             .map(|(idx, nm)| (*idx, nm.iter().copied().collect::<Vec<_>>()))
             .collect::<HashMap<_, _>>();
         {
-            let mut scheduler = Scheduler::new(self, &None)?;
+            let mut scheduler = Scheduler::new(self, &None, Default::default())?;
             for (domain, nodes) in domain_nodes.iter() {
                 let workers = scheduler.schedule_domain(*domain, &nodes[..])?;
                 let num_shards = workers.num_rows();
                 let num_replicas = workers[0].len();
-                dmp.place_domain(*domain, workers, nodes.clone());
+                let placement_constraints = self
+                    .domain_placement_constraints
+                    .get(domain)
+                    .cloned()
+                    .unwrap_or_default();
+                dmp.place_domain(*domain, workers, nodes.clone(), placement_constraints);
                 dmp.set_domain_settings(
                     *domain,
                     DomainSettings {
@@ -1372,6 +2321,12 @@ impl DfState {
         )?;
 
         self.materializations.extend(&mut self.ingredients, &new)?;
+        routing::set_egress_predicates(
+            &self.ingredients,
+            &self.materializations,
+            &mut dmp,
+            &self.ingredients.node_indices().collect(),
+        )?;
 
         self.materializations
             .commit(&mut self.ingredients, &new, &mut dmp)?;
@@ -1390,6 +2345,8 @@ impl DfState {
     pub(crate) fn touch_up(&mut self) {
         self.domains = Default::default();
         self.channel_coordinator = Default::default();
+        self.backfills = Default::default();
+        self.auto_increments = Default::default();
         self.read_addrs = Default::default();
         self.workers = Default::default();
 
@@ -1404,6 +2361,107 @@ impl DfState {
     }
 }
 
+/// Ask each of `domains` for the sizes of its nodes, returning a map of node indices to sizes
+async fn node_sizes<'a, I>(
+    domains: I,
+    workers: &HashMap<WorkerIdentifier, Worker>,
+) -> ReadySetResult<HashMap<NodeIndex, NodeSize>>
+where
+    I: IntoIterator<Item = &'a DomainHandle>,
+{
+    let counts_per_domain: Vec<Vec<Vec<Vec<(NodeIndex, NodeSize)>>>> = stream::iter(domains)
+        .map(|domain| {
+            domain.send_to_healthy::<Vec<(NodeIndex, NodeSize)>>(
+                DomainRequest::RequestNodeSizes,
+                workers,
+            )
+        })
+        .buffer_unordered(CONCURRENT_REQUESTS)
+        .try_collect()
+        .await?;
+    let mut res = HashMap::new();
+    for (node_index, count) in counts_per_domain.into_iter().flatten().flatten().flatten() {
+        // We may have multiple entries for the same node in the case of sharding, so this code
+        // adds together the key counts for any duplicate nodes we come across:
+        res.entry(node_index)
+            .and_modify(|s| *s += count)
+            .or_insert(count);
+    }
+    Ok(res)
+}
+
+/// A request for the size of every materialized node, holding handles to the domains so that it
+/// can be sent without holding the lock on the dataflow state
+pub(super) struct NodeSizesRequest {
+    domains: Vec<DomainHandle>,
+    workers: HashMap<WorkerIdentifier, Worker>,
+}
+
+impl NodeSizesRequest {
+    /// Ask each domain for the sizes of its nodes, returning a map of node indices to sizes
+    pub(super) async fn send(&self) -> ReadySetResult<HashMap<NodeIndex, NodeSize>> {
+        node_sizes(&self.domains, &self.workers).await
+    }
+}
+
+/// A request for the [`ViewStats`] of every view, holding handles to the domains of the views'
+/// readers so that it can be sent without holding the lock on the dataflow state
+pub(super) struct ViewStatsRequest {
+    /// The name of each view, along with the domain and local index of its reader
+    views: Vec<(Relation, DomainHandle, LocalNodeIndex)>,
+    workers: HashMap<WorkerIdentifier, Worker>,
+}
+
+impl ViewStatsRequest {
+    /// Ask the domain of each view's reader for its stats, returning a map of view names to stats
+    pub(super) async fn send(self) -> ReadySetResult<BTreeMap<Relation, ViewStats>> {
+        let mut res = BTreeMap::new();
+        for (name, domain, node) in self.views {
+            let per_shard = domain
+                .send_to_healthy::<ViewStats>(
+                    DomainRequest::RequestViewStats { node },
+                    &self.workers,
+                )
+                .await?;
+            // Every replica of a shard stores the same rows, so only take the stats of the first
+            let stats = per_shard
+                .into_iter()
+                .filter_map(|replicas| replicas.into_iter().next())
+                .fold(ViewStats::default(), |stats, shard| stats.merge(&shard));
+            res.insert(name, stats);
+        }
+        Ok(res)
+    }
+}
+
+/// A check of the limits on the materialized bytes of the namespaces that new caches are being
+/// created in, gathered from the dataflow state so that it can be run without holding the lock on
+/// it (see [`DfState::namespace_size_check`])
+pub(super) struct NamespaceSizeCheck {
+    /// Each namespace to check, along with its limit and the nodes whose state counts towards it
+    namespaces: Vec<(SqlIdentifier, usize, HashSet<NodeIndex>)>,
+    node_sizes: NodeSizesRequest,
+}
+
+impl NamespaceSizeCheck {
+    /// Returns an error if the state materialized for the caches in any of the namespaces has
+    /// already reached its limit
+    pub(super) async fn run(self) -> ReadySetResult<()> {
+        let node_sizes = self.node_sizes.send().await?;
+        for (namespace, max_bytes, nodes) in self.namespaces {
+            let bytes: usize = nodes
+                .iter()
+                .filter_map(|ni| node_sizes.get(ni))
+                .map(|size| size.bytes.bytes())
+                .sum();
+            if bytes >= max_bytes {
+                return Err(NamespaceLimit::MaterializedBytes(max_bytes).exceeded(&namespace));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// This structure acts as a wrapper for a [`DfStateReader`] in order to guarantee
 /// thread-safe access (read and writes) to ReadySet's dataflow state.
 ///
@@ -1697,7 +2755,8 @@ pub(super) fn graphviz(
         .flat_map(|m| m.iter())
         .flat_map(|(di, nodes)| nodes.iter().map(|(_, ni)| (*ni, *di)))
         .collect::<HashMap<_, _>>();
-    let mut domains_to_nodes = HashMap::new();
+    // Ordered by domain, so that the same graph is always rendered identically
+    let mut domains_to_nodes = BTreeMap::new();
     for index in graph.node_indices() {
         let domain = domain_for_node.get(&index).copied();
         domains_to_nodes
@@ -0,0 +1,199 @@
+//! Periodic detection of replicas of a domain whose materialized state has silently diverged from
+//! one another.
+//!
+//! If [`Config::state_checksum_interval`](crate::Config::state_checksum_interval) is set, the
+//! leader periodically asks every replicated domain for [checksums](StateChecksum) of one
+//! [`KeyRange`] of the state of each of its materialized nodes, moving on to the next key range
+//! each time, and compares the checksums across the replicas of each shard of the domain.
+//!
+//! Replicas process updates independently of one another, so their state can differ briefly while
+//! an update is being processed. When a key range differs between replicas, it's checked again
+//! after the next interval, and only the nodes whose state differed both times are reported: they
+//! are logged as errors, and counted by the [`CONTROLLER_STATE_DIVERGENCES`] metric.
+//!
+//! [`CONTROLLER_STATE_DIVERGENCES`]: recorded::CONTROLLER_STATE_DIVERGENCES
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use dataflow::prelude::{DomainIndex, NodeIndex};
+use dataflow::{DomainRequest, KeyRange, StateChecksum};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use metrics::increment_counter;
+use readyset_client::metrics::recorded;
+use readyset_errors::ReadySetResult;
+use readyset_tracing::{debug, error, warn};
+
+use crate::controller::domain_handle::DomainHandle;
+use crate::controller::state::{DfStateHandle, CONCURRENT_REQUESTS};
+use crate::controller::{Worker, WorkerIdentifier};
+
+/// The number of key ranges the state of each node is divided into. Each check covers one key
+/// range.
+const KEY_RANGES: u32 = 64;
+
+/// A node in a replica of a domain whose materialized state differs from the state of the same
+/// node in the first replica of the same shard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) struct StateDivergence {
+    pub(super) domain: DomainIndex,
+    pub(super) shard: usize,
+    pub(super) replica: usize,
+    pub(super) node: NodeIndex,
+}
+
+/// Compare the checksums of the state of the nodes in each replica of each shard of `domain` (as
+/// returned for [`DomainRequest::RequestStateChecksums`]) with those of the first replica of the
+/// shard.
+///
+/// Nodes which aren't materialized in both replicas are ignored, since that may just mean that a
+/// migration is in progress.
+///
+/// [`DomainRequest::RequestStateChecksums`]: dataflow::DomainRequest::RequestStateChecksums
+pub(super) fn find_divergences(
+    domain: DomainIndex,
+    checksums: Vec<Vec<Vec<(NodeIndex, StateChecksum)>>>,
+) -> Vec<StateDivergence> {
+    let mut res = vec![];
+    for (shard, replicas) in checksums.into_iter().enumerate() {
+        let mut replicas = replicas.into_iter().enumerate();
+        let first = match replicas.next() {
+            Some((_, checksums)) => checksums.into_iter().collect::<HashMap<_, _>>(),
+            None => continue,
+        };
+        for (replica, checksums) in replicas {
+            res.extend(
+                checksums
+                    .into_iter()
+                    .filter(|(node, checksum)| {
+                        first
+                            .get(node)
+                            .map_or(false, |first| first.diverges_from(checksum))
+                    })
+                    .map(|(node, _)| StateDivergence {
+                        domain,
+                        shard,
+                        replica,
+                        node,
+                    }),
+            );
+        }
+    }
+    res
+}
+
+/// Compare checksums of the given key range of the materialized state of every node in each of
+/// `domains` across the replicas of each of the domain's shards, and return the replicas whose
+/// state differs from that of the first replica of their shard
+pub(super) async fn replica_state_divergences(
+    domains: &[DomainHandle],
+    workers: &HashMap<WorkerIdentifier, Worker>,
+    range: KeyRange,
+) -> ReadySetResult<Vec<StateDivergence>> {
+    stream::iter(domains)
+        .map(|domain| async move {
+            let checksums = domain
+                .send_to_healthy::<Vec<(NodeIndex, StateChecksum)>>(
+                    DomainRequest::RequestStateChecksums { range },
+                    workers,
+                )
+                .await?;
+            ReadySetResult::Ok(find_divergences(domain.index(), checksums))
+        })
+        .buffer_unordered(CONCURRENT_REQUESTS)
+        .try_concat()
+        .await
+}
+
+/// Compare the state of the replicas of every replicated domain every `interval`, forever. See the
+/// [module documentation](self) for more information.
+pub(super) async fn check_state_periodically(
+    dataflow_state_handle: Arc<DfStateHandle>,
+    interval: Duration,
+) {
+    let mut interval = tokio::time::interval(interval);
+    let mut range = KeyRange {
+        index: 0,
+        ranges: KEY_RANGES,
+    };
+    // Divergences found the last time `range` was checked, which haven't been reported yet
+    let mut suspected: HashSet<StateDivergence> = HashSet::new();
+
+    loop {
+        interval.tick().await;
+        // Don't hold the lock on the dataflow state while waiting for the domains to respond,
+        // which would block migrations for as long
+        let (domains, workers) = dataflow_state_handle.read().await.replicated_domains();
+        let divergences = match replica_state_divergences(&domains, &workers, range).await {
+            Ok(divergences) => divergences,
+            Err(error) => {
+                warn!(%error, "Failed to compare the state of domain replicas");
+                continue;
+            }
+        };
+
+        if suspected.is_empty() && !divergences.is_empty() {
+            debug!(
+                key_range = range.index,
+                num_divergences = divergences.len(),
+                "State of domain replicas differs; checking again before reporting"
+            );
+            suspected.extend(divergences);
+            continue;
+        }
+
+        for divergence in divergences.iter().filter(|d| suspected.contains(d)) {
+            error!(
+                domain = %divergence.domain.index(),
+                shard = divergence.shard,
+                replica = divergence.replica,
+                node = %divergence.node.index(),
+                key_range = range.index,
+                "State of domain replica has diverged from the first replica of its shard"
+            );
+            increment_counter!(
+                recorded::CONTROLLER_STATE_DIVERGENCES,
+                "domain" => divergence.domain.index().to_string(),
+                "node" => divergence.node.index().to_string()
+            );
+        }
+        suspected.clear();
+        range.index = (range.index + 1) % range.ranges;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn full(checksum: u64) -> StateChecksum {
+        StateChecksum::Full { rows: 1, checksum }
+    }
+
+    #[test]
+    fn finds_divergent_replicas() {
+        let domain = DomainIndex::from(1);
+        let (n1, n2) = (NodeIndex::new(1), NodeIndex::new(2));
+        let checksums = vec![
+            // shard 0: replica 2 differs on n2
+            vec![
+                vec![(n1, full(1)), (n2, full(2))],
+                vec![(n2, full(2)), (n1, full(1))],
+                vec![(n1, full(1)), (n2, full(3))],
+            ],
+            // shard 1: n2 isn't materialized in replica 1 yet
+            vec![vec![(n1, full(4)), (n2, full(5))], vec![(n1, full(4))]],
+        ];
+
+        assert_eq!(
+            find_divergences(domain, checksums),
+            vec![StateDivergence {
+                domain,
+                shard: 0,
+                replica: 2,
+                node: n2
+            }]
+        );
+    }
+}
@@ -2,6 +2,7 @@ use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 
 use dataflow::prelude::*;
+use readyset_client::channel::ChannelSecurity;
 use readyset_client::consensus::Authority;
 use readyset_client::prelude::*;
 use readyset_data::Dialect;
@@ -45,8 +46,10 @@ impl Handle {
         event_tx: Sender<HandleRequest>,
         kill: Trigger,
         descriptor: ControllerDescriptor,
+        channel_security: Option<ChannelSecurity>,
     ) -> Self {
-        let c = ReadySetHandle::make(authority, None, None);
+        let mut c = ReadySetHandle::make(authority, None, None);
+        c.set_channel_security(channel_security);
         Handle {
             c: Some(c),
             event_tx: Some(event_tx),
@@ -1,30 +1,40 @@
 use std::future::Future;
 use std::io;
-use std::net::{IpAddr, SocketAddr};
+use std::net::IpAddr;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
 use anyhow::anyhow;
-use futures::TryFutureExt;
+use futures::{stream, TryFutureExt};
 use health_reporter::{HealthReporter, State};
 use hyper::header::CONTENT_TYPE;
 use hyper::service::make_service_fn;
 use hyper::{self, Body, Method, Request, Response, StatusCode};
+use readyset_client::channel::ChannelSecurity;
 use readyset_client::consensus::{Authority, AuthorityControl};
 use readyset_client::metrics::recorded;
-use readyset_client::ReadySetError;
-use readyset_tracing::warn;
+use readyset_client::status::SnapshotStatus;
+use readyset_client::{ReadySetError, ReadySetHandle};
+use readyset_tracing::{info, warn};
 use stream_cancel::Valve;
 use tokio::net::TcpListener;
 use tokio::sync::mpsc::Sender;
+use tokio::sync::OnceCell;
 use tokio_stream::wrappers::TcpListenerStream;
 use tower::Service;
+use url::Url;
 
 use crate::controller::ControllerRequest;
 use crate::metrics::{get_global_recorder, Clear, RecorderType};
+use crate::startup::bind_listeners;
 use crate::worker::WorkerRequest;
 
+/// How long to wait for the leader to respond to requests other than migrations, such as
+/// reporting whether snapshotting has completed when handling a request to `/readiness`
+const READINESS_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// Routes requests from an HTTP server to noria server workers and controllers.
 /// The NoriaServerHttpRouter takes several channels (`worker_tx`, `controller_tx`)
 /// used to pass messages from this context to the worker and controller threads.
@@ -32,9 +42,9 @@ use crate::worker::WorkerRequest;
 /// impl Service<Request<Body>> for NoriaServerHttpRouter.
 #[derive(Clone)]
 pub struct NoriaServerHttpRouter {
-    /// The address to attempt to listen on.
-    pub listen_addr: IpAddr,
-    /// The port to attempt to listen on.
+    /// The addresses to attempt to listen on.
+    pub listen_addrs: Vec<IpAddr>,
+    /// The port to attempt to listen on, on every address.
     pub port: u16,
     /// Channel to the running `Worker`.
     pub worker_tx: Sender<WorkerRequest>,
@@ -46,35 +56,63 @@ pub struct NoriaServerHttpRouter {
     pub valve: Valve,
     /// Used to record and report the servers current health.
     pub health_reporter: HealthReporter,
+    /// The URI of the worker running in this server, once it's known. Used to ask the leader to
+    /// move domains off of this worker when it's drained.
+    pub worker_uri: Option<Url>,
     /// Used to communicate externally that a failpoint request has been received and successfully
     /// handled.
     /// Most commonly used to block on further startup action if --wait-for-failpoint is supplied.
     pub failpoint_channel: Option<Arc<Sender<()>>>,
+    /// Used to check the signatures of requests to the controller and worker, and to sign the
+    /// requests this router makes to the controller, if configured
+    pub channel_security: Option<ChannelSecurity>,
+    /// A handle to the leader, created the first time it's needed and then shared by all the
+    /// requests this router makes to the leader
+    pub leader_handle: Arc<OnceCell<ReadySetHandle>>,
 }
 
 impl NoriaServerHttpRouter {
-    /// Creates a listener object to be used to route requests.
-    pub async fn create_listener(&self) -> anyhow::Result<TcpListener> {
-        let http_listener = TcpListener::bind(SocketAddr::new(self.listen_addr, self.port))
+    /// Returns a handle to the leader, creating it if this is the first time it's needed
+    async fn leader_handle(
+        leader_handle: &OnceCell<ReadySetHandle>,
+        authority: Arc<Authority>,
+        channel_security: Option<ChannelSecurity>,
+    ) -> ReadySetHandle {
+        leader_handle
+            .get_or_init(|| async move {
+                let mut handle =
+                    ReadySetHandle::with_timeouts(authority, Some(READINESS_REQUEST_TIMEOUT), None)
+                        .await;
+                handle.set_channel_security(channel_security);
+                handle
+            })
+            .await
+            .clone()
+    }
+
+    /// Creates the listener objects to be used to route requests, one for each of the addresses
+    /// in `listen_addrs`, all on the same port.
+    pub async fn create_listeners(&self) -> anyhow::Result<Vec<TcpListener>> {
+        let http_listeners = bind_listeners(&self.listen_addrs, self.port)
             .or_else(|_| {
                 warn!("Could not bind to provided external port; using a random port instead!");
 
-                TcpListener::bind(SocketAddr::new(self.listen_addr, 0))
+                bind_listeners(&self.listen_addrs, 0)
             })
             .await?;
-        Ok(http_listener)
+        Ok(http_listeners)
     }
 
-    /// Routes requests for a noria server http router received on `http_listener`
+    /// Routes requests for a noria server http router received on any of `http_listeners`
     /// the service layer of the NoriaServerHttpRouter, see
     /// mpl Service<_> for NoriaServerHttpRouter.
     pub async fn route_requests(
         router: NoriaServerHttpRouter,
-        http_listener: TcpListener,
+        http_listeners: Vec<TcpListener>,
     ) -> anyhow::Result<()> {
-        hyper::server::Server::builder(hyper::server::accept::from_stream(
-            router.valve.wrap(TcpListenerStream::new(http_listener)),
-        ))
+        hyper::server::Server::builder(hyper::server::accept::from_stream(router.valve.wrap(
+            stream::select_all(http_listeners.into_iter().map(TcpListenerStream::new)),
+        )))
         .serve(make_service_fn(move |_| {
             let s = router.clone();
             async move { io::Result::Ok(s) }
@@ -84,11 +122,25 @@ impl NoriaServerHttpRouter {
     }
 }
 
+/// Returns true if requests to `path` must be signed when a channel secret is configured. Health
+/// checks, metrics and the graph visualization are left open so that they can be used by tools
+/// which don't know the secret.
+fn requires_signature(path: &str) -> bool {
+    !matches!(
+        path,
+        "/health" | "/readiness" | "/metrics" | "/metrics_dump" | "/reset_metrics" | "/graph.html"
+    )
+}
+
 /// Tower service definition to route http requests `Request<Body>` to their
 /// responses. Requests on the endpoint `/worker_request` are routed to the
 /// worker along the `worker_tx` channel, while any request that is not specifically
 /// handled by the http server directly is routed to the controller along the
 /// `controller_tx` channel.
+///
+/// If the router is configured with a [`ChannelSecurity`] with a secret, requests to every
+/// endpoint for which [`requires_signature`] returns true are rejected unless they were signed
+/// with [`ChannelSecurity::sign_request`].
 impl Service<Request<Body>> for NoriaServerHttpRouter {
     type Response = Response<Body>;
     type Error = hyper::Error;
@@ -99,6 +151,37 @@ impl Service<Request<Body>> for NoriaServerHttpRouter {
     }
 
     fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let security = match &self.channel_security {
+            Some(security) if security.signs_requests() && requires_signature(req.uri().path()) => {
+                security.clone()
+            }
+            _ => return self.route(req),
+        };
+
+        let mut router = self.clone();
+        Box::pin(async move {
+            let (parts, body) = req.into_parts();
+            let body = hyper::body::to_bytes(body).await?;
+            let path_and_query = parts.uri.path_and_query().map_or("/", |p| p.as_str());
+            if let Err(error) =
+                security.verify_request(&parts.method, path_and_query, &body, &parts.headers)
+            {
+                warn!(%error, path = %parts.uri.path(), "Rejecting unauthenticated request");
+                return Ok(Response::builder()
+                    .status(StatusCode::UNAUTHORIZED)
+                    .header(CONTENT_TYPE, "text/plain")
+                    .body(hyper::Body::from(error.to_string()))
+                    .unwrap());
+            }
+            router
+                .route(Request::from_parts(parts, Body::from(body)))
+                .await
+        })
+    }
+}
+
+impl NoriaServerHttpRouter {
+    fn route(&mut self, req: Request<Body>) -> <Self as Service<Request<Body>>>::Future {
         let res = Response::builder()
             // disable CORS to allow use as API server
             .header(hyper::header::ACCESS_CONTROL_ALLOW_ORIGIN, "*");
@@ -175,7 +258,7 @@ impl Service<Request<Body>> for NoriaServerHttpRouter {
                 Box::pin(async move {
                     let body = format!("Server is in {} state", &state).into();
                     let res = match state {
-                        State::Healthy | State::ShuttingDown => res
+                        State::Healthy | State::Draining | State::ShuttingDown => res
                             .status(200)
                             .header(CONTENT_TYPE, "text/plain")
                             .body(body),
@@ -188,6 +271,66 @@ impl Service<Request<Body>> for NoriaServerHttpRouter {
                     Ok(res.unwrap())
                 })
             }
+            (&Method::GET, "/readiness") => {
+                let state = self.health_reporter.health().state;
+                let authority = self.authority.clone();
+                let channel_security = self.channel_security.clone();
+                let leader_handle = self.leader_handle.clone();
+                Box::pin(async move {
+                    let snapshot_status = if state == State::Healthy {
+                        let mut handle =
+                            Self::leader_handle(&leader_handle, authority, channel_security).await;
+                        handle.status().await.map(|s| s.snapshot_status).ok()
+                    } else {
+                        None
+                    };
+                    let res = res.header(CONTENT_TYPE, "text/plain");
+                    let res = match snapshot_status {
+                        Some(SnapshotStatus::Completed) => {
+                            res.status(200).body("Server is ready".into())
+                        }
+                        Some(SnapshotStatus::InProgress) => res
+                            .status(StatusCode::SERVICE_UNAVAILABLE)
+                            .body("Snapshotting is in progress".into()),
+                        None => res
+                            .status(StatusCode::SERVICE_UNAVAILABLE)
+                            .body(format!("Server is in {} state", &state).into()),
+                    };
+                    Ok(res.unwrap())
+                })
+            }
+            (&Method::POST, "/drain") => {
+                let mut health_reporter = self.health_reporter.clone();
+                let authority = self.authority.clone();
+                let worker_uri = self.worker_uri.clone();
+                let channel_security = self.channel_security.clone();
+                let leader_handle = self.leader_handle.clone();
+                Box::pin(async move {
+                    health_reporter.set_state(State::Draining);
+                    let res = res.header(CONTENT_TYPE, "text/plain");
+                    let worker_uri = match worker_uri {
+                        Some(worker_uri) => worker_uri,
+                        None => {
+                            return Ok(res
+                                .status(StatusCode::SERVICE_UNAVAILABLE)
+                                .body("Worker has not started yet".into())
+                                .unwrap())
+                        }
+                    };
+                    info!(%worker_uri, "Draining worker");
+                    let mut handle =
+                        Self::leader_handle(&leader_handle, authority, channel_security).await;
+                    let res = match handle.drain_worker(worker_uri).await {
+                        Ok(()) => res.status(200).body("Worker drained".into()),
+                        Err(error) => {
+                            warn!(%error, "Failed to drain worker");
+                            res.status(StatusCode::INTERNAL_SERVER_ERROR)
+                                .body(format!("Failed to drain worker: {error}").into())
+                        }
+                    };
+                    Ok(res.unwrap())
+                })
+            }
             (&Method::POST, "/metrics_dump") => {
                 let render = get_global_recorder().and_then(|r| r.render(RecorderType::Noria));
                 let res = match render {
@@ -5,7 +5,7 @@
 //! to prevent flaky behavior.
 #![allow(clippy::many_single_char_names)]
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::{TryFrom, TryInto};
 use std::ops::Bound;
 use std::sync::Arc;
@@ -23,18 +23,30 @@ use dataflow::ops::project::Project;
 use dataflow::ops::union::{self, Union};
 use dataflow::utils::{dataflow_column, make_columns};
 use dataflow::{
-    BinaryOperator, DurabilityMode, Expr as DfExpr, PersistenceParameters, ReaderProcessing,
+    BinaryOperator, DurabilityMode, Expr as DfExpr, LazyJoin, PersistenceParameters,
+    ReaderProcessing,
 };
 use futures::StreamExt;
 use itertools::Itertools;
-use nom_sql::{parse_create_cache, parse_create_view, parse_query, OrderType, Relation, SqlQuery};
+use mir::node::MirNodeInner;
+use mir::plan::MirPlan;
+use nom_sql::{
+    parse_create_cache, parse_create_view, parse_query, parse_select_statement,
+    AlterReadysetStatement, OrderType, Relation, SqlQuery,
+};
 use readyset_client::consensus::{Authority, LocalAuthority, LocalAuthorityStore};
 use readyset_client::consistency::Timestamp;
 use readyset_client::internal::LocalNodeIndex;
+use readyset_client::placement::{PlacementConstraints, WorkerConstraint, WorkerResources};
+use readyset_client::query::QueryId;
 use readyset_client::recipe::changelist::{Change, ChangeList};
-use readyset_client::{KeyComparison, Modification, SchemaType, ViewPlaceholder, ViewQuery};
+use readyset_client::{
+    KeyComparison, Modification, ReadBehavior, SchemaType, TableOperation, ViewCreateRequest,
+    ViewPlaceholder, ViewQuery, WriteBatch,
+};
 use readyset_data::{DfType, DfValue, Dialect};
 use readyset_errors::ReadySetError::{MigrationPlanFailed, RpcFailed, SelectQueryCreationFailed};
+use readyset_errors::UnsupportedFeature;
 use readyset_util::eventually;
 use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
@@ -283,6 +295,93 @@ async fn test_timestamp_propagation_multitable() {
     ));
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn write_batch_multitable() {
+    let mut g = start_simple_unsharded("write_batch_multitable").await;
+
+    let (a, b) = g
+        .migrate(|mig| {
+            let a = mig.add_base(
+                "a",
+                make_columns(&["a", "b"]),
+                Base::new().with_primary_key([0]),
+            );
+            let b = mig.add_base(
+                "b",
+                make_columns(&["a", "b"]),
+                Base::new().with_primary_key([0]),
+            );
+
+            let mut emits = HashMap::new();
+            emits.insert(a, vec![0, 1]);
+            emits.insert(b, vec![0, 1]);
+            let u = Union::new(emits, union::DuplicateMode::UnionAll).unwrap();
+            let c = mig.add_ingredient("c", make_columns(&["a", "b"]), u);
+            mig.maintain_anonymous(c, &Index::hash_map(vec![0]));
+            (a, b)
+        })
+        .await;
+
+    let mut cq = g.view("c").await.unwrap().into_reader_handle().unwrap();
+    let muta = g.table_by_index(a).await.unwrap();
+    let mutb = g.table_by_index(b).await.unwrap();
+
+    let mut batch = WriteBatch::new(1);
+    batch.add(&muta, vec![vec![DfValue::Int(1), DfValue::Int(2)]]);
+    batch.add(&mutb, vec![vec![DfValue::Int(1), DfValue::Int(3)]]);
+    batch.commit().await.unwrap();
+
+    let mut res = cq
+        .raw_lookup(ViewQuery::from((
+            vec![KeyComparison::Equal(vec1![DfValue::Int(1)])],
+            true,
+            Some(timestamp(vec![(0, 1), (1, 1)])),
+        )))
+        .await
+        .unwrap()
+        .into_vec();
+    res.sort();
+    assert_eq!(
+        res,
+        vec![
+            vec![DfValue::Int(1), DfValue::Int(2)],
+            vec![DfValue::Int(1), DfValue::Int(3)],
+        ]
+    );
+
+    // A batch containing an invalid write isn't performed at all
+    let mut batch = WriteBatch::new(2);
+    batch.add(&muta, vec![vec![DfValue::Int(2), DfValue::Int(2)]]);
+    batch.add(&mutb, vec![vec![DfValue::Int(2)]]);
+    batch.commit().await.unwrap_err();
+
+    // Batches can only contain writes which can be undone if performing the batch fails
+    let mut batch = WriteBatch::new(2);
+    batch.add(&muta, vec![vec![DfValue::Int(2), DfValue::Int(2)]]);
+    batch.add(
+        &mutb,
+        vec![TableOperation::DeleteByKey {
+            key: vec![DfValue::Int(1)],
+        }],
+    );
+    batch.commit().await.unwrap_err();
+
+    let mut batch = WriteBatch::new(3);
+    batch.add(&mutb, vec![vec![DfValue::Int(2), DfValue::Int(3)]]);
+    batch.commit().await.unwrap();
+
+    let res = cq
+        .raw_lookup(ViewQuery::from((
+            vec![KeyComparison::Equal(vec1![DfValue::Int(2)])],
+            true,
+            Some(timestamp(vec![(1, 3)])),
+        )))
+        .await
+        .unwrap()
+        .into_vec();
+    assert_eq!(res, vec![vec![DfValue::Int(2), DfValue::Int(3)]]);
+}
+
 #[tokio::test(flavor = "multi_thread")]
 #[ignore = "Ignoring sharded tests"]
 async fn sharded_shuffle() {
@@ -745,147 +844,689 @@ async fn it_works_w_partial_mat_below_empty() {
 }
 
 #[tokio::test(flavor = "multi_thread")]
-async fn it_works_deletion() {
-    // set up graph
-    let mut g = start_simple_unsharded("it_works_deletion").await;
-    let (a, b) = g
-        .migrate(|mig| {
-            let a = mig.add_base(
-                "a",
-                make_columns(&["x", "y"]),
-                Base::new().with_primary_key([1]),
-            );
-            let b = mig.add_base(
-                "b",
-                make_columns(&["_", "x", "y"]),
-                Base::new().with_primary_key([2]),
-            );
+async fn statistics_counters() {
+    let mut g = start_simple_unsharded("statistics_counters").await;
+    let a = g
+        .migrate(|mig| mig.add_base("a", make_columns(&["a", "b"]), Base::default()))
+        .await;
 
-            let mut emits = HashMap::new();
-            emits.insert(a, vec![0, 1]);
-            emits.insert(b, vec![1, 2]);
-            let u = Union::new(emits, union::DuplicateMode::UnionAll).unwrap();
-            let c = mig.add_ingredient("c", make_columns(&["x", "y"]), u);
-            mig.maintain_anonymous(c, &Index::hash_map(vec![0]));
-            (a, b)
+    let mut muta = g.table_by_index(a).await.unwrap();
+    muta.insert(vec![1.into(), 2.into()]).await.unwrap();
+    sleep().await;
+
+    let _ = g
+        .migrate(move |mig| {
+            let b = mig.add_ingredient("b", make_columns(&["a", "b"]), Identity::new(a));
+            mig.maintain_anonymous(b, &Index::hash_map(vec![0]));
+            b
         })
         .await;
+    sleep().await;
 
-    let mut cq = g.view("c").await.unwrap().into_reader_handle().unwrap();
-    let mut muta = g.table_by_index(a).await.unwrap();
-    let mut mutb = g.table_by_index(b).await.unwrap();
+    let mut bq = g.view("b").await.unwrap().into_reader_handle().unwrap();
+    let res = bq.lookup(&[1.into()], true).await.unwrap().into_vec();
+    assert_eq!(res, vec![vec![DfValue::from(1), DfValue::from(2)]]);
+
+    let stats = g.statistics().await.unwrap();
+    let (domain_stats, node_stats) = stats
+        .values()
+        .find(|(domain_stats, _)| domain_stats.counters.misses > 0)
+        .unwrap();
+    assert!(domain_stats.counters.packets_processed > 0);
+    assert!(domain_stats.counters.replays_requested > 0);
+    assert!(node_stats.values().any(|n| n.counters.misses > 0));
+    #[cfg(unix)]
+    assert!(domain_stats.cpu_time.unwrap() > 0);
+    let reset_at = domain_stats.counters_reset_at;
+
+    let reader_stats = stats
+        .values()
+        .flat_map(|(_, node_stats)| node_stats.values())
+        .find_map(|n| n.reader.clone())
+        .unwrap();
+    assert_eq!(reader_stats.key_count, 1);
+    assert_eq!(reader_stats.hottest_keys, vec![(vec![DfValue::from(1)], 1)]);
+    assert!(reader_stats.value_sizes.max > 0);
+
+    g.reset_statistics().await.unwrap();
+
+    let stats = g.statistics().await.unwrap();
+    for (domain_stats, node_stats) in stats.values() {
+        assert!(domain_stats.counters_reset_at > reset_at);
+        assert!(domain_stats.collected_at >= domain_stats.counters_reset_at);
+        assert_eq!(domain_stats.counters.replays_requested, 0);
+        assert_eq!(domain_stats.counters.misses, 0);
+        for n in node_stats.values() {
+            assert_eq!(n.counters.replays_requested, 0);
+            assert_eq!(n.counters.misses, 0);
+            if let Some(reader_stats) = &n.reader {
+                assert!(reader_stats.hottest_keys.is_empty());
+            }
+        }
+    }
+}
 
-    // send a value on a
+#[tokio::test(flavor = "multi_thread")]
+async fn statistics_counters_count_domain_misses_once() {
+    let mut g = start_simple_unsharded("statistics_counters_count_domain_misses_once").await;
+    let a = g
+        .migrate(|mig| mig.add_base("a", make_columns(&["a", "b"]), Base::default()))
+        .await;
+
+    let mut muta = g.table_by_index(a).await.unwrap();
     muta.insert(vec![1.into(), 2.into()]).await.unwrap();
+    muta.insert(vec![2.into(), 2.into()]).await.unwrap();
+    sleep().await;
+
+    let _ = g
+        .migrate(move |mig| {
+            let agg = mig.add_ingredient(
+                "agg",
+                make_columns(&["b", "c"]),
+                Aggregation::Count
+                    .over(a, 0, &[1], &DfType::Unknown)
+                    .unwrap(),
+            );
+            mig.maintain_anonymous(agg, &Index::hash_map(vec![0]));
+            agg
+        })
+        .await;
     sleep().await;
+
+    let mut aggq = g.view("agg").await.unwrap().into_reader_handle().unwrap();
+    let res = aggq.lookup(&[2.into()], true).await.unwrap().into_vec();
+    assert_eq!(res, vec![vec![DfValue::from(2), DfValue::from(2)]]);
+
+    // The miss in the reader triggers a replay which misses again in the aggregation, but both
+    // are the result of the same read
+    let stats = g.statistics().await.unwrap();
+    let (domain_stats, node_stats) = stats
+        .values()
+        .find(|(domain_stats, _)| domain_stats.counters.misses > 0)
+        .unwrap();
+    assert_eq!(domain_stats.counters.misses, 1);
     assert_eq!(
-        cq.lookup(&[1.into()], true).await.unwrap().into_vec(),
-        vec![vec![1.into(), 2.into()]]
+        node_stats.values().map(|n| n.counters.misses).sum::<u64>(),
+        2
     );
+}
 
-    // send a value on b
-    mutb.insert(vec![0.into(), 1.into(), 4.into()])
-        .await
-        .unwrap();
-    sleep().await;
+#[tokio::test(flavor = "multi_thread")]
+async fn cache_read_history() {
+    let mut g = start_simple_unsharded("cache_read_history").await;
+    let a = g
+        .migrate(|mig| mig.add_base("a", make_columns(&["a", "b"]), Base::default()))
+        .await;
 
-    let res = cq.lookup(&[1.into()], true).await.unwrap().into_vec();
-    assert_eq!(res.len(), 2);
-    assert!(res.contains(&vec![1.into(), 2.into()]));
-    assert!(res.contains(&vec![1.into(), 4.into()]));
+    let mut muta = g.table_by_index(a).await.unwrap();
+    muta.insert(vec![1.into(), 2.into()]).await.unwrap();
+    sleep().await;
 
-    // delete first value
-    muta.delete(vec![2.into()]).await.unwrap();
+    let _ = g
+        .migrate(move |mig| {
+            let b = mig.add_ingredient("b", make_columns(&["a", "b"]), Identity::new(a));
+            mig.maintain_anonymous(b, &Index::hash_map(vec![0]));
+            b
+        })
+        .await;
     sleep().await;
+
+    let mut bq = g.view("b").await.unwrap().into_reader_handle().unwrap();
+    for _ in 0..2 {
+        let res = bq.lookup(&[1.into()], true).await.unwrap().into_vec();
+        assert_eq!(res, vec![vec![DfValue::from(1), DfValue::from(2)]]);
+    }
+
+    let history = g.cache_read_history().await.unwrap();
+    let buckets = &history[&Relation::from("b")];
+    assert_eq!(buckets.iter().map(|b| b.reads).sum::<u64>(), 2);
+    assert_eq!(buckets.iter().map(|b| b.misses).sum::<u64>(), 1);
+
+    // Read history is retained across resets of the cumulative counters
+    g.reset_statistics().await.unwrap();
+    let history = g.cache_read_history().await.unwrap();
     assert_eq!(
-        cq.lookup(&[1.into()], true).await.unwrap().into_vec(),
-        vec![vec![1.into(), 4.into()]]
+        history[&Relation::from("b")]
+            .iter()
+            .map(|b| b.reads)
+            .sum::<u64>(),
+        2
     );
 }
 
+#[cfg(feature = "failure_injection")]
 #[tokio::test(flavor = "multi_thread")]
-async fn delete_row() {
-    let mut g = start_simple_unsharded("delete_row").await;
-    g.extend_recipe(
+async fn restart_panicked_domain() {
+    let mut g = start_simple_unsharded("restart_panicked_domain").await;
+    let a = g
+        .migrate(|mig| mig.add_base("a", make_columns(&["a", "b"]), Base::default()))
+        .await;
+    let _ = g
+        .migrate(move |mig| {
+            let b = mig.add_ingredient("b", make_columns(&["a", "b"]), Identity::new(a));
+            mig.maintain_anonymous(b, &Index::hash_map(vec![0]));
+            b
+        })
+        .await;
+
+    let mut muta = g.table_by_index(a).await.unwrap();
+    muta.insert(vec![1.into(), 2.into()]).await.unwrap();
+    sleep().await;
+
+    let stats = g.statistics().await.unwrap();
+    let reader_domain = stats
+        .domains
+        .iter()
+        .find(|(_, (_, nodes))| nodes.values().any(|n| n.reader.is_some()))
+        .unwrap()
+        .0
+        .domain_index;
+
+    // Make the domain containing the reader for `b` panic on the next packet it handles, which
+    // will be the next write to `a`
+    fail::cfg(
+        readyset_client::failpoints::DOMAIN_PANIC,
+        &format!("return({})", reader_domain.index()),
+    )
+    .unwrap();
+    muta.insert(vec![1.into(), 3.into()]).await.unwrap();
+    eventually!(run_test: {
+        fail::list()
+    }, then_assert: |failpoints| {
+        assert!(failpoints
+            .iter()
+            .all(|(name, _)| name != readyset_client::failpoints::DOMAIN_PANIC));
+    });
+
+    // The domain is restarted, and its state rebuilt from `a`, including the write it panicked on
+    eventually!(run_test: {
+        async {
+            let mut bq = g.view("b").await?.into_reader_handle().unwrap();
+            bq.lookup(&[1.into()], true).await
+        }
+        .await
+        .map(|res| res.into_vec())
+    }, then_assert: |res| {
+        let mut rows = res.unwrap();
+        rows.sort();
+        assert_eq!(
+            rows,
+            vec![
+                vec![DfValue::from(1), DfValue::from(2)],
+                vec![DfValue::from(1), DfValue::from(3)],
+            ]
+        );
+    });
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn canonical_plan_is_independent_of_numbering() {
+    let schema = "CREATE TABLE t1 (id int, x int, y int);
+         CREATE TABLE t2 (id int, z int);";
+    let cache = "CREATE CACHE q FROM
+         SELECT t1.x, t2.z FROM t1 JOIN t2 ON t1.id = t2.id
+         WHERE t1.y = ? UNION SELECT t1.x, t1.y FROM t1 WHERE t1.y = ?;";
+
+    let mut g1 = start_simple_unsharded("canonical_plan_1").await;
+    g1.extend_recipe(ChangeList::from_str(schema, Dialect::DEFAULT_MYSQL).unwrap())
+        .await
+        .unwrap();
+    g1.extend_recipe(ChangeList::from_str(cache, Dialect::DEFAULT_MYSQL).unwrap())
+        .await
+        .unwrap();
+
+    // Migrate some unrelated tables and caches first, so that the nodes and domains for `q` are
+    // numbered differently
+    let mut g2 = start_simple_unsharded("canonical_plan_2").await;
+    g2.extend_recipe(
         ChangeList::from_str(
-            "CREATE TABLE t1 (x int, y int, z int);
-             CREATE CACHE all_rows FROM SELECT * FROM t1;",
+            "CREATE TABLE other (a int, b int);
+             CREATE CACHE other_q FROM SELECT a FROM other WHERE b = ?;",
             Dialect::DEFAULT_MYSQL,
         )
         .unwrap(),
     )
     .await
     .unwrap();
-    let mut t = g.table("t1").await.unwrap();
-    let mut all_rows = g
-        .view("all_rows")
+    g2.extend_recipe(ChangeList::from_str(schema, Dialect::DEFAULT_MYSQL).unwrap())
         .await
-        .unwrap()
-        .into_reader_handle()
         .unwrap();
-
-    t.insert_many(vec![
-        vec![DfValue::from(1), DfValue::from(2), DfValue::from(3)],
-        vec![DfValue::from(1), DfValue::from(2), DfValue::from(3)],
-        vec![DfValue::from(4), DfValue::from(5), DfValue::from(6)],
-    ])
-    .await
-    .unwrap();
-
-    t.delete_row(vec![DfValue::from(1), DfValue::from(2), DfValue::from(3)])
+    g2.extend_recipe(ChangeList::from_str(cache, Dialect::DEFAULT_MYSQL).unwrap())
         .await
         .unwrap();
 
-    assert_eq!(
-        all_rows.lookup(&[0.into()], true).await.unwrap().into_vec(),
-        vec![
-            vec![DfValue::from(1), DfValue::from(2), DfValue::from(3)],
-            vec![DfValue::from(4), DfValue::from(5), DfValue::from(6)],
-        ]
-    );
+    let plan = g1.canonical_plan("q").await.unwrap();
+    assert_eq!(plan, g2.canonical_plan("q").await.unwrap());
+    assert!(plan.contains("name: `t1`"), "{plan}");
+    assert!(plan.contains("Internal (Join)"), "{plan}");
+    assert!(plan.contains("Reader"), "{plan}");
+
+    g1.canonical_plan("nonexistent").await.unwrap_err();
 }
 
 #[tokio::test(flavor = "multi_thread")]
-async fn it_works_with_sql_recipe() {
-    let mut g = start_simple_unsharded("it_works_with_sql_recipe").await;
-    let sql = "
-        CREATE TABLE Car (id int, brand varchar(255), PRIMARY KEY(id));
-        CREATE CACHE CountCars FROM SELECT COUNT(*) FROM Car WHERE brand = ?;
-    ";
-    g.extend_recipe(ChangeList::from_str(sql, Dialect::DEFAULT_MYSQL).unwrap())
-        .await
-        .unwrap();
-
-    let mut mutator = g.table("Car").await.unwrap();
-    let mut getter = g
-        .view("CountCars")
-        .await
-        .unwrap()
-        .into_reader_handle()
-        .unwrap();
-
-    assert_eq!(*mutator.table_name(), "Car".into());
-    assert_eq!(mutator.columns(), &["id", "brand"]);
+async fn sharded_migration_planning_is_deterministic() {
+    // The join is on columns neither table is sharded by, so both sides need to be resharded, and
+    // the aggregate needs its input resharded by the group column
+    let recipe = "CREATE TABLE t1 (id int, x int, y int, PRIMARY KEY(id));
+         CREATE TABLE t2 (id int, z int, PRIMARY KEY(id));
+         CREATE CACHE q1 FROM SELECT t1.x, t2.id FROM t1 JOIN t2 ON t1.y = t2.z WHERE t1.x = ?;
+         CREATE CACHE q2 FROM SELECT count(id) FROM t1 WHERE y = ?;";
 
-    let brands = vec!["Volvo", "Volvo", "Volkswagen"];
-    for (i, &brand) in brands.iter().enumerate() {
-        mutator
-            .insert(vec![i.into(), brand.try_into().unwrap()])
+    let mut graphs = vec![];
+    for i in 0..2 {
+        let mut g = start_simple(&format!("sharded_migration_planning_{i}")).await;
+        g.extend_recipe(ChangeList::from_str(recipe, Dialect::DEFAULT_MYSQL).unwrap())
             .await
             .unwrap();
+        graphs.push(g.graphviz().await.unwrap());
     }
 
-    // Let writes propagate:
-    sleep().await;
-
-    // Retrieve the result of the count query:
-    let result = getter
-        .lookup(&["Volvo".try_into().unwrap()], true)
-        .await
-        .unwrap()
-        .into_vec();
-    assert_eq!(result.len(), 1);
-    assert_eq!(result[0][0], 2.into());
+    assert!(graphs[0].contains("shard by"), "{}", graphs[0]);
+    assert_eq!(graphs[0], graphs[1]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn co_partitioned_group_by_is_not_reshuffled() {
+    let mut g = start_simple("co_partitioned_group_by_is_not_reshuffled").await;
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE TABLE t (tenant int, x int, v int, PRIMARY KEY (tenant));
+             CREATE CACHE q FROM
+             SELECT tenant, x, count(v) FROM t WHERE tenant = ? GROUP BY tenant, x;",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    // `t` is sharded by `tenant`, so grouping by a compound key that includes `tenant` can be done
+    // on each shard, without shuffling or de-sharding
+    let plan = g.canonical_plan("q").await.unwrap();
+    assert!(!plan.contains("Sharder"), "{plan}");
+    assert!(!plan.contains("ForcedNone"), "{plan}");
+
+    let mut t = g.table("t").await.unwrap();
+    t.insert_many((1..=4).map(|i| vec![DfValue::from(i), DfValue::from(i % 2), DfValue::from(i)]))
+        .await
+        .unwrap();
+    sleep().await;
+
+    let mut q = g.view("q").await.unwrap().into_reader_handle().unwrap();
+    for i in 1..=4 {
+        let rows = q
+            .lookup(&[DfValue::from(i)], true)
+            .await
+            .unwrap()
+            .into_vec();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0][..2], [DfValue::from(i), DfValue::from(i % 2)]);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn caches_sharing_a_filter() {
+    let mut g = start_simple_unsharded("caches_sharing_a_filter").await;
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE TABLE t (id int, x int, v int);
+             CREATE CACHE q1 FROM SELECT id, x FROM t WHERE v > 5 AND id = ?;
+             CREATE CACHE q2 FROM SELECT id, v FROM t WHERE v > 5 AND id = ?;",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let mut t = g.table("t").await.unwrap();
+    t.insert_many((1..=10).map(|i| vec![DfValue::from(i % 2), DfValue::from(i), DfValue::from(i)]))
+        .await
+        .unwrap();
+    sleep().await;
+
+    let mut q1 = g.view("q1").await.unwrap().into_reader_handle().unwrap();
+    let mut q2 = g.view("q2").await.unwrap().into_reader_handle().unwrap();
+    for id in 0..=1 {
+        let expected = (6..=10).filter(|i| i % 2 == id).collect::<Vec<i32>>();
+        for q in [&mut q1, &mut q2] {
+            let mut values = q
+                .lookup(&[DfValue::from(id)], true)
+                .await
+                .unwrap()
+                .into_vec()
+                .into_iter()
+                .map(|row| i32::try_from(&row[1]).unwrap())
+                .collect::<Vec<_>>();
+            values.sort_unstable();
+            assert_eq!(values, expected);
+        }
+    }
+
+    // A cache over the same table without the filter still sees every row
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE CACHE q3 FROM SELECT id, v FROM t WHERE id = ?;",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+    t.insert(vec![DfValue::from(0), DfValue::from(0), DfValue::from(0)])
+        .await
+        .unwrap();
+    sleep().await;
+
+    let mut q3 = g.view("q3").await.unwrap().into_reader_handle().unwrap();
+    let rows = q3
+        .lookup(&[DfValue::from(0)], true)
+        .await
+        .unwrap()
+        .into_vec();
+    assert_eq!(rows.len(), 6);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn override_mir_plan() {
+    let mut g = start_simple_unsharded("override_mir_plan").await;
+    let schema = "CREATE TABLE t1 (id int, x int);
+                  CREATE TABLE t2 (id int, z int);";
+    let cache = "CREATE CACHE MAX ROWS PER KEY 1 ON LIMIT ERROR q FROM
+                 SELECT t1.x, t2.z FROM t1 JOIN t2 ON t1.id = t2.id WHERE t1.x = ?;";
+    g.extend_recipe(
+        ChangeList::from_str(format!("{schema}{cache}"), Dialect::DEFAULT_MYSQL).unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let mut t1 = g.table("t1").await.unwrap();
+    let mut t2 = g.table("t2").await.unwrap();
+    t1.insert(vec![DfValue::from(1), DfValue::from(10)])
+        .await
+        .unwrap();
+    t2.insert(vec![DfValue::from(1), DfValue::from(100)])
+        .await
+        .unwrap();
+    t1.insert(vec![DfValue::from(2), DfValue::from(20)])
+        .await
+        .unwrap();
+    t2.insert_many(vec![
+        vec![DfValue::from(2), DfValue::from(200)],
+        vec![DfValue::from(2), DfValue::from(201)],
+    ])
+    .await
+    .unwrap();
+    sleep().await;
+
+    let plan: MirPlan = serde_json::from_str(&g.mir_plan("q").await.unwrap()).unwrap();
+    // A plan without a leaf is rejected
+    let invalid = MirPlan {
+        nodes: plan.nodes[..plan.nodes.len() - 1].to_vec(),
+    };
+
+    // Swap the sides of the join
+    let mut swapped = plan;
+    let join = swapped
+        .nodes
+        .iter_mut()
+        .find(|node| matches!(node.inner, MirNodeInner::Join { .. }))
+        .unwrap();
+    join.parents.reverse();
+    if let MirNodeInner::Join { on, .. } = &mut join.inner {
+        for (left, right) in on {
+            std::mem::swap(left, right);
+        }
+    }
+    g.set_mir_plan(
+        "q",
+        serde_json::to_string(&swapped).unwrap(),
+        Dialect::DEFAULT_MYSQL,
+    )
+    .await
+    .unwrap();
+    g.set_mir_plan(
+        "q",
+        serde_json::to_string(&invalid).unwrap(),
+        Dialect::DEFAULT_MYSQL,
+    )
+    .await
+    .unwrap_err();
+    let overrides = g.mir_plan_overrides().await.unwrap();
+    assert_eq!(overrides.len(), 1);
+    assert_eq!(overrides[0].0, Relation::from("q"));
+
+    let mut q = g.view("q").await.unwrap().into_reader_handle().unwrap();
+    assert_eq!(
+        q.lookup(&[DfValue::from(10)], true)
+            .await
+            .unwrap()
+            .into_vec(),
+        vec![vec![DfValue::from(10), DfValue::from(100)]]
+    );
+    // The new reader keeps the cache's result limits
+    q.lookup(&[DfValue::from(20)], true).await.unwrap_err();
+
+    // Replaying the cache definitions and the overridden plan against a fresh deployment recreates
+    // the same dataflow
+    let mut g2 = start_simple_unsharded("override_mir_plan_import").await;
+    g2.extend_recipe(
+        ChangeList::from_str(
+            format!(
+                "{schema}{cache}{};",
+                AlterReadysetStatement::SetMirPlan {
+                    cache: overrides[0].0.clone(),
+                    plan: overrides[0].1.clone(),
+                }
+            ),
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(g2.mir_plan_overrides().await.unwrap(), overrides);
+    assert_eq!(
+        g2.canonical_plan("q").await.unwrap(),
+        g.canonical_plan("q").await.unwrap()
+    );
+
+    // Dropping the cache forgets its plan
+    g.extend_recipe(ChangeList::from_str("DROP CACHE q", Dialect::DEFAULT_MYSQL).unwrap())
+        .await
+        .unwrap();
+    assert!(g.mir_plan_overrides().await.unwrap().is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn add_index_to_existing_state() {
+    let mut g = Builder::for_tests();
+    g.disable_partial();
+    g.set_sharding(None);
+    g.set_persistence(get_persistence_params("add_index_to_existing_state"));
+    let mut g = g.start_local().await.unwrap();
+    let a = g
+        .migrate(|mig| {
+            let a = mig.add_base("a", make_columns(&["a", "b"]), Base::default());
+            let agg = mig.add_ingredient(
+                "agg",
+                make_columns(&["b", "c"]),
+                Aggregation::Count
+                    .over(a, 0, &[1], &DfType::Unknown)
+                    .unwrap(),
+            );
+            let q = mig.add_ingredient("q", make_columns(&["b", "c"]), Identity::new(agg));
+            mig.maintain_anonymous_with_reader_processing(
+                q,
+                &Index::hash_map(vec![0]),
+                ReaderProcessing::new(None, None, Some(vec![0, 1]), None, None).unwrap(),
+            );
+            a
+        })
+        .await;
+
+    let mut muta = g.table_by_index(a).await.unwrap();
+    muta.insert(vec![1.into(), 2.into()]).await.unwrap();
+    muta.insert(vec![2.into(), 2.into()]).await.unwrap();
+    sleep().await;
+
+    // The index is traced through the identity to the aggregation feeding it
+    assert!(g.add_index(&"q".into(), vec![1]).await.unwrap());
+    // ...but only added once
+    assert!(!g.add_index(&"q".into(), vec![1]).await.unwrap());
+    g.add_index(&"q".into(), vec![5]).await.unwrap_err();
+
+    muta.insert(vec![3.into(), 2.into()]).await.unwrap();
+    sleep().await;
+
+    let mut q = g.view("q").await.unwrap().into_reader_handle().unwrap();
+    let res = q.lookup(&[2.into()], true).await.unwrap().into_vec();
+    assert_eq!(res, vec![vec![DfValue::from(2), DfValue::from(3)]]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn it_works_deletion() {
+    // set up graph
+    let mut g = start_simple_unsharded("it_works_deletion").await;
+    let (a, b) = g
+        .migrate(|mig| {
+            let a = mig.add_base(
+                "a",
+                make_columns(&["x", "y"]),
+                Base::new().with_primary_key([1]),
+            );
+            let b = mig.add_base(
+                "b",
+                make_columns(&["_", "x", "y"]),
+                Base::new().with_primary_key([2]),
+            );
+
+            let mut emits = HashMap::new();
+            emits.insert(a, vec![0, 1]);
+            emits.insert(b, vec![1, 2]);
+            let u = Union::new(emits, union::DuplicateMode::UnionAll).unwrap();
+            let c = mig.add_ingredient("c", make_columns(&["x", "y"]), u);
+            mig.maintain_anonymous(c, &Index::hash_map(vec![0]));
+            (a, b)
+        })
+        .await;
+
+    let mut cq = g.view("c").await.unwrap().into_reader_handle().unwrap();
+    let mut muta = g.table_by_index(a).await.unwrap();
+    let mut mutb = g.table_by_index(b).await.unwrap();
+
+    // send a value on a
+    muta.insert(vec![1.into(), 2.into()]).await.unwrap();
+    sleep().await;
+    assert_eq!(
+        cq.lookup(&[1.into()], true).await.unwrap().into_vec(),
+        vec![vec![1.into(), 2.into()]]
+    );
+
+    // send a value on b
+    mutb.insert(vec![0.into(), 1.into(), 4.into()])
+        .await
+        .unwrap();
+    sleep().await;
+
+    let res = cq.lookup(&[1.into()], true).await.unwrap().into_vec();
+    assert_eq!(res.len(), 2);
+    assert!(res.contains(&vec![1.into(), 2.into()]));
+    assert!(res.contains(&vec![1.into(), 4.into()]));
+
+    // delete first value
+    muta.delete(vec![2.into()]).await.unwrap();
+    sleep().await;
+    assert_eq!(
+        cq.lookup(&[1.into()], true).await.unwrap().into_vec(),
+        vec![vec![1.into(), 4.into()]]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn delete_row() {
+    let mut g = start_simple_unsharded("delete_row").await;
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE TABLE t1 (x int, y int, z int);
+             CREATE CACHE all_rows FROM SELECT * FROM t1;",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+    let mut t = g.table("t1").await.unwrap();
+    let mut all_rows = g
+        .view("all_rows")
+        .await
+        .unwrap()
+        .into_reader_handle()
+        .unwrap();
+
+    t.insert_many(vec![
+        vec![DfValue::from(1), DfValue::from(2), DfValue::from(3)],
+        vec![DfValue::from(1), DfValue::from(2), DfValue::from(3)],
+        vec![DfValue::from(4), DfValue::from(5), DfValue::from(6)],
+    ])
+    .await
+    .unwrap();
+
+    t.delete_row(vec![DfValue::from(1), DfValue::from(2), DfValue::from(3)])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        all_rows.lookup(&[0.into()], true).await.unwrap().into_vec(),
+        vec![
+            vec![DfValue::from(1), DfValue::from(2), DfValue::from(3)],
+            vec![DfValue::from(4), DfValue::from(5), DfValue::from(6)],
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn it_works_with_sql_recipe() {
+    let mut g = start_simple_unsharded("it_works_with_sql_recipe").await;
+    let sql = "
+        CREATE TABLE Car (id int, brand varchar(255), PRIMARY KEY(id));
+        CREATE CACHE CountCars FROM SELECT COUNT(*) FROM Car WHERE brand = ?;
+    ";
+    g.extend_recipe(ChangeList::from_str(sql, Dialect::DEFAULT_MYSQL).unwrap())
+        .await
+        .unwrap();
+
+    let mut mutator = g.table("Car").await.unwrap();
+    let mut getter = g
+        .view("CountCars")
+        .await
+        .unwrap()
+        .into_reader_handle()
+        .unwrap();
+
+    assert_eq!(*mutator.table_name(), "Car".into());
+    assert_eq!(mutator.columns(), &["id", "brand"]);
+
+    let brands = vec!["Volvo", "Volvo", "Volkswagen"];
+    for (i, &brand) in brands.iter().enumerate() {
+        mutator
+            .insert(vec![i.into(), brand.try_into().unwrap()])
+            .await
+            .unwrap();
+    }
+
+    // Let writes propagate:
+    sleep().await;
+
+    // Retrieve the result of the count query:
+    let result = getter
+        .lookup(&["Volvo".try_into().unwrap()], true)
+        .await
+        .unwrap()
+        .into_vec();
+    assert_eq!(result.len(), 1);
+    assert_eq!(result[0][0], 2.into());
 }
 
 #[tokio::test(flavor = "multi_thread")]
@@ -3615,7 +4256,8 @@ async fn finkelstein1982_queries() {
                     inc.add_table(stmt.table, stmt.body.unwrap(), mig).unwrap();
                 }
                 SqlQuery::Select(stmt) => {
-                    inc.add_query(None, stmt, false, &[], mig).unwrap();
+                    inc.add_query(None, stmt, false, true, false, false, &[], mig)
+                        .unwrap();
                 }
                 _ => panic!("unexpected query type"),
             }
@@ -5122,7 +5764,7 @@ async fn post_read_ilike() {
     let res = reader
         .raw_lookup(ViewQuery {
             key_comparisons: vec![KeyComparison::from_range(&(..))],
-            block: true,
+            read_behavior: ReadBehavior::Blocking,
             filter: Some(DfExpr::Op {
                 left: Box::new(DfExpr::Column {
                     index: 0,
@@ -5134,8 +5776,11 @@ async fn post_read_ilike() {
                     ty: DfType::DEFAULT_TEXT,
                 }),
                 ty: DfType::Bool,
+                dialect: Dialect::DEFAULT_MYSQL,
+                non_null_operands: false,
             }),
             timestamp: None,
+            snapshot: None,
             limit: None,
             offset: None,
         })
@@ -5150,55 +5795,273 @@ async fn post_read_ilike() {
             vec![DfValue::from("baz"), DfValue::from(3)],
             vec![DfValue::from("BAZ"), DfValue::from(4)],
         ]
-    )
+    )
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cast_projection() {
+    let mut g = start_simple_unsharded("cast").await;
+
+    g.extend_recipe(
+            ChangeList::from_str("CREATE TABLE users (id int, created_at timestamp);
+         CREATE CACHE user FROM SELECT id, CAST(created_at AS date) AS created_day FROM users WHERE id = ?;", Dialect::DEFAULT_MYSQL)
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let mut table = g.table("users").await.unwrap();
+    table
+        .insert(vec![
+            1i32.into(),
+            NaiveDate::from_ymd(2020, 3, 16).and_hms(16, 40, 30).into(),
+        ])
+        .await
+        .unwrap();
+
+    sleep().await;
+
+    let mut view = g.view("user").await.unwrap().into_reader_handle().unwrap();
+
+    let result = view
+        .lookup(&[1i32.into()], true)
+        .await
+        .unwrap()
+        .into_vec()
+        .remove(0);
+
+    assert_eq!(
+        result,
+        vec![DfValue::from(1), NaiveDate::from_ymd(2020, 3, 16).into()]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn aggregate_expression() {
+    let mut g = start_simple_unsharded("aggregate_expression").await;
+
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE TABLE t (string_num text);
+         CREATE CACHE q FROM SELECT max(cast(t.string_num as int)) as max_num from t;",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let mut t = g.table("t").await.unwrap();
+    let mut q = g.view("q").await.unwrap().into_reader_handle().unwrap();
+
+    t.insert_many(vec![vec![DfValue::from("100")], vec![DfValue::from("5")]])
+        .await
+        .unwrap();
+
+    sleep().await;
+
+    let res = &q
+        .lookup(&[0i32.into()], true)
+        .await
+        .unwrap()
+        .into_vec()
+        .remove(0);
+
+    assert_eq!(get_col!(q, res, "max_num"), &DfValue::from(100));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn aggregate_missing_columns() {
+    let mut g = start_simple_unsharded("aggregate_missing_columns").await;
+
+    g.extend_recipe(
+        ChangeList::from_str("CREATE TABLE t (id INT);", Dialect::DEFAULT_MYSQL).unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let res = g
+        .extend_recipe(
+            ChangeList::from_str(
+                "CREATE CACHE q FROM SELECT max(idd) FROM t",
+                Dialect::DEFAULT_MYSQL,
+            )
+            .unwrap(),
+        )
+        .await;
+    assert!(res.is_err());
+    assert!(res.err().unwrap().to_string().contains("idd"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn post_join_filter() {
+    let mut g = start_simple_unsharded("post_join_filter").await;
+
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE TABLE t1 (id int, val_1 int);
+         CREATE TABLE t2 (id int, val_2 int);
+         CREATE CACHE q FROM
+            SELECT t1.id AS id_1, t1.val_1 AS val_1, t2.val_2 AS val_2
+            FROM t1
+            JOIN t2 ON t1.id = t2.id
+            WHERE t1.val_1 >= t2.val_2",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let mut t1 = g.table("t1").await.unwrap();
+    let mut t2 = g.table("t2").await.unwrap();
+    let mut q = g.view("q").await.unwrap().into_reader_handle().unwrap();
+
+    t1.insert_many(vec![
+        vec![DfValue::from(1), DfValue::from(1)],
+        vec![DfValue::from(2), DfValue::from(2)],
+        vec![DfValue::from(3), DfValue::from(3)],
+        vec![DfValue::from(4), DfValue::from(4)],
+    ])
+    .await
+    .unwrap();
+
+    t2.insert_many(vec![
+        vec![DfValue::from(1), DfValue::from(1)],
+        vec![DfValue::from(2), DfValue::from(1)],
+        vec![DfValue::from(2), DfValue::from(5)],
+        vec![DfValue::from(3), DfValue::from(5)],
+    ])
+    .await
+    .unwrap();
+
+    sleep().await;
+
+    let mut res: Vec<_> = q.lookup(&[0.into()], true).await.unwrap().into();
+    res.sort();
+
+    assert_eq!(
+        res,
+        vec![
+            vec![1.into(), 1.into(), 1.into()],
+            vec![2.into(), 2.into(), 1.into()],
+        ]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+/// Tests the case where two tables have the same column name and those columns are
+/// used in a post-join filter.
+async fn duplicate_column_names() {
+    let mut g = start_simple_unsharded("duplicate_column_names").await;
+
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE TABLE t1 (id int, val int);
+         CREATE TABLE t2 (id int, val int);
+         CREATE CACHE q FROM
+            SELECT t1.id AS id_1, t1.val AS val_1, t2.val AS val_2
+            FROM t1
+            JOIN t2 ON t1.id = t2.id
+            WHERE t1.val >= t2.val",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let mut t1 = g.table("t1").await.unwrap();
+    let mut t2 = g.table("t2").await.unwrap();
+    let mut q = g.view("q").await.unwrap().into_reader_handle().unwrap();
+
+    t1.insert_many(vec![
+        vec![DfValue::from(1), DfValue::from(1)],
+        vec![DfValue::from(2), DfValue::from(2)],
+        vec![DfValue::from(3), DfValue::from(3)],
+        vec![DfValue::from(4), DfValue::from(4)],
+    ])
+    .await
+    .unwrap();
+
+    t2.insert_many(vec![
+        vec![DfValue::from(1), DfValue::from(1)],
+        vec![DfValue::from(2), DfValue::from(1)],
+        vec![DfValue::from(2), DfValue::from(5)],
+        vec![DfValue::from(3), DfValue::from(5)],
+    ])
+    .await
+    .unwrap();
+
+    sleep().await;
+
+    let mut res: Vec<_> = q.lookup(&[0.into()], true).await.unwrap().into();
+    res.sort();
+
+    assert_eq!(
+        res,
+        vec![
+            vec![1.into(), 1.into(), 1.into()],
+            vec![2.into(), 2.into(), 1.into()]
+        ]
+    );
 }
-
 #[tokio::test(flavor = "multi_thread")]
-async fn cast_projection() {
-    let mut g = start_simple_unsharded("cast").await;
+async fn filter_on_expression() {
+    let mut g = start_simple_unsharded("filter_on_expression").await;
 
     g.extend_recipe(
-            ChangeList::from_str("CREATE TABLE users (id int, created_at timestamp);
-         CREATE CACHE user FROM SELECT id, CAST(created_at AS date) AS created_day FROM users WHERE id = ?;", Dialect::DEFAULT_MYSQL)
+        ChangeList::from_str(
+            "CREATE TABLE users (id int, birthday date);
+         CREATE CACHE friday_babies FROM SELECT id FROM users WHERE dayofweek(birthday) = 6;",
+            Dialect::DEFAULT_MYSQL,
+        )
         .unwrap(),
     )
     .await
     .unwrap();
 
-    let mut table = g.table("users").await.unwrap();
-    table
-        .insert(vec![
-            1i32.into(),
-            NaiveDate::from_ymd(2020, 3, 16).and_hms(16, 40, 30).into(),
-        ])
+    let mut t = g.table("users").await.unwrap();
+    let mut q = g
+        .view("friday_babies")
         .await
+        .unwrap()
+        .into_reader_handle()
         .unwrap();
 
-    sleep().await;
+    t.insert_many(vec![
+        vec![
+            DfValue::from(1),
+            DfValue::from(NaiveDate::from_ymd(1995, 6, 2)),
+        ],
+        vec![
+            DfValue::from(2),
+            DfValue::from(NaiveDate::from_ymd(2015, 5, 15)),
+        ],
+    ])
+    .await
+    .unwrap();
 
-    let mut view = g.view("user").await.unwrap().into_reader_handle().unwrap();
+    sleep().await;
 
-    let result = view
-        .lookup(&[1i32.into()], true)
-        .await
-        .unwrap()
-        .into_vec()
-        .remove(0);
+    let res = &q.lookup(&[0i32.into()], true).await.unwrap().into_vec();
 
-    assert_eq!(
-        result,
-        vec![DfValue::from(1), NaiveDate::from_ymd(2020, 3, 16).into()]
-    );
+    assert_eq!(get_col!(q, res[0], "id"), &DfValue::from(1));
 }
 
 #[tokio::test(flavor = "multi_thread")]
-async fn aggregate_expression() {
-    let mut g = start_simple_unsharded("aggregate_expression").await;
-
+async fn compound_join_key() {
+    let mut g = start_simple_unsharded("compound_join_key").await;
     g.extend_recipe(
         ChangeList::from_str(
-            "CREATE TABLE t (string_num text);
-         CREATE CACHE q FROM SELECT max(cast(t.string_num as int)) as max_num from t;",
+            "
+      CREATE TABLE t1 (id_1 int, id_2 int, val_1 int);
+      CREATE TABLE t2 (id_1 int, id_2 int, val_2 int);
+      CREATE CACHE q FROM
+        SELECT t1.val_1, t2.val_2
+        FROM t1
+        JOIN t2
+          ON t1.id_1 = t2.id_1 AND t1.id_2 = t2.id_2;",
             Dialect::DEFAULT_MYSQL,
         )
         .unwrap(),
@@ -5206,61 +6069,125 @@ async fn aggregate_expression() {
     .await
     .unwrap();
 
-    let mut t = g.table("t").await.unwrap();
+    let mut t1 = g.table("t1").await.unwrap();
+    let mut t2 = g.table("t2").await.unwrap();
     let mut q = g.view("q").await.unwrap().into_reader_handle().unwrap();
 
-    t.insert_many(vec![vec![DfValue::from("100")], vec![DfValue::from("5")]])
-        .await
-        .unwrap();
+    t1.insert_many(vec![
+        vec![
+            DfValue::from(1i32),
+            DfValue::from(2i32),
+            DfValue::from(3i32),
+        ],
+        vec![
+            DfValue::from(1i32),
+            DfValue::from(3i32),
+            DfValue::from(4i32),
+        ],
+        vec![
+            DfValue::from(2i32),
+            DfValue::from(3i32),
+            DfValue::from(4i32),
+        ],
+        vec![
+            DfValue::from(2i32),
+            DfValue::from(4i32),
+            DfValue::from(5i32),
+        ],
+    ])
+    .await
+    .unwrap();
+
+    t2.insert_many(vec![
+        vec![
+            DfValue::from(1i32),
+            DfValue::from(2i32),
+            DfValue::from(33i32),
+        ],
+        vec![
+            DfValue::from(1i32),
+            DfValue::from(3i32),
+            DfValue::from(44i32),
+        ],
+        vec![
+            DfValue::from(1i32),
+            DfValue::from(4i32),
+            DfValue::from(123i32),
+        ],
+        vec![
+            DfValue::from(2i32),
+            DfValue::from(3i32),
+            DfValue::from(44i32),
+        ],
+        vec![
+            DfValue::from(2i32),
+            DfValue::from(5i32),
+            DfValue::from(123i32),
+        ],
+    ])
+    .await
+    .unwrap();
 
     sleep().await;
 
-    let res = &q
+    let res = q
         .lookup(&[0i32.into()], true)
         .await
         .unwrap()
-        .into_vec()
-        .remove(0);
+        .into_iter()
+        .map(|r| (get_col!(q, r, "val_1", i32), get_col!(q, r, "val_2", i32)))
+        .sorted()
+        .collect::<Vec<(i32, i32)>>();
 
-    assert_eq!(get_col!(q, res, "max_num"), &DfValue::from(100));
+    assert_eq!(res, vec![(3, 33), (4, 44), (4, 44)]);
 }
 
 #[tokio::test(flavor = "multi_thread")]
-async fn aggregate_missing_columns() {
-    let mut g = start_simple_unsharded("aggregate_missing_columns").await;
+async fn left_join_null() {
+    let mut g = start_simple_unsharded("left_join_null").await;
 
     g.extend_recipe(
-        ChangeList::from_str("CREATE TABLE t (id INT);", Dialect::DEFAULT_MYSQL).unwrap(),
+            ChangeList::from_str("CREATE TABLE jim (id int, a int);
+         CREATE TABLE bob (id int);
+         CREATE CACHE funky FROM SELECT * FROM jim LEFT JOIN bob ON jim.id = bob.id WHERE bob.id IS NULL;", Dialect::DEFAULT_MYSQL)
+        .unwrap(),
     )
     .await
     .unwrap();
 
-    let res = g
-        .extend_recipe(
-            ChangeList::from_str(
-                "CREATE CACHE q FROM SELECT max(idd) FROM t",
-                Dialect::DEFAULT_MYSQL,
-            )
-            .unwrap(),
-        )
-        .await;
-    assert!(res.is_err());
-    assert!(res.err().unwrap().to_string().contains("idd"));
+    let mut t = g.table("jim").await.unwrap();
+    let mut t2 = g.table("bob").await.unwrap();
+    let mut q = g.view("funky").await.unwrap().into_reader_handle().unwrap();
+
+    t.insert_many(vec![
+        vec![DfValue::from(1), DfValue::from(2)],
+        vec![DfValue::from(3), DfValue::from(6)],
+        vec![DfValue::from(4), DfValue::from(6)],
+    ])
+    .await
+    .unwrap();
+    t2.insert_many(vec![vec![DfValue::from(3)]]).await.unwrap();
+
+    sleep().await;
+
+    let num_res = q
+        .lookup(&[0.into()], true)
+        .await
+        .unwrap()
+        .into_iter()
+        .count();
+    assert_eq!(num_res, 2);
 }
 
 #[tokio::test(flavor = "multi_thread")]
-async fn post_join_filter() {
-    let mut g = start_simple_unsharded("post_join_filter").await;
+async fn not_in_subquery_with_nulls() {
+    let mut g = start_simple_unsharded("not_in_subquery_with_nulls").await;
 
     g.extend_recipe(
         ChangeList::from_str(
-            "CREATE TABLE t1 (id int, val_1 int);
-         CREATE TABLE t2 (id int, val_2 int);
-         CREATE CACHE q FROM
-            SELECT t1.id AS id_1, t1.val_1 AS val_1, t2.val_2 AS val_2
-            FROM t1
-            JOIN t2 ON t1.id = t2.id
-            WHERE t1.val_1 >= t2.val_2",
+            "CREATE TABLE t1 (x int);
+             CREATE TABLE t2 (y int);
+             CREATE CACHE q FROM SELECT t1.x FROM t1 WHERE t1.x NOT IN (SELECT t2.y FROM t2);",
             Dialect::DEFAULT_MYSQL,
         )
         .unwrap(),
@@ -5272,53 +6199,71 @@ async fn post_join_filter() {
     let mut t2 = g.table("t2").await.unwrap();
     let mut q = g.view("q").await.unwrap().into_reader_handle().unwrap();
 
+    async fn lookup(q: &mut readyset_client::ReaderHandle) -> Vec<Vec<DfValue>> {
+        let mut rows = q.lookup(&[0.into()], true).await.unwrap().into_vec();
+        rows.sort();
+        rows
+    }
+
     t1.insert_many(vec![
-        vec![DfValue::from(1), DfValue::from(1)],
-        vec![DfValue::from(2), DfValue::from(2)],
-        vec![DfValue::from(3), DfValue::from(3)],
-        vec![DfValue::from(4), DfValue::from(4)],
+        vec![DfValue::from(1)],
+        vec![DfValue::from(2)],
+        vec![DfValue::None],
     ])
     .await
     .unwrap();
+    sleep().await;
 
-    t2.insert_many(vec![
-        vec![DfValue::from(1), DfValue::from(1)],
-        vec![DfValue::from(2), DfValue::from(1)],
-        vec![DfValue::from(2), DfValue::from(5)],
-        vec![DfValue::from(3), DfValue::from(5)],
-    ])
-    .await
-    .unwrap();
+    // x NOT IN (<empty>) is true, even if x is NULL
+    assert_eq!(
+        lookup(&mut q).await,
+        vec![
+            vec![DfValue::None],
+            vec![DfValue::from(1)],
+            vec![DfValue::from(2)]
+        ]
+    );
 
+    // NULL NOT IN (<non-empty>) is NULL
+    t2.insert(vec![DfValue::from(1)]).await.unwrap();
     sleep().await;
+    assert_eq!(lookup(&mut q).await, vec![vec![DfValue::from(2)]]);
 
-    let mut res: Vec<_> = q.lookup(&[0.into()], true).await.unwrap().into();
-    res.sort();
+    // x NOT IN (<anything containing NULL>) is never true
+    t2.insert(vec![DfValue::None]).await.unwrap();
+    sleep().await;
+    assert_eq!(lookup(&mut q).await, Vec::<Vec<DfValue>>::new());
+
+    t2.delete_row(vec![DfValue::None]).await.unwrap();
+    sleep().await;
+    assert_eq!(lookup(&mut q).await, vec![vec![DfValue::from(2)]]);
 
+    t2.delete_row(vec![DfValue::from(1)]).await.unwrap();
+    sleep().await;
     assert_eq!(
-        res,
+        lookup(&mut q).await,
         vec![
-            vec![1.into(), 1.into(), 1.into()],
-            vec![2.into(), 2.into(), 1.into()],
+            vec![DfValue::None],
+            vec![DfValue::from(1)],
+            vec![DfValue::from(2)]
         ]
     );
 }
 
 #[tokio::test(flavor = "multi_thread")]
-/// Tests the case where two tables have the same column name and those columns are
-/// used in a post-join filter.
-async fn duplicate_column_names() {
-    let mut g = start_simple_unsharded("duplicate_column_names").await;
+async fn filter_pushdown() {
+    let mut g = start_simple_unsharded("filter_pushdown").await;
 
     g.extend_recipe(
         ChangeList::from_str(
-            "CREATE TABLE t1 (id int, val int);
-         CREATE TABLE t2 (id int, val int);
-         CREATE CACHE q FROM
-            SELECT t1.id AS id_1, t1.val AS val_1, t2.val AS val_2
-            FROM t1
-            JOIN t2 ON t1.id = t2.id
-            WHERE t1.val >= t2.val",
+            "CREATE TABLE jim (id int, a int);
+             CREATE TABLE bob (id int, b int);
+             CREATE CACHE pushed FROM SELECT jim.id, bob.b FROM jim JOIN bob ON jim.id = bob.id \
+                 WHERE jim.a = 6 AND bob.b > 1;
+             CREATE CACHE NO FILTER PUSHDOWN not_pushed FROM SELECT jim.id, bob.b FROM jim \
+                 JOIN bob ON jim.id = bob.id WHERE jim.a = 6 AND bob.b >= 2;
+             CREATE CACHE left_pushed FROM SELECT jim.id, bob.b FROM jim \
+                 LEFT JOIN bob ON jim.id = bob.id WHERE jim.a = 6 AND bob.b IS NULL;",
             Dialect::DEFAULT_MYSQL,
         )
         .unwrap(),
@@ -5326,49 +6271,123 @@ async fn duplicate_column_names() {
     .await
     .unwrap();
 
-    let mut t1 = g.table("t1").await.unwrap();
-    let mut t2 = g.table("t2").await.unwrap();
-    let mut q = g.view("q").await.unwrap().into_reader_handle().unwrap();
+    let mut jim = g.table("jim").await.unwrap();
+    let mut bob = g.table("bob").await.unwrap();
 
-    t1.insert_many(vec![
-        vec![DfValue::from(1), DfValue::from(1)],
+    jim.insert_many(vec![
+        vec![DfValue::from(1), DfValue::from(2)],
+        vec![DfValue::from(2), DfValue::from(6)],
+        vec![DfValue::from(3), DfValue::from(6)],
+        vec![DfValue::from(4), DfValue::from(6)],
+    ])
+    .await
+    .unwrap();
+    bob.insert_many(vec![
+        vec![DfValue::from(1), DfValue::from(2)],
         vec![DfValue::from(2), DfValue::from(2)],
-        vec![DfValue::from(3), DfValue::from(3)],
-        vec![DfValue::from(4), DfValue::from(4)],
+        vec![DfValue::from(3), DfValue::from(1)],
     ])
     .await
     .unwrap();
 
-    t2.insert_many(vec![
-        vec![DfValue::from(1), DfValue::from(1)],
-        vec![DfValue::from(2), DfValue::from(1)],
-        vec![DfValue::from(2), DfValue::from(5)],
-        vec![DfValue::from(3), DfValue::from(5)],
+    sleep().await;
+
+    for cache in ["pushed", "not_pushed"] {
+        let mut q = g.view(cache).await.unwrap().into_reader_handle().unwrap();
+        let res = q.lookup(&[0.into()], true).await.unwrap().into_vec();
+        assert_eq!(
+            res,
+            vec![vec![DfValue::from(2), DfValue::from(2)]],
+            "{cache}"
+        );
+    }
+
+    let mut q = g
+        .view("left_pushed")
+        .await
+        .unwrap()
+        .into_reader_handle()
+        .unwrap();
+    let res = q.lookup(&[0.into()], true).await.unwrap().into_vec();
+    assert_eq!(res, vec![vec![DfValue::from(4), DfValue::None]]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn approximate_aggregates() {
+    let mut g = start_simple_unsharded("approximate_aggregates").await;
+
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE TABLE t (id int primary key, g int, x int);
+             CREATE CACHE APPROXIMATE AGGREGATES approx FROM SELECT g, count(distinct x), \
+                 percentile_cont(0.5) within group (order by x) FROM t WHERE g = ? GROUP BY g;",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let mut t = g.table("t").await.unwrap();
+    t.insert_many(vec![
+        vec![DfValue::from(1), DfValue::from(1), DfValue::from(1)],
+        vec![DfValue::from(2), DfValue::from(1), DfValue::from(2)],
+        vec![DfValue::from(3), DfValue::from(1), DfValue::from(2)],
+        vec![DfValue::from(4), DfValue::from(1), DfValue::from(4)],
+        vec![DfValue::from(5), DfValue::from(2), DfValue::from(5)],
     ])
     .await
     .unwrap();
 
     sleep().await;
 
-    let mut res: Vec<_> = q.lookup(&[0.into()], true).await.unwrap().into();
-    res.sort();
+    let mut q = g
+        .view("approx")
+        .await
+        .unwrap()
+        .into_reader_handle()
+        .unwrap();
+    let res = q.lookup(&[1.into()], true).await.unwrap().into_vec();
+    assert_eq!(
+        res,
+        vec![vec![
+            DfValue::from(1),
+            DfValue::from(3),
+            DfValue::Double(2.0)
+        ]]
+    );
+
+    t.delete(vec![DfValue::from(4)]).await.unwrap();
+    sleep().await;
 
+    let res = q.lookup(&[1.into()], true).await.unwrap().into_vec();
     assert_eq!(
         res,
-        vec![
-            vec![1.into(), 1.into(), 1.into()],
-            vec![2.into(), 2.into(), 1.into()]
-        ]
+        vec![vec![
+            DfValue::from(1),
+            DfValue::from(2),
+            DfValue::Double(2.0)
+        ]]
     );
 }
+
 #[tokio::test(flavor = "multi_thread")]
-async fn filter_on_expression() {
-    let mut g = start_simple_unsharded("filter_on_expression").await;
+async fn lookup_typed() {
+    #[derive(Debug, PartialEq, Eq, serde::Deserialize)]
+    struct Article {
+        id: i32,
+        title: String,
+        score: Option<u8>,
+    }
+
+    let mut g = start_simple_unsharded("lookup_typed").await;
 
     g.extend_recipe(
         ChangeList::from_str(
-            "CREATE TABLE users (id int, birthday date);
-         CREATE CACHE friday_babies FROM SELECT id FROM users WHERE dayofweek(birthday) = 6;",
+            "CREATE TABLE articles (id int primary key, title text, score int, author text);
+             CREATE CACHE articles_by_id FROM SELECT id, title, score, author FROM articles \
+                 WHERE id = ?;
+             CREATE CACHE titles_by_id FROM SELECT title, score FROM articles WHERE id = ?;",
             Dialect::DEFAULT_MYSQL,
         )
         .unwrap(),
@@ -5376,47 +6395,133 @@ async fn filter_on_expression() {
     .await
     .unwrap();
 
-    let mut t = g.table("users").await.unwrap();
-    let mut q = g
-        .view("friday_babies")
+    let mut articles = g.table("articles").await.unwrap();
+    articles
+        .insert(vec![
+            DfValue::from(1),
+            DfValue::from("Hello"),
+            DfValue::None,
+            DfValue::from("someone"),
+        ])
         .await
-        .unwrap()
-        .into_reader_handle()
         .unwrap();
 
-    t.insert_many(vec![
-        vec![
-            DfValue::from(1),
-            DfValue::from(NaiveDate::from_ymd(1995, 6, 2)),
-        ],
-        vec![
-            DfValue::from(2),
-            DfValue::from(NaiveDate::from_ymd(2015, 5, 15)),
-        ],
+    sleep().await;
+
+    let mut q = g.view("articles_by_id").await.unwrap();
+    let res = q.lookup_typed::<Article>(&[1.into()], true).await.unwrap();
+    assert_eq!(
+        res,
+        vec![Article {
+            id: 1,
+            title: "Hello".to_owned(),
+            score: None,
+        }]
+    );
+
+    // `author` doesn't match any field of `Article`
+    q.lookup_typed_strict::<Article>(&[1.into()], true)
+        .await
+        .unwrap_err();
+
+    #[derive(Debug, PartialEq, Eq, serde::Deserialize)]
+    struct Title {
+        title: String,
+        score: Option<u8>,
+    }
+
+    // The key column `id` isn't returned by the query, so it doesn't need to match a field
+    let mut q = g.view("titles_by_id").await.unwrap();
+    let res = q
+        .lookup_typed_strict::<Title>(&[1.into()], true)
+        .await
+        .unwrap();
+    assert_eq!(
+        res,
+        vec![Title {
+            title: "Hello".to_owned(),
+            score: None,
+        }]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn lazy_join() {
+    let mut g = start_simple_unsharded("lazy_join").await;
+
+    let (fact, dim) = g
+        .migrate(|mig| {
+            let fact = mig.add_base(
+                "fact",
+                make_columns(&["id", "dim_id"]),
+                Base::new().with_primary_key([0]),
+            );
+            let dim = mig.add_base(
+                "dim",
+                make_columns(&["id", "name"]),
+                Base::new().with_primary_key([0]),
+            );
+            let dimension = mig.maintain_lazy_join_dimension(dim, vec![0]);
+            mig.maintain_anonymous_with_lazy_joins(
+                fact,
+                &Index::hash_map(vec![0]),
+                Default::default(),
+                vec![LazyJoin {
+                    dimension,
+                    on: vec![1],
+                    emit: vec![1],
+                    kind: JoinType::Left,
+                }],
+            );
+            (fact, dim)
+        })
+        .await;
+
+    let mut fact = g.table_by_index(fact).await.unwrap();
+    let mut dim = g.table_by_index(dim).await.unwrap();
+    let mut view = g.view("fact").await.unwrap().into_reader_handle().unwrap();
+
+    dim.insert(vec![1.into(), "one".into()]).await.unwrap();
+    fact.insert_many(vec![
+        vec![DfValue::from(1), 1.into()],
+        vec![DfValue::from(2), 2.into()],
     ])
     .await
     .unwrap();
-
     sleep().await;
 
-    let res = &q.lookup(&[0i32.into()], true).await.unwrap().into_vec();
+    // the fact reader is partial, so this exercises both the miss and the hit path
+    for _ in 0..2 {
+        assert_eq!(
+            view.lookup(&[1.into()], true).await.unwrap().into_vec(),
+            vec![vec![DfValue::from(1), 1.into(), "one".into()]]
+        );
+    }
+    assert_eq!(
+        view.lookup(&[2.into()], true).await.unwrap().into_vec(),
+        vec![vec![DfValue::from(2), 2.into(), DfValue::None]]
+    );
 
-    assert_eq!(get_col!(q, res[0], "id"), &DfValue::from(1));
+    // writes to the dimension are visible without touching the fact rows
+    dim.insert(vec![2.into(), "two".into()]).await.unwrap();
+    sleep().await;
+    assert_eq!(
+        view.lookup(&[2.into()], true).await.unwrap().into_vec(),
+        vec![vec![DfValue::from(2), 2.into(), "two".into()]]
+    );
 }
 
 #[tokio::test(flavor = "multi_thread")]
-async fn compound_join_key() {
-    let mut g = start_simple_unsharded("compound_join_key").await;
+async fn lazy_joins_from_sql() {
+    let mut g = start_simple_unsharded("lazy_joins_from_sql").await;
+
     g.extend_recipe(
         ChangeList::from_str(
-            "
-      CREATE TABLE t1 (id_1 int, id_2 int, val_1 int);
-      CREATE TABLE t2 (id_1 int, id_2 int, val_2 int);
-      CREATE CACHE q FROM
-        SELECT t1.val_1, t2.val_2
-        FROM t1
-        JOIN t2
-          ON t1.id_1 = t2.id_1 AND t1.id_2 = t2.id_2;",
+            "CREATE TABLE orders (id int, customer_id int, total int, PRIMARY KEY (id));
+             CREATE TABLE customers (id int, name text, PRIMARY KEY (id));
+             CREATE CACHE LAZY JOINS orders_with_customers FROM SELECT orders.id, \
+                 orders.customer_id, orders.total, customers.name FROM orders \
+                 LEFT JOIN customers ON orders.customer_id = customers.id WHERE orders.id = ?;",
             Dialect::DEFAULT_MYSQL,
         )
         .unwrap(),
@@ -5424,114 +6529,114 @@ async fn compound_join_key() {
     .await
     .unwrap();
 
-    let mut t1 = g.table("t1").await.unwrap();
-    let mut t2 = g.table("t2").await.unwrap();
-    let mut q = g.view("q").await.unwrap().into_reader_handle().unwrap();
-
-    t1.insert_many(vec![
-        vec![
-            DfValue::from(1i32),
-            DfValue::from(2i32),
-            DfValue::from(3i32),
-        ],
-        vec![
-            DfValue::from(1i32),
-            DfValue::from(3i32),
-            DfValue::from(4i32),
-        ],
-        vec![
-            DfValue::from(2i32),
-            DfValue::from(3i32),
-            DfValue::from(4i32),
-        ],
-        vec![
-            DfValue::from(2i32),
-            DfValue::from(4i32),
-            DfValue::from(5i32),
-        ],
-    ])
-    .await
-    .unwrap();
+    // The join is performed by the reader, rather than by a join node
+    let graphviz = g.graphviz().await.unwrap();
+    assert!(!graphviz.contains('⋉'), "{graphviz}");
 
-    t2.insert_many(vec![
-        vec![
-            DfValue::from(1i32),
-            DfValue::from(2i32),
-            DfValue::from(33i32),
-        ],
-        vec![
-            DfValue::from(1i32),
-            DfValue::from(3i32),
-            DfValue::from(44i32),
-        ],
-        vec![
-            DfValue::from(1i32),
-            DfValue::from(4i32),
-            DfValue::from(123i32),
-        ],
-        vec![
-            DfValue::from(2i32),
-            DfValue::from(3i32),
-            DfValue::from(44i32),
-        ],
-        vec![
-            DfValue::from(2i32),
-            DfValue::from(5i32),
-            DfValue::from(123i32),
-        ],
-    ])
-    .await
-    .unwrap();
+    let mut orders = g.table("orders").await.unwrap();
+    let mut customers = g.table("customers").await.unwrap();
+    let mut view = g
+        .view("orders_with_customers")
+        .await
+        .unwrap()
+        .into_reader_handle()
+        .unwrap();
+    assert_eq!(view.columns(), &["id", "customer_id", "total", "name"]);
 
+    customers
+        .insert(vec![10.into(), "alice".into()])
+        .await
+        .unwrap();
+    orders
+        .insert_many(vec![
+            vec![DfValue::from(1), 10.into(), 100.into()],
+            vec![DfValue::from(2), 20.into(), 200.into()],
+        ])
+        .await
+        .unwrap();
     sleep().await;
 
-    let res = q
-        .lookup(&[0i32.into()], true)
-        .await
-        .unwrap()
-        .into_iter()
-        .map(|r| (get_col!(q, r, "val_1", i32), get_col!(q, r, "val_2", i32)))
-        .sorted()
-        .collect::<Vec<(i32, i32)>>();
+    assert_eq!(
+        view.lookup(&[1.into()], true).await.unwrap().into_vec(),
+        vec![vec![
+            DfValue::from(1),
+            10.into(),
+            100.into(),
+            "alice".into()
+        ]]
+    );
+    assert_eq!(
+        view.lookup(&[2.into()], true).await.unwrap().into_vec(),
+        vec![vec![DfValue::from(2), 20.into(), 200.into(), DfValue::None]]
+    );
 
-    assert_eq!(res, vec![(3, 33), (4, 44), (4, 44)]);
+    customers
+        .insert(vec![20.into(), "bob".into()])
+        .await
+        .unwrap();
+    sleep().await;
+    assert_eq!(
+        view.lookup(&[2.into()], true).await.unwrap().into_vec(),
+        vec![vec![DfValue::from(2), 20.into(), 200.into(), "bob".into()]]
+    );
 }
 
 #[tokio::test(flavor = "multi_thread")]
-async fn left_join_null() {
-    let mut g = start_simple_unsharded("left_join_null").await;
+async fn text_interning_pool() {
+    let mut g = Builder::for_tests();
+    g.set_sharding(None);
+    g.set_persistence(get_persistence_params("text_interning_pool"));
+    g.set_text_interning_pool_size(1024);
+    let mut g = g.start_local().await.unwrap();
 
     g.extend_recipe(
-            ChangeList::from_str("CREATE TABLE jim (id int, a int);
-         CREATE TABLE bob (id int);
-         CREATE CACHE funky FROM SELECT * FROM jim LEFT JOIN bob ON jim.id = bob.id WHERE bob.id IS NULL;", Dialect::DEFAULT_MYSQL)
+        ChangeList::from_str(
+            "CREATE TABLE orders (id int, status text, PRIMARY KEY (id));
+             CREATE CACHE by_status FROM SELECT id FROM orders WHERE status = ?;",
+            Dialect::DEFAULT_MYSQL,
+        )
         .unwrap(),
     )
     .await
     .unwrap();
 
-    let mut t = g.table("jim").await.unwrap();
-    let mut t2 = g.table("bob").await.unwrap();
-    let mut q = g.view("funky").await.unwrap().into_reader_handle().unwrap();
-
-    t.insert_many(vec![
-        vec![DfValue::from(1), DfValue::from(2)],
-        vec![DfValue::from(3), DfValue::from(6)],
-        vec![DfValue::from(4), DfValue::from(6)],
-    ])
-    .await
-    .unwrap();
-    t2.insert_many(vec![vec![DfValue::from(3)]]).await.unwrap();
+    let mut orders = g.table("orders").await.unwrap();
+    let mut by_status = g
+        .view("by_status")
+        .await
+        .unwrap()
+        .into_reader_handle()
+        .unwrap();
 
+    let shipped = "shipped to the customer";
+    orders
+        .insert_many((0..10).map(|i| {
+            vec![
+                DfValue::from(i),
+                DfValue::from(if i % 2 == 0 {
+                    shipped
+                } else {
+                    "awaiting payment"
+                }),
+            ]
+        }))
+        .await
+        .unwrap();
     sleep().await;
 
-    let num_res = q
-        .lookup(&[0.into()], true)
+    let mut res = by_status
+        .lookup(&[shipped.into()], true)
         .await
         .unwrap()
-        .into_iter()
-        .count();
-    assert_eq!(num_res, 2);
+        .into_vec();
+    res.sort();
+    assert_eq!(
+        res,
+        (0..10)
+            .step_by(2)
+            .map(|i| vec![DfValue::from(i)])
+            .collect::<Vec<_>>()
+    );
 }
 
 #[tokio::test(flavor = "multi_thread")]
@@ -5749,6 +6854,50 @@ async fn multiple_aggregate_sum() {
     assert_eq!(res, vec![(1, 3), (5, 7), (12, 8)]);
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn group_by_expression() {
+    let mut g = start_simple_unsharded("group_by_expression").await;
+
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE TABLE test (number int, value int);
+             CREATE CACHE groupbyexpr FROM SELECT number % 2 AS parity, sum(value) AS s
+             FROM test GROUP BY number % 2 ORDER BY number % 2;",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let mut t = g.table("test").await.unwrap();
+    let mut q = g
+        .view("groupbyexpr")
+        .await
+        .unwrap()
+        .into_reader_handle()
+        .unwrap();
+
+    t.insert_many((1..=5).map(|i: i32| vec![DfValue::from(i), DfValue::from(i * 10)]))
+        .await
+        .unwrap();
+
+    sleep().await;
+
+    let rows = q.lookup(&[0i32.into()], true).await.unwrap();
+    let res = rows
+        .into_iter()
+        .map(|r| {
+            (
+                get_col!(q, r, "parity", i32),
+                get_col!(q, r, "s", Decimal).to_i32().unwrap(),
+            )
+        })
+        .collect::<Vec<(i32, i32)>>();
+
+    assert_eq!(res, vec![(0, 60), (1, 90)]);
+}
+
 // multiple_aggregate_same_col tests multiple aggregators of different types operating on the same
 // column.
 #[tokio::test(flavor = "multi_thread")]
@@ -6863,6 +8012,58 @@ async fn assign_nonreader_domains_to_nonreader_workers() {
     assert!(matches!(result, Ok(_)));
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn drain_worker_with_base_tables_requires_upstream() {
+    let authority_store = Arc::new(LocalAuthorityStore::new());
+    let w1_authority = Arc::new(Authority::from(LocalAuthority::new_with_store(
+        authority_store.clone(),
+    )));
+    let w2_authority = Arc::new(Authority::from(LocalAuthority::new_with_store(
+        authority_store,
+    )));
+    let cluster_name = "drain_worker_with_base_tables_requires_upstream";
+
+    let mut w1 = build_custom(cluster_name, None, true, w1_authority, false, None).await;
+    w1.extend_recipe(
+        ChangeList::from_str(
+            "CREATE TABLE t (id int PRIMARY KEY, v int);
+             CREATE CACHE q FROM SELECT v FROM t WHERE id = ?;",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+    let mut t = w1.table("t").await.unwrap();
+    t.insert(vec![1.into(), 2.into()]).await.unwrap();
+
+    // The second worker joins after all the domains have been placed on the first
+    let _w2 = build_custom(cluster_name, None, false, w2_authority, false, None).await;
+    sleep().await;
+    assert_eq!(w1.workers().await.unwrap().len(), 2);
+
+    // The base table's state can't be moved, and there's no upstream database to snapshot it
+    // again from, so draining the worker it's on is refused rather than losing its contents
+    let w1_uri = w1.controller_uri().await.unwrap();
+    let res = w1.drain_worker(w1_uri).await;
+    assert!(
+        matches!(
+            &res,
+            Err(RpcFailed {
+                source: box ReadySetError::BadRequest(msg),
+                ..
+            }) if msg.contains("upstream database")
+        ),
+        "{res:?}"
+    );
+
+    let mut q = w1.view("q").await.unwrap().into_reader_handle().unwrap();
+    assert_eq!(
+        q.lookup(&[1.into()], true).await.unwrap().into_vec(),
+        vec![vec![DfValue::from(2)]]
+    );
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn join_straddled_columns() {
     let mut g = start_simple_unsharded("join_straddled_columns").await;
@@ -7341,96 +8542,368 @@ async fn group_by_agg_col_multi() {
     .await
     .unwrap();
 
-    let mut t = g.table("test").await.unwrap();
-    let mut q = g
-        .view("groupbyaggcolmulti")
-        .await
-        .unwrap()
-        .into_reader_handle()
-        .unwrap();
-
-    t.insert_many(vec![
-        vec![DfValue::from(1i32), DfValue::from(4i32)],
-        vec![DfValue::from(1i32), DfValue::from(4i32)],
-        vec![DfValue::from(2i32), DfValue::from(6i32)],
-        vec![DfValue::from(2i32), DfValue::from(3i32)],
-        vec![DfValue::from(2i32), DfValue::from(3i32)],
-        vec![DfValue::from(3i32), DfValue::from(2i32)],
-        vec![DfValue::from(3i32), DfValue::from(7i32)],
-        vec![DfValue::from(3i32), DfValue::from(7i32)],
-        vec![DfValue::from(3i32), DfValue::from(8i32)],
-    ])
+    let mut t = g.table("test").await.unwrap();
+    let mut q = g
+        .view("groupbyaggcolmulti")
+        .await
+        .unwrap()
+        .into_reader_handle()
+        .unwrap();
+
+    t.insert_many(vec![
+        vec![DfValue::from(1i32), DfValue::from(4i32)],
+        vec![DfValue::from(1i32), DfValue::from(4i32)],
+        vec![DfValue::from(2i32), DfValue::from(6i32)],
+        vec![DfValue::from(2i32), DfValue::from(3i32)],
+        vec![DfValue::from(2i32), DfValue::from(3i32)],
+        vec![DfValue::from(3i32), DfValue::from(2i32)],
+        vec![DfValue::from(3i32), DfValue::from(7i32)],
+        vec![DfValue::from(3i32), DfValue::from(7i32)],
+        vec![DfValue::from(3i32), DfValue::from(8i32)],
+    ])
+    .await
+    .unwrap();
+
+    sleep().await;
+
+    let rows = q.lookup(&[0i32.into()], true).await.unwrap();
+
+    let res = rows
+        .into_iter()
+        .map(|r| (get_col!(q, r, "c", i32), get_col!(q, r, "a", f64)))
+        .sorted_by(|a, b| a.0.cmp(&b.0))
+        .collect::<Vec<(i32, f64)>>();
+
+    assert_eq!(res, vec![(2, 4.), (3, 4.), (4, 6.)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn group_by_agg_col_with_join() {
+    let mut g = start_simple_unsharded("group_by_agg_col_with_join").await;
+    let sql = "
+        # base tables
+        CREATE TABLE test (id int, number int, PRIMARY KEY(id));
+        CREATE TABLE test2 (test_id int, value int);
+
+        # read queries
+        CREATE CACHE groupbyaggcolwithjoin FROM SELECT count(number) as c, avg(test2.value) AS a \
+                    FROM test \
+                    INNER JOIN test2 \
+                    ON (test.id = test2.test_id)
+                    GROUP BY number;
+    ";
+
+    g.extend_recipe(ChangeList::from_str(sql, Dialect::DEFAULT_MYSQL).unwrap())
+        .await
+        .unwrap();
+    let mut test = g.table("test").await.unwrap();
+    let mut test2 = g.table("test2").await.unwrap();
+    let mut q = g
+        .view("groupbyaggcolwithjoin")
+        .await
+        .unwrap()
+        .into_reader_handle()
+        .unwrap();
+
+    test.insert_many(vec![
+        vec![0i64.into(), 10i64.into()],
+        vec![1i64.into(), 10i64.into()],
+        vec![2i64.into(), 10i64.into()],
+    ])
+    .await
+    .unwrap();
+    test2
+        .insert_many(vec![
+            vec![0i64.into(), 20i64.into()],
+            vec![1i64.into(), 20i64.into()],
+            vec![2i64.into(), 20i64.into()],
+        ])
+        .await
+        .unwrap();
+
+    sleep().await;
+
+    let rows = q.lookup(&[0i32.into()], true).await.unwrap();
+
+    let res = rows
+        .into_iter()
+        .map(|r| (get_col!(q, r, "c", i32), get_col!(q, r, "a", f64)))
+        .sorted_by(|a, b| a.0.cmp(&b.0))
+        .collect::<Vec<(i32, f64)>>();
+
+    assert_eq!(res, vec![(3, 20.)]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn compound_aggregates() {
+    let mut g = start_simple_unsharded("compound_aggregates").await;
+    let sql = "
+        CREATE TABLE test (id int, g int, x int, PRIMARY KEY(id));
+        CREATE CACHE compound FROM
+        SELECT test.g, AVG(test.x) AS a, VARIANCE(test.x) AS v, STDDEV_POP(test.x) AS s
+        FROM test WHERE test.g = ? GROUP BY test.g;
+    ";
+    g.extend_recipe(ChangeList::from_str(sql, Dialect::DEFAULT_MYSQL).unwrap())
+        .await
+        .unwrap();
+
+    let mut test = g.table("test").await.unwrap();
+    test.insert_many(
+        [2, 4, 4, 4, 5, 5, 7, 9, 11]
+            .into_iter()
+            .enumerate()
+            .map(|(id, x)| vec![DfValue::from(id as i32), 1.into(), x.into()])
+            .chain([
+                vec![100.into(), 1.into(), DfValue::None],
+                vec![101.into(), 2.into(), DfValue::None],
+            ]),
+    )
+    .await
+    .unwrap();
+    // Deleting a row updates all the compound aggregates, and the NULL x values are ignored
+    test.delete(vec![DfValue::from(8)]).await.unwrap();
+    sleep().await;
+
+    let mut q = g
+        .view("compound")
+        .await
+        .unwrap()
+        .into_reader_handle()
+        .unwrap();
+    let rows = q.lookup(&[1.into()], true).await.unwrap().into_vec();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(
+        rows[0][1..]
+            .iter()
+            .map(|v| f64::try_from(v).unwrap())
+            .collect::<Vec<_>>(),
+        vec![5., 4., 2.]
+    );
+
+    // Groups with only NULL values have NULL compound aggregates
+    let rows = q.lookup(&[2.into()], true).await.unwrap().into_vec();
+    assert_eq!(
+        rows,
+        vec![vec![2.into(), DfValue::None, DfValue::None, DfValue::None]]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sharded_global_aggregates() {
+    let mut g = start_simple("sharded_global_aggregates").await;
+    let sql = "
+        CREATE TABLE test (id int, x int, PRIMARY KEY(id));
+        CREATE CACHE aggs FROM SELECT COUNT(*) AS c, SUM(x) AS s, MIN(x) AS mn, MAX(x) AS mx FROM test;
+    ";
+    g.extend_recipe(ChangeList::from_str(sql, Dialect::DEFAULT_MYSQL).unwrap())
+        .await
+        .unwrap();
+
+    let mut test = g.table("test").await.unwrap();
+    test.insert_many((1..=20).map(|i| vec![DfValue::from(i), DfValue::from(i * 10)]))
+        .await
+        .unwrap();
+    test.delete(vec![DfValue::from(20)]).await.unwrap();
+    sleep().await;
+
+    let mut q = g.view("aggs").await.unwrap().into_reader_handle().unwrap();
+    let rows = q.lookup(&[0i32.into()], true).await.unwrap().into_vec();
+    assert_eq!(
+        rows,
+        vec![vec![
+            DfValue::from(19),
+            DfValue::from(Decimal::from(1900)),
+            DfValue::from(10),
+            DfValue::from(190)
+        ]]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn full_materialization_budget() {
+    let mut g = Builder::for_tests();
+    g.set_sharding(None);
+    g.disable_partial();
+    g.set_persistence(get_persistence_params("full_materialization_budget"));
+    g.set_max_full_materialization_bytes(Some(1));
+    let mut g = g.start_local().await.unwrap();
+
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE TABLE t (id int, x int, PRIMARY KEY(id));",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+    let mut t = g.table("t").await.unwrap();
+    t.insert_many((0..10i32).map(|i| vec![i.into(), (i % 3).into()]))
+        .await
+        .unwrap();
+
+    let err = g
+        .extend_recipe(
+            ChangeList::from_str(
+                "CREATE CACHE q1 FROM SELECT x, count(*) FROM t WHERE x = ? GROUP BY x;",
+                Dialect::DEFAULT_MYSQL,
+            )
+            .unwrap(),
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("exceeding the budget of 1 bytes"),
+        "{err}"
+    );
+
+    // CREATE CACHE ALWAYS overrides the budget
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE CACHE ALWAYS q1 FROM SELECT x, count(*) FROM t WHERE x = ? GROUP BY x;",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+    let mut q1 = g.view("q1").await.unwrap().into_reader_handle().unwrap();
+    let rows = q1.lookup(&[0i32.into()], true).await.unwrap().into_vec();
+    assert_eq!(rows, vec![vec![DfValue::from(0), DfValue::from(4)]]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn namespace_cache_quota() {
+    let mut g = Builder::for_tests();
+    g.set_sharding(None);
+    g.set_persistence(get_persistence_params("namespace_cache_quota"));
+    g.set_namespace_quotas(["limited:caches=1".parse().unwrap()].into_iter().collect());
+    let mut g = g.start_local().await.unwrap();
+
+    let extend = |sql: &str, schema: &str| {
+        ChangeList::from_str(sql, Dialect::DEFAULT_MYSQL)
+            .unwrap()
+            .with_schema_search_path(vec![schema.into()])
+    };
+    g.extend_recipe(extend(
+        "CREATE TABLE t (id int, x int, PRIMARY KEY(id));",
+        "limited",
+    ))
+    .await
+    .unwrap();
+    g.extend_recipe(extend(
+        "CREATE TABLE t (id int, x int, PRIMARY KEY(id));",
+        "other",
+    ))
+    .await
+    .unwrap();
+
+    g.extend_recipe(extend(
+        "CREATE CACHE q1 FROM SELECT x FROM t WHERE id = ?;",
+        "limited",
+    ))
+    .await
+    .unwrap();
+    // Re-creating an existing cache doesn't count towards the quota
+    g.extend_recipe(extend(
+        "CREATE CACHE FROM SELECT x FROM t WHERE id = ?;",
+        "limited",
+    ))
     .await
     .unwrap();
 
-    sleep().await;
-
-    let rows = q.lookup(&[0i32.into()], true).await.unwrap();
-
-    let res = rows
-        .into_iter()
-        .map(|r| (get_col!(q, r, "c", i32), get_col!(q, r, "a", f64)))
-        .sorted_by(|a, b| a.0.cmp(&b.0))
-        .collect::<Vec<(i32, f64)>>();
+    let err = g
+        .extend_recipe(extend(
+            "CREATE CACHE q2 FROM SELECT id FROM t WHERE x = ?;",
+            "limited",
+        ))
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string()
+            .contains("Namespace limited is limited to at most 1 caches"),
+        "{err}"
+    );
 
-    assert_eq!(res, vec![(2, 4.), (3, 4.), (4, 6.)]);
+    // Other namespaces aren't limited
+    for (i, column) in ["id", "x"].iter().enumerate() {
+        g.extend_recipe(extend(
+            &format!("CREATE CACHE other_{i} FROM SELECT {column} FROM t WHERE {column} = ?;"),
+            "other",
+        ))
+        .await
+        .unwrap();
+    }
 }
 
 #[tokio::test(flavor = "multi_thread")]
-async fn group_by_agg_col_with_join() {
-    let mut g = start_simple_unsharded("group_by_agg_col_with_join").await;
-    let sql = "
-        # base tables
-        CREATE TABLE test (id int, number int, PRIMARY KEY(id));
-        CREATE TABLE test2 (test_id int, value int);
+async fn reader_placement_constraints() {
+    let mut g = Builder::for_tests();
+    g.set_sharding(None);
+    g.set_persistence(get_persistence_params("reader_placement_constraints"));
+    g.set_worker_resources(WorkerResources {
+        zone: Some("us-east-1a".into()),
+        ..Default::default()
+    });
+    let mut g = g.start_local().await.unwrap();
 
-        # read queries
-        CREATE CACHE groupbyaggcolwithjoin FROM SELECT count(number) as c, avg(test2.value) AS a \
-                    FROM test \
-                    INNER JOIN test2 \
-                    ON (test.id = test2.test_id)
-                    GROUP BY number;
-    ";
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE TABLE t (id int, x int, PRIMARY KEY(id));",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
 
-    g.extend_recipe(ChangeList::from_str(sql, Dialect::DEFAULT_MYSQL).unwrap())
-        .await
-        .unwrap();
-    let mut test = g.table("test").await.unwrap();
-    let mut test2 = g.table("test2").await.unwrap();
-    let mut q = g
-        .view("groupbyaggcolwithjoin")
-        .await
+    let constraints = |zone: &str| PlacementConstraints {
+        readers: vec![WorkerConstraint::Zone(zone.into())],
+        base_tables: vec![],
+    };
+    let cache = || {
+        ChangeList::from_str(
+            "CREATE CACHE q FROM SELECT x FROM t WHERE id = ?;",
+            Dialect::DEFAULT_MYSQL,
+        )
         .unwrap()
-        .into_reader_handle()
-        .unwrap();
+    };
 
-    test.insert_many(vec![
-        vec![0i64.into(), 10i64.into()],
-        vec![1i64.into(), 10i64.into()],
-        vec![2i64.into(), 10i64.into()],
-    ])
-    .await
-    .unwrap();
-    test2
-        .insert_many(vec![
-            vec![0i64.into(), 20i64.into()],
-            vec![1i64.into(), 20i64.into()],
-            vec![2i64.into(), 20i64.into()],
-        ])
+    // The only worker is in a different zone
+    let err = g
+        .extend_recipe_with_placement_constraints(cache(), constraints("us-east-1b"))
         .await
-        .unwrap();
-
-    sleep().await;
+        .unwrap_err();
+    assert!(format!("{err:?}").contains("NoAvailableWorkers"), "{err}");
 
-    let rows = q.lookup(&[0i32.into()], true).await.unwrap();
+    g.extend_recipe_with_placement_constraints(cache(), constraints("us-east-1a"))
+        .await
+        .unwrap();
+    g.view("q").await.unwrap();
 
-    let res = rows
-        .into_iter()
-        .map(|r| (get_col!(q, r, "c", i32), get_col!(q, r, "a", f64)))
-        .sorted_by(|a, b| a.0.cmp(&b.0))
-        .collect::<Vec<(i32, f64)>>();
+    // Constraints can also be given in the CREATE CACHE statement
+    let err = g
+        .extend_recipe(
+            ChangeList::from_str(
+                "CREATE CACHE PLACE READERS ON 'zone=us-east-1b' q2 FROM SELECT id FROM t WHERE x = ?;",
+                Dialect::DEFAULT_MYSQL,
+            )
+            .unwrap(),
+        )
+        .await
+        .unwrap_err();
+    assert!(format!("{err:?}").contains("NoAvailableWorkers"), "{err}");
 
-    assert_eq!(res, vec![(3, 20.)]);
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE CACHE PLACE READERS ON 'zone=us-east-1a' q2 FROM SELECT id FROM t WHERE x = ?;",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+    g.view("q2").await.unwrap();
 }
 
 #[tokio::test(flavor = "multi_thread")]
@@ -8638,20 +10111,284 @@ async fn simple_dry_run_unsupported() {
     g.table("table_1").await.unwrap();
     g.view("t1").await.unwrap();
 
-    let unsupported_query = "CREATE CACHE FROM SELECT 1";
-    let res = g
-        .dry_run(ChangeList::from_str(unsupported_query, Dialect::DEFAULT_MYSQL).unwrap())
-        .await;
-    assert!(matches!(
-        res,
-        Err(RpcFailed {
-            source: box SelectQueryCreationFailed {
-                source: box ReadySetError::Unsupported(_),
-                ..
-            },
-            ..
-        })
-    ));
+    let unsupported_query = "CREATE CACHE FROM SELECT 1";
+    let res = g
+        .dry_run(ChangeList::from_str(unsupported_query, Dialect::DEFAULT_MYSQL).unwrap())
+        .await;
+    assert!(matches!(
+        res,
+        Err(RpcFailed {
+            source: box SelectQueryCreationFailed {
+                source: box ReadySetError::UnsupportedFeature {
+                    feature: UnsupportedFeature::NoTables,
+                    ..
+                },
+                ..
+            },
+            ..
+        })
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn unsupported_queries_are_remembered() {
+    let mut g = start_simple_unsharded("unsupported_queries_are_remembered").await;
+    g.extend_recipe(
+        ChangeList::from_str("CREATE TABLE t (x int);", Dialect::DEFAULT_MYSQL).unwrap(),
+    )
+    .await
+    .unwrap();
+    assert!(g.unsupported_queries().await.unwrap().is_empty());
+
+    let unsupported_query = "CREATE CACHE FROM SELECT 1";
+    let err = g
+        .dry_run(ChangeList::from_str(unsupported_query, Dialect::DEFAULT_MYSQL).unwrap())
+        .await
+        .unwrap_err();
+    assert!(err.caused_by_unsupported());
+
+    let unsupported = g.unsupported_queries().await.unwrap();
+    assert_eq!(unsupported.len(), 1);
+    let (id, request, reason, feature) = &unsupported[0];
+    assert_eq!(*id, QueryId::from(request));
+    assert_eq!(request.statement.to_string(), "SELECT 1");
+    assert_eq!(Some(reason.as_str()), err.unsupported_cause());
+    assert_eq!(*feature, Some(UnsupportedFeature::NoTables));
+    assert_eq!(err.unsupported_feature(), *feature);
+
+    // Subsequent attempts to migrate the query fail with the same reason
+    let err = g
+        .extend_recipe(ChangeList::from_str(unsupported_query, Dialect::DEFAULT_MYSQL).unwrap())
+        .await
+        .unwrap_err();
+    assert_eq!(err.unsupported_cause(), Some(reason.as_str()));
+    assert_eq!(err.unsupported_feature(), *feature);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn cache_definitions_recreate_caches() {
+    let schema = "CREATE TABLE t (x int, y int);";
+    let mut g = start_simple_unsharded("cache_definitions_recreate_caches").await;
+    g.extend_recipe(
+        ChangeList::from_str(
+            format!(
+                "{schema}
+                 CREATE CACHE q1 FROM SELECT x FROM t WHERE y = ?;
+                 CREATE CACHE ALWAYS q2 FROM SELECT y FROM t WHERE x = 1;
+                 CREATE CACHE NO FILTER PUSHDOWN MAX STALENESS 10 MAX ROWS PER KEY 5 ON LIMIT ERROR
+                     q3 FROM SELECT x FROM t WHERE y = ? AND x = 2;"
+            ),
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let caches = g.cache_definitions().await.unwrap();
+    assert_eq!(
+        caches
+            .iter()
+            .map(|stmt| (stmt.name.clone().unwrap(), stmt.always))
+            .collect::<Vec<_>>(),
+        vec![
+            ("q1".into(), false),
+            ("q2".into(), true),
+            ("q3".into(), false)
+        ]
+    );
+    // The rest of each cache's settings are kept too
+    let q3 = &caches[2];
+    assert!(!q3.filter_pushdown);
+    assert_eq!(q3.max_staleness, Some(10));
+    assert_eq!(q3.result_limits.max_rows_per_key, Some(5));
+    assert_eq!(
+        q3.result_limits.on_exceeded,
+        nom_sql::ResultLimitPolicy::Error
+    );
+
+    // Replaying the definitions (in either their SQL or structured form) against a fresh
+    // deployment recreates the same caches
+    let script = caches.iter().map(|stmt| format!("{stmt};")).join("\n");
+    for changes in [
+        ChangeList::from_str(script, Dialect::DEFAULT_MYSQL).unwrap(),
+        ChangeList::from_changes(
+            caches
+                .iter()
+                .cloned()
+                .map(Change::CreateCache)
+                .collect::<Vec<_>>(),
+            Dialect::DEFAULT_MYSQL,
+        ),
+    ] {
+        let mut g2 = start_simple_unsharded("cache_definitions_recreate_caches_import").await;
+        g2.extend_recipe(ChangeList::from_str(schema, Dialect::DEFAULT_MYSQL).unwrap())
+            .await
+            .unwrap();
+        g2.extend_recipe(changes).await.unwrap();
+        assert_eq!(g2.cache_definitions().await.unwrap(), caches);
+
+        let mut q1 = g2.view("q1").await.unwrap().into_reader_handle().unwrap();
+        assert!(q1
+            .lookup(&[1.into()], true)
+            .await
+            .unwrap()
+            .into_vec()
+            .is_empty());
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn reload_config() {
+    let mut g = start_simple_unsharded("reload_config").await;
+    let settings = |settings: &[(&str, &str)]| {
+        settings
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<BTreeMap<_, _>>()
+    };
+
+    let result = g
+        .reload_config(settings(&[
+            ("memory", "1073741824"),
+            ("eviction-policy", "lru"),
+            ("upstream-db-url", "mysql://root@localhost/db"),
+        ]))
+        .await
+        .unwrap();
+    assert_eq!(result.applied, vec!["memory", "eviction-policy"]);
+    assert_eq!(result.requires_restart, vec!["upstream-db-url"]);
+
+    // Invalid settings are rejected without applying anything
+    g.reload_config(settings(&[("memory", "lots")]))
+        .await
+        .unwrap_err();
+    g.reload_config(settings(&[("not-a-setting", "1")]))
+        .await
+        .unwrap_err();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn query_ids_persist_across_restarts() {
+    let authority_store = Arc::new(LocalAuthorityStore::new());
+    let authority = Arc::new(Authority::from(LocalAuthority::new_with_store(
+        authority_store.clone(),
+    )));
+    let request = ViewCreateRequest::new(
+        parse_select_statement(nom_sql::Dialect::MySQL, "SELECT * FROM t WHERE x = ?").unwrap(),
+        vec!["s1".into()],
+    );
+    let id = QueryId::from(&request);
+
+    {
+        let mut g = Builder::for_tests().start(authority.clone()).await.unwrap();
+        g.backend_ready().await;
+
+        assert_eq!(g.query_for_id(id).await.unwrap(), None);
+        g.register_queries(vec![request.clone()]).await.unwrap();
+        assert_eq!(
+            g.query_for_id(id).await.unwrap(),
+            Some((id, request.clone()))
+        );
+
+        g.shutdown();
+        g.wait_done().await;
+        if let Authority::LocalAuthority(l) = authority.as_ref() {
+            l.delete_ephemeral();
+        }
+    }
+
+    sleep().await;
+
+    let authority = Arc::new(Authority::from(LocalAuthority::new_with_store(
+        authority_store,
+    )));
+    let mut g = Builder::for_tests().start(authority).await.unwrap();
+    g.backend_ready().await;
+
+    assert_eq!(
+        g.query_for_id(id).await.unwrap(),
+        Some((id, request.clone()))
+    );
+    // Ids handed out by versions of ReadySet from before query ids were versioned still resolve
+    assert_eq!(
+        g.query_for_id(QueryId::legacy(&request)).await.unwrap(),
+        Some((id, request))
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn migrating_a_query_registers_its_id() {
+    let mut g = start_simple_unsharded("migrating_a_query_registers_its_id").await;
+    g.extend_recipe(
+        ChangeList::from_str("CREATE TABLE t (x int);", Dialect::DEFAULT_MYSQL).unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let request = ViewCreateRequest::new(
+        parse_select_statement(nom_sql::Dialect::MySQL, "SELECT * FROM t WHERE x = ?").unwrap(),
+        vec![],
+    );
+    let id = QueryId::from(&request);
+    assert_eq!(g.query_for_id(id).await.unwrap(), None);
+
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE CACHE FROM SELECT * FROM t WHERE x = ?",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(g.query_for_id(id).await.unwrap(), Some((id, request)));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn query_matches_cache() {
+    let mut g = start_simple_unsharded("query_matches_cache").await;
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE TABLE t (x int, y int);
+             CREATE CACHE by_x FROM SELECT * FROM t WHERE x = ?;",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let request = |query| {
+        ViewCreateRequest::new(
+            parse_select_statement(nom_sql::Dialect::MySQL, query).unwrap(),
+            vec![],
+        )
+    };
+    assert!(g
+        .query_matches_cache(
+            "by_x".into(),
+            request("SELECT * FROM t WHERE x = ?"),
+            Dialect::DEFAULT_MYSQL
+        )
+        .await
+        .unwrap());
+    assert!(!g
+        .query_matches_cache(
+            "by_x".into(),
+            request("SELECT * FROM t WHERE y = ?"),
+            Dialect::DEFAULT_MYSQL
+        )
+        .await
+        .unwrap());
+    assert!(!g
+        .query_matches_cache(
+            "nonexistent".into(),
+            request("SELECT * FROM t WHERE x = ?"),
+            Dialect::DEFAULT_MYSQL
+        )
+        .await
+        .unwrap());
 }
 
 #[tokio::test(flavor = "multi_thread")]
@@ -8999,3 +10736,416 @@ async fn views_out_of_order() {
     .await
     .unwrap();
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn full_replay_spills_to_disk() {
+    let mut builder = Builder::for_tests();
+    builder.disable_partial();
+    builder.set_sharding(None);
+    builder.set_persistence(get_persistence_params("full_replay_spills_to_disk"));
+    builder.set_replay_spill_threshold(Some(10));
+    let mut g = builder.start_local().await.unwrap();
+
+    g.extend_recipe(
+        ChangeList::from_str("CREATE TABLE t (id int, val int);", Dialect::DEFAULT_MYSQL).unwrap(),
+    )
+    .await
+    .unwrap();
+    let mut t = g.table("t").await.unwrap();
+    t.insert_many((0..1000).map(|i| vec![DfValue::from(i), DfValue::from(i % 7)]))
+        .await
+        .unwrap();
+    sleep().await;
+
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE CACHE q FROM SELECT id FROM t WHERE val = ?;",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let mut q = g.view("q").await.unwrap().into_reader_handle().unwrap();
+    let mut res = q.lookup(&[3.into()], true).await.unwrap().into_vec();
+    res.sort();
+    assert_eq!(
+        res,
+        (0..1000)
+            .filter(|i| i % 7 == 3)
+            .map(|i| vec![DfValue::from(i)])
+            .collect::<Vec<_>>()
+    );
+}
+
+#[cfg(feature = "failure_injection")]
+#[tokio::test(flavor = "multi_thread")]
+async fn concurrent_backfills_dont_block_migrations() {
+    let mut builder = Builder::for_tests();
+    builder.disable_partial();
+    builder.set_sharding(None);
+    builder.set_persistence(get_persistence_params(
+        "concurrent_backfills_dont_block_migrations",
+    ));
+    let mut g = builder.start_local().await.unwrap();
+
+    g.extend_recipe(
+        ChangeList::from_str("CREATE TABLE t (id int, val int);", Dialect::DEFAULT_MYSQL).unwrap(),
+    )
+    .await
+    .unwrap();
+    let mut t = g.table("t").await.unwrap();
+    t.insert_many((0..100).map(|i| vec![DfValue::from(i), DfValue::from(i % 7)]))
+        .await
+        .unwrap();
+    sleep().await;
+
+    // Hold every backfill until the failpoint is removed
+    fail::cfg(readyset_client::failpoints::BACKFILL, "return").unwrap();
+
+    let mut backfills = vec![];
+    for (name, val) in [("q1", 1), ("q2", 2)] {
+        let backfill = g
+            .extend_recipe_concurrently(
+                ChangeList::from_str(
+                    format!("CREATE CACHE {name} FROM SELECT id FROM t WHERE val = {val};"),
+                    Dialect::DEFAULT_MYSQL,
+                )
+                .unwrap(),
+            )
+            .await
+            .unwrap()
+            .unwrap();
+        backfills.push(backfill);
+    }
+    for backfill in &backfills {
+        assert!(!g.backfill_done(*backfill).await.unwrap());
+    }
+
+    // Other migrations go ahead while the backfills are running
+    g.extend_recipe(
+        ChangeList::from_str("CREATE TABLE u (id int);", Dialect::DEFAULT_MYSQL).unwrap(),
+    )
+    .await
+    .unwrap();
+    g.table("u").await.unwrap();
+    for backfill in &backfills {
+        assert!(!g.backfill_done(*backfill).await.unwrap());
+    }
+
+    fail::remove(readyset_client::failpoints::BACKFILL);
+    for backfill in backfills {
+        eventually!(run_test: {
+            g.backfill_done(backfill).await.unwrap()
+        }, then_assert: |done| {
+            assert!(done);
+        });
+    }
+
+    for (name, val) in [("q1", 1), ("q2", 2)] {
+        let mut q = g.view(name).await.unwrap().into_reader_handle().unwrap();
+        let mut res = q.lookup(&[0.into()], true).await.unwrap().into_vec();
+        res.sort();
+        assert_eq!(
+            res,
+            (0..100)
+                .filter(|i| i % 7 == val)
+                .map(|i| vec![DfValue::from(i)])
+                .collect::<Vec<_>>()
+        );
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn auto_increments_are_unique() {
+    let mut g = start_simple_unsharded("auto_increments_are_unique").await;
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE TABLE t (id int AUTO_INCREMENT PRIMARY KEY, val int);",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+    let mut t = g.table("t").await.unwrap();
+    t.insert_many((1..=5).map(|i| vec![DfValue::from(i), DfValue::from(i)]))
+        .await
+        .unwrap();
+    sleep().await;
+
+    // The counter is seeded from the largest value in the table, and advanced past explicit values
+    assert_eq!(
+        g.auto_increments("t", 0, vec![None, Some(10), None])
+            .await
+            .unwrap(),
+        vec![6, 11]
+    );
+
+    // Values generated for different handles (such as those of different adapters) never collide
+    let mut other = g.c.clone().unwrap();
+    assert_eq!(
+        other.auto_increments("t", 0, vec![None]).await.unwrap(),
+        vec![12]
+    );
+    assert_eq!(
+        g.auto_increments("t", 0, vec![None]).await.unwrap(),
+        vec![13]
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn row_provenance() {
+    let mut builder = Builder::for_tests();
+    builder.set_sharding(None);
+    builder.set_persistence(get_persistence_params("row_provenance"));
+    builder.set_record_provenance(true);
+    let mut g = builder.start_local().await.unwrap();
+
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE TABLE t (id int, val int, PRIMARY KEY (id));
+             CREATE CACHE q FROM SELECT id FROM t WHERE val = ?;",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+    let mut t = g.table("t").await.unwrap();
+    for (id, val) in [(1, 3), (2, 3), (3, 4)] {
+        t.insert(vec![DfValue::from(id), DfValue::from(val)])
+            .await
+            .unwrap();
+    }
+    sleep().await;
+
+    // Nothing has been read yet, so nothing has been replayed into the partial reader
+    let provenance = g.row_provenance("q", vec![DfValue::from(3)]).await.unwrap();
+    assert!(provenance.is_empty());
+
+    // Filling the key replays the rows out of the base table, each tagged with just the key that
+    // was replayed
+    let mut q = g.view("q").await.unwrap().into_reader_handle().unwrap();
+    let rows = q.lookup(&[DfValue::from(3)], true).await.unwrap();
+    assert_eq!(rows.into_vec().len(), 2);
+    let provenance = g.row_provenance("q", vec![DfValue::from(3)]).await.unwrap();
+    assert_eq!(provenance.len(), 2);
+    for row in &provenance {
+        assert!(row.replay);
+        assert_eq!(row.tags.len(), 1);
+        assert_eq!(row.tags[0].table.name, "t");
+        assert_eq!(row.tags[0].key, vec![DfValue::from(3)]);
+    }
+
+    // Writes to the filled key are tagged with the write they result from
+    t.insert(vec![DfValue::from(4), DfValue::from(3)])
+        .await
+        .unwrap();
+    sleep().await;
+    let provenance = g.row_provenance("q", vec![DfValue::from(3)]).await.unwrap();
+    assert_eq!(provenance.len(), 3);
+    let written = provenance
+        .iter()
+        .filter(|row| !row.replay)
+        .collect::<Vec<_>>();
+    assert_eq!(written.len(), 1);
+    let written = written[0];
+    assert_eq!(written.tags.len(), 1);
+    assert_eq!(written.tags[0].key, vec![DfValue::from(4)]);
+
+    // Rows which are removed from the reader are forgotten
+    t.delete(vec![DfValue::from(1)]).await.unwrap();
+    sleep().await;
+    let provenance = g.row_provenance("q", vec![DfValue::from(3)]).await.unwrap();
+    assert_eq!(provenance.len(), 2);
+
+    // As are the rows of keys which are evicted
+    g.flush_partial().await.unwrap();
+    sleep().await;
+    let provenance = g.row_provenance("q", vec![DfValue::from(3)]).await.unwrap();
+    assert!(provenance.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn quiesce() {
+    let mut builder = Builder::for_tests();
+    builder.disable_partial();
+    builder.set_sharding(Some(DEFAULT_SHARDING));
+    builder.set_persistence(get_persistence_params("quiesce"));
+    let mut g = builder.start_local().await.unwrap();
+
+    // The join and the lookup key both force the rows to be resharded, so the barrier has to make
+    // it through sharders and shard mergers
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE TABLE a (id int, b_id int, PRIMARY KEY (id));
+             CREATE TABLE b (id int, x int, PRIMARY KEY (id));
+             CREATE CACHE q FROM SELECT a.id, b.x FROM a JOIN b ON a.b_id = b.id WHERE b.x = ?;",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+    let mut a = g.table("a").await.unwrap();
+    let mut b = g.table("b").await.unwrap();
+    let mut q = g.view("q").await.unwrap().into_reader_handle().unwrap();
+
+    let first_barrier = g.quiesce().await.unwrap();
+
+    for id in 0..10 {
+        b.insert(vec![DfValue::from(id), DfValue::from(id % 2)])
+            .await
+            .unwrap();
+        a.insert(vec![DfValue::from(id), DfValue::from(id)])
+            .await
+            .unwrap();
+    }
+
+    // No sleeping: once quiesce returns, every write above must be visible
+    let second_barrier = g.quiesce().await.unwrap();
+    assert!(second_barrier > first_barrier);
+    for x in 0..2 {
+        let rows = q
+            .lookup(&[DfValue::from(x)], true)
+            .await
+            .unwrap()
+            .into_vec();
+        assert_eq!(rows.len(), 5);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn view_stats() {
+    let mut g = start_simple_unsharded("view_stats").await;
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE TABLE t (id int, x int, PRIMARY KEY (id));
+             CREATE CACHE full_q FROM SELECT id, x FROM t;
+             CREATE CACHE partial_q FROM SELECT id FROM t WHERE x = ?;",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+    let mut t = g.table("t").await.unwrap();
+    for id in 0..6 {
+        t.insert(vec![DfValue::from(id), DfValue::from(id % 3)])
+            .await
+            .unwrap();
+    }
+    sleep().await;
+
+    let mut partial_q = g
+        .view("partial_q")
+        .await
+        .unwrap()
+        .into_reader_handle()
+        .unwrap();
+    assert_eq!(partial_q.stats().await.unwrap().keys, 0);
+    partial_q.lookup(&[DfValue::from(1)], true).await.unwrap();
+
+    let stats = g.view_stats().await.unwrap();
+    let partial = stats[&Relation::from("partial_q")];
+    assert!(partial.partial);
+    assert_eq!(partial.keys, 1);
+    assert_eq!(partial.rows, 2);
+    assert_eq!(stats[&Relation::from("full_q")].rows, 6);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn expired_time_buckets_evicted() {
+    let mut g = start_simple_unsharded("expired_time_buckets_evicted").await;
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE TABLE events (id int, bucket timestamp, PRIMARY KEY (id));
+             CREATE CACHE BUCKET RETENTION 1 q FROM
+             SELECT bucket, count(id) FROM events WHERE bucket = ? GROUP BY bucket;",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let old = DfValue::from(NaiveDate::from_ymd(2020, 1, 1).and_hms(0, 0, 0));
+    let new = DfValue::from(NaiveDate::from_ymd(2100, 1, 1).and_hms(0, 0, 0));
+    let mut events = g.table("events").await.unwrap();
+    for (id, bucket) in [&old, &old, &new].into_iter().enumerate() {
+        events
+            .insert(vec![DfValue::from(id as i32), bucket.clone()])
+            .await
+            .unwrap();
+    }
+    sleep().await;
+
+    let mut q = g.view("q").await.unwrap().into_reader_handle().unwrap();
+    for (bucket, count) in [(&old, 2), (&new, 1)] {
+        let rows = q.lookup(&[bucket.clone()], true).await.unwrap().into_vec();
+        assert_eq!(rows, vec![vec![bucket.clone(), DfValue::from(count)]]);
+    }
+    assert_eq!(q.stats().await.unwrap().keys, 2);
+
+    // The old bucket is evicted from the reader, and from the aggregate it's read from
+    eventually!(run_test: {
+        q.stats().await.unwrap().keys
+    }, then_assert: |keys| {
+        assert_eq!(keys, 1);
+    });
+    let stats = g.statistics().await.unwrap();
+    assert!(stats
+        .values()
+        .flat_map(|(_, node_stats)| node_stats.values())
+        .any(|n| n.reader.is_none() && n.counters.evictions > 0));
+
+    // The bucket is replayed again if it's read
+    let rows = q.lookup(&[old.clone()], true).await.unwrap().into_vec();
+    assert_eq!(rows, vec![vec![old, DfValue::from(2)]]);
+}
+
+// Every address in 127.0.0.0/8 is bound to the loopback interface on Linux, but not on all
+// platforms
+#[cfg(target_os = "linux")]
+#[tokio::test(flavor = "multi_thread")]
+async fn listens_on_multiple_addresses() {
+    let addrs: Vec<std::net::IpAddr> =
+        vec!["127.0.0.1".parse().unwrap(), "127.0.0.2".parse().unwrap()];
+    let mut builder = Builder::for_tests();
+    builder.set_listen_addrs(addrs.clone());
+    let mut g = builder.start_local().await.unwrap();
+    g.extend_recipe(
+        ChangeList::from_str(
+            "CREATE TABLE t (id int, val int);
+             CREATE CACHE q FROM SELECT val FROM t WHERE id = ?;",
+            Dialect::DEFAULT_MYSQL,
+        )
+        .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let mut t = g.table("t").await.unwrap();
+    t.insert(vec![DfValue::from(1), DfValue::from(2)])
+        .await
+        .unwrap();
+    sleep().await;
+
+    let mut q = g.view("q").await.unwrap().into_reader_handle().unwrap();
+    let rows = q
+        .lookup(&[DfValue::from(1)], true)
+        .await
+        .unwrap()
+        .into_vec();
+    assert_eq!(rows, vec![vec![DfValue::from(2)]]);
+
+    // The HTTP API is served on the same port on every address
+    let port = g.get_address().port().unwrap();
+    for addr in addrs {
+        tokio::net::TcpStream::connect(std::net::SocketAddr::new(addr, port))
+            .await
+            .unwrap();
+    }
+}
@@ -444,7 +444,7 @@ pub use controller::migrate::materialization::FrontierStrategy;
 pub use controller::replication::{ReplicationOptions, ReplicationStrategy};
 use controller::sql;
 use database_utils::UpstreamConfig;
-pub use dataflow::{DurabilityMode, PersistenceParameters};
+pub use dataflow::{DurabilityMode, PacketLogRecord, PacketLogReplayer, PersistenceParameters};
 pub use petgraph::graph::NodeIndex;
 pub use readyset_client::consensus::{Authority, LocalAuthority};
 pub use readyset_client::*;
@@ -468,6 +468,11 @@ use std::time::Duration;
 use anyhow::anyhow;
 use clap::{ArgEnum, Parser};
 use dataflow::DomainConfig;
+use readyset_client::channel::{ChannelSecurity, TlsFiles};
+use readyset_client::namespace::{NamespaceQuota, NamespaceQuotaOverride, NamespaceQuotas};
+use readyset_client::placement::{PlacementConstraints, WorkerConstraint};
+use readyset_util::redacted::RedactedString;
+use replicators::ReplicationTransformHook;
 use serde::{Deserialize, Serialize};
 
 /// Configuration for an running noria cluster
@@ -493,6 +498,9 @@ pub struct Config {
     pub(crate) mir_config: sql::mir::Config,
     #[serde(flatten)]
     pub(crate) replicator_config: UpstreamConfig,
+    /// A hook to transform rows replicated from the upstream database
+    #[serde(skip)]
+    pub(crate) replication_transform: ReplicationTransformHook,
     pub(crate) keep_prior_recipes: bool,
     #[serde(default)]
     pub(crate) replication_strategy: ReplicationStrategy,
@@ -501,6 +509,45 @@ pub struct Config {
     /// The duration to wait before canceling a task waiting on a worker request. Worker requests
     /// are typically issued as part of migrations.
     pub(crate) worker_request_timeout: Duration,
+    /// The maximum size in bytes of each worker's pool of interned text values (0 = disabled)
+    #[serde(default)]
+    pub(crate) text_interning_pool_size: usize,
+    /// The CPUs to pin each worker's domain threads to, assigned round-robin (empty = don't pin)
+    #[serde(default)]
+    pub(crate) domain_cpus: Vec<usize>,
+    /// The CPUs to pin the threads serving reads to, assigned round-robin (empty = don't pin)
+    #[serde(default)]
+    pub(crate) reader_cpus: Vec<usize>,
+    /// Used to authenticate and encrypt connections to domains, if set
+    #[serde(skip)]
+    pub(crate) channel_security: Option<ChannelSecurity>,
+    /// Limits on the caches in each namespace
+    #[serde(default)]
+    pub(crate) namespace_quotas: NamespaceQuotas,
+    /// Constraints on which workers domains containing readers or base tables may be scheduled
+    /// onto, for all migrations
+    #[serde(default)]
+    pub(crate) placement_constraints: PlacementConstraints,
+    /// How often the leader compares checksums of the state of the replicas of each replicated
+    /// domain, if at all
+    #[serde(default)]
+    pub(crate) state_checksum_interval: Option<Duration>,
+    /// The maximum number of times the controller will restart each domain after it fails within
+    /// `domain_restart_window` (0 = never restart failed domains)
+    #[serde(default = "default_domain_restart_budget")]
+    pub(crate) domain_restart_budget: usize,
+    /// The window of time over which the restarts of each domain are limited by
+    /// `domain_restart_budget`
+    #[serde(default = "default_domain_restart_window")]
+    pub(crate) domain_restart_window: Duration,
+}
+
+fn default_domain_restart_budget() -> usize {
+    3
+}
+
+fn default_domain_restart_window() -> Duration {
+    Duration::from_secs(600)
 }
 
 impl Default for Config {
@@ -519,6 +566,13 @@ impl Default for Config {
                 // now.
                 table_request_timeout: Duration::from_millis(1800000),
                 eviction_kind: dataflow::EvictionKind::Random,
+                replay_spill_threshold: None,
+                packet_log_dir: None,
+                replication_conflict_policy: Default::default(),
+                record_provenance: false,
+                reader_key_ttl: None,
+                reader_key_ttl_jitter_percent: 10,
+                max_key_expirations_per_second: None,
             },
             persistence: Default::default(),
             quorum: 1,
@@ -526,10 +580,20 @@ impl Default for Config {
             abort_on_task_failure: true,
             mir_config: Default::default(),
             replicator_config: Default::default(),
+            replication_transform: Default::default(),
             keep_prior_recipes: true,
             replication_strategy: Default::default(),
             upquery_timeout: Duration::from_millis(5000),
             worker_request_timeout: Duration::from_millis(1800000),
+            text_interning_pool_size: 0,
+            domain_cpus: vec![],
+            reader_cpus: vec![],
+            channel_security: None,
+            namespace_quotas: Default::default(),
+            placement_constraints: Default::default(),
+            state_checksum_interval: None,
+            domain_restart_budget: default_domain_restart_budget(),
+            domain_restart_window: default_domain_restart_window(),
         }
     }
 }
@@ -578,6 +642,92 @@ pub struct WorkerOptions {
     #[clap(long = "eviction-policy", arg_enum, default_value_t = dataflow::EvictionKind::Random)]
     pub eviction_kind: dataflow::EvictionKind,
 
+    /// Maximum size, in bytes, of the pool used to share identical text values received in writes
+    /// and replays between rows (0 = disabled)
+    #[clap(long, default_value = "0", env = "TEXT_INTERNING_POOL_SIZE")]
+    pub text_interning_pool_size: usize,
+
+    /// List of CPUs to pin domain threads to, such as `0-3,8`. Each domain runs on a single
+    /// thread, and domain threads are assigned to the listed CPUs round-robin. Since memory is
+    /// allocated local to the NUMA node of the CPU a thread first touches it from, pinning
+    /// domains also keeps the state of each domain on the NUMA node it runs on (unset = don't
+    /// pin domain threads)
+    #[clap(long, env = "DOMAIN_CPUS")]
+    pub domain_cpus: Option<readyset_util::cpu::CpuList>,
+
+    /// List of CPUs to pin the threads serving reads to, such as `4-7`. Reads are served by a
+    /// dedicated runtime with one thread per listed CPU, so that pinning them doesn't pin the rest
+    /// of the server (unset = serve reads on the main runtime, without pinning)
+    #[clap(long, env = "READER_CPUS")]
+    pub reader_cpus: Option<readyset_util::cpu::CpuList>,
+
+    /// Maximum number of rows of a fully materialized node's state to hold in memory while
+    /// replaying it to backfill a new materialization. Rows beyond this are spilled to a temporary
+    /// file on disk until they're replayed (unset = never spill)
+    #[clap(long, env = "REPLAY_SPILL_THRESHOLD")]
+    pub replay_spill_threshold: Option<usize>,
+
+    /// Record every packet handled by each domain to a packet log in this directory, which can be
+    /// replayed offline with the `packet_replay` tool to reproduce bugs in materialization.
+    /// Recording slows down every domain significantly, so this should only be enabled while
+    /// capturing a bug.
+    #[clap(long, env = "DOMAIN_PACKET_LOG_DIR")]
+    pub domain_packet_log_dir: Option<PathBuf>,
+
+    /// Periodically compare checksums of the materialized state of the replicas of each replicated
+    /// domain this often, in seconds, and report replicas whose state has diverged. Each check
+    /// covers a fraction of each node's keys, so it takes several checks to cover all the state
+    /// (unset = don't check)
+    #[clap(long, env = "STATE_CHECKSUM_INTERVAL_SECONDS")]
+    pub state_checksum_interval_seconds: Option<u64>,
+
+    /// Maximum number of times to restart each domain after it fails (by returning an error, or
+    /// panicking in one of its operators) within --domain-restart-window-seconds. A domain which
+    /// fails more often than this is left stopped (0 = never restart failed domains)
+    #[clap(long, default_value = "3", env = "DOMAIN_RESTART_BUDGET")]
+    pub domain_restart_budget: usize,
+
+    /// The window of time, in seconds, over which the restarts of each domain are limited by
+    /// --domain-restart-budget
+    #[clap(long, default_value = "600", env = "DOMAIN_RESTART_WINDOW_SECONDS")]
+    pub domain_restart_window_seconds: u64,
+
+    /// What base tables do with replicated writes which update or delete a row that doesn't exist
+    /// in ReadySet: drop them (`ignore`), insert the row if the update sets all of its columns
+    /// (`insert`), or re-snapshot the table (`resnapshot`, the default). Dropped writes are
+    /// counted by the `base_table.replication_conflicts` metric.
+    #[clap(
+        long,
+        arg_enum,
+        default_value = "resnapshot",
+        env = "REPLICATION_CONFLICT_POLICY"
+    )]
+    pub replication_conflict_policy: dataflow::node::special::ReplicationConflictPolicy,
+
+    /// Tag updates with the base table, primary key and replication offset of the writes they
+    /// result from, and record the tags of the update which last wrote each row in each cache, so
+    /// that where a row came from can be retrieved with `ReadySetHandle::row_provenance`. This
+    /// costs memory and throughput, and is intended for debugging wrong results.
+    #[clap(long, env = "RECORD_PROVENANCE")]
+    pub record_provenance: bool,
+
+    /// Evict keys of partially materialized caches this many seconds after they're filled, so that
+    /// they're replayed with up-to-date results the next time they're read (unset = keep keys
+    /// until they're evicted to free memory)
+    #[clap(long, env = "READER_KEY_TTL_SECONDS")]
+    pub reader_key_ttl_seconds: Option<u64>,
+
+    /// Randomly vary the time-to-live of each cache key by up to this percentage of
+    /// --reader-key-ttl-seconds, so that keys filled at the same time (such as when a cache is
+    /// warmed) don't all expire, and get replayed, at the same time
+    #[clap(long, default_value = "10", env = "READER_KEY_TTL_JITTER_PERCENT")]
+    pub reader_key_ttl_jitter_percent: u8,
+
+    /// Evict at most this many expired cache keys per second in each domain, spreading out the
+    /// replays of keys which expire at the same time (unset = no limit)
+    #[clap(long, env = "MAX_KEY_EXPIRATIONS_PER_SECOND")]
+    pub max_key_expirations_per_second: Option<usize>,
+
     /// Disable partial
     #[clap(long = "nopartial")]
     pub no_partial: bool,
@@ -586,6 +736,12 @@ pub struct WorkerOptions {
     #[clap(long, env = "FORBID_FULL_MATERIALIZATION")]
     pub forbid_full_materialization: bool,
 
+    /// Refuse to create caches that require fully materializing a node whose state is estimated
+    /// to be larger than this many bytes, unless they're created with `CREATE CACHE ALWAYS`
+    /// (unset = no limit)
+    #[clap(long, env = "MAX_FULL_MATERIALIZATION_BYTES")]
+    pub max_full_materialization_bytes: Option<usize>,
+
     /// Enable packet filters in egresses before readers
     #[clap(long)]
     pub enable_packet_filters: bool,
@@ -619,6 +775,12 @@ pub struct WorkerOptions {
     #[clap(long, env = "EXPERIMENTAL_MIXED_COMPARISONS_SUPPORT", hide = true)]
     pub enable_experimental_mixed_comparisons: bool,
 
+    /// When running without an upstream database, treat inserts of rows whose primary key already
+    /// exists in the table as updates which replace the existing row, rather than rejecting them
+    /// with a duplicate entry error
+    #[clap(long, env = "UPSERT_DUPLICATE_KEYS")]
+    pub upsert_duplicate_keys: bool,
+
     /// Directory in which to store replicated table data. If not specified, defaults to the
     /// current working directory.
     #[clap(long, env = "DB_DIR")]
@@ -631,6 +793,185 @@ pub struct WorkerOptions {
     #[allow(missing_docs)]
     #[clap(flatten)]
     pub replicator_config: UpstreamConfig,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub channel_security: ChannelSecurityOptions,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub namespace_quotas: NamespaceQuotaOptions,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub placement: PlacementOptions,
+}
+
+// Command-line options for authenticating and encrypting connections to domains.
+//
+// NOTE These must be set identically for all ReadySet processes (both servers and adapters) in a
+// deployment.
+#[allow(missing_docs)] // Allows us to exclude docs (from doc comments) from --help text
+#[derive(Parser, Debug, Clone)]
+pub struct ChannelSecurityOptions {
+    /// Shared secret used to mutually authenticate connections between domains, and from clients
+    /// writing to tables or reading from caches, and to sign requests to the controller and
+    /// worker HTTP endpoints (all but /health, /readiness, /graph.html and the metrics
+    /// endpoints). Without TLS, every frame sent over an authenticated connection is tagged with a
+    /// key derived from the secret (unset = don't authenticate connections)
+    #[clap(long, env = "CHANNEL_SECRET")]
+    pub channel_secret: Option<RedactedString>,
+
+    /// Path to a PEM-encoded certificate chain used to encrypt connections between domains, and
+    /// from clients writing to tables, with TLS. The certificate, key and CA files are re-read
+    /// whenever they change, so certificates can be rotated without restarting (unset = don't
+    /// encrypt connections)
+    #[clap(
+        long,
+        env = "CHANNEL_TLS_CERT",
+        requires_all = &["channel-tls-key", "channel-tls-ca"]
+    )]
+    pub channel_tls_cert: Option<PathBuf>,
+
+    /// Path to the PEM-encoded PKCS#8 private key for --channel-tls-cert
+    #[clap(long, env = "CHANNEL_TLS_KEY", requires = "channel-tls-cert")]
+    pub channel_tls_key: Option<PathBuf>,
+
+    /// Path to the PEM-encoded certificate of the CA which must have signed the channel TLS
+    /// certificates of other ReadySet processes
+    #[clap(long, env = "CHANNEL_TLS_CA", requires = "channel-tls-cert")]
+    pub channel_tls_ca: Option<PathBuf>,
+
+    /// The name that the channel TLS certificates of other ReadySet processes must be valid for
+    #[clap(long, env = "CHANNEL_TLS_SERVER_NAME", default_value = "readyset")]
+    pub channel_tls_server_name: String,
+}
+
+impl ChannelSecurityOptions {
+    /// Build the [`ChannelSecurity`] configured by these options, if any
+    pub fn security(&self) -> Option<ChannelSecurity> {
+        let tls = match (
+            &self.channel_tls_cert,
+            &self.channel_tls_key,
+            &self.channel_tls_ca,
+        ) {
+            (Some(cert), Some(key), Some(ca)) => Some(TlsFiles {
+                cert: cert.clone(),
+                key: key.clone(),
+                ca: ca.clone(),
+                server_name: self.channel_tls_server_name.clone(),
+            }),
+            _ => None,
+        };
+        ChannelSecurity::new(self.channel_secret.as_deref().map(String::as_str), tls)
+    }
+}
+
+// Command-line options for the resources and other properties a server advertises to the
+// controller, and constraints on which servers domains may be placed on. See the documentation of
+// [`readyset_client::placement`].
+#[allow(missing_docs)] // Allows us to exclude docs (from doc comments) from --help text
+#[derive(Parser, Debug, Clone)]
+pub struct PlacementOptions {
+    /// Zone (for example, the cloud availability zone) this server is running in. Adapters prefer
+    /// to read from replicas of caches running in the same zone as them
+    #[clap(long, env = "WORKER_ZONE")]
+    pub worker_zone: Option<String>,
+
+    /// Label to attach to this server, in the format `<key>=<value>`. May be passed multiple
+    /// times, or separated by `;` in the environment variable
+    #[clap(
+        long = "worker-label",
+        env = "WORKER_LABELS",
+        value_delimiter = ';',
+        parse(try_from_str = parse_worker_label)
+    )]
+    pub worker_labels: Vec<(String, String)>,
+
+    /// Constraint on the servers that domains containing readers may be placed on: one of
+    /// `zone=<zone>`, `label:<key>=<value>`, `disk-backed`, `min-memory=<bytes>` or
+    /// `min-cores=<cores>`. May be passed multiple times, or separated by `;` in the environment
+    /// variable
+    #[clap(
+        long = "reader-placement-constraint",
+        env = "READER_PLACEMENT_CONSTRAINTS",
+        value_delimiter = ';'
+    )]
+    pub reader_placement_constraints: Vec<WorkerConstraint>,
+
+    /// Constraint on the servers that domains containing base tables may be placed on, in the
+    /// same format as `--reader-placement-constraint`
+    #[clap(
+        long = "base-table-placement-constraint",
+        env = "BASE_TABLE_PLACEMENT_CONSTRAINTS",
+        value_delimiter = ';'
+    )]
+    pub base_table_placement_constraints: Vec<WorkerConstraint>,
+}
+
+fn parse_worker_label(s: &str) -> anyhow::Result<(String, String)> {
+    let (key, value) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Invalid worker label `{s}`: expected `<key>=<value>`"))?;
+    Ok((key.trim().to_owned(), value.trim().to_owned()))
+}
+
+impl PlacementOptions {
+    /// Build the [`PlacementConstraints`] configured by these options
+    pub fn constraints(&self) -> PlacementConstraints {
+        PlacementConstraints {
+            readers: self.reader_placement_constraints.clone(),
+            base_tables: self.base_table_placement_constraints.clone(),
+        }
+    }
+}
+
+// Command-line options for limiting the caches in each namespace (the upstream schema of the
+// tables a cache reads from). See the documentation of [`readyset_client::namespace`].
+//
+// NOTE These must be set identically for all ReadySet processes (both servers and adapters) in a
+// deployment.
+#[allow(missing_docs)] // Allows us to exclude docs (from doc comments) from --help text
+#[derive(Parser, Debug, Clone)]
+pub struct NamespaceQuotaOptions {
+    /// Maximum number of caches in each namespace (unset = unlimited)
+    #[clap(long, env = "NAMESPACE_MAX_CACHES")]
+    pub namespace_max_caches: Option<usize>,
+
+    /// Maximum number of bytes of state materialized for the caches in each namespace, beyond
+    /// which no new caches can be created in the namespace (unset = unlimited)
+    #[clap(long, env = "NAMESPACE_MAX_MATERIALIZED_BYTES")]
+    pub namespace_max_materialized_bytes: Option<usize>,
+
+    /// Maximum number of reads per second from the caches in each namespace, per adapter. Reads
+    /// over this limit are proxied to the upstream database (unset = unlimited)
+    #[clap(long, env = "NAMESPACE_MAX_READS_PER_SECOND")]
+    pub namespace_max_reads_per_second: Option<u32>,
+
+    /// Quota for a single namespace, overriding the limits above, in the format
+    /// `<namespace>:<limit>=<value>,...`, where each limit is one of `caches`, `bytes` or `qps`.
+    /// Limits which aren't given are unlimited for the namespace. May be passed multiple times,
+    /// or separated by `;` in the environment variable
+    #[clap(
+        long = "namespace-quota",
+        env = "NAMESPACE_QUOTAS",
+        value_delimiter = ';'
+    )]
+    pub namespace_quota_overrides: Vec<NamespaceQuotaOverride>,
+}
+
+impl NamespaceQuotaOptions {
+    /// Build the [`NamespaceQuotas`] configured by these options
+    pub fn quotas(&self) -> NamespaceQuotas {
+        NamespaceQuotas {
+            default: NamespaceQuota {
+                max_caches: self.namespace_max_caches,
+                max_materialized_bytes: self.namespace_max_materialized_bytes,
+                max_reads_per_second: self.namespace_max_reads_per_second,
+            },
+            ..self.namespace_quota_overrides.iter().cloned().collect()
+        }
+    }
 }
 
 use std::pin::Pin;
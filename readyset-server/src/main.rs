@@ -1,4 +1,5 @@
 use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
 use std::process;
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -13,7 +14,7 @@ use readyset_server::metrics::{
 };
 use readyset_server::{resolve_addr, Builder, NoriaMetricsRecorder, WorkerOptions};
 use readyset_telemetry_reporter::{TelemetryEvent, TelemetryInitializer};
-use readyset_tracing::{error, info};
+use readyset_tracing::{error, info, warn};
 use readyset_version::*;
 
 #[cfg(not(target_env = "msvc"))]
@@ -49,22 +50,40 @@ pub async fn get_aws_private_ip() -> anyhow::Result<IpAddr> {
 #[derive(Parser, Debug)]
 #[clap(version = VERSION_STR_PRETTY)]
 struct Options {
-    /// IP address to listen on
+    /// IP address to listen on for traffic from clients. May be passed multiple times, or
+    /// separated by `,`, to listen on several addresses (for example, both an IPv4 and an IPv6
+    /// address).
     #[clap(
         long,
         short = 'a',
         env = "LISTEN_ADDRESS",
         default_value = "127.0.0.1",
+        value_delimiter = ',',
         parse(try_from_str = resolve_addr)
     )]
-    address: IpAddr,
+    address: Vec<IpAddr>,
+
+    /// IP address to listen on for traffic from other noria instances running in the same
+    /// deployment (connections between domains).
+    ///
+    /// If not specified, defaults to the first value of `address`
+    #[clap(long, env = "INTERNAL_ADDRESS", parse(try_from_str=resolve_addr))]
+    internal_address: Option<IpAddr>,
 
     /// IP address to advertise to other noria instances running in the same deployment.
     ///
-    /// If not specified, defaults to the value of `address`
+    /// If not specified, defaults to the first value of `address`
     #[clap(long, env = "EXTERNAL_ADDRESS", parse(try_from_str=resolve_addr))]
     external_address: Option<IpAddr>,
 
+    /// IP address to advertise to other noria instances running in the same deployment for
+    /// connections between domains, if it differs from the external address (for example, if
+    /// clients reach this instance through NAT but other instances don't).
+    ///
+    /// If not specified, defaults to the external address
+    #[clap(long, env = "INTERNAL_EXTERNAL_ADDRESS", parse(try_from_str=resolve_addr))]
+    internal_external_address: Option<IpAddr>,
+
     /// Port to advertise to other ReadySet instances running in the same deployment.
     #[clap(long, short = 'p', default_value = "6033", parse(try_from_str))]
     external_port: u16,
@@ -85,8 +104,8 @@ struct Options {
     #[clap(long, env = "AUTHORITY_ADDRESS", default_value = "127.0.0.1:8500")]
     authority_address: String,
 
-    /// The authority to use. Possible values: zookeeper, consul.
-    #[clap(long, env = "AUTHORITY", default_value = "consul", possible_values = &["consul", "zookeeper"])]
+    /// The authority to use. Possible values: zookeeper, consul, etcd, standalone.
+    #[clap(long, env = "AUTHORITY", default_value = "consul", possible_values = &["consul", "zookeeper", "etcd", "standalone"])]
     authority: AuthorityType,
 
     /// Whether this server should only run reader domains
@@ -119,6 +138,11 @@ struct Options {
     #[clap(long, env = "DISABLE_TELEMETRY")]
     disable_telemetry: bool,
 
+    /// Path to a file of `name = value` settings, named after their command-line options, which
+    /// is re-read and applied to the running deployment whenever SIGHUP is received.
+    #[clap(long, env = "CONFIG_FILE")]
+    config_file: Option<PathBuf>,
+
     /// Whether we should wait for a failpoint request to the servers http router, which may
     /// impact startup.
     #[clap(long, hide = true)]
@@ -151,7 +175,7 @@ fn main() -> anyhow::Result<()> {
     let external_addr = if opts.use_aws_external_address {
         Either::Left(get_aws_private_ip())
     } else {
-        Either::Right(future::ok(opts.external_address.unwrap_or(opts.address)))
+        Either::Right(future::ok(opts.external_address.unwrap_or(opts.address[0])))
     };
 
     let mut recs = Vec::new();
@@ -195,7 +219,13 @@ fn main() -> anyhow::Result<()> {
         _ => opts.authority_address.clone(),
     };
     let mut builder = Builder::from_worker_options(opts.worker_options, &opts.deployment);
-    builder.set_listen_addr(opts.address);
+    builder.set_listen_addrs(opts.address);
+    if let Some(internal_address) = opts.internal_address {
+        builder.set_internal_listen_addr(internal_address);
+    }
+    if let Some(internal_external_address) = opts.internal_external_address {
+        builder.set_internal_external_addr(internal_external_address);
+    }
     builder.set_telemetry_sender(telemetry_sender.clone());
     builder.set_wait_for_failpoint(opts.wait_for_failpoint);
 
@@ -213,7 +243,7 @@ fn main() -> anyhow::Result<()> {
     let deployment = opts.deployment;
     let external_port = opts.external_port;
     let mut handle = rt.block_on(async move {
-        let authority = authority.to_authority(&authority_addr, &deployment).await;
+        let authority = authority.to_authority(&authority_addr, &deployment).await?;
 
         let external_addr = external_addr.await.unwrap_or_else(|error| {
             error!(%error, "Error obtaining external IP address");
@@ -223,6 +253,45 @@ fn main() -> anyhow::Result<()> {
         builder.start(Arc::new(authority)).await
     })?;
 
+    if let (Some(config_file), Some(controller)) = (opts.config_file, handle.c.clone()) {
+        let mut sighup = {
+            let _guard = rt.enter();
+            tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()).unwrap()
+        };
+        rt.spawn(async move {
+            let mut controller = controller;
+            while sighup.recv().await.is_some() {
+                info!(path = %config_file.display(), "SIGHUP received, reloading configuration");
+                let contents = match std::fs::read_to_string(&config_file) {
+                    Ok(contents) => contents,
+                    Err(error) => {
+                        error!(%error, "Error reading configuration file");
+                        continue;
+                    }
+                };
+                let settings = match readyset_client::config::parse_settings(&contents) {
+                    Ok(settings) => settings,
+                    Err(error) => {
+                        error!(%error, "Error parsing configuration file");
+                        continue;
+                    }
+                };
+                match controller.reload_config(settings).await {
+                    Ok(result) => {
+                        if !result.requires_restart.is_empty() {
+                            warn!(
+                                settings = ?result.requires_restart,
+                                "Some settings can only be changed by restarting ReadySet"
+                            );
+                        }
+                        info!(applied = ?result.applied, "Configuration reloaded");
+                    }
+                    Err(error) => error!(%error, "Error reloading configuration"),
+                }
+            }
+        });
+    }
+
     // Wait for a shutdown condition, being one of:
     // - CTRL-C
     // - SIGTERM
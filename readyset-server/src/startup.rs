@@ -49,16 +49,19 @@
 
 use std::net::{IpAddr, SocketAddr};
 use std::sync::{Arc, Mutex};
-use std::{process, time};
+use std::{io, process, time};
 
-use dataflow::Readers;
+use dataflow::{Readers, TextPool};
 use failpoint_macros::set_failpoint;
 use futures_util::future::{Either, TryFutureExt};
+use futures_util::stream::StreamExt;
 use health_reporter::{HealthReporter, State as ServerState};
+use readyset_client::channel::{ChannelCoordinator, ChannelSecurity};
 use readyset_client::consensus::{Authority, WorkerSchedulingConfig};
 use readyset_client::{ControllerDescriptor, WorkerDescriptor};
 use readyset_telemetry_reporter::{TelemetryBuilder, TelemetryEvent, TelemetrySender};
-use readyset_tracing::error;
+use readyset_tracing::{error, warn};
+use readyset_util::cpu::{self, CpuAssigner};
 use readyset_util::futures::abort_on_panic;
 use stream_cancel::{Trigger, Valve};
 use tokio::net::TcpListener;
@@ -82,39 +85,163 @@ macro_rules! maybe_abort_on_panic {
     }};
 }
 
-/// Starts the reader layer of the noria server instance. Returns the external address
-/// of the reader listener.
+/// The addresses a server instance listens on, and the addresses it advertises to clients and to
+/// other server instances.
+///
+/// Traffic from clients (requests to the HTTP API, and reads from readers) and traffic between
+/// server instances (connections between domains) can be bound to different addresses, and
+/// client traffic can be bound to several addresses at once, such as an IPv4 and an IPv6
+/// address. Note that on most platforms, listening on the IPv6 unspecified address (`::`) also
+/// listens on the IPv4 unspecified address, so the two can't be combined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListenConfig {
+    /// The IP addresses to listen on for traffic from clients. The same port is used on every
+    /// address.
+    pub listen_addrs: Vec<IpAddr>,
+    /// The IP address to listen on for connections to domains from other server instances
+    pub internal_listen_addr: IpAddr,
+    /// The IP address and HTTP port to advertise to clients
+    pub external_addr: SocketAddr,
+    /// The IP address to advertise to other server instances for connections to domains
+    pub internal_external_addr: IpAddr,
+}
+
+/// Bind a TCP listener on `port` on each of `addrs`, so that the same port can be advertised for
+/// all of them. If `port` is 0, a random port is picked when binding the first address, and that
+/// port is used for the rest.
+pub(crate) async fn bind_listeners(addrs: &[IpAddr], port: u16) -> io::Result<Vec<TcpListener>> {
+    let mut port = port;
+    let mut listeners = Vec::with_capacity(addrs.len());
+    for addr in addrs {
+        let listener = TcpListener::bind(SocketAddr::new(*addr, port)).await?;
+        port = listener.local_addr()?.port();
+        listeners.push(listener);
+    }
+    Ok(listeners)
+}
+
+/// Returns a handle to the runtime to serve reads on. If `cpus` is non-empty, this is a dedicated
+/// runtime with one thread pinned to each of `cpus`, so that pinning the threads serving reads
+/// doesn't also pin the rest of the server, which is shut down once `valve` is closed. Otherwise,
+/// reads are served on the current runtime.
+fn reader_runtime(cpus: Vec<usize>, valve: &Valve) -> io::Result<tokio::runtime::Handle> {
+    if cpus.is_empty() {
+        return Ok(tokio::runtime::Handle::current());
+    }
+
+    let threads = cpus.len();
+    let cpus = CpuAssigner::new(cpus);
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .worker_threads(threads)
+        .thread_name("reader")
+        .on_thread_start(move || {
+            if let Some(cpu) = cpus.next_cpu() {
+                if !cpu::pin_current_thread(cpu) {
+                    warn!(cpu, "Failed to pin reader thread");
+                }
+            }
+        })
+        .build()?;
+    let handle = runtime.handle().clone();
+
+    // produces a value when the `Valve` is closed
+    let mut shutdown_stream = valve.wrap(futures_util::stream::pending::<()>());
+    tokio::spawn(async move {
+        shutdown_stream.next().await;
+        // Dropping a runtime waits for its threads to exit, which isn't allowed from within
+        // another runtime
+        runtime.shutdown_background();
+    });
+
+    Ok(handle)
+}
+
+/// Starts the reader layer of the noria server instance, on the runtime returned by
+/// [`reader_runtime`]. Returns the external address of the reader listener.
 async fn start_readers(
-    listen_addr: IpAddr,
-    external_addr: SocketAddr,
+    listen: &ListenConfig,
     upquery_timeout: time::Duration,
     abort_on_task_failure: bool,
     readers: Readers,
+    reader_cpus: Vec<usize>,
+    channel_security: Option<ChannelSecurity>,
     valve: Valve,
 ) -> Result<SocketAddr, anyhow::Error> {
-    let readers_listener = TcpListener::bind(SocketAddr::new(listen_addr, 0)).await?;
-    let reader_addr = SocketAddr::new(external_addr.ip(), readers_listener.local_addr()?.port());
-    tokio::spawn(maybe_abort_on_panic!(
-        abort_on_task_failure,
-        crate::worker::readers::listen(
-            valve.clone(),
-            readers_listener,
-            readers.clone(),
-            upquery_timeout,
-        )
-    ));
+    let runtime = reader_runtime(reader_cpus, &valve)?;
+    let listen_addrs = listen.listen_addrs.clone();
+    let external_ip = listen.external_addr.ip();
+
+    // The listeners have to be created on the runtime they're served on, so spawn the whole setup
+    // there
+    runtime
+        .spawn(async move {
+            let readers_listeners = bind_listeners(&listen_addrs, 0).await?;
+            let reader_addr =
+                SocketAddr::new(external_ip, readers_listeners[0].local_addr()?.port());
+            for readers_listener in readers_listeners {
+                tokio::spawn(maybe_abort_on_panic!(
+                    abort_on_task_failure,
+                    crate::worker::readers::listen(
+                        valve.clone(),
+                        readers_listener,
+                        readers.clone(),
+                        upquery_timeout,
+                        channel_security.clone(),
+                    )
+                ));
+            }
 
-    Ok(reader_addr)
+            // Also listen on a unix domain socket, so that clients on the same host can skip the
+            // network stack when reading. Clients fall back to TCP if this socket doesn't exist, so
+            // failing to create it isn't fatal.
+            #[cfg(unix)]
+            {
+                let path = readyset_client::reader_socket_path(reader_addr);
+                let listener = readyset_client::create_reader_socket_dir().and_then(|_| {
+                    // Clean up after any previous worker that exited without removing its socket
+                    let _ = std::fs::remove_file(&path);
+                    tokio::net::UnixListener::bind(&path)
+                });
+                match listener {
+                    Ok(local_listener) => {
+                        tokio::spawn(maybe_abort_on_panic!(
+                            abort_on_task_failure,
+                            crate::worker::readers::listen_local(
+                                valve.clone(),
+                                local_listener,
+                                path,
+                                readers.clone(),
+                                upquery_timeout,
+                                channel_security.clone(),
+                            )
+                        ));
+                    }
+                    Err(error) => {
+                        warn!(
+                            %error,
+                            path = %path.display(),
+                            "Could not listen on local reader socket"
+                        )
+                    }
+                }
+            }
+
+            Ok::<_, anyhow::Error>(reader_addr)
+        })
+        .await?
 }
 
 async fn start_worker(
     worker_rx: Receiver<WorkerRequest>,
-    listen_addr: IpAddr,
-    external_addr: SocketAddr,
+    listen: &ListenConfig,
     abort_on_task_failure: bool,
     readers: Readers,
     memory_limit: Option<usize>,
     memory_check_frequency: Option<time::Duration>,
+    text_interning_pool_size: usize,
+    domain_cpus: Vec<usize>,
+    channel_security: Option<ChannelSecurity>,
     valve: Valve,
 ) -> Result<(), anyhow::Error> {
     set_failpoint!("start-worker");
@@ -124,9 +251,9 @@ async fn start_worker(
         evict_interval: memory_check_frequency.map(|f| tokio::time::interval(f)),
         memory_limit,
         rx: worker_rx,
-        coord: Arc::new(Default::default()),
-        domain_bind: listen_addr,
-        domain_external: external_addr.ip(),
+        coord: Arc::new(ChannelCoordinator::with_security(channel_security)),
+        domain_bind: listen.internal_listen_addr,
+        domain_external: listen.internal_external_addr,
         state_sizes: Default::default(),
         readers,
         valve,
@@ -134,6 +261,9 @@ async fn start_worker(
         memory: MemoryTracker::new()?,
         is_evicting: Default::default(),
         domain_wait_queue: Default::default(),
+        domain_failure_reports: Default::default(),
+        text_pool: TextPool::new(text_interning_pool_size),
+        domain_cpus: CpuAssigner::new(domain_cpus),
     };
 
     tokio::spawn(maybe_abort_on_panic!(abort_on_task_failure, worker.run()));
@@ -194,34 +324,40 @@ async fn start_controller(
 
 async fn start_request_router(
     authority: Arc<Authority>,
-    listen_addr: IpAddr,
-    external_addr: SocketAddr,
+    listen: &ListenConfig,
     worker_tx: Sender<WorkerRequest>,
     controller_tx: Sender<ControllerRequest>,
     abort_on_task_failure: bool,
     valve: Valve,
     health_reporter: HealthReporter,
     failpoint_channel: Option<Arc<Sender<()>>>,
+    channel_security: Option<ChannelSecurity>,
 ) -> Result<Url, anyhow::Error> {
-    let http_server = NoriaServerHttpRouter {
-        listen_addr,
-        port: external_addr.port(),
+    let mut http_server = NoriaServerHttpRouter {
+        listen_addrs: listen.listen_addrs.clone(),
+        port: listen.external_addr.port(),
         valve,
         worker_tx: worker_tx.clone(),
         controller_tx,
         authority: authority.clone(),
         health_reporter: health_reporter.clone(),
+        worker_uri: None,
         failpoint_channel,
+        channel_security,
+        leader_handle: Default::default(),
     };
 
-    let http_listener = http_server.create_listener().await?;
-    let real_external_addr =
-        SocketAddr::new(external_addr.ip(), http_listener.local_addr()?.port());
-    // FIXME(eta): this won't work for IPv6
+    let http_listeners = http_server.create_listeners().await?;
+    let real_external_addr = SocketAddr::new(
+        listen.external_addr.ip(),
+        http_listeners[0].local_addr()?.port(),
+    );
+    // IPv6 socket addresses are formatted with the address in brackets, as URLs require
     let http_uri = Url::parse(&format!("http://{}", real_external_addr))?;
+    http_server.worker_uri = Some(http_uri.clone());
     tokio::spawn(maybe_abort_on_panic!(
         abort_on_task_failure,
-        NoriaServerHttpRouter::route_requests(http_server, http_listener)
+        NoriaServerHttpRouter::route_requests(http_server, http_listeners)
     ));
 
     Ok(http_uri)
@@ -257,8 +393,7 @@ async fn maybe_wait_for_failpoint(_: Option<Receiver<()>>) {}
 /// Creates the instance, with an existing set of readers and a valve to cancel the intsance.
 pub async fn start_instance_inner(
     authority: Arc<Authority>,
-    listen_addr: IpAddr,
-    external_addr: SocketAddr,
+    listen: ListenConfig,
     config: Config,
     memory_limit: Option<usize>,
     memory_check_frequency: Option<time::Duration>,
@@ -277,21 +412,25 @@ pub async fn start_instance_inner(
 
     let Config {
         abort_on_task_failure,
+        text_interning_pool_size,
+        ref domain_cpus,
+        ref channel_security,
         ..
     } = config;
+    let channel_security = channel_security.clone();
 
     let (tx, rx) = maybe_create_failpoint_chann(wait_for_failpoint);
     let mut health_reporter = HealthReporter::new();
     let http_uri = start_request_router(
         authority.clone(),
-        listen_addr,
-        external_addr,
+        &listen,
         worker_tx.clone(),
         controller_tx,
         abort_on_task_failure,
         valve.clone(),
         health_reporter.clone(),
         tx,
+        channel_security.clone(),
     )
     .await?;
 
@@ -302,12 +441,14 @@ pub async fn start_instance_inner(
 
     start_worker(
         worker_rx,
-        listen_addr,
-        external_addr,
+        &listen,
         abort_on_task_failure,
         readers,
         memory_limit,
         memory_check_frequency,
+        text_interning_pool_size,
+        domain_cpus.clone(),
+        channel_security.clone(),
         valve.clone(),
     )
     .await?;
@@ -337,15 +478,20 @@ pub async fn start_instance_inner(
 
     health_reporter.set_state(ServerState::Healthy);
 
-    Ok(Handle::new(authority, handle_tx, trigger, our_descriptor))
+    Ok(Handle::new(
+        authority,
+        handle_tx,
+        trigger,
+        our_descriptor,
+        channel_security,
+    ))
 }
 
 /// Start up a new instance and return a handle to it. Dropping the handle will stop the
 /// instance. Make sure that this method is run while on a runtime.
 pub(super) async fn start_instance(
     authority: Arc<Authority>,
-    listen_addr: IpAddr,
-    external_addr: SocketAddr,
+    listen: ListenConfig,
     config: Config,
     memory_limit: Option<usize>,
     memory_check_frequency: Option<time::Duration>,
@@ -358,24 +504,26 @@ pub(super) async fn start_instance(
     let Config {
         abort_on_task_failure,
         upquery_timeout,
+        ref reader_cpus,
+        ref channel_security,
         ..
     } = config;
 
     let readers: Readers = Arc::new(Mutex::new(Default::default()));
     let reader_addr = start_readers(
-        listen_addr,
-        external_addr,
+        &listen,
         upquery_timeout,
         abort_on_task_failure,
         readers.clone(),
+        reader_cpus.clone(),
+        channel_security.clone(),
         valve.clone(),
     )
     .await?;
 
     start_instance_inner(
         authority,
-        listen_addr,
-        external_addr,
+        listen,
         config,
         memory_limit,
         memory_check_frequency,
@@ -390,3 +538,47 @@ pub(super) async fn start_instance(
     )
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn bind_listeners_with_random_port() {
+        let addrs: Vec<IpAddr> = vec!["127.0.0.1".parse().unwrap()];
+        let listeners = bind_listeners(&addrs, 0).await.unwrap();
+        assert_eq!(listeners.len(), 1);
+        assert_ne!(listeners[0].local_addr().unwrap().port(), 0);
+    }
+
+    // Every address in 127.0.0.0/8 is bound to the loopback interface on Linux, but not on all
+    // platforms
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn bind_listeners_shares_port_between_addresses() {
+        let addrs: Vec<IpAddr> = vec!["127.0.0.1".parse().unwrap(), "127.0.0.2".parse().unwrap()];
+        let listeners = bind_listeners(&addrs, 0).await.unwrap();
+        let local_addrs = listeners
+            .iter()
+            .map(|l| l.local_addr().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            local_addrs.iter().map(|a| a.ip()).collect::<Vec<_>>(),
+            addrs
+        );
+        let port = local_addrs[0].port();
+        assert!(local_addrs.iter().all(|a| a.port() == port));
+
+        for addr in local_addrs {
+            tokio::net::TcpStream::connect(addr).await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn bind_listeners_fails_if_port_is_taken() {
+        let addrs: Vec<IpAddr> = vec!["127.0.0.1".parse().unwrap()];
+        let listeners = bind_listeners(&addrs, 0).await.unwrap();
+        let port = listeners[0].local_addr().unwrap().port();
+        bind_listeners(&addrs, port).await.unwrap_err();
+    }
+}
@@ -3,22 +3,25 @@ use std::collections::hash_map::Entry::{Occupied, Vacant};
 use std::collections::HashMap;
 use std::future::Future;
 use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
-use dataflow::{DomainBuilder, DomainRequest, Packet, Readers};
+use dataflow::{DomainBuilder, DomainRequest, Packet, Readers, TextPool};
 use futures::stream::FuturesUnordered;
 use futures::FutureExt;
 use futures_util::future::TryFutureExt;
 use futures_util::sink::SinkExt;
 use futures_util::stream::StreamExt;
-use metrics::{counter, gauge, histogram};
+use hyper::http::{HeaderMap, Method};
+use metrics::{counter, gauge, histogram, increment_counter};
 use readyset_client::internal::ReplicaAddress;
 use readyset_client::metrics::recorded;
 use readyset_client::{channel, ReadySetError};
 use readyset_errors::internal_err;
 use readyset_tracing::{debug, error, info, trace, warn};
+use readyset_util::cpu::{self, CpuAssigner};
 use readyset_util::select;
 use serde::{Deserialize, Serialize};
 use stream_cancel::Valve;
@@ -43,6 +46,14 @@ mod replica;
 
 type ChannelCoordinator = channel::ChannelCoordinator<ReplicaAddress, Box<Packet>>;
 
+/// How often to check memory usage against the memory limit, if a memory limit is set on a running
+/// worker which was started without one. Matches the default of `--memory-check-every`.
+const DEFAULT_MEMORY_CHECK_FREQUENCY: Duration = Duration::from_secs(1);
+
+/// How long to wait before trying again to report a domain failure to the controller, if reporting
+/// it failed (or there was no controller to report it to)
+const DOMAIN_FAILURE_REPORT_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
 /// Some kind of request for a running ReadySet worker.
 ///
 /// Most of these requests return `()`, apart from `DomainRequest`.
@@ -60,6 +71,9 @@ pub enum WorkerRequestKind {
     /// Clear domains.
     ClearDomains,
 
+    /// Stop running the given domain replica, if it's running on this worker.
+    StopDomain(ReplicaAddress),
+
     /// A set of domains has been started elsewhere in the distributed system.
     ///
     /// The message contains information on how the domain can be reached, in order that
@@ -87,6 +101,18 @@ pub enum WorkerRequestKind {
         /// The limit in bytes
         limit: Option<usize>,
     },
+
+    /// Update the memory limit for this worker, leaving the settings which are `None` unchanged
+    UpdateMemoryLimit {
+        /// The new period with which eviction check will be performed
+        period: Option<Duration>,
+        /// The new limit in bytes. `Some(None)` removes the limit
+        limit: Option<Option<usize>>,
+    },
+
+    /// Return the current memory limit settings of this worker, as a tuple of the period with
+    /// which eviction checks are performed and the limit in bytes
+    MemoryLimit,
 }
 
 /// A request to a running ReadySet worker, containing a request kind and a completion channel.
@@ -104,7 +130,6 @@ pub struct WorkerRequest {
 #[derive(Clone, Debug)]
 pub struct WorkerElectionState {
     /// The URI of the currently active controller.
-    #[allow(dead_code)] // never read - do we care?
     controller_uri: Url,
 }
 
@@ -148,33 +173,64 @@ pub(crate) type FinishedDomainFuture = Box<
         + Send,
 >;
 
-/// Domains failures typically indicate that the system has entered an unrecoverable
-/// state. When these situations occur, panic, to kill the worker and in production abort the
-/// process. The future may be cancelled or gracefully complete when torndown, in these cases
-/// do not panic.
+/// A report, sent by a worker to the controller, that one of the domains it was running has failed
+/// (either by returning an error or by panicking) and has been stopped.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DomainFailure {
+    /// The address of the domain replica which failed
+    pub replica_address: ReplicaAddress,
+    /// A description of the error or panic which caused the failure
+    pub error: String,
+}
+
+/// An in-flight report of a [`DomainFailure`] to the controller, which resolves to the failure
+/// along with whether it was reported successfully
+pub(crate) type DomainFailureReport = Pin<Box<dyn Future<Output = (DomainFailure, bool)> + Send>>;
+
+/// Handle the completion of the future running a domain. The future may be cancelled or gracefully
+/// complete when torndown, in which case there's nothing to do. Otherwise, the domain has failed -
+/// either returning an error, or panicking in one of its operators - and a [`DomainFailure`]
+/// describing the failure is returned, so that the domain can be restarted by the controller
+/// without taking down the rest of the worker.
 fn handle_domain_future_completion(
     result: (Result<Result<(), anyhow::Error>, JoinError>, ReplicaAddress),
-) {
+) -> Option<DomainFailure> {
     let (handle, replica_address) = result;
-    match handle {
+    let error = match handle {
         Ok(Ok(())) => {
             warn!(
                 domain = %replica_address,
                 "domain future completed without error"
-            )
+            );
+            return None;
         }
         Ok(Err(e)) => {
             error!(domain = %replica_address, err = %e, "domain failed with an error");
-            panic!("domain failed: {}", e);
+            e.to_string()
         }
         Err(e) if e.is_cancelled() => {
-            warn!(domain = %replica_address, err = %e, "domain future cancelled")
+            warn!(domain = %replica_address, err = %e, "domain future cancelled");
+            return None;
         }
         Err(e) => {
             error!(domain = %replica_address, err = %e, "domain future failed");
-            panic!("domain future failure: {}", e);
+            match e.try_into_panic() {
+                Ok(payload) => payload
+                    .downcast_ref::<String>()
+                    .cloned()
+                    .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+                    .map_or_else(
+                        || "domain panicked".to_owned(),
+                        |msg| format!("panic: {msg}"),
+                    ),
+                Err(e) => e.to_string(),
+            }
         }
-    }
+    };
+    Some(DomainFailure {
+        replica_address,
+        error,
+    })
 }
 
 /// A ReadySet worker, responsible for executing some domains.
@@ -208,6 +264,12 @@ pub struct Worker {
     pub(crate) memory: MemoryTracker,
     pub(crate) is_evicting: Arc<AtomicBool>,
     pub(crate) domain_wait_queue: FuturesUnordered<FinishedDomainFuture>,
+    /// Reports of domain failures to the controller which are in flight, or waiting to be retried
+    pub(crate) domain_failure_reports: FuturesUnordered<DomainFailureReport>,
+    /// Pool of interned text values shared by all the domains run by this worker.
+    pub(crate) text_pool: TextPool,
+    /// CPUs to pin the threads running domains to.
+    pub(crate) domain_cpus: CpuAssigner,
 }
 
 impl Worker {
@@ -221,6 +283,65 @@ impl Worker {
         ));
     }
 
+    /// Mark the domain replica which failed as no longer running on this worker, and report the
+    /// failure to the controller so that it can restart the domain.
+    async fn handle_domain_failure(&mut self, failure: DomainFailure) {
+        let replica_address = failure.replica_address;
+        increment_counter!(
+            recorded::WORKER_DOMAIN_FAILURES,
+            "domain" => replica_address.domain_index.index().to_string()
+        );
+        self.domains.remove(&replica_address);
+        self.coord.remove(&replica_address);
+        self.state_sizes.lock().await.remove(&replica_address);
+
+        self.report_domain_failure(failure, Duration::ZERO);
+    }
+
+    /// Report `failure` to the current controller after `delay`. Reports which fail (including
+    /// because there's no controller yet) are retried by [`Worker::run`] until they succeed,
+    /// using whichever controller is current at the time.
+    fn report_domain_failure(&mut self, failure: DomainFailure, delay: Duration) {
+        let controller_uri = self
+            .election_state
+            .as_ref()
+            .map(|state| state.controller_uri.clone());
+        let security = self.coord.security().cloned();
+        // Report the failure concurrently with handling worker requests, since the controller will
+        // send requests back to this worker while restarting the domain
+        self.domain_failure_reports.push(Box::pin(async move {
+            tokio::time::sleep(delay).await;
+            let res = async {
+                let Some(controller_uri) = controller_uri else {
+                    anyhow::bail!("no controller to report domain failure to");
+                };
+                let url = controller_uri.join("domain_failed")?;
+                let body = bincode::serialize(&failure)?;
+                let mut headers = HeaderMap::new();
+                if let Some(security) = &security {
+                    security.sign_request(&Method::POST, url.path(), &body, &mut headers);
+                }
+                reqwest::Client::new()
+                    .post(url)
+                    .headers(headers)
+                    .body(body)
+                    .send()
+                    .await?
+                    .error_for_status()?;
+                Ok::<_, anyhow::Error>(())
+            }
+            .await;
+            if let Err(error) = &res {
+                warn!(
+                    domain = %failure.replica_address,
+                    %error,
+                    "failed to report domain failure to the controller; retrying"
+                );
+            }
+            (failure, res.is_ok())
+        }));
+    }
+
     async fn process_worker_request(&mut self, req: WorkerRequest) {
         let ret = self.handle_worker_request(req.kind).await;
         if let Err(ref e) = ret {
@@ -245,11 +366,24 @@ impl Worker {
                 self.coord.clear();
                 self.domains.clear();
                 while let Some(res) = self.domain_wait_queue.next().await {
-                    handle_domain_future_completion(res);
+                    if let Some(failure) = handle_domain_future_completion(res) {
+                        self.handle_domain_failure(failure).await;
+                    }
                 }
 
                 Ok(None)
             }
+            WorkerRequestKind::StopDomain(replica_address) => {
+                if self.domains.remove(&replica_address).is_some() {
+                    info!(
+                        domain = %replica_address,
+                        "controller requested that this worker stops domain"
+                    );
+                    self.coord.remove(&replica_address);
+                    self.state_sizes.lock().await.remove(&replica_address);
+                }
+                Ok(None)
+            }
             WorkerRequestKind::RunDomain(builder) => {
                 let replica_addr = builder.address();
                 let span = info_span!("domain", address = %replica_addr);
@@ -273,8 +407,12 @@ impl Worker {
                 bind_external.set_ip(self.domain_external);
 
                 let state_size = Arc::new(AtomicUsize::new(0));
-                let domain =
-                    builder.build(self.readers.clone(), self.coord.clone(), state_size.clone());
+                let domain = builder.build(
+                    self.readers.clone(),
+                    self.coord.clone(),
+                    state_size.clone(),
+                    self.text_pool.clone(),
+                );
 
                 // this channel is used for in-process domain traffic, to avoid going through the
                 // network stack unnecessarily
@@ -311,11 +449,21 @@ impl Worker {
                 );
 
                 let (_domain_abort, domain_abort_rx) = oneshot::channel::<()>();
+                let cpu = self.domain_cpus.next_cpu();
                 // Spawn the actual thread to run the domain
                 std::thread::Builder::new()
                     .name(format!("Domain {}", replica_addr))
                     .stack_size(2 * 1024 * 1024) // Use the same value tokio is using
                     .spawn(move || {
+                        // Pin the thread before the domain starts running, so that its state is
+                        // allocated on the NUMA node local to the CPU it runs on
+                        if let Some(cpu) = cpu {
+                            if cpu::pin_current_thread(cpu) {
+                                debug!(domain = %replica_addr, cpu, "pinned domain thread");
+                            } else {
+                                warn!(domain = %replica_addr, cpu, "failed to pin domain thread");
+                            }
+                        }
                         // The runtime will run until the abort signal is sent.
                         // This will happen either if the DomainHandle is dropped (and error is
                         // recieved) or an actual signal is sent on the
@@ -378,6 +526,24 @@ impl Worker {
                 self.memory_limit = limit;
                 Ok(None)
             }
+            WorkerRequestKind::UpdateMemoryLimit { period, limit } => {
+                if let Some(limit) = limit {
+                    self.memory_limit = limit;
+                }
+                if let Some(period) = period {
+                    self.evict_interval = Some(tokio::time::interval(period));
+                } else if self.evict_interval.is_none() && self.memory_limit.is_some() {
+                    // The worker was started without a memory limit, so it was never checking
+                    // its memory usage
+                    self.evict_interval =
+                        Some(tokio::time::interval(DEFAULT_MEMORY_CHECK_FREQUENCY));
+                }
+                Ok(None)
+            }
+            WorkerRequestKind::MemoryLimit => {
+                let period = self.evict_interval.as_ref().map(Interval::period);
+                Ok(Some(bincode::serialize(&(period, self.memory_limit))?))
+            }
         }
     }
 
@@ -416,7 +582,14 @@ impl Worker {
                     self.process_eviction();
                 }
                 Some(res) = self.domain_wait_queue.next() => {
-                    handle_domain_future_completion(res);
+                    if let Some(failure) = handle_domain_future_completion(res) {
+                        self.handle_domain_failure(failure).await;
+                    }
+                }
+                Some((failure, reported)) = self.domain_failure_reports.next() => {
+                    if !reported {
+                        self.report_domain_failure(failure, DOMAIN_FAILURE_REPORT_RETRY_INTERVAL);
+                    }
                 }
             }
         }
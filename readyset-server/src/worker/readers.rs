@@ -10,18 +10,21 @@ use std::time::Duration;
 use async_bincode::AsyncBincodeStream;
 use bincode::Options;
 use dataflow::prelude::*;
+pub use dataflow::ReaderUpdatedNotifier;
 use dataflow::{
-    Expr as DfExpr, LookupError, ReaderMap, ReaderUpdatedNotifier, Readers, SingleReadHandle,
+    Expr as DfExpr, KeyRange, LookupError, ReadOutcome, ReaderMap, Readers, SingleReadHandle,
+    PENDING_REPLAY_TIMEOUT,
 };
 use failpoint_macros::set_failpoint;
 use futures_util::future::TryFutureExt;
 use futures_util::stream::{StreamExt, TryStreamExt};
 use pin_project::pin_project;
+use readyset_client::channel::{self, ChannelSecurity, ChannelStream};
 use readyset_client::consistency::Timestamp;
 #[cfg(feature = "failure_injection")]
 use readyset_client::failpoints;
 use readyset_client::metrics::recorded;
-use readyset_client::results::ResultIterator;
+use readyset_client::results::{ResultIterator, SharedResults};
 use readyset_client::{
     KeyComparison, LookupResult, ReadQuery, ReadReply, ReadReplyStats, ReaderAddress, Tagged,
     ViewQuery,
@@ -32,9 +35,12 @@ use serde::ser::Serializer;
 use serde::Serialize;
 use stream_cancel::Valve;
 use streaming_iterator::StreamingIterator;
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::sync::mpsc::UnboundedReceiver;
 use tokio::sync::oneshot;
 use tokio_stream::wrappers::TcpListenerStream;
+#[cfg(unix)]
+use tokio_stream::wrappers::UnixListenerStream;
 use tokio_tower::multiplex::server;
 use tower::Service;
 use tracing::instrument;
@@ -135,6 +141,7 @@ pub struct ReadRequestHandler {
     wait: tokio::sync::mpsc::UnboundedSender<(BlockingRead, Ack)>,
     miss_ctr: metrics::Counter,
     hit_ctr: metrics::Counter,
+    stale_hit_ctr: metrics::Counter,
     upquery_timeout: Duration,
 }
 
@@ -159,10 +166,20 @@ impl ReadRequestHandler {
             wait,
             miss_ctr: metrics::register_counter!(recorded::SERVER_VIEW_QUERY_MISS),
             hit_ctr: metrics::register_counter!(recorded::SERVER_VIEW_QUERY_HIT),
+            stale_hit_ctr: metrics::register_counter!(recorded::SERVER_VIEW_QUERY_STALE_HIT),
             upquery_timeout,
         }
     }
 
+    /// Returns a [`ReaderUpdatedNotifier`] which will be notified the next time the contents of the
+    /// reader at `target` change
+    pub fn reader_updates(
+        &mut self,
+        target: &ReaderAddress,
+    ) -> ReadySetResult<ReaderUpdatedNotifier> {
+        Ok(get_reader_from_cache(target, &mut self.readers_cache, &self.global_readers)?.updates())
+    }
+
     /// Always returns `ServerReadReplyBatch::Unserialized` if `raw_result` is passed. The response
     /// is either an immediate response or a deffered response via a channel
     pub fn handle_normal_read_query(
@@ -172,10 +189,12 @@ impl ReadRequestHandler {
         query: ViewQuery,
         raw_result: bool,
     ) -> CallResult<impl Future<Output = Reply>> {
+        let start = time::Instant::now();
         let ViewQuery {
             key_comparisons,
-            block,
+            read_behavior,
             timestamp,
+            snapshot,
             filter,
             limit,
             offset,
@@ -205,12 +224,49 @@ impl ReadRequestHandler {
             Err(e) => reply_with_error!(e),
         };
 
-        let consistency_miss = !has_sufficient_timestamp(reader, &timestamp);
+        macro_rules! reply_with_results {
+            ($hit: expr, $outcome: expr) => {{
+                let (results, stats) = match prepare_results(
+                    $hit,
+                    reader,
+                    &self.global_readers,
+                    limit,
+                    offset,
+                    filter,
+                ) {
+                    Ok(res) => res,
+                    Err(e) => {
+                        reader.record_read(start.elapsed(), ReadOutcome::Error);
+                        reply_with_error!(e)
+                    }
+                };
+                reader.record_read(start.elapsed(), $outcome);
+
+                let results = if raw_result {
+                    ServerReadReplyBatch::Unserialized(results)
+                } else {
+                    ServerReadReplyBatch::serialize(results)
+                };
+
+                reply_with_ok!(LookupResult::Results(vec![results], stats));
+            }};
+        }
+
+        let consistency_miss = !has_sufficient_timestamp(reader, &timestamp, &snapshot);
 
         let (keys_to_replay, receiver) = match reader.get_multi_with_notifier(&key_comparisons) {
-            Err(LookupError::NotReady) => reply_with_error!(ReadySetError::ViewNotYetAvailable),
-            Err(LookupError::Destroyed) => reply_with_error!(ReadySetError::ViewDestroyed),
-            Err(LookupError::Error(e)) => reply_with_error!(e),
+            Err(LookupError::NotReady) => {
+                reader.record_read(start.elapsed(), ReadOutcome::Error);
+                reply_with_error!(ReadySetError::ViewNotYetAvailable)
+            }
+            Err(LookupError::Destroyed) => {
+                reader.record_read(start.elapsed(), ReadOutcome::Error);
+                reply_with_error!(ReadySetError::ViewDestroyed)
+            }
+            Err(LookupError::Error(e)) => {
+                reader.record_read(start.elapsed(), ReadOutcome::Error);
+                reply_with_error!(e)
+            }
             // We missed some keys
             Err(LookupError::Miss((misses, _))) if consistency_miss => (misses, None),
             Err(LookupError::Miss((misses, notifier))) => (misses, Some(notifier)),
@@ -221,19 +277,7 @@ impl ReadRequestHandler {
                 // We hit on all keys, and there is no consistency miss, can return results
                 // immediately
                 self.hit_ctr.increment(1);
-
-                let results = ResultIterator::new(hit, &reader.post_lookup, limit, offset, filter);
-
-                let results = if raw_result {
-                    ServerReadReplyBatch::Unserialized(results)
-                } else {
-                    ServerReadReplyBatch::serialize(results)
-                };
-
-                reply_with_ok!(LookupResult::Results(
-                    vec![results],
-                    ReadReplyStats::default()
-                ));
+                reply_with_results!(hit, ReadOutcome::Hit)
             }
         };
 
@@ -244,7 +288,18 @@ impl ReadRequestHandler {
             reader.trigger(keys_to_replay.into_iter().map(|k| k.into_owned()));
         }
 
-        if !block {
+        // If both the read and the reader allow stale reads, answer with the rows the missed keys
+        // held before they were evicted, and let the replays we just triggered refresh them in the
+        // background
+        if read_behavior.allows_stale() && !consistency_miss {
+            if let Some(stale) = reader.get_multi_or_stale(&key_comparisons) {
+                self.stale_hit_ctr.increment(1);
+                reply_with_results!(stale, ReadOutcome::Miss)
+            }
+        }
+
+        if !read_behavior.is_blocking() {
+            reader.record_read(start.elapsed(), ReadOutcome::Miss);
             reply_with_ok!(LookupResult::NonBlockingMiss);
         } else {
             let (tx, rx) = oneshot::channel();
@@ -255,12 +310,15 @@ impl ReadRequestHandler {
                     target,
                     key_comparisons,
                     truth: self.global_readers.clone(),
-                    first: time::Instant::now(),
+                    first: start,
+                    waiting_since: time::Instant::now(),
+                    triggered_at: time::Instant::now(),
                     warned: false,
                     limit,
                     offset,
                     filter,
                     timestamp,
+                    snapshot,
                     upquery_timeout: self.upquery_timeout,
                     raw_result,
                     receiver,
@@ -287,12 +345,21 @@ impl ReadRequestHandler {
         })
     }
 
-    fn handle_keys_query(&mut self, tag: u32, target: &ReaderAddress) -> Reply {
+    fn handle_keys_query(&mut self, tag: u32, target: &ReaderAddress, range: KeyRange) -> Reply {
         let reader = get_reader_from_cache(target, &mut self.readers_cache, &self.global_readers)?;
 
         Ok(Tagged {
             tag,
-            v: ReadReply::Keys(reader.keys()),
+            v: ReadReply::Keys(reader.keys(range)),
+        })
+    }
+
+    fn handle_stats_query(&mut self, tag: u32, target: &ReaderAddress) -> Reply {
+        let reader = get_reader_from_cache(target, &mut self.readers_cache, &self.global_readers)?;
+
+        Ok(Tagged {
+            tag,
+            v: ReadReply::Stats(reader.stats()),
         })
     }
 }
@@ -321,10 +388,23 @@ impl Service<Tagged<ReadQuery>> for ReadRequestHandler {
                 let _g = span.enter();
                 CallResult::Immediate(self.handle_size_query(tag, target))
             }
-            ReadQuery::Keys { ref target } => {
+            ReadQuery::Keys {
+                ref target,
+                index,
+                ranges,
+            } => {
                 let span = readyset_tracing::child_span!(INFO, "keys_query");
                 let _g = span.enter();
-                CallResult::Immediate(self.handle_keys_query(tag, target))
+                CallResult::Immediate(self.handle_keys_query(
+                    tag,
+                    target,
+                    KeyRange { index, ranges },
+                ))
+            }
+            ReadQuery::Stats { ref target } => {
+                let span = readyset_tracing::child_span!(INFO, "stats_query");
+                let _g = span.enter();
+                CallResult::Immediate(self.handle_stats_query(tag, target))
             }
         };
 
@@ -347,8 +427,13 @@ pub async fn retry_misses(mut rx: UnboundedReceiver<(BlockingRead, Ack)>) {
         loop {
             if let Some(recv) = &mut pending.receiver {
                 // If a receiever is available (on miss) then we simply wait for a notification that
-                // a hole has been filled, then recheck
-                let _ = recv.recv().await;
+                // a hole has been filled, then recheck. The hole might never be filled, so we stop
+                // waiting once the upquery timeout has passed, at which point the check fails the
+                // read.
+                let remaining = pending
+                    .upquery_timeout
+                    .saturating_sub(pending.first.elapsed());
+                let _ = tokio::time::timeout(remaining, recv.recv()).await;
                 while !recv.is_empty() {
                     // This drains all the messages from the notifier so we don't get woken right up
                     // again
@@ -373,6 +458,7 @@ pub(crate) async fn listen(
     on: tokio::net::TcpListener,
     readers: Readers,
     upquery_timeout: Duration,
+    security: Option<ChannelSecurity>,
 ) {
     let mut stream = valve.wrap(TcpListenerStream::new(on)).into_stream();
     while let Some(stream) = stream.next().await {
@@ -383,45 +469,120 @@ pub(crate) async fn listen(
         }
 
         let stream = stream.unwrap();
-        let readers = readers.clone();
         stream.set_nodelay(true).expect("could not set TCP_NODELAY");
+        accept(stream, security.clone(), readers.clone(), upquery_timeout);
+    }
+}
+
+/// Listen for connections to readers from clients on the same host on the unix domain socket at
+/// `path`, which is removed once the listener is shut down by `valve`. See
+/// [`readyset_client::reader_socket_path`].
+#[cfg(unix)]
+pub(crate) async fn listen_local(
+    valve: Valve,
+    on: tokio::net::UnixListener,
+    path: std::path::PathBuf,
+    readers: Readers,
+    upquery_timeout: Duration,
+    security: Option<ChannelSecurity>,
+) {
+    let mut stream = valve.wrap(UnixListenerStream::new(on)).into_stream();
+    while let Some(stream) = stream.next().await {
+        set_failpoint!(failpoints::READ_QUERY);
+        let Ok(stream) = stream else { continue };
+        if let Err(error) = readyset_client::verify_local_peer(&stream) {
+            warn!(%error, "rejected connection to local reader socket");
+            continue;
+        }
+        accept(stream, security.clone(), readers.clone(), upquery_timeout);
+    }
+
+    if let Err(error) = std::fs::remove_file(&path) {
+        warn!(%error, path = %path.display(), "Could not remove local reader socket");
+    }
+}
+
+/// Secure a newly accepted connection with `security`, if any, and check that the client uses
+/// the same version of the packet encoding as we do, then serve read requests received over it.
+/// Connections which fail to authenticate or use a different version are dropped.
+fn accept<S>(
+    stream: S,
+    security: Option<ChannelSecurity>,
+    readers: Readers,
+    upquery_timeout: Duration,
+) where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    // Don't hold up accepting other connections while this one performs its handshakes
+    tokio::spawn(async move {
+        let mut stream = match security {
+            Some(security) => match security.accept(stream).await {
+                Ok(stream) => stream,
+                Err(error) => {
+                    warn!(%error, "rejected reader connection which failed to authenticate");
+                    return;
+                }
+            },
+            None => ChannelStream::Plain(stream),
+        };
+        if let Err(error) = channel::accept_reader_handshake(&mut stream).await {
+            warn!(%error, "rejected reader connection which failed the version handshake");
+            return;
+        }
+        serve(stream, readers, upquery_timeout);
+    });
+}
 
-        // future that ensures all blocking reads are handled in FIFO order
-        // and avoid hogging the executors with read retries
-        let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<(BlockingRead, Ack)>();
-        tokio::spawn(retry_misses(rx));
-
-        let r = ReadRequestHandler::new(readers, tx, upquery_timeout);
-
-        let server = server::Server::new(AsyncBincodeStream::from(stream).for_async(), r);
-
-        tokio::spawn(server.map_err(|e| {
-            match e {
-                // server is shutting down -- no need to report this error
-                server::Error::Service(ReadySetError::ServerShuttingDown) => {}
-                server::Error::BrokenTransportRecv(ref e)
-                | server::Error::BrokenTransportSend(ref e) => {
-                    if let bincode::ErrorKind::Io(ref e) = **e {
-                        if e.kind() == std::io::ErrorKind::BrokenPipe
-                            || e.kind() == std::io::ErrorKind::ConnectionReset
-                        {
-                            // client went away
-                        }
-                    } else {
-                        error!(error = %e, "client transport error");
+/// Spawn a task serving read requests received over `stream`
+fn serve<S>(stream: S, readers: Readers, upquery_timeout: Duration)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+{
+    // future that ensures all blocking reads are handled in FIFO order
+    // and avoid hogging the executors with read retries
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel::<(BlockingRead, Ack)>();
+    tokio::spawn(retry_misses(rx));
+
+    let r = ReadRequestHandler::new(readers, tx, upquery_timeout);
+
+    let server = server::Server::new(AsyncBincodeStream::from(stream).for_async(), r);
+
+    tokio::spawn(server.map_err(|e| {
+        match e {
+            // server is shutting down -- no need to report this error
+            server::Error::Service(ReadySetError::ServerShuttingDown) => {}
+            server::Error::BrokenTransportRecv(ref e)
+            | server::Error::BrokenTransportSend(ref e) => {
+                if let bincode::ErrorKind::Io(ref e) = **e {
+                    if e.kind() == std::io::ErrorKind::BrokenPipe
+                        || e.kind() == std::io::ErrorKind::ConnectionReset
+                    {
+                        // client went away
                     }
+                } else {
+                    error!(error = %e, "client transport error");
                 }
-                e => error!(error = %e, "reader service error"),
             }
-        }));
-    }
+            e => error!(error = %e, "reader service error"),
+        }
+    }));
 }
 
 /// Verifies that the timestamp in the reader node associated with the read handle, `reader`,
-/// has a greater timestamp than `timestamp`. A greater reader timestamp indicates the writes
-/// in the node include all of the writes associated with `timestamp`.
-fn has_sufficient_timestamp(reader: &SingleReadHandle, timestamp: &Option<Timestamp>) -> bool {
-    if timestamp.is_none() {
+/// has a greater timestamp than `timestamp`, and reflects the `snapshot` being read at, if any. A
+/// greater reader timestamp indicates the writes in the node include all of the writes associated
+/// with `timestamp`.
+fn has_sufficient_timestamp(
+    reader: &SingleReadHandle,
+    timestamp: &Option<Timestamp>,
+    snapshot: &Option<Timestamp>,
+) -> bool {
+    // An empty snapshot was taken before any timestamps were propagated, so is reflected by all
+    // data
+    let snapshot = snapshot
+        .as_ref()
+        .filter(|snapshot| !snapshot.map.is_empty());
+    if timestamp.is_none() && snapshot.is_none() {
         return true;
     }
 
@@ -429,10 +590,51 @@ fn has_sufficient_timestamp(reader: &SingleReadHandle, timestamp: &Option<Timest
     if dataflow_timestamp.is_none() {
         return false;
     }
+    let dataflow_timestamp = dataflow_timestamp.unwrap();
+
+    timestamp
+        .as_ref()
+        .map_or(true, |timestamp| dataflow_timestamp.satisfies(timestamp))
+        && snapshot.map_or(true, |snapshot| {
+            dataflow_timestamp.reflects_snapshot(snapshot)
+        })
+}
 
-    dataflow_timestamp
-        .unwrap()
-        .satisfies(timestamp.as_ref().unwrap())
+/// Prepares the rows that were just looked up from `reader` to be returned, by performing its
+/// [`LazyJoin`](dataflow::LazyJoin)s and post-lookup operations while enforcing its result
+/// limits, and returns them along with the stats to reply with.
+fn prepare_results(
+    hit: SharedResults,
+    reader: &mut SingleReadHandle,
+    global_readers: &Readers,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    filter: Option<DfExpr>,
+) -> ReadySetResult<(ResultIterator, ReadReplyStats)> {
+    let hit = reader.apply_lazy_joins(hit, |dimension| {
+        global_readers
+            .lock()
+            .unwrap()
+            .get(dimension)
+            .cloned()
+            .ok_or(ReadySetError::ReaderNotFound)
+    })?;
+    let rows = hit.iter().map(|rows| rows.len() as u64).sum();
+    let (hit, truncated_keys) = reader.limit_rows_per_key(hit, filter.as_ref())?;
+    let results = ResultIterator::new(hit, &reader.post_lookup, limit, offset, filter);
+    let (results, truncated_bytes) = reader.limit_result_bytes(results)?;
+    let truncated = truncated_keys || truncated_bytes;
+    if truncated {
+        metrics::increment_counter!(recorded::SERVER_VIEW_QUERY_RESULT_TRUNCATED);
+    }
+    Ok((
+        results,
+        ReadReplyStats {
+            truncated,
+            rows,
+            ..Default::default()
+        },
+    ))
 }
 
 /// Issues a blocking read against a reader. This can be repeatedly polled via `check` for
@@ -447,8 +649,14 @@ pub struct BlockingRead {
     offset: Option<usize>,
     filter: Option<DfExpr>,
     first: time::Instant,
+    /// When the read started waiting for its misses to be filled, after triggering replays for
+    /// them
+    waiting_since: time::Instant,
+    /// When replays were last triggered for the keys this read is waiting on
+    triggered_at: time::Instant,
     warned: bool,
     timestamp: Option<Timestamp>,
+    snapshot: Option<Timestamp>,
     upquery_timeout: Duration,
     raw_result: bool,
     receiver: Option<ReaderUpdatedNotifier>,
@@ -462,7 +670,9 @@ impl std::fmt::Debug for BlockingRead {
             .field("target", &self.target)
             .field("key_comparisons", &self.key_comparisons)
             .field("first", &self.first)
+            .field("waiting_since", &self.waiting_since)
             .field("timestamp", &self.timestamp)
+            .field("snapshot", &self.snapshot)
             .field("eviction_epoch", &self.eviction_epoch)
             .finish()
     }
@@ -478,23 +688,39 @@ impl BlockingRead {
             readers.get(target).unwrap().clone()
         });
 
-        let consistency_miss = !has_sufficient_timestamp(reader, &self.timestamp);
+        let consistency_miss = !has_sufficient_timestamp(reader, &self.timestamp, &self.snapshot);
 
         let still_waiting = match reader.get_multi(&self.key_comparisons) {
             // We hit on all keys, but there is a consistency miss. This just counts as a miss,
             // but no keys needs triggering.
             Ok(_) if consistency_miss => vec![],
             Err(LookupError::Miss((misses, _))) => misses,
-            Err(_) => return Poll::Ready(Err(ReadySetError::ServerShuttingDown)),
+            Err(_) => {
+                reader.record_read(self.first.elapsed(), ReadOutcome::Error);
+                return Poll::Ready(Err(ReadySetError::ServerShuttingDown));
+            }
             Ok(hit) => {
                 // We hit on all keys, and there is no consistency miss, can return results
-                let results = ResultIterator::new(
+                let replay_wait = self.waiting_since.elapsed();
+                let (results, mut stats) = match prepare_results(
                     hit,
-                    &reader.post_lookup,
+                    reader,
+                    &self.truth,
                     self.limit,
                     self.offset,
                     self.filter.take(),
-                );
+                ) {
+                    Ok(res) => res,
+                    Err(e) => {
+                        reader.record_read(self.first.elapsed(), ReadOutcome::Error);
+                        return Poll::Ready(Ok(Tagged {
+                            tag: self.tag,
+                            v: ReadReply::Normal(Err(e)),
+                        }));
+                    }
+                };
+                reader.record_read(self.first.elapsed(), ReadOutcome::Miss);
+                stats.replay_wait_us = replay_wait.as_micros() as u64;
 
                 let results = if self.raw_result {
                     ServerReadReplyBatch::Unserialized(results)
@@ -504,10 +730,7 @@ impl BlockingRead {
 
                 return Poll::Ready(Ok(Tagged {
                     tag: self.tag,
-                    v: ReadReply::Normal(Ok(LookupResult::Results(
-                        vec![results],
-                        ReadReplyStats::default(),
-                    ))),
+                    v: ReadReply::Normal(Ok(LookupResult::Results(vec![results], stats))),
                 }));
             }
         };
@@ -519,18 +742,25 @@ impl BlockingRead {
         }
 
         let cur_eviction_epoch = reader.eviction_epoch();
-        // Only retrigger if there was an eviction since we last checked
-        if cur_eviction_epoch > self.eviction_epoch {
+        // Only retrigger if there was an eviction since we last checked, or if the replays we're
+        // waiting on may have been lost
+        if cur_eviction_epoch > self.eviction_epoch
+            || self.triggered_at.elapsed() >= PENDING_REPLAY_TIMEOUT
+        {
             self.eviction_epoch = cur_eviction_epoch;
+            self.triggered_at = time::Instant::now();
             // Retrigger all un-read keys. Its possible they could have been filled and then
-            // evicted again without us reading it.
+            // evicted again without us reading it. Keys whose replays are still in flight won't be
+            // requested again.
             if !reader.trigger(still_waiting.into_iter().map(|v| v.into_owned())) {
                 // server is shutting down and won't do the backfill
+                reader.record_read(self.first.elapsed(), ReadOutcome::Error);
                 return Poll::Ready(Err(ReadySetError::ServerShuttingDown));
             }
         }
 
-        if self.first.elapsed() > self.upquery_timeout {
+        if self.first.elapsed() >= self.upquery_timeout {
+            reader.record_read(self.first.elapsed(), ReadOutcome::Timeout);
             Poll::Ready(Err(ReadySetError::UpqueryTimeout))
         } else {
             Poll::Pending
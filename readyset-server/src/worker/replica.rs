@@ -13,13 +13,13 @@ use dataflow::{Domain, DomainRequest, Packet};
 use futures_util::sink::{Sink, SinkExt};
 use futures_util::stream::StreamExt;
 use futures_util::FutureExt;
-use readyset_client::channel::{self, CONNECTION_FROM_BASE};
+use readyset_client::channel::{self, ChannelSecurity, ChannelStream, Preamble};
 use readyset_client::internal::ReplicaAddress;
 use readyset_client::{KeyComparison, PacketData, PacketPayload, Tagged};
 use readyset_tracing::{debug, error, warn};
 use strawpoll::Strawpoll;
 use time::Duration;
-use tokio::io::{AsyncReadExt, BufReader, BufStream, BufWriter};
+use tokio::io::{BufReader, BufStream, BufWriter};
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::{mpsc, oneshot, Mutex};
 use tokio_stream::wrappers::IntervalStream;
@@ -28,8 +28,12 @@ use tracing::{info_span, instrument, Span};
 use super::ChannelCoordinator;
 use crate::ReadySetResult;
 
-type DualTcpStream =
-    channel::DualTcpStream<BufStream<TcpStream>, Box<Packet>, Tagged<PacketData>, AsyncDestination>;
+type DualTcpStream = channel::DualTcpStream<
+    BufStream<ChannelStream<TcpStream>>,
+    Box<Packet>,
+    Tagged<PacketData>,
+    AsyncDestination,
+>;
 
 type Outputs =
     AHashMap<ReplicaAddress, Box<dyn Sink<Box<Packet>, Error = bincode::Error> + Send + Unpin>>;
@@ -142,20 +146,45 @@ impl Replica {
         )
     }
 
-    /// Read the first byte of a connection to determine if it is from a base node, and convert
-    /// it to a DualTcpStream, returning a unique token for the connection together with the
-    /// upgraded connection
+    /// Secure the connection with `security`, if any, then read the [`Preamble`] of the
+    /// connection to determine if it is from a base node (and check that we know how to decode the
+    /// packets sent on it), and convert it to a DualTcpStream, returning a unique token for the
+    /// connection together with the upgraded connection
     async fn handle_new_connection(
-        mut stream: TcpStream,
+        stream: TcpStream,
+        security: Option<ChannelSecurity>,
     ) -> Result<(u64, DualTcpStream), anyhow::Error> {
-        let mut tag: u8 = 0;
-        stream.read_exact(std::slice::from_mut(&mut tag)).await?;
-        let is_base = tag == CONNECTION_FROM_BASE;
+        let _ = stream.set_nodelay(true);
+        let mut stream = match security {
+            Some(security) => match security.accept(stream).await {
+                Ok(stream) => stream,
+                Err(error) => {
+                    warn!(%error, "rejected connection which failed to authenticate");
+                    return Err(error.into());
+                }
+            },
+            None => ChannelStream::Plain(stream),
+        };
+        let Preamble { is_base, version } = Preamble::read(&mut stream).await?;
+        let (min_version, from) = if is_base {
+            (channel::MIN_BASE_PROTOCOL_VERSION, "base table")
+        } else {
+            (channel::MIN_DOMAIN_PROTOCOL_VERSION, "domain")
+        };
+        if version < min_version {
+            warn!(
+                version,
+                from, "rejected connection using an outdated channel protocol version"
+            );
+            anyhow::bail!(
+                "{from} connection uses channel protocol version {version}, but the oldest \
+                 supported version is {min_version}"
+            );
+        }
 
-        debug!(base = is_base, "established new connection");
+        debug!(base = is_base, version, "established new connection");
 
         let token = next_token();
-        let _ = stream.set_nodelay(true);
 
         let tcp = if is_base {
             DualTcpStream::upgrade(BufStream::new(stream), move |Tagged { v, tag }| {
@@ -166,12 +195,14 @@ impl Replica {
                         inner: input,
                         src: SourceChannelIdentifier { token, tag },
                     }),
-                    PacketPayload::Timestamp(_) => Box::new(Packet::Timestamp {
-                        // The link values propagated to the base table are not used.
-                        link: None,
-                        src: SourceChannelIdentifier { token, tag },
-                        timestamp: input,
-                    }),
+                    PacketPayload::Timestamp(_) | PacketPayload::Barrier(_) => {
+                        Box::new(Packet::Timestamp {
+                            // The link values propagated to the base table are not used.
+                            link: None,
+                            src: SourceChannelIdentifier { token, tag },
+                            timestamp: input,
+                        })
+                    }
                 }
             })
         } else {
@@ -354,7 +385,10 @@ impl Replica {
                 conn = incoming.accept() => {
                     let (conn, addr) = conn.context("listening")?;
                     span.in_scope(|| debug!(from = ?addr, "accepted new connection"));
-                    connection_preambles.push(Self::handle_new_connection(conn));
+                    connection_preambles.push(Self::handle_new_connection(
+                        conn,
+                        coord.security().cloned(),
+                    ));
                 },
 
                 // Handle any connections that we accepted but still need to preprocess and convert to DualTcpStream
@@ -406,10 +440,20 @@ impl Replica {
                                 _ => None,
                             };
 
-                            span.in_scope(|| domain.handle_packet(packet, out))?;
+                            let result = match span.in_scope(|| domain.handle_packet(packet, out)) {
+                                // Writes rejected by a base table are reported back to the client
+                                // that sent them, rather than taking down the domain
+                                Err(error) if ack.is_some() && error.caused_by_rejected_write() => {
+                                    Err(error)
+                                }
+                                result => {
+                                    result?;
+                                    Ok(())
+                                }
+                            };
 
                             if let Some((tag, conn)) = ack {
-                                conn.send(Tagged { tag, v: () }).await?;
+                                conn.send(Tagged { tag, v: result }).await?;
                             }
                         }
                     },
@@ -422,7 +466,7 @@ impl Replica {
                 Some(_) = refresh_sizes.next() => domain.update_state_sizes(),
 
                 // Wait for a possible sleep
-                _ = tokio::time::sleep(domain.next_poll_duration().unwrap_or_else(|| Duration::from_secs(3600))) => domain.handle_timeout()?,
+                _ = tokio::time::sleep(domain.next_poll_duration().unwrap_or_else(|| Duration::from_secs(3600))) => domain.handle_timeout(out)?,
             }
 
             // Check if the previous batch of send packets is done, and issue a new batch if needed
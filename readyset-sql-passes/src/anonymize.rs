@@ -191,13 +191,19 @@ impl<'ast> VisitorMut<'ast> for AnonymizeVisitor<'_> {
                     self.anonymize_string(from_db)
                 }
             }
+            nom_sql::ShowStatement::Columns(ref mut columns) => {
+                self.visit_table(&mut columns.table)?;
+            }
+            nom_sql::ShowStatement::CreateTable(ref mut table) => self.visit_table(table)?,
             // No anonymizaion needed
             nom_sql::ShowStatement::Events
             | nom_sql::ShowStatement::CachedQueries(..)
             | nom_sql::ShowStatement::ProxiedQueries(..)
+            | nom_sql::ShowStatement::UnsupportedQueries(..)
             | nom_sql::ShowStatement::ReadySetStatus
             | nom_sql::ShowStatement::ReadySetVersion
-            | nom_sql::ShowStatement::ReadySetTables => {}
+            | nom_sql::ShowStatement::ReadySetTables
+            | nom_sql::ShowStatement::Variables(..) => {}
         }
         Ok(())
     }
@@ -6,7 +6,9 @@ use nom_sql::{
     BinaryOperator, Column, Expr, FieldDefinitionExpr, JoinConstraint, JoinRightSide, Relation,
     SelectStatement, SqlIdentifier, SqlQuery, TableExpr, TableExprInner,
 };
-use readyset_errors::{internal_err, invalid_err, unsupported, unsupported_err, ReadySetResult};
+use readyset_errors::{
+    internal_err, invalid_err, unsupported, unsupported_err, unsupported_feature, ReadySetResult,
+};
 
 pub trait DetectProblematicSelfJoins: Sized {
     /// Detect and return an unsupported error for any joins where both sides of the join key are
@@ -215,7 +217,10 @@ fn check_select_statement<'a>(
             JoinConstraint::Using(_) => unsupported!("USING is unsupported"),
             JoinConstraint::On(expr) => {
                 if expr_is_problematic(expr, stmt, cte_ctx)? {
-                    unsupported!("Self-joins using the same column are unsupported")
+                    unsupported_feature!(
+                        SelfJoin,
+                        "Self-joins using the same column are unsupported"
+                    )
                 }
             }
             JoinConstraint::Empty => {}
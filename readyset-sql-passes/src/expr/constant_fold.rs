@@ -4,10 +4,10 @@ use nom_sql::{Column, Expr, Literal, Relation};
 use readyset_data::{DfType, DfValue};
 use readyset_errors::{internal, ReadySetResult};
 
-/// Statically evaluate the given expression, returning a literal value representing the result.
+/// Statically evaluate the given expression, returning its value.
 ///
 /// Returns an error if the expression evaluation failed, or if the expression is not constant
-fn const_eval(expr: &Expr, dialect: Dialect) -> ReadySetResult<Literal> {
+pub fn const_eval(expr: &Expr, dialect: Dialect) -> ReadySetResult<DfValue> {
     #[derive(Clone)]
     struct ConstEvalLowerContext;
     impl LowerContext for ConstEvalLowerContext {
@@ -22,8 +22,7 @@ fn const_eval(expr: &Expr, dialect: Dialect) -> ReadySetResult<Literal> {
     }
 
     let dataflow_expr = DataflowExpr::lower(expr.clone(), dialect, ConstEvalLowerContext)?;
-    let res = dataflow_expr.eval::<DfValue>(&[])?;
-    res.try_into()
+    dataflow_expr.eval::<DfValue>(&[])
 }
 
 struct ConstantFoldVisitor {
@@ -43,7 +42,7 @@ impl<'ast> VisitorMut<'ast> for ConstantFoldVisitor {
         // constant-valued; we just try to evaluate it in a context where we return errors for
         // column references and placeholders, and then only use the result if that error doesn't
         // happen.
-        match const_eval(expr, self.dialect) {
+        match const_eval(expr, self.dialect).and_then(Literal::try_from) {
             Ok(res) => {
                 *expr = Expr::Literal(res);
                 Ok(())
@@ -39,12 +39,20 @@ fn negate_expr(expr: &mut Expr) -> bool {
                 | BinaryOperator::HashSubtract
                 | BinaryOperator::Multiply
                 | BinaryOperator::Divide
+                | BinaryOperator::Modulo
+                | BinaryOperator::IntegerDivide
+                | BinaryOperator::BitwiseAnd
+                | BinaryOperator::BitwiseOr
+                | BinaryOperator::BitwiseXor
+                | BinaryOperator::ShiftLeft
+                | BinaryOperator::ShiftRight
                 | BinaryOperator::DoublePipe
                 | BinaryOperator::Arrow1
                 | BinaryOperator::Arrow2
                 | BinaryOperator::HashArrow1
                 | BinaryOperator::HashArrow2 => return false,
-                BinaryOperator::QuestionMark
+                BinaryOperator::NullSafeEqual
+                | BinaryOperator::QuestionMark
                 | BinaryOperator::QuestionMarkPipe
                 | BinaryOperator::QuestionMarkAnd
                 | BinaryOperator::AtArrowRight
@@ -19,6 +19,7 @@ mod order_limit_removal;
 mod remove_numeric_field_references;
 mod resolve_schemas;
 mod rewrite_between;
+mod rewrite_compound_aggregates;
 mod star_expansion;
 mod strip_literals;
 mod strip_post_filters;
@@ -46,11 +47,13 @@ pub use crate::order_limit_removal::OrderLimitRemoval;
 pub use crate::remove_numeric_field_references::RemoveNumericFieldReferences;
 pub use crate::resolve_schemas::ResolveSchemas;
 pub use crate::rewrite_between::RewriteBetween;
+pub use crate::rewrite_compound_aggregates::RewriteCompoundAggregates;
 pub use crate::star_expansion::StarExpansion;
 pub use crate::strip_literals::{SelectStatementSkeleton, StripLiterals};
 pub use crate::strip_post_filters::StripPostFilters;
 pub use crate::util::{
-    is_correlated, is_logical_op, is_predicate, map_aggregates, outermost_table_exprs, LogicalOp,
+    contains_placeholders, is_correlated, is_logical_op, is_post_lookup_predicate, is_predicate,
+    map_aggregates, outermost_table_exprs, LogicalOp,
 };
 
 /// Context provided to all query rewriting passes.
@@ -162,6 +165,7 @@ impl Rewrite for SelectStatement {
             .expand_implied_tables(context.view_schemas)?
             .normalize_topk_with_aggregate()?
             .rewrite_count_star(context.view_schemas, context.non_replicated_relations)?
+            .rewrite_compound_aggregates(context.dialect)
             .detect_problematic_self_joins()?
             .remove_numeric_field_references()?
             .order_limit_removal(context.base_schemas)
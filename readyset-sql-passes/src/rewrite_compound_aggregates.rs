@@ -0,0 +1,493 @@
+use dataflow_expression::Dialect;
+use nom_sql::analysis::visit_mut::{self, VisitorMut};
+use nom_sql::{
+    BinaryOperator, CaseWhenBranch, Expr, FieldDefinitionExpr, FunctionExpr, SelectStatement,
+    SqlType,
+};
+use readyset_data::dialect::SqlEngine;
+
+/// Aggregate functions which aren't maintained directly in the dataflow graph, but are instead
+/// computed from other aggregates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompoundAggregate {
+    /// Population variance
+    VarPop,
+    /// Population standard deviation
+    StddevPop,
+}
+
+impl CompoundAggregate {
+    fn from_name(name: &str, dialect: Dialect) -> Option<Self> {
+        match (name.to_lowercase().as_str(), dialect.engine()) {
+            ("var_pop", _) => Some(Self::VarPop),
+            ("stddev_pop", _) => Some(Self::StddevPop),
+            // In PostgreSQL, `variance` and `stddev` are aliases for the *sample* variance and
+            // standard deviation, which we don't support
+            ("variance", SqlEngine::MySQL) => Some(Self::VarPop),
+            ("std" | "stddev", SqlEngine::MySQL) => Some(Self::StddevPop),
+            _ => None,
+        }
+    }
+}
+
+pub trait RewriteCompoundAggregates: Sized {
+    /// Rewrite all aggregate functions that can be computed from other, incrementally
+    /// maintainable, aggregates into expressions over those aggregates, which are evaluated in a
+    /// projection after the aggregation. For example, the following query:
+    ///
+    /// ```sql
+    /// SELECT AVG(x) FROM t;
+    /// ```
+    ///
+    /// becomes:
+    ///
+    /// ```sql
+    /// SELECT CASE WHEN count(x) != 0 THEN sum(x) / count(x) END AS `avg(x)` FROM t;
+    /// ```
+    ///
+    /// The population variance (`VAR_POP`, and `VARIANCE` in MySQL) is computed from the sum of
+    /// squares as `(sum(x * x) * count(x) - sum(x) * sum(x)) / (count(x) * count(x))`, and the
+    /// population standard deviation (`STDDEV_POP`, and `STD` and `STDDEV` in MySQL) as the square
+    /// root of the population variance.
+    ///
+    /// Fields in the select list that are rewritten are aliased to their original name, so that
+    /// the name of the column in the result set is unchanged.
+    #[must_use]
+    fn rewrite_compound_aggregates(self, dialect: Dialect) -> Self;
+}
+
+fn count(expr: &Expr, distinct: bool) -> Expr {
+    Expr::Call(FunctionExpr::Count {
+        expr: Box::new(expr.clone()),
+        distinct,
+    })
+}
+
+fn sum(expr: Expr, distinct: bool) -> Expr {
+    Expr::Call(FunctionExpr::Sum {
+        expr: Box::new(expr),
+        distinct,
+    })
+}
+
+fn binop(lhs: Expr, op: BinaryOperator, rhs: Expr) -> Expr {
+    Expr::BinaryOp {
+        lhs: Box::new(lhs),
+        op,
+        rhs: Box::new(rhs),
+    }
+}
+
+/// Returns `sum(expr) / count(expr)`
+fn mean(expr: Expr, distinct: bool) -> Expr {
+    binop(
+        sum(expr.clone(), distinct),
+        BinaryOperator::Divide,
+        count(&expr, distinct),
+    )
+}
+
+/// Returns `CASE WHEN count(expr) != 0 THEN value END`, to make compound aggregates over only
+/// NULL values NULL rather than dividing by zero. `distinct` should match the aggregates in
+/// `value`, so that the count is shared with them.
+fn when_non_empty(expr: &Expr, distinct: bool, value: Expr) -> Expr {
+    Expr::CaseWhen {
+        branches: vec![CaseWhenBranch {
+            condition: binop(
+                count(expr, distinct),
+                BinaryOperator::NotEqual,
+                Expr::Literal(0.into()),
+            ),
+            body: value,
+        }],
+        else_expr: None,
+    }
+}
+
+/// Returns the population variance of `expr`, as:
+///
+/// ```sql
+/// CASE WHEN n > 0 THEN n / (count(expr) * count(expr)) ELSE 0 END
+/// ```
+///
+/// where `n` is `sum(expr * expr) * count(expr) - sum(expr) * sum(expr)`.
+///
+/// Only dividing once, at the end, keeps the computation exact for integer and decimal values
+/// (whose sums are decimals), rather than accumulating rounding errors from dividing each term by
+/// the count. For floating-point values, rounding can make `n` slightly negative when all the
+/// values are (close to) equal, which is clamped to 0.
+fn var_pop(expr: Expr) -> Expr {
+    let square = binop(expr.clone(), BinaryOperator::Multiply, expr.clone());
+    let numerator = binop(
+        binop(
+            sum(square, false),
+            BinaryOperator::Multiply,
+            count(&expr, false),
+        ),
+        BinaryOperator::Subtract,
+        binop(
+            sum(expr.clone(), false),
+            BinaryOperator::Multiply,
+            sum(expr.clone(), false),
+        ),
+    );
+    Expr::CaseWhen {
+        branches: vec![CaseWhenBranch {
+            condition: binop(
+                numerator.clone(),
+                BinaryOperator::Greater,
+                Expr::Literal(0.into()),
+            ),
+            body: binop(
+                numerator,
+                BinaryOperator::Divide,
+                binop(
+                    count(&expr, false),
+                    BinaryOperator::Multiply,
+                    count(&expr, false),
+                ),
+            ),
+        }],
+        else_expr: Some(Box::new(Expr::Literal(0.into()))),
+    }
+}
+
+struct RewriteCompoundAggregatesVisitor {
+    dialect: Dialect,
+}
+
+impl RewriteCompoundAggregatesVisitor {
+    fn rewrite(&self, expr: &Expr) -> Option<Expr> {
+        match expr {
+            Expr::Call(FunctionExpr::Avg { expr, distinct }) => Some(when_non_empty(
+                expr,
+                *distinct,
+                mean((**expr).clone(), *distinct),
+            )),
+            Expr::Call(FunctionExpr::Call { name, arguments }) if arguments.len() == 1 => {
+                let agg = CompoundAggregate::from_name(name, self.dialect)?;
+                let arg = &arguments[0];
+                let var_pop = var_pop(arg.clone());
+                Some(when_non_empty(
+                    arg,
+                    false,
+                    match agg {
+                        // MySQL always returns the variance as a DOUBLE, whereas PostgreSQL
+                        // returns a NUMERIC for integer and decimal values
+                        CompoundAggregate::VarPop if self.dialect.engine() == SqlEngine::MySQL => {
+                            Expr::Cast {
+                                expr: Box::new(var_pop),
+                                ty: SqlType::Double,
+                                postgres_style: false,
+                            }
+                        }
+                        CompoundAggregate::VarPop => var_pop,
+                        CompoundAggregate::StddevPop => Expr::Call(FunctionExpr::Call {
+                            name: "sqrt".into(),
+                            arguments: vec![var_pop],
+                        }),
+                    },
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<'ast> VisitorMut<'ast> for RewriteCompoundAggregatesVisitor {
+    type Error = !;
+
+    fn visit_select_statement(
+        &mut self,
+        select_statement: &'ast mut SelectStatement,
+    ) -> Result<(), Self::Error> {
+        for field in &mut select_statement.fields {
+            if let FieldDefinitionExpr::Expr { expr, alias } = field {
+                if alias.is_none() {
+                    let name = expr.to_string();
+                    self.visit_expr(expr)?;
+                    if name != expr.to_string() {
+                        *alias = Some(name.into());
+                    }
+                }
+            }
+        }
+
+        visit_mut::walk_select_statement(self, select_statement)
+    }
+
+    fn visit_expr(&mut self, expr: &'ast mut Expr) -> Result<(), Self::Error> {
+        if let Some(rewritten) = self.rewrite(expr) {
+            *expr = rewritten;
+            // The arguments to the rewritten aggregates can't themselves contain aggregates, so
+            // there's nothing left to rewrite
+            return Ok(());
+        }
+
+        visit_mut::walk_expr(self, expr)
+    }
+}
+
+impl RewriteCompoundAggregates for SelectStatement {
+    fn rewrite_compound_aggregates(mut self, dialect: Dialect) -> Self {
+        let Ok(()) = RewriteCompoundAggregatesVisitor { dialect }.visit_select_statement(&mut self);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nom_sql::{parse_expr, parse_select_statement, Dialect as ParserDialect};
+    use readyset_data::DfValue;
+
+    use super::*;
+    use crate::expr::const_eval;
+
+    fn parser_dialect(dialect: Dialect) -> ParserDialect {
+        match dialect.engine() {
+            SqlEngine::MySQL => ParserDialect::MySQL,
+            SqlEngine::PostgreSQL => ParserDialect::PostgreSQL,
+        }
+    }
+
+    fn rewrites_to(dialect: Dialect, query: &str, expected: &str) {
+        let query = parse_select_statement(parser_dialect(dialect), query).unwrap();
+        let expected = parse_select_statement(parser_dialect(dialect), expected).unwrap();
+        assert_eq!(query.rewrite_compound_aggregates(dialect), expected);
+    }
+
+    #[test]
+    fn avg() {
+        rewrites_to(
+            Dialect::DEFAULT_MYSQL,
+            "SELECT avg(DISTINCT t.y) AS a FROM t",
+            "SELECT CASE WHEN count(DISTINCT t.y) != 0 \
+             THEN sum(DISTINCT t.y) / count(DISTINCT t.y) END AS a FROM t",
+        );
+    }
+
+    #[test]
+    fn unaliased_fields_keep_their_name() {
+        let query = parse_select_statement(ParserDialect::MySQL, "SELECT avg(t.x) FROM t")
+            .unwrap()
+            .rewrite_compound_aggregates(Dialect::DEFAULT_MYSQL);
+        assert_eq!(
+            query.fields,
+            vec![FieldDefinitionExpr::Expr {
+                expr: parse_expr(
+                    ParserDialect::MySQL,
+                    "CASE WHEN count(t.x) != 0 THEN sum(t.x) / count(t.x) END"
+                )
+                .unwrap(),
+                alias: Some(
+                    parse_expr(ParserDialect::MySQL, "avg(t.x)")
+                        .unwrap()
+                        .to_string()
+                        .into()
+                ),
+            }]
+        );
+    }
+
+    #[test]
+    fn avg_in_having() {
+        rewrites_to(
+            Dialect::DEFAULT_MYSQL,
+            "SELECT t.y FROM t GROUP BY t.y HAVING avg(t.x) > 1",
+            "SELECT t.y FROM t GROUP BY t.y \
+             HAVING CASE WHEN count(t.x) != 0 THEN sum(t.x) / count(t.x) END > 1",
+        );
+    }
+
+    #[test]
+    fn variance_and_stddev() {
+        let n = "sum(t.x * t.x) * count(t.x) - sum(t.x) * sum(t.x)";
+        let var_pop =
+            format!("CASE WHEN {n} > 0 THEN ({n}) / (count(t.x) * count(t.x)) ELSE 0 END");
+        rewrites_to(
+            Dialect::DEFAULT_MYSQL,
+            "SELECT variance(t.x) AS v, std(t.x) AS s FROM t",
+            &format!(
+                "SELECT CASE WHEN count(t.x) != 0 THEN CAST({var_pop} AS DOUBLE) END AS v, \
+                 CASE WHEN count(t.x) != 0 THEN sqrt({var_pop}) END AS s FROM t"
+            ),
+        );
+        rewrites_to(
+            Dialect::DEFAULT_POSTGRESQL,
+            "SELECT var_pop(t.x) AS v, stddev_pop(t.x) AS s FROM t",
+            &format!(
+                "SELECT CASE WHEN count(t.x) != 0 THEN {var_pop} END AS v, \
+                 CASE WHEN count(t.x) != 0 THEN sqrt({var_pop}) END AS s FROM t"
+            ),
+        );
+    }
+
+    #[test]
+    fn postgres_sample_variance_is_unchanged() {
+        rewrites_to(
+            Dialect::DEFAULT_POSTGRESQL,
+            "SELECT variance(t.x) AS v FROM t",
+            "SELECT variance(t.x) AS v FROM t",
+        );
+    }
+
+    /// Evaluate `aggregate` (as rewritten by [`RewriteCompoundAggregates`]) over the given values
+    /// of the column `t.x`, by computing each of the aggregates it's rewritten to directly and
+    /// then evaluating the resulting expression
+    fn eval_aggregate(dialect: Dialect, aggregate: &str, values: &[DfValue]) -> DfValue {
+        struct EvalAggregates<'a> {
+            dialect: Dialect,
+            values: &'a [DfValue],
+        }
+
+        impl<'a> EvalAggregates<'a> {
+            /// Returns the non-NULL values of `arg` for each of the values of `t.x`
+            fn eval_arg(&self, arg: &Expr, distinct: bool) -> Vec<DfValue> {
+                let mut res = vec![];
+                for value in self.values {
+                    let mut arg = arg.clone();
+                    let Ok(()) = SubstituteColumn(value).visit_expr(&mut arg);
+                    let value = const_eval(&arg, self.dialect).unwrap();
+                    if !value.is_none() && !(distinct && res.contains(&value)) {
+                        res.push(value);
+                    }
+                }
+                res
+            }
+        }
+
+        impl<'ast, 'a> VisitorMut<'ast> for EvalAggregates<'a> {
+            type Error = !;
+
+            fn visit_expr(&mut self, expr: &'ast mut Expr) -> Result<(), Self::Error> {
+                let value = match expr {
+                    Expr::Call(FunctionExpr::Count { expr, distinct }) => {
+                        DfValue::from(self.eval_arg(expr, *distinct).len())
+                    }
+                    Expr::Call(FunctionExpr::Sum { expr, distinct }) => {
+                        let values = self.eval_arg(expr, *distinct);
+                        // Sums of floats are doubles, and sums of everything else are decimals,
+                        // as in the dataflow graph
+                        let zero = if values.iter().any(|v| v.is_real()) {
+                            DfValue::Double(0.0)
+                        } else {
+                            DfValue::Numeric(Default::default())
+                        };
+                        if values.is_empty() {
+                            DfValue::None
+                        } else {
+                            values.iter().fold(zero, |acc, v| (&acc + v).unwrap())
+                        }
+                    }
+                    _ => return visit_mut::walk_expr(self, expr),
+                };
+                *expr = Expr::Literal(value.try_into().unwrap());
+                Ok(())
+            }
+        }
+
+        struct SubstituteColumn<'a>(&'a DfValue);
+
+        impl<'ast, 'a> VisitorMut<'ast> for SubstituteColumn<'a> {
+            type Error = !;
+
+            fn visit_expr(&mut self, expr: &'ast mut Expr) -> Result<(), Self::Error> {
+                if matches!(expr, Expr::Column(_)) {
+                    *expr = Expr::Literal(self.0.clone().try_into().unwrap());
+                    return Ok(());
+                }
+                visit_mut::walk_expr(self, expr)
+            }
+        }
+
+        let query = parse_select_statement(
+            parser_dialect(dialect),
+            format!("SELECT {aggregate} AS a FROM t"),
+        )
+        .unwrap()
+        .rewrite_compound_aggregates(dialect);
+        let mut expr = match query.fields.into_iter().next().unwrap() {
+            FieldDefinitionExpr::Expr { expr, .. } => expr,
+            _ => panic!("Expected an expression field"),
+        };
+        let Ok(()) = EvalAggregates { dialect, values }.visit_expr(&mut expr);
+        const_eval(&expr, dialect).unwrap()
+    }
+
+    fn assert_aggregate_eq(dialect: Dialect, aggregate: &str, values: &[DfValue], expected: f64) {
+        let res = eval_aggregate(dialect, aggregate, values);
+        let res = f64::try_from(&res).unwrap_or_else(|_| panic!("{aggregate} returned {res:?}"));
+        assert!(
+            (res - expected).abs() < 1e-9,
+            "{aggregate} returned {res}, expected {expected}"
+        );
+    }
+
+    fn ints(values: &[i64]) -> Vec<DfValue> {
+        values.iter().copied().map(DfValue::from).collect()
+    }
+
+    #[test]
+    fn eval_integers() {
+        let mut values = ints(&[2, 4, 4, 4, 5, 5, 7, 9]);
+        values.push(DfValue::None);
+        for dialect in [Dialect::DEFAULT_MYSQL, Dialect::DEFAULT_POSTGRESQL] {
+            assert_aggregate_eq(dialect, "avg(t.x)", &values, 5.0);
+            assert_aggregate_eq(dialect, "avg(DISTINCT t.x)", &values, 5.4);
+            assert_aggregate_eq(dialect, "var_pop(t.x)", &values, 4.0);
+            assert_aggregate_eq(dialect, "stddev_pop(t.x)", &values, 2.0);
+        }
+        assert_aggregate_eq(Dialect::DEFAULT_MYSQL, "variance(t.x)", &values, 4.0);
+        assert_aggregate_eq(Dialect::DEFAULT_MYSQL, "std(t.x)", &values, 2.0);
+    }
+
+    #[test]
+    fn eval_non_integral_mean() {
+        let values = ints(&[1, 2, 2]);
+        assert_aggregate_eq(Dialect::DEFAULT_MYSQL, "avg(t.x)", &values, 5.0 / 3.0);
+        assert_aggregate_eq(Dialect::DEFAULT_MYSQL, "var_pop(t.x)", &values, 2.0 / 9.0);
+        assert_aggregate_eq(
+            Dialect::DEFAULT_POSTGRESQL,
+            "stddev_pop(t.x)",
+            &values,
+            (2.0_f64 / 9.0).sqrt(),
+        );
+    }
+
+    #[test]
+    fn eval_large_values() {
+        // Computing the mean before squaring it loses precision for values far from zero
+        let values = ints(&[1_000_000_004, 1_000_000_007, 1_000_000_013, 1_000_000_016]);
+        assert_aggregate_eq(Dialect::DEFAULT_MYSQL, "var_pop(t.x)", &values, 22.5);
+    }
+
+    #[test]
+    fn eval_doubles() {
+        let values = [1.3, 1.3, 1.3]
+            .into_iter()
+            .map(|v| DfValue::try_from(v).unwrap())
+            .collect::<Vec<_>>();
+        assert_aggregate_eq(Dialect::DEFAULT_MYSQL, "avg(t.x)", &values, 1.3);
+        // Rounding errors mustn't make the variance negative, which would make the standard
+        // deviation NULL
+        assert_aggregate_eq(Dialect::DEFAULT_MYSQL, "var_pop(t.x)", &values, 0.0);
+        assert_aggregate_eq(Dialect::DEFAULT_MYSQL, "stddev_pop(t.x)", &values, 0.0);
+
+        let values = [1.5, 2.5, 3.5]
+            .into_iter()
+            .map(|v| DfValue::try_from(v).unwrap())
+            .collect::<Vec<_>>();
+        assert_aggregate_eq(Dialect::DEFAULT_MYSQL, "var_pop(t.x)", &values, 2.0 / 3.0);
+    }
+
+    #[test]
+    fn eval_only_nulls() {
+        let values = [DfValue::None, DfValue::None];
+        for aggregate in ["avg(t.x)", "var_pop(t.x)", "stddev_pop(t.x)"] {
+            assert_eq!(
+                eval_aggregate(Dialect::DEFAULT_MYSQL, aggregate, &values),
+                DfValue::None
+            );
+        }
+    }
+}
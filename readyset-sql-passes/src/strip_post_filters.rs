@@ -5,38 +5,56 @@ use nom_sql::{
 pub trait StripPostFilters {
     /// Remove all filters from the given query that cannot be done as nodes in the query graph, and
     /// require a post-lookup filter. Currently, this is LIKE and ILIKE against a placeholder.
+    ///
+    /// In SELECT statements, LIKE against a placeholder is instead rewritten to a `>=` comparison
+    /// against that placeholder, so that the column becomes a range lookup key. Lookups into the
+    /// query's reader then look up the range of values starting with the literal prefix of the
+    /// pattern, and filter the results with the full pattern.
     #[must_use]
     fn strip_post_filters(self) -> Self;
 }
 
+fn strip_post_filters(conds: Option<Expr>, like_as_range_key: bool) -> Option<Expr> {
+    conds.and_then(|conds| match conds {
+        Expr::BinaryOp {
+            op: BinaryOperator::Like,
+            lhs: lhs @ box Expr::Column(_),
+            rhs: rhs @ box Expr::Literal(Literal::Placeholder(_)),
+        } if like_as_range_key => Some(Expr::BinaryOp {
+            op: BinaryOperator::GreaterOrEqual,
+            lhs,
+            rhs,
+        }),
+        Expr::BinaryOp {
+            op: BinaryOperator::ILike | BinaryOperator::Like,
+            lhs: box Expr::Column(_),
+            rhs: box Expr::Literal(Literal::Placeholder(_)),
+        } => None,
+        Expr::BinaryOp { op, lhs, rhs } => match (
+            strip_post_filters(Some(*lhs), like_as_range_key),
+            strip_post_filters(Some(*rhs), like_as_range_key),
+        ) {
+            (None, None) => None,
+            (Some(cond), None) | (None, Some(cond)) => Some(cond),
+            (Some(left), Some(right)) => Some(Expr::BinaryOp {
+                op,
+                lhs: Box::new(left),
+                rhs: Box::new(right),
+            }),
+        },
+        _ => Some(conds),
+    })
+}
+
 impl StripPostFilters for Option<Expr> {
     fn strip_post_filters(self) -> Self {
-        self.and_then(|conds| match conds {
-            Expr::BinaryOp {
-                op: BinaryOperator::ILike | BinaryOperator::Like,
-                lhs: box Expr::Column(_),
-                rhs: box Expr::Literal(Literal::Placeholder(_)),
-            } => None,
-            Expr::BinaryOp { op, lhs, rhs } => match (
-                Some(*lhs).strip_post_filters(),
-                Some(*rhs).strip_post_filters(),
-            ) {
-                (None, None) => None,
-                (Some(cond), None) | (None, Some(cond)) => Some(cond),
-                (Some(left), Some(right)) => Some(Expr::BinaryOp {
-                    op,
-                    lhs: Box::new(left),
-                    rhs: Box::new(right),
-                }),
-            },
-            _ => Some(conds),
-        })
+        strip_post_filters(self, false)
     }
 }
 
 impl StripPostFilters for SelectStatement {
     fn strip_post_filters(mut self) -> Self {
-        self.where_clause = self.where_clause.strip_post_filters();
+        self.where_clause = strip_post_filters(self.where_clause, true);
         self
     }
 }
@@ -100,4 +118,20 @@ mod tests {
         let result = query.strip_post_filters();
         assert_eq!(result, expected, "result = {}", result);
     }
+
+    #[test]
+    fn like_becomes_range_key() {
+        let query = parse_query(
+            Dialect::MySQL,
+            "SELECT id FROM posts WHERE title LIKE ? AND id = ?;",
+        )
+        .unwrap();
+        let expected = parse_query(
+            Dialect::MySQL,
+            "SELECT id FROM posts WHERE title >= ? AND id = ?;",
+        )
+        .unwrap();
+        let result = query.strip_post_filters();
+        assert_eq!(result, expected, "result = {}", result);
+    }
 }
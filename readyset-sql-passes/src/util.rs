@@ -5,7 +5,8 @@ use itertools::Either;
 use nom_sql::analysis::is_aggregate;
 use nom_sql::{
     BinaryOperator, Column, CommonTableExpr, Expr, FieldDefinitionExpr, FunctionExpr, InValue,
-    JoinClause, JoinRightSide, Relation, SelectStatement, SqlIdentifier, TableExpr, TableExprInner,
+    JoinClause, JoinRightSide, Literal, Relation, SelectStatement, SqlIdentifier, TableExpr,
+    TableExprInner,
 };
 
 pub(crate) fn join_clause_tables(join: &JoinClause) -> impl Iterator<Item = &TableExpr> {
@@ -159,11 +160,60 @@ pub fn is_predicate(op: &BinaryOperator) -> bool {
             | GreaterOrEqual
             | Less
             | LessOrEqual
+            | NullSafeEqual
             | Is
             | IsNot
     )
 }
 
+/// Returns true if the given expression contains any placeholders, not counting any placeholders
+/// inside of subqueries
+pub fn contains_placeholders(expr: &Expr) -> bool {
+    iter::once(expr)
+        .chain(expr.recursive_subexpressions())
+        .any(|expr| matches!(expr, Expr::Literal(Literal::Placeholder(_))))
+}
+
+/// Returns true if the given condition from the WHERE clause of a query has placeholders in
+/// positions that can't be turned into lookup keys for the query's reader - anywhere other than
+/// directly compared against a column with an operator that a reader index can look up, as in
+/// `x = ?` or `x > ?`. Such conditions (for example, `round(price, ?) > ?` or `status != ?`) are
+/// instead evaluated as residual predicates against the results of each lookup, with the values for
+/// their placeholders substituted in.
+///
+/// `LIKE` and `ILIKE` comparisons against a placeholder are not considered post-lookup predicates,
+/// since they're handled separately (see [`StripPostFilters`]).
+///
+/// Conjunctions are never considered post-lookup predicates themselves, since each side of the
+/// conjunction can be classified separately.
+///
+/// [`StripPostFilters`]: crate::StripPostFilters
+pub fn is_post_lookup_predicate(expr: &Expr) -> bool {
+    match expr {
+        Expr::BinaryOp {
+            op: BinaryOperator::And,
+            ..
+        } => false,
+        Expr::BinaryOp {
+            lhs: box Expr::Column(_),
+            op,
+            rhs: box Expr::Literal(Literal::Placeholder(_)),
+        } => !matches!(
+            op,
+            BinaryOperator::Equal
+                | BinaryOperator::NullSafeEqual
+                | BinaryOperator::Is
+                | BinaryOperator::Greater
+                | BinaryOperator::GreaterOrEqual
+                | BinaryOperator::Less
+                | BinaryOperator::LessOrEqual
+                | BinaryOperator::Like
+                | BinaryOperator::ILike
+        ),
+        _ => contains_placeholders(expr),
+    }
+}
+
 /// Returns true if the given binary operator is a (boolean-valued) logical operator
 ///
 /// TODO(grfn): Replace this with actual typechecking at some point
@@ -232,4 +282,44 @@ mod tests {
             assert!(is_correlated(&query));
         }
     }
+
+    mod is_post_lookup_predicate {
+        use nom_sql::{parse_expr, Dialect};
+
+        use super::*;
+
+        fn is_post_lookup(expr: &str) -> bool {
+            is_post_lookup_predicate(&parse_expr(Dialect::MySQL, expr).unwrap())
+        }
+
+        #[test]
+        fn key_comparisons() {
+            assert!(!is_post_lookup("t.x = ?"));
+            assert!(!is_post_lookup("t.x > ?"));
+            assert!(!is_post_lookup("t.x LIKE ?"));
+            assert!(!is_post_lookup("t.x = ? AND round(t.y, ?) > ?"));
+        }
+
+        #[test]
+        fn no_placeholders() {
+            assert!(!is_post_lookup("round(t.y, 2) > 3"));
+            assert!(!is_post_lookup("t.x IN (SELECT u.x FROM u WHERE u.y = ?)"));
+        }
+
+        #[test]
+        fn placeholders_in_expressions() {
+            assert!(is_post_lookup("round(t.y, ?) > ?"));
+            assert!(is_post_lookup("t.y * ? > 10"));
+            assert!(is_post_lookup("? = t.x"));
+            assert!(is_post_lookup("t.x = ? OR t.y = ?"));
+        }
+
+        #[test]
+        fn non_key_comparisons() {
+            assert!(is_post_lookup("t.status != ?"));
+            assert!(is_post_lookup("t.x NOT LIKE ?"));
+            assert!(is_post_lookup("t.x NOT ILIKE ?"));
+            assert!(is_post_lookup("t.x IS NOT ?"));
+        }
+    }
 }
@@ -0,0 +1,169 @@
+#![warn(clippy::panic)]
+//! Tool to export the definitions of all the caches in a deployment, and to recreate those caches
+//! on another (eg freshly deployed) deployment.
+
+use std::path::PathBuf;
+
+use anyhow::bail;
+use clap::{ArgEnum, Parser, Subcommand};
+use dataflow_expression::Dialect;
+use nom_sql::{AlterReadysetStatement, CreateCacheStatement, Relation};
+use readyset_client::consensus::AuthorityType;
+use readyset_client::recipe::changelist::{Change, ChangeList};
+use readyset_client::ReadySetHandle;
+use serde::{Deserialize, Serialize};
+
+#[derive(Parser)]
+#[clap(name = "cache_dump")]
+struct CacheDump {
+    #[clap(short, long, env("AUTHORITY_ADDRESS"), default_value("127.0.0.1:2181"))]
+    authority_address: String,
+
+    #[clap(long, env("AUTHORITY"), default_value("zookeeper"), possible_values = &["consul", "zookeeper"])]
+    authority: AuthorityType,
+
+    #[clap(short, long, env("DEPLOYMENT"), forbid_empty_values = true)]
+    deployment: String,
+
+    /// The format that cache definitions are exported in, or imported from.
+    #[clap(long, arg_enum, default_value = "sql")]
+    format: Format,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Clone, Copy, ArgEnum)]
+enum Format {
+    /// A script of `CREATE CACHE` statements, one per line, followed by an `ALTER READYSET SET MIR
+    /// PLAN` statement for each cache whose MIR plan has been overridden
+    Sql,
+    /// A JSON object containing an array of `CREATE CACHE` statements, and the name and MIR plan
+    /// of each cache whose MIR plan has been overridden
+    Json,
+}
+
+/// The cache definitions written in the JSON format
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum JsonDefinitions {
+    WithMirPlans {
+        caches: Vec<CreateCacheStatement>,
+        mir_plans: Vec<(Relation, String)>,
+    },
+    /// Files exported before MIR plan overrides were included only contain the caches
+    Caches(Vec<CreateCacheStatement>),
+}
+
+#[derive(Clone, Copy, ArgEnum)]
+enum DatabaseType {
+    Mysql,
+    Postgresql,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the definitions of all the caches in the deployment to stdout
+    Export,
+    /// Create all the caches defined in the given file, as previously written by `export`
+    Import {
+        /// The type of the upstream database of the deployment being imported into, used to
+        /// determine the expression evaluation semantics of the caches.
+        #[clap(long, arg_enum, default_value = "mysql")]
+        database_type: DatabaseType,
+
+        path: PathBuf,
+    },
+}
+
+impl CacheDump {
+    pub async fn run(self) -> anyhow::Result<()> {
+        let authority = self
+            .authority
+            .to_authority(&self.authority_address, &self.deployment)
+            .await?;
+
+        let mut handle: ReadySetHandle = ReadySetHandle::new(authority).await;
+        handle.ready().await.unwrap();
+
+        match self.command {
+            Command::Export => {
+                let caches = handle.cache_definitions().await?;
+                let mir_plans = handle.mir_plan_overrides().await?;
+                match self.format {
+                    Format::Sql => {
+                        for stmt in caches {
+                            println!("{stmt};");
+                        }
+                        for (cache, plan) in mir_plans {
+                            println!("{};", AlterReadysetStatement::SetMirPlan { cache, plan });
+                        }
+                    }
+                    Format::Json => println!(
+                        "{}",
+                        serde_json::to_string_pretty(&JsonDefinitions::WithMirPlans {
+                            caches,
+                            mir_plans
+                        })?
+                    ),
+                }
+            }
+            Command::Import {
+                database_type,
+                path,
+            } => {
+                let dialect = match database_type {
+                    DatabaseType::Mysql => Dialect::DEFAULT_MYSQL,
+                    DatabaseType::Postgresql => Dialect::DEFAULT_POSTGRESQL,
+                };
+                let contents = std::fs::read_to_string(path)?;
+                let changes = match self.format {
+                    Format::Sql => ChangeList::from_str(contents, dialect)?,
+                    Format::Json => {
+                        let (caches, mir_plans) = match serde_json::from_str(&contents)? {
+                            JsonDefinitions::WithMirPlans { caches, mir_plans } => {
+                                (caches, mir_plans)
+                            }
+                            JsonDefinitions::Caches(caches) => (caches, vec![]),
+                        };
+                        ChangeList::from_changes(
+                            caches
+                                .into_iter()
+                                .map(Change::CreateCache)
+                                .chain(
+                                    mir_plans
+                                        .into_iter()
+                                        .map(|(name, plan)| Change::SetMirPlan { name, plan }),
+                                )
+                                .collect::<Vec<_>>(),
+                            dialect,
+                        )
+                    }
+                };
+                if changes.changes().any(|change| {
+                    !matches!(change, Change::CreateCache(_) | Change::SetMirPlan { .. })
+                }) {
+                    bail!(
+                        "Cache definitions may only contain CREATE CACHE and ALTER READYSET SET \
+                         MIR PLAN statements"
+                    );
+                }
+
+                let num_caches = changes
+                    .changes()
+                    .filter(|change| matches!(change, Change::CreateCache(_)))
+                    .count();
+                handle.extend_recipe(changes).await?;
+                println!("Created {num_caches} caches");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cache_dump = CacheDump::parse();
+    cache_dump.run().await
+}
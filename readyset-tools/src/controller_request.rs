@@ -63,7 +63,7 @@ impl ControllerRequest {
         let authority = self
             .authority
             .to_authority(&self.authority_address, &self.deployment)
-            .await;
+            .await?;
 
         let mut handle: ReadySetHandle = ReadySetHandle::new(authority).await;
         handle.ready().await.unwrap();
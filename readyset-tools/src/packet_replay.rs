@@ -0,0 +1,114 @@
+//! Replays a domain packet log, as recorded by a readyset-server run with
+//! `--domain-packet-log-dir`, to reconstruct the state of the domain's materializations step by
+//! step.
+//!
+//! # Example
+//!
+//! Replay the first 1500 records in a log, then print the contents of the materialization of the
+//! node with local index 2 in the domain:
+//!
+//! ```bash
+//! cargo run --bin packet_replay -- domain-3.0.0-1665000000000.packets --until 1500 --dump-node 2
+//! ```
+//!
+//! Running the same command with different values of `--until` can be used to bisect the record at
+//! which a materialization first diverges from what it should contain.
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use clap::Parser;
+use readyset_client::internal::LocalNodeIndex;
+use readyset_server::{PacketLogRecord, PacketLogReplayer};
+
+#[derive(Parser)]
+#[clap(name = "packet_replay")]
+struct PacketReplay {
+    /// The packet log to replay
+    path: PathBuf,
+
+    /// Stop after replaying this many records, rather than at the end of the log
+    #[clap(long)]
+    until: Option<usize>,
+
+    /// Stop at the first record which the domain returns an error for
+    #[clap(long)]
+    stop_on_error: bool,
+
+    /// Print every record as it's replayed
+    #[clap(short, long)]
+    verbose: bool,
+
+    /// Print all the rows materialized for the node with this local index once replay stops. Can
+    /// be given multiple times.
+    #[clap(long)]
+    dump_node: Vec<u32>,
+}
+
+/// A short description of a record, omitting its (potentially very large) contents
+fn describe(record: &PacketLogRecord) -> String {
+    match record {
+        PacketLogRecord::Start(builder) => format!("start domain {}", builder.address()),
+        PacketLogRecord::Packet(packet) => format!("{:?}", packet),
+        PacketLogRecord::Request(req) => {
+            let req = format!("{:?}", req);
+            let name_len = req
+                .find(|c: char| !c.is_alphanumeric())
+                .unwrap_or(req.len());
+            format!("DomainRequest::{}", &req[..name_len])
+        }
+        PacketLogRecord::Timeout => "timeout".to_owned(),
+    }
+}
+
+impl PacketReplay {
+    fn run(self) -> anyhow::Result<()> {
+        let mut replayer = PacketLogReplayer::open(&self.path)?;
+        let domain = replayer.domain();
+        println!(
+            "Replaying domain {}.{}.{} from {}",
+            domain.index().index(),
+            domain.shard(),
+            domain.replica(),
+            self.path.display()
+        );
+
+        while self.until.map_or(true, |until| replayer.steps() < until) {
+            let (record, res) = match replayer.step()? {
+                Some(step) => step,
+                None => break,
+            };
+            if self.verbose || res.is_err() {
+                println!("{:>8}: {}", replayer.steps(), describe(&record));
+            }
+            if let Err(error) = res {
+                println!("          error: {error}");
+                if self.stop_on_error {
+                    break;
+                }
+            }
+        }
+
+        let domain = replayer.domain();
+        println!("Replayed {} records", replayer.steps());
+        for (node, rows) in domain.state_row_counts() {
+            println!("  node {node}: {rows} rows");
+        }
+
+        for node in self.dump_node {
+            let rows = domain
+                .materialized_rows(LocalNodeIndex::make(node))
+                .ok_or_else(|| anyhow!("Node l{node} isn't materialized in this domain"))?;
+            println!("Node l{node}:");
+            for row in rows {
+                println!("  {row:?}");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> anyhow::Result<()> {
+    PacketReplay::parse().run()
+}
@@ -0,0 +1,153 @@
+//! Utilities for controlling which CPUs threads run on, and measuring how much CPU time they use
+
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use core_affinity::CoreId;
+
+/// Parse a list of CPUs in the format used by `taskset` and `/sys/devices/system/cpu/online`: a
+/// comma-separated list of either individual CPU ids or inclusive ranges of CPU ids, eg
+/// `"0-3,8,10-11"`.
+pub fn parse_cpu_list(s: &str) -> Result<Vec<usize>, String> {
+    let mut cpus = vec![];
+    for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let parse = |n: &str| {
+            n.trim()
+                .parse::<usize>()
+                .map_err(|e| format!("Invalid CPU id {:?}: {}", n, e))
+        };
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (parse(start)?, parse(end)?);
+                if start > end {
+                    return Err(format!("Invalid CPU range {:?}", part));
+                }
+                cpus.extend(start..=end);
+            }
+            None => cpus.push(parse(part)?),
+        }
+    }
+    Ok(cpus)
+}
+
+/// A list of CPU ids, parsed from a string with [`parse_cpu_list`]. Useful as the type of a
+/// command-line option.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CpuList(pub Vec<usize>);
+
+impl FromStr for CpuList {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_cpu_list(s).map(Self)
+    }
+}
+
+/// Pin the current thread to run only on the CPU with the given id.
+///
+/// Returns `false` if the CPU affinity of the thread could not be set, either because the platform
+/// does not support it or because the CPU does not exist.
+pub fn pin_current_thread(cpu: usize) -> bool {
+    core_affinity::set_for_current(CoreId { id: cpu });
+    // `set_for_current` doesn't report failure, so check that the affinity was actually applied
+    current_thread_pinned_to(cpu)
+}
+
+#[cfg(target_os = "linux")]
+fn current_thread_pinned_to(cpu: usize) -> bool {
+    // SAFETY: `cpu_set_t` is a plain bitmask, for which all zeroes is a valid (empty) value, and
+    // `sched_getaffinity` writes at most `size_of::<cpu_set_t>()` bytes into it.
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+            return false;
+        }
+        libc::CPU_ISSET(cpu, &set) && libc::CPU_COUNT(&set) == 1
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn current_thread_pinned_to(_cpu: usize) -> bool {
+    false
+}
+
+/// Returns the total CPU time consumed by the current thread so far, or `None` if that can't be
+/// measured on this platform.
+#[cfg(unix)]
+pub fn thread_cpu_time() -> Option<Duration> {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `ts` is a valid, writable timespec
+    if unsafe { libc::clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID, &mut ts) } != 0 {
+        return None;
+    }
+    Some(Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32))
+}
+
+/// Returns the total CPU time consumed by the current thread so far, or `None` if that can't be
+/// measured on this platform.
+#[cfg(not(unix))]
+pub fn thread_cpu_time() -> Option<Duration> {
+    None
+}
+
+/// Hands out CPUs from a fixed list in round-robin order, for spreading a set of threads over a
+/// set of CPUs. Cloning a [`CpuAssigner`] yields a handle to the same sequence of CPUs.
+#[derive(Debug, Clone, Default)]
+pub struct CpuAssigner {
+    cpus: Arc<[usize]>,
+    next: Arc<AtomicUsize>,
+}
+
+impl CpuAssigner {
+    /// Construct a new [`CpuAssigner`] handing out the given CPUs
+    pub fn new(cpus: Vec<usize>) -> Self {
+        Self {
+            cpus: cpus.into(),
+            next: Default::default(),
+        }
+    }
+
+    /// Returns true if this [`CpuAssigner`] has no CPUs to hand out
+    pub fn is_empty(&self) -> bool {
+        self.cpus.is_empty()
+    }
+
+    /// Returns the next CPU to run a thread on, or `None` if this [`CpuAssigner`] has no CPUs
+    pub fn next_cpu(&self) -> Option<usize> {
+        if self.cpus.is_empty() {
+            return None;
+        }
+        let idx = self.next.fetch_add(1, Ordering::Relaxed) % self.cpus.len();
+        Some(self.cpus[idx])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_cpu_lists() {
+        assert_eq!(parse_cpu_list("").unwrap(), Vec::<usize>::new());
+        assert_eq!(parse_cpu_list("3").unwrap(), vec![3]);
+        assert_eq!(parse_cpu_list("0-3,8").unwrap(), vec![0, 1, 2, 3, 8]);
+        assert_eq!(parse_cpu_list(" 4 , 6-7 ").unwrap(), vec![4, 6, 7]);
+        parse_cpu_list("3-1").unwrap_err();
+        parse_cpu_list("a").unwrap_err();
+    }
+
+    #[test]
+    fn assigns_cpus_round_robin() {
+        let assigner = CpuAssigner::new(vec![2, 5]);
+        let clone = assigner.clone();
+        assert_eq!(assigner.next_cpu(), Some(2));
+        assert_eq!(clone.next_cpu(), Some(5));
+        assert_eq!(assigner.next_cpu(), Some(2));
+        assert_eq!(CpuAssigner::default().next_cpu(), None);
+    }
+}
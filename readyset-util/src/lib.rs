@@ -8,12 +8,14 @@ use std::collections::{BTreeMap, HashMap};
 use std::hash::Hash;
 
 pub mod arbitrary;
+pub mod cpu;
 pub mod display;
 pub mod futures;
 pub mod hash;
 pub mod intervals;
 pub mod math;
 pub mod nonmaxusize;
+pub mod process;
 pub mod properties;
 pub mod redacted;
 
@@ -0,0 +1,8 @@
+//! Utilities for querying properties of the current process
+
+/// Returns the effective user id of the current process
+#[cfg(unix)]
+pub fn current_uid() -> u32 {
+    // SAFETY: `geteuid` has no preconditions and always succeeds
+    unsafe { libc::geteuid() }
+}
@@ -8,8 +8,8 @@ use std::collections::HashMap;
 use std::io;
 use std::marker::Send;
 use std::net::{IpAddr, SocketAddr};
+use std::path::PathBuf;
 use std::str::FromStr;
-use std::sync::atomic::AtomicUsize;
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
@@ -18,7 +18,7 @@ use async_trait::async_trait;
 use clap::{ArgGroup, Parser};
 use database_utils::DatabaseType;
 use failpoint_macros::set_failpoint;
-use futures_util::future::FutureExt;
+use futures_util::future::{FutureExt, OptionFuture};
 use futures_util::stream::StreamExt;
 use health_reporter::{HealthReporter as AdapterHealthReporter, State as AdapterState};
 use maplit::hashmap;
@@ -26,16 +26,26 @@ use metrics_exporter_prometheus::PrometheusBuilder;
 use nom_sql::Relation;
 use readyset_adapter::backend::noria_connector::{NoriaConnector, ReadBehavior};
 use readyset_adapter::backend::MigrationMode;
+use readyset_adapter::connections::ConnectionRegistry;
 use readyset_adapter::fallback_cache::{
     DiskModeledCache, EvictionModeledCache, FallbackCache, SimpleFallbackCache,
 };
+use readyset_adapter::fallback_limiter::{FallbackLimiter, FallbackLimits};
+use readyset_adapter::flight::ArrowFlightServer;
 use readyset_adapter::http_router::NoriaAdapterHttpRouter;
+use readyset_adapter::index_advisor::IndexAdvisor;
+use readyset_adapter::micro_cache::MicroCache;
 use readyset_adapter::migration_handler::MigrationHandler;
+use readyset_adapter::namespace_limiter::NamespaceLimiter;
 use readyset_adapter::proxied_queries_reporter::ProxiedQueriesReporter;
 use readyset_adapter::query_status_cache::{MigrationStyle, QueryStatusCache};
+use readyset_adapter::replication_lag::ReplicationLagMonitor;
+use readyset_adapter::slow_read_log::SlowReadLog;
 use readyset_adapter::views_synchronizer::ViewsSynchronizer;
 use readyset_adapter::{Backend, BackendBuilder, QueryHandler, UpstreamDatabase};
-use readyset_client::consensus::{AuthorityControl, AuthorityType, ConsulAuthority};
+use readyset_client::consensus::{
+    Authority, AuthorityControl, AuthorityType, AuthorityWorkerHeartbeatResponse,
+};
 #[cfg(feature = "failure_injection")]
 use readyset_client::failpoints;
 use readyset_client::metrics::recorded;
@@ -86,6 +96,10 @@ pub trait ConnectionHandler {
 
     /// Return an immediate error to a newly-established connection, then immediately disconnect
     async fn immediate_error(self, stream: net::TcpStream, error_message: String);
+
+    /// Tell a newly-established connection that the adapter has reached its limit on the number of
+    /// open connections, then immediately disconnect
+    async fn too_many_connections(self, stream: net::TcpStream);
 }
 
 /// How to behave when receiving unsupported `SET` statements.
@@ -162,13 +176,13 @@ pub struct Options {
     #[clap(long, env = "DATABASE_TYPE", possible_values=&["mysql", "postgresql"])]
     pub database_type: DatabaseType,
 
-    /// The authority to use. Possible values: zookeeper, consul, standalone.
+    /// The authority to use. Possible values: zookeeper, consul, etcd, standalone.
     #[clap(
         long,
         env = "AUTHORITY",
         default_value_if("standalone", None, Some("standalone")),
         default_value = "consul",
-        possible_values = &["consul", "zookeeper", "standalone"]
+        possible_values = &["consul", "zookeeper", "etcd", "standalone"]
     )]
     authority: AuthorityType,
 
@@ -180,7 +194,8 @@ pub struct Options {
         env = "AUTHORITY_ADDRESS",
         default_value_if("authority", Some("standalone"), Some(".")),
         default_value_if("authority", Some("consul"), Some("127.0.0.1:8500")),
-        default_value_if("authority", Some("zookeeper"), Some("127.0.0.1:2181"))
+        default_value_if("authority", Some("zookeeper"), Some("127.0.0.1:2181")),
+        default_value_if("authority", Some("etcd"), Some("127.0.0.1:2379"))
     )]
     authority_address: String,
 
@@ -188,6 +203,16 @@ pub struct Options {
     #[clap(long)]
     log_slow: bool,
 
+    /// Write every read from a cache which takes longer than --slow-read-threshold-ms to this
+    /// file, as a line of JSON recording the cache, the keys read, how long the read spent waiting
+    /// for replays and falling back to the upstream database, and the number of rows read.
+    #[clap(long, env = "SLOW_READ_LOG")]
+    slow_read_log: Option<PathBuf>,
+
+    /// The minimum duration, in milliseconds, of reads written to the --slow-read-log.
+    #[clap(long, env = "SLOW_READ_THRESHOLD_MS", default_value = "100")]
+    slow_read_threshold_ms: u64,
+
     /// Don't require authentication for any client connections
     #[clap(long, env = "ALLOW_UNAUTHENTICATED_CONNECTIONS")]
     allow_unauthenticated_connections: bool,
@@ -231,6 +256,19 @@ pub struct Options {
     )]
     metrics_address: SocketAddr,
 
+    /// IP:PORT to serve the contents of caches on over Apache Arrow Flight, for analytical
+    /// consumers such as BI tools or dataframe libraries. If not set, no Arrow Flight server is
+    /// started.
+    ///
+    /// Clients must authenticate with the same username and password as database connections. If
+    /// --allow-unauthenticated-connections is passed, this must be a loopback address.
+    #[clap(long, env = "ARROW_FLIGHT_ADDRESS", parse(try_from_str))]
+    arrow_flight_address: Option<SocketAddr>,
+
+    /// The maximum number of rows in each record batch returned by the Arrow Flight server
+    #[clap(long, env = "ARROW_FLIGHT_BATCH_SIZE", default_value = "8192")]
+    arrow_flight_batch_size: usize,
+
     /// Allow database connections authenticated as this user. Ignored if
     /// --allow-unauthenticated-connections is passed
     #[clap(long, env = "ALLOWED_USERNAME", short = 'u')]
@@ -241,6 +279,17 @@ pub struct Options {
     #[clap(long, env = "ALLOWED_PASSWORD", short = 'p')]
     password: Option<RedactedString>,
 
+    /// Also allow database connections authenticated as this user, who is the only user allowed to
+    /// run administrative statements (such as `SET GLOBAL readyset.*`) which change the behavior
+    /// of the whole deployment. Ignored if --allow-unauthenticated-connections is passed, in
+    /// which case any connection can run administrative statements.
+    #[clap(long, env = "ADMIN_USERNAME", requires = "admin-password")]
+    admin_username: Option<String>,
+
+    /// Password to authenticate database connections as the --admin-username user with
+    #[clap(long, env = "ADMIN_PASSWORD", requires = "admin-username")]
+    admin_password: Option<RedactedString>,
+
     /// Enable recording and exposing Prometheus metrics
     #[clap(long, env = "PROMETHEUS_METRICS")]
     prometheus_metrics: bool,
@@ -330,10 +379,76 @@ pub struct Options {
     )]
     fallback_recovery_seconds: u64,
 
+    /// The maximum number of reads that can be proxied to the upstream database at once, across
+    /// all connections, when they can't be served by ReadySet. Reads over this limit wait for
+    /// another read to finish. 0 means no limit.
+    #[clap(long, env = "MAX_CONCURRENT_FALLBACK_QUERIES")]
+    max_concurrent_fallback_queries: Option<usize>,
+
+    /// The maximum number of reads per second that can be proxied to the upstream database,
+    /// across all connections, when they can't be served by ReadySet. Reads over this limit wait
+    /// until they can be sent without exceeding it. 0 means no limit.
+    #[clap(long, env = "MAX_FALLBACK_QUERIES_PER_SECOND")]
+    max_fallback_queries_per_second: Option<u32>,
+
+    /// If set, stop proxying reads to the upstream database (returning errors instead) once this
+    /// many of them in a row have failed to reach it, until
+    /// --fallback-circuit-breaker-cooldown-seconds have passed.
+    #[clap(long, env = "FALLBACK_CIRCUIT_BREAKER_FAILURES")]
+    fallback_circuit_breaker_failures: Option<u32>,
+
+    /// How long to wait, in seconds, after the upstream database has failed
+    /// --fallback-circuit-breaker-failures reads in a row, before proxying reads to it again.
+    #[clap(
+        long,
+        env = "FALLBACK_CIRCUIT_BREAKER_COOLDOWN_SECONDS",
+        default_value = "10"
+    )]
+    fallback_circuit_breaker_cooldown_seconds: u64,
+
+    /// The maximum number of client connections that can be open to the adapter at once. New
+    /// connections over this limit are refused with a "Too many connections" error.
+    #[clap(long, env = "MAX_CONNECTIONS")]
+    max_connections: Option<usize>,
+
+    /// Close client connections once they've been idle (not executing a statement) for this many
+    /// seconds. 0 disables the timeout.
+    #[clap(long, env = "CONNECTION_IDLE_TIMEOUT_SECONDS", default_value = "0")]
+    connection_idle_timeout_seconds: u64,
+
     /// Whether to use non-blocking or blocking reads against the cache.
+    ///
+    /// The read behavior can be changed for a single session with
+    /// `SET @readyset_read_behavior = '<blocking | non_blocking | stale_if_available>'`. Caches
+    /// created with `MAX STALENESS` only return stale results to sessions which use
+    /// `stale_if_available`.
     #[clap(long, env = "NON_BLOCKING_READS")]
     non_blocking_reads: bool,
 
+    /// If set, automatically add an index to the fully materialized state feeding a cache once
+    /// reads from that cache have had to be filtered on the same set of columns after the lookup
+    /// into the cache this many times.
+    #[clap(long, env = "ADAPTIVE_INDEX_THRESHOLD")]
+    adaptive_index_threshold: Option<u64>,
+
+    /// If set, cache the results of point reads from ReadySet within the adapter for this many
+    /// milliseconds, so that extremely hot, identical reads don't need to go to ReadySet at all.
+    /// Must be less than 1000. Cached results are invalidated by writes through this adapter and,
+    /// with embedded readers, by updates to the reader they were read from. The wire encoding of
+    /// cached results is cached along with them, so repeated reads aren't re-encoded either.
+    #[clap(long, env = "MICRO_CACHE_TTL_MS")]
+    micro_cache_ttl_ms: Option<u64>,
+
+    /// The maximum number of results to keep in the micro cache enabled by --micro-cache-ttl-ms.
+    /// Once it's full, the least recently used results are evicted first.
+    #[clap(long, env = "MICRO_CACHE_MAX_ENTRIES", default_value = "10000")]
+    micro_cache_max_entries: usize,
+
+    /// How often, in milliseconds, to poll the replication lag from the controller. Reads from
+    /// caches created with a MAX REPLICATION LAG are checked against the most recently polled lag.
+    #[clap(long, env = "REPLICATION_LAG_POLL_INTERVAL_MS", default_value = "1000")]
+    replication_lag_poll_interval_ms: u64,
+
     /// Run ReadySet in standalone mode, running a readyset-server instance within this adapter.
     #[clap(long, env = "STANDALONE", conflicts_with = "embedded-readers")]
     standalone: bool,
@@ -426,13 +541,22 @@ where
         info!(?options, "Starting ReadySet adapter");
         let users: &'static HashMap<String, String> = Box::leak(Box::new(
             if !options.allow_unauthenticated_connections {
-                hashmap! {
-                    options.username.ok_or_else(|| {
+                let mut users = hashmap! {
+                    options.username.clone().ok_or_else(|| {
                         anyhow!("Must specify --username/-u unless --allow-unauthenticated-connections is passed")
-                    })? => options.password.map(|x| x.0).ok_or_else(|| {
+                    })? => options.password.clone().map(|x| x.0).ok_or_else(|| {
                         anyhow!("Must specify --password/-p unless --allow-unauthenticated-connections is passed")
                     })?
+                };
+                if let (Some(admin_username), Some(admin_password)) =
+                    (&options.admin_username, &options.admin_password)
+                {
+                    if users.contains_key(admin_username) {
+                        bail!("--admin-username must be different from --username");
+                    }
+                    users.insert(admin_username.clone(), admin_password.0.clone());
                 }
+                users
             } else {
                 HashMap::new()
             },
@@ -451,7 +575,6 @@ where
 
         info!(%listen_address, "Listening for new connections");
 
-        let auto_increments: Arc<RwLock<HashMap<Relation, AtomicUsize>>> = Arc::default();
         let query_cache: Arc<RwLock<HashMap<ViewCreateRequest, Relation>>> = Arc::default();
         let mut health_reporter = AdapterHealthReporter::new();
 
@@ -482,11 +605,13 @@ where
             && options
                 .server_worker_options
                 .enable_experimental_paginate_support;
+        let channel_security = options.server_worker_options.channel_security.security();
+        let read_locality_zone = options.server_worker_options.placement.worker_zone.clone();
 
-        let rh = rt.block_on(async {
+        let mut rh = rt.block_on(async {
             let authority = authority
                 .to_authority(&authority_address, &deployment)
-                .await;
+                .await?;
 
             Ok::<ReadySetHandle, ReadySetError>(
                 ReadySetHandle::with_timeouts(
@@ -498,6 +623,8 @@ where
                 .await,
             )
         })?;
+        rh.set_channel_security(channel_security);
+        rh.set_read_locality_zone(read_locality_zone);
 
         rs_connect.in_scope(|| info!("ReadySetHandle created"));
 
@@ -656,6 +783,7 @@ where
                 valve,
                 prometheus_handle,
                 health_reporter: health_reporter.clone(),
+                readyset_handle: rh.clone(),
                 failpoint_channel: tx,
             };
 
@@ -679,6 +807,27 @@ where
             handle
         };
 
+        if let Some(address) = options.arrow_flight_address {
+            if users.is_empty() && !address.ip().is_loopback() {
+                bail!(
+                    "--arrow-flight-address must be a loopback address if \
+                     --allow-unauthenticated-connections is passed"
+                );
+            }
+        }
+        let arrow_flight_handle = options.arrow_flight_address.map(|address| {
+            rs_connect.in_scope(|| info!(%address, "Spawning Arrow Flight server task"));
+            let (handle, valve) = Valve::new();
+            let server =
+                ArrowFlightServer::new(rh.clone(), options.arrow_flight_batch_size, users.clone());
+            rt.handle().spawn(async move {
+                if let Err(error) = server.listen(address, valve).await {
+                    error!(%error, "Arrow Flight server failed");
+                }
+            });
+            handle
+        });
+
         let fallback_cache: Option<
             FallbackCache<
                 <<H as ConnectionHandler>::UpstreamDatabase as UpstreamDatabase>::CachedReadResult,
@@ -717,7 +866,7 @@ where
         if let MigrationMode::OutOfBand = migration_mode {
             set_failpoint!("adapter-out-of-band");
             let rh = rh.clone();
-            let (auto_increments, query_cache) = (auto_increments.clone(), query_cache.clone());
+            let query_cache = query_cache.clone();
             let shutdown_recv = shutdown_sender.subscribe();
             let loop_interval = options.migration_task_interval;
             let max_retry = options.max_processing_minutes;
@@ -755,7 +904,6 @@ where
                 let noria =
                     NoriaConnector::new(
                         rh.clone(),
-                        auto_increments.clone(),
                         query_cache.clone(),
                         noria_read_behavior,
                         expr_dialect,
@@ -811,12 +959,13 @@ where
         // Spin up async task that is in charge of creating a session with the authority,
         // regularly updating the heartbeat to keep the session live, and registering the adapters
         // http endpoint.
-        // For now we only support registering adapters over consul.
-        if let AuthorityType::Consul = options.authority {
+        // For now we only support registering adapters over consul and etcd.
+        if let AuthorityType::Consul | AuthorityType::Etcd = options.authority {
             set_failpoint!(failpoints::AUTHORITY);
-            rs_connect.in_scope(|| info!("Spawning Consul session task"));
-            let connection = span!(Level::DEBUG, "consul_session", addr = ?authority_address);
+            rs_connect.in_scope(|| info!("Spawning authority session task"));
+            let connection = span!(Level::DEBUG, "authority_session", addr = ?authority_address);
             let fut = reconcile_endpoint_registration(
+                options.authority.clone(),
                 authority_address.clone(),
                 deployment,
                 options.metrics_address.port(),
@@ -831,6 +980,9 @@ where
         let readers: Readers = Arc::new(Mutex::new(Default::default()));
 
         let upstream_config = options.server_worker_options.replicator_config.clone();
+        let namespace_limiter = Arc::new(NamespaceLimiter::new(
+            options.server_worker_options.namespace_quotas.quotas(),
+        ));
 
         // Run a readyset-server instance within this adapter.
         let internal_server_handle = if options.standalone || options.embedded_readers {
@@ -849,22 +1001,20 @@ where
             }
 
             builder.set_telemetry_sender(telemetry_sender.clone());
+            let reader_ip = builder.external_addr().ip();
 
             let server_handle = rt.block_on(async move {
                 let authority = Arc::new(
                     authority
                         .to_authority(&authority_address, &deployment)
-                        .await,
+                        .await?,
                 );
 
                 builder
                     .start_with_readers(
                         authority,
                         r,
-                        SocketAddr::new(
-                            std::net::IpAddr::V4(std::net::Ipv4Addr::new(127, 0, 0, 1)),
-                            4000,
-                        ),
+                        SocketAddr::new(reader_ip, 4000),
                         valve,
                         handle,
                     )
@@ -885,19 +1035,100 @@ where
 
         rs_connect.in_scope(|| info!(supported = %server_supports_pagination));
 
+        let index_advisor = options.adaptive_index_threshold.map(|threshold| {
+            rs_connect.in_scope(|| info!(%threshold, "Adaptive index creation enabled"));
+            Arc::new(IndexAdvisor::new(threshold, rh.clone()))
+        });
+
+        let micro_cache = options
+            .micro_cache_ttl_ms
+            .map(|ttl_ms| {
+                ensure!(
+                    ttl_ms < 1000,
+                    "--micro-cache-ttl-ms must be less than 1000, found {}",
+                    ttl_ms
+                );
+                rs_connect.in_scope(|| info!(%ttl_ms, "Micro cache enabled"));
+                Ok(Arc::new(MicroCache::new(
+                    Duration::from_millis(ttl_ms),
+                    options.micro_cache_max_entries,
+                )))
+            })
+            .transpose()?;
+
+        let replication_lag = upstream_config.upstream_db_url.is_some().then(|| {
+            let replication_lag = Arc::new(ReplicationLagMonitor::default());
+            let fut = {
+                let replication_lag = replication_lag.clone();
+                let rh = rh.clone();
+                let poll_interval = Duration::from_millis(options.replication_lag_poll_interval_ms);
+                let shutdown_recv = shutdown_sender.subscribe();
+                async move { replication_lag.run(rh, poll_interval, shutdown_recv).await }
+            };
+            rt.handle().spawn(abort_on_panic(fut));
+            replication_lag
+        });
+
+        let slow_read_log = options
+            .slow_read_log
+            .as_ref()
+            .map(|path| {
+                let threshold = Duration::from_millis(options.slow_read_threshold_ms);
+                rs_connect.in_scope(
+                    || info!(path = %path.display(), ?threshold, "Slow read log enabled"),
+                );
+                SlowReadLog::open(path, threshold).map(Arc::new)
+            })
+            .transpose()?;
+
+        let fallback_limiter = Arc::new(FallbackLimiter::new(FallbackLimits {
+            max_concurrent: options.max_concurrent_fallback_queries,
+            max_per_second: options.max_fallback_queries_per_second,
+            circuit_breaker_failures: options.fallback_circuit_breaker_failures,
+            circuit_breaker_cooldown: Duration::from_secs(
+                options.fallback_circuit_breaker_cooldown_seconds,
+            ),
+        }));
+
+        let connections = Arc::new(ConnectionRegistry::new(options.max_connections));
+        let idle_timeout = (options.connection_idle_timeout_seconds != 0)
+            .then(|| Duration::from_secs(options.connection_idle_timeout_seconds));
+
         let expr_dialect = self.expr_dialect;
         while let Some(Ok(s)) = rt.block_on(listener.next()) {
             let connection = span!(Level::DEBUG, "connection", addr = ?s.peer_addr().unwrap());
             connection.in_scope(|| info!("Accepted new connection"));
 
+            if health_reporter.state() == AdapterState::Draining {
+                connection.in_scope(|| debug!("Refusing new connection while draining"));
+                let connection_handler = self.connection_handler.clone();
+                rt.handle().spawn(
+                    connection_handler
+                        .immediate_error(s, "ReadySet adapter is shutting down".to_owned()),
+                );
+                continue;
+            }
+
+            let mut registration = match connections.register() {
+                Some(registration) => registration,
+                None => {
+                    connection.in_scope(|| warn!("Refusing new connection: too many connections"));
+                    let connection_handler = self.connection_handler.clone();
+                    rt.handle()
+                        .spawn(connection_handler.too_many_connections(s));
+                    continue;
+                }
+            };
+
             // bunch of stuff to move into the async block below
             let rh = rh.clone();
-            let (auto_increments, query_cache) = (auto_increments.clone(), query_cache.clone());
+            let query_cache = query_cache.clone();
             let mut connection_handler = self.connection_handler.clone();
             let backend_builder = BackendBuilder::new()
                 .slowlog(options.log_slow)
                 .users(users.clone())
                 .require_authentication(!options.allow_unauthenticated_connections)
+                .admin_user(options.admin_username.clone())
                 .dialect(self.parse_dialect)
                 .query_log(qlog_sender.clone(), options.query_log_ad_hoc)
                 .validate_queries(options.validate_queries, options.fail_invalidated_queries)
@@ -909,7 +1140,9 @@ where
                 .migration_mode(migration_mode)
                 .query_max_failure_seconds(options.query_max_failure_seconds)
                 .telemetry_sender(telemetry_sender.clone())
-                .fallback_recovery_seconds(options.fallback_recovery_seconds);
+                .fallback_recovery_seconds(options.fallback_recovery_seconds)
+                .fallback_limiter(fallback_limiter.clone())
+                .slow_read_log(slow_read_log.clone());
             let telemetry_sender = telemetry_sender.clone();
 
             // Initialize the reader layer for the adapter.
@@ -923,7 +1156,12 @@ where
 
             let query_status_cache = query_status_cache;
             let upstream_config = upstream_config.clone();
+            let row_size_limit = upstream_config.row_size_limit();
             let fallback_cache = fallback_cache.clone();
+            let index_advisor = index_advisor.clone();
+            let micro_cache = micro_cache.clone();
+            let replication_lag = replication_lag.clone();
+            let namespace_limiter = namespace_limiter.clone();
             let fut = async move {
                 let upstream_res = if upstream_config.upstream_db_url.is_some() {
                     set_failpoint!(failpoints::UPSTREAM);
@@ -943,6 +1181,21 @@ where
 
                 match upstream_res {
                     Ok(mut upstream) => {
+                        // Use the id of the upstream connection, so that clients can kill
+                        // connections by the id returned from `CONNECTION_ID()`
+                        if let Some(upstream_id) = upstream
+                            .as_ref()
+                            .and_then(H::UpstreamDatabase::connection_id)
+                        {
+                            if !registration.set_id(upstream_id) {
+                                warn!(
+                                    upstream_id,
+                                    "Connection id already in use; not using upstream connection id"
+                                );
+                            }
+                        }
+                        let connection_handle = registration.handle();
+
                         if let Err(e) =
                             telemetry_sender.send_event(TelemetryEvent::UpstreamConnected)
                         {
@@ -967,9 +1220,8 @@ where
 
                         match schema_search_path_res {
                             Ok(ssp) => {
-                                let noria = NoriaConnector::new_with_local_reads(
+                                let mut noria = NoriaConnector::new_with_local_reads(
                                     rh.clone(),
-                                    auto_increments.clone(),
                                     query_cache.clone(),
                                     noria_read_behavior,
                                     r,
@@ -979,13 +1231,37 @@ where
                                 )
                                 .instrument(debug_span!("Building noria connector"))
                                 .await;
-
-                                let backend = backend_builder.clone().build(
-                                    noria,
-                                    upstream,
-                                    query_status_cache,
+                                if let Some(index_advisor) = index_advisor {
+                                    noria.set_index_advisor(index_advisor);
+                                }
+                                if let Some(micro_cache) = micro_cache {
+                                    noria.set_micro_cache(micro_cache);
+                                }
+                                if let Some(replication_lag) = replication_lag {
+                                    noria.set_replication_lag_monitor(replication_lag);
+                                }
+                                if !namespace_limiter.is_unlimited() {
+                                    noria.set_namespace_limiter(namespace_limiter);
+                                }
+                                if let Some(row_size_limit) = row_size_limit {
+                                    noria.set_row_size_limit(row_size_limit);
+                                }
+
+                                let backend = backend_builder
+                                    .connection(connection_handle.clone())
+                                    .build(noria, upstream, query_status_cache);
+                                let idle = OptionFuture::from(
+                                    idle_timeout.map(|timeout| connection_handle.idle(timeout)),
                                 );
-                                connection_handler.process_connection(s, backend).await;
+                                tokio::select! {
+                                    _ = connection_handler.process_connection(s, backend) => {}
+                                    _ = connection_handle.killed() => {
+                                        info!("Connection killed");
+                                    }
+                                    Some(()) = idle => {
+                                        info!("Closing idle connection");
+                                    }
+                                }
                             }
                             Err(error) => {
                                 error!(
@@ -1011,6 +1287,8 @@ where
                 }
 
                 debug!("disconnected");
+                // Only unregister the connection once it's closed
+                drop(registration);
             }
             .instrument(connection);
 
@@ -1026,6 +1304,7 @@ where
             info!("Shutting down all tcp streams started by the adapters http router")
         });
         drop(router_handle);
+        drop(arrow_flight_handle);
 
         rs_shutdown.in_scope(|| info!("Dropping controller handle"));
         drop(rh);
@@ -1114,25 +1393,37 @@ async fn my_aws_ip() -> anyhow::Result<IpAddr> {
         .parse()?)
 }
 
-/// Facilitates continuously updating consul with this adapters externally accessibly http
+/// Facilitates continuously updating the authority with this adapters externally accessibly http
 /// endpoint.
 async fn reconcile_endpoint_registration(
+    authority_type: AuthorityType,
     authority_address: String,
     deployment: String,
     port: u16,
     use_aws_external: bool,
 ) {
-    let connect_string = format!("http://{}/{}", &authority_address, &deployment);
-    debug!("{}", connect_string);
-    let authority = ConsulAuthority::new(&connect_string).unwrap();
+    debug!(%authority_address, %deployment);
+    let authority = match authority_type
+        .to_authority(&authority_address, &deployment)
+        .await
+    {
+        Ok(authority) => authority,
+        Err(error) => {
+            error!(%error, "Could not connect to the authority to register the http endpoint");
+            return;
+        }
+    };
 
     let mut initializing = true;
     let mut interval = tokio::time::interval(REGISTER_HTTP_INIT_INTERVAL);
     let mut session_id = None;
 
-    async fn needs_refresh(id: &Option<String>, consul: &ConsulAuthority) -> bool {
+    async fn needs_refresh(id: &Option<String>, authority: &Authority) -> bool {
         if let Some(id) = id {
-            consul.worker_heartbeat(id.to_owned()).await.is_err()
+            !matches!(
+                authority.worker_heartbeat(id.to_owned()).await,
+                Ok(AuthorityWorkerHeartbeatResponse::Alive)
+            )
         } else {
             true
         }
@@ -39,4 +39,16 @@ impl ConnectionHandler for MySqlHandler {
             error!(%error, "Could not send immediate error packet")
         }
     }
+
+    async fn too_many_connections(self, stream: TcpStream) {
+        if let Err(error) = mysql_srv::send_immediate_err(
+            stream,
+            mysql_srv::ErrorKind::ER_CON_COUNT_ERROR,
+            b"Too many connections",
+        )
+        .await
+        {
+            error!(%error, "Could not send immediate error packet")
+        }
+    }
 }
@@ -33,4 +33,15 @@ impl ConnectionHandler for PsqlHandler {
             error!(%error, "Could not send immediate error packet")
         }
     }
+
+    async fn too_many_connections(self, stream: net::TcpStream) {
+        if let Err(error) = psql_srv::send_immediate_err::<readyset_psql::Backend, _>(
+            stream,
+            psql_srv::Error::TooManyConnections("sorry, too many clients already".to_owned()),
+        )
+        .await
+        {
+            error!(%error, "Could not send immediate error packet")
+        }
+    }
 }
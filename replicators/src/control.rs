@@ -0,0 +1,354 @@
+//! Control over, and observation of, a running replicator from outside of the replication task.
+//!
+//! Replication can be *paused*, for example during maintenance of the upstream database or to
+//! investigate a suspected bad replication event. While paused, the replicator stops applying
+//! replication events to ReadySet once it's finished applying the current event, so the
+//! position it has replicated up to (which is persisted in ReadySet along with the data) is left
+//! unchanged, and reads continue to be served from the data replicated so far. Once resumed,
+//! replication continues from that position.
+//!
+//! The upstream database may close the replication connection if the replicator stops reading
+//! from it for long enough, in which case the replicator reconnects from the last position it
+//! applied once it's resumed. Note that while replication is paused, the upstream database
+//! retains all the binlog (or WAL) written since the position it was paused at.
+//!
+//! Pausing replication doesn't interrupt the initial snapshot of the upstream database; a pause
+//! requested during snapshotting takes effect once streaming replication begins.
+//!
+//! The replicator also records the latest [`Timestamp`] it has propagated through the dataflow
+//! graph for each table, which is used as the snapshot for snapshot reads (see
+//! [`ViewQuery::snapshot`](readyset_client::ViewQuery::snapshot)). The controller persists the
+//! timestamp before handing it out as a snapshot, and restores it when a new controller takes over,
+//! so snapshots don't go back in time across a failover.
+//!
+//! Finally, the replicator tracks its [replication lag](ReplicationControl::replication_lag): how
+//! long ago the upstream database committed the oldest replication event the replicator has
+//! received but not yet applied, measured against the event's timestamp in the binlog (or WAL)
+//! rather than the time the replicator got around to it. Once every event received has been
+//! applied, the replicator has caught up and the lag is zero. Lag is only known while streaming
+//! replication; it's unknown while snapshotting, and after replication fails until it restarts.
+//!
+//! It also counts the number of times it has [reconnected](ReplicationControl::reconnects) to the
+//! upstream database after losing its replication connection (for example, because an idle
+//! connection was dropped by a firewall), resuming replication from the last position it read.
+//!
+//! A *resnapshot* can be [requested](ReplicationControl::request_resnapshot) when base tables have
+//! lost their state (for example, because they were moved off of a drained worker). The replicator
+//! then restarts, and snapshots every table which doesn't have a replication offset.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use readyset_client::consistency::Timestamp;
+use readyset_tracing::info;
+use tokio::sync::watch;
+
+/// What the replicator is currently doing, for the purposes of measuring replication lag
+#[derive(Debug, Clone, Copy, Default)]
+enum LagState {
+    /// The replicator isn't streaming replication events
+    #[default]
+    NotStreaming,
+    /// The replicator is streaming replication events, and has received events that were
+    /// committed upstream at the given time or later which it hasn't applied yet, if any
+    Streaming {
+        oldest_unapplied: Option<SystemTime>,
+    },
+}
+
+/// A handle used to pause and resume replication. Cloning a [`ReplicationControl`] returns a new
+/// handle to the same replicator.
+#[derive(Debug, Clone)]
+pub struct ReplicationControl {
+    paused: Arc<watch::Sender<bool>>,
+    timestamp: Arc<Mutex<Timestamp>>,
+    lag_state: Arc<Mutex<LagState>>,
+    reconnects: Arc<AtomicU64>,
+    /// The number of resnapshots which have been requested, and the number of those which have
+    /// been completed
+    resnapshots: Arc<watch::Sender<(u64, u64)>>,
+}
+
+impl Default for ReplicationControl {
+    fn default() -> Self {
+        Self {
+            paused: Arc::new(watch::channel(false).0),
+            timestamp: Default::default(),
+            lag_state: Default::default(),
+            reconnects: Default::default(),
+            resnapshots: Arc::new(watch::channel((0, 0)).0),
+        }
+    }
+}
+
+impl ReplicationControl {
+    /// Pause replication. Returns `false` if replication was already paused.
+    pub fn pause(&self) -> bool {
+        !self.paused.send_replace(true)
+    }
+
+    /// Resume replication. Returns `false` if replication wasn't paused.
+    pub fn resume(&self) -> bool {
+        self.paused.send_replace(false)
+    }
+
+    /// Returns `true` if replication is currently paused
+    pub fn is_paused(&self) -> bool {
+        *self.paused.borrow()
+    }
+
+    /// If replication is paused, wait for it to be resumed
+    pub(crate) async fn wait_until_resumed(&self) {
+        let mut paused = self.paused.subscribe();
+        if !*paused.borrow_and_update() {
+            return;
+        }
+
+        info!("Replication paused");
+        while *paused.borrow_and_update() {
+            // The sender lives as long as `self`, so this can't fail
+            let _ = paused.changed().await;
+        }
+        info!("Replication resumed");
+    }
+
+    /// Returns the latest timestamp propagated by the replicator for every table it has
+    /// propagated a timestamp for
+    pub fn timestamp(&self) -> Timestamp {
+        self.timestamp.lock().unwrap().clone()
+    }
+
+    /// Record that the given timestamp has been propagated through the dataflow graph
+    pub fn advance_timestamp(&self, timestamp: &Timestamp) {
+        let mut current = self.timestamp.lock().unwrap();
+        *current = Timestamp::join(&current, timestamp);
+    }
+
+    /// Returns how far replication is behind the upstream database, or `None` if the replicator
+    /// isn't streaming replication events, in which case the lag can't be measured
+    pub fn replication_lag(&self) -> Option<Duration> {
+        match *self.lag_state.lock().unwrap() {
+            LagState::NotStreaming => None,
+            LagState::Streaming {
+                oldest_unapplied: None,
+            } => Some(Duration::ZERO),
+            LagState::Streaming {
+                oldest_unapplied: Some(commit_time),
+            } => Some(commit_time.elapsed().unwrap_or(Duration::ZERO)),
+        }
+    }
+
+    /// Record that the replicator has started streaming replication events, with no events
+    /// received yet
+    pub(crate) fn start_streaming(&self) {
+        *self.lag_state.lock().unwrap() = LagState::Streaming {
+            oldest_unapplied: None,
+        };
+    }
+
+    /// Record that the replicator has stopped streaming replication events, so the replication
+    /// lag is no longer known
+    pub(crate) fn stop_streaming(&self) {
+        *self.lag_state.lock().unwrap() = LagState::NotStreaming;
+    }
+
+    /// Record that the replicator has received an event that was committed upstream at
+    /// `commit_time`, which it hasn't applied yet
+    pub(crate) fn event_received(&self, commit_time: SystemTime) {
+        if let LagState::Streaming {
+            oldest_unapplied: oldest_unapplied @ None,
+        } = &mut *self.lag_state.lock().unwrap()
+        {
+            *oldest_unapplied = Some(commit_time);
+        }
+    }
+
+    /// Record that the replicator has applied all the events it has received, other than those
+    /// committed upstream at `pending` or later, if any
+    pub(crate) fn events_applied(&self, pending: Option<SystemTime>) {
+        if let LagState::Streaming { oldest_unapplied } = &mut *self.lag_state.lock().unwrap() {
+            *oldest_unapplied = pending;
+        }
+    }
+
+    /// Returns the number of times the replicator has reconnected to the upstream database after
+    /// losing its replication connection
+    pub fn reconnects(&self) -> u64 {
+        self.reconnects.load(Ordering::Relaxed)
+    }
+
+    /// Record that the replicator has reconnected to the upstream database
+    pub(crate) fn record_reconnect(&self) {
+        self.reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Request that the replicator restarts, and snapshots all the tables which don't have a
+    /// replication offset. Returns an identifier for the request, which can be passed to
+    /// [`wait_for_resnapshot`](Self::wait_for_resnapshot).
+    pub fn request_resnapshot(&self) -> u64 {
+        let mut request = 0;
+        self.resnapshots.send_modify(|(requested, _)| {
+            *requested += 1;
+            request = *requested;
+        });
+        request
+    }
+
+    /// Returns `true` if a resnapshot has been requested, but hasn't completed yet
+    pub fn resnapshot_pending(&self) -> bool {
+        let (requested, completed) = *self.resnapshots.borrow();
+        requested > completed
+    }
+
+    /// Wait for the resnapshot requested by the call to
+    /// [`request_resnapshot`](Self::request_resnapshot) which returned `request` to complete
+    pub async fn wait_for_resnapshot(&self, request: u64) {
+        let mut resnapshots = self.resnapshots.subscribe();
+        while resnapshots.borrow_and_update().1 < request {
+            // The sender lives as long as `self`, so this can't fail
+            let _ = resnapshots.changed().await;
+        }
+    }
+
+    /// Wait until a resnapshot is requested which hasn't completed yet
+    pub(crate) async fn resnapshot_requested(&self) {
+        let mut resnapshots = self.resnapshots.subscribe();
+        loop {
+            let (requested, completed) = *resnapshots.borrow_and_update();
+            if requested > completed {
+                return;
+            }
+            // The sender lives as long as `self`, so this can't fail
+            let _ = resnapshots.changed().await;
+        }
+    }
+
+    /// Returns the latest resnapshot request, to be passed to
+    /// [`resnapshot_completed`](Self::resnapshot_completed) once the replicator has snapshotted
+    /// all the tables without a replication offset
+    pub(crate) fn latest_resnapshot_request(&self) -> u64 {
+        self.resnapshots.borrow().0
+    }
+
+    /// Record that all resnapshots requested up to and including `request` have completed
+    pub(crate) fn resnapshot_completed(&self, request: u64) {
+        self.resnapshots.send_if_modified(|(_, completed)| {
+            if *completed < request {
+                *completed = request;
+                true
+            } else {
+                false
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn pause_and_resume() {
+        let control = ReplicationControl::default();
+        control.wait_until_resumed().await;
+
+        assert!(control.pause());
+        assert!(!control.pause());
+        assert!(control.is_paused());
+        tokio::time::timeout(Duration::from_millis(50), control.wait_until_resumed())
+            .await
+            .unwrap_err();
+
+        let waiting = tokio::spawn({
+            let control = control.clone();
+            async move { control.wait_until_resumed().await }
+        });
+        assert!(control.resume());
+        assert!(!control.resume());
+        waiting.await.unwrap();
+    }
+
+    #[test]
+    fn timestamp_advances() {
+        let control = ReplicationControl::default();
+        assert_eq!(control.timestamp(), Timestamp::default());
+
+        control.advance_timestamp(&"1:3".parse().unwrap());
+        control.advance_timestamp(&"0:5,1:2".parse().unwrap());
+        assert_eq!(control.timestamp(), "0:5,1:3".parse().unwrap());
+    }
+
+    #[test]
+    fn replication_lag() {
+        let control = ReplicationControl::default();
+        assert_eq!(control.replication_lag(), None);
+
+        // Events received before streaming starts aren't counted
+        control.event_received(SystemTime::now() - Duration::from_secs(60));
+        assert_eq!(control.replication_lag(), None);
+
+        control.start_streaming();
+        assert_eq!(control.replication_lag(), Some(Duration::ZERO));
+
+        // Lag is measured from the oldest event received but not yet applied
+        control.event_received(SystemTime::now() - Duration::from_secs(10));
+        control.event_received(SystemTime::now() - Duration::from_secs(5));
+        assert!(control.replication_lag().unwrap() >= Duration::from_secs(10));
+
+        // An event held back after applying the rest is still counted
+        control.events_applied(Some(SystemTime::now() - Duration::from_secs(5)));
+        let lag = control.replication_lag().unwrap();
+        assert!(lag >= Duration::from_secs(5) && lag < Duration::from_secs(10));
+
+        control.events_applied(None);
+        assert_eq!(control.replication_lag(), Some(Duration::ZERO));
+
+        control.stop_streaming();
+        assert_eq!(control.replication_lag(), None);
+    }
+
+    #[tokio::test]
+    async fn resnapshot() {
+        let control = ReplicationControl::default();
+        assert!(!control.resnapshot_pending());
+        tokio::time::timeout(Duration::from_millis(50), control.resnapshot_requested())
+            .await
+            .unwrap_err();
+
+        let request = control.request_resnapshot();
+        assert!(control.resnapshot_pending());
+        control.resnapshot_requested().await;
+
+        let waiting = tokio::spawn({
+            let control = control.clone();
+            async move { control.wait_for_resnapshot(request).await }
+        });
+        // A later request isn't satisfied by completing an earlier one
+        let snapshotting = control.latest_resnapshot_request();
+        let later = control.request_resnapshot();
+        control.resnapshot_completed(snapshotting);
+        waiting.await.unwrap();
+        assert!(control.resnapshot_pending());
+        tokio::time::timeout(
+            Duration::from_millis(50),
+            control.wait_for_resnapshot(later),
+        )
+        .await
+        .unwrap_err();
+
+        control.resnapshot_completed(later);
+        assert!(!control.resnapshot_pending());
+        control.wait_for_resnapshot(later).await;
+    }
+
+    #[test]
+    fn reconnects() {
+        let control = ReplicationControl::default();
+        assert_eq!(control.reconnects(), 0);
+
+        control.record_reconnect();
+        control.clone().record_reconnect();
+        assert_eq!(control.reconnects(), 2);
+    }
+}
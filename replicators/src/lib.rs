@@ -5,17 +5,22 @@
     string_remove_matches,
     iter_intersperse
 )]
+pub(crate) mod control;
 pub mod db_util;
 pub(crate) mod mysql_connector;
 pub(crate) mod noria_adapter;
 pub(crate) mod postgres_connector;
+pub(crate) mod schema_drift;
 pub(crate) mod table_filter;
+pub(crate) mod transform;
 
 use std::time::Duration;
 
+pub use control::ReplicationControl;
 pub use mysql_connector::BinlogPosition;
 pub use noria_adapter::NoriaAdapter;
 pub use postgres_connector::PostgresPosition;
+pub use transform::{ReplicationTransform, ReplicationTransformHook, RowChange};
 
 /// Provide a simplistic human-readable estimate for how much time remains to complete an operation
 pub(crate) fn estimate_remaining_time(elapsed: Duration, progress: f64, total: f64) -> String {
@@ -1,7 +1,12 @@
+use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
+use std::io;
+use std::ops::Deref;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use async_trait::async_trait;
 use binlog::consts::{BinlogChecksumAlg, EventType};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use metrics::counter;
 use mysql::binlog::events::StatusVarVal;
 use mysql::binlog::jsonb::{self, JsonbToJsonError};
@@ -10,24 +15,42 @@ use mysql_async as mysql;
 use mysql_common::binlog;
 use mysql_common::binlog::row::BinlogRow;
 use mysql_common::binlog::value::BinlogValue;
-use nom_sql::Relation;
+use nom_sql::{CreateTableBody, Relation, TableKey};
 use readyset_client::metrics::recorded;
 use readyset_client::recipe::ChangeList;
 use readyset_client::replication::ReplicationOffset;
-use readyset_client::{ReadySetError, ReadySetResult};
-use readyset_data::{DfValue, Dialect};
+use readyset_client::{
+    Modification, ReadySetError, ReadySetHandle, ReadySetResult, TableOperation,
+};
+use readyset_data::{column_default_value_at, DfValue, Dialect};
 use readyset_tracing::warn;
 
 use super::BinlogPosition;
+use crate::control::ReplicationControl;
 use crate::noria_adapter::{Connector, ReplicationAction};
 
 const CHECKSUM_QUERY: &str = "SET @master_binlog_checksum='CRC32'";
 const DEFAULT_SERVER_ID: u32 = u32::MAX - 55;
 
+/// Limits on the batches of row operations accumulated by a [`MySqlBinlogConnector`].
+///
+/// Writing to ReadySet is relatively expensive, so row operations from consecutive row events for
+/// the same table within a transaction are batched together. A batch is flushed once it reaches
+/// `max_actions` operations, or once `max_latency` has passed since its first operation was read
+/// (whether or not any more events have been read since), so that large or slow transactions don't
+/// hold back writes indefinitely. Batches are always flushed at the end of a transaction.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BatchLimits {
+    pub(crate) max_actions: usize,
+    pub(crate) max_latency: Duration,
+}
+
 /// A connector that connects to a MySQL server and starts reading binlogs from a given position.
 ///
-/// The server must be configured with `binlog_format` set to `row` and `binlog_row_image` set to
-/// `full`.
+/// The server must be configured with `binlog_format` set to `row`. `binlog_row_image` should be
+/// set to `full`, although inserts logged with a `minimal` (or `noblob`) row image are also
+/// supported: columns omitted from those are filled in with their default values from the table's
+/// schema in ReadySet.
 ///
 /// The connector user may optionally have the following permissions:
 /// * `BACKUP_ADMIN` - (optional) to perform LOCK INSTANCE FOR BACKUP, not available on RDS
@@ -38,9 +61,22 @@ const DEFAULT_SERVER_ID: u32 = u32::MAX - 55;
 /// * `REPLICATION CLIENT` - to use SHOW MASTER STATUS, SHOW SLAVE STATUS, and SHOW BINARY LOGS;
 ///
 /// The connector must also be assigned a unique `server_id` value
+///
+/// If a heartbeat period is configured, the server is asked to send a heartbeat event whenever
+/// nothing has been written to the binlog for that long, which keeps the replication connection
+/// from being dropped as idle by firewalls and NAT gateways along the way. If the connection is
+/// lost anyway, or nothing at all is received on it for two heartbeat periods, the connector
+/// reconnects and resumes reading the binlog from the last position it read.
 pub(crate) struct MySqlBinlogConnector {
     /// This is the underlying (regular) MySQL connection
     connection: mysql::Conn,
+    /// The options used to open `connection`, kept to reconnect with if it's lost
+    mysql_opts: mysql::Opts,
+    /// How long the server may go without writing to the binlog before it sends us a heartbeat
+    /// event, if at all
+    heartbeat_period: Option<Duration>,
+    /// Used to record reconnections to the server
+    control: ReplicationControl,
     /// Reader is a decoder for binlog events
     reader: binlog::EventStreamReader,
     /// The binlog "slave" must be assigned a unique `server_id` in the replica topology
@@ -51,6 +87,110 @@ pub(crate) struct MySqlBinlogConnector {
     /// The GTID of the current transaction. Table modification events will have
     /// the current GTID attached if enabled in mysql.
     current_gtid: Option<u64>,
+    /// Row operations for a single table, accumulated across consecutive row events within the
+    /// current transaction so they can be written to ReadySet all at once
+    batcher: RowsBatcher,
+    /// Row operations (and the log position of the event they were read from) which couldn't be
+    /// added to the previous batch because they're for a different table
+    pending_rows: Option<(Relation, Vec<TableOperation>, u32)>,
+    /// An event which ended the previous batch, to be handled on the next call to
+    /// [`next_action_inner`](Self::next_action_inner)
+    peek: Option<binlog::events::Event>,
+    /// The time the upstream database recorded the most recently read event at
+    event_time: Option<SystemTime>,
+    /// Set when the replication connection was lost while row operations were batched, so that
+    /// the batch could be flushed before reconnecting
+    reconnect_pending: bool,
+    /// Handle to ReadySet, used to look up the schemas of tables that rows are inserted into with
+    /// a partial row image
+    noria: ReadySetHandle,
+    /// The schemas of tables looked up via `noria` (or `None` for tables that don't exist in
+    /// ReadySet), cleared whenever we see a schema change
+    table_schemas: HashMap<Relation, Option<CreateTableBody>>,
+}
+
+/// A batch of row operations to be applied to a single table
+struct RowsBatch {
+    table: Relation,
+    actions: Vec<TableOperation>,
+    /// The GTID of the transaction the operations were read from
+    txid: Option<u64>,
+    /// When the first operation in the batch was read
+    started: Instant,
+}
+
+/// Accumulates row operations into a [`RowsBatch`], within the given [`BatchLimits`]
+struct RowsBatcher {
+    limits: BatchLimits,
+    batch: Option<RowsBatch>,
+}
+
+impl RowsBatcher {
+    fn new(limits: BatchLimits) -> Self {
+        Self {
+            limits,
+            batch: None,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.batch.is_none()
+    }
+
+    /// Add row operations for `table` to the current batch, or start a new batch for them (read
+    /// from the transaction with GTID `txid`) if there isn't one.
+    ///
+    /// If the current batch is for a different table, the operations are returned rather than
+    /// added, to be added again once the current batch has been flushed.
+    fn add(
+        &mut self,
+        table: Relation,
+        mut actions: Vec<TableOperation>,
+        txid: Option<u64>,
+    ) -> Result<(), (Relation, Vec<TableOperation>)> {
+        match &mut self.batch {
+            Some(batch) if batch.table != table => return Err((table, actions)),
+            Some(batch) => batch.actions.append(&mut actions),
+            None => {
+                self.batch = Some(RowsBatch {
+                    table,
+                    actions,
+                    txid,
+                    started: Instant::now(),
+                })
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns how long the current batch may be held for before it must be flushed, if there is
+    /// one
+    fn time_left(&self) -> Option<Duration> {
+        self.batch.as_ref().map(|batch| {
+            self.limits
+                .max_latency
+                .saturating_sub(batch.started.elapsed())
+        })
+    }
+
+    /// Returns true if the current batch has reached either of the limits, and must be flushed
+    fn is_full(&self) -> bool {
+        self.batch.as_ref().map_or(false, |batch| {
+            batch.actions.len() >= self.limits.max_actions
+                || batch.started.elapsed() >= self.limits.max_latency
+        })
+    }
+
+    /// Take the current batch of row operations, if any, as an action to flush
+    fn take(&mut self) -> Option<ReplicationAction> {
+        self.batch
+            .take()
+            .map(|batch| ReplicationAction::TableAction {
+                table: batch.table,
+                actions: batch.actions,
+                txid: batch.txid,
+            })
+    }
 }
 
 impl PartialOrd for BinlogPosition {
@@ -157,9 +297,19 @@ impl MySqlBinlogConnector {
 
     /// In order to request a binlog, we must first register as a replica, and let the primary
     /// know what type of checksum we support (NONE and CRC32 are the options), NONE seems to work
-    /// but others use CRC32 🤷‍♂️
+    /// but others use CRC32 🤷‍♂️. This is also when we let the primary know how often to send us
+    /// heartbeats.
     async fn register_as_replica(&mut self) -> mysql::Result<()> {
         self.connection.query_drop(CHECKSUM_QUERY).await?;
+        if let Some(heartbeat_period) = self.heartbeat_period {
+            // The heartbeat period is given in nanoseconds
+            self.connection
+                .query_drop(format!(
+                    "SET @master_heartbeat_period={}",
+                    heartbeat_period.as_nanos()
+                ))
+                .await?;
+        }
 
         let cmd = mysql_common::packets::ComRegisterSlave::new(self.server_id());
         self.connection.write_command(&cmd).await?;
@@ -199,13 +349,28 @@ impl MySqlBinlogConnector {
         mysql_opts: O,
         next_position: BinlogPosition,
         server_id: Option<u32>,
+        heartbeat_period: Option<Duration>,
+        batch_limits: BatchLimits,
+        noria: ReadySetHandle,
+        control: ReplicationControl,
     ) -> ReadySetResult<Self> {
+        let mysql_opts = mysql_opts.into();
         let mut connector = MySqlBinlogConnector {
-            connection: mysql::Conn::new(mysql_opts).await?,
+            connection: mysql::Conn::new(mysql_opts.clone()).await?,
+            mysql_opts,
+            heartbeat_period,
+            control,
             reader: binlog::EventStreamReader::new(binlog::consts::BinlogVersion::Version4),
             server_id,
             next_position,
             current_gtid: None,
+            batcher: RowsBatcher::new(batch_limits),
+            pending_rows: None,
+            peek: None,
+            event_time: None,
+            reconnect_pending: false,
+            noria,
+            table_schemas: HashMap::new(),
         };
 
         connector.register_as_replica().await?;
@@ -214,15 +379,331 @@ impl MySqlBinlogConnector {
         Ok(connector)
     }
 
-    /// Get the next raw binlog event
-    async fn next_event(&mut self) -> mysql::Result<binlog::events::Event> {
-        let packet = self.connection.read_packet().await?;
+    /// Reconnect to the server and resume reading the binlog from `next_position`.
+    ///
+    /// `next_position` has already been advanced past any row operations in the current batch, so
+    /// they won't be read again - the batch must be flushed before reconnecting (see
+    /// [`next_event`](Self::next_event)). The table maps read so far are kept, as row events read
+    /// after reconnecting may refer to table maps from before `next_position`.
+    async fn reconnect(&mut self) -> mysql::Result<()> {
+        debug_assert!(
+            self.batcher.is_empty(),
+            "Batched rows would be lost on reconnect"
+        );
+        self.connection = mysql::Conn::new(self.mysql_opts.clone()).await?;
+        self.register_as_replica().await?;
+        self.request_binlog().await?;
+
+        counter!(recorded::REPLICATOR_RECONNECTS, 1u64);
+        self.control.record_reconnect();
+        Ok(())
+    }
+
+    /// Read the next packet from the replication connection. If the server is sending us
+    /// heartbeats, hearing nothing at all for two heartbeat periods means the connection has
+    /// been silently dropped, which is reported as an IO error.
+    async fn read_packet(&mut self) -> mysql::Result<Vec<u8>> {
+        let heartbeat_period = match self.heartbeat_period {
+            Some(heartbeat_period) => heartbeat_period,
+            None => return self.connection.read_packet().await,
+        };
+
+        tokio::time::timeout(heartbeat_period * 2, self.connection.read_packet())
+            .await
+            .map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "No heartbeat received on replication connection",
+                )
+            })?
+    }
+
+    /// Get the next raw binlog event, reconnecting once if the replication connection was lost.
+    ///
+    /// Returns `None` instead if row operations are batched and the batch has to be flushed before
+    /// an event is read: either because it has been held for as long as it may be, or because the
+    /// connection was lost (in which case we reconnect on the next call). In the latter case the
+    /// batch must be flushed in between, since reading the binlog again from `next_position` won't
+    /// return the operations in it again.
+    async fn next_event(&mut self) -> mysql::Result<Option<binlog::events::Event>> {
+        let packet = if std::mem::take(&mut self.reconnect_pending) {
+            self.reconnect().await?;
+            self.read_packet().await?
+        } else {
+            let read = match self.batcher.time_left() {
+                // Reading a packet is cancel safe: any part of a packet read before the timeout
+                // stays buffered in the connection, to be read on the next call
+                Some(time_left) => {
+                    match tokio::time::timeout(time_left, self.read_packet()).await {
+                        Ok(read) => read,
+                        Err(_) => return Ok(None),
+                    }
+                }
+                None => self.read_packet().await,
+            };
+            match read {
+                Ok(packet) => packet,
+                Err(error @ mysql::Error::Io(_)) => {
+                    warn!(
+                        %error,
+                        position = ?self.next_position,
+                        "Lost replication connection, reconnecting"
+                    );
+                    if !self.batcher.is_empty() {
+                        self.reconnect_pending = true;
+                        return Ok(None);
+                    }
+                    self.reconnect().await?;
+                    self.read_packet().await?
+                }
+                Err(error) => return Err(error),
+            }
+        };
         // TODO: byte 0 of packet should be zero, unless EOF is reached, however we should never get
         // one without the NON_BLOCKING SQL flag set
         assert_eq!(packet.first(), Some(&0));
         let event = self.reader.read(&packet[1..])?;
         assert!(Self::validate_event_checksum(&event)); // TODO: definitely should never fail a CRC check, but what to do if we do?
-        Ok(event)
+        Ok(Some(event))
+    }
+
+    /// Convert a `WRITE_ROWS_EVENT`, `UPDATE_ROWS_EVENT` or `DELETE_ROWS_EVENT` into the table it
+    /// modifies and the list of [`TableOperation`]s to apply to that table
+    fn rows_event_actions(
+        &self,
+        binlog_event: &binlog::events::Event,
+    ) -> mysql::Result<(Relation, Vec<TableOperation>)> {
+        use mysql_common::binlog::events;
+
+        let event_type = binlog_event
+            .header()
+            .event_type()
+            .map_err(|ev| format!("Unknown binlog event type {}", ev))?;
+
+        let (tme, actions) = match event_type {
+            EventType::WRITE_ROWS_EVENT => {
+                // This is the event we get on `INSERT INTO`
+                let ev: events::WriteRowsEvent = binlog_event.read_event()?;
+                // Retrieve the corresponding TABLE_MAP_EVENT
+                let tme = self
+                    .reader
+                    .get_tme(ev.table_id())
+                    .ok_or("TME not found for WRITE_ROWS_EVENT")?;
+
+                let num_columns = ev.num_columns() as usize;
+                let columns = image_columns(ev.columns_after_image(), num_columns);
+                let schema = self
+                    .table_schemas
+                    .get(&tme_relation(tme))
+                    .and_then(Option::as_ref);
+                let now = self.event_timestamp();
+
+                let mut inserted_rows = Vec::new();
+
+                for row in ev.rows(tme) {
+                    // For each row in the event we produce a vector of ReadySet types that
+                    // represent that row
+                    let row = binlog_row_to_noria_row(
+                        &row?.1.ok_or("Missing data in WRITE_ROWS_EVENT")?,
+                        tme,
+                        &columns,
+                    )?;
+                    inserted_rows.push(TableOperation::Insert(fill_omitted_columns(
+                        row,
+                        &columns,
+                        num_columns,
+                        schema,
+                        now,
+                    )?));
+                }
+
+                (tme, inserted_rows)
+            }
+
+            EventType::UPDATE_ROWS_EVENT => {
+                // This is the event we get on `UPDATE`
+                let ev: events::UpdateRowsEvent = binlog_event.read_event()?;
+                // Retrieve the corresponding TABLE_MAP_EVENT
+                let tme = self
+                    .reader
+                    .get_tme(ev.table_id())
+                    .ok_or_else(|| format!("TME not found for UPDATE_ROWS_EVENT {:?}", ev))?;
+
+                let num_columns = ev.num_columns() as usize;
+                let before_columns = image_columns(ev.columns_before_image(), num_columns);
+                let after_columns = image_columns(ev.columns_after_image(), num_columns);
+                let schema = self
+                    .table_schemas
+                    .get(&tme_relation(tme))
+                    .and_then(Option::as_ref);
+
+                let mut updated_rows = Vec::new();
+
+                for row in ev.rows(tme) {
+                    // For each row in the event we produce the ReadySet table operations to
+                    // replace the previous entry with the new one
+                    let row = &row?;
+                    let before = binlog_row_to_noria_row(
+                        row.0.as_ref().ok_or_else(|| {
+                            format!("Missing before rows in UPDATE_ROWS_EVENT {:?}", row)
+                        })?,
+                        tme,
+                        &before_columns,
+                    )?;
+                    let after = binlog_row_to_noria_row(
+                        row.1.as_ref().ok_or_else(|| {
+                            format!("Missing after rows in UPDATE_ROWS_EVENT {:?}", row)
+                        })?,
+                        tme,
+                        &after_columns,
+                    )?;
+                    updated_rows.extend(update_operations(
+                        (before, &before_columns),
+                        (after, &after_columns),
+                        num_columns,
+                        schema,
+                    )?);
+                }
+
+                (tme, updated_rows)
+            }
+
+            EventType::DELETE_ROWS_EVENT => {
+                // This is the event we get on `DELETE FROM`
+                let ev: events::DeleteRowsEvent = binlog_event.read_event()?;
+                // Retrieve the corresponding TABLE_MAP_EVENT
+                let tme = self
+                    .reader
+                    .get_tme(ev.table_id())
+                    .ok_or_else(|| format!("TME not found for DELETE_ROWS_EVENT {:?}", ev))?;
+
+                let num_columns = ev.num_columns() as usize;
+                let columns = image_columns(ev.columns_before_image(), num_columns);
+                let schema = self
+                    .table_schemas
+                    .get(&tme_relation(tme))
+                    .and_then(Option::as_ref);
+
+                let mut deleted_rows = Vec::new();
+
+                for row in ev.rows(tme) {
+                    // For each row in the event we produce a vector of ReadySet types that
+                    // represent that row
+                    let row = binlog_row_to_noria_row(
+                        &row?.0.ok_or("Missing data in DELETE_ROWS_EVENT")?,
+                        tme,
+                        &columns,
+                    )?;
+                    deleted_rows.push(if columns.len() == num_columns {
+                        TableOperation::DeleteRow { row }
+                    } else {
+                        // Partial before images only contain the primary key
+                        TableOperation::DeleteByKey {
+                            key: primary_key_values(&row, &columns, schema)?,
+                        }
+                    });
+                }
+
+                (tme, deleted_rows)
+            }
+
+            _ => return Err(format!("Not a rows event: {:?}", event_type).into()),
+        };
+
+        Ok((tme_relation(tme), actions))
+    }
+
+    /// Returns the time the most recently read event was written to the binlog, in UTC, which is
+    /// the time that upstream evaluated `CURRENT_TIMESTAMP` and the like as for the rows in that
+    /// event. Binlog timestamps have a resolution of one second.
+    fn event_timestamp(&self) -> NaiveDateTime {
+        self.event_time
+            .map_or_else(Utc::now, DateTime::<Utc>::from)
+            .naive_utc()
+    }
+
+    /// If the given rows event was logged with partial row images (as it is with
+    /// `binlog_row_image` set to `minimal`), make sure we've looked up the schema of the table it
+    /// modifies, so that [`rows_event_actions`](Self::rows_event_actions) can fill in the columns
+    /// omitted from inserted rows with their default values, and find the primary keys of updated
+    /// and deleted rows
+    async fn load_schema_for_partial_image(
+        &mut self,
+        binlog_event: &binlog::events::Event,
+        event_type: EventType,
+    ) -> mysql::Result<()> {
+        use mysql_common::binlog::events;
+
+        let is_partial = |cols: Vec<usize>, num_columns| cols.len() != num_columns;
+        let (table_id, partial) = match event_type {
+            EventType::WRITE_ROWS_EVENT => {
+                let ev: events::WriteRowsEvent = binlog_event.read_event()?;
+                let num_columns = ev.num_columns() as usize;
+                let after = image_columns(ev.columns_after_image(), num_columns);
+                (ev.table_id(), is_partial(after, num_columns))
+            }
+            EventType::UPDATE_ROWS_EVENT => {
+                let ev: events::UpdateRowsEvent = binlog_event.read_event()?;
+                let num_columns = ev.num_columns() as usize;
+                let before = image_columns(ev.columns_before_image(), num_columns);
+                (ev.table_id(), is_partial(before, num_columns))
+            }
+            EventType::DELETE_ROWS_EVENT => {
+                let ev: events::DeleteRowsEvent = binlog_event.read_event()?;
+                let num_columns = ev.num_columns() as usize;
+                let before = image_columns(ev.columns_before_image(), num_columns);
+                (ev.table_id(), is_partial(before, num_columns))
+            }
+            _ => return Ok(()),
+        };
+        if !partial {
+            return Ok(());
+        }
+
+        let tme = self
+            .reader
+            .get_tme(table_id)
+            .ok_or_else(|| format!("TME not found for {:?}", event_type))?;
+        let table = tme_relation(tme);
+        if self.table_schemas.contains_key(&table) {
+            return Ok(());
+        }
+
+        let schema = match self.noria.table(table.clone()).await {
+            Ok(table) => table.schema().cloned(),
+            Err(error) => {
+                // Rows for tables that don't exist in ReadySet are discarded anyway, so there's no
+                // need to fill them in
+                warn!(%error, %table, "Could not look up schema for table");
+                None
+            }
+        };
+        self.table_schemas.insert(table, schema);
+        Ok(())
+    }
+
+    /// Add the given row operations, read from an event ending at `log_pos`, to the current
+    /// batch, returning an action to flush if the batch can't be extended any further.
+    ///
+    /// If the operations are for a different table than the one currently being batched, the
+    /// current batch is returned and the operations are kept aside to start the next batch.
+    fn add_to_batch(
+        &mut self,
+        table: Relation,
+        actions: Vec<TableOperation>,
+        log_pos: u32,
+    ) -> Option<ReplicationAction> {
+        if let Err((table, actions)) = self.batcher.add(table, actions, self.current_gtid) {
+            self.pending_rows = Some((table, actions, log_pos));
+            return self.batcher.take();
+        }
+        self.next_position.position = log_pos;
+
+        if self.batcher.is_full() {
+            self.batcher.take()
+        } else {
+            None
+        }
     }
 
     /// Process binlog events until an actionable event occurs.
@@ -238,15 +719,61 @@ impl MySqlBinlogConnector {
         use mysql_common::binlog::events;
 
         loop {
-            let binlog_event = self.next_event().await?;
-
-            self.next_position.position = binlog_event.header().log_pos();
+            if let Some((table, actions, log_pos)) = self.pending_rows.take() {
+                if let Some(action) = self.add_to_batch(table, actions, log_pos) {
+                    return Ok((action, &self.next_position));
+                }
+            }
 
-            match binlog_event
+            let binlog_event = match self.peek.take() {
+                Some(event) => event,
+                None => {
+                    let event = match self.next_event().await? {
+                        Some(event) => event,
+                        #[allow(clippy::unwrap_used)] // next_event only returns None with a batch
+                        None => return Ok((self.batcher.take().unwrap(), &self.next_position)),
+                    };
+                    // Binlog timestamps have a resolution of one second, and are zero for the
+                    // artificial events the server sends when we connect
+                    let timestamp = event.header().timestamp();
+                    if timestamp != 0 {
+                        let event_time = UNIX_EPOCH + Duration::from_secs(timestamp.into());
+                        self.event_time = Some(event_time);
+                        self.control.event_received(event_time);
+                    }
+                    event
+                }
+            };
+            let log_pos = binlog_event.header().log_pos();
+            let event_type = binlog_event
                 .header()
                 .event_type()
-                .map_err(|ev| format!("Unknown binlog event type {}", ev))?
+                .map_err(|ev| format!("Unknown binlog event type {}", ev))?;
+
+            // Only consecutive row events within a single transaction can be batched together, so
+            // flush the current batch before handling anything that might end the transaction or
+            // change the schema, and keep the event aside to handle on the next call
+            if !self.batcher.is_empty()
+                && matches!(
+                    event_type,
+                    EventType::ROTATE_EVENT | EventType::QUERY_EVENT | EventType::GTID_EVENT
+                )
             {
+                self.peek = Some(binlog_event);
+                #[allow(clippy::unwrap_used)] // We just checked that there's a batch
+                return Ok((self.batcher.take().unwrap(), &self.next_position));
+            }
+
+            if !matches!(
+                event_type,
+                EventType::WRITE_ROWS_EVENT
+                    | EventType::UPDATE_ROWS_EVENT
+                    | EventType::DELETE_ROWS_EVENT
+            ) {
+                self.next_position.position = log_pos;
+            }
+
+            match event_type {
                 EventType::ROTATE_EVENT => {
                     // Written when mysqld switches to a new binary log file.
                     // This occurs when someone issues a FLUSH LOGS statement or the current binary
@@ -296,6 +823,9 @@ impl MySqlBinlogConnector {
                         }
                     };
 
+                    // Any of the schemas we've looked up may have just changed
+                    self.table_schemas.clear();
+
                     return Ok((
                         ReplicationAction::DdlChange { schema, changes },
                         &self.next_position,
@@ -316,122 +846,24 @@ impl MySqlBinlogConnector {
                     // `binlog::EventStreamReader`
                 }
 
-                EventType::WRITE_ROWS_EVENT => {
-                    // This is the event we get on `INSERT INTO`
-                    let ev: events::WriteRowsEvent = binlog_event.read_event()?;
-                    // Retrieve the corresponding TABLE_MAP_EVENT
-                    let tme = self
-                        .reader
-                        .get_tme(ev.table_id())
-                        .ok_or("TME not found for WRITE_ROWS_EVENT")?;
-
-                    let mut inserted_rows = Vec::new();
-
-                    for row in ev.rows(tme) {
-                        // For each row in the event we produce a vector of ReadySet types that
-                        // represent that row
-                        inserted_rows.push(readyset_client::TableOperation::Insert(
-                            binlog_row_to_noria_row(
-                                &row?.1.ok_or("Missing data in WRITE_ROWS_EVENT")?,
-                                tme,
-                            )?,
-                        ));
+                EventType::WRITE_ROWS_EVENT
+                | EventType::UPDATE_ROWS_EVENT
+                | EventType::DELETE_ROWS_EVENT => {
+                    self.load_schema_for_partial_image(&binlog_event, event_type)
+                        .await?;
+                    let (table, actions) = self.rows_event_actions(&binlog_event)?;
+                    if let Some(action) = self.add_to_batch(table, actions, log_pos) {
+                        return Ok((action, &self.next_position));
                     }
-
-                    return Ok((
-                        ReplicationAction::TableAction {
-                            table: Relation {
-                                schema: Some(tme.database_name().into()),
-                                name: tme.table_name().into(),
-                            },
-                            actions: inserted_rows,
-                            txid: self.current_gtid,
-                        },
-                        &self.next_position,
-                    ));
                 }
 
-                EventType::UPDATE_ROWS_EVENT => {
-                    // This is the event we get on `UPDATE`
-                    let ev: events::UpdateRowsEvent = binlog_event.read_event()?;
-                    // Retrieve the corresponding TABLE_MAP_EVENT
-                    let tme = self
-                        .reader
-                        .get_tme(ev.table_id())
-                        .ok_or_else(|| format!("TME not found for UPDATE_ROWS_EVENT {:?}", ev))?;
-
-                    let mut updated_rows = Vec::new();
-
-                    for row in ev.rows(tme) {
-                        // For each row in the event we produce a pair of ReadySet table operations
-                        // to delete the previous entry and insert the new
-                        // one
-                        let row = &row?;
-                        updated_rows.push(readyset_client::TableOperation::DeleteRow {
-                            row: binlog_row_to_noria_row(
-                                row.0.as_ref().ok_or_else(|| {
-                                    format!("Missing before rows in UPDATE_ROWS_EVENT {:?}", row)
-                                })?,
-                                tme,
-                            )?,
-                        });
-
-                        updated_rows.push(readyset_client::TableOperation::Insert(
-                            binlog_row_to_noria_row(
-                                row.1.as_ref().ok_or_else(|| {
-                                    format!("Missing after rows in UPDATE_ROWS_EVENT {:?}", row)
-                                })?,
-                                tme,
-                            )?,
-                        ));
-                    }
-
-                    return Ok((
-                        ReplicationAction::TableAction {
-                            table: Relation {
-                                schema: Some(tme.database_name().into()),
-                                name: tme.table_name().into(),
-                            },
-                            actions: updated_rows,
-                            txid: self.current_gtid,
-                        },
-                        &self.next_position,
-                    ));
-                }
-
-                EventType::DELETE_ROWS_EVENT => {
-                    // This is the event we get on `ALTER TABLE`
-                    let ev: events::DeleteRowsEvent = binlog_event.read_event()?;
-                    // Retrieve the corresponding TABLE_MAP_EVENT
-                    let tme = self
-                        .reader
-                        .get_tme(ev.table_id())
-                        .ok_or_else(|| format!("TME not found for UPDATE_ROWS_EVENT {:?}", ev))?;
-
-                    let mut deleted_rows = Vec::new();
-
-                    for row in ev.rows(tme) {
-                        // For each row in the event we produce a vector of ReadySet types that
-                        // represent that row
-                        deleted_rows.push(readyset_client::TableOperation::DeleteRow {
-                            row: binlog_row_to_noria_row(
-                                &row?.0.ok_or("Missing data in DELETE_ROWS_EVENT")?,
-                                tme,
-                            )?,
-                        });
+                EventType::XID_EVENT => {
+                    // Generated for a commit of a transaction that modifies one or more tables of
+                    // an XA-capable storage engine. There is no knowing when the next transaction
+                    // is coming, so flush whatever we've batched so far
+                    if let Some(action) = self.batcher.take() {
+                        return Ok((action, &self.next_position));
                     }
-
-                    return Ok((
-                        ReplicationAction::TableAction {
-                            table: Relation {
-                                schema: Some(tme.database_name().into()),
-                                name: tme.table_name().into(),
-                            },
-                            actions: deleted_rows,
-                            txid: self.current_gtid,
-                        },
-                        &self.next_position,
-                    ));
                 }
 
                 EventType::WRITE_ROWS_EVENT_V1 => unimplemented!(), /* The V1 event numbers are */
@@ -460,13 +892,6 @@ impl MySqlBinlogConnector {
 
                 EventType::ANONYMOUS_GTID_EVENT => {}
 
-                EventType::XID_EVENT => {
-                    // Generated for a commit of a transaction that modifies one or more tables of an XA-capable
-                    // storage engine. Normal transactions are implemented by sending a QUERY_EVENT containing a
-                    // BEGIN statement and a QUERY_EVENT containing a COMMIT statement
-                    // (or a ROLLBACK statement if the transaction is rolled back).
-                }
-
                 EventType::START_EVENT_V3 // Old version of FORMAT_DESCRIPTION_EVENT
                 | EventType::FORMAT_DESCRIPTION_EVENT // A descriptor event that is written to the beginning of each binary log file. This event is used as of MySQL 5.0; it supersedes START_EVENT_V3.
                 | EventType::STOP_EVENT // Written when mysqld stops
@@ -510,7 +935,11 @@ impl MySqlBinlogConnector {
             if let Some(limit) = until {
                 let limit = BinlogPosition::try_from(limit).expect("Valid binlog limit");
                 if self.next_position >= limit {
-                    return Ok((ReplicationAction::LogPosition, &self.next_position));
+                    let action = self
+                        .batcher
+                        .take()
+                        .unwrap_or(ReplicationAction::LogPosition);
+                    return Ok((action, &self.next_position));
                 }
             }
         }
@@ -567,19 +996,48 @@ fn binlog_val_to_noria_val(
     }
 }
 
+/// Returns the table modified by the rows events following the given `TABLE_MAP_EVENT`
+fn tme_relation(tme: &binlog::events::TableMapEvent<'static>) -> Relation {
+    Relation {
+        schema: Some(tme.database_name().into()),
+        name: tme.table_name().into(),
+    }
+}
+
+/// Returns the indices of the columns included in the row images of a rows event, given the
+/// event's column bitmap for those images
+fn image_columns<I>(cols: I, num_columns: usize) -> Vec<usize>
+where
+    I: IntoIterator,
+    I::Item: Deref<Target = bool>,
+{
+    cols.into_iter()
+        .take(num_columns)
+        .enumerate()
+        .filter(|(_, present)| **present)
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// Convert a row image from a rows event, containing the values of the columns at the indices in
+/// `columns` (see [`image_columns`]), to the values of those columns
 fn binlog_row_to_noria_row(
     binlog_row: &BinlogRow,
     tme: &binlog::events::TableMapEvent<'static>,
+    columns: &[usize],
 ) -> mysql::Result<Vec<DfValue>> {
     (0..binlog_row.len())
         .map(|idx| {
+            let column = *columns
+                .get(idx)
+                .ok_or_else(|| format!("Unexpected column in row image {:?}", binlog_row))?;
             match binlog_row.as_ref(idx).unwrap() {
                 BinlogValue::Value(val) => {
                     let (kind, meta) = (
-                        tme.get_column_type(idx)
+                        tme.get_column_type(column)
                             .map_err(|e| format!("Unable to get column type {}", e))?
                             .unwrap(),
-                        tme.get_column_metadata(idx).unwrap(),
+                        tme.get_column_metadata(column).unwrap(),
                     );
                     binlog_val_to_noria_val(val, kind, meta)
                 }
@@ -611,6 +1069,127 @@ fn binlog_row_to_noria_row(
         .collect()
 }
 
+/// Build a full row for a table with `num_columns` columns out of the `values` of the columns at
+/// the indices in `columns`, filling in the remaining columns with their default values according
+/// to `schema` (evaluating functions returning the current time as of `now`), or with NULL if we
+/// don't know the table's schema.
+///
+/// Returns an error if `schema` has a different number of columns than the row image, since the
+/// defaults would then be filled in at the wrong positions.
+fn fill_omitted_columns(
+    values: Vec<DfValue>,
+    columns: &[usize],
+    num_columns: usize,
+    schema: Option<&CreateTableBody>,
+    now: NaiveDateTime,
+) -> mysql::Result<Vec<DfValue>> {
+    if columns.len() == num_columns {
+        return Ok(values);
+    }
+
+    if let Some(schema) = schema {
+        if schema.fields.len() != num_columns {
+            return Err(format!(
+                "Row image has {num_columns} columns, but the table's schema has {}",
+                schema.fields.len()
+            )
+            .into());
+        }
+    }
+
+    let mut row = (0..num_columns)
+        .map(
+            |idx| match schema.and_then(|schema| schema.fields.get(idx)) {
+                Some(spec) => column_default_value_at(spec, Dialect::DEFAULT_MYSQL, now)
+                    .map_err(|e| format!("Unable to compute default value: {}", e)),
+                None => Ok(DfValue::None),
+            },
+        )
+        .collect::<Result<Vec<_>, _>>()?;
+    for (idx, value) in columns.iter().zip(values) {
+        row[*idx] = value;
+    }
+    Ok(row)
+}
+
+/// Convert the before and after images of a row changed by an `UPDATE_ROWS_EVENT`, each given
+/// with the indices of the columns they contain (see [`image_columns`]), to the operations which
+/// make that change to a table with `num_columns` columns.
+///
+/// Partial before images (which only contain the primary key when `binlog_row_image` is set to
+/// `minimal`) are turned into operations by the primary key of the table described by `schema`,
+/// and columns omitted from partial after images keep their existing values.
+fn update_operations(
+    (before, before_columns): (Vec<DfValue>, &[usize]),
+    (after, after_columns): (Vec<DfValue>, &[usize]),
+    num_columns: usize,
+    schema: Option<&CreateTableBody>,
+) -> mysql::Result<Vec<TableOperation>> {
+    let after_is_full = after_columns.len() == num_columns;
+    if before_columns.len() == num_columns {
+        let row = if after_is_full {
+            after
+        } else {
+            let mut row = before.clone();
+            for (idx, value) in after_columns.iter().zip(after) {
+                row[*idx] = value;
+            }
+            row
+        };
+        return Ok(vec![
+            TableOperation::DeleteRow { row: before },
+            TableOperation::Insert(row),
+        ]);
+    }
+
+    let key = primary_key_values(&before, before_columns, schema)?;
+    if after_is_full {
+        return Ok(vec![
+            TableOperation::DeleteByKey { key },
+            TableOperation::Insert(after),
+        ]);
+    }
+    let mut update = vec![Modification::None; num_columns];
+    for (idx, value) in after_columns.iter().zip(after) {
+        update[*idx] = Modification::Set(value);
+    }
+    Ok(vec![TableOperation::Update { key, update }])
+}
+
+/// Returns the values of the primary key of the table described by `schema`, in the order of the
+/// key, out of a row image containing the `values` of the columns at the indices in `columns`
+fn primary_key_values(
+    values: &[DfValue],
+    columns: &[usize],
+    schema: Option<&CreateTableBody>,
+) -> mysql::Result<Vec<DfValue>> {
+    let schema = schema.ok_or("Partial row image for a table with an unknown schema")?;
+    let key = schema
+        .keys
+        .iter()
+        .flatten()
+        .find_map(|key| match key {
+            TableKey::PrimaryKey { columns, .. } => Some(columns),
+            _ => None,
+        })
+        .ok_or("Partial row image for a table without a primary key")?;
+
+    key.iter()
+        .map(|col| {
+            schema
+                .fields
+                .iter()
+                .position(|field| field.column.name == col.name)
+                .and_then(|idx| columns.iter().position(|c| *c == idx))
+                .and_then(|pos| values.get(pos))
+                .cloned()
+                .ok_or_else(|| {
+                    format!("Primary key column {} missing from row image", col.name).into()
+                })
+        })
+        .collect()
+}
+
 #[async_trait]
 impl Connector for MySqlBinlogConnector {
     async fn next_action(
@@ -621,4 +1200,338 @@ impl Connector for MySqlBinlogConnector {
         let (action, pos) = self.next_action_inner(until).await?;
         Ok((action, pos.try_into()?))
     }
+
+    fn pending_event_time(&self) -> Option<SystemTime> {
+        // Anything held back from the last action was read from the most recently read event
+        if self.peek.is_some() || self.pending_rows.is_some() || !self.batcher.is_empty() {
+            self.event_time
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use nom_sql::parse_create_table;
+    use readyset_client::consensus::{Authority, LocalAuthority};
+
+    use super::*;
+
+    fn schema(sql: &str) -> CreateTableBody {
+        parse_create_table(nom_sql::Dialect::MySQL, sql)
+            .unwrap()
+            .body
+            .unwrap()
+    }
+
+    #[test]
+    fn fills_omitted_columns_with_defaults() {
+        let schema = schema("CREATE TABLE t (a int, b int DEFAULT 5, c int)");
+        assert_eq!(
+            fill_omitted_columns(
+                vec![DfValue::from(1), DfValue::from(3)],
+                &[0, 2],
+                3,
+                Some(&schema),
+                Utc::now().naive_utc(),
+            )
+            .unwrap(),
+            vec![DfValue::from(1), DfValue::from(5), DfValue::from(3)]
+        );
+    }
+
+    #[test]
+    fn rejects_row_image_with_wrong_arity() {
+        let schema = schema("CREATE TABLE t (a int, b int DEFAULT 5)");
+        fill_omitted_columns(
+            vec![DfValue::from(1)],
+            &[0],
+            3,
+            Some(&schema),
+            Utc::now().naive_utc(),
+        )
+        .unwrap_err();
+    }
+
+    #[test]
+    fn minimal_update_images_update_by_primary_key() {
+        let schema = schema("CREATE TABLE t (a int, b int, c int, PRIMARY KEY (c, a))");
+        assert_eq!(
+            update_operations(
+                (vec![DfValue::from(1), DfValue::from(3)], &[0, 2]),
+                (vec![DfValue::from(5)], &[1]),
+                3,
+                Some(&schema)
+            )
+            .unwrap(),
+            vec![TableOperation::Update {
+                key: vec![DfValue::from(3), DfValue::from(1)],
+                update: vec![
+                    Modification::None,
+                    Modification::Set(DfValue::from(5)),
+                    Modification::None
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn partial_after_images_keep_existing_values() {
+        let before = vec![DfValue::from(1), DfValue::from(2), DfValue::from(3)];
+        assert_eq!(
+            update_operations(
+                (before.clone(), &[0, 1, 2]),
+                (vec![DfValue::from(5)], &[1]),
+                3,
+                None
+            )
+            .unwrap(),
+            vec![
+                TableOperation::DeleteRow { row: before },
+                TableOperation::Insert(vec![DfValue::from(1), DfValue::from(5), DfValue::from(3)]),
+            ]
+        );
+    }
+
+    #[test]
+    fn partial_images_need_a_primary_key() {
+        let schema = schema("CREATE TABLE t (a int, b int)");
+        primary_key_values(&[DfValue::from(1)], &[0], Some(&schema)).unwrap_err();
+        primary_key_values(&[DfValue::from(1)], &[0], None).unwrap_err();
+    }
+
+    fn table(name: &str) -> Relation {
+        Relation {
+            schema: Some("public".into()),
+            name: name.into(),
+        }
+    }
+
+    fn insert(x: i32) -> TableOperation {
+        TableOperation::Insert(vec![DfValue::from(x)])
+    }
+
+    #[test]
+    fn batches_rows_for_one_table() {
+        let mut batcher = RowsBatcher::new(BatchLimits {
+            max_actions: 3,
+            max_latency: Duration::from_secs(3600),
+        });
+        assert!(batcher.is_empty());
+        assert!(batcher.time_left().is_none());
+
+        batcher.add(table("t1"), vec![insert(1)], Some(1)).unwrap();
+        batcher.add(table("t1"), vec![insert(2)], Some(1)).unwrap();
+        assert!(!batcher.is_full());
+        assert!(batcher.time_left().unwrap() > Duration::from_secs(3500));
+
+        // Rows for another table are handed back, to start the next batch
+        let (rejected_table, rejected) = batcher
+            .add(table("t2"), vec![insert(3)], Some(1))
+            .unwrap_err();
+        assert_eq!(rejected_table, table("t2"));
+        assert_eq!(rejected, vec![insert(3)]);
+
+        batcher.add(table("t1"), vec![insert(4)], Some(1)).unwrap();
+        assert!(batcher.is_full());
+        match batcher.take() {
+            Some(ReplicationAction::TableAction {
+                table: flushed,
+                actions,
+                txid,
+            }) => {
+                assert_eq!(flushed, table("t1"));
+                assert_eq!(actions, vec![insert(1), insert(2), insert(4)]);
+                assert_eq!(txid, Some(1));
+            }
+            _ => panic!("Expected a batch to flush"),
+        }
+        assert!(batcher.is_empty());
+        assert!(batcher.take().is_none());
+    }
+
+    #[test]
+    fn batch_is_full_after_max_latency() {
+        let mut batcher = RowsBatcher::new(BatchLimits {
+            max_actions: 1000,
+            max_latency: Duration::from_millis(10),
+        });
+        batcher.add(table("t1"), vec![insert(1)], None).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(batcher.is_full());
+        assert_eq!(batcher.time_left(), Some(Duration::ZERO));
+    }
+
+    fn mysql_url() -> String {
+        format!(
+            "mysql://root:noria@{}:{}/public",
+            std::env::var("MYSQL_HOST").unwrap_or_else(|_| "127.0.0.1".into()),
+            std::env::var("MYSQL_TCP_PORT").unwrap_or_else(|_| "3306".into()),
+        )
+    }
+
+    /// Connect to the binlog at `position`, and skip the rotate event the server sends when we
+    /// connect
+    async fn connect(
+        opts: mysql::Opts,
+        position: BinlogPosition,
+        batch_limits: BatchLimits,
+        control: ReplicationControl,
+    ) -> MySqlBinlogConnector {
+        let noria =
+            ReadySetHandle::make(Arc::new(Authority::from(LocalAuthority::new())), None, None);
+        let mut connector =
+            MySqlBinlogConnector::connect(opts, position, None, None, batch_limits, noria, control)
+                .await
+                .unwrap();
+        connector.next_action_inner(None).await.unwrap();
+        connector
+    }
+
+    async fn binlog_position(client: &mut mysql::Conn) -> BinlogPosition {
+        let status: mysql::Row = client
+            .query_first("SHOW MASTER STATUS")
+            .await
+            .unwrap()
+            .unwrap();
+        BinlogPosition {
+            binlog_file: status.get(0).unwrap(),
+            position: status.get(1).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn flushes_batch_after_max_latency() {
+        let opts = mysql::Opts::from_url(&mysql_url()).unwrap();
+        let mut client =
+            mysql::Conn::new(mysql::OptsBuilder::from_opts(opts.clone()).db_name::<String>(None))
+                .await
+                .unwrap();
+        client
+            .query_drop("CREATE DATABASE IF NOT EXISTS public")
+            .await
+            .unwrap();
+        let position = binlog_position(&mut client).await;
+
+        let control = ReplicationControl::default();
+        let mut connector = connect(
+            opts,
+            position,
+            BatchLimits {
+                max_actions: 1000,
+                max_latency: Duration::from_millis(100),
+            },
+            control.clone(),
+        )
+        .await;
+
+        // Nothing is written to the binlog, so the batch is only flushed once it's been held for
+        // the maximum latency
+        connector
+            .batcher
+            .add(table("idle_batch"), vec![insert(1)], None)
+            .unwrap();
+        let started = Instant::now();
+        let action =
+            tokio::time::timeout(Duration::from_secs(10), connector.next_action_inner(None))
+                .await
+                .expect("Batch should be flushed without reading any more events")
+                .unwrap()
+                .0;
+        assert!(started.elapsed() < Duration::from_secs(5));
+        match action {
+            ReplicationAction::TableAction {
+                table: t, actions, ..
+            } => {
+                assert_eq!(t, table("idle_batch"));
+                assert_eq!(actions, vec![insert(1)]);
+            }
+            _ => panic!("Expected the batch to be flushed"),
+        }
+        assert!(connector.batcher.is_empty());
+        assert_eq!(control.reconnects(), 0);
+    }
+
+    #[tokio::test]
+    async fn flushes_batch_before_reconnecting() {
+        let opts = mysql::Opts::from_url(&mysql_url()).unwrap();
+        let mut client =
+            mysql::Conn::new(mysql::OptsBuilder::from_opts(opts.clone()).db_name::<String>(None))
+                .await
+                .unwrap();
+        client
+            .query_drop(
+                "CREATE DATABASE IF NOT EXISTS public;
+                 USE public;
+                 DROP TABLE IF EXISTS reconnect_mid_batch;
+                 CREATE TABLE reconnect_mid_batch (x int)",
+            )
+            .await
+            .unwrap();
+        let position = binlog_position(&mut client).await;
+
+        let control = ReplicationControl::default();
+        let mut connector = connect(
+            opts,
+            position,
+            BatchLimits {
+                max_actions: 1000,
+                max_latency: Duration::from_secs(3600),
+            },
+            control.clone(),
+        )
+        .await;
+
+        // The server isn't sending heartbeats, so with nothing written to the binlog this makes the
+        // connection look lost while a batch of rows is being read
+        connector.heartbeat_period = Some(Duration::from_millis(100));
+        let table = Relation {
+            schema: Some("public".into()),
+            name: "reconnect_mid_batch".into(),
+        };
+        connector
+            .batcher
+            .add(
+                table.clone(),
+                vec![TableOperation::Insert(vec![DfValue::from(1)])],
+                None,
+            )
+            .unwrap();
+
+        match connector.next_action_inner(None).await.unwrap().0 {
+            ReplicationAction::TableAction {
+                table: flushed,
+                actions,
+                ..
+            } => {
+                assert_eq!(flushed, table);
+                assert_eq!(
+                    actions,
+                    vec![TableOperation::Insert(vec![DfValue::from(1)])]
+                );
+            }
+            _ => panic!("Expected the batch to be flushed"),
+        }
+        assert_eq!(control.reconnects(), 0);
+
+        client
+            .query_drop("INSERT INTO reconnect_mid_batch VALUES (2)")
+            .await
+            .unwrap();
+        let actions = loop {
+            if let ReplicationAction::TableAction { actions, .. } =
+                connector.next_action_inner(None).await.unwrap().0
+            {
+                break actions;
+            }
+        };
+        assert_eq!(control.reconnects(), 1);
+        assert_eq!(
+            actions,
+            vec![TableOperation::Insert(vec![DfValue::from(2)])]
+        );
+    }
 }
@@ -1,8 +1,8 @@
 mod connector;
 mod snapshot;
 
-pub(crate) use connector::MySqlBinlogConnector;
-pub(crate) use snapshot::MySqlReplicator;
+pub(crate) use connector::{BatchLimits, MySqlBinlogConnector};
+pub(crate) use snapshot::{MySqlReplicator, MYSQL_INTERNAL_DBS};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct BinlogPosition {
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::convert::{TryFrom, TryInto};
 use std::error::Error;
 use std::fmt::{self, Display};
@@ -26,6 +27,7 @@ use tracing_futures::Instrument;
 use super::BinlogPosition;
 use crate::db_util::DatabaseSchemas;
 use crate::table_filter::TableFilter;
+use crate::transform::ReplicationTransformHook;
 
 const BATCH_SIZE: usize = 1000; // How many queries to buffer before pushing to ReadySet
 
@@ -52,6 +54,8 @@ pub(crate) struct MySqlReplicator {
     pub(crate) pool: mysql::Pool,
     /// Filters out the desired tables to snapshot and replicate
     pub(crate) table_filter: TableFilter,
+    /// Transforms snapshotted rows before they're written to ReadySet
+    pub(crate) transform: ReplicationTransformHook,
 }
 
 /// Get the list of tables defined in the database
@@ -177,63 +181,8 @@ impl MySqlReplicator {
             ))
             .await?;
 
-        let all_tables_formatted = replicated_tables
-            .iter()
-            .map(|(db, tbl)| format!("`{db}`.`{tbl}`"))
-            .collect::<Vec<_>>();
-
-        for tables in all_tables_formatted.chunks(20) {
-            // There is a default limit of 61 tables per join, so we chunk into smaller joins just
-            // in case
-            let metalock = format!("SELECT 1 FROM {} LIMIT 0", tables.iter().join(","));
-            tx.query_drop(metalock).await?;
-        }
-
-        let mut bad_tables = Vec::new();
-        // Process `CREATE TABLE` statements
-        for (db, table) in replicated_tables.iter() {
-            match create_for_table(&mut tx, db, table, TableKind::BaseTable)
-                .map_err(|e| e.into())
-                .and_then(|create_table| {
-                    debug!(%create_table, "Extending recipe");
-                    db_schemas.extend_create_schema_for_table(
-                        db.to_string(),
-                        table.to_string(),
-                        create_table.clone(),
-                        nom_sql::Dialect::MySQL,
-                    );
-
-                    future::ready(ChangeList::from_str(create_table, Dialect::DEFAULT_MYSQL))
-                })
-                .and_then(|changelist| {
-                    noria.extend_recipe_no_leader_ready(
-                        changelist.with_schema_search_path(vec![db.clone().into()]),
-                    )
-                })
-                .await
-            {
-                Ok(_) => {}
-                Err(error) => {
-                    warn!(%error, "Error extending CREATE TABLE, table will not be used");
-                    // Prevent the table from being snapshotted as well
-                    bad_tables.push((db.clone(), table.clone()));
-
-                    noria
-                        .extend_recipe_no_leader_ready(ChangeList::from_change(
-                            Change::AddNonReplicatedRelation(Relation {
-                                schema: Some(db.into()),
-                                name: table.into(),
-                            }),
-                            Dialect::DEFAULT_MYSQL,
-                        ))
-                        .await?;
-                }
-            }
-        }
-
-        bad_tables
-            .into_iter()
-            .for_each(|(db, table)| self.table_filter.deny_replication(&db, &table));
+        self.install_tables(&mut tx, noria, db_schemas, &replicated_tables)
+            .await?;
 
         // We process all views, regardless of their schemas and the table filter, since a view can
         // exist that only selects from tables in other schemas.
@@ -300,6 +249,77 @@ impl MySqlReplicator {
         Ok((tx, table_list))
     }
 
+    /// Acquire a metadata lock on the given tables within `tx`, then install their `CREATE TABLE`
+    /// statements in ReadySet one-by-one. Any tables that fail to install are marked as
+    /// non-replicated, and denied replication in the table filter.
+    async fn install_tables(
+        &mut self,
+        tx: &mut Transaction<'static>,
+        noria: &mut readyset_client::ReadySetHandle,
+        db_schemas: &mut DatabaseSchemas,
+        tables: &[(String, String)],
+    ) -> ReadySetResult<()> {
+        let all_tables_formatted = tables
+            .iter()
+            .map(|(db, tbl)| format!("`{db}`.`{tbl}`"))
+            .collect::<Vec<_>>();
+
+        for chunk in all_tables_formatted.chunks(20) {
+            // There is a default limit of 61 tables per join, so we chunk into smaller joins just
+            // in case
+            let metalock = format!("SELECT 1 FROM {} LIMIT 0", chunk.iter().join(","));
+            tx.query_drop(metalock).await?;
+        }
+
+        let mut bad_tables = Vec::new();
+        // Process `CREATE TABLE` statements
+        for (db, table) in tables.iter() {
+            match create_for_table(tx, db, table, TableKind::BaseTable)
+                .map_err(|e| e.into())
+                .and_then(|create_table| {
+                    debug!(%create_table, "Extending recipe");
+                    db_schemas.extend_create_schema_for_table(
+                        db.to_string(),
+                        table.to_string(),
+                        create_table.clone(),
+                        nom_sql::Dialect::MySQL,
+                    );
+
+                    future::ready(ChangeList::from_str(create_table, Dialect::DEFAULT_MYSQL))
+                })
+                .and_then(|changelist| {
+                    noria.extend_recipe_no_leader_ready(
+                        changelist.with_schema_search_path(vec![db.clone().into()]),
+                    )
+                })
+                .await
+            {
+                Ok(_) => {}
+                Err(error) => {
+                    warn!(%error, "Error extending CREATE TABLE, table will not be used");
+                    // Prevent the table from being snapshotted as well
+                    bad_tables.push((db.clone(), table.clone()));
+
+                    noria
+                        .extend_recipe_no_leader_ready(ChangeList::from_change(
+                            Change::AddNonReplicatedRelation(Relation {
+                                schema: Some(db.into()),
+                                name: table.into(),
+                            }),
+                            Dialect::DEFAULT_MYSQL,
+                        ))
+                        .await?;
+                }
+            }
+        }
+
+        bad_tables
+            .into_iter()
+            .for_each(|(db, table)| self.table_filter.deny_replication(&db, &table));
+
+        Ok(())
+    }
+
     /// Call `SELECT * FROM table` and convert all rows into a ReadySet row
     /// it may seem inefficient but apparently that is the correct way to
     /// replicate a table, and `mysqldump` and `debezium` do just that
@@ -356,10 +376,13 @@ impl MySqlReplicator {
     /// converting every MySQL row into ReadySet row and calling `insert_many` in batches
     async fn replicate_table(
         mut dumper: TableDumper,
+        mut noria: readyset_client::ReadySetHandle,
         mut table_mutator: readyset_client::Table,
+        transform: ReplicationTransformHook,
         snapshot_report_interval_secs: u16,
     ) -> ReadySetResult<()> {
         let mut cnt = 0;
+        let mut redirected = HashMap::new();
 
         // Query for number of rows first
         let nrows: usize = dumper
@@ -404,10 +427,18 @@ impl MySqlReplicator {
             if rows.len() == BATCH_SIZE {
                 // We aggregate rows into batches and then send them all to noria
                 let send_rows = std::mem::replace(&mut rows, Vec::with_capacity(BATCH_SIZE));
-                table_mutator.insert_many(send_rows).await.map_err(|err| {
-                    progress_percentage_metric.set(0.0);
-                    log_err(err)
-                })?;
+                transform
+                    .insert_snapshot_rows(
+                        &mut noria,
+                        &mut table_mutator,
+                        &mut redirected,
+                        send_rows,
+                    )
+                    .await
+                    .map_err(|err| {
+                        progress_percentage_metric.set(0.0);
+                        log_err(err)
+                    })?;
             }
 
             if snapshot_report_interval_secs != 0
@@ -424,10 +455,13 @@ impl MySqlReplicator {
         }
 
         if !rows.is_empty() {
-            table_mutator.insert_many(rows).await.map_err(|err| {
-                progress_percentage_metric.set(0.0);
-                log_err(err)
-            })?;
+            transform
+                .insert_snapshot_rows(&mut noria, &mut table_mutator, &mut redirected, rows)
+                .await
+                .map_err(|err| {
+                    progress_percentage_metric.set(0.0);
+                    log_err(err)
+                })?;
         }
 
         info!(rows_replicated = %cnt, "Replication finished");
@@ -461,6 +495,137 @@ impl MySqlReplicator {
         result
     }
 
+    /// Bring the set of tables replicated into an already snapshotted ReadySet deployment in line
+    /// with the table filter, which may have changed since the deployment was snapshotted (for
+    /// example if tables were added to `--replication-tables` between restarts).
+    ///
+    /// Upstream tables which pass the filter but don't exist in ReadySet yet are installed and
+    /// snapshotted individually, without snapshotting any other tables. Since each of those tables
+    /// is snapshotted at its own binlog position, streaming replication can catch them up by just
+    /// resuming from the existing replication offset, the same as for a partial snapshot. Tables
+    /// which exist in ReadySet but no longer pass the filter are dropped, and marked as
+    /// non-replicated.
+    ///
+    /// Returns `true` if any tables were added or dropped.
+    pub(crate) async fn sync_table_filter(
+        mut self,
+        noria: &mut readyset_client::ReadySetHandle,
+        db_schemas: &mut DatabaseSchemas,
+        replication_offsets: &ReplicationOffsets,
+        snapshot_report_interval_secs: u16,
+    ) -> ReadySetResult<bool> {
+        let result = self
+            .snapshot_new_tables(
+                noria,
+                db_schemas,
+                replication_offsets,
+                snapshot_report_interval_secs,
+            )
+            .await;
+
+        self.pool.disconnect().await?;
+        result
+    }
+
+    async fn snapshot_new_tables(
+        &mut self,
+        noria: &mut readyset_client::ReadySetHandle,
+        db_schemas: &mut DatabaseSchemas,
+        replication_offsets: &ReplicationOffsets,
+        snapshot_report_interval_secs: u16,
+    ) -> ReadySetResult<bool> {
+        let dropped_tables = replication_offsets
+            .tables
+            .keys()
+            .filter(|table| match &table.schema {
+                Some(schema) => !self
+                    .table_filter
+                    .should_be_processed(schema.as_str(), table.name.as_str()),
+                None => false,
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if !dropped_tables.is_empty() {
+            info!(
+                tables = %dropped_tables.iter().join(", "),
+                "Dropping tables which are no longer replicated"
+            );
+            noria
+                .extend_recipe_no_leader_ready(ChangeList::from_changes(
+                    dropped_tables
+                        .iter()
+                        .flat_map(|table| {
+                            [
+                                Change::Drop {
+                                    name: table.clone(),
+                                    if_exists: true,
+                                },
+                                Change::AddNonReplicatedRelation(table.clone()),
+                            ]
+                        })
+                        .collect::<Vec<_>>(),
+                    Dialect::DEFAULT_MYSQL,
+                ))
+                .await?;
+        }
+
+        let mut tx = self.pool.start_transaction(tx_opts()).await?;
+
+        let _ = tx
+            .query_drop("SET SESSION MAX_EXECUTION_TIME=0")
+            .await
+            .map_err(log_err);
+
+        let new_tables = get_table_list(&mut tx, TableKind::BaseTable)
+            .await?
+            .into_iter()
+            .filter(|(schema, table)| {
+                self.table_filter
+                    .should_be_processed(schema.as_str(), table.as_str())
+                    && !replication_offsets.tables.contains_key(&Relation {
+                        schema: Some(schema.into()),
+                        name: table.into(),
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        if new_tables.is_empty() {
+            return Ok(!dropped_tables.is_empty());
+        }
+
+        info!(
+            tables = %new_tables.iter().map(|(db, tbl)| format!("`{db}`.`{tbl}`")).join(", "),
+            "Snapshotting newly replicated tables"
+        );
+        self.install_tables(&mut tx, noria, db_schemas, &new_tables)
+            .await?;
+
+        let table_list = new_tables
+            .into_iter()
+            // refilter to remove any bad tables that failed to extend recipe
+            .filter(|(schema, table)| {
+                self.table_filter
+                    .should_be_processed(schema.as_str(), table.as_str())
+            })
+            .map(|(schema, name)| Relation {
+                schema: Some(schema.into()),
+                name: name.into(),
+            })
+            .collect::<Vec<_>>();
+
+        let replication_offsets = noria.replication_offsets().await?;
+        self.dump_tables(
+            noria,
+            table_list,
+            &replication_offsets,
+            snapshot_report_interval_secs,
+        )
+        .await?;
+
+        Ok(true)
+    }
+
     /// This is a fallback method to obtaining a database lock, that obtains table level locks
     /// instead of a global lock. The only difference between that and obtaining a global lock
     /// is that some `CREATE TABLE` or `CREATE VIEW` statements may be missed if they happen to
@@ -538,14 +703,22 @@ impl MySqlReplicator {
         span.in_scope(|| info!("Read lock released"));
 
         let table_mutator = noria.table(table.clone()).instrument(span.clone()).await?;
+        let noria = noria.clone();
+        let transform = self.transform.clone();
 
         Ok(tokio::spawn(async move {
             (
                 table,
                 repl_offset,
-                Self::replicate_table(dumper, table_mutator, snapshot_report_interval_secs)
-                    .instrument(span)
-                    .await,
+                Self::replicate_table(
+                    dumper,
+                    noria,
+                    table_mutator,
+                    transform,
+                    snapshot_report_interval_secs,
+                )
+                .instrument(span)
+                .await,
             )
         }))
     }
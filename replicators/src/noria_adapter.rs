@@ -1,7 +1,7 @@
 use std::collections::{hash_map, HashMap, HashSet};
 use std::mem;
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 
 use async_trait::async_trait;
 use database_utils::{DatabaseURL, UpstreamConfig};
@@ -10,7 +10,7 @@ use failpoint_macros::set_failpoint;
 use futures::FutureExt;
 use metrics::{counter, histogram};
 use mysql::prelude::Queryable;
-use mysql::{OptsBuilder, PoolConstraints, PoolOpts, SslOpts};
+use mysql::{OptsBuilder, PoolConstraints, PoolOpts};
 use nom_sql::Relation;
 use postgres_native_tls::MakeTlsConnector;
 use readyset_client::consensus::Authority;
@@ -30,12 +30,15 @@ use tokio::sync::Notify;
 use tracing::{info_span, Instrument};
 use {mysql_async as mysql, tokio_postgres as pgsql};
 
+use crate::control::ReplicationControl;
 use crate::db_util::{CreateSchema, DatabaseSchemas};
-use crate::mysql_connector::{MySqlBinlogConnector, MySqlReplicator};
+use crate::mysql_connector::{BatchLimits, MySqlBinlogConnector, MySqlReplicator};
 use crate::postgres_connector::{
     PostgresReplicator, PostgresWalConnector, PUBLICATION_NAME, REPLICATION_SLOT,
 };
+use crate::schema_drift::{SchemaDriftDetector, UpstreamSchema};
 use crate::table_filter::TableFilter;
+use crate::transform::ReplicationTransformHook;
 
 /// Time to wait for requests to coalesce between snapshotting. Useful for preventing a series of
 /// DDL changes from thrashing snapshotting
@@ -79,6 +82,17 @@ pub(crate) trait Connector {
         last_pos: &ReplicationOffset,
         until: Option<&ReplicationOffset>,
     ) -> ReadySetResult<(ReplicationAction, ReplicationOffset)>;
+
+    /// Returns the time the upstream database committed the oldest event the connector has
+    /// received but not yet returned from [`next_action`](Connector::next_action), if there is
+    /// one and its time is known.
+    ///
+    /// Connectors that know the commit time of the events they receive should also report it to
+    /// the [`ReplicationControl`] as they receive them, so that the replication lag accounts for
+    /// events received while the connector is still waiting for the rest of an action.
+    fn pending_event_time(&self) -> Option<SystemTime> {
+        None
+    }
 }
 
 /// An adapter that converts database events into ReadySet API calls
@@ -104,6 +118,10 @@ pub struct NoriaAdapter {
     table_filter: TableFilter,
     /// If the connector can partially resnapshot a database
     supports_resnapshot: bool,
+    /// Transforms replicated rows before they're written to ReadySet
+    transform: ReplicationTransformHook,
+    /// Used to pause replication
+    control: ReplicationControl,
 }
 
 impl NoriaAdapter {
@@ -113,7 +131,15 @@ impl NoriaAdapter {
         config: UpstreamConfig,
     ) -> ReadySetResult<!> {
         let noria = readyset_client::ReadySetHandle::new(authority).await;
-        NoriaAdapter::start(noria, config, None, telemetry_sender).await
+        NoriaAdapter::start(
+            noria,
+            config,
+            None,
+            telemetry_sender,
+            ReplicationTransformHook::default(),
+            ReplicationControl::default(),
+        )
+        .await
     }
 
     pub async fn start(
@@ -121,6 +147,8 @@ impl NoriaAdapter {
         mut config: UpstreamConfig,
         mut notify: Option<Arc<Notify>>,
         telemetry_sender: TelemetrySender,
+        transform: ReplicationTransformHook,
+        control: ReplicationControl,
     ) -> ReadySetResult<!> {
         let mut resnapshot = false;
         let url: DatabaseURL = config
@@ -129,6 +157,32 @@ impl NoriaAdapter {
             .ok_or_else(|| internal_err!("Replication URL not supplied"))?
             .parse()
             .map_err(|e| invalid_err!("Invalid URL supplied to --upstream-db-url: {e}"))?;
+        let transform = transform.with_row_size_limit(config.row_size_limit());
+
+        // Created once, rather than each time replication restarts, so that the number of
+        // resnapshots caused by each drifted table is remembered across resnapshots
+        let schema_drift_interval =
+            Duration::from_secs(config.schema_drift_check_interval_secs.into());
+        let _schema_drift = if schema_drift_interval.is_zero() {
+            None
+        } else {
+            let upstream = match &url {
+                DatabaseURL::MySQL(options) => UpstreamSchema::MySql(Self::mysql_replication_pool(
+                    &config.apply_mysql_ssl_opts(options.clone()),
+                    &config,
+                )),
+                DatabaseURL::PostgreSQL(options) => {
+                    let connector = config.native_tls_connector().await?;
+                    UpstreamSchema::Postgres(
+                        pg_pool(options.clone(), 1, MakeTlsConnector::new(connector)).await?,
+                    )
+                }
+            };
+            Some(
+                SchemaDriftDetector::new(upstream, schema_drift_interval)
+                    .spawn(noria.clone(), control.clone()),
+            )
+        };
 
         while let Err(err) = match url.clone() {
             DatabaseURL::MySQL(options) => {
@@ -141,22 +195,15 @@ impl NoriaAdapter {
                     &mut notify,
                     resnapshot,
                     &telemetry_sender,
+                    transform.clone(),
+                    control.clone(),
                 )
                 .await
             }
             DatabaseURL::PostgreSQL(options) => {
                 let noria = noria.clone();
                 let config = config.clone();
-                let connector = {
-                    let mut builder = native_tls::TlsConnector::builder();
-                    if config.disable_upstream_ssl_verification {
-                        builder.danger_accept_invalid_certs(true);
-                    }
-                    if let Some(root_cert) = config.get_root_cert().await {
-                        builder.add_root_certificate(root_cert?);
-                    }
-                    builder.build().unwrap() // Never returns an error
-                };
+                let connector = config.native_tls_connector().await?;
                 let tls_connector = postgres_native_tls::MakeTlsConnector::new(connector);
                 let pool = pg_pool(
                     options.clone(),
@@ -174,6 +221,8 @@ impl NoriaAdapter {
                     &telemetry_sender,
                     tls_connector,
                     pool,
+                    transform.clone(),
+                    control.clone(),
                 )
                 .await
             }
@@ -192,6 +241,26 @@ impl NoriaAdapter {
         unreachable!("inner loop will never stop with an Ok status");
     }
 
+    /// Create a new connection pool to use for snapshotting, sized according to
+    /// [`UpstreamConfig::replication_pool_size`]
+    fn mysql_replication_pool(mysql_options: &mysql::Opts, config: &UpstreamConfig) -> mysql::Pool {
+        // The default min is already 10, so we keep that the same to reduce complexity
+        // overhead of too many flags.
+        // The only way PoolConstraints::new() can panic on unwrap is if min is not less
+        // than or equal to max, so we naively reset min if max is below 10.
+        let constraints = if config.replication_pool_size <= 10 {
+            PoolConstraints::new(config.replication_pool_size, config.replication_pool_size)
+                .unwrap()
+        } else {
+            PoolConstraints::new(10, config.replication_pool_size).unwrap()
+        };
+        let pool_opts = PoolOpts::default().with_constraints(constraints);
+        let replicator_opts: mysql_async::Opts = OptsBuilder::from_opts(mysql_options.clone())
+            .pool_opts(pool_opts)
+            .into();
+        mysql::Pool::new(replicator_opts)
+    }
+
     /// Finish the build and begin monitoring the binlog for changes
     /// If noria has no replication offset information, it will replicate the target database in its
     /// entirety to ReadySet before listening on the binlog
@@ -209,15 +278,16 @@ impl NoriaAdapter {
         ready_notify: &mut Option<Arc<Notify>>,
         resnapshot: bool,
         telemetry_sender: &TelemetrySender,
+        transform: ReplicationTransformHook,
+        control: ReplicationControl,
     ) -> ReadySetResult<!> {
         use crate::mysql_connector::BinlogPosition;
 
-        if let Some(cert_path) = config.ssl_root_cert.clone() {
-            let ssl_opts = SslOpts::default().with_root_cert_path(Some(cert_path));
-            mysql_options = OptsBuilder::from_opts(mysql_options)
-                .ssl_opts(ssl_opts)
-                .into();
-        }
+        mysql_options = config.apply_mysql_ssl_opts(mysql_options);
+
+        // Any resnapshot requested before now is handled by snapshotting the tables without a
+        // replication offset below
+        let resnapshot_request = control.latest_resnapshot_request();
 
         // Load the replication offset for all tables and the schema from ReadySet
         let mut replication_offsets = noria.replication_offsets().await?;
@@ -225,6 +295,7 @@ impl NoriaAdapter {
         let table_filter = TableFilter::try_new(
             nom_sql::Dialect::MySQL,
             config.replication_tables.take(),
+            config.replication_tables_ignore.take(),
             mysql_options.db_name(),
         )?;
 
@@ -233,22 +304,7 @@ impl NoriaAdapter {
         let pos = match (replication_offsets.max_offset()?, resnapshot) {
             (None, _) | (_, true) => {
                 let span = info_span!("taking database snapshot");
-                // The default min is already 10, so we keep that the same to reduce complexity
-                // overhead of too many flags.
-                // The only way PoolConstraints::new() can panic on unwrap is if min is not less
-                // than or equal to max, so we naively reset min if max is below 10.
-                let constraints = if config.replication_pool_size <= 10 {
-                    PoolConstraints::new(config.replication_pool_size, config.replication_pool_size)
-                        .unwrap()
-                } else {
-                    PoolConstraints::new(10, config.replication_pool_size).unwrap()
-                };
-                let pool_opts = PoolOpts::default().with_constraints(constraints);
-                let replicator_opts: mysql_async::Opts =
-                    OptsBuilder::from_opts(mysql_options.clone())
-                        .pool_opts(pool_opts)
-                        .into();
-                let pool = mysql::Pool::new(replicator_opts);
+                let pool = Self::mysql_replication_pool(&mysql_options, &config);
 
                 // Query mysql server version
                 let db_version = pool
@@ -263,6 +319,7 @@ impl NoriaAdapter {
                 let replicator = MySqlReplicator {
                     pool,
                     table_filter: table_filter.clone(),
+                    transform: transform.clone(),
                 };
 
                 let snapshot_start = Instant::now();
@@ -332,7 +389,32 @@ impl NoriaAdapter {
 
                 pos
             }
-            (Some(pos), _) => pos.clone().into(),
+            (Some(pos), _) => {
+                let pos: BinlogPosition = pos.clone().into();
+
+                // The table filter may have changed since we last snapshotted, so snapshot any
+                // tables that should now be replicated but weren't before, and drop any that
+                // shouldn't be replicated anymore.
+                let replicator = MySqlReplicator {
+                    pool: Self::mysql_replication_pool(&mysql_options, &config),
+                    table_filter: table_filter.clone(),
+                    transform: transform.clone(),
+                };
+                if replicator
+                    .sync_table_filter(
+                        &mut noria,
+                        &mut db_schemas,
+                        &replication_offsets,
+                        config.snapshot_report_interval_secs,
+                    )
+                    .instrument(info_span!("syncing replicated tables"))
+                    .await?
+                {
+                    replication_offsets = noria.replication_offsets().await?;
+                }
+
+                pos
+            }
         };
 
         // TODO: it is possible that the binlog position from noria is no longer
@@ -343,6 +425,14 @@ impl NoriaAdapter {
                 mysql_options.clone(),
                 pos.clone(),
                 config.replication_server_id,
+                (config.replication_heartbeat_period_secs > 0)
+                    .then(|| Duration::from_secs(config.replication_heartbeat_period_secs.into())),
+                BatchLimits {
+                    max_actions: config.replication_max_batch_size.max(1),
+                    max_latency: Duration::from_millis(config.replication_max_batch_latency_ms),
+                },
+                noria.clone(),
+                control.clone(),
             )
             .await?,
         );
@@ -356,6 +446,8 @@ impl NoriaAdapter {
             table_filter,
             supports_resnapshot: true,
             dialect: Dialect::DEFAULT_MYSQL,
+            transform,
+            control,
         };
 
         let mut current_pos: ReplicationOffset = pos.try_into()?;
@@ -383,6 +475,7 @@ impl NoriaAdapter {
         if let Some(notify) = ready_notify.take() {
             notify.notify_one();
         }
+        adapter.control.resnapshot_completed(resnapshot_request);
 
         adapter.main_loop(&mut current_pos, None).await?;
 
@@ -399,11 +492,17 @@ impl NoriaAdapter {
         telemetry_sender: &TelemetrySender,
         tls_connector: MakeTlsConnector,
         pool: deadpool_postgres::Pool,
+        transform: ReplicationTransformHook,
+        control: ReplicationControl,
     ) -> ReadySetResult<!> {
         let dbname = pgsql_opts.get_dbname().ok_or_else(|| {
             ReadySetError::ReplicationFailed("No database specified for replication".to_string())
         })?;
 
+        // Any resnapshot requested before now is handled by snapshotting the tables without a
+        // replication offset below
+        let resnapshot_request = control.latest_resnapshot_request();
+
         // Attempt to retrieve the latest replication offset from ReadySet-server, if none is
         // present begin the snapshot process
         let replication_offsets = noria.replication_offsets().await?;
@@ -413,6 +512,7 @@ impl NoriaAdapter {
         let table_filter = TableFilter::try_new(
             nom_sql::Dialect::PostgreSQL,
             config.replication_tables.take(),
+            config.replication_tables_ignore.take(),
             None,
         )?;
 
@@ -443,6 +543,7 @@ impl NoriaAdapter {
                 config,
                 pos,
                 tls_connector.clone(),
+                control.clone(),
             )
             .await?,
         );
@@ -485,9 +586,14 @@ impl NoriaAdapter {
                 .and_then(|row| row.try_get::<_, String>(0))
                 .unwrap_or_else(|_| "unknown".to_owned());
 
-            let mut replicator =
-                PostgresReplicator::new(&mut client, pool, &mut noria, table_filter.clone())
-                    .await?;
+            let mut replicator = PostgresReplicator::new(
+                &mut client,
+                pool,
+                &mut noria,
+                table_filter.clone(),
+                transform.clone(),
+            )
+            .await?;
 
             select! {
                 snapshot_result = replicator.snapshot_to_noria(&replication_slot, &mut create_schema, snapshot_report_interval_secs).fuse() =>  {
@@ -552,6 +658,8 @@ impl NoriaAdapter {
             table_filter,
             supports_resnapshot: true,
             dialect: Dialect::DEFAULT_POSTGRESQL,
+            transform,
+            control,
         };
 
         if min_pos != max_pos {
@@ -563,6 +671,7 @@ impl NoriaAdapter {
         if let Some(notify) = ready_notify.take() {
             notify.notify_one();
         }
+        adapter.control.resnapshot_completed(resnapshot_request);
 
         info!("Streaming replication started");
 
@@ -689,14 +798,11 @@ impl NoriaAdapter {
     async fn handle_table_actions(
         &mut self,
         table: Relation,
-        mut actions: Vec<TableOperation>,
+        actions: Vec<TableOperation>,
         txid: Option<u64>,
         pos: ReplicationOffset,
     ) -> ReadySetResult<()> {
-        // Send the rows as are
-        let table_mutator = if let Some(table) = self.mutator_for_table(&table).await? {
-            table
-        } else {
+        if self.mutator_for_table(&table).await?.is_none() {
             // The only error we are semi "ok" to ignore for table actions is when a table is not
             // found. Failing to execute an action for an existing table may very well get noria
             // into an inconsistent state. This may happen if eg. a worker fails.
@@ -711,9 +817,51 @@ impl NoriaAdapter {
                 );
             }
             return Ok(());
-        };
-        actions.push(TableOperation::SetReplicationOffset(pos.clone()));
-        table_mutator.perform_all(actions).await?;
+        }
+
+        if self.transform.has_transform()
+            && ReplicationTransformHook::has_keyed_operations(&actions)
+        {
+            // Operations without a full row can't be transformed, so applying them unchanged would
+            // bypass the transform. Stop replicating the table instead.
+            return self.deny_untransformable_table(table).await;
+        }
+
+        let mut actions = self.transform.apply(&table, actions);
+        // Update the replication offset for the table along with the last of its actions
+        match actions.last_mut() {
+            Some((target, actions)) if *target == table => {
+                actions.push(TableOperation::SetReplicationOffset(pos.clone()))
+            }
+            _ => actions.push((
+                table.clone(),
+                vec![TableOperation::SetReplicationOffset(pos.clone())],
+            )),
+        }
+
+        let transform = self.transform.clone();
+        for (target, mut actions) in actions {
+            match self.mutator_for_table(&target).await? {
+                Some(table_mutator) => {
+                    transform.check_row_sizes(&target, &table_mutator.key_columns(), &mut actions);
+                    match table_mutator.perform_all(actions).await {
+                        Err(error) if error.caused_by_replication_conflict() => {
+                            return self.resnapshot_conflicting_table(target, error).await;
+                        }
+                        res => res?,
+                    }
+                }
+                None => {
+                    if self.warned_missing_tables.insert(target.clone()) {
+                        warn!(
+                            table_name = %target,
+                            num_actions = actions.len(),
+                            "Could not find table to redirect replicated rows to, discarding actions"
+                        );
+                    }
+                }
+            }
+        }
 
         // If there was a transaction id associated, propagate the timestamp with that transaction
         // id.
@@ -721,9 +869,12 @@ impl NoriaAdapter {
         // TODO(vlad): We have to propagate txid to every table or else we won't be able to ensure
         // proper read after write
         if let Some(tx) = txid {
-            let mut timestamp = Timestamp::default();
-            timestamp.map.insert(table_mutator.node, tx);
-            table_mutator.update_timestamp(timestamp).await?;
+            if let Some(table_mutator) = self.mutator_for_table(&table).await? {
+                let mut timestamp = Timestamp::default();
+                timestamp.map.insert(table_mutator.node, tx);
+                table_mutator.update_timestamp(timestamp.clone()).await?;
+                self.control.advance_timestamp(&timestamp);
+            }
         }
 
         self.replication_offsets.tables.insert(table, Some(pos));
@@ -801,6 +952,17 @@ impl NoriaAdapter {
         &mut self,
         position: &mut ReplicationOffset,
         until: Option<ReplicationOffset>,
+    ) -> ReadySetResult<()> {
+        self.control.start_streaming();
+        let res = self.main_loop_inner(position, until).await;
+        self.control.stop_streaming();
+        res
+    }
+
+    async fn main_loop_inner(
+        &mut self,
+        position: &mut ReplicationOffset,
+        until: Option<ReplicationOffset>,
     ) -> ReadySetResult<()> {
         loop {
             set_failpoint!(failpoints::UPSTREAM, |_| ReadySetResult::Err(
@@ -813,13 +975,26 @@ impl NoriaAdapter {
                 return Ok(());
             }
 
-            let (action, pos) = self.connector.next_action(position, until.as_ref()).await?;
+            self.control.wait_until_resumed().await;
+            let (action, pos) = tokio::select! {
+                res = self.connector.next_action(position, until.as_ref()) => res?,
+                _ = self.control.resnapshot_requested() => {
+                    info!("Resnapshot requested, restarting replication");
+                    return Err(ReadySetError::ResnapshotNeeded);
+                }
+            };
+            // Replication may have been paused while we were waiting for the next action, in
+            // which case we hold on to it without applying it until replication is resumed
+            self.control.wait_until_resumed().await;
             *position = pos.clone();
             debug!(%position, "Received replication action");
 
             trace!(?action);
 
-            if let Err(err) = self.handle_action(action, pos, until.is_some()).await {
+            let res = self.handle_action(action, pos, until.is_some()).await;
+            self.control
+                .events_applied(self.connector.pending_event_time());
+            if let Err(err) = res {
                 if matches!(err, ReadySetError::ResnapshotNeeded) {
                     info!("Change in DDL requires partial resnapshot");
                 } else {
@@ -854,7 +1029,7 @@ impl NoriaAdapter {
                     // successfully removing the table from readyset--that would lead to permanently
                     // stale results.
                     set_failpoint_return_err!("ignore-table-fail-dropping-table");
-                    self.remove_table_from_readyset(table.clone()).await.map_err(|error| {
+                    self.remove_table_from_readyset(table.clone(), true).await.map_err(|error| {
                         error!(%error, "failed to remove ignored table from readyset, will need to resnapshot it to continue");
                         ReadySetError::ResnapshotNeeded
                     })?;
@@ -874,6 +1049,46 @@ impl NoriaAdapter {
         }
     }
 
+    /// Drop a table which rejected a replicated write because the write conflicted with the
+    /// table's state (which base tables do if `--replication-conflict-policy` is `resnapshot`),
+    /// and return [`ReadySetError::ResnapshotNeeded`] so that the table is recreated and
+    /// snapshotted again.
+    async fn resnapshot_conflicting_table(
+        &mut self,
+        table: Relation,
+        error: ReadySetError,
+    ) -> ReadySetResult<()> {
+        warn!(
+            %table,
+            %error,
+            "Replicated write conflicted with the state of table, will resnapshot it"
+        );
+        self.remove_table_from_readyset(table, false).await?;
+
+        if let Some(pos) = self.replication_offsets.max_offset()?.cloned() {
+            // Forward all positions to the maximum position to avoid needless replay later
+            self.handle_log_position(pos).await?;
+        }
+        Err(ReadySetError::ResnapshotNeeded)
+    }
+
+    /// Drop a table for which operations without a full row were replicated while a
+    /// [`ReplicationTransform`](crate::ReplicationTransform) is configured, and stop replicating
+    /// it, since those operations can't be passed to the transform.
+    async fn deny_untransformable_table(&mut self, table: Relation) -> ReadySetResult<()> {
+        warn!(
+            %table,
+            "Replicated change to table does not contain the full row, so it can't be transformed. \
+             The table will not be replicated; set its REPLICA IDENTITY to FULL and resnapshot \
+             to replicate it"
+        );
+        if let Some(schema) = &table.schema {
+            self.table_filter
+                .deny_replication(schema.as_str(), table.name.as_str());
+        }
+        self.remove_table_from_readyset(table, true).await
+    }
+
     /// When schema changes there is a risk the cached mutators will no longer be in sync
     /// and we need to drop them all
     fn clear_mutator_cache(&mut self) {
@@ -897,23 +1112,27 @@ impl NoriaAdapter {
     }
 
     /// Remove the table referenced by the provided schema and table name from our base table and
-    /// dataflow state (if any).
-    async fn remove_table_from_readyset(&mut self, table: Relation) -> ReadySetResult<()> {
+    /// dataflow state (if any). If `non_replicated` is true, the table is also recorded as a
+    /// relation that exists upstream but isn't replicated; otherwise it is recreated by the next
+    /// snapshot.
+    async fn remove_table_from_readyset(
+        &mut self,
+        table: Relation,
+        non_replicated: bool,
+    ) -> ReadySetResult<()> {
         info!(%table, "Removing table state from readyset");
         self.replication_offsets.tables.remove(&table);
         self.mutator_map.remove(&table);
         // Dropping the table cleans up any dataflow state that may have been made as well as
         // cleaning up the base table on disk.
-        let changelist = ChangeList::from_changes(
-            vec![
-                Change::Drop {
-                    name: table.clone(),
-                    if_exists: true,
-                },
-                Change::AddNonReplicatedRelation(table),
-            ],
-            self.dialect,
-        );
+        let mut changes = vec![Change::Drop {
+            name: table.clone(),
+            if_exists: true,
+        }];
+        if non_replicated {
+            changes.push(Change::AddNonReplicatedRelation(table));
+        }
+        let changelist = ChangeList::from_changes(changes, self.dialect);
 
         self.noria.extend_recipe(changelist).await?;
         Ok(())
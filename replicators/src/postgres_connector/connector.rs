@@ -1,3 +1,5 @@
+use std::time::SystemTime;
+
 use async_trait::async_trait;
 use database_utils::UpstreamConfig;
 use futures::FutureExt;
@@ -14,6 +16,7 @@ use super::ddl_replication::setup_ddl_replication;
 use super::lsn::Lsn;
 use super::wal_reader::{WalEvent, WalReader};
 use super::{PostgresPosition, PUBLICATION_NAME, REPLICATION_SLOT};
+use crate::control::ReplicationControl;
 use crate::db_util::error_is_slot_not_found;
 use crate::noria_adapter::{Connector, ReplicationAction};
 use crate::postgres_connector::wal::WalError;
@@ -43,6 +46,8 @@ pub struct PostgresWalConnector {
     next_position: Option<PostgresPosition>,
     /// The replication slot if was created for this connector
     pub(crate) replication_slot: Option<CreatedSlot>,
+    /// Used to record the commit times of the transactions events are received from
+    control: ReplicationControl,
 }
 
 /// The decoded response to `IDENTIFY_SYSTEM`
@@ -88,6 +93,7 @@ impl PostgresWalConnector {
         config: UpstreamConfig,
         next_position: Option<PostgresPosition>,
         tls_connector: MakeTlsConnector,
+        control: ReplicationControl,
     ) -> ReadySetResult<Self> {
         if !config.disable_setup_ddl_replication {
             setup_ddl_replication(pg_config.clone(), tls_connector.clone()).await?;
@@ -104,6 +110,7 @@ impl PostgresWalConnector {
             peek: None,
             next_position,
             replication_slot: None,
+            control,
         };
 
         if next_position.is_none() {
@@ -157,12 +164,18 @@ impl PostgresWalConnector {
         let PostgresWalConnector {
             reader,
             connection_handle,
+            control,
             ..
         } = self;
 
         if let Some(reader) = reader.as_mut() {
             select! {
-                ev = reader.next_event().fuse() => ev,
+                ev = reader.next_event().fuse() => {
+                    if let Some(commit_time) = reader.commit_time() {
+                        control.event_received(commit_time);
+                    }
+                    ev
+                },
                 err = connection_handle.fuse() => match err.unwrap() { // This unwrap is ok, because it is on the handle
                     Ok(_) => unreachable!(), // Unreachable because it runs in infinite loop unless errors
                     Err(err) => Err(WalError::ReadySetError(err.into())),
@@ -638,4 +651,13 @@ impl Connector for PostgresWalConnector {
             }
         }
     }
+
+    fn pending_event_time(&self) -> Option<SystemTime> {
+        // An event held back from the last action belongs to the transaction currently being read
+        if self.peek.is_some() {
+            self.reader.as_ref().and_then(WalReader::commit_time)
+        } else {
+            None
+        }
+    }
 }
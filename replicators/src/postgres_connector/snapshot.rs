@@ -1,3 +1,4 @@
+use std::collections::{HashMap, HashSet};
 use std::convert::{TryFrom, TryInto};
 use std::error::Error;
 use std::fmt::{self, Display};
@@ -26,6 +27,7 @@ use super::connector::CreatedSlot;
 use super::PostgresPosition;
 use crate::db_util::CreateSchema;
 use crate::table_filter::TableFilter;
+use crate::transform::ReplicationTransformHook;
 
 const BATCH_SIZE: usize = 1024; // How many queries to buffer before pushing to ReadySet
 
@@ -37,6 +39,8 @@ pub struct PostgresReplicator<'a> {
     pub(crate) noria: &'a mut readyset_client::ReadySetHandle,
     /// Filters out tables we are not interested in
     pub(crate) table_filter: TableFilter,
+    /// Transforms snapshotted rows before they're written to ReadySet
+    pub(crate) transform: ReplicationTransformHook,
 }
 
 #[derive(Debug)]
@@ -446,10 +450,13 @@ impl TableDescription {
     async fn dump<'a>(
         &self,
         transaction: &'a deadpool_postgres::Transaction<'a>,
+        mut noria: readyset_client::ReadySetHandle,
         mut noria_table: readyset_client::Table,
+        transform: &ReplicationTransformHook,
         snapshot_report_interval_secs: u16,
     ) -> ReadySetResult<()> {
         let mut cnt = 0;
+        let mut redirected = HashMap::new();
 
         let nrows = transaction
             .query_one(
@@ -504,11 +511,13 @@ impl TableDescription {
             // Accumulate as many inserts as possible before calling into noria, as
             // those calls can be quite expensive
             if noria_rows.len() >= BATCH_SIZE {
-                noria_table
-                    .insert_many(std::mem::replace(
-                        &mut noria_rows,
-                        Vec::with_capacity(BATCH_SIZE),
-                    ))
+                transform
+                    .insert_snapshot_rows(
+                        &mut noria,
+                        &mut noria_table,
+                        &mut redirected,
+                        std::mem::replace(&mut noria_rows, Vec::with_capacity(BATCH_SIZE)),
+                    )
                     .await
                     .map_err(|err| {
                         progress_percentage_metric.set(0.0);
@@ -530,10 +539,13 @@ impl TableDescription {
         }
 
         if !noria_rows.is_empty() {
-            noria_table.insert_many(noria_rows).await.map_err(|err| {
-                progress_percentage_metric.set(0.0);
-                err
-            })?;
+            transform
+                .insert_snapshot_rows(&mut noria, &mut noria_table, &mut redirected, noria_rows)
+                .await
+                .map_err(|err| {
+                    progress_percentage_metric.set(0.0);
+                    err
+                })?;
         }
 
         info!(rows_replicated = %cnt, "Snapshotting finished");
@@ -549,6 +561,7 @@ impl<'a> PostgresReplicator<'a> {
         pool: deadpool_postgres::Pool,
         noria: &'a mut readyset_client::ReadySetHandle,
         table_filter: TableFilter,
+        transform: ReplicationTransformHook,
     ) -> ReadySetResult<PostgresReplicator<'a>> {
         let transaction = client
             .build_transaction()
@@ -563,14 +576,18 @@ impl<'a> PostgresReplicator<'a> {
             pool,
             noria,
             table_filter,
+            transform,
         })
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn snapshot_table(
         pool: deadpool_postgres::Pool,
         span: tracing::Span,
         table: &TableDescription,
+        noria: readyset_client::ReadySetHandle,
         noria_table: readyset_client::Table,
+        transform: &ReplicationTransformHook,
         snapshot_report_interval_secs: u16,
         snapshot_name: String,
     ) -> ReadySetResult<()> {
@@ -589,7 +606,13 @@ impl<'a> PostgresReplicator<'a> {
         transaction.query(query.as_str(), &[]).await?;
 
         table
-            .dump(&transaction, noria_table, snapshot_report_interval_secs)
+            .dump(
+                &transaction,
+                noria,
+                noria_table,
+                transform,
+                snapshot_report_interval_secs,
+            )
             .instrument(span.clone())
             .await
     }
@@ -609,10 +632,33 @@ impl<'a> PostgresReplicator<'a> {
         let view_list = self.get_table_list(TableKind::View).await?;
         let custom_types = self.get_custom_types().await?;
 
-        let (table_list, non_replicated) = table_list.into_iter().partition::<Vec<_>, _>(|tbl| {
-            self.table_filter
-                .should_be_processed(tbl.schema.as_str(), tbl.name.as_str())
-        });
+        let (mut table_list, mut non_replicated) =
+            table_list.into_iter().partition::<Vec<_>, _>(|tbl| {
+                self.table_filter
+                    .should_be_processed(tbl.schema.as_str(), tbl.name.as_str())
+            });
+
+        if self.transform.has_transform() {
+            // Updates and deletes for tables without a `REPLICA IDENTITY` of `FULL` are replicated
+            // with just the key of the row, which can't be passed to the transform
+            let partial_identity = self.get_tables_without_full_replica_identity().await?;
+            let (untransformable, transformable) =
+                table_list.into_iter().partition::<Vec<_>, _>(|tbl| {
+                    partial_identity.contains(&(tbl.schema.clone(), tbl.name.clone()))
+                });
+            for tbl in &untransformable {
+                warn!(
+                    schema = %tbl.schema,
+                    table = %tbl.name,
+                    "Table does not have a REPLICA IDENTITY of FULL, so replicated changes to it \
+                     can't be transformed. Table will not be used"
+                );
+                self.table_filter
+                    .deny_replication(tbl.schema.as_str(), tbl.name.as_str());
+            }
+            non_replicated.extend(untransformable);
+            table_list = transformable;
+        }
 
         // We don't filter the view list by schemas since a view could be in schema 1 (that may not
         // be replicated), but refer to only tables in schema 2 that are all replicated. If we try
@@ -793,7 +839,9 @@ impl<'a> PostgresReplicator<'a> {
                 pool,
                 span,
                 table,
+                self.noria.clone(),
                 noria_table,
+                &self.transform,
                 snapshot_report_interval_secs,
                 snapshot_name,
             ))
@@ -866,6 +914,25 @@ impl<'a> PostgresReplicator<'a> {
         tables.into_iter().map(TryInto::try_into).collect()
     }
 
+    /// Retrieve the schemas and names of all regular tables whose `REPLICA IDENTITY` isn't `FULL`
+    async fn get_tables_without_full_replica_identity(
+        &mut self,
+    ) -> Result<HashSet<(String, String)>, pgsql::Error> {
+        let query = r"
+        SELECT n.nspname, c.relname
+        FROM pg_catalog.pg_class c
+        LEFT JOIN pg_catalog.pg_namespace n
+        ON n.oid = c.relnamespace
+        WHERE c.relkind = 'r' AND c.relreplident <> 'f'
+        ";
+
+        let tables = self.transaction.query(query, &[]).await?;
+        tables
+            .into_iter()
+            .map(|row| Ok((row.try_get(0)?, row.try_get(1)?)))
+            .collect()
+    }
+
     /// Retrieve a list of custom types
     ///
     /// Currently this is limited to enum types since that's all we support, but in the future this
@@ -1,6 +1,7 @@
 use std::collections::{HashMap, HashSet};
 use std::convert::TryInto;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use bit_vec::BitVec;
 use mysql_time::MySqlTime;
@@ -18,6 +19,10 @@ use super::lsn::Lsn;
 use super::wal::{self, RelationMapping, WalData, WalError, WalRecord};
 use crate::postgres_connector::wal::TupleEntry;
 
+/// The PostgreSQL epoch (2000-01-01), which commit timestamps are relative to, in seconds since the
+/// UNIX epoch
+const POSTGRES_EPOCH: u64 = 946_684_800;
+
 /// The names of the schema table that DDL replication logs will be written to
 pub(crate) const DDL_REPLICATION_LOG_SCHEMA: &str = "readyset";
 pub(crate) const DDL_REPLICATION_LOG_TABLE: &str = "ddl_replication_log";
@@ -35,6 +40,8 @@ pub struct WalReader {
     relations: HashMap<i32, Relation>,
     /// Keeps track of the OIDs of all custom types we've seen
     custom_types: HashSet<u32>,
+    /// The commit time of the transaction currently being read, if any
+    commit_time: Option<SystemTime>,
 }
 
 #[derive(Debug)]
@@ -81,15 +88,22 @@ impl WalReader {
         WalReader {
             relations: Default::default(),
             custom_types: Default::default(),
+            commit_time: None,
             wal,
         }
     }
 
+    /// Returns the time the transaction currently being read was committed upstream, if known
+    pub(crate) fn commit_time(&self) -> Option<SystemTime> {
+        self.commit_time
+    }
+
     pub(crate) async fn next_event(&mut self) -> Result<(WalEvent, Lsn), WalError> {
         let WalReader {
             wal,
             relations,
             custom_types,
+            commit_time,
         } = self;
 
         loop {
@@ -121,7 +135,10 @@ impl WalReader {
             trace!(?record);
 
             match record {
-                WalRecord::Commit { .. } => return Ok((WalEvent::Commit, end)),
+                WalRecord::Commit { .. } => {
+                    *commit_time = None;
+                    return Ok((WalEvent::Commit, end));
+                }
                 WalRecord::Relation(mapping) => {
                     // Store the relation in the hash map for future use
                     let id = mapping.id;
@@ -368,7 +385,13 @@ impl WalReader {
                         }
                     }
                 }
-                WalRecord::Begin { .. } => {}
+                WalRecord::Begin { timestamp, .. } => {
+                    *commit_time = Some(
+                        UNIX_EPOCH
+                            + Duration::from_secs(POSTGRES_EPOCH)
+                            + Duration::from_micros(timestamp.max(0) as u64),
+                    );
+                }
                 WalRecord::Message {
                     prefix,
                     payload,
@@ -0,0 +1,621 @@
+//! Detection of upstream schema changes that were missed by replication.
+//!
+//! Normally, ReadySet learns about changes to the schema of the upstream database from the DDL
+//! in the replication stream. If the replication stream doesn't contain a change (for example if
+//! replication was started from a position after the change was made), the tables in ReadySet can
+//! silently diverge from their upstream counterparts, and rows get written to the wrong columns.
+//!
+//! To guard against this, the replicator periodically compares a fingerprint of the names and
+//! types of the columns of each replicated table in ReadySet with a fingerprint of the columns of
+//! the same table in the upstream database's catalog. Upstream column types are parsed the same
+//! way they are when a table is snapshotted, so that a table which hasn't changed has the same
+//! fingerprint in both places. Since ReadySet can legitimately lag behind the
+//! upstream database, a table is only considered to have drifted once the same mismatch has been
+//! seen by two consecutive checks. Once drift is detected, it's logged and the replicator
+//! resnapshots, which recreates the drifted tables with their upstream schema and snapshots their
+//! data again. Recreating a table drops all the caches which read from it; the adapters will
+//! migrate those caches again against the new schema the next time they're queried.
+//!
+//! Checks run on their own timer, independently of the replication stream, so drift is detected
+//! even if no replication events arrive. Checks are skipped while the replicator isn't streaming
+//! (for example, while it's snapshotting), since there's nothing to compare against until it is.
+//! If a table's drift survives [`MAX_DRIFT_RESNAPSHOTS`] resnapshots (for example, because the
+//! upstream table has a column type which ReadySet snapshots as a different type), the drift is
+//! logged as an error and the table isn't resnapshotted for it again, rather than resnapshotting
+//! forever. The count is reset once the table stops drifting, or drifts in a different way.
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+use itertools::Itertools;
+use metrics::counter;
+use mysql::prelude::Queryable;
+use nom_sql::{parse_sql_type, ColumnSpecification, Dialect, Relation, SqlIdentifier, SqlType};
+use readyset_client::metrics::recorded;
+use readyset_client::{ReadySetHandle, ReadySetResult};
+use readyset_tracing::{error, warn};
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use {mysql_async as mysql, tokio_postgres as pgsql};
+
+use crate::control::ReplicationControl;
+use crate::mysql_connector::MYSQL_INTERNAL_DBS;
+
+/// The number of times a table is resnapshotted because of the same drift before giving up on it
+pub(crate) const MAX_DRIFT_RESNAPSHOTS: usize = 3;
+
+/// Query used to load the columns of all tables in a MySQL database, in order. `column_type` is
+/// the full type of the column (including its length, precision or enum values, and whether it's
+/// unsigned) in the same format as `SHOW CREATE TABLE`, which is what tables are created from
+/// when they're snapshotted.
+const MYSQL_COLUMNS_QUERY: &str = "SELECT table_schema, table_name, column_name, column_type \
+     FROM information_schema.columns \
+     ORDER BY table_schema, table_name, ordinal_position";
+
+/// Query used to load the columns of all tables in a PostgreSQL database, in order. Column types
+/// are formatted the same way as when tables are snapshotted (see
+/// `postgres_connector::snapshot`), which names enum types (and arrays of them) explicitly.
+const POSTGRES_COLUMNS_QUERY: &str = r#"
+    SELECT
+        n.nspname::text,
+        c.relname::text,
+        a.attname::text,
+        CASE
+        WHEN t.typtype = 'e'
+        THEN format('"%s"."%s"', tn.nspname, t.typname)
+        WHEN member_t.oid IS NOT NULL AND member_t.typtype = 'e'
+        THEN format('"%s"."%s"[]', member_tn.nspname, member_t.typname)
+        ELSE pg_catalog.format_type(a.atttypid, a.atttypmod)
+        END
+    FROM pg_catalog.pg_attribute a
+    JOIN pg_catalog.pg_class c ON a.attrelid = c.oid
+    JOIN pg_catalog.pg_namespace n ON c.relnamespace = n.oid
+    JOIN pg_catalog.pg_type t ON a.atttypid = t.oid
+    JOIN pg_catalog.pg_namespace tn ON t.typnamespace = tn.oid
+    LEFT JOIN pg_catalog.pg_type member_t ON t.typelem = member_t.oid
+    LEFT JOIN pg_catalog.pg_namespace member_tn ON member_t.typnamespace = member_tn.oid
+    WHERE c.relkind IN ('r', 'p')
+    AND a.attnum > 0
+    AND NOT a.attisdropped
+    AND n.nspname NOT IN ('pg_catalog', 'information_schema')
+    ORDER BY n.nspname, c.relname, a.attnum
+    "#;
+
+/// The name and type of a column of a table, as compared between ReadySet and the upstream
+/// database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ColumnSchema {
+    pub(crate) name: SqlIdentifier,
+    /// The type of the column, or `None` if the upstream type couldn't be parsed (in which case
+    /// the table couldn't have been snapshotted with that type either)
+    pub(crate) sql_type: Option<SqlType>,
+}
+
+impl ColumnSchema {
+    fn parse(dialect: Dialect, name: String, sql_type: &str) -> Self {
+        Self {
+            name: name.into(),
+            sql_type: parse_sql_type(dialect, sql_type).ok(),
+        }
+    }
+}
+
+impl From<&ColumnSpecification> for ColumnSchema {
+    fn from(spec: &ColumnSpecification) -> Self {
+        Self {
+            name: spec.column.name.clone(),
+            sql_type: Some(spec.sql_type.clone()),
+        }
+    }
+}
+
+impl Display for ColumnSchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.sql_type {
+            Some(sql_type) => write!(f, "{} {}", self.name, sql_type),
+            None => write!(f, "{} <unknown type>", self.name),
+        }
+    }
+}
+
+/// A fingerprint of the names and types of the columns of a table. Column names are compared
+/// case-insensitively.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct SchemaFingerprint(u64);
+
+impl SchemaFingerprint {
+    pub(crate) fn new<'a, I>(columns: I) -> Self
+    where
+        I: IntoIterator<Item = &'a ColumnSchema>,
+    {
+        let mut hasher = DefaultHasher::new();
+        for column in columns {
+            column.name.to_lowercase().hash(&mut hasher);
+            column.sql_type.hash(&mut hasher);
+        }
+        Self(hasher.finish())
+    }
+}
+
+impl Display for SchemaFingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// A connection to the upstream database used to load its schema
+pub(crate) enum UpstreamSchema {
+    MySql(mysql::Pool),
+    Postgres(deadpool_postgres::Pool),
+    /// A fixed schema, for tests
+    #[cfg(test)]
+    Fixed(HashMap<Relation, Vec<ColumnSchema>>),
+}
+
+impl UpstreamSchema {
+    /// Load the names and types of the columns of every table in the upstream database, in order
+    async fn columns(&self) -> ReadySetResult<HashMap<Relation, Vec<ColumnSchema>>> {
+        let (dialect, rows): (_, Vec<(String, String, String, String)>) = match self {
+            UpstreamSchema::MySql(pool) => (
+                Dialect::MySQL,
+                pool.get_conn()
+                    .await?
+                    .query::<(String, String, String, String), _>(MYSQL_COLUMNS_QUERY)
+                    .await?
+                    .into_iter()
+                    .filter(|(schema, ..)| !MYSQL_INTERNAL_DBS.contains(&schema.as_str()))
+                    .collect(),
+            ),
+            UpstreamSchema::Postgres(pool) => (
+                Dialect::PostgreSQL,
+                pool.get()
+                    .await?
+                    .query(POSTGRES_COLUMNS_QUERY, &[])
+                    .await?
+                    .into_iter()
+                    .map(|row: pgsql::Row| (row.get(0), row.get(1), row.get(2), row.get(3)))
+                    .collect(),
+            ),
+            #[cfg(test)]
+            UpstreamSchema::Fixed(columns) => return Ok(columns.clone()),
+        };
+
+        let mut columns: HashMap<Relation, Vec<ColumnSchema>> = HashMap::new();
+        for (schema, table, column, sql_type) in rows {
+            columns
+                .entry(Relation {
+                    schema: Some(schema.into()),
+                    name: table.into(),
+                })
+                .or_default()
+                .push(ColumnSchema::parse(dialect, column, &sql_type));
+        }
+        Ok(columns)
+    }
+}
+
+/// A table whose columns in ReadySet don't match its columns in the upstream database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SchemaDrift {
+    pub(crate) table: Relation,
+    pub(crate) readyset_columns: Vec<ColumnSchema>,
+    pub(crate) upstream_columns: Vec<ColumnSchema>,
+}
+
+impl Display for SchemaDrift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: ReadySet has columns ({}) [{}], upstream has columns ({}) [{}]",
+            self.table,
+            self.readyset_columns.iter().join(", "),
+            SchemaFingerprint::new(&self.readyset_columns),
+            self.upstream_columns.iter().join(", "),
+            SchemaFingerprint::new(&self.upstream_columns),
+        )
+    }
+}
+
+/// Returns the tables whose column names or types in ReadySet differ from their columns upstream.
+/// Tables which don't exist upstream are skipped, since they may just not be visible to the
+/// replication user.
+pub(crate) fn find_drift<'a, I>(
+    readyset: I,
+    upstream: &HashMap<Relation, Vec<ColumnSchema>>,
+) -> Vec<SchemaDrift>
+where
+    I: IntoIterator<Item = (&'a Relation, &'a [ColumnSchema])>,
+{
+    readyset
+        .into_iter()
+        .filter_map(|(table, readyset_columns)| {
+            let upstream_columns = upstream.get(table)?;
+            (SchemaFingerprint::new(readyset_columns) != SchemaFingerprint::new(upstream_columns))
+                .then(|| SchemaDrift {
+                    table: table.clone(),
+                    readyset_columns: readyset_columns.to_vec(),
+                    upstream_columns: upstream_columns.clone(),
+                })
+        })
+        .collect()
+}
+
+/// Returns the names and types of the columns of every replicated table in ReadySet
+async fn readyset_columns(
+    noria: &mut ReadySetHandle,
+) -> ReadySetResult<Vec<(Relation, Vec<ColumnSchema>)>> {
+    let tables = noria.replication_offsets().await?.tables.into_keys();
+    let mut columns = vec![];
+    for table in tables {
+        // Tables which have been dropped since the offsets were loaded are skipped
+        if let Some(schema) = noria
+            .table(table.clone())
+            .await
+            .ok()
+            .and_then(|t| t.schema().cloned())
+        {
+            columns.push((
+                table,
+                schema.fields.iter().map(ColumnSchema::from).collect(),
+            ));
+        }
+    }
+    Ok(columns)
+}
+
+/// Periodically compares the schema of the replicated tables with the upstream database, and
+/// requests a resnapshot if they've drifted. See the [module documentation](self) for more
+/// information.
+pub(crate) struct SchemaDriftDetector {
+    upstream: UpstreamSchema,
+    interval: Duration,
+    /// Drift seen by the last check, which is only acted on if it's seen again by the next check
+    suspected: Vec<SchemaDrift>,
+    /// The number of resnapshots requested for each drifted table, along with the fingerprint of
+    /// the table's upstream columns at the time
+    resnapshots: HashMap<Relation, (SchemaFingerprint, usize)>,
+}
+
+/// A running [`SchemaDriftDetector`], which is stopped when dropped
+pub(crate) struct SchemaDriftTask(JoinHandle<()>);
+
+impl Drop for SchemaDriftTask {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+impl SchemaDriftDetector {
+    /// Create a new [`SchemaDriftDetector`] checking for drift every `interval`
+    pub(crate) fn new(upstream: UpstreamSchema, interval: Duration) -> Self {
+        Self {
+            upstream,
+            interval,
+            suspected: vec![],
+            resnapshots: HashMap::new(),
+        }
+    }
+
+    /// Start checking for drift in the background, requesting resnapshots through `control`
+    pub(crate) fn spawn(
+        mut self,
+        mut noria: ReadySetHandle,
+        control: ReplicationControl,
+    ) -> SchemaDriftTask {
+        SchemaDriftTask(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.interval);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            // The first tick completes immediately
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if control.replication_lag().is_none() || control.resnapshot_pending() {
+                    continue;
+                }
+                let result = match readyset_columns(&mut noria).await {
+                    Ok(columns) => self.check_and_resnapshot(&columns, &control).await,
+                    Err(error) => Err(error),
+                };
+                if let Err(error) = result {
+                    warn!(%error, "Failed to check for schema drift");
+                }
+            }
+        }))
+    }
+
+    /// Compare the given columns of the tables in ReadySet with the upstream schema, and request a
+    /// resnapshot through `control` if any tables have drifted. Does nothing if the replicator
+    /// isn't streaming, or a resnapshot is already pending.
+    pub(crate) async fn check_and_resnapshot(
+        &mut self,
+        readyset: &[(Relation, Vec<ColumnSchema>)],
+        control: &ReplicationControl,
+    ) -> ReadySetResult<()> {
+        if control.replication_lag().is_none() || control.resnapshot_pending() {
+            return Ok(());
+        }
+
+        let drift = self
+            .check(
+                readyset
+                    .iter()
+                    .map(|(table, columns)| (table, columns.as_slice())),
+            )
+            .await?;
+        let drift = self.bound_resnapshots(drift);
+        if drift.is_empty() {
+            return Ok(());
+        }
+
+        for drift in &drift {
+            warn!(%drift, "Table schema has drifted from the upstream database, will resnapshot");
+            counter!(
+                recorded::REPLICATOR_SCHEMA_DRIFT,
+                1u64,
+                "schema" => drift.table.schema.as_deref().unwrap_or_default().to_string(),
+                "name" => drift.table.name.to_string()
+            );
+        }
+        // Drift seen before the resnapshot says nothing about the tables it recreates
+        self.suspected.clear();
+        control.request_resnapshot();
+        Ok(())
+    }
+
+    /// Load the upstream schema and compare it with the given columns of the tables in ReadySet,
+    /// returning the tables which have drifted in the same way since the last check
+    pub(crate) async fn check<'a, I>(&mut self, readyset: I) -> ReadySetResult<Vec<SchemaDrift>>
+    where
+        I: IntoIterator<Item = (&'a Relation, &'a [ColumnSchema])>,
+    {
+        let drift = find_drift(readyset, &self.upstream.columns().await?);
+        let confirmed = drift
+            .iter()
+            .filter(|drift| self.suspected.contains(drift))
+            .cloned()
+            .collect();
+        // Tables which have stopped drifting get a fresh set of resnapshots if they drift again
+        self.resnapshots
+            .retain(|table, _| drift.iter().any(|drift| drift.table == *table));
+        self.suspected = drift;
+        Ok(confirmed)
+    }
+
+    /// Record a resnapshot for each of the given drifted tables, returning those which haven't
+    /// already been resnapshotted [`MAX_DRIFT_RESNAPSHOTS`] times for the same drift
+    fn bound_resnapshots(&mut self, drift: Vec<SchemaDrift>) -> Vec<SchemaDrift> {
+        drift
+            .into_iter()
+            .filter(|drift| {
+                let upstream = SchemaFingerprint::new(&drift.upstream_columns);
+                let (fingerprint, attempts) = self
+                    .resnapshots
+                    .entry(drift.table.clone())
+                    .or_insert((upstream, 0));
+                if *fingerprint != upstream {
+                    *fingerprint = upstream;
+                    *attempts = 0;
+                }
+                match (*attempts).cmp(&MAX_DRIFT_RESNAPSHOTS) {
+                    std::cmp::Ordering::Less => {
+                        *attempts += 1;
+                        true
+                    }
+                    std::cmp::Ordering::Equal => {
+                        error!(
+                            %drift,
+                            "Table schema has still drifted from the upstream database after \
+                             {MAX_DRIFT_RESNAPSHOTS} resnapshots, won't resnapshot it again"
+                        );
+                        *attempts += 1;
+                        false
+                    }
+                    std::cmp::Ordering::Greater => false,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns(columns: &[(&str, SqlType)]) -> Vec<ColumnSchema> {
+        columns
+            .iter()
+            .map(|(name, sql_type)| ColumnSchema {
+                name: (*name).into(),
+                sql_type: Some(sql_type.clone()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fingerprint_ignores_case() {
+        assert_eq!(
+            SchemaFingerprint::new(&columns(&[
+                ("id", SqlType::Int(None)),
+                ("Name", SqlType::Text)
+            ])),
+            SchemaFingerprint::new(&columns(&[
+                ("ID", SqlType::Int(None)),
+                ("name", SqlType::Text)
+            ]))
+        );
+        assert_ne!(
+            SchemaFingerprint::new(&columns(&[
+                ("id", SqlType::Int(None)),
+                ("name", SqlType::Text)
+            ])),
+            SchemaFingerprint::new(&columns(&[
+                ("name", SqlType::Text),
+                ("id", SqlType::Int(None))
+            ]))
+        );
+    }
+
+    #[test]
+    fn fingerprint_includes_types() {
+        assert_ne!(
+            SchemaFingerprint::new(&columns(&[("id", SqlType::Int(None))])),
+            SchemaFingerprint::new(&columns(&[("id", SqlType::BigInt(None))]))
+        );
+        assert_ne!(
+            SchemaFingerprint::new(&columns(&[("id", SqlType::Int(None))])),
+            SchemaFingerprint::new(&columns(&[("id", SqlType::UnsignedInt(None))]))
+        );
+        assert_ne!(
+            SchemaFingerprint::new(&columns(&[("x", SqlType::VarChar(Some(10)))])),
+            SchemaFingerprint::new(&columns(&[("x", SqlType::VarChar(Some(20)))]))
+        );
+        assert_ne!(
+            SchemaFingerprint::new(&columns(&[("x", SqlType::Numeric(Some((10, Some(2)))))])),
+            SchemaFingerprint::new(&columns(&[("x", SqlType::Numeric(Some((10, Some(4)))))]))
+        );
+    }
+
+    #[test]
+    fn upstream_types_parse_like_snapshotted_types() {
+        assert_eq!(
+            ColumnSchema::parse(Dialect::MySQL, "x".into(), "int(10) unsigned"),
+            columns(&[("x", SqlType::UnsignedInt(Some(10)))])[0]
+        );
+        assert_eq!(
+            ColumnSchema::parse(Dialect::PostgreSQL, "x".into(), "character varying(255)"),
+            columns(&[("x", SqlType::VarChar(Some(255)))])[0]
+        );
+        assert_eq!(
+            ColumnSchema::parse(Dialect::MySQL, "x".into(), "(10)").sql_type,
+            None
+        );
+    }
+
+    #[test]
+    fn find_drifted_tables() {
+        let t1 = Relation {
+            schema: Some("db".into()),
+            name: "t1".into(),
+        };
+        let t2 = Relation {
+            schema: Some("db".into()),
+            name: "t2".into(),
+        };
+        let t3 = Relation {
+            schema: Some("db".into()),
+            name: "t3".into(),
+        };
+        let t4 = Relation {
+            schema: Some("db".into()),
+            name: "t4".into(),
+        };
+        let readyset = [
+            (
+                t1.clone(),
+                columns(&[("id", SqlType::Int(None)), ("x", SqlType::Text)]),
+            ),
+            (
+                t2.clone(),
+                columns(&[("id", SqlType::Int(None)), ("y", SqlType::Text)]),
+            ),
+            (t3, columns(&[("id", SqlType::Int(None))])),
+            (t4.clone(), columns(&[("id", SqlType::Int(None))])),
+        ];
+        let upstream = HashMap::from([
+            (
+                t1,
+                columns(&[("id", SqlType::Int(None)), ("x", SqlType::Text)]),
+            ),
+            (
+                t2.clone(),
+                columns(&[
+                    ("id", SqlType::Int(None)),
+                    ("y", SqlType::Text),
+                    ("z", SqlType::Text),
+                ]),
+            ),
+            (t4.clone(), columns(&[("id", SqlType::BigInt(None))])),
+        ]);
+
+        let mut drift = find_drift(
+            readyset
+                .iter()
+                .map(|(table, columns)| (table, columns.as_slice())),
+            &upstream,
+        );
+        drift.sort_by(|d1, d2| d1.table.name.cmp(&d2.table.name));
+        assert_eq!(
+            drift,
+            vec![
+                SchemaDrift {
+                    table: t2,
+                    readyset_columns: columns(&[("id", SqlType::Int(None)), ("y", SqlType::Text)]),
+                    upstream_columns: columns(&[
+                        ("id", SqlType::Int(None)),
+                        ("y", SqlType::Text),
+                        ("z", SqlType::Text),
+                    ]),
+                },
+                SchemaDrift {
+                    table: t4,
+                    readyset_columns: columns(&[("id", SqlType::Int(None))]),
+                    upstream_columns: columns(&[("id", SqlType::BigInt(None))]),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn resnapshots_drifted_tables_a_bounded_number_of_times() {
+        let table = Relation {
+            schema: Some("db".into()),
+            name: "t".into(),
+        };
+        let drifted = [(table.clone(), columns(&[("id", SqlType::Int(None))]))];
+        let fixed = [(table.clone(), columns(&[("id", SqlType::BigInt(None))]))];
+        let mut detector = SchemaDriftDetector::new(
+            UpstreamSchema::Fixed(HashMap::from(fixed.clone())),
+            Duration::from_secs(1),
+        );
+        let control = ReplicationControl::default();
+
+        // Runs `n` checks, returning whether a resnapshot was requested, and completing it if so
+        async fn resnapshots(
+            detector: &mut SchemaDriftDetector,
+            control: &ReplicationControl,
+            readyset: &[(Relation, Vec<ColumnSchema>)],
+            n: usize,
+        ) -> bool {
+            let mut requested = false;
+            for _ in 0..n {
+                detector
+                    .check_and_resnapshot(readyset, control)
+                    .await
+                    .unwrap();
+                if control.resnapshot_pending() {
+                    requested = true;
+                    control.resnapshot_completed(control.latest_resnapshot_request());
+                }
+            }
+            requested
+        }
+
+        // Nothing is checked until the replicator is streaming
+        assert!(!resnapshots(&mut detector, &control, &drifted, 2).await);
+        control.start_streaming();
+
+        // Drift has to be seen by two consecutive checks
+        assert!(!resnapshots(&mut detector, &control, &drifted, 1).await);
+        for _ in 0..MAX_DRIFT_RESNAPSHOTS {
+            assert!(resnapshots(&mut detector, &control, &drifted, 2).await);
+        }
+        // The same drift isn't resnapshotted forever
+        assert!(!resnapshots(&mut detector, &control, &drifted, 4).await);
+
+        // Once the table stops drifting, it can be resnapshotted again
+        assert!(!resnapshots(&mut detector, &control, &fixed, 1).await);
+        assert!(resnapshots(&mut detector, &control, &drifted, 2).await);
+    }
+}
@@ -8,16 +8,17 @@ use readyset_util::redacted::RedactedString;
 
 /// A [`TableFilter`] keeps lists of all the tables readyset-server is interested in, as well as a
 /// list of tables that we explicitly want to filter out of replication.
-/// Tables may be filtered from replication in 2 ways:
+/// Tables may be filtered from replication in 3 ways:
 /// 1. All tables will be filtered other than the ones provided to the option --replication_tables,
 ///    if it is used
-/// 2. If we encounter a unrecoverable failure in replication for a table, we can filter out the
+/// 2. All tables provided to the option --replication-tables-ignore will be filtered
+/// 3. If we encounter a unrecoverable failure in replication for a table, we can filter out the
 ///    table to keep the process running without that table, which is better than being stuck until
 ///    we fix why that table isn't replicating.
 ///
-/// NOTE: 2. takes precedence over 1. above. So if a table is explicitly replicated with
-/// --replication_tables, but then experiences an error in replication, we will stop replicating
-/// that table.
+/// NOTE: 2. and 3. take precedence over 1. above. So if a table is explicitly replicated with
+/// --replication_tables, but is also ignored or experiences an error in replication, we will not
+/// replicate that table.
 ///
 /// When a replication event happens, the event is filtered based on its
 /// schema/table before being sent to readyset-server.
@@ -33,6 +34,8 @@ pub(crate) struct TableFilter {
     /// This is only populated by the --rplication-tables option
     explicitly_replicated: BTreeMap<SqlIdentifier, ReplicateTableSpec>,
     /// A mapping between schema to the list of tables to *NOT* replicate from that schema.
+    /// This is populated by the --replication-tables-ignore option, and by tables that fail to
+    /// replicate.
     /// Any other valid tables will be replicated, where a valid table is either one of the tables
     /// in `explicitly_replicated`, or all tables if that is empty.
     replication_denied: BTreeMap<SqlIdentifier, ReplicateTableSpec>,
@@ -89,76 +92,66 @@ impl ReplicateTableSpec {
 }
 
 impl TableFilter {
+    /// Create a new filter from the lists of tables passed to `--replication-tables` and
+    /// `--replication-tables-ignore`. Tables in `ignore_table_list` are never replicated, even if
+    /// they also match `filter_table_list`.
     pub(crate) fn try_new(
         dialect: Dialect,
         filter_table_list: Option<RedactedString>,
+        ignore_table_list: Option<RedactedString>,
         default_schema: Option<&str>,
     ) -> ReadySetResult<TableFilter> {
         let default_schema = default_schema.map(SqlIdentifier::from);
 
-        let mut schemas = BTreeMap::new();
-
-        let filtered = match filter_table_list {
-            None => {
-                match default_schema {
-                    Some(default) => {
-                        // Will load all tables for the default schema
-                        schemas.insert(default, ReplicateTableSpec::empty_all_tables());
-                        return Ok(TableFilter {
-                            explicitly_replicated: schemas,
-                            replication_denied: BTreeMap::new(),
-                        });
+        let mut filter = match filter_table_list {
+            None => match &default_schema {
+                // Will load all tables for the default schema
+                Some(default) => TableFilter {
+                    explicitly_replicated: BTreeMap::from([(
+                        default.clone(),
+                        ReplicateTableSpec::empty_all_tables(),
+                    )]),
+                    replication_denied: BTreeMap::new(),
+                },
+                // We will learn what the tables are by `update_table_list` at snapshot
+                // time since `for_all_schemas` is true.
+                None => Self::for_all_tables(),
+            },
+            Some(filtered) if filtered.as_str() == "*.*" => Self::for_all_tables(),
+            Some(filtered) => {
+                let mut schemas = BTreeMap::new();
+                for (table_schema, table_name) in
+                    parse_table_list(dialect, &filtered, default_schema.as_ref())?
+                {
+                    if table_name == "*" {
+                        schemas.insert(table_schema, ReplicateTableSpec::empty_all_tables());
+                    } else {
+                        let tables = schemas
+                            .entry(table_schema)
+                            .or_insert_with(ReplicateTableSpec::empty);
+                        tables.insert(table_name);
                     }
-                    None => {
-                        // We will learn what the tables are by `update_table_list` at snapshot
-                        // time since `for_all_schemas` is true.
-                        return Ok(Self::for_all_tables());
-                    }
-                };
+                }
+                TableFilter {
+                    explicitly_replicated: schemas,
+                    replication_denied: BTreeMap::new(),
+                }
             }
-            Some(t) => t,
         };
 
-        if filtered.as_str() == "*.*" {
-            return Ok(Self::for_all_tables());
-        }
-
-        let filter_list =
-            match replicator_table_list(dialect)(LocatedSpan::new(filtered.as_bytes())) {
-                Ok((rem, tables)) if rem.is_empty() => tables,
-                _ => {
-                    return Err(ReadySetError::ReplicationFailed(
-                        "Unable to parse filtered tables list".to_string(),
-                    ))
+        if let Some(ignored) = ignore_table_list {
+            for (table_schema, table_name) in
+                parse_table_list(dialect, &ignored, default_schema.as_ref())?
+            {
+                if table_name == "*" {
+                    filter.deny_schema(table_schema);
+                } else {
+                    filter.deny_replication(&table_schema, &table_name);
                 }
-            };
-
-        for table in filter_list {
-            let table_name = table.name;
-            let table_schema =
-                table
-                    .schema
-                    .or_else(|| default_schema.clone())
-                    .ok_or_else(|| {
-                        ReadySetError::ReplicationFailed(format!(
-                            "No database and no default database for table {table_name}"
-                        ))
-                    })?;
-
-            if table_name == "*" {
-                schemas.insert(table_schema, ReplicateTableSpec::empty_all_tables());
-            } else {
-                let tables = schemas
-                    .entry(table_schema)
-                    .or_insert_with(ReplicateTableSpec::empty);
-                tables.insert(table_name);
             }
         }
 
-        Ok(TableFilter {
-            explicitly_replicated: schemas,
-            replication_denied: BTreeMap::new(),
-        })
+        Ok(filter)
     }
 
     /// Create a new filter that will pass all tables
@@ -183,6 +176,16 @@ impl TableFilter {
         tables.insert(table);
     }
 
+    /// Stop replicating all tables in the provided schema
+    fn deny_schema(&mut self, schema: SqlIdentifier) {
+        tracing::info!(%schema, "denying replication");
+        if let Some(tables) = self.explicitly_replicated.get_mut(&schema) {
+            *tables = ReplicateTableSpec::empty();
+        }
+        self.replication_denied
+            .insert(schema, ReplicateTableSpec::empty_all_tables());
+    }
+
     /// Check if a given table should be processed
     pub(crate) fn should_be_processed<Q1, Q2>(&self, schema: &Q1, table: &Q2) -> bool
     where
@@ -235,13 +238,48 @@ impl TableFilter {
     }
 }
 
+/// Parse a comma-separated list of (optionally schema-qualified) table names, as passed to
+/// `--replication-tables` or `--replication-tables-ignore`, into a list of schemas and table names.
+/// Tables without a schema are assumed to be in `default_schema`.
+fn parse_table_list(
+    dialect: Dialect,
+    table_list: &RedactedString,
+    default_schema: Option<&SqlIdentifier>,
+) -> ReadySetResult<Vec<(SqlIdentifier, SqlIdentifier)>> {
+    let tables = match replicator_table_list(dialect)(LocatedSpan::new(table_list.as_bytes())) {
+        Ok((rem, tables)) if rem.is_empty() => tables,
+        _ => {
+            return Err(ReadySetError::ReplicationFailed(
+                "Unable to parse filtered tables list".to_string(),
+            ))
+        }
+    };
+
+    tables
+        .into_iter()
+        .map(|table| {
+            let table_name = table.name;
+            let table_schema = table
+                .schema
+                .or_else(|| default_schema.cloned())
+                .ok_or_else(|| {
+                    ReadySetError::ReplicationFailed(format!(
+                        "No database and no default database for table {table_name}"
+                    ))
+                })?;
+            Ok((table_schema, table_name))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::TableFilter;
 
     #[test]
     fn empty_list() {
-        let filter = TableFilter::try_new(nom_sql::Dialect::MySQL, None, Some("noria")).unwrap();
+        let filter =
+            TableFilter::try_new(nom_sql::Dialect::MySQL, None, None, Some("noria")).unwrap();
         // By default should only allow all tables from the default schema
         assert!(filter.should_be_processed("noria", "table"));
         assert!(!filter.should_be_processed("readyset", "table"));
@@ -252,6 +290,7 @@ mod tests {
         let filter = TableFilter::try_new(
             nom_sql::Dialect::MySQL,
             Some("*.*".to_string().into()),
+            None,
             Some("noria"),
         )
         .unwrap();
@@ -261,7 +300,7 @@ mod tests {
 
     #[test]
     fn all_schemas_implicit() {
-        let filter = TableFilter::try_new(nom_sql::Dialect::MySQL, None, None).unwrap();
+        let filter = TableFilter::try_new(nom_sql::Dialect::MySQL, None, None, None).unwrap();
         assert!(filter.should_be_processed("noria", "table"));
         assert!(filter.should_be_processed("readyset", "table"));
     }
@@ -271,6 +310,7 @@ mod tests {
         let filter = TableFilter::try_new(
             nom_sql::Dialect::MySQL,
             Some("t1,t2,t3".to_string().into()),
+            None,
             Some("noria"),
         )
         .unwrap();
@@ -287,6 +327,7 @@ mod tests {
         let filter = TableFilter::try_new(
             nom_sql::Dialect::MySQL,
             Some("t1,noria.t2,readyset.t4,t3".to_string().into()),
+            None,
             Some("noria"),
         )
         .unwrap();
@@ -303,6 +344,7 @@ mod tests {
         let filter = TableFilter::try_new(
             nom_sql::Dialect::MySQL,
             Some("noria.*, readyset.t4, t3".to_string().into()),
+            None,
             Some("noria"),
         )
         .unwrap();
@@ -320,6 +362,7 @@ mod tests {
         let mut filter = TableFilter::try_new(
             nom_sql::Dialect::MySQL,
             Some("noria.*, readyset.t4, t3".to_string().into()),
+            None,
             Some("noria"),
         )
         .unwrap();
@@ -336,4 +379,50 @@ mod tests {
         filter.deny_replication("readyset", "t4");
         assert!(!filter.should_be_processed("readyset", "t4"));
     }
+
+    #[test]
+    fn ignore_list() {
+        let filter = TableFilter::try_new(
+            nom_sql::Dialect::MySQL,
+            None,
+            Some("t1, readyset.t2".to_string().into()),
+            Some("noria"),
+        )
+        .unwrap();
+        assert!(!filter.should_be_processed("noria", "t1"));
+        assert!(filter.should_be_processed("noria", "t2"));
+        assert!(!filter.should_be_processed("readyset", "t2"));
+        assert!(!filter.should_be_processed("readyset", "t3"));
+    }
+
+    #[test]
+    fn ignore_list_all_schemas() {
+        let filter = TableFilter::try_new(
+            nom_sql::Dialect::MySQL,
+            Some("*.*".to_string().into()),
+            Some("noria.t1, readyset.*".to_string().into()),
+            Some("noria"),
+        )
+        .unwrap();
+        assert!(!filter.should_be_processed("noria", "t1"));
+        assert!(filter.should_be_processed("noria", "t2"));
+        assert!(!filter.should_be_processed("readyset", "t1"));
+        assert!(filter.should_be_processed("other", "t1"));
+    }
+
+    #[test]
+    fn ignore_list_takes_precedence() {
+        let filter = TableFilter::try_new(
+            nom_sql::Dialect::MySQL,
+            Some("noria.*, t3, readyset.t4".to_string().into()),
+            Some("noria.t1, readyset.*".to_string().into()),
+            Some("noria"),
+        )
+        .unwrap();
+        assert!(!filter.should_be_processed("noria", "t1"));
+        assert!(filter.should_be_processed("noria", "t2"));
+        assert!(filter.should_be_processed("noria", "t3"));
+        assert!(!filter.should_be_processed("readyset", "t4"));
+        assert!(!filter.should_be_processed("other", "t1"));
+    }
 }
@@ -0,0 +1,501 @@
+//! Hooks for transforming rows replicated from the upstream database before they're written to
+//! ReadySet.
+//!
+//! A [`ReplicationTransform`] is called once for every row that's replicated, both while
+//! snapshotting and during streaming replication, and can drop the row, rewrite its values (for
+//! example to normalize text encodings or anonymize personal data), or redirect it to a different
+//! base table. This allows sanitizing data on its way into ReadySet without changing it in the
+//! upstream database.
+//!
+//! The [`RowSizeLimit`] configured for replication (if any) is enforced by the same hook, after
+//! the transform has been applied and the table each row is written to is known, so that it
+//! applies to all rows in the same way and never truncates the key columns of that table.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use database_utils::row_size::{RowSizeCheck, RowSizeLimit};
+use metrics::increment_counter;
+use nom_sql::Relation;
+use readyset_client::metrics::recorded;
+use readyset_client::{Modification, ReadySetHandle, ReadySetResult, Table, TableOperation};
+use readyset_data::DfValue;
+use readyset_tracing::warn;
+
+/// A change to a single row replicated from the upstream database.
+///
+/// Updates to a row are replicated as a [`Delete`](Self::Delete) of the old value of the row
+/// followed by an [`Insert`](Self::Insert) of its new value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RowChange {
+    /// A row was inserted
+    Insert(Vec<DfValue>),
+    /// A row was deleted
+    Delete(Vec<DfValue>),
+}
+
+/// A hook invoked for every row replicated from the upstream database. See the [module
+/// documentation](self) for more information.
+pub trait ReplicationTransform: Send + Sync + 'static {
+    /// Transform a single change to a row in `table`, returning the (schema-qualified) table to
+    /// apply the change to along with the change to apply, or `None` to drop the change entirely.
+    ///
+    /// Since deleting a row requires the deleted row to exactly match a row that was previously
+    /// inserted, transformations should be deterministic, and should apply the same rewrite to a
+    /// [`RowChange::Delete`] as they would to the [`RowChange::Insert`] of the same row.
+    ///
+    /// Rows snapshotted from the upstream database can only be redirected to tables with a primary
+    /// key, and replace any existing row with the same key in the table they're redirected to, so
+    /// that snapshotting a table again doesn't duplicate the rows redirected from it.
+    ///
+    /// Replicated operations which don't contain a full row (such as deletes by key, which are
+    /// used for postgres tables with a `REPLICA IDENTITY` of `DEFAULT`) can't be passed to this
+    /// method, so while a transform is configured, tables that such operations are replicated for
+    /// are not replicated at all.
+    fn transform(&self, table: &Relation, change: RowChange) -> Option<(Relation, RowChange)>;
+}
+
+/// An optional [`ReplicationTransform`], which can be cloned and stored in configuration.
+#[derive(Clone, Default)]
+pub struct ReplicationTransformHook {
+    transform: Option<Arc<dyn ReplicationTransform>>,
+    row_size_limit: Option<RowSizeLimit>,
+}
+
+impl ReplicationTransformHook {
+    /// Construct a new hook which calls the given transform for every replicated row
+    pub fn new<T: ReplicationTransform>(transform: T) -> Self {
+        Self {
+            transform: Some(Arc::new(transform)),
+            row_size_limit: None,
+        }
+    }
+
+    /// Also check every replicated row against the given limit, after applying the transform
+    pub(crate) fn with_row_size_limit(mut self, row_size_limit: Option<RowSizeLimit>) -> Self {
+        self.row_size_limit = row_size_limit;
+        self
+    }
+
+    fn is_noop(&self) -> bool {
+        self.transform.is_none() && self.row_size_limit.is_none()
+    }
+
+    /// Returns true if a user-supplied [`ReplicationTransform`] is configured.
+    ///
+    /// Tables replicated with a transform must be replicated with full rows, since operations which
+    /// only contain a key (such as the updates and deletes replicated for postgres tables with a
+    /// `REPLICA IDENTITY` other than `FULL`) can't be passed to the transform.
+    pub(crate) fn has_transform(&self) -> bool {
+        self.transform.is_some()
+    }
+
+    /// Returns true if `actions` contains any operations which can't be passed to the transform,
+    /// because they don't contain a full row
+    pub(crate) fn has_keyed_operations(actions: &[TableOperation]) -> bool {
+        actions.iter().any(|action| {
+            matches!(
+                action,
+                TableOperation::DeleteByKey { .. } | TableOperation::Update { .. }
+            )
+        })
+    }
+
+    /// Apply the transform to a single change to a row in `table`
+    fn transform(&self, table: &Relation, change: RowChange) -> Option<(Relation, RowChange)> {
+        match &self.transform {
+            Some(transform) => transform.transform(table, change),
+            None => Some((table.clone(), change)),
+        }
+    }
+
+    /// Check the row data in `actions` for the table `target` against the row size limit, if any,
+    /// truncating oversized rows in place or removing them from `actions` according to the
+    /// limit's policy. Values in any of the `key_columns` of `target` are never truncated.
+    pub(crate) fn check_row_sizes(
+        &self,
+        target: &Relation,
+        key_columns: &[usize],
+        actions: &mut Vec<TableOperation>,
+    ) {
+        let limit = match &self.row_size_limit {
+            Some(limit) => limit,
+            None => return,
+        };
+        actions.retain_mut(|action| {
+            let check = limit.check_op(action, key_columns);
+            if let Some(action_taken) = check.action() {
+                // Deletes of oversized rows are truncated or skipped just like the inserts of the
+                // same rows were, so don't warn about them
+                if !matches!(action, TableOperation::DeleteRow { .. }) {
+                    warn!(
+                        table = %target,
+                        max_row_size = limit.max_row_size,
+                        action = action_taken,
+                        "Replicated row exceeds the maximum row size"
+                    );
+                }
+                increment_counter!(
+                    recorded::BASE_TABLE_OVERSIZED_ROWS,
+                    "table_name" => target.to_string(),
+                    "source" => "replication",
+                    "action" => action_taken
+                );
+            }
+            check != RowSizeCheck::Skip
+        });
+    }
+
+    /// Apply the transform to a list of operations replicated for `table`, returning the
+    /// operations to perform grouped into runs of consecutive operations for the same table.
+    ///
+    /// The returned operations still have to be checked against the row size limit with
+    /// [`check_row_sizes`](Self::check_row_sizes) once the table they're written to is known.
+    pub(crate) fn apply(
+        &self,
+        table: &Relation,
+        actions: Vec<TableOperation>,
+    ) -> Vec<(Relation, Vec<TableOperation>)> {
+        if self.transform.is_none() {
+            return vec![(table.clone(), actions)];
+        }
+
+        let mut res: Vec<(Relation, Vec<TableOperation>)> = vec![];
+        for action in actions {
+            let (target, action) = match action {
+                TableOperation::Insert(row) => {
+                    match self.transform(table, RowChange::Insert(row)) {
+                        Some((target, change)) => (target, change.into()),
+                        None => continue,
+                    }
+                }
+                TableOperation::DeleteRow { row } => {
+                    match self.transform(table, RowChange::Delete(row)) {
+                        Some((target, change)) => (target, change.into()),
+                        None => continue,
+                    }
+                }
+                action => (table.clone(), action),
+            };
+
+            match res.last_mut() {
+                Some((last, actions)) if *last == target => actions.push(action),
+                _ => res.push((target, vec![action])),
+            }
+        }
+        res
+    }
+
+    /// Insert a batch of rows snapshotted from the upstream table for `table` into ReadySet,
+    /// applying the transform to each row first. Mutators for any tables the rows are redirected to
+    /// are looked up using `noria`, and cached in `redirected`.
+    pub(crate) async fn insert_snapshot_rows(
+        &self,
+        noria: &mut ReadySetHandle,
+        table: &mut Table,
+        redirected: &mut HashMap<Relation, Table>,
+        rows: Vec<Vec<DfValue>>,
+    ) -> ReadySetResult<()> {
+        if self.is_noop() {
+            return table.insert_many(rows).await;
+        }
+
+        let table_name = table.table_name().clone();
+        let actions = rows.into_iter().map(TableOperation::Insert).collect();
+        for (target, mut actions) in self.apply(&table_name, actions) {
+            if target == table_name {
+                self.check_row_sizes(&target, &table.key_columns(), &mut actions);
+                table.perform_all(actions).await?;
+                continue;
+            }
+
+            let mutator = match redirected.get_mut(&target) {
+                Some(mutator) => mutator,
+                None => match noria.table(target.clone()).await {
+                    Ok(mutator) => redirected.entry(target.clone()).or_insert(mutator),
+                    Err(error) => {
+                        warn!(
+                            %error,
+                            table = %target,
+                            "Could not find table to redirect rows to, discarding rows"
+                        );
+                        continue;
+                    }
+                },
+            };
+            if !mutator.has_primary_key() {
+                warn!(
+                    table = %target,
+                    source = %table_name,
+                    "Snapshotted rows can only be redirected to tables with a primary key, \
+                     discarding rows"
+                );
+                continue;
+            }
+            self.check_row_sizes(&target, &mutator.key_columns(), &mut actions);
+            mutator.perform_all(replacing_upserts(actions)).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Rewrite the inserts of snapshotted rows which were redirected to another table into upserts
+/// which replace any existing row with the same key.
+///
+/// Snapshotting a table again only clears that table, not the tables its rows were redirected to,
+/// so inserting the redirected rows again would duplicate the rows redirected by the previous
+/// snapshot (or reject them as duplicate keys) rather than replacing them.
+fn replacing_upserts(actions: Vec<TableOperation>) -> Vec<TableOperation> {
+    actions
+        .into_iter()
+        .map(|action| match action {
+            TableOperation::Insert(row) => TableOperation::InsertOrUpdate {
+                update: row.iter().cloned().map(Modification::Set).collect(),
+                row,
+            },
+            action => action,
+        })
+        .collect()
+}
+
+impl From<RowChange> for TableOperation {
+    fn from(change: RowChange) -> Self {
+        match change {
+            RowChange::Insert(row) => TableOperation::Insert(row),
+            RowChange::Delete(row) => TableOperation::DeleteRow { row },
+        }
+    }
+}
+
+impl fmt::Debug for ReplicationTransformHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReplicationTransformHook")
+            .field("transform", &self.transform.as_ref().map(|_| ".."))
+            .field("row_size_limit", &self.row_size_limit)
+            .finish()
+    }
+}
+
+impl PartialEq for ReplicationTransformHook {
+    fn eq(&self, other: &Self) -> bool {
+        let transforms_eq = match (&self.transform, &other.transform) {
+            (Some(t1), Some(t2)) => {
+                std::ptr::eq(Arc::as_ptr(t1) as *const (), Arc::as_ptr(t2) as *const ())
+            }
+            (None, None) => true,
+            _ => false,
+        };
+        transforms_eq && self.row_size_limit == other.row_size_limit
+    }
+}
+
+impl Eq for ReplicationTransformHook {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestTransform;
+
+    impl ReplicationTransform for TestTransform {
+        fn transform(&self, table: &Relation, change: RowChange) -> Option<(Relation, RowChange)> {
+            let rewrite = |mut row: Vec<DfValue>| {
+                row[1] = DfValue::from("redacted");
+                row
+            };
+            match change {
+                RowChange::Insert(row) | RowChange::Delete(row) if row[0] == DfValue::from(0) => {
+                    None
+                }
+                RowChange::Insert(row) if row[0] == DfValue::from(1) => Some((
+                    Relation {
+                        schema: table.schema.clone(),
+                        name: "other".into(),
+                    },
+                    RowChange::Insert(row),
+                )),
+                RowChange::Insert(row) => Some((table.clone(), RowChange::Insert(rewrite(row)))),
+                RowChange::Delete(row) => Some((table.clone(), RowChange::Delete(rewrite(row)))),
+            }
+        }
+    }
+
+    fn relation(name: &str) -> Relation {
+        Relation {
+            schema: Some("public".into()),
+            name: name.into(),
+        }
+    }
+
+    fn row(id: i32, value: &str) -> Vec<DfValue> {
+        vec![DfValue::from(id), DfValue::from(value)]
+    }
+
+    #[test]
+    fn no_transform() {
+        let actions = vec![
+            TableOperation::Insert(row(0, "a")),
+            TableOperation::DeleteRow { row: row(1, "b") },
+        ];
+        assert_eq!(
+            ReplicationTransformHook::default().apply(&relation("t"), actions.clone()),
+            vec![(relation("t"), actions)]
+        );
+    }
+
+    #[test]
+    fn drop_rewrite_and_redirect() {
+        let hook = ReplicationTransformHook::new(TestTransform);
+        let res = hook.apply(
+            &relation("t"),
+            vec![
+                TableOperation::Insert(row(0, "dropped")),
+                TableOperation::Insert(row(2, "a")),
+                TableOperation::DeleteRow { row: row(3, "b") },
+                TableOperation::Insert(row(1, "c")),
+                TableOperation::Insert(row(1, "d")),
+                TableOperation::DeleteByKey {
+                    key: vec![DfValue::from(4)],
+                },
+                TableOperation::DeleteRow {
+                    row: row(0, "dropped"),
+                },
+            ],
+        );
+        assert_eq!(
+            res,
+            vec![
+                (
+                    relation("t"),
+                    vec![
+                        TableOperation::Insert(row(2, "redacted")),
+                        TableOperation::DeleteRow {
+                            row: row(3, "redacted")
+                        },
+                    ]
+                ),
+                (
+                    relation("other"),
+                    vec![
+                        TableOperation::Insert(row(1, "c")),
+                        TableOperation::Insert(row(1, "d")),
+                    ]
+                ),
+                (
+                    relation("t"),
+                    vec![TableOperation::DeleteByKey {
+                        key: vec![DfValue::from(4)],
+                    }]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn row_size_limit() {
+        use database_utils::row_size::OversizedRowPolicy;
+
+        let hook = |policy| {
+            ReplicationTransformHook::default().with_row_size_limit(Some(RowSizeLimit {
+                max_row_size: 10,
+                policy,
+            }))
+        };
+        let actions = vec![
+            TableOperation::Insert(row(1, "a")),
+            TableOperation::Insert(row(2, "abcdefgh")),
+            TableOperation::DeleteRow {
+                row: row(2, "abcdefgh"),
+            },
+            TableOperation::Update {
+                key: vec![DfValue::from(1)],
+                update: vec![
+                    Modification::None,
+                    Modification::Set(DfValue::from("abcdefghijkl")),
+                ],
+            },
+        ];
+
+        let check = |policy, key_columns: &[usize]| {
+            let mut actions = actions.clone();
+            hook(policy).check_row_sizes(&relation("t"), key_columns, &mut actions);
+            actions
+        };
+
+        assert_eq!(
+            check(OversizedRowPolicy::Truncate, &[0]),
+            vec![
+                TableOperation::Insert(row(1, "a")),
+                TableOperation::Insert(row(2, "ab")),
+                TableOperation::DeleteRow { row: row(2, "ab") },
+                TableOperation::Update {
+                    key: vec![DfValue::from(1)],
+                    update: vec![
+                        Modification::None,
+                        Modification::Set(DfValue::from("abcdefghij")),
+                    ],
+                },
+            ]
+        );
+        assert_eq!(
+            check(OversizedRowPolicy::Skip, &[0]),
+            vec![TableOperation::Insert(row(1, "a"))]
+        );
+
+        // Values in key columns are never truncated, so rows which can only fit by truncating them
+        // are skipped
+        assert_eq!(
+            check(OversizedRowPolicy::Truncate, &[0, 1]),
+            vec![TableOperation::Insert(row(1, "a"))]
+        );
+    }
+
+    #[test]
+    fn redirected_snapshot_rows_replace_existing_rows() {
+        assert_eq!(
+            replacing_upserts(vec![
+                TableOperation::Insert(row(1, "a")),
+                TableOperation::DeleteRow { row: row(2, "b") },
+            ]),
+            vec![
+                TableOperation::InsertOrUpdate {
+                    row: row(1, "a"),
+                    update: vec![
+                        Modification::Set(DfValue::from(1)),
+                        Modification::Set(DfValue::from("a")),
+                    ],
+                },
+                TableOperation::DeleteRow { row: row(2, "b") },
+            ]
+        );
+    }
+
+    #[test]
+    fn hook_eq() {
+        let hook = ReplicationTransformHook::new(TestTransform);
+        assert_eq!(hook, hook.clone());
+        assert_ne!(hook, ReplicationTransformHook::new(TestTransform));
+        assert_ne!(hook, ReplicationTransformHook::default());
+    }
+
+    #[test]
+    fn keyed_operations() {
+        assert!(!ReplicationTransformHook::has_keyed_operations(&[
+            TableOperation::Insert(row(0, "a")),
+            TableOperation::DeleteRow { row: row(1, "b") },
+        ]));
+        assert!(ReplicationTransformHook::has_keyed_operations(&[
+            TableOperation::Insert(row(0, "a")),
+            TableOperation::DeleteByKey {
+                key: vec![DfValue::from(1)],
+            },
+        ]));
+        assert!(ReplicationTransformHook::has_keyed_operations(&[
+            TableOperation::Update {
+                key: vec![DfValue::from(1)],
+                update: vec![],
+            },
+        ]));
+    }
+}
@@ -306,6 +306,8 @@ impl TestHandle {
                 },
                 ready_notify.clone(),
                 telemetry_sender,
+                Default::default(),
+                Default::default(),
             )
             .await
             {
@@ -564,6 +566,12 @@ async fn mysql_replication_filter() -> ReadySetResult<()> {
     replication_filter_inner(&mysql_url()).await
 }
 
+#[tokio::test(flavor = "multi_thread")]
+#[serial_test::serial]
+async fn mysql_replication_filter_changed_on_restart() -> ReadySetResult<()> {
+    replication_filter_changed_on_restart_inner(&mysql_url()).await
+}
+
 #[tokio::test(flavor = "multi_thread")]
 #[serial_test::serial]
 async fn pgsql_replication_all_schemas() -> ReadySetResult<()> {
@@ -1104,6 +1112,82 @@ async fn replication_filter_inner(url: &str) -> ReadySetResult<()> {
     Ok(())
 }
 
+/// Adding a table to (and removing a table from) the set of replicated tables between restarts of
+/// the replicator should snapshot just the newly replicated table, and drop the table which is no
+/// longer replicated.
+async fn replication_filter_changed_on_restart_inner(url: &str) -> ReadySetResult<()> {
+    readyset_tracing::init_test_logging();
+    let mut client = DbConnection::connect(url).await?;
+    client
+        .query(
+            "
+            DROP TABLE IF EXISTS ft1 CASCADE; CREATE TABLE ft1 (id int);
+            DROP TABLE IF EXISTS ft2 CASCADE; CREATE TABLE ft2 (id int);
+            DROP TABLE IF EXISTS ft3 CASCADE; CREATE TABLE ft3 (id int);
+            INSERT INTO ft1 VALUES (1),(2);
+            INSERT INTO ft2 VALUES (1),(2);
+            INSERT INTO ft3 VALUES (1),(2);
+            ",
+        )
+        .await?;
+
+    let mut ctx = TestHandle::start_noria(
+        url.to_string(),
+        Some(Config {
+            replication_tables: Some("public.ft1, public.ft3".to_string().into()),
+            ..Default::default()
+        }),
+    )
+    .await?;
+    ctx.ready_notify.as_ref().unwrap().notified().await;
+
+    ctx.assert_table_exists("public", "ft1").await;
+    ctx.assert_table_missing("public", "ft2").await;
+    ctx.assert_table_exists("public", "ft3").await;
+
+    ctx.stop_repl().await;
+    client
+        .query("INSERT INTO ft1 VALUES (3); INSERT INTO ft2 VALUES (3);")
+        .await?;
+
+    // Start replicating ft2, and stop replicating ft3
+    ctx.start_repl(
+        Some(Config {
+            replication_tables: Some("public.*".to_string().into()),
+            replication_tables_ignore: Some("public.ft3".to_string().into()),
+            ..Default::default()
+        }),
+        TelemetrySender::new_no_op(),
+    )
+    .await?;
+
+    let all_rows: &[&[DfValue]] = &[&[DfValue::Int(1)], &[DfValue::Int(2)], &[DfValue::Int(3)]];
+    ctx.check_results("ft2", "Newly replicated table snapshotted", all_rows)
+        .await?;
+    ctx.check_results("ft1", "Existing table caught up", all_rows)
+        .await?;
+    ctx.assert_table_missing("public", "ft3").await;
+
+    // The newly replicated table keeps replicating
+    client.query("INSERT INTO ft2 VALUES (4)").await?;
+    ctx.check_results(
+        "ft2",
+        "Newly replicated table replicating",
+        &[
+            &[DfValue::Int(1)],
+            &[DfValue::Int(2)],
+            &[DfValue::Int(3)],
+            &[DfValue::Int(4)],
+        ],
+    )
+    .await?;
+
+    ctx.stop().await;
+    client.stop().await;
+
+    Ok(())
+}
+
 async fn replication_all_schemas_inner(url: &str) -> ReadySetResult<()> {
     readyset_tracing::init_test_logging();
     let mut client = DbConnection::connect(url).await?;